@@ -0,0 +1,544 @@
+//! Commands shared by the REPL and one-shot mode
+//!
+//! Each command produces an [`Output`] with a human readable form and a JSON
+//! form, so the same command can be typed in the REPL or run from a script
+//! with `--json`. Trees are named by their `name` setting or by their root ID.
+
+use crate::{DEFAULT_CLI_KEY, server};
+use eidetica::Tree;
+use eidetica::auth::types::SigKey;
+use eidetica::backend::database::InMemory;
+use eidetica::basedb::BaseDB;
+use eidetica::crdt::Map;
+use eidetica::crdt::map::Value;
+use eidetica::entry::{Entry, ID};
+use eidetica::subtree::Dict;
+use serde_json::{Value as Json, json};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// Usage line and description of every command, in the order they are listed in help.
+pub(crate) const COMMANDS: &[(&str, &str)] = &[
+    (
+        "create-tree <name>",
+        "Create a new tree with the given name",
+    ),
+    ("list-trees", "List all trees"),
+    ("get-root <tree>", "Get the root ID of a tree"),
+    ("get-entry <entry-id>", "Get details of an entry by ID"),
+    (
+        "set <tree> <subtree> <key> <value>",
+        "Set a key in a Dict subtree; JSON values keep their type",
+    ),
+    (
+        "get <tree> <subtree> <key>",
+        "Print the value of a key in a Dict subtree",
+    ),
+    (
+        "delete <tree> <subtree> <key>",
+        "Delete a key from a Dict subtree",
+    ),
+    (
+        "list-keys <tree> <subtree>",
+        "List the keys of a Dict subtree",
+    ),
+    (
+        "show-subtree <tree> <subtree>",
+        "Print the merged state of a Dict subtree as JSON",
+    ),
+    (
+        "export <tree> [<file>]",
+        "Write a tree as a bundle to a file or stdout",
+    ),
+    ("import <file>", "Import a tree from a bundle file"),
+    (
+        "reconcile <tree> <file>",
+        "Compare a tree with a replica exported as a bundle and plan a repair",
+    ),
+    (
+        "dag <tree> [<format>]",
+        "Print the entry DAG of a tree; format is 'ascii' (default) or 'dot' for Graphviz",
+    ),
+];
+
+/// The result of a successful command.
+pub(crate) struct Output {
+    /// Human readable output
+    pub text: String,
+    /// Machine readable output, printed with `--json`
+    pub json: Json,
+}
+
+impl Output {
+    fn new(text: impl Into<String>, json: Json) -> Self {
+        Self {
+            text: text.into(),
+            json,
+        }
+    }
+}
+
+/// Why a command failed.
+#[derive(Debug)]
+pub(crate) enum CommandError {
+    /// The command was called with the wrong arguments; holds its usage line
+    Usage(&'static str),
+    /// A tree, entry or key that the command needs does not exist
+    NotFound(String),
+    /// The database returned an error
+    Failed(eidetica::Error),
+}
+
+impl CommandError {
+    /// The process exit code for this error in one-shot mode.
+    pub(crate) fn exit_code(&self) -> i32 {
+        match self {
+            CommandError::Failed(_) => 1,
+            CommandError::Usage(_) => 2,
+            CommandError::NotFound(_) => 3,
+        }
+    }
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandError::Usage(usage) => write!(f, "Usage: {usage}"),
+            CommandError::NotFound(message) => f.write_str(message),
+            CommandError::Failed(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl From<eidetica::Error> for CommandError {
+    fn from(e: eidetica::Error) -> Self {
+        if e.is_not_found() {
+            CommandError::NotFound(e.to_string())
+        } else {
+            CommandError::Failed(e)
+        }
+    }
+}
+
+impl From<serde_json::Error> for CommandError {
+    fn from(e: serde_json::Error) -> Self {
+        CommandError::Failed(e.into())
+    }
+}
+
+/// Returns true if `name` is one of the [`COMMANDS`].
+pub(crate) fn is_command(name: &str) -> bool {
+    COMMANDS
+        .iter()
+        .any(|(usage, _)| usage.split(' ').next() == Some(name))
+}
+
+/// Returns true if the command changes the database, so it must be saved afterwards.
+pub(crate) fn modifies(name: &str) -> bool {
+    matches!(name, "create-tree" | "set" | "delete" | "import")
+}
+
+/// Runs the command `name` with `args`.
+pub(crate) fn run(db: &BaseDB, name: &str, args: &[&str]) -> Result<Output, CommandError> {
+    let usage = COMMANDS
+        .iter()
+        .map(|(usage, _)| *usage)
+        .find(|usage| usage.split(' ').next() == Some(name))
+        .unwrap_or("help");
+    // Required arguments are the ones in angle brackets without square brackets
+    let required = usage.matches(" <").count();
+    let optional = usage.matches(" [").count();
+    if args.len() < required || args.len() > required + optional {
+        return Err(CommandError::Usage(usage));
+    }
+
+    match name {
+        "create-tree" => create_tree(db, args[0]),
+        "list-trees" => list_trees(db),
+        "get-root" => {
+            let tree = find_tree(db, args[0])?;
+            let root = tree.root_id().to_string();
+            Ok(Output::new(root.clone(), json!({ "root": root })))
+        }
+        "get-entry" => get_entry(db, args[0]),
+        "set" => {
+            let tree = find_tree(db, args[0])?;
+            let op = tree.new_authenticated_operation(DEFAULT_CLI_KEY)?;
+            op.get_subtree::<Dict>(args[1])?
+                .set_value(args[2], parse_value(args[3]))?;
+            committed(op.commit()?)
+        }
+        "get" => get_key(db, args[0], args[1], args[2]),
+        "delete" => {
+            let tree = find_tree(db, args[0])?;
+            let op = tree.new_authenticated_operation(DEFAULT_CLI_KEY)?;
+            op.get_subtree::<Dict>(args[1])?.delete(args[2])?;
+            committed(op.commit()?)
+        }
+        "list-keys" => {
+            let state = subtree_state(&find_tree(db, args[0])?, args[1])?;
+            let mut keys: Vec<&String> = state
+                .iter()
+                .filter(|(_, value)| !matches!(value, Value::Deleted))
+                .map(|(key, _)| key)
+                .collect();
+            keys.sort();
+            let text = keys
+                .iter()
+                .map(|key| key.as_str())
+                .collect::<Vec<_>>()
+                .join("\n");
+            Ok(Output::new(text, json!(keys)))
+        }
+        "show-subtree" => {
+            let state = subtree_state(&find_tree(db, args[0])?, args[1])?;
+            let json = server::map_to_json(&state);
+            Ok(Output::new(serde_json::to_string_pretty(&json)?, json))
+        }
+        "export" => export(db, args[0], args.get(1).copied()),
+        "import" => import(db, args[0]),
+        "reconcile" => reconcile(db, args[0], args[1]),
+        "dag" => dag(db, args[0], args.get(1).copied().unwrap_or("ascii")),
+        _ => Err(CommandError::Usage(usage)),
+    }
+}
+
+/// Finds a tree by name, or by root ID if no tree has that name.
+///
+/// A name shared by several trees is an error; the root ID picks one of them.
+fn find_tree(db: &BaseDB, name: &str) -> Result<Tree, CommandError> {
+    match db.find_tree_by_name(name) {
+        Ok(tree) => Ok(tree),
+        Err(e) if e.is_not_found() => db
+            .load_tree(&ID::from(name))
+            .map_err(|_| CommandError::NotFound(format!("Tree '{name}' not found"))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Parses a value given on the command line.
+///
+/// Valid JSON is stored as the matching CRDT value, so `42`, `true` and
+/// `{"a": 1}` are an integer, a boolean and a map. Anything else is stored as
+/// text, so quotes are only needed for text that would otherwise parse as JSON.
+fn parse_value(input: &str) -> Value {
+    match serde_json::from_str(input) {
+        Ok(json) => server::json_to_value(&json),
+        Err(_) => Value::Text(input.to_string()),
+    }
+}
+
+/// The merged state of a Dict subtree.
+fn subtree_state(tree: &Tree, subtree: &str) -> eidetica::Result<Map> {
+    tree.get_subtree_viewer::<Dict>(subtree)?.get_all()
+}
+
+fn committed(id: ID) -> Result<Output, CommandError> {
+    Ok(Output::new(
+        format!("Committed entry {id}"),
+        json!({ "entry": id.to_string() }),
+    ))
+}
+
+fn tree_summary(tree: &Tree) -> Json {
+    json!({
+        "name": tree.get_name().ok(),
+        "root": tree.root_id().to_string(),
+    })
+}
+
+fn create_tree(db: &BaseDB, name: &str) -> Result<Output, CommandError> {
+    let mut settings = Map::new();
+    settings.set_string("name", name);
+    let tree = db.new_tree(settings, DEFAULT_CLI_KEY)?;
+    Ok(Output::new(
+        format!("Created tree '{}' with root ID: {}", name, tree.root_id()),
+        tree_summary(&tree),
+    ))
+}
+
+fn list_trees(db: &BaseDB) -> Result<Output, CommandError> {
+    let trees = db.all_trees()?;
+    let text = if trees.is_empty() {
+        "No trees created yet".to_string()
+    } else {
+        trees
+            .iter()
+            .map(|tree| {
+                let name = tree.get_name().unwrap_or_else(|_| "<unnamed>".to_string());
+                format!("{} (root: {})", name, tree.root_id())
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    Ok(Output::new(text, trees.iter().map(tree_summary).collect()))
+}
+
+fn get_entry(db: &BaseDB, id: &str) -> Result<Output, CommandError> {
+    let entry = db
+        .backend()
+        .get(&ID::from(id))
+        .map_err(|_| CommandError::NotFound(format!("Entry with ID '{id}' not found")))?;
+    Ok(Output::new(
+        format_entry(&entry),
+        serde_json::to_value(&entry)?,
+    ))
+}
+
+/// Looks up a key, printing text values as they are so scripts can use them directly.
+fn get_key(db: &BaseDB, tree: &str, subtree: &str, key: &str) -> Result<Output, CommandError> {
+    let dict = find_tree(db, tree)?.get_subtree_viewer::<Dict>(subtree)?;
+    let value = match dict.get(key) {
+        Ok(value) => value,
+        Err(e) if e.is_not_found() => {
+            return Err(CommandError::NotFound(format!(
+                "Key '{key}' not found in subtree '{subtree}'"
+            )));
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let json = server::value_to_json(&value);
+    let text = match &value {
+        Value::Text(text) => text.clone(),
+        _ => json.to_string(),
+    };
+    Ok(Output::new(text, json))
+}
+
+fn export(db: &BaseDB, name: &str, path: Option<&str>) -> Result<Output, CommandError> {
+    let tree = find_tree(db, name)?;
+    let mut bundle = Vec::new();
+    let count = tree.export_bundle(&mut bundle)?;
+
+    let Some(path) = path else {
+        let json: Json = serde_json::from_slice(&bundle)?;
+        return Ok(Output::new(serde_json::to_string_pretty(&json)?, json));
+    };
+    std::fs::write(path, &bundle).map_err(|e| CommandError::Failed(e.into()))?;
+    Ok(Output::new(
+        format!("Exported {count} entries to {path}"),
+        json!({ "path": path, "entries": count }),
+    ))
+}
+
+fn import(db: &BaseDB, path: &str) -> Result<Output, CommandError> {
+    let file = std::fs::File::open(path).map_err(|e| CommandError::Failed(e.into()))?;
+    let tree = db.import_bundle(std::io::BufReader::new(file))?;
+    Ok(Output::new(
+        format!("Imported tree with root ID: {}", tree.root_id()),
+        tree_summary(&tree),
+    ))
+}
+
+/// Compares a tree with the replica in a bundle, without importing the bundle.
+fn reconcile(db: &BaseDB, name: &str, path: &str) -> Result<Output, CommandError> {
+    let tree = find_tree(db, name)?;
+    let file = std::fs::File::open(path).map_err(|e| CommandError::Failed(e.into()))?;
+    let scratch = BaseDB::new(Box::new(InMemory::new()));
+    let other = scratch.import_bundle(std::io::BufReader::new(file))?;
+    let plan = tree.reconcile_with(&other)?;
+
+    let ids = |ids: &[ID]| ids.iter().map(ID::to_string).collect::<Vec<_>>();
+    let mut lines = vec![format!("Shared entries: {}", plan.shared)];
+    if plan.is_consistent() {
+        lines.push("The replicas are consistent".to_string());
+    }
+    for id in &plan.copy_to_local {
+        lines.push(format!("Copy from bundle: {id}"));
+    }
+    for id in &plan.copy_to_other {
+        lines.push(format!("Copy to bundle: {id}"));
+    }
+    for entry in &plan.quarantine {
+        lines.push(format!(
+            "Quarantine ({}): {} ({})",
+            entry.replica, entry.id, entry.reason
+        ));
+    }
+    let quarantine: Vec<Json> = plan
+        .quarantine
+        .iter()
+        .map(|entry| {
+            json!({
+                "id": entry.id.to_string(),
+                "replica": entry.replica.to_string(),
+                "reason": entry.reason.to_string(),
+            })
+        })
+        .collect();
+    Ok(Output::new(
+        lines.join("\n"),
+        json!({
+            "shared": plan.shared,
+            "copy_to_local": ids(&plan.copy_to_local),
+            "copy_to_other": ids(&plan.copy_to_other),
+            "quarantine": quarantine,
+        }),
+    ))
+}
+
+/// An entry of a tree, as shown by `dag`.
+struct DagNode {
+    entry: Entry,
+    id: ID,
+    /// Distance from the root along the longest path of parents
+    height: usize,
+    tip: bool,
+}
+
+/// Prints the entries of a tree with their heights, subtrees and signing keys.
+///
+/// Entries are listed newest first. The DOT output draws an edge from each
+/// entry to its parents, and marks tips with a double border.
+fn dag(db: &BaseDB, name: &str, format: &str) -> Result<Output, CommandError> {
+    if !matches!(format, "ascii" | "dot") {
+        return Err(CommandError::Usage("dag <tree> [ascii|dot]"));
+    }
+    let tree = find_tree(db, name)?;
+    let tips: HashSet<ID> = tree.get_tips()?.into_iter().collect();
+
+    // The backend returns entries in topological order, so parents come first
+    let mut heights: HashMap<ID, usize> = HashMap::new();
+    let mut nodes = Vec::new();
+    for entry in tree.get_all_entries()? {
+        let id = entry.id();
+        let height = entry
+            .parents()
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|parent| heights.get(parent))
+            .max()
+            .map_or(0, |height| height + 1);
+        heights.insert(id.clone(), height);
+        let tip = tips.contains(&id);
+        nodes.push(DagNode {
+            entry,
+            id,
+            height,
+            tip,
+        });
+    }
+    nodes.sort_by(|a, b| b.height.cmp(&a.height).then_with(|| a.id.cmp(&b.id)));
+
+    let text = match format {
+        "dot" => dag_dot(&tree, &nodes),
+        _ => dag_ascii(&nodes),
+    };
+    let json = nodes
+        .iter()
+        .map(|node| {
+            json!({
+                "id": node.id.to_string(),
+                "height": node.height,
+                "parents": node.entry.parents().unwrap_or_default().iter().map(ID::to_string).collect::<Vec<_>>(),
+                "subtrees": node.entry.subtrees(),
+                "key": signing_key(&node.entry),
+                "tip": node.tip,
+            })
+        })
+        .collect();
+    Ok(Output::new(text, json))
+}
+
+fn dag_ascii(nodes: &[DagNode]) -> String {
+    nodes
+        .iter()
+        .map(|node| {
+            let parents = node.entry.parents().unwrap_or_default();
+            // Merges and the root stand out from ordinary commits
+            let marker = match parents.len() {
+                0 => 'o',
+                1 => '*',
+                _ => 'M',
+            };
+            let mut line = format!(
+                "{marker} {:>4}  {}  key={}  subtrees=[{}]",
+                node.height,
+                short_id(&node.id),
+                signing_key(&node.entry),
+                node.entry.subtrees().join(", "),
+            );
+            if !parents.is_empty() {
+                let parents: Vec<&str> = parents.iter().map(short_id).collect();
+                line.push_str(&format!("  parents=[{}]", parents.join(", ")));
+            }
+            if node.tip {
+                line.push_str("  (tip)");
+            }
+            line
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn dag_dot(tree: &Tree, nodes: &[DagNode]) -> String {
+    let escape = |text: &str| text.replace('\\', "\\\\").replace('"', "\\\"");
+    let name = tree
+        .get_name()
+        .unwrap_or_else(|_| tree.root_id().to_string());
+    let mut lines = vec![
+        format!("digraph \"{}\" {{", escape(&name)),
+        "  rankdir=BT;".to_string(),
+        "  node [shape=box, fontname=monospace];".to_string(),
+    ];
+    for node in nodes {
+        let label = [
+            short_id(&node.id).to_string(),
+            format!("height {}", node.height),
+            format!("key: {}", signing_key(&node.entry)),
+            node.entry.subtrees().join(", "),
+        ]
+        .map(|line| escape(&line))
+        .join("\\n");
+        let style = if node.tip { ", peripheries=2" } else { "" };
+        let id = escape(node.id.as_str());
+        lines.push(format!("  \"{id}\" [label=\"{label}\"{style}];"));
+        for parent in node.entry.parents().unwrap_or_default() {
+            lines.push(format!("  \"{id}\" -> \"{}\";", escape(parent.as_str())));
+        }
+    }
+    lines.push("}".to_string());
+    lines.join("\n")
+}
+
+/// The first 12 characters of an ID's digest, enough to tell entries apart.
+fn short_id(id: &ID) -> &str {
+    let digest = id.digest().unwrap_or(id.as_str());
+    let end = digest
+        .char_indices()
+        .nth(12)
+        .map_or(digest.len(), |(i, _)| i);
+    &digest[..end]
+}
+
+/// The name of the key that signed an entry, following any delegation path.
+fn signing_key(entry: &Entry) -> String {
+    match &entry.sig.key {
+        SigKey::Direct(name) if name.is_empty() => "<unsigned>".to_string(),
+        SigKey::Direct(name) => name.clone(),
+        SigKey::DelegationPath(steps) => steps
+            .iter()
+            .map(|step| step.key.as_str())
+            .collect::<Vec<_>>()
+            .join(" -> "),
+    }
+}
+
+fn format_entry(entry: &Entry) -> String {
+    let mut lines = vec![
+        format!("ID: {}", entry.id()),
+        format!("Root: {}", entry.root()),
+    ];
+    for subtree in entry.subtrees() {
+        lines.push(format!("Subtree: {subtree}"));
+        match entry.data(&subtree) {
+            Ok(data) => lines.push(format!("  Data: {data}")),
+            Err(_) => lines.push("  Data: <no data>".to_string()),
+        }
+    }
+    lines.push(format!(
+        "Parents: {:?}",
+        entry.parents().unwrap_or_default()
+    ));
+    lines.join("\n")
+}
@@ -1,26 +1,42 @@
-use eidetica::Tree;
+mod commands;
+mod server;
+
+use commands::{COMMANDS, CommandError};
 use eidetica::backend::database::InMemory;
-use eidetica::basedb::BaseDB;
-use eidetica::entry::Entry;
+use eidetica::basedb::errors::BaseError;
+use eidetica::basedb::{AutoPersist, AutoPersistConfig, BaseDB};
+use serde_json::json;
 use signal_hook::flag as signal_flag;
-use std::collections::HashMap;
 use std::io::{self, BufRead, Write};
+use std::path::Path;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 
 const DB_FILE: &str = "eidetica.json";
 
+/// Key used to sign all entries created from the CLI (all entries must be authenticated)
+pub(crate) const DEFAULT_CLI_KEY: &str = "cli_default_key";
+
+/// Default address for `serve` when none is given
+const DEFAULT_SERVE_ADDR: &str = "127.0.0.1:3000";
+
+/// Writes the database to `DB_FILE`.
+fn write_database(db: &BaseDB) -> eidetica::Result<()> {
+    match db.backend().as_any().downcast_ref::<InMemory>() {
+        Some(in_memory_backend) => in_memory_backend.save_to_file(DB_FILE),
+        None => Err(BaseError::InvalidOperation {
+            reason: "only InMemory databases can be saved".to_string(),
+        }
+        .into()),
+    }
+}
+
 // Helper function to save the database
 fn save_database(db: &BaseDB) {
     println!("Saving database to {DB_FILE}...");
-    let backend_any = db.backend().as_any();
-    if let Some(in_memory_backend) = backend_any.downcast_ref::<InMemory>() {
-        match in_memory_backend.save_to_file(DB_FILE) {
-            Ok(_) => println!("Database saved successfully."),
-            Err(e) => println!("Failed to save database: {e:?}"),
-        }
-    } else {
-        println!("Failed to downcast database to InMemory for saving.");
+    match write_database(db) {
+        Ok(()) => println!("Database saved successfully."),
+        Err(e) => println!("Failed to save database: {e:?}"),
     }
 }
 
@@ -35,33 +51,125 @@ fn main() -> io::Result<()> {
         let _ = signal_flag::register(*signal, Arc::clone(&term_signal));
     }
 
-    println!("Welcome to Eidetica REPL");
-    println!("Database is automatically loaded from and saved to '{DB_FILE}'");
-    print_help();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        None => run_repl(&term_signal),
+        Some("serve") => {
+            let addr = args.get(1).map_or(DEFAULT_SERVE_ADDR, String::as_str);
+            let db = load_database();
+            // Commits are saved in the background; the final save happens on shutdown
+            let persist = AutoPersist::to_file(&db, DB_FILE, AutoPersistConfig::default())
+                .map_err(io::Error::other)?;
+            persist.notify();
+            server::run(&db, addr, &term_signal)?;
+            println!("Saving database to {DB_FILE}...");
+            match persist.shutdown() {
+                Ok(()) => println!("Database saved successfully."),
+                Err(e) => println!("Failed to save database: {e:?}"),
+            }
+            Ok(())
+        }
+        Some("help" | "--help" | "-h") => {
+            print_usage();
+            Ok(())
+        }
+        Some(name) if commands::is_command(name) => {
+            let code = run_once(&args);
+            std::process::exit(code);
+        }
+        Some(other) => {
+            println!("Unknown command: {other}");
+            print_usage();
+            std::process::exit(2);
+        }
+    }
+}
 
-    // Create or load the in-memory backend
-    let backend: Box<dyn eidetica::backend::Database> = match InMemory::load_from_file(DB_FILE) {
-        Ok(backend) => {
-            println!("Loaded database from {DB_FILE}");
-            Box::new(backend)
+/// Opens the database at `DB_FILE`, or creates a new one, and ensures the CLI key exists.
+fn open_database() -> eidetica::Result<BaseDB> {
+    let db = BaseDB::open(DB_FILE)?;
+    // Add a default key for CLI operations (all entries must now be authenticated)
+    if db.get_public_key(DEFAULT_CLI_KEY)?.is_none() {
+        db.add_private_key(DEFAULT_CLI_KEY)?;
+    }
+    Ok(db)
+}
+
+/// Loads the database from `DB_FILE`, or creates a new one if it cannot be loaded.
+fn load_database() -> BaseDB {
+    match open_database() {
+        Ok(db) => {
+            if Path::new(DB_FILE).exists() {
+                println!("Loaded database from {DB_FILE}");
+            }
+            db
         }
         Err(e) => {
             println!("Failed to load database: {e:?}. Creating a new one.");
-            Box::new(InMemory::new())
+            let db = BaseDB::new(Box::new(InMemory::new()));
+            if let Err(e) = db.add_private_key(DEFAULT_CLI_KEY) {
+                println!("Failed to add CLI key: {e:?}");
+            }
+            db
         }
-    };
+    }
+}
 
-    // Initialize BaseDB with the loaded or new backend
-    let db = BaseDB::new(backend);
+/// Runs a single command given on the command line and returns the exit code.
+///
+/// The result is printed to stdout and errors to stderr, both as JSON if
+/// `--json` is given. Commands that change the database save it before returning.
+fn run_once(args: &[String]) -> i32 {
+    let as_json = args.iter().any(|arg| arg == "--json");
+    let args: Vec<&str> = args
+        .iter()
+        .map(String::as_str)
+        .filter(|arg| *arg != "--json")
+        .collect();
 
-    // Add a default key for CLI operations (all entries must now be authenticated)
-    const DEFAULT_CLI_KEY: &str = "cli_default_key";
-    if db.add_private_key(DEFAULT_CLI_KEY).is_err() {
-        // Key might already exist, which is fine
+    let result = open_database()
+        .map_err(CommandError::Failed)
+        .and_then(|db| {
+            let output = commands::run(&db, args[0], &args[1..])?;
+            if commands::modifies(args[0]) {
+                write_database(&db).map_err(CommandError::Failed)?;
+            }
+            Ok(output)
+        });
+
+    // Write errors are ignored so that piping into e.g. `head` does not panic
+    let mut stdout = io::stdout().lock();
+    match result {
+        Ok(output) if as_json => {
+            let _ = writeln!(stdout, "{}", output.json);
+            0
+        }
+        Ok(output) => {
+            if !output.text.is_empty() {
+                let _ = writeln!(stdout, "{}", output.text);
+            }
+            0
+        }
+        Err(e) => {
+            if as_json {
+                eprintln!(
+                    "{}",
+                    json!({ "error": e.to_string(), "code": e.exit_code() })
+                );
+            } else {
+                eprintln!("{e}");
+            }
+            e.exit_code()
+        }
     }
+}
 
-    // Store trees by name
-    let mut trees: HashMap<String, Tree> = HashMap::new();
+fn run_repl(term_signal: &Arc<AtomicBool>) -> io::Result<()> {
+    println!("Welcome to Eidetica REPL");
+    println!("Database is automatically loaded from and saved to '{DB_FILE}'");
+    print_help();
+
+    let db = load_database();
 
     // Restore trees using the new BaseDB.all_trees method
     match db.all_trees() {
@@ -70,7 +178,6 @@ fn main() -> io::Result<()> {
                 match tree.get_name() {
                     Ok(name) => {
                         println!("Restored tree '{}' with root ID: {}", name, tree.root_id());
-                        trees.insert(name.clone(), tree);
                     }
                     Err(e) => {
                         println!(
@@ -105,7 +212,10 @@ fn main() -> io::Result<()> {
         stdout.flush()?;
 
         input.clear();
-        stdin.lock().read_line(&mut input)?;
+        if stdin.lock().read_line(&mut input)? == 0 {
+            // End of input, as if `exit` was typed
+            break;
+        }
 
         let args: Vec<&str> = input.split_whitespace().collect();
 
@@ -128,75 +238,20 @@ fn main() -> io::Result<()> {
             "save" => {
                 save_database(&db);
             }
-            "create-tree" => {
-                if args.len() < 3 {
-                    println!("Usage: create-tree <name>");
-                    continue;
-                }
-
-                let name = args[1];
-
-                match db.new_tree_default(DEFAULT_CLI_KEY) {
-                    Ok(tree) => {
-                        println!("Created tree '{}' with root ID: {}", name, tree.root_id());
-                        trees.insert(name.to_string(), tree);
-                    }
-                    Err(e) => println!("Error creating tree: {e:?}"),
-                }
-            }
-            "list-trees" => {
-                if trees.is_empty() {
-                    println!("No trees created yet");
-                } else {
-                    println!("Trees:");
-                    for (name, tree) in &trees {
-                        println!("  {} (root: {})", name, tree.root_id());
-                    }
-                }
-            }
-            "get-root" => {
-                if args.len() < 2 {
-                    println!("Usage: get-root <tree-name>");
-                    continue;
+            name if commands::is_command(name) => {
+                let mut args = args[1..].to_vec();
+                // The value of `set` is the rest of the line, spaces included
+                if name == "set" && args.len() > 3 {
+                    args.truncate(3);
+                    args.push(words_after(&input, 4));
                 }
-
-                let name = args[1];
-
-                if let Some(tree) = trees.get(name) {
-                    println!("Root ID for tree '{}': {}", name, tree.root_id());
-                } else {
-                    println!("Tree '{name}' not found");
-                }
-            }
-            "get-entry" => {
-                if args.len() < 2 {
-                    println!("Usage: get-entry <entry-id>");
-                    continue;
-                }
-
-                let id = args[1];
-                let mut found = false;
-
-                for (name, tree) in &trees {
-                    if tree.root_id() == id {
-                        match tree.get_root() {
-                            Ok(entry) => {
-                                println!("Entry found in tree '{name}':");
-                                print_entry(&entry);
-                                found = true;
-                                break;
-                            }
-                            Err(e) => {
-                                println!("Error retrieving entry: {e:?}");
-                                found = true;
-                                break;
-                            }
+                match commands::run(&db, name, &args) {
+                    Ok(output) => {
+                        if !output.text.is_empty() {
+                            println!("{}", output.text);
                         }
                     }
-                }
-
-                if !found {
-                    println!("Entry with ID '{id}' not found");
+                    Err(e) => println!("{e}"),
                 }
             }
             _ => println!(
@@ -215,33 +270,49 @@ fn main() -> io::Result<()> {
     Ok(())
 }
 
+/// Returns the rest of `line` after its first `count` words.
+fn words_after(line: &str, count: usize) -> &str {
+    let mut rest = line.trim_start();
+    for _ in 0..count {
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        rest = rest[end..].trim_start();
+    }
+    rest.trim_end()
+}
+
+fn print_usage() {
+    println!("Usage:");
+    println!("  eidetica                - Start the interactive REPL");
+    println!(
+        "  eidetica serve [<addr>] - Serve trees over HTTP/JSON (default {DEFAULT_SERVE_ADDR})"
+    );
+    println!("  eidetica <command> [--json]");
+    println!("                          - Run one command against '{DB_FILE}' and exit");
+    println!("  eidetica help           - Show this help message");
+    println!();
+    print_commands();
+    println!();
+    println!("With --json, results are printed as JSON and errors as");
+    println!("{{\"error\": ..., \"code\": ...}} on stderr.");
+    println!("Exit codes: 0 success, 1 error, 2 invalid arguments, 3 not found.");
+}
+
 fn print_help() {
-    println!("Available commands:");
+    print_commands();
     println!("  help                  - Show this help message");
-    println!("  create-tree <n> <settings> - Create a new tree with the given name and settings");
-    println!("  list-trees            - List all created trees");
-    println!("  get-root <tree-name>  - Get the root ID of a tree");
-    println!("  get-entry <entry-id>  - Get details of an entry by ID");
     println!("  save                  - Save the database to disk");
     println!("  exit                  - Save database and exit the REPL");
     println!("  exit-no-save          - Exit the REPL without saving the database");
 }
 
-fn print_entry(entry: &Entry) {
-    println!("  ID: {}", entry.id());
-    println!("  Root: {}", entry.root());
-    for subtree in entry.subtrees() {
-        println!("  Subtree: {subtree}");
-        println!("    Data:");
-        if let Ok(data) = entry.data(&subtree) {
-            println!("      {data}");
+fn print_commands() {
+    println!("Available commands:");
+    for (usage, description) in COMMANDS {
+        if usage.len() < 22 {
+            println!("  {usage:<21} - {description}");
         } else {
-            println!("      <no data>");
+            println!("  {usage}");
+            println!("  {:<21} - {description}", "");
         }
     }
-    if let Ok(parents) = entry.parents() {
-        println!("  Parents: {parents:?}");
-    } else {
-        println!("  Parents: []");
-    }
 }
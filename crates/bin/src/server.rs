@@ -0,0 +1,253 @@
+//! HTTP/JSON server mode
+//!
+//! Exposes the trees of a database over HTTP so that clients in any language can
+//! read and write them. All request and response bodies are JSON.
+//!
+//! | Method | Path                                | Description                                   |
+//! |--------|-------------------------------------|-----------------------------------------------|
+//! | GET    | `/trees`                            | List trees as `{"name", "root"}` objects      |
+//! | POST   | `/trees`                            | Create a tree, body `{"name": "..."}`         |
+//! | GET    | `/trees/{root}`                     | Tree name, root ID and tips                   |
+//! | GET    | `/trees/{root}/entries`             | All entries in a tree                         |
+//! | GET    | `/trees/{root}/subtrees/{name}`     | Merged state of a Dict subtree as plain JSON  |
+//! | POST   | `/trees/{root}/operations`          | Commit changes, see below                     |
+//! | GET    | `/entries/{id}`                     | A single entry                                |
+//!
+//! An operation body maps subtree names to the keys to set in that subtree; a
+//! `null` value deletes the key:
+//!
+//! ```json
+//! {"subtrees": {"users": {"alice": {"age": 30}, "bob": null}}}
+//! ```
+
+use crate::DEFAULT_CLI_KEY;
+use eidetica::Tree;
+use eidetica::basedb::BaseDB;
+use eidetica::crdt::Map;
+use eidetica::crdt::map::{List, Value};
+use eidetica::entry::ID;
+use eidetica::subtree::Dict;
+use serde_json::{Value as Json, json};
+use std::io;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+/// How often the server checks for a termination signal while idle.
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// An HTTP response status and JSON body.
+type Reply = (u16, Json);
+
+/// Serves the database on `addr` until a termination signal is received.
+pub fn run(db: &BaseDB, addr: &str, term_signal: &Arc<AtomicBool>) -> io::Result<()> {
+    let server = Server::http(addr).map_err(io::Error::other)?;
+    println!(
+        "Serving Eidetica over HTTP on http://{}",
+        server.server_addr()
+    );
+
+    while !term_signal.load(Ordering::Relaxed) {
+        let Some(mut request) = server.recv_timeout(POLL_INTERVAL)? else {
+            continue;
+        };
+
+        let mut body = String::new();
+        let (status, json) = match request.as_reader().read_to_string(&mut body) {
+            Ok(_) => handle(db, request.method(), request.url(), &body),
+            Err(e) => error(400, format!("Failed to read request body: {e}")),
+        };
+        respond(request, status, &json);
+    }
+
+    println!("\nTermination signal received, stopping server...");
+    Ok(())
+}
+
+fn respond(request: Request, status: u16, json: &Json) {
+    let header =
+        Header::from_bytes("Content-Type", "application/json").expect("static header is valid");
+    let response = Response::from_string(json.to_string())
+        .with_status_code(status)
+        .with_header(header);
+    if let Err(e) = request.respond(response) {
+        println!("Failed to send response: {e}");
+    }
+}
+
+/// Routes a request and returns the response to send.
+fn handle(db: &BaseDB, method: &Method, url: &str, body: &str) -> Reply {
+    let path = url.split('?').next().unwrap_or_default();
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+    let result = match (method, segments.as_slice()) {
+        (Method::Get, ["trees"]) => list_trees(db),
+        (Method::Post, ["trees"]) => create_tree(db, body),
+        (Method::Get, ["trees", root]) => get_tree(db, root),
+        (Method::Get, ["trees", root, "entries"]) => get_tree_entries(db, root),
+        (Method::Get, ["trees", root, "subtrees", name]) => get_subtree(db, root, name),
+        (Method::Post, ["trees", root, "operations"]) => commit_operation(db, root, body),
+        (Method::Get, ["entries", id]) => get_entry(db, id),
+        (_, ["trees"] | ["trees", ..] | ["entries", _]) => {
+            return error(405, format!("Method {method} not allowed for {path}"));
+        }
+        _ => return error(404, format!("No route for {path}")),
+    };
+
+    result.unwrap_or_else(|e| {
+        let status = if e.is_not_found() { 404 } else { 500 };
+        error(status, e.to_string())
+    })
+}
+
+fn error(status: u16, message: impl Into<String>) -> Reply {
+    (status, json!({ "error": message.into() }))
+}
+
+fn tree_summary(tree: &Tree) -> Json {
+    json!({
+        "name": tree.get_name().ok(),
+        "root": tree.root_id().to_string(),
+    })
+}
+
+fn list_trees(db: &BaseDB) -> eidetica::Result<Reply> {
+    let trees = db.all_trees()?;
+    Ok((200, trees.iter().map(tree_summary).collect()))
+}
+
+fn create_tree(db: &BaseDB, body: &str) -> eidetica::Result<Reply> {
+    let mut settings = Map::new();
+    if !body.trim().is_empty() {
+        let request: Json = match serde_json::from_str(body) {
+            Ok(request) => request,
+            Err(e) => return Ok(error(400, format!("Invalid JSON body: {e}"))),
+        };
+        match request.get("name") {
+            Some(Json::String(name)) => {
+                settings.set_string("name", name.clone());
+            }
+            None | Some(Json::Null) => {}
+            Some(_) => return Ok(error(400, "Tree name must be a string")),
+        }
+    }
+
+    let tree = db.new_tree(settings, DEFAULT_CLI_KEY)?;
+    Ok((201, tree_summary(&tree)))
+}
+
+fn get_tree(db: &BaseDB, root: &str) -> eidetica::Result<Reply> {
+    let tree = db.load_tree(&ID::from(root))?;
+    let mut summary = tree_summary(&tree);
+    let tips: Vec<String> = tree.get_tips()?.iter().map(ID::to_string).collect();
+    summary["tips"] = json!(tips);
+    Ok((200, summary))
+}
+
+fn get_tree_entries(db: &BaseDB, root: &str) -> eidetica::Result<Reply> {
+    let tree = db.load_tree(&ID::from(root))?;
+    let entries = tree.get_all_entries()?;
+    Ok((200, serde_json::to_value(entries)?))
+}
+
+fn get_subtree(db: &BaseDB, root: &str, name: &str) -> eidetica::Result<Reply> {
+    let tree = db.load_tree(&ID::from(root))?;
+    let state = tree.get_subtree_viewer::<Dict>(name)?.get_all()?;
+    Ok((200, map_to_json(&state)))
+}
+
+fn get_entry(db: &BaseDB, id: &str) -> eidetica::Result<Reply> {
+    let entry = db.backend().get(&ID::from(id))?;
+    Ok((200, serde_json::to_value(entry)?))
+}
+
+fn commit_operation(db: &BaseDB, root: &str, body: &str) -> eidetica::Result<Reply> {
+    let request: Json = match serde_json::from_str(body) {
+        Ok(request) => request,
+        Err(e) => return Ok(error(400, format!("Invalid JSON body: {e}"))),
+    };
+    let Some(Json::Object(subtrees)) = request.get("subtrees") else {
+        return Ok(error(400, "Body must contain a \"subtrees\" object"));
+    };
+
+    let tree = db.load_tree(&ID::from(root))?;
+    let op = tree.new_authenticated_operation(DEFAULT_CLI_KEY)?;
+    for (name, changes) in subtrees {
+        let Json::Object(changes) = changes else {
+            return Ok(error(
+                400,
+                format!("Changes for subtree '{name}' must be an object"),
+            ));
+        };
+        let dict = op.get_subtree::<Dict>(name)?;
+        for (key, value) in changes {
+            match value {
+                Json::Null => dict.delete(key)?,
+                value => dict.set(key, json_to_value(value))?,
+            }
+        }
+    }
+    let id = op.commit()?;
+    Ok((201, json!({ "id": id.to_string() })))
+}
+
+/// Converts a CRDT value to plain JSON, leaving out deleted values.
+pub(crate) fn value_to_json(value: &Value) -> Json {
+    match value {
+        Value::Null | Value::Deleted => Json::Null,
+        Value::Bool(b) => Json::Bool(*b),
+        Value::Int(i) => Json::from(*i),
+        // Non-finite floats become null
+        Value::Float(f) => Json::from(*f),
+        Value::Text(s) => Json::String(s.clone()),
+        Value::Bytes(bytes) => json!({ "bytes": bytes }),
+        Value::Link(id) => json!({ "link": id.to_string() }),
+        Value::Map(map) => map_to_json(map),
+        Value::List(list) => Json::Array(
+            list.iter()
+                .filter(|v| !matches!(v, Value::Deleted))
+                .map(value_to_json)
+                .collect(),
+        ),
+    }
+}
+
+pub(crate) fn map_to_json(map: &Map) -> Json {
+    Json::Object(
+        map.iter()
+            .filter(|(_, v)| !matches!(v, Value::Deleted))
+            .map(|(k, v)| (k.clone(), value_to_json(v)))
+            .collect(),
+    )
+}
+
+/// Converts plain JSON to a CRDT value.
+///
+/// Numbers that fit an `i64` are stored as integers, other numbers as floats.
+pub(crate) fn json_to_value(json: &Json) -> Value {
+    match json {
+        Json::Null => Value::Null,
+        Json::Bool(b) => Value::Bool(*b),
+        Json::Number(n) => match (n.as_i64(), n.as_f64()) {
+            (Some(i), _) => Value::Int(i),
+            (None, Some(f)) => Value::Float(f),
+            (None, None) => Value::Text(n.to_string()),
+        },
+        Json::String(s) => Value::Text(s.clone()),
+        Json::Array(items) => {
+            let mut list = List::new();
+            for item in items {
+                list.push(json_to_value(item));
+            }
+            Value::List(list)
+        }
+        Json::Object(fields) => {
+            let mut map = Map::new();
+            for (key, value) in fields {
+                map.set(key.clone(), json_to_value(value));
+            }
+            Value::Map(map)
+        }
+    }
+}
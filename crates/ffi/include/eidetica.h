@@ -0,0 +1,98 @@
+/*
+ * C bindings for Eidetica.
+ *
+ * Every function returns an eidetica_status_t. On failure, a description is
+ * available from eidetica_last_error() on the same thread, and out-parameters
+ * are left untouched.
+ *
+ * Databases, trees and operations are referred to by handles, released with
+ * eidetica_handle_release(). Handles are never reused and are never 0.
+ *
+ * Strings are NUL-terminated UTF-8. Strings returned through out-parameters
+ * are owned by the caller and freed with eidetica_string_free().
+ *
+ * Every function may be called from any thread.
+ */
+
+#ifndef EIDETICA_H
+#define EIDETICA_H
+
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef enum eidetica_status {
+    /* The call succeeded */
+    EIDETICA_OK = 0,
+    /* A pointer argument was null, or a string was not valid UTF-8 */
+    EIDETICA_INVALID_ARGUMENT = 1,
+    /* The handle was never issued, was already released, or is of the wrong kind */
+    EIDETICA_INVALID_HANDLE = 2,
+    /* The tree, entry, key or value does not exist */
+    EIDETICA_NOT_FOUND = 3,
+    /* The signing key is not allowed to make the change */
+    EIDETICA_PERMISSION_DENIED = 4,
+    /* The key or tree already exists, or the tree changed concurrently */
+    EIDETICA_CONFLICT = 5,
+    /* Reading or writing storage failed */
+    EIDETICA_IO = 6,
+    /* Any other error reported by Eidetica */
+    EIDETICA_FAILED = 7,
+    /* Eidetica panicked; the database may be in an inconsistent state */
+    EIDETICA_PANIC = 8,
+} eidetica_status_t;
+
+typedef uint64_t eidetica_handle_t;
+
+/* Message of the last failed call on this thread, or NULL if it succeeded.
+ * Owned by the library, valid until the next eidetica_* call on this thread. */
+const char *eidetica_last_error(void);
+
+/* Frees a string returned by this library. NULL is ignored. */
+void eidetica_string_free(char *value);
+
+/* Releases a database, tree or operation. Releasing an operation discards
+ * its staged changes. */
+eidetica_status_t eidetica_handle_release(eidetica_handle_t handle);
+
+/* Databases */
+
+eidetica_status_t eidetica_db_new_in_memory(eidetica_handle_t *out_db);
+/* Opens the journal at path, creating it if needed. Changes are appended to
+ * the journal as they are made. */
+eidetica_status_t eidetica_db_open(const char *path, eidetica_handle_t *out_db);
+eidetica_status_t eidetica_db_flush(eidetica_handle_t db);
+eidetica_status_t eidetica_db_add_private_key(eidetica_handle_t db, const char *key_name);
+
+/* Trees */
+
+eidetica_status_t eidetica_tree_create(eidetica_handle_t db, const char *key_name,
+                                       eidetica_handle_t *out_tree);
+eidetica_status_t eidetica_tree_load(eidetica_handle_t db, const char *root_id,
+                                     eidetica_handle_t *out_tree);
+eidetica_status_t eidetica_tree_root_id(eidetica_handle_t tree, char **out_root_id);
+/* Reads a committed value. EIDETICA_NOT_FOUND if the key is not set. */
+eidetica_status_t eidetica_tree_dict_get(eidetica_handle_t tree, const char *subtree,
+                                         const char *key, char **out_value);
+
+/* Operations */
+
+eidetica_status_t eidetica_op_new(eidetica_handle_t tree, eidetica_handle_t *out_op);
+eidetica_status_t eidetica_op_dict_set(eidetica_handle_t op, const char *subtree,
+                                       const char *key, const char *value);
+/* Reads a value, including changes staged in the operation. */
+eidetica_status_t eidetica_op_dict_get(eidetica_handle_t op, const char *subtree,
+                                       const char *key, char **out_value);
+eidetica_status_t eidetica_op_dict_delete(eidetica_handle_t op, const char *subtree,
+                                          const char *key);
+/* Commits the staged changes as one entry and releases the operation, whether
+ * or not the commit succeeds. out_entry_id may be NULL. */
+eidetica_status_t eidetica_op_commit(eidetica_handle_t op, char **out_entry_id);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* EIDETICA_H */
@@ -0,0 +1,116 @@
+//! Registry of the objects handed out to C callers
+//!
+//! C code never sees Rust pointers. Every database, tree and operation lives
+//! in a process-wide table and is referred to by a `u64` handle, which is
+//! never reused. A stale or mistyped handle is therefore reported as
+//! `EIDETICA_INVALID_HANDLE` instead of being dereferenced.
+
+use crate::status::{EideticaStatus, Failure};
+use eidetica::Tree;
+use eidetica::atomicop::AtomicOp;
+use eidetica::basedb::BaseDB;
+use std::collections::BTreeMap;
+use std::sync::{Mutex, MutexGuard};
+
+/// A handle to an object owned by the registry. Never 0.
+pub type EideticaHandle = u64;
+
+/// An object a handle refers to.
+pub(crate) enum Object {
+    Db(BaseDB),
+    Tree(Tree),
+    Op(AtomicOp),
+}
+
+impl Object {
+    fn kind(&self) -> &'static str {
+        match self {
+            Object::Db(_) => "database",
+            Object::Tree(_) => "tree",
+            Object::Op(_) => "operation",
+        }
+    }
+}
+
+struct Registry {
+    objects: BTreeMap<EideticaHandle, Object>,
+    next: EideticaHandle,
+}
+
+static REGISTRY: Mutex<Registry> = Mutex::new(Registry {
+    objects: BTreeMap::new(),
+    next: 1,
+});
+
+fn registry() -> MutexGuard<'static, Registry> {
+    // A panic while the lock was held is reported to that caller; the
+    // table itself is always left consistent
+    REGISTRY
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn invalid(handle: EideticaHandle, expected: &str) -> Failure {
+    Failure::new(
+        EideticaStatus::InvalidHandle,
+        format!("{handle} is not a live {expected} handle"),
+    )
+}
+
+/// Stores `object` and returns its new handle.
+pub(crate) fn insert(object: Object) -> EideticaHandle {
+    let mut registry = registry();
+    let handle = registry.next;
+    registry.next += 1;
+    registry.objects.insert(handle, object);
+    handle
+}
+
+/// Removes the object behind `handle`, whatever its kind.
+pub(crate) fn remove(handle: EideticaHandle) -> Result<Object, Failure> {
+    registry()
+        .objects
+        .remove(&handle)
+        .ok_or_else(|| invalid(handle, "live"))
+}
+
+/// A clone of the database behind `handle`.
+///
+/// Objects are cloned out of the registry so that no lock is held while
+/// Eidetica runs; databases and trees are cheap to clone.
+pub(crate) fn db(handle: EideticaHandle) -> Result<BaseDB, Failure> {
+    match registry().objects.get(&handle) {
+        Some(Object::Db(db)) => Ok(db.clone()),
+        _ => Err(invalid(handle, "database")),
+    }
+}
+
+/// A clone of the tree behind `handle`.
+pub(crate) fn tree(handle: EideticaHandle) -> Result<Tree, Failure> {
+    match registry().objects.get(&handle) {
+        Some(Object::Tree(tree)) => Ok(tree.clone()),
+        _ => Err(invalid(handle, "tree")),
+    }
+}
+
+/// A clone of the operation behind `handle`, sharing its staged changes.
+pub(crate) fn op(handle: EideticaHandle) -> Result<AtomicOp, Failure> {
+    match registry().objects.get(&handle) {
+        Some(Object::Op(op)) => Ok(op.clone()),
+        _ => Err(invalid(handle, "operation")),
+    }
+}
+
+/// Removes the operation behind `handle`, leaving other objects in place.
+pub(crate) fn take_op(handle: EideticaHandle) -> Result<AtomicOp, Failure> {
+    let mut registry = registry();
+    match registry.objects.remove(&handle) {
+        Some(Object::Op(op)) => Ok(op),
+        Some(other) => {
+            let kind = other.kind();
+            registry.objects.insert(handle, other);
+            Err(invalid(handle, &format!("operation (it is a {kind})")))
+        }
+        None => Err(invalid(handle, "operation")),
+    }
+}
@@ -0,0 +1,393 @@
+//! C bindings for Eidetica
+//!
+//! This crate exposes a small `extern "C"` API over the `eidetica` crate, so
+//! that iOS, Android and other languages can embed Eidetica through a C
+//! library instead of reimplementing it. The matching header is
+//! `include/eidetica.h`.
+//!
+//! Conventions shared by every function:
+//!
+//! - Every function returns an [`EideticaStatus`]. On failure a description is
+//!   available from [`eidetica_last_error`] on the same thread, and
+//!   out-parameters are left untouched.
+//! - Databases, trees and operations are referred to by [`EideticaHandle`]s,
+//!   released with [`eidetica_handle_release`]. Handles are never reused, so a
+//!   released handle fails with `EIDETICA_INVALID_HANDLE`.
+//! - Strings are NUL-terminated UTF-8. Strings returned through out-parameters
+//!   are owned by the caller and freed with [`eidetica_string_free`].
+//! - Every function may be called from any thread. Panics are caught and
+//!   reported as `EIDETICA_PANIC`.
+//!
+//! Writes go through operations: create one with [`eidetica_op_new`], stage
+//! changes with [`eidetica_op_dict_set`], and commit them as one entry with
+//! [`eidetica_op_commit`].
+
+mod handles;
+mod status;
+
+pub use handles::EideticaHandle;
+pub use status::EideticaStatus;
+
+use eidetica::backend::database::InMemory;
+use eidetica::basedb::BaseDB;
+use eidetica::entry::ID;
+use eidetica::subtree::Dict;
+use handles::Object;
+use status::Failure;
+use std::ffi::{CStr, CString, c_char};
+use std::panic::{AssertUnwindSafe, catch_unwind};
+
+/// Runs the body of an exported function, turning its outcome and any panic
+/// into a status.
+fn call(body: impl FnOnce() -> Result<(), Failure>) -> EideticaStatus {
+    let outcome = catch_unwind(AssertUnwindSafe(body)).unwrap_or_else(|panic| {
+        let message = panic
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        Err(Failure::new(EideticaStatus::Panic, message))
+    });
+    status::report(outcome)
+}
+
+/// Reads a string argument.
+///
+/// # Safety
+/// `ptr` must be null or point to a NUL-terminated string that outlives the call.
+unsafe fn arg<'a>(ptr: *const c_char, name: &str) -> Result<&'a str, Failure> {
+    if ptr.is_null() {
+        return Err(Failure::new(
+            EideticaStatus::InvalidArgument,
+            format!("{name} is null"),
+        ));
+    }
+    // SAFETY: non-null and NUL-terminated per this function's contract
+    unsafe { CStr::from_ptr(ptr) }.to_str().map_err(|_| {
+        Failure::new(
+            EideticaStatus::InvalidArgument,
+            format!("{name} is not valid UTF-8"),
+        )
+    })
+}
+
+/// Checks that an out-parameter is not null.
+fn out<T>(ptr: *mut T, name: &str) -> Result<(), Failure> {
+    if ptr.is_null() {
+        Err(Failure::new(
+            EideticaStatus::InvalidArgument,
+            format!("{name} is null"),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Converts a returned string into one owned by the caller.
+fn c_string(value: impl Into<Vec<u8>>) -> Result<*mut c_char, Failure> {
+    CString::new(value).map(CString::into_raw).map_err(|_| {
+        Failure::new(
+            EideticaStatus::Failed,
+            "value contains a NUL byte and cannot be returned as a C string",
+        )
+    })
+}
+
+/// The message describing the last failed call on this thread.
+///
+/// Returns null if the last call on this thread succeeded. The string is owned
+/// by the library and valid until the next `eidetica_*` call on this thread.
+#[unsafe(no_mangle)]
+pub extern "C" fn eidetica_last_error() -> *const c_char {
+    status::last_error()
+}
+
+/// Frees a string returned by this library. Null is ignored.
+///
+/// # Safety
+/// `value` must be null or a string returned through an out-parameter of this
+/// library that has not been freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn eidetica_string_free(value: *mut c_char) {
+    if !value.is_null() {
+        // SAFETY: allocated by `CString::into_raw` in `c_string`
+        drop(unsafe { CString::from_raw(value) });
+    }
+}
+
+/// Releases a database, tree or operation handle.
+///
+/// Releasing an operation discards its staged changes. Trees and operations
+/// keep working after the database they came from is released.
+#[unsafe(no_mangle)]
+pub extern "C" fn eidetica_handle_release(handle: EideticaHandle) -> EideticaStatus {
+    call(|| handles::remove(handle).map(drop))
+}
+
+/// Creates a database that lives in memory only.
+///
+/// # Safety
+/// `out_db` must be null or valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn eidetica_db_new_in_memory(out_db: *mut EideticaHandle) -> EideticaStatus {
+    call(|| {
+        out(out_db, "out_db")?;
+        let db = BaseDB::new(Box::new(InMemory::new()));
+        // SAFETY: checked for null above, valid for writes per the contract
+        unsafe { *out_db = handles::insert(Object::Db(db)) };
+        Ok(())
+    })
+}
+
+/// Opens the database journaled at `path`, creating the file if it does not
+/// exist.
+///
+/// Every change is appended to the journal as it is made, see
+/// `InMemory::open_with_journal`.
+///
+/// # Safety
+/// `path` must be null or a NUL-terminated string, and `out_db` null or valid
+/// for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn eidetica_db_open(
+    path: *const c_char,
+    out_db: *mut EideticaHandle,
+) -> EideticaStatus {
+    call(|| {
+        // SAFETY: forwarded from this function's contract
+        let path = unsafe { arg(path, "path") }?;
+        out(out_db, "out_db")?;
+        let db = BaseDB::new(Box::new(InMemory::open_with_journal(path)?));
+        // SAFETY: checked for null above, valid for writes per the contract
+        unsafe { *out_db = handles::insert(Object::Db(db)) };
+        Ok(())
+    })
+}
+
+/// Makes every change written to the database so far durable.
+#[unsafe(no_mangle)]
+pub extern "C" fn eidetica_db_flush(db: EideticaHandle) -> EideticaStatus {
+    call(|| Ok(handles::db(db)?.backend().flush()?))
+}
+
+/// Generates a private key and stores it in the database under `key_name`.
+///
+/// # Safety
+/// `key_name` must be null or a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn eidetica_db_add_private_key(
+    db: EideticaHandle,
+    key_name: *const c_char,
+) -> EideticaStatus {
+    call(|| {
+        // SAFETY: forwarded from this function's contract
+        let key_name = unsafe { arg(key_name, "key_name") }?;
+        handles::db(db)?.add_private_key(key_name)?;
+        Ok(())
+    })
+}
+
+/// Creates a tree signed with the private key `key_name`.
+///
+/// # Safety
+/// `key_name` must be null or a NUL-terminated string, and `out_tree` null or
+/// valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn eidetica_tree_create(
+    db: EideticaHandle,
+    key_name: *const c_char,
+    out_tree: *mut EideticaHandle,
+) -> EideticaStatus {
+    call(|| {
+        // SAFETY: forwarded from this function's contract
+        let key_name = unsafe { arg(key_name, "key_name") }?;
+        out(out_tree, "out_tree")?;
+        let tree = handles::db(db)?.new_tree_default(key_name)?;
+        // SAFETY: checked for null above, valid for writes per the contract
+        unsafe { *out_tree = handles::insert(Object::Tree(tree)) };
+        Ok(())
+    })
+}
+
+/// Loads the tree with root entry `root_id`.
+///
+/// # Safety
+/// `root_id` must be null or a NUL-terminated string, and `out_tree` null or
+/// valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn eidetica_tree_load(
+    db: EideticaHandle,
+    root_id: *const c_char,
+    out_tree: *mut EideticaHandle,
+) -> EideticaStatus {
+    call(|| {
+        // SAFETY: forwarded from this function's contract
+        let root_id = ID::from(unsafe { arg(root_id, "root_id") }?);
+        out(out_tree, "out_tree")?;
+        let tree = handles::db(db)?.load_tree(&root_id)?;
+        // SAFETY: checked for null above, valid for writes per the contract
+        unsafe { *out_tree = handles::insert(Object::Tree(tree)) };
+        Ok(())
+    })
+}
+
+/// The ID of the tree's root entry, which identifies the tree.
+///
+/// # Safety
+/// `out_root_id` must be null or valid for writes. The string written to it
+/// must be freed with `eidetica_string_free`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn eidetica_tree_root_id(
+    tree: EideticaHandle,
+    out_root_id: *mut *mut c_char,
+) -> EideticaStatus {
+    call(|| {
+        out(out_root_id, "out_root_id")?;
+        let root_id = c_string(handles::tree(tree)?.root_id().as_str())?;
+        // SAFETY: checked for null above, valid for writes per the contract
+        unsafe { *out_root_id = root_id };
+        Ok(())
+    })
+}
+
+/// Reads the committed value of `key` in the Dict subtree `subtree`.
+///
+/// Fails with `EIDETICA_NOT_FOUND` if the key is not set.
+///
+/// # Safety
+/// `subtree` and `key` must be null or NUL-terminated strings, and `out_value`
+/// null or valid for writes. The string written to it must be freed with
+/// `eidetica_string_free`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn eidetica_tree_dict_get(
+    tree: EideticaHandle,
+    subtree: *const c_char,
+    key: *const c_char,
+    out_value: *mut *mut c_char,
+) -> EideticaStatus {
+    call(|| {
+        // SAFETY: forwarded from this function's contract
+        let (subtree, key) = unsafe { (arg(subtree, "subtree")?, arg(key, "key")?) };
+        out(out_value, "out_value")?;
+        let dict = handles::tree(tree)?.get_subtree_viewer::<Dict>(subtree)?;
+        let value = c_string(dict.get_string(key)?)?;
+        // SAFETY: checked for null above, valid for writes per the contract
+        unsafe { *out_value = value };
+        Ok(())
+    })
+}
+
+/// Starts an operation on the tree, building on its current tips.
+///
+/// # Safety
+/// `out_op` must be null or valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn eidetica_op_new(
+    tree: EideticaHandle,
+    out_op: *mut EideticaHandle,
+) -> EideticaStatus {
+    call(|| {
+        out(out_op, "out_op")?;
+        let op = handles::tree(tree)?.new_operation()?;
+        // SAFETY: checked for null above, valid for writes per the contract
+        unsafe { *out_op = handles::insert(Object::Op(op)) };
+        Ok(())
+    })
+}
+
+/// Stages setting `key` to the string `value` in the Dict subtree `subtree`.
+///
+/// # Safety
+/// `subtree`, `key` and `value` must be null or NUL-terminated strings.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn eidetica_op_dict_set(
+    op: EideticaHandle,
+    subtree: *const c_char,
+    key: *const c_char,
+    value: *const c_char,
+) -> EideticaStatus {
+    call(|| {
+        // SAFETY: forwarded from this function's contract
+        let (subtree, key, value) = unsafe {
+            (
+                arg(subtree, "subtree")?,
+                arg(key, "key")?,
+                arg(value, "value")?,
+            )
+        };
+        handles::op(op)?
+            .get_subtree::<Dict>(subtree)?
+            .set_string(key, value)?;
+        Ok(())
+    })
+}
+
+/// Reads `key` in the Dict subtree `subtree`, including changes staged in
+/// the operation.
+///
+/// Fails with `EIDETICA_NOT_FOUND` if the key is not set.
+///
+/// # Safety
+/// `subtree` and `key` must be null or NUL-terminated strings, and `out_value`
+/// null or valid for writes. The string written to it must be freed with
+/// `eidetica_string_free`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn eidetica_op_dict_get(
+    op: EideticaHandle,
+    subtree: *const c_char,
+    key: *const c_char,
+    out_value: *mut *mut c_char,
+) -> EideticaStatus {
+    call(|| {
+        // SAFETY: forwarded from this function's contract
+        let (subtree, key) = unsafe { (arg(subtree, "subtree")?, arg(key, "key")?) };
+        out(out_value, "out_value")?;
+        let dict = handles::op(op)?.get_subtree::<Dict>(subtree)?;
+        let value = c_string(dict.get_string(key)?)?;
+        // SAFETY: checked for null above, valid for writes per the contract
+        unsafe { *out_value = value };
+        Ok(())
+    })
+}
+
+/// Stages deleting `key` from the Dict subtree `subtree`.
+///
+/// # Safety
+/// `subtree` and `key` must be null or NUL-terminated strings.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn eidetica_op_dict_delete(
+    op: EideticaHandle,
+    subtree: *const c_char,
+    key: *const c_char,
+) -> EideticaStatus {
+    call(|| {
+        // SAFETY: forwarded from this function's contract
+        let (subtree, key) = unsafe { (arg(subtree, "subtree")?, arg(key, "key")?) };
+        handles::op(op)?.get_subtree::<Dict>(subtree)?.delete(key)?;
+        Ok(())
+    })
+}
+
+/// Commits the staged changes of the operation as one entry.
+///
+/// The operation handle is released whether or not the commit succeeds.
+///
+/// # Safety
+/// `out_entry_id` must be null or valid for writes. If it is not null, the
+/// ID of the new entry is written to it and must be freed with
+/// `eidetica_string_free`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn eidetica_op_commit(
+    op: EideticaHandle,
+    out_entry_id: *mut *mut c_char,
+) -> EideticaStatus {
+    call(|| {
+        let id = handles::take_op(op)?.commit()?;
+        if !out_entry_id.is_null() {
+            let id = c_string(id.as_str())?;
+            // SAFETY: not null, valid for writes per the contract
+            unsafe { *out_entry_id = id };
+        }
+        Ok(())
+    })
+}
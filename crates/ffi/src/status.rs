@@ -0,0 +1,95 @@
+//! Status codes and the last error message of each thread
+
+use std::cell::RefCell;
+use std::ffi::{CString, c_char};
+
+/// Result of every `eidetica_*` function.
+///
+/// On anything but `EIDETICA_OK`, a description of the failure is available
+/// from `eidetica_last_error` on the same thread, and out-parameters are left
+/// untouched.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EideticaStatus {
+    /// The call succeeded
+    Ok = 0,
+    /// A pointer argument was null, or a string was not valid UTF-8
+    InvalidArgument = 1,
+    /// The handle was never issued, was already released, or is of the wrong kind
+    InvalidHandle = 2,
+    /// The tree, entry, key or value does not exist
+    NotFound = 3,
+    /// The signing key is not allowed to make the change
+    PermissionDenied = 4,
+    /// The key or tree already exists, or the tree changed concurrently
+    Conflict = 5,
+    /// Reading or writing storage failed
+    Io = 6,
+    /// Any other error reported by Eidetica
+    Failed = 7,
+    /// Eidetica panicked; the database may be in an inconsistent state
+    Panic = 8,
+}
+
+/// A failed call: its status and a description for `eidetica_last_error`.
+#[derive(Debug)]
+pub(crate) struct Failure {
+    pub(crate) status: EideticaStatus,
+    pub(crate) message: String,
+}
+
+impl Failure {
+    pub(crate) fn new(status: EideticaStatus, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<eidetica::Error> for Failure {
+    fn from(error: eidetica::Error) -> Self {
+        let concurrent = matches!(&error, eidetica::Error::AtomicOp(e) if e.is_concurrency_error());
+        let status = if error.is_not_found() {
+            EideticaStatus::NotFound
+        } else if error.is_permission_denied() {
+            EideticaStatus::PermissionDenied
+        } else if error.is_conflict() || concurrent {
+            EideticaStatus::Conflict
+        } else if error.is_io_error() {
+            EideticaStatus::Io
+        } else {
+            EideticaStatus::Failed
+        };
+        Self::new(status, error.to_string())
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Records the outcome of a call for `eidetica_last_error`.
+pub(crate) fn report(outcome: Result<(), Failure>) -> EideticaStatus {
+    match outcome {
+        Ok(()) => {
+            LAST_ERROR.with(|last| last.borrow_mut().take());
+            EideticaStatus::Ok
+        }
+        Err(failure) => {
+            // Interior NULs cannot be represented in a C string
+            let message = CString::new(failure.message.replace('\0', " ")).unwrap_or_default();
+            LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+            failure.status
+        }
+    }
+}
+
+/// The message recorded by the last failed call on this thread, or null.
+pub(crate) fn last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(std::ptr::null(), |message| message.as_ptr())
+    })
+}
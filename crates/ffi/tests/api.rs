@@ -0,0 +1,231 @@
+//! Tests of the C API, called the way C code would call it
+
+use eidetica_ffi::*;
+use std::ffi::{CStr, CString, c_char};
+use std::ptr;
+
+fn c(value: &str) -> CString {
+    CString::new(value).unwrap()
+}
+
+/// Takes ownership of a string returned by the library.
+fn take(value: *mut c_char) -> String {
+    assert!(!value.is_null());
+    let string = unsafe { CStr::from_ptr(value) }
+        .to_str()
+        .unwrap()
+        .to_string();
+    unsafe { eidetica_string_free(value) };
+    string
+}
+
+fn last_error() -> String {
+    let message = eidetica_last_error();
+    assert!(!message.is_null());
+    unsafe { CStr::from_ptr(message) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// A database with a private key and a tree signed with it.
+fn setup() -> (EideticaHandle, EideticaHandle) {
+    let (mut db, mut tree) = (0, 0);
+    unsafe {
+        assert_eq!(eidetica_db_new_in_memory(&mut db), EideticaStatus::Ok);
+        assert_eq!(
+            eidetica_db_add_private_key(db, c("key").as_ptr()),
+            EideticaStatus::Ok
+        );
+        assert_eq!(
+            eidetica_tree_create(db, c("key").as_ptr(), &mut tree),
+            EideticaStatus::Ok
+        );
+    }
+    (db, tree)
+}
+
+fn dict_get(tree: EideticaHandle, key: &str) -> Result<String, EideticaStatus> {
+    let mut value = ptr::null_mut();
+    let status =
+        unsafe { eidetica_tree_dict_get(tree, c("notes").as_ptr(), c(key).as_ptr(), &mut value) };
+    match status {
+        EideticaStatus::Ok => Ok(take(value)),
+        status => Err(status),
+    }
+}
+
+#[test]
+fn test_set_commit_and_read_back() {
+    let (db, tree) = setup();
+    let notes = c("notes");
+
+    let mut op = 0;
+    unsafe {
+        assert_eq!(eidetica_op_new(tree, &mut op), EideticaStatus::Ok);
+        assert_eq!(
+            eidetica_op_dict_set(op, notes.as_ptr(), c("title").as_ptr(), c("Hello").as_ptr()),
+            EideticaStatus::Ok
+        );
+
+        // Staged changes are visible through the operation only
+        let mut staged = ptr::null_mut();
+        assert_eq!(
+            eidetica_op_dict_get(op, notes.as_ptr(), c("title").as_ptr(), &mut staged),
+            EideticaStatus::Ok
+        );
+        assert_eq!(take(staged), "Hello");
+        assert_eq!(dict_get(tree, "title"), Err(EideticaStatus::NotFound));
+
+        let mut entry = ptr::null_mut();
+        assert_eq!(eidetica_op_commit(op, &mut entry), EideticaStatus::Ok);
+        assert_eq!(take(entry).len(), 64);
+    }
+    assert_eq!(dict_get(tree, "title").unwrap(), "Hello");
+
+    // The committed operation's handle was released
+    assert_eq!(
+        unsafe { eidetica_op_commit(op, ptr::null_mut()) },
+        EideticaStatus::InvalidHandle
+    );
+
+    // The tree can be loaded again by its root ID
+    let mut root = ptr::null_mut();
+    let mut loaded = 0;
+    unsafe {
+        assert_eq!(eidetica_tree_root_id(tree, &mut root), EideticaStatus::Ok);
+        let root = CString::new(take(root)).unwrap();
+        assert_eq!(
+            eidetica_tree_load(db, root.as_ptr(), &mut loaded),
+            EideticaStatus::Ok
+        );
+    }
+    assert_ne!(loaded, tree);
+    assert_eq!(dict_get(loaded, "title").unwrap(), "Hello");
+
+    for handle in [loaded, tree, db] {
+        assert_eq!(eidetica_handle_release(handle), EideticaStatus::Ok);
+    }
+}
+
+#[test]
+fn test_delete_and_release_discards_changes() {
+    let (_db, tree) = setup();
+    let notes = c("notes");
+    unsafe {
+        let mut op = 0;
+        eidetica_op_new(tree, &mut op);
+        eidetica_op_dict_set(op, notes.as_ptr(), c("a").as_ptr(), c("1").as_ptr());
+        eidetica_op_dict_set(op, notes.as_ptr(), c("b").as_ptr(), c("2").as_ptr());
+        assert_eq!(eidetica_op_commit(op, ptr::null_mut()), EideticaStatus::Ok);
+
+        let mut op = 0;
+        eidetica_op_new(tree, &mut op);
+        assert_eq!(
+            eidetica_op_dict_delete(op, notes.as_ptr(), c("a").as_ptr()),
+            EideticaStatus::Ok
+        );
+        assert_eq!(eidetica_op_commit(op, ptr::null_mut()), EideticaStatus::Ok);
+
+        // A released operation commits nothing
+        let mut op = 0;
+        eidetica_op_new(tree, &mut op);
+        eidetica_op_dict_set(op, notes.as_ptr(), c("b").as_ptr(), c("3").as_ptr());
+        assert_eq!(eidetica_handle_release(op), EideticaStatus::Ok);
+    }
+    assert_eq!(dict_get(tree, "a"), Err(EideticaStatus::NotFound));
+    assert_eq!(dict_get(tree, "b").unwrap(), "2");
+}
+
+#[test]
+fn test_errors_are_reported_with_codes_and_messages() {
+    let (db, tree) = setup();
+
+    // Wrong kind of handle, and the message names the handle
+    let mut op = 0;
+    assert_eq!(
+        unsafe { eidetica_op_new(db, &mut op) },
+        EideticaStatus::InvalidHandle
+    );
+    assert!(last_error().contains(&db.to_string()));
+    assert_eq!(op, 0);
+
+    // The tree handle passed to an operation function is left alone
+    assert_eq!(
+        unsafe { eidetica_op_commit(tree, ptr::null_mut()) },
+        EideticaStatus::InvalidHandle
+    );
+    assert_eq!(dict_get(tree, "missing"), Err(EideticaStatus::NotFound));
+
+    // Null and non-UTF-8 arguments
+    let mut other = 0;
+    assert_eq!(
+        unsafe { eidetica_tree_create(db, ptr::null(), &mut other) },
+        EideticaStatus::InvalidArgument
+    );
+    assert!(last_error().contains("key_name"));
+    let invalid = [0xffu8 as c_char, 0];
+    assert_eq!(
+        unsafe { eidetica_tree_create(db, invalid.as_ptr(), &mut other) },
+        EideticaStatus::InvalidArgument
+    );
+    assert_eq!(
+        unsafe { eidetica_tree_create(db, c("key").as_ptr(), ptr::null_mut()) },
+        EideticaStatus::InvalidArgument
+    );
+
+    // Unknown keys and trees
+    assert_eq!(
+        unsafe { eidetica_tree_create(db, c("nobody").as_ptr(), &mut other) },
+        EideticaStatus::NotFound
+    );
+    assert_eq!(
+        unsafe { eidetica_tree_load(db, c("not-a-root").as_ptr(), &mut other) },
+        EideticaStatus::NotFound
+    );
+
+    // A successful call clears the message
+    let mut root = ptr::null_mut();
+    assert_eq!(
+        unsafe { eidetica_tree_root_id(tree, &mut root) },
+        EideticaStatus::Ok
+    );
+    take(root);
+    assert!(eidetica_last_error().is_null());
+
+    assert_eq!(eidetica_handle_release(db), EideticaStatus::Ok);
+    assert_eq!(eidetica_handle_release(db), EideticaStatus::InvalidHandle);
+}
+
+#[test]
+fn test_journaled_database_persists() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = c(dir.path().join("db.journal").to_str().unwrap());
+
+    let mut root = ptr::null_mut();
+    unsafe {
+        let mut db = 0;
+        assert_eq!(eidetica_db_open(path.as_ptr(), &mut db), EideticaStatus::Ok);
+        eidetica_db_add_private_key(db, c("key").as_ptr());
+        let mut tree = 0;
+        eidetica_tree_create(db, c("key").as_ptr(), &mut tree);
+        let mut op = 0;
+        eidetica_op_new(tree, &mut op);
+        eidetica_op_dict_set(op, c("notes").as_ptr(), c("a").as_ptr(), c("1").as_ptr());
+        assert_eq!(eidetica_op_commit(op, ptr::null_mut()), EideticaStatus::Ok);
+        assert_eq!(eidetica_db_flush(db), EideticaStatus::Ok);
+        eidetica_tree_root_id(tree, &mut root);
+        eidetica_handle_release(tree);
+        eidetica_handle_release(db);
+    }
+    let root = CString::new(take(root)).unwrap();
+
+    let (mut db, mut tree) = (0, 0);
+    unsafe {
+        assert_eq!(eidetica_db_open(path.as_ptr(), &mut db), EideticaStatus::Ok);
+        assert_eq!(
+            eidetica_tree_load(db, root.as_ptr(), &mut tree),
+            EideticaStatus::Ok
+        );
+    }
+    assert_eq!(dict_get(tree, "a").unwrap(), "1");
+}
@@ -1,7 +1,15 @@
 use criterion::{BenchmarkId, Criterion, Throughput, black_box, criterion_group, criterion_main};
 use eidetica::backend::database::InMemory;
 use eidetica::basedb::BaseDB;
-use eidetica::subtree::Dict;
+use eidetica::subtree::{Dict, Table};
+use serde::{Deserialize, Serialize};
+
+/// Row type for the Table benchmarks
+#[derive(Clone, Serialize, Deserialize)]
+struct BenchRow {
+    name: String,
+    value: u64,
+}
 
 /// Creates a fresh empty tree with in-memory backend for benchmarking
 fn setup_tree() -> eidetica::Tree {
@@ -92,6 +100,84 @@ fn bench_batch_add_entries(c: &mut Criterion) {
                 });
             },
         );
+        group.bench_with_input(
+            BenchmarkId::new("set_many", batch_size),
+            batch_size,
+            |b, &batch_size| {
+                b.iter_with_setup(setup_tree, |tree| {
+                    let op = tree.new_operation().expect("Failed to start operation");
+                    let dict = op.get_subtree::<Dict>("data").expect("Failed to get Dict");
+
+                    dict.set_many(black_box(
+                        (0..batch_size)
+                            .map(|i| (format!("batch_key_{i}"), format!("batch_value_{i}"))),
+                    ))
+                    .expect("Failed to set values");
+
+                    op.commit().expect("Failed to commit operation");
+                });
+            },
+        );
+    }
+
+    group.finish();
+}
+
+/// Benchmarks inserting rows into a Table one at a time vs with insert_batch
+/// Each single insert re-serializes the staged subtree, so its cost grows
+/// quadratically with the number of rows, while insert_batch serializes once
+fn bench_table_insert_batch(c: &mut Criterion) {
+    let mut group = c.benchmark_group("table_insert_batch");
+
+    let rows = |count: usize| -> Vec<BenchRow> {
+        (0..count)
+            .map(|i| BenchRow {
+                name: format!("row_{i}"),
+                value: i as u64,
+            })
+            .collect()
+    };
+
+    for row_count in [10, 100, 1000].iter() {
+        group.throughput(Throughput::Elements(*row_count as u64));
+        group.bench_with_input(
+            BenchmarkId::new("insert", row_count),
+            row_count,
+            |b, &row_count| {
+                b.iter_with_setup(
+                    || (setup_tree(), rows(row_count)),
+                    |(tree, rows)| {
+                        let op = tree.new_operation().expect("Failed to start operation");
+                        let table = op
+                            .get_subtree::<Table<BenchRow>>("rows")
+                            .expect("Failed to get Table");
+                        for row in rows {
+                            table.insert(black_box(row)).expect("Failed to insert row");
+                        }
+                        op.commit().expect("Failed to commit operation");
+                    },
+                );
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("insert_batch", row_count),
+            row_count,
+            |b, &row_count| {
+                b.iter_with_setup(
+                    || (setup_tree(), rows(row_count)),
+                    |(tree, rows)| {
+                        let op = tree.new_operation().expect("Failed to start operation");
+                        let table = op
+                            .get_subtree::<Table<BenchRow>>("rows")
+                            .expect("Failed to get Table");
+                        table
+                            .insert_batch(black_box(rows))
+                            .expect("Failed to insert rows");
+                        op.commit().expect("Failed to commit operation");
+                    },
+                );
+            },
+        );
     }
 
     group.finish();
@@ -209,6 +295,7 @@ criterion_group! {
     targets =
         bench_add_entries,
         bench_batch_add_entries,
+        bench_table_insert_batch,
         bench_incremental_add_entries,
         bench_access_entries,
         bench_tree_operations,
@@ -0,0 +1,166 @@
+//! Entry compression benchmarks
+//!
+//! Measures the cost of compressing and decompressing stored entry data and
+//! reports the size win for representative payloads. The sizes are printed
+//! once per payload before it is benchmarked:
+//!
+//! ```text
+//! cargo bench --features compression --bench compression_benchmarks
+//! ```
+
+use criterion::{BenchmarkId, Criterion, Throughput, black_box, criterion_group, criterion_main};
+use eidetica::atomicop::AtomicOp;
+use eidetica::backend::Compression;
+use eidetica::backend::database::InMemory;
+use eidetica::basedb::BaseDB;
+use eidetica::subtree::{Dict, Table};
+use serde::{Deserialize, Serialize};
+
+/// A table row with enough fields to make the serialized form realistic
+#[derive(Clone, Serialize, Deserialize)]
+struct Record {
+    name: String,
+    email: String,
+    bio: String,
+    age: u32,
+}
+
+/// Creates a tree and returns the serialized JSON of its latest entry after
+/// `write` has filled one operation
+fn entry_json(write: impl FnOnce(&AtomicOp)) -> Vec<u8> {
+    let db = BaseDB::new(Box::new(InMemory::new()));
+    db.add_private_key("BENCH_KEY")
+        .expect("Failed to add benchmark key");
+    let tree = db
+        .new_tree_default("BENCH_KEY")
+        .expect("Failed to create tree");
+    let op = tree.new_operation().expect("Failed to start operation");
+    write(&op);
+    let id = op.commit().expect("Failed to commit operation");
+    let entry = tree.backend().get(&id).expect("Failed to get entry");
+    serde_json::to_vec(&entry).expect("Failed to serialize entry")
+}
+
+/// An entry setting `keys` Dict keys with short values
+fn dict_payload(keys: usize) -> Vec<u8> {
+    entry_json(|op| {
+        let dict = op.get_subtree::<Dict>("data").expect("Failed to get Dict");
+        for i in 0..keys {
+            dict.set(format!("setting_{i}"), format!("value_{}", i % 13))
+                .expect("Failed to set value");
+        }
+    })
+}
+
+/// An entry inserting `rows` table records
+fn table_payload(rows: usize) -> Vec<u8> {
+    entry_json(|op| {
+        let table = op
+            .get_subtree::<Table<Record>>("records")
+            .expect("Failed to get Table");
+        for i in 0..rows {
+            table
+                .insert(Record {
+                    name: format!("user_{i}"),
+                    email: format!("user_{i}@example.com"),
+                    bio: "Enjoys distributed systems, long walks and merge conflicts".to_string(),
+                    age: (i % 80) as u32,
+                })
+                .expect("Failed to insert record");
+        }
+    })
+}
+
+fn report_size(name: &str, payload: &[u8]) {
+    let compressed = Compression::Lz4.compress(payload);
+    println!(
+        "{name}: {} bytes -> {} bytes ({:.1}% of original)",
+        payload.len(),
+        compressed.len(),
+        100.0 * compressed.len() as f64 / payload.len() as f64
+    );
+}
+
+/// Benchmarks compressing and decompressing entry payloads of varying size
+fn bench_entry_compression(c: &mut Criterion) {
+    let mut group = c.benchmark_group("entry_compression");
+
+    let payloads = [
+        ("dict", 10, dict_payload(10)),
+        ("dict", 1000, dict_payload(1000)),
+        ("table", 100, table_payload(100)),
+    ];
+    for (kind, size, payload) in &payloads {
+        report_size(&format!("{kind}/{size}"), payload);
+        let compressed = Compression::Lz4.compress(payload).into_owned();
+        group.throughput(Throughput::Bytes(payload.len() as u64));
+
+        group.bench_with_input(
+            BenchmarkId::new(format!("compress_{kind}"), size),
+            payload,
+            |b, payload| {
+                b.iter(|| black_box(Compression::Lz4.compress(payload)));
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new(format!("decompress_{kind}"), size),
+            &compressed,
+            |b, compressed| {
+                b.iter(|| {
+                    black_box(
+                        Compression::decompress(compressed, usize::MAX)
+                            .expect("Failed to decompress"),
+                    )
+                });
+            },
+        );
+    }
+
+    group.finish();
+}
+
+/// Benchmarks saving a database snapshot with and without compression
+fn bench_snapshot_compression(c: &mut Criterion) {
+    let mut group = c.benchmark_group("snapshot_compression");
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+
+    for compression in [Compression::None, Compression::Lz4] {
+        let backend = InMemory::new().with_compression(compression);
+        let db = BaseDB::new(Box::new(backend));
+        db.add_private_key("BENCH_KEY")
+            .expect("Failed to add benchmark key");
+        let tree = db
+            .new_tree_default("BENCH_KEY")
+            .expect("Failed to create tree");
+        for i in 0..200 {
+            let op = tree.new_operation().expect("Failed to start operation");
+            let dict = op.get_subtree::<Dict>("data").expect("Failed to get Dict");
+            dict.set(format!("key_{i}"), format!("value_{}", i % 7))
+                .expect("Failed to set value");
+            op.commit().expect("Failed to commit operation");
+        }
+
+        let backend = db
+            .backend()
+            .as_any()
+            .downcast_ref::<InMemory>()
+            .expect("Backend is InMemory");
+        let path = dir.path().join(format!("{}.db", compression.name()));
+        backend.save_to_file(&path).expect("Failed to save");
+        let size = std::fs::metadata(&path).expect("Failed to stat").len();
+        println!("snapshot/{}: {size} bytes", compression.name());
+
+        group.bench_function(BenchmarkId::new("save", compression.name()), |b| {
+            b.iter(|| backend.save_to_file(&path).expect("Failed to save"));
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(30).configure_from_args();
+    targets = bench_entry_compression, bench_snapshot_compression,
+}
+criterion_main!(benches);
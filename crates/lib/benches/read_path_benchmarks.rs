@@ -0,0 +1,329 @@
+//! Read path benchmarks
+//!
+//! Covers the hot paths of reading data back out of a tree: constructing
+//! subtree viewers, merging many concurrent branches cached and cold,
+//! searching tables and traversing the DAG. Group and benchmark IDs are kept stable so results can
+//! be compared across commits with criterion's saved baselines:
+//!
+//! ```text
+//! cargo bench --bench read_path_benchmarks -- --save-baseline main
+//! cargo bench --bench read_path_benchmarks -- --baseline main
+//! ```
+
+use criterion::{BenchmarkId, Criterion, Throughput, black_box, criterion_group, criterion_main};
+use eidetica::backend::database::InMemory;
+use eidetica::basedb::BaseDB;
+use eidetica::entry::ID;
+use eidetica::subtree::{Dict, Table};
+use serde::{Deserialize, Serialize};
+
+/// A table row with enough fields to make deserialization realistic
+#[derive(Clone, Serialize, Deserialize)]
+struct Record {
+    name: String,
+    email: String,
+    age: u32,
+    active: bool,
+}
+
+/// Creates a fresh empty tree with in-memory backend for benchmarking
+fn setup_tree() -> eidetica::Tree {
+    let backend = Box::new(InMemory::new());
+    let db = BaseDB::new(backend);
+    db.add_private_key("BENCH_KEY")
+        .expect("Failed to add benchmark key");
+    db.new_tree_default("BENCH_KEY")
+        .expect("Failed to create tree")
+}
+
+/// Creates a tree with a linear history of `length` entries in the "data" Dict
+fn setup_linear_tree(length: usize) -> eidetica::Tree {
+    let tree = setup_tree();
+    for i in 0..length {
+        let op = tree.new_operation().expect("Failed to start operation");
+        let dict = op.get_subtree::<Dict>("data").expect("Failed to get Dict");
+        dict.set(format!("key_{i}"), format!("value_{i}"))
+            .expect("Failed to set value");
+        op.commit().expect("Failed to commit operation");
+    }
+    tree
+}
+
+/// Creates a tree with `branches` concurrent entries on top of a shared base,
+/// leaving that many tips to merge
+fn setup_branched_tree(branches: usize) -> (eidetica::Tree, ID, Vec<ID>) {
+    let tree = setup_linear_tree(1);
+    let base = tree.get_tips().expect("Failed to get tips")[0].clone();
+
+    let tips = (0..branches)
+        .map(|i| {
+            let op = tree
+                .new_operation_with_tips(std::slice::from_ref(&base))
+                .expect("Failed to start operation");
+            let dict = op.get_subtree::<Dict>("data").expect("Failed to get Dict");
+            dict.set(format!("branch_{i}"), format!("value_{i}"))
+                .expect("Failed to set value");
+            dict.set("shared", format!("value_{i}"))
+                .expect("Failed to set value");
+            op.commit().expect("Failed to commit operation")
+        })
+        .collect();
+
+    (tree, base, tips)
+}
+
+/// Creates a tree whose "records" table holds `rows` records
+fn setup_table_tree(rows: usize) -> eidetica::Tree {
+    let tree = setup_tree();
+    let op = tree.new_operation().expect("Failed to start operation");
+    let table = op
+        .get_subtree::<Table<Record>>("records")
+        .expect("Failed to get Table");
+    for i in 0..rows {
+        table
+            .insert(Record {
+                name: format!("user_{i}"),
+                email: format!("user_{i}@example.com"),
+                age: (i % 80) as u32,
+                active: i % 3 == 0,
+            })
+            .expect("Failed to insert record");
+    }
+    op.commit().expect("Failed to commit operation");
+    tree
+}
+
+/// Benchmarks constructing subtree viewers on histories of varying length
+/// Viewer construction computes the subtree state at the current tips
+fn bench_viewer_construction(c: &mut Criterion) {
+    let mut group = c.benchmark_group("viewer_construction");
+
+    for history in [10, 100, 500].iter() {
+        let tree = setup_linear_tree(*history);
+        group.bench_with_input(BenchmarkId::new("dict", history), history, |b, _| {
+            b.iter(|| {
+                black_box(
+                    tree.get_subtree_viewer::<Dict>("data")
+                        .expect("Failed to get viewer"),
+                );
+            });
+        });
+
+        // Reading the first entry's state exercises the historical read path
+        let first = tree.get_all_entries().expect("Failed to get entries")[1].id();
+        group.bench_with_input(
+            BenchmarkId::new("dict_at_entry", history),
+            history,
+            |b, _| {
+                b.iter(|| {
+                    black_box(
+                        tree.get_subtree_viewer_at::<Dict>("data", std::slice::from_ref(&first))
+                            .expect("Failed to get viewer"),
+                    );
+                });
+            },
+        );
+    }
+
+    group.finish();
+}
+
+/// Benchmarks reading the merged state of many concurrent branches
+/// The per-entry state cache does not cover merges of several tips, but the
+/// merged state cache of the BaseDB does, so repeated reads are cache hits
+fn bench_large_merges(c: &mut Criterion) {
+    let mut group = c.benchmark_group("large_merges");
+
+    for branches in [10, 50, 200].iter() {
+        let (tree, _base, _tips) = setup_branched_tree(*branches);
+        group.throughput(Throughput::Elements(*branches as u64));
+        group.bench_with_input(BenchmarkId::new("tips", branches), branches, |b, _| {
+            b.iter(|| {
+                let dict = tree
+                    .get_subtree_viewer::<Dict>("data")
+                    .expect("Failed to get viewer");
+                black_box(dict.get("shared").expect("Failed to get value"));
+            });
+        });
+    }
+
+    group.finish();
+}
+
+/// Creates a tree with `branches` concurrent branches of `length` entries each,
+/// with the merged state cache turned off so every read recomputes the merge
+fn setup_long_branches(branches: usize, length: usize) -> eidetica::Tree {
+    let db = BaseDB::new(Box::new(InMemory::new()));
+    db.set_state_cache_capacity(0);
+    db.add_private_key("BENCH_KEY")
+        .expect("Failed to add benchmark key");
+    let tree = db
+        .new_tree_default("BENCH_KEY")
+        .expect("Failed to create tree");
+    let op = tree.new_operation().expect("Failed to start operation");
+    op.get_subtree::<Dict>("data")
+        .expect("Failed to get Dict")
+        .set("base", "value")
+        .expect("Failed to set value");
+    let base = vec![op.commit().expect("Failed to commit operation")];
+
+    for branch in 0..branches {
+        let mut tips = base.clone();
+        for i in 0..length {
+            let op = tree
+                .new_operation_with_tips(&tips)
+                .expect("Failed to start operation");
+            let dict = op.get_subtree::<Dict>("data").expect("Failed to get Dict");
+            dict.set(format!("branch_{branch}_{i}"), format!("value_{i}"))
+                .expect("Failed to set value");
+            tips = vec![op.commit().expect("Failed to commit operation")];
+        }
+    }
+    tree
+}
+
+/// Benchmarks cold reads of long concurrent branches, with no cached states
+/// The paths from the LCA are merged in parallel with the `parallel` feature:
+///
+/// ```text
+/// cargo bench --bench read_path_benchmarks -- cold_reads
+/// cargo bench --bench read_path_benchmarks --features parallel -- cold_reads
+/// ```
+fn bench_cold_reads(c: &mut Criterion) {
+    let mut group = c.benchmark_group("cold_reads");
+
+    for length in [100, 500].iter() {
+        let tree = setup_long_branches(4, *length);
+        group.throughput(Throughput::Elements(4 * *length as u64));
+        group.bench_with_input(BenchmarkId::new("branches", length), length, |b, _| {
+            b.iter_with_setup(
+                || {
+                    tree.backend()
+                        .clear_crdt_cache()
+                        .expect("Failed to clear cache")
+                },
+                |_| {
+                    let dict = tree
+                        .get_subtree_viewer::<Dict>("data")
+                        .expect("Failed to get viewer");
+                    black_box(dict.get_all().expect("Failed to get state"));
+                },
+            );
+        });
+    }
+
+    group.finish();
+}
+
+/// Benchmarks searching and iterating tables of varying size
+fn bench_table_search(c: &mut Criterion) {
+    let mut group = c.benchmark_group("table_search");
+
+    for rows in [100, 1000].iter() {
+        let tree = setup_table_tree(*rows);
+        let table = tree
+            .get_subtree_viewer::<Table<Record>>("records")
+            .expect("Failed to get Table");
+        group.throughput(Throughput::Elements(*rows as u64));
+
+        group.bench_with_input(BenchmarkId::new("search", rows), rows, |b, _| {
+            b.iter(|| {
+                black_box(
+                    table
+                        .search(|record| record.active && record.age > 40)
+                        .expect("Failed to search"),
+                );
+            });
+        });
+
+        group.bench_with_input(BenchmarkId::new("iter", rows), rows, |b, _| {
+            b.iter(|| {
+                for row in table.iter().expect("Failed to iterate") {
+                    black_box(row.expect("Failed to read row"));
+                }
+            });
+        });
+    }
+
+    group.finish();
+}
+
+/// Benchmarks the DAG queries that state computation and sync are built on
+fn bench_dag_traversal(c: &mut Criterion) {
+    let mut group = c.benchmark_group("dag_traversal");
+
+    for history in [100, 500].iter() {
+        let tree = setup_linear_tree(*history);
+        let backend = tree.backend();
+        let root = tree.root_id().clone();
+        let tips = tree.get_tips().expect("Failed to get tips");
+
+        group.bench_with_input(BenchmarkId::new("get_tree", history), history, |b, _| {
+            b.iter(|| black_box(backend.get_tree(&root).expect("Failed to get tree")));
+        });
+
+        group.bench_with_input(
+            BenchmarkId::new("subtree_tips", history),
+            history,
+            |b, _| {
+                b.iter(|| {
+                    black_box(
+                        backend
+                            .get_subtree_tips(&root, "data")
+                            .expect("Failed to get subtree tips"),
+                    )
+                });
+            },
+        );
+
+        let first = backend.get_tree(&root).expect("Failed to get tree")[1].id();
+        group.bench_with_input(
+            BenchmarkId::new("path_from_to", history),
+            history,
+            |b, _| {
+                b.iter(|| {
+                    black_box(
+                        backend
+                            .get_path_from_to(&root, "data", &first, &tips)
+                            .expect("Failed to get path"),
+                    )
+                });
+            },
+        );
+    }
+
+    for branches in [10, 50].iter() {
+        let (tree, _base, tips) = setup_branched_tree(*branches);
+        let backend = tree.backend();
+        let root = tree.root_id().clone();
+        group.bench_with_input(BenchmarkId::new("find_lca", branches), branches, |b, _| {
+            b.iter(|| {
+                black_box(
+                    backend
+                        .find_lca(&root, "data", &tips)
+                        .expect("Failed to find LCA"),
+                )
+            });
+        });
+    }
+
+    group.finish();
+}
+
+/// Custom Criterion configuration for consistent benchmarking
+/// Fixed sample size ensures reproducible results across different machines
+fn criterion_config() -> Criterion {
+    Criterion::default().sample_size(50).configure_from_args()
+}
+
+criterion_group! {
+    name = benches;
+    config = criterion_config();
+    targets =
+        bench_viewer_construction,
+        bench_large_merges,
+        bench_cold_reads,
+        bench_table_search,
+        bench_dag_traversal,
+}
+criterion_main!(benches);
@@ -0,0 +1,238 @@
+//! Write coalescing
+//!
+//! A [`WriteCoalescer`] batches writes to `Dict` subtrees and commits them on
+//! time and size thresholds set per subtree. It suits writers that update the
+//! same keys far more often than anyone needs to see every value, such as
+//! cursor positions or sensor readings: only the last value written to a key
+//! before a commit is kept, so each subtree produces at most one entry per
+//! interval however fast it is written to.
+
+use crate::Result;
+use crate::crdt::map::Value;
+use crate::entry::ID;
+use crate::subtree::Dict;
+use crate::tree::Tree;
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::Duration;
+use web_time::Instant;
+
+/// When a [`WriteCoalescer`] commits the writes staged for a subtree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CoalescePolicy {
+    /// How long writes are held after the first one is staged
+    pub interval: Duration,
+    /// Number of distinct keys that triggers a commit before the interval ends
+    pub max_pending: usize,
+}
+
+impl CoalescePolicy {
+    /// A policy committing writes `interval` after the first one is staged.
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            max_pending: usize::MAX,
+        }
+    }
+
+    /// Also commit as soon as `max_pending` distinct keys are staged.
+    pub fn max_pending(mut self, max_pending: usize) -> Self {
+        self.max_pending = max_pending.max(1);
+        self
+    }
+}
+
+/// The writes staged for one subtree.
+struct Pending {
+    since: Instant,
+    values: BTreeMap<String, Value>,
+}
+
+/// Batches writes to `Dict` subtrees of a tree into shared entries.
+///
+/// Writes are staged per subtree with [`set`](Self::set) and
+/// [`delete`](Self::delete). A later write to a key replaces the staged one, so
+/// only the last value reaches the tree. The writes staged for a subtree are
+/// committed once its policy's interval has passed since the first of them, or
+/// once they cover `max_pending` keys. Every subtree that is due at that time is
+/// committed in the same entry.
+///
+/// Thresholds are checked when writes are staged, so writes to a subtree that
+/// has gone quiet are committed by the next write to any subtree, by
+/// [`commit_due`](Self::commit_due), which a caller can run on a timer, by
+/// [`flush`](Self::flush), or when the `WriteCoalescer` is dropped. Entries are
+/// signed with the tree's default authentication key.
+///
+/// Created by [`Tree::write_coalescer`].
+pub struct WriteCoalescer {
+    tree: Tree,
+    default_policy: CoalescePolicy,
+    policies: HashMap<String, CoalescePolicy>,
+    pending: Mutex<BTreeMap<String, Pending>>,
+}
+
+impl WriteCoalescer {
+    pub(crate) fn new(tree: &Tree, policy: CoalescePolicy) -> Self {
+        Self {
+            tree: tree.clone(),
+            default_policy: policy,
+            policies: HashMap::new(),
+            pending: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Use `policy` for `subtree` instead of the default policy.
+    pub fn subtree_policy(mut self, subtree: impl Into<String>, policy: CoalescePolicy) -> Self {
+        self.policies.insert(subtree.into(), policy);
+        self
+    }
+
+    /// The policy that applies to `subtree`.
+    pub fn policy(&self, subtree: &str) -> CoalescePolicy {
+        self.policies
+            .get(subtree)
+            .copied()
+            .unwrap_or(self.default_policy)
+    }
+
+    /// Stages `value` for `key` in the `Dict` subtree `subtree`, replacing any
+    /// value staged for it before.
+    ///
+    /// # Returns
+    /// The ID of the entry committed if this write made any subtree due.
+    ///
+    /// # Errors
+    /// Returns an error if committing fails. The writes that were due stay
+    /// staged, behind any written since, and are committed by the next commit.
+    pub fn set(
+        &self,
+        subtree: impl Into<String>,
+        key: impl Into<String>,
+        value: impl Into<Value>,
+    ) -> Result<Option<ID>> {
+        self.stage(subtree.into(), key.into(), value.into())
+    }
+
+    /// Stages the deletion of `key` from the `Dict` subtree `subtree`.
+    ///
+    /// See [`set`](Self::set).
+    pub fn delete(&self, subtree: impl Into<String>, key: impl Into<String>) -> Result<Option<ID>> {
+        self.stage(subtree.into(), key.into(), Value::Deleted)
+    }
+
+    /// Commits the writes of every subtree whose interval has passed.
+    ///
+    /// # Returns
+    /// The ID of the committed entry, or `None` if no subtree was due.
+    pub fn commit_due(&self) -> Result<Option<ID>> {
+        let mut pending = self.pending.lock().unwrap();
+        let due = self.take_due(&mut pending, None);
+        self.commit(&mut pending, due)
+    }
+
+    /// Commits all staged writes, whatever the thresholds.
+    ///
+    /// # Returns
+    /// The ID of the committed entry, or `None` if nothing was staged.
+    ///
+    /// # Errors
+    /// Returns an error if committing fails, in which case the writes stay
+    /// staged.
+    pub fn flush(&self) -> Result<Option<ID>> {
+        let mut pending = self.pending.lock().unwrap();
+        let due = std::mem::take(&mut *pending);
+        self.commit(&mut pending, due)
+    }
+
+    /// The number of keys with staged writes in `subtree`.
+    pub fn pending_keys(&self, subtree: &str) -> usize {
+        self.pending
+            .lock()
+            .unwrap()
+            .get(subtree)
+            .map_or(0, |pending| pending.values.len())
+    }
+
+    fn stage(&self, subtree: String, key: String, value: Value) -> Result<Option<ID>> {
+        let mut pending = self.pending.lock().unwrap();
+        pending
+            .entry(subtree.clone())
+            .or_insert_with(|| Pending {
+                since: Instant::now(),
+                values: BTreeMap::new(),
+            })
+            .values
+            .insert(key, value);
+        let due = self.take_due(&mut pending, Some(&subtree));
+        self.commit(&mut pending, due)
+    }
+
+    /// Removes and returns the writes of the subtrees whose interval has
+    /// passed, and of `written` if it has reached its size threshold.
+    fn take_due(
+        &self,
+        pending: &mut BTreeMap<String, Pending>,
+        written: Option<&str>,
+    ) -> BTreeMap<String, Pending> {
+        pending
+            .extract_if(.., |subtree, writes| {
+                let policy = self.policy(subtree);
+                writes.since.elapsed() >= policy.interval
+                    || (written == Some(subtree.as_str())
+                        && writes.values.len() >= policy.max_pending)
+            })
+            .collect()
+    }
+
+    /// Commits `due` in one entry, or puts its writes back into `pending` if
+    /// that fails so that a later commit retries them.
+    fn commit(
+        &self,
+        pending: &mut BTreeMap<String, Pending>,
+        due: BTreeMap<String, Pending>,
+    ) -> Result<Option<ID>> {
+        if due.is_empty() {
+            return Ok(None);
+        }
+        self.write(&due).map(Some).inspect_err(|_| {
+            for (subtree, writes) in due {
+                match pending.entry(subtree) {
+                    Entry::Vacant(entry) => {
+                        entry.insert(writes);
+                    }
+                    Entry::Occupied(mut entry) => {
+                        // Writes staged since are newer and win
+                        let staged = entry.get_mut();
+                        staged.since = staged.since.min(writes.since);
+                        for (key, value) in writes.values {
+                            staged.values.entry(key).or_insert(value);
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    fn write(&self, due: &BTreeMap<String, Pending>) -> Result<ID> {
+        let op = self.tree.new_operation()?;
+        for (subtree, writes) in due {
+            let dict = op.get_subtree::<Dict>(subtree)?;
+            for (key, value) in &writes.values {
+                match value {
+                    Value::Deleted => dict.delete(key)?,
+                    value => dict.set_value(key, value.clone())?,
+                }
+            }
+        }
+        op.commit()
+    }
+}
+
+impl Drop for WriteCoalescer {
+    /// Commits any staged writes. Errors are ignored; call
+    /// [`flush`](WriteCoalescer::flush) first to observe them.
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
@@ -94,6 +94,23 @@ pub enum AtomicOpError {
     /// Subtree operation failed
     #[error("Subtree operation failed for '{subtree}': {reason}")]
     SubtreeOperationFailed { subtree: String, reason: String },
+
+    /// The operation writes to a frozen subtree, or changes its freeze record
+    #[error("Subtree '{subtree}' is frozen")]
+    SubtreeFrozen { subtree: String },
+
+    /// The entry is not hashed with the tree's hash algorithm, or the operation
+    /// changes the algorithm recorded in the tree's settings
+    #[error("Tree entries must be hashed with '{expected}'")]
+    HashAlgorithmMismatch { expected: String },
+
+    /// The entry would break one of the tree's quotas
+    #[error("Tree quota '{quota}' exceeded: {actual} is over the limit of {limit}")]
+    QuotaExceeded {
+        quota: String,
+        limit: u64,
+        actual: u64,
+    },
 }
 
 impl AtomicOpError {
@@ -132,9 +149,25 @@ impl AtomicOpError {
             AtomicOpError::SubtreeSerializationFailed { .. }
                 | AtomicOpError::SubtreeDeserializationFailed { .. }
                 | AtomicOpError::SubtreeOperationFailed { .. }
+                | AtomicOpError::SubtreeFrozen { .. }
         )
     }
 
+    /// Check if this error was caused by writing to a frozen subtree
+    pub fn is_frozen(&self) -> bool {
+        matches!(self, AtomicOpError::SubtreeFrozen { .. })
+    }
+
+    /// Check if this error was caused by an entry breaking one of the tree's quotas
+    pub fn is_quota_exceeded(&self) -> bool {
+        matches!(self, AtomicOpError::QuotaExceeded { .. })
+    }
+
+    /// Check if this error was caused by an entry not using the tree's hash algorithm
+    pub fn is_hash_algorithm_mismatch(&self) -> bool {
+        matches!(self, AtomicOpError::HashAlgorithmMismatch { .. })
+    }
+
     /// Check if this error is related to backend operations
     pub fn is_backend_error(&self) -> bool {
         matches!(self, AtomicOpError::BackendOperationFailed { .. })
@@ -148,6 +181,8 @@ impl AtomicOpError {
                 | AtomicOpError::InvalidTip { .. }
                 | AtomicOpError::EmptyTipsNotAllowed
                 | AtomicOpError::InvalidOperationState { .. }
+                | AtomicOpError::HashAlgorithmMismatch { .. }
+                | AtomicOpError::QuotaExceeded { .. }
         )
     }
 
@@ -166,7 +201,8 @@ impl AtomicOpError {
         match self {
             AtomicOpError::SubtreeSerializationFailed { subtree, .. }
             | AtomicOpError::SubtreeDeserializationFailed { subtree, .. }
-            | AtomicOpError::SubtreeOperationFailed { subtree, .. } => Some(subtree),
+            | AtomicOpError::SubtreeOperationFailed { subtree, .. }
+            | AtomicOpError::SubtreeFrozen { subtree } => Some(subtree),
             _ => None,
         }
     }
@@ -220,6 +256,43 @@ mod tests {
         assert!(!validation_err.is_backend_error());
     }
 
+    #[test]
+    fn test_frozen_error() {
+        let err = AtomicOpError::SubtreeFrozen {
+            subtree: "ballot".to_owned(),
+        };
+        assert!(err.is_frozen());
+        assert!(err.is_subtree_error());
+        assert_eq!(err.subtree_name(), Some("ballot"));
+        assert!(!AtomicOpError::ConcurrentModification.is_frozen());
+    }
+
+    #[test]
+    fn test_hash_algorithm_mismatch_error() {
+        let err = AtomicOpError::HashAlgorithmMismatch {
+            expected: "blake3".to_owned(),
+        };
+        assert!(err.is_hash_algorithm_mismatch());
+        assert!(err.is_validation_error());
+        assert!(!AtomicOpError::ConcurrentModification.is_hash_algorithm_mismatch());
+    }
+
+    #[test]
+    fn test_quota_exceeded_error() {
+        let err = AtomicOpError::QuotaExceeded {
+            quota: "max_entries".to_owned(),
+            limit: 10,
+            actual: 11,
+        };
+        assert!(err.is_quota_exceeded());
+        assert!(err.is_validation_error());
+        assert_eq!(
+            err.to_string(),
+            "Tree quota 'max_entries' exceeded: 11 is over the limit of 10"
+        );
+        assert!(!AtomicOpError::ConcurrentModification.is_quota_exceeded());
+    }
+
     #[test]
     fn test_already_committed() {
         let err = AtomicOpError::OperationAlreadyCommitted;
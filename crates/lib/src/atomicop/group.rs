@@ -0,0 +1,176 @@
+//! Group commits
+//!
+//! A [`GroupCommit`] coalesces many small logical operations into one entry.
+//! Writers that commit at a high rate, such as telemetry collectors, would
+//! otherwise create and sign an entry per write. Each logical operation is
+//! staged into a shared `AtomicOp`, and the timestamp and optional label of
+//! every operation are recorded in the committed entry's metadata, where they
+//! can be read back with [`Entry::grouped_ops`](crate::entry::Entry::grouped_ops).
+
+use super::AtomicOp;
+use crate::Result;
+use crate::clock::Hlc;
+use crate::entry::ID;
+use crate::tree::Tree;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Duration;
+use web_time::Instant;
+
+/// A logical operation recorded in a group-committed entry.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupedOp {
+    /// When the operation was staged
+    pub timestamp: Hlc,
+    /// The label passed to [`GroupCommit::stage_labeled`], if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+/// The operation currently collecting staged changes.
+struct Pending {
+    op: AtomicOp,
+    started: Instant,
+    ops: Vec<GroupedOp>,
+}
+
+/// Coalesces logical operations on a tree into shared entries.
+///
+/// Operations are staged with [`stage`](Self::stage) into the pending entry,
+/// which is committed once it has been open for longer than the window or holds
+/// `max_ops` operations. The window is checked when operations are staged, so an
+/// idle group is committed by the next `stage`, an explicit [`flush`](Self::flush),
+/// or when the `GroupCommit` is dropped.
+///
+/// An operation that returns an error is rolled back without affecting the other
+/// operations in the group. All operations are signed together with the tree's
+/// default authentication key.
+///
+/// Created by [`Tree::group_commit`].
+pub struct GroupCommit {
+    tree: Tree,
+    window: Duration,
+    max_ops: usize,
+    pending: Mutex<Option<Pending>>,
+}
+
+impl GroupCommit {
+    pub(crate) fn new(tree: &Tree, window: Duration) -> Self {
+        Self {
+            tree: tree.clone(),
+            window,
+            max_ops: usize::MAX,
+            pending: Mutex::new(None),
+        }
+    }
+
+    /// Commits the group as soon as it holds `max_ops` operations, even if the
+    /// window has not elapsed.
+    pub fn max_ops(mut self, max_ops: usize) -> Self {
+        self.max_ops = max_ops.max(1);
+        self
+    }
+
+    /// Stages a logical operation into the pending entry.
+    ///
+    /// `stage` receives the shared operation and makes its changes through
+    /// subtrees as usual, but must not commit it.
+    ///
+    /// # Returns
+    /// The value returned by `stage`. If it returns an error, its changes are
+    /// discarded and the error is returned.
+    ///
+    /// # Errors
+    /// Also returns an error if committing the group fails, in which case every
+    /// operation in the group is lost.
+    pub fn stage<R>(&self, stage: impl FnOnce(&AtomicOp) -> Result<R>) -> Result<R> {
+        self.stage_op(None, stage)
+    }
+
+    /// Stages a logical operation, recording `label` with it in the entry metadata.
+    ///
+    /// See [`stage`](Self::stage).
+    pub fn stage_labeled<R>(
+        &self,
+        label: impl Into<String>,
+        stage: impl FnOnce(&AtomicOp) -> Result<R>,
+    ) -> Result<R> {
+        self.stage_op(Some(label.into()), stage)
+    }
+
+    /// Commits the pending entry, if any operations are staged.
+    ///
+    /// # Returns
+    /// The ID of the committed entry, or `None` if nothing was staged.
+    pub fn flush(&self) -> Result<Option<ID>> {
+        let pending = self.pending.lock().unwrap().take();
+        match pending {
+            Some(pending) => commit(pending).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// The number of operations waiting to be committed.
+    pub fn pending_ops(&self) -> usize {
+        self.pending
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map_or(0, |pending| pending.ops.len())
+    }
+
+    fn stage_op<R>(
+        &self,
+        label: Option<String>,
+        stage: impl FnOnce(&AtomicOp) -> Result<R>,
+    ) -> Result<R> {
+        let mut slot = self.pending.lock().unwrap();
+
+        // Close a group whose window has elapsed before starting the next one
+        if let Some(pending) = slot.take_if(|pending| pending.started.elapsed() >= self.window) {
+            commit(pending)?;
+        }
+        let pending = match slot.as_mut() {
+            Some(pending) => pending,
+            None => slot.insert(Pending {
+                op: self.tree.new_operation()?,
+                started: Instant::now(),
+                ops: Vec::new(),
+            }),
+        };
+
+        let checkpoint = pending.op.checkpoint()?;
+        let result = match stage(&pending.op) {
+            Ok(result) => result,
+            Err(e) => {
+                pending.op.rollback(checkpoint);
+                if pending.ops.is_empty() {
+                    *slot = None;
+                }
+                return Err(e);
+            }
+        };
+        let timestamp = Hlc::now_after(pending.ops.last().map(|op| op.timestamp));
+        pending.ops.push(GroupedOp { timestamp, label });
+
+        if pending.ops.len() >= self.max_ops
+            && let Some(pending) = slot.take()
+        {
+            commit(pending)?;
+        }
+        Ok(result)
+    }
+}
+
+impl Drop for GroupCommit {
+    /// Commits any staged operations. Errors are ignored; call
+    /// [`flush`](GroupCommit::flush) first to observe them.
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+fn commit(pending: Pending) -> Result<ID> {
+    pending.op.record_grouped_ops(pending.ops)?;
+    pending.op.commit()
+}
@@ -1,31 +1,68 @@
+mod coalesce;
 pub mod errors;
+mod group;
+mod resolve;
 
 use crate::Result;
 use crate::auth::crypto::sign_entry;
-use crate::auth::types::{Operation, SigInfo, SigKey};
+use crate::auth::invite::is_redemption;
+use crate::auth::types::{DelegationStep, Operation, SigInfo, SigKey};
 use crate::auth::validation::AuthValidator;
+use crate::auth::validation::freeze::frozen_write;
+use crate::auth::validation::hash::hash_mismatch;
+use crate::basedb::ephemeral::ephemeral_subtrees;
+use crate::basedb::quota::quota_violation;
+use crate::clock::Hlc;
 use crate::constants::SETTINGS;
 use crate::crdt::CRDT;
 use crate::crdt::Map;
-use crate::crdt::map::Value;
-use crate::entry::{Entry, EntryBuilder, ID};
+use crate::crdt::map::{Stamp, Value};
+use crate::entry::{AppInfo, Entry, EntryBuilder, HashAlgorithm, ID};
+use crate::instrument::{self, timed};
 use crate::subtree::SubTree;
+use crate::subtree::encoding::{self, SubtreeEncoding};
 use crate::tree::Tree;
-use std::cell::RefCell;
-use std::rc::Rc;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::{Arc, Mutex};
 
 use serde::{Deserialize, Serialize};
 
+pub use coalesce::{CoalescePolicy, WriteCoalescer};
 pub use errors::AtomicOpError;
+pub use group::{GroupCommit, GroupedOp};
+
+/// Paths from an LCA at least this long are merged in parallel with the
+/// `parallel` feature; shorter ones are not worth the hand-off to other threads
+#[cfg(feature = "parallel")]
+const PARALLEL_PATH_LEN: usize = 64;
 
 /// Metadata structure for entries
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 struct EntryMetadata {
     /// Tips of the _settings subtree at the time this entry was created
     /// This is used for improving sync performance and for validation in sparse checkouts.
+    #[serde(default)]
     settings_tips: Vec<ID>,
     /// Random entropy for ensuring unique IDs for root entries
     entropy: Option<u64>,
+    /// Hybrid logical clock timestamp of the commit, see `crate::clock`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    hlc: Option<Hlc>,
+    /// Logical operations coalesced into this entry by a `GroupCommit`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    ops: Vec<GroupedOp>,
+    /// Subtrees whose data in this entry is their full state, see `Tree::create_checkpoint`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    checkpoint: Vec<String>,
+    /// Device and application that wrote the entry, see `AppInfo`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    app: Option<AppInfo>,
+}
+
+/// Staged state of an `AtomicOp`, restored by `AtomicOp::rollback`
+pub(crate) struct Checkpoint {
+    builder: EntryBuilder,
+    merged_subtrees: HashSet<String>,
 }
 
 /// Represents a single, atomic transaction for modifying a `Tree`.
@@ -47,11 +84,15 @@ struct EntryMetadata {
 #[derive(Clone)]
 pub struct AtomicOp {
     /// The entry builder being modified, wrapped in Option to support consuming on commit
-    entry_builder: Rc<RefCell<Option<EntryBuilder>>>,
+    entry_builder: Arc<Mutex<Option<EntryBuilder>>>,
     /// The tree this operation belongs to
     tree: Tree,
     /// Optional authentication key ID for signing entries
     auth_key_name: Option<String>,
+    /// Delegation steps leading to `auth_key_name`, when it belongs to a delegated tree
+    delegation: Option<Vec<DelegationStep>>,
+    /// Subtrees staged with `merge_subtree`, kept in the entry even without data
+    merged_subtrees: Arc<Mutex<HashSet<String>>>,
 }
 
 impl AtomicOp {
@@ -95,6 +136,7 @@ impl AtomicOp {
 
         // Start with a basic entry linked to the tree's root.
         // Data and parents will be filled based on the operation type.
+        // It is hashed with the same algorithm as the root.
         let mut builder = Entry::builder(tree.root_id().clone());
 
         // Use the provided tips as parents (only if not empty)
@@ -103,9 +145,11 @@ impl AtomicOp {
         }
 
         Ok(Self {
-            entry_builder: Rc::new(RefCell::new(Some(builder))),
+            entry_builder: Arc::new(Mutex::new(Some(builder))),
             tree: tree.clone(),
             auth_key_name: None,
+            delegation: None,
+            merged_subtrees: Arc::new(Mutex::new(HashSet::new())),
         })
     }
 
@@ -138,6 +182,28 @@ impl AtomicOp {
         self.auth_key_name.as_deref()
     }
 
+    /// The tree this operation writes to.
+    pub(crate) fn tree(&self) -> &Tree {
+        &self.tree
+    }
+
+    /// Sign through a delegation path instead of with a key of this tree.
+    ///
+    /// The authentication key is looked up at the end of the path: `steps` are the
+    /// delegated tree references leading to it, each with the tips of its delegated
+    /// tree, and the key itself is appended as the final step on commit. The delegated
+    /// trees must be available in this tree's backend.
+    ///
+    /// # Arguments
+    /// * `steps` - The non-final steps of the delegation path
+    ///
+    /// # Returns
+    /// Self for method chaining
+    pub fn with_delegation(mut self, steps: Vec<DelegationStep>) -> Self {
+        self.delegation = Some(steps);
+        self
+    }
+
     /// Get the current settings for this operation.
     ///
     /// This method provides a unified view of settings by merging historical settings
@@ -185,7 +251,7 @@ impl AtomicOp {
     /// # Arguments
     /// * `root` - The tree root ID to set (use empty string for top-level roots)
     pub(crate) fn set_entry_root(&self, root: impl Into<String>) -> Result<()> {
-        let mut builder_ref = self.entry_builder.borrow_mut();
+        let mut builder_ref = self.entry_builder.lock().unwrap();
         let builder = builder_ref
             .as_mut()
             .ok_or(AtomicOpError::OperationAlreadyCommitted)?;
@@ -193,6 +259,162 @@ impl AtomicOp {
         Ok(())
     }
 
+    /// Set the algorithm the entry's ID will be hashed with.
+    ///
+    /// Only used when creating a tree, whose root entry chooses the algorithm
+    /// for every later entry.
+    pub(crate) fn set_hash_algorithm(&self, algorithm: HashAlgorithm) -> Result<()> {
+        let mut builder_ref = self.entry_builder.lock().unwrap();
+        let builder = builder_ref
+            .as_mut()
+            .ok_or(AtomicOpError::OperationAlreadyCommitted)?;
+        builder.set_hash_algorithm_mut(algorithm);
+        Ok(())
+    }
+
+    /// Captures the changes staged so far, so later changes can be discarded.
+    pub(crate) fn checkpoint(&self) -> Result<Checkpoint> {
+        let builder = self
+            .entry_builder
+            .lock()
+            .unwrap()
+            .as_ref()
+            .ok_or(AtomicOpError::OperationAlreadyCommitted)?
+            .clone();
+        Ok(Checkpoint {
+            builder,
+            merged_subtrees: self.merged_subtrees.lock().unwrap().clone(),
+        })
+    }
+
+    /// Discards the changes staged since `checkpoint` was taken.
+    pub(crate) fn rollback(&self, checkpoint: Checkpoint) {
+        *self.entry_builder.lock().unwrap() = Some(checkpoint.builder);
+        *self.merged_subtrees.lock().unwrap() = checkpoint.merged_subtrees;
+    }
+
+    /// Record the device and application writing this operation's entry.
+    ///
+    /// Replaces the default set with
+    /// [`BaseDB::set_default_app_info`](crate::basedb::BaseDB::set_default_app_info).
+    /// The information is stored in the entry metadata and is part of the entry
+    /// ID. See [`AppInfo`].
+    ///
+    /// # Errors
+    /// Fails if the operation was already committed.
+    pub fn set_app_info(&self, info: AppInfo) -> Result<()> {
+        let mut builder_ref = self.entry_builder.lock().unwrap();
+        builder_ref
+            .as_mut()
+            .ok_or(AtomicOpError::OperationAlreadyCommitted)?
+            .set_app_info_mut(info);
+        Ok(())
+    }
+
+    /// Records the logical operations coalesced into this entry in its metadata.
+    pub(crate) fn record_grouped_ops(&self, ops: Vec<GroupedOp>) -> Result<()> {
+        let mut builder_ref = self.entry_builder.lock().unwrap();
+        let builder = builder_ref
+            .as_mut()
+            .ok_or(AtomicOpError::OperationAlreadyCommitted)?;
+        let mut metadata = builder
+            .metadata()
+            .and_then(|m| serde_json::from_str::<EntryMetadata>(m).ok())
+            .unwrap_or_default();
+        metadata.ops = ops;
+        builder.set_metadata_mut(serde_json::to_string(&metadata)?);
+        Ok(())
+    }
+
+    /// Stages the full state of each of `subtrees` as a checkpoint.
+    ///
+    /// Subtrees whose state is not a `Map` are skipped. The checkpointed subtrees
+    /// are recorded in the entry metadata, see `Tree::create_checkpoint`.
+    pub(crate) fn stage_checkpoint(
+        &self,
+        subtrees: impl IntoIterator<Item = String>,
+    ) -> Result<Vec<String>> {
+        let mut checkpointed = Vec::new();
+        for subtree in subtrees {
+            let before = self.checkpoint()?;
+            match self.get_full_state::<Map>(&subtree) {
+                Ok(state) => {
+                    self.update_subtree(&subtree, serde_json::to_string(&state)?)?;
+                    checkpointed.push(subtree);
+                }
+                Err(crate::Error::Serialize(_)) => self.rollback(before),
+                Err(err) => return Err(err),
+            }
+        }
+        self.mark_checkpoint(checkpointed.clone())?;
+        Ok(checkpointed)
+    }
+
+    /// Stages the full state of `subtree`, read as `T`, as a checkpoint.
+    ///
+    /// Like `stage_checkpoint`, for subtrees whose state is not a `Map`, such as
+    /// `YDoc`. Fails if the subtree has no entries.
+    #[cfg(feature = "y-crdt")]
+    pub(crate) fn stage_checkpoint_as<T>(&self, subtree: &str) -> Result<()>
+    where
+        T: CRDT + Default + Send + Sync + 'static,
+    {
+        if self.subtree_tips(subtree)?.is_empty() {
+            return Err(crate::subtree::SubtreeError::InvalidOperation {
+                subtree: subtree.to_string(),
+                operation: "checkpoint".to_string(),
+                reason: "the subtree has no entries".to_string(),
+            }
+            .into());
+        }
+        let state = self.get_full_state::<T>(subtree)?;
+        self.update_subtree(subtree, serde_json::to_string(&state)?)?;
+        self.mark_checkpoint(vec![subtree.to_string()])
+    }
+
+    /// Stages each given state as a checkpoint of its subtree.
+    ///
+    /// Unlike `stage_checkpoint`, the states are not computed from history, so a
+    /// checkpoint can leave out changes that are about to be removed. The extra
+    /// parents given with a state are added to the subtree's parents, keeping
+    /// entries that would otherwise lose all their children in the subtree from
+    /// becoming its tips again.
+    pub(crate) fn stage_checkpoint_states(
+        &self,
+        states: Vec<(String, Map, Vec<ID>)>,
+    ) -> Result<()> {
+        let mut checkpointed = Vec::with_capacity(states.len());
+        for (subtree, state, extra_parents) in states {
+            self.update_subtree(&subtree, serde_json::to_string(&state)?)?;
+            if !extra_parents.is_empty() {
+                let mut builder_ref = self.entry_builder.lock().unwrap();
+                let builder = builder_ref
+                    .as_mut()
+                    .ok_or(AtomicOpError::OperationAlreadyCommitted)?;
+                let mut parents = builder.subtree_parents(&subtree).unwrap_or_default();
+                parents.extend(extra_parents);
+                builder.set_subtree_parents_mut(&subtree, parents);
+            }
+            checkpointed.push(subtree);
+        }
+        self.mark_checkpoint(checkpointed)
+    }
+
+    /// Records the checkpointed subtrees in the entry metadata.
+    fn mark_checkpoint(&self, subtrees: Vec<String>) -> Result<()> {
+        let mut builder_ref = self.entry_builder.lock().unwrap();
+        let builder = builder_ref
+            .as_mut()
+            .ok_or(AtomicOpError::OperationAlreadyCommitted)?;
+        let mut metadata = builder
+            .metadata()
+            .and_then(|m| serde_json::from_str::<EntryMetadata>(m).ok())
+            .unwrap_or_default();
+        metadata.checkpoint = subtrees;
+        builder.set_metadata_mut(serde_json::to_string(&metadata)?);
+        Ok(())
+    }
+
     /// Stages an update for a specific subtree within this atomic operation.
     ///
     /// This method is primarily intended for internal use by `SubTree` implementations
@@ -216,7 +438,7 @@ impl AtomicOp {
     ) -> Result<()> {
         let subtree = subtree.as_ref();
         let data = data.as_ref();
-        let mut builder_ref = self.entry_builder.borrow_mut();
+        let mut builder_ref = self.entry_builder.lock().unwrap();
         let builder = builder_ref
             .as_mut()
             .ok_or(AtomicOpError::OperationAlreadyCommitted)?;
@@ -262,8 +484,38 @@ impl AtomicOp {
         T: SubTree,
     {
         let subtree_name = subtree_name.into();
+        self.stage_subtree(&subtree_name)?;
+
+        // Now create the SubTree with the atomic operation
+        T::new(self, subtree_name)
+    }
+
+    /// Stages a merge of all tips of a subtree.
+    ///
+    /// The committed entry will include `subtree_name` with every current tip of
+    /// that subtree (reachable from this operation's parents) as its subtree
+    /// parents, even if no data is staged for it. This converges the subtree's
+    /// history to a single tip without changing its state or touching other
+    /// subtrees.
+    ///
+    /// Data may still be staged for the subtree through `get_subtree`.
+    ///
+    /// # Arguments
+    /// * `subtree_name` - The name of the subtree to merge.
+    pub fn merge_subtree(&self, subtree_name: impl Into<String>) -> Result<()> {
+        let subtree_name = subtree_name.into();
+        self.stage_subtree(&subtree_name)?;
+        self.merged_subtrees.lock().unwrap().insert(subtree_name);
+        Ok(())
+    }
+
+    /// Adds a subtree to the entry being built, recording its parent tips.
+    ///
+    /// Does nothing if the subtree is already part of the entry.
+    fn stage_subtree(&self, subtree_name: &str) -> Result<()> {
+        let subtree_name = subtree_name.to_string();
         {
-            let mut builder_ref = self.entry_builder.borrow_mut();
+            let mut builder_ref = self.entry_builder.lock().unwrap();
             let builder = builder_ref
                 .as_mut()
                 .ok_or(AtomicOpError::OperationAlreadyCommitted)?;
@@ -293,9 +545,7 @@ impl AtomicOp {
                 builder.set_subtree_parents_mut(&subtree_name, tips);
             }
         }
-
-        // Now create the SubTree with the atomic operation
-        T::new(self, subtree_name)
+        Ok(())
     }
 
     /// Gets the currently staged data for a specific subtree within this operation.
@@ -326,7 +576,7 @@ impl AtomicOp {
         T: crate::crdt::Data + Default,
     {
         let subtree_name = subtree_name.as_ref();
-        let builder_ref = self.entry_builder.borrow();
+        let builder_ref = self.entry_builder.lock().unwrap();
         let builder = builder_ref
             .as_ref()
             .ok_or(AtomicOpError::OperationAlreadyCommitted)?;
@@ -370,13 +620,118 @@ impl AtomicOp {
     /// # Returns
     /// A `Result<T>` containing the merged historical data of type `T`. Returns `Ok(T::default())`
     /// if the subtree has no history prior to this operation.
+    ///
+    /// The in-memory state of an ephemeral subtree is merged over its history,
+    /// whatever tips the operation is based on.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(tree = %self.tree.root_id(), subtree = subtree_name.as_ref(), tips)
+        )
+    )]
     pub(crate) fn get_full_state<T>(&self, subtree_name: impl AsRef<str>) -> Result<T>
     where
-        T: CRDT + Default,
+        T: CRDT + Default + Send + Sync + 'static,
+    {
+        let subtree_name = subtree_name.as_ref();
+        let state = self.get_stored_state::<T>(subtree_name)?;
+        match self
+            .tree
+            .ephemeral_state()
+            .get::<T>(self.tree.root_id(), subtree_name)?
+        {
+            Some(ephemeral) => state.merge(&ephemeral),
+            None => Ok(state),
+        }
+    }
+
+    /// Merges the history of a subtree up to the tips of this operation; see
+    /// [`get_full_state`](Self::get_full_state).
+    fn get_stored_state<T>(&self, subtree_name: &str) -> Result<T>
+    where
+        T: CRDT + Default + Send + Sync + 'static,
+    {
+        let parents = self.subtree_tips(subtree_name)?;
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("tips", parents.len());
+
+        // If there are no parents, return a default
+        if parents.is_empty() {
+            return Ok(T::default());
+        }
+
+        // The merged state only depends on the tips, so it may have been read before
+        let cache = self.tree.state_cache();
+        let root = self.tree.root_id();
+        if let Some(state) = cache.get::<T>(root, subtree_name, &parents) {
+            #[cfg(feature = "tracing")]
+            tracing::trace!("merged state cached");
+            return Ok(state);
+        }
+
+        // Compute the CRDT state using LCA-based ROOT-to-target computation
+        let state: T = timed(
+            instrument::Operation::Merge,
+            Some(root),
+            Some(subtree_name),
+            || self.compute_subtree_state_lca_based(subtree_name, &parents),
+        )?;
+        cache.insert(root, subtree_name, &parents, state.clone());
+        Ok(state)
+    }
+
+    /// Gets the entries of a subtree that this operation builds on, with the
+    /// subtree state as of each entry.
+    ///
+    /// Only entries whose own data satisfies `filter` are returned, in
+    /// topological order. Checkpoints are skipped, as they make no changes.
+    pub(crate) fn subtree_history<T>(
+        &self,
+        subtree_name: impl AsRef<str>,
+        filter: impl Fn(&T) -> bool,
+    ) -> Result<Vec<(Entry, T)>>
+    where
+        T: CRDT + Default + Send,
     {
         let subtree_name = subtree_name.as_ref();
+        let mut history = Vec::new();
+        for entry in self.subtree_entries(subtree_name)? {
+            if !filter(&local_state::<T>(&entry, subtree_name)?) {
+                continue;
+            }
+            let state = self.compute_single_entry_state_recursive(subtree_name, &entry.id())?;
+            history.push((entry, state));
+        }
+        Ok(history)
+    }
+
+    /// Gets the entries of a subtree that this operation builds on.
+    ///
+    /// Entries are in topological order, by height and then by ID, so every
+    /// replica with the same entries orders them the same way. Checkpoints are
+    /// skipped, as they make no changes.
+    pub(crate) fn subtree_entries(&self, subtree_name: &str) -> Result<Vec<Entry>> {
+        let tips = self.subtree_tips(subtree_name)?;
+        if tips.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut entries =
+            self.tree
+                .backend()
+                .get_subtree_from_tips(self.tree.root_id(), subtree_name, &tips)?;
+        entries.retain(|entry| !entry.is_checkpoint_of(subtree_name));
+        Ok(entries)
+    }
+
+    /// Gets the tips of a subtree that this operation builds on.
+    ///
+    /// The tips are recorded as the subtree's parents the first time the subtree
+    /// is accessed, so later reads see the same state.
+    fn subtree_tips(&self, subtree_name: &str) -> Result<Vec<ID>> {
         // Get the entry builder to get parent pointers
-        let mut builder_ref = self.entry_builder.borrow_mut();
+        let mut builder_ref = self.entry_builder.lock().unwrap();
         let builder = builder_ref
             .as_mut()
             .ok_or(AtomicOpError::OperationAlreadyCommitted)?;
@@ -406,15 +761,7 @@ impl AtomicOp {
         }
 
         // Get the parent pointers for this subtree
-        let parents = builder.subtree_parents(subtree_name).unwrap_or_default();
-
-        // If there are no parents, return a default
-        if parents.is_empty() {
-            return Ok(T::default());
-        }
-
-        // Compute the CRDT state using LCA-based ROOT-to-target computation
-        self.compute_subtree_state_lca_based(subtree_name, &parents)
+        Ok(builder.subtree_parents(subtree_name).unwrap_or_default())
     }
 
     /// Computes the CRDT state for a subtree using correct recursive LCA-based algorithm.
@@ -439,7 +786,7 @@ impl AtomicOp {
         entry_ids: &[ID],
     ) -> Result<T>
     where
-        T: CRDT + Default,
+        T: CRDT + Default + Send,
     {
         // Base case: no entries
         if entry_ids.is_empty() {
@@ -458,30 +805,59 @@ impl AtomicOp {
             .tree
             .backend()
             .find_lca(self.tree.root_id(), subtree_name, entry_ids)?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(lca = %lca_id, tips = ?entry_ids, "merging tips from their common ancestor");
 
-        // Get the LCA state recursively
-        let mut result = self.compute_single_entry_state_recursive(subtree_name, &lca_id)?;
+        self.compute_state_from_lca(subtree_name, &lca_id, entry_ids)
+    }
 
-        // Get all entries from LCA to all tip entries (deduplicated and sorted)
-        let path_entries = {
-            self.tree.backend().get_path_from_to(
-                self.tree.root_id(),
-                subtree_name,
-                &lca_id,
-                entry_ids,
-            )?
-        };
+    /// Computes the merged state of `targets` from their LCA.
+    ///
+    /// The state of the LCA and the entries on the paths from it to `targets`
+    /// do not depend on each other. With the `parallel` feature, long paths are
+    /// merged on rayon's thread pool while the LCA state is computed, and the
+    /// two results are merged at the join.
+    ///
+    /// # Arguments
+    /// * `subtree_name` - The name of the subtree
+    /// * `lca_id` - The lowest common ancestor of `targets` in the subtree
+    /// * `targets` - The entries whose merged state is computed
+    fn compute_state_from_lca<T>(
+        &self,
+        subtree_name: &str,
+        lca_id: &ID,
+        targets: &[ID],
+    ) -> Result<T>
+    where
+        T: CRDT + Default + Send,
+    {
+        // Get all entries from LCA to all targets (deduplicated and sorted)
+        let path_entries = self.tree.backend().get_path_from_to(
+            self.tree.root_id(),
+            subtree_name,
+            lca_id,
+            targets,
+        )?;
 
-        // Merge all path entries in order
-        result = self.merge_path_entries(subtree_name, result, &path_entries)?;
+        #[cfg(feature = "parallel")]
+        if path_entries.len() >= PARALLEL_PATH_LEN {
+            // The LCA state recurses, so it stays on this thread
+            let (lca_state, path_state) = rayon::join(
+                || self.compute_single_entry_state_recursive::<T>(subtree_name, lca_id),
+                || self.merge_path_entries_parallel::<T>(subtree_name, &path_entries),
+            );
+            return lca_state?.merge(&path_state?);
+        }
 
-        Ok(result)
+        // Get the LCA state recursively, then merge all path entries in order
+        let lca_state = self.compute_single_entry_state_recursive(subtree_name, lca_id)?;
+        self.merge_path_entries(subtree_name, lca_state, &path_entries)
     }
 
     /// Computes the CRDT state for a single entry using correct recursive LCA algorithm.
     ///
     /// Algorithm:
-    /// 1. Check if entry state is cached → return it
+    /// 1. Check if entry state is cached, or stored by a checkpoint → return it
     /// 2. Find LCA of parents and get its state (recursively)
     /// 3. Merge all entries from LCA to current entry into that state
     ///
@@ -494,13 +870,21 @@ impl AtomicOp {
     ///
     /// # Returns
     /// A `Result<T>` containing the computed CRDT state for the entry
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "trace",
+            skip_all,
+            fields(entry = %entry_id, subtree = subtree_name)
+        )
+    )]
     fn compute_single_entry_state_recursive<T>(
         &self,
         subtree_name: &str,
         entry_id: &ID,
     ) -> Result<T>
     where
-        T: CRDT + Default,
+        T: CRDT + Default + Send,
     {
         // Step 1: Check if already cached
         {
@@ -509,11 +893,25 @@ impl AtomicOp {
                 .backend()
                 .get_cached_crdt_state(entry_id, subtree_name)?
             {
+                #[cfg(feature = "tracing")]
+                tracing::trace!("state cached");
                 let result: T = serde_json::from_str(&cached_state)?;
                 return Ok(result);
             }
         }
 
+        // A checkpoint holds the full state, so the history before it is not needed
+        let entry = self.tree.backend().get(entry_id)?;
+        if entry.is_checkpoint_of(subtree_name) {
+            let result = local_state::<T>(&entry, subtree_name)?;
+            self.tree.backend().cache_crdt_state(
+                entry_id,
+                subtree_name,
+                serde_json::to_string(&result)?,
+            )?;
+            return Ok(result);
+        }
+
         // Get the parents of this entry in the subtree
         let parents = {
             self.tree.backend().get_sorted_subtree_parents(
@@ -523,55 +921,27 @@ impl AtomicOp {
             )?
         };
 
-        // Step 2: Compute LCA state recursively
-        let (lca_state, lca_id_opt) = if parents.is_empty() {
+        // Step 2 and 3: Compute the merged state of the parents
+        let mut result = if parents.is_empty() {
             // No parents - this is a root, start with default
-            (T::default(), None)
+            T::default()
         } else if parents.len() == 1 {
             // Single parent - recursively get its state
-            (
-                self.compute_single_entry_state_recursive(subtree_name, &parents[0])?,
-                None,
-            )
+            self.compute_single_entry_state_recursive(subtree_name, &parents[0])?
         } else {
-            // Multiple parents - find LCA and get its state
+            // Multiple parents - merge the paths from their LCA to all parents
             let lca_id = {
                 self.tree
                     .backend()
                     .find_lca(self.tree.root_id(), subtree_name, &parents)?
             };
-            let lca_state = self.compute_single_entry_state_recursive(subtree_name, &lca_id)?;
-            (lca_state, Some(lca_id))
+            #[cfg(feature = "tracing")]
+            tracing::trace!(lca = %lca_id, ?parents, "merging parents from their common ancestor");
+            self.compute_state_from_lca(subtree_name, &lca_id, &parents)?
         };
 
-        // Step 3: Merge entries from LCA to current entry
-        let mut result = lca_state;
-
-        // If we have multiple parents, we need to merge paths from LCA to all parents
-        if let Some(lca_id) = lca_id_opt {
-            // Get all entries from LCA to all parents (deduplicated and sorted)
-            let path_entries = {
-                self.tree.backend().get_path_from_to(
-                    self.tree.root_id(),
-                    subtree_name,
-                    &lca_id,
-                    &parents,
-                )?
-            };
-
-            // Merge all path entries in order
-            result = self.merge_path_entries(subtree_name, result, &path_entries)?;
-        }
-
         // Finally, merge the current entry's local data
-        let local_data = {
-            let entry = self.tree.backend().get(entry_id)?;
-            if let Ok(data) = entry.data(subtree_name) {
-                serde_json::from_str::<T>(data)?
-            } else {
-                T::default()
-            }
-        };
+        let local_data = local_state::<T>(&entry, subtree_name)?;
 
         result = result.merge(&local_data)?;
 
@@ -602,12 +972,13 @@ impl AtomicOp {
         for entry_id in entry_ids {
             let entry = self.tree.backend().get(entry_id)?;
 
+            // A checkpoint makes no changes, its data is the state of its ancestors
+            if entry.is_checkpoint_of(subtree_name) {
+                continue;
+            }
+
             // Get local data for this entry in the subtree
-            let local_data = if let Ok(data) = entry.data(subtree_name) {
-                serde_json::from_str::<T>(data)?
-            } else {
-                T::default()
-            };
+            let local_data = local_state::<T>(&entry, subtree_name)?;
 
             state = state.merge(&local_data)?;
         }
@@ -615,6 +986,31 @@ impl AtomicOp {
         Ok(state)
     }
 
+    /// Merges a sequence of entries into a single state on rayon's thread pool.
+    ///
+    /// Runs of adjacent entries are merged on different threads and their
+    /// results merged in path order, which gives the same state as merging the
+    /// entries one by one because `CRDT::merge` is associative.
+    #[cfg(feature = "parallel")]
+    fn merge_path_entries_parallel<T>(&self, subtree_name: &str, entry_ids: &[ID]) -> Result<T>
+    where
+        T: CRDT + Default + Send,
+    {
+        use rayon::prelude::*;
+
+        entry_ids
+            .par_iter()
+            .try_fold(T::default, |state, entry_id| {
+                let entry = self.tree.backend().get(entry_id)?;
+                // A checkpoint makes no changes, its data is the state of its ancestors
+                if entry.is_checkpoint_of(subtree_name) {
+                    return Ok(state);
+                }
+                state.merge(&local_state::<T>(&entry, subtree_name)?)
+            })
+            .try_reduce(T::default, |left, right| left.merge(&right))
+    }
+
     /// Commits the operation, finalizing and persisting the entry to the backend.
     ///
     /// This method:
@@ -629,15 +1025,44 @@ impl AtomicOp {
     /// 9. Persists the entry to the backend
     /// 10. Returns the ID of the newly created entry
     ///
+    /// Changes to ephemeral subtrees (see [`Tree::mark_ephemeral`]) are merged
+    /// into the tree's in-memory ephemeral state instead of the entry. If there
+    /// is nothing else to commit, no entry is stored and the ID of one of the
+    /// operation's parents is returned.
+    ///
     /// After commit, the operation cannot be used again, as the internal
     /// `EntryBuilder` has been consumed.
     ///
     /// # Returns
     /// A `Result<ID>` containing the ID of the committed entry.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "commit",
+            skip_all,
+            fields(tree = %self.tree.root_id(), entry)
+        )
+    )]
     pub fn commit(self) -> Result<ID> {
+        let tree = self.tree.root_id().clone();
+        let result = timed(instrument::Operation::Commit, Some(&tree), None, || {
+            self.commit_entry()
+        });
+        #[cfg(feature = "tracing")]
+        match &result {
+            Ok(id) => {
+                tracing::Span::current().record("entry", tracing::field::display(id));
+            }
+            Err(error) => tracing::debug!(%error, "commit failed"),
+        }
+        result
+    }
+
+    /// Builds, signs and stores the entry; see [`commit`](Self::commit).
+    fn commit_entry(self) -> Result<ID> {
         // Check if this is a settings subtree update and get the effective settings before any borrowing
         let has_settings_update = {
-            let builder_cell = self.entry_builder.borrow();
+            let builder_cell = self.entry_builder.lock().unwrap();
             let builder = builder_cell
                 .as_ref()
                 .ok_or(AtomicOpError::OperationAlreadyCommitted)?;
@@ -647,14 +1072,25 @@ impl AtomicOp {
         // Get settings using full CRDT state computation
         let historical_settings = self.get_full_state::<Map>(SETTINGS)?;
 
+        // The _settings tips the historical settings were computed from
+        let historical_settings_tips = {
+            let builder_cell = self.entry_builder.lock().unwrap();
+            let builder = builder_cell
+                .as_ref()
+                .ok_or(AtomicOpError::OperationAlreadyCommitted)?;
+            builder.subtree_parents(SETTINGS).unwrap_or_default()
+        };
+
         // However, if this is a settings update and there's no historical auth but staged auth exists,
         // use the staged settings for validation (this handles initial tree creation with auth)
+        let mut uses_staged_settings = false;
         let effective_settings_for_validation = if has_settings_update {
             let historical_has_auth = matches!(historical_settings.get("auth"), Some(Value::Map(auth_map)) if !auth_map.as_hashmap().is_empty());
             if !historical_has_auth {
                 let staged_settings = self.get_local_data::<Map>(SETTINGS)?;
                 let staged_has_auth = matches!(staged_settings.get("auth"), Some(Value::Map(auth_map)) if !auth_map.as_hashmap().is_empty());
                 if staged_has_auth {
+                    uses_staged_settings = true;
                     staged_settings
                 } else {
                     historical_settings
@@ -666,14 +1102,15 @@ impl AtomicOp {
             historical_settings
         };
 
-        // Get the entry out of the RefCell, consuming self in the process
-        let builder_cell = self.entry_builder.borrow_mut();
-        let builder_from_cell = builder_cell
+        // Clone the builder out of the shared cell, releasing the lock so the
+        // staged data can still be read below
+        let mut builder = self
+            .entry_builder
+            .lock()
+            .unwrap()
             .as_ref()
-            .ok_or(AtomicOpError::OperationAlreadyCommitted)?;
-
-        // Clone the builder since we can't easily take ownership from RefCell<Option<>>
-        let mut builder = builder_from_cell.clone();
+            .ok_or(AtomicOpError::OperationAlreadyCommitted)?
+            .clone();
 
         // Add metadata with settings tips for all entries
         // Get the backend to access settings tips
@@ -687,14 +1124,26 @@ impl AtomicOp {
         let mut metadata = builder
             .metadata()
             .and_then(|m| serde_json::from_str::<EntryMetadata>(m).ok())
-            .unwrap_or_else(|| EntryMetadata {
-                settings_tips: Vec::new(),
-                entropy: None,
-            });
+            .unwrap_or_default();
 
         // Update settings tips
         metadata.settings_tips = settings_tips;
 
+        // Timestamp the entry after all of its parents, and after the latest
+        // ephemeral write
+        let mut parent_timestamps = Vec::new();
+        for parent in builder.parents().unwrap_or_default() {
+            if let Some(hlc) = self.tree.backend().get(&parent)?.timestamp() {
+                parent_timestamps.push(hlc);
+            }
+        }
+        parent_timestamps.extend(
+            self.tree
+                .ephemeral_state()
+                .latest_write(self.tree.root_id()),
+        );
+        metadata.hlc = Some(Hlc::now_after(parent_timestamps));
+
         // Serialize the metadata
         let metadata_json = serde_json::to_string(&metadata)?;
 
@@ -705,10 +1154,18 @@ impl AtomicOp {
         // All entries must now be authenticated - fail if no auth key is configured
         let signing_key = if let Some(key_name) = &self.auth_key_name {
             // Set auth ID on the entry builder (without signature initially)
-            builder.set_sig_mut(SigInfo {
-                key: SigKey::Direct(key_name.clone()),
-                sig: None,
-            });
+            let key = match &self.delegation {
+                Some(steps) => {
+                    let mut steps = steps.clone();
+                    steps.push(DelegationStep {
+                        key: key_name.clone(),
+                        tips: None,
+                    });
+                    SigKey::DelegationPath(steps)
+                }
+                None => SigKey::Direct(key_name.clone()),
+            };
+            builder.set_sig_mut(SigInfo { key, sig: None });
 
             // Get the private key from backend for signing
             let signing_key = self.tree.backend().get_private_key(key_name)?;
@@ -767,8 +1224,70 @@ impl AtomicOp {
             return Err(AtomicOpError::AuthenticationRequired.into());
         };
 
-        // Remove empty subtrees and build the final immutable Entry
-        let mut entry = builder.remove_empty_subtrees().build();
+        // Stamp the keys written to map subtrees with the entry's timestamp.
+        // Checkpoints restate existing values and keep their stamps.
+        if let Some(hlc) = metadata.hlc {
+            let stamp = Stamp::new(hlc);
+            for subtree in builder.subtrees() {
+                if metadata.checkpoint.contains(&subtree) {
+                    continue;
+                }
+                let Ok(mut data) = serde_json::from_str::<Map>(builder.data(&subtree)?) else {
+                    continue;
+                };
+                if data.as_hashmap().is_empty() {
+                    continue;
+                }
+                data.stamp_writes(stamp);
+                builder.set_subtree_data_mut(subtree, serde_json::to_string(&data)?);
+            }
+        }
+
+        // Writes to ephemeral subtrees are kept in memory instead of the entry
+        let ephemeral = ephemeral_subtrees(&effective_settings_for_validation);
+        let mut ephemeral_writes = BTreeMap::new();
+        for subtree in &ephemeral {
+            let data = match builder.data(subtree) {
+                Ok(data) if !data.trim().is_empty() => data,
+                _ => continue,
+            };
+            let data: Map =
+                serde_json::from_str(data).map_err(|e| AtomicOpError::SubtreeOperationFailed {
+                    subtree: subtree.clone(),
+                    reason: format!("Ephemeral subtrees only hold Dict data: {e}"),
+                })?;
+            builder.set_subtree_data_mut(subtree.clone(), String::new());
+            if !data.as_hashmap().is_empty() {
+                ephemeral_writes.insert(subtree.clone(), data);
+            }
+        }
+
+        // Convert the data of subtrees declared with another encoding than JSON.
+        // Internal subtrees are always JSON.
+        for subtree in builder.subtrees() {
+            if subtree.starts_with('_') {
+                continue;
+            }
+            let encoding = SubtreeEncoding::declared(&effective_settings_for_validation, &subtree);
+            let data = builder.data(&subtree)?;
+            if encoding != SubtreeEncoding::Json && !data.trim().is_empty() {
+                let encoded = encoding.encode(&subtree, data)?;
+                builder.set_subtree_data_mut(subtree, encoded);
+            }
+        }
+
+        // Remove empty subtrees, other than explicit merges, and build the final immutable Entry
+        let merged_subtrees = self.merged_subtrees.lock().unwrap().clone();
+        let mut entry = builder
+            .remove_empty_subtrees_except(&merged_subtrees)
+            .build();
+
+        if let Some(subtree) = frozen_write(&entry, &effective_settings_for_validation) {
+            return Err(AtomicOpError::SubtreeFrozen { subtree }.into());
+        }
+        if let Some(expected) = hash_mismatch(&entry, &effective_settings_for_validation) {
+            return Err(AtomicOpError::HashAlgorithmMismatch { expected }.into());
+        }
 
         // Sign the entry if we have a signing key
         if let Some(signing_key) = signing_key {
@@ -776,8 +1295,33 @@ impl AtomicOp {
             entry.sig.sig = Some(signature);
         }
 
+        // Quotas are checked on the signed entry, as peers will see it
+        if let Some(violation) = quota_violation(
+            &entry,
+            &effective_settings_for_validation,
+            Some(self.tree.backend().as_ref()),
+        )? {
+            return Err(AtomicOpError::QuotaExceeded {
+                quota: violation.quota,
+                limit: violation.limit,
+                actual: violation.actual,
+            }
+            .into());
+        }
+
         // Validate authentication (all entries must be authenticated)
-        let mut validator = AuthValidator::new();
+        // Historical settings are identified by their tips, so the tree's cached
+        // validator can be reused; staged settings get a fresh validator.
+        let mut fresh_validator = AuthValidator::new();
+        let mut tree_validator =
+            (!uses_staged_settings).then(|| self.tree.validator().lock().unwrap());
+        let validator: &mut AuthValidator = match tree_validator.as_mut() {
+            Some(tree_validator) => {
+                tree_validator.pin_settings_tips(&historical_settings_tips);
+                tree_validator
+            }
+            None => &mut fresh_validator,
+        };
 
         // Get the final settings state for validation
         // IMPORTANT: For permission checking, we must use the historical auth configuration
@@ -790,6 +1334,11 @@ impl AtomicOp {
             &settings_for_validation,
             Some(self.tree.backend()),
         ) {
+            // A redemption's key is authorized by its invitation, which the
+            // validator has checked
+            Ok(true) if is_redemption(&entry, &settings_for_validation) => {
+                crate::backend::VerificationStatus::Verified
+            }
             Ok(true) => {
                 // Authentication validation succeeded - check permissions
                 match settings_for_validation.get("auth") {
@@ -855,13 +1404,70 @@ impl AtomicOp {
                 return Err(e);
             }
         };
+        drop(tree_validator);
+
+        // An operation that only wrote to ephemeral subtrees leaves no entry
+        let root = self.tree.root_id();
+        if !ephemeral_writes.is_empty() && entry.subtrees().is_empty() {
+            let parent = entry.parents()?.into_iter().next().ok_or_else(|| {
+                AtomicOpError::InvalidOperationState {
+                    reason: "Ephemeral writes need a parent entry".to_string(),
+                }
+            })?;
+            self.tree
+                .ephemeral_state()
+                .merge(root, ephemeral_writes, &ephemeral)?;
+            return Ok(parent);
+        }
 
         // Get the entry's ID
         let id = entry.id();
 
         // Store in the backend with the determined verification status
         self.tree.backend().put(verification_status, entry)?;
+        self.tree
+            .ephemeral_state()
+            .merge(root, ephemeral_writes, &ephemeral)?;
+        self.tree.notify_commit(&id);
 
         Ok(id)
     }
 }
+
+/// Deserializes an entry's own data for a subtree.
+///
+/// Entries that do not include the subtree, or that include it without data
+/// (such as subtree merges), contribute `T::default()`.
+pub(crate) fn local_state<T>(entry: &Entry, subtree_name: &str) -> Result<T>
+where
+    T: Default + serde::de::DeserializeOwned,
+{
+    match entry.data(subtree_name) {
+        Ok(data) if !data.trim().is_empty() => encoding::decode(subtree_name, data),
+        _ => Ok(T::default()),
+    }
+}
+
+/// Async versions of the `AtomicOp` methods that touch the backend.
+///
+/// Staging changes through subtrees only touches the backend the first time a
+/// subtree is accessed; committing computes merged state, signs and persists the
+/// entry, so it is offloaded to Tokio's blocking thread pool.
+/// Requires the "async" feature and a running Tokio runtime.
+#[cfg(feature = "async")]
+impl AtomicOp {
+    /// Async version of [`AtomicOp::commit`].
+    pub async fn commit_async(self) -> Result<ID> {
+        crate::backend::asynchronous::run_blocking(move || self.commit()).await
+    }
+
+    /// Async version of [`AtomicOp::get_subtree`].
+    pub async fn get_subtree_async<T>(&self, subtree_name: impl Into<String>) -> Result<T>
+    where
+        T: SubTree + Send + 'static,
+    {
+        let op = self.clone();
+        let subtree_name = subtree_name.into();
+        crate::backend::asynchronous::run_blocking(move || op.get_subtree(subtree_name)).await
+    }
+}
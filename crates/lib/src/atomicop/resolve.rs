@@ -0,0 +1,86 @@
+//! Subtree state with custom conflict resolution.
+//!
+//! The state computed here differs from `get_full_state` only at merges: where
+//! an entry has several subtree parents, or the operation several subtree tips,
+//! conflicting leaves are passed to a [`MergeResolver`] instead of taking the
+//! value merged last. Resolved states depend on the resolver, so they are never
+//! written to the backend's CRDT state cache; every entry's state is computed
+//! once per call, walking the history in topological order.
+
+use super::{AtomicOp, local_state};
+use crate::Result;
+use crate::crdt::map::{MergeResolver, resolve_conflicts};
+use crate::crdt::{CRDT, Map};
+use crate::entry::{Entry, ID};
+use std::collections::HashMap;
+
+impl AtomicOp {
+    /// Gets the merged state of a `Map` subtree up to the point this operation
+    /// began, resolving conflicting writes at every merge with `resolver`.
+    pub(crate) fn get_full_state_resolved(
+        &self,
+        subtree_name: &str,
+        resolver: &MergeResolver,
+    ) -> Result<Map> {
+        let tips = self.subtree_tips(subtree_name)?;
+        if tips.is_empty() {
+            return Ok(Map::default());
+        }
+
+        let backend = self.tree.backend();
+        let root = self.tree.root_id();
+        let entries = backend.get_subtree_from_tips(root, subtree_name, &tips)?;
+        let mut states: HashMap<ID, Map> = HashMap::with_capacity(entries.len());
+        for entry in &entries {
+            let state = if entry.is_checkpoint_of(subtree_name) {
+                local_state::<Map>(entry, subtree_name)?
+            } else {
+                let parents = entry.subtree_parents(subtree_name)?;
+                let merged = self.merge_branches(subtree_name, &parents, &states, resolver)?;
+                merged.merge(&local_state::<Map>(entry, subtree_name)?)?
+            };
+            states.insert(entry.id(), state);
+        }
+        self.merge_branches(subtree_name, &tips, &states, resolver)
+    }
+
+    /// Merges the states of `heads`, which must already be in `states`.
+    fn merge_branches(
+        &self,
+        subtree_name: &str,
+        heads: &[ID],
+        states: &HashMap<ID, Map>,
+        resolver: &MergeResolver,
+    ) -> Result<Map> {
+        let state_of = |id: &ID| -> Result<&Map> {
+            states.get(id).ok_or_else(|| {
+                crate::backend::errors::DatabaseError::EntryNotFound { id: id.clone() }.into()
+            })
+        };
+        match heads {
+            [] => return Ok(Map::default()),
+            [head] => return Ok(state_of(head)?.clone()),
+            _ => {}
+        }
+
+        let backend = self.tree.backend();
+        let root = self.tree.root_id();
+        let lca = backend.find_lca(root, subtree_name, heads)?;
+        let base = state_of(&lca)?;
+        let path = backend.get_path_from_to(root, subtree_name, &lca, heads)?;
+        let mut merged = self.merge_path_entries(subtree_name, base.clone(), &path)?;
+
+        // Branches are resolved in the order their heads were written
+        let mut ordered: Vec<Entry> = heads
+            .iter()
+            .map(|id| backend.get(id))
+            .collect::<Result<_>>()?;
+        ordered.sort_by_key(|entry| (entry.timestamp(), entry.id()));
+        let branches = ordered
+            .iter()
+            .map(|entry| state_of(&entry.id()))
+            .collect::<Result<Vec<_>>>()?;
+        resolve_conflicts(&mut merged, base, &branches, resolver.as_ref());
+        Ok(merged)
+    }
+}
@@ -2,12 +2,43 @@
 //!
 //! This module provides Ed25519 signature generation and verification
 //! for authenticating entries in the database.
+//!
+//! Signatures are checked with strict verification, which rejects weak public
+//! keys and malleable signatures. Earlier versions used the lax check, so
+//! entries stored by them with such signatures fail re-verification. Private
+//! keys are zeroized when dropped.
+//!
+//! No comparison made here depends on secret data: verification compares
+//! values derived from the public key, the signature and the signed bytes, all
+//! of which are public. Private keys are only used by `ed25519-dalek` to sign.
 
 use super::errors::AuthError;
 use crate::entry::Entry;
 use base64ct::{Base64, Encoding};
 use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
 use rand;
+use std::marker::PhantomData;
+use zeroize::ZeroizeOnDrop;
+
+/// Whether signatures are checked with strict Ed25519 verification.
+pub(crate) const STRICT_SIGNATURES: bool = true;
+
+/// Whether private keys are zeroized when dropped, which holds as long as
+/// `SigningKey` implements `ZeroizeOnDrop`.
+pub(crate) const KEYS_ZEROIZED: bool = zeroized_on_drop(PhantomData::<SigningKey>);
+
+const fn zeroized_on_drop<T: ZeroizeOnDrop>(_: PhantomData<T>) -> bool {
+    true
+}
+
+/// Checks `signature` over `data`, strictly if [`STRICT_SIGNATURES`] is set.
+pub(crate) fn verify(verifying_key: &VerifyingKey, data: &[u8], signature: &Signature) -> bool {
+    if STRICT_SIGNATURES {
+        verifying_key.verify_strict(data, signature).is_ok()
+    } else {
+        verifying_key.verify(data, signature).is_ok()
+    }
+}
 
 /// Parse a public key from string format
 ///
@@ -100,10 +131,7 @@ pub fn verify_entry_signature(
             reason: format!("Failed to get signing bytes: {e}"),
         })?;
 
-    match verifying_key.verify(&signing_bytes, &signature) {
-        Ok(()) => Ok(true),
-        Err(_) => Ok(false),
-    }
+    Ok(verify(verifying_key, &signing_bytes, &signature))
 }
 
 /// Sign data with an Ed25519 private key
@@ -138,10 +166,7 @@ pub fn verify_signature(
 
     let signature = Signature::from_bytes(&signature_array);
 
-    match verifying_key.verify(data, &signature) {
-        Ok(()) => Ok(true),
-        Err(_) => Ok(false),
-    }
+    Ok(verify(verifying_key, data, &signature))
 }
 
 #[cfg(test)]
@@ -176,6 +201,36 @@ mod tests {
         assert_eq!(parsed.unwrap(), verifying_key);
     }
 
+    #[test]
+    fn test_weak_key_signatures_are_rejected() {
+        // The identity point as public key with an identity R and a zero scalar
+        // satisfies the lax verification equation for any message
+        let mut weak_key = [0u8; 32];
+        weak_key[0] = 1;
+        let verifying_key = VerifyingKey::from_bytes(&weak_key).unwrap();
+        let mut signature_bytes = [0u8; 64];
+        signature_bytes[0] = 1;
+        let signature = Signature::from_bytes(&signature_bytes);
+
+        let mut entry = crate::entry::Entry::builder("root123").build();
+        let signing_bytes = entry.signing_bytes().unwrap();
+        assert!(
+            ed25519_dalek::Verifier::verify(&verifying_key, &signing_bytes, &signature).is_ok()
+        );
+
+        // Strict verification, used for all entries, rejects it
+        entry.sig.sig = Some(Base64::encode_string(&signature_bytes));
+        assert!(!verify_entry_signature(&entry, &verifying_key).unwrap());
+        assert!(
+            !verify_signature(
+                b"any data",
+                &Base64::encode_string(&signature_bytes),
+                &verifying_key
+            )
+            .unwrap()
+        );
+    }
+
     #[test]
     fn test_entry_signing() {
         let (signing_key, verifying_key) = generate_keypair();
@@ -143,12 +143,48 @@ pub enum AuthError {
         value: String,
     },
 
+    /// A key with this name already exists.
+    #[error("Key already exists: {key_name}")]
+    KeyAlreadyExists {
+        /// The name of the existing key
+        key_name: String,
+    },
+
     /// Permission denied for an operation.
     #[error("Permission denied: {reason}")]
     PermissionDenied {
         /// Description of why permission was denied
         reason: String,
     },
+
+    /// An invitation is malformed, was not signed by a key allowed to issue
+    /// it, or was already redeemed or revoked.
+    #[error("Invalid invitation: {reason}")]
+    InvalidInvitation {
+        /// Description of why the invitation is invalid
+        reason: String,
+    },
+
+    /// An invitation expired before it was redeemed.
+    #[error("Invitation {invitation} has expired")]
+    InvitationExpired {
+        /// The ID of the expired invitation
+        invitation: String,
+    },
+
+    /// An encrypted value was not sealed to the key used to open it.
+    #[error("Value is not encrypted to key {key_name}")]
+    NotARecipient {
+        /// The name of the key
+        key_name: String,
+    },
+
+    /// An encrypted value could not be decrypted.
+    #[error("Decryption failed: {reason}")]
+    DecryptionFailed {
+        /// Description of why decryption failed
+        reason: String,
+    },
 }
 
 impl AuthError {
@@ -157,6 +193,11 @@ impl AuthError {
         matches!(self, AuthError::KeyNotFound { .. })
     }
 
+    /// Check if this error indicates a key already exists.
+    pub fn is_already_exists(&self) -> bool {
+        matches!(self, AuthError::KeyAlreadyExists { .. })
+    }
+
     /// Check if this error indicates invalid signature.
     pub fn is_invalid_signature(&self) -> bool {
         matches!(
@@ -170,6 +211,22 @@ impl AuthError {
         matches!(self, AuthError::PermissionDenied { .. })
     }
 
+    /// Check if this error is about an invalid or expired invitation.
+    pub fn is_invitation_error(&self) -> bool {
+        matches!(
+            self,
+            AuthError::InvalidInvitation { .. } | AuthError::InvitationExpired { .. }
+        )
+    }
+
+    /// Check if this error indicates an encrypted value could not be opened.
+    pub fn is_decryption_error(&self) -> bool {
+        matches!(
+            self,
+            AuthError::NotARecipient { .. } | AuthError::DecryptionFailed { .. }
+        )
+    }
+
     /// Check if this error indicates a configuration problem.
     pub fn is_configuration_error(&self) -> bool {
         matches!(
@@ -235,6 +292,18 @@ mod tests {
 
         let err = AuthError::EmptyDelegationPath;
         assert!(err.is_delegation_error());
+
+        let err = AuthError::InvitationExpired {
+            invitation: "test".to_string(),
+        };
+        assert!(err.is_invitation_error());
+        assert!(!err.is_permission_denied());
+
+        let err = AuthError::NotARecipient {
+            key_name: "test-key".to_string(),
+        };
+        assert!(err.is_decryption_error());
+        assert!(!err.is_invitation_error());
     }
 
     #[test]
@@ -0,0 +1,275 @@
+//! Signed tree invitations
+//!
+//! An admin of a tree mints an [`Invitation`] with
+//! [`Tree::create_invite`](crate::Tree::create_invite): a capability token,
+//! signed by the admin's key, that grants a permission on the tree until it
+//! expires. The invitation is passed to the invitee out of band, as a string
+//! from [`Invitation::to_token`].
+//!
+//! The invitee redeems it with
+//! [`BaseDB::redeem_invite`](crate::basedb::BaseDB::redeem_invite), which
+//! commits an entry signed by the invitee's own key that adds the key to
+//! `_settings.auth` and records the invitation under `invites`. The key is not
+//! yet authorized when the entry is validated, so the entry is authorized by
+//! the invitation instead: it must add exactly the signing key, with the
+//! invited permission, and nothing else, and the invitation must be signed by
+//! an active key of the tree that may create keys with that permission.
+//!
+//! Each invitation can be redeemed once along a history: an invitation already
+//! recorded under `invites` is rejected. Admins cancel an unredeemed
+//! invitation by recording it themselves with
+//! [`Tree::revoke_invite`](crate::Tree::revoke_invite), and revoking the key
+//! that signed it cancels all of its invitations.
+
+use super::crypto::{parse_public_key, verify};
+use super::errors::AuthError;
+use super::settings::AuthSettings;
+use super::types::{KeyStatus, Permission, ResolvedAuth, SigKey};
+use crate::constants::SETTINGS;
+use crate::crdt::Map;
+use crate::crdt::map::Value;
+use crate::entry::{Entry, ID};
+use base64ct::{Base64, Base64UrlUnpadded, Encoding};
+use ed25519_dalek::{Signature, Signer, SigningKey};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Settings key holding the invitations redeemed or revoked in a tree, by invitation ID.
+pub(crate) const INVITES: &str = "invites";
+
+/// Record of an invitation revoked by an admin, in place of the redeemed token.
+pub(crate) const REVOKED_INVITE: &str = "revoked";
+
+/// A signed invitation to join a tree with a given permission.
+///
+/// See the [module documentation](self) for the handshake.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Invitation {
+    /// Random identifier, so invitations with the same terms differ
+    id: String,
+    /// Root of the tree the invitation is for
+    tree: ID,
+    /// Permission granted to the invitee's key
+    permission: Permission,
+    /// Expiry in milliseconds since the Unix epoch, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires_at_ms: Option<u64>,
+    /// Name of the key in the tree's auth settings that signed the invitation
+    issuer: String,
+    /// Base64-encoded Ed25519 signature of the other fields by the issuer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sig: Option<String>,
+}
+
+impl Invitation {
+    /// Creates an invitation signed by `signing_key`, the private key of `issuer`.
+    pub(crate) fn new(
+        tree: ID,
+        permission: Permission,
+        expires_at: Option<SystemTime>,
+        issuer: impl Into<String>,
+        signing_key: &SigningKey,
+    ) -> Self {
+        let id: [u8; 16] = rand::thread_rng().r#gen();
+        let mut invite = Self {
+            id: id.iter().map(|b| format!("{b:02x}")).collect(),
+            tree,
+            permission,
+            expires_at_ms: expires_at.map(|at| {
+                at.duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis() as u64
+            }),
+            issuer: issuer.into(),
+            sig: None,
+        };
+        let signature = signing_key.sign(&invite.signing_bytes());
+        invite.sig = Some(Base64::encode_string(&signature.to_bytes()));
+        invite
+    }
+
+    /// The invitation's unique identifier.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The root ID of the tree the invitation is for.
+    pub fn tree(&self) -> &ID {
+        &self.tree
+    }
+
+    /// The permission granted to the key that redeems the invitation.
+    pub fn permission(&self) -> &Permission {
+        &self.permission
+    }
+
+    /// When the invitation expires, if it does.
+    pub fn expires_at(&self) -> Option<SystemTime> {
+        self.expires_at_ms
+            .map(|ms| UNIX_EPOCH + Duration::from_millis(ms))
+    }
+
+    /// The name of the key that signed the invitation.
+    pub fn issuer(&self) -> &str {
+        &self.issuer
+    }
+
+    /// Encodes the invitation as a URL-safe string, to hand to the invitee.
+    pub fn to_token(&self) -> String {
+        let json = serde_json::to_vec(self).expect("Invitation serializes to JSON");
+        Base64UrlUnpadded::encode_string(&json)
+    }
+
+    /// Decodes an invitation from a string produced by [`Invitation::to_token`].
+    ///
+    /// This only checks the format; the signature and terms are checked on redemption.
+    pub fn from_token(token: &str) -> Result<Self, AuthError> {
+        let invalid = |reason: String| AuthError::InvalidInvitation { reason };
+        let json = Base64UrlUnpadded::decode_vec(token.trim())
+            .map_err(|e| invalid(format!("invalid encoding: {e}")))?;
+        serde_json::from_slice(&json).map_err(|e| invalid(format!("invalid content: {e}")))
+    }
+
+    /// The bytes covered by the signature: the invitation without it.
+    fn signing_bytes(&self) -> Vec<u8> {
+        let unsigned = Self {
+            sig: None,
+            ..self.clone()
+        };
+        serde_json::to_vec(&unsigned).expect("Invitation serializes to JSON")
+    }
+
+    /// Checks that the invitation may be redeemed against `settings` at `now_ms`.
+    ///
+    /// The issuer must be an active key in `settings` that may create keys with
+    /// the invited permission, the signature must be its, the invitation must
+    /// not be recorded under `invites` yet, and it must not have expired.
+    pub(crate) fn check(&self, settings: &Map, now_ms: Option<u64>) -> Result<(), AuthError> {
+        let invalid = |reason: &str| AuthError::InvalidInvitation {
+            reason: reason.to_string(),
+        };
+
+        if let Some(Value::Map(invites)) = settings.get(INVITES)
+            && invites.get(&self.id).is_some()
+        {
+            return Err(invalid("already redeemed or revoked"));
+        }
+        if let Some(expires_at_ms) = self.expires_at_ms
+            && now_ms.is_none_or(|now| now > expires_at_ms)
+        {
+            return Err(AuthError::InvitationExpired {
+                invitation: self.id.clone(),
+            });
+        }
+
+        let auth = match settings.get("auth") {
+            Some(Value::Map(auth)) => AuthSettings::from_map(auth.clone()),
+            _ => return Err(AuthError::NoAuthConfiguration),
+        };
+        let issuer = match auth.get_key(&self.issuer) {
+            Some(Ok(key)) => key,
+            _ => return Err(invalid("issuer is not a key of the tree")),
+        };
+        let public_key = parse_public_key(&issuer.pubkey)?;
+        let resolved = ResolvedAuth {
+            public_key,
+            effective_permission: issuer.permissions,
+            key_status: issuer.status,
+        };
+        if resolved.key_status != KeyStatus::Active
+            || !auth
+                .can_create_key(&resolved, &self.permission)
+                .unwrap_or(false)
+        {
+            return Err(invalid("issuer may not grant the permission"));
+        }
+
+        let signature = self
+            .sig
+            .as_deref()
+            .and_then(|sig| Base64::decode_vec(sig).ok())
+            .and_then(|bytes| <[u8; 64]>::try_from(bytes).ok())
+            .map(|bytes| Signature::from_bytes(&bytes))
+            .ok_or(AuthError::InvalidSignature)?;
+        if !verify(&public_key, &self.signing_bytes(), &signature) {
+            return Err(invalid("signature does not match the issuer"));
+        }
+        Ok(())
+    }
+}
+
+/// Whether `entry` redeems an invitation: it is signed by a key that is not in
+/// the auth settings of `settings`, and writes to `invites`.
+pub(crate) fn is_redemption(entry: &Entry, settings: &Map) -> bool {
+    let SigKey::Direct(key_name) = &entry.sig.key else {
+        return false;
+    };
+    let known = matches!(
+        settings.get("auth"),
+        Some(Value::Map(auth)) if auth.get(key_name).is_some()
+    );
+    !key_name.is_empty() && !known && settings_changes(entry).get(INVITES).is_some()
+}
+
+/// Validates an entry for which [`is_redemption`] holds.
+///
+/// Returns the key the entry adds, whose public key checks the entry's
+/// signature, if the entry is a valid redemption.
+pub(crate) fn redeemed_key(entry: &Entry, settings: &Map) -> Option<ResolvedAuth> {
+    let SigKey::Direct(key_name) = &entry.sig.key else {
+        return None;
+    };
+
+    // The entry may only record the invitation and add the signing key
+    if entry.subtrees() != [SETTINGS] {
+        return None;
+    }
+    let changes = settings_changes(entry);
+    if changes.as_hashmap().len() != 2 {
+        return None;
+    }
+    let (record_id, invite) = match changes.get(INVITES) {
+        Some(Value::Map(records)) if records.as_hashmap().len() == 1 => {
+            let (id, record) = records.as_hashmap().iter().next()?;
+            match record {
+                Value::Text(token) => (id.clone(), Invitation::from_token(token).ok()?),
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+    let key = match changes.get("auth") {
+        Some(Value::Map(auth)) if auth.as_hashmap().len() == 1 => {
+            AuthSettings::from_map(auth.clone())
+                .get_key(key_name)?
+                .ok()?
+        }
+        _ => return None,
+    };
+
+    if record_id != invite.id
+        || invite.tree != entry.root()
+        || key.permissions != invite.permission
+        || key.status != KeyStatus::Active
+    {
+        return None;
+    }
+    let now_ms = entry.timestamp().map(|hlc| hlc.wall_ms);
+    invite.check(settings, now_ms).ok()?;
+
+    Some(ResolvedAuth {
+        public_key: parse_public_key(&key.pubkey).ok()?,
+        effective_permission: key.permissions,
+        key_status: key.status,
+    })
+}
+
+/// The changes `entry` makes to `_settings`, empty if it makes none.
+fn settings_changes(entry: &Entry) -> Map {
+    entry
+        .data(SETTINGS)
+        .ok()
+        .and_then(|data| serde_json::from_str::<Map>(data).ok())
+        .unwrap_or_default()
+}
@@ -6,7 +6,9 @@
 
 pub mod crypto;
 pub mod errors;
+pub mod invite;
 pub mod permission;
+pub(crate) mod seal;
 pub mod settings;
 pub mod types;
 pub mod validation;
@@ -14,6 +16,7 @@ pub mod validation;
 // Re-export main types for easier access
 pub use crypto::*;
 pub use errors::AuthError;
+pub use invite::Invitation;
 pub use permission::*;
 pub use settings::*;
 pub use types::*;
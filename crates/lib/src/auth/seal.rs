@@ -0,0 +1,304 @@
+//! Values encrypted to keys of a tree
+//!
+//! [`Dict::set_encrypted`](crate::subtree::Dict::set_encrypted) seals a value
+//! to a set of keys from the tree's auth settings, and
+//! [`Dict::get_encrypted`](crate::subtree::Dict::get_encrypted) opens it with
+//! the private key of one of them. The sealed value is stored as a single
+//! text value holding the JSON of a [`SealedValue`], so it merges like any
+//! other value: concurrent writes resolve to one of the sealed values, whole.
+//!
+//! # Construction
+//!
+//! Keys are Ed25519 keys, so each recipient's public key is converted to its
+//! X25519 form. A random content key encrypts the value with the
+//! XChaCha20-Poly1305 AEAD. The content key is wrapped for each recipient with
+//! the same AEAD, under a key derived from an X25519 exchange between a
+//! per-value ephemeral key and the recipient's key. Every encryption uses a
+//! random nonce and authenticates, as associated data, the tree and subtree the
+//! value is stored in, the ephemeral key and the list of recipients, so none of
+//! them can be changed or the value moved to another subtree unnoticed.
+//!
+//! Anyone who can write to the subtree can replace a sealed value, and anyone
+//! who can read it sees the names of its recipients. A recipient removed from
+//! the tree keeps access to the values sealed to it until they are written again.
+
+use super::errors::AuthError;
+use base64ct::{Base64, Encoding};
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use curve25519_dalek::montgomery::MontgomeryPoint;
+use ed25519_dalek::{SigningKey, VerifyingKey};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+/// Version of the sealed value format
+const VERSION: u32 = 2;
+
+/// BLAKE3 key derivation context for the keys wrapping the content key
+const WRAP_CONTEXT: &str = "eidetica 2025 sealed value key wrapping";
+
+/// A value encrypted to one or more keys.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct SealedValue {
+    /// Format version, also marking the JSON as a sealed value
+    sealed: u32,
+    /// Ephemeral X25519 public key
+    epk: String,
+    /// XChaCha20-Poly1305 nonce of the value
+    nonce: String,
+    /// The encrypted value and its authentication tag
+    ciphertext: String,
+    /// The content key, wrapped for each recipient
+    recipients: Vec<Recipient>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Recipient {
+    /// Name of the key in the tree's auth settings
+    key: String,
+    /// The key's public key, in `ed25519:` format
+    pubkey: String,
+    /// XChaCha20-Poly1305 nonce of the wrapped key
+    nonce: String,
+    /// The content key, encrypted with a key derived from the exchange with `epk`
+    wrapped: String,
+}
+
+impl SealedValue {
+    /// Encrypts `plaintext` to `recipients`, given by name and public key.
+    ///
+    /// `context` identifies where the value is stored; opening it requires the same context.
+    pub(crate) fn seal(
+        plaintext: &[u8],
+        context: &[u8],
+        recipients: &[(String, VerifyingKey)],
+    ) -> Self {
+        let mut rng = rand::rngs::OsRng;
+        let mut content_key = Zeroizing::new([0u8; 32]);
+        rng.fill_bytes(content_key.as_mut());
+        let mut ephemeral = Zeroizing::new([0u8; 32]);
+        rng.fill_bytes(ephemeral.as_mut());
+        let epk = MontgomeryPoint::mul_base_clamped(*ephemeral);
+
+        let listed: Vec<(String, String)> = recipients
+            .iter()
+            .map(|(name, public_key)| (name.clone(), super::crypto::format_public_key(public_key)))
+            .collect();
+        let aad = associated_data(context, &epk, &listed);
+
+        let recipients = recipients
+            .iter()
+            .zip(listed)
+            .map(|((_, public_key), (key, pubkey))| {
+                let recipient = public_key.to_montgomery();
+                let shared = Zeroizing::new(recipient.mul_clamped(*ephemeral));
+                let nonce = XChaCha20Poly1305::generate_nonce(&mut rng);
+                let wrapped = wrap_cipher(&shared, &epk, &recipient)
+                    .encrypt(&nonce, payload(content_key.as_ref(), &aad))
+                    .expect("encrypting in memory cannot fail");
+                Recipient {
+                    key,
+                    pubkey,
+                    nonce: Base64::encode_string(&nonce),
+                    wrapped: Base64::encode_string(&wrapped),
+                }
+            })
+            .collect();
+
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut rng);
+        let ciphertext = XChaCha20Poly1305::new(content_key.as_ref().into())
+            .encrypt(&nonce, payload(plaintext, &aad))
+            .expect("encrypting in memory cannot fail");
+
+        Self {
+            sealed: VERSION,
+            epk: Base64::encode_string(epk.as_bytes()),
+            nonce: Base64::encode_string(&nonce),
+            ciphertext: Base64::encode_string(&ciphertext),
+            recipients,
+        }
+    }
+
+    /// Parses a sealed value from its JSON, as stored.
+    pub(crate) fn parse(json: &str) -> Option<Self> {
+        serde_json::from_str::<Self>(json)
+            .ok()
+            .filter(|sealed| sealed.sealed == VERSION)
+    }
+
+    /// The JSON form of the sealed value, as stored.
+    pub(crate) fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("SealedValue serializes to JSON")
+    }
+
+    /// Decrypts the value with the private key of one of its recipients.
+    pub(crate) fn open(
+        &self,
+        context: &[u8],
+        key_name: &str,
+        signing_key: &SigningKey,
+    ) -> Result<Vec<u8>, AuthError> {
+        let pubkey = super::crypto::format_public_key(&signing_key.verifying_key());
+        let recipient = self
+            .recipients
+            .iter()
+            .find(|recipient| recipient.pubkey == pubkey)
+            .ok_or_else(|| AuthError::NotARecipient {
+                key_name: key_name.to_string(),
+            })?;
+
+        let corrupt = || AuthError::DecryptionFailed {
+            reason: "the sealed value is corrupt".to_string(),
+        };
+        let unauthenticated = |_| AuthError::DecryptionFailed {
+            reason: "the sealed value does not authenticate".to_string(),
+        };
+        let decode = |field: &str| Base64::decode_vec(field).map_err(|_| corrupt());
+        let nonce = |field: &str| -> Result<XNonce, AuthError> {
+            let bytes: [u8; 24] = decode(field)?.try_into().map_err(|_| corrupt())?;
+            Ok(bytes.into())
+        };
+        let epk = MontgomeryPoint(decode(&self.epk)?.try_into().map_err(|_| corrupt())?);
+        let listed: Vec<(String, String)> = self
+            .recipients
+            .iter()
+            .map(|recipient| (recipient.key.clone(), recipient.pubkey.clone()))
+            .collect();
+        let aad = associated_data(context, &epk, &listed);
+
+        let own = signing_key.verifying_key().to_montgomery();
+        let shared = Zeroizing::new(epk.mul_clamped(signing_key.to_scalar_bytes()));
+        let unwrapped = Zeroizing::new(
+            wrap_cipher(&shared, &epk, &own)
+                .decrypt(
+                    &nonce(&recipient.nonce)?,
+                    payload(&decode(&recipient.wrapped)?, &aad),
+                )
+                .map_err(unauthenticated)?,
+        );
+        let content_key: Zeroizing<[u8; 32]> =
+            Zeroizing::new(unwrapped.as_slice().try_into().map_err(|_| corrupt())?);
+
+        XChaCha20Poly1305::new(content_key.as_ref().into())
+            .decrypt(
+                &nonce(&self.nonce)?,
+                payload(&decode(&self.ciphertext)?, &aad),
+            )
+            .map_err(unauthenticated)
+    }
+}
+
+/// The data authenticated alongside the value and every wrapped key: the
+/// context, the ephemeral key and the recipients' names and public keys, each
+/// prefixed with its length.
+fn associated_data(
+    context: &[u8],
+    epk: &MontgomeryPoint,
+    recipients: &[(String, String)],
+) -> Vec<u8> {
+    let mut aad = Vec::new();
+    let mut push = |field: &[u8]| {
+        aad.extend_from_slice(&(field.len() as u64).to_le_bytes());
+        aad.extend_from_slice(field);
+    };
+    push(&VERSION.to_le_bytes());
+    push(context);
+    push(epk.as_bytes());
+    for (key, pubkey) in recipients {
+        push(key.as_bytes());
+        push(pubkey.as_bytes());
+    }
+    aad
+}
+
+fn payload<'a>(msg: &'a [u8], aad: &'a [u8]) -> Payload<'a, 'a> {
+    Payload { msg, aad }
+}
+
+/// The cipher wrapping the content key for one recipient.
+fn wrap_cipher(
+    shared: &MontgomeryPoint,
+    epk: &MontgomeryPoint,
+    recipient: &MontgomeryPoint,
+) -> XChaCha20Poly1305 {
+    let mut material = Zeroizing::new(Vec::with_capacity(96));
+    material.extend_from_slice(shared.as_bytes());
+    material.extend_from_slice(epk.as_bytes());
+    material.extend_from_slice(recipient.as_bytes());
+    let key = Zeroizing::new(blake3::derive_key(WRAP_CONTEXT, &material));
+    XChaCha20Poly1305::new(key.as_ref().into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::crypto::generate_keypair;
+
+    #[test]
+    fn test_seal_and_open() {
+        let (alice, alice_public) = generate_keypair();
+        let (bob, bob_public) = generate_keypair();
+        let (eve, _) = generate_keypair();
+        let sealed = SealedValue::seal(
+            b"secret",
+            b"context",
+            &[("alice".into(), alice_public), ("bob".into(), bob_public)],
+        );
+
+        let parsed = SealedValue::parse(&sealed.to_json()).unwrap();
+        assert_eq!(parsed, sealed);
+        assert_eq!(sealed.open(b"context", "alice", &alice).unwrap(), b"secret");
+        assert_eq!(sealed.open(b"context", "bob", &bob).unwrap(), b"secret");
+        assert!(matches!(
+            sealed.open(b"context", "eve", &eve),
+            Err(AuthError::NotARecipient { .. })
+        ));
+        assert!(matches!(
+            sealed.open(b"elsewhere", "alice", &alice),
+            Err(AuthError::DecryptionFailed { .. })
+        ));
+        assert!(SealedValue::parse(r#"{"text":"plain"}"#).is_none());
+    }
+
+    #[test]
+    fn test_tampering_is_detected() {
+        let (alice, alice_public) = generate_keypair();
+        let mut sealed = SealedValue::seal(b"secret", b"", &[("alice".into(), alice_public)]);
+        let mut ciphertext = Base64::decode_vec(&sealed.ciphertext).unwrap();
+        ciphertext[0] ^= 1;
+        sealed.ciphertext = Base64::encode_string(&ciphertext);
+        assert!(sealed.open(b"", "alice", &alice).is_err());
+    }
+
+    #[test]
+    fn test_header_tampering_is_detected() {
+        let (alice, alice_public) = generate_keypair();
+        let (_, bob_public) = generate_keypair();
+        let sealed = SealedValue::seal(
+            b"secret",
+            b"",
+            &[("alice".into(), alice_public), ("bob".into(), bob_public)],
+        );
+
+        // Dropping or renaming a recipient
+        let mut dropped = sealed.clone();
+        dropped.recipients.pop();
+        assert!(dropped.open(b"", "alice", &alice).is_err());
+        let mut renamed = sealed.clone();
+        renamed.recipients[1].key = "mallory".into();
+        assert!(renamed.open(b"", "alice", &alice).is_err());
+
+        // Replacing the ephemeral key
+        let mut replaced = sealed.clone();
+        let (_, other) = generate_keypair();
+        replaced.epk = Base64::encode_string(other.to_montgomery().as_bytes());
+        assert!(replaced.open(b"", "alice", &alice).is_err());
+
+        // Swapping the wrapped keys of two recipients
+        let mut swapped = sealed;
+        let bob_wrapped = swapped.recipients[1].wrapped.clone();
+        swapped.recipients[0].wrapped = bob_wrapped;
+        assert!(swapped.open(b"", "alice", &alice).is_err());
+    }
+}
@@ -61,6 +61,7 @@ impl From<KeyStatus> for String {
         match status {
             KeyStatus::Active => "active".to_string(),
             KeyStatus::Revoked => "revoked".to_string(),
+            KeyStatus::Rotated => "rotated".to_string(),
         }
     }
 }
@@ -72,6 +73,7 @@ impl TryFrom<String> for KeyStatus {
         match s.as_str() {
             "active" => Ok(KeyStatus::Active),
             "revoked" => Ok(KeyStatus::Revoked),
+            "rotated" => Ok(KeyStatus::Rotated),
             _ => Err(AuthError::InvalidKeyStatus { value: s }),
         }
     }
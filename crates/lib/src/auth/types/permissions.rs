@@ -89,6 +89,35 @@ pub enum KeyStatus {
     /// Key is revoked - cannot create new entries, but historical entries are preserved
     /// Content of revoked entries is preserved during merges, but cannot be parents of new entries
     Revoked,
+    /// Key was replaced by a successor with `Tree::rotate_key`
+    /// Treated like a revoked key: entries it signed before the rotation remain valid
+    Rotated,
+}
+
+/// How key revocations affect an entry, as reported by `Tree::revocation_status`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RevocationStatus {
+    /// Not signed by a revoked key and not built on entries that were
+    Unaffected,
+    /// Signed by a key that was revoked later; the entry is an ancestor of the revocation
+    Historical { key_name: String },
+    /// Signed by a revoked key by a writer that had not seen the revocation yet
+    /// Content is preserved during merges, as for historical entries
+    Concurrent { key_name: String },
+    /// Signed by a key that was already revoked in the entry's own history
+    Revoked { key_name: String },
+    /// Built on top of a `Revoked` entry, so its content depends on data from a revoked key
+    Tainted { source: crate::entry::ID },
+}
+
+impl RevocationStatus {
+    /// Whether the entry remains valid under this status
+    pub fn is_valid(&self) -> bool {
+        !matches!(
+            self,
+            RevocationStatus::Revoked { .. } | RevocationStatus::Tainted { .. }
+        )
+    }
 }
 
 /// Permission bounds for delegated trees
@@ -104,7 +104,7 @@ impl DelegationResolver {
                 })?;
 
                 let tips_valid =
-                    self.validate_tip_ancestry(tips, &current_tips, &current_backend)?;
+                    self.validate_tip_ancestry(&root_id, tips, &current_tips, &current_backend)?;
                 if !tips_valid {
                     return Err(AuthError::InvalidDelegationTips {
                         tree_id: root_id.to_string(),
@@ -195,11 +195,13 @@ impl DelegationResolver {
     /// using the backend's DAG traversal capabilities.
     ///
     /// # Arguments
+    /// * `tree` - Root ID of the delegated tree the tips must belong to
     /// * `claimed_tips` - Tips claimed by the entry being validated
     /// * `current_tips` - Current tips from the backend
     /// * `backend` - Backend to use for DAG traversal
     fn validate_tip_ancestry(
         &self,
+        tree: &ID,
         claimed_tips: &[ID],
         current_tips: &[ID],
         backend: &Arc<dyn Database>,
@@ -235,12 +237,15 @@ impl DelegationResolver {
                 is_valid = true;
             } else {
                 // TODO: For now, we'll use a simplified check and accept the claimed tips
-                // if they exist in the tree at all. A more sophisticated implementation
-                // would verify the actual ancestry relationships using the backend's
-                // DAG traversal methods.
-
-                // Try to get the entry to verify it exists in the tree
-                if backend.get(claimed_tip).is_ok() {
+                // if they exist in the delegated tree at all. A more sophisticated
+                // implementation would verify the actual ancestry relationships using
+                // the backend's DAG traversal methods.
+
+                // Tips from other trees would let a delegation path borrow their state
+                if backend
+                    .get(claimed_tip)
+                    .is_ok_and(|entry| entry.in_tree(tree))
+                {
                     is_valid = true;
                 }
             }
@@ -5,20 +5,31 @@
 
 use crate::Result;
 use crate::auth::crypto::verify_entry_signature;
+use crate::auth::invite::{is_redemption, redeemed_key};
 use crate::auth::types::{KeyStatus, Operation, ResolvedAuth, SigKey};
 use crate::backend::Database;
+use crate::basedb::quota::quota_violation;
 use crate::crdt::Map;
 use crate::crdt::map::Value;
-use crate::entry::Entry;
+use crate::entry::{Entry, ID};
 use std::collections::HashMap;
 use std::sync::Arc;
 
+use super::freeze::frozen_write;
+use super::hash::hash_mismatch;
 use super::resolver::KeyResolver;
 
 /// Authentication validator for validating entries and resolving auth information
+///
+/// A validator can memoize resolved keys by pinning it to a set of `_settings`
+/// tips with `pin_settings_tips`. Each `Tree` keeps such a validator for its
+/// commits, so repeated commits against unchanged settings skip key resolution;
+/// pinning different tips discards the cached keys.
 pub struct AuthValidator {
     /// Cache for resolved authentication data to improve performance
     auth_cache: HashMap<String, ResolvedAuth>,
+    /// Sorted `_settings` tips that `auth_cache` was resolved against, if pinned
+    settings_tips: Option<Vec<ID>>,
     /// Key resolver for handling key resolution
     pub(crate) resolver: KeyResolver,
 }
@@ -28,22 +39,73 @@ impl AuthValidator {
     pub fn new() -> Self {
         Self {
             auth_cache: HashMap::new(),
+            settings_tips: None,
             resolver: KeyResolver::new(),
         }
     }
 
+    /// Pin the validator to the settings state identified by the given `_settings` tips.
+    ///
+    /// While pinned, direct keys resolved by this validator are cached. Callers
+    /// must only pass settings computed from exactly these tips. Pinning a
+    /// different set of tips clears the cache.
+    pub(crate) fn pin_settings_tips(&mut self, tips: &[ID]) {
+        let mut tips = tips.to_vec();
+        tips.sort();
+        if self.settings_tips.as_ref() != Some(&tips) {
+            self.auth_cache.clear();
+            self.settings_tips = Some(tips);
+        }
+    }
+
+    /// Number of resolved keys currently cached
+    #[cfg(test)]
+    pub(crate) fn cached_keys(&self) -> usize {
+        self.auth_cache.len()
+    }
+
     /// Validate authentication information for an entry
     ///
     /// # Arguments
     /// * `entry` - The entry to validate
     /// * `settings_state` - Current state of the _settings subtree for key lookup
     /// * `backend` - Backend for loading delegated trees (optional for direct keys)
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(entry = %entry.id(), key = ?entry.sig.key),
+            ret
+        )
+    )]
     pub fn validate_entry(
         &mut self,
         entry: &Entry,
         settings_state: &Map,
         backend: Option<&Arc<dyn Database>>,
     ) -> Result<bool> {
+        // Writes to frozen subtrees are rejected whoever signed them
+        if frozen_write(entry, settings_state).is_some() {
+            return Ok(false);
+        }
+
+        // So are entries hashed with another algorithm than the tree's
+        if hash_mismatch(entry, settings_state).is_some() {
+            return Ok(false);
+        }
+
+        // And entries breaking the tree's quotas
+        if quota_violation(
+            entry,
+            settings_state,
+            backend.map(|backend| backend.as_ref()),
+        )?
+        .is_some()
+        {
+            return Ok(false);
+        }
+
         // Handle unsigned entries (for backward compatibility)
         // An entry is considered unsigned if it has an empty Direct key name and no signature
         if let SigKey::Direct(key_name) = &entry.sig.key
@@ -56,11 +118,9 @@ impl AuthValidator {
 
         // If the settings state has no 'auth' section or an empty 'auth' map, allow unsigned entries.
         match settings_state.get("auth") {
-            Some(Value::Map(auth_map)) => {
-                // If 'auth' section exists and is a map, check if it's empty
-                if auth_map.as_hashmap().is_empty() {
-                    return Ok(true);
-                }
+            Some(Value::Map(auth_map)) if auth_map.as_hashmap().is_empty() => {
+                // If 'auth' section exists and is an empty map, no keys are configured
+                return Ok(true);
             }
             None => {
                 // If 'auth' section does not exist at all, it means no keys are configured
@@ -72,6 +132,17 @@ impl AuthValidator {
             }
         }
 
+        // Entries redeeming an invitation are signed by the key they add, so the
+        // invitation authorizes them instead of the settings
+        if is_redemption(entry, settings_state) {
+            return match redeemed_key(entry, settings_state) {
+                Some(resolved_auth) => {
+                    Ok(verify_entry_signature(entry, &resolved_auth.public_key)?)
+                }
+                None => Ok(false),
+            };
+        }
+
         // For all other entries, proceed with normal authentication validation
         // Resolve the authentication information
         let resolved_auth = self.resolve_sig_key(&entry.sig.key, settings_state, backend)?;
@@ -97,6 +168,19 @@ impl AuthValidator {
         settings: &Map,
         backend: Option<&Arc<dyn Database>>,
     ) -> Result<ResolvedAuth> {
+        // Direct keys depend only on this tree's settings, so they can be cached while
+        // the settings are pinned. Delegated keys also depend on other trees' state.
+        if self.settings_tips.is_some()
+            && let SigKey::Direct(key_name) = sig_key
+        {
+            if let Some(resolved) = self.auth_cache.get(key_name) {
+                return Ok(resolved.clone());
+            }
+            let resolved = self.resolver.resolve_sig_key(sig_key, settings, backend)?;
+            self.auth_cache.insert(key_name.clone(), resolved.clone());
+            return Ok(resolved);
+        }
+
         // Delegate to the resolver
         self.resolver.resolve_sig_key(sig_key, settings, backend)
     }
@@ -113,6 +197,7 @@ impl AuthValidator {
     /// Clear the authentication cache
     pub fn clear_cache(&mut self) {
         self.auth_cache.clear();
+        self.settings_tips = None;
         self.resolver.clear_cache();
     }
 }
@@ -0,0 +1,59 @@
+//! Frozen subtrees
+//!
+//! An admin freezes a subtree by adding a record for it under `frozen` in the
+//! tree's `_settings`. Entries validated against settings that contain the
+//! record must not write to the subtree, nor change or remove the record, so
+//! the subtree stays as it was for good. Entries are validated against the
+//! settings as of their parents, so writes made concurrently with the freeze,
+//! on a branch that had not seen it yet, are still accepted.
+
+use crate::constants::SETTINGS;
+use crate::crdt::Map;
+use crate::crdt::map::Value;
+use crate::entry::Entry;
+
+/// Settings key holding a record for each frozen subtree, by subtree name.
+pub(crate) const FROZEN: &str = "frozen";
+
+/// The names of the subtrees frozen in `settings`, sorted.
+pub(crate) fn frozen_subtrees(settings: &Map) -> Vec<String> {
+    let mut frozen: Vec<String> = match settings.get(FROZEN) {
+        Some(Value::Map(records)) => records.keys().cloned().collect(),
+        _ => Vec::new(),
+    };
+    frozen.sort();
+    frozen
+}
+
+/// Returns the first subtree frozen in `settings` that `entry` writes to or
+/// whose freeze record it changes.
+pub(crate) fn frozen_write(entry: &Entry, settings: &Map) -> Option<String> {
+    let frozen = frozen_subtrees(settings);
+    if frozen.is_empty() {
+        return None;
+    }
+
+    for subtree in entry.subtrees() {
+        let data = entry.data(&subtree).map(String::as_str).unwrap_or_default();
+        if data.is_empty() || data == "{}" {
+            continue;
+        }
+        if frozen.contains(&subtree) {
+            return Some(subtree);
+        }
+    }
+
+    // A change to the `frozen` section may not touch an existing record
+    let changes = entry
+        .data(SETTINGS)
+        .ok()
+        .and_then(|data| serde_json::from_str::<Map>(data).ok())
+        .and_then(|changes| changes.as_hashmap().get(FROZEN).cloned());
+    match changes {
+        Some(Value::Map(records)) => frozen
+            .into_iter()
+            .find(|subtree| records.as_hashmap().contains_key(subtree)),
+        Some(_) => frozen.into_iter().next(),
+        None => None,
+    }
+}
@@ -0,0 +1,47 @@
+//! Entry hash algorithm
+//!
+//! A tree records the algorithm its entry IDs are hashed with under
+//! `hash_algorithm` in its `_settings` when it is created. Entries validated
+//! against settings that contain the record must be hashed with that
+//! algorithm, and may not change or remove the record, so every replica
+//! derives the same IDs for the tree's entries. Trees created without the
+//! record use SHA-256.
+
+use crate::constants::{HASH_ALGORITHM, SETTINGS};
+use crate::crdt::Map;
+use crate::crdt::map::Value;
+use crate::entry::Entry;
+use crate::entry::id::HashAlgorithm;
+
+/// The name of the hash algorithm recorded in `settings`.
+///
+/// This is the name as written, which may not be a known algorithm if the
+/// settings were not written by this library.
+pub(crate) fn recorded_hash_algorithm(settings: &Map) -> String {
+    match settings.get(HASH_ALGORITHM) {
+        Some(Value::Text(name)) => name.clone(),
+        Some(_) => String::new(),
+        None => HashAlgorithm::default().name().to_string(),
+    }
+}
+
+/// Returns the name of the algorithm recorded in `settings` if `entry` is
+/// not hashed with it or changes the record.
+pub(crate) fn hash_mismatch(entry: &Entry, settings: &Map) -> Option<String> {
+    let recorded = recorded_hash_algorithm(settings);
+    if HashAlgorithm::from_name(&recorded) != Some(entry.hash_algorithm()) {
+        return Some(recorded);
+    }
+
+    // A change to `_settings` may only repeat the recorded algorithm
+    let change = entry
+        .data(SETTINGS)
+        .ok()
+        .and_then(|data| serde_json::from_str::<Map>(data).ok())
+        .and_then(|changes| changes.as_hashmap().get(HASH_ALGORITHM).cloned());
+    match change {
+        Some(Value::Text(name)) if name == recorded => None,
+        Some(_) => Some(recorded),
+        None => None,
+    }
+}
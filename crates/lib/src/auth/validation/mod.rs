@@ -1,10 +1,13 @@
 //! Authentication validation for Eidetica
 //!
 //! This module provides validation logic for authentication information,
-//! including key resolution, permission checking, and signature verification.
+//! including key resolution, permission checking, signature verification,
+//! frozen subtrees and entry hash algorithms.
 
 pub mod delegation;
 pub mod entry;
+pub(crate) mod freeze;
+pub(crate) mod hash;
 pub mod permissions;
 pub mod resolver;
 
@@ -6,7 +6,7 @@ use crate::auth::types::{
     AuthKey, DelegationStep, KeyStatus, Operation, Permission, SigInfo, SigKey,
 };
 use crate::crdt::Map;
-use crate::entry::Entry;
+use crate::entry::{Entry, ID};
 
 fn create_test_settings_with_key(key_name: &str, auth_key: &AuthKey) -> Map {
     let mut settings = Map::new();
@@ -673,3 +673,52 @@ fn test_delegation_depth_limit() {
     assert!(error.to_string().contains("Maximum delegation depth"));
     assert!(error.to_string().contains("exceeded"));
 }
+
+#[test]
+fn test_pinned_validator_caches_direct_keys() {
+    let mut validator = AuthValidator::new();
+    let (_, verifying_key) = generate_keypair();
+    let sig_key = SigKey::Direct("KEY_1".to_string());
+
+    let active = AuthKey {
+        pubkey: format_public_key(&verifying_key),
+        permissions: Permission::Write(10),
+        status: KeyStatus::Active,
+    };
+    let revoked = AuthKey {
+        status: KeyStatus::Revoked,
+        ..active.clone()
+    };
+    let active_settings = create_test_settings_with_key("KEY_1", &active);
+    let revoked_settings = create_test_settings_with_key("KEY_1", &revoked);
+
+    // Unpinned validators never cache
+    validator
+        .resolve_sig_key(&sig_key, &active_settings, None)
+        .unwrap();
+    assert_eq!(validator.cached_keys(), 0);
+
+    let tips_a = vec![ID::from("tip_a"), ID::from("tip_b")];
+    validator.pin_settings_tips(&tips_a);
+    let resolved = validator
+        .resolve_sig_key(&sig_key, &active_settings, None)
+        .unwrap();
+    assert_eq!(resolved.key_status, KeyStatus::Active);
+    assert_eq!(validator.cached_keys(), 1);
+
+    // The same tips in another order are the same settings state, so the cache is kept
+    validator.pin_settings_tips(&[ID::from("tip_b"), ID::from("tip_a")]);
+    assert_eq!(validator.cached_keys(), 1);
+    let resolved = validator
+        .resolve_sig_key(&sig_key, &revoked_settings, None)
+        .unwrap();
+    assert_eq!(resolved.key_status, KeyStatus::Active);
+
+    // New tips invalidate the cache
+    validator.pin_settings_tips(&[ID::from("tip_c")]);
+    assert_eq!(validator.cached_keys(), 0);
+    let resolved = validator
+        .resolve_sig_key(&sig_key, &revoked_settings, None)
+        .unwrap();
+    assert_eq!(resolved.key_status, KeyStatus::Revoked);
+}
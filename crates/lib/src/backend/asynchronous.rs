@@ -0,0 +1,131 @@
+//! Async access to a `Database`
+//!
+//! `Database` implementations are synchronous and may block on locks or disk I/O.
+//! `DatabaseAsync` exposes the commonly used operations as futures that run the
+//! blocking call on Tokio's blocking thread pool, so async services can await
+//! them without stalling a worker thread.
+//!
+//! Requires the "async" feature and a running Tokio runtime.
+
+use crate::Result;
+use crate::backend::{Database, VerificationStatus};
+use crate::entry::{Entry, ID};
+use std::future::Future;
+use std::sync::Arc;
+
+/// Runs a blocking closure on Tokio's blocking thread pool and awaits its result.
+///
+/// Panics inside the closure are propagated to the caller. If the runtime is
+/// shutting down and the task is cancelled, an `Interrupted` I/O error is returned.
+pub(crate) async fn run_blocking<T, F>(f: F) -> Result<T>
+where
+    F: FnOnce() -> Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    match tokio::task::spawn_blocking(f).await {
+        Ok(result) => result,
+        Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+        Err(_) => Err(std::io::Error::new(
+            std::io::ErrorKind::Interrupted,
+            "blocking database task was cancelled",
+        )
+        .into()),
+    }
+}
+
+/// Async counterpart of the `Database` trait.
+///
+/// Implemented for `Arc<dyn Database>`, which is how `BaseDB` and `Tree` share
+/// their backend, so `tree.backend().get_async(&id).await` works directly.
+/// Each method clones its arguments and runs the corresponding `Database`
+/// method via `tokio::task::spawn_blocking`.
+pub trait DatabaseAsync {
+    /// Async version of [`Database::get`].
+    fn get_async(&self, id: &ID) -> impl Future<Output = Result<Entry>> + Send;
+
+    /// Async version of [`Database::put`].
+    fn put_async(
+        &self,
+        verification_status: VerificationStatus,
+        entry: Entry,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// Async version of [`Database::get_tips`].
+    fn get_tips_async(&self, tree: &ID) -> impl Future<Output = Result<Vec<ID>>> + Send;
+
+    /// Async version of [`Database::get_subtree_tips`].
+    fn get_subtree_tips_async(
+        &self,
+        tree: &ID,
+        subtree: &str,
+    ) -> impl Future<Output = Result<Vec<ID>>> + Send;
+
+    /// Async version of [`Database::all_roots`].
+    fn all_roots_async(&self) -> impl Future<Output = Result<Vec<ID>>> + Send;
+
+    /// Async version of [`Database::get_tree`].
+    fn get_tree_async(&self, tree: &ID) -> impl Future<Output = Result<Vec<Entry>>> + Send;
+
+    /// Async version of [`Database::get_subtree`].
+    fn get_subtree_async(
+        &self,
+        tree: &ID,
+        subtree: &str,
+    ) -> impl Future<Output = Result<Vec<Entry>>> + Send;
+}
+
+impl DatabaseAsync for Arc<dyn Database> {
+    fn get_async(&self, id: &ID) -> impl Future<Output = Result<Entry>> + Send {
+        let backend = Arc::clone(self);
+        let id = id.clone();
+        run_blocking(move || backend.get(&id))
+    }
+
+    fn put_async(
+        &self,
+        verification_status: VerificationStatus,
+        entry: Entry,
+    ) -> impl Future<Output = Result<()>> + Send {
+        let backend = Arc::clone(self);
+        run_blocking(move || backend.put(verification_status, entry))
+    }
+
+    fn get_tips_async(&self, tree: &ID) -> impl Future<Output = Result<Vec<ID>>> + Send {
+        let backend = Arc::clone(self);
+        let tree = tree.clone();
+        run_blocking(move || backend.get_tips(&tree))
+    }
+
+    fn get_subtree_tips_async(
+        &self,
+        tree: &ID,
+        subtree: &str,
+    ) -> impl Future<Output = Result<Vec<ID>>> + Send {
+        let backend = Arc::clone(self);
+        let tree = tree.clone();
+        let subtree = subtree.to_string();
+        run_blocking(move || backend.get_subtree_tips(&tree, &subtree))
+    }
+
+    fn all_roots_async(&self) -> impl Future<Output = Result<Vec<ID>>> + Send {
+        let backend = Arc::clone(self);
+        run_blocking(move || backend.all_roots())
+    }
+
+    fn get_tree_async(&self, tree: &ID) -> impl Future<Output = Result<Vec<Entry>>> + Send {
+        let backend = Arc::clone(self);
+        let tree = tree.clone();
+        run_blocking(move || backend.get_tree(&tree))
+    }
+
+    fn get_subtree_async(
+        &self,
+        tree: &ID,
+        subtree: &str,
+    ) -> impl Future<Output = Result<Vec<Entry>>> + Send {
+        let backend = Arc::clone(self);
+        let tree = tree.clone();
+        let subtree = subtree.to_string();
+        run_blocking(move || backend.get_subtree(&tree, &subtree))
+    }
+}
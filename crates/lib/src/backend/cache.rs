@@ -0,0 +1,165 @@
+//! Cache of decoded entries
+//!
+//! Backends that keep entries encoded, such as `Sqlite` and `Archive`, decode an
+//! entry every time it is read. Traversals read the same entries over and over:
+//! finding the subtree tips at a set of main tree entries, computing heights,
+//! finding paths between entries and folding CRDT states all walk the same part
+//! of the DAG, often several times per operation.
+//!
+//! An [`EntryCache`] keeps the most recently read entries in memory, decoded, so
+//! these walks only decode each entry once while it stays cached. Entries are
+//! content-addressed and never change once stored, so a cached entry is never
+//! stale; backends only drop entries from the cache when they are removed.
+
+use crate::entry::{Entry, ID};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+/// Number of entries kept by default
+pub const DEFAULT_ENTRY_CACHE_CAPACITY: usize = 1024;
+
+/// Statistics about a backend's entry cache, see
+/// [`Database::cache_stats`](super::Database::cache_stats).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Maximum number of entries kept
+    pub capacity: usize,
+    /// Number of entries currently cached
+    pub entries: usize,
+    /// Reads answered from the cache
+    pub hits: u64,
+    /// Reads that had to decode the entry
+    pub misses: u64,
+    /// Entries dropped to make room for others
+    pub evictions: u64,
+}
+
+/// Decoded entries by ID, least recently used first.
+///
+/// A capacity of zero disables the cache.
+pub(crate) struct EntryCache {
+    inner: Mutex<Inner>,
+}
+
+struct Inner {
+    capacity: usize,
+    /// Cached entries with the tick they were last used at
+    entries: HashMap<ID, (Entry, u64)>,
+    /// Keys of `entries` by the tick they were last used at, least recent first
+    order: BTreeMap<u64, ID>,
+    tick: u64,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+}
+
+impl Default for EntryCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_ENTRY_CACHE_CAPACITY)
+    }
+}
+
+impl std::fmt::Debug for EntryCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("EntryCache").field(&self.stats()).finish()
+    }
+}
+
+impl EntryCache {
+    /// Creates a cache holding up to `capacity` entries.
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                capacity,
+                entries: HashMap::new(),
+                order: BTreeMap::new(),
+                tick: 0,
+                hits: 0,
+                misses: 0,
+                evictions: 0,
+            }),
+        }
+    }
+
+    /// Gets a copy of the cached entry with the given ID, if any.
+    pub(crate) fn get(&self, id: &ID) -> Option<Entry> {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.capacity == 0 {
+            return None;
+        }
+        let tick = inner.next_tick();
+        let Inner {
+            entries,
+            order,
+            hits,
+            misses,
+            ..
+        } = &mut *inner;
+        match entries.get_mut(id) {
+            Some((entry, used)) => {
+                *hits += 1;
+                order.remove(used);
+                order.insert(tick, id.clone());
+                *used = tick;
+                Some(entry.clone())
+            }
+            None => {
+                *misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Caches a decoded entry, evicting the least recently used entries.
+    pub(crate) fn insert(&self, id: &ID, entry: &Entry) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.capacity == 0 {
+            return;
+        }
+        let tick = inner.next_tick();
+        if let Some((_, used)) = inner.entries.insert(id.clone(), (entry.clone(), tick)) {
+            inner.order.remove(&used);
+        }
+        inner.order.insert(tick, id.clone());
+        inner.evict();
+    }
+
+    /// Drops the given entries, for instance after they were removed from storage.
+    #[cfg_attr(not(feature = "sqlite"), allow(dead_code))]
+    pub(crate) fn remove<'a>(&self, ids: impl IntoIterator<Item = &'a ID>) {
+        let mut inner = self.inner.lock().unwrap();
+        for id in ids {
+            if let Some((_, used)) = inner.entries.remove(id) {
+                inner.order.remove(&used);
+            }
+        }
+    }
+
+    pub(crate) fn stats(&self) -> CacheStats {
+        let inner = self.inner.lock().unwrap();
+        CacheStats {
+            capacity: inner.capacity,
+            entries: inner.entries.len(),
+            hits: inner.hits,
+            misses: inner.misses,
+            evictions: inner.evictions,
+        }
+    }
+}
+
+impl Inner {
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    fn evict(&mut self) {
+        while self.entries.len() > self.capacity {
+            let Some((_, id)) = self.order.pop_first() else {
+                break;
+            };
+            self.entries.remove(&id);
+            self.evictions += 1;
+        }
+    }
+}
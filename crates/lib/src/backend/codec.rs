@@ -0,0 +1,229 @@
+//! Serialization formats for stored data
+//!
+//! Entries and database snapshots are serialized with an [`EntryCodec`] when they
+//! cross the backend boundary. JSON is the default and is always available; CBOR
+//! is a more compact and faster binary format available behind the `cbor` feature.
+//!
+//! The codec only affects how data is stored. Entry IDs are always computed from
+//! the canonical JSON form of an entry, so the same entry has the same ID whichever
+//! codec stored it.
+//!
+//! Binary output written with [`EntryCodec::encode_framed`] starts with a magic
+//! header naming the codec, so readers can detect the format automatically. JSON
+//! output is left unframed, which keeps JSON files readable and compatible with
+//! earlier versions.
+
+use crate::Result;
+use crate::backend::errors::DatabaseError;
+use crate::entry::Entry;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+/// Magic bytes at the start of framed binary data.
+const MAGIC: &[u8; 4] = b"EDB\0";
+
+/// Header tag identifying JSON. JSON data is never framed, but other formats
+/// that record their codec, such as archives, use this tag.
+const JSON_TAG: u8 = 0;
+
+/// Header tag identifying CBOR, defined even without the `cbor` feature so that
+/// such data can be recognized and rejected with a helpful error.
+const CBOR_TAG: u8 = 1;
+
+/// A serialization format for entries and stored database state.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum EntryCodec {
+    /// JSON, the default and most portable format
+    #[default]
+    Json,
+    /// CBOR (RFC 8949), a compact binary format. Requires the "cbor" feature.
+    #[cfg(feature = "cbor")]
+    Cbor,
+}
+
+impl EntryCodec {
+    /// The name of the codec.
+    pub fn name(&self) -> &'static str {
+        match self {
+            EntryCodec::Json => "JSON",
+            #[cfg(feature = "cbor")]
+            EntryCodec::Cbor => "CBOR",
+        }
+    }
+
+    /// Returns true for binary codecs, whose output is framed with a header.
+    pub fn is_binary(&self) -> bool {
+        !matches!(self, EntryCodec::Json)
+    }
+
+    /// Serializes a value with this codec.
+    pub fn encode<T: Serialize + ?Sized>(&self, value: &T) -> Result<Vec<u8>> {
+        match self {
+            EntryCodec::Json => serde_json::to_vec(value)
+                .map_err(|e| DatabaseError::SerializationFailed { source: e }.into()),
+            #[cfg(feature = "cbor")]
+            EntryCodec::Cbor => {
+                let mut out = Vec::new();
+                ciborium::into_writer(value, &mut out).map_err(|e| self.failed(e))?;
+                Ok(out)
+            }
+        }
+    }
+
+    /// Deserializes a value written by [`encode`](Self::encode) with this codec.
+    pub fn decode<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T> {
+        match self {
+            EntryCodec::Json => serde_json::from_slice(bytes)
+                .map_err(|e| DatabaseError::DeserializationFailed { source: e }.into()),
+            #[cfg(feature = "cbor")]
+            EntryCodec::Cbor => ciborium::from_reader(bytes).map_err(|e| self.failed(e)),
+        }
+    }
+
+    /// Serializes an entry with this codec.
+    pub fn encode_entry(&self, entry: &Entry) -> Result<Vec<u8>> {
+        self.encode(entry)
+    }
+
+    /// Deserializes an entry written by [`encode_entry`](Self::encode_entry).
+    pub fn decode_entry(&self, bytes: &[u8]) -> Result<Entry> {
+        self.decode(bytes)
+    }
+
+    /// Serializes a value, prefixed with a header identifying the codec for binary codecs.
+    ///
+    /// JSON is written pretty-printed and without a header.
+    pub fn encode_framed<T: Serialize + ?Sized>(&self, value: &T) -> Result<Vec<u8>> {
+        match self {
+            EntryCodec::Json => serde_json::to_vec_pretty(value)
+                .map_err(|e| DatabaseError::SerializationFailed { source: e }.into()),
+            #[cfg(feature = "cbor")]
+            EntryCodec::Cbor => {
+                let mut out = MAGIC.to_vec();
+                out.push(CBOR_TAG);
+                out.extend(self.encode(value)?);
+                Ok(out)
+            }
+        }
+    }
+
+    /// Detects the codec of data written by [`encode_framed`](Self::encode_framed)
+    /// and returns it along with the encoded payload.
+    ///
+    /// # Errors
+    /// Returns `DatabaseError::InvalidEncoding` if the header names an unknown
+    /// codec or one whose feature is not enabled.
+    pub fn detect(bytes: &[u8]) -> Result<(EntryCodec, &[u8])> {
+        let Some(rest) = bytes.strip_prefix(MAGIC.as_slice()) else {
+            return Ok((EntryCodec::Json, bytes));
+        };
+        match rest.split_first() {
+            Some((&JSON_TAG, _)) => Err(DatabaseError::InvalidEncoding {
+                reason: "JSON data is never framed".to_string(),
+            }
+            .into()),
+            Some((&tag, payload)) => Ok((Self::from_tag(tag)?, payload)),
+            None => Err(DatabaseError::InvalidEncoding {
+                reason: "truncated codec header".to_string(),
+            }
+            .into()),
+        }
+    }
+
+    /// Deserializes data written by [`encode_framed`](Self::encode_framed) with any codec.
+    pub fn decode_framed<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+        let (codec, payload) = Self::detect(bytes)?;
+        codec.decode(payload)
+    }
+
+    /// The tag identifying this codec in binary headers.
+    #[cfg(feature = "archive")]
+    pub(crate) fn tag(&self) -> u8 {
+        match self {
+            EntryCodec::Json => JSON_TAG,
+            #[cfg(feature = "cbor")]
+            EntryCodec::Cbor => CBOR_TAG,
+        }
+    }
+
+    /// The codec identified by a tag from a binary header.
+    ///
+    /// # Errors
+    /// Returns `DatabaseError::InvalidEncoding` if the tag names an unknown
+    /// codec or one whose feature is not enabled.
+    pub(crate) fn from_tag(tag: u8) -> Result<Self> {
+        match tag {
+            JSON_TAG => Ok(EntryCodec::Json),
+            #[cfg(feature = "cbor")]
+            CBOR_TAG => Ok(EntryCodec::Cbor),
+            #[cfg(not(feature = "cbor"))]
+            CBOR_TAG => Err(DatabaseError::InvalidEncoding {
+                reason: "data is CBOR encoded but the \"cbor\" feature is not enabled".to_string(),
+            }
+            .into()),
+            tag => Err(DatabaseError::InvalidEncoding {
+                reason: format!("unknown codec tag {tag}"),
+            }
+            .into()),
+        }
+    }
+
+    #[cfg(feature = "cbor")]
+    fn failed(&self, err: impl std::fmt::Display) -> crate::Error {
+        DatabaseError::CodecFailed {
+            codec: self.name().to_string(),
+            reason: err.to_string(),
+        }
+        .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_is_unframed() {
+        let framed = EntryCodec::Json.encode_framed(&vec![1, 2, 3]).unwrap();
+        assert!(!framed.starts_with(MAGIC));
+        let (codec, _) = EntryCodec::detect(&framed).unwrap();
+        assert_eq!(codec, EntryCodec::Json);
+        let decoded: Vec<u32> = EntryCodec::decode_framed(&framed).unwrap();
+        assert_eq!(decoded, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_unknown_tag_is_rejected() {
+        let mut data = MAGIC.to_vec();
+        data.push(0x7F);
+        assert!(EntryCodec::detect(&data).is_err());
+        assert!(EntryCodec::detect(MAGIC).is_err());
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn test_cbor_round_trip() {
+        let entry = Entry::builder("root")
+            .set_subtree_data("data", "{\"key\":\"value\"}")
+            .build();
+        let encoded = EntryCodec::Cbor.encode_entry(&entry).unwrap();
+        let decoded = EntryCodec::Cbor.decode_entry(&encoded).unwrap();
+        assert_eq!(decoded, entry);
+        assert_eq!(decoded.id(), entry.id());
+
+        let framed = EntryCodec::Cbor.encode_framed(&entry).unwrap();
+        assert!(framed.starts_with(MAGIC));
+        let detected: Entry = EntryCodec::decode_framed(&framed).unwrap();
+        assert_eq!(detected, entry);
+    }
+
+    #[cfg(not(feature = "cbor"))]
+    #[test]
+    fn test_cbor_requires_feature() {
+        let mut data = MAGIC.to_vec();
+        data.push(CBOR_TAG);
+        let err = EntryCodec::detect(&data).unwrap_err();
+        assert!(err.to_string().contains("cbor"));
+    }
+}
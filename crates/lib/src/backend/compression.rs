@@ -0,0 +1,409 @@
+//! Transparent compression of stored entry data
+//!
+//! Backends can be configured with a [`Compression`] to shrink the data they
+//! store. Large subtree payloads, such as Y-CRDT updates and JSON maps, often
+//! compress well. Compression only affects how bytes are stored: entries and
+//! their IDs are unchanged.
+//!
+//! Compressed data starts with a header naming the algorithm and the size of the
+//! original data, so readers detect it automatically and data stored without
+//! compression remains readable. Data is only stored compressed when that makes
+//! it smaller.
+//!
+//! Compression uses the LZ4 block format of the `lz4_flex` crate, or `zstd`
+//! with a built-in dictionary, and is available behind the `compression`
+//! feature.
+
+use crate::Result;
+use crate::backend::errors::DatabaseError;
+use std::borrow::Cow;
+#[cfg(feature = "compression")]
+use std::io::Read;
+#[cfg(feature = "compression")]
+use std::sync::LazyLock;
+#[cfg(feature = "compression")]
+use zstd::dict::{DecoderDictionary, EncoderDictionary};
+
+/// Magic bytes at the start of compressed data.
+const MAGIC: &[u8; 4] = b"EDBZ";
+
+/// Length of the header: magic, algorithm tag and original length.
+const HEADER_LEN: usize = MAGIC.len() + 1 + 4;
+
+/// Header tags identifying LZ4 and zstd with [`ENTRY_DICTIONARY`], defined even
+/// without the `compression` feature so that such data can be recognized and
+/// rejected with a helpful error.
+const LZ4_TAG: u8 = 1;
+const ZSTD_DICT_TAG: u8 = 2;
+
+/// Largest ratio of original to compressed size the LZ4 block format can reach.
+#[cfg(feature = "compression")]
+const MAX_RATIO: usize = 255;
+
+/// Data shorter than this is never compressed.
+#[cfg(feature = "compression")]
+const MIN_COMPRESS_LEN: usize = 64;
+
+/// A zstd dictionary trained on the JSON of entries and sync messages.
+///
+/// Compressing with it lets even a single small entry refer back to the
+/// structure all entries share. It was trained by the ignored
+/// `train_entry_dictionary` test of the sync protocol; data compressed with it
+/// can only be read with it, so it must not change.
+#[cfg(feature = "compression")]
+const ENTRY_DICTIONARY: &[u8] = include_bytes!("entries.dict");
+
+/// zstd compression level used with [`ENTRY_DICTIONARY`].
+#[cfg(feature = "compression")]
+const ZSTD_LEVEL: i32 = 3;
+
+/// A compression algorithm for stored data.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Compression {
+    /// Store data as is
+    #[default]
+    None,
+    /// The LZ4 block format, fast with a moderate ratio. Requires the "compression" feature.
+    #[cfg(feature = "compression")]
+    Lz4,
+    /// zstd with a built-in dictionary trained on the JSON of entries.
+    /// Compresses small payloads, such as single entries in sync messages,
+    /// much better than `Lz4`, at some cost in speed. Requires the
+    /// "compression" feature.
+    #[cfg(feature = "compression")]
+    ZstdDict,
+}
+
+impl Compression {
+    /// The name of the algorithm.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Compression::None => "none",
+            #[cfg(feature = "compression")]
+            Compression::Lz4 => "LZ4",
+            #[cfg(feature = "compression")]
+            Compression::ZstdDict => "zstd-dict",
+        }
+    }
+
+    /// Looks up an algorithm by its [`name`](Self::name).
+    ///
+    /// Returns `None` for unknown names and algorithms whose feature is not enabled.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "none" => Some(Compression::None),
+            #[cfg(feature = "compression")]
+            "LZ4" => Some(Compression::Lz4),
+            #[cfg(feature = "compression")]
+            "zstd-dict" => Some(Compression::ZstdDict),
+            _ => None,
+        }
+    }
+
+    /// Compresses data, prefixed with a header, if that makes it smaller.
+    ///
+    /// Returns the data unchanged when compression is disabled or does not help,
+    /// so the result can always be read back with [`decompress`](Self::decompress).
+    pub fn compress<'a>(&self, data: &'a [u8]) -> Cow<'a, [u8]> {
+        match self {
+            Compression::None => Cow::Borrowed(data),
+            #[cfg(feature = "compression")]
+            Compression::Lz4 => frame(LZ4_TAG, data, |data| Some(lz4_flex::block::compress(data))),
+            #[cfg(feature = "compression")]
+            Compression::ZstdDict => frame(ZSTD_DICT_TAG, data, |data| {
+                zstd::bulk::Compressor::with_prepared_dictionary(&ENCODER_DICTIONARY)
+                    .and_then(|mut compressor| compressor.compress(data))
+                    .ok()
+            }),
+        }
+    }
+
+    /// Detects compressed data written by [`compress`](Self::compress) and
+    /// restores the original bytes. Uncompressed data is returned unchanged.
+    ///
+    /// The header declares the size of the original data; data declaring more
+    /// than `max_len` bytes is rejected before any memory is set aside for it,
+    /// so untrusted input cannot make decompression allocate unbounded memory.
+    ///
+    /// # Errors
+    /// Returns `DatabaseError::InvalidEncoding` if the header names an unknown
+    /// algorithm or one whose feature is not enabled, or declares more than
+    /// `max_len` bytes, and `DatabaseError::CodecFailed` if the compressed data
+    /// is corrupt.
+    pub fn decompress(data: &[u8], max_len: usize) -> Result<Cow<'_, [u8]>> {
+        let Some(rest) = data.strip_prefix(MAGIC.as_slice()) else {
+            return Ok(Cow::Borrowed(data));
+        };
+        if rest.len() < HEADER_LEN - MAGIC.len() {
+            return Err(DatabaseError::InvalidEncoding {
+                reason: "truncated compression header".to_string(),
+            }
+            .into());
+        }
+        let (tag, rest) = (rest[0], &rest[1..]);
+        let len = u32::from_le_bytes(rest[..4].try_into().unwrap()) as usize;
+        let payload = &rest[4..];
+        if len > max_len {
+            return Err(DatabaseError::InvalidEncoding {
+                reason: format!(
+                    "compressed data declares {len} bytes, more than the limit of {max_len}"
+                ),
+            }
+            .into());
+        }
+
+        match tag {
+            #[cfg(feature = "compression")]
+            LZ4_TAG => Compression::Lz4.decompress_lz4(payload, len),
+            #[cfg(feature = "compression")]
+            ZSTD_DICT_TAG => Compression::ZstdDict.decompress_zstd(payload, len),
+            #[cfg(not(feature = "compression"))]
+            LZ4_TAG | ZSTD_DICT_TAG => {
+                let _ = (len, payload);
+                Err(DatabaseError::InvalidEncoding {
+                    reason: "data is compressed but the \"compression\" feature is not enabled"
+                        .to_string(),
+                }
+                .into())
+            }
+            tag => Err(DatabaseError::InvalidEncoding {
+                reason: format!("unknown compression tag {tag}"),
+            }
+            .into()),
+        }
+    }
+
+    /// Returns true if `data` starts with a compression header.
+    pub fn is_compressed(data: &[u8]) -> bool {
+        data.starts_with(MAGIC)
+    }
+
+    /// Decompresses an LZ4 block that must restore exactly `len` bytes.
+    #[cfg(feature = "compression")]
+    fn decompress_lz4<'a>(&self, block: &[u8], len: usize) -> Result<Cow<'a, [u8]>> {
+        // Refuse lengths the block cannot expand to before allocating them
+        if len > block.len().saturating_mul(MAX_RATIO) {
+            return Err(self.failed(format!(
+                "{} bytes cannot expand to the declared {len} bytes",
+                block.len()
+            )));
+        }
+        let out =
+            lz4_flex::block::decompress(block, len).map_err(|e| self.failed(e.to_string()))?;
+        self.exactly(out, len)
+    }
+
+    /// Decompresses a zstd frame that must restore exactly `len` bytes.
+    ///
+    /// The frame is streamed, so memory grows with the data actually
+    /// decompressed rather than with a size declared by the frame.
+    #[cfg(feature = "compression")]
+    fn decompress_zstd<'a>(&self, frame: &[u8], len: usize) -> Result<Cow<'a, [u8]>> {
+        let decoder =
+            zstd::stream::read::Decoder::with_prepared_dictionary(frame, &DECODER_DICTIONARY)
+                .map_err(|e| self.failed(e.to_string()))?;
+        let mut out = Vec::new();
+        decoder
+            .take(len as u64 + 1)
+            .read_to_end(&mut out)
+            .map_err(|e| self.failed(e.to_string()))?;
+        self.exactly(out, len)
+    }
+
+    /// Checks that decompression restored exactly `len` bytes.
+    #[cfg(feature = "compression")]
+    fn exactly<'a>(&self, out: Vec<u8>, len: usize) -> Result<Cow<'a, [u8]>> {
+        if out.len() != len {
+            return Err(self.failed(format!("expected {len} bytes, decompressed {}", out.len())));
+        }
+        Ok(Cow::Owned(out))
+    }
+
+    #[cfg(feature = "compression")]
+    fn failed(&self, reason: String) -> crate::Error {
+        DatabaseError::CodecFailed {
+            codec: self.name().to_string(),
+            reason,
+        }
+        .into()
+    }
+}
+
+/// [`ENTRY_DICTIONARY`] prepared for compression, shared by all calls.
+#[cfg(feature = "compression")]
+static ENCODER_DICTIONARY: LazyLock<EncoderDictionary<'static>> =
+    LazyLock::new(|| EncoderDictionary::copy(ENTRY_DICTIONARY, ZSTD_LEVEL));
+
+/// [`ENTRY_DICTIONARY`] prepared for decompression, shared by all calls.
+#[cfg(feature = "compression")]
+static DECODER_DICTIONARY: LazyLock<DecoderDictionary<'static>> =
+    LazyLock::new(|| DecoderDictionary::copy(ENTRY_DICTIONARY));
+
+/// Compresses `data` with `compress` behind a header, or returns it as is if it
+/// is too short, cannot be compressed or does not get smaller.
+#[cfg(feature = "compression")]
+fn frame<'a>(
+    tag: u8,
+    data: &'a [u8],
+    compress: impl FnOnce(&[u8]) -> Option<Vec<u8>>,
+) -> Cow<'a, [u8]> {
+    let Ok(len) = u32::try_from(data.len()) else {
+        return Cow::Borrowed(data);
+    };
+    if data.len() < MIN_COMPRESS_LEN {
+        return Cow::Borrowed(data);
+    }
+
+    let Some(compressed) = compress(data) else {
+        return Cow::Borrowed(data);
+    };
+    let mut out = Vec::with_capacity(HEADER_LEN + compressed.len());
+    out.extend_from_slice(MAGIC);
+    out.push(tag);
+    out.extend_from_slice(&len.to_le_bytes());
+    out.extend_from_slice(&compressed);
+
+    if out.len() < data.len() {
+        Cow::Owned(out)
+    } else {
+        Cow::Borrowed(data)
+    }
+}
+
+#[cfg(all(test, feature = "compression"))]
+mod tests {
+    use super::*;
+
+    fn roundtrip(data: &[u8]) {
+        for compression in [Compression::Lz4, Compression::ZstdDict] {
+            let compressed = compression.compress(data);
+            assert_eq!(
+                Compression::decompress(&compressed, usize::MAX).unwrap(),
+                data
+            );
+        }
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        roundtrip(b"");
+        roundtrip(b"short");
+        roundtrip(&[7u8; 10_000]);
+        let text: Vec<u8> = (0..2000)
+            .flat_map(|i| format!("{{\"key_{i}\":\"value_{}\"}}", i % 17).into_bytes())
+            .collect();
+        roundtrip(&text);
+        let noise: Vec<u8> = (0..5000u32)
+            .map(|i| (i.wrapping_mul(2654435761) >> 13) as u8)
+            .collect();
+        roundtrip(&noise);
+    }
+
+    #[test]
+    fn test_dictionary_helps_small_entries() {
+        let entry = br#"{"sig":{"key":"laptop","sig":"c2ln"},"subtrees":[{"data":"{\"children\":{\"title\":{\"Text\":\"Buy milk\"}}}","name":"todos","parents":[]}],"tree":{"metadata":null,"parents":["abc"],"root":"abc"}}"#;
+        let plain = Compression::Lz4.compress(entry);
+        let primed = Compression::ZstdDict.compress(entry);
+        assert!(Compression::is_compressed(&primed));
+        assert!(primed.len() < plain.len());
+        assert_eq!(
+            Compression::decompress(&primed, usize::MAX).unwrap(),
+            entry.as_slice()
+        );
+    }
+
+    #[test]
+    fn test_from_name() {
+        for compression in [Compression::None, Compression::Lz4, Compression::ZstdDict] {
+            assert_eq!(
+                Compression::from_name(compression.name()),
+                Some(compression)
+            );
+        }
+        assert_eq!(Compression::from_name("zstd"), None);
+    }
+
+    #[test]
+    fn test_incompressible_data_is_stored_as_is() {
+        let noise: Vec<u8> = (0..256u32)
+            .map(|i| (i.wrapping_mul(2654435761) >> 13) as u8)
+            .collect();
+        let stored = Compression::Lz4.compress(&noise);
+        assert!(!Compression::is_compressed(&stored));
+    }
+
+    /// Frames a raw block behind a header declaring `len` bytes.
+    fn framed(tag: u8, len: u32, block: &[u8]) -> Vec<u8> {
+        [MAGIC.as_slice(), &[tag], &len.to_le_bytes(), block].concat()
+    }
+
+    #[test]
+    fn test_malformed_headers_are_rejected() {
+        // Too short to hold the tag and length
+        assert!(Compression::decompress(b"EDBZ\x01\x05", usize::MAX).is_err());
+        // An unknown algorithm
+        assert!(Compression::decompress(&framed(9, 1, &[0x10, b'a']), usize::MAX).is_err());
+        // Data without the magic bytes is not compressed
+        assert_eq!(
+            Compression::decompress(b"EDB", usize::MAX).unwrap(),
+            b"EDB".as_slice()
+        );
+    }
+
+    #[test]
+    fn test_overlong_lengths_are_rejected() {
+        let data = vec![b'a'; 1000];
+        for compression in [Compression::Lz4, Compression::ZstdDict] {
+            let compressed = compression.compress(&data);
+            assert!(Compression::decompress(&compressed, data.len()).is_ok());
+            assert!(Compression::decompress(&compressed, data.len() - 1).is_err());
+
+            // A frame expanding past the size in its header
+            let mut short = compressed.into_owned();
+            short[MAGIC.len() + 1..HEADER_LEN].copy_from_slice(&100u32.to_le_bytes());
+            assert!(Compression::decompress(&short, usize::MAX).is_err());
+        }
+
+        // A header claiming 4 GiB is refused before anything is allocated
+        let bomb = framed(LZ4_TAG, u32::MAX, &[0x10, b'a']);
+        assert!(Compression::decompress(&bomb, 64 * 1024 * 1024).is_err());
+        // Without a limit the block itself does not match the declared size
+        assert!(Compression::decompress(&bomb, usize::MAX).is_err());
+
+        // A block expanding past its declared size
+        let block = [0x1F, b'a', 1, 0, 0xFF, 0xFF, 0x00];
+        assert!(Compression::decompress(&framed(LZ4_TAG, 100, &block), usize::MAX).is_err());
+    }
+
+    #[test]
+    fn test_bad_offsets_are_rejected() {
+        for offset in [0u8, 2] {
+            // One literal, then a match `offset` bytes back and a final literal
+            let block = [0x10, b'a', offset, 0, 0x10, b'b'];
+            let data = framed(LZ4_TAG, 6, &block);
+            assert!(
+                Compression::decompress(&data, usize::MAX).is_err(),
+                "offset {offset} was accepted"
+            );
+        }
+        let valid = [0x10, b'a', 1, 0, 0x10, b'b'];
+        assert_eq!(
+            Compression::decompress(&framed(LZ4_TAG, 6, &valid), usize::MAX).unwrap(),
+            b"aaaaab".as_slice()
+        );
+    }
+
+    #[test]
+    fn test_corrupt_data_is_rejected() {
+        let data = vec![b'a'; 1000];
+        for compression in [Compression::Lz4, Compression::ZstdDict] {
+            let mut compressed = compression.compress(&data).into_owned();
+            assert!(compressed.len() < data.len());
+            let last = compressed.len() - 1;
+            compressed[last] ^= 0xFF;
+            compressed.truncate(compressed.len() - 2);
+            assert!(Compression::decompress(&compressed, usize::MAX).is_err());
+        }
+    }
+}
@@ -0,0 +1,137 @@
+//! Content-addressed storage of subtree payloads
+//!
+//! Identical payloads are common across entries and trees: the same document
+//! imported into several trees, settings every device writes the same way, a
+//! row set back to a value it had before. A [`ContentStore`] keeps one copy of
+//! each payload, keyed by its SHA-256 hash, and counts the stored entries that
+//! refer to it. Backends move an entry's payloads into the store when the entry
+//! is stored, keep the entry without them, and put them back when it is read.
+//! Removing an entry, for instance by pruning, releases its references; a
+//! payload is freed with its last reference.
+//!
+//! Payloads shorter than [`MIN_SHARED_PAYLOAD`] bytes stay in their entry, as
+//! sharing them would save little or nothing over keeping their hash.
+
+use crate::entry::{Entry, ID, RawData};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::collections::hash_map;
+
+/// Payloads at least this long are shared through the store.
+pub(crate) const MIN_SHARED_PAYLOAD: usize = 64;
+
+/// SHA-256 hash of a payload.
+type PayloadHash = [u8; 32];
+
+/// A shared payload and the number of stored entries referring to it.
+#[derive(Debug)]
+struct Payload {
+    data: RawData,
+    refs: usize,
+}
+
+/// What a backend's shared payloads hold and save.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PayloadStats {
+    /// Number of distinct shared payloads
+    pub payloads: usize,
+    /// Number of references to them from stored entries
+    pub references: usize,
+    /// Total size of the distinct shared payloads, in bytes
+    pub bytes: u64,
+    /// Size of the duplicates that are not stored, in bytes
+    pub bytes_saved: u64,
+}
+
+/// Shared subtree payloads of the entries stored in a backend.
+#[derive(Debug, Default)]
+pub(crate) struct ContentStore {
+    payloads: HashMap<PayloadHash, Payload>,
+    /// Hashes of each entry's shared payloads, by position among its subtrees.
+    /// Entries without shared payloads are not listed.
+    entries: HashMap<ID, Vec<Option<PayloadHash>>>,
+}
+
+impl ContentStore {
+    /// Moves the payloads of `entry`, stored as `id`, that are long enough to
+    /// share into the store, and returns the entry without them.
+    ///
+    /// Storing an entry that is already stored adds no references.
+    pub(crate) fn insert(&mut self, id: &ID, mut entry: Entry) -> Entry {
+        if let Some(hashes) = self.entries.get(id) {
+            for (payload, hash) in entry.payloads_mut().zip(hashes) {
+                if hash.is_some() {
+                    *payload = RawData::new();
+                }
+            }
+            return entry;
+        }
+
+        let hashes: Vec<Option<PayloadHash>> = entry
+            .payloads_mut()
+            .map(|payload| {
+                if payload.len() < MIN_SHARED_PAYLOAD {
+                    return None;
+                }
+                let hash: PayloadHash = Sha256::digest(payload.as_bytes()).into();
+                let data = std::mem::take(payload);
+                self.payloads
+                    .entry(hash)
+                    .or_insert(Payload { data, refs: 0 })
+                    .refs += 1;
+                Some(hash)
+            })
+            .collect();
+        if hashes.iter().any(Option::is_some) {
+            self.entries.insert(id.clone(), hashes);
+        }
+        entry
+    }
+
+    /// Returns a copy of `entry`, stored as `id`, with its shared payloads put back.
+    pub(crate) fn restored(&self, id: &ID, entry: &Entry) -> Entry {
+        let mut entry = entry.clone();
+        if let Some(hashes) = self.entries.get(id) {
+            for (payload, hash) in entry.payloads_mut().zip(hashes) {
+                if let Some(shared) = hash.and_then(|hash| self.payloads.get(&hash)) {
+                    payload.clone_from(&shared.data);
+                }
+            }
+        }
+        entry
+    }
+
+    /// Releases the references of the entry stored as `id`, returning the
+    /// number of payloads freed because nothing refers to them any more.
+    pub(crate) fn remove(&mut self, id: &ID) -> usize {
+        let Some(hashes) = self.entries.remove(id) else {
+            return 0;
+        };
+        let mut freed = 0;
+        for hash in hashes.into_iter().flatten() {
+            if let hash_map::Entry::Occupied(mut payload) = self.payloads.entry(hash) {
+                payload.get_mut().refs -= 1;
+                if payload.get().refs == 0 {
+                    payload.remove();
+                    freed += 1;
+                }
+            }
+        }
+        freed
+    }
+
+    /// Counts the shared payloads, their references and the bytes they save.
+    pub(crate) fn stats(&self) -> PayloadStats {
+        let mut stats = PayloadStats {
+            payloads: self.payloads.len(),
+            ..PayloadStats::default()
+        };
+        for payload in self.payloads.values() {
+            let size = payload.data.len() as u64;
+            stats.references += payload.refs;
+            stats.bytes += size;
+            stats.bytes_saved += size * (payload.refs as u64 - 1);
+        }
+        stats
+    }
+}
@@ -0,0 +1,328 @@
+//! On-disk layout of archive files
+//!
+//! All integers are little-endian. An archive consists of:
+//!
+//! * A fixed 64-byte header: magic, format version, the codec of the encoded
+//!   sections, the entry count and the location of the other sections.
+//! * The data section: for every entry, its ID followed by the encoded entry.
+//! * The entry index: one fixed-size record per entry, sorted by ID, giving the
+//!   location of the ID and the encoded entry and the verification status.
+//!   Lookups binary search this index directly in the mapped file.
+//! * The tree index: for every tree and every subtree of it, the entries in
+//!   topological order with their heights, and the tips. Entries are referenced
+//!   by their position in the entry index.
+//!
+//! The tree index is encoded with the archive's codec and decoded when the
+//! archive is opened. Entries are only decoded when they are read.
+
+use crate::Result;
+use crate::backend::codec::EntryCodec;
+use crate::backend::errors::DatabaseError;
+use crate::backend::{Database, VerificationStatus};
+use crate::entry::{Entry, ID};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::Write;
+
+/// Magic bytes at the start of every archive.
+pub(crate) const MAGIC: &[u8; 8] = b"EDBARCH\0";
+
+/// Version of the archive format written by `write`.
+pub(crate) const FORMAT_VERSION: u16 = 1;
+
+pub(crate) const HEADER_LEN: usize = 64;
+
+/// Size of a record in the entry index.
+pub(crate) const RECORD_LEN: usize = 32;
+
+/// The fixed header at the start of an archive.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Header {
+    pub(crate) codec: EntryCodec,
+    pub(crate) entry_count: u64,
+    pub(crate) index_offset: u64,
+    pub(crate) trees_offset: u64,
+    pub(crate) trees_len: u64,
+}
+
+impl Header {
+    fn to_bytes(self) -> [u8; HEADER_LEN] {
+        let mut bytes = [0u8; HEADER_LEN];
+        bytes[0..8].copy_from_slice(MAGIC);
+        bytes[8..10].copy_from_slice(&FORMAT_VERSION.to_le_bytes());
+        bytes[10] = self.codec.tag();
+        bytes[16..24].copy_from_slice(&self.entry_count.to_le_bytes());
+        bytes[24..32].copy_from_slice(&self.index_offset.to_le_bytes());
+        bytes[32..40].copy_from_slice(&self.trees_offset.to_le_bytes());
+        bytes[40..48].copy_from_slice(&self.trees_len.to_le_bytes());
+        bytes
+    }
+
+    /// Parses and validates the header of an archive.
+    pub(crate) fn parse(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < HEADER_LEN || !bytes.starts_with(MAGIC) {
+            return Err(invalid("not an Eidetica archive"));
+        }
+        let version = u16::from_le_bytes([bytes[8], bytes[9]]);
+        if version != FORMAT_VERSION {
+            return Err(invalid(format!("unsupported archive version {version}")));
+        }
+        let header = Self {
+            codec: EntryCodec::from_tag(bytes[10])?,
+            entry_count: read_u64(bytes, 16),
+            index_offset: read_u64(bytes, 24),
+            trees_offset: read_u64(bytes, 32),
+            trees_len: read_u64(bytes, 40),
+        };
+
+        let len = bytes.len() as u64;
+        let index_end = header
+            .entry_count
+            .checked_mul(RECORD_LEN as u64)
+            .and_then(|size| size.checked_add(header.index_offset));
+        let trees_end = header.trees_offset.checked_add(header.trees_len);
+        if !matches!(index_end, Some(end) if end <= len)
+            || !matches!(trees_end, Some(end) if end <= len)
+        {
+            return Err(invalid("archive is truncated"));
+        }
+        Ok(header)
+    }
+}
+
+/// A record of the entry index.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Record {
+    pub(crate) id_offset: u64,
+    pub(crate) id_len: u32,
+    pub(crate) entry_len: u32,
+    pub(crate) entry_offset: u64,
+    pub(crate) status: VerificationStatus,
+}
+
+impl Record {
+    fn to_bytes(self) -> [u8; RECORD_LEN] {
+        let mut bytes = [0u8; RECORD_LEN];
+        bytes[0..8].copy_from_slice(&self.id_offset.to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.id_len.to_le_bytes());
+        bytes[12..16].copy_from_slice(&self.entry_len.to_le_bytes());
+        bytes[16..24].copy_from_slice(&self.entry_offset.to_le_bytes());
+        bytes[24] = match self.status {
+            VerificationStatus::Verified => 0,
+            VerificationStatus::Failed => 1,
+        };
+        bytes
+    }
+
+    pub(crate) fn parse(bytes: &[u8]) -> Result<Self> {
+        let status = match bytes[24] {
+            0 => VerificationStatus::Verified,
+            1 => VerificationStatus::Failed,
+            other => return Err(invalid(format!("unknown verification status {other}"))),
+        };
+        Ok(Self {
+            id_offset: read_u64(bytes, 0),
+            id_len: read_u32(bytes, 8),
+            entry_len: read_u32(bytes, 12),
+            entry_offset: read_u64(bytes, 16),
+            status,
+        })
+    }
+}
+
+/// The entries of a tree or subtree.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct ContextIndex {
+    /// Positions in the entry index and heights, sorted by height then ID
+    pub(crate) entries: Vec<(u32, u32)>,
+    /// Positions in the entry index of the tips, sorted by ID
+    pub(crate) tips: Vec<u32>,
+}
+
+/// The index of a single tree.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct TreeIndex {
+    /// Position of the root entry in the entry index
+    pub(crate) root: u32,
+    pub(crate) entries: ContextIndex,
+    pub(crate) subtrees: BTreeMap<String, ContextIndex>,
+}
+
+/// The tree index section.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct TreesIndex {
+    pub(crate) trees: Vec<TreeIndex>,
+}
+
+/// Writes the contents of `source` as an archive.
+///
+/// Every entry of every tree is included, along with its verification status.
+/// Private keys and cached state are not.
+pub(crate) fn write(source: &dyn Database, codec: EntryCodec, out: &mut impl Write) -> Result<()> {
+    // Gather every entry, deduplicated and sorted by ID
+    let mut entries: BTreeMap<ID, Entry> = BTreeMap::new();
+    let mut trees: Vec<(ID, Vec<Entry>)> = Vec::new();
+    let mut roots = source.all_roots()?;
+    roots.sort();
+    for root in roots {
+        let tree_entries = source.get_tree(&root)?;
+        for entry in &tree_entries {
+            entries.entry(entry.id()).or_insert_with(|| entry.clone());
+        }
+        trees.push((root, tree_entries));
+    }
+    let positions: HashMap<&ID, u32> = entries
+        .keys()
+        .enumerate()
+        .map(|(position, id)| (id, position as u32))
+        .collect();
+
+    // Data section, recording where each entry ends up
+    let mut data = Vec::new();
+    let mut records = Vec::with_capacity(entries.len());
+    for (id, entry) in &entries {
+        let encoded = codec.encode_entry(entry)?;
+        let id_offset = (HEADER_LEN + data.len()) as u64;
+        data.extend_from_slice(id.as_str().as_bytes());
+        let entry_offset = (HEADER_LEN + data.len()) as u64;
+        data.extend_from_slice(&encoded);
+        records.push(Record {
+            id_offset,
+            id_len: len_u32(id.as_str().len())?,
+            entry_len: len_u32(encoded.len())?,
+            entry_offset,
+            status: source.get_verification_status(id)?,
+        });
+    }
+
+    let mut index = TreesIndex::default();
+    for (root, tree_entries) in &trees {
+        index
+            .trees
+            .push(index_tree(root, tree_entries, &positions)?);
+    }
+    let trees_bytes = codec.encode(&index)?;
+
+    let index_offset = (HEADER_LEN + data.len()) as u64;
+    let trees_offset = index_offset + (records.len() * RECORD_LEN) as u64;
+    let header = Header {
+        codec,
+        entry_count: records.len() as u64,
+        index_offset,
+        trees_offset,
+        trees_len: trees_bytes.len() as u64,
+    };
+
+    let io = |e| -> crate::Error { DatabaseError::FileIo { source: e }.into() };
+    out.write_all(&header.to_bytes()).map_err(io)?;
+    out.write_all(&data).map_err(io)?;
+    for record in records {
+        out.write_all(&record.to_bytes()).map_err(io)?;
+    }
+    out.write_all(&trees_bytes).map_err(io)?;
+    out.flush().map_err(io)
+}
+
+/// Builds the index of a tree from its topologically sorted entries.
+fn index_tree(root: &ID, entries: &[Entry], positions: &HashMap<&ID, u32>) -> Result<TreeIndex> {
+    let position = |id: &ID| -> Result<u32> {
+        positions.get(id).copied().ok_or_else(|| {
+            DatabaseError::StateInconsistency {
+                reason: format!("entry {id} is missing from the archive"),
+            }
+            .into()
+        })
+    };
+
+    let mut subtree_entries: BTreeMap<String, Vec<&Entry>> = BTreeMap::new();
+    for entry in entries {
+        for subtree in entry.subtrees() {
+            subtree_entries.entry(subtree).or_default().push(entry);
+        }
+    }
+
+    let tree_context = index_context(entries.iter(), |entry| entry.parents(), &position)?;
+    let mut subtrees = BTreeMap::new();
+    for (name, members) in subtree_entries {
+        let context = index_context(
+            members.into_iter(),
+            |entry| entry.subtree_parents(&name),
+            &position,
+        )?;
+        subtrees.insert(name, context);
+    }
+
+    Ok(TreeIndex {
+        root: position(root)?,
+        entries: tree_context,
+        subtrees,
+    })
+}
+
+/// Computes heights and tips for the entries of a tree or subtree.
+///
+/// The height of an entry is the length of the longest path to it from a root
+/// of the context, ignoring parents outside the context.
+fn index_context<'a>(
+    entries: impl Iterator<Item = &'a Entry>,
+    parents_of: impl Fn(&Entry) -> Result<Vec<ID>>,
+    position: &impl Fn(&ID) -> Result<u32>,
+) -> Result<ContextIndex> {
+    let entries: Vec<(ID, Vec<ID>)> = entries
+        .map(|entry| Ok((entry.id(), parents_of(entry)?)))
+        .collect::<Result<_>>()?;
+    let members: HashSet<&ID> = entries.iter().map(|(id, _)| id).collect();
+
+    // Entries are sorted topologically, so parents always come first
+    let mut heights: HashMap<&ID, u32> = HashMap::new();
+    let mut has_children: HashSet<&ID> = HashSet::new();
+    for (id, parents) in &entries {
+        let mut height = 0;
+        for parent in parents.iter().filter(|parent| members.contains(parent)) {
+            has_children.insert(parent);
+            let parent_height = heights.get(parent).copied().ok_or_else(|| {
+                DatabaseError::HeightCalculationCorruption {
+                    reason: format!("parent {parent} of {id} is not sorted before it"),
+                }
+            })?;
+            height = height.max(parent_height + 1);
+        }
+        heights.insert(id, height);
+    }
+
+    let mut sorted: Vec<(&ID, u32)> = heights.iter().map(|(id, h)| (*id, *h)).collect();
+    sorted.sort_by(|(a, a_height), (b, b_height)| a_height.cmp(b_height).then_with(|| a.cmp(b)));
+    let mut tips: Vec<&ID> = entries
+        .iter()
+        .map(|(id, _)| id)
+        .filter(|id| !has_children.contains(id))
+        .collect();
+    tips.sort();
+
+    Ok(ContextIndex {
+        entries: sorted
+            .into_iter()
+            .map(|(id, height)| Ok((position(id)?, height)))
+            .collect::<Result<_>>()?,
+        tips: tips.into_iter().map(position).collect::<Result<_>>()?,
+    })
+}
+
+fn len_u32(len: usize) -> Result<u32> {
+    u32::try_from(len).map_err(|_| invalid(format!("{len} bytes is too large for an archive")))
+}
+
+fn read_u64(bytes: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap())
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+}
+
+pub(crate) fn invalid(reason: impl Into<String>) -> crate::Error {
+    DatabaseError::InvalidEncoding {
+        reason: reason.into(),
+    }
+    .into()
+}
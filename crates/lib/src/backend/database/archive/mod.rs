@@ -0,0 +1,441 @@
+//! Read-only archive database backend
+//!
+//! This module provides `Archive`, a `Database` that serves entries directly
+//! from a single immutable file. Archives are created from any other backend
+//! with [`Archive::export`] and are meant for distributing snapshots of a
+//! dataset to many consumers, which can open them without loading or parsing
+//! the whole file.
+//!
+//! The file is memory-mapped. Entries are found by binary searching an index of
+//! fixed-size records, and are only decoded when read. Tips, heights and the
+//! topological order of every tree and subtree are computed on export and stored
+//! in the archive, so traversals do not have to rediscover them. See `format`
+//! for the layout. Recently read entries are kept decoded in an LRU cache, see
+//! [`Archive::with_entry_cache`].
+//!
+//! This module is only available when the "archive" feature is enabled.
+
+mod format;
+mod traversal;
+
+use crate::Result;
+use crate::backend::cache::EntryCache;
+use crate::backend::codec::EntryCodec;
+use crate::backend::errors::DatabaseError;
+use crate::backend::{CacheStats, Database, KeyStorage, VerificationStatus};
+use crate::entry::{Entry, ID};
+use ed25519_dalek::SigningKey;
+use format::{ContextIndex, Header, RECORD_LEN, Record, TreeIndex, TreesIndex};
+use memmap2::Mmap;
+use std::any::Any;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+/// A read-only database backend serving entries from an archive file.
+///
+/// Any attempt to store entries, change verification statuses or store private
+/// keys fails with `DatabaseError::ReadOnly`. Trees can be loaded and read as
+/// usual; computed CRDT states are cached in memory only.
+///
+/// The semantics of tips, heights and traversal match the
+/// [`InMemory`](super::InMemory) backend.
+///
+/// # Example
+/// ```
+/// # use eidetica::backend::database::{Archive, InMemory};
+/// # use eidetica::basedb::BaseDB;
+/// # let dir = tempfile::tempdir().unwrap();
+/// # let path = dir.path().join("snapshot.edba");
+/// let db = BaseDB::new(Box::new(InMemory::new()));
+/// db.add_private_key("key").unwrap();
+/// let tree = db.new_tree_default("key").unwrap();
+///
+/// Archive::export(db.backend().as_ref(), &path).unwrap();
+///
+/// let snapshot = BaseDB::new(Box::new(Archive::open(&path).unwrap()));
+/// let tree = snapshot.load_tree(tree.root_id()).unwrap();
+/// ```
+pub struct Archive {
+    path: PathBuf,
+    map: Mmap,
+    header: Header,
+    /// Tree indexes keyed by root ID
+    trees: HashMap<ID, TreeIndex>,
+    /// Computed CRDT states, keyed by entry ID and subtree
+    crdt_cache: RwLock<HashMap<(ID, String), String>>,
+    /// Recently read entries, decoded
+    entry_cache: EntryCache,
+}
+
+impl std::fmt::Debug for Archive {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Archive")
+            .field("path", &self.path)
+            .field("entries", &self.header.entry_count)
+            .field("trees", &self.trees.len())
+            .finish()
+    }
+}
+
+impl Archive {
+    /// Writes every tree of `source` to an archive at `path`, encoding entries as JSON.
+    ///
+    /// Verification statuses are kept. Private keys and cached state are not
+    /// exported. The archive is written to a temporary file first and moved into
+    /// place, so an existing archive at `path` is replaced atomically.
+    ///
+    /// # Arguments
+    /// * `source` - The database to export
+    /// * `path` - Where to write the archive
+    pub fn export<P: AsRef<Path>>(source: &dyn Database, path: P) -> Result<()> {
+        Self::export_with(source, path, EntryCodec::Json)
+    }
+
+    /// Like [`export`](Self::export), encoding entries with the given codec.
+    pub fn export_with<P: AsRef<Path>>(
+        source: &dyn Database,
+        path: P,
+        codec: EntryCodec,
+    ) -> Result<()> {
+        let path = path.as_ref();
+        let tmp_path = path.with_extension("tmp");
+        let file = File::create(&tmp_path).map_err(io_err)?;
+        let mut writer = BufWriter::new(file);
+        format::write(source, codec, &mut writer)?;
+        let file = writer.into_inner().map_err(|e| io_err(e.into_error()))?;
+        file.sync_all().map_err(io_err)?;
+        std::fs::rename(&tmp_path, path).map_err(io_err)
+    }
+
+    /// Opens an archive written by [`export`](Self::export).
+    ///
+    /// The file is memory-mapped and must not be modified while it is open.
+    ///
+    /// # Errors
+    /// Returns `DatabaseError::FileIo` if the file cannot be opened or mapped,
+    /// or `DatabaseError::InvalidEncoding` if it is not a valid archive.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(io_err)?;
+        // SAFETY: archives are immutable once written; `export` never writes to an
+        // existing archive in place, and callers are told not to modify the file.
+        let map = unsafe { Mmap::map(&file) }.map_err(io_err)?;
+        let header = Header::parse(&map)?;
+
+        let start = header.trees_offset as usize;
+        let index: TreesIndex = header
+            .codec
+            .decode(&map[start..start + header.trees_len as usize])?;
+
+        let mut archive = Self {
+            path: path.to_path_buf(),
+            map,
+            header,
+            trees: HashMap::new(),
+            crdt_cache: RwLock::new(HashMap::new()),
+            entry_cache: EntryCache::default(),
+        };
+        for tree in index.trees {
+            archive.check_context(&tree.entries)?;
+            for context in tree.subtrees.values() {
+                archive.check_context(context)?;
+            }
+            let root = archive.id_at(tree.root)?;
+            archive.trees.insert(root, tree);
+        }
+        Ok(archive)
+    }
+
+    /// Sets how many decoded entries are kept in memory.
+    ///
+    /// The most recently read entries are kept, up to
+    /// [`DEFAULT_ENTRY_CACHE_CAPACITY`](crate::backend::DEFAULT_ENTRY_CACHE_CAPACITY)
+    /// by default; a capacity of zero disables the cache.
+    pub fn with_entry_cache(mut self, capacity: usize) -> Self {
+        self.entry_cache = EntryCache::new(capacity);
+        self
+    }
+
+    /// Returns the path the archive was opened from.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the number of entries in the archive.
+    pub fn len(&self) -> usize {
+        self.header.entry_count as usize
+    }
+
+    /// Returns true if the archive contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the codec entries in the archive are encoded with.
+    pub fn codec(&self) -> EntryCodec {
+        self.header.codec
+    }
+
+    /// Reads the record at a position of the entry index.
+    fn record(&self, position: u32) -> Result<Record> {
+        if u64::from(position) >= self.header.entry_count {
+            return Err(format::invalid(format!(
+                "entry position {position} is out of range"
+            )));
+        }
+        let start = self.header.index_offset as usize + position as usize * RECORD_LEN;
+        Record::parse(&self.map[start..start + RECORD_LEN])
+    }
+
+    /// Returns the bytes of a region of the file, checking that it is in bounds.
+    fn region(&self, offset: u64, len: u32) -> Result<&[u8]> {
+        let start = offset as usize;
+        start
+            .checked_add(len as usize)
+            .and_then(|end| self.map.get(start..end))
+            .ok_or_else(|| format::invalid("entry data is out of bounds"))
+    }
+
+    fn id_bytes(&self, record: &Record) -> Result<&[u8]> {
+        self.region(record.id_offset, record.id_len)
+    }
+
+    /// Returns the ID of the entry at a position of the entry index.
+    fn id_at(&self, position: u32) -> Result<ID> {
+        let record = self.record(position)?;
+        let id = std::str::from_utf8(self.id_bytes(&record)?)
+            .map_err(|_| format::invalid("entry ID is not valid UTF-8"))?;
+        Ok(ID::from(id))
+    }
+
+    /// Decodes the entry at a position of the entry index.
+    fn entry_at(&self, position: u32) -> Result<Entry> {
+        let record = self.record(position)?;
+        self.header
+            .codec
+            .decode_entry(self.region(record.entry_offset, record.entry_len)?)
+    }
+
+    /// Finds the position of an entry in the entry index.
+    fn position_of(&self, id: &ID) -> Result<Option<u32>> {
+        let target = id.as_str().as_bytes();
+        let (mut low, mut high) = (0u64, self.header.entry_count);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let record = self.record(mid as u32)?;
+            match self.id_bytes(&record)?.cmp(target) {
+                std::cmp::Ordering::Less => low = mid + 1,
+                std::cmp::Ordering::Greater => high = mid,
+                std::cmp::Ordering::Equal => return Ok(Some(mid as u32)),
+            }
+        }
+        Ok(None)
+    }
+
+    /// Checks that every position referenced by a context is in range.
+    fn check_context(&self, context: &ContextIndex) -> Result<()> {
+        let positions = context
+            .entries
+            .iter()
+            .map(|(position, _)| position)
+            .chain(&context.tips);
+        for &position in positions {
+            self.record(position)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the index of a tree or one of its subtrees, if it exists.
+    pub(crate) fn context(&self, tree: &ID, subtree: Option<&str>) -> Option<&ContextIndex> {
+        let index = self.trees.get(tree)?;
+        match subtree {
+            Some(subtree_name) => index.subtrees.get(subtree_name),
+            None => Some(&index.entries),
+        }
+    }
+
+    /// Returns the IDs of the given positions.
+    fn ids_at(&self, positions: &[u32]) -> Result<Vec<ID>> {
+        positions.iter().map(|&p| self.id_at(p)).collect()
+    }
+
+    fn read_only(operation: &str) -> crate::Error {
+        DatabaseError::ReadOnly {
+            operation: operation.to_string(),
+        }
+        .into()
+    }
+}
+
+fn io_err(e: std::io::Error) -> crate::Error {
+    DatabaseError::FileIo { source: e }.into()
+}
+
+impl Database for Archive {
+    fn get(&self, id: &ID) -> Result<Entry> {
+        if let Some(entry) = self.entry_cache.get(id) {
+            return Ok(entry);
+        }
+        match self.position_of(id)? {
+            Some(position) => {
+                let entry = self.entry_at(position)?;
+                entry.validate_as(id)?;
+                self.entry_cache.insert(id, &entry);
+                Ok(entry)
+            }
+            None => Err(DatabaseError::EntryNotFound { id: id.clone() }.into()),
+        }
+    }
+
+    fn get_verification_status(&self, id: &ID) -> Result<VerificationStatus> {
+        match self.position_of(id)? {
+            Some(position) => Ok(self.record(position)?.status),
+            None => Err(DatabaseError::VerificationStatusNotFound { id: id.clone() }.into()),
+        }
+    }
+
+    fn put(&self, _verification_status: VerificationStatus, _entry: Entry) -> Result<()> {
+        Err(Self::read_only("store entries"))
+    }
+
+    fn update_verification_status(
+        &self,
+        _id: &ID,
+        _verification_status: VerificationStatus,
+    ) -> Result<()> {
+        Err(Self::read_only("update verification status"))
+    }
+
+    fn get_entries_by_verification_status(&self, status: VerificationStatus) -> Result<Vec<ID>> {
+        let mut ids = Vec::new();
+        for position in 0..self.header.entry_count as u32 {
+            if self.record(position)?.status == status {
+                ids.push(self.id_at(position)?);
+            }
+        }
+        Ok(ids)
+    }
+
+    fn get_tips(&self, tree: &ID) -> Result<Vec<ID>> {
+        match self.context(tree, None) {
+            Some(context) => self.ids_at(&context.tips),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn get_subtree_tips(&self, tree: &ID, subtree: &str) -> Result<Vec<ID>> {
+        match self.context(tree, Some(subtree)) {
+            Some(context) => self.ids_at(&context.tips),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn get_subtree_tips_up_to_entries(
+        &self,
+        tree: &ID,
+        subtree: &str,
+        main_entries: &[ID],
+    ) -> Result<Vec<ID>> {
+        traversal::get_subtree_tips_up_to_entries(self, tree, subtree, main_entries)
+    }
+
+    fn all_roots(&self) -> Result<Vec<ID>> {
+        Ok(self.trees.keys().cloned().collect())
+    }
+
+    fn find_lca(&self, tree: &ID, subtree: &str, entry_ids: &[ID]) -> Result<ID> {
+        traversal::find_lca(self, tree, subtree, entry_ids)
+    }
+
+    fn collect_root_to_target(
+        &self,
+        tree: &ID,
+        subtree: &str,
+        target_entry: &ID,
+    ) -> Result<Vec<ID>> {
+        traversal::build_path_from_root(self, tree, subtree, target_entry)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn get_tree(&self, tree: &ID) -> Result<Vec<Entry>> {
+        traversal::get_context(self, tree, None)
+    }
+
+    fn get_subtree(&self, tree: &ID, subtree: &str) -> Result<Vec<Entry>> {
+        traversal::get_context(self, tree, Some(subtree))
+    }
+
+    fn get_tree_from_tips(&self, tree: &ID, tips: &[ID]) -> Result<Vec<Entry>> {
+        traversal::get_from_tips(self, tree, None, tips)
+    }
+
+    fn get_subtree_from_tips(&self, tree: &ID, subtree: &str, tips: &[ID]) -> Result<Vec<Entry>> {
+        traversal::get_from_tips(self, tree, Some(subtree), tips)
+    }
+
+    fn store_private_key(&self, _key_name: &str, _private_key: SigningKey) -> Result<()> {
+        Err(Self::read_only("store private keys"))
+    }
+
+    fn get_private_key(&self, _key_name: &str) -> Result<Option<SigningKey>> {
+        Ok(None)
+    }
+
+    fn list_private_keys(&self) -> Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    fn remove_private_key(&self, _key_name: &str) -> Result<()> {
+        // Archives hold no private keys, so there is never anything to remove
+        Ok(())
+    }
+
+    fn key_storage(&self) -> KeyStorage {
+        KeyStorage::None
+    }
+
+    fn cache_stats(&self) -> Option<CacheStats> {
+        Some(self.entry_cache.stats())
+    }
+
+    fn get_cached_crdt_state(&self, entry_id: &ID, subtree: &str) -> Result<Option<String>> {
+        let cache = self.crdt_cache.read().unwrap();
+        Ok(cache.get(&(entry_id.clone(), subtree.to_string())).cloned())
+    }
+
+    fn cache_crdt_state(&self, entry_id: &ID, subtree: &str, state: String) -> Result<()> {
+        let mut cache = self.crdt_cache.write().unwrap();
+        cache.insert((entry_id.clone(), subtree.to_string()), state);
+        Ok(())
+    }
+
+    fn clear_crdt_cache(&self) -> Result<()> {
+        self.crdt_cache.write().unwrap().clear();
+        Ok(())
+    }
+
+    fn get_sorted_subtree_parents(
+        &self,
+        tree_id: &ID,
+        entry_id: &ID,
+        subtree: &str,
+    ) -> Result<Vec<ID>> {
+        traversal::get_sorted_subtree_parents(self, tree_id, entry_id, subtree)
+    }
+
+    fn get_path_from_to(
+        &self,
+        tree_id: &ID,
+        subtree: &str,
+        from_id: &ID,
+        to_ids: &[ID],
+    ) -> Result<Vec<ID>> {
+        traversal::get_path_from_to(self, tree_id, subtree, from_id, to_ids)
+    }
+}
@@ -0,0 +1,326 @@
+//! Tree traversal and pathfinding for the archive database
+//!
+//! Heights and the topological order of every tree and subtree are stored in
+//! the archive, so unlike the other backends nothing here recomputes them.
+
+use super::Archive;
+use crate::Result;
+use crate::backend::Database;
+use crate::backend::errors::DatabaseError;
+use crate::clock::Hlc;
+use crate::entry::{Entry, ID};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// The stored heights of the entries of a tree or subtree.
+fn heights(archive: &Archive, tree: &ID, subtree: Option<&str>) -> Result<HashMap<ID, usize>> {
+    let Some(context) = archive.context(tree, subtree) else {
+        return Ok(HashMap::new());
+    };
+    context
+        .entries
+        .iter()
+        .map(|&(position, height)| Ok((archive.id_at(position)?, height as usize)))
+        .collect()
+}
+
+/// Sorts IDs by height, then ID, for deterministic ordering.
+fn sort_ids_by_heights(ids: &mut [ID], heights: &HashMap<ID, usize>) {
+    ids.sort_by(|a, b| {
+        let a_height = heights.get(a).copied().unwrap_or(0);
+        let b_height = heights.get(b).copied().unwrap_or(0);
+        a_height.cmp(&b_height).then_with(|| a.cmp(b))
+    });
+}
+
+/// Retrieves all entries of a tree or subtree in their stored topological order.
+pub(crate) fn get_context(
+    archive: &Archive,
+    tree: &ID,
+    subtree: Option<&str>,
+) -> Result<Vec<Entry>> {
+    match archive.context(tree, subtree) {
+        Some(context) => context
+            .entries
+            .iter()
+            .map(|&(position, _)| archive.entry_at(position))
+            .collect(),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Collects the entries of a tree or subtree reachable from `tips` via parent links.
+fn collect_from_tips(
+    archive: &Archive,
+    tree: &ID,
+    subtree: Option<&str>,
+    tips: &[ID],
+) -> Result<Vec<(ID, Entry)>> {
+    let mut result = Vec::new();
+    let mut to_process: VecDeque<ID> = tips.iter().cloned().collect();
+    let mut processed = HashSet::new();
+
+    while let Some(current) = to_process.pop_front() {
+        if !processed.insert(current.clone()) {
+            continue;
+        }
+        let entry = match archive.get(&current) {
+            Ok(entry) => entry,
+            Err(e) if e.is_not_found() => continue,
+            Err(e) => return Err(e),
+        };
+        let in_context = match subtree {
+            Some(subtree_name) => entry.in_tree(tree) && entry.in_subtree(subtree_name),
+            None => entry.in_tree(tree),
+        };
+        if !in_context {
+            continue;
+        }
+
+        let parents = match subtree {
+            Some(subtree_name) => entry.subtree_parents(subtree_name)?,
+            None => entry.parents()?,
+        };
+        to_process.extend(parents.into_iter().filter(|p| !processed.contains(p)));
+        result.push((current, entry));
+    }
+
+    Ok(result)
+}
+
+/// Retrieves all entries of a tree or subtree reachable from the given tips, sorted topologically.
+pub(crate) fn get_from_tips(
+    archive: &Archive,
+    tree: &ID,
+    subtree: Option<&str>,
+    tips: &[ID],
+) -> Result<Vec<Entry>> {
+    if tips.is_empty() {
+        return Ok(vec![]);
+    }
+    let mut entries = collect_from_tips(archive, tree, subtree, tips)?;
+    let heights = heights(archive, tree, subtree)?;
+    entries.sort_by(|(a, _), (b, _)| {
+        let a_height = heights.get(a).copied().unwrap_or(0);
+        let b_height = heights.get(b).copied().unwrap_or(0);
+        a_height.cmp(&b_height).then_with(|| a.cmp(b))
+    });
+    Ok(entries.into_iter().map(|(_, entry)| entry).collect())
+}
+
+/// Find subtree tips considering only entries reachable from the given main tree entries.
+pub(crate) fn get_subtree_tips_up_to_entries(
+    archive: &Archive,
+    tree: &ID,
+    subtree: &str,
+    main_entries: &[ID],
+) -> Result<Vec<ID>> {
+    if main_entries.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // Fast path: the tree tips cover the whole subtree, so the index answers directly
+    let tree_tips: HashSet<ID> = archive.get_tips(tree)?.into_iter().collect();
+    if main_entries.len() == tree_tips.len() && main_entries.iter().all(|id| tree_tips.contains(id))
+    {
+        return archive.get_subtree_tips(tree, subtree);
+    }
+
+    let subtree_entries: Vec<(ID, Entry)> = collect_from_tips(archive, tree, None, main_entries)?
+        .into_iter()
+        .filter(|(_, entry)| entry.in_subtree(subtree))
+        .collect();
+
+    let mut referenced = HashSet::new();
+    for (_, entry) in &subtree_entries {
+        referenced.extend(entry.subtree_parents(subtree)?);
+    }
+
+    Ok(subtree_entries
+        .into_iter()
+        .map(|(id, _)| id)
+        .filter(|id| !referenced.contains(id))
+        .collect())
+}
+
+/// Get the subtree parent IDs for a specific entry and subtree, sorted by height then ID.
+pub(crate) fn get_sorted_subtree_parents(
+    archive: &Archive,
+    tree_id: &ID,
+    entry_id: &ID,
+    subtree: &str,
+) -> Result<Vec<ID>> {
+    let entry = archive.get(entry_id)?;
+    if !entry.in_tree(tree_id) || !entry.in_subtree(subtree) {
+        return Ok(Vec::new());
+    }
+
+    let mut parents = match entry.subtree_parents(subtree) {
+        Ok(parents) => parents,
+        Err(_) => return Ok(Vec::new()),
+    };
+
+    if !parents.is_empty() {
+        let heights = heights(archive, tree_id, Some(subtree))?;
+        sort_ids_by_heights(&mut parents, &heights);
+    }
+    Ok(parents)
+}
+
+/// Get all entry IDs on paths from `from_id` to any of `to_ids`, sorted by height,
+/// timestamp, then ID.
+///
+/// `from_id` itself is excluded from the result.
+pub(crate) fn get_path_from_to(
+    archive: &Archive,
+    tree_id: &ID,
+    subtree: &str,
+    from_id: &ID,
+    to_ids: &[ID],
+) -> Result<Vec<ID>> {
+    if to_ids.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let heights = heights(archive, tree_id, Some(subtree))?;
+    let mut result = Vec::new();
+    let mut timestamps: HashMap<ID, Option<Hlc>> = HashMap::new();
+    let mut to_process: VecDeque<ID> = to_ids.iter().filter(|id| *id != from_id).cloned().collect();
+    let mut processed = HashSet::new();
+
+    while let Some(current) = to_process.pop_front() {
+        if !processed.insert(current.clone()) || current == *from_id {
+            continue;
+        }
+        let entry = archive.get(&current)?;
+        timestamps.insert(current.clone(), entry.timestamp());
+        result.push(current);
+
+        if entry.in_tree(tree_id)
+            && entry.in_subtree(subtree)
+            && let Ok(mut parents) = entry.subtree_parents(subtree)
+        {
+            sort_ids_by_heights(&mut parents, &heights);
+            to_process.extend(parents.into_iter().filter(|p| !processed.contains(p)));
+        }
+    }
+
+    // Among concurrent entries at the same height, order by timestamp so the most
+    // recent edit is merged last
+    result.sort_by(|a, b| {
+        let a_height = heights.get(a).copied().unwrap_or(0);
+        let b_height = heights.get(b).copied().unwrap_or(0);
+        a_height
+            .cmp(&b_height)
+            .then_with(|| timestamps[a].cmp(&timestamps[b]))
+            .then_with(|| a.cmp(b))
+    });
+    Ok(result)
+}
+
+/// Build the path from the tree/subtree root to a target entry by following first parents.
+pub(crate) fn build_path_from_root(
+    archive: &Archive,
+    tree: &ID,
+    subtree: &str,
+    target_entry: &ID,
+) -> Result<Vec<ID>> {
+    let mut path = Vec::new();
+    let mut current = target_entry.clone();
+    let mut visited = HashSet::new();
+
+    loop {
+        if !visited.insert(current.clone()) {
+            return Err(DatabaseError::CycleDetected { entry_id: current }.into());
+        }
+        path.push(current.clone());
+
+        let entry = archive.get(&current)?;
+        if current == *tree || entry.is_root() {
+            break;
+        }
+
+        let parents = if subtree.is_empty() || entry.subtree_parents(subtree).is_err() {
+            entry.parents()?
+        } else {
+            entry.subtree_parents(subtree)?
+        };
+
+        match parents.first() {
+            Some(parent) => current = parent.clone(),
+            None => break,
+        }
+    }
+
+    path.reverse();
+    Ok(path)
+}
+
+/// Find the Lowest Common Ancestor (LCA) of multiple entries within a tree/subtree.
+pub(crate) fn find_lca(
+    archive: &Archive,
+    tree: &ID,
+    subtree: &str,
+    entry_ids: &[ID],
+) -> Result<ID> {
+    if entry_ids.is_empty() {
+        return Err(DatabaseError::EmptyEntryList {
+            operation: "LCA".to_string(),
+        }
+        .into());
+    }
+
+    if entry_ids.len() == 1 {
+        return Ok(entry_ids[0].clone());
+    }
+
+    for entry_id in entry_ids {
+        let entry = archive.get(entry_id)?;
+        if !entry.in_tree(tree) {
+            return Err(DatabaseError::EntryNotInTree {
+                entry_id: entry_id.clone(),
+                tree_id: tree.clone(),
+            }
+            .into());
+        }
+    }
+
+    // Track which starting entries can reach each ancestor, walking upward in lockstep
+    let mut ancestors: HashMap<ID, HashSet<usize>> = HashMap::new();
+    let mut queues: Vec<VecDeque<ID>> = entry_ids
+        .iter()
+        .map(|id| VecDeque::from([id.clone()]))
+        .collect();
+
+    loop {
+        let mut any_progress = false;
+
+        for (idx, queue) in queues.iter_mut().enumerate() {
+            if let Some(current) = queue.pop_front() {
+                any_progress = true;
+
+                let reachable_by = ancestors.entry(current.clone()).or_default();
+                reachable_by.insert(idx);
+                if reachable_by.len() == entry_ids.len() {
+                    return Ok(current);
+                }
+
+                if let Ok(entry) = archive.get(&current) {
+                    let parents = match entry.subtree_parents(subtree) {
+                        Ok(parents) => parents,
+                        Err(_) => entry.parents()?,
+                    };
+                    queue.extend(parents);
+                }
+            }
+        }
+
+        if !any_progress {
+            break;
+        }
+    }
+
+    Err(DatabaseError::NoCommonAncestor {
+        entry_ids: entry_ids.to_vec(),
+    }
+    .into())
+}
@@ -0,0 +1,103 @@
+//! Detection of the storage format of a database file
+//!
+//! Each file-backed database starts with a recognizable header: SQLite's own
+//! magic string, the archive magic, a journal record, or an `InMemory`
+//! snapshot (JSON, or framed by a codec or compression header). This lets a
+//! database be opened without being told how it was written.
+
+use super::InMemory;
+use crate::Result;
+use crate::backend::Database;
+use crate::backend::errors::DatabaseError;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// Magic string at the start of every SQLite database file.
+const SQLITE_MAGIC: &[u8] = b"SQLite format 3\0";
+
+/// Magic string at the start of archive files, see `archive::format`.
+const ARCHIVE_MAGIC: &[u8] = b"EDBARCH\0";
+
+/// Start of every record of an `InMemory` journal.
+const JOURNAL_PREFIX: &[u8] = b"{\"op\":";
+
+/// How a database file was written.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StorageFormat {
+    /// A snapshot written by `InMemory::save_to_file`, in any codec or compression
+    Snapshot,
+    /// An append-only journal written by `InMemory::open_with_journal`
+    Journal,
+    /// A SQLite database. Opening it requires the "sqlite" feature.
+    Sqlite,
+    /// A read-only archive. Opening it requires the "archive" feature.
+    Archive,
+}
+
+impl StorageFormat {
+    /// Detects the format of the file at `path` from its first bytes.
+    ///
+    /// An empty file is taken to be a journal that has no records yet.
+    ///
+    /// # Returns
+    /// `None` if the file does not exist.
+    pub fn detect<P: AsRef<Path>>(path: P) -> Result<Option<Self>> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let mut header = Vec::with_capacity(SQLITE_MAGIC.len());
+        File::open(path)
+            .and_then(|file| {
+                file.take(SQLITE_MAGIC.len() as u64)
+                    .read_to_end(&mut header)
+            })
+            .map_err(|e| DatabaseError::FileIo { source: e })?;
+
+        let format = if header.starts_with(SQLITE_MAGIC) {
+            StorageFormat::Sqlite
+        } else if header.starts_with(ARCHIVE_MAGIC) {
+            StorageFormat::Archive
+        } else if header.is_empty() || header.starts_with(JOURNAL_PREFIX) {
+            StorageFormat::Journal
+        } else {
+            StorageFormat::Snapshot
+        };
+        Ok(Some(format))
+    }
+
+    /// Opens the file at `path` with the backend for this format.
+    ///
+    /// # Errors
+    /// Returns `DatabaseError::InvalidEncoding` if the backend for this format
+    /// is behind a feature that is not enabled.
+    pub fn open<P: AsRef<Path>>(&self, path: P) -> Result<Box<dyn Database>> {
+        let path = path.as_ref();
+        match self {
+            StorageFormat::Snapshot => Ok(Box::new(InMemory::load_from_file(path)?)),
+            StorageFormat::Journal => Ok(Box::new(InMemory::open_with_journal(path)?)),
+            #[cfg(feature = "sqlite")]
+            StorageFormat::Sqlite => Ok(Box::new(super::Sqlite::open(path)?)),
+            #[cfg(feature = "archive")]
+            StorageFormat::Archive => Ok(Box::new(super::Archive::open(path)?)),
+            #[cfg(not(feature = "sqlite"))]
+            StorageFormat::Sqlite => Err(missing_feature(path, "a SQLite", "sqlite")),
+            #[cfg(not(feature = "archive"))]
+            StorageFormat::Archive => Err(missing_feature(path, "an archive", "archive")),
+        }
+    }
+}
+
+#[cfg(any(not(feature = "sqlite"), not(feature = "archive")))]
+fn missing_feature(path: &Path, format: &str, feature: &str) -> crate::Error {
+    DatabaseError::InvalidEncoding {
+        reason: format!(
+            "{} is {format} database but the \"{feature}\" feature is not enabled",
+            path.display()
+        ),
+    }
+    .into()
+}
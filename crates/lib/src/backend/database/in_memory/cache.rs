@@ -4,6 +4,7 @@
 //! as well as CRDT state caching for improved performance.
 
 use super::InMemory;
+use super::index::in_tree;
 use crate::Result;
 use crate::backend::errors::DatabaseError;
 use crate::entry::{Entry, ID};
@@ -35,7 +36,7 @@ pub(crate) fn calculate_heights(
                 let entries = backend.entries.read().unwrap();
                 let mut tree_entries = Vec::new();
                 for (id, entry) in entries.iter() {
-                    if entry.in_tree(tree) {
+                    if in_tree(id, entry, tree) {
                         tree_entries.push(id.clone());
                     }
                 }
@@ -82,12 +83,14 @@ pub(crate) fn calculate_heights(
             if let Some(tree_cache) = heights_cache.get(tree) {
                 // Try to serve from cache
                 let entries = backend.entries.read().unwrap();
+                let subtree_index = backend.subtree_index.read().unwrap();
                 let mut subtree_entries = Vec::new();
                 for (id, entry) in entries.iter() {
-                    if entry.in_tree(tree) && entry.in_subtree(subtree_name) {
+                    if subtree_index.contains(tree, subtree_name, id, entry) {
                         subtree_entries.push(id.clone());
                     }
                 }
+                drop(subtree_index);
                 drop(entries);
 
                 let mut result = HashMap::new();
@@ -113,6 +116,10 @@ pub(crate) fn calculate_heights(
             }
             drop(heights_cache);
 
+            // Cache the tree heights first, so that no entry is cached below
+            // without its tree height
+            calculate_heights(backend, tree, None)?;
+
             // Compute heights and cache them
             let computed_heights = calculate_heights_original(backend, tree, Some(subtree_name))?;
 
@@ -150,11 +157,12 @@ fn calculate_heights_original(
 
     // 1. Build graph structure (children_map, in_degree) for the context
     let entries = backend.entries.read().unwrap();
+    let subtree_index = backend.subtree_index.read().unwrap();
     for (id, entry) in entries.iter() {
         // Check if entry is in the context (tree or tree+subtree)
         let in_context = match subtree {
-            Some(subtree_name) => entry.in_tree(tree) && entry.in_subtree(subtree_name),
-            None => entry.in_tree(tree),
+            Some(subtree_name) => subtree_index.contains(tree, subtree_name, id, entry),
+            None => in_tree(id, entry, tree),
         };
         if !in_context {
             continue;
@@ -177,8 +185,10 @@ fn calculate_heights_original(
             let parent_in_context = entries
                 .get(&parent_id)
                 .is_some_and(|p_entry| match subtree {
-                    Some(subtree_name) => p_entry.in_tree(tree) && p_entry.in_subtree(subtree_name),
-                    None => p_entry.in_tree(tree),
+                    Some(subtree_name) => {
+                        subtree_index.contains(tree, subtree_name, &parent_id, p_entry)
+                    }
+                    None => in_tree(&parent_id, p_entry, tree),
                 });
 
             if parent_in_context {
@@ -340,6 +350,12 @@ pub(crate) fn create_crdt_cache_key(entry_id: &ID, subtree: &str) -> String {
     key
 }
 
+/// Returns the entry ID of a CRDT cache key, or `None` for other keys.
+pub(crate) fn crdt_cache_entry(key: &str) -> Option<ID> {
+    let (entry_id, _subtree) = key.strip_prefix("crdt:")?.split_once(':')?;
+    Some(ID::from(entry_id))
+}
+
 /// Get cached CRDT state for a subtree at a specific entry.
 pub(crate) fn get_cached_crdt_state(
     backend: &InMemory,
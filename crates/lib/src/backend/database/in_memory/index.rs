@@ -0,0 +1,197 @@
+//! Subtree membership index for InMemory database
+//!
+//! Subtree traversals scan entries and test each one for membership in the
+//! requested tree and subtree. In trees with many subtrees most entries are
+//! irrelevant to any given subtree, so a bloom filter of entry IDs is kept per
+//! (tree, subtree) pair. A negative answer from the filter lets a scan skip an
+//! entry without inspecting it; a positive answer is confirmed against the entry
+//! itself, so false positives never change results.
+//!
+//! The index is derived from the stored entries. It is maintained on every `put`
+//! and rebuilt when a database is loaded; it is never persisted.
+
+use crate::entry::{Entry, ID};
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// Number of members a new filter is sized for.
+const INITIAL_CAPACITY: usize = 64;
+
+/// Bits allocated per expected member (~1% false positives with `HASH_COUNT`).
+const BITS_PER_MEMBER: usize = 10;
+
+/// Number of bit positions set per member.
+const HASH_COUNT: u64 = 7;
+
+/// A fixed-size bloom filter over entry IDs.
+#[derive(Debug, Clone)]
+pub(crate) struct BloomFilter {
+    bits: Vec<u64>,
+    members: usize,
+    capacity: usize,
+}
+
+impl BloomFilter {
+    fn with_capacity(capacity: usize) -> Self {
+        let words = (capacity * BITS_PER_MEMBER).div_ceil(64).max(1);
+        Self {
+            bits: vec![0; words],
+            members: 0,
+            capacity,
+        }
+    }
+
+    /// Derives the two base hashes used for double hashing.
+    ///
+    /// IDs are normally hex SHA-256 digests, which are already uniformly
+    /// distributed, so their leading hex digits are used directly. Other IDs
+    /// fall back to hashing the string.
+    fn base_hashes(id: &ID) -> (u64, u64) {
+        let id = id.as_str();
+        if id.len() >= 32
+            && let (Ok(h1), Ok(h2)) = (
+                u64::from_str_radix(&id[..16], 16),
+                u64::from_str_radix(&id[16..32], 16),
+            )
+        {
+            return (h1, h2 | 1);
+        }
+        let mut hasher = DefaultHasher::new();
+        id.hash(&mut hasher);
+        let h1 = hasher.finish();
+        h1.hash(&mut hasher);
+        (h1, hasher.finish() | 1)
+    }
+
+    fn positions(&self, id: &ID) -> impl Iterator<Item = usize> + use<> {
+        let (h1, h2) = Self::base_hashes(id);
+        let len = (self.bits.len() * 64) as u64;
+        (0..HASH_COUNT).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % len) as usize)
+    }
+
+    fn insert(&mut self, id: &ID) {
+        for pos in self.positions(id) {
+            self.bits[pos / 64] |= 1 << (pos % 64);
+        }
+        self.members += 1;
+    }
+
+    /// Returns false if `id` is definitely not a member.
+    pub(crate) fn may_contain(&self, id: &ID) -> bool {
+        self.positions(id)
+            .all(|pos| self.bits[pos / 64] & (1 << (pos % 64)) != 0)
+    }
+}
+
+/// Bloom filters of subtree members, grouped by tree then subtree name.
+#[derive(Debug, Default)]
+pub(crate) struct SubtreeIndex {
+    filters: HashMap<ID, HashMap<String, BloomFilter>>,
+}
+
+/// `Entry::in_tree` for a stored entry, using its known ID instead of rehashing it.
+pub(crate) fn in_tree(entry_id: &ID, entry: &Entry, tree: &ID) -> bool {
+    entry.root() == *tree || entry_id == tree
+}
+
+/// The trees an entry is a member of, mirroring `Entry::in_tree`.
+fn entry_trees(entry: &Entry, entry_id: &ID) -> Vec<ID> {
+    let mut trees = Vec::with_capacity(2);
+    let root = entry.root();
+    if !root.is_empty() {
+        trees.push(root);
+    }
+    if entry.is_root() {
+        trees.push(entry_id.clone());
+    }
+    trees
+}
+
+impl SubtreeIndex {
+    /// Builds the index for all given entries.
+    pub(crate) fn build(entries: &HashMap<ID, Entry>) -> Self {
+        let mut members: HashMap<ID, HashMap<String, Vec<&ID>>> = HashMap::new();
+        for (id, entry) in entries {
+            for tree in entry_trees(entry, id) {
+                let tree_members = members.entry(tree).or_default();
+                for subtree in entry.subtrees() {
+                    tree_members.entry(subtree).or_default().push(id);
+                }
+            }
+        }
+
+        let filters = members
+            .into_iter()
+            .map(|(tree, subtrees)| {
+                let subtrees = subtrees
+                    .into_iter()
+                    .map(|(subtree, ids)| {
+                        let capacity = ids.len().next_power_of_two().max(INITIAL_CAPACITY);
+                        let mut filter = BloomFilter::with_capacity(capacity);
+                        for id in ids {
+                            filter.insert(id);
+                        }
+                        (subtree, filter)
+                    })
+                    .collect();
+                (tree, subtrees)
+            })
+            .collect();
+        Self { filters }
+    }
+
+    /// Records a newly stored entry.
+    ///
+    /// `entries` must contain every stored entry; it is used to rebuild a
+    /// filter with a larger capacity once it fills up.
+    pub(crate) fn insert(&mut self, entry_id: &ID, entry: &Entry, entries: &HashMap<ID, Entry>) {
+        for tree in entry_trees(entry, entry_id) {
+            let tree_filters = self.filters.entry(tree.clone()).or_default();
+            for subtree in entry.subtrees() {
+                let filter = tree_filters
+                    .entry(subtree.clone())
+                    .or_insert_with(|| BloomFilter::with_capacity(INITIAL_CAPACITY));
+                if filter.members >= filter.capacity {
+                    *filter = Self::rebuild_filter(&tree, &subtree, filter.capacity * 2, entries);
+                    if entries.contains_key(entry_id) {
+                        // The rebuild already picked up this entry
+                        continue;
+                    }
+                }
+                filter.insert(entry_id);
+            }
+        }
+    }
+
+    fn rebuild_filter(
+        tree: &ID,
+        subtree: &str,
+        capacity: usize,
+        entries: &HashMap<ID, Entry>,
+    ) -> BloomFilter {
+        let mut filter = BloomFilter::with_capacity(capacity);
+        for (id, entry) in entries {
+            if in_tree(id, entry, tree) && entry.in_subtree(subtree) {
+                filter.insert(id);
+            }
+        }
+        filter
+    }
+
+    /// Returns the filter for a subtree, or `None` if the subtree has no entries.
+    pub(crate) fn filter(&self, tree: &ID, subtree: &str) -> Option<&BloomFilter> {
+        self.filters.get(tree)?.get(subtree)
+    }
+
+    /// Checks whether an entry is in the given tree and subtree.
+    ///
+    /// Consults the filter first and only inspects the entry when the filter
+    /// cannot rule it out.
+    pub(crate) fn contains(&self, tree: &ID, subtree: &str, entry_id: &ID, entry: &Entry) -> bool {
+        self.filter(tree, subtree)
+            .is_some_and(|filter| filter.may_contain(entry_id))
+            && in_tree(entry_id, entry, tree)
+            && entry.in_subtree(subtree)
+    }
+}
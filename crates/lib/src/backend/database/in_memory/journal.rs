@@ -0,0 +1,308 @@
+//! Append-only journal persistence for InMemory database
+//!
+//! Instead of rewriting the whole database on every save, a journal records
+//! each mutation as one JSON line as it happens. Opening a journal replays the
+//! log into memory. Since entries are immutable, the log only grows redundant
+//! through repeated puts, verification status changes and key removals; once
+//! enough redundant records accumulate the journal is compacted by rewriting
+//! it from the current in-memory state.
+
+use super::InMemory;
+use crate::backend::VerificationStatus;
+use crate::backend::errors::DatabaseError;
+use crate::entry::{Entry, ID};
+use crate::{Error, Result};
+use ed25519_dalek::SigningKey;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use zeroize::Zeroizing;
+
+/// Number of redundant records after which the journal is compacted automatically.
+pub(crate) const COMPACTION_THRESHOLD: usize = 1024;
+
+/// A single mutation recorded in the journal.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum JournalRecord {
+    Put {
+        status: VerificationStatus,
+        entry: Entry,
+    },
+    UpdateStatus {
+        id: ID,
+        status: VerificationStatus,
+    },
+    StorePrivateKey {
+        name: String,
+        key: Zeroizing<[u8; 32]>,
+    },
+    RemovePrivateKey {
+        name: String,
+    },
+}
+
+/// Open handle to a journal file.
+#[derive(Debug)]
+pub(crate) struct Journal {
+    path: PathBuf,
+    file: File,
+    /// Number of records currently in the file
+    records: usize,
+}
+
+fn io_err(e: std::io::Error) -> Error {
+    DatabaseError::FileIo { source: e }.into()
+}
+
+impl Journal {
+    fn open_append(path: &Path, records: usize) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(io_err)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            records,
+        })
+    }
+
+    fn append(&mut self, record: &JournalRecord) -> Result<()> {
+        let mut line = serde_json::to_string(record)
+            .map_err(|e| -> Error { DatabaseError::SerializationFailed { source: e }.into() })?;
+        line.push('\n');
+        self.file.write_all(line.as_bytes()).map_err(io_err)?;
+        self.file.flush().map_err(io_err)?;
+        self.records += 1;
+        Ok(())
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// Opens a journal, replaying any existing records into a new `InMemory` database.
+///
+/// A truncated final line, as left behind by a crash mid-write, is ignored and
+/// cut off the file so that new records start on a fresh line.
+pub(crate) fn open_with_journal<P: AsRef<Path>>(path: P) -> Result<InMemory> {
+    let path = path.as_ref();
+    let backend = InMemory::new();
+    let mut records = 0;
+
+    if path.exists() {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(path)
+            .map_err(io_err)?;
+        let file_len = file.metadata().map_err(io_err)?.len();
+        // Byte offset where the last complete record ends
+        let mut valid_len = 0;
+        let mut reader = BufReader::new(&file);
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            let read = reader.read_until(b'\n', &mut line).map_err(io_err)?;
+            if read == 0 {
+                break;
+            }
+            let complete = line.ends_with(b"\n");
+            if complete && line.trim_ascii().is_empty() {
+                valid_len += read as u64;
+                continue;
+            }
+            let record: JournalRecord = match serde_json::from_slice(&line) {
+                Ok(record) if complete => record,
+                Ok(_) => break,
+                Err(_) if reader.fill_buf().map_err(io_err)?.is_empty() => break,
+                Err(e) => return Err(DatabaseError::DeserializationFailed { source: e }.into()),
+            };
+            apply(&backend, record)?;
+            records += 1;
+            valid_len += read as u64;
+        }
+
+        if valid_len < file_len {
+            file.set_len(valid_len).map_err(io_err)?;
+            file.sync_all().map_err(io_err)?;
+        }
+    }
+
+    *backend.journal.lock().unwrap() = Some(Journal::open_append(path, records)?);
+    Ok(backend)
+}
+
+fn apply(backend: &InMemory, record: JournalRecord) -> Result<()> {
+    match record {
+        JournalRecord::Put { status, entry } => {
+            entry.validate_structure()?;
+            super::storage::put(backend, status, entry)
+        }
+        JournalRecord::UpdateStatus { id, status } => {
+            backend
+                .verification_status
+                .write()
+                .unwrap()
+                .insert(id, status);
+            Ok(())
+        }
+        JournalRecord::StorePrivateKey { name, key } => {
+            backend
+                .private_keys
+                .write()
+                .unwrap()
+                .insert(name, SigningKey::from_bytes(&key));
+            Ok(())
+        }
+        JournalRecord::RemovePrivateKey { name } => {
+            backend.private_keys.write().unwrap().remove(&name);
+            Ok(())
+        }
+    }
+}
+
+/// Appends a record if the database has a journal, compacting it when it grows too redundant.
+fn record(backend: &InMemory, record: JournalRecord) -> Result<()> {
+    let mut journal = backend.journal.lock().unwrap();
+    let Some(handle) = journal.as_mut() else {
+        return Ok(());
+    };
+    handle.append(&record)?;
+
+    let live = backend.entries.read().unwrap().len() + backend.private_keys.read().unwrap().len();
+    if handle.records.saturating_sub(live) >= COMPACTION_THRESHOLD {
+        let path = handle.path.clone();
+        *journal = Some(rewrite(backend, &path)?);
+    }
+    Ok(())
+}
+
+pub(crate) fn record_put(
+    backend: &InMemory,
+    status: VerificationStatus,
+    entry: &Entry,
+) -> Result<()> {
+    if backend.journal.lock().unwrap().is_none() {
+        return Ok(());
+    }
+    record(
+        backend,
+        JournalRecord::Put {
+            status,
+            entry: entry.clone(),
+        },
+    )
+}
+
+pub(crate) fn record_update_status(
+    backend: &InMemory,
+    id: &ID,
+    status: VerificationStatus,
+) -> Result<()> {
+    record(
+        backend,
+        JournalRecord::UpdateStatus {
+            id: id.clone(),
+            status,
+        },
+    )
+}
+
+pub(crate) fn record_store_private_key(
+    backend: &InMemory,
+    name: &str,
+    key: &SigningKey,
+) -> Result<()> {
+    record(
+        backend,
+        JournalRecord::StorePrivateKey {
+            name: name.to_string(),
+            key: Zeroizing::new(key.to_bytes()),
+        },
+    )
+}
+
+pub(crate) fn record_remove_private_key(backend: &InMemory, name: &str) -> Result<()> {
+    record(
+        backend,
+        JournalRecord::RemovePrivateKey {
+            name: name.to_string(),
+        },
+    )
+}
+
+/// Rewrites the journal from the current state. Does nothing without a journal.
+pub(crate) fn compact(backend: &InMemory) -> Result<()> {
+    let mut journal = backend.journal.lock().unwrap();
+    if let Some(handle) = journal.as_ref() {
+        let path = handle.path.clone();
+        *journal = Some(rewrite(backend, &path)?);
+    }
+    Ok(())
+}
+
+/// Syncs the journal file to disk. Does nothing without a journal.
+///
+/// A poisoned lock is ignored: records are appended whole or as a truncated
+/// final line, which replay tolerates, so the file is safe to sync after a panic.
+pub(crate) fn sync(backend: &InMemory) -> Result<()> {
+    let journal = backend
+        .journal
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    match journal.as_ref() {
+        Some(handle) => handle.file.sync_data().map_err(io_err),
+        None => Ok(()),
+    }
+}
+
+/// Writes a snapshot of all entries and keys to a temporary file and atomically
+/// replaces the journal with it.
+///
+/// Must be called with the journal lock held so no records are lost in between.
+fn rewrite(backend: &InMemory, path: &Path) -> Result<Journal> {
+    let tmp_path = path.with_extension("compact");
+    let mut records = 0;
+    {
+        let mut writer = BufWriter::new(File::create(&tmp_path).map_err(io_err)?);
+        let mut write_record = |record: &JournalRecord| -> Result<()> {
+            serde_json::to_writer(&mut writer, record).map_err(|e| -> Error {
+                DatabaseError::SerializationFailed { source: e }.into()
+            })?;
+            writer.write_all(b"\n").map_err(io_err)?;
+            records += 1;
+            Ok(())
+        };
+
+        let entries = backend.entries.read().unwrap();
+        let payloads = backend.payloads.read().unwrap();
+        let statuses = backend.verification_status.read().unwrap();
+        for (id, entry) in entries.iter() {
+            write_record(&JournalRecord::Put {
+                status: statuses.get(id).copied().unwrap_or_default(),
+                entry: payloads.restored(id, entry),
+            })?;
+        }
+        drop(statuses);
+        drop(payloads);
+        drop(entries);
+
+        for (name, key) in backend.private_keys.read().unwrap().iter() {
+            write_record(&JournalRecord::StorePrivateKey {
+                name: name.clone(),
+                key: Zeroizing::new(key.to_bytes()),
+            })?;
+        }
+
+        let file = writer.into_inner().map_err(|e| io_err(e.into_error()))?;
+        file.sync_all().map_err(io_err)?;
+    }
+
+    fs::rename(&tmp_path, path).map_err(io_err)?;
+    Journal::open_append(path, records)
+}
@@ -5,20 +5,26 @@
 //! is not strictly required or is handled externally.
 
 mod cache;
+mod index;
+mod journal;
 mod persistence;
 mod storage;
 mod traversal;
 
 use crate::Result;
+use crate::backend::content::ContentStore;
 use crate::backend::errors::DatabaseError;
-use crate::backend::{Database, VerificationStatus};
+use crate::backend::{
+    Compression, Database, EntryCodec, EntryIter, KeyStorage, PayloadStats, PruneStats,
+    VerificationStatus, stream_entries,
+};
 use crate::entry::{Entry, ID};
 use ed25519_dalek::SigningKey;
 use serde::{Deserialize, Serialize};
 use std::any::Any;
 use std::collections::{HashMap, HashSet};
-use std::path::Path;
-use std::sync::RwLock;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
 
 /// Heights cache: entry_id -> (tree_height, subtree_name -> subtree_height)
 pub(crate) type TreeHeightsCache = HashMap<ID, (usize, HashMap<String, usize>)>;
@@ -37,15 +43,20 @@ pub(crate) struct TreeTipsCache {
 /// (e.g., by saving/loading the entire state to/from a file).
 ///
 /// It provides basic persistence capabilities via `save_to_file` and
-/// `load_from_file`, serializing the `HashMap` to JSON.
+/// `load_from_file`, serializing the `HashMap` to JSON. For large databases,
+/// `open_with_journal` instead appends each change to a log as it happens.
 ///
 /// **Security Note**: Private keys are stored in memory in plaintext in this implementation.
 /// This is acceptable for development and testing but should not be used in production
 /// without proper encryption or hardware security module integration.
 #[derive(Debug)]
 pub struct InMemory {
-    /// Entries storage with read-write lock for concurrent access
+    /// Entries storage with read-write lock for concurrent access.
+    /// Entries are stored without their shared payloads, see `payloads`.
     pub(crate) entries: RwLock<HashMap<ID, Entry>>,
+    /// Subtree payloads shared by the stored entries, across all trees.
+    /// Always locked after `entries` when both are held.
+    pub(crate) payloads: RwLock<ContentStore>,
     /// Verification status for each entry
     pub(crate) verification_status: RwLock<HashMap<ID, VerificationStatus>>,
     /// Private key storage for authentication
@@ -60,6 +71,16 @@ pub struct InMemory {
     pub(crate) heights: RwLock<HashMap<ID, TreeHeightsCache>>,
     /// Cached tips grouped by tree: tree_id -> (tree_tips, subtree_name -> subtree_tips)
     pub(crate) tips: RwLock<HashMap<ID, TreeTipsCache>>,
+    /// Bloom filters of subtree members, used to skip entries during subtree scans.
+    /// Always locked after `entries` when both are held.
+    pub(crate) subtree_index: RwLock<index::SubtreeIndex>,
+    /// Parents that stored entries refer to but that have not been stored yet.
+    /// Always locked after `entries` when both are held.
+    pub(crate) missing_parents: RwLock<HashSet<ID>>,
+    /// Append-only journal that mutations are written to, if opened with one
+    pub(crate) journal: Mutex<Option<journal::Journal>>,
+    /// Compression applied to files written by `save_to_file`
+    pub(crate) compression: Compression,
 }
 
 impl InMemory {
@@ -67,20 +88,50 @@ impl InMemory {
     pub fn new() -> Self {
         Self {
             entries: RwLock::new(HashMap::new()),
+            payloads: RwLock::new(ContentStore::default()),
             verification_status: RwLock::new(HashMap::new()),
             private_keys: RwLock::new(HashMap::new()),
             cache: RwLock::new(HashMap::new()),
             heights: RwLock::new(HashMap::new()),
             tips: RwLock::new(HashMap::new()),
+            subtree_index: RwLock::new(index::SubtreeIndex::default()),
+            missing_parents: RwLock::new(HashSet::new()),
+            journal: Mutex::new(None),
+            compression: Compression::None,
         }
     }
 
+    /// Sets the compression applied to files written by `save_to_file` and
+    /// `save_to_file_with`.
+    ///
+    /// Compressed files are detected when loading, whatever this is set to. The
+    /// journal of `open_with_journal` is not compressed.
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Returns the compression applied to saved files.
+    pub fn compression(&self) -> Compression {
+        self.compression
+    }
+
     /// Returns a vector containing the IDs of all entries currently stored in the database.
     pub fn all_ids(&self) -> Vec<ID> {
         let entries = self.entries.read().unwrap();
         entries.keys().cloned().collect()
     }
 
+    /// Returns how many subtree payloads are shared between stored entries and
+    /// how much storing them once saves.
+    ///
+    /// Payloads of at least 64 bytes are stored once however many entries, in
+    /// any of the trees, contain them. A payload is freed when the last entry
+    /// containing it is pruned or removed.
+    pub fn payload_stats(&self) -> PayloadStats {
+        self.payloads.read().unwrap().stats()
+    }
+
     /// Saves the entire database state (all entries) to a specified file as JSON.
     ///
     /// # Arguments
@@ -89,12 +140,29 @@ impl InMemory {
     /// # Returns
     /// A `Result` indicating success or an I/O or serialization error.
     pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
-        persistence::save_to_file(self, path)
+        persistence::save_to_file(self, path, EntryCodec::Json)
+    }
+
+    /// Saves the entire database state to a specified file using the given codec.
+    ///
+    /// Files written with a binary codec start with a header identifying it, so
+    /// `load_from_file` reads them without being told the format.
+    ///
+    /// # Arguments
+    /// * `path` - The path to the file where the state should be saved.
+    /// * `codec` - The format to write the file in.
+    ///
+    /// # Returns
+    /// A `Result` indicating success or an I/O or serialization error.
+    pub fn save_to_file_with<P: AsRef<Path>>(&self, path: P, codec: EntryCodec) -> Result<()> {
+        persistence::save_to_file(self, path, codec)
     }
 
-    /// Loads the database state from a specified JSON file.
+    /// Loads the database state from a specified file.
     ///
-    /// If the file does not exist, a new, empty `InMemory` database is returned.
+    /// The format (JSON, or a binary codec written by `save_to_file_with`) is
+    /// detected automatically. If the file does not exist, a new, empty
+    /// `InMemory` database is returned.
     ///
     /// # Arguments
     /// * `path` - The path to the file from which to load the state.
@@ -105,6 +173,40 @@ impl InMemory {
         persistence::load_from_file(path)
     }
 
+    /// Opens a database backed by an append-only journal file.
+    ///
+    /// Existing records in the journal are replayed into memory. From then on every
+    /// `put`, verification status change and private key change is appended to the
+    /// journal as it happens, so no explicit save is needed. When the journal has
+    /// accumulated enough redundant records it is compacted automatically.
+    ///
+    /// If the file does not exist, it is created and an empty database is returned.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the journal file.
+    ///
+    /// # Returns
+    /// A `Result` containing the loaded `InMemory` database or an I/O or deserialization error.
+    pub fn open_with_journal<P: AsRef<Path>>(path: P) -> Result<Self> {
+        journal::open_with_journal(path)
+    }
+
+    /// Rewrites the journal so it only contains the current state.
+    ///
+    /// Does nothing if the database was not opened with `open_with_journal`.
+    pub fn compact_journal(&self) -> Result<()> {
+        journal::compact(self)
+    }
+
+    /// Returns the path of the journal file, if the database has one.
+    pub fn journal_path(&self) -> Option<PathBuf> {
+        self.journal
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|journal| journal.path().to_path_buf())
+    }
+
     /// Calculate heights for entries in a tree or subtree (exposed for testing)
     ///
     /// # Arguments
@@ -182,11 +284,7 @@ impl Database for InMemory {
     /// A `Result` containing the `Entry` if found, or a `DatabaseError::EntryNotFound` otherwise.
     /// Returns an owned copy to support concurrent access with internal synchronization.
     fn get(&self, id: &ID) -> Result<Entry> {
-        let entries = self.entries.read().unwrap();
-        entries
-            .get(id)
-            .cloned()
-            .ok_or_else(|| DatabaseError::EntryNotFound { id: id.clone() }.into())
+        storage::get(self, id)
     }
 
     /// Gets the verification status of an entry.
@@ -227,7 +325,8 @@ impl Database for InMemory {
         let mut verification_status_map = self.verification_status.write().unwrap();
         if verification_status_map.contains_key(id) {
             verification_status_map.insert(id.clone(), verification_status);
-            Ok(())
+            drop(verification_status_map);
+            journal::record_update_status(self, id, verification_status)
         } else {
             Err(DatabaseError::EntryNotFound { id: id.clone() }.into())
         }
@@ -257,6 +356,24 @@ impl Database for InMemory {
         traversal::get_tips(self, tree)
     }
 
+    /// Returns the height of an entry from the cached heights of its tree,
+    /// computing them first if they are not cached.
+    fn get_height(&self, tree: &ID, id: &ID) -> Result<usize> {
+        if let Some((height, _)) = self
+            .heights
+            .read()
+            .unwrap()
+            .get(tree)
+            .and_then(|cache| cache.get(id))
+        {
+            return Ok(*height);
+        }
+        cache::calculate_heights(self, tree, None)?
+            .get(id)
+            .copied()
+            .ok_or_else(|| DatabaseError::EntryNotFound { id: id.clone() }.into())
+    }
+
     fn get_subtree_tips(&self, tree: &ID, subtree: &str) -> Result<Vec<ID>> {
         traversal::get_subtree_tips(self, tree, subtree)
     }
@@ -282,9 +399,9 @@ impl Database for InMemory {
     fn all_roots(&self) -> Result<Vec<ID>> {
         let entries = self.entries.read().unwrap();
         let roots: Vec<ID> = entries
-            .values()
-            .filter(|entry| entry.is_root())
-            .map(|entry| entry.id())
+            .iter()
+            .filter(|(_, entry)| entry.is_root())
+            .map(|(id, _)| id.clone())
             .collect();
         Ok(roots)
     }
@@ -310,6 +427,14 @@ impl Database for InMemory {
         storage::get_tree(self, tree)
     }
 
+    fn prune(&self, tree: &ID, keep_tips: &[ID]) -> Result<PruneStats> {
+        storage::prune(self, tree, keep_tips)
+    }
+
+    fn remove_entries(&self, tree: &ID, entries: &[ID]) -> Result<PruneStats> {
+        storage::remove_entries(self, tree, entries)
+    }
+
     fn get_subtree(&self, tree: &ID, subtree: &str) -> Result<Vec<Entry>> {
         storage::get_subtree(self, tree, subtree)
     }
@@ -322,6 +447,17 @@ impl Database for InMemory {
         storage::get_subtree_from_tips(self, tree, subtree, tips)
     }
 
+    fn get_tree_iter(&self, tree: &ID) -> Result<EntryIter<'_>> {
+        Ok(stream_entries(self, storage::sorted_ids(self, tree, None)?))
+    }
+
+    fn get_subtree_iter(&self, tree: &ID, subtree: &str) -> Result<EntryIter<'_>> {
+        Ok(stream_entries(
+            self,
+            storage::sorted_ids(self, tree, Some(subtree))?,
+        ))
+    }
+
     /// Store a private key in the database's local key storage.
     ///
     /// Private keys are stored separately from entries and are not part of the content-addressable
@@ -339,8 +475,9 @@ impl Database for InMemory {
     /// Production systems should consider encryption at rest and hardware security modules.
     fn store_private_key(&self, key_name: &str, private_key: SigningKey) -> Result<()> {
         let mut private_keys = self.private_keys.write().unwrap();
-        private_keys.insert(key_name.to_string(), private_key);
-        Ok(())
+        private_keys.insert(key_name.to_string(), private_key.clone());
+        drop(private_keys);
+        journal::record_store_private_key(self, key_name, &private_key)
     }
 
     /// Retrieve a private key from the database's local key storage.
@@ -373,10 +510,32 @@ impl Database for InMemory {
     /// A `Result` indicating success or an error. Succeeds even if the key doesn't exist.
     fn remove_private_key(&self, key_name: &str) -> Result<()> {
         let mut private_keys = self.private_keys.write().unwrap();
-        private_keys.remove(key_name);
+        let removed = private_keys.remove(key_name).is_some();
+        drop(private_keys);
+        if removed {
+            journal::record_remove_private_key(self, key_name)?;
+        }
         Ok(())
     }
 
+    /// Syncs the journal file to disk, if the database was opened with one.
+    ///
+    /// A database without a journal only lives in memory, so there is nothing to
+    /// flush; use `save_to_file` or `AutoPersist` to write it out.
+    fn flush(&self) -> Result<()> {
+        journal::sync(self)
+    }
+
+    /// Keys written to a journal reach the disk unencrypted; otherwise they stay
+    /// in memory until the database is saved.
+    fn key_storage(&self) -> KeyStorage {
+        if self.journal.lock().unwrap().is_some() {
+            KeyStorage::Unencrypted
+        } else {
+            KeyStorage::Memory
+        }
+    }
+
     fn get_cached_crdt_state(&self, entry_id: &ID, subtree: &str) -> Result<Option<String>> {
         cache::get_cached_crdt_state(self, entry_id, subtree)
     }
@@ -2,28 +2,50 @@
 //!
 //! This module handles serialization and file I/O for saving/loading
 //! the in-memory database state to/from JSON files.
+//!
+//! Files are written in a compact format in which every ID is stored once in a
+//! binary `IdTable` and referenced by index, see `backend::encoding`. Files in the
+//! original format, with IDs written out in full everywhere, can still be loaded.
+//!
+//! Files are JSON by default. They can also be written with a binary
+//! `EntryCodec`, in which case they start with a header naming the codec and are
+//! detected automatically when loading. Either may be compressed with the
+//! backend's `Compression`, which is likewise detected when loading.
 
+use super::index::SubtreeIndex;
+use super::storage;
 use super::{InMemory, TreeHeightsCache, TreeTipsCache};
 use crate::backend::VerificationStatus;
+use crate::backend::codec::EntryCodec;
+use crate::backend::compression::Compression;
+use crate::backend::content::ContentStore;
+use crate::backend::encoding::{self, IdTable};
 use crate::backend::errors::DatabaseError;
 use crate::entry::{Entry, ID};
 use crate::{Error, Result};
+use base64ct::{Base64, Encoding};
 use ed25519_dalek::SigningKey;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
-use std::sync::RwLock;
+use std::sync::{Mutex, RwLock};
+use zeroize::Zeroizing;
+
+/// Version of the compact file format written by `save_to_file`.
+const COMPACT_FORMAT_VERSION: u32 = 2;
 
 /// Serializable version of InMemory database for persistence
+///
+/// This is the original format, which is still accepted when loading.
 #[derive(Serialize, Deserialize)]
 struct SerializableDatabase {
     entries: HashMap<ID, Entry>,
     #[serde(default)]
     verification_status: HashMap<ID, VerificationStatus>,
-    /// Private keys stored as 32-byte arrays for serialization
+    /// Private keys stored as 32-byte arrays for serialization, zeroized on drop
     #[serde(default)]
-    private_keys_bytes: HashMap<String, [u8; 32]>,
+    private_keys_bytes: HashMap<String, Zeroizing<[u8; 32]>>,
     /// Generic key-value cache (not serialized - cache is rebuilt on load)
     #[serde(default)]
     cache: HashMap<String, String>,
@@ -35,32 +57,243 @@ struct SerializableDatabase {
     tips: HashMap<ID, TreeTipsCache>,
 }
 
-impl Serialize for InMemory {
-    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        let entries = self.entries.read().unwrap().clone();
-        let verification_status = self.verification_status.read().unwrap().clone();
-        let private_keys = self.private_keys.read().unwrap();
-        let private_keys_bytes = private_keys
+/// Compact serializable version of InMemory database.
+///
+/// All IDs are stored once in `ids` and referenced by their index in that table.
+#[derive(Serialize, Deserialize)]
+struct CompactDatabase {
+    /// Format version, always `COMPACT_FORMAT_VERSION`
+    format: u32,
+    /// Base64-encoded `IdTable` of every ID referenced in this file
+    ids: String,
+    entries: Vec<CompactEntry>,
+    /// Private keys stored as 32-byte arrays for serialization, zeroized on drop
+    #[serde(default)]
+    private_keys_bytes: HashMap<String, Zeroizing<[u8; 32]>>,
+    #[serde(default)]
+    cache: HashMap<String, String>,
+    /// Cached heights grouped by tree
+    #[serde(default)]
+    heights: Vec<CompactHeights>,
+    /// Cached tips grouped by tree
+    #[serde(default)]
+    tips: Vec<CompactTips>,
+}
+
+/// An entry with its root and parent IDs replaced by encoded table references.
+#[derive(Serialize, Deserialize)]
+struct CompactEntry {
+    id: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    status: Option<VerificationStatus>,
+    /// Base64-encoded references produced by `encoding::encode_entry_refs`
+    refs: String,
+    entry: Entry,
+}
+
+/// Cached heights of one tree: (entry, tree height, subtree heights).
+#[derive(Serialize, Deserialize)]
+struct CompactHeights {
+    tree: u32,
+    heights: Vec<(u32, usize, HashMap<String, usize>)>,
+}
+
+/// Cached tips of one tree, as base64-encoded ID lists.
+#[derive(Serialize, Deserialize)]
+struct CompactTips {
+    tree: u32,
+    tree_tips: String,
+    #[serde(default)]
+    subtree_tips: HashMap<String, String>,
+}
+
+/// Either file format, as accepted when loading.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredDatabase {
+    Compact(CompactDatabase),
+    Legacy(SerializableDatabase),
+}
+
+fn encode_list(table: &IdTable, ids: impl IntoIterator<Item = ID>) -> String {
+    let mut list: Vec<ID> = ids.into_iter().collect();
+    list.sort();
+    let mut out = Vec::new();
+    table.encode_list(&list, &mut out);
+    Base64::encode_string(&out)
+}
+
+fn decode_base64(data: &str) -> Result<Vec<u8>> {
+    Base64::decode_vec(data).map_err(|e| {
+        DatabaseError::InvalidEncoding {
+            reason: format!("invalid base64: {e}"),
+        }
+        .into()
+    })
+}
+
+fn decode_list(table: &IdTable, data: &str) -> Result<Vec<ID>> {
+    table.decode_id_list(&decode_base64(data)?)
+}
+
+impl CompactDatabase {
+    fn from_backend(backend: &InMemory) -> Self {
+        let entries = backend.entries.read().unwrap();
+        let payloads = backend.payloads.read().unwrap();
+        let verification_status = backend.verification_status.read().unwrap();
+        let heights = backend.heights.read().unwrap();
+        let tips = backend.tips.read().unwrap();
+
+        // Gather every ID referenced anywhere so they can be stored once
+        let mut ids = Vec::new();
+        for (id, entry) in entries.iter() {
+            ids.push(id.clone());
+            encoding::collect_entry_ids(entry, &mut ids);
+        }
+        ids.extend(verification_status.keys().cloned());
+        for (tree, tree_heights) in heights.iter() {
+            ids.push(tree.clone());
+            ids.extend(tree_heights.keys().cloned());
+        }
+        for (tree, tree_tips) in tips.iter() {
+            ids.push(tree.clone());
+            ids.extend(tree_tips.tree_tips.iter().cloned());
+            for subtree_tips in tree_tips.subtree_tips.values() {
+                ids.extend(subtree_tips.iter().cloned());
+            }
+        }
+        let table = IdTable::from_ids(ids);
+
+        let mut compact_entries: Vec<CompactEntry> = entries
+            .iter()
+            .map(|(id, entry)| {
+                let entry = payloads.restored(id, entry);
+                let (stripped, refs) = encoding::encode_entry_refs(&entry, &table);
+                CompactEntry {
+                    id: table.index_of(id),
+                    status: verification_status.get(id).copied(),
+                    refs: Base64::encode_string(&refs),
+                    entry: stripped,
+                }
+            })
+            .collect();
+        compact_entries.sort_by_key(|entry| entry.id);
+
+        let mut compact_heights: Vec<CompactHeights> = heights
             .iter()
-            .map(|(k, v)| (k.clone(), v.to_bytes()))
+            .map(|(tree, tree_heights)| {
+                let mut heights: Vec<_> = tree_heights
+                    .iter()
+                    .map(|(id, (height, subtree_heights))| {
+                        (table.index_of(id), *height, subtree_heights.clone())
+                    })
+                    .collect();
+                heights.sort_by_key(|(id, _, _)| *id);
+                CompactHeights {
+                    tree: table.index_of(tree),
+                    heights,
+                }
+            })
+            .collect();
+        compact_heights.sort_by_key(|heights| heights.tree);
+
+        let mut compact_tips: Vec<CompactTips> = tips
+            .iter()
+            .map(|(tree, tree_tips)| CompactTips {
+                tree: table.index_of(tree),
+                tree_tips: encode_list(&table, tree_tips.tree_tips.iter().cloned()),
+                subtree_tips: tree_tips
+                    .subtree_tips
+                    .iter()
+                    .map(|(name, ids)| (name.clone(), encode_list(&table, ids.iter().cloned())))
+                    .collect(),
+            })
             .collect();
-        let cache = self.cache.read().unwrap().clone();
-        let heights = self.heights.read().unwrap().clone();
-        let tips = self.tips.read().unwrap().clone();
+        compact_tips.sort_by_key(|tips| tips.tree);
+
+        let mut table_bytes = Vec::new();
+        table.encode(&mut table_bytes);
+
+        Self {
+            format: COMPACT_FORMAT_VERSION,
+            ids: Base64::encode_string(&table_bytes),
+            entries: compact_entries,
+            private_keys_bytes: backend
+                .private_keys
+                .read()
+                .unwrap()
+                .iter()
+                .map(|(k, v)| (k.clone(), Zeroizing::new(v.to_bytes())))
+                .collect(),
+            cache: backend.cache.read().unwrap().clone(),
+            heights: compact_heights,
+            tips: compact_tips,
+        }
+    }
+
+    fn into_serializable(self) -> Result<SerializableDatabase> {
+        if self.format != COMPACT_FORMAT_VERSION {
+            return Err(DatabaseError::InvalidEncoding {
+                reason: format!("unsupported file format version {}", self.format),
+            }
+            .into());
+        }
+        let table = IdTable::decode(&decode_base64(&self.ids)?)?;
 
-        let serializable = SerializableDatabase {
+        let mut entries = HashMap::with_capacity(self.entries.len());
+        let mut verification_status = HashMap::with_capacity(self.entries.len());
+        for compact in self.entries {
+            let id = table.get(compact.id)?.clone();
+            let entry =
+                encoding::decode_entry_refs(compact.entry, &decode_base64(&compact.refs)?, &table)?;
+            if let Some(status) = compact.status {
+                verification_status.insert(id.clone(), status);
+            }
+            entries.insert(id, entry);
+        }
+
+        let mut heights = HashMap::with_capacity(self.heights.len());
+        for compact in self.heights {
+            let mut tree_heights = TreeHeightsCache::with_capacity(compact.heights.len());
+            for (id, height, subtree_heights) in compact.heights {
+                tree_heights.insert(table.get(id)?.clone(), (height, subtree_heights));
+            }
+            heights.insert(table.get(compact.tree)?.clone(), tree_heights);
+        }
+
+        let mut tips = HashMap::with_capacity(self.tips.len());
+        for compact in self.tips {
+            let mut tree_tips = TreeTipsCache {
+                tree_tips: decode_list(&table, &compact.tree_tips)?
+                    .into_iter()
+                    .collect(),
+                ..Default::default()
+            };
+            for (name, list) in compact.subtree_tips {
+                tree_tips
+                    .subtree_tips
+                    .insert(name, decode_list(&table, &list)?.into_iter().collect());
+            }
+            tips.insert(table.get(compact.tree)?.clone(), tree_tips);
+        }
+
+        Ok(SerializableDatabase {
             entries,
             verification_status,
-            private_keys_bytes,
-            cache,
+            private_keys_bytes: self.private_keys_bytes,
+            cache: self.cache,
             heights,
             tips,
-        };
+        })
+    }
+}
 
-        serializable.serialize(serializer)
+impl Serialize for InMemory {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        CompactDatabase::from_backend(self).serialize(serializer)
     }
 }
 
@@ -69,7 +302,12 @@ impl<'de> Deserialize<'de> for InMemory {
     where
         D: Deserializer<'de>,
     {
-        let serializable = SerializableDatabase::deserialize(deserializer)?;
+        let serializable = match StoredDatabase::deserialize(deserializer)? {
+            StoredDatabase::Compact(compact) => compact
+                .into_serializable()
+                .map_err(serde::de::Error::custom)?,
+            StoredDatabase::Legacy(legacy) => legacy,
+        };
 
         let private_keys = serializable
             .private_keys_bytes
@@ -80,32 +318,55 @@ impl<'de> Deserialize<'de> for InMemory {
             })
             .collect();
 
+        let mut payloads = ContentStore::default();
+        let entries: HashMap<ID, Entry> = serializable
+            .entries
+            .into_iter()
+            .map(|(id, entry)| {
+                entry.validate_as(&id).map_err(serde::de::Error::custom)?;
+                let stored = payloads.insert(&id, entry);
+                Ok((id, stored))
+            })
+            .collect::<std::result::Result<_, D::Error>>()?;
+        let subtree_index = SubtreeIndex::build(&entries);
+        let missing_parents = storage::missing_parents(&entries);
+
         Ok(InMemory {
-            entries: RwLock::new(serializable.entries),
+            entries: RwLock::new(entries),
+            payloads: RwLock::new(payloads),
             verification_status: RwLock::new(serializable.verification_status),
             private_keys: RwLock::new(private_keys),
             cache: RwLock::new(serializable.cache),
             heights: RwLock::new(serializable.heights),
             tips: RwLock::new(serializable.tips),
+            subtree_index: RwLock::new(subtree_index),
+            missing_parents: RwLock::new(missing_parents),
+            journal: Mutex::new(None),
+            compression: Compression::None,
         })
     }
 }
 
-/// Saves the entire database state (all entries) to a specified file as JSON.
+/// Saves the entire database state (all entries) to a specified file.
 ///
 /// # Arguments
 /// * `backend` - The InMemory database to save
 /// * `path` - The path to the file where the state should be saved.
+/// * `codec` - The format to write the file in.
 ///
 /// # Returns
 /// A `Result` indicating success or an I/O or serialization error.
-pub(crate) fn save_to_file<P: AsRef<Path>>(backend: &InMemory, path: P) -> Result<()> {
-    let json = serde_json::to_string_pretty(backend)
-        .map_err(|e| -> Error { DatabaseError::SerializationFailed { source: e }.into() })?;
-    fs::write(path, json).map_err(|e| -> Error { DatabaseError::FileIo { source: e }.into() })
+pub(crate) fn save_to_file<P: AsRef<Path>>(
+    backend: &InMemory,
+    path: P,
+    codec: EntryCodec,
+) -> Result<()> {
+    let data = codec.encode_framed(backend)?;
+    fs::write(path, backend.compression.compress(&data))
+        .map_err(|e| -> Error { DatabaseError::FileIo { source: e }.into() })
 }
 
-/// Loads the database state from a specified JSON file.
+/// Loads the database state from a specified file, detecting its format.
 ///
 /// If the file does not exist, a new, empty `InMemory` database is returned.
 ///
@@ -119,10 +380,8 @@ pub(crate) fn load_from_file<P: AsRef<Path>>(path: P) -> Result<InMemory> {
         return Ok(InMemory::new());
     }
 
-    let json = fs::read_to_string(path)
-        .map_err(|e| -> Error { DatabaseError::FileIo { source: e }.into() })?;
-    let database: InMemory = serde_json::from_str(&json)
-        .map_err(|e| -> Error { DatabaseError::DeserializationFailed { source: e }.into() })?;
-
-    Ok(database)
+    let data =
+        fs::read(path).map_err(|e| -> Error { DatabaseError::FileIo { source: e }.into() })?;
+    // The file is the database's own, so its declared size is not limited
+    EntryCodec::decode_framed(&Compression::decompress(&data, usize::MAX)?)
 }
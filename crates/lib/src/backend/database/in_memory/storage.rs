@@ -1,10 +1,14 @@
 //! Core storage operations for InMemory database
 
+use super::cache::crdt_cache_entry;
+use super::index::{SubtreeIndex, in_tree};
 use super::{InMemory, TreeHeightsCache};
 use crate::Result;
-use crate::backend::VerificationStatus;
 use crate::backend::errors::DatabaseError;
+use crate::backend::prune::{entry_size, retained_entries};
+use crate::backend::{PruneStats, VerificationStatus};
 use crate::entry::{Entry, ID};
+use std::collections::{HashMap, HashSet};
 
 /// Retrieves an entry by ID from the internal `HashMap`.
 /// Used internally by traversal functions.
@@ -12,7 +16,7 @@ pub(crate) fn get(backend: &InMemory, id: &ID) -> Result<Entry> {
     let entries = backend.entries.read().unwrap();
     entries
         .get(id)
-        .cloned()
+        .map(|entry| backend.payloads.read().unwrap().restored(id, entry))
         .ok_or_else(|| DatabaseError::EntryNotFound { id: id.clone() }.into())
 }
 
@@ -25,11 +29,31 @@ pub(crate) fn put(
     let entry_id = entry.id();
     let tree_id = entry.root();
 
-    // Store the entry
-    {
+    // Store the entry and record its subtree memberships. Entries normally
+    // arrive parents first; note when they do not, as the cached tips and
+    // heights of the tree can then no longer be updated incrementally.
+    let (arrived_late, parents_missing) = {
         let mut entries = backend.entries.write().unwrap();
-        entries.insert(entry_id.clone(), entry.clone());
-    }
+        let stored = backend
+            .payloads
+            .write()
+            .unwrap()
+            .insert(&entry_id, entry.clone());
+        entries.insert(entry_id.clone(), stored);
+        let mut subtree_index = backend.subtree_index.write().unwrap();
+        subtree_index.insert(&entry_id, &entry, &entries);
+
+        let mut missing_parents = backend.missing_parents.write().unwrap();
+        let arrived_late = missing_parents.remove(&entry_id);
+        let mut parents_missing = false;
+        for parent in parent_ids(&entry) {
+            if !entries.contains_key(&parent) {
+                missing_parents.insert(parent);
+                parents_missing = true;
+            }
+        }
+        (arrived_late, parents_missing)
+    };
 
     // Store the verification status
     {
@@ -37,6 +61,24 @@ pub(crate) fn put(
         verification_status_map.insert(entry_id.clone(), verification_status);
     }
 
+    // Append to the journal, if any, once the entry is part of the in-memory state
+    super::journal::record_put(backend, verification_status, &entry)?;
+
+    if arrived_late || parents_missing {
+        backend.heights.write().unwrap().remove(&tree_id);
+        backend.tips.write().unwrap().remove(&tree_id);
+        if arrived_late {
+            // States computed while this entry was missing are incomplete
+            let entries = backend.entries.read().unwrap();
+            let mut cache = backend.cache.write().unwrap();
+            cache.retain(|key, _| {
+                crdt_cache_entry(key)
+                    .is_none_or(|id| entries.get(&id).is_none_or(|e| !in_tree(&id, e, &tree_id)))
+            });
+        }
+        return Ok(());
+    }
+
     // Smart cache update for heights
     {
         let mut heights_cache = backend.heights.write().unwrap();
@@ -88,6 +130,24 @@ pub(crate) fn put(
     Ok(())
 }
 
+/// All parents of an entry, in the tree and in each of its subtrees.
+fn parent_ids(entry: &Entry) -> HashSet<ID> {
+    let mut parents: HashSet<ID> = entry.parents().unwrap_or_default().into_iter().collect();
+    for subtree in entry.subtrees() {
+        parents.extend(entry.subtree_parents(&subtree).unwrap_or_default());
+    }
+    parents
+}
+
+/// Collects the parents that `entries` refer to but that are not among them.
+pub(crate) fn missing_parents(entries: &HashMap<ID, Entry>) -> HashSet<ID> {
+    entries
+        .values()
+        .flat_map(parent_ids)
+        .filter(|parent| !entries.contains_key(parent))
+        .collect()
+}
+
 /// Helper function to check if an entry is a tip within its tree.
 ///
 /// An entry is a tip if no other entry in the same tree lists it as a parent.
@@ -104,23 +164,6 @@ pub(crate) fn is_tip(backend: &InMemory, tree: &ID, entry_id: &ID) -> bool {
     true
 }
 
-/// Helper function to check if an entry is a tip within a specific subtree.
-///
-/// An entry is a subtree tip if no other entry in the same subtree lists it as a subtree parent.
-pub(crate) fn is_subtree_tip(backend: &InMemory, tree: &ID, subtree: &str, entry_id: &ID) -> bool {
-    let entries = backend.entries.read().unwrap();
-    for other_entry in entries.values() {
-        if other_entry.root() == tree
-            && other_entry.subtrees().contains(&subtree.to_string())
-            && let Ok(subtree_parents) = other_entry.subtree_parents(subtree)
-            && subtree_parents.contains(entry_id)
-        {
-            return false;
-        }
-    }
-    true
-}
-
 /// Helper function to update cached heights
 fn update_cached_heights(cache: &mut TreeHeightsCache, entry: &Entry, entry_id: &ID) {
     // Calculate height based on parents
@@ -171,12 +214,14 @@ fn update_cached_heights(cache: &mut TreeHeightsCache, entry: &Entry, entry_id:
 /// Retrieves all entries belonging to a specific tree, sorted topologically.
 pub(crate) fn get_tree(backend: &InMemory, tree: &ID) -> Result<Vec<Entry>> {
     let entries = backend.entries.read().unwrap();
+    let payloads = backend.payloads.read().unwrap();
     let mut tree_entries: Vec<Entry> = entries
-        .values()
-        .filter(|entry| entry.in_tree(tree))
-        .cloned()
+        .iter()
+        .filter(|(id, entry)| in_tree(id, entry, tree))
+        .map(|(id, entry)| payloads.restored(id, entry))
         .collect();
 
+    drop(payloads);
     drop(entries); // Release the lock before calling sort_entries_by_height
 
     // Sort by height using the cache module function
@@ -187,12 +232,20 @@ pub(crate) fn get_tree(backend: &InMemory, tree: &ID) -> Result<Vec<Entry>> {
 /// Retrieves all entries belonging to a specific subtree within a tree, sorted topologically.
 pub(crate) fn get_subtree(backend: &InMemory, tree: &ID, subtree: &str) -> Result<Vec<Entry>> {
     let entries = backend.entries.read().unwrap();
+    let payloads = backend.payloads.read().unwrap();
+    let subtree_index = backend.subtree_index.read().unwrap();
+    if subtree_index.filter(tree, subtree).is_none() {
+        // No entry has ever been stored in this subtree
+        return Ok(vec![]);
+    }
     let mut subtree_entries: Vec<Entry> = entries
-        .values()
-        .filter(|entry| entry.in_tree(tree) && entry.in_subtree(subtree))
-        .cloned()
+        .iter()
+        .filter(|(id, entry)| subtree_index.contains(tree, subtree, id, entry))
+        .map(|(id, entry)| payloads.restored(id, entry))
         .collect();
 
+    drop(payloads);
+    drop(subtree_index);
     drop(entries); // Release the lock before calling sort_entries_by_subtree_height
 
     // Sort by subtree height using the cache module function
@@ -200,6 +253,15 @@ pub(crate) fn get_subtree(backend: &InMemory, tree: &ID, subtree: &str) -> Resul
     Ok(subtree_entries)
 }
 
+/// The IDs of the entries of a tree, or of a subtree within it, in the order
+/// of `get_tree` and `get_subtree`.
+pub(crate) fn sorted_ids(backend: &InMemory, tree: &ID, subtree: Option<&str>) -> Result<Vec<ID>> {
+    let heights = super::cache::calculate_heights(backend, tree, subtree)?;
+    let mut ids: Vec<ID> = heights.keys().cloned().collect();
+    ids.sort_by(|a, b| heights[a].cmp(&heights[b]).then_with(|| a.cmp(b)));
+    Ok(ids)
+}
+
 /// Retrieves all entries belonging to a specific tree up to the given tips, sorted topologically.
 pub(crate) fn get_tree_from_tips(backend: &InMemory, tree: &ID, tips: &[ID]) -> Result<Vec<Entry>> {
     if tips.is_empty() {
@@ -213,10 +275,11 @@ pub(crate) fn get_tree_from_tips(backend: &InMemory, tree: &ID, tips: &[ID]) ->
 
     // Initialize with tips
     let entries = backend.entries.read().unwrap();
+    let payloads = backend.payloads.read().unwrap();
     for tip in tips {
         if let Some(entry) = entries.get(tip) {
             // Only include entries that are part of the specified tree
-            if entry.in_tree(tree) {
+            if in_tree(tip, entry, tree) {
                 to_process.push_back(tip.clone());
             }
         }
@@ -231,7 +294,7 @@ pub(crate) fn get_tree_from_tips(backend: &InMemory, tree: &ID, tips: &[ID]) ->
 
         if let Some(entry) = entries.get(&current_id) {
             // Entry must be in the specified tree to be included
-            if entry.in_tree(tree) {
+            if in_tree(&current_id, entry, tree) {
                 // Add parents to be processed
                 if let Ok(parents) = entry.parents() {
                     for parent in parents {
@@ -242,11 +305,12 @@ pub(crate) fn get_tree_from_tips(backend: &InMemory, tree: &ID, tips: &[ID]) ->
                 }
 
                 // Include this entry in the result
-                result.push(entry.clone());
+                result.push(payloads.restored(&current_id, entry));
                 processed.insert(current_id);
             }
         }
     }
+    drop(payloads);
     drop(entries);
 
     // Sort the result by height within the tree context
@@ -275,10 +339,12 @@ pub(crate) fn get_subtree_from_tips(
 
     // Initialize with tips
     let entries = backend.entries.read().unwrap();
+    let payloads = backend.payloads.read().unwrap();
+    let subtree_index = backend.subtree_index.read().unwrap();
     for tip in tips {
         if let Some(entry) = entries.get(tip) {
             // Only include entries that are part of both the tree and the subtree
-            if entry.in_tree(tree) && entry.in_subtree(subtree) {
+            if subtree_index.contains(tree, subtree, tip, entry) {
                 to_process.push_back(tip.clone());
             }
         }
@@ -293,7 +359,7 @@ pub(crate) fn get_subtree_from_tips(
 
         if let Some(entry) = entries.get(&current_id) {
             // Entry must be in both the tree and subtree to be included
-            if entry.in_tree(tree) && entry.in_subtree(subtree) {
+            if subtree_index.contains(tree, subtree, &current_id, entry) {
                 // Add subtree parents to be processed
                 if let Ok(subtree_parents) = entry.subtree_parents(subtree) {
                     for parent in subtree_parents {
@@ -304,11 +370,13 @@ pub(crate) fn get_subtree_from_tips(
                 }
 
                 // Include this entry in the result
-                result.push(entry.clone());
+                result.push(payloads.restored(&current_id, entry));
                 processed.insert(current_id);
             }
         }
     }
+    drop(subtree_index);
+    drop(payloads);
     drop(entries);
 
     // Sort the result by subtree height
@@ -318,3 +386,83 @@ pub(crate) fn get_subtree_from_tips(
 
     Ok(result)
 }
+
+/// Removes the entries of a tree that are not reachable from `keep_tips`.
+pub(crate) fn prune(backend: &InMemory, tree: &ID, keep_tips: &[ID]) -> Result<PruneStats> {
+    remove_where(backend, tree, |tree_entries| {
+        let retained = retained_entries(tree, tree_entries, keep_tips)?;
+        Ok(tree_entries
+            .keys()
+            .filter(|id| !retained.contains(*id))
+            .cloned()
+            .collect())
+    })
+}
+
+/// Removes the given entries of a tree; IDs that are not entries of the tree
+/// are ignored.
+pub(crate) fn remove_entries(backend: &InMemory, tree: &ID, ids: &[ID]) -> Result<PruneStats> {
+    remove_where(backend, tree, |tree_entries| {
+        Ok(ids
+            .iter()
+            .filter(|id| tree_entries.contains_key(*id))
+            .cloned()
+            .collect())
+    })
+}
+
+/// Removes the entries of a tree chosen by `select` from all of its entries.
+///
+/// The subtree index is rebuilt and the tree's cached heights and tips are
+/// dropped, as are cached CRDT states of removed entries. A journal, if any, is
+/// compacted so the removed entries do not reappear on replay.
+fn remove_where(
+    backend: &InMemory,
+    tree: &ID,
+    select: impl FnOnce(&HashMap<ID, Entry>) -> Result<HashSet<ID>>,
+) -> Result<PruneStats> {
+    let mut stats = PruneStats::default();
+    let removed: Vec<ID> = {
+        let mut entries = backend.entries.write().unwrap();
+        let tree_entries: HashMap<ID, Entry> = entries
+            .iter()
+            .filter(|(id, entry)| in_tree(id, entry, tree))
+            .map(|(id, entry)| (id.clone(), entry.clone()))
+            .collect();
+        let removed: Vec<ID> = select(&tree_entries)?.into_iter().collect();
+        let mut payloads = backend.payloads.write().unwrap();
+        for id in &removed {
+            if let Some(entry) = entries.remove(id) {
+                stats.bytes_removed += entry_size(&payloads.restored(id, &entry));
+                stats.payloads_freed += payloads.remove(id);
+            }
+        }
+        drop(payloads);
+        stats.entries_removed = removed.len();
+        stats.entries_kept = tree_entries.len() - removed.len();
+
+        let mut subtree_index = backend.subtree_index.write().unwrap();
+        *subtree_index = SubtreeIndex::build(&entries);
+        *backend.missing_parents.write().unwrap() = missing_parents(&entries);
+        removed
+    };
+
+    {
+        let mut verification_status_map = backend.verification_status.write().unwrap();
+        for id in &removed {
+            verification_status_map.remove(id);
+        }
+    }
+    backend.heights.write().unwrap().remove(tree);
+    backend.tips.write().unwrap().remove(tree);
+    {
+        let removed: HashSet<&ID> = removed.iter().collect();
+        let mut cache = backend.cache.write().unwrap();
+        cache.retain(|key, _| crdt_cache_entry(key).is_none_or(|id| !removed.contains(&id)));
+    }
+
+    if stats.entries_removed > 0 {
+        super::journal::compact(backend)?;
+    }
+    Ok(stats)
+}
@@ -5,9 +5,11 @@
 //! parent-child relationships, and tip finding.
 
 use super::InMemory;
+use super::index::in_tree;
 use crate::Result;
 use crate::backend::errors::DatabaseError;
-use crate::entry::ID;
+use crate::clock::Hlc;
+use crate::entry::{Entry, ID};
 use std::collections::{HashMap, HashSet, VecDeque};
 
 /// Build the complete path from tree/subtree root to a target entry
@@ -107,7 +109,7 @@ pub(crate) fn collect_root_to_target(
 ///
 /// # Returns
 /// A `Result` containing a vector of entry IDs on paths from `from_id` to any `to_ids`,
-/// sorted by height then ID for deterministic ordering.
+/// sorted by height, timestamp, then ID for deterministic ordering.
 pub(crate) fn get_path_from_to(
     backend: &InMemory,
     tree_id: &ID,
@@ -148,8 +150,9 @@ pub(crate) fn get_path_from_to(
         result.push(current.clone());
         processed.insert(current.clone());
 
-        // Get parents in the subtree
-        let parents = get_sorted_subtree_parents(backend, tree_id, &current, subtree)?;
+        // Get parents in the subtree; their order does not matter, as the
+        // result is sorted below
+        let parents = subtree_parents(backend, tree_id, &current, subtree)?;
 
         // Add all parents to be processed
         for parent in parents {
@@ -159,16 +162,28 @@ pub(crate) fn get_path_from_to(
         }
     }
 
-    // Deduplicate and sort result by height then ID for deterministic ordering
+    // Deduplicate and sort result by height, then timestamp, then ID for deterministic
+    // ordering. Entries are merged in this order, so among concurrent entries at the
+    // same height the most recent edit wins.
     result.sort();
     result.dedup();
 
     if !result.is_empty() {
         let heights = super::cache::calculate_heights(backend, tree_id, Some(subtree))?;
+        let timestamps: HashMap<ID, Option<Hlc>> = {
+            let entries = backend.entries.read().unwrap();
+            result
+                .iter()
+                .map(|id| (id.clone(), entries.get(id).and_then(Entry::timestamp)))
+                .collect()
+        };
         result.sort_by(|a, b| {
             let a_height = *heights.get(a).unwrap_or(&0);
             let b_height = *heights.get(b).unwrap_or(&0);
-            a_height.cmp(&b_height).then_with(|| a.cmp(b))
+            a_height
+                .cmp(&b_height)
+                .then_with(|| timestamps[a].cmp(&timestamps[b]))
+                .then_with(|| a.cmp(b))
         });
     }
 
@@ -194,22 +209,7 @@ pub(crate) fn get_sorted_subtree_parents(
     entry_id: &ID,
     subtree: &str,
 ) -> Result<Vec<ID>> {
-    let entries = backend.entries.read().unwrap();
-    let entry = entries
-        .get(entry_id)
-        .ok_or_else(|| DatabaseError::EntryNotFound {
-            id: entry_id.clone(),
-        })?;
-
-    if !entry.in_tree(tree_id) || !entry.in_subtree(subtree) {
-        return Ok(Vec::new());
-    }
-
-    let mut parents = match entry.subtree_parents(subtree) {
-        Ok(parents) => parents,
-        Err(_) => return Ok(Vec::new()),
-    };
-    drop(entries);
+    let mut parents = subtree_parents(backend, tree_id, entry_id, subtree)?;
 
     // Sort parents by height (ascending), then by ID for determinism
     if !parents.is_empty() {
@@ -224,6 +224,30 @@ pub(crate) fn get_sorted_subtree_parents(
     Ok(parents)
 }
 
+/// Get the subtree parent IDs for a specific entry and subtree, in the order
+/// the entry lists them.
+///
+/// Entries outside the tree or the subtree have no parents.
+fn subtree_parents(
+    backend: &InMemory,
+    tree_id: &ID,
+    entry_id: &ID,
+    subtree: &str,
+) -> Result<Vec<ID>> {
+    let entries = backend.entries.read().unwrap();
+    let entry = entries
+        .get(entry_id)
+        .ok_or_else(|| DatabaseError::EntryNotFound {
+            id: entry_id.clone(),
+        })?;
+
+    if !in_tree(entry_id, entry, tree_id) || !entry.in_subtree(subtree) {
+        return Ok(Vec::new());
+    }
+
+    Ok(entry.subtree_parents(subtree).unwrap_or_default())
+}
+
 /// Find the Lowest Common Ancestor (LCA) of multiple entries within a tree/subtree
 ///
 /// This function uses breadth-first search to find the first common ancestor
@@ -355,10 +379,7 @@ pub(crate) fn get_tips(backend: &InMemory, tree: &ID) -> Result<Vec<ID>> {
     for (id, entry) in entries.iter() {
         if entry.root() == tree && super::storage::is_tip(backend, tree, id) {
             tips.push(id.clone());
-        } else if entry.is_root()
-            && entry.id() == *tree
-            && super::storage::is_tip(backend, tree, id)
-        {
+        } else if entry.is_root() && id == tree && super::storage::is_tip(backend, tree, id) {
             // Handle the special case of the root entry
             tips.push(id.clone());
         }
@@ -436,18 +457,19 @@ pub(crate) fn get_subtree_tips_up_to_entries(
     // use the original algorithm that checks all entries
     let current_tree_tips = get_tips(backend, tree)?;
     if main_entries == current_tree_tips {
-        // Use original algorithm for current tips case
-        let mut tips = Vec::new();
+        // Every subtree entry that no other subtree entry lists as a parent is a tip
         let entries = backend.entries.read().unwrap();
+        let subtree_index = backend.subtree_index.read().unwrap();
+        let mut members = Vec::new();
+        let mut referenced = HashSet::new();
         for (id, entry) in entries.iter() {
-            if entry.in_tree(tree)
-                && entry.in_subtree(subtree)
-                && super::storage::is_subtree_tip(backend, tree, subtree, id)
-            {
-                tips.push(id.clone());
+            if subtree_index.contains(tree, subtree, id, entry) {
+                referenced.extend(entry.subtree_parents(subtree).unwrap_or_default());
+                members.push(id.clone());
             }
         }
-        return Ok(tips);
+        members.retain(|id| !referenced.contains(id));
+        return Ok(members);
     }
 
     // For custom tips: Get all tree entries reachable from the main entries,
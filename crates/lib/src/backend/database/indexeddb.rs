@@ -0,0 +1,611 @@
+//! IndexedDB database backend for browsers
+//!
+//! This module provides `IndexedDb`, a `Database` that persists entries and
+//! private keys in the browser's IndexedDB, for Eidetica compiled to
+//! `wasm32-unknown-unknown`. IndexedDB is asynchronous while the `Database`
+//! trait is not, so the whole database is read into an [`InMemory`] database
+//! when it is opened and reads are served from memory. Writes update memory
+//! and are queued to IndexedDB straight away; they complete in the background,
+//! in the order they were made.
+//!
+//! Requires the "indexeddb" feature.
+
+use super::InMemory;
+use crate::backend::errors::DatabaseError;
+use crate::backend::{Database, EntryIter, KeyStorage, VerificationStatus};
+use crate::entry::{Entry, ID};
+use crate::{Error, Result};
+use ed25519_dalek::SigningKey;
+use js_sys::Array;
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{
+    DomException, Event, IdbDatabase, IdbFactory, IdbObjectStore, IdbRequest, IdbTransaction,
+    IdbTransactionMode,
+};
+use zeroize::Zeroizing;
+
+/// Version of the IndexedDB schema, bumped when object stores change.
+const SCHEMA_VERSION: u32 = 1;
+
+/// Object store of entries and their verification status, keyed by entry ID.
+const ENTRIES: &str = "entries";
+
+/// Object store of private keys, keyed by key name.
+const KEYS: &str = "private_keys";
+
+/// An entry as stored in the `entries` object store.
+#[derive(Serialize, Deserialize)]
+struct StoredEntry {
+    status: VerificationStatus,
+    entry: Entry,
+}
+
+/// Writes queued to IndexedDB that have not completed yet.
+#[derive(Default)]
+struct Writes {
+    pending: usize,
+    /// The first write that failed since errors were last reported
+    error: Option<String>,
+    waiters: Vec<Waker>,
+}
+
+impl Writes {
+    fn settle(&mut self, error: Option<String>) {
+        self.pending = self.pending.saturating_sub(1);
+        if let Some(error) = error {
+            self.error.get_or_insert(error);
+        }
+        if self.pending == 0 {
+            self.waiters.drain(..).for_each(Waker::wake);
+        }
+    }
+
+    fn take_error(&mut self) -> Result<()> {
+        match self.error.take() {
+            Some(reason) => Err(idb_error("write", reason)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A database backend persisted in the browser's IndexedDB.
+///
+/// Each `IndexedDb` is one IndexedDB database, named when it is opened, so an
+/// application can keep several apart. Entries and private keys are stored in
+/// IndexedDB; computed CRDT states are cached in memory only.
+///
+/// Every change is queued to IndexedDB as it is made, so nothing needs to be
+/// saved explicitly. Writes finish asynchronously: await
+/// [`wait_for_writes`](Self::wait_for_writes) before relying on them, for
+/// example before the page is closed. A write that fails is reported by the
+/// next `wait_for_writes` or [`flush`](Database::flush).
+///
+/// Private keys are stored unencrypted, readable by any script of the same
+/// origin.
+///
+/// # Example
+/// ```ignore
+/// use eidetica::backend::database::IndexedDb;
+/// use eidetica::basedb::BaseDB;
+///
+/// let backend = IndexedDb::open("notes").await?;
+/// let db = BaseDB::new(Box::new(backend));
+/// ```
+pub struct IndexedDb {
+    name: String,
+    db: IdbDatabase,
+    loaded: InMemory,
+    writes: Arc<Mutex<Writes>>,
+    on_complete: js_sys::Function,
+    on_abort: js_sys::Function,
+}
+
+// SAFETY: JavaScript objects can only be used from the thread that created
+// them. Without the `atomics` target feature, wasm32 runs on a single thread,
+// so an `IndexedDb` is never shared with or sent to another thread.
+#[cfg(not(target_feature = "atomics"))]
+unsafe impl Send for IndexedDb {}
+#[cfg(not(target_feature = "atomics"))]
+unsafe impl Sync for IndexedDb {}
+
+impl std::fmt::Debug for IndexedDb {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IndexedDb")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+impl IndexedDb {
+    /// Opens the IndexedDB database `name`, creating it if it does not exist,
+    /// and loads its entries and private keys into memory.
+    ///
+    /// Works in windows and in workers.
+    ///
+    /// # Errors
+    /// Returns `DatabaseError::IndexedDb` if IndexedDB is not available or the
+    /// database cannot be opened or read, and a deserialization error if it
+    /// holds records this version cannot read.
+    pub async fn open(name: &str) -> Result<Self> {
+        let request = factory()?
+            .open_with_u32(name, SCHEMA_VERSION)
+            .map_err(|e| js_error("open", e))?;
+
+        // Runs when the database is created, before it is opened
+        let on_upgrade = Closure::<dyn FnMut()>::new({
+            let request = request.clone();
+            move || {
+                let Ok(db) = request.result() else {
+                    return;
+                };
+                let db: IdbDatabase = db.unchecked_into();
+                for store in [ENTRIES, KEYS] {
+                    if !db.object_store_names().contains(store) {
+                        // A failure aborts the upgrade, which fails the open request
+                        let _ = db.create_object_store(store);
+                    }
+                }
+            }
+        });
+        request.set_onupgradeneeded(Some(on_upgrade.as_ref().unchecked_ref()));
+        let db: IdbDatabase = settle_request(&request, "open").await?.unchecked_into();
+        request.set_onupgradeneeded(None);
+
+        // Issue every read before awaiting any, so the transaction cannot
+        // commit in between
+        let transaction = db
+            .transaction_with_str_sequence(&stores())
+            .map_err(|e| js_error("read", e))?;
+        let entries = all(object_store(&transaction, ENTRIES)?.get_all());
+        let keys = object_store(&transaction, KEYS)?;
+        let (names, values) = (all(keys.get_all_keys()), all(keys.get_all()));
+
+        let loaded = InMemory::new();
+        for value in entries.await?.iter() {
+            let stored: StoredEntry = parse(&value)?;
+            loaded.put(stored.status, stored.entry)?;
+        }
+        let names = names.await?;
+        for (name, value) in names.iter().zip(values.await?.iter()) {
+            let key: Zeroizing<[u8; 32]> = parse(&value)?;
+            let name = name.as_string().unwrap_or_default();
+            loaded.store_private_key(&name, SigningKey::from_bytes(&key))?;
+        }
+
+        let writes = Arc::new(Mutex::new(Writes::default()));
+        // Owned by JavaScript, so transactions still running when the
+        // database is dropped can call them
+        let on_complete = Closure::<dyn FnMut()>::new({
+            let writes = Arc::clone(&writes);
+            move || writes.lock().unwrap().settle(None)
+        })
+        .into_js_value()
+        .unchecked_into();
+        let on_abort = Closure::<dyn FnMut(Event)>::new({
+            let writes = Arc::clone(&writes);
+            move |event: Event| {
+                let reason = event
+                    .target()
+                    .and_then(|target| target.dyn_into::<IdbTransaction>().ok())
+                    .and_then(|transaction| transaction.error())
+                    .map_or_else(|| "transaction aborted".to_string(), |e| e.message());
+                writes.lock().unwrap().settle(Some(reason));
+            }
+        })
+        .into_js_value()
+        .unchecked_into();
+
+        Ok(Self {
+            name: name.to_string(),
+            db,
+            loaded,
+            writes,
+            on_complete,
+            on_abort,
+        })
+    }
+
+    /// Deletes the IndexedDB database `name` and everything in it.
+    ///
+    /// Waits until every open connection to the database has been closed.
+    pub async fn delete(name: &str) -> Result<()> {
+        let request = factory()?
+            .delete_database(name)
+            .map_err(|e| js_error("delete", e))?;
+        settle_request(&request, "delete").await?;
+        Ok(())
+    }
+
+    /// The name of the IndexedDB database.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Waits until every write made so far has reached IndexedDB.
+    ///
+    /// # Errors
+    /// Returns `DatabaseError::IndexedDb` if a write failed since the last
+    /// `wait_for_writes` or `flush`.
+    pub async fn wait_for_writes(&self) -> Result<()> {
+        WritesSettled {
+            writes: Arc::clone(&self.writes),
+        }
+        .await
+    }
+
+    /// Queues a change to the object stores in a new transaction.
+    fn write(&self, change: impl FnOnce(&IdbTransaction) -> Result<IdbRequest>) -> Result<()> {
+        let transaction = self
+            .db
+            .transaction_with_str_sequence_and_mode(&stores(), IdbTransactionMode::Readwrite)
+            .map_err(|e| js_error("write", e))?;
+        transaction.set_oncomplete(Some(&self.on_complete));
+        transaction.set_onabort(Some(&self.on_abort));
+        if let Err(e) = change(&transaction) {
+            let _ = transaction.abort();
+            return Err(e);
+        }
+        self.writes.lock().unwrap().pending += 1;
+        Ok(())
+    }
+
+    /// Stores the entry `id` with its current verification status.
+    fn write_entry(&self, id: &ID) -> Result<()> {
+        let stored = StoredEntry {
+            status: self.loaded.get_verification_status(id)?,
+            entry: self.loaded.get(id)?,
+        };
+        let value = serialize(&stored)?;
+        self.write(|transaction| {
+            object_store(transaction, ENTRIES)?
+                .put_with_key(&value, &JsValue::from_str(id.as_str()))
+                .map_err(|e| js_error("write", e))
+        })
+    }
+}
+
+impl Drop for IndexedDb {
+    fn drop(&mut self) {
+        self.db.close();
+    }
+}
+
+impl Database for IndexedDb {
+    fn get(&self, id: &ID) -> Result<Entry> {
+        self.loaded.get(id)
+    }
+
+    fn get_verification_status(&self, id: &ID) -> Result<VerificationStatus> {
+        self.loaded.get_verification_status(id)
+    }
+
+    fn put(&self, verification_status: VerificationStatus, entry: Entry) -> Result<()> {
+        let id = entry.id();
+        self.loaded.put(verification_status, entry)?;
+        self.write_entry(&id)
+    }
+
+    fn update_verification_status(
+        &self,
+        id: &ID,
+        verification_status: VerificationStatus,
+    ) -> Result<()> {
+        self.loaded
+            .update_verification_status(id, verification_status)?;
+        self.write_entry(id)
+    }
+
+    fn get_entries_by_verification_status(&self, status: VerificationStatus) -> Result<Vec<ID>> {
+        self.loaded.get_entries_by_verification_status(status)
+    }
+
+    fn get_tips(&self, tree: &ID) -> Result<Vec<ID>> {
+        self.loaded.get_tips(tree)
+    }
+
+    fn get_subtree_tips(&self, tree: &ID, subtree: &str) -> Result<Vec<ID>> {
+        self.loaded.get_subtree_tips(tree, subtree)
+    }
+
+    fn get_subtree_tips_up_to_entries(
+        &self,
+        tree: &ID,
+        subtree: &str,
+        main_entries: &[ID],
+    ) -> Result<Vec<ID>> {
+        self.loaded
+            .get_subtree_tips_up_to_entries(tree, subtree, main_entries)
+    }
+
+    fn all_roots(&self) -> Result<Vec<ID>> {
+        self.loaded.all_roots()
+    }
+
+    fn find_lca(&self, tree: &ID, subtree: &str, entry_ids: &[ID]) -> Result<ID> {
+        self.loaded.find_lca(tree, subtree, entry_ids)
+    }
+
+    fn collect_root_to_target(
+        &self,
+        tree: &ID,
+        subtree: &str,
+        target_entry: &ID,
+    ) -> Result<Vec<ID>> {
+        self.loaded
+            .collect_root_to_target(tree, subtree, target_entry)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn get_tree(&self, tree: &ID) -> Result<Vec<Entry>> {
+        self.loaded.get_tree(tree)
+    }
+
+    fn get_subtree(&self, tree: &ID, subtree: &str) -> Result<Vec<Entry>> {
+        self.loaded.get_subtree(tree, subtree)
+    }
+
+    fn get_tree_iter(&self, tree: &ID) -> Result<EntryIter<'_>> {
+        self.loaded.get_tree_iter(tree)
+    }
+
+    fn get_subtree_iter(&self, tree: &ID, subtree: &str) -> Result<EntryIter<'_>> {
+        self.loaded.get_subtree_iter(tree, subtree)
+    }
+
+    fn get_tree_from_tips(&self, tree: &ID, tips: &[ID]) -> Result<Vec<Entry>> {
+        self.loaded.get_tree_from_tips(tree, tips)
+    }
+
+    fn get_subtree_from_tips(&self, tree: &ID, subtree: &str, tips: &[ID]) -> Result<Vec<Entry>> {
+        self.loaded.get_subtree_from_tips(tree, subtree, tips)
+    }
+
+    fn store_private_key(&self, key_name: &str, private_key: SigningKey) -> Result<()> {
+        let value = serialize(&Zeroizing::new(private_key.to_bytes()))?;
+        self.loaded.store_private_key(key_name, private_key)?;
+        self.write(|transaction| {
+            object_store(transaction, KEYS)?
+                .put_with_key(&value, &JsValue::from_str(key_name))
+                .map_err(|e| js_error("write", e))
+        })
+    }
+
+    fn get_private_key(&self, key_name: &str) -> Result<Option<SigningKey>> {
+        self.loaded.get_private_key(key_name)
+    }
+
+    fn list_private_keys(&self) -> Result<Vec<String>> {
+        self.loaded.list_private_keys()
+    }
+
+    fn remove_private_key(&self, key_name: &str) -> Result<()> {
+        self.loaded.remove_private_key(key_name)?;
+        self.write(|transaction| {
+            object_store(transaction, KEYS)?
+                .delete(&JsValue::from_str(key_name))
+                .map_err(|e| js_error("write", e))
+        })
+    }
+
+    fn key_storage(&self) -> KeyStorage {
+        KeyStorage::Unencrypted
+    }
+
+    /// Reports a write that failed since the last flush.
+    ///
+    /// Writes are queued to IndexedDB as they are made and cannot be waited
+    /// for synchronously; use [`IndexedDb::wait_for_writes`] for that.
+    fn flush(&self) -> Result<()> {
+        self.writes.lock().unwrap().take_error()
+    }
+
+    fn get_cached_crdt_state(&self, entry_id: &ID, subtree: &str) -> Result<Option<String>> {
+        self.loaded.get_cached_crdt_state(entry_id, subtree)
+    }
+
+    fn cache_crdt_state(&self, entry_id: &ID, subtree: &str, state: String) -> Result<()> {
+        self.loaded.cache_crdt_state(entry_id, subtree, state)
+    }
+
+    fn clear_crdt_cache(&self) -> Result<()> {
+        self.loaded.clear_crdt_cache()
+    }
+
+    fn get_sorted_subtree_parents(
+        &self,
+        tree_id: &ID,
+        entry_id: &ID,
+        subtree: &str,
+    ) -> Result<Vec<ID>> {
+        self.loaded
+            .get_sorted_subtree_parents(tree_id, entry_id, subtree)
+    }
+
+    fn get_path_from_to(
+        &self,
+        tree_id: &ID,
+        subtree: &str,
+        from_id: &ID,
+        to_ids: &[ID],
+    ) -> Result<Vec<ID>> {
+        self.loaded
+            .get_path_from_to(tree_id, subtree, from_id, to_ids)
+    }
+}
+
+fn idb_error(operation: &str, reason: impl Into<String>) -> Error {
+    DatabaseError::IndexedDb {
+        operation: operation.to_string(),
+        reason: reason.into(),
+    }
+    .into()
+}
+
+/// Converts an exception thrown by an IndexedDB call.
+fn js_error(operation: &str, error: JsValue) -> Error {
+    let reason = match error.dyn_ref::<DomException>() {
+        Some(exception) => exception.message(),
+        None => error.as_string().unwrap_or_else(|| format!("{error:?}")),
+    };
+    idb_error(operation, reason)
+}
+
+/// The IndexedDB factory of the current window or worker.
+fn factory() -> Result<IdbFactory> {
+    js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("indexedDB"))
+        .ok()
+        .and_then(|factory| factory.dyn_into::<IdbFactory>().ok())
+        .ok_or_else(|| idb_error("open", "IndexedDB is not available"))
+}
+
+/// The names of all object stores, as a transaction scope.
+fn stores() -> Array {
+    Array::of2(&JsValue::from_str(ENTRIES), &JsValue::from_str(KEYS))
+}
+
+fn object_store(transaction: &IdbTransaction, name: &str) -> Result<IdbObjectStore> {
+    transaction
+        .object_store(name)
+        .map_err(|e| js_error("transaction", e))
+}
+
+fn serialize<T: Serialize>(value: &T) -> Result<JsValue> {
+    let json = serde_json::to_string(value)
+        .map_err(|e| -> Error { DatabaseError::SerializationFailed { source: e }.into() })?;
+    Ok(JsValue::from_str(&json))
+}
+
+fn parse<T: for<'de> Deserialize<'de>>(value: &JsValue) -> Result<T> {
+    let json = value
+        .as_string()
+        .ok_or_else(|| idb_error("read", "stored record is not a string"))?;
+    serde_json::from_str(&json)
+        .map_err(|e| DatabaseError::DeserializationFailed { source: e }.into())
+}
+
+/// Waits for a `getAll` or `getAllKeys` request.
+fn all(request: std::result::Result<IdbRequest, JsValue>) -> impl Future<Output = Result<Array>> {
+    let settled = request
+        .map_err(|e| js_error("read", e))
+        .map(|request| settle_request(&request, "read"));
+    async move { Ok(settled?.await?.unchecked_into()) }
+}
+
+/// Waits for a request to succeed or fail and returns its result.
+///
+/// The callbacks are installed before this returns, so requests made together
+/// can be awaited one after the other without missing their events.
+fn settle_request(
+    request: &IdbRequest,
+    operation: &str,
+) -> impl Future<Output = Result<JsValue>> + use<> {
+    let settled = Settled::new(|on_success, on_error| {
+        request.set_onsuccess(Some(on_success));
+        request.set_onerror(Some(on_error));
+    });
+    let request = request.clone();
+    let operation = operation.to_string();
+    async move {
+        let succeeded = settled.await;
+        request.set_onsuccess(None);
+        request.set_onerror(None);
+
+        if succeeded {
+            request.result().map_err(|e| js_error(&operation, e))
+        } else {
+            let reason = match request.error() {
+                Ok(Some(exception)) => exception.message(),
+                _ => "request failed".to_string(),
+            };
+            Err(idb_error(&operation, reason))
+        }
+    }
+}
+
+#[derive(Default)]
+struct SettledState {
+    succeeded: Option<bool>,
+    waker: Option<Waker>,
+}
+
+/// Resolves to whether the success or the error callback of an IndexedDB
+/// request fired first.
+///
+/// The callbacks are dropped with the future, so it must be kept until the
+/// request fires one of them.
+struct Settled {
+    state: Rc<RefCell<SettledState>>,
+    _callbacks: [Closure<dyn FnMut()>; 2],
+}
+
+impl Settled {
+    /// Creates the callbacks and passes them to `install`: on success, then on error.
+    fn new(install: impl FnOnce(&js_sys::Function, &js_sys::Function)) -> Self {
+        let state = Rc::new(RefCell::new(SettledState::default()));
+        let callback = |succeeded: bool| {
+            let state = Rc::clone(&state);
+            Closure::<dyn FnMut()>::new(move || {
+                let mut state = state.borrow_mut();
+                state.succeeded.get_or_insert(succeeded);
+                if let Some(waker) = state.waker.take() {
+                    waker.wake();
+                }
+            })
+        };
+        let callbacks = [callback(true), callback(false)];
+        install(
+            callbacks[0].as_ref().unchecked_ref(),
+            callbacks[1].as_ref().unchecked_ref(),
+        );
+        Self {
+            state,
+            _callbacks: callbacks,
+        }
+    }
+}
+
+impl Future for Settled {
+    type Output = bool;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<bool> {
+        let mut state = self.state.borrow_mut();
+        match state.succeeded {
+            Some(succeeded) => Poll::Ready(succeeded),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Resolves once no writes are pending.
+struct WritesSettled {
+    writes: Arc<Mutex<Writes>>,
+}
+
+impl Future for WritesSettled {
+    type Output = Result<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        let mut writes = self.writes.lock().unwrap();
+        if writes.pending == 0 {
+            Poll::Ready(writes.take_error())
+        } else {
+            writes.waiters.push(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
@@ -0,0 +1,246 @@
+//! Instrumented database backend: times the operations of another backend
+//!
+//! This module provides `Instrumented`, a `Database` that passes every call
+//! through to the database it wraps and reports how long puts, gets and tip
+//! calculations took to the hooks and metrics of the
+//! [`instrument`](crate::instrument) module. With the `tracing` feature, the
+//! same calls are also traced as spans.
+
+use crate::Result;
+use crate::backend::{CacheStats, Database, EntryIter, KeyStorage, PruneStats, VerificationStatus};
+use crate::entry::{Entry, ID};
+use crate::instrument::{Operation, timed};
+use ed25519_dalek::SigningKey;
+use std::any::Any;
+
+/// A database that measures the operations of the database it wraps.
+///
+/// Puts and gets are reported with the tree of the entry when it is known, and
+/// tip calculations with their tree and subtree. All other calls are passed
+/// through unmeasured.
+///
+/// `BaseDB::backend` returns the wrapper, so code that downcasts the backend
+/// to its concrete type must downcast to `Instrumented<D>` and use
+/// [`inner`](Self::inner).
+///
+/// # Example
+/// ```
+/// # use eidetica::backend::database::{InMemory, Instrumented};
+/// # use eidetica::basedb::BaseDB;
+/// let db = BaseDB::new(Box::new(Instrumented::new(InMemory::new())));
+/// let in_memory = db
+///     .backend()
+///     .as_any()
+///     .downcast_ref::<Instrumented<InMemory>>()
+///     .unwrap()
+///     .inner();
+/// ```
+#[derive(Debug)]
+pub struct Instrumented<D> {
+    inner: D,
+}
+
+impl<D: Database> Instrumented<D> {
+    /// Wraps `inner`, measuring its operations.
+    pub fn new(inner: D) -> Self {
+        Self { inner }
+    }
+
+    /// The wrapped database.
+    pub fn inner(&self) -> &D {
+        &self.inner
+    }
+
+    /// Unwraps the database.
+    pub fn into_inner(self) -> D {
+        self.inner
+    }
+}
+
+impl<D: Database> Database for Instrumented<D> {
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all, fields(entry = %id)))]
+    fn get(&self, id: &ID) -> Result<Entry> {
+        timed(Operation::Get, None, None, || self.inner.get(id))
+    }
+
+    fn get_verification_status(&self, id: &ID) -> Result<VerificationStatus> {
+        self.inner.get_verification_status(id)
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip_all, fields(entry = %entry.id(), ?verification_status))
+    )]
+    fn put(&self, verification_status: VerificationStatus, entry: Entry) -> Result<()> {
+        // Root entries are reported without a tree
+        let tree = Some(entry.root()).filter(|tree| !tree.is_empty());
+        timed(Operation::Put, tree.as_ref(), None, || {
+            self.inner.put(verification_status, entry)
+        })
+    }
+
+    fn update_verification_status(
+        &self,
+        id: &ID,
+        verification_status: VerificationStatus,
+    ) -> Result<()> {
+        self.inner
+            .update_verification_status(id, verification_status)
+    }
+
+    fn get_entries_by_verification_status(&self, status: VerificationStatus) -> Result<Vec<ID>> {
+        self.inner.get_entries_by_verification_status(status)
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all, fields(%tree)))]
+    fn get_tips(&self, tree: &ID) -> Result<Vec<ID>> {
+        timed(Operation::Tips, Some(tree), None, || {
+            self.inner.get_tips(tree)
+        })
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip_all, fields(%tree, subtree))
+    )]
+    fn get_subtree_tips(&self, tree: &ID, subtree: &str) -> Result<Vec<ID>> {
+        timed(Operation::Tips, Some(tree), Some(subtree), || {
+            self.inner.get_subtree_tips(tree, subtree)
+        })
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip_all, fields(%tree, subtree))
+    )]
+    fn get_subtree_tips_up_to_entries(
+        &self,
+        tree: &ID,
+        subtree: &str,
+        main_entries: &[ID],
+    ) -> Result<Vec<ID>> {
+        timed(Operation::Tips, Some(tree), Some(subtree), || {
+            self.inner
+                .get_subtree_tips_up_to_entries(tree, subtree, main_entries)
+        })
+    }
+
+    fn all_roots(&self) -> Result<Vec<ID>> {
+        self.inner.all_roots()
+    }
+
+    fn find_lca(&self, tree: &ID, subtree: &str, entry_ids: &[ID]) -> Result<ID> {
+        self.inner.find_lca(tree, subtree, entry_ids)
+    }
+
+    fn collect_root_to_target(
+        &self,
+        tree: &ID,
+        subtree: &str,
+        target_entry: &ID,
+    ) -> Result<Vec<ID>> {
+        self.inner
+            .collect_root_to_target(tree, subtree, target_entry)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn get_tree(&self, tree: &ID) -> Result<Vec<Entry>> {
+        self.inner.get_tree(tree)
+    }
+
+    fn get_subtree(&self, tree: &ID, subtree: &str) -> Result<Vec<Entry>> {
+        self.inner.get_subtree(tree, subtree)
+    }
+
+    fn get_tree_iter(&self, tree: &ID) -> Result<EntryIter<'_>> {
+        self.inner.get_tree_iter(tree)
+    }
+
+    fn get_subtree_iter(&self, tree: &ID, subtree: &str) -> Result<EntryIter<'_>> {
+        self.inner.get_subtree_iter(tree, subtree)
+    }
+
+    fn get_tree_from_tips(&self, tree: &ID, tips: &[ID]) -> Result<Vec<Entry>> {
+        self.inner.get_tree_from_tips(tree, tips)
+    }
+
+    fn get_subtree_from_tips(&self, tree: &ID, subtree: &str, tips: &[ID]) -> Result<Vec<Entry>> {
+        self.inner.get_subtree_from_tips(tree, subtree, tips)
+    }
+
+    fn get_height(&self, tree: &ID, id: &ID) -> Result<usize> {
+        self.inner.get_height(tree, id)
+    }
+
+    fn store_private_key(&self, key_name: &str, private_key: SigningKey) -> Result<()> {
+        self.inner.store_private_key(key_name, private_key)
+    }
+
+    fn get_private_key(&self, key_name: &str) -> Result<Option<SigningKey>> {
+        self.inner.get_private_key(key_name)
+    }
+
+    fn list_private_keys(&self) -> Result<Vec<String>> {
+        self.inner.list_private_keys()
+    }
+
+    fn remove_private_key(&self, key_name: &str) -> Result<()> {
+        self.inner.remove_private_key(key_name)
+    }
+
+    fn key_storage(&self) -> KeyStorage {
+        self.inner.key_storage()
+    }
+
+    fn cache_stats(&self) -> Option<CacheStats> {
+        self.inner.cache_stats()
+    }
+
+    fn flush(&self) -> Result<()> {
+        self.inner.flush()
+    }
+
+    fn prune(&self, tree: &ID, keep_tips: &[ID]) -> Result<PruneStats> {
+        self.inner.prune(tree, keep_tips)
+    }
+
+    fn remove_entries(&self, tree: &ID, entries: &[ID]) -> Result<PruneStats> {
+        self.inner.remove_entries(tree, entries)
+    }
+
+    fn get_cached_crdt_state(&self, entry_id: &ID, subtree: &str) -> Result<Option<String>> {
+        self.inner.get_cached_crdt_state(entry_id, subtree)
+    }
+
+    fn cache_crdt_state(&self, entry_id: &ID, subtree: &str, state: String) -> Result<()> {
+        self.inner.cache_crdt_state(entry_id, subtree, state)
+    }
+
+    fn clear_crdt_cache(&self) -> Result<()> {
+        self.inner.clear_crdt_cache()
+    }
+
+    fn get_sorted_subtree_parents(
+        &self,
+        tree_id: &ID,
+        entry_id: &ID,
+        subtree: &str,
+    ) -> Result<Vec<ID>> {
+        self.inner
+            .get_sorted_subtree_parents(tree_id, entry_id, subtree)
+    }
+
+    fn get_path_from_to(
+        &self,
+        tree_id: &ID,
+        subtree: &str,
+        from_id: &ID,
+        to_ids: &[ID],
+    ) -> Result<Vec<ID>> {
+        self.inner
+            .get_path_from_to(tree_id, subtree, from_id, to_ids)
+    }
+}
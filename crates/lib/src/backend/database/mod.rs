@@ -2,6 +2,28 @@
 //!
 //! These backends provide persistent, queryable storage similar to traditional databases.
 
+#[cfg(feature = "archive")]
+mod archive;
+#[cfg(not(target_arch = "wasm32"))]
+mod format;
 mod in_memory;
+#[cfg(feature = "indexeddb")]
+mod indexeddb;
+mod instrumented;
+mod sparse;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+mod tiered;
 
+#[cfg(feature = "archive")]
+pub use archive::Archive;
+#[cfg(not(target_arch = "wasm32"))]
+pub use format::StorageFormat;
 pub use in_memory::InMemory;
+#[cfg(feature = "indexeddb")]
+pub use indexeddb::IndexedDb;
+pub use instrumented::Instrumented;
+pub use sparse::Sparse;
+#[cfg(feature = "sqlite")]
+pub use sqlite::Sqlite;
+pub use tiered::Tiered;
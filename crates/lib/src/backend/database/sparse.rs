@@ -0,0 +1,286 @@
+//! Sparse database backend holding part of a single tree
+//!
+//! This module provides `Sparse`, a `Database` that loads only the entries of
+//! a tree that write to a chosen set of subtrees, for devices that cannot
+//! afford to hold whole trees in memory. Entries are fetched from another
+//! backend once, when the sparse database is created, and kept in an
+//! [`InMemory`] database; writes go to both.
+
+use super::InMemory;
+use crate::Result;
+use crate::backend::errors::DatabaseError;
+use crate::backend::{Database, EntryIter, KeyStorage, VerificationStatus};
+use crate::constants::SETTINGS;
+use crate::entry::{Entry, ID};
+use ed25519_dalek::SigningKey;
+use std::any::Any;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+/// A database backend holding the entries of one tree that participate in a
+/// chosen set of subtrees.
+///
+/// The root entry and the `_settings` subtree are always loaded, since they are
+/// needed to authenticate everything else. Queries about any other subtree fail
+/// with `DatabaseError::SubtreeNotLoaded`, and entries outside the loaded
+/// subtrees are not found.
+///
+/// Entries put into the database are stored in the source database first and
+/// then cached if they write to a loaded subtree. Tree tips are the tips of the
+/// loaded entries, so new entries build on the latest entries of the loaded
+/// subtrees, not necessarily on the tips of the full tree. Private keys are
+/// kept in the source database; computed CRDT states are cached in memory only.
+///
+/// Usually created with [`Tree::load_sparse`](crate::Tree::load_sparse).
+pub struct Sparse {
+    source: Arc<dyn Database>,
+    tree: ID,
+    subtrees: BTreeSet<String>,
+    loaded: InMemory,
+}
+
+impl std::fmt::Debug for Sparse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Sparse")
+            .field("tree", &self.tree)
+            .field("subtrees", &self.subtrees)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Sparse {
+    /// Loads the entries of `tree` that write to any of `subtrees` from `source`.
+    ///
+    /// Verification statuses are copied from `source`.
+    ///
+    /// # Errors
+    /// Returns `DatabaseError::EntryNotFound` if `source` does not have the tree.
+    pub fn load(source: Arc<dyn Database>, tree: &ID, subtrees: &[&str]) -> Result<Self> {
+        let mut names: BTreeSet<String> = subtrees.iter().map(|name| name.to_string()).collect();
+        names.insert(SETTINGS.to_string());
+
+        let loaded = InMemory::new();
+        let root = source.get(tree)?;
+        loaded.put(source.get_verification_status(tree)?, root)?;
+        for name in &names {
+            for entry in source.get_subtree(tree, name)? {
+                let id = entry.id();
+                if loaded.get(&id).is_err() {
+                    loaded.put(source.get_verification_status(&id)?, entry)?;
+                }
+            }
+        }
+
+        Ok(Self {
+            source,
+            tree: tree.clone(),
+            subtrees: names,
+            loaded,
+        })
+    }
+
+    /// The tree this database holds part of.
+    pub fn tree(&self) -> &ID {
+        &self.tree
+    }
+
+    /// The loaded subtrees, including `_settings`.
+    pub fn subtrees(&self) -> impl Iterator<Item = &str> {
+        self.subtrees.iter().map(String::as_str)
+    }
+
+    /// Returns true if the entries of `subtree` are loaded.
+    pub fn is_loaded(&self, subtree: &str) -> bool {
+        self.subtrees.contains(subtree)
+    }
+
+    /// Fails unless `subtree` of `tree` is loaded.
+    fn check(&self, tree: &ID, subtree: &str) -> Result<()> {
+        if *tree == self.tree && self.is_loaded(subtree) {
+            Ok(())
+        } else {
+            Err(DatabaseError::SubtreeNotLoaded {
+                tree_id: tree.clone(),
+                subtree: subtree.to_string(),
+            }
+            .into())
+        }
+    }
+}
+
+impl Database for Sparse {
+    fn get(&self, id: &ID) -> Result<Entry> {
+        self.loaded.get(id)
+    }
+
+    fn get_verification_status(&self, id: &ID) -> Result<VerificationStatus> {
+        self.loaded.get_verification_status(id)
+    }
+
+    /// Stores the entry in the source database, and caches it if it belongs
+    /// to this tree and writes to a loaded subtree.
+    fn put(&self, verification_status: VerificationStatus, entry: Entry) -> Result<()> {
+        self.source.put(verification_status, entry.clone())?;
+        let cached = entry.in_tree(&self.tree)
+            && entry
+                .subtrees()
+                .iter()
+                .any(|name| self.subtrees.contains(name));
+        if cached {
+            self.loaded.put(verification_status, entry)?;
+        }
+        Ok(())
+    }
+
+    fn update_verification_status(
+        &self,
+        id: &ID,
+        verification_status: VerificationStatus,
+    ) -> Result<()> {
+        self.source
+            .update_verification_status(id, verification_status)?;
+        if self.loaded.get(id).is_ok() {
+            self.loaded
+                .update_verification_status(id, verification_status)?;
+        }
+        Ok(())
+    }
+
+    fn get_entries_by_verification_status(&self, status: VerificationStatus) -> Result<Vec<ID>> {
+        self.loaded.get_entries_by_verification_status(status)
+    }
+
+    fn get_tips(&self, tree: &ID) -> Result<Vec<ID>> {
+        self.loaded.get_tips(tree)
+    }
+
+    fn get_subtree_tips(&self, tree: &ID, subtree: &str) -> Result<Vec<ID>> {
+        self.check(tree, subtree)?;
+        self.loaded.get_subtree_tips(tree, subtree)
+    }
+
+    fn get_subtree_tips_up_to_entries(
+        &self,
+        tree: &ID,
+        subtree: &str,
+        main_entries: &[ID],
+    ) -> Result<Vec<ID>> {
+        self.check(tree, subtree)?;
+        self.loaded
+            .get_subtree_tips_up_to_entries(tree, subtree, main_entries)
+    }
+
+    fn all_roots(&self) -> Result<Vec<ID>> {
+        self.loaded.all_roots()
+    }
+
+    fn find_lca(&self, tree: &ID, subtree: &str, entry_ids: &[ID]) -> Result<ID> {
+        self.check(tree, subtree)?;
+        self.loaded.find_lca(tree, subtree, entry_ids)
+    }
+
+    fn collect_root_to_target(
+        &self,
+        tree: &ID,
+        subtree: &str,
+        target_entry: &ID,
+    ) -> Result<Vec<ID>> {
+        self.check(tree, subtree)?;
+        self.loaded
+            .collect_root_to_target(tree, subtree, target_entry)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn get_tree(&self, tree: &ID) -> Result<Vec<Entry>> {
+        self.loaded.get_tree(tree)
+    }
+
+    fn get_subtree(&self, tree: &ID, subtree: &str) -> Result<Vec<Entry>> {
+        self.check(tree, subtree)?;
+        self.loaded.get_subtree(tree, subtree)
+    }
+
+    fn get_tree_iter(&self, tree: &ID) -> Result<EntryIter<'_>> {
+        self.loaded.get_tree_iter(tree)
+    }
+
+    fn get_subtree_iter(&self, tree: &ID, subtree: &str) -> Result<EntryIter<'_>> {
+        self.check(tree, subtree)?;
+        self.loaded.get_subtree_iter(tree, subtree)
+    }
+
+    fn get_tree_from_tips(&self, tree: &ID, tips: &[ID]) -> Result<Vec<Entry>> {
+        self.loaded.get_tree_from_tips(tree, tips)
+    }
+
+    fn get_subtree_from_tips(&self, tree: &ID, subtree: &str, tips: &[ID]) -> Result<Vec<Entry>> {
+        self.check(tree, subtree)?;
+        self.loaded.get_subtree_from_tips(tree, subtree, tips)
+    }
+
+    fn get_height(&self, tree: &ID, id: &ID) -> Result<usize> {
+        self.loaded.get_height(tree, id)
+    }
+
+    fn store_private_key(&self, key_name: &str, private_key: SigningKey) -> Result<()> {
+        self.source.store_private_key(key_name, private_key)
+    }
+
+    fn get_private_key(&self, key_name: &str) -> Result<Option<SigningKey>> {
+        self.source.get_private_key(key_name)
+    }
+
+    fn list_private_keys(&self) -> Result<Vec<String>> {
+        self.source.list_private_keys()
+    }
+
+    fn remove_private_key(&self, key_name: &str) -> Result<()> {
+        self.source.remove_private_key(key_name)
+    }
+
+    fn key_storage(&self) -> KeyStorage {
+        self.source.key_storage()
+    }
+
+    fn flush(&self) -> Result<()> {
+        self.source.flush()
+    }
+
+    fn get_cached_crdt_state(&self, entry_id: &ID, subtree: &str) -> Result<Option<String>> {
+        self.loaded.get_cached_crdt_state(entry_id, subtree)
+    }
+
+    fn cache_crdt_state(&self, entry_id: &ID, subtree: &str, state: String) -> Result<()> {
+        self.loaded.cache_crdt_state(entry_id, subtree, state)
+    }
+
+    fn clear_crdt_cache(&self) -> Result<()> {
+        self.loaded.clear_crdt_cache()
+    }
+
+    fn get_sorted_subtree_parents(
+        &self,
+        tree_id: &ID,
+        entry_id: &ID,
+        subtree: &str,
+    ) -> Result<Vec<ID>> {
+        self.check(tree_id, subtree)?;
+        self.loaded
+            .get_sorted_subtree_parents(tree_id, entry_id, subtree)
+    }
+
+    fn get_path_from_to(
+        &self,
+        tree_id: &ID,
+        subtree: &str,
+        from_id: &ID,
+        to_ids: &[ID],
+    ) -> Result<Vec<ID>> {
+        self.check(tree_id, subtree)?;
+        self.loaded
+            .get_path_from_to(tree_id, subtree, from_id, to_ids)
+    }
+}
@@ -0,0 +1,309 @@
+//! SQLite database backend implementation
+//!
+//! This module provides a `Database` implementation that persists entries in a
+//! SQLite database. It is intended for applications that already embed SQLite,
+//! or that want durable storage without explicit save/load steps.
+//!
+//! Entries are stored as JSON together with their verification status. Parent
+//! edges, subtree membership and tips are indexed in separate tables that are
+//! maintained on every `put`, so tip lookups do not require scanning the tree.
+//! With a [`Compression`] configured, entry JSON is stored compressed whenever
+//! that makes it smaller. Recently read entries are kept decoded in an LRU cache
+//! shared by all traversals, see [`Sqlite::with_entry_cache`].
+//!
+//! This module is only available when the "sqlite" feature is enabled.
+
+mod storage;
+mod traversal;
+
+use crate::Result;
+use crate::backend::cache::EntryCache;
+use crate::backend::{
+    CacheStats, Compression, Database, EntryIter, KeyStorage, PruneStats, VerificationStatus,
+    stream_entries,
+};
+use crate::entry::{Entry, ID};
+use ed25519_dalek::SigningKey;
+use rusqlite::Connection;
+use std::any::Any;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+
+/// A database backend that stores entries in a SQLite database.
+///
+/// All data lives in the SQLite file, so there is no separate save step: every
+/// `put` is committed in its own transaction. The semantics of tips, heights and
+/// traversal match the [`InMemory`](super::InMemory) backend.
+///
+/// **Security Note**: Private keys are stored unencrypted in the database file.
+/// Protect the file accordingly, or keep signing keys in a separate backend.
+#[derive(Debug)]
+pub struct Sqlite {
+    /// Connection to the underlying SQLite database
+    pub(crate) conn: Mutex<Connection>,
+    /// Compression applied to the data of newly stored entries
+    pub(crate) compression: Compression,
+    /// Recently read entries, decoded
+    pub(crate) entry_cache: EntryCache,
+    /// Heights of entries found by `get_height`
+    pub(crate) heights: Mutex<HashMap<ID, usize>>,
+}
+
+impl Sqlite {
+    /// Opens (or creates) a SQLite database at the given path.
+    ///
+    /// The schema is created if it does not exist yet.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the SQLite database file.
+    ///
+    /// # Returns
+    /// A `Result` containing the opened database or a `DatabaseError::Sqlite` error.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let conn = Connection::open(path).map_err(storage::sql_err)?;
+        Self::from_connection(conn)
+    }
+
+    /// Creates a new database backed by a private, in-memory SQLite instance.
+    ///
+    /// Mostly useful for testing; data is lost when the value is dropped.
+    pub fn open_in_memory() -> Result<Self> {
+        let conn = Connection::open_in_memory().map_err(storage::sql_err)?;
+        Self::from_connection(conn)
+    }
+
+    /// Wraps an existing SQLite connection, creating the schema if needed.
+    ///
+    /// This allows sharing configuration (pragmas, encryption extensions, etc.)
+    /// with an application that already manages its own connections. Eidetica's
+    /// tables are created alongside any existing tables.
+    pub fn from_connection(conn: Connection) -> Result<Self> {
+        storage::init_schema(&conn)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+            compression: Compression::None,
+            entry_cache: EntryCache::default(),
+            heights: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Sets the compression applied to the data of entries stored from now on.
+    ///
+    /// Entries are read back whichever compression they were stored with, so
+    /// this can be changed for an existing database at any time.
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Sets how many decoded entries are kept in memory.
+    ///
+    /// Every read of an entry otherwise decodes it from its stored form. The
+    /// most recently read entries are kept, up to
+    /// [`DEFAULT_ENTRY_CACHE_CAPACITY`](crate::backend::DEFAULT_ENTRY_CACHE_CAPACITY)
+    /// by default; a capacity of zero disables the cache.
+    pub fn with_entry_cache(mut self, capacity: usize) -> Self {
+        self.entry_cache = EntryCache::new(capacity);
+        self
+    }
+
+    /// Returns the compression applied to newly stored entries.
+    pub fn compression(&self) -> Compression {
+        self.compression
+    }
+
+    /// Returns a vector containing the IDs of all entries currently stored in the database.
+    pub fn all_ids(&self) -> Result<Vec<ID>> {
+        storage::all_ids(self)
+    }
+
+    /// Calculate heights for entries in a tree or subtree
+    ///
+    /// # Arguments
+    /// * `tree` - The ID of the tree to calculate heights for
+    /// * `subtree` - Optional subtree name to limit calculation to a specific subtree
+    ///
+    /// # Returns
+    /// A `Result` containing a `HashMap` mapping entry IDs to their heights.
+    pub fn calculate_heights(
+        &self,
+        tree: &ID,
+        subtree: Option<&str>,
+    ) -> Result<HashMap<ID, usize>> {
+        traversal::calculate_heights(self, tree, subtree)
+    }
+
+    /// Check if an entry is a tip within its tree
+    ///
+    /// An entry is a tip if no other entry in the same tree lists it as a parent.
+    pub fn is_tip(&self, tree: &ID, entry_id: &ID) -> Result<bool> {
+        storage::is_tip(self, tree, None, entry_id)
+    }
+
+    /// Locks and returns the underlying connection.
+    pub(crate) fn conn(&self) -> MutexGuard<'_, Connection> {
+        self.conn.lock().unwrap()
+    }
+}
+
+impl Database for Sqlite {
+    fn get(&self, id: &ID) -> Result<Entry> {
+        storage::get(self, id)
+    }
+
+    fn get_verification_status(&self, id: &ID) -> Result<VerificationStatus> {
+        storage::get_verification_status(self, id)
+    }
+
+    fn put(&self, verification_status: VerificationStatus, entry: Entry) -> Result<()> {
+        storage::put(self, verification_status, entry)
+    }
+
+    fn update_verification_status(
+        &self,
+        id: &ID,
+        verification_status: VerificationStatus,
+    ) -> Result<()> {
+        storage::update_verification_status(self, id, verification_status)
+    }
+
+    fn get_entries_by_verification_status(&self, status: VerificationStatus) -> Result<Vec<ID>> {
+        storage::get_entries_by_verification_status(self, status)
+    }
+
+    fn get_tips(&self, tree: &ID) -> Result<Vec<ID>> {
+        storage::get_tips(self, tree, None)
+    }
+
+    fn get_subtree_tips(&self, tree: &ID, subtree: &str) -> Result<Vec<ID>> {
+        storage::get_tips(self, tree, Some(subtree))
+    }
+
+    fn get_subtree_tips_up_to_entries(
+        &self,
+        tree: &ID,
+        subtree: &str,
+        main_entries: &[ID],
+    ) -> Result<Vec<ID>> {
+        traversal::get_subtree_tips_up_to_entries(self, tree, subtree, main_entries)
+    }
+
+    fn all_roots(&self) -> Result<Vec<ID>> {
+        storage::all_roots(self)
+    }
+
+    fn find_lca(&self, tree: &ID, subtree: &str, entry_ids: &[ID]) -> Result<ID> {
+        traversal::find_lca(self, tree, subtree, entry_ids)
+    }
+
+    fn collect_root_to_target(
+        &self,
+        tree: &ID,
+        subtree: &str,
+        target_entry: &ID,
+    ) -> Result<Vec<ID>> {
+        traversal::build_path_from_root(self, tree, subtree, target_entry)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn prune(&self, tree: &ID, keep_tips: &[ID]) -> Result<PruneStats> {
+        storage::prune(self, tree, keep_tips)
+    }
+
+    fn remove_entries(&self, tree: &ID, entries: &[ID]) -> Result<PruneStats> {
+        storage::remove_entries(self, tree, entries)
+    }
+
+    fn get_tree(&self, tree: &ID) -> Result<Vec<Entry>> {
+        traversal::get_tree(self, tree)
+    }
+
+    fn get_subtree(&self, tree: &ID, subtree: &str) -> Result<Vec<Entry>> {
+        traversal::get_subtree(self, tree, subtree)
+    }
+
+    fn get_tree_iter(&self, tree: &ID) -> Result<EntryIter<'_>> {
+        Ok(stream_entries(
+            self,
+            traversal::sorted_ids(self, tree, None)?,
+        ))
+    }
+
+    fn get_subtree_iter(&self, tree: &ID, subtree: &str) -> Result<EntryIter<'_>> {
+        Ok(stream_entries(
+            self,
+            traversal::sorted_ids(self, tree, Some(subtree))?,
+        ))
+    }
+
+    fn get_tree_from_tips(&self, tree: &ID, tips: &[ID]) -> Result<Vec<Entry>> {
+        traversal::get_tree_from_tips(self, tree, tips)
+    }
+
+    fn get_subtree_from_tips(&self, tree: &ID, subtree: &str, tips: &[ID]) -> Result<Vec<Entry>> {
+        traversal::get_subtree_from_tips(self, tree, subtree, tips)
+    }
+
+    fn get_height(&self, tree: &ID, id: &ID) -> Result<usize> {
+        traversal::get_height(self, tree, id)
+    }
+
+    fn store_private_key(&self, key_name: &str, private_key: SigningKey) -> Result<()> {
+        storage::store_private_key(self, key_name, private_key)
+    }
+
+    fn get_private_key(&self, key_name: &str) -> Result<Option<SigningKey>> {
+        storage::get_private_key(self, key_name)
+    }
+
+    fn list_private_keys(&self) -> Result<Vec<String>> {
+        storage::list_private_keys(self)
+    }
+
+    fn remove_private_key(&self, key_name: &str) -> Result<()> {
+        storage::remove_private_key(self, key_name)
+    }
+
+    fn key_storage(&self) -> KeyStorage {
+        KeyStorage::Unencrypted
+    }
+
+    fn cache_stats(&self) -> Option<CacheStats> {
+        Some(self.entry_cache.stats())
+    }
+
+    fn get_cached_crdt_state(&self, entry_id: &ID, subtree: &str) -> Result<Option<String>> {
+        storage::get_cached_crdt_state(self, entry_id, subtree)
+    }
+
+    fn cache_crdt_state(&self, entry_id: &ID, subtree: &str, state: String) -> Result<()> {
+        storage::cache_crdt_state(self, entry_id, subtree, state)
+    }
+
+    fn clear_crdt_cache(&self) -> Result<()> {
+        storage::clear_crdt_cache(self)
+    }
+
+    fn get_sorted_subtree_parents(
+        &self,
+        tree_id: &ID,
+        entry_id: &ID,
+        subtree: &str,
+    ) -> Result<Vec<ID>> {
+        traversal::get_sorted_subtree_parents(self, tree_id, entry_id, subtree)
+    }
+
+    fn get_path_from_to(
+        &self,
+        tree_id: &ID,
+        subtree: &str,
+        from_id: &ID,
+        to_ids: &[ID],
+    ) -> Result<Vec<ID>> {
+        traversal::get_path_from_to(self, tree_id, subtree, from_id, to_ids)
+    }
+}
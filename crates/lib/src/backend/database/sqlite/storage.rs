@@ -0,0 +1,753 @@
+//! Schema and core storage operations for the SQLite database
+
+use super::Sqlite;
+use crate::backend::compression::Compression;
+use crate::backend::encoding;
+use crate::backend::errors::DatabaseError;
+use crate::backend::prune::{entry_size, retained_entries};
+use crate::backend::{PruneStats, VerificationStatus};
+use crate::entry::{Entry, ID, MAX_ENTRY_SIZE};
+use crate::{Error, Result};
+use ed25519_dalek::SigningKey;
+use rusqlite::types::Value;
+use rusqlite::{Connection, OptionalExtension, Transaction, params};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use zeroize::Zeroizing;
+
+/// Columns identifying a DAG in the `parents` and `tips` tables.
+///
+/// The main tree DAG (`None`) is flagged with `is_subtree = 0` and an empty
+/// scope, subtree DAGs with `is_subtree = 1` and the subtree name, so no
+/// subtree name can collide with the tree DAG.
+fn scope_columns(subtree: Option<&str>) -> (bool, &str) {
+    (subtree.is_some(), subtree.unwrap_or_default())
+}
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS entries (
+    id TEXT PRIMARY KEY NOT NULL,
+    tree_id TEXT NOT NULL,
+    is_root INTEGER NOT NULL,
+    verification_status TEXT NOT NULL,
+    data TEXT NOT NULL,
+    refs BLOB NOT NULL
+);
+CREATE INDEX IF NOT EXISTS entries_by_tree ON entries (tree_id);
+CREATE INDEX IF NOT EXISTS entries_by_status ON entries (verification_status);
+
+CREATE TABLE IF NOT EXISTS entry_subtrees (
+    entry_id TEXT NOT NULL,
+    subtree TEXT NOT NULL,
+    PRIMARY KEY (entry_id, subtree)
+);
+CREATE INDEX IF NOT EXISTS entry_subtrees_by_subtree ON entry_subtrees (subtree);
+
+CREATE TABLE IF NOT EXISTS parents (
+    tree_id TEXT NOT NULL,
+    is_subtree INTEGER NOT NULL,
+    scope TEXT NOT NULL,
+    child TEXT NOT NULL,
+    parent TEXT NOT NULL,
+    PRIMARY KEY (tree_id, is_subtree, scope, child, parent)
+);
+CREATE INDEX IF NOT EXISTS parents_by_parent ON parents (tree_id, is_subtree, scope, parent);
+
+CREATE TABLE IF NOT EXISTS tips (
+    tree_id TEXT NOT NULL,
+    is_subtree INTEGER NOT NULL,
+    scope TEXT NOT NULL,
+    entry_id TEXT NOT NULL,
+    PRIMARY KEY (tree_id, is_subtree, scope, entry_id)
+);
+
+CREATE TABLE IF NOT EXISTS private_keys (
+    name TEXT PRIMARY KEY NOT NULL,
+    key BLOB NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS crdt_cache (
+    entry_id TEXT NOT NULL,
+    subtree TEXT NOT NULL,
+    state TEXT NOT NULL,
+    PRIMARY KEY (entry_id, subtree)
+);
+";
+
+/// Converts a SQLite error into the crate error type.
+pub(crate) fn sql_err(source: rusqlite::Error) -> Error {
+    DatabaseError::Sqlite { source }.into()
+}
+
+/// Creates all tables and indexes if they do not exist yet.
+pub(crate) fn init_schema(conn: &Connection) -> Result<()> {
+    conn.execute_batch(SCHEMA).map_err(sql_err)
+}
+
+fn status_to_sql(status: VerificationStatus) -> &'static str {
+    match status {
+        VerificationStatus::Verified => "verified",
+        VerificationStatus::Failed => "failed",
+    }
+}
+
+fn status_from_sql(id: &ID, status: &str) -> Result<VerificationStatus> {
+    match status {
+        "verified" => Ok(VerificationStatus::Verified),
+        "failed" => Ok(VerificationStatus::Failed),
+        other => Err(DatabaseError::StateInconsistency {
+            reason: format!("Unknown verification status '{other}' for entry {id}"),
+        }
+        .into()),
+    }
+}
+
+/// Decodes an entry stored as JSON without its ID references plus the compactly
+/// encoded references, see `backend::encoding`.
+///
+/// The JSON is stored as text, or as a blob when it was compressed.
+fn decode_entry(data: &Value, refs: &[u8]) -> Result<Entry> {
+    let stripped = match data {
+        Value::Text(text) => serde_json::from_str(text),
+        Value::Blob(bytes) => {
+            serde_json::from_slice(&Compression::decompress(bytes, MAX_ENTRY_SIZE)?)
+        }
+        other => {
+            return Err(DatabaseError::StateInconsistency {
+                reason: format!("Unexpected entry data type {:?}", other.data_type()),
+            }
+            .into());
+        }
+    }
+    .map_err(|e| -> Error { DatabaseError::DeserializationFailed { source: e }.into() })?;
+    encoding::decode_entry_standalone(stripped, refs)
+}
+
+/// Decodes a stored entry, or takes it from the entry cache if it is there.
+///
+/// Entries read from the file are checked with `Entry::validate_as` before they
+/// are cached.
+fn decode_cached(backend: &Sqlite, id: &ID, data: &Value, refs: &[u8]) -> Result<Entry> {
+    if let Some(entry) = backend.entry_cache.get(id) {
+        return Ok(entry);
+    }
+    let entry = decode_entry(data, refs)?;
+    entry.validate_as(id)?;
+    backend.entry_cache.insert(id, &entry);
+    Ok(entry)
+}
+
+/// Encodes an entry's JSON for storage, as a compressed blob if compression is
+/// enabled and helps, as text otherwise.
+fn encode_data(data: String, compression: Compression) -> Value {
+    match compression.compress(data.as_bytes()) {
+        Cow::Owned(compressed) => Value::Blob(compressed),
+        Cow::Borrowed(_) => Value::Text(data),
+    }
+}
+
+/// The trees whose DAG an entry participates in.
+///
+/// Regular entries belong to the tree named by their root. Root entries also
+/// anchor their own tree, mirroring `Entry::in_tree`.
+fn tree_scopes(entry: &Entry, entry_id: &ID) -> Vec<ID> {
+    let mut trees = Vec::with_capacity(2);
+    if !entry.root().is_empty() {
+        trees.push(entry.root());
+    }
+    if entry.is_root() {
+        trees.push(entry_id.clone());
+    }
+    trees
+}
+
+/// Runs a query returning `(id, data, refs)` rows and decodes the entries.
+pub(crate) fn query_entries(
+    backend: &Sqlite,
+    sql: &str,
+    params: impl rusqlite::Params,
+) -> Result<Vec<(ID, Entry)>> {
+    let conn = backend.conn();
+    let mut stmt = conn.prepare_cached(sql).map_err(sql_err)?;
+    let rows = stmt
+        .query_map(params, |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Value>(1)?,
+                row.get::<_, Vec<u8>>(2)?,
+            ))
+        })
+        .map_err(sql_err)?;
+
+    let mut entries = Vec::new();
+    for row in rows {
+        let (id, data, refs) = row.map_err(sql_err)?;
+        let id = ID::from(id);
+        let entry = decode_cached(backend, &id, &data, &refs)?;
+        entries.push((id, entry));
+    }
+    Ok(entries)
+}
+
+/// Runs a query returning a single column of IDs.
+fn query_ids(backend: &Sqlite, sql: &str, params: impl rusqlite::Params) -> Result<Vec<ID>> {
+    let conn = backend.conn();
+    let mut stmt = conn.prepare_cached(sql).map_err(sql_err)?;
+    let rows = stmt
+        .query_map(params, |row| row.get::<_, String>(0))
+        .map_err(sql_err)?;
+    rows.map(|row| row.map(ID::from).map_err(sql_err)).collect()
+}
+
+/// Retrieves an entry by ID.
+pub(crate) fn get(backend: &Sqlite, id: &ID) -> Result<Entry> {
+    if let Some(entry) = backend.entry_cache.get(id) {
+        return Ok(entry);
+    }
+    let conn = backend.conn();
+    let stored: Option<(Value, Vec<u8>)> = conn
+        .prepare_cached("SELECT data, refs FROM entries WHERE id = ?1")
+        .and_then(|mut stmt| {
+            stmt.query_row(params![id.as_str()], |row| Ok((row.get(0)?, row.get(1)?)))
+                .optional()
+        })
+        .map_err(sql_err)?;
+    drop(conn);
+
+    match stored {
+        Some((data, refs)) => {
+            let entry = decode_entry(&data, &refs)?;
+            entry.validate_as(id)?;
+            backend.entry_cache.insert(id, &entry);
+            Ok(entry)
+        }
+        None => Err(DatabaseError::EntryNotFound { id: id.clone() }.into()),
+    }
+}
+
+/// Retrieves the verification status of an entry.
+pub(crate) fn get_verification_status(backend: &Sqlite, id: &ID) -> Result<VerificationStatus> {
+    let conn = backend.conn();
+    let status: Option<String> = conn
+        .prepare_cached("SELECT verification_status FROM entries WHERE id = ?1")
+        .and_then(|mut stmt| {
+            stmt.query_row(params![id.as_str()], |row| row.get(0))
+                .optional()
+        })
+        .map_err(sql_err)?;
+
+    match status {
+        Some(status) => status_from_sql(id, &status),
+        None => Err(DatabaseError::VerificationStatusNotFound { id: id.clone() }.into()),
+    }
+}
+
+/// Stores an entry with the specified verification status.
+///
+/// The entry, its subtree memberships, parent edges and the affected tips are
+/// all written in a single transaction. Storing an entry that already exists
+/// only updates its verification status.
+pub(crate) fn put(
+    backend: &Sqlite,
+    verification_status: VerificationStatus,
+    entry: Entry,
+) -> Result<()> {
+    let entry_id = entry.id();
+    let (stripped, refs) = encoding::encode_entry_standalone(&entry);
+    let data = serde_json::to_string(&stripped)
+        .map_err(|e| -> Error { DatabaseError::SerializationFailed { source: e }.into() })?;
+
+    let mut conn = backend.conn();
+    let tx = conn.transaction().map_err(sql_err)?;
+
+    let exists = tx
+        .query_row(
+            "SELECT 1 FROM entries WHERE id = ?1",
+            params![entry_id.as_str()],
+            |_| Ok(()),
+        )
+        .optional()
+        .map_err(sql_err)?
+        .is_some();
+
+    if exists {
+        tx.execute(
+            "UPDATE entries SET verification_status = ?2 WHERE id = ?1",
+            params![entry_id.as_str(), status_to_sql(verification_status)],
+        )
+        .map_err(sql_err)?;
+    } else {
+        tx.execute(
+            "INSERT INTO entries (id, tree_id, is_root, verification_status, data, refs)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                entry_id.as_str(),
+                entry.root().as_str(),
+                entry.is_root(),
+                status_to_sql(verification_status),
+                encode_data(data, backend.compression),
+                refs
+            ],
+        )
+        .map_err(sql_err)?;
+        index_entry(&tx, &entry, &entry_id)?;
+    }
+
+    tx.commit().map_err(sql_err)
+}
+
+/// Records subtree membership, parent edges and tip changes for a new entry.
+fn index_entry(tx: &Transaction<'_>, entry: &Entry, entry_id: &ID) -> Result<()> {
+    let subtrees = entry.subtrees();
+    for subtree in &subtrees {
+        tx.execute(
+            "INSERT OR IGNORE INTO entry_subtrees (entry_id, subtree) VALUES (?1, ?2)",
+            params![entry_id.as_str(), subtree],
+        )
+        .map_err(sql_err)?;
+    }
+
+    for tree in tree_scopes(entry, entry_id) {
+        index_edges(tx, &tree, None, entry_id, &entry.parents()?)?;
+        for subtree in &subtrees {
+            index_edges(
+                tx,
+                &tree,
+                Some(subtree),
+                entry_id,
+                &entry.subtree_parents(subtree)?,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Adds the parent edges of an entry within the tree DAG or one subtree DAG and
+/// updates its tips.
+///
+/// Parents stop being tips. The entry itself becomes a tip unless a child of it
+/// was already stored, which happens when entries arrive out of order; cached
+/// CRDT states of the tree are then dropped.
+fn index_edges(
+    tx: &Transaction<'_>,
+    tree: &ID,
+    subtree: Option<&str>,
+    entry_id: &ID,
+    parents: &[ID],
+) -> Result<()> {
+    let (is_subtree, scope) = scope_columns(subtree);
+    for parent in parents {
+        tx.execute(
+            "INSERT OR IGNORE INTO parents (tree_id, is_subtree, scope, child, parent)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                tree.as_str(),
+                is_subtree,
+                scope,
+                entry_id.as_str(),
+                parent.as_str()
+            ],
+        )
+        .map_err(sql_err)?;
+        tx.execute(
+            "DELETE FROM tips WHERE tree_id = ?1 AND is_subtree = ?2 AND scope = ?3 AND entry_id = ?4",
+            params![tree.as_str(), is_subtree, scope, parent.as_str()],
+        )
+        .map_err(sql_err)?;
+    }
+
+    let has_children = tx
+        .query_row(
+            "SELECT 1 FROM parents
+             WHERE tree_id = ?1 AND is_subtree = ?2 AND scope = ?3 AND parent = ?4 LIMIT 1",
+            params![tree.as_str(), is_subtree, scope, entry_id.as_str()],
+            |_| Ok(()),
+        )
+        .optional()
+        .map_err(sql_err)?
+        .is_some();
+
+    if has_children {
+        // States cached while this entry was missing are incomplete
+        tx.execute(
+            "DELETE FROM crdt_cache WHERE entry_id IN (SELECT id FROM entries WHERE tree_id = ?1)",
+            params![tree.as_str()],
+        )
+        .map_err(sql_err)?;
+    } else {
+        tx.execute(
+            "INSERT OR IGNORE INTO tips (tree_id, is_subtree, scope, entry_id)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![tree.as_str(), is_subtree, scope, entry_id.as_str()],
+        )
+        .map_err(sql_err)?;
+    }
+    Ok(())
+}
+
+/// Updates the verification status of an existing entry.
+pub(crate) fn update_verification_status(
+    backend: &Sqlite,
+    id: &ID,
+    verification_status: VerificationStatus,
+) -> Result<()> {
+    let conn = backend.conn();
+    let updated = conn
+        .execute(
+            "UPDATE entries SET verification_status = ?2 WHERE id = ?1",
+            params![id.as_str(), status_to_sql(verification_status)],
+        )
+        .map_err(sql_err)?;
+    if updated == 0 {
+        return Err(DatabaseError::EntryNotFound { id: id.clone() }.into());
+    }
+    Ok(())
+}
+
+/// Retrieves the IDs of all entries with the given verification status.
+pub(crate) fn get_entries_by_verification_status(
+    backend: &Sqlite,
+    status: VerificationStatus,
+) -> Result<Vec<ID>> {
+    query_ids(
+        backend,
+        "SELECT id FROM entries WHERE verification_status = ?1",
+        params![status_to_sql(status)],
+    )
+}
+
+/// Retrieves the IDs of all stored entries.
+pub(crate) fn all_ids(backend: &Sqlite) -> Result<Vec<ID>> {
+    query_ids(backend, "SELECT id FROM entries", [])
+}
+
+/// Retrieves the IDs of all root entries.
+pub(crate) fn all_roots(backend: &Sqlite) -> Result<Vec<ID>> {
+    query_ids(backend, "SELECT id FROM entries WHERE is_root = 1", [])
+}
+
+/// Retrieves the indexed tips of a tree (`None`) or one of its subtrees.
+pub(crate) fn get_tips(backend: &Sqlite, tree: &ID, subtree: Option<&str>) -> Result<Vec<ID>> {
+    let (is_subtree, scope) = scope_columns(subtree);
+    query_ids(
+        backend,
+        "SELECT entry_id FROM tips WHERE tree_id = ?1 AND is_subtree = ?2 AND scope = ?3",
+        params![tree.as_str(), is_subtree, scope],
+    )
+}
+
+/// Checks whether an entry has no children within a tree (`None`) or one of its subtrees.
+pub(crate) fn is_tip(
+    backend: &Sqlite,
+    tree: &ID,
+    subtree: Option<&str>,
+    entry_id: &ID,
+) -> Result<bool> {
+    let (is_subtree, scope) = scope_columns(subtree);
+    let conn = backend.conn();
+    let has_children = conn
+        .query_row(
+            "SELECT 1 FROM parents
+             WHERE tree_id = ?1 AND is_subtree = ?2 AND scope = ?3 AND parent = ?4 LIMIT 1",
+            params![tree.as_str(), is_subtree, scope, entry_id.as_str()],
+            |_| Ok(()),
+        )
+        .optional()
+        .map_err(sql_err)?
+        .is_some();
+    Ok(!has_children)
+}
+
+/// Loads all entries of a tree, unsorted.
+pub(crate) fn load_tree_entries(backend: &Sqlite, tree: &ID) -> Result<Vec<(ID, Entry)>> {
+    query_entries(
+        backend,
+        "SELECT id, data, refs FROM entries WHERE tree_id = ?1 OR id = ?1",
+        params![tree.as_str()],
+    )
+}
+
+/// Removes the entries of a tree that are not reachable from `keep_tips`.
+pub(crate) fn prune(backend: &Sqlite, tree: &ID, keep_tips: &[ID]) -> Result<PruneStats> {
+    remove_where(backend, tree, |tree_entries| {
+        let retained = retained_entries(tree, tree_entries, keep_tips)?;
+        Ok(tree_entries
+            .keys()
+            .filter(|id| !retained.contains(*id))
+            .cloned()
+            .collect())
+    })
+}
+
+/// Removes the given entries of a tree; IDs that are not entries of the tree
+/// are ignored.
+pub(crate) fn remove_entries(backend: &Sqlite, tree: &ID, ids: &[ID]) -> Result<PruneStats> {
+    remove_where(backend, tree, |tree_entries| {
+        Ok(ids
+            .iter()
+            .filter(|id| tree_entries.contains_key(*id))
+            .cloned()
+            .collect())
+    })
+}
+
+/// Removes the entries of a tree chosen by `select` from all of its entries.
+///
+/// The entries, their index rows and cached CRDT states are deleted and the
+/// tree's tips are recomputed, all in a single transaction. The removed entries
+/// are dropped from the entry cache once it is committed.
+fn remove_where(
+    backend: &Sqlite,
+    tree: &ID,
+    select: impl FnOnce(&HashMap<ID, Entry>) -> Result<HashSet<ID>>,
+) -> Result<PruneStats> {
+    let mut conn = backend.conn();
+    let tx = conn.transaction().map_err(sql_err)?;
+
+    let mut tree_entries = HashMap::new();
+    {
+        let mut stmt = tx
+            .prepare("SELECT id, data, refs FROM entries WHERE tree_id = ?1 OR id = ?1")
+            .map_err(sql_err)?;
+        let rows = stmt
+            .query_map(params![tree.as_str()], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, Value>(1)?,
+                    row.get::<_, Vec<u8>>(2)?,
+                ))
+            })
+            .map_err(sql_err)?;
+        for row in rows {
+            let (id, data, refs) = row.map_err(sql_err)?;
+            tree_entries.insert(ID::from(id), decode_entry(&data, &refs)?);
+        }
+    }
+    let removed = select(&tree_entries)?;
+
+    let mut stats = PruneStats {
+        entries_kept: tree_entries.len() - removed.len(),
+        ..PruneStats::default()
+    };
+    for (id, entry) in tree_entries.iter().filter(|(id, _)| removed.contains(*id)) {
+        for sql in [
+            "DELETE FROM entries WHERE id = ?1",
+            "DELETE FROM entry_subtrees WHERE entry_id = ?1",
+            "DELETE FROM parents WHERE child = ?1",
+            "DELETE FROM tips WHERE entry_id = ?1",
+            "DELETE FROM crdt_cache WHERE entry_id = ?1",
+        ] {
+            tx.execute(sql, params![id.as_str()]).map_err(sql_err)?;
+        }
+        stats.entries_removed += 1;
+        stats.bytes_removed += entry_size(entry);
+    }
+
+    if stats.entries_removed > 0 {
+        // Kept entries whose only children were removed become tips again
+        tx.execute(
+            "INSERT OR IGNORE INTO tips (tree_id, is_subtree, scope, entry_id)
+             SELECT ?1, 0, '', e.id FROM entries e
+             WHERE (e.tree_id = ?1 OR e.id = ?1) AND NOT EXISTS (
+                 SELECT 1 FROM parents p
+                 WHERE p.tree_id = ?1 AND p.is_subtree = 0 AND p.parent = e.id)",
+            params![tree.as_str()],
+        )
+        .map_err(sql_err)?;
+        tx.execute(
+            "INSERT OR IGNORE INTO tips (tree_id, is_subtree, scope, entry_id)
+             SELECT ?1, 1, s.subtree, e.id FROM entries e
+             JOIN entry_subtrees s ON s.entry_id = e.id
+             WHERE (e.tree_id = ?1 OR e.id = ?1) AND NOT EXISTS (
+                 SELECT 1 FROM parents p
+                 WHERE p.tree_id = ?1 AND p.is_subtree = 1 AND p.scope = s.subtree
+                     AND p.parent = e.id)",
+            params![tree.as_str()],
+        )
+        .map_err(sql_err)?;
+    }
+
+    tx.commit().map_err(sql_err)?;
+    drop(conn);
+    backend.entry_cache.remove(&removed);
+    // Heights are relative to the entries kept, so they are found again
+    backend.heights.lock().unwrap().clear();
+    Ok(stats)
+}
+
+/// Loads all entries of a subtree within a tree, unsorted.
+pub(crate) fn load_subtree_entries(
+    backend: &Sqlite,
+    tree: &ID,
+    subtree: &str,
+) -> Result<Vec<(ID, Entry)>> {
+    query_entries(
+        backend,
+        "SELECT e.id, e.data, e.refs FROM entries e
+         JOIN entry_subtrees s ON s.entry_id = e.id
+         WHERE (e.tree_id = ?1 OR e.id = ?1) AND s.subtree = ?2",
+        params![tree.as_str(), subtree],
+    )
+}
+
+/// Loads the IDs of the entries of a tree, or of a subtree within it, each
+/// with its parents in that context, from the parent index.
+pub(crate) fn load_parent_graph(
+    backend: &Sqlite,
+    tree: &ID,
+    subtree: Option<&str>,
+) -> Result<Vec<(ID, Vec<ID>)>> {
+    let ids = match subtree {
+        Some(subtree_name) => query_ids(
+            backend,
+            "SELECT e.id FROM entries e
+             JOIN entry_subtrees s ON s.entry_id = e.id
+             WHERE (e.tree_id = ?1 OR e.id = ?1) AND s.subtree = ?2",
+            params![tree.as_str(), subtree_name],
+        )?,
+        None => query_ids(
+            backend,
+            "SELECT id FROM entries WHERE tree_id = ?1 OR id = ?1",
+            params![tree.as_str()],
+        )?,
+    };
+
+    let mut parents: HashMap<ID, Vec<ID>> = HashMap::new();
+    {
+        let conn = backend.conn();
+        let mut stmt = conn
+            .prepare_cached(
+                "SELECT child, parent FROM parents
+                 WHERE tree_id = ?1 AND is_subtree = ?2 AND scope = ?3",
+            )
+            .map_err(sql_err)?;
+        let (is_subtree, scope) = scope_columns(subtree);
+        let rows = stmt
+            .query_map(params![tree.as_str(), is_subtree, scope], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(sql_err)?;
+        for row in rows {
+            let (child, parent) = row.map_err(sql_err)?;
+            parents
+                .entry(ID::from(child))
+                .or_default()
+                .push(ID::from(parent));
+        }
+    }
+
+    Ok(ids
+        .into_iter()
+        .map(|id| {
+            let entry_parents = parents.remove(&id).unwrap_or_default();
+            (id, entry_parents)
+        })
+        .collect())
+}
+
+/// Stores a private key, replacing any key with the same name.
+pub(crate) fn store_private_key(
+    backend: &Sqlite,
+    key_name: &str,
+    private_key: SigningKey,
+) -> Result<()> {
+    let conn = backend.conn();
+    conn.execute(
+        "INSERT OR REPLACE INTO private_keys (name, key) VALUES (?1, ?2)",
+        params![key_name, &Zeroizing::new(private_key.to_bytes())[..]],
+    )
+    .map_err(sql_err)?;
+    Ok(())
+}
+
+/// Retrieves a private key by name.
+pub(crate) fn get_private_key(backend: &Sqlite, key_name: &str) -> Result<Option<SigningKey>> {
+    let conn = backend.conn();
+    let bytes: Option<Zeroizing<Vec<u8>>> = conn
+        .query_row(
+            "SELECT key FROM private_keys WHERE name = ?1",
+            params![key_name],
+            |row| row.get(0).map(Zeroizing::new),
+        )
+        .optional()
+        .map_err(sql_err)?;
+
+    bytes
+        .map(|bytes| {
+            let bytes: Zeroizing<[u8; 32]> = bytes
+                .as_slice()
+                .try_into()
+                .map(Zeroizing::new)
+                .map_err(|_| -> Error {
+                    DatabaseError::StateInconsistency {
+                        reason: format!("Private key '{key_name}' has an invalid length"),
+                    }
+                    .into()
+                })?;
+            Ok(SigningKey::from_bytes(&bytes))
+        })
+        .transpose()
+}
+
+/// Lists the names of all stored private keys.
+pub(crate) fn list_private_keys(backend: &Sqlite) -> Result<Vec<String>> {
+    let conn = backend.conn();
+    let mut stmt = conn
+        .prepare_cached("SELECT name FROM private_keys")
+        .map_err(sql_err)?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(sql_err)?;
+    rows.map(|row| row.map_err(sql_err)).collect()
+}
+
+/// Removes a private key. Succeeds even if the key doesn't exist.
+pub(crate) fn remove_private_key(backend: &Sqlite, key_name: &str) -> Result<()> {
+    let conn = backend.conn();
+    conn.execute(
+        "DELETE FROM private_keys WHERE name = ?1",
+        params![key_name],
+    )
+    .map_err(sql_err)?;
+    Ok(())
+}
+
+/// Get cached CRDT state for a subtree at a specific entry.
+pub(crate) fn get_cached_crdt_state(
+    backend: &Sqlite,
+    entry_id: &ID,
+    subtree: &str,
+) -> Result<Option<String>> {
+    let conn = backend.conn();
+    conn.query_row(
+        "SELECT state FROM crdt_cache WHERE entry_id = ?1 AND subtree = ?2",
+        params![entry_id.as_str(), subtree],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(sql_err)
+}
+
+/// Cache CRDT state for a subtree at a specific entry.
+pub(crate) fn cache_crdt_state(
+    backend: &Sqlite,
+    entry_id: &ID,
+    subtree: &str,
+    state: String,
+) -> Result<()> {
+    let conn = backend.conn();
+    conn.execute(
+        "INSERT OR REPLACE INTO crdt_cache (entry_id, subtree, state) VALUES (?1, ?2, ?3)",
+        params![entry_id.as_str(), subtree, state],
+    )
+    .map_err(sql_err)?;
+    Ok(())
+}
+
+/// Clear all cached CRDT states.
+pub(crate) fn clear_crdt_cache(backend: &Sqlite) -> Result<()> {
+    let conn = backend.conn();
+    conn.execute("DELETE FROM crdt_cache", [])
+        .map_err(sql_err)?;
+    Ok(())
+}
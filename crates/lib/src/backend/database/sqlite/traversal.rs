@@ -0,0 +1,463 @@
+//! Tree traversal, height calculation and pathfinding for the SQLite database
+//!
+//! Heights are computed on demand from the entries of a tree or subtree,
+//! using the same longest-path-from-root definition as the InMemory backend.
+
+use super::Sqlite;
+use super::storage;
+use crate::Result;
+use crate::backend::errors::DatabaseError;
+use crate::clock::Hlc;
+use crate::entry::{Entry, ID};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Calculate the heights of all entries within a tree or subtree.
+///
+/// The height of an entry is the length of the longest path from a root of the
+/// context to that entry. Parents outside the context are ignored.
+pub(crate) fn calculate_heights(
+    backend: &Sqlite,
+    tree: &ID,
+    subtree: Option<&str>,
+) -> Result<HashMap<ID, usize>> {
+    let entries = match subtree {
+        Some(subtree_name) => storage::load_subtree_entries(backend, tree, subtree_name)?,
+        None => storage::load_tree_entries(backend, tree)?,
+    };
+    heights_for_entries(&entries, subtree)
+}
+
+/// Returns the height of an entry in a tree.
+///
+/// Heights are remembered, so an entry whose parents' heights are known is
+/// resolved from them. Otherwise the heights of the whole tree are computed
+/// and remembered.
+pub(crate) fn get_height(backend: &Sqlite, tree: &ID, id: &ID) -> Result<usize> {
+    if let Some(height) = backend.heights.lock().unwrap().get(id) {
+        return Ok(*height);
+    }
+    let parents = storage::get(backend, id)?.parents()?;
+    {
+        let mut heights = backend.heights.lock().unwrap();
+        let known: Option<Vec<usize>> = parents
+            .iter()
+            .map(|parent| heights.get(parent).copied())
+            .collect();
+        if let Some(known) = known {
+            let height = known.into_iter().max().map_or(0, |height| height + 1);
+            heights.insert(id.clone(), height);
+            return Ok(height);
+        }
+    }
+
+    let tree_heights = heights_for_graph(&storage::load_parent_graph(backend, tree, None)?)?;
+    let height = tree_heights
+        .get(id)
+        .copied()
+        .ok_or_else(|| DatabaseError::EntryNotFound { id: id.clone() })?;
+    backend.heights.lock().unwrap().extend(tree_heights);
+    Ok(height)
+}
+
+/// Computes heights for a set of entries using a topological (Kahn) traversal.
+fn heights_for_entries(
+    entries: &[(ID, Entry)],
+    subtree: Option<&str>,
+) -> Result<HashMap<ID, usize>> {
+    let graph = entries
+        .iter()
+        .map(|(id, entry)| {
+            let parents = match subtree {
+                Some(subtree_name) => entry.subtree_parents(subtree_name)?,
+                None => entry.parents()?,
+            };
+            Ok((id.clone(), parents))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    heights_for_graph(&graph)
+}
+
+/// Computes heights for entries given as `(id, parents)` pairs.
+fn heights_for_graph(graph: &[(ID, Vec<ID>)]) -> Result<HashMap<ID, usize>> {
+    let in_context: HashSet<&ID> = graph.iter().map(|(id, _)| id).collect();
+    let mut in_degree: HashMap<ID, usize> = HashMap::new();
+    let mut children_map: HashMap<ID, Vec<ID>> = HashMap::new();
+
+    for (id, parents) in graph {
+        let mut degree = 0;
+        for parent in parents.iter().filter(|p| in_context.contains(p)) {
+            children_map
+                .entry(parent.clone())
+                .or_default()
+                .push(id.clone());
+            degree += 1;
+        }
+        in_degree.insert(id.clone(), degree);
+    }
+
+    let mut heights: HashMap<ID, usize> = HashMap::new();
+    let mut queue: VecDeque<ID> = VecDeque::new();
+    for (id, degree) in &in_degree {
+        if *degree == 0 {
+            heights.insert(id.clone(), 0);
+            queue.push_back(id.clone());
+        }
+    }
+
+    let mut processed = 0;
+    while let Some(current) = queue.pop_front() {
+        processed += 1;
+        let current_height = heights[&current];
+        if let Some(children) = children_map.get(&current) {
+            for child in children {
+                let child_height = heights.entry(child.clone()).or_insert(0);
+                *child_height = (*child_height).max(current_height + 1);
+
+                let degree = in_degree.get_mut(child).ok_or_else(|| {
+                    DatabaseError::HeightCalculationCorruption {
+                        reason: format!("In-degree missing for child {child}"),
+                    }
+                })?;
+                *degree -= 1;
+                if *degree == 0 {
+                    queue.push_back(child.clone());
+                }
+            }
+        }
+    }
+
+    if processed != in_degree.len() {
+        return Err(DatabaseError::HeightCalculationCorruption {
+            reason: format!(
+                "processed {processed} of {} entries, the DAG contains a cycle",
+                in_degree.len()
+            ),
+        }
+        .into());
+    }
+
+    Ok(heights)
+}
+
+/// Sorts `(id, entry)` pairs by height, then ID, and drops the IDs.
+fn sort_by_heights(mut entries: Vec<(ID, Entry)>, heights: &HashMap<ID, usize>) -> Vec<Entry> {
+    entries.sort_by(|(a, _), (b, _)| {
+        let a_height = heights.get(a).copied().unwrap_or(0);
+        let b_height = heights.get(b).copied().unwrap_or(0);
+        a_height.cmp(&b_height).then_with(|| a.cmp(b))
+    });
+    entries.into_iter().map(|(_, entry)| entry).collect()
+}
+
+/// Sorts IDs by height, then ID, for deterministic ordering.
+fn sort_ids_by_heights(ids: &mut [ID], heights: &HashMap<ID, usize>) {
+    ids.sort_by(|a, b| {
+        let a_height = heights.get(a).copied().unwrap_or(0);
+        let b_height = heights.get(b).copied().unwrap_or(0);
+        a_height.cmp(&b_height).then_with(|| a.cmp(b))
+    });
+}
+
+/// Retrieves all entries belonging to a tree, sorted topologically.
+pub(crate) fn get_tree(backend: &Sqlite, tree: &ID) -> Result<Vec<Entry>> {
+    let entries = storage::load_tree_entries(backend, tree)?;
+    let heights = heights_for_entries(&entries, None)?;
+    Ok(sort_by_heights(entries, &heights))
+}
+
+/// The IDs of the entries of a tree, or of a subtree within it, in the order
+/// of `get_tree` and `get_subtree`.
+///
+/// Only the IDs and the parent index are read, not the entries.
+pub(crate) fn sorted_ids(backend: &Sqlite, tree: &ID, subtree: Option<&str>) -> Result<Vec<ID>> {
+    let graph = storage::load_parent_graph(backend, tree, subtree)?;
+    let heights = heights_for_graph(&graph)?;
+    let mut ids: Vec<ID> = graph.into_iter().map(|(id, _)| id).collect();
+    sort_ids_by_heights(&mut ids, &heights);
+    Ok(ids)
+}
+
+/// Retrieves all entries belonging to a subtree within a tree, sorted topologically.
+pub(crate) fn get_subtree(backend: &Sqlite, tree: &ID, subtree: &str) -> Result<Vec<Entry>> {
+    let entries = storage::load_subtree_entries(backend, tree, subtree)?;
+    let heights = heights_for_entries(&entries, Some(subtree))?;
+    Ok(sort_by_heights(entries, &heights))
+}
+
+/// Collects the entries of a tree or subtree reachable from `tips` via parent links.
+fn collect_from_tips(
+    backend: &Sqlite,
+    tree: &ID,
+    subtree: Option<&str>,
+    tips: &[ID],
+) -> Result<Vec<(ID, Entry)>> {
+    let mut result = Vec::new();
+    let mut to_process: VecDeque<ID> = tips.iter().cloned().collect();
+    let mut processed = HashSet::new();
+
+    while let Some(current) = to_process.pop_front() {
+        if !processed.insert(current.clone()) {
+            continue;
+        }
+        let entry = match storage::get(backend, &current) {
+            Ok(entry) => entry,
+            Err(e) if e.is_not_found() => continue,
+            Err(e) => return Err(e),
+        };
+        let in_context = match subtree {
+            Some(subtree_name) => entry.in_tree(tree) && entry.in_subtree(subtree_name),
+            None => entry.in_tree(tree),
+        };
+        if !in_context {
+            continue;
+        }
+
+        let parents = match subtree {
+            Some(subtree_name) => entry.subtree_parents(subtree_name)?,
+            None => entry.parents()?,
+        };
+        to_process.extend(parents.into_iter().filter(|p| !processed.contains(p)));
+        result.push((current, entry));
+    }
+
+    Ok(result)
+}
+
+/// Retrieves all entries of a tree reachable from the given tips, sorted topologically.
+pub(crate) fn get_tree_from_tips(backend: &Sqlite, tree: &ID, tips: &[ID]) -> Result<Vec<Entry>> {
+    if tips.is_empty() {
+        return Ok(vec![]);
+    }
+    let entries = collect_from_tips(backend, tree, None, tips)?;
+    let heights = calculate_heights(backend, tree, None)?;
+    Ok(sort_by_heights(entries, &heights))
+}
+
+/// Retrieves all entries of a subtree reachable from the given tips, sorted topologically.
+pub(crate) fn get_subtree_from_tips(
+    backend: &Sqlite,
+    tree: &ID,
+    subtree: &str,
+    tips: &[ID],
+) -> Result<Vec<Entry>> {
+    if tips.is_empty() {
+        return Ok(vec![]);
+    }
+    let entries = collect_from_tips(backend, tree, Some(subtree), tips)?;
+    let heights = calculate_heights(backend, tree, Some(subtree))?;
+    Ok(sort_by_heights(entries, &heights))
+}
+
+/// Find subtree tips considering only entries reachable from the given main tree entries.
+pub(crate) fn get_subtree_tips_up_to_entries(
+    backend: &Sqlite,
+    tree: &ID,
+    subtree: &str,
+    main_entries: &[ID],
+) -> Result<Vec<ID>> {
+    if main_entries.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // Fast path: the current tree tips cover the whole subtree, so the index answers directly
+    let current_tips: HashSet<ID> = storage::get_tips(backend, tree, None)?
+        .into_iter()
+        .collect();
+    if main_entries.len() == current_tips.len()
+        && main_entries.iter().all(|id| current_tips.contains(id))
+    {
+        return storage::get_tips(backend, tree, Some(subtree));
+    }
+
+    let subtree_entries: Vec<(ID, Entry)> = collect_from_tips(backend, tree, None, main_entries)?
+        .into_iter()
+        .filter(|(_, entry)| entry.in_subtree(subtree))
+        .collect();
+
+    let mut referenced = HashSet::new();
+    for (_, entry) in &subtree_entries {
+        referenced.extend(entry.subtree_parents(subtree)?);
+    }
+
+    Ok(subtree_entries
+        .into_iter()
+        .map(|(id, _)| id)
+        .filter(|id| !referenced.contains(id))
+        .collect())
+}
+
+/// Get the subtree parent IDs for a specific entry and subtree, sorted by height then ID.
+pub(crate) fn get_sorted_subtree_parents(
+    backend: &Sqlite,
+    tree_id: &ID,
+    entry_id: &ID,
+    subtree: &str,
+) -> Result<Vec<ID>> {
+    let entry = storage::get(backend, entry_id)?;
+    if !entry.in_tree(tree_id) || !entry.in_subtree(subtree) {
+        return Ok(Vec::new());
+    }
+
+    let mut parents = match entry.subtree_parents(subtree) {
+        Ok(parents) => parents,
+        Err(_) => return Ok(Vec::new()),
+    };
+
+    if !parents.is_empty() {
+        let heights = calculate_heights(backend, tree_id, Some(subtree))?;
+        sort_ids_by_heights(&mut parents, &heights);
+    }
+    Ok(parents)
+}
+
+/// Get all entry IDs on paths from `from_id` to any of `to_ids`, sorted by height,
+/// timestamp, then ID.
+///
+/// `from_id` itself is excluded from the result.
+pub(crate) fn get_path_from_to(
+    backend: &Sqlite,
+    tree_id: &ID,
+    subtree: &str,
+    from_id: &ID,
+    to_ids: &[ID],
+) -> Result<Vec<ID>> {
+    if to_ids.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut result = Vec::new();
+    let mut to_process: VecDeque<ID> = to_ids.iter().filter(|id| *id != from_id).cloned().collect();
+    let mut processed = HashSet::new();
+
+    while let Some(current) = to_process.pop_front() {
+        if !processed.insert(current.clone()) || current == *from_id {
+            continue;
+        }
+        result.push(current.clone());
+
+        let parents = get_sorted_subtree_parents(backend, tree_id, &current, subtree)?;
+        to_process.extend(parents.into_iter().filter(|p| !processed.contains(p)));
+    }
+
+    // Among concurrent entries at the same height, order by timestamp so the most
+    // recent edit is merged last
+    if !result.is_empty() {
+        let heights = calculate_heights(backend, tree_id, Some(subtree))?;
+        let mut timestamps: HashMap<ID, Option<Hlc>> = HashMap::new();
+        for id in &result {
+            timestamps.insert(id.clone(), storage::get(backend, id)?.timestamp());
+        }
+        result.sort_by(|a, b| {
+            let a_height = heights.get(a).copied().unwrap_or(0);
+            let b_height = heights.get(b).copied().unwrap_or(0);
+            a_height
+                .cmp(&b_height)
+                .then_with(|| timestamps[a].cmp(&timestamps[b]))
+                .then_with(|| a.cmp(b))
+        });
+    }
+    Ok(result)
+}
+
+/// Build the path from the tree/subtree root to a target entry by following first parents.
+pub(crate) fn build_path_from_root(
+    backend: &Sqlite,
+    tree: &ID,
+    subtree: &str,
+    target_entry: &ID,
+) -> Result<Vec<ID>> {
+    let mut path = Vec::new();
+    let mut current = target_entry.clone();
+    let mut visited = HashSet::new();
+
+    loop {
+        if !visited.insert(current.clone()) {
+            return Err(DatabaseError::CycleDetected { entry_id: current }.into());
+        }
+        path.push(current.clone());
+
+        let entry = storage::get(backend, &current)?;
+        if current == *tree || entry.is_root() {
+            break;
+        }
+
+        let parents = if subtree.is_empty() || entry.subtree_parents(subtree).is_err() {
+            entry.parents()?
+        } else {
+            entry.subtree_parents(subtree)?
+        };
+
+        match parents.first() {
+            Some(parent) => current = parent.clone(),
+            None => break,
+        }
+    }
+
+    path.reverse();
+    Ok(path)
+}
+
+/// Find the Lowest Common Ancestor (LCA) of multiple entries within a tree/subtree.
+pub(crate) fn find_lca(backend: &Sqlite, tree: &ID, subtree: &str, entry_ids: &[ID]) -> Result<ID> {
+    if entry_ids.is_empty() {
+        return Err(DatabaseError::EmptyEntryList {
+            operation: "LCA".to_string(),
+        }
+        .into());
+    }
+
+    if entry_ids.len() == 1 {
+        return Ok(entry_ids[0].clone());
+    }
+
+    for entry_id in entry_ids {
+        let entry = storage::get(backend, entry_id)?;
+        if !entry.in_tree(tree) {
+            return Err(DatabaseError::EntryNotInTree {
+                entry_id: entry_id.clone(),
+                tree_id: tree.clone(),
+            }
+            .into());
+        }
+    }
+
+    // Track which starting entries can reach each ancestor, walking upward in lockstep
+    let mut ancestors: HashMap<ID, HashSet<usize>> = HashMap::new();
+    let mut queues: Vec<VecDeque<ID>> = entry_ids
+        .iter()
+        .map(|id| VecDeque::from([id.clone()]))
+        .collect();
+
+    loop {
+        let mut any_progress = false;
+
+        for (idx, queue) in queues.iter_mut().enumerate() {
+            if let Some(current) = queue.pop_front() {
+                any_progress = true;
+
+                let reachable_by = ancestors.entry(current.clone()).or_default();
+                reachable_by.insert(idx);
+                if reachable_by.len() == entry_ids.len() {
+                    return Ok(current);
+                }
+
+                if let Ok(entry) = storage::get(backend, &current) {
+                    let parents = match entry.subtree_parents(subtree) {
+                        Ok(parents) => parents,
+                        Err(_) => entry.parents()?,
+                    };
+                    queue.extend(parents);
+                }
+            }
+        }
+
+        if !any_progress {
+            break;
+        }
+    }
+
+    Err(DatabaseError::NoCommonAncestor {
+        entry_ids: entry_ids.to_vec(),
+    }
+    .into())
+}
@@ -0,0 +1,326 @@
+//! Tiered database backend: a fast hot layer over a durable cold layer
+//!
+//! This module provides `Tiered`, a `Database` composed of two others. Reads
+//! are served from the hot layer, usually an [`InMemory`](super::InMemory)
+//! database, and fall back to the cold layer, usually a persistent one such as
+//! `Sqlite`. Writes go to the cold layer first and then to the hot one, so
+//! everything written is durable and the hot layer never holds an entry the
+//! cold layer lacks.
+//!
+//! The hot layer fills up as it is used: an entry is copied from the cold layer
+//! the first time it is read, and a whole tree the first time its tips, history
+//! or subtrees are queried. From then on the tree is served from the hot layer
+//! alone.
+
+use crate::Result;
+use crate::backend::{CacheStats, Database, EntryIter, KeyStorage, PruneStats, VerificationStatus};
+use crate::entry::{Entry, ID};
+use ed25519_dalek::SigningKey;
+use std::any::Any;
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+/// A database serving reads from a hot layer and persisting to a cold layer.
+///
+/// Entries and their verification statuses are written through to both
+/// layers. Private keys are kept in the cold layer only, and computed CRDT
+/// states in the hot layer only. Listing roots and entries by verification
+/// status asks the cold layer, which holds every entry.
+///
+/// The cold layer must not be written to except through this database, or the
+/// hot layer may miss entries of trees it has already loaded.
+///
+/// # Example
+/// ```
+/// # use eidetica::backend::Database;
+/// # use eidetica::backend::database::{InMemory, Tiered};
+/// # use eidetica::basedb::BaseDB;
+/// // Usually the cold layer is persistent, such as `Sqlite`
+/// let db = BaseDB::new(Box::new(Tiered::new(InMemory::new(), InMemory::new())));
+/// db.add_private_key("key").unwrap();
+/// let tree = db.new_tree_default("key").unwrap();
+///
+/// let tiered = db.backend().as_any().downcast_ref::<Tiered<InMemory, InMemory>>().unwrap();
+/// assert!(tiered.cold().get(tree.root_id()).is_ok());
+/// assert!(tiered.hot().get(tree.root_id()).is_ok());
+/// ```
+pub struct Tiered<Hot, Cold> {
+    hot: Hot,
+    cold: Cold,
+    /// Trees whose entries have all been copied to the hot layer
+    loaded: Mutex<HashSet<ID>>,
+}
+
+impl<Hot, Cold> std::fmt::Debug for Tiered<Hot, Cold> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Tiered")
+            .field("loaded", &self.loaded.lock().unwrap().len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<Hot: Database, Cold: Database> Tiered<Hot, Cold> {
+    /// Layers `hot` over `cold`.
+    ///
+    /// `hot` is usually empty. Entries it already holds must also be in `cold`.
+    pub fn new(hot: Hot, cold: Cold) -> Self {
+        Self {
+            hot,
+            cold,
+            loaded: Mutex::default(),
+        }
+    }
+
+    /// The hot layer.
+    pub fn hot(&self) -> &Hot {
+        &self.hot
+    }
+
+    /// The cold layer.
+    pub fn cold(&self) -> &Cold {
+        &self.cold
+    }
+
+    /// Returns true if every entry of `tree` has been copied to the hot layer.
+    pub fn is_loaded(&self, tree: &ID) -> bool {
+        self.loaded.lock().unwrap().contains(tree)
+    }
+
+    /// Copies the entries of `tree` the hot layer lacks from the cold layer,
+    /// unless that has been done already.
+    fn load(&self, tree: &ID) -> Result<()> {
+        let mut loaded = self.loaded.lock().unwrap();
+        if loaded.contains(tree) {
+            return Ok(());
+        }
+        for entry in self.cold.get_tree(tree)? {
+            let id = entry.id();
+            if self.hot.get(&id).is_err() {
+                self.hot
+                    .put(self.cold.get_verification_status(&id)?, entry)?;
+            }
+        }
+        loaded.insert(tree.clone());
+        Ok(())
+    }
+
+    /// Removes from the hot layer the entries of `tree` the cold layer no longer has.
+    fn drop_removed(&self, tree: &ID) -> Result<()> {
+        if !self.is_loaded(tree) {
+            return Ok(());
+        }
+        let removed: Vec<ID> = self
+            .hot
+            .get_tree(tree)?
+            .iter()
+            .map(Entry::id)
+            .filter(|id| self.cold.get(id).is_err())
+            .collect();
+        self.hot.remove_entries(tree, &removed)?;
+        if self.cold.get(tree).is_err() {
+            self.loaded.lock().unwrap().remove(tree);
+        }
+        Ok(())
+    }
+}
+
+impl<Hot: Database, Cold: Database> Database for Tiered<Hot, Cold> {
+    /// Reads from the hot layer, copying the entry from the cold layer on a miss.
+    fn get(&self, id: &ID) -> Result<Entry> {
+        if let Ok(entry) = self.hot.get(id) {
+            return Ok(entry);
+        }
+        let entry = self.cold.get(id)?;
+        self.hot
+            .put(self.cold.get_verification_status(id)?, entry.clone())?;
+        Ok(entry)
+    }
+
+    fn get_verification_status(&self, id: &ID) -> Result<VerificationStatus> {
+        match self.hot.get_verification_status(id) {
+            Ok(status) => Ok(status),
+            Err(_) => self.cold.get_verification_status(id),
+        }
+    }
+
+    /// Stores the entry in the cold layer, then in the hot layer.
+    fn put(&self, verification_status: VerificationStatus, entry: Entry) -> Result<()> {
+        self.cold.put(verification_status, entry.clone())?;
+        self.hot.put(verification_status, entry)
+    }
+
+    fn update_verification_status(
+        &self,
+        id: &ID,
+        verification_status: VerificationStatus,
+    ) -> Result<()> {
+        self.cold
+            .update_verification_status(id, verification_status)?;
+        if self.hot.get(id).is_ok() {
+            self.hot
+                .update_verification_status(id, verification_status)?;
+        }
+        Ok(())
+    }
+
+    fn get_entries_by_verification_status(&self, status: VerificationStatus) -> Result<Vec<ID>> {
+        self.cold.get_entries_by_verification_status(status)
+    }
+
+    fn get_tips(&self, tree: &ID) -> Result<Vec<ID>> {
+        self.load(tree)?;
+        self.hot.get_tips(tree)
+    }
+
+    fn get_subtree_tips(&self, tree: &ID, subtree: &str) -> Result<Vec<ID>> {
+        self.load(tree)?;
+        self.hot.get_subtree_tips(tree, subtree)
+    }
+
+    fn get_subtree_tips_up_to_entries(
+        &self,
+        tree: &ID,
+        subtree: &str,
+        main_entries: &[ID],
+    ) -> Result<Vec<ID>> {
+        self.load(tree)?;
+        self.hot
+            .get_subtree_tips_up_to_entries(tree, subtree, main_entries)
+    }
+
+    fn all_roots(&self) -> Result<Vec<ID>> {
+        self.cold.all_roots()
+    }
+
+    fn find_lca(&self, tree: &ID, subtree: &str, entry_ids: &[ID]) -> Result<ID> {
+        self.load(tree)?;
+        self.hot.find_lca(tree, subtree, entry_ids)
+    }
+
+    fn collect_root_to_target(
+        &self,
+        tree: &ID,
+        subtree: &str,
+        target_entry: &ID,
+    ) -> Result<Vec<ID>> {
+        self.load(tree)?;
+        self.hot.collect_root_to_target(tree, subtree, target_entry)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn get_tree(&self, tree: &ID) -> Result<Vec<Entry>> {
+        self.load(tree)?;
+        self.hot.get_tree(tree)
+    }
+
+    fn get_subtree(&self, tree: &ID, subtree: &str) -> Result<Vec<Entry>> {
+        self.load(tree)?;
+        self.hot.get_subtree(tree, subtree)
+    }
+
+    fn get_tree_iter(&self, tree: &ID) -> Result<EntryIter<'_>> {
+        self.load(tree)?;
+        self.hot.get_tree_iter(tree)
+    }
+
+    fn get_subtree_iter(&self, tree: &ID, subtree: &str) -> Result<EntryIter<'_>> {
+        self.load(tree)?;
+        self.hot.get_subtree_iter(tree, subtree)
+    }
+
+    fn get_tree_from_tips(&self, tree: &ID, tips: &[ID]) -> Result<Vec<Entry>> {
+        self.load(tree)?;
+        self.hot.get_tree_from_tips(tree, tips)
+    }
+
+    fn get_subtree_from_tips(&self, tree: &ID, subtree: &str, tips: &[ID]) -> Result<Vec<Entry>> {
+        self.load(tree)?;
+        self.hot.get_subtree_from_tips(tree, subtree, tips)
+    }
+
+    fn get_height(&self, tree: &ID, id: &ID) -> Result<usize> {
+        self.load(tree)?;
+        self.hot.get_height(tree, id)
+    }
+
+    fn store_private_key(&self, key_name: &str, private_key: SigningKey) -> Result<()> {
+        self.cold.store_private_key(key_name, private_key)
+    }
+
+    fn get_private_key(&self, key_name: &str) -> Result<Option<SigningKey>> {
+        self.cold.get_private_key(key_name)
+    }
+
+    fn list_private_keys(&self) -> Result<Vec<String>> {
+        self.cold.list_private_keys()
+    }
+
+    fn remove_private_key(&self, key_name: &str) -> Result<()> {
+        self.cold.remove_private_key(key_name)
+    }
+
+    fn key_storage(&self) -> KeyStorage {
+        self.cold.key_storage()
+    }
+
+    fn cache_stats(&self) -> Option<CacheStats> {
+        self.cold.cache_stats()
+    }
+
+    fn flush(&self) -> Result<()> {
+        self.cold.flush()
+    }
+
+    /// Prunes the cold layer, then drops the removed entries from the hot layer.
+    fn prune(&self, tree: &ID, keep_tips: &[ID]) -> Result<PruneStats> {
+        let stats = self.cold.prune(tree, keep_tips)?;
+        self.drop_removed(tree)?;
+        Ok(stats)
+    }
+
+    fn remove_entries(&self, tree: &ID, entries: &[ID]) -> Result<PruneStats> {
+        let stats = self.cold.remove_entries(tree, entries)?;
+        self.hot.remove_entries(tree, entries)?;
+        if self.cold.get(tree).is_err() {
+            self.loaded.lock().unwrap().remove(tree);
+        }
+        Ok(stats)
+    }
+
+    fn get_cached_crdt_state(&self, entry_id: &ID, subtree: &str) -> Result<Option<String>> {
+        self.hot.get_cached_crdt_state(entry_id, subtree)
+    }
+
+    fn cache_crdt_state(&self, entry_id: &ID, subtree: &str, state: String) -> Result<()> {
+        self.hot.cache_crdt_state(entry_id, subtree, state)
+    }
+
+    fn clear_crdt_cache(&self) -> Result<()> {
+        self.hot.clear_crdt_cache()
+    }
+
+    fn get_sorted_subtree_parents(
+        &self,
+        tree_id: &ID,
+        entry_id: &ID,
+        subtree: &str,
+    ) -> Result<Vec<ID>> {
+        self.load(tree_id)?;
+        self.hot
+            .get_sorted_subtree_parents(tree_id, entry_id, subtree)
+    }
+
+    fn get_path_from_to(
+        &self,
+        tree_id: &ID,
+        subtree: &str,
+        from_id: &ID,
+        to_ids: &[ID],
+    ) -> Result<Vec<ID>> {
+        self.load(tree_id)?;
+        self.hot.get_path_from_to(tree_id, subtree, from_id, to_ids)
+    }
+}
@@ -0,0 +1,344 @@
+//! Compact ID encoding for persistent storage
+//!
+//! Entry IDs are hex-encoded SHA-256 hashes that are repeated throughout a stored
+//! database: once per entry, again in every child's parent lists, and in the
+//! caches. This module stores each distinct ID once in a sorted [`IdTable`] as
+//! raw 32-byte hashes, eliding the prefix each shares with the previous one, and
+//! refers to IDs elsewhere by table index. Parent lists are written as
+//! zigzag-encoded deltas between consecutive indices, so the usually sorted
+//! lists take one or two bytes per parent.
+//!
+//! IDs that are not lowercase SHA-256 hex (e.g. the empty root of top-level
+//! entries, or hand-written test IDs) are stored verbatim, so every `ID`
+//! round-trips exactly and entry hashes are unaffected.
+//!
+//! The logical `Database` API is unchanged; only the stored representation uses
+//! this encoding.
+
+use crate::Result;
+use crate::backend::errors::DatabaseError;
+use crate::entry::{Entry, ID};
+use std::collections::HashMap;
+
+/// Length in bytes of a binary SHA-256 ID.
+const HASH_LEN: usize = 32;
+
+/// Table tag marking an ID stored verbatim rather than as a binary hash.
+const RAW_ID_TAG: u8 = 0xFF;
+
+fn invalid(reason: impl Into<String>) -> crate::Error {
+    DatabaseError::InvalidEncoding {
+        reason: reason.into(),
+    }
+    .into()
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn unzigzag(value: u64) -> i64 {
+    ((value >> 1) as i64) ^ -((value & 1) as i64)
+}
+
+/// Parses a lowercase hex SHA-256 ID into its raw bytes.
+///
+/// Uppercase hex is rejected so that decoding always reproduces the original string.
+fn hash_bytes(id: &str) -> Option<[u8; HASH_LEN]> {
+    let hex = id.as_bytes();
+    if hex.len() != HASH_LEN * 2 {
+        return None;
+    }
+    let nibble = |c: u8| match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        _ => None,
+    };
+    let mut bytes = [0u8; HASH_LEN];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = (nibble(hex[2 * i])? << 4) | nibble(hex[2 * i + 1])?;
+    }
+    Some(bytes)
+}
+
+fn hash_hex(bytes: &[u8; HASH_LEN]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Cursor over encoded bytes.
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    fn byte(&mut self) -> Result<u8> {
+        let byte = *self
+            .buf
+            .get(self.pos)
+            .ok_or_else(|| invalid("unexpected end of data"))?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.buf.len())
+            .ok_or_else(|| invalid("unexpected end of data"))?;
+        let bytes = &self.buf[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn varint(&mut self) -> Result<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= u64::from(byte & 0x7F) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(invalid("varint is too long"))
+    }
+
+    fn len(&mut self) -> Result<usize> {
+        usize::try_from(self.varint()?).map_err(|_| invalid("length out of range"))
+    }
+
+    fn finish(&self) -> Result<()> {
+        if self.pos == self.buf.len() {
+            Ok(())
+        } else {
+            Err(invalid("trailing bytes after encoded data"))
+        }
+    }
+}
+
+/// A sorted, deduplicated table of IDs that other encoded data refers to by index.
+#[derive(Debug, Default)]
+pub(crate) struct IdTable {
+    ids: Vec<ID>,
+    index: HashMap<ID, u32>,
+}
+
+impl IdTable {
+    /// Builds a table containing every given ID.
+    pub(crate) fn from_ids(ids: impl IntoIterator<Item = ID>) -> Self {
+        let mut ids: Vec<ID> = ids.into_iter().collect();
+        ids.sort();
+        ids.dedup();
+        Self::from_sorted(ids)
+    }
+
+    fn from_sorted(ids: Vec<ID>) -> Self {
+        let index = ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (id.clone(), i as u32))
+            .collect();
+        Self { ids, index }
+    }
+
+    /// Returns the index of an ID, which must be in the table.
+    pub(crate) fn index_of(&self, id: &ID) -> u32 {
+        *self
+            .index
+            .get(id)
+            .unwrap_or_else(|| panic!("ID {id} missing from IdTable"))
+    }
+
+    /// Returns the ID at an index.
+    pub(crate) fn get(&self, index: u32) -> Result<&ID> {
+        self.ids
+            .get(index as usize)
+            .ok_or_else(|| invalid(format!("ID index {index} out of range")))
+    }
+
+    /// Encodes the table.
+    ///
+    /// Hash IDs are written as a byte giving the length of the prefix shared with
+    /// the previous hash, followed by the remaining bytes. Other IDs are written
+    /// as `RAW_ID_TAG`, a varint length and the UTF-8 bytes.
+    pub(crate) fn encode(&self, out: &mut Vec<u8>) {
+        write_varint(out, self.ids.len() as u64);
+        let mut previous = [0u8; HASH_LEN];
+        for id in &self.ids {
+            match hash_bytes(id) {
+                Some(bytes) => {
+                    let shared = bytes
+                        .iter()
+                        .zip(previous.iter())
+                        .take_while(|(a, b)| a == b)
+                        .count()
+                        .min(HASH_LEN - 1);
+                    out.push(shared as u8);
+                    out.extend_from_slice(&bytes[shared..]);
+                    previous = bytes;
+                }
+                None => {
+                    out.push(RAW_ID_TAG);
+                    write_varint(out, id.len() as u64);
+                    out.extend_from_slice(id.as_bytes());
+                }
+            }
+        }
+    }
+
+    fn decode_from(reader: &mut Reader<'_>) -> Result<Self> {
+        let count = reader.len()?;
+        let mut ids = Vec::with_capacity(count.min(reader.buf.len()));
+        let mut previous = [0u8; HASH_LEN];
+        for _ in 0..count {
+            let tag = reader.byte()?;
+            if tag == RAW_ID_TAG {
+                let len = reader.len()?;
+                let raw = std::str::from_utf8(reader.bytes(len)?)
+                    .map_err(|_| invalid("ID is not valid UTF-8"))?;
+                ids.push(ID::from(raw));
+            } else {
+                let shared = tag as usize;
+                if shared >= HASH_LEN {
+                    return Err(invalid(format!("invalid shared prefix length {shared}")));
+                }
+                previous[shared..].copy_from_slice(reader.bytes(HASH_LEN - shared)?);
+                ids.push(ID::from(hash_hex(&previous)));
+            }
+        }
+        Ok(Self::from_sorted(ids))
+    }
+
+    /// Decodes a table written by `encode`.
+    pub(crate) fn decode(bytes: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(bytes);
+        let table = Self::decode_from(&mut reader)?;
+        reader.finish()?;
+        Ok(table)
+    }
+
+    /// Encodes a list of IDs as zigzag deltas between consecutive table indices.
+    pub(crate) fn encode_list(&self, list: &[ID], out: &mut Vec<u8>) {
+        write_varint(out, list.len() as u64);
+        let mut previous = 0i64;
+        for id in list {
+            let index = i64::from(self.index_of(id));
+            write_varint(out, zigzag(index - previous));
+            previous = index;
+        }
+    }
+
+    fn decode_list(&self, reader: &mut Reader<'_>) -> Result<Vec<ID>> {
+        let len = reader.len()?;
+        let mut list = Vec::with_capacity(len.min(self.ids.len()));
+        let mut previous = 0i64;
+        for _ in 0..len {
+            let index = previous
+                .checked_add(unzigzag(reader.varint()?))
+                .and_then(|i| u32::try_from(i).ok())
+                .ok_or_else(|| invalid("ID index out of range"))?;
+            list.push(self.get(index)?.clone());
+            previous = i64::from(index);
+        }
+        Ok(list)
+    }
+
+    /// Decodes a single list written by `encode_list`.
+    pub(crate) fn decode_id_list(&self, bytes: &[u8]) -> Result<Vec<ID>> {
+        let mut reader = Reader::new(bytes);
+        let list = self.decode_list(&mut reader)?;
+        reader.finish()?;
+        Ok(list)
+    }
+}
+
+/// Adds every ID referenced by an entry (root and all parents) to `ids`.
+pub(crate) fn collect_entry_ids(entry: &Entry, ids: &mut Vec<ID>) {
+    ids.push(entry.root());
+    ids.extend(entry.parents().unwrap_or_default());
+    for subtree in entry.subtrees() {
+        ids.extend(entry.subtree_parents(&subtree).unwrap_or_default());
+    }
+}
+
+/// Splits an entry into a copy without its ID references and the encoded references.
+///
+/// Every referenced ID must be in `table`.
+pub(crate) fn encode_entry_refs(entry: &Entry, table: &IdTable) -> (Entry, Vec<u8>) {
+    let mut stripped = entry.clone();
+    let (root, parents) = stripped.take_id_refs();
+    let mut out = Vec::new();
+    write_varint(&mut out, u64::from(table.index_of(&root)));
+    write_varint(&mut out, parents.len() as u64);
+    for list in &parents {
+        table.encode_list(list, &mut out);
+    }
+    (stripped, out)
+}
+
+fn decode_refs_from(stripped: &mut Entry, reader: &mut Reader<'_>, table: &IdTable) -> Result<()> {
+    let root_index =
+        u32::try_from(reader.varint()?).map_err(|_| invalid("root index out of range"))?;
+    let root = table.get(root_index)?.clone();
+    let count = reader.len()?;
+    let mut parents = Vec::with_capacity(count.min(reader.buf.len()));
+    for _ in 0..count {
+        parents.push(table.decode_list(reader)?);
+    }
+    if stripped.restore_id_refs(root, parents) {
+        Ok(())
+    } else {
+        Err(invalid(
+            "parent list count does not match the entry's subtrees",
+        ))
+    }
+}
+
+/// Restores the ID references encoded by `encode_entry_refs` into a stripped entry.
+pub(crate) fn decode_entry_refs(
+    mut stripped: Entry,
+    refs: &[u8],
+    table: &IdTable,
+) -> Result<Entry> {
+    let mut reader = Reader::new(refs);
+    decode_refs_from(&mut stripped, &mut reader, table)?;
+    reader.finish()?;
+    Ok(stripped)
+}
+
+/// Like `encode_entry_refs`, but self-contained: the encoded references carry
+/// their own `IdTable`. Used where entries are stored individually.
+#[cfg(feature = "sqlite")]
+pub(crate) fn encode_entry_standalone(entry: &Entry) -> (Entry, Vec<u8>) {
+    let mut ids = Vec::new();
+    collect_entry_ids(entry, &mut ids);
+    let table = IdTable::from_ids(ids);
+    let mut out = Vec::new();
+    table.encode(&mut out);
+    let (stripped, refs) = encode_entry_refs(entry, &table);
+    out.extend_from_slice(&refs);
+    (stripped, out)
+}
+
+/// Restores the ID references encoded by `encode_entry_standalone`.
+#[cfg(feature = "sqlite")]
+pub(crate) fn decode_entry_standalone(mut stripped: Entry, refs: &[u8]) -> Result<Entry> {
+    let mut reader = Reader::new(refs);
+    let table = IdTable::decode_from(&mut reader)?;
+    decode_refs_from(&mut stripped, &mut reader, &table)?;
+    reader.finish()?;
+    Ok(stripped)
+}
@@ -102,6 +102,22 @@ pub enum DatabaseError {
         source: serde_json::Error,
     },
 
+    /// Stored data uses an invalid or unsupported compact encoding.
+    #[error("Invalid stored encoding: {reason}")]
+    InvalidEncoding {
+        /// Description of what could not be decoded
+        reason: String,
+    },
+
+    /// Encoding or decoding with a binary entry codec failed.
+    #[error("{codec} codec failed: {reason}")]
+    CodecFailed {
+        /// The name of the codec
+        codec: String,
+        /// Description of the failure
+        reason: String,
+    },
+
     /// File I/O error.
     #[error("File I/O error")]
     FileIo {
@@ -110,6 +126,25 @@ pub enum DatabaseError {
         source: std::io::Error,
     },
 
+    /// SQLite operation failed.
+    #[cfg(feature = "sqlite")]
+    #[error("SQLite operation failed")]
+    Sqlite {
+        /// The underlying SQLite error
+        #[source]
+        source: rusqlite::Error,
+    },
+
+    /// IndexedDB request failed.
+    #[cfg(feature = "indexeddb")]
+    #[error("IndexedDB {operation} failed: {reason}")]
+    IndexedDb {
+        /// What was being done, such as "open" or "write"
+        operation: String,
+        /// The error reported by the browser
+        reason: String,
+    },
+
     /// CRDT cache operation failed.
     #[error("CRDT cache operation failed: {reason}")]
     CrdtCacheError {
@@ -138,6 +173,22 @@ pub enum DatabaseError {
         reason: String,
     },
 
+    /// Attempted to modify a read-only database.
+    #[error("Database is read-only, cannot {operation}")]
+    ReadOnly {
+        /// The operation that was attempted
+        operation: String,
+    },
+
+    /// A subtree was queried on a database that holds only some subtrees of a tree.
+    #[error("Subtree {subtree} of tree {tree_id} is not loaded")]
+    SubtreeNotLoaded {
+        /// The ID of the tree
+        tree_id: ID,
+        /// The name of the subtree
+        subtree: String,
+    },
+
     /// Cache miss or cache corruption.
     #[error("Cache operation failed: {reason}")]
     CacheError {
@@ -170,14 +221,34 @@ impl DatabaseError {
 
     /// Check if this error is related to I/O operations.
     pub fn is_io_error(&self) -> bool {
+        #[cfg(feature = "sqlite")]
+        if matches!(self, DatabaseError::Sqlite { .. }) {
+            return true;
+        }
+        #[cfg(feature = "indexeddb")]
+        if matches!(self, DatabaseError::IndexedDb { .. }) {
+            return true;
+        }
         matches!(
             self,
             DatabaseError::FileIo { .. }
                 | DatabaseError::SerializationFailed { .. }
                 | DatabaseError::DeserializationFailed { .. }
+                | DatabaseError::InvalidEncoding { .. }
+                | DatabaseError::CodecFailed { .. }
         )
     }
 
+    /// Check if this error indicates a write to a read-only database.
+    pub fn is_read_only(&self) -> bool {
+        matches!(self, DatabaseError::ReadOnly { .. })
+    }
+
+    /// Check if this error indicates a query for a subtree that is not loaded.
+    pub fn is_subtree_not_loaded(&self) -> bool {
+        matches!(self, DatabaseError::SubtreeNotLoaded { .. })
+    }
+
     /// Check if this error is related to cache operations.
     pub fn is_cache_error(&self) -> bool {
         matches!(
@@ -213,7 +284,8 @@ impl DatabaseError {
     pub fn tree_id(&self) -> Option<String> {
         match self {
             DatabaseError::EntryNotInTree { tree_id, .. }
-            | DatabaseError::EntryNotInSubtree { tree_id, .. } => Some(tree_id.to_string()),
+            | DatabaseError::EntryNotInSubtree { tree_id, .. }
+            | DatabaseError::SubtreeNotLoaded { tree_id, .. } => Some(tree_id.to_string()),
             DatabaseError::InvalidTreeReference { tree_id } => Some(tree_id.clone()),
             _ => None,
         }
@@ -251,6 +323,11 @@ mod tests {
         };
         assert!(err.is_io_error());
 
+        let err = DatabaseError::InvalidEncoding {
+            reason: "test".to_string(),
+        };
+        assert!(err.is_io_error());
+
         let err = DatabaseError::CacheError {
             reason: "test".to_string(),
         };
@@ -260,6 +337,13 @@ mod tests {
             operation: "test".to_string(),
         };
         assert!(err.is_logical_error());
+
+        let err = DatabaseError::SubtreeNotLoaded {
+            tree_id: ID::from("tree"),
+            subtree: "notes".to_string(),
+        };
+        assert!(err.is_subtree_not_loaded());
+        assert_eq!(err.tree_id(), Some("tree".to_string()));
     }
 
     #[test]
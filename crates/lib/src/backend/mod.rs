@@ -10,13 +10,31 @@ use crate::Result;
 use crate::entry::{Entry, ID};
 use ed25519_dalek::SigningKey;
 use std::any::Any;
+use std::collections::HashMap;
 
 // Category modules
+#[cfg(feature = "async")]
+pub(crate) mod asynchronous;
+// Only the sqlite and archive backends cache decoded entries
+#[cfg_attr(not(any(feature = "sqlite", feature = "archive")), allow(dead_code))]
+pub(crate) mod cache;
+pub mod codec;
+pub mod compression;
+pub(crate) mod content;
 pub mod database;
+pub(crate) mod encoding;
 pub mod errors;
+pub(crate) mod prune;
 
 // Re-export main types for easier access
+#[cfg(feature = "async")]
+pub use asynchronous::DatabaseAsync;
+pub use cache::{CacheStats, DEFAULT_ENTRY_CACHE_CAPACITY};
+pub use codec::EntryCodec;
+pub use compression::Compression;
+pub use content::PayloadStats;
 pub use errors::DatabaseError;
+pub use prune::PruneStats;
 
 /// Verification status for entries in the backend.
 ///
@@ -43,6 +61,40 @@ pub enum VerificationStatus {
     // Unverified,
 }
 
+/// Entries streamed from a database by [`Database::get_tree_iter`] and
+/// [`Database::get_subtree_iter`].
+pub type EntryIter<'a> = Box<dyn Iterator<Item = Result<Entry>> + Send + 'a>;
+
+/// Streams the entries with the given IDs, loading each one as it is reached.
+///
+/// Entries removed from `database` before they are reached are skipped.
+pub(crate) fn stream_entries<D>(database: &D, ids: Vec<ID>) -> EntryIter<'_>
+where
+    D: Database + ?Sized,
+{
+    Box::new(
+        ids.into_iter()
+            .filter_map(move |id| match database.get(&id) {
+                Err(e) if e.is_not_found() => None,
+                result => Some(result),
+            }),
+    )
+}
+
+/// How a database keeps the private keys stored with `Database::store_private_key`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum KeyStorage {
+    /// Kept in process memory only, until explicitly saved.
+    Memory,
+    /// Written to persistent storage without encryption.
+    Unencrypted,
+    /// The database cannot store private keys.
+    None,
+    /// The database does not report how it stores keys.
+    Unknown,
+}
+
 /// Database trait abstracting the underlying storage mechanism for Eidetica entries.
 ///
 /// This trait defines the essential operations required for storing, retrieving,
@@ -318,6 +370,74 @@ pub trait Database: Send + Sync + Any {
     /// sorted topologically, or an error.
     fn get_subtree_from_tips(&self, tree: &ID, subtree: &str, tips: &[ID]) -> Result<Vec<Entry>>;
 
+    /// Streams the entries of a tree, in the order of [`get_tree`](Self::get_tree).
+    ///
+    /// Entries are loaded one at a time as the iterator is advanced, so callers
+    /// that look at each entry once can process large trees without holding the
+    /// whole history in memory. Entries removed while the iterator is in use are
+    /// skipped; entries added are not included.
+    ///
+    /// The default loads the entries with `get_tree` and then yields them.
+    /// Backends that can order a tree's entries without loading them should
+    /// override it.
+    ///
+    /// # Arguments
+    /// * `tree` - The root ID of the tree to stream.
+    ///
+    /// # Returns
+    /// A `Result` containing an iterator over the tree's entries, or an error if
+    /// they cannot be ordered. Loading an individual entry may fail with an error
+    /// yielded by the iterator.
+    fn get_tree_iter(&self, tree: &ID) -> Result<EntryIter<'_>> {
+        Ok(Box::new(self.get_tree(tree)?.into_iter().map(Ok)))
+    }
+
+    /// Streams the entries of a subtree, in the order of [`get_subtree`](Self::get_subtree).
+    ///
+    /// See [`get_tree_iter`](Self::get_tree_iter); the default loads the entries
+    /// with `get_subtree` and then yields them.
+    ///
+    /// # Arguments
+    /// * `tree` - The root ID of the parent tree.
+    /// * `subtree` - The name of the subtree to stream.
+    fn get_subtree_iter(&self, tree: &ID, subtree: &str) -> Result<EntryIter<'_>> {
+        Ok(Box::new(
+            self.get_subtree(tree, subtree)?.into_iter().map(Ok),
+        ))
+    }
+
+    /// Returns the height of an entry in its tree: the length of the longest
+    /// chain of parents from the root to it.
+    ///
+    /// The default computes it from the entry's history with
+    /// `get_tree_from_tips`. Backends that keep the heights of stored entries
+    /// should override it, as it is called for every commit to a tree with an
+    /// entry count quota.
+    ///
+    /// # Arguments
+    /// * `tree` - The root ID of the tree containing the entry.
+    /// * `id` - The ID of the entry.
+    ///
+    /// # Returns
+    /// A `Result` containing the entry's height, or a
+    /// `DatabaseError::EntryNotFound` if it is not in the tree.
+    fn get_height(&self, tree: &ID, id: &ID) -> Result<usize> {
+        let mut heights: HashMap<ID, usize> = HashMap::new();
+        for entry in self.get_tree_from_tips(tree, std::slice::from_ref(id))? {
+            let height = entry
+                .parents()?
+                .iter()
+                .filter_map(|parent| heights.get(parent))
+                .max()
+                .map_or(0, |height| height + 1);
+            heights.insert(entry.id(), height);
+        }
+        heights
+            .get(id)
+            .copied()
+            .ok_or_else(|| DatabaseError::EntryNotFound { id: id.clone() }.into())
+    }
+
     // === Private Key Storage Methods ===
     //
     // These methods provide secure local storage for private keys outside of the Tree structures.
@@ -365,6 +485,81 @@ pub trait Database: Send + Sync + Any {
     /// A `Result` indicating success or an error. Succeeds even if the key doesn't exist.
     fn remove_private_key(&self, key_name: &str) -> Result<()>;
 
+    /// Describe how this database keeps the private keys it stores.
+    ///
+    /// Reported by [`BaseDB::security_audit`](crate::basedb::BaseDB::security_audit).
+    /// Defaults to [`KeyStorage::Unknown`].
+    fn key_storage(&self) -> KeyStorage {
+        KeyStorage::Unknown
+    }
+
+    /// Statistics about the database's cache of decoded entries.
+    ///
+    /// Backends that decode entries on every read keep the most recently read
+    /// ones in an LRU cache shared by all traversals, see [`CacheStats`].
+    /// Defaults to `None`, for backends without such a cache, including those
+    /// that keep entries in memory anyway.
+    fn cache_stats(&self) -> Option<CacheStats> {
+        None
+    }
+
+    /// Make all changes written so far durable.
+    ///
+    /// Backends that persist every change as it is written can use the default,
+    /// which does nothing. Backends that buffer writes, or that are not backed by
+    /// storage at all, should document what a flush guarantees.
+    ///
+    /// # Returns
+    /// A `Result` indicating success or an error while writing to storage.
+    fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Remove the entries of a tree that are not reachable from `keep_tips`.
+    ///
+    /// An entry is kept if it is one of `keep_tips` or an ancestor of one, through
+    /// either tree or subtree parents. The root is always kept. Because checkpoints
+    /// and the history behind them are ancestors of the tips that build on them,
+    /// the state of every subtree at `keep_tips` is unchanged; only the branches
+    /// that `keep_tips` do not include are dropped. After pruning, the tips of the
+    /// tree are `keep_tips` (minus any that are ancestors of others).
+    ///
+    /// Backends that cannot remove entries use the default, which fails with
+    /// `DatabaseError::ReadOnly`.
+    ///
+    /// # Arguments
+    /// * `tree` - The root ID of the tree to prune
+    /// * `keep_tips` - The entries whose history must be kept
+    ///
+    /// # Returns
+    /// A `Result` with the number of entries and bytes removed, or an error if
+    /// `keep_tips` is empty or names an entry that is not in the tree.
+    fn prune(&self, _tree: &ID, _keep_tips: &[ID]) -> Result<PruneStats> {
+        Err(DatabaseError::ReadOnly {
+            operation: "prune entries".to_string(),
+        }
+        .into())
+    }
+
+    /// Remove the given entries of a tree.
+    ///
+    /// Unlike [`prune`](Self::prune), the entries are removed even if other
+    /// entries build on them; those entries are left with missing parents, as
+    /// after a sparse sync. Removing every entry, root included, removes the
+    /// tree. IDs that are not entries of the tree are ignored.
+    ///
+    /// Backends that cannot remove entries use the default, which fails with
+    /// `DatabaseError::ReadOnly`.
+    ///
+    /// # Returns
+    /// A `Result` with the number of entries and bytes removed
+    fn remove_entries(&self, _tree: &ID, _entries: &[ID]) -> Result<PruneStats> {
+        Err(DatabaseError::ReadOnly {
+            operation: "remove entries".to_string(),
+        }
+        .into())
+    }
+
     // === CRDT State Cache Methods ===
     //
     // These methods provide caching for computed CRDT state at specific
@@ -426,7 +621,7 @@ pub trait Database: Send + Sync + Any {
     ///
     /// This function correctly handles diamond patterns by finding ALL entries that are
     /// reachable from any of the to_ids by following parents back to from_id, not just single paths.
-    /// The results are deduplicated and sorted by height then ID for deterministic CRDT merge ordering.
+    /// The results are deduplicated and sorted by height, then entry timestamp (`Entry::timestamp`), then ID for deterministic CRDT merge ordering.
     ///
     /// # Arguments
     /// * `tree_id` - The ID of the tree containing the entries
@@ -435,7 +630,7 @@ pub trait Database: Send + Sync + Any {
     /// * `to_ids` - The target entry IDs (all included in result)
     ///
     /// # Returns
-    /// A `Result<Vec<ID>>` containing all entry IDs between from and any of the targets, deduplicated and sorted by height then ID
+    /// A `Result<Vec<ID>>` containing all entry IDs between from and any of the targets, deduplicated and sorted by height, timestamp, then ID
     fn get_path_from_to(
         &self,
         tree_id: &ID,
@@ -0,0 +1,78 @@
+//! Pruning of entries that are no longer reachable from a tree's kept tips
+//!
+//! `Database::prune` drops the branches of a tree that the caller has decided to
+//! abandon. Everything the kept tips can reach is retained, including the
+//! checkpoints they build on and the history behind those checkpoints, so the
+//! state of every subtree at the kept tips is exactly what it was before.
+//! History behind a checkpoint is not removed because authentication settings
+//! and signature verification still read it.
+
+use crate::Result;
+use crate::backend::errors::DatabaseError;
+use crate::entry::{Entry, ID};
+use std::collections::{HashMap, HashSet};
+
+/// What a call to [`Database::prune`](super::Database::prune) removed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PruneStats {
+    /// Number of entries removed from the tree
+    pub entries_removed: usize,
+    /// Number of entries of the tree that were kept
+    pub entries_kept: usize,
+    /// Serialized size of the removed entries, in bytes
+    pub bytes_removed: u64,
+    /// Number of shared payloads freed because no remaining entry refers to
+    /// them. Only backends that deduplicate payloads count these.
+    pub payloads_freed: usize,
+}
+
+/// Determines which entries of a tree survive pruning down to `keep_tips`.
+///
+/// `entries` must hold every stored entry of the tree. The result is closed
+/// under both tree and subtree parents and always contains the root. Fails if
+/// `keep_tips` is empty or names an entry that is not part of the tree.
+pub(crate) fn retained_entries(
+    tree: &ID,
+    entries: &HashMap<ID, Entry>,
+    keep_tips: &[ID],
+) -> Result<HashSet<ID>> {
+    if keep_tips.is_empty() {
+        return Err(DatabaseError::EmptyEntryList {
+            operation: "prune".to_string(),
+        }
+        .into());
+    }
+    for tip in keep_tips {
+        if !entries.contains_key(tip) {
+            return Err(DatabaseError::EntryNotInTree {
+                entry_id: tip.clone(),
+                tree_id: tree.clone(),
+            }
+            .into());
+        }
+    }
+
+    let mut retained = HashSet::new();
+    let mut queue: Vec<ID> = keep_tips.to_vec();
+    queue.push(tree.clone());
+    while let Some(id) = queue.pop() {
+        if !retained.insert(id.clone()) {
+            continue;
+        }
+        // Parents missing from storage (e.g. after a sparse sync) are simply not retained
+        let Some(entry) = entries.get(&id) else {
+            continue;
+        };
+        queue.extend(entry.parents()?);
+        for subtree in entry.subtrees() {
+            queue.extend(entry.subtree_parents(&subtree)?);
+        }
+    }
+    retained.retain(|id| entries.contains_key(id));
+    Ok(retained)
+}
+
+/// Serialized size of an entry, used for `PruneStats::bytes_removed`.
+pub(crate) fn entry_size(entry: &Entry) -> u64 {
+    serde_json::to_vec(entry).map_or(0, |bytes| bytes.len() as u64)
+}
@@ -0,0 +1,145 @@
+//! Async wrapper around `BaseDB`
+//!
+//! Requires the "async" feature and a running Tokio runtime.
+
+use crate::Result;
+use crate::backend::Database;
+use crate::backend::asynchronous::run_blocking;
+use crate::basedb::BaseDB;
+use crate::crdt::Map;
+use crate::entry::ID;
+use crate::tree::Tree;
+use ed25519_dalek::{SigningKey, VerifyingKey};
+use std::sync::Arc;
+
+/// Async version of `BaseDB`.
+///
+/// Every method runs the corresponding `BaseDB` method on Tokio's blocking
+/// thread pool, so trees can be created, loaded and searched from async code
+/// without blocking a worker thread. The returned `Tree`s are the same as the
+/// synchronous ones and provide their own `*_async` methods.
+///
+/// Cloning is cheap; all clones share the same backend.
+///
+/// # Example
+/// ```
+/// # use eidetica::{backend::database::InMemory, basedb::BaseDBAsync, subtree::Dict};
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() -> eidetica::Result<()> {
+/// let db = BaseDBAsync::new(Box::new(InMemory::new()));
+/// db.add_private_key("key").await?;
+/// let tree = db.new_tree_default("key").await?;
+///
+/// let op = tree.new_operation_async().await?;
+/// op.get_subtree::<Dict>("data")?.set("greeting", "hello")?;
+/// op.commit_async().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct BaseDBAsync {
+    inner: BaseDB,
+}
+
+impl BaseDBAsync {
+    pub fn new(backend: Box<dyn Database>) -> Self {
+        Self {
+            inner: BaseDB::new(backend),
+        }
+    }
+
+    /// Get a reference to the underlying synchronous `BaseDB`.
+    pub fn as_sync(&self) -> &BaseDB {
+        &self.inner
+    }
+
+    /// Get a reference to the backend
+    pub fn backend(&self) -> &Arc<dyn Database> {
+        self.inner.backend()
+    }
+
+    /// Async version of [`BaseDB::new_tree`].
+    pub async fn new_tree(
+        &self,
+        settings: Map,
+        signing_key_name: impl Into<String>,
+    ) -> Result<Tree> {
+        let db = self.inner.clone();
+        let signing_key_name = signing_key_name.into();
+        run_blocking(move || db.new_tree(settings, signing_key_name)).await
+    }
+
+    /// Async version of [`BaseDB::new_tree_default`].
+    pub async fn new_tree_default(&self, signing_key_name: impl Into<String>) -> Result<Tree> {
+        let db = self.inner.clone();
+        let signing_key_name = signing_key_name.into();
+        run_blocking(move || db.new_tree_default(signing_key_name)).await
+    }
+
+    /// Async version of [`BaseDB::load_tree`].
+    pub async fn load_tree(&self, root_id: &ID) -> Result<Tree> {
+        let db = self.inner.clone();
+        let root_id = root_id.clone();
+        run_blocking(move || db.load_tree(&root_id)).await
+    }
+
+    /// Async version of [`BaseDB::all_trees`].
+    pub async fn all_trees(&self) -> Result<Vec<Tree>> {
+        let db = self.inner.clone();
+        run_blocking(move || db.all_trees()).await
+    }
+
+    /// Async version of [`BaseDB::find_tree`].
+    pub async fn find_tree(&self, name: impl Into<String>) -> Result<Vec<Tree>> {
+        let db = self.inner.clone();
+        let name = name.into();
+        run_blocking(move || db.find_tree(name)).await
+    }
+
+    /// Async version of [`BaseDB::add_private_key`].
+    pub async fn add_private_key(&self, key_name: impl Into<String>) -> Result<VerifyingKey> {
+        let db = self.inner.clone();
+        let key_name = key_name.into();
+        run_blocking(move || db.add_private_key(key_name)).await
+    }
+
+    /// Async version of [`BaseDB::import_private_key`].
+    pub async fn import_private_key(
+        &self,
+        key_name: impl Into<String>,
+        private_key: SigningKey,
+    ) -> Result<()> {
+        let db = self.inner.clone();
+        let key_name = key_name.into();
+        run_blocking(move || db.import_private_key(key_name, private_key)).await
+    }
+
+    /// Async version of [`BaseDB::get_public_key`].
+    pub async fn get_public_key(
+        &self,
+        key_name: impl Into<String>,
+    ) -> Result<Option<VerifyingKey>> {
+        let db = self.inner.clone();
+        let key_name = key_name.into();
+        run_blocking(move || db.get_public_key(key_name)).await
+    }
+
+    /// Async version of [`BaseDB::list_private_keys`].
+    pub async fn list_private_keys(&self) -> Result<Vec<String>> {
+        let db = self.inner.clone();
+        run_blocking(move || db.list_private_keys()).await
+    }
+
+    /// Async version of [`BaseDB::remove_private_key`].
+    pub async fn remove_private_key(&self, key_name: impl Into<String>) -> Result<()> {
+        let db = self.inner.clone();
+        let key_name = key_name.into();
+        run_blocking(move || db.remove_private_key(key_name)).await
+    }
+}
+
+impl From<BaseDB> for BaseDBAsync {
+    fn from(inner: BaseDB) -> Self {
+        Self { inner }
+    }
+}
@@ -0,0 +1,143 @@
+//! Security self-check for compliance reviews
+//!
+//! `BaseDB::security_audit` reports how private keys are stored and handled,
+//! how signatures are verified and how each tree's authentication is
+//! configured, and lists findings worth a reviewer's attention. It only reads
+//! local state and changes nothing.
+
+use crate::Result;
+use crate::auth::crypto::{KEYS_ZEROIZED, STRICT_SIGNATURES};
+use crate::auth::settings::AuthSettings;
+use crate::auth::types::{KeyStatus, Permission};
+use crate::backend::{Database, KeyStorage, VerificationStatus};
+use crate::crdt::map::Value;
+use crate::entry::ID;
+use crate::tree::Tree;
+
+/// Security-relevant configuration of a database.
+///
+/// Created by [`BaseDB::security_audit`](super::BaseDB::security_audit).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecurityAudit {
+    /// How the backend keeps private keys
+    pub key_storage: KeyStorage,
+    /// Number of private keys in local storage
+    pub private_keys: usize,
+    /// Whether private keys are zeroized when they are dropped
+    pub keys_zeroized: bool,
+    /// Whether signatures are checked with strict Ed25519 verification, which
+    /// rejects weak public keys and malleable signatures
+    pub strict_signatures: bool,
+    /// Number of stored entries that failed verification
+    pub failed_entries: usize,
+    /// Authentication configuration of each tree, ordered by root ID
+    pub trees: Vec<TreeAudit>,
+}
+
+/// Authentication configuration of one tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeAudit {
+    /// Root ID of the tree
+    pub root: ID,
+    /// Name of the tree, if it has one
+    pub name: Option<String>,
+    /// Keys that can sign entries
+    pub active_keys: usize,
+    /// Active keys with admin permission
+    pub admin_keys: usize,
+    /// Revoked and rotated keys
+    pub inactive_keys: usize,
+    /// Delegated trees whose keys can sign entries
+    pub delegations: usize,
+}
+
+impl TreeAudit {
+    /// Whether the tree has any authentication configured.
+    pub fn auth_configured(&self) -> bool {
+        self.active_keys + self.inactive_keys + self.delegations > 0
+    }
+}
+
+impl SecurityAudit {
+    /// Findings that a compliance review should look at, empty if there are none.
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        match self.key_storage {
+            KeyStorage::Unencrypted if self.private_keys > 0 => {
+                warnings.push("private keys are stored unencrypted".to_string());
+            }
+            KeyStorage::Unknown => {
+                warnings.push("the backend does not report how it stores keys".to_string());
+            }
+            _ => {}
+        }
+        if !self.keys_zeroized {
+            warnings.push("private keys are not zeroized when dropped".to_string());
+        }
+        if !self.strict_signatures {
+            warnings.push("signatures are not verified strictly".to_string());
+        }
+        if self.failed_entries > 0 {
+            warnings.push(format!(
+                "{} stored entries failed verification",
+                self.failed_entries
+            ));
+        }
+        for tree in &self.trees {
+            if !tree.auth_configured() {
+                warnings.push(format!(
+                    "tree {} has no authentication configured",
+                    tree.root
+                ));
+            } else if tree.admin_keys == 0 {
+                warnings.push(format!("tree {} has no active admin key", tree.root));
+            }
+        }
+        warnings
+    }
+}
+
+/// Audits a database and the given trees.
+pub(crate) fn audit(backend: &dyn Database, trees: &[Tree]) -> Result<SecurityAudit> {
+    let mut tree_audits = trees.iter().map(audit_tree).collect::<Result<Vec<_>>>()?;
+    tree_audits.sort_by(|a, b| a.root.cmp(&b.root));
+
+    Ok(SecurityAudit {
+        key_storage: backend.key_storage(),
+        private_keys: backend.list_private_keys()?.len(),
+        keys_zeroized: KEYS_ZEROIZED,
+        strict_signatures: STRICT_SIGNATURES,
+        failed_entries: backend
+            .get_entries_by_verification_status(VerificationStatus::Failed)?
+            .len(),
+        trees: tree_audits,
+    })
+}
+
+fn audit_tree(tree: &Tree) -> Result<TreeAudit> {
+    let settings = tree.get_settings()?.get_all()?;
+    let auth = match settings.get("auth") {
+        Some(Value::Map(auth)) => AuthSettings::from_map(auth.clone()),
+        _ => AuthSettings::new(),
+    };
+
+    let mut audit = TreeAudit {
+        root: tree.root_id().clone(),
+        name: tree.get_name().ok(),
+        active_keys: 0,
+        admin_keys: 0,
+        inactive_keys: 0,
+        delegations: auth.get_all_delegated_trees()?.len(),
+    };
+    for key in auth.get_all_keys()?.values() {
+        if key.status == KeyStatus::Active {
+            audit.active_keys += 1;
+            if matches!(key.permissions, Permission::Admin(_)) {
+                audit.admin_keys += 1;
+            }
+        } else {
+            audit.inactive_keys += 1;
+        }
+    }
+    Ok(audit)
+}
@@ -0,0 +1,393 @@
+//! Whole-database backups
+//!
+//! A backup is a JSON document holding every entry of every tree in a
+//! database, with the ID and verification status each entry had when it was
+//! backed up. It may also hold the database's private keys, encrypted with a
+//! passphrase.
+//!
+//! ```json
+//! {"format": "eidetica-backup", "version": 2,
+//!  "trees": [{"root": "...", "entries": [{"id": "...", "status": "Verified", "entry": {...}}]}],
+//!  "keys": {"salt": "...", "nonce": "...", "ciphertext": "..."}}
+//! ```
+//!
+//! Restoring trusts nothing in the document: each entry's structure is
+//! validated and its ID computed again from its content, and entries that were
+//! verified when backed up are verified again against their tree's
+//! authentication settings.
+//!
+//! # Key encryption
+//!
+//! The passphrase is stretched with Argon2id and a random salt into a key that
+//! encrypts the private keys with the XChaCha20-Poly1305 AEAD, authenticating
+//! the salt along with them, so a wrong passphrase is detected before any key
+//! is restored.
+
+use super::errors::BaseError;
+use crate::Tree;
+use crate::backend::{Database, VerificationStatus};
+use crate::entry::{Entry, EntryError, ID};
+use crate::sync::VerificationPool;
+use crate::{Error, Result};
+use base64ct::{Base64, Encoding};
+use chacha20poly1305::XChaCha20Poly1305;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, Payload};
+use ed25519_dalek::SigningKey;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{Read, Write};
+use std::sync::Arc;
+use subtle::ConstantTimeEq;
+use zeroize::Zeroizing;
+
+/// Value of the `format` field, identifying a document as a backup.
+const FORMAT: &str = "eidetica-backup";
+
+/// Version of the backup format written by this implementation.
+const VERSION: u32 = 2;
+
+#[derive(Serialize, Deserialize)]
+struct Backup {
+    format: String,
+    version: u32,
+    trees: Vec<BackupTree>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    keys: Option<EncryptedKeys>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BackupTree {
+    root: ID,
+    entries: Vec<BackupEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BackupEntry {
+    /// The entry's ID when it was backed up, checked against its content
+    id: ID,
+    status: VerificationStatus,
+    entry: Entry,
+}
+
+/// Private keys encrypted with a passphrase.
+#[derive(Serialize, Deserialize)]
+struct EncryptedKeys {
+    /// Argon2id salt
+    salt: String,
+    /// XChaCha20-Poly1305 nonce
+    nonce: String,
+    /// The JSON of the keys, by name, as Base64-encoded private keys, and its
+    /// authentication tag
+    ciphertext: String,
+}
+
+/// What [`BaseDB::backup`] wrote.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BackupStats {
+    /// Number of trees backed up
+    pub trees: usize,
+    /// Number of entries backed up, across all trees
+    pub entries: usize,
+    /// Number of private keys backed up
+    pub keys: usize,
+}
+
+/// Why an entry of a backup was found to be corrupt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Corruption {
+    /// The entry's content does not hash to the ID it was backed up with, so
+    /// it was not restored
+    HashMismatch {
+        /// The ID the content hashes to
+        actual: ID,
+    },
+    /// The entry is malformed or exceeds the structural limits, see
+    /// [`Entry::validate_structure`], so it was not restored
+    Malformed {
+        /// What is wrong with the entry
+        reason: String,
+    },
+    /// The entry was verified when backed up, but its signature does not
+    /// verify now, so it was restored as failing verification
+    InvalidSignature,
+}
+
+impl fmt::Display for Corruption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Corruption::HashMismatch { actual } => {
+                write!(f, "content hashes to {actual}")
+            }
+            Corruption::Malformed { reason } => f.write_str(reason),
+            Corruption::InvalidSignature => f.write_str("signature does not verify"),
+        }
+    }
+}
+
+/// An entry of a backup that failed its checks during a restore.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorruptEntry {
+    /// Root ID of the entry's tree
+    pub tree: ID,
+    /// ID the entry was backed up with
+    pub id: ID,
+    pub reason: Corruption,
+}
+
+/// What [`BaseDB::restore`] found and restored.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RestoreReport {
+    /// Root IDs of the restored trees
+    pub trees: Vec<ID>,
+    /// Number of entries stored, not counting those already in the database
+    pub entries: usize,
+    /// Names of the private keys restored
+    pub keys: Vec<String>,
+    /// Names of keys in the backup that were not restored because the
+    /// database already has a different key with the same name
+    pub key_conflicts: Vec<String>,
+    /// Entries that failed their checks
+    pub corrupt: Vec<CorruptEntry>,
+}
+
+impl RestoreReport {
+    /// Returns true if every entry of the backup passed its checks.
+    pub fn is_clean(&self) -> bool {
+        self.corrupt.is_empty()
+    }
+}
+
+fn invalid(reason: impl Into<String>) -> Error {
+    BaseError::InvalidBackup {
+        reason: reason.into(),
+    }
+    .into()
+}
+
+/// Writes every tree of `backend`, and its private keys if `passphrase` is
+/// given, to `writer`.
+pub(crate) fn backup(
+    backend: &Arc<dyn Database>,
+    writer: impl Write,
+    passphrase: Option<&str>,
+) -> Result<BackupStats> {
+    let mut stats = BackupStats::default();
+    let mut trees = Vec::new();
+    for root in backend.all_roots()? {
+        let entries = backend
+            .get_tree(&root)?
+            .into_iter()
+            .map(|entry| {
+                let id = entry.id();
+                let status = backend.get_verification_status(&id)?;
+                Ok(BackupEntry { id, status, entry })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        stats.trees += 1;
+        stats.entries += entries.len();
+        trees.push(BackupTree { root, entries });
+    }
+
+    let keys = match passphrase {
+        Some(passphrase) => {
+            let mut keys = BTreeMap::new();
+            for name in backend.list_private_keys()? {
+                if let Some(key) = backend.get_private_key(&name)? {
+                    keys.insert(name, Zeroizing::new(Base64::encode_string(&key.to_bytes())));
+                }
+            }
+            stats.keys = keys.len();
+            Some(encrypt_keys(&keys, passphrase)?)
+        }
+        None => None,
+    };
+
+    let backup = Backup {
+        format: FORMAT.to_string(),
+        version: VERSION,
+        trees,
+        keys,
+    };
+    serde_json::to_writer(writer, &backup)?;
+    Ok(stats)
+}
+
+/// Reads a backup from `reader` into `backend`, and its private keys if
+/// `passphrase` is given.
+///
+/// Keys are decrypted before anything is stored, so a wrong passphrase leaves
+/// the database unchanged.
+pub(crate) fn restore(
+    backend: &Arc<dyn Database>,
+    reader: impl Read,
+    passphrase: Option<&str>,
+) -> Result<RestoreReport> {
+    let backup: Backup =
+        serde_json::from_reader(reader).map_err(|e| invalid(format!("not a backup: {e}")))?;
+    if backup.format != FORMAT {
+        return Err(invalid(format!("unknown format '{}'", backup.format)));
+    }
+    if backup.version != VERSION {
+        return Err(invalid(format!(
+            "unsupported version {}, expected {VERSION}",
+            backup.version
+        )));
+    }
+
+    let mut report = RestoreReport::default();
+    let keys = match (passphrase, &backup.keys) {
+        (Some(passphrase), Some(encrypted)) => decrypt_keys(encrypted, passphrase)?,
+        (Some(_), None) => return Err(invalid("the backup holds no private keys")),
+        (None, _) => Vec::new(),
+    };
+    for (name, key) in keys {
+        match backend.get_private_key(&name)? {
+            Some(existing) if bool::from(existing.ct_eq(&key)) => {}
+            Some(_) => report.key_conflicts.push(name),
+            None => {
+                backend.store_private_key(&name, key)?;
+                report.keys.push(name);
+            }
+        }
+    }
+
+    for tree in backup.trees {
+        let root = tree.root;
+        let mut to_verify = Vec::new();
+        for BackupEntry { id, status, entry } in tree.entries {
+            let reason = match entry.validate_as(&id) {
+                Ok(()) => None,
+                Err(Error::Entry(EntryError::IdMismatch { actual, .. })) => {
+                    Some(Corruption::HashMismatch { actual })
+                }
+                Err(Error::Entry(e)) => Some(Corruption::Malformed {
+                    reason: e.to_string(),
+                }),
+                Err(e) => return Err(e),
+            };
+            if let Some(reason) = reason {
+                report.corrupt.push(CorruptEntry {
+                    tree: root.clone(),
+                    id,
+                    reason,
+                });
+                continue;
+            }
+            if !entry.in_tree(&root) {
+                return Err(BaseError::EntryNotInTree {
+                    entry_id: id,
+                    tree_id: root,
+                }
+                .into());
+            }
+            match backend.get(&id) {
+                Ok(_) => continue,
+                Err(e) if e.is_not_found() => {}
+                Err(e) => return Err(e),
+            }
+            backend.put(VerificationStatus::Failed, entry)?;
+            report.entries += 1;
+            if status == VerificationStatus::Verified {
+                to_verify.push(id);
+            }
+        }
+
+        if backend.get(&root).is_err() {
+            // The root entry itself was corrupt, so nothing can be verified
+            for id in to_verify {
+                report.corrupt.push(CorruptEntry {
+                    tree: root.clone(),
+                    id,
+                    reason: Corruption::InvalidSignature,
+                });
+            }
+            continue;
+        }
+        if !to_verify.is_empty() {
+            let restored = Tree::new_from_id(root.clone(), Arc::clone(backend))?;
+            VerificationPool::default().verify(&restored, &to_verify)?;
+            for id in to_verify {
+                if backend.get_verification_status(&id)? != VerificationStatus::Verified {
+                    report.corrupt.push(CorruptEntry {
+                        tree: root.clone(),
+                        id,
+                        reason: Corruption::InvalidSignature,
+                    });
+                }
+            }
+        }
+        report.trees.push(root);
+    }
+    Ok(report)
+}
+
+fn encrypt_keys(
+    keys: &BTreeMap<String, Zeroizing<String>>,
+    passphrase: &str,
+) -> Result<EncryptedKeys> {
+    let mut rng = rand::rngs::OsRng;
+    let mut salt = [0u8; 16];
+    rng.fill_bytes(&mut salt);
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut rng);
+
+    let plaintext = Zeroizing::new(serde_json::to_vec(keys)?);
+    let master = stretch(passphrase, &salt)?;
+    let ciphertext = XChaCha20Poly1305::new(master.as_ref().into())
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: &plaintext,
+                aad: &salt,
+            },
+        )
+        .map_err(|_| invalid("cannot encrypt the private keys"))?;
+    Ok(EncryptedKeys {
+        salt: Base64::encode_string(&salt),
+        nonce: Base64::encode_string(&nonce),
+        ciphertext: Base64::encode_string(&ciphertext),
+    })
+}
+
+fn decrypt_keys(encrypted: &EncryptedKeys, passphrase: &str) -> Result<Vec<(String, SigningKey)>> {
+    let corrupt = || invalid("the encrypted private keys are corrupt");
+    let decode = |field: &str| Base64::decode_vec(field).map_err(|_| corrupt());
+    let salt = decode(&encrypted.salt)?;
+    let nonce: [u8; 24] = decode(&encrypted.nonce)?
+        .try_into()
+        .map_err(|_| corrupt())?;
+    let ciphertext = decode(&encrypted.ciphertext)?;
+
+    let master = stretch(passphrase, &salt)?;
+    let plaintext = Zeroizing::new(
+        XChaCha20Poly1305::new(master.as_ref().into())
+            .decrypt(
+                &nonce.into(),
+                Payload {
+                    msg: &ciphertext,
+                    aad: &salt,
+                },
+            )
+            .map_err(|_| invalid("wrong passphrase, or the encrypted private keys are corrupt"))?,
+    );
+
+    let keys: BTreeMap<String, Zeroizing<String>> =
+        serde_json::from_slice(&plaintext).map_err(|_| corrupt())?;
+    keys.into_iter()
+        .map(|(name, key)| {
+            let bytes = Zeroizing::new(Base64::decode_vec(&key).map_err(|_| corrupt())?);
+            let bytes: &[u8; 32] = bytes.as_slice().try_into().map_err(|_| corrupt())?;
+            Ok((name, SigningKey::from_bytes(bytes)))
+        })
+        .collect()
+}
+
+/// Stretches a passphrase into a master key with Argon2id.
+fn stretch(passphrase: &str, salt: &[u8]) -> Result<Zeroizing<[u8; 32]>> {
+    let mut master = Zeroizing::new([0u8; 32]);
+    argon2::Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, master.as_mut())
+        .map_err(|e| invalid(format!("cannot derive a key from the passphrase: {e}")))?;
+    Ok(master)
+}
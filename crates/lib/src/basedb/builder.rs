@@ -0,0 +1,112 @@
+//! Builder for new trees
+//!
+//! [`BaseDB::new_tree_default`] creates trees with default settings, which
+//! then take a second commit to configure. A [`TreeBuilder`] collects the
+//! settings first, so they are written in the tree's root entry and the tree
+//! never exists without them.
+
+use super::BaseDB;
+use super::errors::BaseError;
+use super::settings::TreeSettings;
+use crate::Result;
+use crate::constants::HASH_ALGORITHM;
+use crate::crdt::Map;
+use crate::crdt::map::Value;
+use crate::entry::HashAlgorithm;
+use crate::tree::Tree;
+
+/// Configures and creates a new tree, see [`BaseDB::tree_builder`].
+///
+/// # Example
+/// ```
+/// # use eidetica::{backend::database::InMemory, basedb::BaseDB};
+/// let db = BaseDB::new(Box::new(InMemory::new()));
+/// db.add_private_key("k1").unwrap();
+///
+/// let tree = db
+///     .tree_builder()
+///     .name("notes")
+///     .default_key("k1")
+///     .setting("feature", "x")
+///     .create()
+///     .unwrap();
+///
+/// let settings = tree.settings().unwrap();
+/// assert_eq!(settings.name(), Some("notes"));
+/// assert_eq!(settings.custom("feature").and_then(|v| v.as_text()), Some("x"));
+/// assert_eq!(tree.default_auth_key(), Some("k1"));
+/// ```
+#[must_use = "the tree is only created by `create`"]
+pub struct TreeBuilder<'a> {
+    db: &'a BaseDB,
+    settings: TreeSettings,
+    hash_algorithm: Option<HashAlgorithm>,
+    default_key: Option<String>,
+}
+
+impl<'a> TreeBuilder<'a> {
+    pub(crate) fn new(db: &'a BaseDB) -> Self {
+        Self {
+            db,
+            settings: TreeSettings::new(Map::new(), None),
+            hash_algorithm: None,
+            default_key: None,
+        }
+    }
+
+    /// Set the tree's name.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.settings.set_name(name);
+        self
+    }
+
+    /// Set the tree's description.
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.settings.set_description(description);
+        self
+    }
+
+    /// Set a custom field of the tree's settings, see [`TreeSettings::set_custom`].
+    pub fn setting(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.settings.set_custom(key, value);
+        self
+    }
+
+    /// Set the algorithm the IDs of the tree's entries are hashed with.
+    ///
+    /// It cannot be changed once the tree is created.
+    pub fn hash_algorithm(mut self, algorithm: HashAlgorithm) -> Self {
+        self.hash_algorithm = Some(algorithm);
+        self
+    }
+
+    /// Set the key that signs the root entry and becomes the tree's default
+    /// authentication key.
+    ///
+    /// The key's private key must be stored in the database. It is made the
+    /// tree's first admin key.
+    pub fn default_key(mut self, key_name: impl Into<String>) -> Self {
+        self.default_key = Some(key_name.into());
+        self
+    }
+
+    /// Create the tree, with all settings in its root entry.
+    ///
+    /// # Errors
+    /// Returns `BaseError::InvalidTreeConfiguration` if no default key was set,
+    /// and `BaseError::SigningKeyNotFound` if its private key is not stored in
+    /// the database.
+    pub fn create(self) -> Result<Tree> {
+        let key_name = self
+            .default_key
+            .ok_or_else(|| BaseError::InvalidTreeConfiguration {
+                reason: "a default key is required to sign the root entry".to_string(),
+            })?;
+        let mut settings = self.settings.as_map().clone();
+        settings.set_string("tree_id", super::unique_tree_id());
+        if let Some(algorithm) = self.hash_algorithm {
+            settings.set_string(HASH_ALGORITHM, algorithm.name());
+        }
+        self.db.new_tree(settings, key_name)
+    }
+}
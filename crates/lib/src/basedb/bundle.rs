@@ -0,0 +1,136 @@
+//! Portable bundles of a single tree
+//!
+//! A bundle is a JSON document holding every entry of a tree, signatures
+//! included, along with the verification status each entry had when it was
+//! exported. Entries are listed parents first, so a bundle can be imported in
+//! a single pass.
+//!
+//! ```json
+//! {"format": "eidetica-bundle", "version": 1, "root": "...",
+//!  "entries": [{"status": "Verified", "entry": {...}}, ...]}
+//! ```
+
+use super::errors::BaseError;
+use crate::Result;
+use crate::Tree;
+use crate::backend::{Database, VerificationStatus};
+use crate::entry::{Entry, ID};
+use crate::sync::VerificationPool;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::sync::Arc;
+
+/// Value of the `format` field, identifying a document as a bundle.
+const FORMAT: &str = "eidetica-bundle";
+
+/// Version of the bundle format written by this implementation.
+const VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct Bundle {
+    format: String,
+    version: u32,
+    root: ID,
+    entries: Vec<BundleEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BundleEntry {
+    status: VerificationStatus,
+    entry: Entry,
+}
+
+fn invalid(reason: impl Into<String>) -> crate::Error {
+    BaseError::InvalidBundle {
+        reason: reason.into(),
+    }
+    .into()
+}
+
+/// Writes every entry of `tree` to `writer` as a bundle.
+///
+/// Returns the number of entries written.
+pub(crate) fn export(tree: &Tree, writer: impl Write) -> Result<usize> {
+    let backend = tree.backend();
+    let entries = backend
+        .get_tree(tree.root_id())?
+        .into_iter()
+        .map(|entry| {
+            let status = backend.get_verification_status(&entry.id())?;
+            Ok(BundleEntry { status, entry })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let count = entries.len();
+
+    let bundle = Bundle {
+        format: FORMAT.to_string(),
+        version: VERSION,
+        root: tree.root_id().clone(),
+        entries,
+    };
+    serde_json::to_writer(writer, &bundle)?;
+    Ok(count)
+}
+
+/// Reads a bundle from `reader` into `backend` and returns the ID of its tree.
+///
+/// Entries that are already stored are left as they are. The statuses in the
+/// bundle are not trusted: new entries recorded as `Verified` are verified
+/// again against the tree's authentication settings and stored as `Failed` if
+/// they do not pass. Entries recorded as `Failed` stay `Failed`.
+pub(crate) fn import(backend: &Arc<dyn Database>, reader: impl Read) -> Result<ID> {
+    let bundle: Bundle =
+        serde_json::from_reader(reader).map_err(|e| invalid(format!("not a bundle: {e}")))?;
+    if bundle.format != FORMAT {
+        return Err(invalid(format!("unknown format '{}'", bundle.format)));
+    }
+    if bundle.version != VERSION {
+        return Err(invalid(format!(
+            "unsupported version {}, expected {VERSION}",
+            bundle.version
+        )));
+    }
+
+    for item in &bundle.entries {
+        item.entry.validate_structure()?;
+    }
+    let root = bundle.root;
+    if !bundle
+        .entries
+        .iter()
+        .any(|item| item.entry.is_root() && item.entry.id() == root)
+    {
+        return Err(invalid(format!("root entry {root} is missing")));
+    }
+    if let Some(item) = bundle
+        .entries
+        .iter()
+        .find(|item| !item.entry.in_tree(&root))
+    {
+        return Err(BaseError::EntryNotInTree {
+            entry_id: item.entry.id(),
+            tree_id: root,
+        }
+        .into());
+    }
+
+    let mut to_verify = Vec::new();
+    for BundleEntry { status, entry } in bundle.entries {
+        let id = entry.id();
+        match backend.get(&id) {
+            Ok(_) => continue,
+            Err(e) if e.is_not_found() => {}
+            Err(e) => return Err(e),
+        }
+        backend.put(VerificationStatus::Failed, entry)?;
+        if status == VerificationStatus::Verified {
+            to_verify.push(id);
+        }
+    }
+
+    if !to_verify.is_empty() {
+        let tree = Tree::new_from_id(root.clone(), Arc::clone(backend))?;
+        VerificationPool::default().verify(&tree, &to_verify)?;
+    }
+    Ok(root)
+}
@@ -0,0 +1,155 @@
+//! Database event bus
+//!
+//! [`BaseDB::events`](super::BaseDB::events) hands out receivers that each get
+//! every [`DatabaseEvent`] published after they were created: trees created,
+//! entries committed, and entries received and verified through sync. Plugins
+//! and higher layers can react to them from their own threads instead of
+//! polling `all_trees()` or the trees' tips.
+//!
+//! Unlike commit listeners, publishing never waits for subscribers. Each
+//! receiver buffers a bounded number of events; events published while its
+//! buffer is full are dropped for that receiver and counted, see
+//! [`EventReceiver::missed`]. Dropping a receiver unsubscribes it.
+
+use crate::entry::ID;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Something that happened in a database.
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DatabaseEvent {
+    /// A tree was created in this database.
+    TreeCreated {
+        /// The root ID of the new tree
+        tree: ID,
+    },
+    /// An entry was committed to a tree, including the root entry of a new tree.
+    EntryCommitted {
+        /// The root ID of the tree
+        tree: ID,
+        /// The ID of the committed entry
+        entry: ID,
+    },
+    /// An entry received from a peer passed verification.
+    EntryVerified {
+        /// The root ID of the tree
+        tree: ID,
+        /// The ID of the verified entry
+        entry: ID,
+    },
+    /// A sync session stored entries of a tree that were new to this database.
+    SyncReceived {
+        /// The root ID of the tree
+        tree: ID,
+        /// Number of new entries, whether or not they passed verification
+        entries: usize,
+    },
+}
+
+impl DatabaseEvent {
+    /// The root ID of the tree the event is about.
+    pub fn tree(&self) -> &ID {
+        match self {
+            DatabaseEvent::TreeCreated { tree }
+            | DatabaseEvent::EntryCommitted { tree, .. }
+            | DatabaseEvent::EntryVerified { tree, .. }
+            | DatabaseEvent::SyncReceived { tree, .. } => tree,
+        }
+    }
+}
+
+/// A subscriber's channel and its count of dropped events.
+struct Subscriber {
+    sender: SyncSender<DatabaseEvent>,
+    missed: Arc<AtomicU64>,
+}
+
+/// Publishes [`DatabaseEvent`]s to every subscribed [`EventReceiver`].
+///
+/// Obtained from [`BaseDB::event_bus`](super::BaseDB::event_bus), and shared by
+/// all clones of a `BaseDB` and every `Tree` loaded from it.
+#[derive(Default)]
+pub struct EventBus {
+    subscribers: Mutex<Vec<Subscriber>>,
+}
+
+impl EventBus {
+    /// Number of events a receiver buffers by default.
+    pub const DEFAULT_CAPACITY: usize = 1024;
+
+    /// Subscribes to the events published from now on, buffering up to
+    /// [`DEFAULT_CAPACITY`](Self::DEFAULT_CAPACITY) of them.
+    pub fn subscribe(&self) -> EventReceiver {
+        self.subscribe_with_capacity(Self::DEFAULT_CAPACITY)
+    }
+
+    /// Subscribes to the events published from now on, buffering up to
+    /// `capacity` of them, at least one.
+    pub fn subscribe_with_capacity(&self, capacity: usize) -> EventReceiver {
+        let (sender, receiver) = mpsc::sync_channel(capacity.max(1));
+        let missed = Arc::new(AtomicU64::new(0));
+        self.subscribers.lock().unwrap().push(Subscriber {
+            sender,
+            missed: Arc::clone(&missed),
+        });
+        EventReceiver { receiver, missed }
+    }
+
+    /// Returns true if any receiver is subscribed.
+    pub(crate) fn has_subscribers(&self) -> bool {
+        !self.subscribers.lock().unwrap().is_empty()
+    }
+
+    /// Sends `event` to every subscriber, dropping those whose receiver is gone.
+    pub(crate) fn publish(&self, event: DatabaseEvent) {
+        self.subscribers.lock().unwrap().retain(|subscriber| {
+            match subscriber.sender.try_send(event.clone()) {
+                Ok(()) => true,
+                Err(TrySendError::Full(_)) => {
+                    subscriber.missed.fetch_add(1, Ordering::Relaxed);
+                    true
+                }
+                Err(TrySendError::Disconnected(_)) => false,
+            }
+        });
+    }
+}
+
+/// Receives the events of a database, see [`BaseDB::events`](super::BaseDB::events).
+pub struct EventReceiver {
+    receiver: Receiver<DatabaseEvent>,
+    missed: Arc<AtomicU64>,
+}
+
+impl EventReceiver {
+    /// Waits for the next event.
+    ///
+    /// Returns `None` once the database and every tree loaded from it are
+    /// dropped and all buffered events have been received.
+    pub fn recv(&self) -> Option<DatabaseEvent> {
+        self.receiver.recv().ok()
+    }
+
+    /// Waits up to `timeout` for the next event.
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<DatabaseEvent> {
+        self.receiver.recv_timeout(timeout).ok()
+    }
+
+    /// Returns the next buffered event without waiting.
+    pub fn try_recv(&self) -> Option<DatabaseEvent> {
+        self.receiver.try_recv().ok()
+    }
+
+    /// Returns the buffered events without waiting.
+    pub fn try_iter(&self) -> impl Iterator<Item = DatabaseEvent> + '_ {
+        self.receiver.try_iter()
+    }
+
+    /// Number of events dropped because this receiver's buffer was full.
+    pub fn missed(&self) -> u64 {
+        self.missed.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,27 @@
+//! State shared by a `BaseDB` and its trees
+//!
+//! Listeners, logs and caches configured on a `BaseDB` apply to every clone of
+//! it and to every `Tree` loaded from them. They are grouped in one
+//! [`DbContext`] that the database hands to each tree it creates or loads.
+//! Trees created on their own get a context of their own.
+
+use super::{CommitListeners, EphemeralState, EventBus, ReadLog, StateCache};
+use crate::entry::AppInfo;
+use std::sync::{Arc, RwLock};
+
+/// State shared by a `BaseDB`, its clones and the trees loaded from them.
+#[derive(Default)]
+pub(crate) struct DbContext {
+    /// Listeners notified after entries are committed
+    pub(crate) commit_listeners: CommitListeners,
+    /// Log of subtree reads
+    pub(crate) read_log: ReadLog,
+    /// App info recorded by new operations
+    pub(crate) default_app_info: RwLock<Option<AppInfo>>,
+    /// Merged subtree states
+    pub(crate) state_cache: StateCache,
+    /// State of ephemeral subtrees, also handed to sync peers
+    pub(crate) ephemeral: Arc<EphemeralState>,
+    /// Bus publishing database events, also handed to sync peers
+    pub(crate) event_bus: Arc<EventBus>,
+}
@@ -0,0 +1,211 @@
+//! Differences between two states of a tree
+//!
+//! `Tree::diff` compares the merged state of every subtree at two sets of tips
+//! and reports the changes subtree by subtree, for showing what a branch or a
+//! sync changed. Subtrees stored as a Map, such as `Dict` and `Table`, are
+//! compared structurally with [`Map::diff`]; other subtrees are only reported
+//! as changed.
+//!
+//! `Tree::merge_report` compares two branches with the state they diverged
+//! from, and lists the values both branches wrote, of which merging keeps only
+//! one.
+
+use crate::atomicop::AtomicOp;
+use crate::constants::ROOT;
+use crate::crdt::Map;
+use crate::crdt::map::{Change, Value, conflicting_leaves};
+use crate::entry::ID;
+use crate::tree::Tree;
+use crate::{Error, Result};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// How one subtree differs between two states of a tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubtreeDiff {
+    /// The changes to a subtree stored as a Map, see [`Map::diff`]
+    ///
+    /// Table rows show up as changes to the row's key.
+    Changes(Vec<Change>),
+    /// The subtree's history differs, but its state is not a Map (for example
+    /// a `Blob` or `Text`), so the changes are not broken down
+    Opaque,
+}
+
+/// The differences between two states of a tree, as returned by
+/// [`Tree::diff`](crate::Tree::diff).
+///
+/// Only subtrees that differ are included.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TreeDiff {
+    subtrees: BTreeMap<String, SubtreeDiff>,
+}
+
+impl TreeDiff {
+    /// Returns true if the two states are the same.
+    pub fn is_empty(&self) -> bool {
+        self.subtrees.is_empty()
+    }
+
+    /// The differences of one subtree, or `None` if it did not change.
+    pub fn subtree(&self, name: impl AsRef<str>) -> Option<&SubtreeDiff> {
+        self.subtrees.get(name.as_ref())
+    }
+
+    /// The subtrees that changed and their differences, ordered by name.
+    pub fn subtrees(&self) -> impl Iterator<Item = (&str, &SubtreeDiff)> {
+        self.subtrees
+            .iter()
+            .map(|(name, diff)| (name.as_str(), diff))
+    }
+}
+
+/// A value that two branches changed concurrently, as listed by
+/// [`Tree::merge_report`](crate::Tree::merge_report).
+///
+/// A deleted or missing value is [`Value::Deleted`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeConflict {
+    /// The subtree holding the value
+    pub subtree: String,
+    /// Path of the value in the subtree, see [`path::join`](crate::crdt::map::path::join)
+    pub path: String,
+    /// The value when the branches diverged
+    pub base: Value,
+    /// The value written by the first branch
+    pub ours: Value,
+    /// The value written by the second branch
+    pub theirs: Value,
+    /// The value in the merged state, which replaced the other branch's write
+    pub merged: Value,
+}
+
+impl MergeConflict {
+    /// Returns true if the first branch's value was kept.
+    pub fn ours_kept(&self) -> bool {
+        self.merged == self.ours
+    }
+
+    /// Returns true if the second branch's value was kept.
+    pub fn theirs_kept(&self) -> bool {
+        self.merged == self.theirs
+    }
+}
+
+/// The conflicting writes of two branches, as returned by
+/// [`Tree::merge_report`](crate::Tree::merge_report).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MergeReport {
+    conflicts: Vec<MergeConflict>,
+}
+
+impl MergeReport {
+    /// Returns true if the branches can be merged without losing a write.
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+
+    /// The conflicting values, ordered by subtree and then path.
+    pub fn conflicts(&self) -> &[MergeConflict] {
+        &self.conflicts
+    }
+}
+
+pub(crate) fn diff(tree: &Tree, tips_a: &[ID], tips_b: &[ID]) -> Result<TreeDiff> {
+    let op_a = tree.new_operation_with_tips(tips_a)?;
+    let op_b = tree.new_operation_with_tips(tips_b)?;
+
+    let mut names = BTreeSet::new();
+    for entry in tree.get_all_entries()? {
+        names.extend(entry.subtrees());
+    }
+    names.remove(ROOT);
+
+    let backend = tree.backend();
+    let mut subtrees = BTreeMap::new();
+    for name in names {
+        let subtree_tips = |tips: &[ID]| -> Result<BTreeSet<ID>> {
+            Ok(backend
+                .get_subtree_tips_up_to_entries(tree.root_id(), &name, tips)?
+                .into_iter()
+                .collect())
+        };
+        // The state only depends on the subtree's tips
+        if subtree_tips(tips_a)? == subtree_tips(tips_b)? {
+            continue;
+        }
+
+        let diff = match (map_state(&op_a, &name)?, map_state(&op_b, &name)?) {
+            (Some(a), Some(b)) => {
+                let changes = a.diff(&b);
+                if changes.is_empty() {
+                    continue;
+                }
+                SubtreeDiff::Changes(changes)
+            }
+            _ => SubtreeDiff::Opaque,
+        };
+        subtrees.insert(name, diff);
+    }
+    Ok(TreeDiff { subtrees })
+}
+
+pub(crate) fn merge_report(tree: &Tree, tip_a: &ID, tip_b: &ID) -> Result<MergeReport> {
+    let op_a = tree.new_operation_with_tips([tip_a.clone()])?;
+    let op_b = tree.new_operation_with_tips([tip_b.clone()])?;
+    let op_merged = tree.new_operation_with_tips([tip_a.clone(), tip_b.clone()])?;
+
+    let mut names = BTreeSet::new();
+    for entry in tree.get_all_entries()? {
+        names.extend(entry.subtrees());
+    }
+    names.remove(ROOT);
+
+    let backend = tree.backend();
+    let root = tree.root_id();
+    let mut conflicts = Vec::new();
+    for name in names {
+        let tips_a =
+            backend.get_subtree_tips_up_to_entries(root, &name, std::slice::from_ref(tip_a))?;
+        let tips_b =
+            backend.get_subtree_tips_up_to_entries(root, &name, std::slice::from_ref(tip_b))?;
+        // A branch that never wrote the subtree cannot conflict in it
+        if tips_a.is_empty() || tips_b.is_empty() || tips_a == tips_b {
+            continue;
+        }
+
+        let heads: Vec<ID> = tips_a.into_iter().chain(tips_b).collect();
+        let lca = backend.find_lca(root, &name, &heads)?;
+        let op_base = tree.new_operation_with_tips([lca])?;
+        let states = (
+            map_state(&op_base, &name)?,
+            map_state(&op_a, &name)?,
+            map_state(&op_b, &name)?,
+            map_state(&op_merged, &name)?,
+        );
+        let (Some(base), Some(ours), Some(theirs), Some(merged)) = states else {
+            continue;
+        };
+        conflicts.extend(
+            conflicting_leaves(&merged, &base, &ours, &theirs)
+                .into_iter()
+                .map(|leaf| MergeConflict {
+                    subtree: name.clone(),
+                    path: leaf.path,
+                    base: leaf.base,
+                    ours: leaf.ours,
+                    theirs: leaf.theirs,
+                    merged: leaf.merged,
+                }),
+        );
+    }
+    Ok(MergeReport { conflicts })
+}
+
+/// The merged state of a subtree as a Map, or `None` if it is not stored as one.
+fn map_state(op: &AtomicOp, name: &str) -> Result<Option<Map>> {
+    match op.get_full_state::<Map>(name) {
+        Ok(state) => Ok(Some(state)),
+        Err(Error::Serialize(_)) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
@@ -0,0 +1,111 @@
+//! Ephemeral subtrees
+//!
+//! Some data, such as presence or cursor positions, is only interesting while
+//! it is current and should not add to a tree's history. An admin marks such a
+//! subtree as ephemeral by adding a record for it under `ephemeral_subtrees` in
+//! the tree's `_settings`, see [`Tree::mark_ephemeral`](crate::Tree::mark_ephemeral).
+//!
+//! Writes to an ephemeral subtree are made through the usual `Dict` API, but
+//! commits move them into an [`EphemeralState`] held in memory instead of the
+//! entry. Reads of the subtree merge that state over whatever the subtree held
+//! in entries written before it was marked. The state is shared by all clones
+//! of a `BaseDB` and every `Tree` loaded from it, and is exchanged with peers
+//! during sync when given to [`SyncPeer::with_ephemeral_state`](crate::sync::SyncPeer::with_ephemeral_state).
+//! It is lost when the process exits.
+//!
+//! Ephemeral writes are not signed. A commit still checks that its key may
+//! write to the tree, but values received through sync are accepted from any
+//! peer, for subtrees the local settings mark as ephemeral.
+
+use crate::Result;
+use crate::clock::Hlc;
+use crate::crdt::map::Value;
+use crate::crdt::{CRDT, Map};
+use crate::entry::ID;
+use serde::de::DeserializeOwned;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+/// Settings key holding a record for each ephemeral subtree, by subtree name.
+pub(crate) const EPHEMERAL_SUBTREES: &str = "ephemeral_subtrees";
+
+/// The names of the subtrees marked as ephemeral in `settings`, sorted.
+pub(crate) fn ephemeral_subtrees(settings: &Map) -> Vec<String> {
+    let mut subtrees: Vec<String> = match settings.get(EPHEMERAL_SUBTREES) {
+        Some(Value::Map(records)) => records.keys().cloned().collect(),
+        _ => Vec::new(),
+    };
+    subtrees.sort();
+    subtrees
+}
+
+/// The in-memory state of ephemeral subtrees, by tree and subtree name.
+///
+/// Obtained from [`BaseDB::ephemeral_state`](super::BaseDB::ephemeral_state).
+#[derive(Default)]
+pub struct EphemeralState {
+    trees: Mutex<HashMap<ID, BTreeMap<String, Map>>>,
+}
+
+impl EphemeralState {
+    /// Drops the ephemeral state of `tree`, returning true if it had any.
+    pub fn clear(&self, tree: &ID) -> bool {
+        self.trees.lock().unwrap().remove(tree).is_some()
+    }
+
+    /// The state of one subtree, read as `T`, if anything was written to it.
+    pub(crate) fn get<T: DeserializeOwned>(&self, tree: &ID, subtree: &str) -> Result<Option<T>> {
+        let trees = self.trees.lock().unwrap();
+        let Some(state) = trees.get(tree).and_then(|subtrees| subtrees.get(subtree)) else {
+            return Ok(None);
+        };
+        Ok(Some(serde_json::from_value(serde_json::to_value(state)?)?))
+    }
+
+    /// The timestamp of the latest write to the ephemeral subtrees of `tree`.
+    ///
+    /// Ephemeral writes are not ordered by entries, so each commit is
+    /// timestamped after this to make later writes win.
+    pub(crate) fn latest_write(&self, tree: &ID) -> Option<Hlc> {
+        let trees = self.trees.lock().unwrap();
+        trees
+            .get(tree)?
+            .values()
+            .flat_map(|state| state.as_hashmap().keys().filter_map(|key| state.stamp(key)))
+            .map(|stamp| stamp.hlc)
+            .max()
+    }
+
+    /// The state of every ephemeral subtree of `tree`.
+    pub(crate) fn snapshot(&self, tree: &ID) -> BTreeMap<String, Map> {
+        self.trees
+            .lock()
+            .unwrap()
+            .get(tree)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Merges `changes` into the state of each subtree. Changes to subtrees
+    /// not listed in `allowed` are ignored.
+    pub(crate) fn merge(
+        &self,
+        tree: &ID,
+        changes: BTreeMap<String, Map>,
+        allowed: &[String],
+    ) -> Result<()> {
+        let mut trees = self.trees.lock().unwrap();
+        for (subtree, data) in changes {
+            if !allowed.contains(&subtree) {
+                continue;
+            }
+            let subtrees = trees.entry(tree.clone()).or_default();
+            let merged = match subtrees.get(&subtree) {
+                Some(state) => state.merge(&data)?,
+                None => data,
+            };
+            subtrees.insert(subtree, merged);
+        }
+        Ok(())
+    }
+}
@@ -31,6 +31,15 @@ pub enum BaseError {
         name: String,
     },
 
+    /// Several trees have the name that was expected to identify one.
+    #[error("Tree name is not unique: {name} is shared by {} trees", roots.len())]
+    DuplicateTreeName {
+        /// The name shared by the trees
+        name: String,
+        /// Root IDs of the trees with the name, sorted
+        roots: Vec<ID>,
+    },
+
     /// Entry does not belong to the specified tree.
     #[error("Entry '{entry_id}' does not belong to tree '{tree_id}'")]
     EntryNotInTree {
@@ -145,6 +154,21 @@ pub enum BaseError {
         reason: String,
     },
 
+    /// A tree bundle could not be read.
+    #[error("Invalid tree bundle: {reason}")]
+    InvalidBundle {
+        /// Description of why the bundle is invalid
+        reason: String,
+    },
+
+    /// A database backup could not be read, or its private keys could not be
+    /// decrypted.
+    #[error("Invalid backup: {reason}")]
+    InvalidBackup {
+        /// Description of why the backup is invalid
+        reason: String,
+    },
+
     /// Tree state is corrupted or inconsistent.
     #[error("Tree state corruption detected: {reason}")]
     TreeStateCorruption {
@@ -169,6 +193,11 @@ impl BaseError {
         matches!(self, BaseError::TreeAlreadyExists { .. })
     }
 
+    /// Check if this error indicates that a name matched several trees.
+    pub fn is_duplicate_name(&self) -> bool {
+        matches!(self, BaseError::DuplicateTreeName { .. })
+    }
+
     /// Check if this error is authentication-related.
     pub fn is_authentication_error(&self) -> bool {
         matches!(
@@ -202,6 +231,8 @@ impl BaseError {
                 | BaseError::InvalidTreeConfiguration { .. }
                 | BaseError::SettingsValidationFailed { .. }
                 | BaseError::EntryValidationFailed { .. }
+                | BaseError::InvalidBundle { .. }
+                | BaseError::InvalidBackup { .. }
         )
     }
 
@@ -235,7 +266,9 @@ impl BaseError {
     /// Get the tree name if this error is about a named tree.
     pub fn tree_name(&self) -> Option<&str> {
         match self {
-            BaseError::TreeNotFound { name } | BaseError::TreeAlreadyExists { name } => Some(name),
+            BaseError::TreeNotFound { name }
+            | BaseError::TreeAlreadyExists { name }
+            | BaseError::DuplicateTreeName { name, .. } => Some(name),
             _ => None,
         }
     }
@@ -267,6 +300,18 @@ mod tests {
         assert!(err.is_already_exists());
         assert_eq!(err.tree_name(), Some("existing-tree"));
 
+        let err = BaseError::DuplicateTreeName {
+            name: "shared-name".to_string(),
+            roots: vec![ID::from("a"), ID::from("b")],
+        };
+        assert!(err.is_duplicate_name());
+        assert!(!err.is_not_found());
+        assert_eq!(err.tree_name(), Some("shared-name"));
+        assert_eq!(
+            err.to_string(),
+            "Tree name is not unique: shared-name is shared by 2 trees"
+        );
+
         let err = BaseError::EntryNotFound {
             entry_id: ID::from("test-entry"),
         };
@@ -0,0 +1,272 @@
+//! Commit notifications
+//!
+//! `BaseDB` keeps a list of listeners that are called after an entry has been
+//! committed to any of its trees, including the root entries of new trees. All
+//! clones of a `BaseDB`, and all `Tree`s obtained from it, share the same list.
+//!
+//! Entries written directly to the backend, such as those received through
+//! `sync`, do not produce events.
+//!
+//! A listener can be registered with a [`CommitFilter`], which is checked
+//! against the committed entry before the listener is called. The entry is
+//! loaded at most once per commit, and only if some listener has a filter.
+
+use crate::auth::types::SigKey;
+use crate::backend::Database;
+use crate::crdt::Map;
+use crate::entry::{Entry, ID};
+use crate::subtree::encoding;
+use std::sync::{Arc, Mutex};
+
+/// Describes an entry that was committed to a tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitEvent {
+    /// The root ID of the tree the entry was committed to
+    pub tree: ID,
+    /// The ID of the committed entry
+    pub entry: ID,
+}
+
+/// Selects the commits a listener is called for.
+///
+/// Each condition that is set must hold; a condition given several values holds
+/// if any of them does. A filter with no conditions matches every commit.
+///
+/// # Example
+/// ```
+/// # use eidetica::basedb::CommitFilter;
+/// // Changes to user records made with the "admin" key
+/// let filter = CommitFilter::new()
+///     .author("admin")
+///     .subtree("users*")
+///     .key_prefix("user:");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommitFilter {
+    trees: Vec<ID>,
+    authors: Vec<String>,
+    subtrees: Vec<String>,
+    key_prefixes: Vec<String>,
+}
+
+impl CommitFilter {
+    /// Creates a filter that matches every commit.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only match commits to the tree with this root ID.
+    pub fn tree(mut self, tree: impl Into<ID>) -> Self {
+        self.trees.push(tree.into());
+        self
+    }
+
+    /// Only match entries signed with this key name.
+    ///
+    /// For entries signed through a delegation, this is the name of the key in
+    /// the delegated tree.
+    pub fn author(mut self, key_name: impl Into<String>) -> Self {
+        self.authors.push(key_name.into());
+        self
+    }
+
+    /// Only match entries that write a subtree whose name matches this glob.
+    ///
+    /// `*` matches any run of characters and `?` any single character.
+    pub fn subtree(mut self, pattern: impl Into<String>) -> Self {
+        self.subtrees.push(pattern.into());
+        self
+    }
+
+    /// Only match entries that set or delete a top-level key with this prefix.
+    ///
+    /// Only subtrees storing a `Map`, such as `Dict` and `Table`, are checked,
+    /// and only those matching the [`subtree`](Self::subtree) patterns if any are set.
+    pub fn key_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.key_prefixes.push(prefix.into());
+        self
+    }
+
+    /// Returns true if the filter only depends on the event, not the entry.
+    fn needs_entry(&self) -> bool {
+        !(self.authors.is_empty() && self.subtrees.is_empty() && self.key_prefixes.is_empty())
+    }
+
+    fn matches_tree(&self, tree: &ID) -> bool {
+        self.trees.is_empty() || self.trees.contains(tree)
+    }
+
+    /// Checks a committed entry against the filter.
+    pub fn matches(&self, tree: &ID, entry: &Entry) -> bool {
+        if !self.matches_tree(tree) {
+            return false;
+        }
+
+        if !self.authors.is_empty() {
+            let author = match &entry.sig.key {
+                SigKey::Direct(name) => Some(name),
+                SigKey::DelegationPath(steps) => steps.last().map(|step| &step.key),
+            };
+            if !author.is_some_and(|author| self.authors.contains(author)) {
+                return false;
+            }
+        }
+
+        if self.subtrees.is_empty() && self.key_prefixes.is_empty() {
+            return true;
+        }
+        entry.subtrees().iter().any(|name| {
+            let name_matches = self.subtrees.is_empty()
+                || self
+                    .subtrees
+                    .iter()
+                    .any(|pattern| glob_match(pattern, name));
+            name_matches && (self.key_prefixes.is_empty() || self.writes_key_prefix(entry, name))
+        })
+    }
+
+    fn writes_key_prefix(&self, entry: &Entry, subtree: &str) -> bool {
+        let Ok(data) = entry.data(subtree) else {
+            return false;
+        };
+        let Ok(map) = encoding::decode::<Map>(subtree, data) else {
+            return false;
+        };
+        map.as_hashmap().keys().any(|key| {
+            self.key_prefixes
+                .iter()
+                .any(|prefix| key.starts_with(prefix.as_str()))
+        })
+    }
+}
+
+/// Matches `name` against a glob where `*` matches any run of characters and
+/// `?` any single character.
+pub(super) fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Position after the last `*` and the name position it was tried at
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p + 1, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_n)) => {
+                    p = star_p;
+                    n = star_n + 1;
+                    backtrack = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Identifies a listener registered with [`crate::basedb::BaseDB::on_commit`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CommitListenerId(u64);
+
+type Listener = Arc<dyn Fn(&CommitEvent) + Send + Sync>;
+
+/// A listener and the filter it was registered with, if any.
+#[derive(Clone)]
+struct Registration {
+    filter: Option<Arc<CommitFilter>>,
+    listener: Listener,
+}
+
+/// The shared list of commit listeners.
+#[derive(Default)]
+pub(crate) struct CommitListeners {
+    inner: Mutex<ListenerList>,
+}
+
+#[derive(Default)]
+struct ListenerList {
+    next_id: u64,
+    listeners: Vec<(CommitListenerId, Registration)>,
+}
+
+impl CommitListeners {
+    pub(crate) fn add(&self, filter: Option<CommitFilter>, listener: Listener) -> CommitListenerId {
+        let mut inner = self.inner.lock().unwrap();
+        let id = CommitListenerId(inner.next_id);
+        inner.next_id += 1;
+        let registration = Registration {
+            filter: filter.map(Arc::new),
+            listener,
+        };
+        inner.listeners.push((id, registration));
+        id
+    }
+
+    pub(crate) fn remove(&self, id: CommitListenerId) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        let before = inner.listeners.len();
+        inner
+            .listeners
+            .retain(|(listener_id, _)| *listener_id != id);
+        inner.listeners.len() != before
+    }
+
+    /// Calls every listener whose filter matches the event.
+    ///
+    /// The list is copied first, so listeners may register or remove listeners.
+    /// If a filter needs the entry and it cannot be loaded, listeners with such
+    /// filters are skipped.
+    pub(crate) fn notify(&self, event: &CommitEvent, backend: &dyn Database) {
+        let registrations: Vec<Registration> = self
+            .inner
+            .lock()
+            .unwrap()
+            .listeners
+            .iter()
+            .map(|(_, registration)| registration.clone())
+            .collect();
+
+        let mut entry: Option<Option<Entry>> = None;
+        for registration in registrations {
+            if let Some(filter) = &registration.filter {
+                let matched = if filter.needs_entry() {
+                    entry
+                        .get_or_insert_with(|| backend.get(&event.entry).ok())
+                        .as_ref()
+                        .is_some_and(|entry| filter.matches(&event.tree, entry))
+                } else {
+                    filter.matches_tree(&event.tree)
+                };
+                if !matched {
+                    continue;
+                }
+            }
+            (registration.listener)(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::glob_match;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("users", "users"));
+        assert!(!glob_match("users", "users_archive"));
+        assert!(glob_match("users*", "users_archive"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("*_log", "audit_log"));
+        assert!(!glob_match("*_log", "audit_logs"));
+        assert!(glob_match("a*b*c", "axxbyybzc"));
+        assert!(glob_match("doc?", "doc1"));
+        assert!(!glob_match("doc?", "doc"));
+    }
+}
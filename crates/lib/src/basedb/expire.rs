@@ -0,0 +1,179 @@
+//! Expiration of ephemeral trees
+//!
+//! A tree is ephemeral once a maximum age is set in its settings, see
+//! [`Tree::set_max_age`]. Expiring it removes the entries written longer ago
+//! than that age, and the whole tree once nothing has been written to it for
+//! that long. Expiration only runs when asked to, through [`Tree::expire`],
+//! [`BaseDB::expire_trees`](super::BaseDB::expire_trees) or a
+//! [`Maintenance`](super::Maintenance) scheduler.
+//!
+//! Removing entries would leave the remaining ones building on history that is
+//! gone, so expiration first commits a checkpoint of each data subtree holding
+//! only the changes of entries that have not expired, then deletes the expired
+//! entries. Reads stop at that checkpoint. Entries that the tree's structure
+//! depends on are kept however old they are: the root, entries changing
+//! `_settings` or another internal subtree, and entries writing a subtree that
+//! does not store a `Map` and so cannot be checkpointed.
+//!
+//! Expiration is local. Peers keep their own copies of the entries, and
+//! syncing with them can bring expired entries back until they expire there too.
+
+use crate::Result;
+use crate::atomicop::local_state;
+use crate::crdt::{CRDT, Map};
+use crate::entry::{Entry, ID};
+use crate::tree::Tree;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::Duration;
+
+/// Results of one maintenance pass, per ephemeral tree.
+pub type MaintenanceReport = Vec<(ID, Result<ExpireStats>)>;
+
+/// Settings section of an ephemeral tree.
+pub(crate) const EPHEMERAL: &str = "ephemeral";
+/// Key of the maximum age in milliseconds within the `ephemeral` section.
+pub(crate) const MAX_AGE_MS: &str = "max_age_ms";
+
+/// What expiring a tree removed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExpireStats {
+    /// The number of entries deleted from the backend
+    pub entries_removed: usize,
+    /// The checkpoint committed to keep the data that has not expired, if any
+    /// entries expired
+    pub checkpoint: Option<ID>,
+    /// Whether the whole tree was removed, because nothing had been written to
+    /// it within its maximum age
+    pub tree_removed: bool,
+}
+
+/// Removes the entries of `tree` written before `now_ms - max_age`.
+pub(crate) fn expire(tree: &Tree, max_age: Duration, now_ms: u64) -> Result<ExpireStats> {
+    let backend = tree.backend();
+    let root = tree.root_id();
+    let cutoff = now_ms.saturating_sub(max_age.as_millis() as u64);
+    let entries = backend.get_tree(root)?;
+
+    // Checkpoints written by expiration itself do not keep the tree alive
+    let last_write = entries
+        .iter()
+        .filter(|entry| entry.checkpoint_subtrees().is_empty())
+        .map(written_ms)
+        .max();
+    if last_write.is_none_or(|ms| ms < cutoff) {
+        let ids: Vec<ID> = entries.iter().map(Entry::id).collect();
+        let removed = backend.remove_entries(root, &ids)?;
+        tree.state_cache().invalidate_tree(root);
+        return Ok(ExpireStats {
+            entries_removed: removed.entries_removed,
+            checkpoint: None,
+            tree_removed: true,
+        });
+    }
+
+    // The state of each data subtree replayed from the entries that stay
+    let mut states: BTreeMap<String, Map> = BTreeMap::new();
+    let mut pinned: HashSet<String> = HashSet::new();
+    let names: HashSet<String> = entries.iter().flat_map(Entry::subtrees).collect();
+    for name in names {
+        if name.starts_with('_') {
+            pinned.insert(name);
+            continue;
+        }
+        match live_state(tree, &name, cutoff) {
+            Ok(state) => {
+                states.insert(name, state);
+            }
+            Err(crate::Error::Serialize(_)) => {
+                pinned.insert(name);
+            }
+            Err(err) => return Err(err),
+        }
+    }
+
+    let expired: HashSet<ID> = entries
+        .iter()
+        .filter(|entry| {
+            !entry.is_root()
+                && written_ms(entry) < cutoff
+                && entry.subtrees().iter().all(|name| !pinned.contains(name))
+        })
+        .map(Entry::id)
+        .collect();
+    if expired.is_empty() {
+        return Ok(ExpireStats::default());
+    }
+
+    // Kept entries whose children all expire must stay parents of something,
+    // or they would become tips again once the children are removed
+    let mut tips = backend.get_tips(root)?;
+    tips.extend(orphaned(&entries, &expired, |entry| entry.parents().ok()));
+    let op = tree.new_operation_with_tips(&tips)?;
+    let checkpoints = states
+        .into_iter()
+        .map(|(name, state)| {
+            let extra = orphaned(&entries, &expired, |entry| {
+                entry.subtree_parents(&name).ok()
+            });
+            (name, state, extra)
+        })
+        .collect();
+    op.stage_checkpoint_states(checkpoints)?;
+    let checkpoint = op.commit()?;
+
+    let expired: Vec<ID> = expired.into_iter().collect();
+    let removed = backend.remove_entries(root, &expired)?;
+    tree.state_cache().invalidate_tree(root);
+    Ok(ExpireStats {
+        entries_removed: removed.entries_removed,
+        checkpoint: Some(checkpoint),
+        tree_removed: false,
+    })
+}
+
+/// When an entry was written, by its wall clock; entries without a timestamp
+/// count as written at the epoch.
+fn written_ms(entry: &Entry) -> u64 {
+    entry.timestamp().map_or(0, |hlc| hlc.wall_ms)
+}
+
+/// The state of a `Map` subtree with only the changes written at or after `cutoff`.
+///
+/// Checkpoints are skipped, as they may hold the changes of expired entries.
+fn live_state(tree: &Tree, name: &str, cutoff: u64) -> Result<Map> {
+    let mut state = Map::default();
+    for entry in tree.backend().get_subtree_iter(tree.root_id(), name)? {
+        let entry = entry?;
+        if written_ms(&entry) >= cutoff && !entry.is_checkpoint_of(name) {
+            state = state.merge(&local_state::<Map>(&entry, name)?)?;
+        }
+    }
+    Ok(state)
+}
+
+/// The kept entries that have children under `parents_of`, all of which are in
+/// `expired`.
+fn orphaned(
+    entries: &[Entry],
+    expired: &HashSet<ID>,
+    parents_of: impl Fn(&Entry) -> Option<Vec<ID>>,
+) -> Vec<ID> {
+    let mut has_kept_child: HashMap<ID, bool> = HashMap::new();
+    for entry in entries {
+        let kept = !expired.contains(&entry.id());
+        for parent in parents_of(entry).unwrap_or_default() {
+            *has_kept_child.entry(parent).or_default() |= kept;
+        }
+    }
+    // Parents missing locally, such as entries removed by an earlier pass, are skipped
+    let stored: HashSet<ID> = entries.iter().map(Entry::id).collect();
+    let mut orphans: Vec<ID> = has_kept_child
+        .into_iter()
+        .filter(|(id, has_kept_child)| {
+            !has_kept_child && !expired.contains(id) && stored.contains(id)
+        })
+        .map(|(id, _)| id)
+        .collect();
+    orphans.sort();
+    orphans
+}
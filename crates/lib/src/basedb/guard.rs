@@ -0,0 +1,124 @@
+//! Best-effort flushing on exit and panic
+//!
+//! A `PersistGuard` flushes its database's backend when it is dropped, which
+//! covers normal returns and unwinding panics on the thread that owns it. The
+//! first guard also installs a process-wide panic hook that flushes the backends
+//! of all live guards before the previous hook runs, which covers panics on other
+//! threads and builds that abort on panic.
+//!
+//! Flushing from a panic hook is best-effort: the panicking thread may hold
+//! backend locks, so hook flushes run on a helper thread and are abandoned after
+//! a timeout rather than risking a deadlock.
+
+use crate::Result;
+use crate::backend::Database;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Once, mpsc};
+use std::time::Duration;
+
+/// How long the panic hook waits for the backends to flush.
+const HOOK_FLUSH_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Backends of all live guards, flushed by the panic hook.
+static GUARDED: Mutex<Vec<(u64, Arc<dyn Database>)>> = Mutex::new(Vec::new());
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+static INSTALL_HOOK: Once = Once::new();
+/// Set while the hook is flushing, so a panic during the flush does not recurse.
+static FLUSHING: AtomicBool = AtomicBool::new(false);
+
+/// Flushes a database's backend on drop and when the process panics.
+///
+/// Created with [`BaseDB::persist_guard`](crate::basedb::BaseDB::persist_guard).
+/// Keep it alive for as long as the database is in use, typically in `main`.
+///
+/// Only backends that buffer writes have anything to flush; see
+/// [`Database::flush`]. An `InMemory` database without a journal is not written
+/// to disk by a guard, use `AutoPersist` for that.
+///
+/// # Example
+/// ```
+/// # use eidetica::{backend::database::InMemory, basedb::BaseDB};
+/// # let dir = tempfile::tempdir().unwrap();
+/// let db = BaseDB::new(Box::new(InMemory::open_with_journal(dir.path().join("db.journal")).unwrap()));
+/// let _guard = db.persist_guard();
+///
+/// db.add_private_key("key").unwrap();
+/// let tree = db.new_tree_default("key").unwrap();
+/// // The journal is synced to disk when `_guard` goes out of scope, or on a panic
+/// ```
+pub struct PersistGuard {
+    id: u64,
+    backend: Arc<dyn Database>,
+}
+
+impl PersistGuard {
+    pub(crate) fn new(backend: Arc<dyn Database>) -> Self {
+        INSTALL_HOOK.call_once(install_hook);
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        lock_guarded().push((id, Arc::clone(&backend)));
+        Self { id, backend }
+    }
+
+    /// Flushes the backend now.
+    ///
+    /// # Errors
+    /// Returns the error from [`Database::flush`].
+    pub fn flush(&self) -> Result<()> {
+        self.backend.flush()
+    }
+}
+
+impl Drop for PersistGuard {
+    fn drop(&mut self) {
+        lock_guarded().retain(|(id, _)| *id != self.id);
+        let _ = self.backend.flush();
+    }
+}
+
+/// Locks the registry, ignoring poisoning since it only holds a list of backends.
+fn lock_guarded() -> std::sync::MutexGuard<'static, Vec<(u64, Arc<dyn Database>)>> {
+    GUARDED
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn install_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        flush_all_guarded();
+        previous(info);
+    }));
+}
+
+/// Flushes every guarded backend on a helper thread, waiting at most
+/// `HOOK_FLUSH_TIMEOUT` for it to finish.
+fn flush_all_guarded() {
+    if FLUSHING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let backends: Vec<Arc<dyn Database>> = match GUARDED.try_lock() {
+        Ok(guarded) => guarded.iter().map(|(_, b)| Arc::clone(b)).collect(),
+        Err(std::sync::TryLockError::Poisoned(poisoned)) => poisoned
+            .into_inner()
+            .iter()
+            .map(|(_, b)| Arc::clone(b))
+            .collect(),
+        // The panicking thread is registering or dropping a guard
+        Err(std::sync::TryLockError::WouldBlock) => Vec::new(),
+    };
+    if !backends.is_empty() {
+        let (done, finished) = mpsc::channel();
+        let spawned = std::thread::Builder::new()
+            .name("eidetica-panic-flush".to_string())
+            .spawn(move || {
+                for backend in backends {
+                    let _ = backend.flush();
+                }
+                let _ = done.send(());
+            });
+        if spawned.is_ok() {
+            let _ = finished.recv_timeout(HOOK_FLUSH_TIMEOUT);
+        }
+    }
+    FLUSHING.store(false, Ordering::SeqCst);
+}
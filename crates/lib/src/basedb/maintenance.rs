@@ -0,0 +1,150 @@
+//! Scheduled maintenance
+//!
+//! Some upkeep of a database is not tied to any commit and has to run
+//! periodically. `Maintenance` runs it from a background thread at a fixed
+//! interval. Currently this expires the entries of ephemeral trees, see
+//! [`BaseDB::expire_trees`].
+
+use crate::Result;
+use crate::basedb::{BaseDB, MaintenanceReport};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+struct Shared {
+    db: BaseDB,
+    key_name: String,
+    interval: Duration,
+    stopping: Mutex<bool>,
+    wake: Condvar,
+    /// Serializes passes from the worker and the handle
+    run_lock: Mutex<()>,
+    last_error: Mutex<Option<crate::Error>>,
+}
+
+impl Shared {
+    fn run_pass(&self) -> Result<MaintenanceReport> {
+        let _guard = self.run_lock.lock().unwrap();
+        self.db.expire_trees(&self.key_name)
+    }
+
+    fn run(&self) {
+        let mut next = Instant::now() + self.interval;
+        let mut stopping = self.stopping.lock().unwrap();
+        loop {
+            if *stopping {
+                return;
+            }
+            let now = Instant::now();
+            if now < next {
+                stopping = self.wake.wait_timeout(stopping, next - now).unwrap().0;
+                continue;
+            }
+            drop(stopping);
+            let error = match self.run_pass() {
+                Ok(report) => report.into_iter().find_map(|(_, result)| result.err()),
+                Err(e) => Some(e),
+            };
+            if let Some(e) = error {
+                *self.last_error.lock().unwrap() = Some(e);
+            }
+            next = Instant::now() + self.interval;
+            stopping = self.stopping.lock().unwrap();
+        }
+    }
+}
+
+/// Runs database maintenance in the background at a fixed interval.
+///
+/// Each pass expires the entries of every ephemeral tree, committing the
+/// checkpoints that keeps with `key_name`.
+///
+/// # Example
+/// ```
+/// # use eidetica::{backend::database::InMemory, basedb::{BaseDB, Maintenance}};
+/// # use std::time::Duration;
+/// let db = BaseDB::new(Box::new(InMemory::new()));
+/// db.add_private_key("key").unwrap();
+/// let session = db.new_tree_default("key").unwrap();
+/// session.set_max_age(Some(Duration::from_secs(3600))).unwrap();
+///
+/// let maintenance = Maintenance::new(&db, "key", Duration::from_secs(60));
+/// // ...
+/// maintenance.shutdown();
+/// ```
+pub struct Maintenance {
+    shared: Arc<Shared>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl Maintenance {
+    /// Starts running maintenance on `db` every `interval`.
+    ///
+    /// The first pass runs one interval after starting; call
+    /// [`run_now`](Self::run_now) to run one right away.
+    ///
+    /// # Arguments
+    /// * `db` - The database to maintain
+    /// * `key_name` - The private key signing entries committed by maintenance,
+    ///   which needs write permission in each ephemeral tree
+    /// * `interval` - The time between the end of one pass and the start of the next
+    pub fn new(db: &BaseDB, key_name: impl Into<String>, interval: Duration) -> Self {
+        let shared = Arc::new(Shared {
+            db: db.clone(),
+            key_name: key_name.into(),
+            interval,
+            stopping: Mutex::new(false),
+            wake: Condvar::new(),
+            run_lock: Mutex::new(()),
+            last_error: Mutex::new(None),
+        });
+
+        let worker_shared = Arc::clone(&shared);
+        let worker = std::thread::Builder::new()
+            .name("eidetica-maintenance".to_string())
+            .spawn(move || worker_shared.run())
+            .expect("failed to spawn maintenance thread");
+
+        Self {
+            shared,
+            worker: Some(worker),
+        }
+    }
+
+    /// Runs a maintenance pass immediately, without waiting for the interval.
+    ///
+    /// # Returns
+    /// The result of expiring each ephemeral tree
+    pub fn run_now(&self) -> Result<MaintenanceReport> {
+        self.shared.run_pass()
+    }
+
+    /// Takes the most recent error of a background pass, if any.
+    ///
+    /// A pass that fails for one tree still maintains the others.
+    pub fn take_error(&self) -> Option<crate::Error> {
+        self.shared.last_error.lock().unwrap().take()
+    }
+
+    /// Stops the background thread, waiting for a running pass to finish.
+    ///
+    /// Dropping a `Maintenance` does the same.
+    pub fn shutdown(mut self) {
+        self.stop();
+    }
+
+    fn stop(&mut self) {
+        let Some(worker) = self.worker.take() else {
+            return;
+        };
+        *self.shared.stopping.lock().unwrap() = true;
+        self.shared.wake.notify_all();
+        let _ = worker.join();
+    }
+}
+
+impl Drop for Maintenance {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
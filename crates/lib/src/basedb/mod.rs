@@ -6,18 +6,88 @@
 
 use crate::Result;
 use crate::auth::crypto::{format_public_key, generate_keypair};
+use crate::auth::errors::AuthError;
+use crate::auth::invite::{INVITES, Invitation};
+use crate::auth::settings::AuthSettings;
+use crate::auth::types::{AuthKey, KeyStatus};
 use crate::backend::Database;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::backend::DatabaseError;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::backend::database::{InMemory, StorageFormat};
+use crate::clock::wall_clock_ms;
+use crate::constants::SETTINGS;
 use crate::crdt::Map;
-use crate::entry::ID;
-use crate::tree::Tree;
+use crate::crdt::map::Value;
+use crate::entry::{AppInfo, Entry, ID};
+use crate::subtree::Dict;
+use crate::sync::RemoteDatabase;
+use crate::tree::{Tree, resolve_entry};
 use ed25519_dalek::{SigningKey, VerifyingKey};
+use names::NameIndex;
 use rand::Rng;
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::net::ToSocketAddrs;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
 use std::sync::Arc;
 
+#[cfg(feature = "async")]
+mod asynchronous;
+mod audit;
+#[cfg(not(target_arch = "wasm32"))]
+mod backup;
+mod builder;
+pub(crate) mod bundle;
+mod bus;
+mod context;
+pub(crate) mod diff;
+pub(crate) mod ephemeral;
 pub mod errors;
+mod events;
+pub(crate) mod expire;
+#[cfg(not(target_arch = "wasm32"))]
+mod guard;
+#[cfg(not(target_arch = "wasm32"))]
+mod maintenance;
+mod names;
+#[cfg(not(target_arch = "wasm32"))]
+mod persist;
+pub(crate) mod quota;
+mod reads;
+pub(crate) mod repair;
+mod settings;
+mod states;
 
 // Re-export main types for easier access
+#[cfg(feature = "async")]
+pub use asynchronous::BaseDBAsync;
+pub use audit::{SecurityAudit, TreeAudit};
+#[cfg(not(target_arch = "wasm32"))]
+pub use backup::{BackupStats, CorruptEntry, Corruption, RestoreReport};
+pub use builder::TreeBuilder;
+pub use bus::{DatabaseEvent, EventBus, EventReceiver};
+pub(crate) use context::DbContext;
+pub use diff::{MergeConflict, MergeReport, SubtreeDiff, TreeDiff};
+pub use ephemeral::EphemeralState;
 pub use errors::BaseError;
+pub(crate) use events::CommitListeners;
+pub use events::{CommitEvent, CommitFilter, CommitListenerId};
+pub use expire::{ExpireStats, MaintenanceReport};
+#[cfg(not(target_arch = "wasm32"))]
+pub use guard::PersistGuard;
+#[cfg(not(target_arch = "wasm32"))]
+pub use maintenance::Maintenance;
+#[cfg(not(target_arch = "wasm32"))]
+pub use persist::{AutoPersist, AutoPersistConfig};
+pub use quota::TreeQuota;
+pub(crate) use reads::ReadLog;
+pub use reads::{ReadLogConfig, ReadRecord, ReaderIdentity};
+pub use repair::{QuarantineReason, QuarantinedEntry, RepairPlan, Replica};
+pub use settings::TreeSettings;
+pub(crate) use states::StateCache;
+pub use states::StateCacheStats;
 
 /// Database implementation on top of the storage backend.
 ///
@@ -25,9 +95,16 @@ pub use errors::BaseError;
 /// It manages collections of related entries, called `Tree`s, and interacts with a
 /// pluggable `Database` for storage and retrieval.
 /// Each `Tree` represents an independent history of data, identified by a root `Entry`.
+///
+/// Cloning is cheap; all clones share the same backend.
+#[derive(Clone)]
 pub struct BaseDB {
     /// The database storage used by the database.
     backend: Arc<dyn Database>,
+    /// Listeners, logs and caches shared with the trees of this database
+    context: Arc<DbContext>,
+    /// Index of tree names, shared by all clones
+    names: Arc<NameIndex>,
     // Blob storage will be separate, maybe even just an extension
     // storage: IPFS;
 }
@@ -36,14 +113,192 @@ impl BaseDB {
     pub fn new(backend: Box<dyn Database>) -> Self {
         Self {
             backend: Arc::from(backend),
+            context: Arc::default(),
+            names: Arc::default(),
         }
     }
 
+    /// Opens the database stored at `path`, detecting how it was written.
+    ///
+    /// Snapshots written by `InMemory::save_to_file` (in any codec or
+    /// compression), `InMemory` journals, SQLite databases and archives are
+    /// recognized by their first bytes, see [`StorageFormat`]. The private keys
+    /// stored in the file are available right away, and its trees through
+    /// [`all_trees`](Self::all_trees) and [`load_tree`](Self::load_tree).
+    ///
+    /// If nothing exists at `path`, an empty `InMemory` database is returned;
+    /// save it with `save_to_file` or `AutoPersist`.
+    ///
+    /// # Errors
+    /// Fails if the file cannot be read or decoded, or if it needs a backend
+    /// whose feature is not enabled.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let backend = match StorageFormat::detect(&path)? {
+            Some(format) => format.open(&path)?,
+            None => Box::new(InMemory::new()),
+        };
+        Ok(Self::new(backend))
+    }
+
     /// Get a reference to the backend
     pub fn backend(&self) -> &Arc<dyn Database> {
         &self.backend
     }
 
+    /// Register a listener called after every entry committed to a tree of this database.
+    ///
+    /// The listener runs synchronously on the committing thread, after the entry
+    /// has been stored, so it should return quickly. It is shared by all clones of
+    /// this `BaseDB` and every `Tree` loaded from it. See [`CommitEvent`].
+    ///
+    /// # Returns
+    /// An ID that can be passed to `remove_commit_listener`.
+    pub fn on_commit(
+        &self,
+        listener: impl Fn(&CommitEvent) + Send + Sync + 'static,
+    ) -> CommitListenerId {
+        self.context.commit_listeners.add(None, Arc::new(listener))
+    }
+
+    /// Register a listener called only for commits that match `filter`.
+    ///
+    /// The filter is checked before the listener is called, so subscribers that
+    /// only care about a slice of a busy tree are not woken for the rest. Like
+    /// listeners registered with `on_commit`, it runs on the committing thread.
+    ///
+    /// # Returns
+    /// An ID that can be passed to `remove_commit_listener`.
+    pub fn on_commit_filtered(
+        &self,
+        filter: CommitFilter,
+        listener: impl Fn(&CommitEvent) + Send + Sync + 'static,
+    ) -> CommitListenerId {
+        self.context
+            .commit_listeners
+            .add(Some(filter), Arc::new(listener))
+    }
+
+    /// Remove a listener registered with `on_commit`.
+    ///
+    /// # Returns
+    /// `true` if the listener was registered.
+    pub fn remove_commit_listener(&self, id: CommitListenerId) -> bool {
+        self.context.commit_listeners.remove(id)
+    }
+
+    /// Start recording reads of subtrees in a local audit log.
+    ///
+    /// Records which key read which subtree of which tree, and when, in a ring
+    /// buffer shared by all clones of this `BaseDB` and every `Tree` loaded from
+    /// it. Records are kept in memory only. Enabling the log again replaces its
+    /// configuration and discards earlier records. See [`ReadLogConfig`] for the
+    /// privacy controls.
+    pub fn enable_read_log(&self, config: ReadLogConfig) {
+        self.context.read_log.enable(config);
+    }
+
+    /// Stop recording reads and discard the recorded reads.
+    pub fn disable_read_log(&self) {
+        self.context.read_log.disable();
+    }
+
+    /// Check if reads are being recorded.
+    pub fn is_read_log_enabled(&self) -> bool {
+        self.context.read_log.is_enabled()
+    }
+
+    /// Get the recorded reads, oldest first.
+    pub fn read_log(&self) -> Vec<ReadRecord> {
+        self.context.read_log.records()
+    }
+
+    /// Remove and return the recorded reads, oldest first.
+    ///
+    /// Use this to move records to longer-term storage without reading the same
+    /// record twice.
+    pub fn take_read_log(&self) -> Vec<ReadRecord> {
+        self.context.read_log.take()
+    }
+
+    /// Record `info` in the entries of every operation created from now on.
+    ///
+    /// Applies to operations on all trees loaded from this `BaseDB` or its
+    /// clones, including trees loaded earlier. An operation can override it with
+    /// [`AtomicOp::set_app_info`](crate::atomicop::AtomicOp::set_app_info).
+    /// See [`AppInfo`].
+    pub fn set_default_app_info(&self, info: AppInfo) {
+        *self.context.default_app_info.write().unwrap() = Some(info);
+    }
+
+    /// Stop recording a default app info in new entries.
+    pub fn clear_default_app_info(&self) {
+        *self.context.default_app_info.write().unwrap() = None;
+    }
+
+    /// Get the app info recorded in new entries by default, if set.
+    pub fn default_app_info(&self) -> Option<AppInfo> {
+        self.context.default_app_info.read().unwrap().clone()
+    }
+
+    /// Set how many merged subtree states are cached in memory.
+    ///
+    /// Reading a subtree whose tips have not changed since it was last read
+    /// returns a copy of the cached state instead of merging its history again.
+    /// The cache is shared by all clones of this `BaseDB` and every `Tree`
+    /// loaded from it, and holds 64 states by default. A capacity of 0 turns it
+    /// off.
+    pub fn set_state_cache_capacity(&self, capacity: usize) {
+        self.context.state_cache.set_capacity(capacity);
+    }
+
+    /// Drop all cached subtree states.
+    pub fn clear_state_cache(&self) {
+        self.context.state_cache.clear();
+    }
+
+    /// Get the size and hit rate of the subtree state cache.
+    pub fn state_cache_stats(&self) -> StateCacheStats {
+        self.context.state_cache.stats()
+    }
+
+    /// Get the in-memory state of the ephemeral subtrees of this database's trees.
+    ///
+    /// Pass it to [`SyncPeer::with_ephemeral_state`](crate::sync::SyncPeer::with_ephemeral_state)
+    /// to exchange ephemeral subtrees with peers. See [`Tree::mark_ephemeral`].
+    pub fn ephemeral_state(&self) -> Arc<EphemeralState> {
+        Arc::clone(&self.context.ephemeral)
+    }
+
+    /// Subscribe to the events of this database.
+    ///
+    /// The receiver gets every [`DatabaseEvent`] published from now on: trees
+    /// created, and entries committed, through this `BaseDB`, its clones, or any
+    /// `Tree` loaded from them, and entries received through sync sessions given
+    /// the [`event_bus`](Self::event_bus). Unlike listeners registered with
+    /// [`on_commit`](Self::on_commit), receivers are read from any thread and
+    /// never hold up the committing one. See [`EventReceiver`].
+    pub fn events(&self) -> EventReceiver {
+        self.context.event_bus.subscribe()
+    }
+
+    /// Get the bus publishing the events of this database.
+    ///
+    /// Pass it to [`SyncPeer::with_event_bus`](crate::sync::SyncPeer::with_event_bus)
+    /// to publish the entries received during sync. See [`events`](Self::events).
+    pub fn event_bus(&self) -> Arc<EventBus> {
+        Arc::clone(&self.context.event_bus)
+    }
+
+    /// Create a guard that flushes the backend when dropped or when the process panics.
+    ///
+    /// This gives best-effort durability without custom exit or signal handling.
+    /// See [`PersistGuard`] for what is and is not covered.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn persist_guard(&self) -> PersistGuard {
+        PersistGuard::new(Arc::clone(&self.backend))
+    }
+
     /// Create a new tree in the database.
     ///
     /// A `Tree` represents a collection of related entries, analogous to a table.
@@ -57,7 +312,25 @@ impl BaseDB {
     /// # Returns
     /// A `Result` containing the newly created `Tree` or an error.
     pub fn new_tree(&self, settings: Map, signing_key_name: impl AsRef<str>) -> Result<Tree> {
-        Tree::new(settings, Arc::clone(&self.backend), signing_key_name)
+        let tree = Tree::new(settings, Arc::clone(&self.backend), signing_key_name)?
+            .with_context(Arc::clone(&self.context));
+        self.context.commit_listeners.notify(
+            &CommitEvent {
+                tree: tree.root_id().clone(),
+                entry: tree.root_id().clone(),
+            },
+            self.backend.as_ref(),
+        );
+        self.context.event_bus.publish(DatabaseEvent::TreeCreated {
+            tree: tree.root_id().clone(),
+        });
+        self.context
+            .event_bus
+            .publish(DatabaseEvent::EntryCommitted {
+                tree: tree.root_id().clone(),
+                entry: tree.root_id().clone(),
+            });
+        Ok(tree)
     }
 
     /// Create a new tree with default empty settings
@@ -70,23 +343,20 @@ impl BaseDB {
     /// A `Result` containing the newly created `Tree` or an error.
     pub fn new_tree_default(&self, signing_key_name: impl AsRef<str>) -> Result<Tree> {
         let mut settings = Map::new();
-
-        // Add a unique tree identifier to ensure each tree gets a unique root ID
-        // This prevents content-addressable collision when creating multiple trees
-        // with identical settings
-        let unique_id = format!(
-            "tree_{}",
-            rand::thread_rng()
-                .sample_iter(&rand::distributions::Alphanumeric)
-                .take(16)
-                .map(char::from)
-                .collect::<String>()
-        );
-        settings.set_string("tree_id", unique_id);
-
+        settings.set_string("tree_id", unique_tree_id());
         self.new_tree(settings, signing_key_name)
     }
 
+    /// Start configuring a new tree.
+    ///
+    /// Unlike [`new_tree_default`](Self::new_tree_default), the name, settings
+    /// and hash algorithm set on the builder are written in the tree's root
+    /// entry, so the tree is created configured in a single commit. See
+    /// [`TreeBuilder`].
+    pub fn tree_builder(&self) -> TreeBuilder<'_> {
+        TreeBuilder::new(self)
+    }
+
     /// Load an existing tree from the database by its root ID.
     ///
     /// # Arguments
@@ -100,7 +370,232 @@ impl BaseDB {
         self.backend.get(root_id)?;
 
         // Create a tree object with the given root_id
-        Tree::new_from_id(root_id.clone(), Arc::clone(&self.backend))
+        Ok(
+            Tree::new_from_id(root_id.clone(), Arc::clone(&self.backend))?
+                .with_context(Arc::clone(&self.context)),
+        )
+    }
+
+    /// Mount a tree stored on a remote node without replicating it.
+    ///
+    /// The returned tree reads entries from the remote node on demand and pushes
+    /// its commits to it; nothing is written to this database's backend. Private
+    /// keys used to sign commits are still taken from, and stored in, this
+    /// database. The remote node must be serving sync sessions over TCP, e.g.
+    /// with [`SyncPeer::serve`](crate::sync::SyncPeer::serve).
+    ///
+    /// Each mount holds its own connection. See [`RemoteDatabase`] for the
+    /// trade-offs against syncing the tree.
+    ///
+    /// # Arguments
+    /// * `addr` - The address of the remote node
+    /// * `root_id` - The root ID of the tree on the remote node
+    ///
+    /// # Returns
+    /// A `Result` containing the mounted `Tree`, or an error if the connection
+    /// fails or the remote node does not have the tree.
+    pub fn mount_remote(&self, addr: impl ToSocketAddrs, root_id: &ID) -> Result<Tree> {
+        let remote: Arc<dyn Database> =
+            Arc::new(RemoteDatabase::connect(addr, Arc::clone(&self.backend))?);
+        remote.get(root_id)?;
+        Ok(Tree::new_from_id(root_id.clone(), remote)?.with_context(Arc::clone(&self.context)))
+    }
+
+    /// Import a tree from a bundle written by [`Tree::export_bundle`].
+    ///
+    /// Entries that are already stored are skipped, so importing the same or an
+    /// older bundle of a tree again only adds what is missing. Verification
+    /// statuses recorded in the bundle are not trusted: entries the exporter had
+    /// verified are checked again against the tree's authentication settings.
+    ///
+    /// # Returns
+    /// A `Result` containing the imported `Tree`, or `BaseError::InvalidBundle`
+    /// if the data is not a valid bundle.
+    pub fn import_bundle(&self, reader: impl Read) -> Result<Tree> {
+        let root = bundle::import(&self.backend, reader)?;
+        self.load_tree(&root)
+    }
+
+    /// Back up every tree of the database to the file at `path`.
+    ///
+    /// The backup holds every entry with its verification status, but no
+    /// private keys; use [`backup_with_keys`](Self::backup_with_keys) to
+    /// include them. Restore it with [`restore`](Self::restore).
+    ///
+    /// # Returns
+    /// A `Result` containing the number of trees and entries written.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn backup<P: AsRef<Path>>(&self, path: P) -> Result<BackupStats> {
+        self.write_backup(path.as_ref(), None)
+    }
+
+    /// Back up every tree of the database and its private keys to the file at
+    /// `path`, encrypting the keys with `passphrase`.
+    ///
+    /// The passphrase is needed to restore the keys with
+    /// [`restore_with_keys`](Self::restore_with_keys); the trees can be
+    /// restored without it.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn backup_with_keys<P: AsRef<Path>>(
+        &self,
+        path: P,
+        passphrase: impl AsRef<str>,
+    ) -> Result<BackupStats> {
+        self.write_backup(path.as_ref(), Some(passphrase.as_ref()))
+    }
+
+    /// Restore the trees of a backup written by [`backup`](Self::backup) or
+    /// [`backup_with_keys`](Self::backup_with_keys).
+    ///
+    /// Nothing in the backup is trusted. Each entry's ID is computed again from
+    /// its content, and an entry that does not match the ID it was backed up
+    /// with is not restored. Entries that were verified when backed up are
+    /// verified again against their tree's authentication settings, and stored
+    /// as failing verification if their signature does not check out. Both
+    /// are listed in the report's `corrupt` entries. Entries that are already
+    /// stored are skipped.
+    ///
+    /// # Returns
+    /// A `Result` containing what was restored, or `BaseError::InvalidBackup`
+    /// if the file is not a valid backup.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn restore<P: AsRef<Path>>(&self, path: P) -> Result<RestoreReport> {
+        self.read_backup(path.as_ref(), None)
+    }
+
+    /// Restore the trees and private keys of a backup written by
+    /// [`backup_with_keys`](Self::backup_with_keys).
+    ///
+    /// Keys are checked against `passphrase` before anything is restored, so a
+    /// wrong passphrase fails with `BaseError::InvalidBackup` and leaves the
+    /// database unchanged. A key whose name is already used by a different key
+    /// in this database is not restored and is listed in the report's
+    /// `key_conflicts`. Trees are restored as by [`restore`](Self::restore).
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn restore_with_keys<P: AsRef<Path>>(
+        &self,
+        path: P,
+        passphrase: impl AsRef<str>,
+    ) -> Result<RestoreReport> {
+        self.read_backup(path.as_ref(), Some(passphrase.as_ref()))
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn write_backup(&self, path: &Path, passphrase: Option<&str>) -> Result<BackupStats> {
+        let io = |e| -> crate::Error { DatabaseError::FileIo { source: e }.into() };
+        let mut writer = std::io::BufWriter::new(std::fs::File::create(path).map_err(io)?);
+        let stats = backup::backup(&self.backend, &mut writer, passphrase)?;
+        std::io::Write::flush(&mut writer).map_err(io)?;
+        Ok(stats)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn read_backup(&self, path: &Path, passphrase: Option<&str>) -> Result<RestoreReport> {
+        let file = std::fs::File::open(path)
+            .map_err(|e| -> crate::Error { DatabaseError::FileIo { source: e }.into() })?;
+        backup::restore(&self.backend, std::io::BufReader::new(file), passphrase)
+    }
+
+    /// Redeem an invitation created with [`Tree::create_invite`], registering
+    /// the private key `key_name` in the invited tree.
+    ///
+    /// The tree's entries must already be stored in this database, e.g. from a
+    /// sync or a bundle. The invitation is checked against the tree's current
+    /// settings and the local clock, then an entry signed by `key_name` is
+    /// committed that adds its public key to `_settings.auth` with the invited
+    /// permission. Other replicas accept the entry once they sync it, by
+    /// checking the invitation it carries; see [`crate::auth::invite`].
+    ///
+    /// # Arguments
+    /// * `invite` - The invitation, e.g. from [`Invitation::from_token`]
+    /// * `key_name` - The name of a private key in this database, which becomes
+    ///   the key's name in the tree
+    ///
+    /// # Returns
+    /// A `Result` containing the tree, with `key_name` as its default
+    /// authentication key.
+    ///
+    /// # Errors
+    /// Returns `AuthError::InvalidInvitation` or `AuthError::InvitationExpired`
+    /// if the invitation cannot be redeemed, `AuthError::KeyAlreadyExists` if
+    /// the tree already has a key named `key_name`, or an error if the tree or
+    /// the private key is not found.
+    pub fn redeem_invite(&self, invite: &Invitation, key_name: impl AsRef<str>) -> Result<Tree> {
+        let key_name = key_name.as_ref();
+        let mut tree = self.load_tree(invite.tree())?;
+        let signing_key = self.backend.get_private_key(key_name)?.ok_or_else(|| {
+            BaseError::SigningKeyNotFound {
+                key_name: key_name.to_string(),
+            }
+        })?;
+
+        let settings = tree.get_settings()?.get_all()?;
+        if matches!(settings.get("auth"), Some(Value::Map(auth)) if auth.get(key_name).is_some()) {
+            return Err(AuthError::KeyAlreadyExists {
+                key_name: key_name.to_string(),
+            }
+            .into());
+        }
+        invite.check(&settings, Some(wall_clock_ms()))?;
+
+        let mut auth = AuthSettings::new();
+        auth.add_key(
+            key_name,
+            AuthKey {
+                pubkey: format_public_key(&signing_key.verifying_key()),
+                permissions: invite.permission().clone(),
+                status: KeyStatus::Active,
+            },
+        )?;
+        let key = auth
+            .as_map()
+            .get(key_name)
+            .cloned()
+            .expect("the key was just added");
+
+        tree.set_default_auth_key(key_name);
+        let op = tree.new_operation()?;
+        let settings = op.get_subtree::<Dict>(SETTINGS)?;
+        settings.set_at_path(["auth", key_name], key)?;
+        settings.set_at_path([INVITES, invite.id()], Value::Text(invite.to_token()))?;
+        op.commit()?;
+        Ok(tree)
+    }
+
+    /// Resolve a link to an entry of any tree in this database.
+    ///
+    /// The linked entry must be stored locally and its content must still hash
+    /// to the linked ID.
+    ///
+    /// # Arguments
+    /// * `link` - The linked entry ID, typically read from a `Value::Link`
+    ///
+    /// # Returns
+    /// A `Result` containing the linked entry, or an error if it is missing or
+    /// its content does not match the link.
+    pub fn resolve_link(&self, link: &ID) -> Result<Entry> {
+        resolve_entry(self.backend.as_ref(), link)
+    }
+
+    /// Load the tree a linked entry belongs to.
+    ///
+    /// Together with [`Tree::linked_subtree`] this follows a link to the records
+    /// it references in another tree.
+    ///
+    /// # Arguments
+    /// * `link` - The linked entry ID
+    ///
+    /// # Returns
+    /// A `Result` containing the tree of the linked entry, or an error if the link
+    /// cannot be resolved.
+    pub fn load_linked_tree(&self, link: &ID) -> Result<Tree> {
+        let entry = self.resolve_link(link)?;
+        let root = if entry.is_toplevel_root() {
+            link.clone()
+        } else {
+            entry.root()
+        };
+        self.load_tree(&root)
     }
 
     /// Load all trees stored in the backend.
@@ -115,10 +610,10 @@ impl BaseDB {
         let mut trees = Vec::new();
 
         for root_id in root_ids {
-            trees.push(Tree::new_from_id(
-                root_id.clone(),
-                Arc::clone(&self.backend),
-            )?);
+            trees.push(
+                Tree::new_from_id(root_id.clone(), Arc::clone(&self.backend))?
+                    .with_context(Arc::clone(&self.context)),
+            );
         }
 
         Ok(trees)
@@ -126,8 +621,10 @@ impl BaseDB {
 
     /// Find trees by their assigned name.
     ///
-    /// Searches through all trees in the database and returns those whose "name"
-    /// setting matches the provided name.
+    /// Returns the trees whose "name" setting matches the provided name, sorted
+    /// by root ID. Names are looked up in an index that is refreshed from the
+    /// backend on every call, so trees created, renamed or received since the
+    /// last lookup are found.
     ///
     /// # Arguments
     /// * `name` - The name to search for.
@@ -140,27 +637,52 @@ impl BaseDB {
     /// Returns `BaseError::TreeNotFound` if no trees with the specified name are found.
     pub fn find_tree(&self, name: impl AsRef<str>) -> Result<Vec<Tree>> {
         let name = name.as_ref();
-        let all_trees = self.all_trees()?;
-        let mut matching_trees = Vec::new();
-
-        for tree in all_trees {
-            // Attempt to get the name from the tree's settings
-            if let Ok(tree_name) = tree.get_name()
-                && tree_name == name
-            {
-                matching_trees.push(tree);
+        match self.names.roots_by_name(&self.backend)?.remove(name) {
+            Some(roots) => roots.iter().map(|root| self.load_tree(root)).collect(),
+            None => Err(BaseError::TreeNotFound {
+                name: name.to_string(),
             }
-            // Ignore trees where getting the name fails or doesn't match
+            .into()),
         }
+    }
 
-        if matching_trees.is_empty() {
-            Err(BaseError::TreeNotFound {
+    /// Find the tree with the given name.
+    ///
+    /// Like [`find_tree`](Self::find_tree), for names that are expected to be
+    /// unique in the database.
+    ///
+    /// # Errors
+    /// Returns `BaseError::TreeNotFound` if no tree has the name, and
+    /// `BaseError::DuplicateTreeName` if several trees have it.
+    pub fn find_tree_by_name(&self, name: impl AsRef<str>) -> Result<Tree> {
+        let name = name.as_ref();
+        let mut trees = self.find_tree(name)?;
+        if trees.len() > 1 {
+            return Err(BaseError::DuplicateTreeName {
                 name: name.to_string(),
+                roots: trees.iter().map(|tree| tree.root_id().clone()).collect(),
             }
-            .into())
-        } else {
-            Ok(matching_trees)
+            .into());
         }
+        Ok(trees.remove(0))
+    }
+
+    /// All named trees, by name.
+    ///
+    /// Trees sharing a name are listed together, sorted by root ID. Trees
+    /// without a name are left out; [`all_trees`](Self::all_trees) lists them.
+    pub fn trees_by_name(&self) -> Result<BTreeMap<String, Vec<Tree>>> {
+        self.names
+            .roots_by_name(&self.backend)?
+            .into_iter()
+            .map(|(name, roots)| {
+                let trees = roots
+                    .iter()
+                    .map(|root| self.load_tree(root))
+                    .collect::<Result<Vec<_>>>()?;
+                Ok((name, trees))
+            })
+            .collect()
     }
 
     // === Authentication Key Management ===
@@ -279,4 +801,59 @@ impl BaseDB {
             Ok(None)
         }
     }
+
+    /// Run a security self-check of this database.
+    ///
+    /// Reports how the backend stores private keys, how signatures are verified,
+    /// and the authentication configuration of every tree, for compliance
+    /// reviews. [`SecurityAudit::warnings`] summarizes the findings.
+    ///
+    /// # Returns
+    /// A `Result` containing the audit, or an error if the backend or a tree's
+    /// settings cannot be read.
+    pub fn security_audit(&self) -> Result<SecurityAudit> {
+        audit::audit(self.backend.as_ref(), &self.all_trees()?)
+    }
+
+    /// Expire the entries of every ephemeral tree, see [`Tree::expire`].
+    ///
+    /// Trees without a maximum age are skipped. A tree that fails to expire
+    /// does not stop the others; its error is returned with its root ID.
+    ///
+    /// # Arguments
+    /// * `key_name` - The private key signing the checkpoints that expiration
+    ///   commits, which needs write permission in each ephemeral tree
+    ///
+    /// # Returns
+    /// The result of expiring each ephemeral tree
+    pub fn expire_trees(&self, key_name: &str) -> Result<MaintenanceReport> {
+        let mut report = Vec::new();
+        for mut tree in self.all_trees()? {
+            let result = match tree.max_age() {
+                Ok(None) => continue,
+                Ok(Some(_)) => {
+                    tree.set_default_auth_key(key_name);
+                    tree.expire()
+                }
+                Err(e) => Err(e),
+            };
+            report.push((tree.root_id().clone(), result));
+        }
+        Ok(report)
+    }
+}
+
+/// A unique identifier stored in the settings of new trees.
+///
+/// It gives each tree a unique root ID, preventing content-addressable
+/// collisions when several trees are created with identical settings.
+fn unique_tree_id() -> String {
+    format!(
+        "tree_{}",
+        rand::thread_rng()
+            .sample_iter(&rand::distributions::Alphanumeric)
+            .take(16)
+            .map(char::from)
+            .collect::<String>()
+    )
 }
@@ -0,0 +1,80 @@
+//! Index of tree names
+//!
+//! Trees are named by the `name` key of their settings. Looking a tree up by
+//! name would otherwise load and merge the settings of every tree in the
+//! database. The index remembers each tree's name together with the tips of
+//! its `_settings` subtree when the name was read, and reads it again only for
+//! trees whose settings tips have moved since, so it follows trees created
+//! here, settings changed by commits, and entries received through sync or
+//! bundles alike.
+//!
+//! Names are not unique: several trees, for instance created independently on
+//! different devices, can share one.
+
+use crate::Result;
+use crate::backend::Database;
+use crate::constants::SETTINGS;
+use crate::entry::ID;
+use crate::tree::Tree;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+/// A tree's name as of the given settings tips.
+struct IndexedName {
+    settings_tips: Vec<ID>,
+    name: Option<String>,
+}
+
+/// Tree names by root ID, shared by the clones of a `BaseDB`.
+#[derive(Default)]
+pub(crate) struct NameIndex {
+    trees: Mutex<HashMap<ID, IndexedName>>,
+}
+
+impl NameIndex {
+    /// The root IDs of the named trees, by name, each sorted.
+    ///
+    /// Brings the index up to date with the backend first.
+    pub(crate) fn roots_by_name(
+        &self,
+        backend: &Arc<dyn Database>,
+    ) -> Result<BTreeMap<String, Vec<ID>>> {
+        let roots = backend.all_roots()?;
+        let mut trees = self.trees.lock().unwrap();
+        // Trees can disappear, for instance when they expire
+        trees.retain(|root, _| roots.contains(root));
+
+        for root in roots {
+            let mut settings_tips = backend.get_subtree_tips(&root, SETTINGS)?;
+            settings_tips.sort();
+            if trees
+                .get(&root)
+                .is_some_and(|indexed| indexed.settings_tips == settings_tips)
+            {
+                continue;
+            }
+            // Trees without a name, or with one that is not text, are unnamed
+            let name = Tree::new_from_id(root.clone(), Arc::clone(backend))?
+                .get_name()
+                .ok();
+            trees.insert(
+                root,
+                IndexedName {
+                    settings_tips,
+                    name,
+                },
+            );
+        }
+
+        let mut by_name: BTreeMap<String, Vec<ID>> = BTreeMap::new();
+        for (root, indexed) in trees.iter() {
+            if let Some(name) = &indexed.name {
+                by_name.entry(name.clone()).or_default().push(root.clone());
+            }
+        }
+        for roots in by_name.values_mut() {
+            roots.sort();
+        }
+        Ok(by_name)
+    }
+}
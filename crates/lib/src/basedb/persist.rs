@@ -0,0 +1,264 @@
+//! Debounced automatic persistence
+//!
+//! Backends such as `InMemory` only write to disk when asked to. `AutoPersist`
+//! listens for commit events on a `BaseDB` and flushes the backend from a
+//! background thread:
+//!
+//! * A flush happens once no commit has arrived for the debounce period, so a
+//!   burst of commits results in a single write.
+//! * While commits keep arriving, a flush still happens at least once per
+//!   maximum interval, bounding how much work can be lost.
+//! * Any pending changes are flushed when the `AutoPersist` is shut down or dropped.
+
+use crate::Result;
+use crate::backend::database::InMemory;
+use crate::basedb::errors::BaseError;
+use crate::basedb::{BaseDB, CommitListenerId};
+use std::path::PathBuf;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+type FlushFn = Box<dyn Fn() -> Result<()> + Send + Sync>;
+
+/// Timing configuration for [`AutoPersist`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AutoPersistConfig {
+    /// How long to wait after the last commit before flushing
+    pub debounce: Duration,
+    /// The longest changes may stay unflushed while commits keep arriving
+    pub max_interval: Duration,
+}
+
+impl Default for AutoPersistConfig {
+    fn default() -> Self {
+        Self {
+            debounce: Duration::from_millis(500),
+            max_interval: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Pending work shared between the handle, the commit listener and the worker thread.
+#[derive(Default)]
+struct State {
+    /// When the oldest unflushed change was made
+    dirty_since: Option<Instant>,
+    /// When the newest unflushed change was made
+    last_change: Option<Instant>,
+    stopping: bool,
+}
+
+struct Shared {
+    config: AutoPersistConfig,
+    state: Mutex<State>,
+    wake: Condvar,
+    flush: FlushFn,
+    /// Serializes flushes from the worker and the handle
+    flush_lock: Mutex<()>,
+    last_error: Mutex<Option<crate::Error>>,
+}
+
+impl Shared {
+    fn mark_dirty(&self) {
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap();
+        state.dirty_since.get_or_insert(now);
+        state.last_change = Some(now);
+        self.wake.notify_all();
+    }
+
+    /// Flushes if there are unflushed changes.
+    fn flush_pending(&self) -> Result<()> {
+        let _guard = self.flush_lock.lock().unwrap();
+        let was_dirty = {
+            let mut state = self.state.lock().unwrap();
+            state.last_change = None;
+            state.dirty_since.take().is_some()
+        };
+        if !was_dirty {
+            return Ok(());
+        }
+        (self.flush)().inspect_err(|_| {
+            // Keep the changes pending so the next flush retries them
+            let mut state = self.state.lock().unwrap();
+            let now = Instant::now();
+            state.dirty_since.get_or_insert(now);
+            state.last_change.get_or_insert(now);
+        })
+    }
+
+    fn run(&self) {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            let deadline = match (state.dirty_since, state.last_change) {
+                (Some(first), Some(last)) => {
+                    Some((last + self.config.debounce).min(first + self.config.max_interval))
+                }
+                _ => None,
+            };
+            if state.stopping {
+                return;
+            }
+            match deadline {
+                None => state = self.wake.wait(state).unwrap(),
+                Some(deadline) if Instant::now() < deadline => {
+                    let timeout = deadline - Instant::now();
+                    state = self.wake.wait_timeout(state, timeout).unwrap().0;
+                }
+                Some(_) => {
+                    drop(state);
+                    if let Err(e) = self.flush_pending() {
+                        *self.last_error.lock().unwrap() = Some(e);
+                        // Back off before retrying a failing flush
+                        std::thread::sleep(self.config.debounce);
+                    }
+                    state = self.state.lock().unwrap();
+                }
+            }
+        }
+    }
+}
+
+/// Flushes a database in the background after commits, with debouncing.
+///
+/// # Example
+/// ```
+/// # use eidetica::{backend::database::InMemory, basedb::{AutoPersist, AutoPersistConfig, BaseDB}};
+/// # let dir = tempfile::tempdir().unwrap();
+/// # let path = dir.path().join("db.json");
+/// let db = BaseDB::new(Box::new(InMemory::new()));
+/// let persist = AutoPersist::to_file(&db, &path, AutoPersistConfig::default()).unwrap();
+///
+/// db.add_private_key("key").unwrap();
+/// let tree = db.new_tree_default("key").unwrap();
+///
+/// // Dropping or shutting down flushes anything still pending
+/// persist.shutdown().unwrap();
+/// assert!(path.exists());
+/// ```
+pub struct AutoPersist {
+    db: BaseDB,
+    listener: CommitListenerId,
+    shared: Arc<Shared>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl AutoPersist {
+    /// Starts flushing `db` with the given function after commits.
+    ///
+    /// # Arguments
+    /// * `db` - The database whose commits trigger flushes
+    /// * `config` - Debounce and maximum interval timings
+    /// * `flush` - Persists the database, called from a background thread
+    pub fn new(
+        db: &BaseDB,
+        config: AutoPersistConfig,
+        flush: impl Fn() -> Result<()> + Send + Sync + 'static,
+    ) -> Self {
+        let shared = Arc::new(Shared {
+            config,
+            state: Mutex::default(),
+            wake: Condvar::new(),
+            flush: Box::new(flush),
+            flush_lock: Mutex::new(()),
+            last_error: Mutex::new(None),
+        });
+
+        // The listener only holds a weak reference, so it never keeps the state alive
+        let weak = Arc::downgrade(&shared);
+        let listener = db.on_commit(move |_| {
+            if let Some(shared) = weak.upgrade() {
+                shared.mark_dirty();
+            }
+        });
+
+        let worker_shared = Arc::clone(&shared);
+        let worker = std::thread::Builder::new()
+            .name("eidetica-autopersist".to_string())
+            .spawn(move || worker_shared.run())
+            .expect("failed to spawn autopersist thread");
+
+        Self {
+            db: db.clone(),
+            listener,
+            shared,
+            worker: Some(worker),
+        }
+    }
+
+    /// Starts saving an `InMemory` database to `path` after commits.
+    ///
+    /// # Errors
+    /// Returns `BaseError::InvalidOperation` if the database does not use the
+    /// `InMemory` backend.
+    pub fn to_file(
+        db: &BaseDB,
+        path: impl Into<PathBuf>,
+        config: AutoPersistConfig,
+    ) -> Result<Self> {
+        if db.backend().as_any().downcast_ref::<InMemory>().is_none() {
+            return Err(BaseError::InvalidOperation {
+                reason: "AutoPersist::to_file requires the InMemory backend".to_string(),
+            }
+            .into());
+        }
+        let backend = Arc::clone(db.backend());
+        let path = path.into();
+        Ok(Self::new(db, config, move || {
+            backend
+                .as_any()
+                .downcast_ref::<InMemory>()
+                .expect("backend type checked on creation")
+                .save_to_file(&path)
+        }))
+    }
+
+    /// Records a change that did not go through a commit, such as entries
+    /// received through sync, so that it is flushed like a commit.
+    pub fn notify(&self) {
+        self.shared.mark_dirty();
+    }
+
+    /// Returns true if there are changes that have not been flushed yet.
+    pub fn is_dirty(&self) -> bool {
+        self.shared.state.lock().unwrap().dirty_since.is_some()
+    }
+
+    /// Flushes pending changes immediately, without waiting for the debounce period.
+    ///
+    /// # Errors
+    /// Returns the error from the flush function. The changes stay pending.
+    pub fn flush(&self) -> Result<()> {
+        self.shared.flush_pending()
+    }
+
+    /// Takes the error of the most recent failed background flush, if any.
+    pub fn take_error(&self) -> Option<crate::Error> {
+        self.shared.last_error.lock().unwrap().take()
+    }
+
+    /// Stops listening for commits and flushes any pending changes.
+    ///
+    /// Dropping an `AutoPersist` does the same, but ignores flush errors.
+    pub fn shutdown(mut self) -> Result<()> {
+        self.stop()
+    }
+
+    fn stop(&mut self) -> Result<()> {
+        let Some(worker) = self.worker.take() else {
+            return Ok(());
+        };
+        self.db.remove_commit_listener(self.listener);
+        self.shared.state.lock().unwrap().stopping = true;
+        self.shared.wake.notify_all();
+        let _ = worker.join();
+        self.shared.flush_pending()
+    }
+}
+
+impl Drop for AutoPersist {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
@@ -0,0 +1,127 @@
+//! Per-tree write quotas
+//!
+//! A tree declares quotas under `quota` in its `_settings`, see
+//! [`Tree::set_quota`](crate::Tree::set_quota). Like frozen subtrees, quotas are
+//! checked against the settings as of an entry's parents: a commit that breaks
+//! one fails, and an entry received from a peer that breaks one fails
+//! verification, so an abusive writer cannot grow the tree on other replicas.
+//!
+//! The entry count is the number of entries on the longest chain of parents
+//! leading to the entry, its height plus one, which every replica computes the
+//! same way and backends keep without walking the history. Entries written
+//! concurrently share a height, so concurrent writers that each stay within
+//! `max_entries` can exceed it together by a few entries. Entries
+//! that change `_settings` are exempt from all quotas, so an admin can still
+//! raise a quota once the tree is full. Changing settings requires admin
+//! permission, which is already trusted with the whole tree.
+
+use crate::Result;
+use crate::backend::Database;
+use crate::constants::SETTINGS;
+use crate::crdt::Map;
+use crate::crdt::map::Value;
+use crate::entry::Entry;
+
+/// Settings section holding a tree's quotas.
+pub(crate) const QUOTA: &str = "quota";
+/// Key of the maximum serialized entry size within the `quota` section.
+pub(crate) const MAX_ENTRY_SIZE: &str = "max_entry_size";
+/// Key of the maximum size of one subtree's data in an entry.
+pub(crate) const MAX_SUBTREE_SIZE: &str = "max_subtree_size";
+/// Key of the maximum number of entries in the tree.
+pub(crate) const MAX_ENTRIES: &str = "max_entries";
+
+/// The write quotas of a tree, as set with [`Tree::set_quota`](crate::Tree::set_quota).
+///
+/// Limits that are `None` are not enforced.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TreeQuota {
+    /// Maximum size of an entry, in bytes of its serialized form
+    pub max_entry_size: Option<u64>,
+    /// Maximum size of the data an entry writes to any one subtree, in bytes
+    pub max_subtree_size: Option<u64>,
+    /// Maximum number of entries in the tree, counted along the longest chain
+    /// of parents of each new entry
+    pub max_entries: Option<u64>,
+}
+
+impl TreeQuota {
+    /// Reads the quotas declared in a tree's settings.
+    pub(crate) fn from_settings(settings: &Map) -> Self {
+        let Some(Value::Map(section)) = settings.get(QUOTA) else {
+            return Self::default();
+        };
+        let limit = |key| match section.get(key) {
+            Some(Value::Int(limit)) => u64::try_from(*limit).ok(),
+            _ => None,
+        };
+        Self {
+            max_entry_size: limit(MAX_ENTRY_SIZE),
+            max_subtree_size: limit(MAX_SUBTREE_SIZE),
+            max_entries: limit(MAX_ENTRIES),
+        }
+    }
+
+    /// Returns true if no limit is set.
+    pub fn is_unlimited(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// A quota that an entry breaks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct QuotaViolation {
+    /// The settings key of the quota, such as `max_entry_size`
+    pub(crate) quota: String,
+    /// The limit set in the tree's settings
+    pub(crate) limit: u64,
+    /// The size or count the entry would reach
+    pub(crate) actual: u64,
+}
+
+/// Returns the first quota declared in `settings` that `entry` breaks.
+///
+/// `max_entries` is only checked when a backend is given to look up the
+/// heights of the entry's parents with.
+pub(crate) fn quota_violation(
+    entry: &Entry,
+    settings: &Map,
+    backend: Option<&dyn Database>,
+) -> Result<Option<QuotaViolation>> {
+    let quota = TreeQuota::from_settings(settings);
+    if quota.is_unlimited() || entry.in_subtree(SETTINGS) {
+        return Ok(None);
+    }
+    let violation = |quota: &str, limit: u64, actual: u64| {
+        (actual > limit).then(|| QuotaViolation {
+            quota: quota.to_string(),
+            limit,
+            actual,
+        })
+    };
+
+    if let Some(limit) = quota.max_entry_size {
+        let size = entry.canonical_bytes()?.len() as u64;
+        if let Some(v) = violation(MAX_ENTRY_SIZE, limit, size) {
+            return Ok(Some(v));
+        }
+    }
+    if let Some(limit) = quota.max_subtree_size {
+        for subtree in entry.subtrees() {
+            let size = entry.data(&subtree)?.len() as u64;
+            if let Some(v) = violation(MAX_SUBTREE_SIZE, limit, size) {
+                return Ok(Some(v));
+            }
+        }
+    }
+    if let (Some(limit), Some(backend)) = (quota.max_entries, backend) {
+        let mut count = 1;
+        for parent in entry.parents()? {
+            count = count.max(backend.get_height(&entry.root(), &parent)? as u64 + 2);
+        }
+        if let Some(v) = violation(MAX_ENTRIES, limit, count) {
+            return Ok(Some(v));
+        }
+    }
+    Ok(None)
+}
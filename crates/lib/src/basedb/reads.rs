@@ -0,0 +1,195 @@
+//! Read audit log
+//!
+//! Commits are signed and stored, so the history of a tree already records who
+//! wrote what. Reads leave no trace by default. For environments that must also
+//! account for reads, `BaseDB::enable_read_log` turns on a local log recording
+//! which key read which subtree of which tree, and when.
+//!
+//! The log is kept in memory in a ring buffer of fixed capacity; once it is full
+//! the oldest records are dropped. It is never synced or written to the backend.
+//! Applications that need to keep reads longer can drain it periodically with
+//! `BaseDB::take_read_log` and store the records themselves.
+//!
+//! Reads are recorded when a subtree's state is read through
+//! [`Tree::get_subtree_viewer`](crate::Tree::get_subtree_viewer),
+//! [`Tree::get_subtree_viewer_at`](crate::Tree::get_subtree_viewer_at) or a
+//! prepared query. The reader is the tree's default authentication key. Reads
+//! made by the database itself, such as loading `_settings` to validate a
+//! commit, are not recorded unless [`ReadLogConfig::include_internal`] is set.
+//!
+//! [`ReadLogConfig`] limits what is recorded: only subtrees matching the given
+//! patterns, and the reader by name, by pseudonym or not at all.
+
+use super::events::glob_match;
+use crate::entry::ID;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// How the reader of a subtree is recorded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReaderIdentity {
+    /// The name of the key, as passed to `set_default_auth_key`
+    #[default]
+    Name,
+    /// A hash of the key name that is stable while the log is enabled.
+    ///
+    /// Reads by the same key can be correlated, but the key cannot be named
+    /// from the log alone. Pseudonyms change each time the log is enabled.
+    Pseudonym,
+    /// No reader is recorded
+    Omit,
+}
+
+/// Configuration of the read audit log.
+///
+/// # Example
+/// ```
+/// # use eidetica::basedb::{ReadLogConfig, ReaderIdentity};
+/// // Record reads of medical records by pseudonym, keeping the last 10,000
+/// let config = ReadLogConfig {
+///     capacity: 10_000,
+///     subtrees: vec!["patients*".to_string()],
+///     reader: ReaderIdentity::Pseudonym,
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadLogConfig {
+    /// The number of records kept; older records are dropped first
+    pub capacity: usize,
+    /// Glob patterns of the subtree names to record, or all subtrees if empty.
+    ///
+    /// `*` matches any run of characters and `?` any single character.
+    pub subtrees: Vec<String>,
+    /// How the reader is recorded
+    pub reader: ReaderIdentity,
+    /// Also record reads of internal subtrees, whose names start with `_`
+    pub include_internal: bool,
+}
+
+impl Default for ReadLogConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 1024,
+            subtrees: Vec::new(),
+            reader: ReaderIdentity::Name,
+            include_internal: false,
+        }
+    }
+}
+
+/// A read of a subtree recorded by the read audit log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadRecord {
+    /// The root ID of the tree that was read
+    pub tree: ID,
+    /// The name of the subtree that was read
+    pub subtree: String,
+    /// The reader, recorded as configured by [`ReadLogConfig::reader`].
+    ///
+    /// `None` if the tree has no default authentication key or readers are
+    /// omitted.
+    pub reader: Option<String>,
+    /// When the read happened, by the local wall clock
+    pub time: SystemTime,
+}
+
+/// The shared read audit log of a `BaseDB`.
+#[derive(Default)]
+pub(crate) struct ReadLog {
+    inner: Mutex<Option<LogState>>,
+}
+
+struct LogState {
+    config: ReadLogConfig,
+    /// Mixed into pseudonyms so they cannot be computed from key names alone
+    salt: [u8; 16],
+    records: VecDeque<ReadRecord>,
+}
+
+impl ReadLog {
+    /// Starts recording with `config`, discarding any previous records.
+    pub(crate) fn enable(&self, config: ReadLogConfig) {
+        let mut salt = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut salt);
+        *self.inner.lock().unwrap() = Some(LogState {
+            records: VecDeque::with_capacity(config.capacity.min(1024)),
+            config,
+            salt,
+        });
+    }
+
+    /// Stops recording and discards the records.
+    pub(crate) fn disable(&self) {
+        *self.inner.lock().unwrap() = None;
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.inner.lock().unwrap().is_some()
+    }
+
+    /// The current records, oldest first.
+    pub(crate) fn records(&self) -> Vec<ReadRecord> {
+        self.inner
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|state| state.records.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Removes and returns the current records, oldest first.
+    pub(crate) fn take(&self) -> Vec<ReadRecord> {
+        self.inner
+            .lock()
+            .unwrap()
+            .as_mut()
+            .map(|state| state.records.drain(..).collect())
+            .unwrap_or_default()
+    }
+
+    /// Records a read of `subtree` if the log is enabled and configured to.
+    pub(crate) fn record(&self, tree: &ID, subtree: &str, reader: Option<&str>) {
+        let mut inner = self.inner.lock().unwrap();
+        let Some(state) = inner.as_mut() else {
+            return;
+        };
+        let config = &state.config;
+        if config.capacity == 0
+            || (subtree.starts_with('_') && !config.include_internal)
+            || !(config.subtrees.is_empty()
+                || config
+                    .subtrees
+                    .iter()
+                    .any(|pattern| glob_match(pattern, subtree)))
+        {
+            return;
+        }
+
+        let reader = match config.reader {
+            ReaderIdentity::Name => reader.map(str::to_string),
+            ReaderIdentity::Pseudonym => reader.map(|name| pseudonym(&state.salt, name)),
+            ReaderIdentity::Omit => None,
+        };
+        if state.records.len() >= config.capacity {
+            state.records.pop_front();
+        }
+        state.records.push_back(ReadRecord {
+            tree: tree.clone(),
+            subtree: subtree.to_string(),
+            reader,
+            time: crate::clock::now(),
+        });
+    }
+}
+
+fn pseudonym(salt: &[u8], name: &str) -> String {
+    let digest = Sha256::new()
+        .chain_update(salt)
+        .chain_update(name.as_bytes())
+        .finalize();
+    format!("reader-{:x}", digest)[..23].to_string()
+}
@@ -0,0 +1,194 @@
+//! Diagnostics for divergent replicas
+//!
+//! `Tree::reconcile_with` compares the entries two replicas of a tree hold and
+//! plans how to bring them back in line: which entries to copy in each
+//! direction, and which to quarantine instead because copying them would
+//! spread a problem. It only reads both replicas and changes nothing.
+
+use crate::Result;
+use crate::backend::VerificationStatus;
+use crate::basedb::errors::BaseError;
+use crate::entry::{Entry, ID};
+use crate::tree::Tree;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// One of the two replicas compared by [`Tree::reconcile_with`](crate::Tree::reconcile_with).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Replica {
+    /// The tree `reconcile_with` was called on
+    Local,
+    /// The tree passed to `reconcile_with`
+    Other,
+}
+
+impl fmt::Display for Replica {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Replica::Local => f.write_str("local"),
+            Replica::Other => f.write_str("other"),
+        }
+    }
+}
+
+/// Why an entry should not be copied to the other replica.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QuarantineReason {
+    /// The replica holding the entry marked it as failing verification
+    FailedVerification,
+    /// The entry's signature is not valid under the tree's settings as of its parents
+    InvalidSignature,
+    /// A parent of the entry is in neither replica
+    MissingParents,
+    /// A parent of the entry is itself quarantined
+    QuarantinedParent,
+}
+
+impl fmt::Display for QuarantineReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            QuarantineReason::FailedVerification => "failed verification",
+            QuarantineReason::InvalidSignature => "invalid signature",
+            QuarantineReason::MissingParents => "parents missing from both replicas",
+            QuarantineReason::QuarantinedParent => "a parent is quarantined",
+        })
+    }
+}
+
+/// An entry that should be set aside rather than copied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuarantinedEntry {
+    /// ID of the entry
+    pub id: ID,
+    /// The replica holding the entry
+    pub replica: Replica,
+    /// Why the entry is quarantined
+    pub reason: QuarantineReason,
+}
+
+/// How to bring two replicas of a tree back in line.
+///
+/// Created by [`Tree::reconcile_with`](crate::Tree::reconcile_with). Entries
+/// to copy are listed parents first, so they can be inserted in order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepairPlan {
+    /// Number of entries both replicas hold
+    pub shared: usize,
+    /// Entries only the other replica holds, to copy to the local one
+    pub copy_to_local: Vec<ID>,
+    /// Entries only the local replica holds, to copy to the other one
+    pub copy_to_other: Vec<ID>,
+    /// Entries to set aside instead of copying, ordered as found
+    pub quarantine: Vec<QuarantinedEntry>,
+}
+
+impl RepairPlan {
+    /// Returns true if both replicas hold the same entries and none needs quarantining.
+    pub fn is_consistent(&self) -> bool {
+        self.copy_to_local.is_empty() && self.copy_to_other.is_empty() && self.quarantine.is_empty()
+    }
+}
+
+/// The entries of one replica, by ID, and their order.
+struct Entries {
+    tree: Tree,
+    order: Vec<ID>,
+    by_id: HashMap<ID, Entry>,
+}
+
+impl Entries {
+    fn load(tree: &Tree) -> Result<Self> {
+        let entries = tree.backend().get_tree(tree.root_id())?;
+        Ok(Self {
+            tree: tree.clone(),
+            order: entries.iter().map(Entry::id).collect(),
+            by_id: entries
+                .into_iter()
+                .map(|entry| (entry.id(), entry))
+                .collect(),
+        })
+    }
+
+    fn failed(&self, id: &ID) -> Result<bool> {
+        Ok(self.tree.backend().get_verification_status(id)? == VerificationStatus::Failed)
+    }
+}
+
+/// Compares the replicas `local` and `other` of the same tree.
+pub(crate) fn reconcile(local: &Tree, other: &Tree) -> Result<RepairPlan> {
+    if local.root_id() != other.root_id() {
+        return Err(BaseError::InvalidOperation {
+            reason: format!(
+                "cannot reconcile tree {} with a replica of tree {}",
+                local.root_id(),
+                other.root_id()
+            ),
+        }
+        .into());
+    }
+
+    let local = Entries::load(local)?;
+    let other = Entries::load(other)?;
+    let mut plan = RepairPlan::default();
+    let mut quarantined = HashSet::new();
+
+    for (replica, source, target) in [
+        (Replica::Local, &local, &other),
+        (Replica::Other, &other, &local),
+    ] {
+        for id in &source.order {
+            let shared = target.by_id.contains_key(id);
+            if shared && replica == Replica::Local {
+                plan.shared += 1;
+            }
+            let reason = if source.failed(id)? {
+                Some(QuarantineReason::FailedVerification)
+            } else if shared {
+                None
+            } else {
+                copy_blocker(id, source, target, &quarantined)
+            };
+
+            match reason {
+                Some(reason) => {
+                    quarantined.insert(id.clone());
+                    plan.quarantine.push(QuarantinedEntry {
+                        id: id.clone(),
+                        replica,
+                        reason,
+                    });
+                }
+                None if shared => {}
+                None if replica == Replica::Local => plan.copy_to_other.push(id.clone()),
+                None => plan.copy_to_local.push(id.clone()),
+            }
+        }
+    }
+    Ok(plan)
+}
+
+/// Why the entry `id`, held only by `source`, should not be copied to `target`.
+///
+/// Entries are checked parents first, so the parents' quarantine is known.
+fn copy_blocker(
+    id: &ID,
+    source: &Entries,
+    target: &Entries,
+    quarantined: &HashSet<ID>,
+) -> Option<QuarantineReason> {
+    let parents = source.by_id[id].parents().unwrap_or_default();
+    if parents.iter().any(|parent| quarantined.contains(parent)) {
+        return Some(QuarantineReason::QuarantinedParent);
+    }
+    if parents
+        .iter()
+        .any(|parent| !source.by_id.contains_key(parent) && !target.by_id.contains_key(parent))
+    {
+        return Some(QuarantineReason::MissingParents);
+    }
+    // Settings the entry cannot be checked against count as an invalid signature
+    if !matches!(source.tree.verify_entry_signature(id.clone()), Ok(true)) {
+        return Some(QuarantineReason::InvalidSignature);
+    }
+    None
+}
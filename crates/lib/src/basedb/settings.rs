@@ -0,0 +1,159 @@
+//! Typed access to a tree's settings
+//!
+//! A tree's settings are a `Map` in its `_settings` subtree. Besides the
+//! sections managed by dedicated APIs, such as `auth`, `frozen` and
+//! `encodings`, it holds a name, an optional description, and any custom
+//! fields an application wants to share with every replica of the tree.
+//! [`TreeSettings`] reads and edits those without touching the raw map; edits
+//! are committed with [`Tree::update_settings`](crate::Tree::update_settings).
+
+use crate::clock::Hlc;
+use crate::crdt::Map;
+use crate::crdt::map::Value;
+
+/// Settings key holding the tree's name.
+const NAME: &str = "name";
+
+/// Settings key holding the tree's description.
+const DESCRIPTION: &str = "description";
+
+/// Settings key holding the application defined fields, by field name.
+const CUSTOM: &str = "custom";
+
+/// A typed view of a tree's settings.
+///
+/// Obtained from [`Tree::settings`](crate::Tree::settings). Setters change the
+/// view and record the change, so that
+/// [`Tree::update_settings`](crate::Tree::update_settings) writes only the
+/// fields that were changed and concurrent edits to other fields are kept.
+///
+/// # Example
+/// ```
+/// # use eidetica::{backend::database::InMemory, basedb::BaseDB, crdt::Map};
+/// # let db = BaseDB::new(Box::new(InMemory::new()));
+/// # db.add_private_key("key").unwrap();
+/// # let tree = db.new_tree(Map::new(), "key").unwrap();
+/// tree.update_settings(|settings| {
+///     settings.set_name("Recipes");
+///     settings.set_description("Family recipes");
+///     settings.set_custom("servings", 4);
+/// })
+/// .unwrap();
+///
+/// let settings = tree.settings().unwrap();
+/// assert_eq!(settings.name(), Some("Recipes"));
+/// assert_eq!(settings.custom("servings").and_then(|v| v.as_int()), Some(4));
+/// ```
+#[derive(Debug, Clone)]
+pub struct TreeSettings {
+    /// The merged settings, with the changes made through this view applied
+    inner: Map,
+    /// Timestamp of the tree's root entry
+    created_at: Option<Hlc>,
+    /// Settings paths changed through this view, in order, with their new values
+    changes: Vec<(Vec<String>, Value)>,
+}
+
+impl TreeSettings {
+    /// Create a view over `settings`, the merged `_settings` of a tree whose
+    /// root entry was committed at `created_at`.
+    pub(crate) fn new(settings: Map, created_at: Option<Hlc>) -> Self {
+        Self {
+            inner: settings,
+            created_at,
+            changes: Vec::new(),
+        }
+    }
+
+    /// Get the underlying Map, including changes made through this view
+    pub fn as_map(&self) -> &Map {
+        &self.inner
+    }
+
+    /// The tree's name, if set.
+    pub fn name(&self) -> Option<&str> {
+        self.inner.get_text(NAME)
+    }
+
+    /// Set the tree's name.
+    pub fn set_name(&mut self, name: impl Into<String>) {
+        self.change(&[NAME], Value::Text(name.into()));
+    }
+
+    /// The tree's description, if set.
+    pub fn description(&self) -> Option<&str> {
+        self.inner.get_text(DESCRIPTION)
+    }
+
+    /// Set the tree's description.
+    pub fn set_description(&mut self, description: impl Into<String>) {
+        self.change(&[DESCRIPTION], Value::Text(description.into()));
+    }
+
+    /// Remove the tree's description.
+    pub fn remove_description(&mut self) {
+        self.change(&[DESCRIPTION], Value::Deleted);
+    }
+
+    /// When the tree was created: the timestamp of its root entry.
+    ///
+    /// Returns `None` for trees whose root was written without a timestamp.
+    pub fn created_at(&self) -> Option<Hlc> {
+        self.created_at
+    }
+
+    /// The value of a custom field, if set.
+    pub fn custom(&self, key: impl AsRef<str>) -> Option<&Value> {
+        match self.inner.get(CUSTOM) {
+            Some(Value::Map(custom)) => custom.get(key.as_ref()),
+            _ => None,
+        }
+    }
+
+    /// Set a custom field.
+    pub fn set_custom(&mut self, key: impl Into<String>, value: impl Into<Value>) {
+        self.change(&[CUSTOM, &key.into()], value.into());
+    }
+
+    /// Remove a custom field.
+    pub fn remove_custom(&mut self, key: impl AsRef<str>) {
+        self.change(&[CUSTOM, key.as_ref()], Value::Deleted);
+    }
+
+    /// The names of the custom fields, sorted.
+    pub fn custom_keys(&self) -> Vec<String> {
+        let mut keys: Vec<String> = match self.inner.get(CUSTOM) {
+            Some(Value::Map(custom)) => custom.keys().cloned().collect(),
+            _ => Vec::new(),
+        };
+        keys.sort();
+        keys
+    }
+
+    /// The settings paths changed through this view, with their new values.
+    pub(crate) fn changes(&self) -> &[(Vec<String>, Value)] {
+        &self.changes
+    }
+
+    /// Applies a change to the view and records it.
+    fn change(&mut self, path: &[&str], value: Value) {
+        let (key, parents) = path.split_last().expect("settings paths are not empty");
+        let mut map = &mut self.inner;
+        for parent in parents {
+            if !matches!(map.get(*parent), Some(Value::Map(_))) {
+                map.set_map(*parent, Map::new());
+            }
+            map = match map.get_mut(*parent) {
+                Some(Value::Map(child)) => child,
+                _ => unreachable!("the parent was just set to a map"),
+            };
+        }
+        if value.is_deleted() {
+            map.remove(*key);
+        } else {
+            map.set(*key, value.clone());
+        }
+        self.changes
+            .push((path.iter().map(|s| s.to_string()).collect(), value));
+    }
+}
@@ -0,0 +1,182 @@
+//! Cache of merged subtree states
+//!
+//! Reading a subtree merges its history up to the subtree's tips. The state
+//! of each entry is cached by the backend, but a subtree with several tips is
+//! still merged from their common ancestor on every read, and every read
+//! parses the cached state again.
+//!
+//! Entries are content-addressed, so the merged state of a subtree is fully
+//! determined by its tree, its name and its set of tips. This cache keeps the
+//! most recently read states in memory under that key, so reading a subtree
+//! again while its tips are unchanged only clones the cached state. It is
+//! shared by all clones of a `BaseDB` and every `Tree` loaded from it.
+//!
+//! A commit changes the tips, so it never makes a cached state wrong, but it
+//! does make the tree's cached states unreachable; they are dropped after each
+//! commit to the tree, and when entries are removed by pruning or expiry.
+
+use crate::entry::ID;
+use std::any::{Any, TypeId};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+/// Number of states kept by default
+const DEFAULT_CAPACITY: usize = 64;
+
+/// Statistics about the subtree state cache, see
+/// [`BaseDB::state_cache_stats`](super::BaseDB::state_cache_stats).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StateCacheStats {
+    /// Maximum number of states kept
+    pub capacity: usize,
+    /// Number of states currently cached
+    pub entries: usize,
+    /// Reads answered from the cache
+    pub hits: u64,
+    /// Reads that had to merge the subtree's history
+    pub misses: u64,
+}
+
+/// Identifies a merged state: the same subtree at the same tips, read as the same type.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct Key {
+    tree: ID,
+    subtree: String,
+    /// Sorted, so the order the tips were found in does not matter
+    tips: Vec<ID>,
+    kind: TypeId,
+}
+
+/// Merged subtree states by tree, subtree and tips, least recently used first.
+pub(crate) struct StateCache {
+    inner: Mutex<Inner>,
+}
+
+struct Inner {
+    capacity: usize,
+    states: HashMap<Key, Arc<dyn Any + Send + Sync>>,
+    /// Keys of `states`, least recently used first
+    order: VecDeque<Key>,
+    hits: u64,
+    misses: u64,
+}
+
+impl Default for StateCache {
+    fn default() -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                capacity: DEFAULT_CAPACITY,
+                states: HashMap::new(),
+                order: VecDeque::new(),
+                hits: 0,
+                misses: 0,
+            }),
+        }
+    }
+}
+
+impl StateCache {
+    fn key<T: 'static>(tree: &ID, subtree: &str, tips: &[ID]) -> Key {
+        let mut tips = tips.to_vec();
+        tips.sort();
+        Key {
+            tree: tree.clone(),
+            subtree: subtree.to_string(),
+            tips,
+            kind: TypeId::of::<T>(),
+        }
+    }
+
+    /// Gets a copy of the cached state of `subtree` at `tips`, if any.
+    pub(crate) fn get<T: Clone + 'static>(
+        &self,
+        tree: &ID,
+        subtree: &str,
+        tips: &[ID],
+    ) -> Option<T> {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.capacity == 0 {
+            return None;
+        }
+        let key = Self::key::<T>(tree, subtree, tips);
+        let state = inner
+            .states
+            .get(&key)
+            .and_then(|state| state.downcast_ref::<T>())
+            .cloned();
+        match state {
+            Some(state) => {
+                inner.hits += 1;
+                if let Some(position) = inner.order.iter().position(|k| *k == key) {
+                    inner.order.remove(position);
+                }
+                inner.order.push_back(key);
+                Some(state)
+            }
+            None => {
+                inner.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Caches the state of `subtree` at `tips`, evicting the least recently used states.
+    pub(crate) fn insert<T: Send + Sync + 'static>(
+        &self,
+        tree: &ID,
+        subtree: &str,
+        tips: &[ID],
+        state: T,
+    ) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.capacity == 0 {
+            return;
+        }
+        let key = Self::key::<T>(tree, subtree, tips);
+        if inner.states.insert(key.clone(), Arc::new(state)).is_none() {
+            inner.order.push_back(key);
+        }
+        inner.evict();
+    }
+
+    /// Drops the cached states of `tree`.
+    pub(crate) fn invalidate_tree(&self, tree: &ID) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.states.retain(|key, _| key.tree != *tree);
+        inner.order.retain(|key| key.tree != *tree);
+    }
+
+    /// Drops all cached states.
+    pub(crate) fn clear(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.states.clear();
+        inner.order.clear();
+    }
+
+    /// Sets how many states are kept, evicting states over the new capacity.
+    pub(crate) fn set_capacity(&self, capacity: usize) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.capacity = capacity;
+        inner.evict();
+    }
+
+    pub(crate) fn stats(&self) -> StateCacheStats {
+        let inner = self.inner.lock().unwrap();
+        StateCacheStats {
+            capacity: inner.capacity,
+            entries: inner.states.len(),
+            hits: inner.hits,
+            misses: inner.misses,
+        }
+    }
+}
+
+impl Inner {
+    fn evict(&mut self) {
+        while self.order.len() > self.capacity {
+            if let Some(key) = self.order.pop_front() {
+                self.states.remove(&key);
+            }
+        }
+    }
+}
@@ -0,0 +1,124 @@
+//! Hybrid logical clocks
+//!
+//! Every entry committed through an `AtomicOp` records a hybrid logical clock
+//! (HLC) timestamp in its metadata. An HLC combines the wall clock time in
+//! milliseconds with a logical counter:
+//!
+//! * A new entry's timestamp is greater than the timestamps of all its parents,
+//!   even if the local wall clock is behind the clock of the device that wrote
+//!   them. The counter is incremented in that case instead.
+//! * When the wall clock is ahead of all parents, the timestamp follows it.
+//!
+//! Timestamps therefore respect the causal order of entries while staying close
+//! to real time, which makes them usable across devices with skewed clocks. They
+//! are part of the signed entry content, so every replica sees the same value.
+//!
+//! Timestamps are used to break ties between concurrent entries when merging
+//! subtree state (the later edit wins) and for "last edited" displays.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A hybrid logical clock timestamp.
+///
+/// Ordered by wall clock time, then by the logical counter.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub struct Hlc {
+    /// Wall clock time in milliseconds since the Unix epoch
+    pub wall_ms: u64,
+    /// Logical counter distinguishing timestamps with the same wall clock time
+    pub counter: u32,
+}
+
+impl Hlc {
+    /// Creates a timestamp from its parts.
+    pub fn new(wall_ms: u64, counter: u32) -> Self {
+        Self { wall_ms, counter }
+    }
+
+    /// Returns the timestamp for a new event.
+    ///
+    /// The result is greater than every timestamp in `observed` (typically the
+    /// timestamps of the new entry's parents) and follows `now_ms` when the wall
+    /// clock is ahead of them.
+    pub fn next(observed: impl IntoIterator<Item = Hlc>, now_ms: u64) -> Self {
+        match observed.into_iter().max() {
+            Some(latest) if latest.wall_ms >= now_ms => match latest.counter.checked_add(1) {
+                Some(counter) => Self::new(latest.wall_ms, counter),
+                None => Self::new(latest.wall_ms + 1, 0),
+            },
+            _ => Self::new(now_ms, 0),
+        }
+    }
+
+    /// Returns the timestamp for a new event at the current wall clock time.
+    pub fn now_after(observed: impl IntoIterator<Item = Hlc>) -> Self {
+        Self::next(observed, wall_clock_ms())
+    }
+
+    /// The wall clock component as a `SystemTime`.
+    pub fn wall_time(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis(self.wall_ms)
+    }
+}
+
+impl fmt::Display for Hlc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.wall_ms, self.counter)
+    }
+}
+
+/// The current wall clock time in milliseconds since the Unix epoch.
+///
+/// Read through `web_time`, since `SystemTime::now` panics on
+/// `wasm32-unknown-unknown`; it uses `Date.now()` there.
+pub(crate) fn wall_clock_ms() -> u64 {
+    web_time::SystemTime::now()
+        .duration_since(web_time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// The current wall clock time, with millisecond precision.
+pub(crate) fn now() -> SystemTime {
+    UNIX_EPOCH + Duration::from_millis(wall_clock_ms())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_follows_wall_clock() {
+        assert_eq!(Hlc::next([], 1000), Hlc::new(1000, 0));
+        assert_eq!(Hlc::next([Hlc::new(900, 5)], 1000), Hlc::new(1000, 0));
+    }
+
+    #[test]
+    fn test_next_is_after_skewed_parents() {
+        // A parent written by a device whose clock is ahead
+        let parents = [Hlc::new(2000, 3), Hlc::new(1500, 9)];
+        assert_eq!(Hlc::next(parents, 1000), Hlc::new(2000, 4));
+        assert_eq!(Hlc::next([Hlc::new(1000, 0)], 1000), Hlc::new(1000, 1));
+    }
+
+    #[test]
+    fn test_next_counter_overflow() {
+        let parent = Hlc::new(1000, u32::MAX);
+        assert_eq!(Hlc::next([parent], 1000), Hlc::new(1001, 0));
+    }
+
+    #[test]
+    fn test_ordering_and_display() {
+        assert!(Hlc::new(1, 5) < Hlc::new(2, 0));
+        assert!(Hlc::new(2, 0) < Hlc::new(2, 1));
+        assert_eq!(Hlc::new(1234, 7).to_string(), "1234.7");
+        assert_eq!(
+            Hlc::new(1500, 0).wall_time(),
+            UNIX_EPOCH + Duration::from_millis(1500)
+        );
+    }
+}
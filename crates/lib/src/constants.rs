@@ -8,3 +8,10 @@ pub const SETTINGS: &str = "_settings";
 
 /// Reserved subtree name for marking root entries.
 pub const ROOT: &str = "_root";
+
+/// Settings key recording the algorithm a tree's entry IDs are hashed with.
+///
+/// Set it to the name of a [`HashAlgorithm`](crate::entry::HashAlgorithm) in the
+/// settings passed to `BaseDB::new_tree` to choose the algorithm. It cannot be
+/// changed after the tree is created.
+pub const HASH_ALGORITHM: &str = "hash_algorithm";
@@ -58,9 +58,17 @@ pub enum CRDTError {
     #[error("Invalid CRDT path: {path}")]
     InvalidPath { path: String },
 
+    /// A path string could not be parsed
+    #[error("Malformed path '{path}': {reason}")]
+    MalformedPath { path: String, reason: String },
+
     /// List index out of bounds
     #[error("List index out of bounds: index {index}, length {len}")]
     ListIndexOutOfBounds { index: usize, len: usize },
+
+    /// Text position out of bounds, counted in characters
+    #[error("Text index out of bounds: index {index}, length {len}")]
+    TextIndexOutOfBounds { index: usize, len: usize },
 }
 
 impl CRDTError {
@@ -107,6 +115,19 @@ impl CRDTError {
         matches!(self, CRDTError::ListIndexOutOfBounds { .. })
     }
 
+    /// Check if this error is related to text operations
+    pub fn is_text_error(&self) -> bool {
+        matches!(self, CRDTError::TextIndexOutOfBounds { .. })
+    }
+
+    /// Check if this error is about an invalid or malformed path
+    pub fn is_path_error(&self) -> bool {
+        matches!(
+            self,
+            CRDTError::InvalidPath { .. } | CRDTError::MalformedPath { .. }
+        )
+    }
+
     /// Get the operation type if this is an operation-specific error
     pub fn operation(&self) -> Option<&str> {
         match self {
@@ -119,9 +140,9 @@ impl CRDTError {
     /// Get the path if this is a path-related error
     pub fn path(&self) -> Option<&str> {
         match self {
-            CRDTError::NestedOperationFailed { path, .. } | CRDTError::InvalidPath { path } => {
-                Some(path)
-            }
+            CRDTError::NestedOperationFailed { path, .. }
+            | CRDTError::InvalidPath { path }
+            | CRDTError::MalformedPath { path, .. } => Some(path),
             _ => None,
         }
     }
@@ -208,11 +229,22 @@ mod tests {
         assert!(nested_error.is_nested_error());
         assert_eq!(nested_error.path(), Some("user.profile"));
 
+        let path_error = CRDTError::MalformedPath {
+            path: "user..name".to_string(),
+            reason: "empty segment at position 5".to_string(),
+        };
+        assert!(path_error.is_path_error());
+        assert_eq!(path_error.path(), Some("user..name"));
+
         let not_found_error = CRDTError::ElementNotFound {
             key: "missing".to_string(),
         };
         assert!(not_found_error.is_not_found_error());
         assert_eq!(not_found_error.key(), Some("missing"));
+
+        let text_error = CRDTError::TextIndexOutOfBounds { index: 4, len: 2 };
+        assert!(text_error.is_text_error());
+        assert!(!text_error.is_list_error());
     }
 
     #[test]
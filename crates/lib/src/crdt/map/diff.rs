@@ -0,0 +1,226 @@
+//! Structural differences between two maps.
+//!
+//! [`Map::diff`] walks two states of a map side by side and reports what it
+//! takes to get from the first to the second. Nested maps are compared key by
+//! key and lists item by item, so a change deep inside a document is reported
+//! at its own path rather than as a change of the whole document.
+//!
+//! List items are matched by the position they were inserted at, not by their
+//! index: inserting an item at the front of a list reports that one item as
+//! added, without shifting every other item.
+//!
+//! ```
+//! # use eidetica::crdt::map::{Change, Map, Value};
+//! let mut old = Map::new();
+//! old.set("name", "Alice");
+//! old.set("age", 30);
+//!
+//! let mut new = old.clone();
+//! new.set("age", 31);
+//! new.set("email", "alice@example.com");
+//! new.remove("name");
+//!
+//! assert_eq!(
+//!     old.diff(&new),
+//!     vec![
+//!         Change::Changed {
+//!             path: "age".to_string(),
+//!             old: Value::Int(30),
+//!             new: Value::Int(31),
+//!         },
+//!         Change::Added {
+//!             path: "email".to_string(),
+//!             value: Value::Text("alice@example.com".to_string()),
+//!         },
+//!         Change::Removed {
+//!             path: "name".to_string(),
+//!             value: Value::Text("Alice".to_string()),
+//!         },
+//!     ]
+//! );
+//! ```
+
+use super::list::Position;
+use super::{List, Map, Value, path};
+use std::collections::HashMap;
+
+/// One difference between two states of a [`Map`], see [`Map::diff`].
+///
+/// Paths are joined with [`path::join`]. List items are addressed by their
+/// index: in the old list for removed items, and in the new list otherwise.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    /// A key or list item that only exists in the new state
+    Added { path: String, value: Value },
+    /// A key or list item that only exists in the old state
+    Removed { path: String, value: Value },
+    /// A value that was replaced
+    ///
+    /// Maps and lists are compared recursively, so `old` and `new` are never
+    /// both maps or both lists.
+    Changed {
+        path: String,
+        old: Value,
+        new: Value,
+    },
+    /// A list item that kept its value but moved relative to the other items
+    ///
+    /// Items that only shift because others were added or removed before them
+    /// are not reported.
+    Moved {
+        path: String,
+        from: usize,
+        to: usize,
+    },
+}
+
+impl Change {
+    /// The path the change applies to.
+    ///
+    /// For [`Change::Moved`] this is the path of the list.
+    pub fn path(&self) -> &str {
+        match self {
+            Change::Added { path, .. }
+            | Change::Removed { path, .. }
+            | Change::Changed { path, .. }
+            | Change::Moved { path, .. } => path,
+        }
+    }
+}
+
+impl Map {
+    /// Lists the changes that turn this map into `other`.
+    ///
+    /// Changes are ordered by key, depth first. Deleted keys and list items
+    /// count as missing, so a key deleted in `other` is reported as removed.
+    /// Write stamps are not compared.
+    pub fn diff(&self, other: &Map) -> Vec<Change> {
+        let mut changes = Vec::new();
+        diff_maps(self, other, &mut Vec::new(), &mut changes);
+        changes
+    }
+}
+
+fn diff_maps(old: &Map, new: &Map, prefix: &mut Vec<String>, changes: &mut Vec<Change>) {
+    let mut keys: Vec<&String> = old.keys().chain(new.keys()).collect();
+    keys.sort_unstable();
+    keys.dedup();
+
+    for key in keys {
+        prefix.push(key.clone());
+        match (old.get(key), new.get(key)) {
+            (Some(old), Some(new)) => diff_values(old, new, prefix, changes),
+            (Some(old), None) => changes.push(Change::Removed {
+                path: path::join(prefix),
+                value: old.clone(),
+            }),
+            (None, Some(new)) => changes.push(Change::Added {
+                path: path::join(prefix),
+                value: new.clone(),
+            }),
+            (None, None) => {}
+        }
+        prefix.pop();
+    }
+}
+
+fn diff_values(old: &Value, new: &Value, prefix: &mut Vec<String>, changes: &mut Vec<Change>) {
+    match (old, new) {
+        (Value::Map(old), Value::Map(new)) => diff_maps(old, new, prefix, changes),
+        (Value::List(old), Value::List(new)) => diff_lists(old, new, prefix, changes),
+        (old, new) if old != new => changes.push(Change::Changed {
+            path: path::join(prefix),
+            old: old.clone(),
+            new: new.clone(),
+        }),
+        _ => {}
+    }
+}
+
+fn diff_lists(old: &List, new: &List, prefix: &mut Vec<String>, changes: &mut Vec<Change>) {
+    let old_items = live_items(old);
+    let new_items = live_items(new);
+    let old_index: HashMap<&Position, usize> = old_items
+        .iter()
+        .enumerate()
+        .map(|(index, (position, _))| (*position, index))
+        .collect();
+    let new_index: HashMap<&Position, usize> = new_items
+        .iter()
+        .enumerate()
+        .map(|(index, (position, _))| (*position, index))
+        .collect();
+
+    for (index, (position, value)) in old_items.iter().enumerate() {
+        if !new_index.contains_key(position) {
+            prefix.push(index.to_string());
+            changes.push(Change::Removed {
+                path: path::join(prefix),
+                value: (*value).clone(),
+            });
+            prefix.pop();
+        }
+    }
+
+    // Items kept in both lists, in their new order, with their old index
+    let mut kept = Vec::new();
+    for (index, (position, value)) in new_items.iter().enumerate() {
+        prefix.push(index.to_string());
+        match old_index.get(position) {
+            Some(&from) => {
+                diff_values(old_items[from].1, value, prefix, changes);
+                kept.push((from, index));
+            }
+            None => changes.push(Change::Added {
+                path: path::join(prefix),
+                value: (*value).clone(),
+            }),
+        }
+        prefix.pop();
+    }
+
+    // The longest run of kept items that are still in their old order stays
+    // put; every other kept item moved
+    let in_order = longest_increasing(&kept.iter().map(|&(from, _)| from).collect::<Vec<_>>());
+    let list_path = path::join(prefix);
+    for (i, &(from, to)) in kept.iter().enumerate() {
+        if !in_order[i] {
+            changes.push(Change::Moved {
+                path: list_path.clone(),
+                from,
+                to,
+            });
+        }
+    }
+}
+
+/// The items of a list that are not deleted, in order.
+fn live_items(list: &List) -> Vec<(&Position, &Value)> {
+    list.iter_with_positions()
+        .filter(|(_, value)| !matches!(value, Value::Deleted))
+        .collect()
+}
+
+/// Marks the elements of a longest strictly increasing subsequence of `values`.
+fn longest_increasing(values: &[usize]) -> Vec<bool> {
+    // `tails[k]` is the index of the smallest element ending a run of length k + 1
+    let mut tails: Vec<usize> = Vec::new();
+    let mut previous: Vec<Option<usize>> = vec![None; values.len()];
+    for (i, &value) in values.iter().enumerate() {
+        let k = tails.partition_point(|&tail| values[tail] < value);
+        previous[i] = k.checked_sub(1).map(|k| tails[k]);
+        if k == tails.len() {
+            tails.push(i);
+        } else {
+            tails[k] = i;
+        }
+    }
+
+    let mut marked = vec![false; values.len()];
+    let mut next = tails.last().copied();
+    while let Some(i) = next {
+        marked[i] = true;
+        next = previous[i];
+    }
+    marked
+}
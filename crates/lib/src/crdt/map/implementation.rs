@@ -95,14 +95,17 @@
 //! - **CRDT semantics**: Proper conflict resolution and merge behavior
 //! - **Tombstone hiding**: Internal deletion markers are hidden from public API
 
-use std::collections::{BTreeMap, HashMap};
+use base64ct::{Base64, Encoding};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt;
 
 use super::list::Position;
+use super::stamp::Stamp;
 use uuid::Uuid;
 
 use crate::crdt::CRDTError;
 use crate::crdt::traits::{CRDT, Data};
+use crate::entry::ID;
 
 /// Position identifier for list elements that enables stable ordering in distributed systems.
 ///
@@ -159,7 +162,10 @@ use crate::crdt::traits::{CRDT, Data};
 /// - [`Value::Null`] - Represents null/empty values
 /// - [`Value::Bool`] - Boolean values (true/false)
 /// - [`Value::Int`] - 64-bit signed integers
+/// - [`Value::Float`] - 64-bit floating point numbers
 /// - [`Value::Text`] - UTF-8 text strings
+/// - [`Value::Bytes`] - Byte arrays
+/// - [`Value::Link`] - References to other entries, possibly in other trees
 ///
 /// ## Branch Values (Container Nodes)
 /// - [`Value::Map`] - Nested tree structures
@@ -210,7 +216,7 @@ use crate::crdt::traits::{CRDT, Data};
 /// let deleted = Value::Deleted;
 /// val3.merge(&deleted);  // val3 becomes Deleted (tombstone wins)
 /// ```
-#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub enum Value {
     // Leaf values (terminal nodes)
     /// Null/empty value
@@ -219,8 +225,17 @@ pub enum Value {
     Bool(bool),
     /// Integer value
     Int(i64),
+    /// Floating point value
+    ///
+    /// Floats are compared by their bits, so `NaN` equals itself and `0.0`
+    /// differs from `-0.0`. Non-finite values are stored as strings.
+    Float(#[serde(with = "float_repr")] f64),
     /// Text string value
     Text(String),
+    /// Byte array value, stored base64 encoded
+    Bytes(#[serde(with = "bytes_repr")] Vec<u8>),
+    /// Link to another entry, by ID
+    Link(ID),
 
     // Branch values (can contain other nodes)
     /// Sub-tree containing other nodes
@@ -281,10 +296,31 @@ pub enum Value {
 /// After merge: ["item1", "item2", "item4", "item3"]
 /// (order determined by Position comparison)
 /// ```
+///
+/// # Moving Items
+///
+/// [`move_item`](List::move_item) reorders an item without removing and
+/// reinserting it. Items keep the position they were inserted at as their
+/// identity, and a move records a new position to order the item by, so edits
+/// made concurrently with a move still apply to the moved item. When the same
+/// item is moved concurrently, the move made after more earlier moves of the
+/// item wins, then the one with the larger position, and the item ends up in
+/// one place.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct List {
     /// Internal storage using BTreeMap for ordered access
     items: BTreeMap<Position, Value>,
+    /// Moved items, by the position they were inserted at
+    moves: BTreeMap<Position, ListMove>,
+}
+
+/// Where a list item was moved to.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct ListMove {
+    /// Number of moves of the item up to and including this one
+    version: u64,
+    /// Position the item is ordered by
+    to: Position,
 }
 
 /// The root tree structure containing child nodes.
@@ -360,10 +396,146 @@ pub struct List {
 ///     .with_bool("active", true)
 ///     .with_list("tags", List::new());
 /// ```
-#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+///
+/// # Equality
+/// Maps compare equal when they hold the same values and renames; the write
+/// stamps of their keys are not compared, so a map read back after a commit
+/// equals the map that was written. [`Map::same_state`] compares stamps too.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Map {
     /// Child nodes indexed by string keys
     children: HashMap<String, Value>,
+    /// Renamed keys, indexed by their old name
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    moves: HashMap<String, Move>,
+    /// Write stamps of keys written by committed entries, see [`Stamp`]
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    stamps: HashMap<String, Stamp>,
+    /// Stamp of the latest tombstone or other value this map replaced. Keys
+    /// with older stamps were removed by it and are dropped when merged in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cleared: Option<Stamp>,
+}
+
+impl PartialEq for Map {
+    fn eq(&self, other: &Self) -> bool {
+        self.children == other.children && self.moves == other.moves
+    }
+}
+
+impl Eq for Map {}
+
+/// A value found at a renamed key while merging, to be moved to the new name.
+struct Redirect {
+    from: String,
+    to: String,
+    value: Value,
+    stamp: Option<Stamp>,
+    /// The value the rename moved
+    moved: Value,
+}
+
+/// Marker left at the old name of a renamed key.
+///
+/// The old key holds a tombstone, and values merged into it later are
+/// redirected to `to`. `value` is the value the key had when it was renamed,
+/// so a different value found at the old key when the marker is merged is
+/// known to have been written concurrently with the rename.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+struct Move {
+    to: String,
+    value: Value,
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Null, Value::Null) | (Value::Deleted, Value::Deleted) => true,
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::Int(a), Value::Int(b)) => a == b,
+            (Value::Float(a), Value::Float(b)) => a.to_bits() == b.to_bits(),
+            (Value::Text(a), Value::Text(b)) => a == b,
+            (Value::Bytes(a), Value::Bytes(b)) => a == b,
+            (Value::Link(a), Value::Link(b)) => a == b,
+            (Value::Map(a), Value::Map(b)) => a == b,
+            (Value::List(a), Value::List(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Value {}
+
+/// Serializes floats as numbers, and non-finite floats, which JSON cannot
+/// represent, as strings.
+mod float_repr {
+    use serde::de::{self, Visitor};
+    use serde::{Deserializer, Serializer};
+    use std::fmt;
+
+    pub(super) fn serialize<S: Serializer>(value: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+        if value.is_finite() {
+            serializer.serialize_f64(*value)
+        } else if value.is_nan() {
+            serializer.serialize_str("NaN")
+        } else if value.is_sign_positive() {
+            serializer.serialize_str("inf")
+        } else {
+            serializer.serialize_str("-inf")
+        }
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+        struct FloatVisitor;
+
+        impl Visitor<'_> for FloatVisitor {
+            type Value = f64;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a number, \"NaN\", \"inf\" or \"-inf\"")
+            }
+
+            fn visit_f64<E: de::Error>(self, value: f64) -> Result<f64, E> {
+                Ok(value)
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<f64, E> {
+                Ok(value as f64)
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<f64, E> {
+                Ok(value as f64)
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<f64, E> {
+                match value {
+                    "NaN" => Ok(f64::NAN),
+                    "inf" => Ok(f64::INFINITY),
+                    "-inf" => Ok(f64::NEG_INFINITY),
+                    _ => Err(E::invalid_value(de::Unexpected::Str(value), &self)),
+                }
+            }
+        }
+
+        deserializer.deserialize_any(FloatVisitor)
+    }
+}
+
+/// Serializes byte arrays as base64 strings.
+mod bytes_repr {
+    use base64ct::{Base64, Encoding};
+    use serde::{Deserialize, Deserializer, Serializer, de};
+
+    pub(super) fn serialize<S: Serializer>(value: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&Base64::encode_string(value))
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        Base64::decode_vec(&encoded).map_err(|e| de::Error::custom(format!("invalid base64: {e}")))
+    }
 }
 
 impl Value {
@@ -371,7 +543,14 @@ impl Value {
     pub fn is_leaf(&self) -> bool {
         matches!(
             self,
-            Value::Null | Value::Bool(_) | Value::Int(_) | Value::Text(_) | Value::Deleted
+            Value::Null
+                | Value::Bool(_)
+                | Value::Int(_)
+                | Value::Float(_)
+                | Value::Text(_)
+                | Value::Bytes(_)
+                | Value::Link(_)
+                | Value::Deleted
         )
     }
 
@@ -396,7 +575,10 @@ impl Value {
             Value::Null => "null",
             Value::Bool(_) => "bool",
             Value::Int(_) => "int",
+            Value::Float(_) => "float",
             Value::Text(_) => "text",
+            Value::Bytes(_) => "bytes",
+            Value::Link(_) => "link",
             Value::Map(_) => "node",
             Value::List(_) => "list",
             Value::Deleted => "deleted",
@@ -439,6 +621,23 @@ impl Value {
         self.as_int().unwrap_or(0)
     }
 
+    /// Attempts to convert to a float
+    ///
+    /// Integers are converted too, so numbers written either way can be read
+    /// as floats.
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            Value::Float(n) => Some(*n),
+            Value::Int(n) => Some(*n as f64),
+            _ => None,
+        }
+    }
+
+    /// Attempts to convert to a float, returning default if not a number
+    pub fn as_float_or(&self, default: f64) -> f64 {
+        self.as_float().unwrap_or(default)
+    }
+
     /// Attempts to convert to a string
     pub fn as_text(&self) -> Option<&str> {
         match self {
@@ -452,6 +651,43 @@ impl Value {
         self.as_text().unwrap_or("")
     }
 
+    /// Attempts to convert to a byte array
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Value::Bytes(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// Attempts to convert to a link
+    pub fn as_link(&self) -> Option<&ID> {
+        match self {
+            Value::Link(id) => Some(id),
+            _ => None,
+        }
+    }
+
+    /// Returns every link in this value, including links nested in maps and lists.
+    ///
+    /// Deleted values are skipped.
+    pub fn links(&self) -> Vec<&ID> {
+        let mut links = Vec::new();
+        self.collect_links(&mut links);
+        links
+    }
+
+    fn collect_links<'a>(&'a self, links: &mut Vec<&'a ID>) {
+        match self {
+            Value::Link(id) => links.push(id),
+            Value::Map(node) => node
+                .children
+                .values()
+                .for_each(|value| value.collect_links(links)),
+            Value::List(list) => list.iter().for_each(|value| value.collect_links(links)),
+            _ => {}
+        }
+    }
+
     /// Attempts to convert to a node (returns immutable reference)
     pub fn as_node(&self) -> Option<&Map> {
         match self {
@@ -502,17 +738,7 @@ impl Value {
         match other {
             Value::Map(other_node) => {
                 if let Value::Map(self_node) = self {
-                    // For in-place merge, manually merge the children
-                    for (key, other_value) in &other_node.children {
-                        match self_node.children.get_mut(key) {
-                            Some(self_value) => {
-                                self_value.merge(other_value);
-                            }
-                            None => {
-                                self_node.children.insert(key.clone(), other_value.clone());
-                            }
-                        }
-                    }
+                    self_node.merge_from(other_node);
                 } else {
                     // Different types, replace with other
                     *self = other.clone();
@@ -559,7 +785,12 @@ impl Value {
             Value::Null => "null".to_string(),
             Value::Bool(b) => b.to_string(),
             Value::Int(n) => n.to_string(),
+            // JSON has no non-finite numbers
+            Value::Float(n) if n.is_finite() => n.to_string(),
+            Value::Float(_) => "null".to_string(),
             Value::Text(s) => format!("\"{}\"", s.replace('\"', "\\\"")),
+            Value::Bytes(bytes) => format!("\"{}\"", Base64::encode_string(bytes)),
+            Value::Link(id) => format!("\"{id}\""),
             Value::Map(node) => node.to_json_string(),
             Value::List(list) => {
                 let mut result = String::with_capacity(list.len() * 8); // Reasonable initial capacity
@@ -584,7 +815,10 @@ impl fmt::Display for Value {
             Value::Null => write!(f, "null"),
             Value::Bool(b) => write!(f, "{b}"),
             Value::Int(n) => write!(f, "{n}"),
+            Value::Float(n) => write!(f, "{n}"),
             Value::Text(s) => write!(f, "{s}"),
+            Value::Bytes(bytes) => write!(f, "<{} bytes>", bytes.len()),
+            Value::Link(id) => write!(f, "<link {id}>"),
             Value::Map(node) => write!(f, "{node}"),
             Value::List(list) => {
                 write!(f, "[")?;
@@ -623,8 +857,7 @@ impl From<u64> for Value {
 
 impl From<f64> for Value {
     fn from(value: f64) -> Self {
-        // Convert to i64, truncating the fractional part
-        Value::Int(value as i64)
+        Value::Float(value)
     }
 }
 
@@ -642,7 +875,19 @@ impl From<u32> for Value {
 
 impl From<f32> for Value {
     fn from(value: f32) -> Self {
-        Value::Int(value as i64)
+        Value::Float(value as f64)
+    }
+}
+
+impl From<Vec<u8>> for Value {
+    fn from(value: Vec<u8>) -> Self {
+        Value::Bytes(value)
+    }
+}
+
+impl From<&[u8]> for Value {
+    fn from(value: &[u8]) -> Self {
+        Value::Bytes(value.to_vec())
     }
 }
 
@@ -658,6 +903,12 @@ impl From<&str> for Value {
     }
 }
 
+impl From<ID> for Value {
+    fn from(value: ID) -> Self {
+        Value::Link(value)
+    }
+}
+
 impl From<Map> for Value {
     fn from(value: Map) -> Self {
         Value::Map(value)
@@ -722,6 +973,16 @@ impl PartialEq<u32> for Value {
     }
 }
 
+impl PartialEq<f64> for Value {
+    fn eq(&self, other: &f64) -> bool {
+        match self {
+            // By bits, like `Value::Float` against itself
+            Value::Float(n) => n.to_bits() == other.to_bits(),
+            _ => false,
+        }
+    }
+}
+
 impl PartialEq<bool> for Value {
     fn eq(&self, other: &bool) -> bool {
         match self {
@@ -768,6 +1029,12 @@ impl PartialEq<Value> for u32 {
     }
 }
 
+impl PartialEq<Value> for f64 {
+    fn eq(&self, other: &Value) -> bool {
+        other == self
+    }
+}
+
 impl PartialEq<Value> for bool {
     fn eq(&self, other: &Value) -> bool {
         other == self
@@ -785,6 +1052,7 @@ impl List {
     pub fn new() -> Self {
         Self {
             items: BTreeMap::new(),
+            moves: BTreeMap::new(),
         }
     }
 
@@ -806,11 +1074,40 @@ impl List {
         self.items.is_empty()
     }
 
+    /// The position an item is ordered by, after any move.
+    fn order_of<'a>(&'a self, position: &'a Position) -> &'a Position {
+        self.moves.get(position).map_or(position, |moved| &moved.to)
+    }
+
+    /// All items in list order, including tombstones, with the position they
+    /// were inserted at.
+    fn ordered(&self) -> Vec<(&Position, &Value)> {
+        let mut items: Vec<_> = self.items.iter().collect();
+        if !self.moves.is_empty() {
+            items.sort_by(|(a, _), (b, _)| self.order_of(a).cmp(self.order_of(b)));
+        }
+        items
+    }
+
+    /// The first and last ordering positions, including tombstones.
+    fn bounds(&self) -> Option<(&Position, &Position)> {
+        if self.moves.is_empty() {
+            let (first, _) = self.items.first_key_value()?;
+            let (last, _) = self.items.last_key_value()?;
+            return Some((first, last));
+        }
+        let mut orders = self.items.keys().map(|position| self.order_of(position));
+        let first = orders.next()?;
+        Some(orders.fold((first, first), |(min, max), order| {
+            (min.min(order), max.max(order))
+        }))
+    }
+
     /// Pushes a value to the end of the list
     /// Returns the index of the newly added element
     pub fn push(&mut self, value: impl Into<Value>) -> usize {
         let value = value.into();
-        let position = if let Some((last_pos, _)) = self.items.last_key_value() {
+        let position = if let Some((_, last_pos)) = self.bounds() {
             // Create a position after the last element
             Position::new(last_pos.numerator.saturating_add(1), 1)
         } else {
@@ -825,6 +1122,50 @@ impl List {
 
     /// Inserts a value at a specific index
     pub fn insert(&mut self, index: usize, value: impl Into<Value>) -> Result<(), CRDTError> {
+        let position = self.insert_position(index)?;
+        self.items.insert(position, value.into());
+        Ok(())
+    }
+
+    /// Inserts a value right after the item inserted at `position`, and
+    /// returns the new item's position.
+    ///
+    /// Unlike [`insert`](Self::insert), the new item is placed relative to an
+    /// item rather than an index, so it stays next to that item however the
+    /// items before it change. The item may be a tombstone.
+    ///
+    /// # Errors
+    /// Returns `CRDTError::ElementNotFound` if no item has `position`.
+    pub fn insert_after(
+        &mut self,
+        position: &Position,
+        value: impl Into<Value>,
+    ) -> Result<Position, CRDTError> {
+        if !self.items.contains_key(position) {
+            return Err(CRDTError::ElementNotFound {
+                key: format_position(position),
+            });
+        }
+        let ordered = self.ordered();
+        let index = ordered
+            .iter()
+            .position(|(item, _)| *item == position)
+            .expect("the item is in the list");
+        let left = self.order_of(position);
+        let new_position = match ordered.get(index + 1) {
+            Some((next, _)) => Position::between(left, self.order_of(next)),
+            None => Position::new(left.numerator.saturating_add(1), left.denominator),
+        };
+        self.items.insert(new_position.clone(), value.into());
+        Ok(new_position)
+    }
+
+    /// Returns a new position that places a value at `index` when inserted
+    /// with [`insert_at_position`](Self::insert_at_position).
+    ///
+    /// Indexes count only non-tombstone elements, and `index` may equal the
+    /// length to append.
+    pub fn insert_position(&self, index: usize) -> Result<Position, CRDTError> {
         let len = self.len();
         if index > len {
             return Err(CRDTError::ListIndexOutOfBounds { index, len });
@@ -832,59 +1173,106 @@ impl List {
 
         let position = if index == 0 {
             // Insert at beginning
-            if let Some((first_pos, _)) = self.items.first_key_value() {
+            if let Some((first_pos, _)) = self.bounds() {
                 Position::new(first_pos.numerator - 1, first_pos.denominator)
             } else {
                 Position::beginning()
             }
         } else if index == len {
             // Insert at end (same as push)
-            if let Some((last_pos, _)) = self.items.last_key_value() {
+            if let Some((_, last_pos)) = self.bounds() {
                 Position::new(last_pos.numerator + 1, last_pos.denominator)
             } else {
                 Position::beginning()
             }
         } else {
-            // Insert between two existing positions
-            let positions: Vec<_> = self.items.keys().collect();
-            let left_pos = positions[index - 1];
-            let right_pos = positions[index];
-            Position::between(left_pos, right_pos)
+            // Insert between two existing elements
+            let left_pos = self.position(index - 1).expect("index is within bounds");
+            let right_pos = self.position(index).expect("index is within bounds");
+            Position::between(self.order_of(left_pos), self.order_of(right_pos))
         };
+        Ok(position)
+    }
 
-        self.items.insert(position, value.into());
+    /// Moves the element at index `from` so that it ends up at index `to`.
+    ///
+    /// Indexes count only non-tombstone elements. The element keeps its
+    /// position as its identity, so concurrent edits to it still apply after
+    /// the move; see [Moving Items](List#moving-items).
+    ///
+    /// # Errors
+    /// Returns `CRDTError::ListIndexOutOfBounds` if either index is not an
+    /// element of the list.
+    pub fn move_item(&mut self, from: usize, to: usize) -> Result<(), CRDTError> {
+        let len = self.len();
+        for index in [from, to] {
+            if index >= len {
+                return Err(CRDTError::ListIndexOutOfBounds { index, len });
+            }
+        }
+        if from == to {
+            return Ok(());
+        }
+
+        let live: Vec<&Position> = self
+            .ordered()
+            .into_iter()
+            .filter(|(_, value)| !matches!(value, Value::Deleted))
+            .map(|(position, _)| position)
+            .collect();
+        let moving = live[from].clone();
+        // Neighbours of the target index once the element is taken out
+        let others: Vec<&Position> = live
+            .iter()
+            .copied()
+            .filter(|position| **position != moving)
+            .collect();
+        let left = to.checked_sub(1).map(|index| self.order_of(others[index]));
+        let right = others.get(to).map(|position| self.order_of(position));
+        let target = match (left, right) {
+            (Some(left), Some(right)) => Position::between(left, right),
+            (Some(left), None) => Position::new(left.numerator.saturating_add(1), left.denominator),
+            (None, Some(right)) => Position::new(right.numerator - 1, right.denominator),
+            (None, None) => unreachable!("a list with two elements has neighbours"),
+        };
+
+        let version = self.moves.get(&moving).map_or(0, |moved| moved.version) + 1;
+        self.moves.insert(
+            moving,
+            ListMove {
+                version,
+                to: target,
+            },
+        );
         Ok(())
     }
 
+    /// Gets the position of the element at an index, filtering out tombstones
+    ///
+    /// This is the position the element was inserted at, which identifies it in
+    /// [`get_by_position`](Self::get_by_position) and similar methods even
+    /// after it was moved.
+    pub fn position(&self, index: usize) -> Option<&Position> {
+        self.ordered()
+            .into_iter()
+            .filter(|(_, v)| !matches!(v, Value::Deleted))
+            .nth(index)
+            .map(|(position, _)| position)
+    }
+
     /// Gets a value by index (0-based), filtering out tombstones
     pub fn get(&self, index: usize) -> Option<&Value> {
-        self.items
-            .values()
-            .filter(|v| !matches!(v, Value::Deleted))
+        self.ordered()
+            .into_iter()
+            .filter(|(_, v)| !matches!(v, Value::Deleted))
             .nth(index)
+            .map(|(_, value)| value)
     }
 
     /// Gets a mutable reference to a value by index (0-based), filtering out tombstones
     pub fn get_mut(&mut self, index: usize) -> Option<&mut Value> {
-        // Find the position of the nth non-tombstone element
-        let mut current_index = 0;
-        let mut target_position = None;
-
-        for (pos, value) in &self.items {
-            if !matches!(value, Value::Deleted) {
-                if current_index == index {
-                    target_position = Some(pos.clone());
-                    break;
-                }
-                current_index += 1;
-            }
-        }
-
-        if let Some(pos) = target_position {
-            self.items.get_mut(&pos)
-        } else {
-            None
-        }
+        let position = self.position(index)?.clone();
+        self.items.get_mut(&position)
     }
 
     /// Inserts a value at a specific position (advanced API)
@@ -905,77 +1293,53 @@ impl List {
     /// Sets a value at a specific index, returns the old value if present
     /// Only considers non-tombstone elements for indexing
     pub fn set(&mut self, index: usize, value: impl Into<Value>) -> Option<Value> {
-        let value = value.into();
-        // Find the position of the nth non-tombstone element
-        let mut current_index = 0;
-        let mut target_position = None;
-
-        for (pos, val) in &self.items {
-            if !matches!(val, Value::Deleted) {
-                if current_index == index {
-                    target_position = Some(pos.clone());
-                    break;
-                }
-                current_index += 1;
-            }
-        }
-
-        if let Some(pos) = target_position {
-            self.items.insert(pos, value)
-        } else {
-            None
-        }
+        let position = self.position(index)?.clone();
+        self.items.insert(position, value.into())
     }
 
     /// Removes a value by index (tombstones it for CRDT semantics)
     /// Only considers non-tombstone elements for indexing
     pub fn remove(&mut self, index: usize) -> Option<Value> {
-        // Find the position of the nth non-tombstone element
-        let mut current_index = 0;
-        let mut target_position = None;
-
-        for (pos, val) in &self.items {
-            if !matches!(val, Value::Deleted) {
-                if current_index == index {
-                    target_position = Some(pos.clone());
-                    break;
-                }
-                current_index += 1;
-            }
-        }
-
-        if let Some(pos) = target_position {
-            let old_value = self.items.get(&pos).cloned();
-            self.items.insert(pos, Value::Deleted);
-            old_value
-        } else {
-            None
-        }
+        let position = self.position(index)?.clone();
+        self.items.insert(position, Value::Deleted)
     }
 
     /// Removes a value by position
     pub fn remove_by_position(&mut self, position: &Position) -> Option<Value> {
+        self.moves.remove(position);
         self.items.remove(position)
     }
 
     /// Returns an iterator over the values in order (excluding tombstones)
     pub fn iter(&self) -> impl Iterator<Item = &Value> {
-        self.items.values().filter(|v| !matches!(v, Value::Deleted))
+        self.ordered()
+            .into_iter()
+            .map(|(_, value)| value)
+            .filter(|v| !matches!(v, Value::Deleted))
     }
 
     /// Returns an iterator over all values including tombstones
     pub fn iter_all(&self) -> impl Iterator<Item = &Value> {
-        self.items.values()
+        self.ordered().into_iter().map(|(_, value)| value)
     }
 
     /// Returns an iterator over position-value pairs in order
+    ///
+    /// Positions are the ones items were inserted at, see
+    /// [`position`](Self::position).
     pub fn iter_with_positions(&self) -> impl Iterator<Item = (&Position, &Value)> {
-        self.items.iter()
+        self.ordered().into_iter()
     }
 
     /// Returns a mutable iterator over the values in order
     pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Value> {
-        self.items.values_mut()
+        let moves = &self.moves;
+        let mut items: Vec<_> = self.items.iter_mut().collect();
+        if !moves.is_empty() {
+            let order_of = |position| moves.get(position).map_or(position, |moved| &moved.to);
+            items.sort_by(|(a, _), (b, _)| order_of(*a).cmp(order_of(*b)));
+        }
+        items.into_iter().map(|(_, value)| value)
     }
 
     /// Merges another List into this one (CRDT merge operation)
@@ -992,19 +1356,51 @@ impl List {
                 }
             }
         }
+        // The latest move of each item wins, independently of merge order
+        for (position, moved) in &other.moves {
+            match self.moves.get_mut(position) {
+                Some(existing) if *existing >= *moved => {}
+                Some(existing) => *existing = moved.clone(),
+                None => {
+                    self.moves.insert(position.clone(), moved.clone());
+                }
+            }
+        }
     }
 
     /// Clears all items from the list
     pub fn clear(&mut self) {
         self.items.clear();
+        self.moves.clear();
     }
 
     /// Converts to a Vec of values (loses position information)
     pub fn to_vec(&self) -> Vec<Value> {
-        self.items.values().cloned().collect()
+        self.iter_all().cloned().collect()
     }
 }
 
+/// Formats a position as in serialized lists.
+fn format_position(position: &Position) -> String {
+    format!(
+        "{}:{}:{}",
+        position.numerator, position.denominator, position.unique_id
+    )
+}
+
+/// Parses a position formatted by [`format_position`].
+fn parse_position(key: &str) -> Result<Position, String> {
+    let parts: Vec<&str> = key.split(':').collect();
+    let [numerator, denominator, unique_id] = parts[..] else {
+        return Err(format!("invalid list position '{key}'"));
+    };
+    Ok(Position {
+        numerator: numerator.parse::<i64>().map_err(|e| e.to_string())?,
+        denominator: denominator.parse::<u64>().map_err(|e| e.to_string())?,
+        unique_id: unique_id.parse::<Uuid>().map_err(|e| e.to_string())?,
+    })
+}
+
 impl Default for List {
     fn default() -> Self {
         Self::new()
@@ -1026,6 +1422,9 @@ impl Map {
     pub fn new() -> Self {
         Self {
             children: HashMap::new(),
+            moves: HashMap::new(),
+            stamps: HashMap::new(),
+            cleared: None,
         }
     }
 
@@ -1133,6 +1532,16 @@ impl Map {
         self.get(key).and_then(|v| v.as_int())
     }
 
+    /// Gets a float value by key, converting integers
+    pub fn get_float(&self, key: impl AsRef<str>) -> Option<f64> {
+        self.get(key).and_then(|v| v.as_float())
+    }
+
+    /// Gets a byte array value by key
+    pub fn get_bytes(&self, key: impl AsRef<str>) -> Option<&[u8]> {
+        self.get(key).and_then(|v| v.as_bytes())
+    }
+
     /// Gets a boolean value by key
     pub fn get_bool(&self, key: impl AsRef<str>) -> Option<bool> {
         self.get(key).and_then(|v| v.as_bool())
@@ -1154,7 +1563,9 @@ impl Map {
         K: Into<String>,
         V: Into<Value>,
     {
-        self.children.insert(key.into(), value.into())
+        let key = key.into();
+        self.stamps.remove(&key);
+        self.children.insert(key, value.into())
     }
 
     /// Removes a value by key, returns the old value if present.
@@ -1171,6 +1582,7 @@ impl Map {
     pub fn remove(&mut self, key: impl Into<String>) -> Option<Value> {
         let key_string = key.into();
         let key_ref = &key_string;
+        self.stamps.remove(key_ref);
         match self.children.get(key_ref) {
             Some(Value::Deleted) => {
                 // Already deleted, return None and don't modify anything
@@ -1205,6 +1617,7 @@ impl Map {
         let key_string = key.into();
         let key_ref = &key_string;
         if self.children.contains_key(key_ref) {
+            self.stamps.remove(key_ref);
             self.children.insert(key_string, Value::Deleted);
             true
         } else {
@@ -1212,6 +1625,281 @@ impl Map {
         }
     }
 
+    /// Renames `from` to `to`, keeping its value.
+    ///
+    /// `from` becomes a tombstone that redirects values merged into it later to
+    /// `to`, so writes made concurrently with the rename on other replicas are
+    /// not lost. Renaming a key onto `from` makes it an ordinary key again.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use eidetica::crdt::map::Map;
+    /// # use eidetica::crdt::traits::CRDT;
+    /// let mut map = Map::new();
+    /// map.set("colour", "red");
+    /// let mut renamed = map.clone();
+    /// assert!(renamed.rename("colour", "color"));
+    ///
+    /// // A replica that has not seen the rename updates the old key
+    /// let mut concurrent = map.clone();
+    /// concurrent.set("colour", "blue");
+    ///
+    /// let merged = renamed.merge(&concurrent).unwrap();
+    /// assert_eq!(merged.get_text("color"), Some("blue"));
+    /// assert!(merged.get("colour").is_none());
+    /// assert_eq!(merged.moved_to("colour").as_deref(), Some("color"));
+    /// ```
+    ///
+    /// # Returns
+    /// `true` if the key was renamed, `false` if `from` has no value.
+    pub fn rename(&mut self, from: impl Into<String>, to: impl Into<String>) -> bool {
+        let (from, to) = (from.into(), to.into());
+        let Some(value) = self.get(&from).cloned() else {
+            return false;
+        };
+        if from == to {
+            return true;
+        }
+        self.stamps.remove(&from);
+        self.stamps.remove(&to);
+        self.children.insert(from.clone(), Value::Deleted);
+        self.children.insert(to.clone(), value.clone());
+        self.moves.remove(&to);
+        self.moves.insert(from, Move { to, value });
+        true
+    }
+
+    /// Returns the key that writes to `key` are redirected to, if it was renamed.
+    ///
+    /// Chains of renames are followed to the current name.
+    pub fn moved_to(&self, key: impl AsRef<str>) -> Option<String> {
+        let key = key.as_ref();
+        self.moves.contains_key(key).then(|| self.resolve(key))
+    }
+
+    /// Returns true if `key` was renamed or is the new name of a renamed key.
+    pub(super) fn is_renamed(&self, key: &str) -> bool {
+        self.moves.contains_key(key) || self.moves.values().any(|next| next.to == key)
+    }
+
+    /// Follows the renames starting at `key` to the key its values belong at.
+    fn resolve(&self, key: &str) -> String {
+        let mut current = key;
+        let mut seen = HashSet::new();
+        while let Some(next) = self.moves.get(current) {
+            // Concurrent renames can form a cycle; stop where it closes
+            if !seen.insert(current) {
+                break;
+            }
+            current = &next.to;
+        }
+        current.to_string()
+    }
+
+    /// Returns the stamp of the committed write that set `key`, if any.
+    ///
+    /// Keys written before stamps were introduced, or staged in an operation
+    /// that has not been committed, have no stamp. See [`Stamp`].
+    pub fn stamp(&self, key: impl AsRef<str>) -> Option<Stamp> {
+        self.stamps.get(key.as_ref()).copied()
+    }
+
+    /// Returns true if both maps are in the same CRDT state.
+    ///
+    /// Besides the values and renames compared by `==`, this compares the write
+    /// stamps of this map and of the maps nested in it, which decide how later
+    /// merges resolve.
+    pub fn same_state(&self, other: &Map) -> bool {
+        self.moves == other.moves
+            && self.stamps == other.stamps
+            && self.cleared == other.cleared
+            && self.children.len() == other.children.len()
+            && self
+                .children
+                .iter()
+                .all(|(key, value)| match (value, other.children.get(key)) {
+                    (Value::Map(map), Some(Value::Map(other))) => map.same_state(other),
+                    (value, Some(other)) => value == other,
+                    (_, None) => false,
+                })
+    }
+
+    /// Stamps every key of this map, and of the maps nested in it, as written
+    /// at `stamp`.
+    ///
+    /// Operations stamp the data of each map subtree when they are committed;
+    /// this is only needed to build stamped maps by hand.
+    pub fn stamp_writes(&mut self, stamp: Stamp) {
+        for (key, value) in &mut self.children {
+            self.stamps.insert(key.clone(), stamp);
+            if let Value::Map(map) = value {
+                map.stamp_writes(stamp);
+            }
+        }
+    }
+
+    /// Drops the stamped keys written before `bound`, here and in nested maps.
+    ///
+    /// Called when this map replaces a tombstone or another value stamped
+    /// `bound`: the keys it held before were removed by that write.
+    fn clear_before(&mut self, bound: Stamp) {
+        if self.cleared >= Some(bound) {
+            return;
+        }
+        self.cleared = Some(bound);
+        self.prune();
+    }
+
+    /// Removes the keys stamped before `cleared`.
+    fn prune(&mut self) {
+        let Some(bound) = self.cleared else {
+            return;
+        };
+        let stale: Vec<String> = self
+            .stamps
+            .iter()
+            .filter(|(_, stamp)| **stamp < bound)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in stale {
+            self.stamps.remove(&key);
+            self.children.remove(&key);
+        }
+        for value in self.children.values_mut() {
+            if let Value::Map(map) = value {
+                map.clear_before(bound);
+            }
+        }
+    }
+
+    /// Merges `other` into this map in place, see [`CRDT::merge`].
+    fn merge_from(&mut self, other: &Map) {
+        // Renames are applied first so that the values in `other` land where it
+        // expects them. A value or tombstone at a renamed key that differs from
+        // the value that was moved was written concurrently with the rename, and
+        // follows it to the new key; the moved value itself is already there.
+        let mut redirected = Vec::new();
+        for (from, next) in &other.moves {
+            if self.moves.get(from) == Some(next) {
+                continue;
+            }
+            if let Some(value) = self.children.get(from)
+                && *value != next.value
+            {
+                redirected.push(Redirect {
+                    from: from.clone(),
+                    to: next.to.clone(),
+                    value: value.clone(),
+                    stamp: self.stamp(from),
+                    moved: next.value.clone(),
+                });
+            }
+            if !other.moves.contains_key(&next.to) {
+                self.moves.remove(&next.to);
+            }
+            self.moves.insert(from.clone(), next.clone());
+        }
+
+        for (key, other_value) in &other.children {
+            let stamp = other.stamp(key);
+            if other.moves.contains_key(key) {
+                if redirected.iter().any(|redirect| redirect.from == *key) {
+                    // The value here follows the rename below
+                    self.children.insert(key.clone(), other_value.clone());
+                    self.set_stamp(key.clone(), stamp);
+                } else {
+                    self.merge_child(key.clone(), other_value, stamp);
+                }
+                continue;
+            }
+            match self.moves.get(key) {
+                Some(moved) if moved.value == *other_value => {}
+                Some(moved) => {
+                    let moved = moved.value.clone();
+                    let to = self.resolve(key);
+                    self.merge_redirected(to, other_value, stamp, &moved);
+                }
+                None => self.merge_child(key.clone(), other_value, stamp),
+            }
+        }
+        for redirect in redirected {
+            let to = self.resolve(&redirect.to);
+            self.merge_redirected(to, &redirect.value, redirect.stamp, &redirect.moved);
+        }
+
+        if other.cleared > self.cleared {
+            self.cleared = other.cleared;
+        }
+        self.prune();
+    }
+
+    /// Merges a value written to a renamed key into `to`, where its value was
+    /// moved.
+    ///
+    /// The value was written concurrently with the rename, so it replaces the
+    /// moved value whatever their stamps. Maps and lists are merged.
+    fn merge_redirected(&mut self, to: String, value: &Value, stamp: Option<Stamp>, moved: &Value) {
+        if self.children.get(&to) != Some(moved) || matches!(value, Value::Map(_) | Value::List(_))
+        {
+            self.merge_child(to, value, stamp);
+            return;
+        }
+        let stamp = self.stamp(&to).max(stamp);
+        self.children.insert(to.clone(), value.clone());
+        self.set_stamp(to, stamp);
+    }
+
+    fn set_stamp(&mut self, key: String, stamp: Option<Stamp>) {
+        match stamp {
+            Some(stamp) => self.stamps.insert(key, stamp),
+            None => self.stamps.remove(&key),
+        };
+    }
+
+    /// Merges a value written at `theirs` into `key`.
+    ///
+    /// When both sides are stamped, the later write wins, and maps and lists
+    /// are merged recursively. A map that wins over a tombstone or another value
+    /// drops its keys that were written before it. Otherwise the value merged
+    /// last wins, see [`Value::merge`].
+    fn merge_child(&mut self, key: String, other_value: &Value, theirs: Option<Stamp>) {
+        let Some(self_value) = self.children.get_mut(&key) else {
+            self.children.insert(key.clone(), other_value.clone());
+            if let Some(stamp) = theirs {
+                self.stamps.insert(key, stamp);
+            }
+            return;
+        };
+        let ours = self.stamps.get(&key).copied();
+        let same_kind = matches!(
+            (&*self_value, other_value),
+            (Value::Map(_), Value::Map(_)) | (Value::List(_), Value::List(_))
+        );
+
+        let stamp = match (ours, theirs) {
+            (Some(ours), Some(theirs)) => {
+                if same_kind {
+                    self_value.merge(other_value);
+                } else if theirs > ours {
+                    let mut value = other_value.clone();
+                    if let Value::Map(map) = &mut value {
+                        map.clear_before(ours);
+                    }
+                    *self_value = value;
+                } else if let Value::Map(map) = self_value {
+                    map.clear_before(theirs);
+                }
+                Some(ours.max(theirs))
+            }
+            _ => {
+                self_value.merge(other_value);
+                if same_kind { ours.max(theirs) } else { theirs }
+            }
+        };
+        self.set_stamp(key, stamp);
+    }
+
     /// Gets a value by path using dot notation (e.g., "users.123.name").
     ///
     /// Traverses the tree structure following the path segments separated by dots.
@@ -1223,6 +1911,9 @@ impl Map {
     /// - **Lists**: Navigate by index (e.g., "items.0.title")
     /// - **Mixed**: Combine both (e.g., "users.0.tags.1")
     ///
+    /// Segments may also be separated by `/`, and `\\` escapes a separator or
+    /// backslash within a key; see the [`path`](super::path) module.
+    ///
     /// # Examples
     ///
     /// ```
@@ -1241,15 +1932,11 @@ impl Map {
     /// # Returns
     ///
     /// - `Some(&Value)` if the path exists
-    /// - `None` if any segment of the path doesn't exist or has wrong type
+    /// - `None` if the path is malformed, or any segment of the path doesn't exist or has wrong type
     pub fn get_path(&self, path: impl AsRef<str>) -> Option<&Value> {
-        let path = path.as_ref();
-        let parts: Vec<&str> = path.split('.').collect();
-        if parts.is_empty() {
-            return None;
-        }
+        let parts = super::path::parse(path.as_ref()).ok()?;
 
-        let mut current_value = self.children.get(parts[0])?;
+        let mut current_value = self.children.get(&parts[0])?;
 
         for part in parts.iter().skip(1) {
             match current_value {
@@ -1298,13 +1985,9 @@ impl Map {
 
     /// Gets a mutable reference to a value by path
     pub fn get_path_mut(&mut self, path: impl AsRef<str>) -> Option<&mut Value> {
-        let path = path.as_ref();
-        let parts: Vec<&str> = path.split('.').collect();
-        if parts.is_empty() {
-            return None;
-        }
+        let parts = super::path::parse(path.as_ref()).ok()?;
 
-        let mut current_value = self.children.get_mut(parts[0])?;
+        let mut current_value = self.children.get_mut(&parts[0])?;
 
         for part in parts.iter().skip(1) {
             match current_value {
@@ -1327,6 +2010,9 @@ impl Map {
     }
 
     /// Sets a value at the given path, creating intermediate nodes as needed
+    ///
+    /// The path uses the syntax of the [`path`](super::path) module. Returns
+    /// `CRDTError::MalformedPath` if it cannot be parsed.
     pub fn set_path(
         &mut self,
         path: impl AsRef<str>,
@@ -1334,25 +2020,16 @@ impl Map {
     ) -> Result<Option<Value>, CRDTError> {
         let path = path.as_ref();
         let value = value.into();
-        let parts: Vec<&str> = path.split('.').collect();
-        if parts.is_empty() {
-            return Err(CRDTError::InvalidPath {
-                path: "Empty path".to_string(),
-            });
-        }
-
-        if parts.len() == 1 {
-            // Simple key set
-            return Ok(self.set(parts[0], value));
-        }
+        let mut parts = super::path::parse(path)?;
+        let final_key = parts.pop().expect("parsed paths are never empty");
 
         // Navigate to the parent, creating intermediate nodes as needed
         let mut current_map = self;
-        for part in parts.iter().take(parts.len() - 1) {
-            let part_owned = part.to_string();
+        for part in &parts {
+            let part_owned = part.clone();
 
             // Check if we need to create a new node
-            let needs_new_node = match current_map.children.get(*part) {
+            let needs_new_node = match current_map.children.get(part) {
                 Some(Value::Map(_)) => false,
                 Some(_) => {
                     // Existing non-node value, can't navigate further
@@ -1379,7 +2056,6 @@ impl Map {
         }
 
         // Set the final value
-        let final_key = parts[parts.len() - 1];
         Ok(current_map.set(final_key, value))
     }
 
@@ -1474,8 +2150,10 @@ impl CRDT for Map {
     /// - Associativity: (A ∪ B) ∪ C = A ∪ (B ∪ C)
     /// - Idempotency: A ∪ A = A
     ///
-    /// Note: This merge operation is NOT commutative (A ∪ B ≠ B ∪ A) due to
-    /// last-write-wins semantics for conflicting scalar values.
+    /// Conflicting values whose writes are both stamped (see [`Stamp`]) resolve
+    /// to the later write, so merging maps of committed data is also
+    /// commutative. Unstamped values fall back to the value merged last, which
+    /// is NOT commutative (A ∪ B ≠ B ∪ A).
     ///
     /// # Merge Strategy
     ///
@@ -1483,6 +2161,8 @@ impl CRDT for Map {
     /// - **Additive**: Keys present in either node appear in the result
     /// - **Value merging**: Conflicting values use Value merge semantics
     /// - **Tombstone handling**: Deletion markers are preserved for consistency
+    /// - **Renames**: Values merged into a renamed key go to its new name, see
+    ///   [`Map::rename`]
     ///
     /// # Examples
     ///
@@ -1504,18 +2184,7 @@ impl CRDT for Map {
     /// ```
     fn merge(&self, other: &Self) -> crate::Result<Self> {
         let mut merged = self.clone();
-        for (key, other_value) in &other.children {
-            match merged.children.get_mut(key) {
-                Some(self_value) => {
-                    // Both nodes have this key, merge the values
-                    self_value.merge(other_value);
-                }
-                None => {
-                    // Only other node has this key, add it
-                    merged.children.insert(key.clone(), other_value.clone());
-                }
-            }
-        }
+        merged.merge_from(other);
         Ok(merged)
     }
 }
@@ -1840,17 +2509,28 @@ impl Map {
     }
 }
 
+/// Prefix of the keys recording moved list items when serialized
+const LIST_MOVE_PREFIX: &str = "move:";
+
 // Custom serialization for List to handle Position keys
+//
+// Moves are stored as extra entries keyed by the moved item's position with
+// `LIST_MOVE_PREFIX`, holding `"<version>@<position>"` as text. Readers that do
+// not know about moves skip these keys.
 impl serde::Serialize for List {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
     {
         use serde::ser::SerializeMap;
-        let mut map = serializer.serialize_map(Some(self.items.len()))?;
+        let mut map = serializer.serialize_map(Some(self.items.len() + self.moves.len()))?;
         for (pos, value) in &self.items {
-            let key = format!("{}:{}:{}", pos.numerator, pos.denominator, pos.unique_id);
-            map.serialize_entry(&key, value)?;
+            map.serialize_entry(&format_position(pos), value)?;
+        }
+        for (pos, moved) in &self.moves {
+            let key = format!("{LIST_MOVE_PREFIX}{}", format_position(pos));
+            let value = Value::Text(format!("{}@{}", moved.version, format_position(&moved.to)));
+            map.serialize_entry(&key, &value)?;
         }
         map.end()
     }
@@ -1861,7 +2541,7 @@ impl<'de> serde::Deserialize<'de> for List {
     where
         D: serde::Deserializer<'de>,
     {
-        use serde::de::{MapAccess, Visitor};
+        use serde::de::{Error, MapAccess, Visitor};
         use std::fmt;
 
         struct ListVisitor;
@@ -1877,25 +2557,25 @@ impl<'de> serde::Deserialize<'de> for List {
             where
                 A: MapAccess<'de>,
             {
-                let mut items = BTreeMap::new();
+                let mut list = List::new();
                 while let Some((key, value)) = map.next_entry::<String, Value>()? {
-                    let parts: Vec<&str> = key.split(':').collect();
-                    if parts.len() == 3 {
-                        let numerator =
-                            parts[0].parse::<i64>().map_err(serde::de::Error::custom)?;
-                        let denominator =
-                            parts[1].parse::<u64>().map_err(serde::de::Error::custom)?;
-                        let unique_id =
-                            parts[2].parse::<Uuid>().map_err(serde::de::Error::custom)?;
-                        let position = Position {
-                            numerator,
-                            denominator,
-                            unique_id,
+                    if let Some(moved) = key.strip_prefix(LIST_MOVE_PREFIX) {
+                        let position = parse_position(moved).map_err(A::Error::custom)?;
+                        let (version, to) = value
+                            .as_text()
+                            .and_then(|text| text.split_once('@'))
+                            .ok_or_else(|| A::Error::custom("invalid list move"))?;
+                        let moved = ListMove {
+                            version: version.parse().map_err(A::Error::custom)?,
+                            to: parse_position(to).map_err(A::Error::custom)?,
                         };
-                        items.insert(position, value);
+                        list.moves.insert(position, moved);
+                    } else if key.split(':').count() == 3 {
+                        let position = parse_position(&key).map_err(A::Error::custom)?;
+                        list.items.insert(position, value);
                     }
                 }
-                Ok(List { items })
+                Ok(list)
             }
         }
 
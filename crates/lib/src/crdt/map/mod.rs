@@ -16,9 +16,11 @@
 //!
 //! ## Conflict Resolution
 //! The Map CRDT implements several conflict resolution strategies:
-//! - **Last-write-wins** for scalar values (text, numbers, booleans)
+//! - **Last-write-wins** for scalar values (text, numbers, booleans), ordered by
+//!   the causal [`Stamp`] of each write
 //! - **Structural merging** for nested maps and lists
 //! - **Tombstone deletion** for preserving CRDT merge semantics
+//! - **Custom resolution** of concurrent scalar writes with a [`MergeResolver`]
 //! - **Stable ordering** for lists using rational number positions
 //!
 //! ## List Ordering with Rational Numbers
@@ -95,8 +97,16 @@
 //! - **CRDT semantics**: Proper conflict resolution and merge behavior
 //! - **Tombstone hiding**: Internal deletion markers are hidden from public API
 
+mod diff;
 mod implementation;
 pub mod list;
+pub mod path;
+mod resolve;
+mod stamp;
 mod tests;
 
+pub use diff::Change;
 pub use implementation::*;
+pub use resolve::MergeResolver;
+pub(crate) use resolve::{conflicting_leaves, resolve_conflicts};
+pub use stamp::Stamp;
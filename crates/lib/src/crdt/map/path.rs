@@ -0,0 +1,100 @@
+//! Path syntax for addressing nested values.
+//!
+//! A path names a value inside nested maps and lists, one segment per level,
+//! with segments separated by `.` or `/`: `user.profile.name` and
+//! `user/profile/name` are the same path. List elements are addressed by their
+//! index, as in `items.0.title`.
+//!
+//! A backslash escapes the character after it, so keys containing separators
+//! can still be addressed: `files.report\.pdf` has the segments `files` and
+//! `report.pdf`. Only `\.`, `\/` and `\\` are valid escapes.
+//!
+//! Paths must have at least one segment, and segments cannot be empty, so
+//! leading, trailing and doubled separators are errors.
+//!
+//! ```
+//! # use eidetica::crdt::map::path;
+//! let segments = path::parse("files/report\\.pdf").unwrap();
+//! assert_eq!(segments, vec!["files", "report.pdf"]);
+//! assert_eq!(path::join(&segments), "files.report\\.pdf");
+//!
+//! assert!(path::parse("user..name").is_err());
+//! ```
+
+use crate::crdt::CRDTError;
+
+/// Characters that separate path segments.
+pub const SEPARATORS: [char; 2] = ['.', '/'];
+
+/// The character that escapes the next character of a path.
+pub const ESCAPE: char = '\\';
+
+/// Splits `path` into its segments, resolving escapes.
+///
+/// # Errors
+/// Returns `CRDTError::MalformedPath` if the path is empty, has an empty
+/// segment, an invalid escape, or ends with an unfinished escape.
+pub fn parse(path: &str) -> Result<Vec<String>, CRDTError> {
+    let malformed = |reason: String| CRDTError::MalformedPath {
+        path: path.to_string(),
+        reason,
+    };
+    if path.is_empty() {
+        return Err(malformed("path is empty".to_string()));
+    }
+
+    let mut segments = Vec::new();
+    let mut segment = String::new();
+    let mut chars = path.char_indices();
+    while let Some((position, c)) = chars.next() {
+        if c == ESCAPE {
+            match chars.next() {
+                Some((_, escaped)) if escaped == ESCAPE || SEPARATORS.contains(&escaped) => {
+                    segment.push(escaped)
+                }
+                Some((_, escaped)) => {
+                    return Err(malformed(format!(
+                        "invalid escape '\\{escaped}' at position {position}"
+                    )));
+                }
+                None => return Err(malformed("path ends with an unfinished escape".to_string())),
+            }
+        } else if SEPARATORS.contains(&c) {
+            if segment.is_empty() {
+                return Err(malformed(format!("empty segment at position {position}")));
+            }
+            segments.push(std::mem::take(&mut segment));
+        } else {
+            segment.push(c);
+        }
+    }
+    if segment.is_empty() {
+        return Err(malformed("path ends with a separator".to_string()));
+    }
+    segments.push(segment);
+    Ok(segments)
+}
+
+/// Escapes a single key so it can be used as one segment of a path.
+pub fn escape(segment: &str) -> String {
+    let mut escaped = String::with_capacity(segment.len());
+    for c in segment.chars() {
+        if c == ESCAPE || SEPARATORS.contains(&c) {
+            escaped.push(ESCAPE);
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Joins segments into a path using `.`, escaping them as needed.
+///
+/// [`parse`] turns the result back into the same segments, as long as none of
+/// them is empty.
+pub fn join<S: AsRef<str>>(segments: &[S]) -> String {
+    segments
+        .iter()
+        .map(|segment| escape(segment.as_ref()))
+        .collect::<Vec<_>>()
+        .join(".")
+}
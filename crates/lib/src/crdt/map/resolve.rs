@@ -0,0 +1,199 @@
+//! Custom resolution of concurrent writes.
+//!
+//! By default, when two branches of history write different values to the
+//! same leaf of a [`Map`], the write merged last wins. A [`MergeResolver`]
+//! replaces that choice: at each point where branches are merged, every leaf
+//! that more than one branch changed to a different value is passed to the
+//! resolver, and its result becomes the merged value.
+//!
+//! Leaves are compared against the state the branches started from (their
+//! lowest common ancestor), so a leaf changed on only one branch takes that
+//! branch's value without calling the resolver. Nested maps are compared key by
+//! key. Lists and renamed keys keep their own merge and are never passed to
+//! the resolver.
+
+use super::{Map, Value, path};
+use std::sync::Arc;
+
+/// Resolves a conflict between two values written concurrently to one leaf.
+///
+/// Called with the path of the leaf (see [`path::join`]), the value of the
+/// branch merged first (`ours`) and the value of the branch merged after it
+/// (`theirs`). A deleted value is passed as [`Value::Deleted`], and returning
+/// `Value::Deleted` deletes the leaf.
+///
+/// The resolver must be deterministic: every replica calls it with the same
+/// arguments in the same order, and only converges if it returns the same
+/// result each time.
+pub type MergeResolver = Arc<dyn Fn(&str, &Value, &Value) -> Value + Send + Sync>;
+
+/// Replaces the leaves of `merged` that `branches` changed concurrently since
+/// `base` with the result of `resolver`.
+///
+/// `merged` is the last-write-wins merge of the branches, and `branches` their
+/// states in merge order. Leaves changed to the same value by every branch
+/// that changed them take that value.
+pub(crate) fn resolve_conflicts(
+    merged: &mut Map,
+    base: &Map,
+    branches: &[&Map],
+    resolver: &(dyn Fn(&str, &Value, &Value) -> Value + Send + Sync),
+) {
+    resolve_map(merged, base, branches, &mut Vec::new(), resolver);
+}
+
+fn resolve_map(
+    merged: &mut Map,
+    base: &Map,
+    branches: &[&Map],
+    prefix: &mut Vec<String>,
+    resolver: &(dyn Fn(&str, &Value, &Value) -> Value + Send + Sync),
+) {
+    let mut keys: Vec<&String> = branches
+        .iter()
+        .flat_map(|branch| branch.as_hashmap().keys())
+        .collect();
+    keys.sort_unstable();
+    keys.dedup();
+
+    for key in keys {
+        if merged.is_renamed(key) {
+            continue;
+        }
+        let base_value = base.as_hashmap().get(key);
+        let values: Vec<Option<&Value>> = branches
+            .iter()
+            .map(|branch| branch.as_hashmap().get(key))
+            .collect();
+
+        prefix.push(key.clone());
+        let all_maps = values
+            .iter()
+            .all(|value| matches!(value, None | Some(Value::Map(_))))
+            && matches!(base_value, None | Some(Value::Map(_)));
+        if all_maps {
+            if let Some(Value::Map(merged_child)) = merged.as_hashmap_mut().get_mut(key) {
+                let empty = Map::new();
+                let child_branches: Vec<&Map> =
+                    values.iter().map(|value| as_map(*value, &empty)).collect();
+                resolve_map(
+                    merged_child,
+                    as_map(base_value, &empty),
+                    &child_branches,
+                    prefix,
+                    resolver,
+                );
+            }
+        } else if !values
+            .iter()
+            .any(|value| matches!(value, Some(Value::List(_))))
+        {
+            let mut changed: Vec<&Value> = Vec::new();
+            for value in values.into_iter().flatten() {
+                if Some(value) != base_value && !changed.contains(&value) {
+                    changed.push(value);
+                }
+            }
+            if let Some((first, rest)) = changed.split_first() {
+                let leaf = path::join(prefix);
+                let resolved = rest.iter().fold((*first).clone(), |ours, theirs| {
+                    resolver(&leaf, &ours, theirs)
+                });
+                merged.as_hashmap_mut().insert(key.clone(), resolved);
+            }
+        }
+        prefix.pop();
+    }
+}
+
+/// The map in `value`, or `empty` if the key is missing.
+fn as_map<'a>(value: Option<&'a Value>, empty: &'a Map) -> &'a Map {
+    match value {
+        Some(Value::Map(map)) => map,
+        _ => empty,
+    }
+}
+
+/// A leaf that two branches changed concurrently to different values.
+pub(crate) struct LeafConflict {
+    /// Path of the leaf, see [`path::join`]
+    pub path: String,
+    /// The value before either branch changed it
+    pub base: Value,
+    pub ours: Value,
+    pub theirs: Value,
+    /// The value in the merged state
+    pub merged: Value,
+}
+
+/// Lists the leaves that both `ours` and `theirs` changed since `base` to
+/// different values, ordered by path.
+///
+/// Leaves are found by the same rules [`resolve_conflicts`] uses: nested maps
+/// are compared key by key, and lists and renamed keys are skipped. A missing
+/// or deleted key is reported as [`Value::Deleted`].
+pub(crate) fn conflicting_leaves(
+    merged: &Map,
+    base: &Map,
+    ours: &Map,
+    theirs: &Map,
+) -> Vec<LeafConflict> {
+    let mut conflicts = Vec::new();
+    find_conflicts(merged, base, ours, theirs, &mut Vec::new(), &mut conflicts);
+    conflicts
+}
+
+fn find_conflicts(
+    merged: &Map,
+    base: &Map,
+    ours: &Map,
+    theirs: &Map,
+    prefix: &mut Vec<String>,
+    conflicts: &mut Vec<LeafConflict>,
+) {
+    let mut keys: Vec<&String> = ours
+        .as_hashmap()
+        .keys()
+        .chain(theirs.as_hashmap().keys())
+        .collect();
+    keys.sort_unstable();
+    keys.dedup();
+
+    for key in keys {
+        if merged.is_renamed(key) {
+            continue;
+        }
+        let value = |map: &Map| map.get(key).cloned().unwrap_or(Value::Deleted);
+        let (base_value, our_value, their_value) = (value(base), value(ours), value(theirs));
+
+        prefix.push(key.clone());
+        match (&our_value, &their_value) {
+            (Value::Map(our_child), Value::Map(their_child)) => {
+                let empty = Map::new();
+                find_conflicts(
+                    merged.get_node(key).unwrap_or(&empty),
+                    as_map(base.get(key), &empty),
+                    our_child,
+                    their_child,
+                    prefix,
+                    conflicts,
+                );
+            }
+            (Value::List(_), _) | (_, Value::List(_)) => {}
+            _ if our_value != base_value
+                && their_value != base_value
+                && our_value != their_value =>
+            {
+                conflicts.push(LeafConflict {
+                    path: path::join(prefix),
+                    base: base_value,
+                    ours: our_value,
+                    theirs: their_value,
+                    merged: value(merged),
+                });
+            }
+            _ => {}
+        }
+        prefix.pop();
+    }
+}
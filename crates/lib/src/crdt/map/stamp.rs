@@ -0,0 +1,80 @@
+//! Causal write stamps for map keys.
+//!
+//! Every key written by a committed entry records a [`Stamp`]: the entry's
+//! hybrid logical clock timestamp and a random site number drawn once per
+//! entry. A timestamp is greater than those of all the entry's ancestors, so a
+//! write always has a larger stamp than every write it could have seen. When
+//! two stamped values meet in a merge, the one with the larger stamp wins, which
+//! makes the result independent of the order branches are merged in. Concurrent
+//! writes are ordered by wall clock time, with the site breaking exact ties.
+//!
+//! Values without a stamp, written before stamps existed or staged in an
+//! operation that has not been committed yet, merge by merge order as before.
+
+use crate::clock::Hlc;
+use crate::crdt::CRDTError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// When and where a map key was written.
+///
+/// Ordered by timestamp, then by site. Serialized as
+/// `"<wall_ms>.<counter>@<site>"` with the site in hex.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct Stamp {
+    /// Timestamp of the entry that wrote the key
+    pub hlc: Hlc,
+    /// Random for each entry, telling apart writes with the same timestamp
+    pub site: u64,
+}
+
+impl Stamp {
+    /// Creates a stamp for a write at `hlc` with a random site.
+    pub fn new(hlc: Hlc) -> Self {
+        Self {
+            hlc,
+            site: rand::random(),
+        }
+    }
+}
+
+impl fmt::Display for Stamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{:016x}", self.hlc, self.site)
+    }
+}
+
+impl FromStr for Stamp {
+    type Err = CRDTError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let invalid = || CRDTError::DeserializationFailed {
+            reason: format!("Invalid write stamp '{s}'"),
+        };
+        let (hlc, site) = s.split_once('@').ok_or_else(invalid)?;
+        let (wall_ms, counter) = hlc.split_once('.').ok_or_else(invalid)?;
+        Ok(Self {
+            hlc: Hlc::new(
+                wall_ms.parse().map_err(|_| invalid())?,
+                counter.parse().map_err(|_| invalid())?,
+            ),
+            site: u64::from_str_radix(site, 16).map_err(|_| invalid())?,
+        })
+    }
+}
+
+impl From<Stamp> for String {
+    fn from(stamp: Stamp) -> Self {
+        stamp.to_string()
+    }
+}
+
+impl TryFrom<String> for Stamp {
+    type Error = CRDTError;
+
+    fn try_from(s: String) -> std::result::Result<Self, Self::Error> {
+        s.parse()
+    }
+}
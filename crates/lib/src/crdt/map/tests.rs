@@ -75,6 +75,7 @@ mod test_map {
             Value::Bool(true),
             Value::Int(42),
             Value::Text("test".to_string()),
+            Value::Link("abc".into()),
             Value::Deleted,
         ];
 
@@ -98,8 +99,40 @@ mod test_map {
         assert_eq!(Value::Bool(true).type_name(), "bool");
         assert_eq!(Value::Int(42).type_name(), "int");
         assert_eq!(Value::Text("test".to_string()).type_name(), "text");
+        assert_eq!(Value::Link("abc".into()).type_name(), "link");
         assert_eq!(Value::Map(Map::new()).type_name(), "node");
         assert_eq!(Value::List(List::new()).type_name(), "list");
         assert_eq!(Value::Deleted.type_name(), "deleted");
     }
+
+    #[test]
+    fn test_resolve_conflicts_leaves_lists_and_renames() {
+        use crate::crdt::CRDT;
+        use crate::crdt::map::resolve_conflicts;
+
+        let mut base = Map::new();
+        base.set("old", "value");
+        base.set("items", List::new());
+
+        let mut ours = base.clone();
+        ours.rename("old", "new");
+        ours.list_add("items", Value::Int(1)).unwrap();
+        ours.set("score", 1);
+        let mut theirs = base.clone();
+        theirs.set("old", "changed");
+        theirs.list_add("items", Value::Int(2)).unwrap();
+        theirs.set("score", 2);
+
+        let mut merged = base.merge(&ours).unwrap().merge(&theirs).unwrap();
+        let expected = merged.clone();
+        resolve_conflicts(&mut merged, &base, &[&ours, &theirs], &|key, _, _| {
+            assert_eq!(key, "score");
+            Value::Int(0)
+        });
+
+        assert_eq!(merged.get_int("score"), Some(0));
+        assert_eq!(merged.get("new"), expected.get("new"));
+        assert_eq!(merged.get("items"), expected.get("items"));
+        assert!(merged.get("old").is_none());
+    }
 }
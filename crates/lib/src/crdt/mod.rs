@@ -10,6 +10,7 @@
 //! - [`map::List`] - An ordered collection with rational number positioning
 //! - [`map::Value`] - The value type for nested structures
 //! - [`map::list::Position`] - Rational number-based positions for stable list ordering
+//! - [`Rga`] - Replicated text, edited by inserting and deleting characters
 //!
 //! # Traits
 //!
@@ -19,9 +20,11 @@
 // Core modules
 pub mod errors;
 pub mod map;
+pub mod text;
 pub mod traits;
 
 // Re-export core types
 pub use errors::CRDTError;
 pub use map::Map;
+pub use text::{CharId, Rga};
 pub use traits::{CRDT, Data};
@@ -0,0 +1,266 @@
+//! Replicated text
+//!
+//! [`Rga`] is a replicated growable array of characters. Every character is
+//! inserted after another one, or at the start, and keeps a unique [`CharId`]
+//! for as long as the text exists. Deleted characters stay behind as
+//! tombstones, so a position another replica inserted at can always be found.
+//! Merging is a union of characters, with a character deleted if either side
+//! deleted it, so replicas that have seen the same changes show the same text.
+//!
+//! Characters inserted concurrently after the same character are ordered by
+//! their IDs, newest first. A new ID has a counter larger than that of every
+//! character present when it was inserted, so text typed in sequence stays
+//! together and a concurrent insertion never lands inside it.
+//!
+//! Every character is stored on its own and tombstones are never dropped,
+//! which suits short strings such as titles and descriptions. Long documents
+//! are better served by the `YDoc` subtree.
+
+use super::{CRDT, CRDTError, Data};
+use crate::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::str::FromStr;
+
+/// The identity of a character in an [`Rga`].
+///
+/// IDs are ordered by counter, then by site. Serialized as `"<counter>@<site>"`
+/// with the site in hex.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct CharId {
+    /// Larger than the counter of every character present at insertion
+    pub counter: u64,
+    /// Random for each insertion, telling apart characters inserted with the
+    /// same counter on different replicas
+    pub site: u64,
+}
+
+impl fmt::Display for CharId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{:016x}", self.counter, self.site)
+    }
+}
+
+impl FromStr for CharId {
+    type Err = CRDTError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let invalid = || CRDTError::DeserializationFailed {
+            reason: format!("Invalid character ID '{s}'"),
+        };
+        let (counter, site) = s.split_once('@').ok_or_else(invalid)?;
+        Ok(Self {
+            counter: counter.parse().map_err(|_| invalid())?,
+            site: u64::from_str_radix(site, 16).map_err(|_| invalid())?,
+        })
+    }
+}
+
+impl From<CharId> for String {
+    fn from(id: CharId) -> Self {
+        id.to_string()
+    }
+}
+
+impl TryFrom<String> for CharId {
+    type Error = CRDTError;
+
+    fn try_from(s: String) -> std::result::Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// A character and where it was inserted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Element {
+    /// The character this one was inserted after, or `None` for the start
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    after: Option<CharId>,
+    value: char,
+    #[serde(default, skip_serializing_if = "is_false")]
+    deleted: bool,
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+/// A replicated growable array of characters, the CRDT behind
+/// [`subtree::Text`](crate::subtree::Text).
+///
+/// Positions are counted in characters (Unicode scalar values), not bytes.
+/// Edits return the characters they added or changed, which is all that needs
+/// to be stored or sent for the edit to merge into other replicas.
+///
+/// # Example
+/// ```
+/// # use eidetica::crdt::{CRDT, Rga};
+/// let mut base = Rga::new();
+/// base.insert(0, "helo").unwrap();
+///
+/// // Two replicas edit the same text concurrently
+/// let mut left = base.clone();
+/// let mut right = base.clone();
+/// left.insert(3, "l").unwrap();
+/// right.insert(4, " world").unwrap();
+///
+/// let merged = left.merge(&right).unwrap();
+/// assert_eq!(merged.to_string(), "hello world");
+/// assert_eq!(merged, right.merge(&left).unwrap());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rga {
+    #[serde(default)]
+    chars: BTreeMap<CharId, Element>,
+}
+
+impl Data for Rga {}
+
+impl CRDT for Rga {
+    fn merge(&self, other: &Self) -> Result<Self> {
+        let mut merged = self.clone();
+        for (id, theirs) in &other.chars {
+            merged
+                .chars
+                .entry(*id)
+                .and_modify(|ours| {
+                    ours.deleted |= theirs.deleted;
+                    // An ID is only ever inserted once; pick deterministically if not
+                    if theirs.value > ours.value {
+                        ours.value = theirs.value;
+                    }
+                })
+                .or_insert_with(|| theirs.clone());
+        }
+        Ok(merged)
+    }
+}
+
+impl Rga {
+    /// Creates empty text.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of characters in the text.
+    pub fn len(&self) -> usize {
+        self.chars
+            .values()
+            .filter(|element| !element.deleted)
+            .count()
+    }
+
+    /// Returns true if the text has no characters.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The IDs of the characters in the text, in order.
+    pub fn char_ids(&self) -> Vec<CharId> {
+        self.order()
+            .into_iter()
+            .filter(|id| !self.chars[id].deleted)
+            .collect()
+    }
+
+    /// Inserts `text` before the character at `index`, or at the end if
+    /// `index` is the length of the text.
+    ///
+    /// # Returns
+    /// The inserted characters
+    ///
+    /// # Errors
+    /// Returns `CRDTError::TextIndexOutOfBounds` if `index` is past the end.
+    pub fn insert(&mut self, index: usize, text: &str) -> Result<Rga> {
+        let visible = self.char_ids();
+        if index > visible.len() {
+            return Err(CRDTError::TextIndexOutOfBounds {
+                index,
+                len: visible.len(),
+            }
+            .into());
+        }
+
+        let site = rand::random();
+        let first = self.chars.keys().map(|id| id.counter).max().unwrap_or(0) + 1;
+        let mut after = index.checked_sub(1).map(|i| visible[i]);
+        let mut inserted = Rga::new();
+        for (counter, value) in (first..).zip(text.chars()) {
+            let id = CharId { counter, site };
+            let element = Element {
+                after,
+                value,
+                deleted: false,
+            };
+            inserted.chars.insert(id, element.clone());
+            self.chars.insert(id, element);
+            after = Some(id);
+        }
+        Ok(inserted)
+    }
+
+    /// Deletes `len` characters starting at `index`.
+    ///
+    /// # Returns
+    /// The deleted characters, marked as deleted
+    ///
+    /// # Errors
+    /// Returns `CRDTError::TextIndexOutOfBounds` if the range goes past the end.
+    pub fn delete(&mut self, index: usize, len: usize) -> Result<Rga> {
+        let visible = self.char_ids();
+        let end = index.saturating_add(len);
+        if end > visible.len() {
+            return Err(CRDTError::TextIndexOutOfBounds {
+                index: end,
+                len: visible.len(),
+            }
+            .into());
+        }
+
+        let mut deleted = Rga::new();
+        for id in &visible[index..end] {
+            let element = self.chars.get_mut(id).expect("visible IDs are stored");
+            element.deleted = true;
+            deleted.chars.insert(*id, element.clone());
+        }
+        Ok(deleted)
+    }
+
+    /// The IDs of all characters, deleted ones included, in text order.
+    ///
+    /// Characters form a tree through the character they were inserted after;
+    /// the text is its pre-order traversal, visiting newer siblings first.
+    fn order(&self) -> Vec<CharId> {
+        let mut children: HashMap<Option<CharId>, Vec<CharId>> = HashMap::new();
+        for (id, element) in &self.chars {
+            // Characters inserted after one that is not stored go at the start
+            let after = element.after.filter(|after| self.chars.contains_key(after));
+            children.entry(after).or_default().push(*id);
+        }
+
+        // Siblings are pushed in ascending ID order, so the newest is visited first
+        let mut order = Vec::with_capacity(self.chars.len());
+        let mut stack = children.remove(&None).unwrap_or_default();
+        while let Some(id) = stack.pop() {
+            order.push(id);
+            if let Some(next) = children.remove(&Some(id)) {
+                stack.extend(next);
+            }
+        }
+        order
+    }
+}
+
+impl fmt::Display for Rga {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for id in self.order() {
+            let element = &self.chars[&id];
+            if !element.deleted {
+                write!(f, "{}", element.value)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,49 @@
+//! Provenance information recorded in entry metadata.
+//!
+//! Every committed entry is timestamped with a hybrid logical clock (see
+//! [`Entry::timestamp`](super::Entry::timestamp)). `AppInfo` adds where the
+//! entry came from: the device it was written on and the version of the
+//! application that wrote it. It is stored in the entry metadata, so it is
+//! covered by the entry ID and cannot be changed after the entry is created.
+
+use serde::{Deserialize, Serialize};
+
+/// Device and application that wrote an entry.
+///
+/// Set it on an [`EntryBuilder`](super::EntryBuilder), on a single
+/// [`AtomicOp`](crate::atomicop::AtomicOp), or as the default for every
+/// operation of a database with
+/// [`BaseDB::set_default_app_info`](crate::basedb::BaseDB::set_default_app_info).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppInfo {
+    /// Name of the device the entry was written on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device: Option<String>,
+    /// Version of the application that wrote the entry
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app_version: Option<String>,
+}
+
+impl AppInfo {
+    /// Create an empty `AppInfo`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the device name.
+    pub fn with_device(mut self, device: impl Into<String>) -> Self {
+        self.device = Some(device.into());
+        self
+    }
+
+    /// Set the application version.
+    pub fn with_app_version(mut self, app_version: impl Into<String>) -> Self {
+        self.app_version = Some(app_version.into());
+        self
+    }
+
+    /// Whether neither the device nor the application version is set.
+    pub fn is_empty(&self) -> bool {
+        self.device.is_none() && self.app_version.is_none()
+    }
+}
@@ -0,0 +1,157 @@
+//! Error types for entries.
+//!
+//! This module defines structured error types for entries whose structure is
+//! invalid, as found by [`Entry::validate_structure`](super::Entry::validate_structure)
+//! when entries are loaded from storage or received from a peer.
+
+use super::ID;
+use thiserror::Error;
+
+/// Errors describing an entry that is malformed or exceeds the structural limits.
+///
+/// # Stability
+///
+/// - New variants may be added in minor versions (enum is `#[non_exhaustive]`)
+/// - Existing variants will not be removed in minor versions
+/// - Field additions/changes require a major version bump
+/// - Helper methods like `is_*()` provide stable APIs
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum EntryError {
+    /// The serialized entry is larger than allowed.
+    #[error("Entry is {size} bytes, more than the limit of {limit}")]
+    TooLarge {
+        /// Size of the serialized entry in bytes
+        size: usize,
+        /// The maximum size, see [`MAX_ENTRY_SIZE`](super::MAX_ENTRY_SIZE)
+        limit: usize,
+    },
+
+    /// The entry lists more parents than allowed.
+    #[error("Entry has {count} parents{}, more than the limit of {limit}", subtree_suffix(.subtree))]
+    TooManyParents {
+        /// The subtree whose parents exceed the limit, or `None` for the main tree
+        subtree: Option<String>,
+        /// Number of parents listed
+        count: usize,
+        /// The maximum number of parents, see [`MAX_PARENTS`](super::MAX_PARENTS)
+        limit: usize,
+    },
+
+    /// The entry has data for more subtrees than allowed.
+    #[error("Entry has {count} subtrees, more than the limit of {limit}")]
+    TooManySubtrees {
+        /// Number of subtrees in the entry
+        count: usize,
+        /// The maximum number of subtrees, see [`MAX_SUBTREES`](super::MAX_SUBTREES)
+        limit: usize,
+    },
+
+    /// A subtree name breaks the naming rules.
+    #[error("Invalid subtree name {name:?}: {reason}")]
+    InvalidSubtreeName {
+        /// The offending name
+        name: String,
+        /// Which rule the name breaks
+        reason: String,
+    },
+
+    /// The entry is not in the canonical form produced by `EntryBuilder`, so
+    /// the same content could be stored under more than one ID.
+    #[error("Entry is not in canonical form: {reason}")]
+    NotCanonical {
+        /// What is out of order or duplicated
+        reason: String,
+    },
+
+    /// The entry's content does not hash to the ID it was stored or sent under.
+    #[error("Entry stored as '{expected}' hashes to '{actual}'")]
+    IdMismatch {
+        /// The ID the entry claimed
+        expected: ID,
+        /// The ID its content hashes to
+        actual: ID,
+    },
+}
+
+fn subtree_suffix(subtree: &Option<String>) -> String {
+    match subtree {
+        Some(name) => format!(" in subtree '{name}'"),
+        None => String::new(),
+    }
+}
+
+impl EntryError {
+    /// Check if this error is about an entry exceeding a size or count limit.
+    pub fn is_limit_exceeded(&self) -> bool {
+        matches!(
+            self,
+            EntryError::TooLarge { .. }
+                | EntryError::TooManyParents { .. }
+                | EntryError::TooManySubtrees { .. }
+        )
+    }
+
+    /// Check if this error is about an entry that is malformed, regardless of its size.
+    pub fn is_malformed(&self) -> bool {
+        matches!(
+            self,
+            EntryError::InvalidSubtreeName { .. }
+                | EntryError::NotCanonical { .. }
+                | EntryError::IdMismatch { .. }
+        )
+    }
+
+    /// Check if this error indicates an entry that does not match its ID.
+    pub fn is_id_mismatch(&self) -> bool {
+        matches!(self, EntryError::IdMismatch { .. })
+    }
+}
+
+// Conversion from EntryError to the main Error type
+impl From<EntryError> for crate::Error {
+    fn from(err: EntryError) -> Self {
+        crate::Error::Entry(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_helpers() {
+        let err = EntryError::TooManyParents {
+            subtree: Some("users".to_string()),
+            count: 2000,
+            limit: 1024,
+        };
+        assert!(err.is_limit_exceeded());
+        assert!(!err.is_malformed());
+        assert_eq!(
+            err.to_string(),
+            "Entry has 2000 parents in subtree 'users', more than the limit of 1024"
+        );
+
+        let err = EntryError::IdMismatch {
+            expected: ID::from("a"),
+            actual: ID::from("b"),
+        };
+        assert!(err.is_malformed());
+        assert!(err.is_id_mismatch());
+    }
+
+    #[test]
+    fn test_error_conversion() {
+        let err: crate::Error = EntryError::NotCanonical {
+            reason: "parents are not sorted".to_string(),
+        }
+        .into();
+        assert_eq!(err.module(), "entry");
+        assert!(err.is_validation_error());
+        match err {
+            crate::Error::Entry(EntryError::NotCanonical { .. }) => {}
+            _ => panic!("Unexpected error variant"),
+        }
+    }
+}
@@ -1,12 +1,96 @@
 //! Content-addressable identifier type used throughout Eidetica.
 //!
-//! The `ID` type represents a hex-encoded SHA-256 hash string.
+//! The `ID` of an entry is the hex-encoded hash of its serialized content.
+//! The hash algorithm is chosen per tree when it is created and recorded in
+//! the tree's settings (see [`HashAlgorithm`]), so every replica hashes the
+//! tree's entries the same way.
+//!
+//! # Format
+//!
+//! IDs describe their own algorithm with a multihash-style prefix: the
+//! multihash code of the algorithm and the digest length, as two hex-encoded
+//! bytes, followed by the hex-encoded digest.
+//!
+//! - SHA-256 IDs are the bare 64 hex characters of the digest. This is the
+//!   format of all IDs written before the prefix existed, so it is kept as the
+//!   SHA-256 form; the prefixed form `1220…` is also recognized.
+//! - BLAKE3 IDs are `1e20` followed by 64 hex characters.
 
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Length in bytes of the digests of every supported algorithm
+const DIGEST_LEN: u8 = 32;
+
+/// The hash algorithm used to derive entry IDs.
+///
+/// A tree uses a single algorithm for all of its entries. It is set in the
+/// tree's settings under [`HASH_ALGORITHM`](crate::constants::HASH_ALGORITHM)
+/// when the tree is created, and defaults to SHA-256.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    /// SHA-256, the algorithm of all trees created before it was configurable
+    #[default]
+    Sha256,
+    /// BLAKE3 with a 256-bit output
+    Blake3,
+}
+
+impl HashAlgorithm {
+    /// The name of the algorithm, as stored in tree settings.
+    pub fn name(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Blake3 => "blake3",
+        }
+    }
+
+    /// Parses an algorithm from its name, as returned by [`HashAlgorithm::name`].
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "sha256" => Some(HashAlgorithm::Sha256),
+            "blake3" => Some(HashAlgorithm::Blake3),
+            _ => None,
+        }
+    }
+
+    /// The multihash code of the algorithm.
+    pub fn multihash_code(&self) -> u8 {
+        match self {
+            HashAlgorithm::Sha256 => 0x12,
+            HashAlgorithm::Blake3 => 0x1e,
+        }
+    }
+
+    /// Hashes `bytes` into an ID in this algorithm's format.
+    pub fn hash(&self, bytes: &[u8]) -> ID {
+        match self {
+            HashAlgorithm::Sha256 => format!("{:x}", Sha256::digest(bytes)).into(),
+            HashAlgorithm::Blake3 => format!(
+                "{:02x}{DIGEST_LEN:02x}{}",
+                self.multihash_code(),
+                blake3::hash(bytes).to_hex()
+            )
+            .into(),
+        }
+    }
+
+    pub(crate) fn is_default(&self) -> bool {
+        *self == HashAlgorithm::default()
+    }
+}
+
+impl std::fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
 
 /// A content-addressable identifier for an `Entry` or other database object.
 ///
-/// Represents a hex-encoded SHA-256 hash string.
+/// Represents a hex-encoded hash string, prefixed with the algorithm for
+/// algorithms other than SHA-256. See the [module documentation](self) for the format.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
 pub struct ID(String);
 
@@ -25,6 +109,35 @@ impl ID {
     pub fn is_empty(&self) -> bool {
         self.0.is_empty()
     }
+
+    /// Returns the algorithm this ID was hashed with, or `None` if it is not
+    /// in a known hash format.
+    pub fn hash_algorithm(&self) -> Option<HashAlgorithm> {
+        self.split_digest().map(|(algorithm, _)| algorithm)
+    }
+
+    /// Returns the hex-encoded digest of this ID without its prefix, or
+    /// `None` if it is not in a known hash format.
+    pub fn digest(&self) -> Option<&str> {
+        self.split_digest().map(|(_, digest)| digest)
+    }
+
+    fn split_digest(&self) -> Option<(HashAlgorithm, &str)> {
+        let hex_len = usize::from(DIGEST_LEN) * 2;
+        let is_digest = |s: &str| {
+            s.len() == hex_len
+                && s.bytes()
+                    .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+        };
+        if is_digest(&self.0) {
+            return Some((HashAlgorithm::Sha256, &self.0));
+        }
+        let (prefix, digest) = self.0.split_at_checked(4)?;
+        let algorithm = [HashAlgorithm::Sha256, HashAlgorithm::Blake3]
+            .into_iter()
+            .find(|a| prefix == format!("{:02x}{DIGEST_LEN:02x}", a.multihash_code()))?;
+        is_digest(digest).then_some((algorithm, digest))
+    }
 }
 
 impl From<String> for ID {
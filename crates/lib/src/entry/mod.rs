@@ -5,16 +5,26 @@
 //! representing a snapshot of data in the main tree and potentially multiple named subtrees.
 //! This module also defines the `ID` type and `RawData` type.
 
+pub mod app_info;
+pub mod errors;
 pub mod id;
+pub mod proof;
+mod validate;
 
-pub use id::ID;
+pub use app_info::AppInfo;
+pub use errors::EntryError;
+pub use id::{HashAlgorithm, ID};
+pub use proof::{InclusionProof, verify_inclusion};
+pub use validate::{MAX_ENTRY_SIZE, MAX_PARENTS, MAX_SUBTREE_NAME_LEN, MAX_SUBTREES};
 
 use crate::Result;
+use crate::atomicop::GroupedOp;
 use crate::auth::types::SigInfo;
+use crate::clock::Hlc;
 use crate::constants::ROOT;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
-use sha2::{Digest, Sha256};
+use std::collections::HashSet;
 
 /// Represents serialized data, typically JSON, provided by the user.
 ///
@@ -37,6 +47,11 @@ struct TreeNode {
     /// Metadata is optional and may not be present in all entries. Future versions
     /// may extend metadata to include additional information.
     pub metadata: Option<RawData>,
+    /// The algorithm the `Entry`'s ID is hashed with.
+    /// Omitted for SHA-256, so entries of SHA-256 trees serialize as they did
+    /// before the algorithm was configurable.
+    #[serde(default, skip_serializing_if = "HashAlgorithm::is_default")]
+    pub hash: HashAlgorithm,
 }
 
 /// Internal representation of a named subtree node within an `Entry`.
@@ -140,17 +155,19 @@ impl Entry {
     /// The ID is calculated on demand by hashing the serialized JSON representation of the entry.
     /// Because entries are immutable once created and their contents are deterministically
     /// serialized, this ensures that identical entries will always have the same ID.
+    ///
+    /// The hash algorithm is the entry's [`hash_algorithm`](Self::hash_algorithm),
+    /// which is serialized with the entry, so the ID commits to it.
     pub fn id(&self) -> ID {
         // Entry itself derives Serialize and contains tree and subtrees.
         // These are kept sorted and finalized by the EntryBuilder before Entry creation.
         let json = serde_json::to_string(self).expect("Failed to serialize entry for hashing");
+        self.tree.hash.hash(json.as_bytes())
+    }
 
-        let mut hasher = Sha256::new();
-        hasher.update(json.as_bytes());
-        // convert the hash to a string
-        let hash = hasher.finalize();
-        // convert the hash to a hex string
-        format!("{hash:x}").into()
+    /// Get the algorithm this entry's ID is hashed with.
+    pub fn hash_algorithm(&self) -> HashAlgorithm {
+        self.tree.hash
     }
 
     /// Get the ID of the root `Entry` of the tree this entry belongs to.
@@ -200,6 +217,77 @@ impl Entry {
         self.tree.metadata.as_ref()
     }
 
+    /// Get the hybrid logical clock timestamp recorded when this entry was committed.
+    ///
+    /// Returns `None` for entries without a timestamp, such as entries built
+    /// directly with an `EntryBuilder` or written by older versions.
+    pub fn timestamp(&self) -> Option<Hlc> {
+        #[derive(Deserialize)]
+        struct TimestampMetadata {
+            hlc: Option<Hlc>,
+        }
+        let metadata = self.tree.metadata.as_deref()?;
+        serde_json::from_str::<TimestampMetadata>(metadata)
+            .ok()?
+            .hlc
+    }
+
+    /// Get the device and application that wrote this entry, if recorded.
+    ///
+    /// See [`AppInfo`] for how it is set.
+    pub fn app_info(&self) -> Option<AppInfo> {
+        #[derive(Deserialize)]
+        struct AppMetadata {
+            app: Option<AppInfo>,
+        }
+        let metadata = self.tree.metadata.as_deref()?;
+        serde_json::from_str::<AppMetadata>(metadata).ok()?.app
+    }
+
+    /// Get the logical operations coalesced into this entry by a
+    /// [`GroupCommit`](crate::atomicop::GroupCommit).
+    ///
+    /// Entries committed directly return an empty list.
+    pub fn grouped_ops(&self) -> Vec<GroupedOp> {
+        #[derive(Deserialize)]
+        struct GroupedMetadata {
+            #[serde(default)]
+            ops: Vec<GroupedOp>,
+        }
+        self.tree
+            .metadata
+            .as_deref()
+            .and_then(|metadata| serde_json::from_str::<GroupedMetadata>(metadata).ok())
+            .map(|metadata| metadata.ops)
+            .unwrap_or_default()
+    }
+
+    /// Get the subtrees checkpointed by this entry.
+    ///
+    /// The data of a checkpointed subtree is its full state as of this entry,
+    /// written by [`Tree::create_checkpoint`](crate::Tree::create_checkpoint).
+    /// Other entries return an empty list.
+    pub fn checkpoint_subtrees(&self) -> Vec<String> {
+        #[derive(Deserialize)]
+        struct CheckpointMetadata {
+            #[serde(default)]
+            checkpoint: Vec<String>,
+        }
+        self.tree
+            .metadata
+            .as_deref()
+            .and_then(|metadata| serde_json::from_str::<CheckpointMetadata>(metadata).ok())
+            .map(|metadata| metadata.checkpoint)
+            .unwrap_or_default()
+    }
+
+    /// Whether this entry holds a checkpoint of the given subtree's full state.
+    pub fn is_checkpoint_of(&self, subtree_name: impl AsRef<str>) -> bool {
+        self.checkpoint_subtrees()
+            .iter()
+            .any(|name| name == subtree_name.as_ref())
+    }
+
     /// Get the `RawData` for a specific named subtree within this entry.
     pub fn data(&self, subtree_name: impl AsRef<str>) -> Result<&RawData> {
         self.subtrees
@@ -237,6 +325,47 @@ impl Entry {
             })
     }
 
+    /// Moves the ID references out of this entry: the tree root and every parent list.
+    ///
+    /// Parent lists are returned in a fixed order, main tree first followed by each
+    /// subtree in stored order. Used by persistent backends to store IDs compactly;
+    /// `restore_id_refs` puts them back.
+    pub(crate) fn take_id_refs(&mut self) -> (ID, Vec<Vec<ID>>) {
+        let root = std::mem::take(&mut self.tree.root);
+        let mut parents = Vec::with_capacity(self.subtrees.len() + 1);
+        parents.push(std::mem::take(&mut self.tree.parents));
+        for subtree in &mut self.subtrees {
+            parents.push(std::mem::take(&mut subtree.parents));
+        }
+        (root, parents)
+    }
+
+    /// Restores ID references previously removed with `take_id_refs`.
+    ///
+    /// Returns `false`, leaving the entry untouched, if the number of parent lists
+    /// doesn't match this entry's subtrees.
+    pub(crate) fn restore_id_refs(&mut self, root: ID, parents: Vec<Vec<ID>>) -> bool {
+        if parents.len() != self.subtrees.len() + 1 {
+            return false;
+        }
+        let mut parents = parents.into_iter();
+        self.tree.root = root;
+        self.tree.parents = parents.next().unwrap_or_default();
+        for (subtree, list) in self.subtrees.iter_mut().zip(parents) {
+            subtree.parents = list;
+        }
+        true
+    }
+
+    /// The payloads of this entry's subtrees, in stored order.
+    ///
+    /// Used by backends that store payloads apart from their entries. An entry
+    /// whose payloads were moved out has a different ID and signature, so it
+    /// must not be handed out before they are put back.
+    pub(crate) fn payloads_mut(&mut self) -> impl Iterator<Item = &mut RawData> {
+        self.subtrees.iter_mut().map(|subtree| &mut subtree.data)
+    }
+
     /// Create a canonical representation of this entry for signing purposes.
     ///
     /// This creates a copy of the entry with the signature field removed from auth,
@@ -338,14 +467,20 @@ impl EntryBuilder {
     /// # Arguments
     /// * `root` - The `ID` of the root `Entry` of the tree this entry will belong to.
     ///
+    /// The entry is hashed with the algorithm of `root`'s ID, or SHA-256 if
+    /// the root ID is not a hash.
+    ///
     /// Note: It's generally preferred to use the static `Entry::builder()` method
     /// instead of calling this constructor directly.
     pub fn new(root: impl Into<ID>) -> Self {
+        let root = root.into();
+        let hash = root.hash_algorithm().unwrap_or_default();
         Self {
             tree: TreeNode {
-                root: root.into(),
+                root,
                 parents: Vec::new(),
                 metadata: None,
+                hash,
             },
             subtrees: Vec::new(),
             sig: SigInfo::default(),
@@ -502,6 +637,14 @@ impl EntryBuilder {
         self
     }
 
+    /// Like `remove_empty_subtrees`, but keeps the named subtrees even when empty.
+    pub(crate) fn remove_empty_subtrees_except(mut self, keep: &HashSet<String>) -> Self {
+        self.subtrees.retain(|subtree| {
+            keep.contains(&subtree.name) || (!subtree.data.is_empty() && subtree.data != "{}")
+        });
+        self
+    }
+
     /// Mutable reference version of remove_empty_subtrees.
     /// Removes subtrees that do not have any data or have data "{}".
     /// This is useful for cleaning up entries before building.
@@ -536,6 +679,27 @@ impl EntryBuilder {
         self
     }
 
+    /// Set the algorithm the entry's ID will be hashed with.
+    ///
+    /// Entries must use the algorithm of the tree they belong to; see
+    /// [`HASH_ALGORITHM`](crate::constants::HASH_ALGORITHM).
+    ///
+    /// # Returns
+    /// Self for method chaining.
+    pub fn set_hash_algorithm(mut self, algorithm: HashAlgorithm) -> Self {
+        self.tree.hash = algorithm;
+        self
+    }
+
+    /// Mutable reference version of set_hash_algorithm.
+    ///
+    /// # Returns
+    /// A mutable reference to self for method chaining.
+    pub fn set_hash_algorithm_mut(&mut self, algorithm: HashAlgorithm) -> &mut Self {
+        self.tree.hash = algorithm;
+        self
+    }
+
     /// Set the parent IDs for the main tree history.
     /// The provided vector will be sorted alphabetically during the `build()` process.
     pub fn set_parents(mut self, parents: Vec<ID>) -> Self {
@@ -724,6 +888,42 @@ impl EntryBuilder {
         self
     }
 
+    /// Record the device and application writing this entry in its metadata.
+    ///
+    /// The information is stored under the `app` key of the metadata, which is
+    /// kept as a JSON object; metadata that is not a JSON object is replaced.
+    /// An empty `AppInfo` removes the key.
+    ///
+    /// # Returns
+    /// Self for method chaining.
+    pub fn set_app_info(mut self, info: AppInfo) -> Self {
+        self.set_app_info_mut(info);
+        self
+    }
+
+    /// Mutable reference version of set_app_info.
+    ///
+    /// # Returns
+    /// A mutable reference to self for method chaining.
+    pub fn set_app_info_mut(&mut self, info: AppInfo) -> &mut Self {
+        let mut metadata = self
+            .tree
+            .metadata
+            .as_deref()
+            .and_then(|m| {
+                serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(m).ok()
+            })
+            .unwrap_or_default();
+        if info.is_empty() {
+            metadata.remove("app");
+        } else {
+            let info = serde_json::to_value(info).expect("AppInfo serializes to JSON");
+            metadata.insert("app".to_string(), info);
+        }
+        self.tree.metadata = Some(serde_json::Value::Object(metadata).to_string());
+        self
+    }
+
     /// Get the current metadata value for this entry builder.
     ///
     /// Metadata is optional information attached to an entry that is not part of the
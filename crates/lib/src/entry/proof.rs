@@ -0,0 +1,75 @@
+//! Inclusion proofs for entries.
+//!
+//! An entry's ID is the hash of its content, and that content includes the
+//! IDs of its parents. A chain of entries from some entry back to the root of
+//! its tree, each one a parent of the one before, therefore proves that the
+//! entry descends from that root: changing any entry in the chain changes its
+//! ID and breaks the link from the entry after it.
+//!
+//! Proofs are created with [`Tree::prove_inclusion`](crate::Tree::prove_inclusion)
+//! and checked with [`verify_inclusion`], which only needs the proof and the
+//! root ID, not a database. A proof shows ancestry only; it does not check
+//! signatures, which need the tree's settings.
+
+use super::{Entry, ID};
+use serde::{Deserialize, Serialize};
+
+/// A chain of entries linking an entry to the root of its tree.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InclusionProof {
+    /// The entry whose inclusion is proven
+    pub entry: ID,
+    /// The entry itself, then one of its parents, and so on up to the root entry
+    pub chain: Vec<Entry>,
+}
+
+impl InclusionProof {
+    /// Number of parent links between the entry and the root.
+    pub fn depth(&self) -> usize {
+        self.chain.len().saturating_sub(1)
+    }
+}
+
+/// Checks that `proof` links its entry to the tree rooted at `root_id`.
+///
+/// Every entry in the chain is hashed, so the proof holds only if the first
+/// entry is `proof.entry`, each entry lists the next one as a parent, every
+/// entry but the last belongs to the tree, and the last entry is the root
+/// entry with ID `root_id`.
+///
+/// # Example
+/// ```
+/// # use eidetica::backend::database::InMemory;
+/// # use eidetica::basedb::BaseDB;
+/// # use eidetica::entry::verify_inclusion;
+/// # use eidetica::subtree::Dict;
+/// # fn main() -> eidetica::Result<()> {
+/// # let db = BaseDB::new(Box::new(InMemory::new()));
+/// # db.add_private_key("key")?;
+/// # let tree = db.new_tree_default("key")?;
+/// let op = tree.new_operation()?;
+/// op.get_subtree::<Dict>("data")?.set("key", "value")?;
+/// let id = op.commit()?;
+///
+/// let proof = tree.prove_inclusion(&id)?;
+/// assert!(verify_inclusion(&proof, tree.root_id()));
+/// # Ok(())
+/// # }
+/// ```
+pub fn verify_inclusion(proof: &InclusionProof, root_id: &ID) -> bool {
+    let ids: Vec<ID> = proof.chain.iter().map(Entry::id).collect();
+    let (Some(first), Some(last)) = (ids.first(), proof.chain.last()) else {
+        return false;
+    };
+    if *first != proof.entry || !last.is_root() || ids[ids.len() - 1] != *root_id {
+        return false;
+    }
+
+    proof.chain.windows(2).zip(&ids[1..]).all(|(pair, parent)| {
+        let child = &pair[0];
+        child.root() == *root_id
+            && child
+                .parents()
+                .is_ok_and(|parents| parents.contains(parent))
+    })
+}
@@ -0,0 +1,143 @@
+//! Structural validation of entries
+//!
+//! Entries built locally are well-formed by construction, but entries read
+//! back from storage or received from a peer are only as trustworthy as their
+//! source. [`Entry::validate_structure`] checks what can be checked without
+//! any other entry: the entry's size and parent and subtree counts are within
+//! limits, its subtree names follow the naming rules, and it is in the
+//! canonical form `EntryBuilder` produces, with sorted and unique parents and
+//! subtrees. [`Entry::validate_as`] also checks that the entry hashes to the ID
+//! it claims.
+
+use super::errors::EntryError;
+use super::{Entry, ID};
+use crate::Result;
+
+/// Maximum size of an entry, in bytes of its serialized form.
+///
+/// Half the largest sync message, so an entry always fits in one.
+pub const MAX_ENTRY_SIZE: usize = 32 * 1024 * 1024;
+
+/// Maximum number of parents of an entry, in the main tree or any one subtree.
+pub const MAX_PARENTS: usize = 1024;
+
+/// Maximum number of subtrees an entry can hold data for.
+pub const MAX_SUBTREES: usize = 1024;
+
+/// Maximum length of a subtree name, in bytes.
+pub const MAX_SUBTREE_NAME_LEN: usize = 255;
+
+impl Entry {
+    /// Check that this entry is well-formed and within the structural limits.
+    ///
+    /// This is run on every entry loaded from a database file and on every
+    /// entry received during a sync, before it is stored. It fails with an
+    /// [`EntryError`] if:
+    /// - the serialized entry is larger than [`MAX_ENTRY_SIZE`]
+    /// - the main tree or a subtree lists more than [`MAX_PARENTS`] parents
+    /// - the entry has more than [`MAX_SUBTREES`] subtrees
+    /// - a subtree name is empty, longer than [`MAX_SUBTREE_NAME_LEN`] bytes,
+    ///   or contains control characters
+    /// - parents or subtrees are out of order or duplicated, which
+    ///   `EntryBuilder::build` never produces
+    pub fn validate_structure(&self) -> Result<()> {
+        self.check_structure().map(|_| ())
+    }
+
+    /// Check the entry like [`validate_structure`](Self::validate_structure),
+    /// and that its content hashes to `id`.
+    pub fn validate_as(&self, id: &ID) -> Result<()> {
+        let json = self.check_structure()?;
+        let actual = self.tree.hash.hash(json.as_bytes());
+        if actual != *id {
+            return Err(EntryError::IdMismatch {
+                expected: id.clone(),
+                actual,
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Runs the structural checks and returns the serialized entry.
+    fn check_structure(&self) -> Result<String> {
+        if self.subtrees.len() > MAX_SUBTREES {
+            return Err(EntryError::TooManySubtrees {
+                count: self.subtrees.len(),
+                limit: MAX_SUBTREES,
+            }
+            .into());
+        }
+        check_parents(None, &self.tree.parents)?;
+        for subtree in &self.subtrees {
+            check_subtree_name(&subtree.name)?;
+            check_parents(Some(&subtree.name), &subtree.parents)?;
+        }
+        if let Some(pair) = self
+            .subtrees
+            .windows(2)
+            .find(|pair| pair[0].name >= pair[1].name)
+        {
+            let reason = if pair[0].name == pair[1].name {
+                format!("subtree '{}' is listed twice", pair[0].name)
+            } else {
+                "subtrees are not sorted by name".to_string()
+            };
+            return Err(EntryError::NotCanonical { reason }.into());
+        }
+
+        let json = serde_json::to_string(self)?;
+        if json.len() > MAX_ENTRY_SIZE {
+            return Err(EntryError::TooLarge {
+                size: json.len(),
+                limit: MAX_ENTRY_SIZE,
+            }
+            .into());
+        }
+        Ok(json)
+    }
+}
+
+fn check_parents(subtree: Option<&str>, parents: &[ID]) -> Result<()> {
+    if parents.len() > MAX_PARENTS {
+        return Err(EntryError::TooManyParents {
+            subtree: subtree.map(str::to_string),
+            count: parents.len(),
+            limit: MAX_PARENTS,
+        }
+        .into());
+    }
+    if let Some(pair) = parents.windows(2).find(|pair| pair[0] >= pair[1]) {
+        let list = match subtree {
+            Some(name) => format!("parents of subtree '{name}'"),
+            None => "parents".to_string(),
+        };
+        let reason = if pair[0] == pair[1] {
+            format!("{list} list '{}' twice", pair[0])
+        } else {
+            format!("{list} are not sorted")
+        };
+        return Err(EntryError::NotCanonical { reason }.into());
+    }
+    Ok(())
+}
+
+fn check_subtree_name(name: &str) -> Result<()> {
+    let reason = if name.is_empty() {
+        "name is empty".to_string()
+    } else if name.len() > MAX_SUBTREE_NAME_LEN {
+        format!(
+            "name is {} bytes, more than the limit of {MAX_SUBTREE_NAME_LEN}",
+            name.len()
+        )
+    } else if name.chars().any(char::is_control) {
+        "name contains control characters".to_string()
+    } else {
+        return Ok(());
+    };
+    Err(EntryError::InvalidSubtreeName {
+        name: name.to_string(),
+        reason,
+    }
+    .into())
+}
@@ -0,0 +1,162 @@
+//! Timing of database operations
+//!
+//! Eidetica measures how long its expensive operations take: CRDT merges when a
+//! subtree's state is computed, and commits. Wrapping a backend in
+//! [`Instrumented`](crate::backend::database::Instrumented) also measures its
+//! puts, gets and tip calculations. Each measurement is passed to the hooks
+//! registered with [`add_hook`], and with the `metrics` feature it is also
+//! reported through the [`metrics`](https://docs.rs/metrics) facade, to whichever
+//! recorder the application has installed:
+//!
+//! - `eidetica_operation_seconds`: histogram of durations, labelled by `operation`
+//! - `eidetica_operations_total`: counter of operations, labelled by `operation`
+//!
+//! Hooks are process-wide, like the `metrics` recorder, and see measurements
+//! from every database. When no hook is registered and the feature is off,
+//! nothing is timed.
+//!
+//! ```
+//! use eidetica::instrument::{self, Operation};
+//! use std::sync::atomic::{AtomicU64, Ordering};
+//! use std::sync::Arc;
+//!
+//! let commit_micros = Arc::new(AtomicU64::new(0));
+//! let total = Arc::clone(&commit_micros);
+//! let hook = instrument::add_hook(move |measurement| {
+//!     if measurement.operation == Operation::Commit {
+//!         total.fetch_add(measurement.elapsed.as_micros() as u64, Ordering::Relaxed);
+//!     }
+//! });
+//! // ... use the database ...
+//! instrument::remove_hook(hook);
+//! ```
+
+use crate::entry::ID;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use web_time::Instant;
+
+/// An operation whose duration is measured.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operation {
+    /// Storing an entry in a backend
+    Put,
+    /// Reading an entry from a backend
+    Get,
+    /// Calculating the tips of a tree or subtree in a backend
+    Tips,
+    /// Merging the history of a subtree into its CRDT state
+    Merge,
+    /// Committing an operation, from settings validation to storing the entry
+    Commit,
+}
+
+impl Operation {
+    /// The name of the operation, as used in metric labels.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Operation::Put => "put",
+            Operation::Get => "get",
+            Operation::Tips => "tips",
+            Operation::Merge => "merge",
+            Operation::Commit => "commit",
+        }
+    }
+}
+
+/// How long one operation took.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Measurement {
+    /// What was measured
+    pub operation: Operation,
+    /// The root of the tree the operation was on, if it was on one
+    pub tree: Option<ID>,
+    /// The subtree the operation was on, if it was on one
+    pub subtree: Option<String>,
+    /// How long the operation took, whether or not it succeeded
+    pub elapsed: Duration,
+}
+
+/// Identifies a hook registered with [`add_hook`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HookId(u64);
+
+type Hook = Arc<dyn Fn(&Measurement) + Send + Sync>;
+
+static HOOKS: RwLock<Vec<(HookId, Hook)>> = RwLock::new(Vec::new());
+/// Number of registered hooks, checked before anything is timed
+static HOOK_COUNT: AtomicUsize = AtomicUsize::new(0);
+static NEXT_HOOK: AtomicU64 = AtomicU64::new(0);
+
+/// Registers a hook called with every measurement, from any thread.
+///
+/// Hooks run on the thread that performed the operation, after it finished, so
+/// they should be quick.
+pub fn add_hook(hook: impl Fn(&Measurement) + Send + Sync + 'static) -> HookId {
+    let id = HookId(NEXT_HOOK.fetch_add(1, Ordering::Relaxed));
+    let mut hooks = HOOKS.write().unwrap();
+    hooks.push((id, Arc::new(hook)));
+    HOOK_COUNT.store(hooks.len(), Ordering::Release);
+    id
+}
+
+/// Unregisters a hook.
+///
+/// # Returns
+/// `true` if the hook was registered.
+pub fn remove_hook(id: HookId) -> bool {
+    let mut hooks = HOOKS.write().unwrap();
+    let before = hooks.len();
+    hooks.retain(|(hook, _)| *hook != id);
+    HOOK_COUNT.store(hooks.len(), Ordering::Release);
+    hooks.len() != before
+}
+
+/// Whether measurements are used by anything.
+fn enabled() -> bool {
+    cfg!(feature = "metrics") || HOOK_COUNT.load(Ordering::Acquire) > 0
+}
+
+/// Runs `f`, measuring how long it takes if measurements are used.
+pub(crate) fn timed<R>(
+    operation: Operation,
+    tree: Option<&ID>,
+    subtree: Option<&str>,
+    f: impl FnOnce() -> R,
+) -> R {
+    if !enabled() {
+        return f();
+    }
+    let start = Instant::now();
+    let result = f();
+    record(Measurement {
+        operation,
+        tree: tree.cloned(),
+        subtree: subtree.map(str::to_string),
+        elapsed: start.elapsed(),
+    });
+    result
+}
+
+fn record(measurement: Measurement) {
+    #[cfg(feature = "metrics")]
+    {
+        let name = measurement.operation.name();
+        metrics::histogram!("eidetica_operation_seconds", "operation" => name)
+            .record(measurement.elapsed.as_secs_f64());
+        metrics::counter!("eidetica_operations_total", "operation" => name).increment(1);
+    }
+
+    // Call the hooks without holding the lock, so they may add or remove hooks
+    let hooks: Vec<Hook> = HOOKS
+        .read()
+        .unwrap()
+        .iter()
+        .map(|(_, hook)| Arc::clone(hook))
+        .collect();
+    for hook in hooks {
+        hook(&measurement);
+    }
+}
@@ -14,21 +14,39 @@
 //! * **SubTrees (`subtree::SubTree`)**: Named data structures within a tree that provide specialized data access patterns:
 //!     * **Dict (`subtree::Dict`)**: A key-value store within a tree.
 //!     * **Table (`subtree::Table`)**: A record-oriented store with automatic primary key generation, similar to a database table.
+//!     * **FileTree (`subtree::FileTree`)**: A hierarchy of directories and files whose renames, moves and deletions merge without duplicating or losing nodes.
+//!     * **TaskList (`subtree::TaskList`)**: An ordered checklist whose items merge individually, with enable-wins checked state.
+//!     * **BlobStore (`subtree::BlobStore`)**: Content-addressed, chunked storage for binary data such as files or images.
 //!     * **YDoc (`subtree::YDoc`)**: A Y-CRDT based store for collaborative data structures (requires the "y-crdt" feature).
+//! * **Clocks (`clock::Hlc`)**: Hybrid logical clock timestamps recorded on every committed entry, ordering concurrent edits consistently across devices.
+//! * **Queries (`query::Query`)**: Filters over the rows of a Dict or Table, prepared once with `Tree::prepare` and executed repeatedly with parameters.
+//! * **Sync (`sync::SyncPeer`)**: Exchanges the entries of a tree with another Eidetica instance over any `Read + Write` transport.
 //! * **Merkle-CRDT**: The underlying principle combining Merkle DAGs (formed by entries and parent links) with CRDTs for efficient, decentralized data synchronization.
 
 pub mod atomicop;
 pub mod auth;
 pub mod backend;
 pub mod basedb;
+pub mod clock;
 pub mod constants;
 pub mod crdt;
 pub mod entry;
+pub mod instrument;
+pub mod query;
 pub mod subtree;
+pub mod sync;
 pub mod tree;
 
-/// Re-export the `Tree` struct for easier access.
-pub use tree::Tree;
+/// Re-export the `Tree` and `ReadOnlyTree` structs for easier access.
+pub use tree::{ReadOnlyTree, Tree};
+
+// These backends and transports need an operating system underneath. In the
+// browser, use the `indexeddb` feature for storage instead.
+#[cfg(all(
+    target_arch = "wasm32",
+    any(feature = "sqlite", feature = "archive", feature = "libp2p")
+))]
+compile_error!("the sqlite, archive and libp2p features are not supported on wasm32");
 
 /// Y-CRDT types re-exported for convenience when the "y-crdt" feature is enabled.
 ///
@@ -63,6 +81,10 @@ pub enum Error {
     #[error(transparent)]
     Base(basedb::BaseError),
 
+    /// Structured entry validation errors from the entry module
+    #[error(transparent)]
+    Entry(entry::EntryError),
+
     /// Structured CRDT errors from the crdt module
     #[error(transparent)]
     CRDT(crdt::CRDTError),
@@ -74,6 +96,14 @@ pub enum Error {
     /// Structured atomic operation errors from the atomicop module
     #[error(transparent)]
     AtomicOp(atomicop::AtomicOpError),
+
+    /// Structured sync errors from the sync module
+    #[error(transparent)]
+    Sync(sync::SyncError),
+
+    /// Structured query errors from the query module
+    #[error(transparent)]
+    Query(query::QueryError),
 }
 
 impl Error {
@@ -83,9 +113,12 @@ impl Error {
             Error::Auth(_) => "auth",
             Error::Backend(_) => "backend",
             Error::Base(_) => "basedb",
+            Error::Entry(_) => "entry",
             Error::CRDT(_) => "crdt",
             Error::Subtree(_) => "subtree",
             Error::AtomicOp(_) => "atomicop",
+            Error::Sync(_) => "sync",
+            Error::Query(_) => "query",
             Error::Io(_) => "io",
             Error::Serialize(_) => "serialize",
         }
@@ -115,6 +148,7 @@ impl Error {
     /// Check if this error indicates a conflict (already exists).
     pub fn is_conflict(&self) -> bool {
         match self {
+            Error::Auth(auth_err) => auth_err.is_already_exists(),
             Error::Base(base_err) => base_err.is_already_exists(),
             _ => false,
         }
@@ -139,6 +173,7 @@ impl Error {
     pub fn is_integrity_error(&self) -> bool {
         match self {
             Error::Backend(backend_err) => backend_err.is_integrity_error(),
+            Error::Entry(entry_err) => entry_err.is_id_mismatch(),
             _ => false,
         }
     }
@@ -148,6 +183,7 @@ impl Error {
         match self {
             Error::Io(_) => true,
             Error::Backend(backend_err) => backend_err.is_io_error(),
+            Error::Sync(sync_err) => sync_err.is_connection_error(),
             _ => false,
         }
     }
@@ -163,6 +199,7 @@ impl Error {
             Error::Base(base_err) => base_err.is_validation_error(),
             Error::Backend(backend_err) => backend_err.is_logical_error(),
             Error::AtomicOp(atomicop_err) => atomicop_err.is_validation_error(),
+            Error::Entry(_) => true,
             _ => false,
         }
     }
@@ -247,6 +284,7 @@ impl Error {
     pub fn is_entry_error(&self) -> bool {
         match self {
             Error::AtomicOp(atomicop_err) => atomicop_err.is_entry_error(),
+            Error::Entry(_) => true,
             _ => false,
         }
     }
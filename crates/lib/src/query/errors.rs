@@ -0,0 +1,129 @@
+//! Error types for queries.
+//!
+//! This module defines structured error types for preparing and executing
+//! queries against a tree's subtrees.
+
+use thiserror::Error;
+
+/// Errors that can occur while preparing or executing a query.
+///
+/// # Stability
+///
+/// - New variants may be added in minor versions (enum is `#[non_exhaustive]`)
+/// - Existing variants will not be removed in minor versions
+/// - Field additions/changes require a major version bump
+/// - Helper methods like `is_*()` provide stable APIs
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum QueryError {
+    /// A field path in the query could not be parsed.
+    #[error("Invalid field '{field}' in query: {reason}")]
+    InvalidField {
+        /// The field path as written in the query
+        field: String,
+        /// Why the path is invalid
+        reason: String,
+    },
+
+    /// The query was executed without a value for one of its parameters.
+    #[error("Missing value for query parameter '{name}'")]
+    MissingParameter {
+        /// The name of the parameter
+        name: String,
+    },
+
+    /// A parameter or literal has a type the query cannot use.
+    #[error("Query operand for {target} must be {expected}")]
+    InvalidOperand {
+        /// What the operand is compared to
+        target: String,
+        /// The type of value that is required
+        expected: String,
+    },
+
+    /// The query was run on a handle for a different subtree than it reads.
+    #[error("Query reads subtree '{query}' but was run on subtree '{subtree}'")]
+    SubtreeMismatch {
+        /// The subtree named by the query
+        query: String,
+        /// The subtree of the handle it was run on
+        subtree: String,
+    },
+}
+
+impl QueryError {
+    /// Check if this error is about the query itself rather than its parameters.
+    pub fn is_invalid_query(&self) -> bool {
+        matches!(
+            self,
+            QueryError::InvalidField { .. } | QueryError::SubtreeMismatch { .. }
+        )
+    }
+
+    /// Check if this error is about the values passed when executing the query.
+    pub fn is_parameter_error(&self) -> bool {
+        matches!(
+            self,
+            QueryError::MissingParameter { .. } | QueryError::InvalidOperand { .. }
+        )
+    }
+
+    /// Get the field path if this error is about a field.
+    pub fn field(&self) -> Option<&str> {
+        match self {
+            QueryError::InvalidField { field, .. } => Some(field),
+            _ => None,
+        }
+    }
+
+    /// Get the parameter name if this error is about a missing parameter.
+    pub fn parameter(&self) -> Option<&str> {
+        match self {
+            QueryError::MissingParameter { name } => Some(name),
+            _ => None,
+        }
+    }
+}
+
+// Conversion from QueryError to the main Error type
+impl From<QueryError> for crate::Error {
+    fn from(err: QueryError) -> Self {
+        crate::Error::Query(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_helpers() {
+        let err = QueryError::InvalidField {
+            field: "user..name".to_string(),
+            reason: "empty segment".to_string(),
+        };
+        assert!(err.is_invalid_query());
+        assert!(!err.is_parameter_error());
+        assert_eq!(err.field(), Some("user..name"));
+
+        let err = QueryError::MissingParameter {
+            name: "min_age".to_string(),
+        };
+        assert!(err.is_parameter_error());
+        assert_eq!(err.parameter(), Some("min_age"));
+        assert_eq!(err.field(), None);
+    }
+
+    #[test]
+    fn test_error_conversion() {
+        let err: crate::Error = QueryError::MissingParameter {
+            name: "id".to_string(),
+        }
+        .into();
+        assert_eq!(err.module(), "query");
+        match err {
+            crate::Error::Query(QueryError::MissingParameter { name }) => assert_eq!(name, "id"),
+            _ => panic!("Unexpected error variant"),
+        }
+    }
+}
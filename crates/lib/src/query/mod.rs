@@ -0,0 +1,280 @@
+//! Queries over the rows of a subtree
+//!
+//! A [`Query`] selects rows of a `Dict` or `Table` subtree: the top-level keys
+//! of the subtree and the values stored under them. Conditions compare a row's
+//! key, or a field of its value, to an operand. Operands are either literal
+//! values or named parameters that are supplied when the query runs.
+//!
+//! Queries are prepared once with [`Tree::prepare`](crate::Tree::prepare),
+//! which parses field paths and chooses how to find the rows, then executed
+//! any number of times with different [`Params`]:
+//!
+//! ```
+//! # use eidetica::backend::database::InMemory;
+//! # use eidetica::basedb::BaseDB;
+//! # use eidetica::subtree::Dict;
+//! use eidetica::query::{Params, Query, gt, param};
+//! # fn main() -> eidetica::Result<()> {
+//! # let db = BaseDB::new(Box::new(InMemory::new()));
+//! # db.add_private_key("key")?;
+//! # let tree = db.new_tree_default("key")?;
+//! let op = tree.new_operation()?;
+//! let users = op.get_subtree::<Dict>("users")?;
+//! users.set_path("alice.age", 34)?;
+//! users.set_path("bob.age", 27)?;
+//! op.commit()?;
+//!
+//! let older_than = tree.prepare(&Query::subtree("users").where_field("age", gt(param("age"))))?;
+//! let rows = older_than.execute(&Params::new().set("age", 30))?;
+//! assert_eq!(rows.len(), 1);
+//! assert_eq!(rows[0].key, "alice");
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Queries can also run directly on a `Dict` or `Table` handle with
+//! [`Dict::query`](crate::subtree::Dict::query) and
+//! [`Table::query`](crate::subtree::Table::query). These see the changes staged
+//! in the handle's operation, and `Table` rows are matched after they have been
+//! upgraded by the table's schema and deserialized:
+//!
+//! ```
+//! # use eidetica::backend::database::InMemory;
+//! # use eidetica::basedb::BaseDB;
+//! # use eidetica::subtree::Table;
+//! # use serde::{Deserialize, Serialize};
+//! use eidetica::query::{Query, gt};
+//! #[derive(Serialize, Deserialize, Clone)]
+//! struct User { name: String, age: i64 }
+//! # fn main() -> eidetica::Result<()> {
+//! # let db = BaseDB::new(Box::new(InMemory::new()));
+//! # db.add_private_key("key")?;
+//! # let tree = db.new_tree_default("key")?;
+//! let op = tree.new_operation()?;
+//! let users = op.get_subtree::<Table<User>>("users")?;
+//! users.insert(User { name: "Alice".into(), age: 34 })?;
+//! users.insert(User { name: "Bob".into(), age: 27 })?;
+//!
+//! let rows = users.query(&Query::subtree("users").where_field("age", gt(30)).limit(10))?;
+//! assert_eq!(rows.len(), 1);
+//! assert_eq!(rows[0].1.name, "Alice");
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Values are compared within their type: integers with integers, text with
+//! text and booleans with booleans. A condition on a field that is missing or
+//! holds another type does not match. `Table` rows are matched on the fields
+//! as they are stored; schema migrations are not applied.
+
+mod errors;
+pub(crate) mod plan;
+mod prepared;
+
+pub use errors::QueryError;
+pub use prepared::{PreparedQuery, Row};
+
+use crate::crdt::map::Value;
+use std::collections::HashMap;
+
+/// How a condition compares a row to its operand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    /// Equal to the operand
+    Eq,
+    /// Not equal to the operand
+    Ne,
+    /// Less than the operand
+    Lt,
+    /// Less than or equal to the operand
+    Le,
+    /// Greater than the operand
+    Gt,
+    /// Greater than or equal to the operand
+    Ge,
+}
+
+/// The value a condition compares to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    /// A value fixed in the query
+    Value(Value),
+    /// A value supplied by name when the query runs
+    Param(String),
+}
+
+impl From<Value> for Operand {
+    fn from(value: Value) -> Self {
+        Operand::Value(value)
+    }
+}
+
+macro_rules! operand_from {
+    ($($ty:ty),*) => {
+        $(impl From<$ty> for Operand {
+            fn from(value: $ty) -> Self {
+                Operand::Value(value.into())
+            }
+        })*
+    };
+}
+
+operand_from!(bool, i64, u64, i32, u32, String, &str);
+
+/// Refers to a parameter supplied when the query runs.
+pub fn param(name: impl Into<String>) -> Operand {
+    Operand::Param(name.into())
+}
+
+/// A comparison and the operand it compares to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Condition {
+    /// How the row is compared
+    pub comparison: Comparison,
+    /// What the row is compared to
+    pub operand: Operand,
+}
+
+macro_rules! condition_fn {
+    ($($name:ident => $comparison:ident: $doc:literal),*) => {
+        $(#[doc = $doc]
+        pub fn $name(operand: impl Into<Operand>) -> Condition {
+            Condition {
+                comparison: Comparison::$comparison,
+                operand: operand.into(),
+            }
+        })*
+    };
+}
+
+condition_fn!(
+    eq => Eq: "Matches values equal to `operand`.",
+    ne => Ne: "Matches values of the same type as `operand` that differ from it.",
+    lt => Lt: "Matches values less than `operand`.",
+    le => Le: "Matches values less than or equal to `operand`.",
+    gt => Gt: "Matches values greater than `operand`.",
+    ge => Ge: "Matches values greater than or equal to `operand`."
+);
+
+/// What a condition applies to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+    /// The row's key
+    Key,
+    /// A field of the row's value, as a path such as `address.city`
+    Field(String),
+}
+
+/// A condition on one part of a row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter {
+    /// The part of the row that is compared
+    pub target: Target,
+    /// The comparison to make
+    pub condition: Condition,
+}
+
+/// A selection of rows from one subtree.
+///
+/// Rows must match every condition. Matching rows are returned in key order,
+/// up to the limit if one is set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    subtree: String,
+    filters: Vec<Filter>,
+    limit: Option<usize>,
+}
+
+impl Query {
+    /// Selects every row of the subtree `name`.
+    pub fn subtree(name: impl Into<String>) -> Self {
+        Self {
+            subtree: name.into(),
+            filters: Vec::new(),
+            limit: None,
+        }
+    }
+
+    /// Keeps rows whose field at `path` matches `condition`.
+    ///
+    /// Paths use the syntax of [`crate::crdt::map::path`].
+    pub fn where_field(mut self, path: impl Into<String>, condition: Condition) -> Self {
+        self.filters.push(Filter {
+            target: Target::Field(path.into()),
+            condition,
+        });
+        self
+    }
+
+    /// Keeps rows whose key matches `condition`.
+    ///
+    /// An equality condition on the key looks the row up directly instead of
+    /// scanning the subtree.
+    pub fn where_key(mut self, condition: Condition) -> Self {
+        self.filters.push(Filter {
+            target: Target::Key,
+            condition,
+        });
+        self
+    }
+
+    /// Returns at most `limit` rows.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// The name of the subtree the query reads.
+    pub fn subtree_name(&self) -> &str {
+        &self.subtree
+    }
+
+    /// The conditions rows must match.
+    pub fn filters(&self) -> &[Filter] {
+        &self.filters
+    }
+
+    /// The maximum number of rows returned, if any.
+    pub fn max_rows(&self) -> Option<usize> {
+        self.limit
+    }
+
+    /// Checks that the query reads the subtree `name`.
+    ///
+    /// # Errors
+    /// Returns `QueryError::SubtreeMismatch` if it reads another subtree.
+    pub(crate) fn check_subtree(&self, name: &str) -> crate::Result<()> {
+        if self.subtree != name {
+            return Err(QueryError::SubtreeMismatch {
+                query: self.subtree.clone(),
+                subtree: name.to_string(),
+            }
+            .into());
+        }
+        Ok(())
+    }
+}
+
+/// Values for the parameters of a query, by name.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Params {
+    values: HashMap<String, Value>,
+}
+
+impl Params {
+    /// Creates an empty set of parameters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the value of parameter `name`.
+    pub fn set(mut self, name: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.values.insert(name.into(), value.into());
+        self
+    }
+
+    /// The value of parameter `name`, if set.
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.values.get(name)
+    }
+}
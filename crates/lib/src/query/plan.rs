@@ -0,0 +1,316 @@
+//! Compiled query filters
+//!
+//! A [`Plan`] is a query with its field paths parsed and the way rows are
+//! found chosen. It is shared by prepared queries, which run against a tree's
+//! committed state, and by queries run directly on `Dict` and `Table` handles.
+
+use super::{Comparison, Operand, Params, Query, QueryError, Row, Target};
+use crate::Result;
+use crate::crdt::map::{Map, Value, path};
+use crate::subtree::SCHEMA_MARKER;
+use std::cmp::Ordering;
+
+/// Field of a `Table` row written with a schema that holds its schema version.
+const VERSION_FIELD: &str = "_v";
+/// Field of a `Table` row written with a schema that holds the record itself.
+const ROW_FIELD: &str = "row";
+
+/// How the rows of a subtree are stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Layout {
+    /// Not known yet, because the subtree had no rows
+    Unknown,
+    /// Values are maps, as written through `Dict`
+    Map,
+    /// Values are JSON documents, as written by `Table`
+    Json,
+}
+
+impl Layout {
+    /// Detects the layout of a subtree's rows from its first row.
+    pub(crate) fn detect(state: &Map) -> Self {
+        match state.iter().find(|(key, _)| *key != SCHEMA_MARKER) {
+            Some((_, Value::Text(_))) => Layout::Json,
+            Some(_) => Layout::Map,
+            None => Layout::Unknown,
+        }
+    }
+}
+
+/// How the rows to filter are found.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Access {
+    /// Look up the one row whose key equals the operand
+    Key(Operand),
+    /// Visit every row in key order
+    Scan,
+}
+
+/// A filter with its field path parsed.
+#[derive(Debug)]
+struct CompiledFilter {
+    /// `None` for the row key, otherwise the segments of the field path
+    field: Option<Vec<String>>,
+    comparison: Comparison,
+    operand: Operand,
+}
+
+/// A query compiled for execution.
+#[derive(Debug)]
+pub(crate) struct Plan {
+    access: Access,
+    filters: Vec<CompiledFilter>,
+    parameters: Vec<String>,
+}
+
+impl Plan {
+    /// Parses the query's field paths and chooses how rows are found.
+    ///
+    /// # Errors
+    /// Returns `QueryError::InvalidField` if a field path cannot be parsed, and
+    /// `QueryError::InvalidOperand` if the row key is compared to a literal that
+    /// is not text.
+    pub(crate) fn new(query: &Query) -> Result<Self> {
+        let mut filters = Vec::new();
+        let mut parameters: Vec<String> = Vec::new();
+        let mut access = Access::Scan;
+        for filter in query.filters() {
+            let condition = &filter.condition;
+            let field = match &filter.target {
+                Target::Key => {
+                    if let Operand::Value(value) = &condition.operand
+                        && value.as_text().is_none()
+                    {
+                        return Err(key_operand_error().into());
+                    }
+                    if condition.comparison == Comparison::Eq && access == Access::Scan {
+                        access = Access::Key(condition.operand.clone());
+                    }
+                    None
+                }
+                Target::Field(field) => {
+                    Some(path::parse(field).map_err(|e| QueryError::InvalidField {
+                        field: field.clone(),
+                        reason: e.to_string(),
+                    })?)
+                }
+            };
+            if let Operand::Param(name) = &condition.operand
+                && !parameters.contains(name)
+            {
+                parameters.push(name.clone());
+            }
+            filters.push(CompiledFilter {
+                field,
+                comparison: condition.comparison,
+                operand: condition.operand.clone(),
+            });
+        }
+
+        Ok(Self {
+            access,
+            filters,
+            parameters,
+        })
+    }
+
+    /// Names of the parameters the query needs, in order of first use.
+    pub(crate) fn parameters(&self) -> &[String] {
+        &self.parameters
+    }
+
+    /// Whether rows are found by looking up their key.
+    pub(crate) fn is_key_lookup(&self) -> bool {
+        matches!(self.access, Access::Key(_))
+    }
+
+    /// Resolves the operand of every filter, in filter order.
+    ///
+    /// # Errors
+    /// Returns `QueryError::MissingParameter` if a parameter has no value.
+    pub(crate) fn operands<'a>(&'a self, params: &'a Params) -> Result<Vec<&'a Value>> {
+        self.filters
+            .iter()
+            .map(|filter| resolve(&filter.operand, params))
+            .collect()
+    }
+
+    /// The key to look up, if rows are found by key.
+    ///
+    /// # Errors
+    /// Returns `QueryError::InvalidOperand` if the key is not text.
+    pub(crate) fn lookup_key<'a>(&'a self, params: &'a Params) -> Result<Option<&'a str>> {
+        match &self.access {
+            Access::Key(operand) => resolve(operand, params)?
+                .as_text()
+                .map(Some)
+                .ok_or_else(|| key_operand_error().into()),
+            Access::Scan => Ok(None),
+        }
+    }
+
+    /// Whether a row matches every filter.
+    ///
+    /// `field` returns the value of the row at a field path, or `None` if the
+    /// row has no such field.
+    pub(crate) fn matches(
+        &self,
+        key: &str,
+        operands: &[&Value],
+        mut field: impl FnMut(&[String]) -> Option<Value>,
+    ) -> bool {
+        self.filters.iter().zip(operands).all(|(filter, operand)| {
+            let actual = match &filter.field {
+                None => Some(Value::Text(key.to_string())),
+                Some(segments) => field(segments),
+            };
+            actual.is_some_and(|actual| compare(filter.comparison, &actual, operand))
+        })
+    }
+}
+
+/// Selects the rows of `state` matching `plan`, in key order.
+pub(crate) fn select(
+    plan: &Plan,
+    state: &Map,
+    layout: Layout,
+    params: &Params,
+    limit: Option<usize>,
+) -> Result<Vec<Row>> {
+    let operands = plan.operands(params)?;
+    let candidates: Vec<(&str, &Value)> = match plan.lookup_key(params)? {
+        Some(key) => state
+            .get(key)
+            .map(|value| (key, value))
+            .into_iter()
+            .collect(),
+        None => {
+            let mut rows: Vec<_> = state.iter().map(|(k, v)| (k.as_str(), v)).collect();
+            rows.sort_unstable_by_key(|(key, _)| *key);
+            rows
+        }
+    };
+
+    let limit = limit.unwrap_or(usize::MAX);
+    let mut rows = Vec::new();
+    for (key, value) in candidates {
+        if rows.len() >= limit {
+            break;
+        }
+        // Table rows are text; anything else is the schema marker
+        if layout != Layout::Map && value.as_text().is_none() {
+            continue;
+        }
+        // Table rows are parsed once, and only if a field is compared
+        let mut document = None;
+        let matched = plan.matches(key, &operands, |segments| {
+            if layout == Layout::Json {
+                let document = document.get_or_insert_with(|| {
+                    value
+                        .as_text()
+                        .and_then(|text| serde_json::from_str(text).ok())
+                        .map(untag)
+                });
+                document
+                    .as_ref()
+                    .and_then(|document| json_field(document, segments))
+            } else {
+                map_field(value, segments).cloned()
+            }
+        });
+        if matched {
+            rows.push(Row {
+                key: key.to_string(),
+                value: value.clone(),
+            });
+        }
+    }
+    Ok(rows)
+}
+
+fn resolve<'a>(operand: &'a Operand, params: &'a Params) -> Result<&'a Value> {
+    match operand {
+        Operand::Value(value) => Ok(value),
+        Operand::Param(name) => params
+            .get(name)
+            .ok_or_else(|| QueryError::MissingParameter { name: name.clone() }.into()),
+    }
+}
+
+fn key_operand_error() -> QueryError {
+    QueryError::InvalidOperand {
+        target: "the row key".to_string(),
+        expected: "text".to_string(),
+    }
+}
+
+/// Unwraps a row written with a `TableSchema`, which nests the record under
+/// a version tag.
+pub(crate) fn untag(document: serde_json::Value) -> serde_json::Value {
+    match document {
+        serde_json::Value::Object(mut object)
+            if object.len() == 2 && object.contains_key(VERSION_FIELD) =>
+        {
+            object.remove(ROW_FIELD).unwrap_or(serde_json::Value::Null)
+        }
+        other => other,
+    }
+}
+
+fn map_field<'a>(value: &'a Value, segments: &[String]) -> Option<&'a Value> {
+    segments
+        .iter()
+        .try_fold(value, |current, segment| match current {
+            Value::Map(map) => map.get(segment),
+            Value::List(list) => list.get(segment.parse().ok()?),
+            _ => None,
+        })
+}
+
+pub(crate) fn json_field(document: &serde_json::Value, segments: &[String]) -> Option<Value> {
+    let field = segments
+        .iter()
+        .try_fold(document, |current, segment| match current {
+            serde_json::Value::Object(object) => object.get(segment),
+            serde_json::Value::Array(items) => items.get(segment.parse::<usize>().ok()?),
+            _ => None,
+        })?;
+    match field {
+        serde_json::Value::Null => Some(Value::Null),
+        serde_json::Value::Bool(b) => Some(Value::Bool(*b)),
+        serde_json::Value::Number(n) => n
+            .as_i64()
+            .map(Value::Int)
+            .or_else(|| n.as_f64().map(Value::Float)),
+        serde_json::Value::String(s) => Some(Value::Text(s.clone())),
+        _ => None,
+    }
+}
+
+/// Compares values of the same type; values of different types never match,
+/// except integers and floats, which are compared as numbers.
+fn compare(comparison: Comparison, actual: &Value, operand: &Value) -> bool {
+    let ordering = match (actual, operand) {
+        (Value::Null, Value::Null) => Ordering::Equal,
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+        (Value::Int(a), Value::Int(b)) => a.cmp(b),
+        (Value::Float(_) | Value::Int(_), Value::Float(_) | Value::Int(_)) => {
+            let (Some(a), Some(b)) = (actual.as_float(), operand.as_float()) else {
+                return false;
+            };
+            a.total_cmp(&b)
+        }
+        (Value::Text(a), Value::Text(b)) => a.cmp(b),
+        (Value::Bytes(a), Value::Bytes(b)) => a.cmp(b),
+        (Value::Link(a), Value::Link(b)) => a.as_str().cmp(b.as_str()),
+        _ => return false,
+    };
+    match comparison {
+        Comparison::Eq => ordering == Ordering::Equal,
+        Comparison::Ne => ordering != Ordering::Equal,
+        Comparison::Lt => ordering == Ordering::Less,
+        Comparison::Le => ordering != Ordering::Greater,
+        Comparison::Gt => ordering == Ordering::Greater,
+        Comparison::Ge => ordering != Ordering::Less,
+    }
+}
@@ -0,0 +1,158 @@
+//! Prepared queries
+//!
+//! Preparing a query does the work that does not depend on its parameters:
+//! field paths are parsed and the way rows are found is chosen. The layout of
+//! the subtree's rows is detected on the first execution that finds a row and
+//! kept from then on. Executing a prepared query then only
+//! resolves parameters, reads the subtree and filters its rows.
+//!
+//! The subtree's state is cached along with the subtree tips it was computed
+//! at, and reused until a commit changes those tips, so repeated executions
+//! against a quiet subtree do not recompute its state from entries.
+
+use super::plan::{Layout, Plan, select, untag};
+use super::{Params, Query};
+use crate::Result;
+use crate::Tree;
+use crate::crdt::map::{Map, Value};
+use crate::entry::ID;
+use crate::subtree::{Dict, SubtreeError};
+use serde::de::DeserializeOwned;
+use std::sync::{Arc, Mutex};
+
+/// A row returned by a query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Row {
+    /// The row's key in the subtree
+    pub key: String,
+    /// The value stored under the key
+    pub value: Value,
+}
+
+impl Row {
+    /// Deserializes a row written by a [`Table`](crate::subtree::Table).
+    ///
+    /// # Errors
+    /// Returns `SubtreeError::DeserializationFailed` if the value is not a
+    /// `T` serialized as JSON.
+    pub fn decode<T: DeserializeOwned>(&self) -> Result<T> {
+        let failed = |reason: String| -> crate::Error {
+            SubtreeError::DeserializationFailed {
+                subtree: String::new(),
+                reason: format!("Failed to decode row '{}': {reason}", self.key),
+            }
+            .into()
+        };
+        let text = self
+            .value
+            .as_text()
+            .ok_or_else(|| failed("value is not a table row".to_string()))?;
+        let document = serde_json::from_str(text).map_err(|e| failed(e.to_string()))?;
+        serde_json::from_value(untag(document)).map_err(|e| failed(e.to_string()))
+    }
+}
+
+/// A subtree state and the subtree tips it was computed at.
+struct Snapshot {
+    tips: Vec<ID>,
+    state: Arc<Map>,
+}
+
+/// A query prepared for repeated execution against one tree.
+///
+/// Created by [`Tree::prepare`]. Executions are independent; a prepared query
+/// can be shared between threads.
+pub struct PreparedQuery {
+    tree: Tree,
+    query: Query,
+    plan: Plan,
+    layout: Mutex<Layout>,
+    snapshot: Mutex<Option<Snapshot>>,
+}
+
+impl std::fmt::Debug for PreparedQuery {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PreparedQuery")
+            .field("tree", self.tree.root_id())
+            .field("query", &self.query)
+            .field("plan", &self.plan)
+            .field("layout", &*self.layout.lock().unwrap())
+            .finish()
+    }
+}
+
+impl PreparedQuery {
+    pub(crate) fn new(tree: Tree, query: Query) -> Result<Self> {
+        Ok(Self {
+            plan: Plan::new(&query)?,
+            tree,
+            query,
+            layout: Mutex::new(Layout::Unknown),
+            snapshot: Mutex::new(None),
+        })
+    }
+
+    /// The query this was prepared from.
+    pub fn query(&self) -> &Query {
+        &self.query
+    }
+
+    /// Names of the parameters the query needs, in order of first use.
+    pub fn parameters(&self) -> &[String] {
+        self.plan.parameters()
+    }
+
+    /// Whether rows are found by looking up their key instead of scanning
+    /// the subtree.
+    pub fn is_key_lookup(&self) -> bool {
+        self.plan.is_key_lookup()
+    }
+
+    /// Runs the query with the given parameter values.
+    ///
+    /// Returns matching rows in key order, up to the query's limit.
+    ///
+    /// # Errors
+    /// Returns `QueryError::MissingParameter` if a parameter has no value, and
+    /// `QueryError::InvalidOperand` if the row key is compared to a value that
+    /// is not text.
+    pub fn execute(&self, params: &Params) -> Result<Vec<Row>> {
+        // Resolve parameters before reading the subtree
+        self.plan.operands(params)?;
+        let state = self.state()?;
+        let layout = self.layout(&state);
+        select(&self.plan, &state, layout, params, self.query.max_rows())
+    }
+
+    /// The subtree's current state, recomputed only if its tips changed.
+    ///
+    /// Each call is one read of the subtree for the read log.
+    fn state(&self) -> Result<Arc<Map>> {
+        let name = self.query.subtree_name();
+        let mut tips = self.tree.subtree_tips(name)?;
+        tips.sort();
+        let mut snapshot = self.snapshot.lock().unwrap();
+        if let Some(snapshot) = snapshot.as_ref()
+            && snapshot.tips == tips
+        {
+            self.tree.record_read(name);
+            return Ok(Arc::clone(&snapshot.state));
+        }
+        let state = Arc::new(self.tree.get_subtree_viewer::<Dict>(name)?.get_all()?);
+        *snapshot = Some(Snapshot {
+            tips,
+            state: Arc::clone(&state),
+        });
+        Ok(state)
+    }
+
+    /// The layout of the subtree's rows, detected from the first row once
+    /// there is one.
+    fn layout(&self, state: &Map) -> Layout {
+        let mut layout = self.layout.lock().unwrap();
+        if *layout == Layout::Unknown {
+            *layout = Layout::detect(state);
+        }
+        *layout
+    }
+}
@@ -0,0 +1,245 @@
+//! Content-addressed binary storage for Eidetica
+//!
+//! This module provides `BlobStore`, a subtree for attaching binary content such
+//! as files or images to a tree. Blobs are immutable and identified by the
+//! SHA-256 hash of their content, so storing the same content twice yields the
+//! same ID and the data is kept only once.
+//!
+//! # Storage Layout
+//!
+//! Blob content is split into chunks of at most [`BlobStore::CHUNK_SIZE`] bytes.
+//! Each chunk is stored under its own hash, and each blob has a manifest listing
+//! its size and chunk hashes. Chunks shared between blobs, or already present in
+//! the tree, are not stored again.
+//!
+//! Chunks live in the subtree's own data rather than in a `Map` CRDT, and since
+//! everything is content-addressed, merging concurrent entries is a plain union.
+//! Entries are JSON documents, so each chunk is base64 encoded once in the entry
+//! it was added by.
+
+use crate::Result;
+use crate::atomicop::AtomicOp;
+use crate::crdt::{CRDT, Data};
+use crate::subtree::SubTree;
+use crate::subtree::errors::SubtreeError;
+use base64ct::{Base64, Encoding};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+/// The size and chunks of a stored blob.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct Manifest {
+    size: u64,
+    chunks: Vec<String>,
+}
+
+/// The stored data of a `BlobStore` subtree.
+///
+/// Both maps are keyed by content hashes, so entries with the same key always
+/// hold the same value and merging is a union.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct BlobData {
+    #[serde(default)]
+    manifests: BTreeMap<String, Manifest>,
+    /// Base64 encoded chunk content, keyed by the hash of the raw bytes
+    #[serde(default)]
+    chunks: BTreeMap<String, String>,
+}
+
+impl Data for BlobData {}
+
+impl CRDT for BlobData {
+    fn merge(&self, other: &Self) -> Result<Self> {
+        let mut merged = self.clone();
+        for (id, manifest) in &other.manifests {
+            merged
+                .manifests
+                .entry(id.clone())
+                .or_insert_with(|| manifest.clone());
+        }
+        for (hash, chunk) in &other.chunks {
+            merged
+                .chunks
+                .entry(hash.clone())
+                .or_insert_with(|| chunk.clone());
+        }
+        Ok(merged)
+    }
+}
+
+/// A content-addressed binary SubTree
+///
+/// `BlobStore` stores arbitrary binary content and returns an ID derived from
+/// the content's SHA-256 hash. Content is chunked and deduplicated, and every
+/// chunk is verified against its hash when read back.
+///
+/// Blobs cannot be modified or deleted: to replace a file, store the new content
+/// and update whatever refers to the old ID.
+///
+/// # Example
+/// ```
+/// # use eidetica::{backend::database::InMemory, basedb::BaseDB, subtree::BlobStore};
+/// # let db = BaseDB::new(Box::new(InMemory::new()));
+/// # db.add_private_key("key").unwrap();
+/// # let tree = db.new_tree_default("key").unwrap();
+/// let op = tree.new_operation().unwrap();
+/// let blobs = op.get_subtree::<BlobStore>("attachments").unwrap();
+///
+/// let id = blobs.put(b"\x89PNG...").unwrap();
+/// assert_eq!(blobs.get(&id).unwrap(), b"\x89PNG...");
+/// op.commit().unwrap();
+/// ```
+pub struct BlobStore {
+    name: String,
+    atomic_op: AtomicOp,
+}
+
+impl SubTree for BlobStore {
+    fn new(op: &AtomicOp, subtree_name: impl Into<String>) -> Result<Self> {
+        Ok(Self {
+            name: subtree_name.into(),
+            atomic_op: op.clone(),
+        })
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl BlobStore {
+    /// The maximum size of a single stored chunk, in bytes.
+    pub const CHUNK_SIZE: usize = 256 * 1024;
+
+    /// Stores binary content and returns its content-addressed ID.
+    ///
+    /// Storing content that is already in the tree is cheap: only chunks that
+    /// are not yet stored are added to the operation.
+    ///
+    /// # Errors
+    /// Returns an error if the subtree state cannot be loaded or the operation fails.
+    pub fn put(&self, content: impl AsRef<[u8]>) -> Result<String> {
+        let content = content.as_ref();
+        let id = hash(content);
+        let existing = self.get_all()?;
+        if existing.manifests.contains_key(&id) {
+            return Ok(id);
+        }
+
+        let mut local = self.local_data()?;
+        let mut chunks = Vec::new();
+        for chunk in content.chunks(Self::CHUNK_SIZE) {
+            let chunk_hash = hash(chunk);
+            if !existing.chunks.contains_key(&chunk_hash) {
+                local
+                    .chunks
+                    .insert(chunk_hash.clone(), Base64::encode_string(chunk));
+            }
+            chunks.push(chunk_hash);
+        }
+        local.manifests.insert(
+            id.clone(),
+            Manifest {
+                size: content.len() as u64,
+                chunks,
+            },
+        );
+
+        let serialized =
+            serde_json::to_string(&local).map_err(|e| SubtreeError::SerializationFailed {
+                subtree: self.name.clone(),
+                reason: format!("Failed to serialize blob data: {e}"),
+            })?;
+        self.atomic_op.update_subtree(&self.name, &serialized)?;
+        Ok(id)
+    }
+
+    /// Reads the content of a blob.
+    ///
+    /// # Errors
+    /// Returns `Error::NotFound` if no blob has the given ID, or an error if
+    /// stored chunks are missing or do not match their hashes.
+    pub fn get(&self, id: impl AsRef<str>) -> Result<Vec<u8>> {
+        let id = id.as_ref();
+        let data = self.get_all()?;
+        let manifest = data.manifests.get(id).ok_or_else(|| self.not_found(id))?;
+
+        let mut content = Vec::with_capacity(manifest.size as usize);
+        for chunk_hash in &manifest.chunks {
+            let encoded = data.chunks.get(chunk_hash).ok_or_else(|| {
+                self.corrupted(format!("Chunk {chunk_hash} of blob {id} is missing"))
+            })?;
+            let chunk = Base64::decode_vec(encoded).map_err(|e| {
+                self.corrupted(format!(
+                    "Chunk {chunk_hash} of blob {id} is not valid base64: {e}"
+                ))
+            })?;
+            if hash(&chunk) != *chunk_hash {
+                return Err(self.corrupted(format!(
+                    "Chunk {chunk_hash} of blob {id} does not match its hash"
+                )));
+            }
+            content.extend_from_slice(&chunk);
+        }
+
+        if hash(&content) != id {
+            return Err(self.corrupted(format!("Blob {id} does not match its hash")));
+        }
+        Ok(content)
+    }
+
+    /// Returns true if a blob with the given ID is stored.
+    pub fn contains(&self, id: impl AsRef<str>) -> Result<bool> {
+        Ok(self.get_all()?.manifests.contains_key(id.as_ref()))
+    }
+
+    /// Returns the size in bytes of a blob without reading its content.
+    ///
+    /// # Errors
+    /// Returns `Error::NotFound` if no blob has the given ID.
+    pub fn size(&self, id: impl AsRef<str>) -> Result<u64> {
+        let id = id.as_ref();
+        self.get_all()?
+            .manifests
+            .get(id)
+            .map(|manifest| manifest.size)
+            .ok_or_else(|| self.not_found(id))
+    }
+
+    /// Returns the IDs of all stored blobs, in sorted order.
+    pub fn list(&self) -> Result<Vec<String>> {
+        Ok(self.get_all()?.manifests.into_keys().collect())
+    }
+
+    /// The merged state of the subtree, including changes staged in this operation.
+    fn get_all(&self) -> Result<BlobData> {
+        let data = self.atomic_op.get_full_state::<BlobData>(&self.name)?;
+        data.merge(&self.local_data()?)
+    }
+
+    fn local_data(&self) -> Result<BlobData> {
+        self.atomic_op.get_local_data::<BlobData>(&self.name)
+    }
+
+    fn not_found(&self, id: &str) -> crate::Error {
+        SubtreeError::KeyNotFound {
+            subtree: self.name.clone(),
+            key: id.to_string(),
+        }
+        .into()
+    }
+
+    fn corrupted(&self, reason: String) -> crate::Error {
+        SubtreeError::DataCorruption {
+            subtree: self.name.clone(),
+            reason,
+        }
+        .into()
+    }
+}
+
+/// The hex-encoded SHA-256 hash of some bytes.
+fn hash(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
@@ -1,9 +1,18 @@
 use crate::Result;
-use crate::atomicop::AtomicOp;
-use crate::crdt::map::{List, Value};
-use crate::crdt::{CRDT, Map};
+use crate::atomicop::{AtomicOp, AtomicOpError};
+use crate::auth::crypto::parse_public_key;
+use crate::auth::seal::SealedValue;
+use crate::auth::{AuthError, AuthSettings, KeyStatus};
+use crate::crdt::map::list::Position;
+use crate::crdt::map::{List, MergeResolver, Value, path};
+use crate::crdt::{CRDT, CRDTError, Map};
+use crate::entry::ID;
+use crate::query::plan::{Layout, Plan, select};
+use crate::query::{Params, Query, Row};
 use crate::subtree::SubTree;
 use crate::subtree::errors::SubtreeError;
+use crate::subtree::history::{KeyChange, key_history};
+use crate::subtree::model::{DictModel, Model};
 
 /// A simple key-value store SubTree providing ergonomic access to Map CRDT data.
 ///
@@ -13,6 +22,8 @@ use crate::subtree::errors::SubtreeError;
 pub struct Dict {
     name: String,
     atomic_op: AtomicOp,
+    /// Resolves concurrent writes to the same value, see [`Dict::with_resolver`]
+    resolver: Option<MergeResolver>,
 }
 
 impl SubTree for Dict {
@@ -20,6 +31,7 @@ impl SubTree for Dict {
         Ok(Self {
             name: subtree_name.into(),
             atomic_op: op.clone(),
+            resolver: None,
         })
     }
 
@@ -29,6 +41,56 @@ impl SubTree for Dict {
 }
 
 impl Dict {
+    /// Resolves concurrent writes to the same value with `resolver` instead of
+    /// last-write-wins.
+    ///
+    /// Where branches of the subtree's history are merged, each value that
+    /// several branches changed to different values is passed to `resolver`
+    /// with its path, the value of the branch written first and the value of the
+    /// branch written after it. The returned value is what this `Dict` reads.
+    /// Values changed on one branch only, lists and renamed keys merge as usual.
+    /// See [`MergeResolver`] for the requirements on the resolver.
+    ///
+    /// Resolution happens when reading: the stored entries are unchanged, and a
+    /// `Dict` of the same subtree without the resolver reads the
+    /// last-write-wins state. Resolved state is not cached between reads.
+    ///
+    /// # Example
+    /// ```
+    /// # use eidetica::backend::database::InMemory;
+    /// # use eidetica::basedb::BaseDB;
+    /// # use eidetica::crdt::map::Value;
+    /// # use eidetica::subtree::Dict;
+    /// # fn main() -> eidetica::Result<()> {
+    /// # let db = BaseDB::new(Box::new(InMemory::new()));
+    /// # db.add_private_key("key")?;
+    /// # let tree = db.new_tree_default("key")?;
+    /// // Concurrent updates to a high score keep the higher one
+    /// let scores = tree
+    ///     .get_subtree_viewer::<Dict>("scores")?
+    ///     .with_resolver(|_, ours, theirs| match (ours, theirs) {
+    ///         (Value::Int(a), Value::Int(b)) => Value::Int(*a.max(b)),
+    ///         _ => theirs.clone(),
+    ///     });
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_resolver(
+        mut self,
+        resolver: impl Fn(&str, &Value, &Value) -> Value + Send + Sync + 'static,
+    ) -> Self {
+        self.resolver = Some(std::sync::Arc::new(resolver));
+        self
+    }
+
+    /// The merged state of the subtree before this operation's changes.
+    fn full_state(&self) -> Result<Map> {
+        match &self.resolver {
+            Some(resolver) => self.atomic_op.get_full_state_resolved(&self.name, resolver),
+            None => self.atomic_op.get_full_state(&self.name),
+        }
+    }
+
     /// Gets a value associated with a key from the SubTree.
     ///
     /// This method prioritizes returning data staged within the current `AtomicOp`.
@@ -54,7 +116,7 @@ impl Dict {
         }
 
         // Otherwise, get the full state from the backend
-        let data: Map = self.atomic_op.get_full_state(&self.name)?;
+        let data = self.full_state()?;
 
         // Get the value
         match data.get(key) {
@@ -138,6 +200,37 @@ impl Dict {
         self.atomic_op.update_subtree(&self.name, &serialized)
     }
 
+    /// Stages setting many key-value pairs at once.
+    ///
+    /// Equivalent to calling [`set`](Self::set) for each pair, but the
+    /// subtree's staged data is read and serialized once for the whole batch
+    /// instead of once per pair. Later pairs win over earlier pairs with the
+    /// same key.
+    ///
+    /// # Arguments
+    /// * `entries` - The key-value pairs to set
+    ///
+    /// # Returns
+    /// A `Result<()>` indicating success or an error during serialization or staging.
+    pub fn set_many<K, V>(&self, entries: impl IntoIterator<Item = (K, V)>) -> Result<()>
+    where
+        K: Into<String>,
+        V: Into<Value>,
+    {
+        let mut data = self
+            .atomic_op
+            .get_local_data::<Map>(&self.name)
+            .unwrap_or_default();
+
+        let map = data.as_hashmap_mut();
+        for (key, value) in entries {
+            map.insert(key.into(), value.into());
+        }
+
+        let serialized = serde_json::to_string(&data)?;
+        self.atomic_op.update_subtree(&self.name, &serialized)
+    }
+
     /// Convenience method to set a string value.
     pub fn set_string(&self, key: impl Into<String>, value: impl Into<String>) -> Result<()> {
         self.set(key, Value::Text(value.into()))
@@ -179,6 +272,94 @@ impl Dict {
         }
     }
 
+    /// Convenience method to get a link to another entry.
+    pub fn get_link(&self, key: impl AsRef<str>) -> Result<ID> {
+        match self.get(key)? {
+            Value::Link(id) => Ok(id),
+            other => Err(SubtreeError::TypeMismatch {
+                subtree: self.name.clone(),
+                expected: "link".to_string(),
+                actual: other.type_name().to_string(),
+            }
+            .into()),
+        }
+    }
+
+    /// Convenience method to set a link to another entry.
+    ///
+    /// Links are resolved with [`Tree::resolve_link`](crate::Tree::resolve_link).
+    pub fn set_link(&self, key: impl Into<String>, id: impl Into<ID>) -> Result<()> {
+        self.set(key, Value::Link(id.into()))
+    }
+
+    /// Convenience method to get an integer value.
+    pub fn get_int(&self, key: impl AsRef<str>) -> Result<i64> {
+        self.get_typed(key, "int", Value::as_int)
+    }
+
+    /// Convenience method to set an integer value.
+    pub fn set_int(&self, key: impl Into<String>, value: i64) -> Result<()> {
+        self.set(key, Value::Int(value))
+    }
+
+    /// Convenience method to get a float value.
+    ///
+    /// Integer values are converted, see [`Value::as_float`].
+    pub fn get_float(&self, key: impl AsRef<str>) -> Result<f64> {
+        self.get_typed(key, "float", Value::as_float)
+    }
+
+    /// Convenience method to set a float value.
+    pub fn set_float(&self, key: impl Into<String>, value: f64) -> Result<()> {
+        self.set(key, Value::Float(value))
+    }
+
+    /// Convenience method to get a boolean value.
+    pub fn get_bool(&self, key: impl AsRef<str>) -> Result<bool> {
+        self.get_typed(key, "bool", Value::as_bool)
+    }
+
+    /// Convenience method to set a boolean value.
+    pub fn set_bool(&self, key: impl Into<String>, value: bool) -> Result<()> {
+        self.set(key, Value::Bool(value))
+    }
+
+    /// Convenience method to get a byte array value.
+    pub fn get_bytes(&self, key: impl AsRef<str>) -> Result<Vec<u8>> {
+        self.get_typed(key, "bytes", |value| value.as_bytes().map(<[u8]>::to_vec))
+    }
+
+    /// Convenience method to set a byte array value.
+    pub fn set_bytes(&self, key: impl Into<String>, value: impl Into<Vec<u8>>) -> Result<()> {
+        self.set(key, Value::Bytes(value.into()))
+    }
+
+    /// Gets a value converted by `convert`, with a `TypeMismatch` error naming
+    /// `expected` if it returns `None`.
+    fn get_typed<T>(
+        &self,
+        key: impl AsRef<str>,
+        expected: &str,
+        convert: impl FnOnce(&Value) -> Option<T>,
+    ) -> Result<T> {
+        let key = key.as_ref();
+        match self.get(key)? {
+            Value::Deleted => Err(SubtreeError::KeyNotFound {
+                subtree: self.name.clone(),
+                key: key.to_string(),
+            }
+            .into()),
+            value => convert(&value).ok_or_else(|| {
+                SubtreeError::TypeMismatch {
+                    subtree: self.name.clone(),
+                    expected: expected.to_string(),
+                    actual: value.type_name().to_string(),
+                }
+                .into()
+            }),
+        }
+    }
+
     /// Convenience method to set a list value.
     pub fn set_list(&self, key: impl Into<String>, list: impl Into<List>) -> Result<()> {
         self.set(key, Value::List(list.into()))
@@ -199,6 +380,112 @@ impl Dict {
         self.get(key)
     }
 
+    /// Stages setting a value encrypted to keys of the tree.
+    ///
+    /// The value is sealed to each of `recipients`, names of active keys in
+    /// the tree's auth settings, and stored as an opaque text value: only the
+    /// holders of their private keys can read it, with
+    /// [`get_encrypted`](Self::get_encrypted). Concurrent writes merge as for
+    /// [`set`](Self::set), with one sealed value winning whole.
+    ///
+    /// The names of the recipients are readable by anyone who can read the
+    /// subtree, and a key removed from the tree can still decrypt the values
+    /// sealed to it before. The value is bound to this tree and subtree, but
+    /// not to `key`, so [`rename`](Self::rename) keeps it readable.
+    ///
+    /// # Arguments
+    /// * `key` - The key to set.
+    /// * `value` - The value to encrypt.
+    /// * `recipients` - Names of the keys that may decrypt the value.
+    ///
+    /// # Errors
+    /// Returns `AuthError::KeyNotFound` if a recipient is not a key of the
+    /// tree, `AuthError::PermissionDenied` if it is not active, and
+    /// `SubtreeError::InvalidOperation` if there are no recipients.
+    pub fn set_encrypted<R: AsRef<str>>(
+        &self,
+        key: impl Into<String>,
+        value: impl Into<Value>,
+        recipients: &[R],
+    ) -> Result<()> {
+        if recipients.is_empty() {
+            return Err(SubtreeError::InvalidOperation {
+                subtree: self.name.clone(),
+                operation: "set_encrypted".to_string(),
+                reason: "an encrypted value needs at least one recipient".to_string(),
+            }
+            .into());
+        }
+
+        let settings = self.atomic_op.get_settings()?;
+        let auth = match settings.get("auth") {
+            Some(Value::Map(auth)) => AuthSettings::from_map(auth.clone()),
+            _ => return Err(AuthError::NoAuthConfiguration.into()),
+        };
+        let mut keys = Vec::with_capacity(recipients.len());
+        for name in recipients {
+            let name = name.as_ref();
+            let auth_key = auth.get_key(name).ok_or_else(|| AuthError::KeyNotFound {
+                key_name: name.to_string(),
+            })??;
+            if auth_key.status != KeyStatus::Active {
+                return Err(AuthError::PermissionDenied {
+                    reason: format!("key {name} is not active"),
+                }
+                .into());
+            }
+            keys.push((name.to_string(), parse_public_key(&auth_key.pubkey)?));
+        }
+
+        let plaintext = serde_json::to_vec(&value.into())?;
+        let sealed = SealedValue::seal(&plaintext, &self.seal_context(), &keys);
+        self.set(key, Value::Text(sealed.to_json()))
+    }
+
+    /// Gets a value stored with [`set_encrypted`](Self::set_encrypted),
+    /// decrypted with the private key `key_name`.
+    ///
+    /// # Errors
+    /// Returns `SubtreeError::KeyNotFound` if the key is not set,
+    /// `SubtreeError::TypeMismatch` if its value is not encrypted,
+    /// `AuthError::NotARecipient` if it was not encrypted to `key_name`,
+    /// `AuthError::DecryptionFailed` if it was tampered with, and
+    /// `AtomicOpError::SigningKeyNotFound` if the private key is not in the backend.
+    pub fn get_encrypted(&self, key: impl AsRef<str>, key_name: impl AsRef<str>) -> Result<Value> {
+        let key_name = key_name.as_ref();
+        let sealed = match self.get(key)? {
+            Value::Text(json) => SealedValue::parse(&json),
+            _ => None,
+        }
+        .ok_or_else(|| SubtreeError::TypeMismatch {
+            subtree: self.name.clone(),
+            expected: "Encrypted".to_string(),
+            actual: "Other".to_string(),
+        })?;
+
+        let signing_key = self
+            .atomic_op
+            .tree()
+            .backend()
+            .get_private_key(key_name)?
+            .ok_or_else(|| AtomicOpError::SigningKeyNotFound {
+                key_name: key_name.to_string(),
+            })?;
+        let plaintext = sealed.open(&self.seal_context(), key_name, &signing_key)?;
+        serde_json::from_slice(&plaintext).map_err(|e| {
+            AuthError::DecryptionFailed {
+                reason: format!("invalid value: {e}"),
+            }
+            .into()
+        })
+    }
+
+    /// What encrypted values are bound to: the tree and this subtree.
+    fn seal_context(&self) -> Vec<u8> {
+        let root = self.atomic_op.tree().root_id();
+        format!("{root}/{}", self.name).into_bytes()
+    }
+
     /// Stages the deletion of a key within the associated `AtomicOp`.
     ///
     /// This method removes the key-value pair from the `Map` data held within
@@ -252,6 +539,141 @@ impl Dict {
         self.atomic_op.update_subtree(&self.name, &serialized)
     }
 
+    /// Stages a new value for a key computed from its current value.
+    ///
+    /// `f` is called with the value of `key` as seen by this operation, staged
+    /// changes included, or `None` if the key has no value. The value it returns
+    /// is staged and returned. Reading and writing through one call keeps the
+    /// new value based on the state the operation commits on top of.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// # use eidetica::crdt::map::Value;
+    /// # use eidetica::subtree::Dict;
+    /// # fn example(store: &Dict) -> eidetica::Result<()> {
+    /// store.update("visits", |old| {
+    ///     Value::Int(old.and_then(|v| v.as_int()).unwrap_or(0) + 1)
+    /// })?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Returns
+    /// A `Result` containing the staged value.
+    pub fn update(
+        &self,
+        key: impl Into<String>,
+        f: impl FnOnce(Option<&Value>) -> Value,
+    ) -> Result<Value> {
+        let key = key.into();
+        let old = self.current(&key)?;
+        let new = f(old.as_ref());
+        self.set(key, new.clone())?;
+        Ok(new)
+    }
+
+    /// Stages a new value for a key only if its current value is `expected`.
+    ///
+    /// The current value is the one seen by this operation, staged changes
+    /// included. An `expected` of `None` means the key must have no value.
+    /// Values are compared with `==`, which ignores the write stamps of maps,
+    /// so a map read from committed history matches the map that was written.
+    ///
+    /// The comparison only covers the history this operation builds on. An
+    /// operation committed concurrently elsewhere can still write the key, in
+    /// which case the two writes are merged like any other.
+    ///
+    /// # Returns
+    /// A `Result` containing `true` if the value was staged, or `false` if the
+    /// current value did not match and nothing was changed.
+    pub fn compare_and_set(
+        &self,
+        key: impl Into<String>,
+        expected: Option<&Value>,
+        new: impl Into<Value>,
+    ) -> Result<bool> {
+        let key = key.into();
+        if self.current(&key)?.as_ref() != expected {
+            return Ok(false);
+        }
+        self.set(key, new)?;
+        Ok(true)
+    }
+
+    /// The value of a key, or `None` if it has none.
+    fn current(&self, key: &str) -> Result<Option<Value>> {
+        // A key deleted in this operation has no value, whatever the backend holds
+        if let Ok(local) = self.atomic_op.get_local_data::<Map>(&self.name)
+            && local.is_tombstone(key)
+        {
+            return Ok(None);
+        }
+        match self.get(key) {
+            Ok(Value::Deleted) => Ok(None),
+            Ok(value) => Ok(Some(value)),
+            Err(e) if e.is_not_found() => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Stages renaming a key, keeping its value.
+    ///
+    /// The value is copied to `new_key` and `old_key` is deleted in a single
+    /// change, which also leaves a move marker at `old_key`. Writes to
+    /// `old_key` merged after the rename, such as writes made on replicas that
+    /// had not seen it yet, are applied to `new_key` instead of being lost.
+    /// Renaming another key to `old_key` makes it an ordinary key again.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// # use eidetica::subtree::Dict;
+    /// # fn example(store: &Dict) -> eidetica::Result<()> {
+    /// store.rename("colour", "color")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Arguments
+    /// * `old_key` - The key to rename.
+    /// * `new_key` - The new name of the key.
+    ///
+    /// # Returns
+    /// A `Result<()>`, with a `KeyNotFound` error if `old_key` has no value.
+    pub fn rename(&self, old_key: impl AsRef<str>, new_key: impl Into<String>) -> Result<()> {
+        let old_key = old_key.as_ref();
+        let value = self.get(old_key)?;
+
+        let mut data = self
+            .atomic_op
+            .get_local_data::<Map>(&self.name)
+            .unwrap_or_default();
+        // The staged data may not hold the value yet, so stage it before moving it
+        data.set(old_key, value);
+        data.rename(old_key, new_key);
+
+        let serialized = serde_json::to_string(&data)?;
+        self.atomic_op.update_subtree(&self.name, &serialized)
+    }
+
+    /// Lists the committed changes to a key, oldest first.
+    ///
+    /// Walks the history this `Dict` builds on and returns every entry that set
+    /// or deleted `key`, with the key the entry was signed with and the value of
+    /// `key` after the change. Changes staged in the current operation are not
+    /// included.
+    ///
+    /// # Arguments
+    /// * `key` - The key to list the changes of.
+    ///
+    /// # Returns
+    /// A `Result` containing the changes in topological order, empty if the key
+    /// was never written.
+    pub fn history(&self, key: impl AsRef<str>) -> Result<Vec<KeyChange<Value>>> {
+        key_history(&self.atomic_op, &self.name, key.as_ref(), |value| {
+            Ok(value.clone())
+        })
+    }
+
     /// Retrieves all key-value pairs, merging staged data with historical state.
     ///
     /// This method combines the data staged within the current `AtomicOp` with the
@@ -266,7 +688,7 @@ impl Dict {
         let local_data = self.atomic_op.get_local_data::<Map>(&self.name);
 
         // Get the full state from the backend
-        let mut data = self.atomic_op.get_full_state::<Map>(&self.name)?;
+        let mut data = self.full_state()?;
 
         // If there's also local data, merge it with the full state
         if let Ok(local) = local_data {
@@ -276,6 +698,31 @@ impl Dict {
         Ok(data)
     }
 
+    /// Runs a query against this Dict, including changes staged in the operation.
+    ///
+    /// Rows are the Dict's top-level keys and the values stored under them; see
+    /// [`crate::query`] for how conditions are matched. Queries with parameters
+    /// are run with [`Tree::prepare`](crate::Tree::prepare) instead.
+    ///
+    /// # Returns
+    /// * `Ok(Vec<Row>)` - The matching rows in key order, up to the query's limit
+    ///
+    /// # Errors
+    /// Returns `QueryError::SubtreeMismatch` if the query reads another subtree,
+    /// and `QueryError::MissingParameter` if it has parameters.
+    pub fn query(&self, query: &Query) -> Result<Vec<Row>> {
+        query.check_subtree(&self.name)?;
+        let plan = Plan::new(query)?;
+        let state = self.get_all()?;
+        select(
+            &plan,
+            &state,
+            Layout::detect(&state),
+            &Params::new(),
+            query.max_rows(),
+        )
+    }
+
     /// Gets a mutable editor for a value associated with the given key.
     ///
     /// If the key does not exist, the editor will be initialized with an empty map,
@@ -296,6 +743,21 @@ impl Dict {
         ValueEditor::new(self, Vec::new())
     }
 
+    /// Returns a typed handle to a `DictModel` struct stored at a dot-separated path.
+    ///
+    /// The handle reads and writes the struct's fields as a nested map, so
+    /// `dict.model::<Config>("app.config")` stores `Config` under `app` → `config`.
+    /// See the [`model`](crate::subtree::model) module for details.
+    pub fn model<T: DictModel>(&self, path: impl AsRef<str>) -> Model<'_, T> {
+        let path = path
+            .as_ref()
+            .split('.')
+            .filter(|segment| !segment.is_empty())
+            .map(str::to_string)
+            .collect();
+        Model::new(self, path)
+    }
+
     /// Retrieves a `Value` from the Dict using a specified path.
     ///
     /// The path is a slice of strings, where each string is a key in the
@@ -473,15 +935,98 @@ impl Dict {
         let serialized_data = serde_json::to_string(&subtree_data)?;
         self.atomic_op.update_subtree(&self.name, &serialized_data)
     }
+
+    /// Retrieves a `Value` from the Dict using a path string such as `"user.profile.name"`.
+    ///
+    /// Segments are separated by `.` or `/`, and a backslash escapes a separator
+    /// or backslash within a key; see the [`path`](crate::crdt::map::path) module.
+    /// Otherwise this behaves like [`get_at_path`](Self::get_at_path).
+    ///
+    /// # Errors
+    ///
+    /// * `CRDTError::MalformedPath` if the path cannot be parsed.
+    /// * The errors of [`get_at_path`](Self::get_at_path).
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// # use eidetica::subtree::Dict;
+    /// # fn example(dict: &Dict) -> eidetica::Result<()> {
+    /// dict.set_path("user.profile.name", "Alice")?;
+    /// dict.set_path("files/report\\.pdf", "final")?; // the key "report.pdf" under "files"
+    /// let name = dict.get_path("user/profile/name")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_path(&self, path: impl AsRef<str>) -> Result<Value> {
+        self.get_at_path(path::parse(path.as_ref())?)
+    }
+
+    /// Sets a `Value` at a path string such as `"user.profile.name"`.
+    ///
+    /// The path syntax is described in [`get_path`](Self::get_path); otherwise
+    /// this behaves like [`set_at_path`](Self::set_at_path), creating
+    /// intermediate maps as needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CRDTError::MalformedPath` if the path cannot be parsed.
+    pub fn set_path(&self, path: impl AsRef<str>, value: impl Into<Value>) -> Result<()> {
+        self.set_at_path(path::parse(path.as_ref())?, value.into())
+    }
+
+    /// Deletes the value at a path string by setting a tombstone there.
+    ///
+    /// The path syntax is described in [`get_path`](Self::get_path).
+    ///
+    /// # Errors
+    ///
+    /// Returns `CRDTError::MalformedPath` if the path cannot be parsed.
+    pub fn delete_path(&self, path: impl AsRef<str>) -> Result<()> {
+        self.set_at_path(path::parse(path.as_ref())?, Value::Deleted)
+    }
+}
+
+/// One step of a `ValueEditor`'s path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    /// A key of a map
+    Key(String),
+    /// An index into a list, counting only elements that are not deleted
+    Index(usize),
+}
+
+/// A `Segment` resolved against the current state, with list indexes
+/// replaced by the stable positions of the elements they point at.
+enum Step {
+    Key(String),
+    Position(Position),
 }
 
 /// An editor for a `Value` obtained from a `Dict`.
 ///
 /// This provides a mutable lens into a value, allowing modifications
 /// to be staged and then saved back to the Dict.
+///
+/// Editors can navigate into maps with [`get_value_mut`](Self::get_value_mut)
+/// and into lists with [`index`](Self::index). Changes to a list element are
+/// staged against that element's position in the list, so they merge with
+/// concurrent changes to other elements instead of replacing the whole list.
+///
+/// # Example
+/// ```rust,no_run
+/// # use eidetica::subtree::Dict;
+/// # use eidetica::crdt::map::Value;
+/// # fn example(dict: &Dict) -> eidetica::Result<()> {
+/// let users = dict.get_value_mut("users");
+/// users.push(Value::Map(Default::default()))?;
+/// users.index(0).get_value_mut("name").set(Value::Text("Alice".into()))?;
+/// users.insert_at(0, Value::Text("first".into()))?;
+/// # Ok(())
+/// # }
+/// ```
 pub struct ValueEditor<'a> {
     kv_store: &'a Dict,
-    keys: Vec<String>,
+    path: Vec<Segment>,
 }
 
 impl<'a> ValueEditor<'a> {
@@ -491,7 +1036,28 @@ impl<'a> ValueEditor<'a> {
     {
         Self {
             kv_store,
-            keys: keys.into(),
+            path: keys.into().into_iter().map(Segment::Key).collect(),
+        }
+    }
+
+    /// The editor's path as map keys, or `None` if it goes through a list.
+    fn keys(&self) -> Option<Vec<String>> {
+        self.path
+            .iter()
+            .map(|segment| match segment {
+                Segment::Key(key) => Some(key.clone()),
+                Segment::Index(_) => None,
+            })
+            .collect()
+    }
+
+    /// An editor for the path one level deeper.
+    fn child(&self, segment: Segment) -> ValueEditor<'a> {
+        let mut path = self.path.clone();
+        path.push(segment);
+        ValueEditor {
+            kv_store: self.kv_store,
+            path,
         }
     }
 
@@ -507,7 +1073,10 @@ impl<'a> ValueEditor<'a> {
     /// Returns `Error::Io` with `ErrorKind::InvalidData` if a non-map value is encountered
     /// during path traversal where a map was expected.
     pub fn get(&self) -> Result<Value> {
-        self.kv_store.get_at_path(&self.keys)
+        match self.keys() {
+            Some(keys) => self.kv_store.get_at_path(&keys),
+            None => self.kv_store.get_at_segments(&self.path),
+        }
     }
 
     /// Sets a `Value` at the path specified by `self.keys` within the `Dict`'s `AtomicOp`.
@@ -519,9 +1088,17 @@ impl<'a> ValueEditor<'a> {
     /// If `self.keys` is empty (editor points to root), the provided `value` must
     /// be a `Value::Map`.
     ///
+    /// List indexes in the path must point at existing elements.
+    ///
     /// Returns `Error::InvalidOperation` if setting the root and `value` is not a map.
     pub fn set(&self, value: Value) -> Result<()> {
-        self.kv_store.set_at_path(&self.keys, value)
+        match self.keys() {
+            Some(keys) => self.kv_store.set_at_path(&keys, value),
+            None => {
+                let steps = self.kv_store.resolve_segments(&self.path)?;
+                self.kv_store.stage_at_steps(&steps, value)
+            }
+        }
     }
 
     /// Returns a nested value by appending `key` to the current editor's path.
@@ -529,24 +1106,75 @@ impl<'a> ValueEditor<'a> {
     /// This is a convenience method that uses `self.get()` to find the map at the current
     /// editor's path, and then retrieves `key` from that map.
     pub fn get_value(&self, key: impl AsRef<str>) -> Result<Value> {
-        let key = key.as_ref();
-        if self.keys.is_empty() {
-            // If the base path is empty, trying to get a sub-key implies trying to get a top-level key.
-            return self.kv_store.get_at_path([key]);
-        }
-
-        let mut path_to_value = self.keys.clone();
-        path_to_value.push(key.to_string());
-        self.kv_store.get_at_path(&path_to_value)
+        self.child(Segment::Key(key.as_ref().to_string())).get()
     }
 
     /// Constructs a new `ValueEditor` for a path one level deeper.
     ///
     /// The new editor's path will be `self.keys` with `key` appended.
     pub fn get_value_mut(&self, key: impl Into<String>) -> ValueEditor<'a> {
-        let mut new_keys = self.keys.clone();
-        new_keys.push(key.into());
-        ValueEditor::new(self.kv_store, new_keys)
+        self.child(Segment::Key(key.into()))
+    }
+
+    /// Constructs a new `ValueEditor` for the element at `index` of the list
+    /// at the current path.
+    ///
+    /// The index counts only elements that are not deleted, and is resolved to
+    /// the element's position when the new editor reads or writes.
+    pub fn index(&self, index: usize) -> ValueEditor<'a> {
+        self.child(Segment::Index(index))
+    }
+
+    /// Appends a value to the list at the editor's current path.
+    ///
+    /// If nothing is stored at the path, a new list is created.
+    ///
+    /// # Returns
+    /// The index of the new element
+    ///
+    /// # Errors
+    /// Returns `SubtreeError::TypeMismatch` if the value at the path is not a list.
+    pub fn push(&self, value: Value) -> Result<usize> {
+        let list = self.list_or_empty()?;
+        let index = list.len();
+        self.insert_into(&list, index, value)?;
+        Ok(index)
+    }
+
+    /// Inserts a value into the list at the editor's current path, so that it
+    /// ends up at `index`.
+    ///
+    /// If nothing is stored at the path, a new list is created.
+    ///
+    /// # Errors
+    /// Returns `SubtreeError::TypeMismatch` if the value at the path is not a
+    /// list, or `CRDTError::ListIndexOutOfBounds` if `index` is past its end.
+    pub fn insert_at(&self, index: usize, value: Value) -> Result<()> {
+        let list = self.list_or_empty()?;
+        self.insert_into(&list, index, value)
+    }
+
+    /// The list at the current path, or an empty list if there is nothing there.
+    fn list_or_empty(&self) -> Result<List> {
+        match self.get() {
+            Ok(Value::List(list)) => Ok(list),
+            Ok(other) => Err(SubtreeError::TypeMismatch {
+                subtree: self.kv_store.name.clone(),
+                expected: "list".to_string(),
+                actual: other.type_name().to_string(),
+            }
+            .into()),
+            Err(e) if e.is_not_found() => Ok(List::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Stages `value` at a new position in `list`, the current value at this path.
+    fn insert_into(&self, list: &List, index: usize, value: Value) -> Result<()> {
+        let position = list.insert_position(index)?;
+        let mut steps = self.kv_store.resolve_segments(&self.path)?;
+        steps.push(Step::Position(position));
+        self.kv_store.stage_at_steps(&steps, value)
     }
 
     /// Marks the value at the editor's current path as deleted.
@@ -562,8 +1190,162 @@ impl<'a> ValueEditor<'a> {
     ///
     /// If the editor points to the root (empty path), this will delete the top-level `key`.
     pub fn delete_child(&self, key: impl Into<String>) -> Result<()> {
-        let mut path_to_delete = self.keys.clone();
-        path_to_delete.push(key.into());
-        self.kv_store.set_at_path(&path_to_delete, Value::Deleted)
+        self.get_value_mut(key).delete_self()
+    }
+}
+
+impl Dict {
+    /// Describes a segment path for error messages, using the path string syntax.
+    fn describe_segments(segments: &[Segment]) -> String {
+        let parts: Vec<String> = segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Key(key) => key.clone(),
+                Segment::Index(index) => index.to_string(),
+            })
+            .collect();
+        path::join(&parts)
+    }
+
+    /// Retrieves the value at a path that may go through lists.
+    fn get_at_segments(&self, segments: &[Segment]) -> Result<Value> {
+        let not_found = || -> crate::Error {
+            SubtreeError::KeyNotFound {
+                subtree: self.name.clone(),
+                key: Self::describe_segments(segments),
+            }
+            .into()
+        };
+
+        let mut current = Value::Map(self.get_all()?);
+        for segment in segments {
+            current = match (segment, current) {
+                (Segment::Key(key), Value::Map(map)) => {
+                    map.get(key).cloned().ok_or_else(not_found)?
+                }
+                (Segment::Index(index), Value::List(list)) => {
+                    list.get(*index).cloned().ok_or_else(not_found)?
+                }
+                (_, Value::Deleted) => return Err(not_found()),
+                (segment, other) => {
+                    return Err(SubtreeError::TypeMismatch {
+                        subtree: self.name.clone(),
+                        expected: match segment {
+                            Segment::Key(_) => "Map".to_string(),
+                            Segment::Index(_) => "list".to_string(),
+                        },
+                        actual: other.type_name().to_string(),
+                    }
+                    .into());
+                }
+            };
+        }
+        match current {
+            Value::Deleted => Err(not_found()),
+            value => Ok(value),
+        }
+    }
+
+    /// Resolves the list indexes of a path to element positions in the current state.
+    ///
+    /// Keys that do not exist yet are allowed, like in `set_at_path`, but every
+    /// index must point at an existing element.
+    fn resolve_segments(&self, segments: &[Segment]) -> Result<Vec<Step>> {
+        let mut current = Some(Value::Map(self.get_all()?));
+        let mut steps = Vec::with_capacity(segments.len());
+        for segment in segments {
+            match segment {
+                Segment::Key(key) => {
+                    current = match current {
+                        Some(Value::Map(map)) => map.get(key).cloned(),
+                        _ => None,
+                    };
+                    steps.push(Step::Key(key.clone()));
+                }
+                Segment::Index(index) => {
+                    let Some(Value::List(list)) = current else {
+                        return Err(SubtreeError::TypeMismatch {
+                            subtree: self.name.clone(),
+                            expected: "list".to_string(),
+                            actual: current
+                                .map_or("nothing", |value| value.type_name())
+                                .to_string(),
+                        }
+                        .into());
+                    };
+                    let Some(position) = list.position(*index) else {
+                        return Err(CRDTError::ListIndexOutOfBounds {
+                            index: *index,
+                            len: list.len(),
+                        }
+                        .into());
+                    };
+                    current = list.get_by_position(position).cloned();
+                    steps.push(Step::Position(position.clone()));
+                }
+            }
+        }
+        Ok(steps)
+    }
+
+    /// Stages `value` at resolved `steps` in the operation's local data.
+    ///
+    /// Only the maps and list elements along the path are written, so merging
+    /// the local data into the current state changes nothing else.
+    fn stage_at_steps(&self, steps: &[Step], value: Value) -> Result<()> {
+        let local = self
+            .atomic_op
+            .get_local_data::<Map>(&self.name)
+            .unwrap_or_default();
+        let mut root = Value::Map(local);
+        stage_value(&mut root, steps, value);
+        let Value::Map(data) = root else {
+            return Err(SubtreeError::InvalidOperation {
+                subtree: self.name.clone(),
+                operation: "set".to_string(),
+                reason: "Root of a Dict must be a map".to_string(),
+            }
+            .into());
+        };
+        let serialized = serde_json::to_string(&data)?;
+        self.atomic_op.update_subtree(&self.name, &serialized)
+    }
+}
+
+/// Writes `value` at `steps` below `target`, creating maps and lists on the way.
+fn stage_value(target: &mut Value, steps: &[Step], value: Value) {
+    let Some((step, rest)) = steps.split_first() else {
+        *target = value;
+        return;
+    };
+    match step {
+        Step::Key(key) => {
+            if !matches!(target, Value::Map(_)) {
+                *target = Value::Map(Map::new());
+            }
+            let Value::Map(map) = target else {
+                unreachable!("target was just made a map")
+            };
+            let child = map
+                .as_hashmap_mut()
+                .entry(key.clone())
+                .or_insert_with(|| Value::Map(Map::new()));
+            stage_value(child, rest, value);
+        }
+        Step::Position(position) => {
+            if !matches!(target, Value::List(_)) {
+                *target = Value::List(List::new());
+            }
+            let Value::List(list) = target else {
+                unreachable!("target was just made a list")
+            };
+            if list.get_by_position(position).is_none() {
+                list.insert_at_position(position.clone(), Value::Map(Map::new()));
+            }
+            let child = list
+                .get_by_position_mut(position)
+                .expect("element was just inserted");
+            stage_value(child, rest, value);
+        }
     }
 }
@@ -0,0 +1,140 @@
+//! Payload encodings for subtree data
+//!
+//! Each subtree can declare in the tree's settings how the data it stores in
+//! entries is encoded. JSON is the default and keeps entries readable; CBOR is
+//! more compact for stores that hold mostly binary or numeric data, such as
+//! `BlobStore` and `YDoc`.
+//!
+//! Subtree implementations always stage JSON. When an operation is committed,
+//! the data of subtrees declared as CBOR is converted, and prefixed with
+//! `cbor:` so that readers recognize it whatever the current declaration says.
+//! Reading decodes either format, so changing a subtree's encoding only affects
+//! entries written afterwards. Writing CBOR requires the `cbor` feature; without
+//! it, data is written as JSON, which every replica can read.
+
+use crate::Result;
+use crate::crdt::Map;
+use crate::crdt::map::Value;
+use crate::subtree::SubtreeError;
+use serde::de::DeserializeOwned;
+use std::fmt;
+
+/// Settings key holding the declared encoding of each subtree, by subtree name.
+pub(crate) const ENCODINGS: &str = "encodings";
+
+/// Prefix of subtree data encoded as base64 CBOR.
+const CBOR_PREFIX: &str = "cbor:";
+
+/// How a subtree's data is encoded in entries.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum SubtreeEncoding {
+    /// JSON text, the default
+    #[default]
+    Json,
+    /// CBOR (RFC 8949), base64 encoded. Written only with the `cbor` feature.
+    Cbor,
+}
+
+impl SubtreeEncoding {
+    /// The name of the encoding, as stored in settings.
+    pub fn name(&self) -> &'static str {
+        match self {
+            SubtreeEncoding::Json => "json",
+            SubtreeEncoding::Cbor => "cbor",
+        }
+    }
+
+    /// The encoding with the given name, see [`name`](Self::name).
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "json" => Some(SubtreeEncoding::Json),
+            "cbor" => Some(SubtreeEncoding::Cbor),
+            _ => None,
+        }
+    }
+
+    /// The encoding declared for `subtree` in `settings`.
+    ///
+    /// Subtrees without a declaration, or with one this version does not
+    /// know, use JSON.
+    pub(crate) fn declared(settings: &Map, subtree: &str) -> Self {
+        match settings.get(ENCODINGS) {
+            Some(Value::Map(encodings)) => encodings
+                .get_text(subtree)
+                .and_then(Self::from_name)
+                .unwrap_or_default(),
+            _ => SubtreeEncoding::Json,
+        }
+    }
+
+    /// Converts staged JSON data of `subtree` to this encoding.
+    ///
+    /// Returns the data unchanged for JSON, and for CBOR without the `cbor`
+    /// feature.
+    #[cfg_attr(not(feature = "cbor"), allow(unused_variables))]
+    pub(crate) fn encode(&self, subtree: &str, json: &str) -> Result<String> {
+        match self {
+            SubtreeEncoding::Json => Ok(json.to_string()),
+            #[cfg(feature = "cbor")]
+            SubtreeEncoding::Cbor => {
+                use base64ct::{Base64, Encoding};
+
+                let value: serde_json::Value =
+                    serde_json::from_str(json).map_err(|e| SubtreeError::SerializationFailed {
+                        subtree: subtree.to_string(),
+                        reason: e.to_string(),
+                    })?;
+                let mut bytes = Vec::new();
+                ciborium::into_writer(&value, &mut bytes).map_err(|e| {
+                    SubtreeError::SerializationFailed {
+                        subtree: subtree.to_string(),
+                        reason: e.to_string(),
+                    }
+                })?;
+                Ok(format!("{CBOR_PREFIX}{}", Base64::encode_string(&bytes)))
+            }
+            #[cfg(not(feature = "cbor"))]
+            SubtreeEncoding::Cbor => Ok(json.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for SubtreeEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Deserializes subtree data stored in entries, in either encoding.
+///
+/// # Errors
+/// Returns `Error::Serialize` if the data does not match `T`, in either
+/// encoding, and `SubtreeError::DeserializationFailed` if CBOR data is not
+/// valid base64 or the `cbor` feature is not enabled.
+pub(crate) fn decode<T: DeserializeOwned>(subtree: &str, data: &str) -> Result<T> {
+    let Some(encoded) = data.strip_prefix(CBOR_PREFIX) else {
+        return Ok(serde_json::from_str(data)?);
+    };
+    let failed = |reason: String| SubtreeError::DeserializationFailed {
+        subtree: subtree.to_string(),
+        reason,
+    };
+
+    #[cfg(feature = "cbor")]
+    {
+        use base64ct::{Base64, Encoding};
+
+        let bytes = Base64::decode_vec(encoded).map_err(|e| failed(e.to_string()))?;
+        // Data that does not match `T` fails as it would for JSON
+        ciborium::from_reader(bytes.as_slice())
+            .map_err(|e| <serde_json::Error as serde::de::Error>::custom(e).into())
+    }
+    #[cfg(not(feature = "cbor"))]
+    {
+        let _ = encoded;
+        Err(
+            failed("data is CBOR encoded but the \"cbor\" feature is not enabled".to_string())
+                .into(),
+        )
+    }
+}
@@ -0,0 +1,511 @@
+use crate::Result;
+use crate::atomicop::AtomicOp;
+use crate::crdt::map::Value;
+use crate::crdt::{CRDT, Map};
+use crate::subtree::SubTree;
+use crate::subtree::errors::SubtreeError;
+use std::collections::{BTreeMap, HashSet};
+use uuid::Uuid;
+
+const NAME: &str = "name";
+const KIND: &str = "kind";
+const PARENT: &str = "parent";
+const CONTENT: &str = "content";
+const DELETED: &str = "deleted";
+
+const KIND_DIRECTORY: &str = "dir";
+const KIND_FILE: &str = "file";
+
+/// The kind of a node in a `FileTree`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NodeKind {
+    /// A directory, which may contain other nodes
+    Directory,
+    /// A file with text content
+    File,
+}
+
+/// A node in a `FileTree`, as seen in the merged state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileNode {
+    /// Stable identifier of the node, unchanged by renames and moves
+    pub id: String,
+    /// Name of the node within its parent directory
+    pub name: String,
+    /// ID of the parent directory, or `None` for nodes at the top level
+    pub parent: Option<String>,
+    /// Whether the node is a directory or a file
+    pub kind: NodeKind,
+}
+
+impl FileNode {
+    /// Returns true if this node is a directory.
+    pub fn is_dir(&self) -> bool {
+        self.kind == NodeKind::Directory
+    }
+}
+
+/// A node as stored, before parents are resolved.
+struct StoredNode {
+    name: String,
+    kind: NodeKind,
+    parent: Option<String>,
+    deleted: bool,
+}
+
+/// A directory/file hierarchy SubTree
+///
+/// `FileTree` models a folder-like hierarchy of directories and text files that
+/// can be edited concurrently. Every node has a stable UUID, and its name, parent
+/// and content are independent fields of a Map CRDT, so concurrent changes merge
+/// field by field:
+///
+/// - A rename and a move of the same node both apply; concurrent renames (or
+///   moves) of one node resolve to a single winner, so nodes are never duplicated.
+/// - Deletion sets a flag that is never cleared, so a delete wins over concurrent
+///   edits of the same node.
+/// - Nodes whose parent directory was deleted concurrently are shown under the
+///   nearest surviving ancestor (or the top level) rather than being lost.
+/// - Concurrent moves that would form a cycle (e.g. `a` into `b` and `b` into
+///   `a`) are broken deterministically by moving the node with the smallest ID
+///   in the cycle to the top level.
+///
+/// Names are not required to be unique within a directory, since two devices may
+/// create the same name concurrently. Listings are ordered by name, then ID, and
+/// path lookups use the first match in that order.
+///
+/// # Example
+/// ```
+/// # use eidetica::{backend::database::InMemory, basedb::BaseDB, subtree::FileTree};
+/// # let db = BaseDB::new(Box::new(InMemory::new()));
+/// # db.add_private_key("key").unwrap();
+/// # let tree = db.new_tree_default("key").unwrap();
+/// let op = tree.new_operation().unwrap();
+/// let files = op.get_subtree::<FileTree>("files").unwrap();
+///
+/// let notes = files.create_dir(None, "notes").unwrap();
+/// let todo = files.create_file(Some(&notes), "todo.md", "- write docs").unwrap();
+/// files.rename(&todo, "tasks.md").unwrap();
+///
+/// assert_eq!(files.path(&todo).unwrap(), "notes/tasks.md");
+/// assert_eq!(files.read_file(&todo).unwrap(), "- write docs");
+/// op.commit().unwrap();
+/// ```
+pub struct FileTree {
+    name: String,
+    atomic_op: AtomicOp,
+}
+
+impl SubTree for FileTree {
+    fn new(op: &AtomicOp, subtree_name: impl Into<String>) -> Result<Self> {
+        Ok(Self {
+            name: subtree_name.into(),
+            atomic_op: op.clone(),
+        })
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl FileTree {
+    /// Creates a directory and returns its ID.
+    ///
+    /// # Arguments
+    /// * `parent` - The directory to create it in, or `None` for the top level
+    /// * `name` - The name of the new directory
+    ///
+    /// # Errors
+    /// Returns an error if `parent` is not an existing directory.
+    pub fn create_dir(&self, parent: Option<&str>, name: impl Into<String>) -> Result<String> {
+        self.create_node(parent, name.into(), NodeKind::Directory, None)
+    }
+
+    /// Creates a file and returns its ID.
+    ///
+    /// # Arguments
+    /// * `parent` - The directory to create it in, or `None` for the top level
+    /// * `name` - The name of the new file
+    /// * `content` - The initial content of the file
+    ///
+    /// # Errors
+    /// Returns an error if `parent` is not an existing directory.
+    pub fn create_file(
+        &self,
+        parent: Option<&str>,
+        name: impl Into<String>,
+        content: impl Into<String>,
+    ) -> Result<String> {
+        self.create_node(parent, name.into(), NodeKind::File, Some(content.into()))
+    }
+
+    /// Gets a node by ID.
+    ///
+    /// # Errors
+    /// Returns `Error::NotFound` if the node does not exist or was deleted.
+    pub fn get(&self, id: impl AsRef<str>) -> Result<FileNode> {
+        let id = id.as_ref();
+        self.nodes()?.remove(id).ok_or_else(|| self.not_found(id))
+    }
+
+    /// Reads the content of a file.
+    ///
+    /// # Errors
+    /// Returns an error if the node does not exist or is a directory.
+    pub fn read_file(&self, id: impl AsRef<str>) -> Result<String> {
+        let id = id.as_ref();
+        let node = self.get(id)?;
+        if node.is_dir() {
+            return Err(self.invalid("read_file", format!("'{id}' is a directory")));
+        }
+        Ok(self
+            .get_all()?
+            .get_node(id)
+            .and_then(|node| node.get_text(CONTENT))
+            .unwrap_or_default()
+            .to_string())
+    }
+
+    /// Replaces the content of a file.
+    ///
+    /// # Errors
+    /// Returns an error if the node does not exist or is a directory.
+    pub fn write_file(&self, id: impl AsRef<str>, content: impl Into<String>) -> Result<()> {
+        let id = id.as_ref();
+        if self.get(id)?.is_dir() {
+            return Err(self.invalid("write_file", format!("'{id}' is a directory")));
+        }
+        self.stage(id, [(CONTENT, Value::Text(content.into()))])
+    }
+
+    /// Renames a node, keeping it in the same directory.
+    ///
+    /// # Errors
+    /// Returns an error if the node does not exist.
+    pub fn rename(&self, id: impl AsRef<str>, name: impl Into<String>) -> Result<()> {
+        let id = id.as_ref();
+        self.get(id)?;
+        self.stage(id, [(NAME, Value::Text(name.into()))])
+    }
+
+    /// Moves a node into another directory, keeping its name.
+    ///
+    /// # Arguments
+    /// * `id` - The node to move
+    /// * `parent` - The destination directory, or `None` for the top level
+    ///
+    /// # Errors
+    /// Returns an error if the node or destination does not exist, the destination
+    /// is not a directory, or the destination is the node itself or one of its
+    /// descendants.
+    pub fn move_node(&self, id: impl AsRef<str>, parent: Option<&str>) -> Result<()> {
+        let id = id.as_ref();
+        let nodes = self.nodes()?;
+        if !nodes.contains_key(id) {
+            return Err(self.not_found(id));
+        }
+        if let Some(parent) = parent {
+            self.check_directory(&nodes, parent, "move_node")?;
+            let mut ancestor = Some(parent);
+            while let Some(current) = ancestor {
+                if current == id {
+                    return Err(self.invalid(
+                        "move_node",
+                        format!("cannot move '{id}' into itself or a descendant"),
+                    ));
+                }
+                ancestor = nodes[current].parent.as_deref();
+            }
+        }
+        self.stage(id, [(PARENT, parent_value(parent))])
+    }
+
+    /// Deletes a node. Deleting a directory also deletes everything in it.
+    ///
+    /// # Errors
+    /// Returns an error if the node does not exist.
+    pub fn delete(&self, id: impl AsRef<str>) -> Result<()> {
+        let id = id.as_ref();
+        let nodes = self.nodes()?;
+        if !nodes.contains_key(id) {
+            return Err(self.not_found(id));
+        }
+
+        let mut doomed = vec![id.to_string()];
+        let mut i = 0;
+        while i < doomed.len() {
+            let current = doomed[i].clone();
+            doomed.extend(
+                nodes
+                    .values()
+                    .filter(|node| node.parent.as_deref() == Some(current.as_str()))
+                    .map(|node| node.id.clone()),
+            );
+            i += 1;
+        }
+
+        let mut data = self.local_data();
+        for node in doomed {
+            let mut fields = data.get_node(&node).cloned().unwrap_or_default();
+            fields.set(DELETED, true);
+            // Drop the content, it is no longer reachable
+            fields.set(CONTENT, Value::Deleted);
+            data.set(node, fields);
+        }
+        self.save_local_data(&data)
+    }
+
+    /// Lists the nodes in a directory, ordered by name then ID.
+    ///
+    /// # Arguments
+    /// * `parent` - The directory to list, or `None` for the top level
+    ///
+    /// # Errors
+    /// Returns an error if `parent` is not an existing directory.
+    pub fn children(&self, parent: Option<&str>) -> Result<Vec<FileNode>> {
+        let nodes = self.nodes()?;
+        if let Some(parent) = parent {
+            self.check_directory(&nodes, parent, "children")?;
+        }
+        let mut children: Vec<FileNode> = nodes
+            .into_values()
+            .filter(|node| node.parent.as_deref() == parent)
+            .collect();
+        children.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.id.cmp(&b.id)));
+        Ok(children)
+    }
+
+    /// Finds a node by its `/`-separated path from the top level.
+    ///
+    /// If a directory contains several nodes with the same name, the one with the
+    /// smallest ID is used.
+    ///
+    /// # Errors
+    /// Returns `Error::NotFound` if no node has the given path.
+    pub fn find(&self, path: impl AsRef<str>) -> Result<FileNode> {
+        let path = path.as_ref();
+        let nodes = self.nodes()?;
+        let mut current: Option<&FileNode> = None;
+        for segment in path.split('/').filter(|s| !s.is_empty()) {
+            let parent = current.map(|node| node.id.as_str());
+            current = nodes
+                .values()
+                .filter(|node| node.parent.as_deref() == parent && node.name == segment)
+                .min_by(|a, b| a.id.cmp(&b.id));
+            if current.is_none() {
+                break;
+            }
+        }
+        current.cloned().ok_or_else(|| self.not_found(path))
+    }
+
+    /// Returns the `/`-separated path of a node from the top level.
+    ///
+    /// # Errors
+    /// Returns `Error::NotFound` if the node does not exist or was deleted.
+    pub fn path(&self, id: impl AsRef<str>) -> Result<String> {
+        let id = id.as_ref();
+        let nodes = self.nodes()?;
+        let mut node = nodes.get(id).ok_or_else(|| self.not_found(id))?;
+        let mut segments = vec![node.name.as_str()];
+        while let Some(parent) = &node.parent {
+            node = &nodes[parent];
+            segments.push(node.name.as_str());
+        }
+        segments.reverse();
+        Ok(segments.join("/"))
+    }
+
+    /// Returns every node in the tree, keyed by ID.
+    ///
+    /// Parents are resolved as described in the type documentation, so every
+    /// returned node's parent is another returned directory or `None`.
+    pub fn nodes(&self) -> Result<BTreeMap<String, FileNode>> {
+        let data = self.get_all()?;
+        let stored: BTreeMap<String, StoredNode> = data
+            .as_hashmap()
+            .iter()
+            .filter_map(|(id, value)| match value {
+                Value::Map(fields) => stored_node(fields).map(|node| (id.clone(), node)),
+                _ => None,
+            })
+            .collect();
+
+        // Attach each live node to its nearest live directory ancestor
+        let mut parents: BTreeMap<&str, Option<&str>> = BTreeMap::new();
+        for (id, node) in stored.iter().filter(|(_, node)| !node.deleted) {
+            let mut visited = HashSet::new();
+            let mut parent = node.parent.as_deref();
+            while let Some(candidate) = parent {
+                match stored.get(candidate) {
+                    Some(dir)
+                        if !dir.deleted && dir.kind == NodeKind::Directory && candidate != id =>
+                    {
+                        break;
+                    }
+                    Some(skipped) if visited.insert(candidate) => {
+                        parent = skipped.parent.as_deref();
+                    }
+                    _ => parent = None,
+                }
+            }
+            parents.insert(id, parent);
+        }
+
+        // Break cycles formed by concurrent moves
+        let ids: Vec<&str> = parents.keys().copied().collect();
+        let mut rooted: HashSet<&str> = HashSet::new();
+        for id in ids {
+            let mut walk: Vec<&str> = Vec::new();
+            let mut current = Some(id);
+            while let Some(node) = current {
+                if rooted.contains(node) {
+                    break;
+                }
+                if let Some(start) = walk.iter().position(|n| *n == node) {
+                    let smallest = *walk[start..].iter().min().expect("cycle is not empty");
+                    parents.insert(smallest, None);
+                    break;
+                }
+                walk.push(node);
+                current = parents[node];
+            }
+            rooted.extend(walk);
+        }
+
+        Ok(parents
+            .into_iter()
+            .map(|(id, parent)| {
+                let node = &stored[id];
+                let file_node = FileNode {
+                    id: id.to_string(),
+                    name: node.name.clone(),
+                    parent: parent.map(str::to_string),
+                    kind: node.kind,
+                };
+                (id.to_string(), file_node)
+            })
+            .collect())
+    }
+
+    fn create_node(
+        &self,
+        parent: Option<&str>,
+        name: String,
+        kind: NodeKind,
+        content: Option<String>,
+    ) -> Result<String> {
+        if let Some(parent) = parent {
+            self.check_directory(&self.nodes()?, parent, "create")?;
+        }
+
+        let id = Uuid::new_v4().to_string();
+        let kind = match kind {
+            NodeKind::Directory => KIND_DIRECTORY,
+            NodeKind::File => KIND_FILE,
+        };
+        let mut fields = vec![
+            (NAME, Value::Text(name)),
+            (KIND, Value::Text(kind.to_string())),
+            (PARENT, parent_value(parent)),
+        ];
+        if let Some(content) = content {
+            fields.push((CONTENT, Value::Text(content)));
+        }
+        self.stage(&id, fields)?;
+        Ok(id)
+    }
+
+    fn check_directory(
+        &self,
+        nodes: &BTreeMap<String, FileNode>,
+        id: &str,
+        operation: &str,
+    ) -> Result<()> {
+        match nodes.get(id) {
+            Some(node) if node.is_dir() => Ok(()),
+            Some(_) => Err(self.invalid(operation, format!("'{id}' is not a directory"))),
+            None => Err(self.not_found(id)),
+        }
+    }
+
+    /// The merged state of the subtree, including changes staged in this operation.
+    fn get_all(&self) -> Result<Map> {
+        let data = self.atomic_op.get_full_state::<Map>(&self.name)?;
+        match self.atomic_op.get_local_data::<Map>(&self.name) {
+            Ok(local) => data.merge(&local),
+            Err(_) => Ok(data),
+        }
+    }
+
+    fn local_data(&self) -> Map {
+        self.atomic_op
+            .get_local_data::<Map>(&self.name)
+            .unwrap_or_default()
+    }
+
+    fn save_local_data(&self, data: &Map) -> Result<()> {
+        let serialized =
+            serde_json::to_string(data).map_err(|e| SubtreeError::SerializationFailed {
+                subtree: self.name.clone(),
+                reason: format!("Failed to serialize subtree data: {e}"),
+            })?;
+        self.atomic_op.update_subtree(&self.name, &serialized)
+    }
+
+    /// Stages changes to some fields of a node, leaving the others untouched.
+    fn stage<'a>(
+        &self,
+        id: &str,
+        changes: impl IntoIterator<Item = (&'a str, Value)>,
+    ) -> Result<()> {
+        let mut data = self.local_data();
+        let mut fields = data.get_node(id).cloned().unwrap_or_default();
+        for (field, value) in changes {
+            fields.set(field, value);
+        }
+        data.set(id, fields);
+        self.save_local_data(&data)
+    }
+
+    fn not_found(&self, key: &str) -> crate::Error {
+        SubtreeError::KeyNotFound {
+            subtree: self.name.clone(),
+            key: key.to_string(),
+        }
+        .into()
+    }
+
+    fn invalid(&self, operation: &str, reason: String) -> crate::Error {
+        SubtreeError::InvalidOperation {
+            subtree: self.name.clone(),
+            operation: operation.to_string(),
+            reason,
+        }
+        .into()
+    }
+}
+
+fn parent_value(parent: Option<&str>) -> Value {
+    Value::Text(parent.unwrap_or_default().to_string())
+}
+
+/// Parses a stored node, skipping nodes with missing or unknown fields.
+fn stored_node(fields: &Map) -> Option<StoredNode> {
+    let kind = match fields.get_text(KIND)? {
+        KIND_DIRECTORY => NodeKind::Directory,
+        KIND_FILE => NodeKind::File,
+        _ => return None,
+    };
+    let parent = fields
+        .get_text(PARENT)
+        .filter(|parent| !parent.is_empty())
+        .map(str::to_string);
+    Some(StoredNode {
+        name: fields.get_text(NAME)?.to_string(),
+        kind,
+        parent,
+        deleted: fields.get_bool(DELETED).unwrap_or(false),
+    })
+}
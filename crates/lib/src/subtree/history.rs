@@ -0,0 +1,57 @@
+//! Change history of individual keys
+//!
+//! `Dict::history` and `Table::history` walk the subtree's DAG and list every
+//! entry that changed a key, for audit trails and "who changed this" views.
+
+use crate::Result;
+use crate::atomicop::AtomicOp;
+use crate::auth::types::SigKey;
+use crate::clock::Hlc;
+use crate::crdt::Map;
+use crate::crdt::map::Value;
+use crate::entry::{AppInfo, ID};
+
+/// One change to a key, as returned by [`Dict::history`](super::Dict::history)
+/// and [`Table::history`](super::Table::history).
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyChange<V> {
+    /// The entry that made the change
+    pub entry: ID,
+    /// The key the entry was signed with
+    pub signer: SigKey,
+    /// When the entry was committed, if it was timestamped
+    pub timestamp: Option<Hlc>,
+    /// The device and application that made the change, if recorded
+    pub app_info: Option<AppInfo>,
+    /// The value of the key after the change, `None` if it was deleted
+    pub value: Option<V>,
+}
+
+/// Lists the changes to `key` in a `Map`-backed subtree, converting each
+/// value with `convert`.
+///
+/// Changes are in topological order. Concurrent changes are both listed, each
+/// with the value as of its own entry.
+pub(crate) fn key_history<V>(
+    op: &AtomicOp,
+    subtree: &str,
+    key: &str,
+    convert: impl Fn(&Value) -> Result<V>,
+) -> Result<Vec<KeyChange<V>>> {
+    op.subtree_history::<Map>(subtree, |data| data.children().contains_key(key))?
+        .into_iter()
+        .map(|(entry, state)| {
+            let value = match state.children().get(key) {
+                None | Some(Value::Deleted) => None,
+                Some(value) => Some(convert(value)?),
+            };
+            Ok(KeyChange {
+                entry: entry.id(),
+                signer: entry.sig.key.clone(),
+                timestamp: entry.timestamp(),
+                app_info: entry.app_info(),
+                value,
+            })
+        })
+        .collect()
+}
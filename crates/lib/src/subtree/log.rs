@@ -0,0 +1,164 @@
+use crate::Result;
+use crate::atomicop::{AtomicOp, local_state};
+use crate::crdt::Map;
+use crate::crdt::map::Value;
+use crate::subtree::SubTree;
+use crate::subtree::errors::SubtreeError;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::marker::PhantomData;
+use uuid::Uuid;
+
+/// An append-only event log SubTree
+///
+/// `Log` stores a sequence of items that are only ever appended, such as an
+/// audit trail or a stream of events. Items have no keys; they are read back in
+/// order by their sequence number, starting from 0.
+///
+/// The order is derived from the tree's DAG rather than from clocks: entries
+/// are ordered by height and then by entry ID, and the items appended by one
+/// entry keep the order they were appended in. Every replica with the same
+/// entries therefore sees the same sequence. Items appended concurrently on
+/// different replicas are interleaved once the replicas sync, so the sequence
+/// numbers of items from concurrent branches may shift until then. Items staged
+/// in the current operation come last.
+///
+/// Each item is stored under a unique key in a Map CRDT, so the merged state of
+/// the subtree, and any checkpoint of it, still holds every item.
+///
+/// # Example
+/// ```
+/// # use eidetica::{backend::database::InMemory, basedb::BaseDB, subtree::Log};
+/// # let db = BaseDB::new(Box::new(InMemory::new()));
+/// # db.add_private_key("key").unwrap();
+/// # let tree = db.new_tree_default("key").unwrap();
+/// let op = tree.new_operation().unwrap();
+/// let events = op.get_subtree::<Log<String>>("events").unwrap();
+/// events.append("started".to_string()).unwrap();
+/// events.append("stopped".to_string()).unwrap();
+/// op.commit().unwrap();
+///
+/// let events = tree.get_subtree_viewer::<Log<String>>("events").unwrap();
+/// assert_eq!(events.len().unwrap(), 2);
+/// let rest: Vec<String> = events.iter_from(1).unwrap().collect();
+/// assert_eq!(rest, vec!["stopped".to_string()]);
+/// ```
+pub struct Log<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    name: String,
+    atomic_op: AtomicOp,
+    phantom: PhantomData<T>,
+}
+
+impl<T> SubTree for Log<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    fn new(op: &AtomicOp, subtree_name: impl Into<String>) -> Result<Self> {
+        Ok(Self {
+            name: subtree_name.into(),
+            atomic_op: op.clone(),
+            phantom: PhantomData,
+        })
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl<T> Log<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    /// Appends an item to the end of the log.
+    ///
+    /// # Errors
+    /// Returns an error if the item cannot be serialized.
+    pub fn append(&self, item: T) -> Result<()> {
+        let serialized =
+            serde_json::to_string(&item).map_err(|e| SubtreeError::SerializationFailed {
+                subtree: self.name.clone(),
+                reason: format!("Failed to serialize log item: {e}"),
+            })?;
+
+        let mut data = self.local_data();
+        // Keys sort by their position in this entry, and are unique across entries
+        let key = format!("{:08}-{}", data.len(), Uuid::new_v4());
+        data.set(key, Value::Text(serialized));
+
+        let serialized =
+            serde_json::to_string(&data).map_err(|e| SubtreeError::SerializationFailed {
+                subtree: self.name.clone(),
+                reason: format!("Failed to serialize subtree data: {e}"),
+            })?;
+        self.atomic_op.update_subtree(&self.name, &serialized)
+    }
+
+    /// Returns the number of items in the log, including items staged in this
+    /// operation.
+    pub fn len(&self) -> Result<usize> {
+        let mut len = self.local_data().len();
+        for entry in self.atomic_op.subtree_entries(&self.name)? {
+            len += local_state::<Map>(&entry, &self.name)?.len();
+        }
+        Ok(len)
+    }
+
+    /// Returns true if the log has no items.
+    pub fn is_empty(&self) -> Result<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Returns the items of the log starting at sequence number `seq`.
+    ///
+    /// Returns an empty iterator if `seq` is past the end of the log.
+    ///
+    /// # Errors
+    /// Returns an error if an item cannot be deserialized into `T`.
+    pub fn iter_from(&self, seq: usize) -> Result<impl Iterator<Item = T> + use<T>> {
+        let mut batches = Vec::new();
+        for entry in self.atomic_op.subtree_entries(&self.name)? {
+            batches.push(local_state::<Map>(&entry, &self.name)?);
+        }
+        batches.push(self.local_data());
+
+        let mut items = Vec::new();
+        for batch in &batches {
+            let mut keyed: Vec<_> = batch.iter().collect();
+            keyed.sort_by_key(|&(key, _)| key);
+            items.extend(keyed.into_iter().map(|(_, value)| value));
+        }
+
+        items
+            .into_iter()
+            .skip(seq)
+            .map(|value| self.deserialize_item(value))
+            .collect::<Result<Vec<_>>>()
+            .map(Vec::into_iter)
+    }
+
+    fn deserialize_item(&self, value: &Value) -> Result<T> {
+        let text = value
+            .as_text()
+            .ok_or_else(|| SubtreeError::DataCorruption {
+                subtree: self.name.clone(),
+                reason: "log item is not stored as text".to_string(),
+            })?;
+        serde_json::from_str(text).map_err(|e| {
+            SubtreeError::DeserializationFailed {
+                subtree: self.name.clone(),
+                reason: format!("Failed to deserialize log item: {e}"),
+            }
+            .into()
+        })
+    }
+
+    fn local_data(&self) -> Map {
+        self.atomic_op
+            .get_local_data::<Map>(&self.name)
+            .unwrap_or_default()
+    }
+}
@@ -7,8 +7,44 @@ pub use errors::SubtreeError;
 mod dict;
 pub use dict::Dict;
 
+pub(crate) mod encoding;
+pub use encoding::SubtreeEncoding;
+
+mod history;
+pub use history::KeyChange;
+
 mod table;
-pub use table::Table;
+pub use table::{Page, Table};
+
+mod schema;
+pub(crate) use schema::SCHEMA_MARKER;
+pub use schema::TableSchema;
+
+pub mod record;
+pub use eidetica_macros::EideticaRecord;
+pub use record::Record;
+
+mod blob;
+pub use blob::BlobStore;
+
+mod filetree;
+pub use filetree::{FileNode, FileTree, NodeKind};
+
+mod tasklist;
+pub use tasklist::{TaskItem, TaskList};
+
+mod text;
+pub use text::Text;
+
+mod log;
+pub use log::Log;
+
+mod queue;
+pub use queue::{Queue, QueueItem};
+
+pub mod model;
+pub use eidetica_macros::DictModel;
+pub use model::{DictModel, Model, ModelValue};
 
 #[cfg(feature = "y-crdt")]
 mod ydoc;
@@ -0,0 +1,317 @@
+//! Typed struct storage on top of `Dict`.
+//!
+//! `DictModel` maps a Rust struct onto a nested `Map`, one key per field, so that
+//! configuration-like data can be read and written without hand-written
+//! `Value::Map` plumbing. It is normally implemented with `#[derive(DictModel)]`:
+//!
+//! ```
+//! # use eidetica::{backend::database::InMemory, basedb::BaseDB};
+//! use eidetica::subtree::{Dict, DictModel};
+//!
+//! #[derive(DictModel, Debug, PartialEq)]
+//! struct ServerConfig {
+//!     host: String,
+//!     port: u16,
+//!     motd: Option<String>,
+//! }
+//!
+//! # fn main() -> eidetica::Result<()> {
+//! # let db = BaseDB::new(Box::new(InMemory::new()));
+//! # db.add_private_key("key")?;
+//! # let tree = db.new_tree_default("key")?;
+//! let op = tree.new_operation()?;
+//! let dict = op.get_subtree::<Dict>("settings")?;
+//! let config = dict.model::<ServerConfig>("server");
+//! config.set(&ServerConfig { host: "localhost".into(), port: 8080, motd: None })?;
+//! config.set_field("port", 9090u16)?;
+//! assert_eq!(config.get()?.port, 9090);
+//! op.commit()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Because every field is its own key, concurrent updates to different fields
+//! of the same struct merge cleanly under the `Map` CRDT's last-write-wins rules.
+
+use crate::Result;
+use crate::crdt::Map;
+use crate::crdt::map::Value;
+use crate::subtree::errors::SubtreeError;
+use crate::subtree::{Dict, SubTree};
+use std::marker::PhantomData;
+
+/// A struct that can be stored as a nested `Map` inside a `Dict`.
+///
+/// Use `#[derive(DictModel)]` rather than implementing this by hand.
+pub trait DictModel: Sized {
+    /// The keys used to store each field, in declaration order.
+    const FIELDS: &'static [&'static str];
+
+    /// Converts the struct into a `Map` with one entry per field.
+    ///
+    /// `None` fields are written as tombstones so that clearing a field
+    /// overrides earlier values when merged.
+    fn to_map(&self) -> Map;
+
+    /// Reconstructs the struct from a `Map`.
+    ///
+    /// On failure returns the key of the first field that was missing or
+    /// had an incompatible type.
+    fn from_map(map: &Map) -> std::result::Result<Self, String>;
+}
+
+/// A field type that can be converted to and from a `Value`.
+pub trait ModelValue: Sized {
+    /// Converts the field into a `Value`.
+    fn to_value(&self) -> Value;
+
+    /// Converts a `Value` back into the field type, or `None` on a type mismatch.
+    fn from_value(value: &Value) -> Option<Self>;
+
+    /// Converts a possibly missing map entry into the field type.
+    ///
+    /// Missing entries are an error by default; `Option<T>` overrides this.
+    fn from_field(value: Option<&Value>) -> Option<Self> {
+        value.and_then(Self::from_value)
+    }
+}
+
+impl ModelValue for String {
+    fn to_value(&self) -> Value {
+        Value::Text(self.clone())
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        value.as_text().map(str::to_string)
+    }
+}
+
+impl ModelValue for bool {
+    fn to_value(&self) -> Value {
+        Value::Bool(*self)
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        value.as_bool()
+    }
+}
+
+macro_rules! impl_model_value_int {
+    ($($ty:ty),*) => {
+        $(
+            impl ModelValue for $ty {
+                fn to_value(&self) -> Value {
+                    Value::Int(*self as i64)
+                }
+
+                fn from_value(value: &Value) -> Option<Self> {
+                    value.as_int().and_then(|i| <$ty>::try_from(i).ok())
+                }
+            }
+        )*
+    };
+}
+
+impl_model_value_int!(i8, i16, i32, i64, u8, u16, u32);
+
+impl ModelValue for f64 {
+    fn to_value(&self) -> Value {
+        Value::Float(*self)
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        value.as_float()
+    }
+}
+
+impl ModelValue for f32 {
+    fn to_value(&self) -> Value {
+        Value::Float(*self as f64)
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        value.as_float().map(|f| f as f32)
+    }
+}
+
+impl ModelValue for Vec<u8> {
+    fn to_value(&self) -> Value {
+        Value::Bytes(self.clone())
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        value.as_bytes().map(<[u8]>::to_vec)
+    }
+}
+
+impl ModelValue for Map {
+    fn to_value(&self) -> Value {
+        Value::Map(self.clone())
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        value.as_node().cloned()
+    }
+}
+
+impl ModelValue for Value {
+    fn to_value(&self) -> Value {
+        self.clone()
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        Some(value.clone())
+    }
+}
+
+impl<T: ModelValue> ModelValue for Option<T> {
+    fn to_value(&self) -> Value {
+        match self {
+            Some(value) => value.to_value(),
+            None => Value::Deleted,
+        }
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Null | Value::Deleted => Some(None),
+            other => T::from_value(other).map(Some),
+        }
+    }
+
+    fn from_field(value: Option<&Value>) -> Option<Self> {
+        match value {
+            Some(value) => Self::from_value(value),
+            None => Some(None),
+        }
+    }
+}
+
+/// Serializes a `#[model(json)]` field as JSON text.
+#[doc(hidden)]
+pub fn json_to_value<T: serde::Serialize>(value: &T) -> Value {
+    serde_json::to_string(value)
+        .map(Value::Text)
+        .unwrap_or(Value::Null)
+}
+
+/// Deserializes a `#[model(json)]` field from JSON text.
+#[doc(hidden)]
+pub fn json_from_value<T: serde::de::DeserializeOwned>(value: Option<&Value>) -> Option<T> {
+    value
+        .and_then(Value::as_text)
+        .and_then(|text| serde_json::from_str(text).ok())
+}
+
+/// A typed handle to a `DictModel` stored at a path inside a `Dict`.
+///
+/// Obtained via [`Dict::model`]. Reads see the merged state including changes
+/// staged in the current operation; writes are staged in the operation.
+pub struct Model<'a, T> {
+    dict: &'a Dict,
+    path: Vec<String>,
+    phantom: PhantomData<T>,
+}
+
+impl<'a, T: DictModel> Model<'a, T> {
+    pub(crate) fn new(dict: &'a Dict, path: Vec<String>) -> Self {
+        Self {
+            dict,
+            path,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Returns the dotted path this model is stored at.
+    pub fn path(&self) -> String {
+        self.path.join(".")
+    }
+
+    /// Reads the whole struct.
+    ///
+    /// # Errors
+    /// * `SubtreeError::KeyNotFound` if nothing is stored at the path
+    /// * `SubtreeError::DeserializationFailed` if a field is missing or has the wrong type
+    pub fn get(&self) -> Result<T> {
+        let map = self.get_map()?;
+        T::from_map(&map).map_err(|field| {
+            SubtreeError::DeserializationFailed {
+                subtree: self.dict.name().to_string(),
+                reason: format!(
+                    "Field '{field}' of model at '{}' is missing or has an incompatible type",
+                    self.path()
+                ),
+            }
+            .into()
+        })
+    }
+
+    /// Writes the whole struct, replacing every field.
+    pub fn set(&self, value: &T) -> Result<()> {
+        self.dict
+            .set_at_path(&self.path, Value::Map(value.to_map()))
+    }
+
+    /// Reads a single field by its stored key.
+    ///
+    /// # Errors
+    /// * `SubtreeError::KeyNotFound` if the field is not one of `T::FIELDS`, or is not set
+    /// * `SubtreeError::TypeMismatch` if the stored value cannot be converted to `V`
+    pub fn get_field<V: ModelValue>(&self, field: &str) -> Result<V> {
+        self.check_field(field)?;
+        let map = self.get_map()?;
+        V::from_field(map.get(field)).ok_or_else(|| match map.get(field) {
+            None => SubtreeError::KeyNotFound {
+                subtree: self.dict.name().to_string(),
+                key: self.field_path(field),
+            }
+            .into(),
+            Some(value) => SubtreeError::TypeMismatch {
+                subtree: self.dict.name().to_string(),
+                expected: std::any::type_name::<V>().to_string(),
+                actual: value.type_name().to_string(),
+            }
+            .into(),
+        })
+    }
+
+    /// Writes a single field by its stored key, leaving the other fields untouched.
+    pub fn set_field<V: ModelValue>(&self, field: &str, value: V) -> Result<()> {
+        self.check_field(field)?;
+        let mut path = self.path.clone();
+        path.push(field.to_string());
+        self.dict.set_at_path(&path, value.to_value())
+    }
+
+    fn get_map(&self) -> Result<Map> {
+        match self.dict.get_at_path(&self.path)? {
+            Value::Map(map) => Ok(map),
+            other => Err(SubtreeError::TypeMismatch {
+                subtree: self.dict.name().to_string(),
+                expected: "Map".to_string(),
+                actual: other.type_name().to_string(),
+            }
+            .into()),
+        }
+    }
+
+    fn check_field(&self, field: &str) -> Result<()> {
+        if T::FIELDS.contains(&field) {
+            Ok(())
+        } else {
+            Err(SubtreeError::KeyNotFound {
+                subtree: self.dict.name().to_string(),
+                key: self.field_path(field),
+            }
+            .into())
+        }
+    }
+
+    fn field_path(&self, field: &str) -> String {
+        if self.path.is_empty() {
+            field.to_string()
+        } else {
+            format!("{}.{field}", self.path())
+        }
+    }
+}
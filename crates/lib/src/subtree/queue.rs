@@ -0,0 +1,274 @@
+use crate::Result;
+use crate::atomicop::AtomicOp;
+use crate::clock::wall_clock_ms;
+use crate::crdt::map::Value;
+use crate::crdt::{CRDT, Map};
+use crate::subtree::SubTree;
+use crate::subtree::errors::SubtreeError;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::marker::PhantomData;
+use uuid::Uuid;
+
+const ITEMS: &str = "items";
+const CLAIMS: &str = "claims";
+const ITEM: &str = "item";
+const PRIORITY: &str = "priority";
+const PUSHED: &str = "pushed";
+
+/// An item in a `Queue`, as returned by [`Queue::pop`] and [`Queue::peek`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueueItem<T> {
+    /// Stable identifier of the item, used to look up its claims
+    pub id: String,
+    /// The priority the item was pushed with
+    pub priority: i64,
+    /// The item itself
+    pub item: T,
+}
+
+/// A priority queue SubTree
+///
+/// `Queue` holds items to be processed, such as a job list shared by several
+/// workers. [`pop`](Self::pop) returns the item with the highest priority, and
+/// items with the same priority are returned in the order they were pushed.
+///
+/// Popping an item removes it from the queue with a tombstone and records a
+/// claim marker naming the operation's authentication key. Delivery is
+/// at-least-once:
+///
+/// - An item is only removed when the operation that popped it commits, so a
+///   worker that fails before committing leaves the item in the queue.
+/// - Replicas that pop the same item concurrently each get it. After they sync,
+///   the item has one claim marker per pop, so [`claims`](Self::claims) shows
+///   which items were processed more than once.
+///
+/// # Example
+/// ```
+/// # use eidetica::{backend::database::InMemory, basedb::BaseDB, subtree::Queue};
+/// # let db = BaseDB::new(Box::new(InMemory::new()));
+/// # db.add_private_key("key").unwrap();
+/// # let tree = db.new_tree_default("key").unwrap();
+/// let op = tree.new_operation().unwrap();
+/// let jobs = op.get_subtree::<Queue<String>>("jobs").unwrap();
+/// jobs.push(1, "index".to_string()).unwrap();
+/// jobs.push(5, "backup".to_string()).unwrap();
+/// op.commit().unwrap();
+///
+/// let op = tree.new_operation().unwrap();
+/// let jobs = op.get_subtree::<Queue<String>>("jobs").unwrap();
+/// let job = jobs.pop().unwrap().unwrap();
+/// assert_eq!(job.item, "backup");
+/// op.commit().unwrap();
+///
+/// let jobs = tree.get_subtree_viewer::<Queue<String>>("jobs").unwrap();
+/// assert_eq!(jobs.len().unwrap(), 1);
+/// assert_eq!(jobs.claims(&job.id).unwrap(), vec!["key".to_string()]);
+/// ```
+pub struct Queue<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    name: String,
+    atomic_op: AtomicOp,
+    phantom: PhantomData<T>,
+}
+
+impl<T> SubTree for Queue<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    fn new(op: &AtomicOp, subtree_name: impl Into<String>) -> Result<Self> {
+        Ok(Self {
+            name: subtree_name.into(),
+            atomic_op: op.clone(),
+            phantom: PhantomData,
+        })
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl<T> Queue<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    /// Pushes an item with the given priority and returns its ID.
+    ///
+    /// Items with a higher priority are popped first.
+    ///
+    /// # Errors
+    /// Returns an error if the item cannot be serialized.
+    pub fn push(&self, priority: i64, item: T) -> Result<String> {
+        let serialized =
+            serde_json::to_string(&item).map_err(|e| SubtreeError::SerializationFailed {
+                subtree: self.name.clone(),
+                reason: format!("Failed to serialize queue item: {e}"),
+            })?;
+
+        let mut data = self.local_data();
+        let mut items = data.get_node(ITEMS).cloned().unwrap_or_default();
+        // Items pushed by one operation within the same millisecond keep their order
+        let latest = items
+            .iter()
+            .filter_map(|(_, value)| match value {
+                Value::Map(fields) => fields.get_int(PUSHED),
+                _ => None,
+            })
+            .max();
+        let pushed = (wall_clock_ms() as i64).max(latest.map_or(0, |latest| latest + 1));
+
+        let mut fields = Map::new();
+        fields.set(ITEM, Value::Text(serialized));
+        fields.set(PRIORITY, Value::Int(priority));
+        fields.set(PUSHED, Value::Int(pushed));
+        let id = Uuid::new_v4().to_string();
+        items.set(id.clone(), fields);
+        data.set(ITEMS, items);
+        self.stage(&data)?;
+        Ok(id)
+    }
+
+    /// Returns the next item without removing it.
+    ///
+    /// # Errors
+    /// Returns an error if the item cannot be deserialized into `T`.
+    pub fn peek(&self) -> Result<Option<QueueItem<T>>> {
+        let data = self.get_all()?;
+        let Some((id, fields)) = next_item(&data) else {
+            return Ok(None);
+        };
+        Ok(Some(self.queue_item(id, fields)?))
+    }
+
+    /// Removes and returns the next item, recording a claim marker for it.
+    ///
+    /// The item is removed once this operation commits. Returns `None` if the
+    /// queue is empty.
+    ///
+    /// # Errors
+    /// Returns an error if the item cannot be deserialized into `T`.
+    pub fn pop(&self) -> Result<Option<QueueItem<T>>> {
+        let Some(next) = self.peek()? else {
+            return Ok(None);
+        };
+
+        let mut data = self.local_data();
+        let mut items = data.get_node(ITEMS).cloned().unwrap_or_default();
+        items.remove(&next.id);
+        data.set(ITEMS, items);
+
+        let mut claims = data.get_node(CLAIMS).cloned().unwrap_or_default();
+        let mut markers = claims.get_node(&next.id).cloned().unwrap_or_default();
+        let claimant = self.atomic_op.auth_key_name().unwrap_or_default();
+        markers.set(
+            Uuid::new_v4().to_string(),
+            Value::Text(claimant.to_string()),
+        );
+        claims.set(next.id.clone(), markers);
+        data.set(CLAIMS, claims);
+
+        self.stage(&data)?;
+        Ok(Some(next))
+    }
+
+    /// Returns the number of items in the queue.
+    pub fn len(&self) -> Result<usize> {
+        Ok(self
+            .get_all()?
+            .get_node(ITEMS)
+            .map_or(0, |items| items.len()))
+    }
+
+    /// Returns true if the queue has no items.
+    pub fn is_empty(&self) -> Result<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Returns the claimants of a popped item, one per pop.
+    ///
+    /// Each claimant is the name of the authentication key of the operation
+    /// that popped the item, or an empty string if it had none. More than one
+    /// claim means the item was popped concurrently on several replicas. Items
+    /// that were never popped have no claims.
+    pub fn claims(&self, id: impl AsRef<str>) -> Result<Vec<String>> {
+        let data = self.get_all()?;
+        let Some(markers) = data
+            .get_node(CLAIMS)
+            .and_then(|claims| claims.get_node(id.as_ref()))
+        else {
+            return Ok(Vec::new());
+        };
+        let mut markers: Vec<_> = markers.iter().collect();
+        markers.sort_by_key(|&(marker, _)| marker);
+        Ok(markers
+            .into_iter()
+            .filter_map(|(_, claimant)| claimant.as_text().map(str::to_string))
+            .collect())
+    }
+
+    fn queue_item(&self, id: &str, fields: &Map) -> Result<QueueItem<T>> {
+        let text = fields
+            .get(ITEM)
+            .and_then(|value| value.as_text())
+            .ok_or_else(|| SubtreeError::DataCorruption {
+                subtree: self.name.clone(),
+                reason: format!("queue item '{id}' has no stored value"),
+            })?;
+        let item = serde_json::from_str(text).map_err(|e| SubtreeError::DeserializationFailed {
+            subtree: self.name.clone(),
+            reason: format!("Failed to deserialize queue item: {e}"),
+        })?;
+        Ok(QueueItem {
+            id: id.to_string(),
+            priority: fields.get_int(PRIORITY).unwrap_or_default(),
+            item,
+        })
+    }
+
+    /// The merged state with this operation's changes applied.
+    fn get_all(&self) -> Result<Map> {
+        let data = self.atomic_op.get_full_state::<Map>(&self.name)?;
+        match self.atomic_op.get_local_data::<Map>(&self.name) {
+            Ok(local) => data.merge(&local),
+            Err(_) => Ok(data),
+        }
+    }
+
+    fn local_data(&self) -> Map {
+        self.atomic_op
+            .get_local_data::<Map>(&self.name)
+            .unwrap_or_default()
+    }
+
+    fn stage(&self, data: &Map) -> Result<()> {
+        let serialized =
+            serde_json::to_string(data).map_err(|e| SubtreeError::SerializationFailed {
+                subtree: self.name.clone(),
+                reason: format!("Failed to serialize subtree data: {e}"),
+            })?;
+        self.atomic_op.update_subtree(&self.name, &serialized)
+    }
+}
+
+/// Finds the item with the highest priority, pushed first.
+///
+/// Ties are broken by ID, so every replica picks the same item.
+fn next_item(data: &Map) -> Option<(&str, &Map)> {
+    data.get_node(ITEMS)?
+        .iter()
+        .filter_map(|(id, value)| match value {
+            Value::Map(fields) => Some((id.as_str(), fields)),
+            _ => None,
+        })
+        .min_by(|(a_id, a), (b_id, b)| {
+            let priority = |fields: &Map| fields.get_int(PRIORITY).unwrap_or_default();
+            let pushed = |fields: &Map| fields.get_int(PUSHED).unwrap_or_default();
+            priority(b)
+                .cmp(&priority(a))
+                .then_with(|| pushed(a).cmp(&pushed(b)))
+                .then_with(|| a_id.cmp(b_id))
+        })
+}
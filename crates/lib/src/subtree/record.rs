@@ -0,0 +1,88 @@
+//! Row types for `Table` that describe their own key, indexes and schema.
+//!
+//! A `Record` is a `Table` row type that knows which of its fields is the
+//! primary key, which fields are indexed, and which schema version it is, along
+//! with the migrations that upgrade rows written by older versions of the
+//! struct. It is normally implemented with `#[derive(EideticaRecord)]`:
+//!
+//! ```
+//! # use eidetica::{backend::database::InMemory, basedb::BaseDB};
+//! use eidetica::subtree::{EideticaRecord, Record, Table};
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(EideticaRecord, Serialize, Deserialize, Clone, Debug, PartialEq)]
+//! #[record(version = 2)]
+//! struct User {
+//!     #[record(key)]
+//!     username: String,
+//!     #[record(index)]
+//!     email: String,
+//!     // Called `name` up to version 1
+//!     #[record(renamed_from = "name", since = 2)]
+//!     display_name: String,
+//! }
+//!
+//! # fn main() -> eidetica::Result<()> {
+//! # let db = BaseDB::new(Box::new(InMemory::new()));
+//! # db.add_private_key("key")?;
+//! # let tree = db.new_tree_default("key")?;
+//! assert_eq!(User::KEY_FIELD, Some("username"));
+//! assert_eq!(User::INDEXED_FIELDS, ["email"]);
+//!
+//! let op = tree.new_operation()?;
+//! let users = op.get_subtree::<Table<User>>("users")?.with_record_schema();
+//! let key = users.put(User {
+//!     username: "alice".into(),
+//!     email: "alice@example.com".into(),
+//!     display_name: "Alice".into(),
+//! })?;
+//! assert_eq!(key, "alice");
+//! assert_eq!(users.find_by("email", "alice@example.com")?.len(), 1);
+//! op.commit()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Rows written through a table with the record schema attached are tagged with
+//! the record's version. Renaming or reshaping the struct then means bumping the
+//! version and declaring how old rows map onto the new layout, rather than
+//! having old rows fail to deserialize, or worse, deserialize with fields
+//! silently defaulted.
+
+use crate::subtree::TableSchema;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+/// A `Table` row type with a primary key, indexed fields and a versioned schema.
+///
+/// Use `#[derive(EideticaRecord)]` rather than implementing this by hand.
+/// Field names are the names the fields are serialized under.
+pub trait Record: Serialize + DeserializeOwned + Clone {
+    /// The field holding the primary key, if the record has one.
+    ///
+    /// Records without a key field are stored under generated keys.
+    const KEY_FIELD: Option<&'static str>;
+
+    /// The fields declared as indexed, in declaration order.
+    const INDEXED_FIELDS: &'static [&'static str];
+
+    /// Every field of the record, in declaration order.
+    const FIELDS: &'static [&'static str];
+
+    /// The schema version rows of this type are written at.
+    const SCHEMA_VERSION: u32;
+
+    /// The primary key of this row, if the record has a key field.
+    fn primary_key(&self) -> Option<String>;
+
+    /// The schema of this record type: its version and the migrations
+    /// upgrading rows written at older versions.
+    fn schema() -> TableSchema {
+        TableSchema::new(Self::SCHEMA_VERSION)
+    }
+
+    /// Returns true if `field` is declared as indexed.
+    fn is_indexed(field: &str) -> bool {
+        Self::INDEXED_FIELDS.contains(&field)
+    }
+}
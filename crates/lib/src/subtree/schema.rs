@@ -0,0 +1,211 @@
+//! Schema versions for `Table` records
+//!
+//! A `TableSchema` attached to a [`Table`](super::Table) tags every row it writes
+//! with the schema version, and upgrades older rows to the latest version when
+//! they are read. Rows written without a schema are treated as version 0.
+//!
+//! Upgrades are a chain of migrations over the row's JSON. Fields added to the
+//! record type don't need a migration if they have a `#[serde(default)]`; renamed,
+//! restructured or recomputed fields are handled by registering a migration for the
+//! version that last used the old layout. Reshaped record types can instead be
+//! upgraded with a typed conversion from the old struct, see
+//! [`TableSchema::upgrade`].
+//!
+//! Tables written through a schema also store the highest version written to them
+//! under a marker key, so that handles with an older schema refuse to write rows
+//! newer handles could no longer tell apart.
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::sync::Arc;
+
+/// Field of a tagged row holding its schema version.
+const VERSION_FIELD: &str = "_v";
+
+/// Field of a tagged row holding the record itself.
+const ROW_FIELD: &str = "row";
+
+/// Key of a versioned table holding the highest schema version written to it.
+///
+/// Its value is an integer, so it is never mistaken for a row.
+pub(crate) const SCHEMA_MARKER: &str = "_schema";
+
+type Migration = Arc<dyn Fn(&mut Value) -> Result<(), String> + Send + Sync>;
+
+/// The current version of a table's record type and how to upgrade older rows.
+///
+/// Cloning is cheap; migrations are shared between clones.
+///
+/// # Example
+/// ```
+/// # use eidetica::subtree::TableSchema;
+/// // Version 1 called the field "name"; version 2 renamed it to "title"
+/// let schema = TableSchema::new(2).rename_field(1, "name", "title");
+/// assert_eq!(schema.version(), 2);
+/// ```
+#[derive(Clone)]
+pub struct TableSchema {
+    version: u32,
+    /// Migrations by the version they upgrade from, in registration order
+    migrations: Vec<(u32, Migration)>,
+}
+
+impl std::fmt::Debug for TableSchema {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TableSchema")
+            .field("version", &self.version)
+            .field("migrations", &self.migrations.len())
+            .finish()
+    }
+}
+
+impl TableSchema {
+    /// Creates a schema whose rows are written at `version`.
+    pub fn new(version: u32) -> Self {
+        Self {
+            version,
+            migrations: Vec::new(),
+        }
+    }
+
+    /// The version rows are written and upgraded to.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Registers a migration upgrading rows from version `from` to `from + 1`.
+    ///
+    /// The migration receives the row's JSON and edits it in place. Several
+    /// migrations may be registered for the same version; they run in the order
+    /// they were registered.
+    pub fn migration(
+        mut self,
+        from: u32,
+        migrate: impl Fn(&mut Value) + Send + Sync + 'static,
+    ) -> Self {
+        self.migrations.push((
+            from,
+            Arc::new(move |row: &mut Value| {
+                migrate(row);
+                Ok(())
+            }),
+        ));
+        self
+    }
+
+    /// Adds a version whose rows are converted from the current version's record type.
+    ///
+    /// Rows at the current version are deserialized as `Old`, converted by
+    /// `convert`, and serialized again as the new version. The schema's version
+    /// goes up by one, so conversions can be chained from an empty schema:
+    ///
+    /// ```
+    /// # use eidetica::subtree::TableSchema;
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize)]
+    /// struct V0 { name: String }
+    /// #[derive(Serialize, Deserialize)]
+    /// struct V1 { first: String, last: String }
+    ///
+    /// let schema = TableSchema::new(0).upgrade(|v0: V0| {
+    ///     let (first, last) = v0.name.split_once(' ').unwrap_or((&v0.name, ""));
+    ///     V1 { first: first.to_string(), last: last.to_string() }
+    /// });
+    /// assert_eq!(schema.version(), 1);
+    /// ```
+    ///
+    /// A row that cannot be read as `Old` fails to decode.
+    pub fn upgrade<Old, New>(mut self, convert: impl Fn(Old) -> New + Send + Sync + 'static) -> Self
+    where
+        Old: DeserializeOwned,
+        New: Serialize,
+    {
+        let from = self.version;
+        self.migrations.push((
+            from,
+            Arc::new(move |row: &mut Value| {
+                let old: Old = serde_json::from_value(row.take())
+                    .map_err(|e| format!("row is not a version {from} record: {e}"))?;
+                *row = serde_json::to_value(convert(old))
+                    .map_err(|e| format!("failed to upgrade row from version {from}: {e}"))?;
+                Ok(())
+            }),
+        ));
+        self.version += 1;
+        self
+    }
+
+    /// Registers a migration renaming a field of rows at version `from`.
+    pub fn rename_field(self, from: u32, old: impl Into<String>, new: impl Into<String>) -> Self {
+        let (old, new) = (old.into(), new.into());
+        self.migration(from, move |row| {
+            if let Some(fields) = row.as_object_mut()
+                && let Some(value) = fields.remove(&old)
+            {
+                fields.insert(new.clone(), value);
+            }
+        })
+    }
+
+    /// Registers a migration transforming one field of rows at version `from`.
+    ///
+    /// `map` receives the field's value, or `Value::Null` if the row does not
+    /// have the field, and returns its new value.
+    pub fn map_field(
+        self,
+        from: u32,
+        field: impl Into<String>,
+        map: impl Fn(Value) -> Value + Send + Sync + 'static,
+    ) -> Self {
+        let field = field.into();
+        self.migration(from, move |row| {
+            if let Some(fields) = row.as_object_mut() {
+                let value = fields.remove(&field).unwrap_or(Value::Null);
+                fields.insert(field.clone(), map(value));
+            }
+        })
+    }
+
+    /// Serializes a record tagged with the current version.
+    pub(crate) fn encode<T: Serialize>(&self, row: &T) -> serde_json::Result<String> {
+        serde_json::to_string(&serde_json::json!({
+            VERSION_FIELD: self.version,
+            ROW_FIELD: row,
+        }))
+    }
+
+    /// Parses a stored row and upgrades it to the current version.
+    ///
+    /// # Returns
+    /// The version the row was stored at and the upgraded row, or a description
+    /// of why it could not be read.
+    pub(crate) fn decode(&self, stored: &str) -> std::result::Result<(u32, Value), String> {
+        let (version, mut row) = split(serde_json::from_str(stored).map_err(|e| e.to_string())?);
+        if version > self.version {
+            return Err(format!(
+                "row has schema version {version}, newer than the latest known version {}",
+                self.version
+            ));
+        }
+        for step in version..self.version {
+            for (_, migrate) in self.migrations.iter().filter(|(from, _)| *from == step) {
+                migrate(&mut row)?;
+            }
+        }
+        Ok((version, row))
+    }
+}
+
+/// Splits a stored row into its version and the record, treating untagged rows as version 0.
+fn split(stored: Value) -> (u32, Value) {
+    if let Value::Object(fields) = &stored
+        && fields.len() == 2
+        && let Some(version) = fields.get(VERSION_FIELD).and_then(Value::as_u64)
+        && let Ok(version) = u32::try_from(version)
+        && let Some(row) = fields.get(ROW_FIELD)
+    {
+        return (version, row.clone());
+    }
+    (0, stored)
+}
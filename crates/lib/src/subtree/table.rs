@@ -1,10 +1,22 @@
 use crate::Result;
 use crate::atomicop::AtomicOp;
+use crate::crdt::map::Value;
 use crate::crdt::{CRDT, Map};
+use crate::query::plan::{Plan, json_field};
+use crate::query::{Params, Query};
+use crate::subtree::SCHEMA_MARKER;
 use crate::subtree::SubTree;
+use crate::subtree::TableSchema;
 use crate::subtree::errors::SubtreeError;
+use crate::subtree::history::{KeyChange, key_history};
+use crate::subtree::record::Record;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::marker::PhantomData;
+use std::ops::{Bound, RangeBounds};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use uuid::Uuid;
 
 /// A Row-based SubTree
@@ -16,6 +28,9 @@ use uuid::Uuid;
 /// - Automatically generates UUIDv4 primary keys for new records
 /// - Provides CRUD operations (Create, Read, Update, Delete) for record-based data
 /// - Supports searching across all records with a predicate function
+/// - Supports ordered iteration, primary key ranges and paginated searches
+/// - Optionally versions records with a [`TableSchema`], upgrading older rows on read
+///   and rewriting them on the next write
 ///
 /// # Type Parameters
 /// - `T`: The record type to be stored, which must be serializable, deserializable, and cloneable
@@ -31,9 +46,24 @@ where
 {
     name: String,
     atomic_op: AtomicOp,
+    schema: Option<TableSchema>,
+    /// Rows upgraded from older versions by reads, re-encoded and waiting to be
+    /// staged by the next write
+    upgraded: Arc<Mutex<BTreeMap<String, String>>>,
+    /// Whether a write has checked the table's schema marker yet
+    marker_checked: AtomicBool,
     phantom: PhantomData<T>,
 }
 
+/// A page of rows returned by [`Table::search_paged`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page<T> {
+    /// The matching rows in this page, ordered by primary key
+    pub rows: Vec<(String, T)>,
+    /// Cursor to pass to the next call, or `None` if there are no more rows
+    pub next_cursor: Option<String>,
+}
+
 impl<T> SubTree for Table<T>
 where
     T: Serialize + for<'de> Deserialize<'de> + Clone,
@@ -42,6 +72,9 @@ where
         Ok(Self {
             name: subtree_name.into(),
             atomic_op: op.clone(),
+            schema: None,
+            upgraded: Arc::default(),
+            marker_checked: AtomicBool::new(false),
             phantom: PhantomData,
         })
     }
@@ -55,6 +88,77 @@ impl<T> Table<T>
 where
     T: Serialize + for<'de> Deserialize<'de> + Clone,
 {
+    /// Attaches a schema to this Table handle.
+    ///
+    /// Rows written through the handle are tagged with the schema's version, and
+    /// rows read through it are upgraded from their stored version by the schema's
+    /// migrations before being deserialized into `T`. Rows written without a schema
+    /// are read as version 0.
+    ///
+    /// # Arguments
+    /// * `schema` - The schema describing the current version of `T`
+    pub fn with_schema(mut self, schema: TableSchema) -> Self {
+        self.schema = Some(schema);
+        self
+    }
+
+    /// Adds a schema version converting rows of the previous record type into `T`.
+    ///
+    /// Rows stored at the previous version, or rows written without a schema if
+    /// this handle has none yet, are deserialized as `Old` and converted by
+    /// `migrate` when read. Each call adds one version, so a chain of record types
+    /// is upgraded by chaining calls from the oldest conversion to the newest,
+    /// with intermediate steps added to the schema by [`TableSchema::upgrade`].
+    ///
+    /// Rows upgraded by reads are rewritten at the new version, along with the
+    /// next write through this handle.
+    ///
+    /// # Example
+    /// ```
+    /// # use eidetica::{backend::database::InMemory, basedb::BaseDB, subtree::Table};
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize, Clone)]
+    /// struct ContactV1 { name: String }
+    /// #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+    /// struct Contact { name: String, emails: Vec<String> }
+    ///
+    /// # fn main() -> eidetica::Result<()> {
+    /// # let db = BaseDB::new(Box::new(InMemory::new()));
+    /// # db.add_private_key("key")?;
+    /// # let tree = db.new_tree_default("key")?;
+    /// # let op = tree.new_operation()?;
+    /// # let key = op.get_subtree::<Table<ContactV1>>("contacts")?.insert(ContactV1 { name: "Ada".into() })?;
+    /// # op.commit()?;
+    /// let op = tree.new_operation()?;
+    /// let contacts = op
+    ///     .get_subtree::<Table<Contact>>("contacts")?
+    ///     .with_migration(|v1: ContactV1| Contact { name: v1.name, emails: Vec::new() });
+    /// assert_eq!(contacts.get(&key)?.name, "Ada");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_migration<Old>(mut self, migrate: impl Fn(Old) -> T + Send + Sync + 'static) -> Self
+    where
+        Old: DeserializeOwned,
+    {
+        let schema = self.schema.take().unwrap_or_else(|| TableSchema::new(0));
+        self.schema = Some(schema.upgrade(migrate));
+        self
+    }
+
+    /// Returns the highest schema version written to this table.
+    ///
+    /// # Returns
+    /// * `Ok(Some(version))` - The version recorded by writes through a schema
+    /// * `Ok(None)` - If no row has been written through a schema
+    pub fn stored_schema_version(&self) -> Result<Option<u32>> {
+        Ok(self
+            .get_all()?
+            .get(SCHEMA_MARKER)
+            .and_then(Value::as_int)
+            .and_then(|version| u32::try_from(version).ok()))
+    }
+
     /// Retrieves a row from the Table by its primary key.
     ///
     /// This method first checks for the record in the current atomic operation's
@@ -65,29 +169,33 @@ where
     ///
     /// # Returns
     /// * `Ok(T)` - The retrieved record if found
-    /// * `Err(Error::NotFound)` - If no record exists with the given key
+    /// * `Err(Error::NotFound)` - If no record exists with the given key, or it was deleted
     ///
     /// # Errors
     /// Returns an error if:
-    /// * The record doesn't exist (`Error::NotFound`)
+    /// * The record doesn't exist or was deleted (`Error::NotFound`)
     /// * There's a serialization/deserialization error
     pub fn get(&self, key: impl AsRef<str>) -> Result<T> {
         let key = key.as_ref();
         // First check if there's any data in the atomic op itself
         let local_data: Result<Map> = self.atomic_op.get_local_data(&self.name);
 
-        // If there's data in the operation and it contains the key, return that
-        if let Ok(data) = local_data
-            && let Some(map_value) = data.get(key)
-            && let Some(value) = map_value.as_text()
-        {
-            return serde_json::from_str(value).map_err(|e| {
-                SubtreeError::DeserializationFailed {
+        if let Ok(data) = local_data {
+            // A record deleted in this operation is gone, whatever the backend holds
+            if data.is_tombstone(key) {
+                return Err(SubtreeError::KeyNotFound {
                     subtree: self.name.clone(),
-                    reason: format!("Failed to deserialize record for key '{key}': {e}"),
+                    key: key.to_string(),
                 }
-                .into()
-            });
+                .into());
+            }
+
+            // If the operation contains the key, return that
+            if let Some(map_value) = data.get(key)
+                && let Some(value) = map_value.as_text()
+            {
+                return self.deserialize_row(key, value);
+            }
         }
 
         // Otherwise, get the full state from the backend
@@ -95,13 +203,7 @@ where
 
         // Get the value
         match data.get(key).and_then(|v| v.as_text()) {
-            Some(value) => serde_json::from_str(value).map_err(|e| {
-                SubtreeError::DeserializationFailed {
-                    subtree: self.name.clone(),
-                    reason: format!("Failed to deserialize record for key '{key}': {e}"),
-                }
-                .into()
-            }),
+            Some(value) => self.deserialize_row(key, value),
             None => Err(SubtreeError::KeyNotFound {
                 subtree: self.name.clone(),
                 key: key.to_string(),
@@ -129,35 +231,56 @@ where
         // Generate a UUIDv4 for the primary key
         let primary_key = Uuid::new_v4().to_string();
 
-        // Get current data from the atomic op, or create new if not existing
-        let mut data = self
-            .atomic_op
-            .get_local_data::<Map>(&self.name)
-            .unwrap_or_default();
-
         // Serialize the row
         let serialized_row =
-            serde_json::to_string(&row).map_err(|e| SubtreeError::SerializationFailed {
-                subtree: self.name.clone(),
-                reason: format!("Failed to serialize record: {e}"),
-            })?;
-
-        // Update the data with the new row
-        data.set(primary_key.clone(), serialized_row);
+            self.serialize_row(&row)
+                .map_err(|e| SubtreeError::SerializationFailed {
+                    subtree: self.name.clone(),
+                    reason: format!("Failed to serialize record: {e}"),
+                })?;
 
-        // Serialize and update the atomic op
-        let serialized_data =
-            serde_json::to_string(&data).map_err(|e| SubtreeError::SerializationFailed {
-                subtree: self.name.clone(),
-                reason: format!("Failed to serialize subtree data: {e}"),
-            })?;
-        self.atomic_op
-            .update_subtree(&self.name, &serialized_data)?;
+        // Stage the new row
+        self.stage(|data| {
+            data.set(primary_key.clone(), serialized_row);
+        })?;
 
         // Return the primary key
         Ok(primary_key)
     }
 
+    /// Inserts many rows at once and returns their generated primary keys.
+    ///
+    /// Equivalent to calling [`insert`](Self::insert) for each row, but the
+    /// subtree's staged data is read and serialized once for the whole batch
+    /// instead of once per row. The keys are returned in the order of `rows`.
+    /// If any row fails to serialize, none of them are staged.
+    ///
+    /// # Arguments
+    /// * `rows` - The records to insert
+    ///
+    /// # Errors
+    /// Returns an error if a row cannot be serialized or the operation fails
+    pub fn insert_batch(&self, rows: Vec<T>) -> Result<Vec<String>> {
+        let mut staged = Vec::with_capacity(rows.len());
+        for row in &rows {
+            let serialized_row =
+                self.serialize_row(row)
+                    .map_err(|e| SubtreeError::SerializationFailed {
+                        subtree: self.name.clone(),
+                        reason: format!("Failed to serialize record: {e}"),
+                    })?;
+            staged.push((Uuid::new_v4().to_string(), serialized_row));
+        }
+
+        let keys = staged.iter().map(|(key, _)| key.clone()).collect();
+        self.stage(|data| {
+            for (key, row) in staged {
+                data.set(key, row);
+            }
+        })?;
+        Ok(keys)
+    }
+
     /// Updates an existing row in the Table with a new value.
     ///
     /// This method completely replaces the existing record with the provided one.
@@ -174,29 +297,71 @@ where
     /// Returns an error if there's a serialization error or the operation fails
     pub fn set(&self, key: impl AsRef<str>, row: T) -> Result<()> {
         let key_str = key.as_ref();
-        // Get current data from the atomic op, or create new if not existing
-        let mut data = self
-            .atomic_op
-            .get_local_data::<Map>(&self.name)
-            .unwrap_or_default();
 
         // Serialize the row
         let serialized_row =
-            serde_json::to_string(&row).map_err(|e| SubtreeError::SerializationFailed {
-                subtree: self.name.clone(),
-                reason: format!("Failed to serialize record for key '{key_str}': {e}"),
-            })?;
+            self.serialize_row(&row)
+                .map_err(|e| SubtreeError::SerializationFailed {
+                    subtree: self.name.clone(),
+                    reason: format!("Failed to serialize record for key '{key_str}': {e}"),
+                })?;
 
-        // Update the data
-        data.set(key_str.to_string(), serialized_row);
+        // Stage the updated row
+        self.stage(|data| {
+            data.set(key_str.to_string(), serialized_row);
+        })
+    }
 
-        // Serialize and update the atomic op
-        let serialized_data =
-            serde_json::to_string(&data).map_err(|e| SubtreeError::SerializationFailed {
+    /// Deletes a row from the Table.
+    ///
+    /// The row is replaced by a tombstone in the underlying Map, so the deletion
+    /// propagates through merges like any other change: `get` returns
+    /// `Error::NotFound` and `search` skips the row. Deleting a key that does not
+    /// exist still records the tombstone.
+    ///
+    /// # Arguments
+    /// * `key` - The primary key of the record to delete
+    ///
+    /// # Returns
+    /// * `Ok(())` - If the deletion was staged successfully
+    ///
+    /// # Errors
+    /// Returns an error if there's a serialization error or the operation fails
+    pub fn delete(&self, key: impl AsRef<str>) -> Result<()> {
+        let key_str = key.as_ref();
+
+        // Remove the row (creates a tombstone)
+        self.stage(|data| {
+            data.remove(key_str);
+        })
+    }
+
+    /// Lists the committed changes to a row, oldest first.
+    ///
+    /// Walks the history this `Table` builds on and returns every entry that
+    /// inserted, updated or deleted the row, with the key the entry was signed
+    /// with and the record after the change. Changes staged in the current
+    /// operation are not included.
+    ///
+    /// # Arguments
+    /// * `key` - The primary key of the row
+    ///
+    /// # Returns
+    /// * `Ok(Vec<KeyChange<T>>)` - The changes in topological order, empty if the row never existed
+    ///
+    /// # Errors
+    /// Returns an error if a version of the record cannot be deserialized
+    pub fn history(&self, key: impl AsRef<str>) -> Result<Vec<KeyChange<T>>> {
+        let key = key.as_ref();
+        key_history(&self.atomic_op, &self.name, key, |value| match value {
+            Value::Text(row) => deserialize_row(&self.name, self.schema.as_ref(), None, key, row),
+            other => Err(SubtreeError::TypeMismatch {
                 subtree: self.name.clone(),
-                reason: format!("Failed to serialize subtree data: {e}"),
-            })?;
-        self.atomic_op.update_subtree(&self.name, &serialized_data)
+                expected: "text".to_string(),
+                actual: other.type_name().to_string(),
+            }
+            .into()),
+        })
     }
 
     /// Searches for rows matching a predicate function.
@@ -210,33 +375,13 @@ where
     /// # Errors
     /// Returns an error if there's a serialization error or the operation fails
     pub fn search(&self, query: impl Fn(&T) -> bool) -> Result<Vec<(String, T)>> {
-        // Get the full state combining local and backend data
         let mut result = Vec::new();
 
-        // Get data from the atomic op if it exists
-        let local_data = self.atomic_op.get_local_data::<Map>(&self.name);
-
-        // Get the full state from the backend
-        let mut data = self.atomic_op.get_full_state::<Map>(&self.name)?;
-
-        // If there's also local data, merge it with the full state
-        if let Ok(local) = local_data {
-            data = data.merge(&local)?;
-        }
-
         // Iterate through all key-value pairs
-        for (key, map_value) in data.as_hashmap().iter() {
+        for (key, value) in self.get_all()?.iter() {
             // Skip non-text values
-            if let Some(value) = map_value.as_text() {
-                // Deserialize the row
-                let row: T = serde_json::from_str(value).map_err(|e| {
-                    SubtreeError::DeserializationFailed {
-                        subtree: self.name.clone(),
-                        reason: format!(
-                            "Failed to deserialize record for key '{key}' during search: {e}"
-                        ),
-                    }
-                })?;
+            if let Some(value) = value.as_text() {
+                let row = self.deserialize_row(key, value)?;
 
                 // Check if the row matches the query
                 if query(&row) {
@@ -247,4 +392,388 @@ where
 
         Ok(result)
     }
+
+    /// Runs a query against this Table, including changes staged in the operation.
+    ///
+    /// Rows are upgraded by the table's schema and deserialized before they are
+    /// matched, so conditions apply to the fields of `T` as it serializes. See
+    /// [`crate::query`] for how conditions are matched. Queries with parameters
+    /// are run with [`Tree::prepare`](crate::Tree::prepare) instead.
+    ///
+    /// # Returns
+    /// * `Ok(Vec<(String, T)>)` - The matching (primary_key, record) pairs in key
+    ///   order, up to the query's limit
+    ///
+    /// # Errors
+    /// Returns `QueryError::SubtreeMismatch` if the query reads another subtree,
+    /// `QueryError::MissingParameter` if it has parameters, or an error if a row
+    /// cannot be deserialized
+    pub fn query(&self, query: &Query) -> Result<Vec<(String, T)>> {
+        query.check_subtree(&self.name)?;
+        let plan = Plan::new(query)?;
+        let params = Params::new();
+        let operands = plan.operands(&params)?;
+        let rows = match plan.lookup_key(&params)? {
+            Some(key) => self.rows_in(Bound::Included(key), Bound::Included(key))?,
+            None => self.rows_in(Bound::Unbounded, Bound::Unbounded)?,
+        };
+
+        let limit = query.max_rows().unwrap_or(usize::MAX);
+        let mut matched = Vec::new();
+        for row in rows {
+            if matched.len() >= limit {
+                break;
+            }
+            let (key, row) = row?;
+            let document = serde_json::to_value(&row).ok();
+            if plan.matches(&key, &operands, |segments| {
+                document
+                    .as_ref()
+                    .and_then(|document| json_field(document, segments))
+            }) {
+                matched.push((key, row));
+            }
+        }
+        Ok(matched)
+    }
+
+    /// Returns an iterator over all rows, ordered by primary key.
+    ///
+    /// Rows are deserialized one at a time as the iterator advances, so callers
+    /// can stop early or process large tables without materializing every record.
+    ///
+    /// # Returns
+    /// * `Ok(iterator)` - An iterator of `Result<(primary_key, record)>` pairs
+    ///
+    /// # Errors
+    /// Returns an error if the subtree state cannot be loaded. Deserialization
+    /// errors are reported per row by the iterator.
+    pub fn iter(&self) -> Result<impl Iterator<Item = Result<(String, T)>> + use<T>> {
+        self.rows_in(Bound::Unbounded, Bound::Unbounded)
+    }
+
+    /// Returns an iterator over the rows whose primary keys fall within `range`.
+    ///
+    /// Keys are compared as strings, so the iteration order is the lexicographic
+    /// order of the primary keys, e.g. `table.iter_range("a".."n")`.
+    ///
+    /// # Arguments
+    /// * `range` - The range of primary keys to include
+    ///
+    /// # Returns
+    /// * `Ok(iterator)` - An iterator of `Result<(primary_key, record)>` pairs in key order
+    ///
+    /// # Errors
+    /// Returns an error if the subtree state cannot be loaded. Deserialization
+    /// errors are reported per row by the iterator.
+    pub fn iter_range<K, R>(
+        &self,
+        range: R,
+    ) -> Result<impl Iterator<Item = Result<(String, T)>> + use<T, K, R>>
+    where
+        K: AsRef<str>,
+        R: RangeBounds<K>,
+    {
+        self.rows_in(
+            range.start_bound().map(|k| k.as_ref()),
+            range.end_bound().map(|k| k.as_ref()),
+        )
+    }
+
+    /// Searches for rows matching a predicate function, one page at a time.
+    ///
+    /// Rows are visited in primary key order. Each call returns at most `limit`
+    /// matching rows along with a cursor; passing that cursor to the next call
+    /// continues the search after the last returned row. Rows inserted or deleted
+    /// between calls are picked up if their keys sort after the cursor.
+    ///
+    /// # Arguments
+    /// * `query` - A function that takes a reference to a record and returns a boolean
+    /// * `limit` - The maximum number of rows to return
+    /// * `cursor` - `None` for the first page, then the `next_cursor` of the previous page
+    ///
+    /// # Returns
+    /// * `Ok(Page<T>)` - The matching rows and the cursor for the next page
+    ///
+    /// # Errors
+    /// Returns an error if there's a serialization error or the operation fails
+    pub fn search_paged(
+        &self,
+        query: impl Fn(&T) -> bool,
+        limit: usize,
+        cursor: Option<&str>,
+    ) -> Result<Page<T>> {
+        let start = match cursor {
+            Some(cursor) => Bound::Excluded(cursor),
+            None => Bound::Unbounded,
+        };
+        let mut rows = Vec::new();
+        let mut remaining = self.rows_in(start, Bound::Unbounded)?;
+
+        for row in remaining.by_ref() {
+            let (key, row) = row?;
+            if query(&row) {
+                rows.push((key, row));
+                if rows.len() >= limit {
+                    break;
+                }
+            }
+        }
+
+        // Only hand out a cursor if the page is full and there may be more rows
+        let next_cursor = match rows.last() {
+            Some((key, _)) if rows.len() >= limit && remaining.next().is_some() => {
+                Some(key.clone())
+            }
+            _ => None,
+        };
+
+        Ok(Page { rows, next_cursor })
+    }
+
+    /// Rewrites every row stored at an older schema version at the current one.
+    ///
+    /// Reads already upgrade old rows on the fly; this stages the upgraded rows in
+    /// the operation so that, once committed, the migrations no longer need to run.
+    /// Rows already at the current version are left untouched. Without a schema
+    /// there are no versions to upgrade and nothing is rewritten.
+    ///
+    /// # Returns
+    /// * `Ok(usize)` - The number of rows rewritten
+    ///
+    /// # Errors
+    /// Returns an error if a row cannot be upgraded or there's a serialization error
+    pub fn rewrite_all(&self) -> Result<usize> {
+        let Some(schema) = &self.schema else {
+            return Ok(0);
+        };
+
+        let mut rewritten = Vec::new();
+        for (key, value) in self.get_all()?.iter() {
+            let Some(value) = value.as_text() else {
+                continue;
+            };
+            let (version, row) =
+                schema
+                    .decode(value)
+                    .map_err(|reason| SubtreeError::DeserializationFailed {
+                        subtree: self.name.clone(),
+                        reason: format!("Failed to deserialize record for key '{key}': {reason}"),
+                    })?;
+            if version == schema.version() {
+                continue;
+            }
+            // Round-trip through T so defaults for new fields are filled in
+            let row: T =
+                serde_json::from_value(row).map_err(|e| SubtreeError::DeserializationFailed {
+                    subtree: self.name.clone(),
+                    reason: format!("Failed to deserialize record for key '{key}': {e}"),
+                })?;
+            let serialized_row =
+                schema
+                    .encode(&row)
+                    .map_err(|e| SubtreeError::SerializationFailed {
+                        subtree: self.name.clone(),
+                        reason: format!("Failed to serialize record for key '{key}': {e}"),
+                    })?;
+            rewritten.push((key.clone(), serialized_row));
+        }
+
+        let count = rewritten.len();
+        if count > 0 {
+            self.stage(|data| {
+                for (key, row) in rewritten {
+                    data.set(key, row);
+                }
+            })?;
+        }
+        Ok(count)
+    }
+
+    /// Returns the rows with primary keys between `start` and `end`, in key order.
+    fn rows_in(
+        &self,
+        start: Bound<&str>,
+        end: Bound<&str>,
+    ) -> Result<impl Iterator<Item = Result<(String, T)>> + use<T>> {
+        let data = self.get_all()?;
+        let mut rows: Vec<(String, String)> = data
+            .iter()
+            .filter(|(key, _)| RangeBounds::<str>::contains(&(start, end), key.as_str()))
+            .filter_map(|(key, value)| Some((key.clone(), value.as_text()?.to_string())))
+            .collect();
+        rows.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+
+        let subtree = self.name.clone();
+        let schema = self.schema.clone();
+        let upgraded = Arc::clone(&self.upgraded);
+        Ok(rows.into_iter().map(move |(key, value)| {
+            let row = deserialize_row(&subtree, schema.as_ref(), Some(&upgraded), &key, &value)?;
+            Ok((key, row))
+        }))
+    }
+
+    /// Stages a change to the table in the operation.
+    ///
+    /// `update` edits the changes already staged for the table. Before it runs,
+    /// rows upgraded by earlier reads through this handle are staged at the
+    /// current version, and on the first write through a schema the table's
+    /// schema marker is checked and raised to the schema's version.
+    ///
+    /// # Errors
+    /// Returns `SubtreeError::InvalidOperation` if a newer schema version has
+    /// already been written to the table, or an error if staging fails
+    fn stage(&self, update: impl FnOnce(&mut Map)) -> Result<()> {
+        let mut data = self
+            .atomic_op
+            .get_local_data::<Map>(&self.name)
+            .unwrap_or_default();
+
+        if !self.marker_checked.load(Ordering::Acquire) {
+            let version = self.schema.as_ref().map_or(0, TableSchema::version);
+            let stored = self.stored_schema_version()?;
+            if let Some(stored) = stored
+                && stored > version
+            {
+                return Err(SubtreeError::InvalidOperation {
+                    subtree: self.name.clone(),
+                    operation: "write".to_string(),
+                    reason: format!(
+                        "table has schema version {stored}, newer than this handle's version {version}"
+                    ),
+                }
+                .into());
+            }
+            if self.schema.is_some() && stored != Some(version) {
+                data.set(SCHEMA_MARKER, version);
+            }
+            self.marker_checked.store(true, Ordering::Release);
+        }
+
+        // Rows changed in this operation already hold their latest value
+        let upgraded = std::mem::take(&mut *self.upgraded.lock().unwrap());
+        for (key, row) in upgraded {
+            if data.get(&key).is_none() && !data.is_tombstone(&key) {
+                data.set(key, row);
+            }
+        }
+
+        update(&mut data);
+
+        // Serialize and update the atomic op
+        let serialized_data =
+            serde_json::to_string(&data).map_err(|e| SubtreeError::SerializationFailed {
+                subtree: self.name.clone(),
+                reason: format!("Failed to serialize subtree data: {e}"),
+            })?;
+        self.atomic_op.update_subtree(&self.name, &serialized_data)
+    }
+
+    /// Gets the full state of the subtree, including changes staged in this operation.
+    fn get_all(&self) -> Result<Map> {
+        let data = self.atomic_op.get_full_state::<Map>(&self.name)?;
+        match self.atomic_op.get_local_data::<Map>(&self.name) {
+            Ok(local) => data.merge(&local),
+            Err(_) => Ok(data),
+        }
+    }
+
+    fn serialize_row(&self, row: &T) -> serde_json::Result<String> {
+        match &self.schema {
+            Some(schema) => schema.encode(row),
+            None => serde_json::to_string(row),
+        }
+    }
+
+    fn deserialize_row(&self, key: &str, value: &str) -> Result<T> {
+        deserialize_row(
+            &self.name,
+            self.schema.as_ref(),
+            Some(&self.upgraded),
+            key,
+            value,
+        )
+    }
+}
+
+impl<T: Record> Table<T> {
+    /// Attaches the schema declared by the record type, see [`Record::schema`].
+    pub fn with_record_schema(self) -> Self {
+        self.with_schema(T::schema())
+    }
+
+    /// Stores a row under its own primary key, replacing any row with that key.
+    ///
+    /// Rows of record types without a key field are inserted under a generated
+    /// key, like [`insert`](Self::insert).
+    ///
+    /// # Returns
+    /// The key the row was stored under
+    pub fn put(&self, row: T) -> Result<String> {
+        match row.primary_key() {
+            Some(key) => {
+                self.set(&key, row)?;
+                Ok(key)
+            }
+            None => self.insert(row),
+        }
+    }
+
+    /// Returns the rows whose indexed `field` equals `value`, with their keys.
+    ///
+    /// # Errors
+    /// Returns `SubtreeError::InvalidOperation` if the record type does not
+    /// declare `field` as indexed.
+    pub fn find_by(&self, field: &str, value: impl Serialize) -> Result<Vec<(String, T)>> {
+        if !T::is_indexed(field) {
+            return Err(SubtreeError::InvalidOperation {
+                subtree: self.name.clone(),
+                operation: "find_by".to_string(),
+                reason: format!("field '{field}' is not indexed"),
+            }
+            .into());
+        }
+        let value = serde_json::to_value(value).map_err(|e| SubtreeError::SerializationFailed {
+            subtree: self.name.clone(),
+            reason: format!("Failed to serialize value of '{field}': {e}"),
+        })?;
+        self.search(|row| {
+            serde_json::to_value(row)
+                .is_ok_and(|row| row.get(field).is_some_and(|found| *found == value))
+        })
+    }
+}
+
+/// Deserializes a stored row, upgrading it through `schema` if there is one.
+///
+/// Rows upgraded from an older version are re-encoded into `upgraded`, if given,
+/// to be rewritten by the next write.
+fn deserialize_row<T: Serialize + for<'de> Deserialize<'de>>(
+    subtree: &str,
+    schema: Option<&TableSchema>,
+    upgraded: Option<&Mutex<BTreeMap<String, String>>>,
+    key: &str,
+    value: &str,
+) -> Result<T> {
+    let failed = |reason: String| -> crate::Error {
+        SubtreeError::DeserializationFailed {
+            subtree: subtree.to_string(),
+            reason: format!("Failed to deserialize record for key '{key}': {reason}"),
+        }
+        .into()
+    };
+    match schema {
+        Some(schema) => {
+            let (version, row) = schema.decode(value).map_err(failed)?;
+            let row: T = serde_json::from_value(row).map_err(|e| failed(e.to_string()))?;
+            if version < schema.version()
+                && let Some(upgraded) = upgraded
+                && let Ok(encoded) = schema.encode(&row)
+            {
+                upgraded.lock().unwrap().insert(key.to_string(), encoded);
+            }
+            Ok(row)
+        }
+        None => serde_json::from_str(value).map_err(|e| failed(e.to_string())),
+    }
 }
@@ -0,0 +1,350 @@
+use crate::Result;
+use crate::atomicop::AtomicOp;
+use crate::crdt::map::Value;
+use crate::crdt::map::list::Position;
+use crate::crdt::{CRDT, Map};
+use crate::subtree::SubTree;
+use crate::subtree::errors::SubtreeError;
+use uuid::Uuid;
+
+const TEXT: &str = "text";
+const POSITION: &str = "position";
+const CHECKS: &str = "checks";
+const DELETED: &str = "deleted";
+
+/// An item in a `TaskList`, as seen in the merged state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskItem {
+    /// Stable identifier of the item, unchanged by edits and moves
+    pub id: String,
+    /// The text of the item
+    pub text: String,
+    /// Whether the item is checked off
+    pub checked: bool,
+}
+
+/// An item as stored, with its position for ordering.
+struct StoredItem {
+    position: Position,
+    item: TaskItem,
+}
+
+/// A checklist SubTree
+///
+/// `TaskList` is an ordered list of items, each with text and a checked state.
+/// Every item has a stable UUID and its fields are stored separately in a Map
+/// CRDT, so concurrent edits merge per item and per field instead of overwriting
+/// the whole list:
+///
+/// - Editing the text of one item and checking another both apply.
+/// - Items are ordered by a [`Position`], so concurrent inserts keep all items
+///   and concurrent moves of the same item resolve to a single winner.
+/// - The checked state is enable-wins: if one device checks an item while
+///   another unchecks it, the item ends up checked. Each check adds a unique
+///   marker and unchecking removes only the markers it has seen.
+/// - Removal sets a flag that is never cleared, so it wins over concurrent edits.
+///
+/// # Example
+/// ```
+/// # use eidetica::{backend::database::InMemory, basedb::BaseDB, subtree::TaskList};
+/// # let db = BaseDB::new(Box::new(InMemory::new()));
+/// # db.add_private_key("key").unwrap();
+/// # let tree = db.new_tree_default("key").unwrap();
+/// let op = tree.new_operation().unwrap();
+/// let tasks = op.get_subtree::<TaskList>("tasks").unwrap();
+///
+/// let milk = tasks.push("Buy milk").unwrap();
+/// tasks.insert(0, "Write list").unwrap();
+/// tasks.check(&milk).unwrap();
+///
+/// let items = tasks.items().unwrap();
+/// assert_eq!(items[0].text, "Write list");
+/// assert!(items[1].checked);
+/// op.commit().unwrap();
+/// ```
+pub struct TaskList {
+    name: String,
+    atomic_op: AtomicOp,
+}
+
+impl SubTree for TaskList {
+    fn new(op: &AtomicOp, subtree_name: impl Into<String>) -> Result<Self> {
+        Ok(Self {
+            name: subtree_name.into(),
+            atomic_op: op.clone(),
+        })
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl TaskList {
+    /// Appends an unchecked item to the end of the list and returns its ID.
+    pub fn push(&self, text: impl Into<String>) -> Result<String> {
+        let len = self.len()?;
+        self.insert(len, text)
+    }
+
+    /// Inserts an unchecked item at `index` and returns its ID.
+    ///
+    /// # Errors
+    /// Returns an error if `index` is greater than the number of items.
+    pub fn insert(&self, index: usize, text: impl Into<String>) -> Result<String> {
+        let position = self.position_for(index, None, "insert")?;
+        let id = Uuid::new_v4().to_string();
+        self.stage(
+            &id,
+            [
+                (TEXT, Value::Text(text.into())),
+                (POSITION, self.position_value(&position)?),
+            ],
+        )?;
+        Ok(id)
+    }
+
+    /// Gets an item by ID.
+    ///
+    /// # Errors
+    /// Returns `Error::NotFound` if the item does not exist or was removed.
+    pub fn get(&self, id: impl AsRef<str>) -> Result<TaskItem> {
+        let id = id.as_ref();
+        self.stored_items()?
+            .into_iter()
+            .find(|stored| stored.item.id == id)
+            .map(|stored| stored.item)
+            .ok_or_else(|| self.not_found(id))
+    }
+
+    /// Returns all items in list order.
+    pub fn items(&self) -> Result<Vec<TaskItem>> {
+        Ok(self
+            .stored_items()?
+            .into_iter()
+            .map(|stored| stored.item)
+            .collect())
+    }
+
+    /// Returns the number of items in the list.
+    pub fn len(&self) -> Result<usize> {
+        Ok(self.stored_items()?.len())
+    }
+
+    /// Returns true if the list has no items.
+    pub fn is_empty(&self) -> Result<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Replaces the text of an item.
+    ///
+    /// # Errors
+    /// Returns `Error::NotFound` if the item does not exist or was removed.
+    pub fn set_text(&self, id: impl AsRef<str>, text: impl Into<String>) -> Result<()> {
+        let id = id.as_ref();
+        self.get(id)?;
+        self.stage(id, [(TEXT, Value::Text(text.into()))])
+    }
+
+    /// Checks off an item. Does nothing if it is already checked.
+    ///
+    /// # Errors
+    /// Returns `Error::NotFound` if the item does not exist or was removed.
+    pub fn check(&self, id: impl AsRef<str>) -> Result<()> {
+        let id = id.as_ref();
+        if self.get(id)?.checked {
+            return Ok(());
+        }
+        let mut checks = self.local_checks(id);
+        checks.set(Uuid::new_v4().to_string(), true);
+        self.stage(id, [(CHECKS, Value::Map(checks))])
+    }
+
+    /// Unchecks an item. Does nothing if it is not checked.
+    ///
+    /// Only the checks visible to this operation are removed, so a concurrent
+    /// check on another device still wins.
+    ///
+    /// # Errors
+    /// Returns `Error::NotFound` if the item does not exist or was removed.
+    pub fn uncheck(&self, id: impl AsRef<str>) -> Result<()> {
+        let id = id.as_ref();
+        if !self.get(id)?.checked {
+            return Ok(());
+        }
+        let seen: Vec<String> = self
+            .get_all()?
+            .get_node(id)
+            .and_then(|fields| fields.get_node(CHECKS))
+            .map(|checks| checks.keys().cloned().collect())
+            .unwrap_or_default();
+        let mut checks = self.local_checks(id);
+        for marker in seen {
+            checks.remove(marker);
+        }
+        self.stage(id, [(CHECKS, Value::Map(checks))])
+    }
+
+    /// Moves an item so that it ends up at `index`.
+    ///
+    /// # Errors
+    /// Returns an error if the item does not exist or `index` is not a valid
+    /// index in the list.
+    pub fn move_to(&self, id: impl AsRef<str>, index: usize) -> Result<()> {
+        let id = id.as_ref();
+        self.get(id)?;
+        let position = self.position_for(index, Some(id), "move_to")?;
+        self.stage(id, [(POSITION, self.position_value(&position)?)])
+    }
+
+    /// Removes an item from the list.
+    ///
+    /// # Errors
+    /// Returns `Error::NotFound` if the item does not exist or was already removed.
+    pub fn remove(&self, id: impl AsRef<str>) -> Result<()> {
+        let id = id.as_ref();
+        self.get(id)?;
+        self.stage(id, [(DELETED, Value::Bool(true)), (TEXT, Value::Deleted)])
+    }
+
+    /// Returns the live items ordered by position.
+    fn stored_items(&self) -> Result<Vec<StoredItem>> {
+        let data = self.get_all()?;
+        let mut items: Vec<StoredItem> = data
+            .as_hashmap()
+            .iter()
+            .filter_map(|(id, value)| match value {
+                Value::Map(fields) => stored_item(id, fields),
+                _ => None,
+            })
+            .collect();
+        items.sort_by(|a, b| a.position.cmp(&b.position));
+        Ok(items)
+    }
+
+    /// Computes a position that places an item at `index`, ignoring `moving`.
+    fn position_for(
+        &self,
+        index: usize,
+        moving: Option<&str>,
+        operation: &str,
+    ) -> Result<Position> {
+        let positions: Vec<Position> = self
+            .stored_items()?
+            .into_iter()
+            .filter(|stored| Some(stored.item.id.as_str()) != moving)
+            .map(|stored| stored.position)
+            .collect();
+        let len = positions.len();
+        if index > len {
+            return Err(self.invalid(
+                operation,
+                format!("index {index} is out of bounds for {len} items"),
+            ));
+        }
+
+        Ok(
+            match (
+                index.checked_sub(1).map(|i| &positions[i]),
+                positions.get(index),
+            ) {
+                (None, None) => Position::beginning(),
+                (None, Some(first)) => Position::new(first.numerator - 1, first.denominator),
+                (Some(last), None) => {
+                    Position::new(last.numerator.saturating_add(1), last.denominator)
+                }
+                (Some(left), Some(right)) => Position::between(left, right),
+            },
+        )
+    }
+
+    /// The merged state of the subtree, including changes staged in this operation.
+    fn get_all(&self) -> Result<Map> {
+        let data = self.atomic_op.get_full_state::<Map>(&self.name)?;
+        match self.atomic_op.get_local_data::<Map>(&self.name) {
+            Ok(local) => data.merge(&local),
+            Err(_) => Ok(data),
+        }
+    }
+
+    fn local_data(&self) -> Map {
+        self.atomic_op
+            .get_local_data::<Map>(&self.name)
+            .unwrap_or_default()
+    }
+
+    /// The check markers of an item staged in this operation.
+    fn local_checks(&self, id: &str) -> Map {
+        self.local_data()
+            .get_node(id)
+            .and_then(|fields| fields.get_node(CHECKS))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Stages changes to some fields of an item, leaving the others untouched.
+    fn stage<'a>(
+        &self,
+        id: &str,
+        changes: impl IntoIterator<Item = (&'a str, Value)>,
+    ) -> Result<()> {
+        let mut data = self.local_data();
+        let mut fields = data.get_node(id).cloned().unwrap_or_default();
+        for (field, value) in changes {
+            fields.set(field, value);
+        }
+        data.set(id, fields);
+
+        let serialized =
+            serde_json::to_string(&data).map_err(|e| SubtreeError::SerializationFailed {
+                subtree: self.name.clone(),
+                reason: format!("Failed to serialize subtree data: {e}"),
+            })?;
+        self.atomic_op.update_subtree(&self.name, &serialized)
+    }
+
+    fn position_value(&self, position: &Position) -> Result<Value> {
+        let serialized =
+            serde_json::to_string(position).map_err(|e| SubtreeError::SerializationFailed {
+                subtree: self.name.clone(),
+                reason: format!("Failed to serialize item position: {e}"),
+            })?;
+        Ok(Value::Text(serialized))
+    }
+
+    fn not_found(&self, key: &str) -> crate::Error {
+        SubtreeError::KeyNotFound {
+            subtree: self.name.clone(),
+            key: key.to_string(),
+        }
+        .into()
+    }
+
+    fn invalid(&self, operation: &str, reason: String) -> crate::Error {
+        SubtreeError::InvalidOperation {
+            subtree: self.name.clone(),
+            operation: operation.to_string(),
+            reason,
+        }
+        .into()
+    }
+}
+
+/// Parses a stored item, skipping removed items and items with missing fields.
+fn stored_item(id: &str, fields: &Map) -> Option<StoredItem> {
+    if fields.get_bool(DELETED).unwrap_or(false) {
+        return None;
+    }
+    let position = serde_json::from_str(fields.get_text(POSITION)?).ok()?;
+    let checked = fields
+        .get_node(CHECKS)
+        .is_some_and(|checks| checks.keys().next().is_some());
+    Some(StoredItem {
+        position,
+        item: TaskItem {
+            id: id.to_string(),
+            text: fields.get_text(TEXT).unwrap_or_default().to_string(),
+            checked,
+        },
+    })
+}
@@ -0,0 +1,126 @@
+//! Collaborative text for Eidetica
+//!
+//! This module provides `Text`, a subtree holding a single string that several
+//! replicas can edit at once. It is backed by the native [`Rga`] CRDT, so
+//! concurrent insertions and deletions merge character by character without
+//! the `yrs` dependency of `YDoc`.
+
+use crate::Result;
+use crate::atomicop::AtomicOp;
+use crate::crdt::{CRDT, Rga};
+use crate::subtree::SubTree;
+use crate::subtree::errors::SubtreeError;
+
+/// A collaboratively edited string SubTree
+///
+/// Positions are counted in characters, not bytes. Each entry stores only the
+/// characters it inserted or deleted, and entries merge into the same text in
+/// any order; see [`Rga`] for how concurrent edits are ordered.
+///
+/// # Example
+/// ```
+/// # use eidetica::{backend::database::InMemory, basedb::BaseDB, subtree::Text};
+/// # let db = BaseDB::new(Box::new(InMemory::new()));
+/// # db.add_private_key("key").unwrap();
+/// # let tree = db.new_tree_default("key").unwrap();
+/// let op = tree.new_operation().unwrap();
+/// let title = op.get_subtree::<Text>("title").unwrap();
+/// title.push_str("Meeting notes").unwrap();
+/// title.insert(0, "Weekly ").unwrap();
+/// title.delete(14, 6).unwrap();
+/// assert_eq!(title.get().unwrap(), "Weekly Meeting");
+/// op.commit().unwrap();
+/// ```
+pub struct Text {
+    name: String,
+    atomic_op: AtomicOp,
+}
+
+impl SubTree for Text {
+    fn new(op: &AtomicOp, subtree_name: impl Into<String>) -> Result<Self> {
+        Ok(Self {
+            name: subtree_name.into(),
+            atomic_op: op.clone(),
+        })
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Text {
+    /// Returns the current text, including changes staged in this operation.
+    pub fn get(&self) -> Result<String> {
+        Ok(self.get_all()?.to_string())
+    }
+
+    /// Returns the number of characters in the text.
+    pub fn len(&self) -> Result<usize> {
+        Ok(self.get_all()?.len())
+    }
+
+    /// Returns true if the text is empty.
+    pub fn is_empty(&self) -> Result<bool> {
+        Ok(self.get_all()?.is_empty())
+    }
+
+    /// Inserts `text` before the character at `index`, or at the end if
+    /// `index` is the length of the text.
+    ///
+    /// # Errors
+    /// Returns `CRDTError::TextIndexOutOfBounds` if `index` is past the end.
+    pub fn insert(&self, index: usize, text: &str) -> Result<()> {
+        let inserted = self.get_all()?.insert(index, text)?;
+        self.stage(&inserted)
+    }
+
+    /// Appends `text` at the end.
+    pub fn push_str(&self, text: &str) -> Result<()> {
+        let mut state = self.get_all()?;
+        let inserted = state.insert(state.len(), text)?;
+        self.stage(&inserted)
+    }
+
+    /// Deletes `len` characters starting at `index`.
+    ///
+    /// # Errors
+    /// Returns `CRDTError::TextIndexOutOfBounds` if the range goes past the end.
+    pub fn delete(&self, index: usize, len: usize) -> Result<()> {
+        let deleted = self.get_all()?.delete(index, len)?;
+        self.stage(&deleted)
+    }
+
+    /// Replaces the whole text.
+    ///
+    /// This deletes every current character, so edits made concurrently
+    /// elsewhere in the text are kept around the new value rather than
+    /// overwritten by it.
+    pub fn set(&self, text: &str) -> Result<()> {
+        let mut state = self.get_all()?;
+        let deleted = state.delete(0, state.len())?;
+        let inserted = state.insert(0, text)?;
+        self.stage(&deleted.merge(&inserted)?)
+    }
+
+    /// The merged state of the subtree, including changes staged in this operation.
+    fn get_all(&self) -> Result<Rga> {
+        let state = self.atomic_op.get_full_state::<Rga>(&self.name)?;
+        state.merge(&self.local_data()?)
+    }
+
+    fn local_data(&self) -> Result<Rga> {
+        self.atomic_op.get_local_data::<Rga>(&self.name)
+    }
+
+    /// Adds changed characters to the data of this operation.
+    fn stage(&self, changes: &Rga) -> Result<()> {
+        let local = self.local_data()?.merge(changes)?;
+        let serialized =
+            serde_json::to_string(&local).map_err(|e| SubtreeError::SerializationFailed {
+                subtree: self.name.clone(),
+                reason: format!("Failed to serialize text: {e}"),
+            })?;
+        self.atomic_op.update_subtree(&self.name, &serialized)
+    }
+}
@@ -11,6 +11,9 @@
 //! - **Efficient Caching**: Caches expensive backend data retrieval operations
 //! - **Seamless Integration**: Works with Eidetica's atomic operation and viewer model
 //! - **Full Y-CRDT API**: Exposes the complete yrs library functionality
+//! - **Compaction**: Consolidates accumulated diffs into a checkpoint entry
+//! - **Reactive Integration**: Observes the updates committed to the subtree and
+//!   computes the updates a remote Yjs client is missing from its state vector
 //!
 //! # Performance Considerations
 //!
@@ -22,14 +25,18 @@
 
 use crate::Result;
 use crate::atomicop::AtomicOp;
+use crate::basedb::{CommitEvent, CommitFilter, CommitListenerId};
 use crate::crdt::{CRDT, Data};
-use crate::subtree::SubTree;
+use crate::entry::ID;
 use crate::subtree::errors::SubtreeError;
+use crate::subtree::{SubTree, encoding};
 use serde::{Deserialize, Serialize};
 use std::cell::RefCell;
+use std::sync::Arc;
 use thiserror::Error;
 use yrs::updates::decoder::Decode;
-use yrs::{Doc, ReadTxn, Transact, Update};
+use yrs::updates::encoder::Encode;
+use yrs::{Doc, ReadTxn, StateVector, Transact, Update};
 
 /// Errors specific to Y-CRDT operations
 #[derive(Debug, Error)]
@@ -400,6 +407,138 @@ impl YDoc {
         Ok(update)
     }
 
+    /// Gets the state vector of the current document, encoded with Yjs' v1 encoding.
+    ///
+    /// The state vector summarizes which updates of each client the document
+    /// contains. It is the first message of the Yjs sync handshake: a remote
+    /// client answers it with the updates this document is missing, which can be
+    /// applied with [`apply_update`](Self::apply_update). Likewise,
+    /// [`get_update_since`](Self::get_update_since) answers the state vector of a
+    /// remote client.
+    ///
+    /// ## Returns
+    /// A `Result` containing the encoded state vector.
+    pub fn state_vector(&self) -> Result<Vec<u8>> {
+        let doc = self.doc()?;
+        let txn = doc.transact();
+        Ok(txn.state_vector().encode_v1())
+    }
+
+    /// Gets the updates of the current document that are missing from a remote
+    /// document with the given state vector.
+    ///
+    /// This answers the first message of a Yjs sync handshake, a state vector
+    /// sent by a remote client, e.g. from `Y.encodeStateVector` in Yjs.
+    ///
+    /// ## Arguments
+    /// * `state_vector` - The remote document's state vector, with Yjs' v1 encoding
+    ///
+    /// ## Returns
+    /// A `Result` containing the binary update data the remote document is missing.
+    ///
+    /// ## Errors
+    /// Returns an error if the state vector is malformed.
+    pub fn get_update_since(&self, state_vector: &[u8]) -> Result<Vec<u8>> {
+        let state_vector = StateVector::decode_v1(state_vector).map_err(|e| {
+            SubtreeError::from(YDocError::InvalidData {
+                reason: format!("Failed to decode Y-CRDT state vector: {e}"),
+            })
+        })?;
+        let doc = self.doc()?;
+        let txn = doc.transact();
+        Ok(txn.encode_state_as_update_v1(&state_vector))
+    }
+
+    /// Calls `observer` with the Y-CRDT update of every entry committed to this
+    /// subtree from now on.
+    ///
+    /// This lets applications push changes to connected editors as other
+    /// operations commit them, without polling. Each update is the diff stored
+    /// by the committed entry, as saved by [`save_doc`](Self::save_doc), and can be
+    /// applied directly with `Y.applyUpdate` by a remote Yjs client.
+    ///
+    /// The observer is registered with the commit listeners of the tree, so it
+    /// is called for commits made through any operation on the tree, including
+    /// the one this `YDoc` belongs to, and keeps being called after this `YDoc`
+    /// is dropped. Like other commit listeners, it runs on the committing thread
+    /// and is not called for entries received through sync.
+    ///
+    /// ## Arguments
+    /// * `observer` - A function that receives the binary update data
+    ///
+    /// ## Returns
+    /// An ID that can be passed to [`unobserve`](Self::unobserve) or
+    /// [`BaseDB::remove_commit_listener`](crate::basedb::BaseDB::remove_commit_listener).
+    ///
+    /// ## Example
+    /// ```rust,no_run
+    /// # use eidetica::Result;
+    /// # fn example(store: &eidetica::subtree::YDoc) -> Result<()> {
+    /// let id = store.observe(|update| {
+    ///     // Send `update` to the connected editors
+    ///     println!("{} bytes committed", update.len());
+    /// });
+    /// store.unobserve(id);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn observe(&self, observer: impl Fn(&[u8]) + Send + Sync + 'static) -> CommitListenerId {
+        let tree = self.atomic_op.tree();
+        let backend = Arc::clone(tree.backend());
+        let name = self.name.clone();
+        let filter = CommitFilter::new().tree(tree.root_id().clone());
+        tree.commit_listeners().add(
+            Some(filter),
+            Arc::new(move |event: &CommitEvent| {
+                let Ok(entry) = backend.get(&event.entry) else {
+                    return;
+                };
+                let Ok(data) = entry.data(&name) else {
+                    return;
+                };
+                if let Ok(update) = encoding::decode::<YrsBinary>(&name, data)
+                    && !update.is_empty()
+                {
+                    observer(update.as_bytes());
+                }
+            }),
+        )
+    }
+
+    /// Stops calling an observer registered with [`observe`](Self::observe).
+    ///
+    /// ## Returns
+    /// `true` if the observer was registered.
+    pub fn unobserve(&self, id: CommitListenerId) -> bool {
+        self.atomic_op.tree().commit_listeners().remove(id)
+    }
+
+    /// Writes the merged state of the document as a checkpoint entry.
+    ///
+    /// Each commit stores an incremental diff, so a long-lived document
+    /// accumulates many small updates, all of which are replayed to read it. The
+    /// checkpoint stores the state of all of them as a single update, and
+    /// reading the document at the checkpoint or any later entry starts from it
+    /// instead of replaying older diffs. The checkpoint itself does not change
+    /// the document, and the older diffs are kept, so history and sync with
+    /// replicas that have not seen the checkpoint are unaffected.
+    ///
+    /// The checkpoint is committed in an operation of its own on the tree's
+    /// current tips, signed with the tree's default key. Changes staged in this
+    /// `YDoc`'s operation are neither included nor committed.
+    ///
+    /// ## Returns
+    /// A `Result` containing the ID of the checkpoint entry.
+    ///
+    /// ## Errors
+    /// Returns `SubtreeError::InvalidOperation` if the subtree has no entries,
+    /// or an error if the checkpoint cannot be committed.
+    pub fn compact(&self) -> Result<ID> {
+        let op = self.atomic_op.tree().new_operation()?;
+        op.stage_checkpoint_as::<YrsBinary>(&self.name)?;
+        op.commit()
+    }
+
     /// Saves the complete document state to the atomic operation.
     ///
     /// This method captures the entire current state of the document and stages it
@@ -497,12 +636,12 @@ impl YDoc {
     ///
     /// ## Errors
     /// Returns an error if the cached backend data cannot be decoded or applied.
-    fn get_initial_state_vector(&self) -> Result<yrs::StateVector> {
+    fn get_initial_state_vector(&self) -> Result<StateVector> {
         // Get the cached backend data
         let backend_data = self.get_cached_backend_data()?;
 
         if backend_data.is_empty() {
-            return Ok(yrs::StateVector::default());
+            return Ok(StateVector::default());
         }
 
         // Construct a temporary document to extract the state vector
@@ -0,0 +1,185 @@
+//! Sync error types for the Eidetica library.
+//!
+//! This module defines structured error types for exchanging entries with a
+//! remote peer, covering transport framing, protocol violations and invalid
+//! data received from the peer.
+
+use crate::entry::ID;
+use thiserror::Error;
+
+/// Errors that can occur while syncing with a remote peer.
+///
+/// # Stability
+///
+/// - New variants may be added in minor versions (enum is `#[non_exhaustive]`)
+/// - Existing variants will not be removed in minor versions
+/// - Field additions/changes require a major version bump
+/// - Helper methods like `is_*()` provide stable APIs
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum SyncError {
+    /// The peer closed the connection in the middle of a sync.
+    #[error("Connection closed by peer")]
+    ConnectionClosed,
+
+    /// A message frame exceeded the maximum allowed size.
+    #[error("Message of {size} bytes exceeds the maximum of {max} bytes")]
+    MessageTooLarge {
+        /// The size of the rejected message
+        size: usize,
+        /// The maximum allowed message size
+        max: usize,
+    },
+
+    /// The peer speaks an incompatible protocol version.
+    #[error("Incompatible sync protocol version: local {local}, remote {remote}")]
+    VersionMismatch {
+        /// The local protocol version
+        local: u32,
+        /// The protocol version sent by the peer
+        remote: u32,
+    },
+
+    /// The peer sent a message that is not valid at this point of the exchange.
+    #[error("Unexpected sync message: expected {expected}, got {actual}")]
+    UnexpectedMessage {
+        /// The message that was expected
+        expected: String,
+        /// The message that was received
+        actual: String,
+    },
+
+    /// The peer sent an entry that does not belong to the tree being synced.
+    #[error("Received entry '{entry_id}' does not belong to tree '{tree_id}'")]
+    EntryNotInTree {
+        /// The ID of the received entry
+        entry_id: ID,
+        /// The ID of the tree being synced
+        tree_id: ID,
+    },
+
+    /// The peer chose a compression algorithm that was not offered to it.
+    #[error("Peer chose unsupported compression '{name}'")]
+    UnsupportedCompression {
+        /// The name of the algorithm chosen by the peer
+        name: String,
+    },
+
+    /// A network transport could not be set up or has stopped.
+    #[error("Sync transport failed: {reason}")]
+    TransportFailed {
+        /// What went wrong
+        reason: String,
+    },
+
+    /// A node ticket could not be parsed.
+    #[error("Invalid node ticket: {reason}")]
+    InvalidTicket {
+        /// Why the ticket is invalid
+        reason: String,
+    },
+
+    /// The peer reported that it failed to process the sync.
+    #[error("Remote peer reported an error: {reason}")]
+    RemoteError {
+        /// The error message sent by the peer
+        reason: String,
+    },
+}
+
+impl SyncError {
+    /// Check if this error was caused by the transport rather than the data.
+    pub fn is_connection_error(&self) -> bool {
+        matches!(
+            self,
+            SyncError::ConnectionClosed
+                | SyncError::MessageTooLarge { .. }
+                | SyncError::TransportFailed { .. }
+        )
+    }
+
+    /// Check if this error indicates the peer violated the sync protocol.
+    pub fn is_protocol_error(&self) -> bool {
+        matches!(
+            self,
+            SyncError::VersionMismatch { .. }
+                | SyncError::UnexpectedMessage { .. }
+                | SyncError::EntryNotInTree { .. }
+                | SyncError::UnsupportedCompression { .. }
+        )
+    }
+
+    /// Check if this error was reported by the remote peer.
+    pub fn is_remote_error(&self) -> bool {
+        matches!(self, SyncError::RemoteError { .. })
+    }
+
+    /// Get the entry ID if this error is about a specific entry.
+    pub fn entry_id(&self) -> Option<&ID> {
+        match self {
+            SyncError::EntryNotInTree { entry_id, .. } => Some(entry_id),
+            _ => None,
+        }
+    }
+}
+
+// Conversion from SyncError to the main Error type
+impl From<SyncError> for crate::Error {
+    fn from(err: SyncError) -> Self {
+        crate::Error::Sync(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_helpers() {
+        let err = SyncError::ConnectionClosed;
+        assert!(err.is_connection_error());
+        assert!(!err.is_protocol_error());
+
+        let err = SyncError::TransportFailed {
+            reason: "no listeners".to_string(),
+        };
+        assert!(err.is_connection_error());
+        assert!(!err.is_remote_error());
+
+        let err = SyncError::InvalidTicket {
+            reason: "missing prefix".to_string(),
+        };
+        assert!(!err.is_connection_error());
+        assert!(!err.is_protocol_error());
+
+        let err = SyncError::VersionMismatch {
+            local: 1,
+            remote: 2,
+        };
+        assert!(err.is_protocol_error());
+
+        let err = SyncError::EntryNotInTree {
+            entry_id: ID::from("entry"),
+            tree_id: ID::from("tree"),
+        };
+        assert!(err.is_protocol_error());
+        assert_eq!(err.entry_id(), Some(&ID::from("entry")));
+
+        let err = SyncError::RemoteError {
+            reason: "failed".to_string(),
+        };
+        assert!(err.is_remote_error());
+        assert_eq!(err.entry_id(), None);
+    }
+
+    #[test]
+    fn test_error_conversion() {
+        let err: crate::Error = SyncError::ConnectionClosed.into();
+        assert_eq!(err.module(), "sync");
+        assert!(err.is_io_error());
+        match err {
+            crate::Error::Sync(SyncError::ConnectionClosed) => {}
+            _ => panic!("Unexpected error variant"),
+        }
+    }
+}
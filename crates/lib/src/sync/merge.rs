@@ -0,0 +1,165 @@
+//! Computing and merging the entries exchanged during a sync
+
+use super::errors::SyncError;
+use super::verify::VerificationPool;
+use crate::Result;
+use crate::Tree;
+use crate::backend::{Database, VerificationStatus};
+use crate::basedb::EphemeralState;
+use crate::basedb::ephemeral::ephemeral_subtrees;
+use crate::crdt::Map;
+use crate::entry::{Entry, ID};
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+
+/// Returns the entries of `tree` that a peer with the given tips is missing,
+/// sorted so that parents come before their children.
+///
+/// The peer has everything reachable from those of its tips that are known
+/// locally. Tips that are unknown locally cannot be used to rule anything out,
+/// so entries may occasionally be sent that the peer already has; merging them
+/// is a no-op. Entries that failed verification locally are never sent.
+pub(crate) fn missing_entries(
+    backend: &Arc<dyn Database>,
+    tree: &ID,
+    remote_tips: &[ID],
+) -> Result<Vec<Entry>> {
+    let known_tips: Vec<ID> = remote_tips
+        .iter()
+        .filter(|id| backend.get(id).is_ok_and(|entry| entry.in_tree(tree)))
+        .cloned()
+        .collect();
+    let remote_has: HashSet<ID> = backend
+        .get_tree_from_tips(tree, &known_tips)?
+        .iter()
+        .map(Entry::id)
+        .collect();
+
+    let mut missing = Vec::new();
+    for entry in backend.get_tree_iter(tree)? {
+        let entry = entry?;
+        let id = entry.id();
+        if !remote_has.contains(&id)
+            && backend.get_verification_status(&id)? == VerificationStatus::Verified
+        {
+            missing.push(entry);
+        }
+    }
+    Ok(missing)
+}
+
+/// Stores entries received from a peer and returns the IDs of those that were
+/// new or not yet verified.
+///
+/// Entries that are already `Verified` locally are skipped. New entries are
+/// stored as `Failed` while awaiting verification, then each new or `Failed`
+/// entry is checked against the tree's authentication settings by `pool` once
+/// the whole batch is stored and marked `Verified` if it passes.
+pub(crate) fn merge_entries(
+    backend: &Arc<dyn Database>,
+    tree: &ID,
+    entries: Vec<Entry>,
+    pool: &VerificationPool,
+) -> Result<Vec<ID>> {
+    let new_ids = store_entries(backend, tree, entries)?;
+    verify_entries(backend, tree, &new_ids, pool)?;
+    Ok(new_ids)
+}
+
+/// Stores entries received from a peer as `Failed` and returns the IDs of
+/// those that still need to be verified.
+///
+/// These are the new entries and those already stored as `Failed`, whose
+/// earlier verification may have been interrupted or failed transiently.
+///
+/// The batch is rejected before anything is stored if any entry is malformed
+/// (see `Entry::validate_structure`) or belongs to another tree.
+pub(crate) fn store_entries(
+    backend: &Arc<dyn Database>,
+    tree: &ID,
+    entries: Vec<Entry>,
+) -> Result<Vec<ID>> {
+    for entry in &entries {
+        entry.validate_structure()?;
+    }
+    if let Some(entry) = entries.iter().find(|entry| !entry.in_tree(tree)) {
+        return Err(SyncError::EntryNotInTree {
+            entry_id: entry.id(),
+            tree_id: tree.clone(),
+        }
+        .into());
+    }
+
+    let mut unverified = Vec::new();
+    for entry in entries {
+        let id = entry.id();
+        match backend.get(&id) {
+            Ok(_) => {
+                if backend.get_verification_status(&id)? == VerificationStatus::Verified {
+                    continue;
+                }
+            }
+            Err(e) if e.is_not_found() => backend.put(VerificationStatus::Failed, entry)?,
+            Err(e) => return Err(e),
+        }
+        unverified.push(id);
+    }
+    Ok(unverified)
+}
+
+/// Verifies stored entries on `pool`, marking those that pass `Verified`.
+pub(crate) fn verify_entries(
+    backend: &Arc<dyn Database>,
+    tree: &ID,
+    ids: &[ID],
+    pool: &VerificationPool,
+) -> Result<()> {
+    if !ids.is_empty() {
+        let tree = Tree::new_from_id(tree.clone(), Arc::clone(backend))?;
+        pool.verify(&tree, ids)?;
+    }
+    Ok(())
+}
+
+/// Verifies those of `ids` whose parents are all stored and returns the others.
+///
+/// An entry is verified against the settings of its parents, so entries that
+/// arrive before their parents have to wait for them.
+pub(crate) fn verify_ready(
+    backend: &Arc<dyn Database>,
+    tree: &ID,
+    ids: Vec<ID>,
+    pool: &VerificationPool,
+) -> Result<Vec<ID>> {
+    let mut ready = Vec::new();
+    let mut waiting = Vec::new();
+    for id in ids {
+        let parents = backend.get(&id)?.parents().unwrap_or_default();
+        if parents.iter().all(|parent| backend.get(parent).is_ok()) {
+            ready.push(id);
+        } else {
+            waiting.push(id);
+        }
+    }
+    verify_entries(backend, tree, &ready, pool)?;
+    Ok(waiting)
+}
+
+/// Merges the ephemeral subtree state received from a peer into `state`.
+///
+/// Only subtrees that the local settings of `tree` mark as ephemeral are kept,
+/// so a peer cannot make this one hold arbitrary data in memory.
+pub(crate) fn merge_ephemeral(
+    backend: &Arc<dyn Database>,
+    tree: &ID,
+    state: &EphemeralState,
+    received: BTreeMap<String, Map>,
+) -> Result<()> {
+    if received.is_empty() {
+        return Ok(());
+    }
+    let settings = Tree::new_from_id(tree.clone(), Arc::clone(backend))?
+        .get_settings()?
+        .get_all()?;
+    state.merge(tree, received, &ephemeral_subtrees(&settings))
+}
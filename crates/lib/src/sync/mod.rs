@@ -0,0 +1,527 @@
+//! Replication between Eidetica instances
+//!
+//! Trees are Merkle DAGs, so two instances can reconcile a tree by exchanging
+//! their tips and sending each other the entries the other side cannot reach.
+//! [`SyncPeer`] implements this exchange over any bidirectional `Read + Write`
+//! transport, such as a `TcpStream` or a `UnixStream`.
+//!
+//! Entries received from a peer are verified against the tree's authentication
+//! settings before being marked `Verified`; entries that fail verification are
+//! stored as `Failed` and are never passed on to other peers. Verification runs
+//! on a [`VerificationPool`] of worker threads.
+//!
+//! Peers can compress sync sessions to save bandwidth, see
+//! [`SyncPeer::with_compression`]. A peer fetching a large part of a tree
+//! receives it in batches, most important entries first, see [`priority`].
+//!
+//! Peers given an [`EphemeralState`] also exchange the in-memory state of the
+//! tree's ephemeral subtrees, see [`SyncPeer::with_ephemeral_state`]. Peers
+//! given an [`EventBus`] publish the entries they receive to it, see
+//! [`SyncPeer::with_event_bus`].
+//!
+//! A serving peer also answers [`RemoteDatabase`], which reads a tree from it on
+//! demand instead of replicating it.
+//!
+//! With the `libp2p` feature, [`transport::Libp2p`] discovers peers and keeps
+//! subscribed trees in sync in the background.
+
+mod errors;
+mod merge;
+pub mod priority;
+mod protocol;
+mod remote;
+pub mod transport;
+mod verify;
+
+pub use errors::SyncError;
+pub use priority::Priority;
+pub use remote::RemoteDatabase;
+pub use verify::VerificationPool;
+
+use crate::Result;
+use crate::backend::{Compression, Database, DatabaseError, VerificationStatus};
+use crate::basedb::{DatabaseEvent, EphemeralState, EventBus};
+use crate::crdt::Map;
+use crate::entry::{Entry, ID};
+use priority::PriorityHook;
+use protocol::{Answer, PROTOCOL_VERSION, Request, Response, read_message, write_message};
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::sync::Arc;
+
+/// Counts of entries exchanged during one sync session.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SyncStats {
+    /// Entries sent to the peer.
+    pub sent: usize,
+    /// Entries received from the peer that were not already stored locally.
+    pub received: usize,
+}
+
+/// One end of a sync connection.
+///
+/// A session is started by the initiating side calling [`SyncPeer::sync_tree`]
+/// while the other side answers with [`SyncPeer::serve_one`] or
+/// [`SyncPeer::serve`]. When the session completes, both backends contain
+/// the union of their entries for that tree.
+///
+/// # Example
+/// ```
+/// # use eidetica::{backend::database::InMemory, basedb::BaseDB, sync::SyncPeer};
+/// # use std::net::{TcpListener, TcpStream};
+/// # fn main() -> eidetica::Result<()> {
+/// let listener = TcpListener::bind("127.0.0.1:0")?;
+/// let addr = listener.local_addr()?;
+///
+/// let remote = BaseDB::new(Box::new(InMemory::new()));
+/// let backend = remote.backend().clone();
+/// let server = std::thread::spawn(move || {
+///     let (stream, _) = listener.accept().unwrap();
+///     SyncPeer::new(backend, stream).serve().unwrap();
+/// });
+///
+/// let db = BaseDB::new(Box::new(InMemory::new()));
+/// db.add_private_key("key")?;
+/// let tree = db.new_tree_default("key")?;
+///
+/// let mut peer = SyncPeer::new(db.backend().clone(), TcpStream::connect(addr)?);
+/// let stats = peer.sync_tree(tree.root_id())?;
+/// assert_eq!(stats.sent, 1);
+///
+/// drop(peer);
+/// server.join().unwrap();
+/// assert!(remote.load_tree(tree.root_id()).is_ok());
+/// # Ok(())
+/// # }
+/// ```
+pub struct SyncPeer<T> {
+    backend: Arc<dyn Database>,
+    transport: T,
+    verification: VerificationPool,
+    /// Compression this peer offers or accepts
+    compression: Compression,
+    /// Compression agreed on for the current session
+    session: Compression,
+    /// Subtrees this peer wants first when fetching
+    subscribed: Vec<String>,
+    /// Adjusts the priority of entries this peer sends
+    priority: Option<PriorityHook>,
+    /// Entries per message of a prioritized transfer
+    batch_size: usize,
+    /// Ephemeral subtree state exchanged with the peer, if any
+    ephemeral: Option<Arc<EphemeralState>>,
+    /// Bus the entries received from the peer are published to, if any
+    events: Option<Arc<EventBus>>,
+}
+
+impl<T: Read + Write> SyncPeer<T> {
+    /// Default number of entries per message when sending in priority order.
+    pub const DEFAULT_BATCH_SIZE: usize = 256;
+
+    /// Creates a peer that syncs `backend` over `transport`.
+    ///
+    /// Received entries are verified on a default [`VerificationPool`], with one
+    /// worker per available CPU.
+    pub fn new(backend: Arc<dyn Database>, transport: T) -> Self {
+        Self {
+            backend,
+            transport,
+            verification: VerificationPool::default(),
+            compression: Compression::None,
+            session: Compression::None,
+            subscribed: Vec::new(),
+            priority: None,
+            batch_size: Self::DEFAULT_BATCH_SIZE,
+            ephemeral: None,
+            events: None,
+        }
+    }
+
+    /// Sets the pool that verifies received entries.
+    pub fn with_verification_pool(mut self, pool: VerificationPool) -> Self {
+        self.verification = pool;
+        self
+    }
+
+    /// Sets the compression to use for sync sessions.
+    ///
+    /// Compression is negotiated per session: the initiating peer offers its
+    /// algorithm and the responding peer uses it if it has compression enabled
+    /// too and supports the algorithm; otherwise the session is uncompressed.
+    /// [`Compression::ZstdDict`] suits sync best, since messages are made of
+    /// entries that share their structure. Disabled by default.
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Asks peers to send recent entries of `subtree` before older history
+    /// when this peer fetches a tree.
+    ///
+    /// If no subtree is subscribed, recent entries of every subtree are sent
+    /// first. Settings always come before anything else; see [`priority`].
+    pub fn subscribe(mut self, subtree: impl Into<String>) -> Self {
+        self.subscribed.push(subtree.into());
+        self
+    }
+
+    /// Sets a hook that decides the priority of each entry this peer sends,
+    /// given the entry and its default priority.
+    ///
+    /// The hook is used when a fetching peer accepts entries in priority order.
+    /// Entries are sent parents first within each priority, but an entry given
+    /// a lower priority than its parents arrives before them.
+    pub fn with_priority<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&Entry, Priority) -> Priority + Send + Sync + 'static,
+    {
+        self.priority = Some(Arc::new(hook));
+        self
+    }
+
+    /// Sets the number of entries per message when sending in priority order.
+    ///
+    /// Smaller batches let the fetching peer store and use the first entries
+    /// sooner. Defaults to [`Self::DEFAULT_BATCH_SIZE`].
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Exchanges the state of ephemeral subtrees with peers, usually
+    /// [`BaseDB::ephemeral_state`](crate::basedb::BaseDB::ephemeral_state).
+    ///
+    /// Both sides of a session send their state of the tree's ephemeral
+    /// subtrees and merge what they receive, for the subtrees their own
+    /// settings mark as ephemeral. Without it, ephemeral subtrees are neither
+    /// sent nor accepted.
+    pub fn with_ephemeral_state(mut self, state: Arc<EphemeralState>) -> Self {
+        self.ephemeral = Some(state);
+        self
+    }
+
+    /// Publishes the entries received from peers to `bus`, usually
+    /// [`BaseDB::event_bus`](crate::basedb::BaseDB::event_bus).
+    ///
+    /// After each session that stored new entries of a tree, an
+    /// [`EntryVerified`](DatabaseEvent::EntryVerified) event is published for
+    /// each of them that passed verification, followed by one
+    /// [`SyncReceived`](DatabaseEvent::SyncReceived) event.
+    pub fn with_event_bus(mut self, bus: Arc<EventBus>) -> Self {
+        self.events = Some(bus);
+        self
+    }
+
+    /// The compression used by the most recent session, or
+    /// `Compression::None` if the peers did not agree on one.
+    pub fn session_compression(&self) -> Compression {
+        self.session
+    }
+
+    /// Get a reference to the backend
+    pub fn backend(&self) -> &Arc<dyn Database> {
+        &self.backend
+    }
+
+    /// Consumes the peer and returns the underlying transport.
+    pub fn into_inner(self) -> T {
+        self.transport
+    }
+
+    /// Syncs a tree with the remote peer, which must be serving.
+    ///
+    /// The tree does not need to exist locally; syncing an unknown tree fetches
+    /// it in full from the peer. Likewise the peer receives the whole tree if it
+    /// does not have it yet.
+    pub fn sync_tree(&mut self, tree: &ID) -> Result<SyncStats> {
+        let tips = self.backend.get_tips(tree)?;
+        self.session = Compression::None;
+        let offered = if self.compression == Compression::None {
+            Vec::new()
+        } else {
+            vec![self.compression.name().to_string()]
+        };
+        self.send(&Request::SyncTree {
+            version: PROTOCOL_VERSION,
+            tree: tree.clone(),
+            tips,
+            compression: offered,
+            prioritized: Some(self.subscribed.clone()),
+        })?;
+
+        let (remote_tips, entries, mut remaining, remote_ephemeral) =
+            match self.receive_response()? {
+                Response::TreeState {
+                    tips,
+                    entries,
+                    compression,
+                    remaining,
+                    ephemeral,
+                } => {
+                    if let Some(name) = compression {
+                        if name != self.compression.name() {
+                            return Err(SyncError::UnsupportedCompression { name }.into());
+                        }
+                        self.session = self.compression;
+                    }
+                    (tips, entries, remaining, ephemeral)
+                }
+                other => return Err(unexpected("TreeState", other.name())),
+            };
+        // Each batch is stored and verified before the next one is read, so
+        // the most important entries can be used while the rest arrive.
+        // Entries whose parents are still to come are verified once they do.
+        let mut waiting = merge::store_entries(&self.backend, tree, entries)?;
+        let mut received = waiting.clone();
+        while remaining > 0 {
+            waiting = merge::verify_ready(&self.backend, tree, waiting, &self.verification)?;
+            let entries = match self.receive_response()? {
+                Response::Entries {
+                    entries,
+                    remaining: left,
+                } if left < remaining => {
+                    remaining = left;
+                    entries
+                }
+                other => return Err(unexpected("Entries", other.name())),
+            };
+            let new_ids = merge::store_entries(&self.backend, tree, entries)?;
+            received.extend(new_ids.iter().cloned());
+            waiting.extend(new_ids);
+        }
+        merge::verify_entries(&self.backend, tree, &waiting, &self.verification)?;
+        self.merge_ephemeral(tree, remote_ephemeral)?;
+        self.publish_received(tree, &received)?;
+        let received = received.len();
+
+        let missing = merge::missing_entries(&self.backend, tree, &remote_tips)?;
+        let sent = missing.len();
+        self.send(&Request::Push {
+            tree: tree.clone(),
+            entries: missing,
+            ephemeral: self.ephemeral_snapshot(tree),
+        })?;
+
+        match self.receive_response()? {
+            Response::Stored { .. } => Ok(SyncStats { sent, received }),
+            other => Err(unexpected("Stored", other.name())),
+        }
+    }
+
+    /// Answers one sync session started by the remote peer.
+    ///
+    /// Queries and stores from a [`RemoteDatabase`] count as sessions
+    /// of their own. Returns `None` if the peer closed the connection instead of
+    /// starting a session. If the session fails, the error is reported to the
+    /// peer before being returned. A failed query is reported to the peer only.
+    pub fn serve_one(&mut self) -> Result<Option<SyncStats>> {
+        let Some(request) = read_message::<_, Request>(&mut self.transport)? else {
+            return Ok(None);
+        };
+        self.session = Compression::None;
+        match self.answer(request) {
+            Ok(stats) => Ok(Some(stats)),
+            Err(e) => {
+                // The connection may already be broken, in which case the
+                // original error is the more useful one to return
+                let _ = self.send(&Response::Error {
+                    reason: e.to_string(),
+                });
+                Err(e)
+            }
+        }
+    }
+
+    /// Answers sync sessions until the remote peer closes the connection.
+    pub fn serve(&mut self) -> Result<()> {
+        while self.serve_one()?.is_some() {}
+        Ok(())
+    }
+
+    fn answer(&mut self, request: Request) -> Result<SyncStats> {
+        match request {
+            Request::SyncTree {
+                version,
+                tree,
+                tips,
+                compression,
+                prioritized,
+            } => {
+                if version != PROTOCOL_VERSION {
+                    return Err(SyncError::VersionMismatch {
+                        local: PROTOCOL_VERSION,
+                        remote: version,
+                    }
+                    .into());
+                }
+                self.session = self.choose_compression(&compression);
+                self.answer_sync(tree, tips, prioritized)
+            }
+            Request::Store { tree, entries } => {
+                let new_ids =
+                    merge::merge_entries(&self.backend, &tree, entries, &self.verification)?;
+                self.publish_received(&tree, &new_ids)?;
+                let received = new_ids.len();
+                self.send(&Response::Stored { count: received })?;
+                Ok(SyncStats { sent: 0, received })
+            }
+            Request::Push { .. } => Err(unexpected("SyncTree", "Push")),
+            Request::Query(query) => {
+                let (response, sent) = match remote::evaluate(self.backend.as_ref(), query) {
+                    Ok(answer) => {
+                        let sent = match &answer {
+                            Answer::Entry(_) => 1,
+                            Answer::Entries(entries) => entries.len(),
+                            _ => 0,
+                        };
+                        (Response::Answer(answer), sent)
+                    }
+                    Err(crate::Error::Backend(DatabaseError::EntryNotFound { id })) => {
+                        (Response::NotFound { id }, 0)
+                    }
+                    Err(e) => (
+                        Response::Error {
+                            reason: e.to_string(),
+                        },
+                        0,
+                    ),
+                };
+                self.send(&response)?;
+                Ok(SyncStats { sent, received: 0 })
+            }
+        }
+    }
+
+    fn answer_sync(
+        &mut self,
+        tree: ID,
+        remote_tips: Vec<ID>,
+        prioritized: Option<Vec<String>>,
+    ) -> Result<SyncStats> {
+        let tips = self.backend.get_tips(&tree)?;
+        let mut missing = merge::missing_entries(&self.backend, &tree, &remote_tips)?;
+        let sent = missing.len();
+        let compression =
+            (self.session != Compression::None).then(|| self.session.name().to_string());
+
+        // Peers that do not accept prioritized transfers get everything at once
+        let mut rest = Vec::new();
+        if let Some(subscribed) = prioritized {
+            missing = priority::prioritize(missing, &tips, &subscribed, self.priority.as_ref());
+            if missing.len() > self.batch_size {
+                rest = missing.split_off(self.batch_size);
+            }
+        }
+        let mut remaining = rest.len();
+        self.send(&Response::TreeState {
+            tips,
+            entries: missing,
+            compression,
+            remaining,
+            ephemeral: self.ephemeral_snapshot(&tree),
+        })?;
+        for batch in rest.chunks(self.batch_size) {
+            remaining -= batch.len();
+            self.send(&Response::Entries {
+                entries: batch.to_vec(),
+                remaining,
+            })?;
+        }
+
+        let (entries, remote_ephemeral) = match read_message::<_, Request>(&mut self.transport)? {
+            Some(Request::Push {
+                tree: push_tree,
+                entries,
+                ephemeral,
+            }) => {
+                if push_tree != tree {
+                    return Err(unexpected(
+                        &format!("Push for tree {tree}"),
+                        &format!("Push for tree {push_tree}"),
+                    ));
+                }
+                (entries, ephemeral)
+            }
+            Some(other) => return Err(unexpected("Push", other.name())),
+            None => return Err(SyncError::ConnectionClosed.into()),
+        };
+        let new_ids = merge::merge_entries(&self.backend, &tree, entries, &self.verification)?;
+        self.merge_ephemeral(&tree, remote_ephemeral)?;
+        self.publish_received(&tree, &new_ids)?;
+        let received = new_ids.len();
+        self.send(&Response::Stored { count: received })?;
+
+        Ok(SyncStats { sent, received })
+    }
+
+    /// This peer's state of the ephemeral subtrees of `tree`, if it shares them.
+    fn ephemeral_snapshot(&self, tree: &ID) -> BTreeMap<String, Map> {
+        self.ephemeral
+            .as_ref()
+            .map(|state| state.snapshot(tree))
+            .unwrap_or_default()
+    }
+
+    /// Merges the ephemeral state received from the peer, if this peer shares it.
+    fn merge_ephemeral(&self, tree: &ID, received: BTreeMap<String, Map>) -> Result<()> {
+        match &self.ephemeral {
+            Some(state) => merge::merge_ephemeral(&self.backend, tree, state, received),
+            None => Ok(()),
+        }
+    }
+
+    /// Publishes the entries of `tree` newly stored in a session, if this peer
+    /// has an event bus.
+    fn publish_received(&self, tree: &ID, new_ids: &[ID]) -> Result<()> {
+        let Some(bus) = &self.events else {
+            return Ok(());
+        };
+        if new_ids.is_empty() || !bus.has_subscribers() {
+            return Ok(());
+        }
+        for id in new_ids {
+            if self.backend.get_verification_status(id)? == VerificationStatus::Verified {
+                bus.publish(DatabaseEvent::EntryVerified {
+                    tree: tree.clone(),
+                    entry: id.clone(),
+                });
+            }
+        }
+        bus.publish(DatabaseEvent::SyncReceived {
+            tree: tree.clone(),
+            entries: new_ids.len(),
+        });
+        Ok(())
+    }
+
+    /// Picks the first offered algorithm this peer supports, if it compresses at all.
+    fn choose_compression(&self, offered: &[String]) -> Compression {
+        if self.compression == Compression::None {
+            return Compression::None;
+        }
+        offered
+            .iter()
+            .find_map(|name| Compression::from_name(name))
+            .unwrap_or(Compression::None)
+    }
+
+    fn send<M: serde::Serialize>(&mut self, message: &M) -> Result<()> {
+        write_message(&mut self.transport, message, self.session)
+    }
+
+    fn receive_response(&mut self) -> Result<Response> {
+        match read_message(&mut self.transport)? {
+            Some(Response::Error { reason }) => Err(SyncError::RemoteError { reason }.into()),
+            Some(response) => Ok(response),
+            None => Err(SyncError::ConnectionClosed.into()),
+        }
+    }
+}
+
+fn unexpected(expected: &str, actual: &str) -> crate::Error {
+    SyncError::UnexpectedMessage {
+        expected: expected.to_string(),
+        actual: actual.to_string(),
+    }
+    .into()
+}
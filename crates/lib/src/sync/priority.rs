@@ -0,0 +1,106 @@
+//! Order in which entries are sent to a peer
+//!
+//! A peer that is missing much of a tree receives it in several batches, most
+//! important entries first, so it can start using the tree before the transfer
+//! is over. By default entries are sent in three groups:
+//!
+//! 1. [`Priority::SETTINGS`]: the root entry and entries that change
+//!    `_settings`, which are needed to verify everything else.
+//! 2. [`Priority::RECENT`]: entries within [`RECENT_DEPTH`] generations of the
+//!    tips that write to a subtree the receiving peer subscribed to, see
+//!    [`SyncPeer::subscribe`](super::SyncPeer::subscribe). If it subscribed to
+//!    nothing, every subtree counts.
+//! 3. [`Priority::HISTORY`]: everything else.
+//!
+//! Applications can change the priority of individual entries with
+//! [`SyncPeer::with_priority`](super::SyncPeer::with_priority). Within a
+//! priority, entries keep their parents-first order.
+
+use crate::constants::SETTINGS;
+use crate::entry::{Entry, ID};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+/// How early an entry is sent during sync; lower priorities are sent first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Priority(pub u8);
+
+impl Priority {
+    /// The root entry and entries that change the tree's settings.
+    pub const SETTINGS: Priority = Priority(0);
+    /// Recent entries of the subtrees the receiving peer subscribed to.
+    pub const RECENT: Priority = Priority(100);
+    /// All other entries.
+    pub const HISTORY: Priority = Priority(200);
+}
+
+/// Number of generations below the tips whose entries count as recent.
+pub const RECENT_DEPTH: usize = 64;
+
+/// Application hook deciding the priority of an entry, given its default priority.
+pub(crate) type PriorityHook = Arc<dyn Fn(&Entry, Priority) -> Priority + Send + Sync>;
+
+/// Sorts `entries`, given parents first, into the order they should be sent.
+///
+/// `tips` are the sending peer's tips of the tree, from which recency is
+/// measured, and `subscribed` the subtrees the receiving peer asked for first.
+pub(crate) fn prioritize(
+    entries: Vec<Entry>,
+    tips: &[ID],
+    subscribed: &[String],
+    hook: Option<&PriorityHook>,
+) -> Vec<Entry> {
+    let depths = depths(&entries, tips);
+    let mut prioritized: Vec<(Priority, Entry)> = entries
+        .into_iter()
+        .map(|entry| {
+            let recent = depths
+                .get(&entry.id())
+                .is_some_and(|depth| *depth < RECENT_DEPTH);
+            let priority = default_priority(&entry, recent, subscribed);
+            let priority = hook.map_or(priority, |hook| hook(&entry, priority));
+            (priority, entry)
+        })
+        .collect();
+    // The sort is stable, so parents stay ahead of their children within a priority
+    prioritized.sort_by_key(|(priority, _)| *priority);
+    prioritized.into_iter().map(|(_, entry)| entry).collect()
+}
+
+fn default_priority(entry: &Entry, recent: bool, subscribed: &[String]) -> Priority {
+    let subtrees = entry.subtrees();
+    if entry.is_root() || subtrees.iter().any(|name| name == SETTINGS) {
+        Priority::SETTINGS
+    } else if recent
+        && (subscribed.is_empty() || subtrees.iter().any(|name| subscribed.contains(name)))
+    {
+        Priority::RECENT
+    } else {
+        Priority::HISTORY
+    }
+}
+
+/// Distance in generations from the nearest tip to each of `entries`.
+///
+/// Only paths through `entries` are followed; entries that cannot be reached
+/// from a tip that way are left out.
+fn depths(entries: &[Entry], tips: &[ID]) -> HashMap<ID, usize> {
+    let by_id: HashMap<ID, &Entry> = entries.iter().map(|entry| (entry.id(), entry)).collect();
+    let mut depths = HashMap::new();
+    let mut queue = VecDeque::new();
+    for tip in tips {
+        if by_id.contains_key(tip) && depths.insert(tip.clone(), 0).is_none() {
+            queue.push_back(tip.clone());
+        }
+    }
+    while let Some(id) = queue.pop_front() {
+        let depth = depths[&id];
+        for parent in by_id[&id].parents().unwrap_or_default() {
+            if by_id.contains_key(&parent) && !depths.contains_key(&parent) {
+                depths.insert(parent.clone(), depth + 1);
+                queue.push_back(parent);
+            }
+        }
+    }
+    depths
+}
@@ -0,0 +1,423 @@
+//! Sync wire protocol
+//!
+//! Messages are JSON documents, each preceded by its length as a big-endian
+//! `u32`. A sync session is a strict request/response exchange started by the
+//! initiating peer:
+//!
+//! 1. `Request::SyncTree` carries the initiator's tips for a tree.
+//! 2. `Response::TreeState` returns the responder's tips and every entry the
+//!    initiator is missing. If the initiator accepts prioritized transfers,
+//!    the entries are sorted by priority and split into batches: `TreeState`
+//!    holds the first one and says how many entries remain, which follow in
+//!    `Response::Entries` messages.
+//! 3. `Request::Push` sends every entry the responder is missing.
+//! 4. `Response::Stored` acknowledges how many new entries were stored.
+//!
+//! Peers that share ephemeral subtrees also send their in-memory state of the
+//! tree's ephemeral subtrees, the responder in `TreeState` and the initiator in
+//! `Push`. Each side merges what it receives once it has stored the entries of
+//! the same message, keeping only subtrees its settings mark as ephemeral.
+//!
+//! Entries of a prioritized transfer may arrive before their parents, which
+//! backends accept. Pushes are always sent in one message, parents first, since
+//! peers that predate prioritized transfers expect them that way.
+//!
+//! Either side may answer with `Response::Error` instead, ending the session.
+//!
+//! The initiator lists the compression algorithms it accepts in `SyncTree`, and
+//! the responder names the one it picked, if any, in `TreeState`. Every later
+//! message of the session, starting with `TreeState`, may then be compressed:
+//! its body starts with a compression header instead of JSON, see
+//! [`Compression`]. Peers that predate compression send an empty list and
+//! ignore the choice, so they never receive a compressed message.
+//!
+//! Outside of a session, the initiator may also send single requests that are
+//! used by [`RemoteDatabase`](super::RemoteDatabase) to read a tree without
+//! replicating it:
+//!
+//! * `Request::Query` asks for the result of one `Database` read, answered with
+//!   `Response::Answer`, or `Response::NotFound` if the entry does not exist.
+//!   A failed query does not end the connection.
+//! * `Request::Store` stores new entries, answered with `Response::Stored`.
+
+use super::errors::SyncError;
+use crate::Result;
+use crate::backend::{Compression, VerificationStatus};
+use crate::crdt::Map;
+use crate::entry::{Entry, ID};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{ErrorKind, Read, Write};
+
+/// Version of the sync protocol spoken by this implementation.
+pub(crate) const PROTOCOL_VERSION: u32 = 1;
+
+/// Largest message accepted from a peer, in bytes.
+pub(crate) const MAX_MESSAGE_SIZE: usize = 64 * 1024 * 1024;
+
+/// Messages sent by the initiating peer.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) enum Request {
+    /// Starts a sync of `tree`, announcing the initiator's current tips.
+    SyncTree {
+        version: u32,
+        tree: ID,
+        tips: Vec<ID>,
+        /// Names of the compression algorithms the initiator accepts
+        #[serde(default)]
+        compression: Vec<String>,
+        /// Present if the initiator accepts entries in priority order, in
+        /// several batches; names the subtrees it wants first
+        #[serde(default)]
+        prioritized: Option<Vec<String>>,
+    },
+    /// Entries of `tree` that the responder is missing, parents first.
+    Push {
+        tree: ID,
+        entries: Vec<Entry>,
+        /// The initiator's state of the tree's ephemeral subtrees, by name
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        ephemeral: BTreeMap<String, Map>,
+    },
+    /// A single read of the responder's database.
+    Query(Query),
+    /// New entries of `tree` committed through a remote database, parents first.
+    Store { tree: ID, entries: Vec<Entry> },
+}
+
+impl Request {
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Request::SyncTree { .. } => "SyncTree",
+            Request::Push { .. } => "Push",
+            Request::Query(_) => "Query",
+            Request::Store { .. } => "Store",
+        }
+    }
+}
+
+/// Reads of a remote database, one for each read method of `Database`.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) enum Query {
+    Get {
+        id: ID,
+    },
+    VerificationStatus {
+        id: ID,
+    },
+    EntriesByVerificationStatus {
+        status: VerificationStatus,
+    },
+    Tips {
+        tree: ID,
+    },
+    SubtreeTips {
+        tree: ID,
+        subtree: String,
+    },
+    SubtreeTipsUpToEntries {
+        tree: ID,
+        subtree: String,
+        entries: Vec<ID>,
+    },
+    AllRoots,
+    FindLca {
+        tree: ID,
+        subtree: String,
+        entries: Vec<ID>,
+    },
+    CollectRootToTarget {
+        tree: ID,
+        subtree: String,
+        target: ID,
+    },
+    Tree {
+        tree: ID,
+    },
+    Subtree {
+        tree: ID,
+        subtree: String,
+    },
+    TreeFromTips {
+        tree: ID,
+        tips: Vec<ID>,
+    },
+    SubtreeFromTips {
+        tree: ID,
+        subtree: String,
+        tips: Vec<ID>,
+    },
+    SortedSubtreeParents {
+        tree: ID,
+        entry: ID,
+        subtree: String,
+    },
+    PathFromTo {
+        tree: ID,
+        subtree: String,
+        from: ID,
+        to: Vec<ID>,
+    },
+}
+
+/// The result of a `Query`.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) enum Answer {
+    Entry(Entry),
+    Entries(Vec<Entry>),
+    Id(ID),
+    Ids(Vec<ID>),
+    Status(VerificationStatus),
+}
+
+impl Answer {
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Answer::Entry(_) => "Entry",
+            Answer::Entries(_) => "Entries",
+            Answer::Id(_) => "Id",
+            Answer::Ids(_) => "Ids",
+            Answer::Status(_) => "Status",
+        }
+    }
+}
+
+/// Messages sent by the responding peer.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) enum Response {
+    /// The responder's tips and the entries the initiator is missing, parents
+    /// first unless the initiator asked for a prioritized transfer.
+    TreeState {
+        tips: Vec<ID>,
+        entries: Vec<Entry>,
+        /// Name of the compression algorithm used for the rest of the session
+        #[serde(default)]
+        compression: Option<String>,
+        /// Number of missing entries that follow in `Entries` messages
+        #[serde(default)]
+        remaining: usize,
+        /// The responder's state of the tree's ephemeral subtrees, by name
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        ephemeral: BTreeMap<String, Map>,
+    },
+    /// The next batch of a prioritized transfer.
+    Entries {
+        entries: Vec<Entry>,
+        remaining: usize,
+    },
+    /// Number of pushed entries that were new to the responder.
+    Stored { count: usize },
+    /// The result of a query.
+    Answer(Answer),
+    /// The entry a query needed does not exist.
+    NotFound { id: ID },
+    /// The peer failed to process the previous message.
+    Error { reason: String },
+}
+
+impl Response {
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Response::TreeState { .. } => "TreeState",
+            Response::Entries { .. } => "Entries",
+            Response::Stored { .. } => "Stored",
+            Response::Answer(_) => "Answer",
+            Response::NotFound { .. } => "NotFound",
+            Response::Error { .. } => "Error",
+        }
+    }
+}
+
+/// Writes a length-prefixed message, compressed with `compression`, and flushes
+/// the transport.
+pub(crate) fn write_message<W: Write, M: Serialize>(
+    writer: &mut W,
+    message: &M,
+    compression: Compression,
+) -> Result<()> {
+    let json = serde_json::to_vec(message)?;
+    let body = compression.compress(&json);
+    let len = u32::try_from(body.len())
+        .ok()
+        .filter(|len| *len as usize <= MAX_MESSAGE_SIZE)
+        .ok_or(SyncError::MessageTooLarge {
+            size: body.len(),
+            max: MAX_MESSAGE_SIZE,
+        })?;
+    writer.write_all(&len.to_be_bytes())?;
+    writer.write_all(&body)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Reads a length-prefixed message, decompressing it if needed.
+///
+/// Returns `None` if the peer closed the connection cleanly before the start of
+/// a message. A connection closed partway through a message is an error.
+pub(crate) fn read_message<R: Read, M: DeserializeOwned>(reader: &mut R) -> Result<Option<M>> {
+    let mut header = [0u8; 4];
+    let mut filled = 0;
+    while filled < header.len() {
+        match reader.read(&mut header[filled..]) {
+            Ok(0) if filled == 0 => return Ok(None),
+            Ok(0) => return Err(SyncError::ConnectionClosed.into()),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    let len = u32::from_be_bytes(header) as usize;
+    if len > MAX_MESSAGE_SIZE {
+        return Err(SyncError::MessageTooLarge {
+            size: len,
+            max: MAX_MESSAGE_SIZE,
+        }
+        .into());
+    }
+
+    let mut body = vec![0u8; len];
+    reader.read_exact(&mut body).map_err(|e| {
+        if e.kind() == ErrorKind::UnexpectedEof {
+            SyncError::ConnectionClosed.into()
+        } else {
+            crate::Error::from(e)
+        }
+    })?;
+
+    Ok(Some(serde_json::from_slice(&Compression::decompress(
+        &body,
+        MAX_MESSAGE_SIZE,
+    )?)?))
+}
+
+#[cfg(all(test, feature = "compression"))]
+mod tests {
+    use super::*;
+    use crate::backend::database::InMemory;
+    use crate::basedb::BaseDB;
+    use crate::subtree::{Dict, Log, Table, Text};
+
+    /// Size of the trained dictionary, in bytes.
+    const DICTIONARY_SIZE: usize = 16 * 1024;
+
+    #[derive(Clone, Serialize, Deserialize)]
+    struct Task {
+        title: String,
+        done: bool,
+        priority: u8,
+    }
+
+    fn json(message: &impl Serialize) -> Vec<u8> {
+        serde_json::to_vec(message).unwrap()
+    }
+
+    /// Messages of sync sessions over trees written like applications do.
+    fn sample_messages() -> Vec<Vec<u8>> {
+        let db = BaseDB::new(Box::new(InMemory::new()));
+        let keys = ["laptop", "phone", "server"];
+        for key in keys {
+            db.add_private_key(key).unwrap();
+        }
+
+        let mut messages = Vec::new();
+        for (t, key) in keys.iter().cycle().take(12).enumerate() {
+            let tree = db.new_tree_default(key).unwrap();
+            for i in 0..40 {
+                let op = tree.new_authenticated_operation(key).unwrap();
+                match (t + i) % 4 {
+                    0 => {
+                        let dict = op.get_subtree::<Dict>("profile").unwrap();
+                        dict.set(format!("field_{i}"), format!("value {i} of {t}"))
+                            .unwrap();
+                        dict.set("visits", i as i64).unwrap();
+                        dict.set("active", i % 2 == 0).unwrap();
+                    }
+                    1 => {
+                        let tasks = op.get_subtree::<Table<Task>>("tasks").unwrap();
+                        tasks
+                            .insert(Task {
+                                title: format!("Task number {i}"),
+                                done: i % 3 == 0,
+                                priority: (i % 5) as u8,
+                            })
+                            .unwrap();
+                    }
+                    2 => {
+                        let log = op.get_subtree::<Log<String>>("events").unwrap();
+                        log.append(format!("event {i} on tree {t}")).unwrap();
+                    }
+                    _ => {
+                        let text = op.get_subtree::<Text>("notes").unwrap();
+                        text.push_str(&format!("Line {i}. ")).unwrap();
+                    }
+                }
+                op.commit().unwrap();
+            }
+
+            let root = tree.root_id().clone();
+            let tips = tree.get_tips().unwrap();
+            let entries = tree.get_all_entries().unwrap();
+            messages.push(json(&Request::SyncTree {
+                version: PROTOCOL_VERSION,
+                tree: root.clone(),
+                tips: tips.clone(),
+                compression: vec![Compression::ZstdDict.name().to_string()],
+                prioritized: None,
+            }));
+            messages.push(json(&Response::Stored { count: t }));
+            for batch in [1, 2, 3, 5] {
+                for chunk in entries.chunks(batch) {
+                    messages.push(json(&Request::Push {
+                        tree: root.clone(),
+                        entries: chunk.to_vec(),
+                        ephemeral: BTreeMap::new(),
+                    }));
+                    messages.push(json(&Response::TreeState {
+                        tips: tips.clone(),
+                        entries: chunk.to_vec(),
+                        compression: Some(Compression::ZstdDict.name().to_string()),
+                        remaining: 0,
+                        ephemeral: BTreeMap::new(),
+                    }));
+                }
+            }
+        }
+        messages
+    }
+
+    /// Trains the dictionary of `Compression::ZstdDict` on sample messages
+    /// and writes it to `src/backend/entries.dict`.
+    ///
+    /// Data compressed with the dictionary can only be read with it, so it
+    /// must not be replaced once data compressed with it has been stored or
+    /// sent to peers that may not update.
+    #[test]
+    #[ignore = "rewrites src/backend/entries.dict"]
+    fn train_entry_dictionary() {
+        let dictionary = zstd::dict::from_samples(&sample_messages(), DICTIONARY_SIZE).unwrap();
+        std::fs::write(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/src/backend/entries.dict"),
+            dictionary,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_entry_dictionary_shrinks_small_messages() {
+        let (mut plain, mut lz4, mut primed) = (0, 0, 0);
+        for message in sample_messages().iter().filter(|m| m.len() < 4096) {
+            let compressed = Compression::ZstdDict.compress(message);
+            assert_eq!(
+                Compression::decompress(&compressed, MAX_MESSAGE_SIZE).unwrap(),
+                message.as_slice()
+            );
+            plain += message.len();
+            lz4 += Compression::Lz4.compress(message).len();
+            primed += compressed.len();
+        }
+        assert!(primed < lz4 / 2, "zstd-dict {primed}, LZ4 {lz4} bytes");
+        assert!(primed < plain / 3, "zstd-dict {primed} of {plain} bytes");
+    }
+}
@@ -0,0 +1,378 @@
+//! Reading trees from a remote peer without replicating them
+//!
+//! `RemoteDatabase` implements `Database` by forwarding every read to a peer
+//! serving sync sessions, using the query messages of the sync protocol.
+//! Entries are immutable, so every entry received is cached by ID for the
+//! lifetime of the connection; tips and other results that change as the tree
+//! grows are always fetched from the peer.
+
+use super::errors::SyncError;
+use super::protocol::{Answer, Query, Request, Response, read_message, write_message};
+use super::unexpected;
+use crate::Result;
+use crate::backend::errors::DatabaseError;
+use crate::backend::{Compression, Database, KeyStorage, VerificationStatus};
+use crate::entry::{Entry, ID};
+use ed25519_dalek::SigningKey;
+use std::any::Any;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex, RwLock};
+
+/// A connection to a remote peer that a `RemoteDatabase` can use.
+trait Transport: Read + Write + Send {}
+
+impl<T: Read + Write + Send> Transport for T {}
+
+/// A database backend that reads from and commits to a remote peer.
+///
+/// The peer must be serving sync sessions, e.g. with [`SyncPeer::serve`](super::SyncPeer::serve).
+/// Reads are fetched on demand, and entries put into the database are pushed to
+/// the peer, which verifies them like any other synced entry. Nothing is stored
+/// locally apart from in-memory caches of entries and computed CRDT states.
+///
+/// Private keys never leave the local machine: they are stored in the `keys`
+/// backend given on construction, usually the backend of the local `BaseDB`.
+/// Verification statuses belong to the peer and cannot be changed remotely.
+///
+/// Requests are sent one at a time over a single connection, so every read
+/// costs a round trip. This suits rarely used trees; frequently used trees
+/// are better replicated with [`SyncPeer::sync_tree`](super::SyncPeer::sync_tree).
+pub struct RemoteDatabase {
+    transport: Mutex<Box<dyn Transport>>,
+    keys: Arc<dyn Database>,
+    entries: RwLock<HashMap<ID, Entry>>,
+    crdt_cache: RwLock<HashMap<(ID, String), String>>,
+}
+
+impl std::fmt::Debug for RemoteDatabase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RemoteDatabase")
+            .field("cached_entries", &self.entries.read().unwrap().len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl RemoteDatabase {
+    /// Creates a database that forwards reads over `transport`, keeping private
+    /// keys in `keys`.
+    pub fn new<T: Read + Write + Send + 'static>(transport: T, keys: Arc<dyn Database>) -> Self {
+        Self {
+            transport: Mutex::new(Box::new(transport)),
+            keys,
+            entries: RwLock::new(HashMap::new()),
+            crdt_cache: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Connects to a peer serving sync sessions over TCP.
+    pub fn connect(addr: impl ToSocketAddrs, keys: Arc<dyn Database>) -> Result<Self> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_nodelay(true)?;
+        Ok(Self::new(stream, keys))
+    }
+
+    /// Sends a request and waits for the response.
+    fn request(&self, request: &Request) -> Result<Response> {
+        let mut transport = self.transport.lock().unwrap();
+        write_message(&mut *transport, request, Compression::None)?;
+        match read_message(&mut *transport)? {
+            Some(Response::Error { reason }) => Err(SyncError::RemoteError { reason }.into()),
+            Some(Response::NotFound { id }) => Err(DatabaseError::EntryNotFound { id }.into()),
+            Some(response) => Ok(response),
+            None => Err(SyncError::ConnectionClosed.into()),
+        }
+    }
+
+    fn query(&self, query: Query) -> Result<Answer> {
+        match self.request(&Request::Query(query))? {
+            Response::Answer(answer) => Ok(answer),
+            other => Err(unexpected("Answer", other.name())),
+        }
+    }
+
+    fn query_entries(&self, query: Query) -> Result<Vec<Entry>> {
+        match self.query(query)? {
+            Answer::Entries(entries) => {
+                let mut cache = self.entries.write().unwrap();
+                for entry in &entries {
+                    cache.entry(entry.id()).or_insert_with(|| entry.clone());
+                }
+                Ok(entries)
+            }
+            other => Err(unexpected("Entries", other.name())),
+        }
+    }
+
+    fn query_ids(&self, query: Query) -> Result<Vec<ID>> {
+        match self.query(query)? {
+            Answer::Ids(ids) => Ok(ids),
+            other => Err(unexpected("Ids", other.name())),
+        }
+    }
+
+    fn query_id(&self, query: Query) -> Result<ID> {
+        match self.query(query)? {
+            Answer::Id(id) => Ok(id),
+            other => Err(unexpected("Id", other.name())),
+        }
+    }
+}
+
+impl Database for RemoteDatabase {
+    fn get(&self, id: &ID) -> Result<Entry> {
+        if let Some(entry) = self.entries.read().unwrap().get(id) {
+            return Ok(entry.clone());
+        }
+        match self.query(Query::Get { id: id.clone() })? {
+            Answer::Entry(entry) => {
+                self.entries
+                    .write()
+                    .unwrap()
+                    .insert(id.clone(), entry.clone());
+                Ok(entry)
+            }
+            other => Err(unexpected("Entry", other.name())),
+        }
+    }
+
+    fn get_verification_status(&self, id: &ID) -> Result<VerificationStatus> {
+        match self.query(Query::VerificationStatus { id: id.clone() })? {
+            Answer::Status(status) => Ok(status),
+            other => Err(unexpected("Status", other.name())),
+        }
+    }
+
+    /// Pushes the entry to the peer.
+    ///
+    /// The peer verifies the entry itself, so `verification_status` is ignored.
+    fn put(&self, _verification_status: VerificationStatus, entry: Entry) -> Result<()> {
+        let id = entry.id();
+        let tree = if entry.is_root() {
+            id.clone()
+        } else {
+            entry.root()
+        };
+        match self.request(&Request::Store {
+            tree,
+            entries: vec![entry.clone()],
+        })? {
+            Response::Stored { .. } => {
+                self.entries.write().unwrap().insert(id, entry);
+                Ok(())
+            }
+            other => Err(unexpected("Stored", other.name())),
+        }
+    }
+
+    fn update_verification_status(
+        &self,
+        _id: &ID,
+        _verification_status: VerificationStatus,
+    ) -> Result<()> {
+        Err(DatabaseError::ReadOnly {
+            operation: "update_verification_status on a remote database".to_string(),
+        }
+        .into())
+    }
+
+    fn get_entries_by_verification_status(&self, status: VerificationStatus) -> Result<Vec<ID>> {
+        self.query_ids(Query::EntriesByVerificationStatus { status })
+    }
+
+    fn get_tips(&self, tree: &ID) -> Result<Vec<ID>> {
+        self.query_ids(Query::Tips { tree: tree.clone() })
+    }
+
+    fn get_subtree_tips(&self, tree: &ID, subtree: &str) -> Result<Vec<ID>> {
+        self.query_ids(Query::SubtreeTips {
+            tree: tree.clone(),
+            subtree: subtree.to_string(),
+        })
+    }
+
+    fn get_subtree_tips_up_to_entries(
+        &self,
+        tree: &ID,
+        subtree: &str,
+        main_entries: &[ID],
+    ) -> Result<Vec<ID>> {
+        self.query_ids(Query::SubtreeTipsUpToEntries {
+            tree: tree.clone(),
+            subtree: subtree.to_string(),
+            entries: main_entries.to_vec(),
+        })
+    }
+
+    fn all_roots(&self) -> Result<Vec<ID>> {
+        self.query_ids(Query::AllRoots)
+    }
+
+    fn find_lca(&self, tree: &ID, subtree: &str, entry_ids: &[ID]) -> Result<ID> {
+        self.query_id(Query::FindLca {
+            tree: tree.clone(),
+            subtree: subtree.to_string(),
+            entries: entry_ids.to_vec(),
+        })
+    }
+
+    fn collect_root_to_target(
+        &self,
+        tree: &ID,
+        subtree: &str,
+        target_entry: &ID,
+    ) -> Result<Vec<ID>> {
+        self.query_ids(Query::CollectRootToTarget {
+            tree: tree.clone(),
+            subtree: subtree.to_string(),
+            target: target_entry.clone(),
+        })
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn get_tree(&self, tree: &ID) -> Result<Vec<Entry>> {
+        self.query_entries(Query::Tree { tree: tree.clone() })
+    }
+
+    fn get_subtree(&self, tree: &ID, subtree: &str) -> Result<Vec<Entry>> {
+        self.query_entries(Query::Subtree {
+            tree: tree.clone(),
+            subtree: subtree.to_string(),
+        })
+    }
+
+    fn get_tree_from_tips(&self, tree: &ID, tips: &[ID]) -> Result<Vec<Entry>> {
+        self.query_entries(Query::TreeFromTips {
+            tree: tree.clone(),
+            tips: tips.to_vec(),
+        })
+    }
+
+    fn get_subtree_from_tips(&self, tree: &ID, subtree: &str, tips: &[ID]) -> Result<Vec<Entry>> {
+        self.query_entries(Query::SubtreeFromTips {
+            tree: tree.clone(),
+            subtree: subtree.to_string(),
+            tips: tips.to_vec(),
+        })
+    }
+
+    fn store_private_key(&self, key_name: &str, private_key: SigningKey) -> Result<()> {
+        self.keys.store_private_key(key_name, private_key)
+    }
+
+    fn get_private_key(&self, key_name: &str) -> Result<Option<SigningKey>> {
+        self.keys.get_private_key(key_name)
+    }
+
+    fn list_private_keys(&self) -> Result<Vec<String>> {
+        self.keys.list_private_keys()
+    }
+
+    fn remove_private_key(&self, key_name: &str) -> Result<()> {
+        self.keys.remove_private_key(key_name)
+    }
+
+    fn key_storage(&self) -> KeyStorage {
+        self.keys.key_storage()
+    }
+
+    fn get_cached_crdt_state(&self, entry_id: &ID, subtree: &str) -> Result<Option<String>> {
+        let cache = self.crdt_cache.read().unwrap();
+        Ok(cache.get(&(entry_id.clone(), subtree.to_string())).cloned())
+    }
+
+    fn cache_crdt_state(&self, entry_id: &ID, subtree: &str, state: String) -> Result<()> {
+        let mut cache = self.crdt_cache.write().unwrap();
+        cache.insert((entry_id.clone(), subtree.to_string()), state);
+        Ok(())
+    }
+
+    fn clear_crdt_cache(&self) -> Result<()> {
+        self.crdt_cache.write().unwrap().clear();
+        Ok(())
+    }
+
+    fn get_sorted_subtree_parents(
+        &self,
+        tree_id: &ID,
+        entry_id: &ID,
+        subtree: &str,
+    ) -> Result<Vec<ID>> {
+        self.query_ids(Query::SortedSubtreeParents {
+            tree: tree_id.clone(),
+            entry: entry_id.clone(),
+            subtree: subtree.to_string(),
+        })
+    }
+
+    fn get_path_from_to(
+        &self,
+        tree_id: &ID,
+        subtree: &str,
+        from_id: &ID,
+        to_ids: &[ID],
+    ) -> Result<Vec<ID>> {
+        self.query_ids(Query::PathFromTo {
+            tree: tree_id.clone(),
+            subtree: subtree.to_string(),
+            from: from_id.clone(),
+            to: to_ids.to_vec(),
+        })
+    }
+}
+
+/// Answers a query against the serving peer's database.
+pub(crate) fn evaluate(backend: &dyn Database, query: Query) -> Result<Answer> {
+    Ok(match query {
+        Query::Get { id } => Answer::Entry(backend.get(&id)?),
+        Query::VerificationStatus { id } => Answer::Status(backend.get_verification_status(&id)?),
+        Query::EntriesByVerificationStatus { status } => {
+            Answer::Ids(backend.get_entries_by_verification_status(status)?)
+        }
+        Query::Tips { tree } => Answer::Ids(backend.get_tips(&tree)?),
+        Query::SubtreeTips { tree, subtree } => {
+            Answer::Ids(backend.get_subtree_tips(&tree, &subtree)?)
+        }
+        Query::SubtreeTipsUpToEntries {
+            tree,
+            subtree,
+            entries,
+        } => Answer::Ids(backend.get_subtree_tips_up_to_entries(&tree, &subtree, &entries)?),
+        Query::AllRoots => Answer::Ids(backend.all_roots()?),
+        Query::FindLca {
+            tree,
+            subtree,
+            entries,
+        } => Answer::Id(backend.find_lca(&tree, &subtree, &entries)?),
+        Query::CollectRootToTarget {
+            tree,
+            subtree,
+            target,
+        } => Answer::Ids(backend.collect_root_to_target(&tree, &subtree, &target)?),
+        Query::Tree { tree } => Answer::Entries(backend.get_tree(&tree)?),
+        Query::Subtree { tree, subtree } => Answer::Entries(backend.get_subtree(&tree, &subtree)?),
+        Query::TreeFromTips { tree, tips } => {
+            Answer::Entries(backend.get_tree_from_tips(&tree, &tips)?)
+        }
+        Query::SubtreeFromTips {
+            tree,
+            subtree,
+            tips,
+        } => Answer::Entries(backend.get_subtree_from_tips(&tree, &subtree, &tips)?),
+        Query::SortedSubtreeParents {
+            tree,
+            entry,
+            subtree,
+        } => Answer::Ids(backend.get_sorted_subtree_parents(&tree, &entry, &subtree)?),
+        Query::PathFromTo {
+            tree,
+            subtree,
+            from,
+            to,
+        } => Answer::Ids(backend.get_path_from_to(&tree, &subtree, &from, &to)?),
+    })
+}
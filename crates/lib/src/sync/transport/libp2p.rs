@@ -0,0 +1,912 @@
+//! Peer-to-peer sync over libp2p
+//!
+//! A [`Libp2p`] node runs a libp2p swarm on a background thread:
+//!
+//! * Its identity is the node's Eidetica signing key, so the libp2p `PeerId`
+//!   of a peer is derived from its Eidetica public key, and the noise handshake
+//!   proves that the peer holds the matching private key. See
+//!   [`peer_id_for_key`] and [`key_for_peer`].
+//! * Peers are discovered with mDNS on the local network and with Kademlia,
+//!   seeded from bootstrap addresses and from every connected peer.
+//! * The tips of subscribed trees are published periodically on a gossipsub
+//!   topic per tree. A node that sees tips it does not have fetches the missing
+//!   entries from the peer that forwarded them.
+//!
+//! Nodes connect over TCP or QUIC. A node behind a NAT can listen through a
+//! relay; peers reach it through the relay first, and the two nodes then punch
+//! a hole for a direct connection (DCUtR). Nodes can act as relays for others.
+//! A [`Ticket`] bundles a node's peer ID with its addresses, including relayed
+//! ones, so that two devices can connect by exchanging tickets.
+//!
+//! Fetches are a request/response exchange: the request carries the fetching
+//! node's tips and the response returns up to a batch of entries it is missing,
+//! parents first, and how many more remain. The fetching node keeps asking
+//! until nothing remains. A node that receives a fetch with tips it does not
+//! know fetches them back, so both sides end up with the union of their entries.
+//! Received entries are verified like any other synced entries.
+//!
+//! Only subscribed trees are served to peers.
+
+use crate::backend::Database;
+use crate::entry::ID;
+use crate::sync::{SyncError, SyncStats, VerificationPool, merge};
+use base64ct::{Base64UrlUnpadded, Encoding};
+use ed25519_dalek::{SigningKey, VerifyingKey};
+use libp2p::futures::StreamExt;
+use libp2p::gossipsub::{self, IdentTopic, MessageAuthenticity};
+use libp2p::identity::{self, Keypair};
+use libp2p::kad::{self, store::MemoryStore};
+use libp2p::multiaddr::Protocol;
+use libp2p::request_response::{self, OutboundRequestId, ProtocolSupport, ResponseChannel};
+use libp2p::swarm::{NetworkBehaviour, SwarmEvent, behaviour::toggle::Toggle, dial_opts::DialOpts};
+use libp2p::{
+    Multiaddr, PeerId, StreamProtocol, Swarm, core::transport::ListenerId, dcutr, identify, mdns,
+    noise, relay, tcp, yamux,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, mpsc as std_mpsc};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::MissedTickBehavior;
+
+/// Protocol for fetching entries from a peer
+const SYNC_PROTOCOL: StreamProtocol = StreamProtocol::new("/eidetica/sync/1.0.0");
+/// Kademlia protocol, kept apart from the public IPFS DHT
+const KAD_PROTOCOL: StreamProtocol = StreamProtocol::new("/eidetica/kad/1.0.0");
+const IDENTIFY_PROTOCOL: &str = "/eidetica/id/1.0.0";
+/// Discovered peers are dialed until the node has this many connections
+const TARGET_PEERS: usize = 16;
+
+/// Settings for a [`Libp2p`] node.
+#[derive(Debug, Clone)]
+pub struct Libp2pConfig {
+    /// Addresses to listen on, TCP and QUIC on every interface by default
+    pub listen: Vec<Multiaddr>,
+    /// Relays to listen through, each address ending with the relay's peer ID
+    ///
+    /// Peers that cannot reach this node directly connect through a relay and
+    /// then upgrade to a direct connection if hole punching succeeds.
+    pub relays: Vec<Multiaddr>,
+    /// Whether to relay connections for other nodes
+    ///
+    /// The node's listen addresses are assumed to be reachable by the peers
+    /// it relays for.
+    pub relay_server: bool,
+    /// Addresses of peers to connect to on start, which also seed Kademlia
+    pub bootstrap: Vec<Multiaddr>,
+    /// Whether to discover peers on the local network with mDNS
+    pub mdns: bool,
+    /// How often the tips of subscribed trees are published
+    pub gossip_interval: Duration,
+    /// Public keys of the peers allowed to connect, or `None` to allow any peer
+    pub allowed_keys: Option<Vec<VerifyingKey>>,
+    /// Entries per fetch response
+    pub batch_size: usize,
+    /// Pool that verifies fetched entries
+    pub verification: VerificationPool,
+}
+
+impl Default for Libp2pConfig {
+    fn default() -> Self {
+        Self {
+            listen: vec![
+                "/ip4/0.0.0.0/tcp/0".parse().expect("valid multiaddr"),
+                "/ip4/0.0.0.0/udp/0/quic-v1"
+                    .parse()
+                    .expect("valid multiaddr"),
+            ],
+            relays: Vec::new(),
+            relay_server: false,
+            bootstrap: Vec::new(),
+            mdns: true,
+            gossip_interval: Duration::from_secs(30),
+            allowed_keys: None,
+            batch_size: 256,
+            verification: VerificationPool::default(),
+        }
+    }
+}
+
+/// Returns the libp2p peer ID of the node using `key`.
+pub fn peer_id_for_key(key: &VerifyingKey) -> PeerId {
+    let key = identity::ed25519::PublicKey::try_from_bytes(key.as_bytes())
+        .expect("a valid ed25519 key is a valid libp2p key");
+    identity::PublicKey::from(key).to_peer_id()
+}
+
+/// Returns the Eidetica public key of a peer, or `None` if the peer does not
+/// use an ed25519 identity.
+pub fn key_for_peer(peer: &PeerId) -> Option<VerifyingKey> {
+    // Ed25519 peer IDs embed the public key instead of hashing it
+    let multihash = peer.as_ref();
+    if multihash.code() != 0 {
+        return None;
+    }
+    let key = identity::PublicKey::try_decode_protobuf(multihash.digest())
+        .ok()?
+        .try_into_ed25519()
+        .ok()?;
+    VerifyingKey::from_bytes(&key.to_bytes()).ok()
+}
+
+/// The peer ID and addresses of a node, shared out of band to connect to it.
+///
+/// Tickets are written as `eidetica-node:` followed by URL-safe base64, so they
+/// can be pasted or put in a QR code. Get one from [`Libp2p::ticket`] and
+/// connect to it with [`Libp2p::dial_ticket`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ticket {
+    /// The node's peer ID
+    pub peer: PeerId,
+    /// Addresses the node can be reached at, possibly through a relay
+    pub addrs: Vec<Multiaddr>,
+}
+
+impl Ticket {
+    const PREFIX: &'static str = "eidetica-node:";
+}
+
+/// How a [`Ticket`] is encoded before base64.
+#[derive(Serialize, Deserialize)]
+struct TicketData {
+    peer: String,
+    addrs: Vec<String>,
+}
+
+impl fmt::Display for Ticket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let data = TicketData {
+            peer: self.peer.to_string(),
+            addrs: self.addrs.iter().map(Multiaddr::to_string).collect(),
+        };
+        let json = serde_json::to_vec(&data).map_err(|_| fmt::Error)?;
+        write!(
+            f,
+            "{}{}",
+            Self::PREFIX,
+            Base64UrlUnpadded::encode_string(&json)
+        )
+    }
+}
+
+impl FromStr for Ticket {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> crate::Result<Self> {
+        let invalid = |reason: String| SyncError::InvalidTicket { reason };
+        let encoded = s
+            .strip_prefix(Self::PREFIX)
+            .ok_or_else(|| invalid(format!("missing '{}' prefix", Self::PREFIX)))?;
+        let json = Base64UrlUnpadded::decode_vec(encoded).map_err(|e| invalid(e.to_string()))?;
+        let data: TicketData = serde_json::from_slice(&json).map_err(|e| invalid(e.to_string()))?;
+        let peer = data
+            .peer
+            .parse()
+            .map_err(|_| invalid(format!("invalid peer ID '{}'", data.peer)))?;
+        let addrs = data
+            .addrs
+            .iter()
+            .map(|addr| {
+                addr.parse()
+                    .map_err(|_| invalid(format!("invalid address '{addr}'")))
+            })
+            .collect::<std::result::Result<_, _>>()?;
+        Ok(Self { peer, addrs })
+    }
+}
+
+/// A libp2p node that keeps subscribed trees in sync with its peers.
+///
+/// Methods block until the background thread has handled them. The node stops
+/// when it is dropped.
+///
+/// # Example
+/// ```no_run
+/// # use eidetica::{backend::database::InMemory, basedb::BaseDB};
+/// # use eidetica::sync::transport::{Libp2p, Libp2pConfig};
+/// # fn main() -> eidetica::Result<()> {
+/// let db = BaseDB::new(Box::new(InMemory::new()));
+/// db.add_private_key("key")?;
+/// let tree = db.new_tree_default("key")?;
+///
+/// let key = db.backend().get_private_key("key")?.unwrap();
+/// let node = Libp2p::start(db.backend().clone(), &key, Libp2pConfig::default())?;
+/// node.subscribe(tree.root_id())?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Libp2p {
+    peer_id: PeerId,
+    commands: Option<mpsc::UnboundedSender<Command>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Libp2p {
+    /// Starts a node for `backend` that identifies itself with `key`.
+    ///
+    /// Returns once the node listens on every address in `config.listen`.
+    pub fn start(
+        backend: Arc<dyn Database>,
+        key: &SigningKey,
+        config: Libp2pConfig,
+    ) -> crate::Result<Self> {
+        let keypair = Keypair::ed25519_from_bytes(key.to_bytes()).map_err(transport_failed)?;
+        let peer_id = keypair.public().to_peer_id();
+        let (commands, receiver) = mpsc::unbounded_channel();
+        let (ready, started) = std_mpsc::channel();
+
+        let thread = std::thread::Builder::new()
+            .name("eidetica-libp2p".to_string())
+            .spawn(move || {
+                let runtime = match tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                {
+                    Ok(runtime) => runtime,
+                    Err(e) => {
+                        let _ = ready.send(Err(e.into()));
+                        return;
+                    }
+                };
+                runtime.block_on(async move {
+                    match Node::new(backend, keypair, config) {
+                        Ok((node, listeners)) => node.run(receiver, listeners, ready).await,
+                        Err(e) => {
+                            let _ = ready.send(Err(e));
+                        }
+                    }
+                });
+            })?;
+
+        match started.recv() {
+            Ok(Ok(())) => Ok(Self {
+                peer_id,
+                commands: Some(commands),
+                thread: Some(thread),
+            }),
+            Ok(Err(e)) => {
+                drop(commands);
+                let _ = thread.join();
+                Err(e)
+            }
+            Err(_) => Err(stopped()),
+        }
+    }
+
+    /// The libp2p peer ID of this node, derived from its signing key.
+    pub fn peer_id(&self) -> PeerId {
+        self.peer_id
+    }
+
+    /// Addresses the node is listening on.
+    pub fn listen_addrs(&self) -> crate::Result<Vec<Multiaddr>> {
+        self.request(Command::ListenAddrs)
+    }
+
+    /// A ticket for connecting to this node.
+    ///
+    /// Holds the addresses the node listens on, relayed ones included once the
+    /// relay has accepted the node, and external addresses observed by peers.
+    pub fn ticket(&self) -> crate::Result<Ticket> {
+        let addrs = self.request(Command::Addresses)?;
+        Ok(Ticket {
+            peer: self.peer_id,
+            addrs,
+        })
+    }
+
+    /// Starts connecting to the node described by `ticket`.
+    ///
+    /// Every address in the ticket is tried. Returns once the dial has started.
+    pub fn dial_ticket(&self, ticket: &Ticket) -> crate::Result<()> {
+        self.request(|reply| Command::DialTicket(ticket.clone(), reply))?
+    }
+
+    /// Peers the node is currently connected to.
+    pub fn connected_peers(&self) -> crate::Result<Vec<PeerId>> {
+        self.request(Command::ConnectedPeers)
+    }
+
+    /// Starts connecting to the peer at `addr`.
+    ///
+    /// Returns once the dial has started; the connection is made in the background.
+    pub fn dial(&self, addr: Multiaddr) -> crate::Result<()> {
+        self.request(|reply| Command::Dial(addr, reply))?
+    }
+
+    /// Keeps `tree` in sync with peers that subscribe to it too.
+    ///
+    /// The node publishes the tree's tips right away and then every gossip
+    /// interval, fetches entries when peers publish tips it does not have, and
+    /// serves the tree to peers that fetch from it.
+    pub fn subscribe(&self, tree: &ID) -> crate::Result<()> {
+        self.request(|reply| Command::Subscribe(tree.clone(), reply))?
+    }
+
+    /// Stops syncing and serving `tree`.
+    pub fn unsubscribe(&self, tree: &ID) -> crate::Result<()> {
+        self.request(|reply| Command::Unsubscribe(tree.clone(), reply))?
+    }
+
+    /// Publishes the current tips of a subscribed tree without waiting for the
+    /// next gossip interval, e.g. right after a commit.
+    pub fn announce(&self, tree: &ID) -> crate::Result<()> {
+        self.request(|reply| Command::Announce(tree.clone(), reply))?
+    }
+
+    /// Fetches the entries of `tree` that `peer` has and this node is missing.
+    ///
+    /// The peer must subscribe to the tree. The tree does not need to exist
+    /// locally. The peer fetches the entries it is missing in turn, in the
+    /// background, so `SyncStats::sent` is always zero.
+    pub fn sync_tree(&self, peer: &PeerId, tree: &ID) -> crate::Result<SyncStats> {
+        self.request(|reply| Command::Sync {
+            peer: *peer,
+            tree: tree.clone(),
+            reply,
+        })?
+    }
+
+    /// Stops the node and waits for its background thread to finish.
+    pub fn shutdown(mut self) {
+        self.stop();
+    }
+
+    fn stop(&mut self) {
+        // Closing the command channel ends the event loop
+        self.commands.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+
+    fn request<R>(&self, command: impl FnOnce(Reply<R>) -> Command) -> crate::Result<R> {
+        let (reply, response) = std_mpsc::sync_channel(1);
+        self.commands
+            .as_ref()
+            .and_then(|commands| commands.send(command(reply)).ok())
+            .ok_or_else(stopped)?;
+        response.recv().map_err(|_| stopped())
+    }
+}
+
+impl Drop for Libp2p {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+type Reply<T> = std_mpsc::SyncSender<T>;
+
+enum Command {
+    ListenAddrs(Reply<Vec<Multiaddr>>),
+    Addresses(Reply<Vec<Multiaddr>>),
+    ConnectedPeers(Reply<Vec<PeerId>>),
+    Dial(Multiaddr, Reply<crate::Result<()>>),
+    DialTicket(Ticket, Reply<crate::Result<()>>),
+    Subscribe(ID, Reply<crate::Result<()>>),
+    Unsubscribe(ID, Reply<crate::Result<()>>),
+    Announce(ID, Reply<crate::Result<()>>),
+    Sync {
+        peer: PeerId,
+        tree: ID,
+        reply: Reply<crate::Result<SyncStats>>,
+    },
+}
+
+/// Tips of a tree, published on the tree's topic.
+#[derive(Debug, Serialize, Deserialize)]
+struct Announcement {
+    tree: ID,
+    tips: Vec<ID>,
+}
+
+/// Asks a peer for the entries of a tree that are not reachable from `tips`.
+#[derive(Debug, Serialize, Deserialize)]
+struct FetchRequest {
+    tree: ID,
+    tips: Vec<ID>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum FetchResponse {
+    /// A batch of missing entries, parents first
+    Entries {
+        entries: Vec<crate::entry::Entry>,
+        remaining: usize,
+    },
+    Error {
+        reason: String,
+    },
+}
+
+#[derive(NetworkBehaviour)]
+struct Behaviour {
+    gossipsub: gossipsub::Behaviour,
+    kademlia: kad::Behaviour<MemoryStore>,
+    identify: identify::Behaviour,
+    mdns: Toggle<mdns::tokio::Behaviour>,
+    relay_client: relay::client::Behaviour,
+    relay: Toggle<relay::Behaviour>,
+    dcutr: dcutr::Behaviour,
+    sync: request_response::json::Behaviour<FetchRequest, FetchResponse>,
+}
+
+/// A fetch waiting for its next response.
+struct Fetch {
+    peer: PeerId,
+    tree: ID,
+    /// New entries stored by earlier responses
+    received: usize,
+    reply: Option<Reply<crate::Result<SyncStats>>>,
+}
+
+/// The state of the event loop on the background thread.
+struct Node {
+    swarm: Swarm<Behaviour>,
+    backend: Arc<dyn Database>,
+    config: Libp2pConfig,
+    allowed: Option<HashSet<PeerId>>,
+    subscribed: HashSet<ID>,
+    fetches: HashMap<OutboundRequestId, Fetch>,
+}
+
+impl Node {
+    /// Builds the swarm and starts listening, returning the listeners that
+    /// have not reported an address yet.
+    fn new(
+        backend: Arc<dyn Database>,
+        keypair: Keypair,
+        config: Libp2pConfig,
+    ) -> crate::Result<(Self, HashSet<ListenerId>)> {
+        let use_mdns = config.mdns;
+        let relay_server = config.relay_server;
+        let mut swarm = libp2p::SwarmBuilder::with_existing_identity(keypair)
+            .with_tokio()
+            .with_tcp(
+                tcp::Config::default(),
+                noise::Config::new,
+                yamux::Config::default,
+            )
+            .map_err(transport_failed)?
+            .with_quic()
+            .with_relay_client(noise::Config::new, yamux::Config::default)
+            .map_err(transport_failed)?
+            .with_behaviour(|key, relay_client| {
+                let peer_id = key.public().to_peer_id();
+                let gossipsub = gossipsub::Behaviour::new(
+                    MessageAuthenticity::Signed(key.clone()),
+                    gossipsub::Config::default(),
+                )?;
+                let mut kademlia = kad::Behaviour::with_config(
+                    peer_id,
+                    MemoryStore::new(peer_id),
+                    kad::Config::new(KAD_PROTOCOL),
+                );
+                kademlia.set_mode(Some(kad::Mode::Server));
+                let identify = identify::Behaviour::new(identify::Config::new(
+                    IDENTIFY_PROTOCOL.to_string(),
+                    key.public(),
+                ));
+                let mdns = use_mdns
+                    .then(|| mdns::tokio::Behaviour::new(mdns::Config::default(), peer_id))
+                    .transpose()?;
+                let relay =
+                    relay_server.then(|| relay::Behaviour::new(peer_id, relay::Config::default()));
+                let sync = request_response::json::Behaviour::new(
+                    [(SYNC_PROTOCOL, ProtocolSupport::Full)],
+                    request_response::Config::default(),
+                );
+                Ok(Behaviour {
+                    gossipsub,
+                    kademlia,
+                    identify,
+                    mdns: mdns.into(),
+                    relay_client,
+                    relay: relay.into(),
+                    dcutr: dcutr::Behaviour::new(peer_id),
+                    sync,
+                })
+            })
+            .map_err(transport_failed)?
+            .with_swarm_config(|c| c.with_idle_connection_timeout(Duration::from_secs(60)))
+            .build();
+
+        let mut listeners = HashSet::new();
+        for addr in &config.listen {
+            listeners.insert(swarm.listen_on(addr.clone()).map_err(transport_failed)?);
+        }
+        // Relayed listeners come up once the relay accepts the reservation,
+        // which is not waited for
+        for relay in &config.relays {
+            swarm
+                .listen_on(relay.clone().with(Protocol::P2pCircuit))
+                .map_err(transport_failed)?;
+        }
+        for addr in &config.bootstrap {
+            swarm.dial(addr.clone()).map_err(transport_failed)?;
+        }
+
+        let allowed = config
+            .allowed_keys
+            .as_ref()
+            .map(|keys| keys.iter().map(peer_id_for_key).collect());
+        let node = Self {
+            swarm,
+            backend,
+            config,
+            allowed,
+            subscribed: HashSet::new(),
+            fetches: HashMap::new(),
+        };
+        Ok((node, listeners))
+    }
+
+    async fn run(
+        mut self,
+        mut commands: mpsc::UnboundedReceiver<Command>,
+        mut listeners: HashSet<ListenerId>,
+        ready: std_mpsc::Sender<crate::Result<()>>,
+    ) {
+        let mut ready = Some(ready);
+        if listeners.is_empty() {
+            ready.take().map(|ready| ready.send(Ok(())));
+        }
+        let mut gossip =
+            tokio::time::interval(self.config.gossip_interval.max(Duration::from_millis(1)));
+        gossip.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                command = commands.recv() => match command {
+                    Some(command) => self.handle_command(command),
+                    None => break,
+                },
+                event = self.swarm.select_next_some() => match event {
+                    SwarmEvent::NewListenAddr { listener_id, address } => {
+                        // Peers reserving a circuit are told the relay's external addresses
+                        if self.config.relay_server && !is_relayed(&address) {
+                            self.swarm.add_external_address(address);
+                        }
+                        if listeners.remove(&listener_id) && listeners.is_empty() {
+                            ready.take().map(|ready| ready.send(Ok(())));
+                        }
+                    }
+                    SwarmEvent::ListenerClosed { listener_id, reason, .. }
+                        if listeners.contains(&listener_id) =>
+                    {
+                        let reason = match reason {
+                            Ok(()) => "listener closed".to_string(),
+                            Err(e) => e.to_string(),
+                        };
+                        if let Some(ready) = ready.take() {
+                            let _ = ready.send(Err(transport_failed(reason)));
+                        }
+                        break;
+                    }
+                    event => self.handle_event(event),
+                },
+                _ = gossip.tick() => {
+                    let trees: Vec<ID> = self.subscribed.iter().cloned().collect();
+                    for tree in trees {
+                        // Tips are published again next interval if this fails
+                        let _ = self.announce(&tree);
+                    }
+                }
+            }
+        }
+    }
+
+    fn handle_command(&mut self, command: Command) {
+        match command {
+            Command::ListenAddrs(reply) => {
+                let _ = reply.send(self.swarm.listeners().cloned().collect());
+            }
+            Command::ConnectedPeers(reply) => {
+                let _ = reply.send(self.swarm.connected_peers().copied().collect());
+            }
+            Command::Addresses(reply) => {
+                let mut addrs: Vec<Multiaddr> = self.swarm.listeners().cloned().collect();
+                for addr in self.swarm.external_addresses() {
+                    if !addrs.contains(addr) {
+                        addrs.push(addr.clone());
+                    }
+                }
+                let _ = reply.send(addrs);
+            }
+            Command::Dial(addr, reply) => {
+                let _ = reply.send(self.swarm.dial(addr).map_err(transport_failed));
+            }
+            Command::DialTicket(ticket, reply) => {
+                for addr in ticket.addrs.iter().filter(|addr| !is_relayed(addr)) {
+                    self.swarm
+                        .behaviour_mut()
+                        .kademlia
+                        .add_address(&ticket.peer, addr.clone());
+                }
+                let opts = DialOpts::peer_id(ticket.peer)
+                    .addresses(ticket.addrs)
+                    .build();
+                let _ = reply.send(self.swarm.dial(opts).map_err(transport_failed));
+            }
+            Command::Subscribe(tree, reply) => {
+                let result = self
+                    .swarm
+                    .behaviour_mut()
+                    .gossipsub
+                    .subscribe(&topic(&tree))
+                    .map_err(transport_failed)
+                    .and_then(|_| {
+                        self.subscribed.insert(tree.clone());
+                        self.announce(&tree)
+                    });
+                let _ = reply.send(result);
+            }
+            Command::Unsubscribe(tree, reply) => {
+                self.subscribed.remove(&tree);
+                let result = self
+                    .swarm
+                    .behaviour_mut()
+                    .gossipsub
+                    .unsubscribe(&topic(&tree))
+                    .map(|_| ())
+                    .map_err(transport_failed);
+                let _ = reply.send(result);
+            }
+            Command::Announce(tree, reply) => {
+                let _ = reply.send(self.announce(&tree));
+            }
+            Command::Sync { peer, tree, reply } => self.fetch(peer, tree, 0, Some(reply)),
+        }
+    }
+
+    fn handle_event(&mut self, event: SwarmEvent<BehaviourEvent>) {
+        match event {
+            SwarmEvent::ConnectionEstablished { peer_id, .. } if !self.is_allowed(&peer_id) => {
+                let _ = self.swarm.disconnect_peer_id(peer_id);
+            }
+            SwarmEvent::Behaviour(BehaviourEvent::Gossipsub(gossipsub::Event::Message {
+                propagation_source,
+                message,
+                ..
+            })) => self.handle_announcement(propagation_source, &message.data),
+            SwarmEvent::Behaviour(BehaviourEvent::Mdns(mdns::Event::Discovered(peers))) => {
+                for (peer, addr) in peers {
+                    self.swarm.behaviour_mut().kademlia.add_address(&peer, addr);
+                    self.connect(peer);
+                }
+            }
+            SwarmEvent::Behaviour(BehaviourEvent::Identify(identify::Event::Received {
+                peer_id,
+                info,
+                ..
+            })) if self.is_allowed(&peer_id) => {
+                for addr in info.listen_addrs {
+                    self.swarm
+                        .behaviour_mut()
+                        .kademlia
+                        .add_address(&peer_id, addr);
+                }
+            }
+            SwarmEvent::Behaviour(BehaviourEvent::Kademlia(kad::Event::RoutingUpdated {
+                peer,
+                ..
+            })) => self.connect(peer),
+            SwarmEvent::Behaviour(BehaviourEvent::Sync(request_response::Event::Message {
+                peer,
+                message,
+            })) => match message {
+                request_response::Message::Request {
+                    request, channel, ..
+                } => self.answer(peer, request, channel),
+                request_response::Message::Response {
+                    request_id,
+                    response,
+                } => self.handle_response(request_id, response),
+            },
+            SwarmEvent::Behaviour(BehaviourEvent::Sync(
+                request_response::Event::OutboundFailure {
+                    request_id, error, ..
+                },
+            )) => {
+                if let Some(Fetch {
+                    reply: Some(reply), ..
+                }) = self.fetches.remove(&request_id)
+                {
+                    let _ = reply.send(Err(transport_failed(error)));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn is_allowed(&self, peer: &PeerId) -> bool {
+        self.allowed
+            .as_ref()
+            .is_none_or(|allowed| allowed.contains(peer))
+    }
+
+    /// Dials a discovered peer if the node wants more connections.
+    fn connect(&mut self, peer: PeerId) {
+        if self.is_allowed(&peer)
+            && peer != *self.swarm.local_peer_id()
+            && !self.swarm.is_connected(&peer)
+            && self.swarm.connected_peers().count() < TARGET_PEERS
+        {
+            // Addresses come from Kademlia; failures are retried on the next discovery
+            let _ = self.swarm.dial(peer);
+        }
+    }
+
+    fn announce(&mut self, tree: &ID) -> crate::Result<()> {
+        if !self.subscribed.contains(tree) {
+            return Err(transport_failed(format!("tree {tree} is not subscribed")));
+        }
+        let announcement = Announcement {
+            tree: tree.clone(),
+            tips: self.backend.get_tips(tree)?,
+        };
+        let data = serde_json::to_vec(&announcement)?;
+        match self
+            .swarm
+            .behaviour_mut()
+            .gossipsub
+            .publish(topic(tree), data)
+        {
+            // Nobody to tell yet; peers that subscribe later get the next announcement
+            Ok(_) | Err(gossipsub::PublishError::InsufficientPeers) => Ok(()),
+            Err(e) => Err(transport_failed(e)),
+        }
+    }
+
+    fn handle_announcement(&mut self, source: PeerId, data: &[u8]) {
+        let Ok(Announcement { tree, tips }) = serde_json::from_slice(data) else {
+            return;
+        };
+        if self.subscribed.contains(&tree) && !self.is_fetching(&tree) && self.lacks_any(&tips) {
+            self.fetch(source, tree, 0, None);
+        }
+    }
+
+    fn is_fetching(&self, tree: &ID) -> bool {
+        self.fetches.values().any(|fetch| fetch.tree == *tree)
+    }
+
+    fn lacks_any(&self, ids: &[ID]) -> bool {
+        ids.iter().any(|id| self.backend.get(id).is_err())
+    }
+
+    /// Asks `peer` for the entries of `tree` this node is missing.
+    fn fetch(
+        &mut self,
+        peer: PeerId,
+        tree: ID,
+        received: usize,
+        reply: Option<Reply<crate::Result<SyncStats>>>,
+    ) {
+        let tips = match self.backend.get_tips(&tree) {
+            Ok(tips) => tips,
+            Err(e) => {
+                if let Some(reply) = reply {
+                    let _ = reply.send(Err(e));
+                }
+                return;
+            }
+        };
+        let request = FetchRequest {
+            tree: tree.clone(),
+            tips,
+        };
+        let request_id = self.swarm.behaviour_mut().sync.send_request(&peer, request);
+        self.fetches.insert(
+            request_id,
+            Fetch {
+                peer,
+                tree,
+                received,
+                reply,
+            },
+        );
+    }
+
+    fn handle_response(&mut self, request_id: OutboundRequestId, response: FetchResponse) {
+        let Some(fetch) = self.fetches.remove(&request_id) else {
+            return;
+        };
+        let result = match response {
+            FetchResponse::Entries { entries, remaining } => merge::merge_entries(
+                &self.backend,
+                &fetch.tree,
+                entries,
+                &self.config.verification,
+            )
+            .map(|received| (received.len(), remaining)),
+            FetchResponse::Error { reason } => Err(SyncError::RemoteError { reason }.into()),
+        };
+        match result {
+            // A batch without new entries means the peer is not making progress
+            Ok((received, remaining)) if remaining > 0 && received > 0 => {
+                self.fetch(
+                    fetch.peer,
+                    fetch.tree,
+                    fetch.received + received,
+                    fetch.reply,
+                );
+            }
+            Ok((received, _)) => {
+                if let Some(reply) = fetch.reply {
+                    let _ = reply.send(Ok(SyncStats {
+                        sent: 0,
+                        received: fetch.received + received,
+                    }));
+                }
+            }
+            Err(e) => {
+                if let Some(reply) = fetch.reply {
+                    let _ = reply.send(Err(e));
+                }
+            }
+        }
+    }
+
+    fn answer(
+        &mut self,
+        peer: PeerId,
+        request: FetchRequest,
+        channel: ResponseChannel<FetchResponse>,
+    ) {
+        let shared = self.is_allowed(&peer) && self.subscribed.contains(&request.tree);
+        let response = if !shared {
+            FetchResponse::Error {
+                reason: format!("tree {} is not shared with this peer", request.tree),
+            }
+        } else {
+            match merge::missing_entries(&self.backend, &request.tree, &request.tips) {
+                Ok(mut entries) => {
+                    let batch_size = self.config.batch_size.max(1);
+                    let remaining = entries.len().saturating_sub(batch_size);
+                    entries.truncate(batch_size);
+                    FetchResponse::Entries { entries, remaining }
+                }
+                Err(e) => FetchResponse::Error {
+                    reason: e.to_string(),
+                },
+            }
+        };
+        // The peer may have gone away, in which case its fetch fails on its side
+        let _ = self
+            .swarm
+            .behaviour_mut()
+            .sync
+            .send_response(channel, response);
+
+        // The peer may have entries this node is missing
+        if shared && !self.is_fetching(&request.tree) && self.lacks_any(&request.tips) {
+            self.fetch(peer, request.tree, 0, None);
+        }
+    }
+}
+
+fn is_relayed(addr: &Multiaddr) -> bool {
+    addr.iter().any(|protocol| protocol == Protocol::P2pCircuit)
+}
+
+/// The gossipsub topic on which a tree's tips are published.
+fn topic(tree: &ID) -> IdentTopic {
+    IdentTopic::new(format!("eidetica/tips/{tree}"))
+}
+
+fn transport_failed(error: impl std::fmt::Display) -> crate::Error {
+    SyncError::TransportFailed {
+        reason: error.to_string(),
+    }
+    .into()
+}
+
+fn stopped() -> crate::Error {
+    transport_failed("libp2p node has stopped")
+}
@@ -0,0 +1,17 @@
+//! Network transports that keep trees in sync without managing connections by hand
+//!
+//! [`SyncPeer`](super::SyncPeer) syncs over a single connection that the caller
+//! opens. The transports here find peers themselves, authenticate them and
+//! sync subscribed trees in the background.
+//!
+//! * `Libp2p` (feature `libp2p`): discovers peers with mDNS and Kademlia and
+//!   gossips the tips of subscribed trees over libp2p. Connects over TCP or
+//!   QUIC, through relays with hole punching, or from an exchanged `Ticket`.
+
+#[cfg(feature = "libp2p")]
+mod libp2p;
+
+#[cfg(feature = "libp2p")]
+pub use self::libp2p::{Libp2p, Libp2pConfig, Ticket, key_for_peer, peer_id_for_key};
+#[cfg(feature = "libp2p")]
+pub use ::libp2p::{Multiaddr, PeerId};
@@ -0,0 +1,133 @@
+//! Parallel verification of entries received from peers
+//!
+//! Verifying an entry resolves the tree's authentication settings as of the
+//! entry and checks its signature. Entries of a received batch are independent
+//! once the whole batch is stored, so a [`VerificationPool`] spreads them over
+//! worker threads. The queue between the ingest path and the workers is
+//! bounded, so a large batch is fed to the workers as they free up instead of
+//! being buffered twice.
+
+use crate::Result;
+use crate::Tree;
+use crate::backend::VerificationStatus;
+use crate::entry::ID;
+use std::num::NonZeroUsize;
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Worker threads that verify the entries received during a sync.
+///
+/// The workers run for the duration of each received batch. Batches smaller
+/// than two entries, and pools with a single worker, are verified on the
+/// calling thread.
+///
+/// # Example
+/// ```
+/// # use eidetica::sync::VerificationPool;
+/// let pool = VerificationPool::new(4).queue_capacity(256);
+/// assert_eq!(pool.workers(), 4);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VerificationPool {
+    workers: usize,
+    queue_capacity: usize,
+}
+
+impl VerificationPool {
+    /// Default number of queued entries per worker.
+    const QUEUE_PER_WORKER: usize = 64;
+
+    /// Creates a pool with the given number of workers, at least one.
+    pub fn new(workers: usize) -> Self {
+        let workers = workers.max(1);
+        Self {
+            workers,
+            queue_capacity: workers * Self::QUEUE_PER_WORKER,
+        }
+    }
+
+    /// Creates a pool that verifies entries on the calling thread.
+    pub fn single_threaded() -> Self {
+        Self::new(1)
+    }
+
+    /// Sets how many entries may wait for a free worker, at least one.
+    pub fn queue_capacity(mut self, capacity: usize) -> Self {
+        self.queue_capacity = capacity.max(1);
+        self
+    }
+
+    /// Get the number of worker threads
+    pub fn workers(&self) -> usize {
+        self.workers
+    }
+
+    /// Verifies stored entries of `tree`, marking those that pass as `Verified`.
+    ///
+    /// Entries that fail, or cannot be checked, keep their current status.
+    /// Returns the number of entries that passed.
+    pub(crate) fn verify(&self, tree: &Tree, ids: &[ID]) -> Result<usize> {
+        if self.workers == 1 || ids.len() < 2 {
+            let mut verified = 0;
+            for id in ids {
+                verified += verify_one(tree, id)? as usize;
+            }
+            return Ok(verified);
+        }
+
+        let (sender, receiver) = mpsc::sync_channel::<&ID>(self.queue_capacity);
+        let receiver = Arc::new(Mutex::new(receiver));
+        thread::scope(|scope| {
+            let workers: Vec<_> = (0..self.workers.min(ids.len()))
+                .map(|_| {
+                    let receiver = Arc::clone(&receiver);
+                    scope.spawn(move || -> Result<usize> {
+                        let mut verified = 0;
+                        loop {
+                            // Release the lock before verifying so others can take work
+                            let next = receiver.lock().unwrap().recv();
+                            let Ok(id) = next else {
+                                return Ok(verified);
+                            };
+                            verified += verify_one(tree, id)? as usize;
+                        }
+                    })
+                })
+                .collect();
+            drop(receiver);
+
+            for id in ids {
+                // Sending only fails once every worker has stopped on an error,
+                // which is reported below
+                if sender.send(id).is_err() {
+                    break;
+                }
+            }
+            drop(sender);
+
+            let mut verified = 0;
+            for worker in workers {
+                verified += worker.join().expect("verification worker panicked")?;
+            }
+            Ok(verified)
+        })
+    }
+}
+
+impl Default for VerificationPool {
+    /// A pool with one worker per available CPU.
+    fn default() -> Self {
+        Self::new(thread::available_parallelism().map_or(1, NonZeroUsize::get))
+    }
+}
+
+/// Verifies one entry, marking it `Verified` if it passes.
+fn verify_one(tree: &Tree, id: &ID) -> Result<bool> {
+    if !tree.verify_entry_signature(id.clone()).unwrap_or(false) {
+        return Ok(false);
+    }
+    tree.backend()
+        .update_verification_status(id, VerificationStatus::Verified)?;
+    Ok(true)
+}
@@ -5,21 +5,49 @@
 //! the history and relationships between entries, interfacing with a backend storage system.
 
 use crate::Result;
-use crate::atomicop::AtomicOp;
-use crate::backend::Database;
+use crate::atomicop::{AtomicOp, AtomicOpError, CoalescePolicy, GroupCommit, WriteCoalescer};
+use crate::backend::database::Sparse;
+use crate::backend::errors::DatabaseError;
+use crate::backend::{Database, EntryIter, PruneStats, VerificationStatus};
+#[cfg(feature = "y-crdt")]
+use crate::basedb::CommitListeners;
+use crate::basedb::ephemeral::{self, EPHEMERAL_SUBTREES};
 use crate::basedb::errors::BaseError;
-use crate::constants::{ROOT, SETTINGS};
+use crate::basedb::expire::{self, EPHEMERAL, MAX_AGE_MS};
+use crate::basedb::quota::{MAX_ENTRIES, MAX_ENTRY_SIZE, MAX_SUBTREE_SIZE, QUOTA};
+use crate::basedb::{
+    CommitEvent, DatabaseEvent, DbContext, EphemeralState, ExpireStats, MergeReport, RepairPlan,
+    StateCache, TreeDiff, TreeQuota, TreeSettings, diff, repair,
+};
+use crate::clock::{self, Hlc, wall_clock_ms};
+use crate::constants::{HASH_ALGORITHM, ROOT, SETTINGS};
 use crate::crdt::Map;
 use crate::crdt::map::Value;
-use crate::entry::{Entry, ID};
-use crate::subtree::{Dict, SubTree};
+use crate::entry::{Entry, HashAlgorithm, ID, InclusionProof};
+use crate::query::{PreparedQuery, Query};
+use crate::subtree::encoding::ENCODINGS;
+use crate::subtree::{Dict, SubTree, SubtreeEncoding};
 
-use crate::auth::crypto::format_public_key;
+use crate::auth::crypto::{format_public_key, generate_keypair, parse_public_key};
+use crate::auth::errors::AuthError;
+use crate::auth::invite::{INVITES, Invitation, REVOKED_INVITE};
 use crate::auth::settings::AuthSettings;
-use crate::auth::types::{AuthKey, KeyStatus, Permission};
+use crate::auth::types::{
+    AuthKey, DelegatedTreeRef, DelegationStep, KeyStatus, Permission, PermissionBounds,
+    ResolvedAuth, RevocationStatus, SigKey, TreeReference,
+};
+use crate::auth::validation::AuthValidator;
+use crate::auth::validation::freeze::{self, FROZEN};
 use rand::{Rng, distributions::Alphanumeric};
 use serde_json;
-use std::sync::Arc;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// How many times `Tree::transact` runs its closure before giving up on a
+/// tree that keeps changing under it.
+const TRANSACT_ATTEMPTS: usize = 8;
 
 /// Represents a collection of related entries, analogous to a table or a branch in a version control system.
 ///
@@ -31,6 +59,14 @@ pub struct Tree {
     backend: Arc<dyn Database>,
     /// Default authentication key name for operations on this tree
     default_auth_key: Option<String>,
+    /// Maximum number of tips a new operation may use as parents before they are
+    /// first merged in batches
+    merge_window: Option<usize>,
+    /// Validator shared by operations on this tree, caching resolved keys
+    /// between commits made against the same settings
+    validator: Arc<Mutex<AuthValidator>>,
+    /// Listeners, logs and caches shared with the `BaseDB` the tree came from
+    context: Arc<DbContext>,
 }
 
 impl Tree {
@@ -87,6 +123,23 @@ impl Tree {
             (signing_key_name.to_string(), final_tree_settings)
         };
 
+        // The root entry is hashed with the algorithm chosen in the settings,
+        // and every later entry follows it
+        let hash_algorithm = match final_tree_settings.get(HASH_ALGORITHM) {
+            None => HashAlgorithm::default(),
+            Some(Value::Text(name)) => HashAlgorithm::from_name(name).ok_or_else(|| {
+                BaseError::InvalidTreeConfiguration {
+                    reason: format!("unknown hash algorithm '{name}'"),
+                }
+            })?,
+            Some(_) => {
+                return Err(BaseError::InvalidTreeConfiguration {
+                    reason: format!("'{HASH_ALGORITHM}' must be the name of a hash algorithm"),
+                }
+                .into());
+            }
+        };
+
         // Create the initial root entry using a temporary Tree and AtomicOp
         // This placeholder ID should not exist in the backend, so get_tips will be empty.
         let bootstrap_placeholder_id = format!(
@@ -102,6 +155,9 @@ impl Tree {
             root: bootstrap_placeholder_id.clone().into(),
             backend: backend.clone(),
             default_auth_key: Some(super_user_key_name.clone()),
+            merge_window: None,
+            validator: Arc::default(),
+            context: Arc::default(),
         };
 
         // Create the operation. If we have an auth key, it will be used automatically
@@ -110,6 +166,7 @@ impl Tree {
         // IMPORTANT: For the root entry, we need to set the tree root to empty string
         // so that is_toplevel_root() returns true and all_roots() can find it
         op.set_entry_root("")?;
+        op.set_hash_algorithm(hash_algorithm)?;
 
         // Populate the SETTINGS and ROOT subtrees for the very first entry
         op.update_subtree(SETTINGS, &serde_json::to_string(&final_tree_settings)?)?;
@@ -123,6 +180,9 @@ impl Tree {
             root: new_root_id,
             backend,
             default_auth_key: Some(super_user_key_name),
+            merge_window: None,
+            validator: Arc::default(),
+            context: Arc::default(),
         })
     }
 
@@ -142,6 +202,9 @@ impl Tree {
             root: id,
             backend,
             default_auth_key: None,
+            merge_window: None,
+            validator: Arc::default(),
+            context: Arc::default(),
         })
     }
 
@@ -185,6 +248,79 @@ impl Tree {
         self.default_auth_key.as_deref()
     }
 
+    /// Set the merge window for operations on this tree.
+    ///
+    /// When a tree has more than `window` tips, for example after heavy offline
+    /// editing on several replicas, `new_operation()` and
+    /// `new_authenticated_operation()` first commit intermediate merge entries
+    /// that each take up to `window` tips as parents, repeating until at most
+    /// `window` tips remain. This keeps parent lists, and therefore entries,
+    /// small. The merge entries carry no data and are signed with the
+    /// operation's key; if no key is available the tips are used unmerged.
+    ///
+    /// Windows smaller than 2 are treated as 2.
+    pub fn set_merge_window(&mut self, window: usize) {
+        self.merge_window = Some(window.max(2));
+    }
+
+    /// Clear the merge window, so operations use all current tips as parents.
+    pub fn clear_merge_window(&mut self) {
+        self.merge_window = None;
+    }
+
+    /// Get the merge window for this tree, if one is set.
+    pub fn merge_window(&self) -> Option<usize> {
+        self.merge_window
+    }
+
+    /// Merge the current tips in batches of at most `window` until no more than
+    /// `window` tips remain.
+    ///
+    /// Each batch is merged by committing an entry with no data whose parents
+    /// are the batch, signed with `key_name`. This is what a merge window applies
+    /// automatically, but it can also be called directly.
+    ///
+    /// # Returns
+    /// A `Result` containing the tips after merging
+    pub fn merge_tips(&self, window: usize, key_name: impl AsRef<str>) -> Result<Vec<ID>> {
+        let tips = self.get_tips()?;
+        self.merge_tip_batches(tips, window.max(2), key_name.as_ref())
+    }
+
+    fn merge_tip_batches(
+        &self,
+        mut tips: Vec<ID>,
+        window: usize,
+        key_name: &str,
+    ) -> Result<Vec<ID>> {
+        // Sort so that replicas merging the same tips produce the same batches
+        tips.sort();
+        while tips.len() > window {
+            let mut merged = Vec::with_capacity(tips.len().div_ceil(window));
+            for batch in tips.chunks(window) {
+                if let [single] = batch {
+                    merged.push(single.clone());
+                } else {
+                    let op = AtomicOp::new_with_tips(self, batch)?.with_auth(key_name);
+                    merged.push(op.commit()?);
+                }
+            }
+            tips = merged;
+        }
+        Ok(tips)
+    }
+
+    /// Get the tips to use as parents for a new operation, applying the merge window.
+    fn operation_tips(&self, key_name: Option<&str>) -> Result<Vec<ID>> {
+        let tips = self.get_tips()?;
+        match (self.merge_window, key_name) {
+            (Some(window), Some(key_name)) if tips.len() > window => {
+                self.merge_tip_batches(tips, window, key_name)
+            }
+            _ => Ok(tips),
+        }
+    }
+
     /// Create a new atomic operation on this tree with authentication.
     ///
     /// This is a convenience method that creates an operation and sets the authentication
@@ -196,8 +332,17 @@ impl Tree {
     /// # Returns
     /// A `Result<AtomicOp>` containing the new authenticated operation
     pub fn new_authenticated_operation(&self, key_name: impl AsRef<str>) -> Result<AtomicOp> {
-        let op = self.new_operation()?;
-        Ok(op.with_auth(key_name.as_ref()))
+        let key_name = key_name.as_ref();
+        let tips = self.operation_tips(Some(key_name))?;
+        let op = self.new_operation_with_tips(&tips)?;
+        Ok(op.with_auth(key_name))
+    }
+
+    /// Get a handle to this tree that can read it but not write to it.
+    ///
+    /// The handle shares this tree's backend and caches. See [`ReadOnlyTree`].
+    pub fn read_only(&self) -> ReadOnlyTree {
+        ReadOnlyTree::from(self.clone())
     }
 
     /// Get the ID of the root entry
@@ -210,6 +355,59 @@ impl Tree {
         &self.backend
     }
 
+    /// Get the validator shared by operations on this tree
+    pub(crate) fn validator(&self) -> &Arc<Mutex<AuthValidator>> {
+        &self.validator
+    }
+
+    /// Share the listeners, logs and caches of the `BaseDB` this tree was loaded from
+    pub(crate) fn with_context(mut self, context: Arc<DbContext>) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// Get the commit listeners notified of commits to this tree
+    #[cfg(feature = "y-crdt")]
+    pub(crate) fn commit_listeners(&self) -> &CommitListeners {
+        &self.context.commit_listeners
+    }
+
+    /// Get the subtree state cache shared by operations on this tree
+    pub(crate) fn state_cache(&self) -> &StateCache {
+        &self.context.state_cache
+    }
+
+    /// Get the state of this tree's ephemeral subtrees
+    pub(crate) fn ephemeral_state(&self) -> &EphemeralState {
+        &self.context.ephemeral
+    }
+
+    /// Record a read of a subtree in the read log, if it is enabled
+    pub(crate) fn record_read(&self, subtree_name: &str) {
+        self.context
+            .read_log
+            .record(&self.root, subtree_name, self.default_auth_key.as_deref());
+    }
+
+    /// Notify commit listeners and event subscribers that an entry was
+    /// committed to this tree
+    pub(crate) fn notify_commit(&self, entry: &ID) {
+        self.context.state_cache.invalidate_tree(&self.root);
+        self.context.commit_listeners.notify(
+            &CommitEvent {
+                tree: self.root.clone(),
+                entry: entry.clone(),
+            },
+            self.backend.as_ref(),
+        );
+        self.context
+            .event_bus
+            .publish(DatabaseEvent::EntryCommitted {
+                tree: self.root.clone(),
+                entry: entry.clone(),
+            });
+    }
+
     /// Retrieve the root entry from the backend
     pub fn get_root(&self) -> Result<Entry> {
         self.backend.get(&self.root)
@@ -234,16 +432,51 @@ impl Tree {
         settings.get_string("name")
     }
 
+    /// Get a typed view of the tree's settings.
+    ///
+    /// See [`TreeSettings`] for the fields it exposes, and
+    /// [`update_settings`](Self::update_settings) to change them.
+    pub fn settings(&self) -> Result<TreeSettings> {
+        let settings = self.get_settings()?.get_all()?;
+        Ok(TreeSettings::new(settings, self.get_root()?.timestamp()))
+    }
+
+    /// Change the tree's settings through a typed view.
+    ///
+    /// `update` is called with the current settings, and the fields it changes
+    /// are committed to the `_settings` subtree with the tree's default
+    /// authentication key. Fields it does not change are not written, so
+    /// concurrent changes to them on other replicas are kept.
+    ///
+    /// # Errors
+    /// Changing settings needs a key with admin permission; the commit fails
+    /// with `AtomicOpError::InsufficientPermissions` otherwise.
+    ///
+    /// # Returns
+    /// The ID of the settings entry
+    pub fn update_settings(&self, update: impl FnOnce(&mut TreeSettings)) -> Result<ID> {
+        let mut settings = self.settings()?;
+        update(&mut settings);
+
+        let op = self.new_operation()?;
+        let store = op.get_subtree::<Dict>(SETTINGS)?;
+        for (path, value) in settings.changes() {
+            store.set_at_path(path, value.clone())?;
+        }
+        op.commit()
+    }
+
     /// Create a new atomic operation on this tree
     ///
     /// This creates a new atomic operation containing a new Entry.
     /// The atomic operation will be initialized with the current state of the tree.
     /// If a default authentication key is set, the operation will use it for signing.
+    /// If a merge window is set, excess tips are merged first (see `set_merge_window`).
     ///
     /// # Returns
     /// A `Result<AtomicOp>` containing the new atomic operation
     pub fn new_operation(&self) -> Result<AtomicOp> {
-        let tips = self.get_tips()?;
+        let tips = self.operation_tips(self.default_auth_key.as_deref())?;
         self.new_operation_with_tips(&tips)
     }
 
@@ -266,9 +499,75 @@ impl Tree {
             op.set_auth_key(key_name);
         }
 
+        // Record the database's default app info, if any
+        if let Some(info) = self.context.default_app_info.read().unwrap().clone() {
+            op.set_app_info(info)?;
+        }
+
         Ok(op)
     }
 
+    /// Coalesce many small operations on this tree into shared entries.
+    ///
+    /// Operations staged through the returned [`GroupCommit`] are committed
+    /// together once the group has been open for `window`. See [`GroupCommit`]
+    /// for details.
+    ///
+    /// # Arguments
+    /// * `window` - How long a group collects operations before it is committed
+    pub fn group_commit(&self, window: Duration) -> GroupCommit {
+        GroupCommit::new(self, window)
+    }
+
+    /// Batch frequent writes to this tree's `Dict` subtrees into shared entries.
+    ///
+    /// Writes staged through the returned [`WriteCoalescer`] are committed
+    /// according to `policy`, or the policy set for their subtree with
+    /// [`WriteCoalescer::subtree_policy`]. Only the last value staged for each
+    /// key is committed. See [`WriteCoalescer`] for details.
+    ///
+    /// # Arguments
+    /// * `policy` - When to commit the writes of subtrees without their own policy
+    pub fn write_coalescer(&self, policy: CoalescePolicy) -> WriteCoalescer {
+        WriteCoalescer::new(self, policy)
+    }
+
+    /// Run `f` in an operation and commit it, retrying if the tree changes meanwhile.
+    ///
+    /// `f` stages changes to any number of subtrees through the operation it is
+    /// given, and can read them back to check invariants that span subtrees.
+    /// If `f` fails, nothing it staged is committed and its error is returned.
+    ///
+    /// The operation builds on the tips of the tree when `f` starts. If other
+    /// commits, local or synced, have moved the tips by the time `f` returns,
+    /// its changes are discarded and `f` runs again on the new state, so what
+    /// it read is what it wrote against. The tips are checked just before
+    /// committing; a commit landing in between is merged as usual. After
+    /// several attempts the transaction fails with
+    /// `AtomicOpError::ConcurrentModification`.
+    ///
+    /// Uses the default authentication key, if set, like `new_operation`.
+    ///
+    /// # Returns
+    /// The ID of the committed entry
+    pub fn transact(&self, mut f: impl FnMut(&AtomicOp) -> Result<()>) -> Result<ID> {
+        for _attempt in 0..TRANSACT_ATTEMPTS {
+            let mut tips = self.operation_tips(self.default_auth_key.as_deref())?;
+            let op = self.new_operation_with_tips(&tips)?;
+            f(&op)?;
+
+            let mut current = self.get_tips()?;
+            tips.sort();
+            current.sort();
+            if current == tips {
+                return op.commit();
+            }
+            #[cfg(feature = "tracing")]
+            tracing::debug!(tree = %self.root, attempt = _attempt, "tips moved, retrying transaction");
+        }
+        Err(AtomicOpError::ConcurrentModification.into())
+    }
+
     /// Insert an entry into the tree without modifying it.
     /// This is primarily for testing purposes or when you need full control over the entry.
     /// Note: Since all entries must now be authenticated, this method assumes the entry
@@ -286,11 +585,41 @@ impl Tree {
     ///
     /// The returned subtree should NOT be used to modify the tree, as it intentionally does not
     /// expose the AtomicOp.
+    ///
+    /// Viewing never writes: unlike [`new_operation`](Self::new_operation), it does
+    /// not merge excess tips, so it works without any private key present.
     pub fn get_subtree_viewer<T>(&self, name: impl Into<String>) -> Result<T>
     where
         T: SubTree,
     {
-        let op = self.new_operation()?;
+        let name = name.into();
+        self.record_read(&name);
+        let op = self.new_operation_with_tips(self.get_tips()?)?;
+        T::new(&op, name)
+    }
+
+    /// Get a read-only view of a subtree as it was at the given entries.
+    ///
+    /// The view contains the changes of `entry_ids` and their ancestors only, so
+    /// passing a single entry shows the subtree as of that commit, and passing a
+    /// past set of tips shows the merged state of those branches. Like
+    /// [`get_subtree_viewer`](Self::get_subtree_viewer), the returned subtree
+    /// should not be used to modify the tree.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the subtree to view
+    /// * `entry_ids` - The entries of this tree to view the subtree at
+    ///
+    /// # Errors
+    /// Returns an error if `entry_ids` is empty, or if an entry is not stored or
+    /// belongs to another tree.
+    pub fn get_subtree_viewer_at<T>(&self, name: impl Into<String>, entry_ids: &[ID]) -> Result<T>
+    where
+        T: SubTree,
+    {
+        let name = name.into();
+        self.record_read(&name);
+        let op = self.new_operation_with_tips(entry_ids)?;
         T::new(&op, name)
     }
 
@@ -304,6 +633,46 @@ impl Tree {
         self.backend.get_tips(&self.root)
     }
 
+    /// Get the current tips of a subtree.
+    ///
+    /// These are the latest entries that modified the subtree, independent of
+    /// the main tree tips. More than one tip means the subtree has concurrent
+    /// changes that no later entry has merged yet.
+    ///
+    /// # Arguments
+    /// * `subtree_name` - The name of the subtree
+    ///
+    /// # Returns
+    /// A `Result` containing the IDs of the subtree's tip entries
+    pub fn subtree_tips(&self, subtree_name: impl AsRef<str>) -> Result<Vec<ID>> {
+        self.backend
+            .get_subtree_tips(&self.root, subtree_name.as_ref())
+    }
+
+    /// Merge the tips of a single subtree into one.
+    ///
+    /// Commits an entry that includes only `subtree_name`, with all of the
+    /// subtree's current tips as parents and no data. The subtree's state is
+    /// unchanged and other subtrees' histories are not touched. The entry is
+    /// signed with the default authentication key.
+    ///
+    /// # Arguments
+    /// * `subtree_name` - The name of the subtree to merge
+    ///
+    /// # Returns
+    /// A `Result` containing the ID of the merge entry, or `None` if the subtree
+    /// already had at most one tip and nothing was committed
+    pub fn merge_subtree(&self, subtree_name: impl AsRef<str>) -> Result<Option<ID>> {
+        let subtree_name = subtree_name.as_ref();
+        if self.subtree_tips(subtree_name)?.len() <= 1 {
+            return Ok(None);
+        }
+
+        let op = self.new_operation()?;
+        op.merge_subtree(subtree_name)?;
+        op.commit().map(Some)
+    }
+
     /// Get the full `Entry` objects for the current tips of the main tree branch.
     ///
     /// # Returns
@@ -314,6 +683,22 @@ impl Tree {
         entries
     }
 
+    /// Get the most recent edit to the tree, by hybrid logical clock timestamp.
+    ///
+    /// Timestamps of new entries are always later than those of their parents, so
+    /// the latest edit is one of the tips. Suitable for "last edited" displays.
+    ///
+    /// # Returns
+    /// A `Result` containing the ID and timestamp of the latest edit, or `None` if
+    /// no tip has a timestamp
+    pub fn latest_edit(&self) -> Result<Option<(ID, Hlc)>> {
+        Ok(self
+            .get_tip_entries()?
+            .into_iter()
+            .filter_map(|entry| entry.timestamp().map(|hlc| (entry.id(), hlc)))
+            .max_by(|(a_id, a), (b_id, b)| a.cmp(b).then_with(|| a_id.cmp(b_id))))
+    }
+
     /// Get a single entry by ID from this tree.
     ///
     /// This is the primary method for retrieving entries after commit operations.
@@ -458,46 +843,1405 @@ impl Tree {
         let historical_settings = self.get_historical_settings_for_entry(&entry)?;
 
         // Use the authentication validator with historical settings
-        let mut validator = crate::auth::validation::AuthValidator::new();
+        let mut validator = AuthValidator::new();
         validator.validate_entry(&entry, &historical_settings, Some(&self.backend))
     }
 
     /// Get the authentication settings that were valid when a specific entry was created.
     ///
-    /// This method examines the entry's metadata to find the settings tips that were active
-    /// at the time of entry creation, then reconstructs the historical settings state.
+    /// This is the `_settings` state merged from the entry's parents, i.e. the
+    /// state the entry was validated against when it was committed. Entries that
+    /// configure auth for the first time (such as the root entry) are validated
+    /// against their own settings, matching the bootstrap rule used on commit.
     ///
     /// # Arguments
     /// * `entry` - The entry to get historical settings for
     ///
     /// # Returns
     /// A `Result` containing the historical settings data
-    fn get_historical_settings_for_entry(&self, _entry: &Entry) -> Result<Map> {
-        // TODO: Implement full historical settings reconstruction from entry metadata
-        // For now, use current settings for simplicity and backward compatibility
-        //
-        // The complete implementation would:
-        // 1. Parse entry metadata to get settings tips active at entry creation time
-        // 2. Reconstruct the CRDT state from those historical tips
-        // 3. Validate against that historical state
-        //
-        // This ensures entries remain valid even if keys are later revoked,
-        // but requires more complex CRDT state reconstruction logic.
+    fn get_historical_settings_for_entry(&self, entry: &Entry) -> Result<Map> {
+        let parents = entry.parents().unwrap_or_default();
+        let historical = if parents.is_empty() {
+            Map::new()
+        } else {
+            self.new_operation_with_tips(&parents)?
+                .get_full_state::<Map>(SETTINGS)?
+        };
 
-        let settings = self.get_settings()?;
-        settings.get_all()
+        if has_auth(&historical) {
+            Ok(historical)
+        } else {
+            self.settings_at(entry.id())
+        }
     }
 
-    // === TREE QUERIES ===
+    // === SETTINGS HISTORY ===
 
-    /// Get all entries in this tree.
+    /// Get the merged `_settings` state as of a specific entry.
     ///
-    /// ⚠️ **Warning**: This method loads all entries into memory. Use with caution on large trees.
-    /// Consider using `get_tips()` or `get_tip_entries()` for more efficient access patterns.
+    /// The state includes every settings change made by the entry and its
+    /// ancestors, but none made by concurrent or later entries.
+    ///
+    /// # Arguments
+    /// * `entry_id` - The entry at which to read the settings
+    ///
+    /// # Errors
+    /// Returns an error if the entry is not found or does not belong to this tree.
+    pub fn settings_at<I: Into<ID>>(&self, entry_id: I) -> Result<Map> {
+        let entry = self.get_entry(entry_id)?;
+        self.new_operation_with_tips([entry.id()])?
+            .get_full_state::<Map>(SETTINGS)
+    }
+
+    /// Get the resolved authentication configuration as of a specific entry.
+    ///
+    /// This is the `auth` section of [`Tree::settings_at`], so it reflects keys
+    /// added, changed or revoked by the entry itself and all of its ancestors.
+    /// Use it to check which keys and permissions applied at any point in the
+    /// tree's history, even if they have changed since.
+    ///
+    /// # Arguments
+    /// * `entry_id` - The entry at which to read the auth configuration
+    ///
+    /// # Errors
+    /// Returns an error if the entry is not found or does not belong to this tree.
+    pub fn auth_state_at<I: Into<ID>>(&self, entry_id: I) -> Result<AuthSettings> {
+        Ok(auth_section(&self.settings_at(entry_id)?))
+    }
+
+    /// Get the history of the tree's authentication configuration.
+    ///
+    /// Returns one item per entry that changed the `auth` section of the settings,
+    /// in topological order, paired with the auth configuration as of that entry.
+    ///
+    /// ⚠️ **Warning**: This reconstructs the settings state once per auth change.
     ///
     /// # Returns
-    /// A `Result` containing a vector of all `Entry` objects in the tree
-    pub fn get_all_entries(&self) -> Result<Vec<Entry>> {
-        self.backend.get_tree(&self.root)
+    /// A `Result` containing `(entry_id, auth_settings)` pairs, oldest first
+    pub fn auth_timeline(&self) -> Result<Vec<(ID, AuthSettings)>> {
+        let mut timeline = Vec::new();
+        for entry in self.backend.get_subtree_iter(&self.root, SETTINGS)? {
+            let entry = entry?;
+            let data = entry.data(SETTINGS)?;
+            let changes_auth = serde_json::from_str::<Map>(data)
+                .map(|settings| settings.get("auth").is_some())
+                .unwrap_or(false);
+            if changes_auth {
+                let id = entry.id();
+                timeline.push((id.clone(), self.auth_state_at(id)?));
+            }
+        }
+        Ok(timeline)
+    }
+
+    // === KEY REVOCATION ===
+
+    /// Revoke a key in the tree's authentication settings.
+    ///
+    /// The revocation is committed with the tree's default authentication key,
+    /// which needs admin permission over the revoked key. Entries the key signed
+    /// before the revocation remain valid; see [`Tree::revocation_status`].
+    ///
+    /// # Arguments
+    /// * `key_name` - The name of the key in the auth settings
+    ///
+    /// # Returns
+    /// A `Result` containing the ID of the entry that revoked the key
+    ///
+    /// # Errors
+    /// Returns `AuthError::KeyNotFound` if the key is not configured,
+    /// `AuthError::CannotRevokeNonKey` if the name refers to a delegated tree,
+    /// or an error if the commit is not authorized.
+    pub fn revoke_key(&self, key_name: impl AsRef<str>) -> Result<ID> {
+        let key_name = key_name.as_ref();
+        let op = self.new_operation()?;
+        let settings = op.get_subtree::<Dict>(SETTINGS)?;
+        let mut auth = auth_section(&settings.get_all()?);
+        auth.revoke_key(key_name)?;
+        let revoked = auth
+            .as_map()
+            .get(key_name)
+            .cloned()
+            .expect("revoke_key succeeded, so the key is present");
+        settings.set_at_path(["auth", key_name], revoked)?;
+        op.commit()
+    }
+
+    /// Replace a key with a newly generated one.
+    ///
+    /// Generates a keypair, stores its private key in the backend under `new_name`,
+    /// and commits a single settings entry signed by `old_name` that adds the new key
+    /// with the old key's permissions and marks the old key as `Rotated`. The old key
+    /// needs admin permission to sign the change. If the old key was this tree's
+    /// default authentication key, the new key becomes the default.
+    ///
+    /// Like revoked keys, rotated keys can no longer sign new entries, while the
+    /// entries they signed before the rotation remain valid.
+    ///
+    /// # Arguments
+    /// * `old_name` - The name of the key being replaced
+    /// * `new_name` - The name for the new key, in both the auth settings and local key storage
+    ///
+    /// # Returns
+    /// A `Result` containing the ID of the entry that rotated the key
+    ///
+    /// # Errors
+    /// Returns `AuthError::KeyNotFound` if the old key is not configured,
+    /// `AuthError::KeyAlreadyExists` if `new_name` is already in use, or an error
+    /// if the commit is not authorized. On error, no private key is left behind.
+    pub fn rotate_key(
+        &mut self,
+        old_name: impl AsRef<str>,
+        new_name: impl AsRef<str>,
+    ) -> Result<ID> {
+        let old_name = old_name.as_ref();
+        let new_name = new_name.as_ref();
+
+        let auth = auth_section(&self.get_settings()?.get_all()?);
+        let mut old_key = match auth.get_key(old_name) {
+            Some(key) => key?,
+            None => {
+                return Err(AuthError::KeyNotFound {
+                    key_name: old_name.to_string(),
+                }
+                .into());
+            }
+        };
+        if auth.as_map().get(new_name).is_some()
+            || self.backend.get_private_key(new_name)?.is_some()
+        {
+            return Err(AuthError::KeyAlreadyExists {
+                key_name: new_name.to_string(),
+            }
+            .into());
+        }
+
+        let (signing_key, verifying_key) = generate_keypair();
+        let mut rotated = auth;
+        rotated.add_key(
+            new_name,
+            AuthKey {
+                pubkey: format_public_key(&verifying_key),
+                permissions: old_key.permissions.clone(),
+                status: KeyStatus::Active,
+            },
+        )?;
+        old_key.status = KeyStatus::Rotated;
+        rotated.add_key(old_name, old_key)?;
+
+        // The private key must be available before the rotation is visible
+        self.backend.store_private_key(new_name, signing_key)?;
+        let committed = self.new_authenticated_operation(old_name).and_then(|op| {
+            let settings = op.get_subtree::<Dict>(SETTINGS)?;
+            for name in [new_name, old_name] {
+                let key = rotated
+                    .as_map()
+                    .get(name)
+                    .cloned()
+                    .expect("both keys were just added");
+                settings.set_at_path(["auth", name], key)?;
+            }
+            op.commit()
+        });
+        let id = match committed {
+            Ok(id) => id,
+            Err(e) => {
+                self.backend.remove_private_key(new_name)?;
+                return Err(e);
+            }
+        };
+
+        if self.default_auth_key.as_deref() == Some(old_name) {
+            self.default_auth_key = Some(new_name.to_string());
+        }
+        Ok(id)
+    }
+
+    /// Determine how the tree's current key revocations affect an entry.
+    ///
+    /// An entry signed by a revoked key is:
+    /// - `Historical` if it is an ancestor of an entry in which the key is revoked,
+    ///   i.e. it was signed before the revocation.
+    /// - `Concurrent` if it was signed on a branch that had not seen the revocation.
+    ///   Like historical entries, its content is preserved during merges.
+    /// - `Revoked` if the key was already revoked in the entry's own history. Such
+    ///   entries can only arrive through sync, as commits with revoked keys are rejected.
+    ///
+    /// Entries built on top of a `Revoked` entry are `Tainted`. Rotated keys are treated
+    /// as revoked. Only keys referenced directly by the tree's auth settings are
+    /// considered, not delegated keys.
+    ///
+    /// # Arguments
+    /// * `entry_id` - The entry to check
+    ///
+    /// # Errors
+    /// Returns an error if the entry is not found or does not belong to this tree.
+    pub fn revocation_status<I: Into<ID>>(&self, entry_id: I) -> Result<RevocationStatus> {
+        let entry = self.get_entry(entry_id)?;
+        let id = entry.id();
+        let ancestry = self
+            .backend
+            .get_tree_from_tips(&self.root, std::slice::from_ref(&id))?;
+        Ok(self
+            .classify_revocations(&ancestry)?
+            .into_iter()
+            .find_map(|(entry_id, status)| (entry_id == id).then_some(status))
+            .unwrap_or(RevocationStatus::Unaffected))
+    }
+
+    /// Re-validate every entry in the tree against the current key revocations.
+    ///
+    /// Entries that are no longer valid (`Revoked` or `Tainted`) are marked as
+    /// failed in the backend's verification status. Call this after a revocation
+    /// has been committed or received through sync.
+    ///
+    /// ⚠️ **Warning**: This loads all entries of the tree into memory.
+    ///
+    /// # Returns
+    /// A `Result` containing every entry affected by a revocation and its status,
+    /// in topological order
+    pub fn revalidate_revocations(&self) -> Result<Vec<(ID, RevocationStatus)>> {
+        let affected: Vec<_> = self
+            .classify_revocations(&self.get_all_entries()?)?
+            .into_iter()
+            .filter(|(_, status)| *status != RevocationStatus::Unaffected)
+            .collect();
+        for (id, status) in &affected {
+            if !status.is_valid() {
+                self.backend
+                    .update_verification_status(id, VerificationStatus::Failed)?;
+            }
+        }
+        Ok(affected)
+    }
+
+    /// Classify topologically sorted entries by the current key revocations.
+    fn classify_revocations(&self, entries: &[Entry]) -> Result<Vec<(ID, RevocationStatus)>> {
+        let revoked_keys: HashSet<String> = auth_section(&self.get_settings()?.get_all()?)
+            .get_all_keys()?
+            .into_iter()
+            .filter(|(_, key)| key.status != KeyStatus::Active)
+            .map(|(name, _)| name)
+            .collect();
+        if revoked_keys.is_empty() {
+            return Ok(entries
+                .iter()
+                .map(|entry| (entry.id(), RevocationStatus::Unaffected))
+                .collect());
+        }
+
+        // Everything seen by an entry in which the key is revoked predates the revocation
+        let mut seen_by_revocation: HashMap<&str, HashSet<ID>> = HashMap::new();
+        for (revoking_id, auth) in self.auth_timeline()? {
+            for key_name in &revoked_keys {
+                if let Some(Ok(key)) = auth.get_key(key_name)
+                    && key.status != KeyStatus::Active
+                {
+                    let ancestors = seen_by_revocation.entry(key_name).or_default();
+                    if !ancestors.contains(&revoking_id) {
+                        let ancestry = self
+                            .backend
+                            .get_tree_from_tips(&self.root, std::slice::from_ref(&revoking_id))?;
+                        ancestors.extend(ancestry.iter().map(Entry::id));
+                    }
+                }
+            }
+        }
+
+        let mut statuses: HashMap<ID, RevocationStatus> = HashMap::new();
+        let mut classified = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let id = entry.id();
+            let status = match &entry.sig.key {
+                SigKey::Direct(key_name) if revoked_keys.contains(key_name) => {
+                    let historical = auth_section(&self.get_historical_settings_for_entry(entry)?);
+                    let revoked_then = matches!(
+                        historical.get_key(key_name),
+                        Some(Ok(key)) if key.status != KeyStatus::Active
+                    );
+                    if revoked_then {
+                        RevocationStatus::Revoked {
+                            key_name: key_name.clone(),
+                        }
+                    } else if seen_by_revocation
+                        .get(key_name.as_str())
+                        .is_some_and(|seen| seen.contains(&id))
+                    {
+                        RevocationStatus::Historical {
+                            key_name: key_name.clone(),
+                        }
+                    } else {
+                        RevocationStatus::Concurrent {
+                            key_name: key_name.clone(),
+                        }
+                    }
+                }
+                _ => RevocationStatus::Unaffected,
+            };
+
+            let status = if status.is_valid() {
+                entry
+                    .parents()
+                    .unwrap_or_default()
+                    .iter()
+                    .find_map(|parent| match statuses.get(parent)? {
+                        RevocationStatus::Revoked { .. } => Some(RevocationStatus::Tainted {
+                            source: parent.clone(),
+                        }),
+                        RevocationStatus::Tainted { source } => Some(RevocationStatus::Tainted {
+                            source: source.clone(),
+                        }),
+                        _ => None,
+                    })
+                    .unwrap_or(status)
+            } else {
+                status
+            };
+
+            statuses.insert(id.clone(), status.clone());
+            classified.push((id, status));
+        }
+        Ok(classified)
+    }
+
+    // === DELEGATION ===
+
+    /// Trust the keys of another tree, such as a central "user tree".
+    ///
+    /// Adds a delegation named `name` to this tree's auth settings, committed with
+    /// the tree's default authentication key. Keys of the delegated tree can then
+    /// sign entries in this tree through the delegation, with their permissions
+    /// clamped to `bounds`. Keys added to or revoked in the delegated tree take
+    /// effect in every tree that delegates to it.
+    ///
+    /// The delegated tree must be stored in the same backend as this tree.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the delegation in the auth settings
+    /// * `tree_root` - The root ID of the delegated tree
+    /// * `bounds` - The permission bounds applied to the delegated tree's keys
+    ///
+    /// # Returns
+    /// A `Result` containing the ID of the entry that added the delegation
+    ///
+    /// # Errors
+    /// Returns `AuthError::KeyAlreadyExists` if `name` is already in use, an error if
+    /// the delegated tree is not found, or an error if the commit is not authorized.
+    pub fn add_delegation(
+        &self,
+        name: impl AsRef<str>,
+        tree_root: &ID,
+        bounds: PermissionBounds,
+    ) -> Result<ID> {
+        let name = name.as_ref();
+        self.backend.get(tree_root)?;
+        let tips = self.backend.get_tips(tree_root)?;
+
+        let op = self.new_operation()?;
+        let settings = op.get_subtree::<Dict>(SETTINGS)?;
+        let mut auth = auth_section(&settings.get_all()?);
+        if auth.as_map().get(name).is_some() {
+            return Err(AuthError::KeyAlreadyExists {
+                key_name: name.to_string(),
+            }
+            .into());
+        }
+        auth.add_delegated_tree(
+            name,
+            DelegatedTreeRef {
+                permission_bounds: bounds,
+                tree: TreeReference {
+                    root: tree_root.clone(),
+                    tips,
+                },
+            },
+        )?;
+        let delegation = auth
+            .as_map()
+            .get(name)
+            .cloned()
+            .expect("the delegation was just added");
+        settings.set_at_path(["auth", name], delegation)?;
+        op.commit()
+    }
+
+    /// Create an operation signed by a key of a delegated tree.
+    ///
+    /// The entry is signed with the private key `key_name` and identified by a
+    /// delegation path through `delegation`, pinned to the delegated tree's current tips.
+    ///
+    /// # Arguments
+    /// * `delegation` - The name of a delegation added with `add_delegation`
+    /// * `key_name` - The name of the key in the delegated tree, which is also the
+    ///   name of the private key in local storage
+    ///
+    /// # Errors
+    /// Returns `AuthError::KeyNotFound` if this tree has no delegation named `delegation`.
+    pub fn new_delegated_operation(
+        &self,
+        delegation: impl AsRef<str>,
+        key_name: impl AsRef<str>,
+    ) -> Result<AtomicOp> {
+        let delegation = delegation.as_ref();
+        let auth = auth_section(&self.get_settings()?.get_all()?);
+        let tree_ref = match auth.get_delegated_tree(delegation) {
+            Some(tree_ref) => tree_ref?,
+            None => {
+                return Err(AuthError::KeyNotFound {
+                    key_name: delegation.to_string(),
+                }
+                .into());
+            }
+        };
+        let tips = self.backend.get_tips(&tree_ref.tree.root)?;
+        Ok(self
+            .new_authenticated_operation(key_name)?
+            .with_delegation(vec![DelegationStep {
+                key: delegation.to_string(),
+                tips: Some(tips),
+            }]))
+    }
+
+    /// Mint a signed invitation to join this tree with `permission`.
+    ///
+    /// The invitation is signed with the tree's default authentication key,
+    /// which must be an active admin key allowed to create keys with
+    /// `permission`. Hand it to the invitee with [`Invitation::to_token`]; they
+    /// redeem it with [`BaseDB::redeem_invite`](crate::basedb::BaseDB::redeem_invite)
+    /// once they have the tree's entries, for example after syncing. See
+    /// [`crate::auth::invite`] for how redemptions are validated.
+    ///
+    /// # Arguments
+    /// * `permission` - The permission granted to the invitee's key
+    /// * `expiry` - How long the invitation can be redeemed for, or `None` for no limit
+    ///
+    /// # Errors
+    /// Returns `BaseError::AuthenticationRequired` if the tree has no default key,
+    /// `AuthError::KeyNotFound` if it is not configured in the tree, or
+    /// `AuthError::PermissionDenied` if it may not grant `permission`.
+    pub fn create_invite(
+        &self,
+        permission: Permission,
+        expiry: Option<Duration>,
+    ) -> Result<Invitation> {
+        let issuer = self
+            .default_auth_key
+            .as_deref()
+            .ok_or(BaseError::AuthenticationRequired)?;
+        let auth = auth_section(&self.get_settings()?.get_all()?);
+        let key = match auth.get_key(issuer) {
+            Some(key) => key?,
+            None => {
+                return Err(AuthError::KeyNotFound {
+                    key_name: issuer.to_string(),
+                }
+                .into());
+            }
+        };
+        let resolved = ResolvedAuth {
+            public_key: parse_public_key(&key.pubkey)?,
+            effective_permission: key.permissions,
+            key_status: key.status,
+        };
+        if resolved.key_status != KeyStatus::Active
+            || !auth.can_create_key(&resolved, &permission)?
+        {
+            return Err(AuthError::PermissionDenied {
+                reason: format!("key '{issuer}' may not invite keys with {permission:?}"),
+            }
+            .into());
+        }
+        let signing_key =
+            self.backend
+                .get_private_key(issuer)?
+                .ok_or_else(|| BaseError::SigningKeyNotFound {
+                    key_name: issuer.to_string(),
+                })?;
+
+        let expires_at = expiry.map(|expiry| clock::now() + expiry);
+        Ok(Invitation::new(
+            self.root.clone(),
+            permission,
+            expires_at,
+            issuer,
+            &signing_key,
+        ))
+    }
+
+    /// Cancel an invitation that has not been redeemed yet.
+    ///
+    /// Records the invitation as used in the tree's settings, so later
+    /// redemptions are rejected. Redemptions on branches that have not seen the
+    /// revocation are still accepted. Needs a key with admin permission.
+    ///
+    /// # Returns
+    /// A `Result` containing the ID of the entry that revoked the invitation
+    ///
+    /// # Errors
+    /// Returns `AuthError::InvalidInvitation` if the invitation is for another
+    /// tree or was already redeemed or revoked.
+    pub fn revoke_invite(&self, invite: &Invitation) -> Result<ID> {
+        let invalid = |reason: &str| AuthError::InvalidInvitation {
+            reason: reason.to_string(),
+        };
+        if invite.tree() != &self.root {
+            return Err(invalid("the invitation is for another tree").into());
+        }
+        let op = self.new_operation()?;
+        let settings = op.get_subtree::<Dict>(SETTINGS)?;
+        if settings.get_at_path([INVITES, invite.id()]).is_ok() {
+            return Err(invalid("already redeemed or revoked").into());
+        }
+        settings.set_at_path(
+            [INVITES, invite.id()],
+            Value::Text(REVOKED_INVITE.to_string()),
+        )?;
+        op.commit()
+    }
+
+    // === LINKS ===
+
+    /// Resolve a link to an entry of this tree.
+    ///
+    /// Links are `Value::Link` values stored in subtrees. Resolving one checks
+    /// that the linked entry is stored locally, that its content still hashes to
+    /// the linked ID, and that it belongs to this tree. Use
+    /// [`BaseDB::resolve_link`](crate::basedb::BaseDB::resolve_link) for links
+    /// into other trees.
+    ///
+    /// # Arguments
+    /// * `link` - The linked entry ID
+    ///
+    /// # Errors
+    /// Returns `DatabaseError::EntryNotFound` if the entry is not stored,
+    /// `DatabaseError::TreeIntegrityViolation` if its content does not match the ID,
+    /// or `BaseError::EntryNotInTree` if it belongs to another tree.
+    pub fn resolve_link(&self, link: &ID) -> Result<Entry> {
+        let entry = resolve_entry(self.backend.as_ref(), link)?;
+        if !entry.in_tree(&self.root) {
+            return Err(BaseError::EntryNotInTree {
+                entry_id: link.clone(),
+                tree_id: self.root.clone(),
+            }
+            .into());
+        }
+        Ok(entry)
+    }
+
+    /// Get a read-only view of a subtree as it was at a linked entry.
+    ///
+    /// The view contains the changes of the linked entry and its ancestors only,
+    /// so a link pins the referenced records to the version that was linked.
+    ///
+    /// # Arguments
+    /// * `link` - The linked entry ID, which must be an entry of this tree
+    /// * `name` - The name of the subtree to view
+    ///
+    /// # Errors
+    /// Returns an error if the link cannot be resolved, see [`resolve_link`](Self::resolve_link).
+    pub fn linked_subtree<T>(&self, link: &ID, name: impl Into<String>) -> Result<T>
+    where
+        T: SubTree,
+    {
+        self.resolve_link(link)?;
+        let op = self.new_operation_with_tips(std::slice::from_ref(link))?;
+        T::new(&op, name)
+    }
+
+    /// Find the links in a subtree that can no longer be resolved.
+    ///
+    /// Every `Value::Link` in the current state of the subtree, including links
+    /// nested in maps and lists, is checked to point at a locally stored entry
+    /// of any tree whose content matches the linked ID.
+    ///
+    /// # Arguments
+    /// * `name` - The name of a subtree storing a `Map`, such as a `Dict`
+    ///
+    /// # Returns
+    /// The unresolvable link targets, in no particular order
+    pub fn broken_links(&self, name: impl AsRef<str>) -> Result<Vec<ID>> {
+        let state = self
+            .new_operation_with_tips(self.get_tips()?)?
+            .get_full_state::<Map>(name)?;
+        let mut broken = Vec::new();
+        for link in Value::Map(state).links() {
+            if !broken.contains(link) && resolve_entry(self.backend.as_ref(), link).is_err() {
+                broken.push(link.clone());
+            }
+        }
+        Ok(broken)
+    }
+
+    // === CHECKPOINTS ===
+
+    /// Write a checkpoint of the current state of the tree's subtrees.
+    ///
+    /// Computing a subtree's state replays its history from the root. A
+    /// checkpoint is an entry storing the merged state of each subtree at the
+    /// current tips, and reading the state of any later entry stops at the
+    /// nearest checkpoint instead of the root. The checkpoint itself does not
+    /// change any data.
+    ///
+    /// Subtrees storing a `Map`, such as `Dict` and `Table`, are checkpointed.
+    /// Other subtrees and the internal `_settings` subtree are not.
+    ///
+    /// # Returns
+    /// The ID of the checkpoint entry
+    pub fn create_checkpoint(&self) -> Result<ID> {
+        let mut subtrees = BTreeSet::new();
+        for entry in self.backend.get_tree_iter(&self.root)? {
+            subtrees.extend(
+                entry?
+                    .subtrees()
+                    .into_iter()
+                    .filter(|name| !name.starts_with('_')),
+            );
+        }
+
+        let op = self.new_operation()?;
+        op.stage_checkpoint(subtrees)?;
+        op.commit()
+    }
+
+    /// Remove the branches of the tree that `keep_tips` do not build on.
+    ///
+    /// Entries that are neither one of `keep_tips` nor an ancestor of one are
+    /// deleted from the backend, see [`Database::prune`]. The state at `keep_tips`,
+    /// including any checkpoints it builds on, is unaffected.
+    ///
+    /// # Returns
+    /// Statistics about the removed entries
+    pub fn prune(&self, keep_tips: &[ID]) -> Result<PruneStats> {
+        let stats = self.backend.prune(&self.root, keep_tips)?;
+        self.context.state_cache.invalidate_tree(&self.root);
+        Ok(stats)
+    }
+
+    // === EXPIRATION ===
+
+    /// Make the tree ephemeral, or keep its entries forever again.
+    ///
+    /// The entries of an ephemeral tree expire once they are older than
+    /// `max_age`, and the whole tree once nothing has been written to it for
+    /// that long. Expired entries are removed by [`expire`](Self::expire), which
+    /// [`BaseDB::expire_trees`](crate::basedb::BaseDB::expire_trees) and the
+    /// [`Maintenance`](crate::basedb::Maintenance) scheduler run for every
+    /// ephemeral tree.
+    ///
+    /// The maximum age is stored in the tree's settings, so changing it is
+    /// committed with the tree's default authentication key, which needs admin
+    /// permission.
+    ///
+    /// # Arguments
+    /// * `max_age` - How long entries are kept, or `None` to keep them forever
+    ///
+    /// # Returns
+    /// The ID of the settings entry
+    pub fn set_max_age(&self, max_age: Option<Duration>) -> Result<ID> {
+        let value = match max_age {
+            Some(max_age) => Value::Int(i64::try_from(max_age.as_millis()).unwrap_or(i64::MAX)),
+            None => Value::Deleted,
+        };
+        let op = self.new_operation()?;
+        op.get_subtree::<Dict>(SETTINGS)?
+            .set_at_path([EPHEMERAL, MAX_AGE_MS], value)?;
+        op.commit()
+    }
+
+    /// The maximum age of the tree's entries, or `None` if the tree is not ephemeral.
+    pub fn max_age(&self) -> Result<Option<Duration>> {
+        let settings = self.get_settings()?.get_all()?;
+        let max_age_ms = match settings.get(EPHEMERAL) {
+            Some(Value::Map(ephemeral)) => ephemeral.get(MAX_AGE_MS),
+            _ => None,
+        };
+        Ok(match max_age_ms {
+            Some(Value::Int(ms)) => u64::try_from(*ms).ok().map(Duration::from_millis),
+            _ => None,
+        })
+    }
+
+    /// Set the tree's write quotas, replacing any set before.
+    ///
+    /// Each limit that is `Some` is enforced for entries committed after this
+    /// one, here and on every replica: commits that break a quota fail with
+    /// `AtomicOpError::QuotaExceeded`, and entries received from peers that
+    /// break one fail verification. Entries that change `_settings` are exempt
+    /// from all quotas, so the quota can still be raised once the tree is full. Pass `TreeQuota::default()` to remove all limits.
+    ///
+    /// Requires admin permission, as it changes `_settings`.
+    ///
+    /// # Returns
+    /// The ID of the settings entry
+    pub fn set_quota(&self, quota: TreeQuota) -> Result<ID> {
+        let limit = |limit: Option<u64>| match limit {
+            Some(limit) => Value::Int(i64::try_from(limit).unwrap_or(i64::MAX)),
+            None => Value::Deleted,
+        };
+        let op = self.new_operation()?;
+        let settings = op.get_subtree::<Dict>(SETTINGS)?;
+        settings.set_at_path([QUOTA, MAX_ENTRY_SIZE], limit(quota.max_entry_size))?;
+        settings.set_at_path([QUOTA, MAX_SUBTREE_SIZE], limit(quota.max_subtree_size))?;
+        settings.set_at_path([QUOTA, MAX_ENTRIES], limit(quota.max_entries))?;
+        op.commit()
+    }
+
+    /// The tree's write quotas, see [`set_quota`](Self::set_quota).
+    pub fn quota(&self) -> Result<TreeQuota> {
+        Ok(TreeQuota::from_settings(&self.get_settings()?.get_all()?))
+    }
+
+    /// Remove the entries that are older than the tree's maximum age.
+    ///
+    /// Does nothing if the tree is not ephemeral. If nothing has been written to
+    /// the tree within its maximum age, all of its entries are removed, the root
+    /// included, and the tree no longer exists in this database. Otherwise a
+    /// checkpoint of the data written within the maximum age is committed with
+    /// the tree's default authentication key and the expired entries are
+    /// deleted. The root and entries changing settings are kept.
+    ///
+    /// Expiration only affects this database; peers keep their copies of the
+    /// entries and may sync them back.
+    ///
+    /// # Returns
+    /// What was removed
+    pub fn expire(&self) -> Result<ExpireStats> {
+        match self.max_age()? {
+            Some(max_age) => expire::expire(self, max_age, wall_clock_ms()),
+            None => Ok(ExpireStats::default()),
+        }
+    }
+
+    // === FREEZING ===
+
+    /// Freeze a subtree, so that no entry may write to it again.
+    ///
+    /// Adds a freeze record for the subtree to the tree's settings, which needs
+    /// a key with admin permission. Every replica then rejects entries that
+    /// write to the subtree, or change or remove its record, when they are
+    /// validated against settings that include the freeze. Writes made
+    /// concurrently with the freeze on a branch that had not seen it yet are
+    /// kept. Use it for finalized documents, published releases or closed
+    /// ballots.
+    ///
+    /// # Errors
+    /// Returns `AtomicOpError::SubtreeOperationFailed` for internal subtrees,
+    /// whose names start with `_`, and `AtomicOpError::SubtreeFrozen` if the
+    /// subtree is already frozen.
+    pub fn freeze_subtree(&self, subtree: impl AsRef<str>) -> Result<ID> {
+        let subtree = subtree.as_ref();
+        if subtree.starts_with('_') {
+            return Err(AtomicOpError::SubtreeOperationFailed {
+                subtree: subtree.to_string(),
+                reason: "Internal subtrees cannot be frozen".to_string(),
+            }
+            .into());
+        }
+        let op = self.new_operation()?;
+        op.get_subtree::<Dict>(SETTINGS)?
+            .set_at_path([FROZEN, subtree], Value::Bool(true))?;
+        op.commit()
+    }
+
+    /// Returns true if the subtree is frozen.
+    pub fn is_frozen(&self, subtree: impl AsRef<str>) -> Result<bool> {
+        let subtree = subtree.as_ref();
+        Ok(self.frozen_subtrees()?.iter().any(|name| name == subtree))
+    }
+
+    /// The names of the frozen subtrees, sorted.
+    pub fn frozen_subtrees(&self) -> Result<Vec<String>> {
+        Ok(freeze::frozen_subtrees(&self.get_settings()?.get_all()?))
+    }
+
+    // === EPHEMERAL SUBTREES ===
+
+    /// Mark a subtree as ephemeral, keeping its writes out of the tree's history.
+    ///
+    /// Once marked, commits no longer write the subtree's changes to the entry
+    /// but merge them into the in-memory
+    /// [`BaseDB::ephemeral_state`](crate::basedb::BaseDB::ephemeral_state), and
+    /// reads of the subtree include them. A commit that only writes to
+    /// ephemeral subtrees creates no entry. The state is shared with peers that
+    /// sync with an ephemeral state and is lost when the process exits. Only
+    /// `Dict` subtrees can be ephemeral.
+    ///
+    /// Entries written to the subtree before it was marked are kept. The mark is
+    /// stored in the tree's settings, so it needs a key with admin permission.
+    ///
+    /// # Errors
+    /// Returns `AtomicOpError::SubtreeOperationFailed` for internal subtrees,
+    /// whose names start with `_`.
+    pub fn mark_ephemeral(&self, subtree: impl AsRef<str>) -> Result<ID> {
+        let subtree = subtree.as_ref();
+        if subtree.starts_with('_') {
+            return Err(AtomicOpError::SubtreeOperationFailed {
+                subtree: subtree.to_string(),
+                reason: "Internal subtrees cannot be ephemeral".to_string(),
+            }
+            .into());
+        }
+        let op = self.new_operation()?;
+        op.get_subtree::<Dict>(SETTINGS)?
+            .set_at_path([EPHEMERAL_SUBTREES, subtree], Value::Bool(true))?;
+        op.commit()
+    }
+
+    /// Returns true if the subtree is ephemeral.
+    pub fn is_ephemeral(&self, subtree: impl AsRef<str>) -> Result<bool> {
+        let subtree = subtree.as_ref();
+        Ok(self
+            .ephemeral_subtrees()?
+            .iter()
+            .any(|name| name == subtree))
+    }
+
+    /// The names of the ephemeral subtrees, sorted.
+    pub fn ephemeral_subtrees(&self) -> Result<Vec<String>> {
+        Ok(ephemeral::ephemeral_subtrees(
+            &self.get_settings()?.get_all()?,
+        ))
+    }
+
+    // === ENCODINGS ===
+
+    /// Declare how a subtree's data is encoded in entries written from now on.
+    ///
+    /// The declaration is stored in the tree's settings, so it applies on every
+    /// replica and needs a key with admin permission. Entries already written
+    /// keep their encoding, and both encodings are read transparently. See
+    /// [`SubtreeEncoding`] for when CBOR is actually written.
+    ///
+    /// # Errors
+    /// Returns `AtomicOpError::SubtreeOperationFailed` for internal subtrees,
+    /// whose names start with `_` and are always JSON.
+    pub fn set_subtree_encoding(
+        &self,
+        subtree: impl AsRef<str>,
+        encoding: SubtreeEncoding,
+    ) -> Result<ID> {
+        let subtree = subtree.as_ref();
+        if subtree.starts_with('_') {
+            return Err(AtomicOpError::SubtreeOperationFailed {
+                subtree: subtree.to_string(),
+                reason: "Internal subtrees are always JSON encoded".to_string(),
+            }
+            .into());
+        }
+        let op = self.new_operation()?;
+        op.get_subtree::<Dict>(SETTINGS)?.set_at_path(
+            [ENCODINGS, subtree],
+            Value::Text(encoding.name().to_string()),
+        )?;
+        op.commit()
+    }
+
+    /// The encoding declared for a subtree, JSON unless set otherwise.
+    pub fn subtree_encoding(&self, subtree: impl AsRef<str>) -> Result<SubtreeEncoding> {
+        let settings = self.get_settings()?.get_all()?;
+        Ok(SubtreeEncoding::declared(&settings, subtree.as_ref()))
+    }
+
+    // === REPAIR ===
+
+    /// Compare this replica of the tree with another one and plan a repair.
+    ///
+    /// `other` is usually the same tree loaded from another database, such as
+    /// a peer's bundle imported into a scratch database. The plan lists the
+    /// entries only one replica holds, to copy to the other, and the entries
+    /// to quarantine instead: those a replica marked as failing verification,
+    /// those with invalid signatures, and those whose parents are missing or
+    /// quarantined. Neither replica is changed.
+    ///
+    /// # Errors
+    /// Returns `BaseError::InvalidOperation` if `other` is a different tree.
+    pub fn reconcile_with(&self, other: &Tree) -> Result<RepairPlan> {
+        repair::reconcile(self, other)
+    }
+
+    // === DIFFS ===
+
+    /// Compare the state of the tree at two sets of tips.
+    ///
+    /// Each subtree's merged state at `tips_a` is compared with its merged
+    /// state at `tips_b`, and the returned diff lists what changed going from
+    /// `a` to `b`: added, removed and changed keys (including table rows),
+    /// and added, removed, changed and moved list items. Passing a single
+    /// entry compares the tree as of that commit.
+    ///
+    /// # Errors
+    /// Returns an error if either set of tips is empty, or if an entry is not
+    /// stored or belongs to another tree.
+    pub fn diff(&self, tips_a: impl AsRef<[ID]>, tips_b: impl AsRef<[ID]>) -> Result<TreeDiff> {
+        diff::diff(self, tips_a.as_ref(), tips_b.as_ref())
+    }
+
+    /// List the values two branches wrote concurrently.
+    ///
+    /// Each subtree is compared at `tip_a`, at `tip_b` and where the two
+    /// diverged. Every value that both branches changed, to different values,
+    /// is reported along with the value that merging them keeps, so the write
+    /// that merging discards can be shown to the user. Lists and renamed keys
+    /// merge without discarding writes, and are not reported.
+    ///
+    /// # Errors
+    /// Returns an error if an entry is not stored or belongs to another tree.
+    pub fn merge_report(&self, tip_a: &ID, tip_b: &ID) -> Result<MergeReport> {
+        diff::merge_report(self, tip_a, tip_b)
+    }
+
+    // === BUNDLES ===
+
+    /// Write the whole tree to `writer` as a portable bundle.
+    ///
+    /// The bundle is a self-contained JSON document with every entry of the
+    /// tree, signatures included, and each entry's verification status. Import
+    /// it into another database with [`BaseDB::import_bundle`](crate::basedb::BaseDB::import_bundle).
+    ///
+    /// # Returns
+    /// The number of entries written
+    pub fn export_bundle(&self, writer: impl Write) -> Result<usize> {
+        crate::basedb::bundle::export(self, writer)
+    }
+
+    // === INCLUSION PROOFS ===
+
+    /// Build a proof that an entry belongs to this tree.
+    ///
+    /// The proof is the shortest chain of entries from the entry back to the
+    /// root entry, following parent links. Anyone holding the proof and the
+    /// root ID can check it with [`verify_inclusion`](crate::entry::verify_inclusion),
+    /// without access to the database.
+    ///
+    /// # Errors
+    /// Returns an error if the entry is not found or is not part of this tree.
+    pub fn prove_inclusion<I: Into<ID>>(&self, entry_id: I) -> Result<InclusionProof> {
+        let entry_id = entry_id.into();
+        let entry = self.get_entry(entry_id.clone())?;
+
+        // Breadth-first search towards the root, remembering each entry's child
+        let mut children: HashMap<ID, ID> = HashMap::new();
+        let mut entries: HashMap<ID, Entry> = HashMap::new();
+        let mut queue = VecDeque::from([entry_id.clone()]);
+        entries.insert(entry_id.clone(), entry);
+        while let Some(id) = queue.pop_front() {
+            if id == self.root {
+                let mut chain = vec![entries.remove(&id).expect("visited entries are kept")];
+                let mut current = id;
+                while let Some(child) = children.get(&current) {
+                    chain.push(entries.remove(child).expect("visited entries are kept"));
+                    current = child.clone();
+                }
+                chain.reverse();
+                return Ok(InclusionProof {
+                    entry: entry_id,
+                    chain,
+                });
+            }
+            for parent in entries[&id].parents()? {
+                if entries.contains_key(&parent) {
+                    continue;
+                }
+                entries.insert(parent.clone(), self.backend.get(&parent)?);
+                children.insert(parent.clone(), id.clone());
+                queue.push_back(parent);
+            }
+        }
+
+        Err(DatabaseError::TreeIntegrityViolation {
+            reason: format!("entry {entry_id} has no path to root {}", self.root),
+        }
+        .into())
+    }
+
+    // === SPARSE LOADING ===
+
+    /// Load only the parts of this tree that write to `subtrees`.
+    ///
+    /// Returns a tree backed by a [`Sparse`](crate::backend::database::Sparse)
+    /// database holding the root entry and the entries of `subtrees` and
+    /// `_settings`, fetched from this tree's backend once. Reading any other
+    /// subtree fails with `DatabaseError::SubtreeNotLoaded`. Commits are stored
+    /// in this tree's backend as well, and notify the same commit listeners.
+    ///
+    /// This keeps memory use down on constrained devices, especially when the
+    /// backend is a [`RemoteDatabase`](crate::sync::RemoteDatabase) or on disk.
+    ///
+    /// # Example
+    /// ```
+    /// # use eidetica::backend::database::InMemory;
+    /// # use eidetica::basedb::BaseDB;
+    /// # use eidetica::subtree::Dict;
+    /// # fn main() -> eidetica::Result<()> {
+    /// # let db = BaseDB::new(Box::new(InMemory::new()));
+    /// # db.add_private_key("key")?;
+    /// # let tree = db.new_tree_default("key")?;
+    /// let notes = tree.load_sparse(&["notes"])?;
+    /// let op = notes.new_authenticated_operation("key")?;
+    /// op.get_subtree::<Dict>("notes")?.set("first", "hello")?;
+    /// op.commit()?;
+    ///
+    /// assert!(notes.get_subtree_viewer::<Dict>("todos")?.get_all().is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn load_sparse(&self, subtrees: &[&str]) -> Result<Tree> {
+        let sparse = Sparse::load(Arc::clone(&self.backend), &self.root, subtrees)?;
+        let mut tree = Tree::new_from_id(self.root.clone(), Arc::new(sparse))?
+            .with_context(Arc::clone(&self.context));
+        tree.default_auth_key = self.default_auth_key.clone();
+        tree.merge_window = self.merge_window;
+        Ok(tree)
+    }
+
+    // === PREPARED QUERIES ===
+
+    /// Prepares `query` for repeated execution against this tree.
+    ///
+    /// Field paths are parsed and the way rows are found is chosen once, here,
+    /// instead of on every execution. See [`crate::query`].
+    ///
+    /// # Errors
+    /// Returns `QueryError::InvalidField` if a field path is malformed, or
+    /// `QueryError::InvalidOperand` if the row key is compared to a literal
+    /// that is not text.
+    pub fn prepare(&self, query: &Query) -> Result<PreparedQuery> {
+        PreparedQuery::new(self.clone(), query.clone())
+    }
+
+    // === TREE QUERIES ===
+
+    /// Get all entries in this tree.
+    ///
+    /// ⚠️ **Warning**: This method loads all entries into memory. Use with caution on large trees.
+    /// Consider using `iter_entries()` to process them one at a time, or `get_tips()` or
+    /// `get_tip_entries()` for more efficient access patterns.
+    ///
+    /// # Returns
+    /// A `Result` containing a vector of all `Entry` objects in the tree
+    pub fn get_all_entries(&self) -> Result<Vec<Entry>> {
+        self.backend.get_tree(&self.root)
+    }
+
+    /// Stream all entries in this tree, in topological order.
+    ///
+    /// Entries are loaded one at a time as the iterator is advanced, see
+    /// [`Database::get_tree_iter`].
+    ///
+    /// # Returns
+    /// A `Result` containing an iterator over the tree's entries
+    pub fn iter_entries(&self) -> Result<EntryIter<'_>> {
+        self.backend.get_tree_iter(&self.root)
+    }
+}
+
+/// A handle to a tree that can read it but never write to it.
+///
+/// Returned by [`Tree::read_only`]. It has the reading methods of [`Tree`] and
+/// none of the writing ones, so code holding only a `ReadOnlyTree` cannot start
+/// an operation or commit an entry, and needs no private key:
+///
+/// ```compile_fail
+/// # fn write(tree: eidetica::tree::ReadOnlyTree) {
+/// tree.new_operation();
+/// # }
+/// ```
+///
+/// Subtree viewers obtained from it are ephemeral like those of a [`Tree`]:
+/// changes staged in them are discarded.
+#[derive(Clone)]
+pub struct ReadOnlyTree {
+    tree: Tree,
+}
+
+impl ReadOnlyTree {
+    /// See [`Tree::root_id`].
+    pub fn root_id(&self) -> &ID {
+        self.tree.root_id()
+    }
+
+    /// See [`Tree::get_root`].
+    pub fn get_root(&self) -> Result<Entry> {
+        self.tree.get_root()
+    }
+
+    /// See [`Tree::get_name`].
+    pub fn get_name(&self) -> Result<String> {
+        self.tree.get_name()
+    }
+
+    /// See [`Tree::get_settings`].
+    pub fn get_settings(&self) -> Result<Dict> {
+        self.tree.get_settings()
+    }
+
+    /// See [`Tree::settings`].
+    pub fn settings(&self) -> Result<TreeSettings> {
+        self.tree.settings()
+    }
+
+    /// See [`Tree::get_subtree_viewer`].
+    pub fn get_subtree_viewer<T>(&self, name: impl Into<String>) -> Result<T>
+    where
+        T: SubTree,
+    {
+        self.tree.get_subtree_viewer(name)
+    }
+
+    /// See [`Tree::get_subtree_viewer_at`].
+    pub fn get_subtree_viewer_at<T>(&self, name: impl Into<String>, entry_ids: &[ID]) -> Result<T>
+    where
+        T: SubTree,
+    {
+        self.tree.get_subtree_viewer_at(name, entry_ids)
+    }
+
+    /// See [`Tree::linked_subtree`].
+    pub fn linked_subtree<T>(&self, link: &ID, name: impl Into<String>) -> Result<T>
+    where
+        T: SubTree,
+    {
+        self.tree.linked_subtree(link, name)
+    }
+
+    /// See [`Tree::prepare`].
+    pub fn prepare(&self, query: &Query) -> Result<PreparedQuery> {
+        self.tree.prepare(query)
+    }
+
+    /// See [`Tree::get_tips`].
+    pub fn get_tips(&self) -> Result<Vec<ID>> {
+        self.tree.get_tips()
+    }
+
+    /// See [`Tree::subtree_tips`].
+    pub fn subtree_tips(&self, subtree_name: impl AsRef<str>) -> Result<Vec<ID>> {
+        self.tree.subtree_tips(subtree_name)
+    }
+
+    /// See [`Tree::get_tip_entries`].
+    pub fn get_tip_entries(&self) -> Result<Vec<Entry>> {
+        self.tree.get_tip_entries()
+    }
+
+    /// See [`Tree::latest_edit`].
+    pub fn latest_edit(&self) -> Result<Option<(ID, Hlc)>> {
+        self.tree.latest_edit()
+    }
+
+    /// See [`Tree::get_entry`].
+    pub fn get_entry<I: Into<ID>>(&self, entry_id: I) -> Result<Entry> {
+        self.tree.get_entry(entry_id)
+    }
+
+    /// See [`Tree::get_entries`].
+    pub fn get_entries<I, T>(&self, entry_ids: I) -> Result<Vec<Entry>>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<ID>,
+    {
+        self.tree.get_entries(entry_ids)
+    }
+
+    /// See [`Tree::get_all_entries`].
+    pub fn get_all_entries(&self) -> Result<Vec<Entry>> {
+        self.tree.get_all_entries()
+    }
+
+    /// See [`Tree::iter_entries`].
+    pub fn iter_entries(&self) -> Result<EntryIter<'_>> {
+        self.tree.iter_entries()
+    }
+
+    /// See [`Tree::resolve_link`].
+    pub fn resolve_link(&self, link: &ID) -> Result<Entry> {
+        self.tree.resolve_link(link)
+    }
+
+    /// See [`Tree::broken_links`].
+    pub fn broken_links(&self, name: impl AsRef<str>) -> Result<Vec<ID>> {
+        self.tree.broken_links(name)
+    }
+
+    /// See [`Tree::verify_entry_signature`].
+    pub fn verify_entry_signature<I: Into<ID>>(&self, entry_id: I) -> Result<bool> {
+        self.tree.verify_entry_signature(entry_id)
+    }
+
+    /// See [`Tree::settings_at`].
+    pub fn settings_at<I: Into<ID>>(&self, entry_id: I) -> Result<Map> {
+        self.tree.settings_at(entry_id)
+    }
+
+    /// See [`Tree::auth_state_at`].
+    pub fn auth_state_at<I: Into<ID>>(&self, entry_id: I) -> Result<AuthSettings> {
+        self.tree.auth_state_at(entry_id)
+    }
+
+    /// See [`Tree::auth_timeline`].
+    pub fn auth_timeline(&self) -> Result<Vec<(ID, AuthSettings)>> {
+        self.tree.auth_timeline()
+    }
+
+    /// See [`Tree::revocation_status`].
+    pub fn revocation_status<I: Into<ID>>(&self, entry_id: I) -> Result<RevocationStatus> {
+        self.tree.revocation_status(entry_id)
+    }
+
+    /// See [`Tree::is_frozen`].
+    pub fn is_frozen(&self, subtree: impl AsRef<str>) -> Result<bool> {
+        self.tree.is_frozen(subtree)
+    }
+
+    /// See [`Tree::frozen_subtrees`].
+    pub fn frozen_subtrees(&self) -> Result<Vec<String>> {
+        self.tree.frozen_subtrees()
+    }
+
+    /// See [`Tree::is_ephemeral`].
+    pub fn is_ephemeral(&self, subtree: impl AsRef<str>) -> Result<bool> {
+        self.tree.is_ephemeral(subtree)
+    }
+
+    /// See [`Tree::ephemeral_subtrees`].
+    pub fn ephemeral_subtrees(&self) -> Result<Vec<String>> {
+        self.tree.ephemeral_subtrees()
+    }
+
+    /// See [`Tree::subtree_encoding`].
+    pub fn subtree_encoding(&self, subtree: impl AsRef<str>) -> Result<SubtreeEncoding> {
+        self.tree.subtree_encoding(subtree)
+    }
+
+    /// See [`Tree::max_age`].
+    pub fn max_age(&self) -> Result<Option<Duration>> {
+        self.tree.max_age()
+    }
+
+    /// See [`Tree::quota`].
+    pub fn quota(&self) -> Result<TreeQuota> {
+        self.tree.quota()
+    }
+
+    /// See [`Tree::diff`].
+    pub fn diff(&self, tips_a: impl AsRef<[ID]>, tips_b: impl AsRef<[ID]>) -> Result<TreeDiff> {
+        self.tree.diff(tips_a, tips_b)
+    }
+
+    /// See [`Tree::merge_report`].
+    pub fn merge_report(&self, tip_a: &ID, tip_b: &ID) -> Result<MergeReport> {
+        self.tree.merge_report(tip_a, tip_b)
+    }
+
+    /// See [`Tree::export_bundle`].
+    pub fn export_bundle(&self, writer: impl Write) -> Result<usize> {
+        self.tree.export_bundle(writer)
+    }
+
+    /// See [`Tree::prove_inclusion`].
+    pub fn prove_inclusion<I: Into<ID>>(&self, entry_id: I) -> Result<InclusionProof> {
+        self.tree.prove_inclusion(entry_id)
+    }
+}
+
+impl From<Tree> for ReadOnlyTree {
+    fn from(tree: Tree) -> Self {
+        Self { tree }
+    }
+}
+
+/// The `auth` section of a settings map, empty if there is none.
+fn auth_section(settings: &Map) -> AuthSettings {
+    match settings.get("auth") {
+        Some(Value::Map(auth)) => AuthSettings::from_map(auth.clone()),
+        _ => AuthSettings::new(),
+    }
+}
+
+/// Whether a settings map has a non-empty `auth` section.
+fn has_auth(settings: &Map) -> bool {
+    matches!(settings.get("auth"), Some(Value::Map(auth)) if !auth.as_hashmap().is_empty())
+}
+
+/// Load a linked entry, checking that its content matches the link.
+pub(crate) fn resolve_entry(backend: &dyn Database, link: &ID) -> Result<Entry> {
+    let entry = backend.get(link)?;
+    let id = entry.id();
+    if id != *link {
+        return Err(DatabaseError::TreeIntegrityViolation {
+            reason: format!("entry stored as {link} hashes to {id}"),
+        }
+        .into());
+    }
+    Ok(entry)
+}
+
+/// Async versions of the `Tree` methods that touch the backend.
+///
+/// Each method runs its synchronous counterpart on Tokio's blocking thread pool.
+/// Requires the "async" feature and a running Tokio runtime.
+#[cfg(feature = "async")]
+impl Tree {
+    /// Async version of [`Tree::new_operation`].
+    pub async fn new_operation_async(&self) -> Result<AtomicOp> {
+        let tree = self.clone();
+        crate::backend::asynchronous::run_blocking(move || tree.new_operation()).await
+    }
+
+    /// Async version of [`Tree::new_operation_with_tips`].
+    pub async fn new_operation_with_tips_async(
+        &self,
+        tips: impl Into<Vec<ID>>,
+    ) -> Result<AtomicOp> {
+        let tree = self.clone();
+        let tips = tips.into();
+        crate::backend::asynchronous::run_blocking(move || tree.new_operation_with_tips(tips)).await
+    }
+
+    /// Async version of [`Tree::get_subtree_viewer`].
+    pub async fn get_subtree_viewer_async<T>(&self, name: impl Into<String>) -> Result<T>
+    where
+        T: SubTree + Send + 'static,
+    {
+        let tree = self.clone();
+        let name = name.into();
+        crate::backend::asynchronous::run_blocking(move || tree.get_subtree_viewer(name)).await
+    }
+
+    /// Async version of [`Tree::get_subtree_viewer_at`].
+    pub async fn get_subtree_viewer_at_async<T>(
+        &self,
+        name: impl Into<String>,
+        entry_ids: impl Into<Vec<ID>>,
+    ) -> Result<T>
+    where
+        T: SubTree + Send + 'static,
+    {
+        let tree = self.clone();
+        let name = name.into();
+        let entry_ids = entry_ids.into();
+        crate::backend::asynchronous::run_blocking(move || {
+            tree.get_subtree_viewer_at(name, &entry_ids)
+        })
+        .await
+    }
+
+    /// Async version of [`Tree::get_tips`].
+    pub async fn get_tips_async(&self) -> Result<Vec<ID>> {
+        let tree = self.clone();
+        crate::backend::asynchronous::run_blocking(move || tree.get_tips()).await
+    }
+
+    /// Async version of [`Tree::get_entry`].
+    pub async fn get_entry_async(&self, entry_id: impl Into<ID>) -> Result<Entry> {
+        let tree = self.clone();
+        let entry_id = entry_id.into();
+        crate::backend::asynchronous::run_blocking(move || tree.get_entry(entry_id)).await
+    }
+
+    /// Async version of [`Tree::get_all_entries`].
+    pub async fn get_all_entries_async(&self) -> Result<Vec<Entry>> {
+        let tree = self.clone();
+        crate::backend::asynchronous::run_blocking(move || tree.get_all_entries()).await
     }
 }
@@ -0,0 +1,133 @@
+//! Group commit tests
+//!
+//! Tests for coalescing logical operations into shared entries with
+//! `Tree::group_commit`: flushing, size and time limits, rollback of failed
+//! operations, and the per-operation metadata recorded in the entry.
+
+use crate::helpers::*;
+use eidetica::subtree::Dict;
+use std::time::Duration;
+
+const LONG_WINDOW: Duration = Duration::from_secs(3600);
+
+#[test]
+fn test_group_commit_coalesces_operations() {
+    let tree = setup_tree();
+    let tips_before = tree.get_tips().unwrap();
+
+    let group = tree.group_commit(LONG_WINDOW);
+    for i in 0..5 {
+        group
+            .stage_labeled(format!("sample-{i}"), |op| {
+                op.get_subtree::<Dict>("telemetry")?
+                    .set(format!("cpu-{i}"), i.to_string())
+            })
+            .unwrap();
+    }
+    assert_eq!(group.pending_ops(), 5);
+    // Nothing is committed until the group is flushed
+    assert_eq!(tree.get_tips().unwrap(), tips_before);
+
+    let id = group.flush().unwrap().expect("group should be committed");
+    assert_eq!(group.pending_ops(), 0);
+    assert_eq!(tree.get_tips().unwrap(), vec![id.clone()]);
+
+    let entry = tree.backend().get(&id).unwrap();
+    assert_eq!(entry.parents().unwrap(), tips_before);
+    let ops = entry.grouped_ops();
+    let labels: Vec<_> = ops.iter().map(|op| op.label.as_deref().unwrap()).collect();
+    assert_eq!(
+        labels,
+        ["sample-0", "sample-1", "sample-2", "sample-3", "sample-4"]
+    );
+    assert!(
+        ops.windows(2)
+            .all(|pair| pair[0].timestamp < pair[1].timestamp)
+    );
+
+    let viewer = tree.get_subtree_viewer::<Dict>("telemetry").unwrap();
+    for i in 0..5 {
+        assert_eq!(
+            viewer.get_string(format!("cpu-{i}")).unwrap(),
+            i.to_string()
+        );
+    }
+}
+
+#[test]
+fn test_group_commit_flush_without_operations() {
+    let tree = setup_tree();
+    let group = tree.group_commit(LONG_WINDOW);
+    assert!(group.flush().unwrap().is_none());
+
+    // Entries committed directly have no grouped operations
+    let tip = &tree.get_tips().unwrap()[0];
+    assert!(tree.backend().get(tip).unwrap().grouped_ops().is_empty());
+}
+
+#[test]
+fn test_group_commit_max_ops() {
+    let tree = setup_tree();
+    let group = tree.group_commit(LONG_WINDOW).max_ops(2);
+
+    for i in 0..3 {
+        group
+            .stage(|op| op.get_subtree::<Dict>("data")?.set(format!("k{i}"), "v"))
+            .unwrap();
+    }
+    // The first two operations were committed together, the third is pending
+    assert_eq!(group.pending_ops(), 1);
+    let tips = tree.get_tips().unwrap();
+    assert_eq!(tips.len(), 1);
+    let entry = tree.backend().get(&tips[0]).unwrap();
+    assert_eq!(entry.grouped_ops().len(), 2);
+    assert!(entry.grouped_ops().iter().all(|op| op.label.is_none()));
+}
+
+#[test]
+fn test_group_commit_window_elapsed() {
+    let tree = setup_tree();
+    let group = tree.group_commit(Duration::ZERO);
+
+    group
+        .stage(|op| op.get_subtree::<Dict>("data")?.set("first", "1"))
+        .unwrap();
+    let tips_before = tree.get_tips().unwrap();
+
+    // The first group's window has elapsed, so staging closes it
+    group
+        .stage(|op| op.get_subtree::<Dict>("data")?.set("second", "2"))
+        .unwrap();
+    assert_ne!(tree.get_tips().unwrap(), tips_before);
+    assert_eq!(group.pending_ops(), 1);
+
+    // Dropping the group commits the rest
+    drop(group);
+    let viewer = tree.get_subtree_viewer::<Dict>("data").unwrap();
+    assert_eq!(viewer.get_string("first").unwrap(), "1");
+    assert_eq!(viewer.get_string("second").unwrap(), "2");
+}
+
+#[test]
+fn test_group_commit_rolls_back_failed_operation() {
+    let tree = setup_tree();
+    let group = tree.group_commit(LONG_WINDOW);
+
+    group
+        .stage(|op| op.get_subtree::<Dict>("data")?.set("kept", "yes"))
+        .unwrap();
+    let result = group.stage(|op| {
+        let dict = op.get_subtree::<Dict>("data")?;
+        dict.set("discarded", "yes")?;
+        dict.get("missing").map(|_| ())
+    });
+    assert!(result.is_err());
+    assert_eq!(group.pending_ops(), 1);
+
+    let id = group.flush().unwrap().unwrap();
+    assert_eq!(tree.backend().get(&id).unwrap().grouped_ops().len(), 1);
+
+    let viewer = tree.get_subtree_viewer::<Dict>("data").unwrap();
+    assert_eq!(viewer.get_string("kept").unwrap(), "yes");
+    assert!(viewer.get("discarded").is_err());
+}
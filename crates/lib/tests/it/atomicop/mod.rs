@@ -1,11 +1,16 @@
 //! AtomicOp integration tests
 //!
 //! This module tests AtomicOp functionality including basic operations,
-//! data manipulation, custom tips, and path finding algorithms.
+//! data manipulation, custom tips, path finding algorithms, group commits,
+//! write coalescing, and the spans traced with the `tracing` feature.
 //! Tests are organized by functional category for better maintainability.
 
 mod basic_operations;
 mod custom_tips;
 mod data_operations;
+mod group_commit;
 mod helpers;
 mod path_finding;
+#[cfg(feature = "tracing")]
+mod tracing;
+mod write_coalescer;
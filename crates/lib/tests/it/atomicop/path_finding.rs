@@ -403,3 +403,85 @@ fn test_complex_path_finding_scenario() {
         &["root", "branch", "unique", "extended", "merge"],
     );
 }
+
+#[test]
+fn test_atomicop_long_branches_merge() {
+    // Branches long enough to be merged in parallel with the `parallel` feature
+    const LENGTH: usize = 80;
+    let (db, tree) = setup_db_and_tree_with_key("test_key");
+
+    let op_base = tree.new_operation().unwrap();
+    op_base
+        .get_subtree::<Dict>("data")
+        .unwrap()
+        .set("base", "initial")
+        .unwrap();
+    let base_id = op_base.commit().unwrap();
+
+    let mut heads = Vec::new();
+    for branch in ["left", "right"] {
+        let mut tip = base_id.clone();
+        for i in 0..LENGTH {
+            let op = tree.new_operation_with_tips([tip]).unwrap();
+            let store = op.get_subtree::<Dict>("data").unwrap();
+            store.set(format!("{branch}_{i}"), "set").unwrap();
+            store
+                .set(format!("{branch}_counter"), i.to_string())
+                .unwrap();
+            if i == LENGTH / 2 {
+                store.delete(format!("{branch}_0")).unwrap();
+            }
+            tip = op.commit().unwrap();
+        }
+        heads.push(tip);
+    }
+
+    let check = |state: &eidetica::crdt::Map| {
+        for branch in ["left", "right"] {
+            // Later entries on each branch win over earlier ones
+            assert_eq!(
+                state.get_text(format!("{branch}_counter")),
+                Some((LENGTH - 1).to_string().as_str())
+            );
+            assert!(state.get(format!("{branch}_0")).is_none());
+            assert!(state.get(format!("{branch}_{}", LENGTH - 1)).is_some());
+        }
+        assert_eq!(state.get_text("base"), Some("initial"));
+    };
+
+    // Merged as the tips of the tree
+    let merged = tree
+        .get_subtree_viewer::<Dict>("data")
+        .unwrap()
+        .get_all()
+        .unwrap();
+    check(&merged);
+
+    // Merged as the parents of an entry
+    let op_merge = tree.new_operation_with_tips(&heads).unwrap();
+    op_merge
+        .get_subtree::<Dict>("data")
+        .unwrap()
+        .set("merged", "yes")
+        .unwrap();
+    op_merge.commit().unwrap();
+    db.clear_state_cache();
+    tree.backend().clear_crdt_cache().unwrap();
+    let after_merge = tree
+        .get_subtree_viewer::<Dict>("data")
+        .unwrap()
+        .get_all()
+        .unwrap();
+    check(&after_merge);
+    assert_eq!(after_merge.get_text("merged"), Some("yes"));
+
+    // Recomputing from scratch gives the same state
+    db.clear_state_cache();
+    tree.backend().clear_crdt_cache().unwrap();
+    let again = tree
+        .get_subtree_viewer::<Dict>("data")
+        .unwrap()
+        .get_all()
+        .unwrap();
+    assert_eq!(again, after_merge);
+}
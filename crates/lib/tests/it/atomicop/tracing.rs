@@ -0,0 +1,160 @@
+//! Tests for the spans traced with the `tracing` feature
+
+use eidetica::backend::database::{InMemory, Instrumented};
+use eidetica::basedb::BaseDB;
+use eidetica::subtree::Dict;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+
+use tracing::{Event, Metadata, Subscriber};
+use tracing_core::span::Current;
+
+/// A span as seen by the subscriber: its name and fields.
+#[derive(Debug, Clone)]
+struct SpanRecord {
+    metadata: &'static Metadata<'static>,
+    fields: HashMap<String, String>,
+}
+
+impl Visit for SpanRecord {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.fields
+            .insert(field.name().to_string(), format!("{value:?}"));
+    }
+}
+
+/// Records every span created on the thread it is the default for.
+#[derive(Default)]
+struct SpanCollector {
+    spans: Arc<Mutex<HashMap<u64, SpanRecord>>>,
+    /// Spans entered and not yet exited, innermost last
+    entered: Mutex<Vec<u64>>,
+    next_id: AtomicU64,
+}
+
+impl Subscriber for SpanCollector {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, attributes: &Attributes<'_>) -> Id {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let mut span = SpanRecord {
+            metadata: attributes.metadata(),
+            fields: HashMap::new(),
+        };
+        attributes.record(&mut span);
+        self.spans.lock().unwrap().insert(id, span);
+        Id::from_u64(id)
+    }
+
+    fn record(&self, id: &Id, values: &Record<'_>) {
+        if let Some(span) = self.spans.lock().unwrap().get_mut(&id.into_u64()) {
+            values.record(span);
+        }
+    }
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+    fn event(&self, _: &Event<'_>) {}
+
+    fn enter(&self, id: &Id) {
+        self.entered.lock().unwrap().push(id.into_u64());
+    }
+
+    fn exit(&self, _: &Id) {
+        self.entered.lock().unwrap().pop();
+    }
+
+    fn current_span(&self) -> Current {
+        let entered = self.entered.lock().unwrap();
+        let spans = self.spans.lock().unwrap();
+        match entered.last() {
+            Some(id) => {
+                let metadata = spans[id].metadata;
+                Current::new(Id::from_u64(*id), metadata)
+            }
+            None => Current::none(),
+        }
+    }
+}
+
+/// Runs `f` with a collector as the default subscriber and returns the spans it created.
+fn collect_spans(f: impl FnOnce()) -> Vec<SpanRecord> {
+    let collector = SpanCollector::default();
+    let spans = Arc::clone(&collector.spans);
+    tracing::subscriber::with_default(collector, f);
+    let spans = spans.lock().unwrap();
+    let mut ids: Vec<_> = spans.keys().copied().collect();
+    ids.sort();
+    ids.into_iter().map(|id| spans[&id].clone()).collect()
+}
+
+fn named<'a>(spans: &'a [SpanRecord], name: &str) -> Vec<&'a SpanRecord> {
+    spans
+        .iter()
+        .filter(|span| span.metadata.name() == name)
+        .collect()
+}
+
+#[test]
+fn test_commit_pipeline_spans() {
+    let db = BaseDB::new(Box::new(Instrumented::new(InMemory::new())));
+    db.add_private_key("key").unwrap();
+    let tree = db.new_tree_default("key").unwrap();
+    let root = tree.root_id().to_string();
+
+    let mut entry = None;
+    let spans = collect_spans(|| {
+        let op = tree.new_operation().unwrap();
+        op.get_subtree::<Dict>("notes")
+            .unwrap()
+            .set("a", "1")
+            .unwrap();
+        entry = Some(op.commit().unwrap());
+    });
+    let entry = entry.unwrap().to_string();
+
+    let commits = named(&spans, "commit");
+    assert_eq!(commits.len(), 1);
+    assert_eq!(commits[0].fields["tree"], root);
+    assert_eq!(commits[0].fields["entry"], entry);
+
+    let validations = named(&spans, "validate_entry");
+    assert!(validations.iter().any(|span| span.fields["entry"] == entry));
+
+    let states = named(&spans, "get_full_state");
+    assert!(
+        states
+            .iter()
+            .any(|span| span.fields["subtree"] == "\"_settings\"")
+    );
+    assert!(states.iter().all(|span| span.fields["tree"] == root));
+
+    let puts = named(&spans, "put");
+    assert!(puts.iter().any(|span| span.fields["entry"] == entry));
+    assert!(!named(&spans, "get_subtree_tips").is_empty());
+}
+
+#[test]
+fn test_state_computation_spans_name_entries() {
+    let db = BaseDB::new(Box::new(InMemory::new()));
+    db.add_private_key("key").unwrap();
+    let tree = db.new_tree_default("key").unwrap();
+    let op = tree.new_operation().unwrap();
+    op.get_subtree::<Dict>("notes")
+        .unwrap()
+        .set("a", "1")
+        .unwrap();
+    let entry = op.commit().unwrap().to_string();
+
+    let spans = collect_spans(|| {
+        let viewer = tree.get_subtree_viewer::<Dict>("notes").unwrap();
+        viewer.get("a").unwrap();
+    });
+    let computed = named(&spans, "compute_single_entry_state_recursive");
+    assert!(computed.iter().any(|span| span.fields["entry"] == entry));
+}
@@ -0,0 +1,139 @@
+//! Write coalescer tests
+//!
+//! Tests for batching writes with `Tree::write_coalescer`: only the last value
+//! of each key is committed, subtrees are committed on their own interval and
+//! size thresholds, due subtrees share an entry, and writes survive failed
+//! commits.
+
+use crate::helpers::*;
+use eidetica::atomicop::CoalescePolicy;
+use eidetica::basedb::TreeQuota;
+use eidetica::subtree::Dict;
+use std::time::Duration;
+
+const LONG_INTERVAL: Duration = Duration::from_secs(3600);
+
+#[test]
+fn test_coalescer_keeps_last_value() {
+    let tree = setup_tree();
+    let tips_before = tree.get_tips().unwrap();
+    let coalescer = tree.write_coalescer(CoalescePolicy::new(LONG_INTERVAL));
+
+    for x in 0..50 {
+        let id = coalescer.set("cursors", "alice", x.to_string()).unwrap();
+        assert!(id.is_none());
+    }
+    coalescer.set("cursors", "bob", "7").unwrap();
+    coalescer.set("cursors", "carol", "3").unwrap();
+    coalescer.delete("cursors", "carol").unwrap();
+    assert_eq!(coalescer.pending_keys("cursors"), 3);
+    assert_eq!(tree.get_tips().unwrap(), tips_before);
+
+    let id = coalescer
+        .flush()
+        .unwrap()
+        .expect("writes should be committed");
+    assert_eq!(coalescer.pending_keys("cursors"), 0);
+    assert!(coalescer.flush().unwrap().is_none());
+    assert_eq!(tree.get_tips().unwrap(), vec![id.clone()]);
+    assert_eq!(
+        tree.backend().get(&id).unwrap().parents().unwrap(),
+        tips_before
+    );
+
+    let cursors = tree.get_subtree_viewer::<Dict>("cursors").unwrap();
+    assert_eq!(cursors.get_string("alice").unwrap(), "49");
+    assert_eq!(cursors.get_string("bob").unwrap(), "7");
+    assert!(cursors.get("carol").is_err());
+}
+
+#[test]
+fn test_coalescer_subtree_policies() {
+    let tree = setup_tree();
+    let coalescer = tree
+        .write_coalescer(CoalescePolicy::new(LONG_INTERVAL))
+        .subtree_policy("telemetry", CoalescePolicy::new(Duration::ZERO))
+        .subtree_policy("samples", CoalescePolicy::new(LONG_INTERVAL).max_pending(2));
+    assert_eq!(
+        coalescer.policy("cursors"),
+        CoalescePolicy::new(LONG_INTERVAL)
+    );
+
+    // Cursors wait for their interval, which has not passed
+    coalescer.set("cursors", "alice", "1").unwrap();
+
+    // Samples are committed once two keys are staged
+    assert!(coalescer.set("samples", "a", "1").unwrap().is_none());
+    assert!(coalescer.set("samples", "a", "2").unwrap().is_none());
+    let id = coalescer.set("samples", "b", "1").unwrap().unwrap();
+    let entry = tree.backend().get(&id).unwrap();
+    assert!(entry.in_subtree("samples"));
+    assert!(!entry.in_subtree("cursors"));
+    assert_eq!(coalescer.pending_keys("cursors"), 1);
+
+    // Telemetry is due as soon as it is written
+    let id = coalescer.set("telemetry", "cpu", "12").unwrap().unwrap();
+    assert!(tree.backend().get(&id).unwrap().in_subtree("telemetry"));
+    assert!(coalescer.commit_due().unwrap().is_none());
+    assert_eq!(coalescer.pending_keys("cursors"), 1);
+
+    // Dropping the coalescer commits the rest
+    drop(coalescer);
+    let cursors = tree.get_subtree_viewer::<Dict>("cursors").unwrap();
+    assert_eq!(cursors.get_string("alice").unwrap(), "1");
+}
+
+#[test]
+fn test_coalescer_commits_due_subtrees_together() {
+    let tree = setup_tree();
+    let coalescer = tree.write_coalescer(CoalescePolicy::new(Duration::from_millis(50)));
+
+    coalescer.set("cursors", "alice", "1").unwrap();
+    coalescer.set("presence", "alice", "online").unwrap();
+    assert!(coalescer.commit_due().unwrap().is_none());
+    std::thread::sleep(Duration::from_millis(60));
+
+    let id = coalescer
+        .commit_due()
+        .unwrap()
+        .expect("both subtrees are due");
+    let entry = tree.backend().get(&id).unwrap();
+    assert!(entry.in_subtree("cursors"));
+    assert!(entry.in_subtree("presence"));
+    assert_eq!(coalescer.pending_keys("cursors"), 0);
+    assert_eq!(coalescer.pending_keys("presence"), 0);
+}
+
+#[test]
+fn test_coalescer_keeps_writes_when_commit_fails() {
+    let tree = setup_tree();
+    tree.set_quota(TreeQuota {
+        max_entry_size: Some(1000),
+        ..TreeQuota::default()
+    })
+    .unwrap();
+    let coalescer = tree.write_coalescer(CoalescePolicy::new(LONG_INTERVAL).max_pending(3));
+    let tips_before = tree.get_tips().unwrap();
+
+    // The entry breaks the quota, so both the flush and a size-triggered
+    // commit fail without dropping anything
+    coalescer.set("cursors", "alice", "x".repeat(2000)).unwrap();
+    coalescer.set("cursors", "bob", "7").unwrap();
+    assert!(coalescer.flush().is_err());
+    assert_eq!(coalescer.pending_keys("cursors"), 2);
+    assert!(coalescer.set("cursors", "carol", "3").is_err());
+    assert_eq!(coalescer.pending_keys("cursors"), 3);
+    assert_eq!(tree.get_tips().unwrap(), tips_before);
+
+    tree.set_quota(TreeQuota::default()).unwrap();
+    coalescer
+        .flush()
+        .unwrap()
+        .expect("writes should be committed");
+    assert_eq!(coalescer.pending_keys("cursors"), 0);
+
+    let cursors = tree.get_subtree_viewer::<Dict>("cursors").unwrap();
+    assert_eq!(cursors.get_string("alice").unwrap(), "x".repeat(2000));
+    assert_eq!(cursors.get_string("bob").unwrap(), "7");
+    assert_eq!(cursors.get_string("carol").unwrap(), "3");
+}
@@ -17,6 +17,7 @@ use eidetica::basedb::BaseDB;
 use eidetica::crdt::Map;
 use eidetica::entry::ID;
 use eidetica::subtree::Dict;
+use eidetica::tree::Tree;
 
 /// Test simple tree creation with auth
 #[test]
@@ -852,3 +853,110 @@ fn test_complex_nested_delegation_chain() -> Result<()> {
 
     Ok(())
 }
+
+/// Creates a user tree holding `alice` and two application trees that trust it
+/// through a delegation named "users".
+fn setup_central_users(bounds: PermissionBounds) -> Result<(BaseDB, Tree, Vec<Tree>)> {
+    let db = setup_db();
+    let users = create_delegated_tree(
+        &db,
+        &[
+            ("user_admin", Permission::Admin(0), KeyStatus::Active),
+            ("alice", Permission::Write(5), KeyStatus::Active),
+        ],
+        "user_admin",
+    )?;
+
+    db.add_private_key("app_admin")?;
+    let mut apps = Vec::new();
+    for _ in 0..2 {
+        let app = db.new_tree_default("app_admin")?;
+        app.add_delegation("users", users.root_id(), bounds.clone())?;
+        apps.push(app);
+    }
+    Ok((db, users, apps))
+}
+
+fn commit_as_alice(tree: &Tree, value: &str) -> Result<ID> {
+    let op = tree.new_delegated_operation("users", "alice")?;
+    op.get_subtree::<Dict>("data")?.set("value", value)?;
+    op.commit()
+}
+
+/// Test that keys managed in one tree can sign in every tree delegating to it
+#[test]
+fn test_central_user_tree() -> Result<()> {
+    let bounds = PermissionBounds {
+        max: Permission::Write(10),
+        min: None,
+    };
+    let (_db, users, apps) = setup_central_users(bounds)?;
+
+    for app in &apps {
+        let id = commit_as_alice(app, "hello")?;
+        let entry = app.get_entry(&id)?;
+        assert!(matches!(entry.sig.key, SigKey::DelegationPath(_)));
+        assert!(app.verify_entry_signature(&id)?);
+    }
+
+    // Revoking the key centrally takes effect in every tree
+    users.revoke_key("alice")?;
+    for app in &apps {
+        assert!(commit_as_alice(app, "revoked").is_err());
+    }
+    Ok(())
+}
+
+/// Test that delegated keys are clamped to the delegation's bounds when committing
+#[test]
+fn test_delegated_commit_respects_bounds() -> Result<()> {
+    let bounds = PermissionBounds {
+        max: Permission::Read,
+        min: None,
+    };
+    let (_db, _users, apps) = setup_central_users(bounds)?;
+    assert!(commit_as_alice(&apps[0], "read only").is_err());
+    Ok(())
+}
+
+/// Test the errors of adding and using delegations by name
+#[test]
+fn test_delegation_names() -> Result<()> {
+    let bounds = PermissionBounds {
+        max: Permission::Write(10),
+        min: None,
+    };
+    let (_db, users, apps) = setup_central_users(bounds.clone())?;
+
+    let err = apps[0]
+        .add_delegation("users", users.root_id(), bounds.clone())
+        .unwrap_err();
+    assert!(err.is_conflict());
+    let err = apps[0]
+        .add_delegation("missing_tree", &ID::from("missing"), bounds)
+        .unwrap_err();
+    assert!(err.is_not_found());
+
+    match apps[0].new_delegated_operation("unknown", "alice") {
+        Err(err) => assert!(err.is_not_found()),
+        Ok(_) => panic!("Created an operation through an unknown delegation"),
+    }
+    Ok(())
+}
+
+/// Test that a delegation path cannot claim tips from a different tree
+#[test]
+fn test_delegated_tips_from_other_tree() -> Result<()> {
+    let bounds = PermissionBounds {
+        max: Permission::Write(10),
+        min: None,
+    };
+    let (db, _users, apps) = setup_central_users(bounds)?;
+
+    let mut validator = AuthValidator::new();
+    let settings = apps[0].get_settings()?.get_all()?;
+    let sig_key = create_delegation_path(&[("users", Some(apps[1].get_tips()?)), ("alice", None)]);
+    let result = validator.resolve_sig_key(&sig_key, &settings, Some(db.backend()));
+    assert!(result.is_err());
+    Ok(())
+}
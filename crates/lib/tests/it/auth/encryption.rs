@@ -0,0 +1,195 @@
+//! Tests for values encrypted to keys of a tree
+//!
+//! Covers `Dict::set_encrypted` and `Dict::get_encrypted`: reading sealed values
+//! with the private keys of their recipients only, the stored form, merging
+//! concurrent encrypted writes, and rejecting tampered values and recipients
+//! that are not active keys of the tree.
+
+use super::helpers::*;
+use eidetica::auth::types::{KeyStatus, Permission};
+use eidetica::basedb::BaseDB;
+use eidetica::crdt::Map;
+use eidetica::crdt::map::Value;
+use eidetica::subtree::Dict;
+use eidetica::tree::Tree;
+
+const ADMIN: &str = "admin";
+const ALICE: &str = "alice";
+const BOB: &str = "bob";
+const EVE: &str = "eve";
+const REVOKED: &str = "revoked";
+
+/// Creates a tree in which every key's private key is in the same database.
+fn setup() -> (BaseDB, Tree) {
+    let keys = [
+        (ADMIN, Permission::Admin(0), KeyStatus::Active),
+        (ALICE, Permission::Write(10), KeyStatus::Active),
+        (BOB, Permission::Write(10), KeyStatus::Active),
+        (EVE, Permission::Write(10), KeyStatus::Active),
+        (REVOKED, Permission::Write(10), KeyStatus::Revoked),
+    ];
+    let (db, public_keys) = setup_test_db_with_keys(&keys);
+    let mut tree = setup_authenticated_tree(&db, &keys, &public_keys);
+    tree.set_default_auth_key(ADMIN);
+    (db, tree)
+}
+
+fn write_secret(tree: &Tree, value: impl Into<Value>, recipients: &[&str]) {
+    let op = tree.new_operation().unwrap();
+    op.get_subtree::<Dict>("secrets")
+        .unwrap()
+        .set_encrypted("secret", value, recipients)
+        .unwrap();
+    op.commit().unwrap();
+}
+
+fn auth_error(result: eidetica::Result<Value>) -> eidetica::auth::AuthError {
+    match result {
+        Err(eidetica::Error::Auth(err)) => err,
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn test_encrypted_round_trip() {
+    let (_db, tree) = setup();
+    let mut value = Map::new();
+    value.set_string("password", "hunter2");
+    value.set("pin", 1234);
+    write_secret(&tree, value.clone(), &[ALICE, BOB]);
+
+    let secrets = tree.get_subtree_viewer::<Dict>("secrets").unwrap();
+    for key_name in [ALICE, BOB] {
+        assert_eq!(
+            secrets.get_encrypted("secret", key_name).unwrap(),
+            Value::Map(value.clone())
+        );
+    }
+
+    // Other keys of the tree cannot read it
+    let err = auth_error(secrets.get_encrypted("secret", EVE));
+    assert!(err.is_decryption_error());
+    assert!(matches!(
+        err,
+        eidetica::auth::AuthError::NotARecipient { .. }
+    ));
+}
+
+#[test]
+fn test_stored_value_is_ciphertext() {
+    let (_db, tree) = setup();
+    write_secret(&tree, "hunter2", &[ALICE]);
+
+    let secrets = tree.get_subtree_viewer::<Dict>("secrets").unwrap();
+    let stored = secrets.get_string("secret").unwrap();
+    assert!(!stored.contains("hunter2"));
+    assert!(stored.contains(ALICE));
+
+    // Plain values are not mistaken for encrypted ones
+    let op = tree.new_operation().unwrap();
+    op.get_subtree::<Dict>("secrets")
+        .unwrap()
+        .set("plain", "hunter2")
+        .unwrap();
+    op.commit().unwrap();
+    let secrets = tree.get_subtree_viewer::<Dict>("secrets").unwrap();
+    match secrets.get_encrypted("plain", ALICE) {
+        Err(eidetica::Error::Subtree(err)) => assert!(err.is_type_error()),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn test_concurrent_encrypted_writes_merge() {
+    let (_db, tree) = setup();
+    write_secret(&tree, "first", &[ALICE]);
+    let tips = tree.get_tips().unwrap();
+
+    for (value, recipient) in [("left", ALICE), ("right", BOB)] {
+        let op = tree.new_operation_with_tips(&tips).unwrap();
+        op.get_subtree::<Dict>("secrets")
+            .unwrap()
+            .set_encrypted("secret", value, &[recipient, ADMIN])
+            .unwrap();
+        op.commit().unwrap();
+    }
+    assert_eq!(tree.get_tips().unwrap().len(), 2);
+
+    // One of the writes wins whole, and stays readable by its recipients
+    let secrets = tree.get_subtree_viewer::<Dict>("secrets").unwrap();
+    let merged = secrets.get_encrypted("secret", ADMIN).unwrap();
+    let winner = if merged == Value::Text("left".to_string()) {
+        ALICE
+    } else {
+        assert_eq!(merged, Value::Text("right".to_string()));
+        BOB
+    };
+    assert_eq!(secrets.get_encrypted("secret", winner).unwrap(), merged);
+
+    // A write on top of the merge replaces it
+    write_secret(&tree, "final", &[EVE]);
+    let secrets = tree.get_subtree_viewer::<Dict>("secrets").unwrap();
+    assert_eq!(
+        secrets.get_encrypted("secret", EVE).unwrap(),
+        Value::Text("final".to_string())
+    );
+}
+
+#[test]
+fn test_tampered_value_is_rejected() {
+    let (_db, tree) = setup();
+    write_secret(&tree, "hunter2", &[ALICE]);
+
+    let secrets = tree.get_subtree_viewer::<Dict>("secrets").unwrap();
+    let stored = secrets.get_string("secret").unwrap();
+    let mut json: serde_json::Value = serde_json::from_str(&stored).unwrap();
+    let ciphertext = json["ciphertext"].as_str().unwrap();
+    let tampered = if ciphertext.starts_with('A') {
+        "B"
+    } else {
+        "A"
+    };
+    json["ciphertext"] = format!("{tampered}{}", &ciphertext[1..]).into();
+
+    let op = tree.new_operation().unwrap();
+    let dict = op.get_subtree::<Dict>("secrets").unwrap();
+    dict.set("secret", json.to_string()).unwrap();
+    let err = auth_error(dict.get_encrypted("secret", ALICE));
+    assert!(matches!(
+        err,
+        eidetica::auth::AuthError::DecryptionFailed { .. }
+    ));
+
+    // Values are bound to their subtree
+    let other = op.get_subtree::<Dict>("elsewhere").unwrap();
+    other.set("secret", stored.clone()).unwrap();
+    assert!(auth_error(other.get_encrypted("secret", ALICE)).is_decryption_error());
+
+    // But not to their key
+    dict.set("moved", stored).unwrap();
+    assert_eq!(
+        dict.get_encrypted("moved", ALICE).unwrap(),
+        Value::Text("hunter2".to_string())
+    );
+}
+
+#[test]
+fn test_recipients_must_be_active_keys() {
+    let (_db, tree) = setup();
+    let op = tree.new_operation().unwrap();
+    let secrets = op.get_subtree::<Dict>("secrets").unwrap();
+
+    match secrets.set_encrypted("secret", "value", &["unknown"]) {
+        Err(eidetica::Error::Auth(err)) => assert!(err.is_key_not_found()),
+        other => panic!("unexpected result: {other:?}"),
+    }
+    match secrets.set_encrypted("secret", "value", &[ALICE, REVOKED]) {
+        Err(eidetica::Error::Auth(err)) => assert!(err.is_permission_denied()),
+        other => panic!("unexpected result: {other:?}"),
+    }
+    match secrets.set_encrypted::<&str>("secret", "value", &[]) {
+        Err(eidetica::Error::Subtree(err)) => assert!(err.is_operation_error()),
+        other => panic!("unexpected result: {other:?}"),
+    }
+    assert!(secrets.get("secret").is_err());
+}
@@ -0,0 +1,125 @@
+//! Tests for frozen subtrees
+//!
+//! Covers `Tree::freeze_subtree`, rejection of writes to frozen subtrees and of
+//! changes to their freeze records, both on commit and when validating entries
+//! received from elsewhere, and writes made concurrently with a freeze.
+
+use super::helpers::*;
+use eidetica::auth::crypto::sign_entry;
+use eidetica::auth::types::{SigInfo, SigKey};
+use eidetica::crdt::map::Value;
+use eidetica::entry::{Entry, ID};
+use eidetica::subtree::Dict;
+use eidetica::tree::Tree;
+
+fn vote(tree: &Tree, key_name: &str, subtree: &str, choice: &str) -> eidetica::Result<ID> {
+    let op = tree.new_authenticated_operation(key_name)?;
+    op.get_subtree::<Dict>(subtree)?.set("choice", choice)?;
+    op.commit()
+}
+
+#[test]
+fn test_freeze_rejects_writes() {
+    let (_db, tree) = setup_admin_writer_tree();
+    vote(&tree, WRITER, "ballot", "yes").unwrap();
+    assert!(!tree.is_frozen("ballot").unwrap());
+
+    tree.freeze_subtree("ballot").unwrap();
+    assert!(tree.is_frozen("ballot").unwrap());
+    assert_eq!(tree.frozen_subtrees().unwrap(), vec!["ballot".to_string()]);
+
+    // Nobody can write to it any more, admins included
+    for key_name in [WRITER, ADMIN] {
+        let err = vote(&tree, key_name, "ballot", "no").unwrap_err();
+        match err {
+            eidetica::Error::AtomicOp(err) => {
+                assert!(err.is_frozen());
+                assert_eq!(err.subtree_name(), Some("ballot"));
+            }
+            other => panic!("unexpected error: {other:?}"),
+        }
+    }
+    let ballot = tree.get_subtree_viewer::<Dict>("ballot").unwrap();
+    assert_eq!(ballot.get_string("choice").unwrap(), "yes");
+
+    // Other subtrees are unaffected
+    vote(&tree, WRITER, "other", "no").unwrap();
+}
+
+#[test]
+fn test_freeze_requires_admin() {
+    let (_db, mut tree) = setup_admin_writer_tree();
+    tree.set_default_auth_key(WRITER);
+    assert!(tree.freeze_subtree("ballot").is_err());
+    assert!(!tree.is_frozen("ballot").unwrap());
+}
+
+#[test]
+fn test_freeze_record_is_permanent() {
+    let (_db, tree) = setup_admin_writer_tree();
+    tree.freeze_subtree("ballot").unwrap();
+
+    // Freezing again, or removing the record, changes it
+    assert!(tree.freeze_subtree("ballot").is_err());
+    let op = tree.new_operation().unwrap();
+    op.get_subtree::<Dict>("_settings")
+        .unwrap()
+        .set_at_path(["frozen", "ballot"], Value::Deleted)
+        .unwrap();
+    assert!(op.commit().is_err());
+    assert!(tree.is_frozen("ballot").unwrap());
+
+    // Other subtrees can still be frozen
+    tree.freeze_subtree("release").unwrap();
+    assert_eq!(
+        tree.frozen_subtrees().unwrap(),
+        vec!["ballot".to_string(), "release".to_string()]
+    );
+}
+
+#[test]
+fn test_internal_subtrees_cannot_be_frozen() {
+    let (_db, tree) = setup_admin_writer_tree();
+    assert!(tree.freeze_subtree("_settings").is_err());
+    assert!(tree.frozen_subtrees().unwrap().is_empty());
+}
+
+#[test]
+fn test_received_writes_to_frozen_subtree_fail_validation() {
+    let (_db, tree) = setup_admin_writer_tree();
+    let freeze = tree.freeze_subtree("ballot").unwrap();
+
+    // An entry built on top of the freeze, as could be received through sync
+    let signing_key = tree.backend().get_private_key(WRITER).unwrap().unwrap();
+    let mut forged = Entry::builder(tree.root_id().clone())
+        .add_parent(freeze)
+        .set_subtree_data("ballot", r#"{"children":{"choice":{"Text":"no"}}}"#)
+        .set_sig(SigInfo {
+            key: SigKey::Direct(WRITER.to_string()),
+            sig: None,
+        })
+        .build();
+    forged.sig.sig = Some(sign_entry(&forged, &signing_key).unwrap());
+    let forged_id = tree.insert_raw(forged).unwrap();
+    assert!(!tree.verify_entry_signature(&forged_id).unwrap());
+}
+
+#[test]
+fn test_concurrent_writes_are_kept() {
+    let (_db, tree) = setup_admin_writer_tree();
+    let before = vote(&tree, WRITER, "ballot", "yes").unwrap();
+    tree.freeze_subtree("ballot").unwrap();
+
+    // A replica that had not seen the freeze keeps voting
+    let op = tree.new_operation_with_tips([before]).unwrap();
+    op.get_subtree::<Dict>("ballot")
+        .unwrap()
+        .set("late", "no")
+        .unwrap();
+    let concurrent = op.commit().unwrap();
+    assert!(tree.verify_entry_signature(&concurrent).unwrap());
+
+    let ballot = tree.get_subtree_viewer::<Dict>("ballot").unwrap();
+    assert_eq!(ballot.get_string("late").unwrap(), "no");
+    assert!(vote(&tree, WRITER, "ballot", "no").is_err());
+}
@@ -102,6 +102,33 @@ pub fn setup_authenticated_tree(
         .expect("Failed to create tree")
 }
 
+/// Admin key of the trees created by `setup_admin_writer_tree`
+pub const ADMIN: &str = "admin";
+/// Write key of the trees created by `setup_admin_writer_tree`
+pub const WRITER: &str = "writer";
+
+/// Create a tree administered by `ADMIN`, its default key, in which `WRITER` can write
+pub fn setup_admin_writer_tree() -> (BaseDB, Tree) {
+    setup_admin_tree(true)
+}
+
+/// Create a tree administered by `ADMIN`, with `WRITER` known to the database but not to the tree
+pub fn setup_admin_tree_without_writer() -> (BaseDB, Tree) {
+    setup_admin_tree(false)
+}
+
+fn setup_admin_tree(with_writer: bool) -> (BaseDB, Tree) {
+    let keys = [
+        (ADMIN, Permission::Admin(0), KeyStatus::Active),
+        (WRITER, Permission::Write(10), KeyStatus::Active),
+    ];
+    let (db, public_keys) = setup_test_db_with_keys(&keys);
+    let listed = if with_writer { keys.len() } else { 1 };
+    let mut tree = setup_authenticated_tree(&db, &keys[..listed], &public_keys[..listed]);
+    tree.set_default_auth_key(ADMIN);
+    (db, tree)
+}
+
 // ===== DELEGATION HELPERS =====
 
 /// Create a complete authentication environment with multiple keys and permission levels
@@ -0,0 +1,219 @@
+//! Tests for signed tree invitations
+//!
+//! Covers minting invitations with `Tree::create_invite`, redeeming them with
+//! `BaseDB::redeem_invite` on another database, validation of the redemption
+//! entry on the inviting side, and invitations that are expired, used,
+//! revoked, tampered with or issued without the right permission.
+
+use super::helpers::*;
+use eidetica::auth::crypto::{format_public_key, generate_keypair, sign_entry};
+use eidetica::auth::types::{AuthKey, KeyStatus, Permission, SigInfo, SigKey};
+use eidetica::auth::{AuthValidator, Invitation};
+use eidetica::backend::VerificationStatus;
+use eidetica::backend::database::InMemory;
+use eidetica::basedb::BaseDB;
+use eidetica::constants::SETTINGS;
+use eidetica::crdt::Map;
+use eidetica::entry::Entry;
+use eidetica::subtree::Dict;
+use eidetica::tree::Tree;
+use std::time::Duration;
+
+const GUEST: &str = "guest";
+
+/// Creates the invitee's database, holding the `GUEST` private key.
+fn guest_db() -> BaseDB {
+    let db = BaseDB::new(Box::new(InMemory::new()));
+    db.add_private_key(GUEST).unwrap();
+    db
+}
+
+/// Copies the entries of `tree` into `db`, standing in for a sync.
+fn transfer(tree: &Tree, db: &BaseDB) -> Tree {
+    let mut bundle = Vec::new();
+    tree.export_bundle(&mut bundle).unwrap();
+    db.import_bundle(bundle.as_slice()).unwrap()
+}
+
+fn auth_error(result: eidetica::Result<Tree>) -> eidetica::auth::AuthError {
+    match result {
+        Err(eidetica::Error::Auth(err)) => err,
+        Err(other) => panic!("unexpected error: {other:?}"),
+        Ok(_) => panic!("the invitation was redeemed"),
+    }
+}
+
+#[test]
+fn test_redeem_invite() {
+    let (db, tree) = setup_admin_writer_tree();
+    let invite = tree
+        .create_invite(Permission::Write(10), Some(Duration::from_secs(3600)))
+        .unwrap();
+    assert_eq!(invite.tree(), tree.root_id());
+    assert_eq!(invite.permission(), &Permission::Write(10));
+    assert_eq!(invite.issuer(), ADMIN);
+    assert!(invite.expires_at().is_some());
+
+    // The invitation travels as a string
+    let token = invite.to_token();
+    let received = Invitation::from_token(&token).unwrap();
+    assert_eq!(received, invite);
+
+    let guest = guest_db();
+    transfer(&tree, &guest);
+    let guest_tree = guest.redeem_invite(&received, GUEST).unwrap();
+    assert_eq!(guest_tree.default_auth_key(), Some(GUEST));
+
+    let op = guest_tree.new_operation().unwrap();
+    op.get_subtree::<Dict>("notes")
+        .unwrap()
+        .set("hello", "from guest")
+        .unwrap();
+    let written = op.commit().unwrap();
+
+    // The inviting side accepts the redemption and the entries signed by the new key
+    let tree = transfer(&guest_tree, &db);
+    let auth = tree.auth_state_at(&written).unwrap();
+    let key = auth.get_key(GUEST).unwrap().unwrap();
+    assert_eq!(key.permissions, Permission::Write(10));
+    for id in tree.get_tips().unwrap() {
+        assert!(tree.verify_entry_signature(&id).unwrap());
+        assert_eq!(
+            tree.backend().get_verification_status(&id).unwrap(),
+            VerificationStatus::Verified
+        );
+    }
+    let notes = tree.get_subtree_viewer::<Dict>("notes").unwrap();
+    assert_eq!(notes.get_string("hello").unwrap(), "from guest");
+}
+
+#[test]
+fn test_invite_is_single_use() {
+    let (_db, tree) = setup_admin_writer_tree();
+    let invite = tree.create_invite(Permission::Read, None).unwrap();
+
+    let guest = guest_db();
+    guest.add_private_key("second").unwrap();
+    transfer(&tree, &guest);
+    guest.redeem_invite(&invite, GUEST).unwrap();
+
+    let err = auth_error(guest.redeem_invite(&invite, "second"));
+    assert!(err.is_invitation_error());
+}
+
+#[test]
+fn test_expired_invite() {
+    let (_db, tree) = setup_admin_writer_tree();
+    let invite = tree
+        .create_invite(Permission::Write(10), Some(Duration::ZERO))
+        .unwrap();
+    std::thread::sleep(Duration::from_millis(5));
+
+    let guest = guest_db();
+    transfer(&tree, &guest);
+    let err = auth_error(guest.redeem_invite(&invite, GUEST));
+    assert!(matches!(
+        err,
+        eidetica::auth::AuthError::InvitationExpired { .. }
+    ));
+}
+
+#[test]
+fn test_revoked_invite() {
+    let (_db, tree) = setup_admin_writer_tree();
+    let invite = tree.create_invite(Permission::Write(10), None).unwrap();
+    tree.revoke_invite(&invite).unwrap();
+    assert!(tree.revoke_invite(&invite).is_err());
+
+    let guest = guest_db();
+    transfer(&tree, &guest);
+    let err = auth_error(guest.redeem_invite(&invite, GUEST));
+    assert!(err.is_invitation_error());
+}
+
+#[test]
+fn test_only_admins_invite() {
+    let (_db, mut tree) = setup_admin_writer_tree();
+    tree.set_default_auth_key(WRITER);
+    match tree.create_invite(Permission::Read, None) {
+        Err(eidetica::Error::Auth(err)) => assert!(err.is_permission_denied()),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn test_tampered_invite() {
+    let (_db, tree) = setup_admin_writer_tree();
+    let invite = tree.create_invite(Permission::Read, None).unwrap();
+
+    let mut json = serde_json::to_value(&invite).unwrap();
+    json["permission"] = serde_json::to_value(Permission::Admin(0)).unwrap();
+    let tampered: Invitation = serde_json::from_value(json).unwrap();
+
+    let guest = guest_db();
+    transfer(&tree, &guest);
+    let err = auth_error(guest.redeem_invite(&tampered, GUEST));
+    assert!(err.is_invitation_error());
+
+    assert!(Invitation::from_token("not a token").is_err());
+}
+
+/// Builds a redemption entry by hand, as a malicious invitee could.
+fn redemption(tree: &Tree, invite: &Invitation, permission: Permission, extra: bool) -> Entry {
+    let (signing_key, verifying_key) = generate_keypair();
+    let mut auth = Map::new();
+    auth.set_json(
+        GUEST,
+        AuthKey {
+            pubkey: format_public_key(&verifying_key),
+            permissions: permission,
+            status: KeyStatus::Active,
+        },
+    )
+    .unwrap();
+    let mut invites = Map::new();
+    invites.set_string(invite.id(), invite.to_token());
+    let mut changes = Map::new();
+    changes.set_map("auth", auth);
+    changes.set_map("invites", invites);
+
+    let mut builder = Entry::builder(tree.root_id().clone())
+        .set_parents(tree.get_tips().unwrap())
+        .set_subtree_data(SETTINGS, serde_json::to_string(&changes).unwrap());
+    if extra {
+        builder = builder.set_subtree_data("data", r#"{"children":{"k":{"Text":"v"}}}"#);
+    }
+    let mut entry = builder.build();
+    entry.sig = SigInfo {
+        key: SigKey::Direct(GUEST.to_string()),
+        sig: None,
+    };
+    entry.sig.sig = Some(sign_entry(&entry, &signing_key).unwrap());
+    entry
+}
+
+#[test]
+fn test_redemption_validation() {
+    let (_db, tree) = setup_admin_writer_tree();
+    let invite = tree.create_invite(Permission::Write(10), None).unwrap();
+    let settings = tree.get_settings().unwrap().get_all().unwrap();
+    let mut validator = AuthValidator::new();
+
+    let valid = redemption(&tree, &invite, Permission::Write(10), false);
+    assert!(validator.validate_entry(&valid, &settings, None).unwrap());
+
+    // More permission than invited, or other changes, are rejected
+    let escalated = redemption(&tree, &invite, Permission::Admin(0), false);
+    assert!(
+        !validator
+            .validate_entry(&escalated, &settings, None)
+            .unwrap()
+    );
+    let extra = redemption(&tree, &invite, Permission::Write(10), true);
+    assert!(!validator.validate_entry(&extra, &settings, None).unwrap());
+
+    // So is an entry whose signature is not by the key it adds
+    let mut forged = valid.clone();
+    forged.sig = escalated.sig.clone();
+    assert!(!validator.validate_entry(&forged, &settings, None).unwrap());
+}
@@ -0,0 +1,100 @@
+//! Tests for key rotation with `Tree::rotate_key`
+
+use super::helpers::*;
+use eidetica::auth::types::{KeyStatus, Permission, RevocationStatus, SigKey};
+use eidetica::entry::ID;
+use eidetica::subtree::Dict;
+use eidetica::tree::Tree;
+
+const NEW_ADMIN: &str = "admin_2";
+
+fn try_commit(tree: &Tree, key_name: &str, value: &str) -> eidetica::Result<ID> {
+    let op = tree.new_authenticated_operation(key_name)?;
+    op.get_subtree::<Dict>("data")?.set("value", value)?;
+    op.commit()
+}
+
+#[test]
+fn test_rotate_key() {
+    let (db, mut tree) = setup_admin_writer_tree();
+    let before = try_commit(&tree, ADMIN, "before").unwrap();
+
+    let rotation = tree.rotate_key(ADMIN, NEW_ADMIN).unwrap();
+
+    // Both changes land in the same entry, signed by the old key
+    let entry = tree.get_entry(&rotation).unwrap();
+    assert_eq!(entry.sig.key, SigKey::Direct(ADMIN.into()));
+    let auth = tree.auth_state_at(&rotation).unwrap();
+    let old_key = auth.get_key(ADMIN).unwrap().unwrap();
+    let new_key = auth.get_key(NEW_ADMIN).unwrap().unwrap();
+    assert_eq!(old_key.status, KeyStatus::Rotated);
+    assert_eq!(new_key.status, KeyStatus::Active);
+    assert_eq!(new_key.permissions, Permission::Admin(0));
+    assert_eq!(
+        Some(new_key.pubkey),
+        db.get_formatted_public_key(NEW_ADMIN).unwrap()
+    );
+
+    // The new key becomes the default and can commit; the old key cannot
+    assert_eq!(tree.default_auth_key(), Some(NEW_ADMIN));
+    try_commit(&tree, NEW_ADMIN, "after").unwrap();
+    assert!(try_commit(&tree, ADMIN, "rejected").is_err());
+
+    // Entries signed before the rotation stay valid
+    assert!(tree.verify_entry_signature(&before).unwrap());
+    assert_eq!(
+        tree.revocation_status(&before).unwrap(),
+        RevocationStatus::Historical {
+            key_name: ADMIN.to_string()
+        }
+    );
+}
+
+#[test]
+fn test_rotate_non_default_key() {
+    let (_db, mut tree) = setup_admin_writer_tree();
+    tree.rotate_key(ADMIN, NEW_ADMIN).unwrap();
+    tree.rotate_key(NEW_ADMIN, "admin_3").unwrap();
+    assert_eq!(tree.default_auth_key(), Some("admin_3"));
+
+    // Rotating a key other than the default leaves the default alone
+    tree.set_default_auth_key(WRITER);
+    assert!(tree.rotate_key("admin_3", "admin_4").is_ok());
+    assert_eq!(tree.default_auth_key(), Some(WRITER));
+}
+
+#[test]
+fn test_rotate_unknown_key() {
+    let (_db, mut tree) = setup_admin_writer_tree();
+    let err = tree.rotate_key("missing", NEW_ADMIN).unwrap_err();
+    assert!(err.is_not_found());
+}
+
+#[test]
+fn test_rotate_to_existing_name() {
+    let (db, mut tree) = setup_admin_writer_tree();
+    let tips = tree.get_tips().unwrap();
+
+    // In use in the auth settings
+    let err = tree.rotate_key(ADMIN, WRITER).unwrap_err();
+    assert!(err.is_conflict());
+
+    // In use in local key storage only
+    db.add_private_key("local_only").unwrap();
+    let err = tree.rotate_key(ADMIN, "local_only").unwrap_err();
+    assert!(err.is_conflict());
+
+    assert_eq!(tree.get_tips().unwrap(), tips);
+}
+
+#[test]
+fn test_rotate_requires_admin() {
+    let (db, mut tree) = setup_admin_writer_tree();
+    let tips = tree.get_tips().unwrap();
+
+    // A write key cannot change the auth settings, so it cannot rotate itself
+    assert!(tree.rotate_key(WRITER, "writer_2").is_err());
+    assert_eq!(tree.get_tips().unwrap(), tips);
+    assert!(db.get_public_key("writer_2").unwrap().is_none());
+    try_commit(&tree, WRITER, "still active").unwrap();
+}
@@ -1,10 +1,16 @@
 pub mod crypto;
 pub mod delegated_trees;
+pub mod encryption;
 pub mod error_handling_tests;
+pub mod freeze;
 pub mod helpers;
 pub mod integration;
+pub mod invite;
+pub mod key_rotation;
 pub mod permission_edge_cases;
+pub mod revocation;
 pub mod security_tests;
+pub mod settings_history;
 pub mod sig_key_edge_cases;
 pub mod types;
 pub mod validation;
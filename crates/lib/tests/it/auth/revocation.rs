@@ -0,0 +1,222 @@
+//! Tests for key revocation
+//!
+//! Covers `Tree::revoke_key`, the classification of entries signed by revoked
+//! keys with `Tree::revocation_status`, and re-validation of a tree after a
+//! revocation with `Tree::revalidate_revocations`.
+
+use super::helpers::*;
+use eidetica::auth::crypto::{format_public_key, sign_entry};
+use eidetica::auth::types::{KeyStatus, Permission, RevocationStatus, SigInfo, SigKey};
+use eidetica::backend::VerificationStatus;
+use eidetica::entry::{Entry, ID};
+use eidetica::subtree::Dict;
+use eidetica::tree::Tree;
+
+/// Creates the shared admin/writer tree with the data subtree already written.
+fn setup() -> Tree {
+    let (_db, tree) = setup_admin_writer_tree();
+    // Create the data subtree up front so later branches share an ancestor
+    commit_data(&tree, ADMIN, "seed");
+    tree
+}
+
+fn commit_data(tree: &Tree, key_name: &str, value: &str) -> ID {
+    let op = tree.new_authenticated_operation(key_name).unwrap();
+    op.get_subtree::<Dict>("data")
+        .unwrap()
+        .set("value", value)
+        .unwrap();
+    op.commit().unwrap()
+}
+
+fn historical() -> RevocationStatus {
+    RevocationStatus::Historical {
+        key_name: WRITER.to_string(),
+    }
+}
+
+#[test]
+fn test_revoke_key() {
+    let tree = setup();
+    let revocation = tree.revoke_key(WRITER).unwrap();
+
+    let auth = tree.auth_state_at(&revocation).unwrap();
+    assert_eq!(
+        auth.get_key(WRITER).unwrap().unwrap().status,
+        KeyStatus::Revoked
+    );
+    // Other keys are untouched
+    assert_eq!(
+        auth.get_key(ADMIN).unwrap().unwrap().status,
+        KeyStatus::Active
+    );
+
+    // The revoked key can no longer commit
+    let op = tree.new_authenticated_operation(WRITER).unwrap();
+    op.get_subtree::<Dict>("data")
+        .unwrap()
+        .set("value", "after revocation")
+        .unwrap();
+    assert!(op.commit().is_err());
+}
+
+#[test]
+fn test_revoke_unknown_key() {
+    let tree = setup();
+    let err = tree.revoke_key("missing").unwrap_err();
+    assert!(err.is_not_found());
+}
+
+#[test]
+fn test_revoke_key_requires_admin() {
+    let mut tree = setup();
+    tree.set_default_auth_key(WRITER);
+    assert!(tree.revoke_key(ADMIN).is_err());
+}
+
+#[test]
+fn test_entries_before_revocation_are_historical() {
+    let tree = setup();
+    let written = commit_data(&tree, WRITER, "from writer");
+    let by_admin = commit_data(&tree, ADMIN, "from admin");
+    tree.revoke_key(WRITER).unwrap();
+
+    assert_eq!(tree.revocation_status(&written).unwrap(), historical());
+    assert_eq!(
+        tree.revocation_status(&by_admin).unwrap(),
+        RevocationStatus::Unaffected
+    );
+    assert!(tree.verify_entry_signature(&written).unwrap());
+
+    // Re-validation reports the entry but keeps it valid
+    let affected = tree.revalidate_revocations().unwrap();
+    assert_eq!(affected, vec![(written.clone(), historical())]);
+    assert_eq!(
+        tree.backend().get_verification_status(&written).unwrap(),
+        VerificationStatus::Verified
+    );
+}
+
+#[test]
+fn test_concurrent_entries_are_preserved() {
+    let tree = setup();
+    let fork = tree.get_tips().unwrap();
+
+    // The revocation and a write by the revoked key happen on separate branches
+    tree.revoke_key(WRITER).unwrap();
+    let op = tree
+        .new_operation_with_tips(&fork)
+        .unwrap()
+        .with_auth(WRITER);
+    op.get_subtree::<Dict>("data")
+        .unwrap()
+        .set("value", "concurrent")
+        .unwrap();
+    let concurrent = op.commit().unwrap();
+
+    let status = tree.revocation_status(&concurrent).unwrap();
+    assert_eq!(
+        status,
+        RevocationStatus::Concurrent {
+            key_name: WRITER.to_string()
+        }
+    );
+    assert!(status.is_valid());
+
+    // A merge by a valid key builds on the concurrent entry and keeps its content
+    let merge = commit_data(&tree, ADMIN, "merged");
+    assert_eq!(
+        tree.revocation_status(&merge).unwrap(),
+        RevocationStatus::Unaffected
+    );
+    let affected = tree.revalidate_revocations().unwrap();
+    assert_eq!(affected.len(), 1);
+    assert_eq!(
+        tree.backend().get_verification_status(&concurrent).unwrap(),
+        VerificationStatus::Verified
+    );
+}
+
+#[test]
+fn test_revalidation_marks_entries_after_revocation() {
+    let tree = setup();
+    let revocation = tree.revoke_key(WRITER).unwrap();
+
+    // An entry signed by the revoked key on top of the revocation, as could be
+    // received through sync
+    let signing_key = tree.backend().get_private_key(WRITER).unwrap().unwrap();
+    let mut forged = Entry::builder(tree.root_id().clone())
+        .add_parent(revocation.clone())
+        .set_subtree_data("forged", "{}")
+        .set_sig(SigInfo {
+            key: SigKey::Direct(WRITER.to_string()),
+            sig: None,
+        })
+        .build();
+    forged.sig.sig = Some(sign_entry(&forged, &signing_key).unwrap());
+    let forged_id = tree.insert_raw(forged).unwrap();
+    assert!(!tree.verify_entry_signature(&forged_id).unwrap());
+
+    // A later entry that builds on it is tainted
+    let descendant = commit_data(&tree, ADMIN, "after forged");
+
+    assert_eq!(
+        tree.revocation_status(&forged_id).unwrap(),
+        RevocationStatus::Revoked {
+            key_name: WRITER.to_string()
+        }
+    );
+    let tainted = RevocationStatus::Tainted {
+        source: forged_id.clone(),
+    };
+    assert_eq!(tree.revocation_status(&descendant).unwrap(), tainted);
+    assert!(!tainted.is_valid());
+
+    let affected = tree.revalidate_revocations().unwrap();
+    assert_eq!(affected.len(), 2);
+    for id in [&forged_id, &descendant] {
+        assert_eq!(
+            tree.backend().get_verification_status(id).unwrap(),
+            VerificationStatus::Failed
+        );
+    }
+    // Entries not affected by the revocation keep their status
+    assert_eq!(
+        tree.backend().get_verification_status(&revocation).unwrap(),
+        VerificationStatus::Verified
+    );
+}
+
+#[test]
+fn test_reactivated_key_is_unaffected() {
+    let tree = setup();
+    let written = commit_data(&tree, WRITER, "from writer");
+    tree.revoke_key(WRITER).unwrap();
+    assert_eq!(tree.revocation_status(&written).unwrap(), historical());
+
+    // Reactivating the key clears the revocation
+    let op = tree.new_operation().unwrap();
+    let settings = op.get_subtree::<Dict>("_settings").unwrap();
+    let public_key = format_public_key(
+        &tree
+            .backend()
+            .get_private_key(WRITER)
+            .unwrap()
+            .unwrap()
+            .verifying_key(),
+    );
+    let mut auth = settings.get_node("auth").unwrap();
+    auth.set_json(
+        WRITER,
+        auth_key(&public_key, Permission::Write(10), KeyStatus::Active),
+    )
+    .unwrap();
+    settings.set_node("auth", auth).unwrap();
+    op.commit().unwrap();
+
+    assert_eq!(
+        tree.revocation_status(&written).unwrap(),
+        RevocationStatus::Unaffected
+    );
+    assert!(tree.revalidate_revocations().unwrap().is_empty());
+}
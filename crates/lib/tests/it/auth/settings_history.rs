@@ -0,0 +1,168 @@
+//! Tests for settings history and the auth timeline
+//!
+//! Covers `Tree::settings_at`, `Tree::auth_state_at` and `Tree::auth_timeline`,
+//! and retroactive validation of entries signed by keys that were later revoked.
+
+use super::helpers::*;
+use eidetica::auth::types::{KeyStatus, Permission};
+use eidetica::crdt::Map;
+use eidetica::entry::ID;
+use eidetica::subtree::Dict;
+use eidetica::tree::Tree;
+
+/// Replaces the auth section of the tree's settings, signed by the admin key.
+fn commit_auth(tree: &Tree, auth: Map) -> ID {
+    let op = tree.new_authenticated_operation(ADMIN).unwrap();
+    let settings = op.get_subtree::<Dict>("_settings").unwrap();
+    settings.set_value("auth", auth.into()).unwrap();
+    op.commit().unwrap()
+}
+
+/// Returns the current auth section with the writer key set to `status`.
+fn auth_with_writer(tree: &Tree, writer_key: &str, status: KeyStatus) -> Map {
+    let settings = tree.get_settings().unwrap().get_all().unwrap();
+    let mut auth = match settings.get("auth") {
+        Some(eidetica::crdt::map::Value::Map(auth)) => auth.clone(),
+        _ => panic!("tree has no auth settings"),
+    };
+    auth.set_json(WRITER, auth_key(writer_key, Permission::Write(10), status))
+        .unwrap();
+    auth
+}
+
+fn commit_data(tree: &Tree, key_name: &str, value: &str) -> ID {
+    let op = tree.new_authenticated_operation(key_name).unwrap();
+    op.get_subtree::<Dict>("data")
+        .unwrap()
+        .set("value", value)
+        .unwrap();
+    op.commit().unwrap()
+}
+
+/// Creates a tree without `WRITER` in its auth settings, returning the writer's public key.
+fn setup() -> (Tree, String) {
+    let (db, tree) = setup_admin_tree_without_writer();
+    let writer_key = db.get_formatted_public_key(WRITER).unwrap().unwrap();
+    (tree, writer_key)
+}
+
+#[test]
+fn test_auth_state_at_tracks_key_additions() {
+    let (tree, writer_key) = setup();
+    let before = commit_data(&tree, ADMIN, "before");
+    let added = commit_auth(
+        &tree,
+        auth_with_writer(&tree, &writer_key, KeyStatus::Active),
+    );
+
+    let root_auth = tree.auth_state_at(tree.root_id()).unwrap();
+    assert!(root_auth.get_key(ADMIN).is_some());
+    assert!(root_auth.get_key(WRITER).is_none());
+
+    // Entries before the change still see the old configuration
+    assert!(
+        tree.auth_state_at(&before)
+            .unwrap()
+            .get_key(WRITER)
+            .is_none()
+    );
+
+    let added_auth = tree.auth_state_at(&added).unwrap();
+    let writer = added_auth.get_key(WRITER).unwrap().unwrap();
+    assert_eq!(writer.status, KeyStatus::Active);
+    assert_eq!(added_auth.get_all_keys().unwrap().len(), 2);
+}
+
+#[test]
+fn test_revoked_key_entries_remain_valid() {
+    let (tree, writer_key) = setup();
+    commit_auth(
+        &tree,
+        auth_with_writer(&tree, &writer_key, KeyStatus::Active),
+    );
+    let written = commit_data(&tree, WRITER, "from writer");
+    let revoked = commit_auth(
+        &tree,
+        auth_with_writer(&tree, &writer_key, KeyStatus::Revoked),
+    );
+
+    let at_write = tree.auth_state_at(&written).unwrap();
+    assert_eq!(
+        at_write.get_key(WRITER).unwrap().unwrap().status,
+        KeyStatus::Active
+    );
+    let at_revoke = tree.auth_state_at(&revoked).unwrap();
+    assert_eq!(
+        at_revoke.get_key(WRITER).unwrap().unwrap().status,
+        KeyStatus::Revoked
+    );
+
+    // The entry is validated against the settings it was created under
+    assert!(tree.verify_entry_signature(&written).unwrap());
+
+    // New entries from the revoked key are still rejected
+    let op = tree.new_authenticated_operation(WRITER).unwrap();
+    op.get_subtree::<Dict>("data")
+        .unwrap()
+        .set("value", "after revocation")
+        .unwrap();
+    assert!(op.commit().is_err());
+}
+
+#[test]
+fn test_settings_at_includes_non_auth_settings() {
+    let (tree, _) = setup();
+    let op = tree.new_authenticated_operation(ADMIN).unwrap();
+    op.get_subtree::<Dict>("_settings")
+        .unwrap()
+        .set("name", "renamed")
+        .unwrap();
+    let renamed = op.commit().unwrap();
+
+    assert!(
+        tree.settings_at(tree.root_id())
+            .unwrap()
+            .get("name")
+            .is_none()
+    );
+    assert_eq!(
+        tree.settings_at(&renamed).unwrap().get_text("name"),
+        Some("renamed")
+    );
+}
+
+#[test]
+fn test_auth_timeline() {
+    let (tree, writer_key) = setup();
+    commit_data(&tree, ADMIN, "unrelated");
+    let added = commit_auth(
+        &tree,
+        auth_with_writer(&tree, &writer_key, KeyStatus::Active),
+    );
+    commit_data(&tree, WRITER, "from writer");
+    let revoked = commit_auth(
+        &tree,
+        auth_with_writer(&tree, &writer_key, KeyStatus::Revoked),
+    );
+
+    let timeline = tree.auth_timeline().unwrap();
+    let ids: Vec<&ID> = timeline.iter().map(|(id, _)| id).collect();
+    assert_eq!(ids, vec![tree.root_id(), &added, &revoked]);
+
+    let statuses: Vec<Option<KeyStatus>> = timeline
+        .iter()
+        .map(|(_, auth)| auth.get_key(WRITER).map(|key| key.unwrap().status))
+        .collect();
+    assert_eq!(
+        statuses,
+        vec![None, Some(KeyStatus::Active), Some(KeyStatus::Revoked)]
+    );
+}
+
+#[test]
+fn test_auth_state_at_rejects_foreign_entry() {
+    let (tree, _) = setup();
+    let (_other_db, other_tree) = setup_db_and_tree_with_key(ADMIN);
+
+    assert!(tree.auth_state_at(other_tree.root_id()).is_err());
+}
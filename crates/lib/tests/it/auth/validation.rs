@@ -443,3 +443,46 @@ fn test_entry_validation_with_invalid_signatures() {
         !verify_entry_signature(&correct_entry, &wrong_verifying_key).expect("Failed to verify")
     );
 }
+
+#[test]
+fn test_validation_follows_settings_changes_between_commits() {
+    let keys = create_auth_keys![
+        ("ADMIN_KEY", Permission::Admin(0), KeyStatus::Active),
+        ("WRITER_KEY", Permission::Write(10), KeyStatus::Active)
+    ];
+    let (db, public_keys) = setup_test_db_with_keys(&keys);
+    let tree = setup_authenticated_tree(&db, &keys, &public_keys);
+
+    // Repeated commits against unchanged settings reuse the tree's validator
+    for i in 0..3 {
+        test_operation_succeeds(&tree, "WRITER_KEY", "data", &format!("Commit {i}"));
+    }
+
+    // Downgrade the writer to read-only
+    let op = tree.new_authenticated_operation("ADMIN_KEY").unwrap();
+    let settings = op.get_subtree::<Dict>("_settings").unwrap();
+    let mut auth = match tree.get_settings().unwrap().get_all().unwrap().get("auth") {
+        Some(Value::Map(auth)) => auth.clone(),
+        _ => panic!("tree has no auth settings"),
+    };
+    auth.set_json(
+        "WRITER_KEY",
+        AuthKey {
+            pubkey: format_public_key(&public_keys[1]),
+            permissions: Permission::Read,
+            status: KeyStatus::Active,
+        },
+    )
+    .unwrap();
+    settings.set_value("auth", auth.into()).unwrap();
+    op.commit().unwrap();
+
+    // The cached write permission must not outlive the settings change
+    test_operation_fails(&tree, "WRITER_KEY", "data", "Commit after downgrade");
+    test_operation_fails(
+        &tree.clone(),
+        "WRITER_KEY",
+        "data",
+        "Commit from a clone after downgrade",
+    );
+}
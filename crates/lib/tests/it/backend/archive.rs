@@ -0,0 +1,298 @@
+//! Tests for the read-only archive database backend
+//!
+//! Archives are exported from an InMemory backend and every query is compared
+//! against the source, so both share the same tip, height and traversal semantics.
+
+use eidetica::backend::database::{Archive, InMemory};
+use eidetica::backend::{Database, VerificationStatus};
+use eidetica::basedb::BaseDB;
+use eidetica::entry::{Entry, ID};
+use eidetica::subtree::Dict;
+use std::collections::HashSet;
+use std::fs;
+
+/// Stores an entry in `subtree`; parents other than the tree root are also subtree parents.
+fn store_subtree_entry(
+    backend: &InMemory,
+    tree: &ID,
+    parents: &[&ID],
+    subtree: &str,
+    data: &str,
+) -> ID {
+    let mut builder = Entry::builder(tree.clone()).set_subtree_data(subtree, data);
+    for parent in parents {
+        builder = builder.add_parent((*parent).clone());
+        if *parent != tree {
+            builder = builder.add_subtree_parent(subtree, (*parent).clone());
+        }
+    }
+    let entry = builder.build();
+    let id = entry.id();
+    backend.put_verified(entry).unwrap();
+    id
+}
+
+/// Builds a tree with a diamond in subtree "a" and a separate branch in subtree "b".
+fn build_source() -> (InMemory, ID, Vec<ID>) {
+    let backend = InMemory::new();
+    let root = Entry::root_builder().build();
+    let root_id = root.id();
+    backend.put_verified(root).unwrap();
+
+    let a1 = store_subtree_entry(&backend, &root_id, &[&root_id], "a", "a1");
+    let a2 = store_subtree_entry(&backend, &root_id, &[&a1], "a", "a2");
+    let a3 = store_subtree_entry(&backend, &root_id, &[&a1], "a", "a3");
+    let a4 = store_subtree_entry(&backend, &root_id, &[&a2, &a3], "a", "a4");
+    let b1 = store_subtree_entry(&backend, &root_id, &[&root_id], "b", "b1");
+
+    // A second, unrelated tree, with one entry that failed verification
+    let other = Entry::root_builder().build();
+    let other_id = other.id();
+    backend.put_verified(other).unwrap();
+    let failed = Entry::builder(other_id.clone())
+        .add_parent(other_id.clone())
+        .set_subtree_data("a", "failed")
+        .build();
+    let failed_id = failed.id();
+    backend.put(VerificationStatus::Failed, failed).unwrap();
+
+    (
+        backend,
+        root_id,
+        vec![a1, a2, a3, a4, b1, other_id, failed_id],
+    )
+}
+
+fn export(source: &InMemory) -> (tempfile::TempDir, Archive) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("snapshot.edba");
+    Archive::export(source, &path).unwrap();
+    let archive = Archive::open(&path).unwrap();
+    (dir, archive)
+}
+
+fn sorted(mut ids: Vec<ID>) -> Vec<ID> {
+    ids.sort();
+    ids
+}
+
+#[test]
+fn test_archive_matches_source() {
+    let (source, root_id, ids) = build_source();
+    let (_dir, archive) = export(&source);
+    let [a1, a2, a3, a4, b1, other_id, failed_id] = ids.try_into().unwrap();
+
+    assert_eq!(archive.len(), 8);
+    assert_eq!(
+        sorted(archive.all_roots().unwrap()),
+        sorted(source.all_roots().unwrap())
+    );
+    for id in [&root_id, &a1, &a4, &failed_id] {
+        assert_eq!(archive.get(id).unwrap(), source.get(id).unwrap());
+    }
+    assert_eq!(
+        archive.get_verification_status(&failed_id).unwrap(),
+        VerificationStatus::Failed
+    );
+    assert_eq!(
+        archive
+            .get_entries_by_verification_status(VerificationStatus::Failed)
+            .unwrap(),
+        vec![failed_id.clone()]
+    );
+
+    // Tips
+    for tree in [&root_id, &other_id] {
+        assert_eq!(
+            sorted(archive.get_tips(tree).unwrap()),
+            sorted(source.get_tips(tree).unwrap())
+        );
+    }
+    for subtree in ["a", "b", "missing"] {
+        assert_eq!(
+            sorted(archive.get_subtree_tips(&root_id, subtree).unwrap()),
+            sorted(source.get_subtree_tips(&root_id, subtree).unwrap())
+        );
+    }
+    assert_eq!(
+        archive
+            .get_subtree_tips_up_to_entries(&root_id, "a", std::slice::from_ref(&a2))
+            .unwrap(),
+        vec![a2.clone()]
+    );
+
+    // Topological traversal
+    assert_eq!(
+        archive.get_tree(&root_id).unwrap(),
+        source.get_tree(&root_id).unwrap()
+    );
+    assert_eq!(
+        archive.get_subtree(&root_id, "a").unwrap(),
+        source.get_subtree(&root_id, "a").unwrap()
+    );
+    assert_eq!(
+        archive
+            .get_tree_from_tips(&root_id, std::slice::from_ref(&a3))
+            .unwrap(),
+        source
+            .get_tree_from_tips(&root_id, std::slice::from_ref(&a3))
+            .unwrap()
+    );
+    assert_eq!(
+        archive
+            .get_subtree_from_tips(&root_id, "a", &[a2.clone(), b1.clone()])
+            .unwrap(),
+        source
+            .get_subtree_from_tips(&root_id, "a", &[a2.clone(), b1.clone()])
+            .unwrap()
+    );
+
+    // Pathfinding
+    assert_eq!(
+        archive
+            .find_lca(&root_id, "a", &[a2.clone(), a3.clone()])
+            .unwrap(),
+        a1
+    );
+    assert_eq!(
+        archive
+            .get_sorted_subtree_parents(&root_id, &a4, "a")
+            .unwrap(),
+        source
+            .get_sorted_subtree_parents(&root_id, &a4, "a")
+            .unwrap()
+    );
+    assert_eq!(
+        archive
+            .get_path_from_to(&root_id, "a", &a1, std::slice::from_ref(&a4))
+            .unwrap(),
+        source
+            .get_path_from_to(&root_id, "a", &a1, std::slice::from_ref(&a4))
+            .unwrap()
+    );
+    assert_eq!(
+        archive.collect_root_to_target(&root_id, "a", &a4).unwrap(),
+        source.collect_root_to_target(&root_id, "a", &a4).unwrap()
+    );
+}
+
+#[test]
+fn test_archive_is_read_only() {
+    let (source, root_id, _) = build_source();
+    let (_dir, archive) = export(&source);
+
+    let entry = Entry::builder(root_id.clone())
+        .add_parent(root_id.clone())
+        .build();
+    let err = archive.put_verified(entry).unwrap_err();
+    assert!(matches!(
+        err,
+        eidetica::Error::Backend(ref e) if e.is_read_only()
+    ));
+    assert!(
+        archive
+            .update_verification_status(&root_id, VerificationStatus::Failed)
+            .is_err()
+    );
+    let (signing_key, _) = eidetica::auth::crypto::generate_keypair();
+    assert!(archive.store_private_key("key", signing_key).is_err());
+    assert!(archive.list_private_keys().unwrap().is_empty());
+
+    // Computed state is cached in memory
+    archive
+        .cache_crdt_state(&root_id, "a", "{}".to_string())
+        .unwrap();
+    assert_eq!(
+        archive.get_cached_crdt_state(&root_id, "a").unwrap(),
+        Some("{}".to_string())
+    );
+    archive.clear_crdt_cache().unwrap();
+    assert_eq!(archive.get_cached_crdt_state(&root_id, "a").unwrap(), None);
+}
+
+#[test]
+fn test_archive_serves_trees() {
+    let db = BaseDB::new(Box::new(InMemory::new()));
+    db.add_private_key("key").unwrap();
+    let tree = db.new_tree_default("key").unwrap();
+    for i in 0..5 {
+        let op = tree.new_operation().unwrap();
+        op.get_subtree::<Dict>("data")
+            .unwrap()
+            .set(format!("key{i}"), format!("value{i}"))
+            .unwrap();
+        op.commit().unwrap();
+    }
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("snapshot.edba");
+    Archive::export(db.backend().as_ref(), &path).unwrap();
+
+    // Consumers open the snapshot without any keys and read it as usual
+    let snapshot = BaseDB::new(Box::new(Archive::open(&path).unwrap()));
+    let loaded = snapshot.load_tree(tree.root_id()).unwrap();
+    assert_eq!(loaded.get_tips().unwrap(), tree.get_tips().unwrap());
+    let data = loaded.get_subtree_viewer::<Dict>("data").unwrap();
+    for i in 0..5 {
+        assert_eq!(
+            data.get_string(format!("key{i}")).unwrap(),
+            format!("value{i}")
+        );
+    }
+
+    // Writing to the snapshot fails
+    let mut loaded = loaded;
+    loaded.set_default_auth_key("key");
+    assert!(loaded.new_operation().unwrap().commit().is_err());
+}
+
+#[test]
+fn test_archive_rejects_invalid_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("invalid.edba");
+
+    fs::write(&path, b"not an archive").unwrap();
+    assert!(Archive::open(&path).is_err());
+
+    // A valid archive with its tail cut off
+    let (source, _, _) = build_source();
+    Archive::export(&source, &path).unwrap();
+    let bytes = fs::read(&path).unwrap();
+    fs::write(&path, &bytes[..bytes.len() / 2]).unwrap();
+    assert!(Archive::open(&path).is_err());
+
+    assert!(Archive::open(dir.path().join("missing.edba")).is_err());
+}
+
+#[test]
+fn test_empty_archive() {
+    let (_dir, archive) = export(&InMemory::new());
+    assert!(archive.is_empty());
+    assert!(archive.all_roots().unwrap().is_empty());
+    assert!(
+        archive
+            .get(&ID::from("missing"))
+            .unwrap_err()
+            .is_not_found()
+    );
+}
+
+#[cfg(feature = "cbor")]
+#[test]
+fn test_archive_with_cbor() {
+    use eidetica::backend::EntryCodec;
+
+    let (source, root_id, _) = build_source();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("snapshot.edba");
+    Archive::export_with(&source, &path, EntryCodec::Cbor).unwrap();
+
+    let archive = Archive::open(&path).unwrap();
+    assert_eq!(archive.codec(), EntryCodec::Cbor);
+    assert_eq!(
+        archive.get_tree(&root_id).unwrap(),
+        source.get_tree(&root_id).unwrap()
+    );
+    let tips: HashSet<ID> = archive.get_tips(&root_id).unwrap().into_iter().collect();
+    assert_eq!(tips.len(), 2);
+}
@@ -0,0 +1,127 @@
+//! Tests for transparent compression of stored entry data
+
+use eidetica::backend::database::InMemory;
+use eidetica::backend::{Compression, Database};
+use eidetica::basedb::BaseDB;
+use eidetica::entry::Entry;
+use eidetica::subtree::Dict;
+use std::fs;
+
+/// An entry with a large, repetitive subtree payload
+fn large_entry() -> Entry {
+    let data: String = (0..500)
+        .map(|i| format!("\"key_{i}\":\"value_{}\",", i % 10))
+        .collect();
+    let root = Entry::root_builder().build();
+    Entry::builder(root.id())
+        .add_parent(root.id())
+        .set_subtree_data("data", format!("{{{}}}", data.trim_end_matches(',')))
+        .build()
+}
+
+#[test]
+fn test_in_memory_compressed_snapshot() {
+    let dir = tempfile::tempdir().unwrap();
+    let plain_path = dir.path().join("plain.db");
+    let compressed_path = dir.path().join("compressed.db");
+
+    let entry = large_entry();
+    let plain = InMemory::new();
+    plain.put_verified(entry.clone()).unwrap();
+    plain.save_to_file(&plain_path).unwrap();
+
+    let compressed = InMemory::new().with_compression(Compression::Lz4);
+    assert_eq!(compressed.compression(), Compression::Lz4);
+    compressed.put_verified(entry.clone()).unwrap();
+    compressed.save_to_file(&compressed_path).unwrap();
+
+    let plain_size = fs::metadata(&plain_path).unwrap().len();
+    let compressed_size = fs::metadata(&compressed_path).unwrap().len();
+    assert!(compressed_size < plain_size / 2);
+    assert!(Compression::is_compressed(
+        &fs::read(&compressed_path).unwrap()
+    ));
+
+    // Compression is detected on load, whatever the loading backend is set to
+    let loaded = InMemory::load_from_file(&compressed_path).unwrap();
+    assert_eq!(loaded.get(&entry.id()).unwrap(), entry);
+}
+
+#[test]
+fn test_compressed_database_with_basedb() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.json");
+
+    let db = BaseDB::new(Box::new(InMemory::new().with_compression(Compression::Lz4)));
+    db.add_private_key("key").unwrap();
+    let tree = db.new_tree_default("key").unwrap();
+    let op = tree.new_operation().unwrap();
+    let dict = op.get_subtree::<Dict>("data").unwrap();
+    for i in 0..100 {
+        dict.set(format!("key_{i}"), "value").unwrap();
+    }
+    op.commit().unwrap();
+    let backend = db.backend().as_any().downcast_ref::<InMemory>().unwrap();
+    backend.save_to_file(&path).unwrap();
+
+    let db = BaseDB::new(Box::new(InMemory::load_from_file(&path).unwrap()));
+    let tree = db.load_tree(tree.root_id()).unwrap();
+    let dict = tree.get_subtree_viewer::<Dict>("data").unwrap();
+    assert_eq!(dict.get_string("key_99").unwrap(), "value");
+}
+
+#[test]
+fn test_corrupt_compressed_snapshot_is_rejected() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("corrupt.db");
+
+    let backend = InMemory::new().with_compression(Compression::Lz4);
+    backend.put_verified(large_entry()).unwrap();
+    backend.save_to_file(&path).unwrap();
+
+    let mut data = fs::read(&path).unwrap();
+    data.truncate(data.len() / 2);
+    fs::write(&path, data).unwrap();
+    assert!(InMemory::load_from_file(&path).is_err());
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn test_sqlite_compression() {
+    use eidetica::backend::database::Sqlite;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.sqlite");
+    let entry = large_entry();
+    let small = Entry::root_builder().build();
+
+    {
+        let backend = Sqlite::open(&path)
+            .unwrap()
+            .with_compression(Compression::Lz4);
+        backend.put_verified(entry.clone()).unwrap();
+        backend.put_verified(small.clone()).unwrap();
+        assert_eq!(backend.get(&entry.id()).unwrap(), entry);
+    }
+
+    // Compressed and uncompressed rows are read back without compression configured
+    let backend = Sqlite::open(&path).unwrap();
+    let plain = large_entry_variant();
+    backend.put_verified(plain.clone()).unwrap();
+    assert_eq!(backend.get(&entry.id()).unwrap(), entry);
+    assert_eq!(backend.get(&small.id()).unwrap(), small);
+    assert_eq!(backend.get(&plain.id()).unwrap(), plain);
+}
+
+/// Another large entry, distinct from `large_entry`
+#[cfg(feature = "sqlite")]
+fn large_entry_variant() -> Entry {
+    let entry = large_entry();
+    Entry::builder(entry.root())
+        .add_parent(entry.id())
+        .set_subtree_data(
+            "data",
+            entry.data("data").unwrap().replace("value", "other"),
+        )
+        .build()
+}
@@ -0,0 +1,115 @@
+//! Tests for the sharing of identical subtree payloads between entries and trees
+
+use eidetica::backend::{Database, PayloadStats, database::InMemory};
+use eidetica::entry::{Entry, ID};
+use std::path::PathBuf;
+
+/// A payload long enough to be shared.
+fn document(text: &str) -> String {
+    format!(r#"{{"body":"{}"}}"#, text.repeat(20))
+}
+
+/// Stores a tree root named `name` with `payload` in its `docs` subtree.
+fn store_root(backend: &InMemory, name: &str, payload: &str) -> ID {
+    let entry = Entry::root_builder()
+        .set_subtree_data("name", name)
+        .set_subtree_data("docs", payload)
+        .build();
+    let id = entry.id();
+    backend.put_verified(entry).unwrap();
+    id
+}
+
+/// Stores a child of `parent` in `tree` with `payload` in its `docs` subtree.
+fn store_child(backend: &InMemory, tree: &ID, parent: &ID, payload: &str) -> ID {
+    let entry = Entry::builder(tree.clone())
+        .add_parent(parent.clone())
+        .set_subtree_data("docs", payload)
+        .build();
+    let id = entry.id();
+    backend.put_verified(entry).unwrap();
+    id
+}
+
+#[test]
+fn test_identical_payloads_stored_once_across_trees() {
+    let backend = InMemory::new();
+    let doc = document("shared");
+    let tree_a = store_root(&backend, "a", &doc);
+    let tree_b = store_root(&backend, "b", &doc);
+    let child = store_child(&backend, &tree_a, &tree_a, &doc);
+
+    let stats = backend.payload_stats();
+    assert_eq!(stats.payloads, 1);
+    assert_eq!(stats.references, 3);
+    assert_eq!(stats.bytes, doc.len() as u64);
+    assert_eq!(stats.bytes_saved, 2 * doc.len() as u64);
+
+    // Entries read back whole, with the IDs they were stored under
+    for id in [&tree_a, &tree_b, &child] {
+        let entry = backend.get(id).unwrap();
+        assert_eq!(entry.id(), *id);
+        assert_eq!(entry.data("docs").unwrap(), &doc);
+    }
+    let tree = backend.get_tree(&tree_a).unwrap();
+    assert!(tree.iter().all(|entry| entry.data("docs").unwrap() == &doc));
+
+    // Storing an entry again adds no reference
+    let again = backend.get(&tree_b).unwrap();
+    backend.put_verified(again).unwrap();
+    assert_eq!(backend.payload_stats().references, 3);
+}
+
+#[test]
+fn test_short_payloads_stay_inline() {
+    let backend = InMemory::new();
+    store_root(&backend, "a", "{}");
+    store_root(&backend, "b", "{}");
+    assert_eq!(backend.payload_stats(), PayloadStats::default());
+}
+
+#[test]
+fn test_removal_releases_shared_payloads() {
+    let backend = InMemory::new();
+    let doc = document("pruned");
+    let tree_a = store_root(&backend, "a", "{}");
+    let kept = store_child(&backend, &tree_a, &tree_a, "{}");
+    let abandoned = store_child(&backend, &tree_a, &tree_a, &doc);
+    let tree_b = store_root(&backend, "b", &doc);
+
+    // Another tree still refers to the payload
+    let stats = backend.prune(&tree_a, std::slice::from_ref(&kept)).unwrap();
+    assert_eq!(stats.entries_removed, 1);
+    assert_eq!(stats.payloads_freed, 0);
+    assert!(stats.bytes_removed > doc.len() as u64);
+    assert!(backend.get(&abandoned).is_err());
+    assert_eq!(backend.get(&tree_b).unwrap().data("docs").unwrap(), &doc);
+    assert_eq!(backend.payload_stats().references, 1);
+
+    // Removing the last reference frees it
+    let stats = backend
+        .remove_entries(&tree_b, std::slice::from_ref(&tree_b))
+        .unwrap();
+    assert_eq!(stats.payloads_freed, 1);
+    assert_eq!(backend.payload_stats(), PayloadStats::default());
+}
+
+#[test]
+fn test_shared_payloads_survive_save_and_load() {
+    let backend = InMemory::new();
+    let doc = document("saved");
+    let tree_a = store_root(&backend, "a", &doc);
+    let tree_b = store_root(&backend, "b", &doc);
+
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test_dedup_save.json");
+    backend.save_to_file(&path).unwrap();
+    let loaded = InMemory::load_from_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.payload_stats(), backend.payload_stats());
+    for id in [&tree_a, &tree_b] {
+        let entry = loaded.get(id).unwrap();
+        assert_eq!(entry.id(), *id);
+        assert_eq!(entry.data("docs").unwrap(), &doc);
+    }
+}
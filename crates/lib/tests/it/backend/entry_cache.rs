@@ -0,0 +1,194 @@
+//! Tests for the cache of decoded entries
+//!
+//! Checks `Database::cache_stats` on the backends that cache decoded entries:
+//! repeated reads and traversals are answered from the cache, the least
+//! recently used entries are evicted at capacity, and removed entries are
+//! never served from it.
+
+#[cfg(any(feature = "sqlite", feature = "archive"))]
+use eidetica::backend::CacheStats;
+use eidetica::backend::Database;
+use eidetica::backend::database::{InMemory, Instrumented};
+use eidetica::entry::{Entry, ID};
+
+/// Stores a chain of `len` entries after a root, returning the root and the chain.
+fn store_chain(backend: &dyn Database, len: usize) -> (ID, Vec<ID>) {
+    let root = Entry::root_builder().build();
+    let root_id = root.id();
+    backend.put_verified(root).unwrap();
+
+    let mut chain = Vec::new();
+    let mut parent = root_id.clone();
+    for i in 0..len {
+        let mut builder = Entry::builder(root_id.clone())
+            .add_parent(parent.clone())
+            .set_subtree_data("data", format!("{i}"));
+        if parent != root_id {
+            builder = builder.add_subtree_parent("data", parent.clone());
+        }
+        let entry = builder.build();
+        parent = entry.id();
+        backend.put_verified(entry).unwrap();
+        chain.push(parent.clone());
+    }
+    (root_id, chain)
+}
+
+#[cfg(any(feature = "sqlite", feature = "archive"))]
+fn stats(backend: &dyn Database) -> CacheStats {
+    backend.cache_stats().expect("the backend caches entries")
+}
+
+#[test]
+fn test_in_memory_has_no_entry_cache() {
+    let backend = InMemory::new();
+    store_chain(&backend, 2);
+    assert_eq!(backend.cache_stats(), None);
+    assert_eq!(Instrumented::new(InMemory::new()).cache_stats(), None);
+}
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use super::*;
+    use eidetica::backend::DEFAULT_ENTRY_CACHE_CAPACITY;
+    use eidetica::backend::database::Sqlite;
+    use eidetica::basedb::BaseDB;
+    use eidetica::subtree::Dict;
+
+    #[test]
+    fn test_repeated_reads_hit_the_cache() {
+        let backend = Sqlite::open_in_memory().unwrap();
+        let (root_id, chain) = store_chain(&backend, 3);
+        assert_eq!(
+            stats(&backend),
+            CacheStats {
+                capacity: DEFAULT_ENTRY_CACHE_CAPACITY,
+                ..CacheStats::default()
+            }
+        );
+
+        assert_eq!(backend.get(&chain[0]).unwrap().id(), chain[0]);
+        assert_eq!(backend.get(&chain[0]).unwrap().id(), chain[0]);
+        let after_reads = stats(&backend);
+        assert_eq!((after_reads.hits, after_reads.misses), (1, 1));
+
+        // A second traversal decodes nothing
+        let first = backend.get_tree_from_tips(&root_id, &chain[2..]).unwrap();
+        let after_first = stats(&backend);
+        assert_eq!(after_first.entries, 4);
+        let second = backend.get_tree_from_tips(&root_id, &chain[2..]).unwrap();
+        let after_second = stats(&backend);
+        assert_eq!(first, second);
+        assert_eq!(after_second.misses, after_first.misses);
+        assert!(after_second.hits > after_first.hits);
+
+        // So do the other walks over the same entries
+        let tips = backend
+            .get_subtree_tips_up_to_entries(&root_id, "data", &chain[1..2])
+            .unwrap();
+        assert_eq!(tips, vec![chain[1].clone()]);
+        backend
+            .get_path_from_to(&root_id, "data", &chain[0], &chain[2..])
+            .unwrap();
+        assert_eq!(stats(&backend).misses, after_first.misses);
+    }
+
+    #[test]
+    fn test_least_recently_used_entries_are_evicted() {
+        let backend = Sqlite::open_in_memory().unwrap().with_entry_cache(2);
+        let (_, chain) = store_chain(&backend, 3);
+
+        for id in &chain {
+            backend.get(id).unwrap();
+        }
+        backend.get(&chain[1]).unwrap();
+        assert_eq!(
+            stats(&backend),
+            CacheStats {
+                capacity: 2,
+                entries: 2,
+                hits: 1,
+                misses: 3,
+                evictions: 1,
+            }
+        );
+
+        // The oldest entry was evicted, the recently read ones were kept
+        backend.get(&chain[0]).unwrap();
+        assert_eq!(stats(&backend).misses, 4);
+        backend.get(&chain[1]).unwrap();
+        assert_eq!(stats(&backend).hits, 2);
+    }
+
+    #[test]
+    fn test_removed_entries_leave_the_cache() {
+        let backend = Sqlite::open_in_memory().unwrap();
+        let (root_id, chain) = store_chain(&backend, 3);
+        for id in &chain {
+            backend.get(id).unwrap();
+        }
+
+        backend
+            .remove_entries(&root_id, std::slice::from_ref(&chain[2]))
+            .unwrap();
+        assert_eq!(stats(&backend).entries, 2);
+        assert!(backend.get(&chain[2]).unwrap_err().is_not_found());
+        assert_eq!(backend.get_tips(&root_id).unwrap(), vec![chain[1].clone()]);
+    }
+
+    #[test]
+    fn test_zero_capacity_disables_the_cache() {
+        let backend = Sqlite::open_in_memory().unwrap().with_entry_cache(0);
+        let (_, chain) = store_chain(&backend, 1);
+        backend.get(&chain[0]).unwrap();
+        backend.get(&chain[0]).unwrap();
+        assert_eq!(stats(&backend), CacheStats::default());
+    }
+
+    #[test]
+    fn test_tree_reads_share_the_cache() {
+        let db = BaseDB::new(Box::new(Instrumented::new(
+            Sqlite::open_in_memory().unwrap(),
+        )));
+        db.add_private_key("key").unwrap();
+        let tree = db.new_tree_default("key").unwrap();
+        for i in 0..3 {
+            let op = tree.new_operation().unwrap();
+            op.get_subtree::<Dict>("data")
+                .unwrap()
+                .set("key", i.to_string())
+                .unwrap();
+            op.commit().unwrap();
+        }
+
+        // Wrappers report the cache of the backend they wrap
+        let before = stats(db.backend().as_ref());
+        let data = tree.get_subtree_viewer::<Dict>("data").unwrap();
+        assert_eq!(data.get_string("key").unwrap(), "2");
+        assert!(stats(db.backend().as_ref()).hits > before.hits);
+    }
+}
+
+#[cfg(feature = "archive")]
+#[test]
+fn test_archive_caches_entries() {
+    use eidetica::backend::database::Archive;
+
+    let source = InMemory::new();
+    let (root_id, chain) = store_chain(&source, 3);
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("snapshot.edba");
+    Archive::export(&source, &path).unwrap();
+    let archive = Archive::open(&path).unwrap().with_entry_cache(8);
+
+    let first = archive.get_tree_from_tips(&root_id, &chain[2..]).unwrap();
+    let after_first = stats(&archive);
+    assert_eq!(after_first.capacity, 8);
+    assert_eq!(after_first.entries, 4);
+    assert_eq!(
+        archive.get_tree_from_tips(&root_id, &chain[2..]).unwrap(),
+        first
+    );
+    assert_eq!(stats(&archive).misses, after_first.misses);
+    assert_eq!(stats(&archive).hits, after_first.hits + 4);
+}
@@ -57,6 +57,12 @@ fn test_calculate_entry_height() {
             (&id_d, 4),    // Fourth level (takes longer path)
         ],
     );
+
+    // Single heights come from the same cache
+    for (id, height) in &heights {
+        assert_eq!(backend.get_height(&root_id, id).unwrap(), *height);
+    }
+    assert!(backend.get_height(&root_id, &"missing".into()).is_err());
 }
 
 #[test]
@@ -0,0 +1,126 @@
+//! Tests for timing database operations with the `instrument` hooks
+
+use eidetica::backend::Database;
+use eidetica::backend::database::{InMemory, Instrumented};
+use eidetica::basedb::BaseDB;
+use eidetica::entry::ID;
+use eidetica::instrument::{self, Measurement, Operation};
+use eidetica::subtree::Dict;
+use std::sync::{Arc, Mutex};
+
+/// Records every measurement until dropped.
+struct Recorder {
+    measurements: Arc<Mutex<Vec<Measurement>>>,
+    hook: instrument::HookId,
+}
+
+impl Recorder {
+    fn new() -> Self {
+        let measurements = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&measurements);
+        let hook = instrument::add_hook(move |m| sink.lock().unwrap().push(m.clone()));
+        Self { measurements, hook }
+    }
+
+    /// The measurements of `operation` on `tree`, in the order they were made.
+    fn on(&self, tree: &ID, operation: Operation) -> Vec<Measurement> {
+        self.measurements
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|m| m.operation == operation && m.tree.as_ref() == Some(tree))
+            .cloned()
+            .collect()
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        instrument::remove_hook(self.hook);
+    }
+}
+
+#[test]
+fn test_instrumented_backend_reports_operations() {
+    let recorder = Recorder::new();
+    let db = BaseDB::new(Box::new(Instrumented::new(InMemory::new())));
+    db.add_private_key("key").unwrap();
+    let tree = db.new_tree_default("key").unwrap();
+
+    let op = tree.new_operation().unwrap();
+    op.get_subtree::<Dict>("notes")
+        .unwrap()
+        .set("a", "1")
+        .unwrap();
+    let entry = op.commit().unwrap();
+    tree.get_subtree_viewer::<Dict>("notes")
+        .unwrap()
+        .get("a")
+        .unwrap();
+    db.backend().get(&entry).unwrap();
+
+    let root = tree.root_id();
+    assert_eq!(recorder.on(root, Operation::Commit).len(), 1);
+    assert!(!recorder.on(root, Operation::Put).is_empty());
+    let tips = recorder.on(root, Operation::Tips);
+    assert!(tips.iter().any(|m| m.subtree.as_deref() == Some("notes")));
+    let merges = recorder.on(root, Operation::Merge);
+    assert!(merges.iter().any(|m| m.subtree.as_deref() == Some("notes")));
+    assert!(
+        recorder
+            .measurements
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|m| m.operation == Operation::Get)
+    );
+
+    // The wrapped backend is still reachable
+    let backend = db.backend();
+    let instrumented = backend
+        .as_any()
+        .downcast_ref::<Instrumented<InMemory>>()
+        .unwrap();
+    assert!(instrumented.inner().get(&entry).is_ok());
+}
+
+#[test]
+fn test_commits_measured_without_instrumented_backend() {
+    let recorder = Recorder::new();
+    let db = BaseDB::new(Box::new(InMemory::new()));
+    db.add_private_key("key").unwrap();
+    let tree = db.new_tree_default("key").unwrap();
+
+    for value in ["1", "2"] {
+        let op = tree.new_operation().unwrap();
+        op.get_subtree::<Dict>("notes")
+            .unwrap()
+            .set("a", value)
+            .unwrap();
+        op.commit().unwrap();
+    }
+
+    let root = tree.root_id();
+    assert_eq!(recorder.on(root, Operation::Commit).len(), 2);
+    assert!(recorder.on(root, Operation::Put).is_empty());
+    assert_eq!(Operation::Commit.name(), "commit");
+}
+
+#[test]
+fn test_removed_hook_is_not_called() {
+    let recorder = Recorder::new();
+    let hook = recorder.hook;
+    assert!(instrument::remove_hook(hook));
+    assert!(!instrument::remove_hook(hook));
+
+    let db = BaseDB::new(Box::new(InMemory::new()));
+    db.add_private_key("key").unwrap();
+    let tree = db.new_tree_default("key").unwrap();
+    let op = tree.new_operation().unwrap();
+    op.get_subtree::<Dict>("notes")
+        .unwrap()
+        .set("a", "1")
+        .unwrap();
+    op.commit().unwrap();
+    assert!(recorder.on(tree.root_id(), Operation::Commit).is_empty());
+}
@@ -0,0 +1,174 @@
+use super::helpers::*;
+use eidetica::backend::{Database, VerificationStatus, database::InMemory};
+use eidetica::basedb::BaseDB;
+use eidetica::entry::Entry;
+use eidetica::subtree::Dict;
+use std::fs;
+use std::io::Write;
+
+#[test]
+fn test_journal_replays_entries_and_keys() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.journal");
+
+    let (root_id, child_id) = {
+        let backend = InMemory::open_with_journal(&path).unwrap();
+        assert_eq!(backend.journal_path(), Some(path.clone()));
+
+        let root_id = create_and_store_root(&backend);
+        let child_id = create_and_store_child(&backend, &root_id, &root_id);
+        backend
+            .update_verification_status(&child_id, VerificationStatus::Failed)
+            .unwrap();
+
+        let (key, _) = eidetica::auth::crypto::generate_keypair();
+        backend.store_private_key("kept", key.clone()).unwrap();
+        backend.store_private_key("dropped", key).unwrap();
+        backend.remove_private_key("dropped").unwrap();
+        (root_id, child_id)
+    };
+
+    let backend = InMemory::open_with_journal(&path).unwrap();
+    assert_eq!(backend.all_roots().unwrap(), vec![root_id.clone()]);
+    assert_eq!(backend.get_tips(&root_id).unwrap(), vec![child_id.clone()]);
+    assert_eq!(
+        backend.get_verification_status(&child_id).unwrap(),
+        VerificationStatus::Failed
+    );
+    assert_eq!(backend.list_private_keys().unwrap(), vec!["kept"]);
+}
+
+#[test]
+fn test_journal_appends_instead_of_rewriting() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.journal");
+
+    let backend = InMemory::open_with_journal(&path).unwrap();
+    let root_id = create_and_store_root(&backend);
+    let first = fs::read_to_string(&path).unwrap();
+
+    create_and_store_child(&backend, &root_id, &root_id);
+    let second = fs::read_to_string(&path).unwrap();
+
+    assert!(second.starts_with(&first));
+    assert_eq!(second.lines().count(), 2);
+}
+
+#[test]
+fn test_journal_compaction() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.journal");
+
+    let backend = InMemory::open_with_journal(&path).unwrap();
+    let root_id = create_and_store_root(&backend);
+    for i in 0..10 {
+        let status = if i % 2 == 0 {
+            VerificationStatus::Failed
+        } else {
+            VerificationStatus::Verified
+        };
+        backend
+            .update_verification_status(&root_id, status)
+            .unwrap();
+    }
+    assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 11);
+
+    backend.compact_journal().unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 1);
+
+    // The journal keeps working after compaction
+    create_and_store_child(&backend, &root_id, &root_id);
+    drop(backend);
+    let backend = InMemory::open_with_journal(&path).unwrap();
+    assert_eq!(backend.all_ids().len(), 2);
+    assert_eq!(
+        backend.get_verification_status(&root_id).unwrap(),
+        VerificationStatus::Verified
+    );
+}
+
+#[test]
+fn test_journal_automatic_compaction() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.journal");
+
+    let backend = InMemory::open_with_journal(&path).unwrap();
+    let root_id = create_and_store_root(&backend);
+    for _ in 0..2000 {
+        backend
+            .update_verification_status(&root_id, VerificationStatus::Verified)
+            .unwrap();
+    }
+
+    let lines = fs::read_to_string(&path).unwrap().lines().count();
+    assert!(lines < 1100, "journal was not compacted: {lines} lines");
+}
+
+#[test]
+fn test_journal_ignores_truncated_last_record() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.journal");
+
+    {
+        let backend = InMemory::open_with_journal(&path).unwrap();
+        backend.put_verified(Entry::root_builder().build()).unwrap();
+    }
+    {
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        write!(file, "{{\"op\":\"put\",\"sta").unwrap();
+    }
+
+    let backend = InMemory::open_with_journal(&path).unwrap();
+    assert_eq!(backend.all_roots().unwrap().len(), 1);
+}
+
+#[test]
+fn test_journal_appends_after_truncated_last_record() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.journal");
+
+    let root_id = {
+        let backend = InMemory::open_with_journal(&path).unwrap();
+        create_and_store_root(&backend)
+    };
+    let intact = fs::read_to_string(&path).unwrap();
+    {
+        // Crash in the middle of writing the next record
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        write!(file, "{{\"op\":\"put\",\"sta").unwrap();
+    }
+
+    let child_id = {
+        let backend = InMemory::open_with_journal(&path).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), intact);
+        create_and_store_child(&backend, &root_id, &root_id)
+    };
+
+    let backend = InMemory::open_with_journal(&path).unwrap();
+    assert_eq!(backend.get_tips(&root_id).unwrap(), vec![child_id]);
+    assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 2);
+}
+
+#[test]
+fn test_journal_with_basedb() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.journal");
+
+    let tree_id = {
+        let db = BaseDB::new(Box::new(InMemory::open_with_journal(&path).unwrap()));
+        db.add_private_key("key").unwrap();
+        let tree = db.new_tree_default("key").unwrap();
+        let op = tree.new_operation().unwrap();
+        op.get_subtree::<Dict>("data")
+            .unwrap()
+            .set("k", "v")
+            .unwrap();
+        op.commit().unwrap();
+        tree.root_id().clone()
+    };
+
+    let db = BaseDB::new(Box::new(InMemory::open_with_journal(&path).unwrap()));
+    let tree = db.load_tree(&tree_id).unwrap();
+    let dict = tree.get_subtree_viewer::<Dict>("data").unwrap();
+    assert_eq!(dict.get_string("k").unwrap(), "v");
+}
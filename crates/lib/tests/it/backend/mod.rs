@@ -1,7 +1,20 @@
+#[cfg(feature = "archive")]
+mod archive;
 mod basic_operations;
+#[cfg(feature = "compression")]
+mod compression;
+mod dedup;
+mod entry_cache;
 mod height_calculations;
 mod helpers;
+mod instrumented;
+mod journal;
+mod prune;
 mod save_load;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+mod streaming;
 mod subtree_operations;
+mod tiered;
 mod tree_operations;
 mod verification;
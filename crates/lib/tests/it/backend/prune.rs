@@ -0,0 +1,163 @@
+//! Tests for pruning entries that are not reachable from kept tips, and for
+//! removing chosen entries
+
+use crate::helpers::{commit_dict_value, commit_dict_value_on};
+use eidetica::Tree;
+use eidetica::backend::Database;
+use eidetica::backend::database::InMemory;
+use eidetica::basedb::BaseDB;
+use eidetica::entry::ID;
+use eidetica::subtree::Dict;
+
+fn setup_tree(backend: Box<dyn Database>) -> Tree {
+    let db = BaseDB::new(backend);
+    db.add_private_key("key").unwrap();
+    db.new_tree_default("key").unwrap()
+}
+
+/// Builds a tree with a shared base and two branches on top of it.
+/// Returns (tree, kept tip, abandoned tip).
+fn setup_branches(backend: Box<dyn Database>) -> (Tree, ID, ID) {
+    let tree = setup_tree(backend);
+    let base = commit_dict_value(&tree, "data", "base", "1");
+    let kept = commit_dict_value_on(&tree, std::slice::from_ref(&base), "data", "kept", "1");
+    let abandoned =
+        commit_dict_value_on(&tree, std::slice::from_ref(&base), "data", "abandoned", "1");
+    let abandoned = commit_dict_value_on(&tree, &[abandoned], "data", "abandoned", "2");
+    (tree, kept, abandoned)
+}
+
+#[test]
+fn test_prune_removes_abandoned_branch() {
+    let (tree, kept, abandoned) = setup_branches(Box::new(InMemory::new()));
+    let total = tree.get_all_entries().unwrap().len();
+    let before = tree
+        .get_subtree_viewer_at::<Dict>("data", std::slice::from_ref(&kept))
+        .unwrap()
+        .get_all()
+        .unwrap();
+
+    let stats = tree.prune(std::slice::from_ref(&kept)).unwrap();
+    assert_eq!(stats.entries_removed, 2);
+    assert_eq!(stats.entries_kept, total - 2);
+    assert!(stats.bytes_removed > 0);
+
+    assert_eq!(tree.get_tips().unwrap(), vec![kept.clone()]);
+    assert!(tree.get_entry(&abandoned).is_err());
+    assert_eq!(tree.get_all_entries().unwrap().len(), total - 2);
+
+    let dict = tree.get_subtree_viewer::<Dict>("data").unwrap();
+    assert_eq!(dict.get_all().unwrap(), before);
+    assert!(dict.get("abandoned").is_err());
+
+    // The pruned tree keeps accepting writes
+    commit_dict_value(&tree, "data", "after", "1");
+    let dict = tree.get_subtree_viewer::<Dict>("data").unwrap();
+    assert_eq!(dict.get_string("kept").unwrap(), "1");
+    assert_eq!(dict.get_string("after").unwrap(), "1");
+}
+
+#[test]
+fn test_prune_keeps_checkpoint_history() {
+    let tree = setup_tree(Box::new(InMemory::new()));
+    let base = commit_dict_value(&tree, "data", "base", "1");
+    let checkpoint = tree.create_checkpoint().unwrap();
+    let abandoned = commit_dict_value_on(&tree, std::slice::from_ref(&base), "data", "base", "2");
+    let kept = commit_dict_value_on(
+        &tree,
+        std::slice::from_ref(&checkpoint),
+        "data",
+        "kept",
+        "1",
+    );
+
+    let stats = tree.prune(std::slice::from_ref(&kept)).unwrap();
+    assert_eq!(stats.entries_removed, 1);
+    assert!(tree.get_entry(&abandoned).is_err());
+
+    // The checkpoint and the history behind it are still there
+    assert!(
+        tree.get_entry(&checkpoint)
+            .unwrap()
+            .is_checkpoint_of("data")
+    );
+    assert!(tree.get_entry(&base).is_ok());
+
+    let dict = tree.get_subtree_viewer::<Dict>("data").unwrap();
+    assert_eq!(dict.get_string("base").unwrap(), "1");
+    assert_eq!(dict.get_string("kept").unwrap(), "1");
+}
+
+#[test]
+fn test_prune_rejects_invalid_tips() {
+    let (tree, _kept, _abandoned) = setup_branches(Box::new(InMemory::new()));
+    let other = setup_tree(Box::new(InMemory::new()));
+    let total = tree.get_all_entries().unwrap().len();
+
+    assert!(tree.prune(&[]).is_err());
+    assert!(tree.prune(&other.get_tips().unwrap()).is_err());
+    assert_eq!(tree.get_all_entries().unwrap().len(), total);
+}
+
+#[test]
+fn test_remove_entries() {
+    let (tree, kept, abandoned) = setup_branches(Box::new(InMemory::new()));
+    let total = tree.get_all_entries().unwrap().len();
+    let base = tree.get_entry(&kept).unwrap().parents().unwrap();
+
+    let stats = tree
+        .backend()
+        .remove_entries(tree.root_id(), &base)
+        .unwrap();
+    assert_eq!(stats.entries_removed, 1);
+    assert_eq!(stats.entries_kept, total - 1);
+    assert!(tree.get_entry(&base[0]).is_err());
+
+    // Children of removed entries stay, with a missing parent
+    assert!(tree.get_entry(&kept).is_ok());
+    assert!(tree.get_entry(&abandoned).is_ok());
+
+    // Unknown IDs are ignored
+    let stats = tree
+        .backend()
+        .remove_entries(tree.root_id(), &base)
+        .unwrap();
+    assert_eq!(stats.entries_removed, 0);
+}
+
+#[test]
+fn test_prune_compacts_journal() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.journal");
+
+    let (root, kept, abandoned) = {
+        let (tree, kept, abandoned) =
+            setup_branches(Box::new(InMemory::open_with_journal(&path).unwrap()));
+        tree.prune(std::slice::from_ref(&kept)).unwrap();
+        (tree.root_id().clone(), kept, abandoned)
+    };
+
+    let backend = InMemory::open_with_journal(&path).unwrap();
+    assert!(backend.get(&abandoned).is_err());
+    assert_eq!(backend.get_tips(&root).unwrap(), vec![kept]);
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn test_prune_sqlite() {
+    use eidetica::backend::database::Sqlite;
+
+    let (tree, kept, abandoned) = setup_branches(Box::new(Sqlite::open_in_memory().unwrap()));
+    let total = tree.get_all_entries().unwrap().len();
+
+    let stats = tree.prune(std::slice::from_ref(&kept)).unwrap();
+    assert_eq!(stats.entries_removed, 2);
+    assert_eq!(stats.entries_kept, total - 2);
+
+    assert!(tree.get_entry(&abandoned).is_err());
+    assert_eq!(tree.get_tips().unwrap(), vec![kept.clone()]);
+    assert_eq!(tree.subtree_tips("data").unwrap(), vec![kept]);
+    let dict = tree.get_subtree_viewer::<Dict>("data").unwrap();
+    assert!(dict.get("abandoned").is_err());
+    assert_eq!(dict.get_string("kept").unwrap(), "1");
+}
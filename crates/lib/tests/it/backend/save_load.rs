@@ -1,4 +1,4 @@
-use eidetica::backend::{Database, database::InMemory};
+use eidetica::backend::{Database, EntryCodec, database::InMemory};
 use eidetica::entry::Entry;
 use std::fs;
 use std::io::Write;
@@ -165,3 +165,209 @@ fn test_save_load_with_various_entries() {
     // Cleanup
     fs::remove_file(file_path).unwrap();
 }
+
+/// Builds a branchy DAG where every entry has several main-tree and subtree parents.
+fn build_branchy_dag(backend: &InMemory) -> (eidetica::entry::ID, Vec<Entry>) {
+    let root = Entry::root_builder().build();
+    let root_id = root.id();
+    backend.put_verified(root.clone()).unwrap();
+
+    let mut entries = vec![root];
+    let mut layer = vec![root_id.clone()];
+    for depth in 0..6 {
+        let mut next = Vec::new();
+        for width in 0..5 {
+            let mut builder = Entry::builder(root_id.clone())
+                .set_subtree_data("data", format!("{depth}-{width}"));
+            for parent in &layer {
+                builder = builder.add_parent(parent.clone());
+            }
+            if depth > 0 {
+                builder = builder.set_subtree_parents("data", layer.clone());
+            }
+            let entry = builder.build();
+            next.push(entry.id());
+            backend.put_verified(entry.clone()).unwrap();
+            entries.push(entry);
+        }
+        layer = next;
+    }
+    (root_id, entries)
+}
+
+#[test]
+fn test_save_uses_compact_id_encoding() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("compact.json");
+
+    let backend = InMemory::new();
+    let (root_id, entries) = build_branchy_dag(&backend);
+    // Populate the tips and heights caches so they are saved too
+    let tips = backend.get_tips(&root_id).unwrap();
+    backend.get_tree(&root_id).unwrap();
+    backend.save_to_file(&path).unwrap();
+
+    // IDs are not spelled out in the saved file
+    let contents = fs::read_to_string(&path).unwrap();
+    for entry in &entries {
+        assert!(!contents.contains(entry.id().as_str()));
+    }
+
+    // Even including the caches, the compact file is much smaller than the
+    // entries alone written out in full
+    let full: std::collections::HashMap<_, _> =
+        entries.iter().map(|e| (e.id(), e.clone())).collect();
+    let full_size = serde_json::to_string_pretty(&full).unwrap().len();
+    assert!(
+        contents.len() * 3 < full_size * 2,
+        "compact file is {} bytes, full entries are {full_size} bytes",
+        contents.len()
+    );
+
+    // Everything round-trips exactly
+    let loaded = InMemory::load_from_file(&path).unwrap();
+    for entry in &entries {
+        let loaded_entry = loaded.get(&entry.id()).unwrap();
+        assert_eq!(&loaded_entry, entry);
+        assert_eq!(loaded_entry.id(), entry.id());
+    }
+    let mut loaded_tips = loaded.get_tips(&root_id).unwrap();
+    loaded_tips.sort();
+    let mut expected_tips = tips;
+    expected_tips.sort();
+    assert_eq!(loaded_tips, expected_tips);
+    assert_eq!(loaded.get_tree(&root_id).unwrap().len(), entries.len());
+}
+
+#[test]
+fn test_load_original_file_format() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("original.json");
+
+    let root = Entry::root_builder().build();
+    let child = Entry::builder(root.id())
+        .add_parent(root.id())
+        .set_subtree_data("data", "value")
+        .build();
+
+    // The original format stores entries keyed by their full ID
+    let original = serde_json::json!({
+        "entries": {
+            root.id().as_str(): root,
+            child.id().as_str(): child,
+        },
+        "verification_status": {
+            root.id().as_str(): "Verified",
+            child.id().as_str(): "Failed",
+        },
+    });
+    fs::write(&path, serde_json::to_string(&original).unwrap()).unwrap();
+
+    let loaded = InMemory::load_from_file(&path).unwrap();
+    assert_eq!(loaded.get(&child.id()).unwrap(), child);
+    assert_eq!(
+        loaded.get_verification_status(&child.id()).unwrap(),
+        eidetica::backend::VerificationStatus::Failed
+    );
+    assert_eq!(loaded.get_tips(&root.id()).unwrap(), vec![child.id()]);
+}
+
+#[test]
+fn test_load_corrupt_compact_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("corrupt.json");
+
+    let backend = InMemory::new();
+    build_branchy_dag(&backend);
+    backend.save_to_file(&path).unwrap();
+
+    // Truncate the ID table
+    let mut value: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    let ids = value["ids"].as_str().unwrap().to_string();
+    value["ids"] = serde_json::Value::String(ids[..ids.len() / 2].to_string());
+    fs::write(&path, serde_json::to_string(&value).unwrap()).unwrap();
+
+    assert!(InMemory::load_from_file(&path).is_err());
+}
+
+#[test]
+fn test_save_with_json_codec() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("explicit.json");
+
+    let backend = InMemory::new();
+    let (root_id, entries) = build_branchy_dag(&backend);
+    backend.save_to_file_with(&path, EntryCodec::Json).unwrap();
+
+    // JSON files are written without a header and stay readable
+    let contents = fs::read(&path).unwrap();
+    assert!(serde_json::from_slice::<serde_json::Value>(&contents).is_ok());
+
+    let loaded = InMemory::load_from_file(&path).unwrap();
+    assert_eq!(loaded.get_tree(&root_id).unwrap().len(), entries.len());
+}
+
+#[test]
+fn test_load_unknown_codec_header() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("unknown.edb");
+    fs::write(&path, b"EDB\0\x7f{}").unwrap();
+
+    assert!(InMemory::load_from_file(&path).is_err());
+}
+
+#[cfg(feature = "cbor")]
+#[test]
+fn test_save_load_cbor() {
+    let dir = tempfile::tempdir().unwrap();
+    let json_path = dir.path().join("db.json");
+    let cbor_path = dir.path().join("db.cbor");
+
+    let backend = InMemory::new();
+    let (root_id, entries) = build_branchy_dag(&backend);
+    let tips = backend.get_tips(&root_id).unwrap();
+    backend.save_to_file(&json_path).unwrap();
+    backend
+        .save_to_file_with(&cbor_path, EntryCodec::Cbor)
+        .unwrap();
+
+    // The binary file is framed with a header and is smaller than the JSON one
+    let contents = fs::read(&cbor_path).unwrap();
+    assert!(contents.starts_with(b"EDB\0"));
+    assert!(contents.len() < fs::metadata(&json_path).unwrap().len() as usize);
+
+    // The format is detected on load and everything round-trips
+    let loaded = InMemory::load_from_file(&cbor_path).unwrap();
+    for entry in &entries {
+        let loaded_entry = loaded.get(&entry.id()).unwrap();
+        assert_eq!(&loaded_entry, entry);
+        assert_eq!(loaded_entry.id(), entry.id());
+    }
+    let mut loaded_tips = loaded.get_tips(&root_id).unwrap();
+    loaded_tips.sort();
+    let mut expected_tips = tips;
+    expected_tips.sort();
+    assert_eq!(loaded_tips, expected_tips);
+}
+
+#[test]
+fn test_load_rejects_entries_not_matching_their_id() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.json");
+    let backend = InMemory::new();
+    let entry = Entry::root_builder()
+        .set_subtree_data("data", r#"{"greeting":"hello"}"#)
+        .build();
+    backend.put_verified(entry).unwrap();
+    backend.save_to_file(&path).unwrap();
+
+    // Damage the stored entry without updating its ID
+    let saved = fs::read_to_string(&path).unwrap();
+    assert!(saved.contains("hello"));
+    fs::write(&path, saved.replace("hello", "jello")).unwrap();
+
+    let err = InMemory::load_from_file(&path).unwrap_err();
+    let cause = std::error::Error::source(&err).unwrap().to_string();
+    assert!(cause.contains("hashes to"), "{cause}");
+}
@@ -0,0 +1,271 @@
+//! Tests for the SQLite database backend
+//!
+//! These mirror the InMemory backend tests to check that both backends
+//! share the same tip, height and traversal semantics.
+
+use eidetica::backend::database::Sqlite;
+use eidetica::backend::{Database, VerificationStatus};
+use eidetica::basedb::BaseDB;
+use eidetica::entry::{Entry, ID};
+use eidetica::subtree::Dict;
+use std::collections::HashSet;
+
+fn store_root(backend: &Sqlite) -> ID {
+    let root = Entry::root_builder().build();
+    let root_id = root.id();
+    backend.put_verified(root).unwrap();
+    root_id
+}
+
+/// Stores an entry in `subtree`; parents other than the tree root are also subtree parents.
+fn store_subtree_entry(
+    backend: &Sqlite,
+    tree: &ID,
+    parents: &[&ID],
+    subtree: &str,
+    data: &str,
+) -> ID {
+    let mut builder = Entry::builder(tree.clone()).set_subtree_data(subtree, data);
+    for parent in parents {
+        builder = builder.add_parent((*parent).clone());
+        if *parent != tree {
+            builder = builder.add_subtree_parent(subtree, (*parent).clone());
+        }
+    }
+    let entry = builder.build();
+    let id = entry.id();
+    backend.put_verified(entry).unwrap();
+    id
+}
+
+#[test]
+fn test_sqlite_put_get_and_verification_status() {
+    let backend = Sqlite::open_in_memory().unwrap();
+    let root_id = store_root(&backend);
+
+    let entry = Entry::builder(root_id.clone())
+        .add_parent(root_id.clone())
+        .set_subtree_data("data", "{}")
+        .build();
+    let id = entry.id();
+    backend
+        .put(VerificationStatus::Failed, entry.clone())
+        .unwrap();
+
+    assert_eq!(backend.get(&id).unwrap(), entry);
+    assert_eq!(
+        backend.get_verification_status(&id).unwrap(),
+        VerificationStatus::Failed
+    );
+    assert_eq!(
+        backend
+            .get_entries_by_verification_status(VerificationStatus::Failed)
+            .unwrap(),
+        vec![id.clone()]
+    );
+
+    backend
+        .update_verification_status(&id, VerificationStatus::Verified)
+        .unwrap();
+    assert_eq!(
+        backend.get_verification_status(&id).unwrap(),
+        VerificationStatus::Verified
+    );
+
+    let missing = ID::from("missing");
+    assert!(backend.get(&missing).unwrap_err().is_not_found());
+    assert!(
+        backend
+            .update_verification_status(&missing, VerificationStatus::Verified)
+            .is_err()
+    );
+    assert_eq!(backend.all_roots().unwrap(), vec![root_id]);
+}
+
+#[test]
+fn test_sqlite_tips_and_heights() {
+    let backend = Sqlite::open_in_memory().unwrap();
+    let root_id = store_root(&backend);
+
+    // Diamond: root -> a, b -> c
+    let a = store_subtree_entry(&backend, &root_id, &[&root_id], "branch", "a");
+    let b = store_subtree_entry(&backend, &root_id, &[&root_id], "branch", "b");
+    let tips: HashSet<ID> = backend.get_tips(&root_id).unwrap().into_iter().collect();
+    assert_eq!(tips, HashSet::from([a.clone(), b.clone()]));
+
+    let c = store_subtree_entry(&backend, &root_id, &[&a, &b], "branch", "c");
+    assert_eq!(backend.get_tips(&root_id).unwrap(), vec![c.clone()]);
+    assert_eq!(
+        backend.get_subtree_tips(&root_id, "branch").unwrap(),
+        vec![c.clone()]
+    );
+    assert!(backend.is_tip(&root_id, &c).unwrap());
+    assert!(!backend.is_tip(&root_id, &a).unwrap());
+
+    let heights = backend.calculate_heights(&root_id, None).unwrap();
+    assert_eq!(heights[&root_id], 0);
+    assert_eq!(heights[&a], 1);
+    assert_eq!(heights[&b], 1);
+    assert_eq!(heights[&c], 2);
+    for (id, height) in &heights {
+        assert_eq!(backend.get_height(&root_id, id).unwrap(), *height);
+    }
+
+    let tree: Vec<ID> = backend
+        .get_tree(&root_id)
+        .unwrap()
+        .iter()
+        .map(|e| e.id())
+        .collect();
+    assert_eq!(tree.len(), 4);
+    assert_eq!(tree[0], root_id);
+    assert_eq!(tree[3], c);
+
+    // Subtree tips limited to the state at `a`
+    assert_eq!(
+        backend
+            .get_subtree_tips_up_to_entries(&root_id, "branch", std::slice::from_ref(&a))
+            .unwrap(),
+        vec![a.clone()]
+    );
+    assert_eq!(
+        backend
+            .get_tree_from_tips(&root_id, std::slice::from_ref(&a))
+            .unwrap()
+            .len(),
+        2
+    );
+    assert!(
+        backend
+            .find_lca(&root_id, "branch", &[a.clone(), b.clone()])
+            .is_err(),
+        "a and b have no common ancestor within the subtree"
+    );
+    assert_eq!(
+        backend
+            .get_path_from_to(&root_id, "branch", &a, std::slice::from_ref(&c))
+            .unwrap(),
+        vec![b, c.clone()]
+    );
+
+    // Heights of new entries are found from those of their parents
+    let d = store_subtree_entry(&backend, &root_id, &[&c], "branch", "d");
+    assert_eq!(backend.get_height(&root_id, &d).unwrap(), 3);
+}
+
+#[test]
+fn test_sqlite_subtree_scope_separate_from_tree() {
+    let backend = Sqlite::open_in_memory().unwrap();
+    let root_id = store_root(&backend);
+
+    // A subtree with an empty name keeps its own DAG apart from the tree's
+    let a = store_subtree_entry(&backend, &root_id, &[&root_id], "", "a");
+    let b = Entry::builder(root_id.clone())
+        .add_parent(a.clone())
+        .build();
+    let b_id = b.id();
+    backend.put_verified(b).unwrap();
+
+    assert_eq!(backend.get_tips(&root_id).unwrap(), vec![b_id]);
+    assert_eq!(
+        backend.get_subtree_tips(&root_id, "").unwrap(),
+        vec![a.clone()]
+    );
+    assert!(!backend.is_tip(&root_id, &a).unwrap());
+}
+
+#[test]
+fn test_sqlite_out_of_order_insertion() {
+    let backend = Sqlite::open_in_memory().unwrap();
+    let root = Entry::root_builder().build();
+    let root_id = root.id();
+    let child = Entry::builder(root_id.clone())
+        .add_parent(root_id.clone())
+        .build();
+    let child_id = child.id();
+
+    // Store the child before its parent, as can happen during sync
+    backend.put_verified(child).unwrap();
+    backend
+        .cache_crdt_state(&child_id, "data", "partial".to_string())
+        .unwrap();
+    backend.put_verified(root).unwrap();
+
+    assert_eq!(backend.get_tips(&root_id).unwrap(), vec![child_id.clone()]);
+    // A state cached while history was missing is dropped once it arrives
+    assert_eq!(
+        backend.get_cached_crdt_state(&child_id, "data").unwrap(),
+        None
+    );
+}
+
+#[test]
+fn test_sqlite_persists_across_reopen() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("eidetica.sqlite");
+
+    let tree_id = {
+        let db = BaseDB::new(Box::new(Sqlite::open(&path).unwrap()));
+        db.add_private_key("key").unwrap();
+        let tree = db.new_tree_default("key").unwrap();
+        let op = tree.new_operation().unwrap();
+        op.get_subtree::<Dict>("data")
+            .unwrap()
+            .set("greeting", "hello")
+            .unwrap();
+        op.commit().unwrap();
+        tree.root_id().clone()
+    };
+
+    let db = BaseDB::new(Box::new(Sqlite::open(&path).unwrap()));
+    assert_eq!(db.list_private_keys().unwrap(), vec!["key".to_string()]);
+    let tree = db.load_tree(&tree_id).unwrap();
+    let viewer = tree.get_subtree_viewer::<Dict>("data").unwrap();
+    assert_eq!(viewer.get_string("greeting").unwrap(), "hello");
+}
+
+#[test]
+fn test_sqlite_private_keys_and_cache() {
+    let backend = Sqlite::open_in_memory().unwrap();
+    let (signing_key, _) = eidetica::auth::crypto::generate_keypair();
+
+    backend
+        .store_private_key("laptop", signing_key.clone())
+        .unwrap();
+    assert_eq!(
+        backend
+            .get_private_key("laptop")
+            .unwrap()
+            .unwrap()
+            .to_bytes(),
+        signing_key.to_bytes()
+    );
+    assert_eq!(backend.list_private_keys().unwrap(), vec!["laptop"]);
+    backend.remove_private_key("laptop").unwrap();
+    assert!(backend.get_private_key("laptop").unwrap().is_none());
+
+    let id = ID::from("entry");
+    assert!(
+        backend
+            .get_cached_crdt_state(&id, "data")
+            .unwrap()
+            .is_none()
+    );
+    backend
+        .cache_crdt_state(&id, "data", "{}".to_string())
+        .unwrap();
+    assert_eq!(
+        backend
+            .get_cached_crdt_state(&id, "data")
+            .unwrap()
+            .as_deref(),
+        Some("{}")
+    );
+    backend.clear_crdt_cache().unwrap();
+    assert!(
+        backend
+            .get_cached_crdt_state(&id, "data")
+            .unwrap()
+            .is_none()
+    );
+}
@@ -0,0 +1,124 @@
+//! Tests for streaming the entries of trees and subtrees
+//!
+//! Checks that `get_tree_iter` and `get_subtree_iter` yield the same entries in
+//! the same order as `get_tree` and `get_subtree` on each backend, and that
+//! entries removed while streaming are skipped.
+
+use crate::helpers::*;
+use eidetica::backend::Database;
+use eidetica::backend::database::InMemory;
+use eidetica::entry::{Entry, ID};
+use eidetica::subtree::Dict;
+
+/// Stores a diamond with a side branch: root -> a, b -> merge, and root -> side,
+/// where a, b and merge write to "data" and side writes to "other".
+fn store_diamond(backend: &dyn Database) -> (ID, Vec<ID>) {
+    let root = Entry::root_builder().build();
+    let root_id = root.id();
+    backend.put_verified(root).unwrap();
+
+    let store = |parents: &[&ID], subtree: &str, data: &str| {
+        let mut builder = Entry::builder(root_id.clone()).set_subtree_data(subtree, data);
+        for parent in parents {
+            builder = builder.add_parent((*parent).clone());
+            if **parent != root_id {
+                builder = builder.add_subtree_parent(subtree, (*parent).clone());
+            }
+        }
+        let entry = builder.build();
+        let id = entry.id();
+        backend.put_verified(entry).unwrap();
+        id
+    };
+    let a = store(&[&root_id], "data", "a");
+    let b = store(&[&root_id], "data", "b");
+    let merge = store(&[&a, &b], "data", "merge");
+    let side = store(&[&root_id], "other", "side");
+    (root_id, vec![a, b, merge, side])
+}
+
+fn ids(entries: impl IntoIterator<Item = eidetica::Result<Entry>>) -> Vec<ID> {
+    entries
+        .into_iter()
+        .map(|entry| entry.unwrap().id())
+        .collect()
+}
+
+fn check_streaming(backend: &dyn Database) {
+    let (root_id, stored) = store_diamond(backend);
+
+    let tree: Vec<ID> = backend
+        .get_tree(&root_id)
+        .unwrap()
+        .iter()
+        .map(Entry::id)
+        .collect();
+    assert_eq!(tree.len(), 5);
+    assert_eq!(ids(backend.get_tree_iter(&root_id).unwrap()), tree);
+
+    for subtree in ["data", "other", "missing"] {
+        let expected: Vec<ID> = backend
+            .get_subtree(&root_id, subtree)
+            .unwrap()
+            .iter()
+            .map(Entry::id)
+            .collect();
+        assert_eq!(
+            ids(backend.get_subtree_iter(&root_id, subtree).unwrap()),
+            expected,
+            "{subtree}"
+        );
+    }
+    assert_eq!(
+        ids(backend.get_subtree_iter(&root_id, "data").unwrap()).last(),
+        Some(&stored[2])
+    );
+}
+
+#[test]
+fn test_stream_in_memory() {
+    check_streaming(&InMemory::new());
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn test_stream_sqlite() {
+    check_streaming(&eidetica::backend::database::Sqlite::open_in_memory().unwrap());
+}
+
+#[test]
+fn test_removed_entries_are_skipped() {
+    let backend = InMemory::new();
+    let (root_id, stored) = store_diamond(&backend);
+
+    let mut entries = backend.get_tree_iter(&root_id).unwrap();
+    assert_eq!(entries.next().unwrap().unwrap().id(), root_id);
+    backend
+        .remove_entries(&root_id, std::slice::from_ref(&stored[3]))
+        .unwrap();
+    let rest = ids(entries);
+    assert_eq!(rest.len(), 3);
+    assert!(!rest.contains(&stored[3]));
+}
+
+#[test]
+fn test_tree_iter_entries() {
+    let tree = setup_tree();
+    for i in 0..3 {
+        let op = tree.new_operation().unwrap();
+        op.get_subtree::<Dict>("data")
+            .unwrap()
+            .set("key", i.to_string())
+            .unwrap();
+        op.commit().unwrap();
+    }
+
+    let all: Vec<ID> = tree
+        .get_all_entries()
+        .unwrap()
+        .iter()
+        .map(Entry::id)
+        .collect();
+    assert_eq!(ids(tree.iter_entries().unwrap()), all);
+    assert_eq!(ids(tree.read_only().iter_entries().unwrap()), all);
+}
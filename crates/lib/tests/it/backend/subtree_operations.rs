@@ -247,3 +247,104 @@ fn test_get_subtree_tips() {
     assert_eq!(sub2_tips.len(), 1);
     assert_eq!(sub2_tips[0], id_d);
 }
+
+/// Builds a tree whose entries are spread across many subtrees, with one
+/// subtree large enough to outgrow its initial membership filter.
+fn store_many_subtrees(backend: &InMemory) -> (ID, ID) {
+    let root_entry = Entry::root_builder()
+        .set_subtree_data("big", "root_big_data")
+        .build();
+    let root_id = root_entry.id();
+    backend.put_verified(root_entry).unwrap();
+
+    let mut parent = root_id.clone();
+    let mut big_tip = root_id.clone();
+    for i in 0..150 {
+        let mut builder = Entry::builder(root_id.clone()).add_parent(parent.clone());
+        if i % 2 == 0 {
+            builder = builder
+                .set_subtree_data("big", format!("big_{i}"))
+                .add_subtree_parent("big", big_tip.clone());
+        }
+        let entry = builder
+            .set_subtree_data(format!("small_{}", i % 30), format!("data_{i}"))
+            .build();
+        let id = entry.id();
+        backend.put_verified(entry).unwrap();
+        if i % 2 == 0 {
+            big_tip = id.clone();
+        }
+        parent = id;
+    }
+    (root_id, big_tip)
+}
+
+fn assert_many_subtrees(backend: &InMemory, root_id: &ID, big_tip: &ID) {
+    // Root plus every even-numbered entry
+    assert_eq!(backend.get_subtree(root_id, "big").unwrap().len(), 76);
+    assert_eq!(
+        backend.get_subtree_tips(root_id, "big").unwrap(),
+        vec![big_tip.clone()]
+    );
+    assert_eq!(
+        backend
+            .get_subtree_from_tips(root_id, "big", std::slice::from_ref(big_tip))
+            .unwrap()
+            .len(),
+        76
+    );
+    for i in 0..30 {
+        let subtree = format!("small_{i}");
+        assert_eq!(backend.get_subtree(root_id, &subtree).unwrap().len(), 5);
+    }
+    assert!(backend.get_subtree(root_id, "missing").unwrap().is_empty());
+    assert!(
+        backend
+            .get_subtree_tips(root_id, "missing")
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[test]
+fn test_subtree_queries_with_many_subtrees() {
+    let backend = InMemory::new();
+    let (root_id, big_tip) = store_many_subtrees(&backend);
+    assert_many_subtrees(&backend, &root_id, &big_tip);
+
+    // Subtree names are scoped to their tree
+    let other_root = Entry::root_builder()
+        .set_subtree_data("big", "other_data")
+        .build();
+    let other_id = other_root.id();
+    backend.put_verified(other_root).unwrap();
+    assert_eq!(backend.get_subtree(&other_id, "big").unwrap().len(), 1);
+    assert_eq!(backend.get_subtree(&root_id, "big").unwrap().len(), 76);
+}
+
+#[test]
+fn test_subtree_queries_after_save_load() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.json");
+
+    let backend = InMemory::new();
+    let (root_id, big_tip) = store_many_subtrees(&backend);
+    backend.save_to_file(&path).unwrap();
+
+    let loaded = InMemory::load_from_file(&path).unwrap();
+    assert_many_subtrees(&loaded, &root_id, &big_tip);
+}
+
+#[test]
+fn test_subtree_queries_after_journal_replay() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.journal");
+
+    let (root_id, big_tip) = {
+        let backend = InMemory::open_with_journal(&path).unwrap();
+        store_many_subtrees(&backend)
+    };
+
+    let backend = InMemory::open_with_journal(&path).unwrap();
+    assert_many_subtrees(&backend, &root_id, &big_tip);
+}
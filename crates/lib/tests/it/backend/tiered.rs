@@ -0,0 +1,147 @@
+//! Tests for the Tiered database, a hot layer over a cold layer
+
+use crate::helpers::{commit_dict_value, commit_dict_value_on};
+use eidetica::Tree;
+use eidetica::backend::Database;
+use eidetica::backend::database::{InMemory, Tiered};
+use eidetica::basedb::BaseDB;
+use eidetica::entry::ID;
+use eidetica::subtree::Dict;
+use std::sync::Arc;
+
+type MemoryTiered = Tiered<InMemory, InMemory>;
+
+fn tiered(db: &BaseDB) -> &MemoryTiered {
+    db.backend()
+        .as_any()
+        .downcast_ref::<MemoryTiered>()
+        .unwrap()
+}
+
+fn get(tree: &Tree, key: &str) -> String {
+    tree.get_subtree_viewer::<Dict>("data")
+        .unwrap()
+        .get_string(key)
+        .unwrap()
+}
+
+/// A cold layer holding a tree with some data, as if written in an earlier session.
+fn populated_cold() -> (InMemory, ID, Vec<ID>) {
+    let db = BaseDB::new(Box::new(InMemory::new()));
+    db.add_private_key("key").unwrap();
+    let tree = db.new_tree_default("key").unwrap();
+    commit_dict_value(&tree, "data", "greeting", "hello");
+    commit_dict_value(&tree, "data", "farewell", "bye");
+
+    let cold = InMemory::new();
+    let entries = tree.get_all_entries().unwrap();
+    for entry in &entries {
+        let status = db.backend().get_verification_status(&entry.id()).unwrap();
+        cold.put(status, entry.clone()).unwrap();
+    }
+    let key = db.backend().get_private_key("key").unwrap().unwrap();
+    cold.store_private_key("key", key).unwrap();
+    let ids = entries.iter().map(|entry| entry.id()).collect();
+    (cold, tree.root_id().clone(), ids)
+}
+
+#[test]
+fn test_tiered_writes_through_to_both_layers() {
+    let db = BaseDB::new(Box::new(Tiered::new(InMemory::new(), InMemory::new())));
+    db.add_private_key("key").unwrap();
+    let tree = db.new_tree_default("key").unwrap();
+    let tip = commit_dict_value(&tree, "data", "greeting", "hello");
+
+    let backend = tiered(&db);
+    assert!(backend.cold().get(&tip).is_ok());
+    assert!(backend.hot().get(&tip).is_ok());
+    assert_eq!(
+        backend.cold().get_verification_status(&tip).unwrap(),
+        backend.hot().get_verification_status(&tip).unwrap()
+    );
+    assert_eq!(get(&tree, "greeting"), "hello");
+
+    // Keys live in the cold layer only
+    assert!(backend.cold().get_private_key("key").unwrap().is_some());
+    assert!(backend.hot().get_private_key("key").unwrap().is_none());
+}
+
+#[test]
+fn test_tiered_reads_fall_back_to_cold() {
+    let (cold, root, ids) = populated_cold();
+    let db = BaseDB::new(Box::new(Tiered::new(InMemory::new(), cold)));
+    let backend = tiered(&db);
+    assert!(backend.hot().all_ids().is_empty());
+    assert!(!backend.is_loaded(&root));
+
+    // A single read copies just the entry read
+    backend.get(&root).unwrap();
+    assert_eq!(backend.hot().all_ids(), vec![root.clone()]);
+
+    // Querying the tree copies all of it, after which it is served from hot
+    let mut tree = db.load_tree(&root).unwrap();
+    assert_eq!(get(&tree, "greeting"), "hello");
+    assert!(backend.is_loaded(&root));
+    let mut hot_ids = backend.hot().all_ids();
+    hot_ids.sort();
+    let mut expected = ids.clone();
+    expected.sort();
+    assert_eq!(hot_ids, expected);
+
+    // The tree can be written to with the key kept in the cold layer
+    tree.set_default_auth_key("key");
+    let tip = commit_dict_value(&tree, "data", "greeting", "hi");
+    assert!(backend.cold().get(&tip).is_ok());
+    assert_eq!(get(&tree, "greeting"), "hi");
+    assert_eq!(db.all_trees().unwrap().len(), 1);
+}
+
+#[test]
+fn test_tiered_prune_removes_from_both_layers() {
+    let db = BaseDB::new(Box::new(Tiered::new(InMemory::new(), InMemory::new())));
+    db.add_private_key("key").unwrap();
+    let tree = db.new_tree_default("key").unwrap();
+    let base = commit_dict_value(&tree, "data", "base", "1");
+    let kept = commit_dict_value_on(&tree, std::slice::from_ref(&base), "data", "kept", "1");
+    let abandoned =
+        commit_dict_value_on(&tree, std::slice::from_ref(&base), "data", "abandoned", "1");
+
+    let stats = db
+        .backend()
+        .prune(tree.root_id(), std::slice::from_ref(&kept))
+        .unwrap();
+    assert_eq!(stats.entries_removed, 1);
+    let backend = tiered(&db);
+    assert!(backend.cold().get(&abandoned).is_err());
+    assert!(backend.hot().get(&abandoned).is_err());
+    assert_eq!(tree.get_tips().unwrap(), vec![kept.clone()]);
+
+    let stats = db
+        .backend()
+        .remove_entries(tree.root_id(), std::slice::from_ref(&kept))
+        .unwrap();
+    assert_eq!(stats.entries_removed, 1);
+    assert!(backend.cold().get(&kept).is_err());
+    assert!(backend.hot().get(&kept).is_err());
+}
+
+#[test]
+fn test_tiered_crdt_cache_stays_hot() {
+    let (cold, root, _) = populated_cold();
+    let backend: Arc<dyn Database> = Arc::new(Tiered::new(InMemory::new(), cold));
+    backend
+        .cache_crdt_state(&root, "data", "state".to_string())
+        .unwrap();
+    assert_eq!(
+        backend.get_cached_crdt_state(&root, "data").unwrap(),
+        Some("state".to_string())
+    );
+
+    let tiered = backend.as_any().downcast_ref::<MemoryTiered>().unwrap();
+    assert_eq!(
+        tiered.cold().get_cached_crdt_state(&root, "data").unwrap(),
+        None
+    );
+    backend.clear_crdt_cache().unwrap();
+    assert_eq!(backend.get_cached_crdt_state(&root, "data").unwrap(), None);
+}
@@ -242,3 +242,51 @@ fn test_get_tips() {
     assert!(tips.contains(&id_b));
     assert!(tips.contains(&id_c));
 }
+
+#[test]
+fn test_entries_stored_before_their_parents() {
+    let backend = InMemory::new();
+    let root_id = create_and_store_root(&backend);
+
+    // Root -> A -> B -> C, all writing to "data"
+    let mut chain = Vec::new();
+    let mut parent = root_id.clone();
+    for data in ["a", "b", "c"] {
+        let mut builder = Entry::builder(root_id.clone())
+            .add_parent(parent.clone())
+            .set_subtree_data("data", data);
+        if parent != root_id {
+            builder = builder.add_subtree_parent("data", parent.clone());
+        }
+        let entry = builder.build();
+        parent = entry.id();
+        chain.push(entry);
+    }
+    let ids: Vec<ID> = chain.iter().map(Entry::id).collect();
+
+    // Fill the caches, then store the chain newest first
+    assert_single_tip(&backend, &root_id, &root_id);
+    backend.calculate_heights(&root_id, None).unwrap();
+    backend.put_verified(chain[2].clone()).unwrap();
+    backend.put_verified(chain[1].clone()).unwrap();
+    backend
+        .cache_crdt_state(&ids[2], "data", "partial".to_string())
+        .unwrap();
+    backend.put_verified(chain[0].clone()).unwrap();
+
+    assert_single_tip(&backend, &root_id, &ids[2]);
+    assert_eq!(
+        backend.get_subtree_tips(&root_id, "data").unwrap(),
+        vec![ids[2].clone()]
+    );
+    let heights = backend.calculate_heights(&root_id, None).unwrap();
+    assert_entry_heights(
+        &heights,
+        &[(&root_id, 0), (&ids[0], 1), (&ids[1], 2), (&ids[2], 3)],
+    );
+    // A state cached while history was missing is dropped once it arrives
+    assert_eq!(
+        backend.get_cached_crdt_state(&ids[2], "data").unwrap(),
+        None
+    );
+}
@@ -0,0 +1,161 @@
+//! Async API tests
+//!
+//! This module tests `BaseDBAsync`, the async `Tree` and `AtomicOp` methods and
+//! the `DatabaseAsync` trait. Requires the "async" feature.
+
+use eidetica::backend::DatabaseAsync;
+use eidetica::backend::database::InMemory;
+use eidetica::basedb::{BaseDB, BaseDBAsync};
+use eidetica::crdt::Map;
+use eidetica::subtree::Dict;
+
+const TEST_KEY: &str = "test_key";
+
+async fn setup_async_db() -> BaseDBAsync {
+    let db = BaseDBAsync::new(Box::new(InMemory::new()));
+    db.add_private_key(TEST_KEY)
+        .await
+        .expect("Failed to add key");
+    db
+}
+
+#[tokio::test]
+async fn test_async_create_commit_and_read() {
+    let db = setup_async_db().await;
+    let tree = db.new_tree_default(TEST_KEY).await.unwrap();
+
+    let op = tree.new_operation_async().await.unwrap();
+    op.get_subtree_async::<Dict>("data")
+        .await
+        .unwrap()
+        .set("key", "value")
+        .unwrap();
+    let id = op.commit_async().await.unwrap();
+
+    assert_eq!(tree.get_tips_async().await.unwrap(), vec![id.clone()]);
+    assert!(
+        tree.get_entry_async(id)
+            .await
+            .unwrap()
+            .in_tree(tree.root_id())
+    );
+
+    let viewer = tree.get_subtree_viewer_async::<Dict>("data").await.unwrap();
+    assert_eq!(viewer.get_string("key").unwrap(), "value");
+    assert_eq!(tree.get_all_entries_async().await.unwrap().len(), 2);
+
+    let past = tree
+        .get_subtree_viewer_at_async::<Dict>("data", vec![tree.root_id().clone()])
+        .await
+        .unwrap();
+    assert!(past.get("key").is_err());
+}
+
+#[tokio::test]
+async fn test_async_load_and_find_trees() {
+    let db = setup_async_db().await;
+
+    let mut settings = Map::new();
+    settings.set_string("name", "async_tree");
+    let tree = db.new_tree(settings, TEST_KEY).await.unwrap();
+
+    let loaded = db.load_tree(tree.root_id()).await.unwrap();
+    assert_eq!(loaded.root_id(), tree.root_id());
+
+    let found = db.find_tree("async_tree").await.unwrap();
+    assert_eq!(found.len(), 1);
+    assert!(db.find_tree("missing").await.is_err());
+    assert_eq!(db.all_trees().await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_async_key_management() {
+    let db = setup_async_db().await;
+
+    assert!(db.get_public_key(TEST_KEY).await.unwrap().is_some());
+    db.add_private_key("second").await.unwrap();
+    let mut keys = db.list_private_keys().await.unwrap();
+    keys.sort();
+    assert_eq!(keys, vec!["second", TEST_KEY]);
+
+    db.remove_private_key("second").await.unwrap();
+    assert!(db.get_public_key("second").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_async_database_trait() {
+    let db = setup_async_db().await;
+    let tree = db.new_tree_default(TEST_KEY).await.unwrap();
+    let backend = db.backend();
+
+    let root = backend.get_async(tree.root_id()).await.unwrap();
+    assert_eq!(&root.id(), tree.root_id());
+    assert_eq!(
+        backend.all_roots_async().await.unwrap(),
+        vec![tree.root_id().clone()]
+    );
+    assert_eq!(
+        backend.get_tips_async(tree.root_id()).await.unwrap(),
+        vec![tree.root_id().clone()]
+    );
+    assert_eq!(
+        backend.get_tree_async(tree.root_id()).await.unwrap().len(),
+        1
+    );
+    assert_eq!(
+        backend
+            .get_subtree_async(tree.root_id(), "_settings")
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_async_concurrent_commits() {
+    let db = setup_async_db().await;
+    let tree = db.new_tree_default(TEST_KEY).await.unwrap();
+
+    // Seed the subtree so the concurrent entries share a common ancestor in it
+    let op = tree.new_operation_async().await.unwrap();
+    op.get_subtree::<Dict>("data")
+        .unwrap()
+        .set("seed", "value")
+        .unwrap();
+    op.commit_async().await.unwrap();
+
+    // Operations are Send, so they can be committed from spawned tasks
+    let mut handles = Vec::new();
+    for i in 0..4 {
+        let tree = tree.clone();
+        handles.push(tokio::spawn(async move {
+            let op = tree.new_operation_async().await.unwrap();
+            op.get_subtree::<Dict>("data")
+                .unwrap()
+                .set(format!("key{i}"), "value")
+                .unwrap();
+            op.commit_async().await.unwrap()
+        }));
+    }
+    for handle in handles {
+        handle.await.unwrap();
+    }
+
+    let viewer = tree.get_subtree_viewer_async::<Dict>("data").await.unwrap();
+    for i in 0..4 {
+        assert_eq!(viewer.get_string(format!("key{i}")).unwrap(), "value");
+    }
+}
+
+#[tokio::test]
+async fn test_async_wraps_existing_basedb() {
+    let sync_db = BaseDB::new(Box::new(InMemory::new()));
+    sync_db.add_private_key(TEST_KEY).unwrap();
+    let tree = sync_db.new_tree_default(TEST_KEY).unwrap();
+
+    let db = BaseDBAsync::from(sync_db);
+    let loaded = db.load_tree(tree.root_id()).await.unwrap();
+    assert_eq!(loaded.root_id(), tree.root_id());
+    assert_eq!(db.as_sync().all_trees().unwrap().len(), 1);
+}
@@ -0,0 +1,224 @@
+//! Tests for backing up and restoring whole databases
+
+use crate::helpers::{commit_dict_value, setup_db_with_key};
+use eidetica::backend::VerificationStatus;
+use eidetica::backend::database::InMemory;
+use eidetica::basedb::{BackupStats, BaseDB, BaseError, Corruption};
+use eidetica::entry::{Entry, ID};
+use eidetica::subtree::Dict;
+use serde_json::Value as Json;
+use std::fs;
+use std::path::Path;
+
+const TEST_KEY: &str = "test_key";
+
+fn empty_db() -> BaseDB {
+    BaseDB::new(Box::new(InMemory::new()))
+}
+
+/// Creates a database with the test key and two trees, returning their roots.
+fn populate() -> (BaseDB, Vec<ID>) {
+    let db = setup_db_with_key(TEST_KEY);
+    let mut roots = Vec::new();
+    for value in ["one", "two"] {
+        let tree = db.new_tree_default(TEST_KEY).unwrap();
+        commit_dict_value(&tree, "data", "key", value);
+        roots.push(tree.root_id().clone());
+    }
+    (db, roots)
+}
+
+fn value(db: &BaseDB, root: &ID) -> String {
+    let tree = db.load_tree(root).unwrap();
+    let data = tree.get_subtree_viewer::<Dict>("data").unwrap();
+    data.get_string("key").unwrap()
+}
+
+fn assert_invalid_backup(result: eidetica::Result<impl std::fmt::Debug>) {
+    match result {
+        Err(eidetica::Error::Base(BaseError::InvalidBackup { .. })) => {}
+        other => panic!("Expected an invalid backup error, got {other:?}"),
+    }
+}
+
+fn rewrite(path: &Path, f: impl FnOnce(&mut Json)) {
+    let mut backup: Json = serde_json::from_slice(&fs::read(path).unwrap()).unwrap();
+    f(&mut backup);
+    fs::write(path, serde_json::to_vec(&backup).unwrap()).unwrap();
+}
+
+#[test]
+fn test_backup_roundtrip() {
+    let (source, roots) = populate();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("backup.json");
+
+    let stats = source.backup(&path).unwrap();
+    assert_eq!(
+        stats,
+        BackupStats {
+            trees: 2,
+            entries: 4,
+            keys: 0,
+        }
+    );
+    // Keys are only included when asked for
+    assert!(!fs::read_to_string(&path).unwrap().contains("\"keys\""));
+
+    let target = empty_db();
+    let report = target.restore(&path).unwrap();
+    assert!(report.is_clean());
+    assert_eq!(report.entries, 4);
+    assert!(report.keys.is_empty());
+    let mut restored = report.trees.clone();
+    restored.sort();
+    let mut expected = roots.clone();
+    expected.sort();
+    assert_eq!(restored, expected);
+    assert_eq!(value(&target, &roots[0]), "one");
+    assert_eq!(value(&target, &roots[1]), "two");
+    for root in &roots {
+        for entry in target.load_tree(root).unwrap().get_all_entries().unwrap() {
+            assert_eq!(
+                target
+                    .backend()
+                    .get_verification_status(&entry.id())
+                    .unwrap(),
+                VerificationStatus::Verified
+            );
+        }
+    }
+
+    // Restoring again only skips what is already there
+    let report = target.restore(&path).unwrap();
+    assert!(report.is_clean());
+    assert_eq!(report.entries, 0);
+    assert_eq!(report.trees.len(), 2);
+}
+
+#[test]
+fn test_backup_with_keys() {
+    let (source, roots) = populate();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("backup.json");
+
+    let stats = source.backup_with_keys(&path, "correct horse").unwrap();
+    assert_eq!(stats.keys, 1);
+
+    // A wrong passphrase restores nothing
+    let target = empty_db();
+    assert_invalid_backup(target.restore_with_keys(&path, "wrong horse"));
+    assert!(target.list_private_keys().unwrap().is_empty());
+    assert!(target.load_tree(&roots[0]).is_err());
+
+    let report = target.restore_with_keys(&path, "correct horse").unwrap();
+    assert!(report.is_clean());
+    assert_eq!(report.keys, vec![TEST_KEY.to_string()]);
+    assert_eq!(
+        target.get_public_key(TEST_KEY).unwrap(),
+        source.get_public_key(TEST_KEY).unwrap()
+    );
+    // The restored key can write to the restored trees
+    let mut tree = target.load_tree(&roots[0]).unwrap();
+    tree.set_default_auth_key(TEST_KEY);
+    commit_dict_value(&tree, "data", "key", "three");
+    assert_eq!(value(&target, &roots[0]), "three");
+
+    // A different key with the same name is not overwritten
+    let other = setup_db_with_key(TEST_KEY);
+    let report = other.restore_with_keys(&path, "correct horse").unwrap();
+    assert!(report.keys.is_empty());
+    assert_eq!(report.key_conflicts, vec![TEST_KEY.to_string()]);
+    assert_ne!(
+        other.get_public_key(TEST_KEY).unwrap(),
+        source.get_public_key(TEST_KEY).unwrap()
+    );
+
+    // Keys cannot be restored from a backup without them
+    let keyless = dir.path().join("keyless.json");
+    source.backup(&keyless).unwrap();
+    assert_invalid_backup(empty_db().restore_with_keys(&keyless, "correct horse"));
+    // Without the passphrase, only the trees are restored
+    let report = empty_db().restore(&path).unwrap();
+    assert!(report.keys.is_empty());
+    assert_eq!(report.trees.len(), 2);
+}
+
+#[test]
+fn test_restore_reports_corruption() {
+    let source = setup_db_with_key(TEST_KEY);
+    let tree = source.new_tree_default(TEST_KEY).unwrap();
+    commit_dict_value(&tree, "data", "first", "value");
+    commit_dict_value(&tree, "data", "second", "value");
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("backup.json");
+    source.backup(&path).unwrap();
+
+    let mut original = Vec::new();
+    let mut forged = None;
+    rewrite(&path, |backup| {
+        let entries = backup["trees"][0]["entries"].as_array_mut().unwrap();
+        for item in entries.iter() {
+            original.push(ID::from(item["id"].as_str().unwrap()));
+        }
+        // Damaged content no longer matches its ID
+        let data = &mut entries[1]["entry"]["subtrees"][0]["data"];
+        *data = Json::String(data.as_str().unwrap().replace("value", "damaged"));
+        // Forged content with a matching ID fails signature verification
+        let data = &mut entries[2]["entry"]["subtrees"][0]["data"];
+        *data = Json::String(data.as_str().unwrap().replace("value", "forged"));
+        let entry: Entry = serde_json::from_value(entries[2]["entry"].clone()).unwrap();
+        entries[2]["id"] = Json::String(entry.id().to_string());
+        forged = Some(entry.id());
+    });
+    let forged = forged.unwrap();
+
+    let target = empty_db();
+    let report = target.restore(&path).unwrap();
+    assert!(!report.is_clean());
+    assert_eq!(report.entries, 2);
+    assert_eq!(report.corrupt.len(), 2);
+    let damaged = &report.corrupt[0];
+    assert_eq!(damaged.tree, *tree.root_id());
+    assert_eq!(damaged.id, original[1]);
+    assert!(matches!(damaged.reason, Corruption::HashMismatch { .. }));
+    assert!(target.backend().get(&original[1]).is_err());
+
+    assert_eq!(report.corrupt[1].id, forged);
+    assert_eq!(report.corrupt[1].reason, Corruption::InvalidSignature);
+    assert_eq!(
+        target.backend().get_verification_status(&forged).unwrap(),
+        VerificationStatus::Failed
+    );
+}
+
+#[test]
+fn test_invalid_backups_are_rejected() {
+    let (source, _) = populate();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("backup.json");
+    let target = empty_db();
+
+    fs::write(&path, b"not a backup").unwrap();
+    assert_invalid_backup(target.restore(&path));
+
+    source.backup_with_keys(&path, "passphrase").unwrap();
+    rewrite(&path, |backup| {
+        backup["format"] = Json::String("eidetica-bundle".to_string());
+    });
+    assert_invalid_backup(target.restore(&path));
+
+    source.backup_with_keys(&path, "passphrase").unwrap();
+    rewrite(&path, |backup| backup["version"] = Json::from(99));
+    assert_invalid_backup(target.restore(&path));
+
+    // Tampered keys are detected like a wrong passphrase
+    source.backup_with_keys(&path, "passphrase").unwrap();
+    rewrite(&path, |backup| {
+        backup["keys"]["nonce"] = Json::String("AAAAAAAAAAAAAAAA".to_string());
+    });
+    assert_invalid_backup(target.restore_with_keys(&path, "passphrase"));
+
+    assert!(target.all_trees().unwrap().is_empty());
+    assert!(empty_db().restore(dir.path().join("missing.json")).is_err());
+}
@@ -0,0 +1,140 @@
+//! Tests for exporting and importing trees as bundles
+
+use crate::helpers::{commit_dict_value, setup_db_with_key};
+use eidetica::Tree;
+use eidetica::backend::VerificationStatus;
+use eidetica::basedb::{BaseDB, BaseError};
+use eidetica::subtree::Dict;
+use serde_json::Value as Json;
+
+const TEST_KEY: &str = "test_key";
+
+fn export(tree: &Tree) -> Vec<u8> {
+    let mut bundle = Vec::new();
+    tree.export_bundle(&mut bundle).unwrap();
+    bundle
+}
+
+fn status(db: &BaseDB, entry: &eidetica::entry::Entry) -> VerificationStatus {
+    db.backend().get_verification_status(&entry.id()).unwrap()
+}
+
+fn assert_invalid_bundle(db: &BaseDB, bundle: &[u8]) {
+    match db.import_bundle(bundle) {
+        Err(eidetica::Error::Base(BaseError::InvalidBundle { .. })) => {}
+        Err(e) => panic!("Expected an invalid bundle error, got {e:?}"),
+        Ok(_) => panic!("Expected an invalid bundle error"),
+    }
+}
+
+#[test]
+fn test_bundle_roundtrip() {
+    let source = setup_db_with_key(TEST_KEY);
+    let tree = source.new_tree_default(TEST_KEY).unwrap();
+    commit_dict_value(&tree, "data", "greeting", "hello");
+    commit_dict_value(&tree, "data", "farewell", "goodbye");
+
+    let mut bundle = Vec::new();
+    assert_eq!(tree.export_bundle(&mut bundle).unwrap(), 3);
+
+    // The target does not need the signing key to import and read the tree
+    let target = BaseDB::new(Box::new(eidetica::backend::database::InMemory::new()));
+    let imported = target.import_bundle(bundle.as_slice()).unwrap();
+    assert_eq!(imported.root_id(), tree.root_id());
+    assert_eq!(imported.get_tips().unwrap(), tree.get_tips().unwrap());
+    let data = imported.get_subtree_viewer::<Dict>("data").unwrap();
+    assert_eq!(data.get_string("greeting").unwrap(), "hello");
+    assert_eq!(data.get_string("farewell").unwrap(), "goodbye");
+    for entry in imported.get_all_entries().unwrap() {
+        assert_eq!(status(&target, &entry), VerificationStatus::Verified);
+    }
+}
+
+#[test]
+fn test_import_adds_only_missing_entries() {
+    let source = setup_db_with_key(TEST_KEY);
+    let tree = source.new_tree_default(TEST_KEY).unwrap();
+    commit_dict_value(&tree, "data", "key", "one");
+    let old_bundle = export(&tree);
+    commit_dict_value(&tree, "data", "key", "two");
+    let new_bundle = export(&tree);
+
+    let target = setup_db_with_key(TEST_KEY);
+    target.import_bundle(new_bundle.as_slice()).unwrap();
+    // Importing an older bundle of the same tree changes nothing
+    let imported = target.import_bundle(old_bundle.as_slice()).unwrap();
+    assert_eq!(imported.get_all_entries().unwrap().len(), 3);
+    let data = imported.get_subtree_viewer::<Dict>("data").unwrap();
+    assert_eq!(data.get_string("key").unwrap(), "two");
+}
+
+#[test]
+fn test_recorded_statuses_are_not_trusted() {
+    let source = setup_db_with_key(TEST_KEY);
+    let tree = source.new_tree_default(TEST_KEY).unwrap();
+    commit_dict_value(&tree, "data", "key", "value");
+    commit_dict_value(&tree, "data", "other", "value");
+
+    let mut bundle: Json = serde_json::from_slice(&export(&tree)).unwrap();
+    let entries = bundle["entries"].as_array_mut().unwrap();
+    // Tamper with the data of the second entry, invalidating its signature
+    let data = &mut entries[1]["entry"]["subtrees"][0]["data"];
+    *data = Json::String(data.as_str().unwrap().replace("value", "forged"));
+    // A valid entry recorded as failed stays failed
+    entries[2]["status"] = Json::String("Failed".to_string());
+
+    let target = setup_db_with_key(TEST_KEY);
+    let imported = target
+        .import_bundle(serde_json::to_vec(&bundle).unwrap().as_slice())
+        .unwrap();
+    let entries = imported.get_all_entries().unwrap();
+    assert_eq!(entries.len(), 3);
+    for entry in entries {
+        let expected = if entry.is_root() {
+            VerificationStatus::Verified
+        } else {
+            VerificationStatus::Failed
+        };
+        assert_eq!(status(&target, &entry), expected);
+    }
+}
+
+#[test]
+fn test_invalid_bundles_are_rejected() {
+    let source = setup_db_with_key(TEST_KEY);
+    let tree = source.new_tree_default(TEST_KEY).unwrap();
+    commit_dict_value(&tree, "data", "key", "value");
+    let bundle: Json = serde_json::from_slice(&export(&tree)).unwrap();
+    let target = setup_db_with_key(TEST_KEY);
+
+    assert_invalid_bundle(&target, b"not a bundle");
+
+    let mut wrong_format = bundle.clone();
+    wrong_format["format"] = Json::String("something-else".to_string());
+    assert_invalid_bundle(&target, &serde_json::to_vec(&wrong_format).unwrap());
+
+    let mut wrong_version = bundle.clone();
+    wrong_version["version"] = Json::from(99);
+    assert_invalid_bundle(&target, &serde_json::to_vec(&wrong_version).unwrap());
+
+    let mut missing_root = bundle.clone();
+    missing_root["entries"].as_array_mut().unwrap().remove(0);
+    assert_invalid_bundle(&target, &serde_json::to_vec(&missing_root).unwrap());
+
+    // Entries of another tree are refused
+    let other = source.new_tree_default(TEST_KEY).unwrap();
+    let other_bundle: Json = serde_json::from_slice(&export(&other)).unwrap();
+    let mut mixed = bundle.clone();
+    mixed["entries"]
+        .as_array_mut()
+        .unwrap()
+        .push(other_bundle["entries"][0].clone());
+    let result = target.import_bundle(serde_json::to_vec(&mixed).unwrap().as_slice());
+    assert!(matches!(
+        result,
+        Err(eidetica::Error::Base(BaseError::EntryNotInTree { .. }))
+    ));
+
+    // Nothing was imported by the failed attempts
+    assert!(target.load_tree(tree.root_id()).is_err());
+}
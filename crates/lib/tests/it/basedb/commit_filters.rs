@@ -0,0 +1,95 @@
+//! Tests for commit listeners registered with a `CommitFilter`
+
+use crate::helpers::{commit_dict_value, setup_db_with_key};
+use eidetica::basedb::{BaseDB, CommitEvent, CommitFilter};
+use eidetica::entry::ID;
+use eidetica::subtree::Dict;
+use std::sync::{Arc, Mutex};
+
+const TEST_KEY: &str = "test_key";
+
+/// Registers a filtered listener that records the entries it is called for.
+fn record(db: &BaseDB, filter: CommitFilter) -> Arc<Mutex<Vec<ID>>> {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let recorded = Arc::clone(&events);
+    db.on_commit_filtered(filter, move |event: &CommitEvent| {
+        recorded.lock().unwrap().push(event.entry.clone())
+    });
+    events
+}
+
+#[test]
+fn test_filter_by_tree() {
+    let db = setup_db_with_key(TEST_KEY);
+    let watched = db.new_tree_default(TEST_KEY).unwrap();
+    let other = db.new_tree_default(TEST_KEY).unwrap();
+    let events = record(&db, CommitFilter::new().tree(watched.root_id().clone()));
+
+    let entry = commit_dict_value(&watched, "data", "key", "value");
+    commit_dict_value(&other, "data", "key", "value");
+
+    assert_eq!(*events.lock().unwrap(), vec![entry]);
+}
+
+#[test]
+fn test_filter_by_author() {
+    let db = setup_db_with_key(TEST_KEY);
+    db.add_private_key("other_key").unwrap();
+    let events = record(&db, CommitFilter::new().author("other_key"));
+
+    let mine = db.new_tree_default(TEST_KEY).unwrap();
+    commit_dict_value(&mine, "data", "key", "value");
+    let theirs = db.new_tree_default("other_key").unwrap();
+    let entry = commit_dict_value(&theirs, "data", "key", "value");
+
+    assert_eq!(
+        *events.lock().unwrap(),
+        vec![theirs.root_id().clone(), entry]
+    );
+}
+
+#[test]
+fn test_filter_by_subtree_and_key_prefix() {
+    let db = setup_db_with_key(TEST_KEY);
+    let tree = db.new_tree_default(TEST_KEY).unwrap();
+    let by_subtree = record(&db, CommitFilter::new().subtree("users_*"));
+    let by_key = record(
+        &db,
+        CommitFilter::new().subtree("users_*").key_prefix("user:"),
+    );
+
+    let eu_user = commit_dict_value(&tree, "users_eu", "user:1", "alice");
+    let us_admin = commit_dict_value(&tree, "users_us", "admin:1", "bob");
+    commit_dict_value(&tree, "logs", "user:1", "logged in");
+
+    // Deleting a matching key is also a change to it
+    let op = tree.new_operation().unwrap();
+    op.get_subtree::<Dict>("users_eu")
+        .unwrap()
+        .delete("user:1")
+        .unwrap();
+    let deletion = op.commit().unwrap();
+
+    assert_eq!(
+        *by_subtree.lock().unwrap(),
+        vec![eu_user.clone(), us_admin, deletion.clone()]
+    );
+    assert_eq!(*by_key.lock().unwrap(), vec![eu_user, deletion]);
+}
+
+#[test]
+fn test_filtered_listener_can_be_removed() {
+    let db = setup_db_with_key(TEST_KEY);
+    let tree = db.new_tree_default(TEST_KEY).unwrap();
+    let events = Arc::new(Mutex::new(0));
+    let counted = Arc::clone(&events);
+    let id = db.on_commit_filtered(CommitFilter::new().subtree("data"), move |_| {
+        *counted.lock().unwrap() += 1
+    });
+
+    commit_dict_value(&tree, "data", "key", "one");
+    assert!(db.remove_commit_listener(id));
+    commit_dict_value(&tree, "data", "key", "two");
+
+    assert_eq!(*events.lock().unwrap(), 1);
+}
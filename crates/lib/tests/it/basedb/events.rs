@@ -0,0 +1,92 @@
+//! Database event bus tests
+//!
+//! Tests for `BaseDB::events`: the events published for new trees and commits,
+//! sharing the bus between clones and loaded trees, unsubscribing by dropping
+//! a receiver, and counting the events a full receiver misses.
+
+use crate::helpers::{commit_dict_value, setup_db_with_key};
+use eidetica::basedb::DatabaseEvent;
+use std::time::Duration;
+
+const TEST_KEY: &str = "test_key";
+
+#[test]
+fn test_tree_and_commit_events() {
+    let db = setup_db_with_key(TEST_KEY);
+    let events = db.events();
+    assert_eq!(events.try_recv(), None);
+
+    let tree = db.new_tree_default(TEST_KEY).unwrap();
+    let entry = commit_dict_value(&tree, "data", "value", "one");
+
+    // Trees loaded later, and clones of the database, share the bus
+    let mut loaded = db.clone().load_tree(tree.root_id()).unwrap();
+    loaded.set_default_auth_key(TEST_KEY);
+    let loaded_entry = commit_dict_value(&loaded, "data", "value", "two");
+
+    let root = tree.root_id().clone();
+    assert_eq!(
+        events.try_iter().collect::<Vec<_>>(),
+        vec![
+            DatabaseEvent::TreeCreated { tree: root.clone() },
+            DatabaseEvent::EntryCommitted {
+                tree: root.clone(),
+                entry: root.clone(),
+            },
+            DatabaseEvent::EntryCommitted {
+                tree: root.clone(),
+                entry,
+            },
+            DatabaseEvent::EntryCommitted {
+                tree: root.clone(),
+                entry: loaded_entry,
+            },
+        ]
+    );
+    assert_eq!(events.missed(), 0);
+
+    // Events are received on other threads
+    let waiter = std::thread::spawn(move || events.recv_timeout(Duration::from_secs(5)));
+    let entry = commit_dict_value(&tree, "data", "value", "three");
+    let event = waiter.join().unwrap().unwrap();
+    assert_eq!(event.tree(), &root);
+    assert_eq!(event, DatabaseEvent::EntryCommitted { tree: root, entry });
+}
+
+#[test]
+fn test_event_receivers() {
+    let db = setup_db_with_key(TEST_KEY);
+    let first = db.events();
+    let small = db.event_bus().subscribe_with_capacity(2);
+    let tree = db.new_tree_default(TEST_KEY).unwrap();
+
+    // Receivers only get events published after they subscribed
+    let late = db.events();
+    commit_dict_value(&tree, "data", "value", "one");
+    commit_dict_value(&tree, "data", "value", "two");
+    assert_eq!(first.try_iter().count(), 4);
+    assert_eq!(late.try_iter().count(), 2);
+
+    // A full receiver drops and counts events without holding up commits
+    assert_eq!(small.missed(), 2);
+    assert_eq!(
+        small.try_recv(),
+        Some(DatabaseEvent::TreeCreated {
+            tree: tree.root_id().clone()
+        })
+    );
+    commit_dict_value(&tree, "data", "value", "three");
+    assert_eq!(small.try_iter().count(), 2);
+    assert_eq!(small.missed(), 2);
+
+    // Dropped receivers are unsubscribed
+    drop(first);
+    drop(small);
+    commit_dict_value(&tree, "data", "value", "four");
+    assert_eq!(late.try_iter().count(), 2);
+
+    // Receivers end once the database and its trees are gone
+    drop(db);
+    drop(tree);
+    assert_eq!(late.recv(), None);
+}
@@ -1,11 +1,23 @@
 //! BaseDB integration tests
 //!
 //! This module tests BaseDB functionality including database operations, tree management,
-//! settings configuration, and basic operations. Tests are organized by functional area
+//! settings configuration, tree bundles, whole-database backups, commit events, filtered commit listeners, the database event bus, automatic persistence, opening stored databases, security audits, read audit logs, the subtree state cache, creating configured trees with the tree builder, and basic operations. Tests are organized by functional area
 //! for better maintainability.
 
+#[cfg(feature = "async")]
+mod async_api;
+mod backup;
 mod basic_operations;
+mod bundles;
+mod commit_filters;
 mod database_operations;
+mod events;
 mod helpers;
+mod open;
+mod persistence;
+mod read_log;
+mod security_audit;
 mod settings_operations;
+mod state_cache;
+mod tree_builder;
 mod tree_management;
@@ -0,0 +1,159 @@
+//! Tests for `BaseDB::open` and storage format detection
+
+use eidetica::Tree;
+use eidetica::backend::database::{InMemory, StorageFormat};
+use eidetica::basedb::BaseDB;
+use eidetica::entry::ID;
+use eidetica::subtree::Dict;
+use std::fs;
+use std::path::Path;
+
+const TEST_KEY: &str = "test_key";
+
+/// Creates a database with the test key and one tree holding a value.
+fn populate(db: &BaseDB) -> ID {
+    db.add_private_key(TEST_KEY).unwrap();
+    let tree = db.new_tree_default(TEST_KEY).unwrap();
+    let op = tree.new_operation().unwrap();
+    op.get_subtree::<Dict>("data")
+        .unwrap()
+        .set("key", "value")
+        .unwrap();
+    op.commit().unwrap();
+    tree.root_id().clone()
+}
+
+fn assert_value(tree: &Tree) {
+    let dict = tree.get_subtree_viewer::<Dict>("data").unwrap();
+    assert_eq!(dict.get_string("key").unwrap(), "value");
+}
+
+/// Opens `path` and checks that it has the tree written by `populate`.
+fn assert_opens(path: &Path, format: StorageFormat, root: &ID) -> BaseDB {
+    assert_eq!(StorageFormat::detect(path).unwrap(), Some(format));
+    let db = BaseDB::open(path).unwrap();
+    let trees = db.all_trees().unwrap();
+    assert!(trees.iter().any(|tree| tree.root_id() == root));
+    assert_value(&db.load_tree(root).unwrap());
+    db
+}
+
+#[test]
+fn test_open_missing_path_creates_empty_database() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("missing.db");
+
+    assert_eq!(StorageFormat::detect(&path).unwrap(), None);
+    let db = BaseDB::open(&path).unwrap();
+    assert!(db.all_trees().unwrap().is_empty());
+    assert!(db.backend().as_any().is::<InMemory>());
+}
+
+#[test]
+fn test_open_snapshot() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.json");
+
+    let db = BaseDB::new(Box::new(InMemory::new()));
+    let root = populate(&db);
+    let backend = db.backend().as_any().downcast_ref::<InMemory>().unwrap();
+    backend.save_to_file(&path).unwrap();
+
+    // Keys are restored along with the trees, so the database is writable
+    let db = assert_opens(&path, StorageFormat::Snapshot, &root);
+    let mut tree = db.load_tree(&root).unwrap();
+    tree.set_default_auth_key(TEST_KEY);
+    let op = tree.new_operation().unwrap();
+    op.get_subtree::<Dict>("data")
+        .unwrap()
+        .set("other", "value")
+        .unwrap();
+    op.commit().unwrap();
+}
+
+#[test]
+fn test_open_journal() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.journal");
+
+    let root = {
+        let db = BaseDB::new(Box::new(InMemory::open_with_journal(&path).unwrap()));
+        populate(&db)
+    };
+
+    let db = assert_opens(&path, StorageFormat::Journal, &root);
+    let backend = db.backend().as_any().downcast_ref::<InMemory>().unwrap();
+    assert_eq!(backend.journal_path(), Some(path.clone()));
+}
+
+#[test]
+fn test_open_empty_file_as_journal() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("empty.journal");
+    fs::write(&path, b"").unwrap();
+
+    assert_eq!(
+        StorageFormat::detect(&path).unwrap(),
+        Some(StorageFormat::Journal)
+    );
+    let db = BaseDB::open(&path).unwrap();
+    assert!(db.all_trees().unwrap().is_empty());
+}
+
+#[test]
+fn test_open_invalid_snapshot_fails() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("garbage.db");
+    fs::write(&path, b"not a database").unwrap();
+
+    assert!(BaseDB::open(&path).is_err());
+}
+
+#[cfg(feature = "compression")]
+#[test]
+fn test_open_compressed_snapshot() {
+    use eidetica::backend::Compression;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.lz4");
+
+    let db = BaseDB::new(Box::new(InMemory::new().with_compression(Compression::Lz4)));
+    let root = populate(&db);
+    let backend = db.backend().as_any().downcast_ref::<InMemory>().unwrap();
+    backend.save_to_file(&path).unwrap();
+
+    assert_opens(&path, StorageFormat::Snapshot, &root);
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn test_open_sqlite() {
+    use eidetica::backend::database::Sqlite;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.sqlite");
+
+    let root = {
+        let db = BaseDB::new(Box::new(Sqlite::open(&path).unwrap()));
+        populate(&db)
+    };
+
+    let db = assert_opens(&path, StorageFormat::Sqlite, &root);
+    assert!(db.backend().as_any().is::<Sqlite>());
+}
+
+#[cfg(feature = "archive")]
+#[test]
+fn test_open_archive() {
+    use eidetica::backend::database::Archive;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.archive");
+
+    let db = BaseDB::new(Box::new(InMemory::new()));
+    let root = populate(&db);
+    Archive::export(db.backend().as_ref(), &path).unwrap();
+
+    let db = assert_opens(&path, StorageFormat::Archive, &root);
+    assert!(db.backend().as_any().is::<Archive>());
+}
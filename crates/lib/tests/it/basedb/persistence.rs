@@ -0,0 +1,249 @@
+//! Commit event and automatic persistence tests
+//!
+//! This module contains tests for `BaseDB::on_commit` notifications and the
+//! debounced flushing performed by `AutoPersist`, and the flushing done by
+//! `PersistGuard` on drop and panic.
+
+use crate::helpers::{commit_dict_value, setup_db_with_key};
+use eidetica::backend::database::InMemory;
+use eidetica::basedb::{AutoPersist, AutoPersistConfig, BaseDB, CommitEvent};
+use eidetica::subtree::Dict;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::sleep;
+use std::time::Duration;
+
+const TEST_KEY: &str = "test_key";
+
+/// Starts an `AutoPersist` that counts its flushes.
+fn counting_persist(db: &BaseDB, config: AutoPersistConfig) -> (AutoPersist, Arc<AtomicUsize>) {
+    let flushes = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&flushes);
+    let persist = AutoPersist::new(db, config, move || {
+        counter.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    });
+    (persist, flushes)
+}
+
+#[test]
+fn test_commit_events() {
+    let db = setup_db_with_key(TEST_KEY);
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let recorded = Arc::clone(&events);
+    let listener = db.on_commit(move |event| recorded.lock().unwrap().push(event.clone()));
+
+    let tree = db.new_tree_default(TEST_KEY).unwrap();
+    let entry = commit_dict_value(&tree, "data", "value", "one");
+
+    // Trees loaded later, and clones of the database, share the listeners
+    let mut loaded = db.clone().load_tree(tree.root_id()).unwrap();
+    loaded.set_default_auth_key(TEST_KEY);
+    let loaded_entry = commit_dict_value(&loaded, "data", "value", "two");
+
+    let root = tree.root_id().clone();
+    assert_eq!(
+        *events.lock().unwrap(),
+        vec![
+            CommitEvent {
+                tree: root.clone(),
+                entry: root.clone(),
+            },
+            CommitEvent {
+                tree: root.clone(),
+                entry,
+            },
+            CommitEvent {
+                tree: root.clone(),
+                entry: loaded_entry,
+            },
+        ]
+    );
+
+    assert!(db.remove_commit_listener(listener));
+    assert!(!db.remove_commit_listener(listener));
+    commit_dict_value(&tree, "data", "value", "three");
+    assert_eq!(events.lock().unwrap().len(), 3);
+}
+
+#[test]
+fn test_autopersist_debounces_commits() {
+    let db = setup_db_with_key(TEST_KEY);
+    let tree = db.new_tree_default(TEST_KEY).unwrap();
+    let config = AutoPersistConfig {
+        debounce: Duration::from_millis(100),
+        max_interval: Duration::from_secs(60),
+    };
+    let (persist, flushes) = counting_persist(&db, config);
+    assert!(!persist.is_dirty());
+
+    for i in 0..5 {
+        commit_dict_value(&tree, "data", "value", &i.to_string());
+    }
+    assert!(persist.is_dirty());
+
+    // A burst of commits is flushed once, after the debounce period
+    sleep(Duration::from_millis(500));
+    assert_eq!(flushes.load(Ordering::SeqCst), 1);
+    assert!(!persist.is_dirty());
+
+    // Nothing is pending, so shutting down does not flush again
+    persist.shutdown().unwrap();
+    assert_eq!(flushes.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_autopersist_max_interval() {
+    let db = setup_db_with_key(TEST_KEY);
+    let tree = db.new_tree_default(TEST_KEY).unwrap();
+    let config = AutoPersistConfig {
+        debounce: Duration::from_secs(60),
+        max_interval: Duration::from_millis(150),
+    };
+    let (persist, flushes) = counting_persist(&db, config);
+
+    // Commits keep arriving within the debounce period, yet flushes still happen
+    for i in 0..10 {
+        commit_dict_value(&tree, "data", "value", &i.to_string());
+        sleep(Duration::from_millis(50));
+    }
+    sleep(Duration::from_millis(300));
+    let flushed = flushes.load(Ordering::SeqCst);
+    assert!(flushed >= 2, "expected periodic flushes, got {flushed}");
+    drop(persist);
+}
+
+#[test]
+fn test_autopersist_flushes_on_shutdown_and_notify() {
+    let db = setup_db_with_key(TEST_KEY);
+    let tree = db.new_tree_default(TEST_KEY).unwrap();
+    let config = AutoPersistConfig {
+        debounce: Duration::from_secs(60),
+        max_interval: Duration::from_secs(60),
+    };
+    let (persist, flushes) = counting_persist(&db, config);
+
+    commit_dict_value(&tree, "data", "value", "pending");
+    persist.flush().unwrap();
+    assert_eq!(flushes.load(Ordering::SeqCst), 1);
+
+    // Changes outside of commits can be recorded explicitly
+    persist.notify();
+    assert!(persist.is_dirty());
+    drop(persist);
+    assert_eq!(flushes.load(Ordering::SeqCst), 2);
+
+    // Once stopped, commits no longer trigger flushes
+    commit_dict_value(&tree, "data", "value", "after");
+    assert_eq!(flushes.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn test_autopersist_failed_flush_is_retried() {
+    let db = setup_db_with_key(TEST_KEY);
+    let tree = db.new_tree_default(TEST_KEY).unwrap();
+    let fail = Arc::new(std::sync::atomic::AtomicBool::new(true));
+    let should_fail = Arc::clone(&fail);
+    let config = AutoPersistConfig {
+        debounce: Duration::from_secs(60),
+        max_interval: Duration::from_secs(60),
+    };
+    let persist = AutoPersist::new(&db, config, move || {
+        if should_fail.load(Ordering::SeqCst) {
+            Err(std::io::Error::other("disk full").into())
+        } else {
+            Ok(())
+        }
+    });
+
+    commit_dict_value(&tree, "data", "value", "value");
+    assert!(persist.flush().is_err());
+    assert!(persist.is_dirty());
+
+    fail.store(false, Ordering::SeqCst);
+    persist.flush().unwrap();
+    assert!(!persist.is_dirty());
+}
+
+#[test]
+fn test_autopersist_to_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("autopersist.json");
+    let db = setup_db_with_key(TEST_KEY);
+    let persist = AutoPersist::to_file(&db, &path, AutoPersistConfig::default()).unwrap();
+
+    let tree = db.new_tree_default(TEST_KEY).unwrap();
+    commit_dict_value(&tree, "data", "value", "saved");
+    persist.shutdown().unwrap();
+
+    let reloaded = BaseDB::new(Box::new(InMemory::load_from_file(&path).unwrap()));
+    let tree = reloaded.load_tree(tree.root_id()).unwrap();
+    let data = tree.get_subtree_viewer::<Dict>("data").unwrap();
+    assert_eq!(data.get_string("value").unwrap(), "saved");
+}
+
+fn journal_db(path: &std::path::Path) -> BaseDB {
+    let db = BaseDB::new(Box::new(InMemory::open_with_journal(path).unwrap()));
+    db.add_private_key(TEST_KEY).unwrap();
+    db
+}
+
+#[test]
+fn test_persist_guard_flushes_journal() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("guarded.journal");
+
+    let root_id = {
+        let db = journal_db(&path);
+        let guard = db.persist_guard();
+        let tree = db.new_tree_default(TEST_KEY).unwrap();
+        commit_dict_value(&tree, "data", "value", "guarded");
+        guard.flush().unwrap();
+        tree.root_id().clone()
+    };
+
+    let reopened = BaseDB::new(Box::new(InMemory::open_with_journal(&path).unwrap()));
+    let mut tree = reopened.load_tree(&root_id).unwrap();
+    tree.set_default_auth_key(TEST_KEY);
+    let data = tree.get_subtree_viewer::<Dict>("data").unwrap();
+    assert_eq!(data.get_string("value").unwrap(), "guarded");
+}
+
+#[test]
+fn test_persist_guard_without_durable_backend() {
+    // Nothing to flush for a plain in-memory database, but the guard still works
+    let db = setup_db_with_key(TEST_KEY);
+    let guard = db.persist_guard();
+    let tree = db.new_tree_default(TEST_KEY).unwrap();
+    commit_dict_value(&tree, "data", "value", "value");
+    guard.flush().unwrap();
+}
+
+#[test]
+fn test_persist_guard_survives_panic() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("panic.journal");
+    let db = journal_db(&path);
+    let guard = db.persist_guard();
+    let tree = db.new_tree_default(TEST_KEY).unwrap();
+
+    // A panic while committing runs the hook without deadlocking, and the
+    // guard and database stay usable afterwards
+    let panicking = tree.clone();
+    let result = std::thread::spawn(move || {
+        commit_dict_value(&panicking, "data", "value", "before panic");
+        panic!("simulated crash");
+    })
+    .join();
+    assert!(result.is_err());
+
+    commit_dict_value(&tree, "data", "value", "after panic");
+    guard.flush().unwrap();
+    drop(guard);
+
+    let reopened = BaseDB::new(Box::new(InMemory::open_with_journal(&path).unwrap()));
+    let mut tree = reopened.load_tree(tree.root_id()).unwrap();
+    tree.set_default_auth_key(TEST_KEY);
+    let data = tree.get_subtree_viewer::<Dict>("data").unwrap();
+    assert_eq!(data.get_string("value").unwrap(), "after panic");
+}
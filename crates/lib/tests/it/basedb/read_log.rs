@@ -0,0 +1,133 @@
+//! Read audit log tests
+//!
+//! Tests for `BaseDB::enable_read_log`: which reads are recorded, the ring
+//! buffer capacity, and the privacy controls of `ReadLogConfig`.
+
+use crate::helpers::{commit_dict_value, setup_db_and_tree_with_key};
+use eidetica::basedb::{ReadLogConfig, ReaderIdentity};
+use eidetica::query::{Params, Query};
+use eidetica::subtree::Dict;
+
+const TEST_KEY: &str = "test_key";
+
+#[test]
+fn test_read_log_is_opt_in() {
+    let (db, tree) = setup_db_and_tree_with_key(TEST_KEY);
+    commit_dict_value(&tree, "data", "key", "value");
+    assert!(!db.is_read_log_enabled());
+
+    tree.get_subtree_viewer::<Dict>("data").unwrap();
+    assert!(db.read_log().is_empty());
+
+    db.enable_read_log(ReadLogConfig::default());
+    assert!(db.is_read_log_enabled());
+    tree.get_subtree_viewer::<Dict>("data").unwrap();
+    let records = db.read_log();
+    assert_eq!(records.len(), 1);
+    assert_eq!(&records[0].tree, tree.root_id());
+    assert_eq!(records[0].subtree, "data");
+    assert_eq!(records[0].reader.as_deref(), Some(TEST_KEY));
+
+    db.disable_read_log();
+    assert!(!db.is_read_log_enabled());
+    assert!(db.read_log().is_empty());
+}
+
+#[test]
+fn test_read_log_skips_writes_and_internal_reads() {
+    let (db, tree) = setup_db_and_tree_with_key(TEST_KEY);
+    db.enable_read_log(ReadLogConfig::default());
+
+    // Commits validate against `_settings`, and writing is not a read
+    commit_dict_value(&tree, "data", "key", "value");
+    tree.get_settings().unwrap();
+    assert!(db.read_log().is_empty());
+
+    db.enable_read_log(ReadLogConfig {
+        include_internal: true,
+        ..Default::default()
+    });
+    tree.get_settings().unwrap();
+    assert_eq!(db.read_log()[0].subtree, "_settings");
+}
+
+#[test]
+fn test_read_log_is_shared_with_loaded_trees() {
+    let (db, tree) = setup_db_and_tree_with_key(TEST_KEY);
+    commit_dict_value(&tree, "data", "key", "value");
+    db.enable_read_log(ReadLogConfig::default());
+
+    let loaded = db.load_tree(tree.root_id()).unwrap();
+    loaded.get_subtree_viewer::<Dict>("data").unwrap();
+    let tips = tree.get_tips().unwrap();
+    tree.get_subtree_viewer_at::<Dict>("data", &tips).unwrap();
+
+    let records = db.clone().take_read_log();
+    assert_eq!(records.len(), 2);
+    // A loaded tree has no default key until one is set
+    assert_eq!(records[0].reader, None);
+    assert_eq!(records[1].reader.as_deref(), Some(TEST_KEY));
+    assert!(records[0].time <= records[1].time);
+    assert!(db.read_log().is_empty());
+}
+
+#[test]
+fn test_read_log_records_each_query_execution() {
+    let (db, tree) = setup_db_and_tree_with_key(TEST_KEY);
+    commit_dict_value(&tree, "data", "key", "value");
+    db.enable_read_log(ReadLogConfig::default());
+
+    let prepared = tree.prepare(&Query::subtree("data")).unwrap();
+    assert!(db.read_log().is_empty());
+    for _ in 0..3 {
+        assert_eq!(prepared.execute(&Params::new()).unwrap().len(), 1);
+    }
+    assert_eq!(db.read_log().len(), 3);
+}
+
+#[test]
+fn test_read_log_capacity_drops_oldest() {
+    let (db, tree) = setup_db_and_tree_with_key(TEST_KEY);
+    db.enable_read_log(ReadLogConfig {
+        capacity: 2,
+        ..Default::default()
+    });
+
+    for subtree in ["first", "second", "third"] {
+        tree.get_subtree_viewer::<Dict>(subtree).unwrap();
+    }
+    let subtrees: Vec<String> = db.read_log().into_iter().map(|r| r.subtree).collect();
+    assert_eq!(subtrees, ["second", "third"]);
+}
+
+#[test]
+fn test_read_log_privacy_controls() {
+    let (db, tree) = setup_db_and_tree_with_key(TEST_KEY);
+    db.enable_read_log(ReadLogConfig {
+        subtrees: vec!["patients*".to_string()],
+        reader: ReaderIdentity::Pseudonym,
+        ..Default::default()
+    });
+
+    for subtree in ["patients", "notes", "patients_archive", "patients"] {
+        tree.get_subtree_viewer::<Dict>(subtree).unwrap();
+    }
+    let records = db.read_log();
+    assert_eq!(records.len(), 3);
+    assert!(records.iter().all(|r| r.subtree.starts_with("patients")));
+    let pseudonym = records[0].reader.clone().unwrap();
+    assert_ne!(pseudonym, TEST_KEY);
+    assert!(!pseudonym.contains(TEST_KEY));
+    assert!(
+        records
+            .iter()
+            .all(|r| r.reader.as_ref() == Some(&pseudonym))
+    );
+
+    db.enable_read_log(ReadLogConfig {
+        reader: ReaderIdentity::Omit,
+        ..Default::default()
+    });
+    tree.get_subtree_viewer::<Dict>("patients").unwrap();
+    assert_eq!(db.read_log()[0].reader, None);
+}
@@ -0,0 +1,70 @@
+//! Security audit tests
+//!
+//! Tests for `BaseDB::security_audit`: the reported key storage mode and
+//! validation configuration, per-tree key counts, and the resulting warnings.
+
+use crate::auth::helpers::{setup_authenticated_tree, setup_test_db_with_keys};
+use crate::helpers::setup_db_and_tree_with_key;
+use eidetica::auth::types::{KeyStatus, Permission};
+use eidetica::backend::KeyStorage;
+use eidetica::backend::database::InMemory;
+use eidetica::basedb::BaseDB;
+
+const TEST_KEY: &str = "test_key";
+
+#[test]
+fn test_security_audit_defaults() {
+    let (db, tree) = setup_db_and_tree_with_key(TEST_KEY);
+    let audit = db.security_audit().unwrap();
+
+    assert_eq!(audit.key_storage, KeyStorage::Memory);
+    assert_eq!(audit.private_keys, 1);
+    assert!(audit.keys_zeroized);
+    assert!(audit.strict_signatures);
+    assert_eq!(audit.failed_entries, 0);
+
+    assert_eq!(audit.trees.len(), 1);
+    let tree_audit = &audit.trees[0];
+    assert_eq!(&tree_audit.root, tree.root_id());
+    assert_eq!(tree_audit.active_keys, 1);
+    assert_eq!(tree_audit.admin_keys, 1);
+    assert_eq!(tree_audit.inactive_keys, 0);
+    assert!(tree_audit.auth_configured());
+
+    assert!(audit.warnings().is_empty());
+}
+
+#[test]
+fn test_security_audit_counts_revoked_keys() {
+    let keys = [
+        ("admin", Permission::Admin(0), KeyStatus::Active),
+        ("writer", Permission::Write(10), KeyStatus::Active),
+        ("reader", Permission::Read, KeyStatus::Active),
+    ];
+    let (db, public_keys) = setup_test_db_with_keys(&keys);
+    let tree = setup_authenticated_tree(&db, &keys, &public_keys);
+    tree.revoke_key("writer").unwrap();
+
+    let audit = db.security_audit().unwrap();
+    let tree_audit = &audit.trees[0];
+    assert_eq!(tree_audit.active_keys, 2);
+    assert_eq!(tree_audit.admin_keys, 1);
+    assert_eq!(tree_audit.inactive_keys, 1);
+    assert_eq!(tree_audit.delegations, 0);
+    assert_eq!(audit.private_keys, 3);
+}
+
+#[test]
+fn test_security_audit_warns_about_unencrypted_keys() {
+    let dir = tempfile::tempdir().unwrap();
+    let backend = InMemory::open_with_journal(dir.path().join("db.journal")).unwrap();
+    let db = BaseDB::new(Box::new(backend));
+    db.add_private_key(TEST_KEY).unwrap();
+
+    let audit = db.security_audit().unwrap();
+    assert_eq!(audit.key_storage, KeyStorage::Unencrypted);
+    assert_eq!(
+        audit.warnings(),
+        vec!["private keys are stored unencrypted".to_string()]
+    );
+}
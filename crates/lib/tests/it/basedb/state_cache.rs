@@ -0,0 +1,107 @@
+//! Subtree state cache tests
+//!
+//! Tests for the cache of merged subtree states shared through `BaseDB`: hits
+//! on repeated reads, invalidation on commit, merged tips, and capacity.
+
+use crate::helpers::{commit_dict_value, setup_db_and_tree_with_key};
+use eidetica::Tree;
+use eidetica::subtree::Dict;
+
+const TEST_KEY: &str = "test_key";
+
+fn read(tree: &Tree, subtree: &str, key: &str) -> String {
+    tree.get_subtree_viewer::<Dict>(subtree)
+        .unwrap()
+        .get_string(key)
+        .unwrap()
+}
+
+#[test]
+fn test_repeated_reads_hit_the_cache() {
+    let (db, tree) = setup_db_and_tree_with_key(TEST_KEY);
+    commit_dict_value(&tree, "data", "key", "value");
+
+    assert_eq!(read(&tree, "data", "key"), "value");
+    let before = db.state_cache_stats();
+    assert!(before.entries > 0);
+
+    assert_eq!(read(&tree, "data", "key"), "value");
+    let after = db.state_cache_stats();
+    assert!(after.hits > before.hits);
+    assert_eq!(after.misses, before.misses);
+
+    // Clones of the database and trees loaded again share the cache
+    let loaded = db.clone().load_tree(tree.root_id()).unwrap();
+    assert_eq!(read(&loaded, "data", "key"), "value");
+    assert_eq!(db.state_cache_stats().misses, before.misses);
+}
+
+#[test]
+fn test_commit_invalidates_the_tree() {
+    let (db, tree) = setup_db_and_tree_with_key(TEST_KEY);
+    let other = db.new_tree_default(TEST_KEY).unwrap();
+    commit_dict_value(&tree, "data", "key", "old");
+    commit_dict_value(&other, "data", "key", "other");
+
+    assert_eq!(read(&tree, "data", "key"), "old");
+    assert_eq!(read(&other, "data", "key"), "other");
+    let cached = db.state_cache_stats().entries;
+
+    // Only the states of the tree committed to are dropped
+    commit_dict_value(&tree, "data", "key", "new");
+    let entries = db.state_cache_stats().entries;
+    assert!(entries > 0 && entries < cached);
+    assert_eq!(read(&tree, "data", "key"), "new");
+
+    db.clear_state_cache();
+    assert_eq!(db.state_cache_stats().entries, 0);
+    assert_eq!(read(&other, "data", "key"), "other");
+}
+
+#[test]
+fn test_merged_tips_are_cached() {
+    let (db, tree) = setup_db_and_tree_with_key(TEST_KEY);
+    commit_dict_value(&tree, "data", "base", "value");
+
+    // Two concurrent changes leave the subtree with two tips
+    let tips = tree.get_tips().unwrap();
+    for (key, value) in [("left", "1"), ("right", "2")] {
+        let op = tree.new_operation_with_tips(&tips).unwrap();
+        op.get_subtree::<Dict>("data")
+            .unwrap()
+            .set(key, value)
+            .unwrap();
+        op.commit().unwrap();
+    }
+    assert_eq!(tree.get_tips().unwrap().len(), 2);
+
+    assert_eq!(read(&tree, "data", "left"), "1");
+    let before = db.state_cache_stats();
+    assert_eq!(read(&tree, "data", "right"), "2");
+    assert_eq!(read(&tree, "data", "base"), "value");
+    let after = db.state_cache_stats();
+    assert_eq!(after.misses, before.misses);
+    assert!(after.hits >= before.hits + 2);
+}
+
+#[test]
+fn test_capacity() {
+    let (db, tree) = setup_db_and_tree_with_key(TEST_KEY);
+    commit_dict_value(&tree, "a", "key", "1");
+    commit_dict_value(&tree, "b", "key", "2");
+    assert_eq!(db.state_cache_stats().capacity, 64);
+
+    db.set_state_cache_capacity(1);
+    read(&tree, "a", "key");
+    read(&tree, "b", "key");
+    assert_eq!(db.state_cache_stats().entries, 1);
+
+    // A capacity of 0 turns the cache off
+    db.set_state_cache_capacity(0);
+    assert_eq!(db.state_cache_stats().entries, 0);
+    let hits = db.state_cache_stats().hits;
+    assert_eq!(read(&tree, "b", "key"), "2");
+    assert_eq!(read(&tree, "b", "key"), "2");
+    assert_eq!(db.state_cache_stats().hits, hits);
+    assert_eq!(db.state_cache_stats().entries, 0);
+}
@@ -0,0 +1,111 @@
+//! Tests for creating configured trees with `BaseDB::tree_builder`
+//!
+//! Checks that the name, description, custom settings, hash algorithm and
+//! default key set on the builder are all written in the tree's root entry, and
+//! that creating a tree without a usable key fails.
+
+use eidetica::auth::types::Permission;
+use eidetica::backend::database::InMemory;
+use eidetica::basedb::BaseDB;
+use eidetica::constants::SETTINGS;
+use eidetica::entry::HashAlgorithm;
+
+const KEY: &str = "k1";
+
+fn setup_db() -> BaseDB {
+    let db = BaseDB::new(Box::new(InMemory::new()));
+    db.add_private_key(KEY).unwrap();
+    db
+}
+
+#[test]
+fn test_builder_settings_are_in_the_root_entry() {
+    let db = setup_db();
+    let tree = db
+        .tree_builder()
+        .name("notes")
+        .description("Meeting notes")
+        .default_key(KEY)
+        .setting("feature", "x")
+        .setting("limit", 10)
+        .create()
+        .unwrap();
+
+    // The tree is configured without any commit after the root
+    assert_eq!(tree.get_tips().unwrap(), vec![tree.root_id().clone()]);
+    let root = tree.get_root().unwrap();
+    let stored = root.data(SETTINGS).unwrap();
+    assert!(stored.contains("notes"));
+    assert!(stored.contains("feature"));
+
+    let settings = tree.settings().unwrap();
+    assert_eq!(settings.name(), Some("notes"));
+    assert_eq!(settings.description(), Some("Meeting notes"));
+    assert_eq!(
+        settings.custom("feature").and_then(|v| v.as_text()),
+        Some("x")
+    );
+    assert_eq!(settings.custom("limit").and_then(|v| v.as_int()), Some(10));
+    assert_eq!(tree.default_auth_key(), Some(KEY));
+
+    // The root key is the tree's admin, and the tree can be found by name
+    let auth = tree.auth_state_at(tree.root_id()).unwrap();
+    let key = auth.get_key(KEY).unwrap().unwrap();
+    assert_eq!(key.permissions, Permission::Admin(0));
+    let found = db.find_tree("notes").unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].root_id(), tree.root_id());
+}
+
+#[test]
+fn test_builder_hash_algorithm() {
+    let db = setup_db();
+    let tree = db
+        .tree_builder()
+        .default_key(KEY)
+        .hash_algorithm(HashAlgorithm::Sha256)
+        .create()
+        .unwrap();
+    assert_eq!(tree.root_id().hash_algorithm(), Some(HashAlgorithm::Sha256));
+    assert_eq!(
+        tree.get_root().unwrap().hash_algorithm(),
+        HashAlgorithm::Sha256
+    );
+}
+
+#[test]
+fn test_identical_builders_create_distinct_trees() {
+    let db = setup_db();
+    let create = || {
+        db.tree_builder()
+            .name("same")
+            .default_key(KEY)
+            .create()
+            .unwrap()
+    };
+    let first = create();
+    let second = create();
+    assert_ne!(first.root_id(), second.root_id());
+    assert_eq!(db.find_tree("same").unwrap().len(), 2);
+}
+
+#[test]
+fn test_builder_requires_a_stored_key() {
+    let db = setup_db();
+    match db.tree_builder().name("keyless").create() {
+        Err(eidetica::Error::Base(err)) => {
+            assert!(matches!(
+                err,
+                eidetica::basedb::BaseError::InvalidTreeConfiguration { .. }
+            ))
+        }
+        other => panic!(
+            "unexpected result: {:?}",
+            other.map(|t| t.root_id().clone())
+        ),
+    }
+
+    let result = db.tree_builder().default_key("missing").create();
+    assert!(result.is_err());
+    assert!(db.find_tree("keyless").is_err());
+}
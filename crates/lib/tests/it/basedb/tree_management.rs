@@ -1,12 +1,13 @@
 //! Tree management tests
 //!
 //! This module contains tests for tree management operations including
-//! tree listing, finding trees by name, and handling multiple trees.
+//! tree listing, finding trees by name through the name index, and handling
+//! multiple trees.
 
 use super::helpers::*;
 use crate::helpers::setup_db_with_key;
 use eidetica::backend::database::InMemory;
-use eidetica::basedb::BaseDB;
+use eidetica::basedb::{BaseDB, BaseError};
 
 const TEST_KEY: &str = "test_key";
 
@@ -235,3 +236,104 @@ fn test_tree_listing_and_searching() {
         &[("name", "DevelopmentApp"), ("version", "3.1-alpha")],
     );
 }
+
+#[test]
+fn test_find_tree_by_name() {
+    let db = setup_db_with_key(TEST_KEY);
+    setup_trees_for_find_testing(&db, TEST_KEY);
+
+    let unique = db.find_tree_by_name("UniqueTree").unwrap();
+    assert_tree_name(&unique, "UniqueTree");
+
+    match db.find_tree_by_name("NonExistent").err().unwrap() {
+        eidetica::Error::Base(err) => assert!(err.is_not_found()),
+        other => panic!("unexpected error: {other:?}"),
+    }
+
+    // Duplicate names are reported with every matching root
+    let mut expected: Vec<_> = db
+        .find_tree("DuplicateName")
+        .unwrap()
+        .iter()
+        .map(|tree| tree.root_id().clone())
+        .collect();
+    expected.sort();
+    match db.find_tree_by_name("DuplicateName").err().unwrap() {
+        eidetica::Error::Base(BaseError::DuplicateTreeName { name, roots }) => {
+            assert_eq!(name, "DuplicateName");
+            assert_eq!(roots, expected);
+        }
+        other => panic!("unexpected error: {other:?}"),
+    }
+}
+
+#[test]
+fn test_trees_by_name() {
+    let db = setup_db_with_key(TEST_KEY);
+    setup_trees_for_find_testing(&db, TEST_KEY);
+    let unnamed = db.new_tree_default(TEST_KEY).unwrap();
+
+    let by_name = db.trees_by_name().unwrap();
+    let names: Vec<&str> = by_name.keys().map(String::as_str).collect();
+    assert_eq!(names, vec!["DuplicateName", "UniqueTree"]);
+    assert_eq!(by_name["DuplicateName"].len(), 2);
+    assert_tree_names_in_collection(&by_name["DuplicateName"], &["DuplicateName"]);
+    assert_eq!(by_name["UniqueTree"].len(), 1);
+    assert!(
+        by_name
+            .values()
+            .flatten()
+            .all(|tree| tree.root_id() != unnamed.root_id())
+    );
+}
+
+#[test]
+fn test_name_index_follows_changes() {
+    let db = setup_db_with_key(TEST_KEY);
+    let tree = create_tree_with_settings(&db, TEST_KEY, "Draft", "1.0");
+    assert_eq!(
+        db.find_tree_by_name("Draft").unwrap().root_id(),
+        tree.root_id()
+    );
+
+    // Renaming the tree moves it in the index
+    tree.update_settings(|settings| settings.set_name("Final"))
+        .unwrap();
+    test_tree_not_found_error(&db, "Draft");
+    assert_eq!(
+        db.find_tree_by_name("Final").unwrap().root_id(),
+        tree.root_id()
+    );
+
+    // Trees created afterwards, by this or another handle, are indexed too
+    let clone = db.clone();
+    let other = create_tree_with_settings(&clone, TEST_KEY, "Other", "1.0");
+    assert_eq!(
+        db.find_tree_by_name("Other").unwrap().root_id(),
+        other.root_id()
+    );
+
+    // A second tree taking the name makes it ambiguous
+    create_tree_with_settings(&db, TEST_KEY, "Final", "1.0");
+    match db.find_tree_by_name("Final").err().unwrap() {
+        eidetica::Error::Base(err) => assert!(err.is_duplicate_name()),
+        other => panic!("unexpected error: {other:?}"),
+    }
+    assert_trees_count(&db.find_tree("Final").unwrap(), 2);
+}
+
+#[test]
+fn test_name_index_finds_imported_trees() {
+    let source = setup_db_with_key(TEST_KEY);
+    let tree = create_tree_with_settings(&source, TEST_KEY, "Shared", "1.0");
+    let mut bundle = Vec::new();
+    tree.export_bundle(&mut bundle).unwrap();
+
+    let target = setup_db_with_key(TEST_KEY);
+    test_tree_not_found_error(&target, "Shared");
+    target.import_bundle(bundle.as_slice()).unwrap();
+    assert_eq!(
+        target.find_tree_by_name("Shared").unwrap().root_id(),
+        tree.root_id()
+    );
+}
@@ -282,8 +282,10 @@ pub fn create_all_value_types() -> Vec<Value> {
         Value::Bool(false),
         Value::Int(42),
         Value::Int(-123),
+        Value::Float(1.5),
         Value::Text("test".to_string()),
         Value::Text("".to_string()),
+        Value::Bytes(vec![0, 255]),
         Value::Map(Map::new()),
         Value::List(List::new()),
         Value::Deleted,
@@ -249,7 +249,7 @@ fn test_list_push_different_types() {
     assert_eq!(list.get(0).unwrap().as_text(), Some("hello"));
     assert_eq!(list.get(1).unwrap().as_int(), Some(42));
     assert_eq!(list.get(2).unwrap().as_bool(), Some(true));
-    assert_eq!(list.get(3).unwrap().as_int(), Some(3)); // float converted to int
+    assert_eq!(list.get(3).unwrap().as_float(), Some(3.13)); // floats are stored natively
 }
 
 #[test]
@@ -296,6 +296,32 @@ fn test_list_insert_at_valid_indices() {
     assert_eq!(list.get(2).unwrap().as_text(), Some("last"));
 }
 
+#[test]
+fn test_list_insert_skips_tombstones() {
+    let mut list = List::new();
+    list.push("a");
+    list.push("removed");
+    list.push("b");
+    list.push("c");
+    list.remove(1);
+
+    // Index 2 is between the visible "b" and "c", not next to the tombstone
+    assert_eq!(
+        list.position(1),
+        list.iter_with_positions()
+            .nth(2)
+            .map(|(position, _)| position)
+    );
+    list.insert(2, "between").unwrap();
+    let values: Vec<_> = list.iter().filter_map(|v| v.as_text()).collect();
+    assert_eq!(values, vec!["a", "b", "between", "c"]);
+
+    let position = list.insert_position(4).unwrap();
+    list.insert_at_position(position, "end");
+    assert_eq!(list.get(4).unwrap().as_text(), Some("end"));
+    assert!(list.insert_position(6).is_err());
+}
+
 #[test]
 fn test_list_insert_at_beginning() {
     let mut list = List::new();
@@ -676,3 +702,146 @@ fn test_list_merge_properties() {
     // The results should be deterministic even if order differs
     assert_eq!(list1_backup.len(), list2_backup.len());
 }
+
+// ===== MOVES AND RELATIVE INSERTION =====
+
+fn texts(list: &List) -> Vec<&str> {
+    list.iter().map(|value| value.as_text().unwrap()).collect()
+}
+
+fn list_of(items: &[&str]) -> List {
+    let mut list = List::new();
+    for item in items {
+        list.push(*item);
+    }
+    list
+}
+
+#[test]
+fn test_list_insert_after() {
+    let mut list = list_of(&["a", "c"]);
+    let a = list.position(0).unwrap().clone();
+    let c = list.position(1).unwrap().clone();
+
+    let b = list.insert_after(&a, "b").unwrap();
+    list.insert_after(&c, "d").unwrap();
+    assert_eq!(texts(&list), vec!["a", "b", "c", "d"]);
+    assert_eq!(list.get_by_position(&b).unwrap().as_text(), Some("b"));
+
+    // Still after its item when that item was moved
+    list.move_item(0, 3).unwrap();
+    list.insert_after(&a, "after a").unwrap();
+    assert_eq!(texts(&list), vec!["b", "c", "d", "a", "after a"]);
+
+    let unknown = Position::new(7, 1);
+    assert!(matches!(
+        list.insert_after(&unknown, "x"),
+        Err(CRDTError::ElementNotFound { .. })
+    ));
+}
+
+#[test]
+fn test_list_move_item() {
+    let mut list = list_of(&["a", "b", "c", "d"]);
+    let a = list.position(0).unwrap().clone();
+
+    list.move_item(0, 2).unwrap();
+    assert_eq!(texts(&list), vec!["b", "c", "a", "d"]);
+    list.move_item(3, 0).unwrap();
+    assert_eq!(texts(&list), vec!["d", "b", "c", "a"]);
+    list.move_item(1, 1).unwrap();
+    assert_eq!(texts(&list), vec!["d", "b", "c", "a"]);
+
+    // The moved item keeps its identity, and indexes follow the new order
+    assert_eq!(list.position(3), Some(&a));
+    assert_eq!(list.get(0).unwrap().as_text(), Some("d"));
+    list.set(3, "A");
+    assert_eq!(list.get_by_position(&a).unwrap().as_text(), Some("A"));
+    list.insert(1, "x").unwrap();
+    list.push("end");
+    assert_eq!(texts(&list), vec!["d", "x", "b", "c", "A", "end"]);
+    assert_eq!(
+        list.to_vec().last().and_then(|value| value.as_text()),
+        Some("end")
+    );
+
+    assert!(matches!(
+        list.move_item(6, 0),
+        Err(CRDTError::ListIndexOutOfBounds { index: 6, len: 6 })
+    ));
+    assert!(list.move_item(0, 6).is_err());
+}
+
+#[test]
+fn test_list_move_keeps_concurrent_edits() {
+    let base = list_of(&["a", "b", "c"]);
+
+    let mut moved = base.clone();
+    moved.move_item(0, 2).unwrap();
+    let mut edited = base.clone();
+    edited.set(0, "a edited");
+    edited.insert(1, "x").unwrap();
+
+    // The edit lands on the moved item, instead of on a removed copy of it
+    let mut merged = moved.clone();
+    merged.merge(&edited);
+    assert_eq!(texts(&merged), vec!["x", "b", "c", "a edited"]);
+
+    // Unstamped values are last-write-wins by merge order, but the item is
+    // never duplicated or lost
+    let mut merged = edited.clone();
+    merged.merge(&moved);
+    assert_eq!(texts(&merged), vec!["x", "b", "c", "a"]);
+
+    // Removing and reinserting the item instead leaves the edit behind
+    let mut reinserted = base.clone();
+    reinserted.remove(0);
+    reinserted.push("a");
+    reinserted.merge(&edited);
+    assert_eq!(texts(&reinserted), vec!["a edited", "x", "b", "c", "a"]);
+}
+
+#[test]
+fn test_list_concurrent_moves_converge() {
+    let base = list_of(&["a", "b", "c", "d"]);
+
+    let mut first = base.clone();
+    first.move_item(0, 3).unwrap();
+    let mut second = base.clone();
+    second.move_item(0, 1).unwrap();
+
+    let mut one = first.clone();
+    one.merge(&second);
+    let mut two = second.clone();
+    two.merge(&first);
+    assert_eq!(texts(&one), texts(&two));
+    assert_eq!(one.len(), 4);
+    assert_eq!(texts(&one).iter().filter(|item| **item == "a").count(), 1);
+
+    // A move made after seeing another move of the item wins over it
+    let mut later = one.clone();
+    later
+        .move_item(texts(&one).iter().position(|item| *item == "a").unwrap(), 0)
+        .unwrap();
+    let mut stale = first.clone();
+    stale.merge(&later);
+    later.merge(&first);
+    assert_eq!(texts(&stale), vec!["a", "b", "c", "d"]);
+    assert_eq!(texts(&later), vec!["a", "b", "c", "d"]);
+}
+
+#[test]
+fn test_list_moves_round_trip() {
+    let mut list = list_of(&["a", "b", "c"]);
+    list.move_item(2, 0).unwrap();
+    list.remove(1);
+
+    let json = serde_json::to_string(&list).unwrap();
+    let restored: List = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored, list);
+    assert_eq!(texts(&restored), vec!["c", "b"]);
+
+    // Lists without moves are stored as before
+    let plain = list_of(&["a"]);
+    assert!(!serde_json::to_string(&plain).unwrap().contains("move:"));
+}
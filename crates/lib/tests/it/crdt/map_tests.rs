@@ -4,7 +4,7 @@
 //! on Map functionality, including basic operations, path operations, iterators,
 //! builder pattern, CRDT merge operations, tombstone handling, and JSON serialization.
 
-use eidetica::crdt::map::{List, Value};
+use eidetica::crdt::map::{List, Value, path};
 use eidetica::crdt::{CRDT, Map};
 
 // ===== BASIC MAP OPERATIONS =====
@@ -167,9 +167,9 @@ fn test_map_path_errors() {
     let result = map.set_path("scalar.nested", "should_fail");
     assert!(result.is_err());
 
-    // Test empty path - this actually works, it just sets at root level
+    // Test empty path - it names no key, so it is malformed
     let result2 = map.set_path("", "value");
-    assert!(result2.is_ok()); // Empty path is treated as root level
+    assert!(result2.unwrap_err().is_path_error());
 
     // Test path with single component
     let result3 = map.set_path("single", "value");
@@ -177,6 +177,54 @@ fn test_map_path_errors() {
     assert_eq!(map.get_text("single"), Some("value"));
 }
 
+#[test]
+fn test_map_path_separators_and_escapes() {
+    let mut map = Map::new();
+
+    map.set_path("user/profile/name", "Alice").unwrap();
+    assert_eq!(map.get_text_at_path("user.profile.name"), Some("Alice"));
+    assert_eq!(map.get_text_at_path("user/profile.name"), Some("Alice"));
+
+    // Escaped separators and backslashes stay in the key
+    map.set_path("files.report\\.pdf", "final").unwrap();
+    map.set_path("files.a\\/b\\\\c", "odd").unwrap();
+    let files = map.get_node_at_path("files").unwrap();
+    assert_eq!(files.get_text("report.pdf"), Some("final"));
+    assert_eq!(files.get_text("a/b\\c"), Some("odd"));
+    assert_eq!(map.get_text_at_path("files/report\\.pdf"), Some("final"));
+    assert!(map.get_path("files.report.pdf").is_none());
+}
+
+#[test]
+fn test_map_malformed_paths() {
+    let mut map = Map::new();
+
+    for malformed in [
+        "",
+        ".user",
+        "user.",
+        "user..name",
+        "user//name",
+        "a\\",
+        "a\\b",
+    ] {
+        let err = map.set_path(malformed, "value").unwrap_err();
+        assert!(err.is_path_error(), "{malformed}: {err}");
+        assert_eq!(err.path(), Some(malformed));
+        assert!(map.get_path(malformed).is_none());
+    }
+    assert!(map.is_empty());
+}
+
+#[test]
+fn test_path_parse_and_join_round_trip() {
+    let segments = vec!["files", "report.pdf", "a/b", "back\\slash"];
+    let joined = path::join(&segments);
+    assert_eq!(joined, "files.report\\.pdf.a\\/b.back\\\\slash");
+    assert_eq!(path::parse(&joined).unwrap(), segments);
+    assert_eq!(path::escape("plain"), "plain");
+}
+
 // ===== ITERATORS =====
 
 #[test]
@@ -777,3 +825,119 @@ fn test_serde_json_round_trip_with_tombstones() {
     assert!(!deserialized_map.contains_key("remove")); // Tombstone hidden from contains_key()
     assert_eq!(deserialized_map.len(), 1); // Only counts non-tombstones
 }
+
+// ===== RENAME TESTS =====
+
+#[test]
+fn test_map_rename() {
+    let mut map = Map::new();
+    map.set("old", "value");
+
+    assert!(map.rename("old", "new"));
+    assert_eq!(map.get_text("new"), Some("value"));
+    assert!(map.is_tombstone("old"));
+    assert_eq!(map.moved_to("old").as_deref(), Some("new"));
+    assert_eq!(map.moved_to("new"), None);
+
+    // Nothing to rename
+    assert!(!map.rename("old", "other"));
+    assert!(!map.rename("missing", "other"));
+}
+
+#[test]
+fn test_map_rename_redirects_concurrent_writes() {
+    let mut base = Map::new();
+    base.set("old", "original");
+    base.set("other", "untouched");
+
+    let mut renamed = base.clone();
+    renamed.rename("old", "new");
+    let mut written = base.clone();
+    written.set("old", "concurrent");
+
+    // The concurrent write ends up at the new key whichever side is merged last
+    for merged in [
+        renamed.merge(&written).unwrap(),
+        written.merge(&renamed).unwrap(),
+    ] {
+        assert_eq!(merged.get_text("new"), Some("concurrent"));
+        assert!(merged.get("old").is_none());
+        assert_eq!(merged.get_text("other"), Some("untouched"));
+    }
+}
+
+#[test]
+fn test_map_rename_keeps_unchanged_values() {
+    let mut base = Map::new();
+    base.set("old", "original");
+
+    let mut renamed = base.clone();
+    renamed.rename("old", "new");
+    renamed.set("new", "updated");
+
+    // Merging a state that predates the rename does not undo later changes
+    let merged = base.merge(&renamed).unwrap();
+    assert_eq!(merged.get_text("new"), Some("updated"));
+    assert!(merged.get("old").is_none());
+    let merged = renamed.merge(&base).unwrap();
+    assert_eq!(merged.get_text("new"), Some("updated"));
+}
+
+#[test]
+fn test_map_rename_chains_and_reuse() {
+    let mut map = Map::new();
+    map.set("a", 1);
+    map.rename("a", "b");
+    map.rename("b", "c");
+    assert_eq!(map.moved_to("a").as_deref(), Some("c"));
+
+    let mut late = Map::new();
+    late.set("a", 2);
+    let merged = map.merge(&late).unwrap();
+    assert_eq!(merged.get_int("c"), Some(2));
+
+    // Renaming onto an old name makes it an ordinary key again
+    let mut reused = merged.clone();
+    reused.set("x", 3);
+    reused.rename("x", "a");
+    assert_eq!(reused.moved_to("a"), None);
+    let merged = merged.merge(&reused).unwrap();
+    assert_eq!(merged.moved_to("a"), None);
+    let merged = merged.merge(&late).unwrap();
+    assert_eq!(merged.get_int("a"), Some(2));
+    assert_eq!(merged.get_int("c"), Some(2));
+}
+
+#[test]
+fn test_map_rename_serialization() {
+    let mut map = Map::new();
+    map.set("key", "value");
+    // Maps without renames serialize as before
+    assert!(!serde_json::to_string(&map).unwrap().contains("moves"));
+
+    map.rename("key", "renamed");
+    let json = serde_json::to_string(&map).unwrap();
+    let deserialized: Map = serde_json::from_str(&json).unwrap();
+    assert_eq!(deserialized, map);
+    assert_eq!(deserialized.moved_to("key").as_deref(), Some("renamed"));
+}
+
+#[test]
+fn test_map_rename_and_concurrent_delete() {
+    let mut base = Map::new();
+    base.set("old", "value");
+
+    let mut renamed = base.clone();
+    renamed.rename("old", "new");
+    let mut deleted = base.clone();
+    deleted.remove("old");
+
+    // The deletion follows the rename, whichever side is merged last
+    for merged in [
+        renamed.merge(&deleted).unwrap(),
+        deleted.merge(&renamed).unwrap(),
+    ] {
+        assert!(merged.get("new").is_none());
+        assert!(merged.get("old").is_none());
+    }
+}
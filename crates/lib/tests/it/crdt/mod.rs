@@ -1,6 +1,7 @@
 //! CRDT integration tests
 //!
-//! This module tests the CRDT implementations including Map, List, and Value types.
+//! This module tests the CRDT implementations including Map, List, Value and Rga types,
+//! and the causal write stamps of Map keys.
 //! Tests are organized by CRDT type for better maintainability.
 
 mod helpers;
@@ -9,4 +10,6 @@ mod list_tests;
 mod map_advanced_tests;
 mod map_tests;
 mod serialization_tests;
+mod stamp_tests;
+mod text_tests;
 mod value_tests;
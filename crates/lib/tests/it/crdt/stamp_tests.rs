@@ -0,0 +1,177 @@
+//! Causal write stamp tests
+//!
+//! This module tests that stamped Map writes merge to the same state in any
+//! order, and that unstamped writes keep merging by merge order.
+
+use eidetica::clock::Hlc;
+use eidetica::crdt::CRDT;
+use eidetica::crdt::map::{Map, Stamp, Value};
+
+fn stamp(wall_ms: u64, site: u64) -> Stamp {
+    Stamp {
+        hlc: Hlc::new(wall_ms, 0),
+        site,
+    }
+}
+
+/// A map holding `key: value`, written at `at`.
+fn write(key: &str, value: impl Into<Value>, at: Stamp) -> Map {
+    let mut map = Map::new();
+    map.set(key, value);
+    map.stamp_writes(at);
+    map
+}
+
+fn merge_all(maps: &[&Map]) -> Map {
+    maps.iter()
+        .fold(Map::new(), |merged, map| merged.merge(map).unwrap())
+}
+
+#[test]
+fn test_later_write_wins_in_any_order() {
+    let early = write("name", "Alice", stamp(100, 1));
+    let late = write("name", "Bob", stamp(200, 1));
+
+    assert_eq!(early.merge(&late).unwrap().get_text("name"), Some("Bob"));
+    assert_eq!(late.merge(&early).unwrap().get_text("name"), Some("Bob"));
+    assert_eq!(
+        late.merge(&early).unwrap().stamp("name"),
+        Some(stamp(200, 1))
+    );
+}
+
+#[test]
+fn test_site_breaks_ties() {
+    let left = write("name", "Alice", stamp(100, 1));
+    let right = write("name", "Bob", stamp(100, 2));
+
+    assert_eq!(left.merge(&right).unwrap().get_text("name"), Some("Bob"));
+    assert_eq!(right.merge(&left).unwrap().get_text("name"), Some("Bob"));
+}
+
+#[test]
+fn test_delete_and_write_converge() {
+    let mut base = Map::new();
+    base.set_path("user.name", "Alice").unwrap();
+    base.set_path("user.age", 30).unwrap();
+    base.stamp_writes(stamp(100, 1));
+
+    let mut deleted = Map::new();
+    deleted.remove("user");
+    deleted.stamp_writes(stamp(200, 2));
+
+    let mut edited = Map::new();
+    edited.set_path("user.email", "alice@example.com").unwrap();
+
+    // The delete is later: the user is gone whichever is merged first
+    let mut before = edited.clone();
+    before.stamp_writes(stamp(150, 3));
+    let a = merge_all(&[&base, &deleted, &before]);
+    let b = merge_all(&[&base, &before, &deleted]);
+    assert_eq!(a, b);
+    assert!(a.get("user").is_none());
+
+    // The write is later: only the user's fields written after the delete remain
+    let mut after = edited.clone();
+    after.stamp_writes(stamp(250, 3));
+    let a = merge_all(&[&base, &deleted, &after]);
+    let b = merge_all(&[&base, &after, &deleted]);
+    assert_eq!(a, b);
+    assert_eq!(a.get_text_at_path("user.email"), Some("alice@example.com"));
+    assert!(a.get_path("user.name").is_none());
+
+    // Old fields merged in later stay deleted
+    let c = merge_all(&[&deleted, &after, &base]);
+    assert_eq!(a, c);
+}
+
+#[test]
+fn test_merge_is_commutative_for_stamped_writes() {
+    let mut writes = Vec::new();
+    for (i, (key, value)) in [("a", "1"), ("b", "2"), ("a", "3"), ("c", "4")]
+        .into_iter()
+        .enumerate()
+    {
+        writes.push(write(key, value, stamp(100 + i as u64 % 2, i as u64)));
+    }
+    let mut deleted = Map::new();
+    deleted.remove("b");
+    deleted.stamp_writes(stamp(101, 9));
+    writes.push(deleted);
+
+    let forward: Vec<&Map> = writes.iter().collect();
+    let backward: Vec<&Map> = writes.iter().rev().collect();
+    let merged = merge_all(&forward);
+    assert_eq!(merged, merge_all(&backward));
+    assert_eq!(merged.get_text("a"), Some("3"));
+    assert!(merged.get("b").is_none());
+}
+
+#[test]
+fn test_unstamped_writes_merge_in_order() {
+    let stamped = write("name", "Alice", stamp(100, 1));
+    let mut staged = Map::new();
+    staged.set("name", "Bob");
+
+    // Staged writes have no stamp yet and win over the state they are merged into
+    let merged = stamped.merge(&staged).unwrap();
+    assert_eq!(merged.get_text("name"), Some("Bob"));
+    assert_eq!(merged.stamp("name"), None);
+
+    // Setting a key locally drops its stamp
+    let mut local = stamped.clone();
+    local.set("name", "Carol");
+    assert_eq!(local.stamp("name"), None);
+}
+
+#[test]
+fn test_stamps_serialize_and_are_ignored_by_equality() {
+    let stamped = write("name", "Alice", stamp(1234, 0xabc));
+    let json = serde_json::to_string(&stamped).unwrap();
+    assert!(json.contains("1234.0@0000000000000abc"));
+    let parsed: Map = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed.stamp("name"), Some(stamp(1234, 0xabc)));
+
+    assert!(parsed.same_state(&stamped));
+
+    let mut unstamped = Map::new();
+    unstamped.set("name", "Alice");
+    assert_eq!(stamped, unstamped);
+    assert!(!stamped.same_state(&unstamped));
+
+    // Stamps of nested maps are part of the state too
+    let nested = Map::new().with_node("profile", stamped.clone());
+    assert_eq!(nested, Map::new().with_node("profile", unstamped.clone()));
+    assert!(
+        !nested.same_state(
+            &Map::new().with_node("profile", write("name", "Alice", stamp(1234, 0xabd)))
+        )
+    );
+    assert!(nested.same_state(&Map::new().with_node("profile", stamped.clone())));
+    assert!(
+        !serde_json::to_string(&unstamped)
+            .unwrap()
+            .contains("stamps")
+    );
+
+    assert!("not a stamp".parse::<Stamp>().is_err());
+}
+
+#[test]
+fn test_rename_keeps_concurrent_write_in_any_order() {
+    let base = write("colour", "red", stamp(100, 1));
+
+    // The rename is stamped later or earlier than the concurrent update
+    for rename_at in [stamp(300, 2), stamp(150, 2)] {
+        let mut renamed = base.clone();
+        renamed.rename("colour", "color");
+        renamed.stamp_writes(rename_at);
+        let updated = write("colour", "blue", stamp(200, 3));
+
+        let a = base.merge(&renamed).unwrap().merge(&updated).unwrap();
+        let b = base.merge(&updated).unwrap().merge(&renamed).unwrap();
+        assert_eq!(a, b);
+        assert_eq!(a.get_text("color"), Some("blue"));
+        assert!(a.get("colour").is_none());
+    }
+}
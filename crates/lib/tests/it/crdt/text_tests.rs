@@ -0,0 +1,117 @@
+//! Rga text CRDT integration tests
+//!
+//! This module contains tests for the Rga implementation: editing by index,
+//! merge properties under concurrent edits, and JSON serialization.
+
+use eidetica::crdt::{CRDT, CRDTError, Rga};
+
+fn text(s: &str) -> Rga {
+    let mut rga = Rga::new();
+    rga.insert(0, s).unwrap();
+    rga
+}
+
+#[test]
+fn test_rga_basic_operations() {
+    let mut rga = Rga::new();
+    assert!(rga.is_empty());
+
+    rga.insert(0, "world").unwrap();
+    rga.insert(0, "hello ").unwrap();
+    rga.insert(11, "!").unwrap();
+    assert_eq!(rga.to_string(), "hello world!");
+    assert_eq!(rga.len(), 12);
+    assert_eq!(rga.char_ids().len(), 12);
+
+    rga.delete(5, 6).unwrap();
+    assert_eq!(rga.to_string(), "hello!");
+    assert_eq!(rga.len(), 6);
+
+    // Positions count characters, not bytes
+    let mut rga = text("naïve ☕");
+    rga.delete(2, 1).unwrap();
+    rga.insert(2, "i").unwrap();
+    assert_eq!(rga.to_string(), "naive ☕");
+    assert_eq!(rga.len(), 7);
+}
+
+#[test]
+fn test_rga_index_out_of_bounds() {
+    let mut rga = text("abc");
+    let err = rga.insert(4, "x").unwrap_err();
+    assert!(matches!(
+        err,
+        eidetica::Error::CRDT(CRDTError::TextIndexOutOfBounds { index: 4, len: 3 })
+    ));
+    assert!(rga.delete(2, 2).is_err());
+    assert_eq!(rga.to_string(), "abc");
+}
+
+#[test]
+fn test_rga_edits_return_changes() {
+    let original = text("abc");
+    let mut edited = original.clone();
+    let inserted = edited.insert(1, "xy").unwrap();
+    assert_eq!(inserted.to_string(), "xy");
+    let deleted = edited.delete(0, 1).unwrap();
+    assert_eq!(deleted.len(), 0);
+    assert_eq!(edited.to_string(), "xybc");
+
+    // Applying only the changes to another replica reproduces the edits
+    let replica = original.merge(&inserted).unwrap().merge(&deleted).unwrap();
+    assert_eq!(replica, edited);
+}
+
+#[test]
+fn test_rga_concurrent_edits_converge() {
+    let base = text("the cat");
+    let mut left = base.clone();
+    let mut right = base.clone();
+    let mut third = base.clone();
+
+    left.insert(4, "black ").unwrap();
+    right.insert(7, "s").unwrap();
+    right.delete(0, 4).unwrap();
+    third.insert(4, "big ").unwrap();
+
+    let lr = left.merge(&right).unwrap();
+    let rl = right.merge(&left).unwrap();
+    assert_eq!(lr, rl);
+    assert_eq!(lr.to_string(), "black cats");
+
+    // Associative and idempotent
+    let a = lr.merge(&third).unwrap();
+    let b = left.merge(&right.merge(&third).unwrap()).unwrap();
+    assert_eq!(a, b);
+    assert_eq!(a.merge(&a).unwrap(), a);
+
+    // Concurrent insertions at the same position stay contiguous
+    let merged = a.to_string();
+    assert!(merged == "black big cats" || merged == "big black cats");
+}
+
+#[test]
+fn test_rga_insert_into_deleted_region() {
+    let base = text("abcdef");
+    let mut left = base.clone();
+    let mut right = base.clone();
+    left.delete(1, 4).unwrap();
+    right.insert(3, "XYZ").unwrap();
+
+    let merged = left.merge(&right).unwrap();
+    assert_eq!(merged.to_string(), "aXYZf");
+    assert_eq!(merged, right.merge(&left).unwrap());
+}
+
+#[test]
+fn test_rga_serialization_roundtrip() {
+    let mut rga = text("hello");
+    rga.delete(0, 1).unwrap();
+    let json = serde_json::to_string(&rga).unwrap();
+    let parsed: Rga = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, rga);
+    assert_eq!(parsed.to_string(), "ello");
+
+    assert!(serde_json::from_str::<Rga>(r#"{"chars":{"bad":{"value":"x"}}}"#).is_err());
+    assert!(serde_json::from_str::<Rga>("{}").unwrap().is_empty());
+}
@@ -373,6 +373,14 @@ fn test_serde_json_round_trip_value_types() {
         Value::Text("hello world".to_string()),
         Value::Text("".to_string()),
         Value::Text("special \"chars\" & symbols!".to_string()),
+        Value::Link("sha256:abc123".into()),
+        Value::Float(2.5),
+        Value::Float(-0.0),
+        Value::Float(f64::NAN),
+        Value::Float(f64::INFINITY),
+        Value::Float(f64::NEG_INFINITY),
+        Value::Bytes(vec![0, 1, 2, 254, 255]),
+        Value::Bytes(Vec::new()),
         Value::Deleted, // This should round-trip as Deleted
     ];
 
@@ -382,6 +390,115 @@ fn test_serde_json_round_trip_value_types() {
     }
 }
 
+// ===== FLOAT AND BYTES TESTS =====
+
+#[test]
+fn test_value_float_accessors() {
+    let float = Value::from(2.5);
+    assert_eq!(float, Value::Float(2.5));
+    assert_eq!(float.as_float(), Some(2.5));
+    assert_eq!(float.as_int(), None);
+    assert!(float.is_leaf());
+    assert!(float == 2.5);
+    assert_eq!(float.to_json_string(), "2.5");
+    assert_eq!(Value::Float(f64::NAN).to_json_string(), "null");
+
+    // Integers read as floats, but floats never read as integers
+    assert_eq!(Value::Int(3).as_float(), Some(3.0));
+    assert_eq!(Value::Text("2.5".to_string()).as_float_or(0.5), 0.5);
+
+    // Floats compare by their bits, keeping `Value` an `Eq` type
+    assert_eq!(Value::Float(f64::NAN), Value::Float(f64::NAN));
+    assert_ne!(Value::Float(0.0), Value::Float(-0.0));
+    assert_ne!(Value::Float(1.0), Value::Int(1));
+}
+
+#[test]
+fn test_value_float_compares_with_f64_by_bits() {
+    // Comparing with a plain `f64` agrees with comparing two `Value`s
+    let nan = f64::NAN;
+    assert!(Value::Float(nan) == nan);
+    assert!(nan == Value::Float(nan));
+    assert!(Value::Float(0.0) != -0.0);
+    assert!(-0.0 != Value::Float(0.0));
+    assert!(Value::Float(-0.0) == -0.0);
+    assert!(Value::Int(1) != 1.0);
+}
+
+#[test]
+fn test_value_bytes_accessors() {
+    let bytes = Value::from(vec![1u8, 2, 3]);
+    assert_eq!(bytes, Value::Bytes(vec![1, 2, 3]));
+    assert_eq!(Value::from(&[1u8, 2, 3][..]), bytes);
+    assert_eq!(bytes.as_bytes(), Some(&[1u8, 2, 3][..]));
+    assert_eq!(bytes.as_text(), None);
+    assert!(bytes.is_leaf());
+    assert_eq!(bytes.to_json_string(), "\"AQID\"");
+
+    // Stored base64 encoded
+    assert_eq!(
+        serde_json::to_string(&bytes).unwrap(),
+        r#"{"Bytes":"AQID"}"#
+    );
+    assert!(serde_json::from_str::<Value>(r#"{"Bytes":"not base64!"}"#).is_err());
+}
+
+#[test]
+fn test_value_float_and_bytes_merge() {
+    let mut value = Value::Float(1.5);
+    value.merge(&Value::Bytes(vec![7]));
+    assert_eq!(value, Value::Bytes(vec![7]));
+    value.merge(&Value::Float(2.5));
+    assert_eq!(value, Value::Float(2.5));
+    value.merge(&Value::Deleted);
+    assert!(value.is_deleted());
+
+    let mut map = Map::new();
+    map.set("ratio", 0.25);
+    map.set("blob", vec![9u8]);
+    assert_eq!(map.get_float("ratio"), Some(0.25));
+    assert_eq!(map.get_bytes("blob"), Some(&[9u8][..]));
+}
+
+// ===== LINK TESTS =====
+
+#[test]
+fn test_value_link_accessors() {
+    let id = eidetica::entry::ID::from("sha256:abc123");
+    let link = Value::from(id.clone());
+
+    assert_eq!(link, Value::Link(id.clone()));
+    assert_eq!(link.as_link(), Some(&id));
+    assert_eq!(link.as_text(), None);
+    assert_eq!(Value::Text("sha256:abc123".to_string()).as_link(), None);
+    assert!(link.is_leaf());
+    assert_eq!(link.to_json_string(), "\"sha256:abc123\"");
+}
+
+#[test]
+fn test_value_links_collects_nested_links() {
+    let mut nested = Map::new();
+    nested.set("doc", Value::Link("b".into()));
+    let mut list = List::new();
+    list.push(Value::Link("c".into()));
+    list.push(Value::Text("not a link".to_string()));
+
+    let mut map = Map::new();
+    map.set("direct", Value::Link("a".into()));
+    map.set("nested", nested);
+    map.set("list", list);
+    map.set("removed", Value::Link("d".into()));
+    map.remove("removed");
+
+    let mut links: Vec<String> = Value::Map(map)
+        .links()
+        .into_iter()
+        .map(|id| id.to_string())
+        .collect();
+    links.sort();
+    assert_eq!(links, ["a", "b", "c"]);
+}
+
 // ===== VALUE COLLECTION HELPER TESTS =====
 
 #[test]
@@ -389,14 +506,16 @@ fn test_all_value_types_helper() {
     // Test that our helper creates all expected Value types
     let all_values = create_all_value_types();
 
-    assert_eq!(all_values.len(), 10); // We should have 10 different Value types
+    assert_eq!(all_values.len(), 12); // We should have 12 different Value types
 
     // Verify we have all the expected types
     let type_names: Vec<&str> = all_values.iter().map(|v| v.type_name()).collect();
     assert!(type_names.contains(&"null"));
     assert!(type_names.contains(&"bool"));
     assert!(type_names.contains(&"int"));
+    assert!(type_names.contains(&"float"));
     assert!(type_names.contains(&"text"));
+    assert!(type_names.contains(&"bytes"));
     assert!(type_names.contains(&"node"));
     assert!(type_names.contains(&"list"));
     assert!(type_names.contains(&"deleted"));
@@ -308,3 +308,119 @@ fn test_value_editor_set_non_map_to_root() -> eidetica::Result<()> {
 
     Ok(())
 }
+
+// ===== LIST NAVIGATION =====
+
+fn text(value: &str) -> Value {
+    Value::Text(value.to_string())
+}
+
+#[test]
+fn test_value_editor_push_and_edit_list_elements() -> eidetica::Result<()> {
+    let (_, tree, op, dict) = setup_complete_test_env("editor_test_store")?;
+
+    let users = dict.get_value_mut("users");
+    assert_eq!(users.push(Value::Map(eidetica::crdt::Map::new()))?, 0);
+    assert_eq!(users.push(Value::Map(eidetica::crdt::Map::new()))?, 1);
+    users.index(0).get_value_mut("name").set(text("alice"))?;
+    users.index(1).get_value_mut("name").set(text("bob"))?;
+    users.index(1).get_value_mut("tags").push(text("admin"))?;
+
+    assert_text_value(&users.index(0).get_value("name")?, "alice");
+    assert_text_value(
+        &users.index(1).get_value_mut("tags").index(0).get()?,
+        "admin",
+    );
+    op.commit()?;
+
+    let viewer = tree.get_subtree_viewer::<eidetica::subtree::Dict>("editor_test_store")?;
+    let users = viewer.get_value_mut("users");
+    assert_text_value(&users.index(1).get_value("name")?, "bob");
+    match viewer.get("users")? {
+        Value::List(list) => assert_eq!(list.len(), 2),
+        other => panic!("Expected a list, got {other:?}"),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_value_editor_insert_at_and_delete_elements() -> eidetica::Result<()> {
+    let (_, _, _op, dict) = setup_complete_test_env("editor_test_store")?;
+
+    let items = dict.get_value_mut("items");
+    items.push(text("b"))?;
+    items.push(text("d"))?;
+    items.insert_at(0, text("a"))?;
+    items.insert_at(2, text("c"))?;
+    items.insert_at(4, text("e"))?;
+    assert!(items.insert_at(9, text("z")).is_err());
+
+    items.index(2).delete_self()?;
+    let values: Vec<Value> = match items.get()? {
+        Value::List(list) => list.iter().cloned().collect(),
+        other => panic!("Expected a list, got {other:?}"),
+    };
+    assert_eq!(values, vec![text("a"), text("b"), text("d"), text("e")]);
+    assert!(items.index(4).get().unwrap_err().is_not_found());
+    Ok(())
+}
+
+#[test]
+fn test_value_editor_list_type_errors() -> eidetica::Result<()> {
+    let (_, _, _op, dict) = setup_complete_test_env("editor_test_store")?;
+    dict.set("name", "alice")?;
+    dict.get_value_mut("items").push(text("one"))?;
+
+    assert!(dict.get_value_mut("name").push(text("x")).is_err());
+    assert!(dict.get_value_mut("name").index(0).get().is_err());
+    assert!(dict.get_value_mut("items").index(3).set(text("x")).is_err());
+    assert!(
+        dict.get_value_mut("missing")
+            .index(0)
+            .set(text("x"))
+            .is_err()
+    );
+    assert!(
+        dict.get_value_mut("items")
+            .index(1)
+            .get()
+            .unwrap_err()
+            .is_not_found()
+    );
+    Ok(())
+}
+
+#[test]
+fn test_value_editor_concurrent_element_edits_merge() -> eidetica::Result<()> {
+    let (_, tree, op, dict) = setup_complete_test_env("editor_test_store")?;
+    let users = dict.get_value_mut("users");
+    for name in ["alice", "bob"] {
+        users.push(Value::Map(eidetica::crdt::Map::new()))?;
+        let index = users.get().map(|value| match value {
+            Value::List(list) => list.len() - 1,
+            _ => unreachable!(),
+        })?;
+        users.index(index).get_value_mut("name").set(text(name))?;
+    }
+    op.commit()?;
+    let tips = tree.get_tips()?;
+
+    // Two branches edit different elements of the same list
+    for (index, role) in [(0, "admin"), (1, "guest")] {
+        let op = tree.new_operation_with_tips(&tips)?;
+        let dict = op.get_subtree::<eidetica::subtree::Dict>("editor_test_store")?;
+        dict.get_value_mut("users")
+            .index(index)
+            .get_value_mut("role")
+            .set(text(role))?;
+        op.commit()?;
+    }
+
+    let viewer = tree.get_subtree_viewer::<eidetica::subtree::Dict>("editor_test_store")?;
+    let users = viewer.get_value_mut("users");
+    assert_text_value(&users.index(0).get_value("name")?, "alice");
+    assert_text_value(&users.index(0).get_value("role")?, "admin");
+    assert_text_value(&users.index(1).get_value("name")?, "bob");
+    assert_text_value(&users.index(1).get_value("role")?, "guest");
+    Ok(())
+}
@@ -0,0 +1,122 @@
+use crate::helpers::*;
+use eidetica::entry::{AppInfo, Entry};
+use eidetica::subtree::Dict;
+
+fn laptop() -> AppInfo {
+    AppInfo::new()
+        .with_device("laptop")
+        .with_app_version("1.2.0")
+}
+
+#[test]
+fn test_builder_app_info_is_part_of_id() {
+    let plain = Entry::builder("root_id").build();
+    let entry = Entry::builder("root_id").set_app_info(laptop()).build();
+
+    assert_eq!(plain.app_info(), None);
+    assert_eq!(entry.app_info(), Some(laptop()));
+    assert_ne!(plain.id(), entry.id());
+
+    let phone = Entry::builder("root_id")
+        .set_app_info(AppInfo::new().with_device("phone"))
+        .build();
+    assert_ne!(phone.id(), entry.id());
+}
+
+#[test]
+fn test_builder_app_info_keeps_other_metadata() {
+    let mut builder = Entry::builder("root_id").set_metadata(r#"{"custom":"data"}"#);
+    builder.set_app_info_mut(laptop());
+    let metadata: serde_json::Value = serde_json::from_str(builder.metadata().unwrap()).unwrap();
+    assert_eq!(metadata["custom"], "data");
+    assert_eq!(metadata["app"]["device"], "laptop");
+
+    // An empty AppInfo removes it again
+    builder.set_app_info_mut(AppInfo::new());
+    let entry = builder.build();
+    assert_eq!(entry.app_info(), None);
+    assert_eq!(entry.metadata().unwrap(), r#"{"custom":"data"}"#);
+}
+
+#[test]
+fn test_operation_app_info_is_committed() {
+    let tree = setup_tree();
+
+    let op = tree.new_operation().unwrap();
+    op.get_subtree::<Dict>("data")
+        .unwrap()
+        .set("key", "value")
+        .unwrap();
+    op.set_app_info(laptop()).unwrap();
+    let id = op.commit().unwrap();
+
+    let entry = tree.get_entry(&id).unwrap();
+    assert_eq!(entry.app_info(), Some(laptop()));
+    assert!(entry.timestamp().is_some());
+
+    // Entries without app info report none
+    let op = tree.new_operation().unwrap();
+    op.get_subtree::<Dict>("data")
+        .unwrap()
+        .set("key", "other")
+        .unwrap();
+    let id = op.commit().unwrap();
+    assert_eq!(tree.get_entry(&id).unwrap().app_info(), None);
+}
+
+#[test]
+fn test_database_default_app_info() {
+    let (db, tree) = setup_db_and_tree_with_key("key");
+    assert_eq!(db.default_app_info(), None);
+
+    // Applies to trees loaded before the default was set
+    db.set_default_app_info(laptop());
+    assert_eq!(db.default_app_info(), Some(laptop()));
+    let op = tree.new_operation().unwrap();
+    op.get_subtree::<Dict>("data")
+        .unwrap()
+        .set("key", "value")
+        .unwrap();
+    let id = op.commit().unwrap();
+    assert_eq!(tree.get_entry(&id).unwrap().app_info(), Some(laptop()));
+
+    // An operation can override the default
+    let phone = AppInfo::new().with_device("phone");
+    let op = tree.new_operation().unwrap();
+    op.get_subtree::<Dict>("data")
+        .unwrap()
+        .set("key", "other")
+        .unwrap();
+    op.set_app_info(phone.clone()).unwrap();
+    let id = op.commit().unwrap();
+    assert_eq!(tree.get_entry(&id).unwrap().app_info(), Some(phone));
+
+    db.clear_default_app_info();
+    let op = tree.new_operation().unwrap();
+    op.get_subtree::<Dict>("data")
+        .unwrap()
+        .set("key", "last")
+        .unwrap();
+    let id = op.commit().unwrap();
+    assert_eq!(tree.get_entry(&id).unwrap().app_info(), None);
+}
+
+#[test]
+fn test_history_reports_app_info() {
+    let (db, tree) = setup_db_and_tree_with_key("key");
+    db.set_default_app_info(laptop());
+    let op = tree.new_operation().unwrap();
+    op.get_subtree::<Dict>("data")
+        .unwrap()
+        .set("key", "value")
+        .unwrap();
+    op.commit().unwrap();
+
+    let history = tree
+        .get_subtree_viewer::<Dict>("data")
+        .unwrap()
+        .history("key")
+        .unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].app_info, Some(laptop()));
+}
@@ -0,0 +1,201 @@
+use crate::helpers::*;
+use eidetica::Tree;
+use eidetica::auth::validation::AuthValidator;
+use eidetica::basedb::BaseDB;
+use eidetica::constants::{HASH_ALGORITHM, SETTINGS};
+use eidetica::crdt::Map;
+use eidetica::entry::{Entry, HashAlgorithm, ID};
+use eidetica::subtree::Dict;
+
+const TEST_KEY: &str = "test_key";
+
+fn blake3_tree(db: &BaseDB) -> Tree {
+    let mut settings = Map::new();
+    settings.set_string("name", "blake3_tree");
+    settings.set_string(HASH_ALGORITHM, "blake3");
+    db.new_tree(settings, TEST_KEY).unwrap()
+}
+
+#[test]
+fn test_id_format() {
+    let sha256 = HashAlgorithm::Sha256.hash(b"data");
+    assert_eq!(sha256.len(), 64);
+    assert_eq!(sha256.hash_algorithm(), Some(HashAlgorithm::Sha256));
+    assert_eq!(sha256.digest(), Some(sha256.as_str()));
+
+    let blake3 = HashAlgorithm::Blake3.hash(b"data");
+    assert_eq!(blake3.len(), 68);
+    assert!(blake3.starts_with("1e20"));
+    assert_eq!(blake3.hash_algorithm(), Some(HashAlgorithm::Blake3));
+    assert_eq!(blake3.digest(), Some(&blake3[4..]));
+    assert_ne!(blake3.digest(), sha256.digest());
+
+    // The prefixed form of SHA-256 is recognized too
+    let prefixed = ID::new(format!("1220{sha256}"));
+    assert_eq!(prefixed.hash_algorithm(), Some(HashAlgorithm::Sha256));
+    assert_eq!(prefixed.digest(), Some(sha256.as_str()));
+
+    for id in [
+        "",
+        "root_id",
+        "1e20abc",
+        &sha256.to_uppercase(),
+        &format!("1320{sha256}"),
+    ] {
+        assert_eq!(ID::new(id).hash_algorithm(), None, "{id}");
+    }
+
+    assert_eq!(
+        HashAlgorithm::from_name("blake3"),
+        Some(HashAlgorithm::Blake3)
+    );
+    assert_eq!(HashAlgorithm::from_name("md5"), None);
+    assert_eq!(HashAlgorithm::default().to_string(), "sha256");
+}
+
+#[test]
+fn test_entry_hash_algorithm() {
+    let sha256 = Entry::builder("root_id").build();
+    assert_eq!(sha256.hash_algorithm(), HashAlgorithm::Sha256);
+    // SHA-256 entries do not record the algorithm, so their IDs are unchanged
+    assert!(!serde_json::to_string(&sha256).unwrap().contains("hash"));
+
+    let blake3 = Entry::builder("root_id")
+        .set_hash_algorithm(HashAlgorithm::Blake3)
+        .build();
+    assert_eq!(blake3.hash_algorithm(), HashAlgorithm::Blake3);
+    assert_eq!(blake3.id().hash_algorithm(), Some(HashAlgorithm::Blake3));
+
+    // The algorithm survives serialization, so the ID does too
+    let json = serde_json::to_string(&blake3).unwrap();
+    let parsed: Entry = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed.id(), blake3.id());
+
+    // Builders take the algorithm of the root ID
+    let child = Entry::builder(blake3.id()).build();
+    assert_eq!(child.hash_algorithm(), HashAlgorithm::Blake3);
+}
+
+#[test]
+fn test_default_tree_uses_sha256() {
+    let (_db, tree) = setup_db_and_tree_with_key(TEST_KEY);
+    let id = commit_dict_value(&tree, "data", "key", "value");
+
+    assert_eq!(tree.root_id().len(), 64);
+    assert_eq!(id.hash_algorithm(), Some(HashAlgorithm::Sha256));
+    assert_eq!(
+        tree.get_entry(&id).unwrap().hash_algorithm(),
+        HashAlgorithm::Sha256
+    );
+}
+
+#[test]
+fn test_blake3_tree() {
+    let db = setup_db_with_key(TEST_KEY);
+    let tree = blake3_tree(&db);
+    assert_eq!(tree.root_id().hash_algorithm(), Some(HashAlgorithm::Blake3));
+
+    let first = commit_dict_value(&tree, "data", "key", "value");
+    let second = commit_dict_value(&tree, "data", "key", "changed");
+    for id in [tree.root_id().clone(), first, second.clone()] {
+        let entry = tree.get_entry(&id).unwrap();
+        assert_eq!(entry.hash_algorithm(), HashAlgorithm::Blake3);
+        assert_eq!(entry.id(), id);
+        assert!(tree.verify_entry_signature(&id).unwrap());
+    }
+    assert_eq!(tree.get_tips().unwrap(), vec![second]);
+
+    // Loading the tree again keeps hashing with BLAKE3
+    let mut loaded = db.load_tree(tree.root_id()).unwrap();
+    loaded.set_default_auth_key(TEST_KEY);
+    let third = commit_dict_value(&loaded, "data", "other", "value");
+    assert_eq!(third.hash_algorithm(), Some(HashAlgorithm::Blake3));
+    let data = loaded.get_subtree_viewer::<Dict>("data").unwrap();
+    assert_eq!(data.get_string("key").unwrap(), "changed");
+    assert_eq!(data.get_string("other").unwrap(), "value");
+}
+
+#[test]
+fn test_unknown_algorithm_is_rejected() {
+    let db = setup_db_with_key(TEST_KEY);
+    let mut settings = Map::new();
+    settings.set_string(HASH_ALGORITHM, "md5");
+
+    match db.new_tree(settings, TEST_KEY) {
+        Err(eidetica::Error::Base(err)) => assert!(err.is_validation_error()),
+        Err(other) => panic!("unexpected error: {other:?}"),
+        Ok(_) => panic!("tree created with an unknown hash algorithm"),
+    }
+}
+
+#[test]
+fn test_algorithm_cannot_be_changed() {
+    let db = setup_db_with_key(TEST_KEY);
+    let tree = blake3_tree(&db);
+
+    let op = tree.new_operation().unwrap();
+    op.get_subtree::<Dict>(SETTINGS)
+        .unwrap()
+        .set(HASH_ALGORITHM, "sha256")
+        .unwrap();
+    match op.commit().unwrap_err() {
+        eidetica::Error::AtomicOp(err) => assert!(err.is_hash_algorithm_mismatch()),
+        other => panic!("unexpected error: {other:?}"),
+    }
+
+    // Repeating the recorded algorithm is fine
+    let op = tree.new_operation().unwrap();
+    op.get_subtree::<Dict>(SETTINGS)
+        .unwrap()
+        .set(HASH_ALGORITHM, "blake3")
+        .unwrap();
+    op.commit().unwrap();
+}
+
+#[test]
+fn test_validation_rejects_other_algorithms() {
+    let mut validator = AuthValidator::new();
+    let mut blake3_settings = Map::new();
+    blake3_settings.set_string(HASH_ALGORITHM, "blake3");
+
+    let sha256 = Entry::builder("root_id").build();
+    let blake3 = Entry::builder("root_id")
+        .set_hash_algorithm(HashAlgorithm::Blake3)
+        .build();
+
+    // Unsigned entries are otherwise accepted
+    assert!(
+        validator
+            .validate_entry(&sha256, &Map::new(), None)
+            .unwrap()
+    );
+    assert!(
+        !validator
+            .validate_entry(&blake3, &Map::new(), None)
+            .unwrap()
+    );
+    assert!(
+        validator
+            .validate_entry(&blake3, &blake3_settings, None)
+            .unwrap()
+    );
+    assert!(
+        !validator
+            .validate_entry(&sha256, &blake3_settings, None)
+            .unwrap()
+    );
+
+    // Nor may an entry change the recorded algorithm
+    let change = Entry::builder("root_id")
+        .set_hash_algorithm(HashAlgorithm::Blake3)
+        .set_subtree_data(
+            SETTINGS,
+            r#"{"children":{"hash_algorithm":{"Text":"sha256"}}}"#,
+        )
+        .build();
+    assert!(
+        !validator
+            .validate_entry(&change, &blake3_settings, None)
+            .unwrap()
+    );
+}
@@ -1,5 +1,8 @@
+mod app_info;
 mod basic;
 mod builder;
+mod hash;
 mod helpers;
 mod id_determinism;
 mod subtree;
+mod validation;
@@ -0,0 +1,161 @@
+//! Entry structure validation tests
+//!
+//! Tests for `Entry::validate_structure` and `Entry::validate_as`: the size and
+//! count limits, the subtree naming rules, the canonical form produced by
+//! `EntryBuilder`, and the check against an entry's ID.
+
+use eidetica::Error;
+use eidetica::entry::{
+    Entry, EntryError, ID, MAX_ENTRY_SIZE, MAX_PARENTS, MAX_SUBTREE_NAME_LEN, MAX_SUBTREES,
+};
+use serde_json::Value as Json;
+
+fn entry_error(result: eidetica::Result<()>) -> EntryError {
+    match result {
+        Err(Error::Entry(err)) => err,
+        other => panic!("expected an entry error, got {other:?}"),
+    }
+}
+
+/// Edits the serialized form of `entry`, as a malicious peer or a damaged
+/// file could, and decodes the result.
+fn tamper(entry: &Entry, f: impl FnOnce(&mut Json)) -> Entry {
+    let mut json = serde_json::to_value(entry).unwrap();
+    f(&mut json);
+    serde_json::from_value(json).unwrap()
+}
+
+#[test]
+fn test_built_entries_are_valid() {
+    let root = Entry::root_builder()
+        .set_subtree_data("_settings", "{}")
+        .build();
+    root.validate_structure().unwrap();
+    root.validate_as(&root.id()).unwrap();
+
+    let entry = Entry::builder(root.id())
+        .set_parents(vec!["b".into(), "a".into(), "b".into()])
+        .set_subtree_data("users", "{}")
+        .set_subtree_parents("users", vec!["d".into(), "c".into()])
+        .set_subtree_data("posts", "{}")
+        .build();
+    entry.validate_as(&entry.id()).unwrap();
+
+    let err = entry_error(entry.validate_as(&root.id()));
+    assert!(err.is_id_mismatch());
+    match err {
+        EntryError::IdMismatch { expected, actual } => {
+            assert_eq!(expected, root.id());
+            assert_eq!(actual, entry.id());
+        }
+        other => panic!("unexpected error {other:?}"),
+    }
+}
+
+#[test]
+fn test_limits() {
+    let parents: Vec<ID> = (0..=MAX_PARENTS)
+        .map(|i| format!("p{i:05}").into())
+        .collect();
+    let entry = Entry::builder("root").set_parents(parents.clone()).build();
+    let err = entry_error(entry.validate_structure());
+    assert!(err.is_limit_exceeded());
+    assert!(matches!(
+        err,
+        EntryError::TooManyParents { subtree: None, count, .. } if count == MAX_PARENTS + 1
+    ));
+
+    let entry = Entry::builder("root")
+        .set_subtree_data("users", "{}")
+        .set_subtree_parents("users", parents)
+        .build();
+    assert!(matches!(
+        entry_error(entry.validate_structure()),
+        EntryError::TooManyParents { subtree: Some(name), .. } if name == "users"
+    ));
+
+    let mut builder = Entry::builder("root");
+    for i in 0..=MAX_SUBTREES {
+        builder.set_subtree_data_mut(format!("s{i:05}"), "{}");
+    }
+    assert!(matches!(
+        entry_error(builder.build().validate_structure()),
+        EntryError::TooManySubtrees { .. }
+    ));
+
+    let entry = Entry::builder("root")
+        .set_subtree_data("blob", "x".repeat(MAX_ENTRY_SIZE))
+        .build();
+    assert!(matches!(
+        entry_error(entry.validate_structure()),
+        EntryError::TooLarge {
+            limit: MAX_ENTRY_SIZE,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn test_subtree_names() {
+    let long = "n".repeat(MAX_SUBTREE_NAME_LEN);
+    Entry::builder("root")
+        .set_subtree_data(long.clone(), "{}")
+        .build()
+        .validate_structure()
+        .unwrap();
+
+    for name in [String::new(), format!("{long}n"), "line\nbreak".to_string()] {
+        let entry = Entry::builder("root")
+            .set_subtree_data(name.clone(), "{}")
+            .build();
+        match entry_error(entry.validate_structure()) {
+            EntryError::InvalidSubtreeName { name: found, .. } => assert_eq!(found, name),
+            other => panic!("unexpected error {other:?} for {name:?}"),
+        }
+    }
+}
+
+#[test]
+fn test_non_canonical_entries() {
+    let entry = Entry::builder("root")
+        .set_parents(vec!["a".into(), "b".into()])
+        .set_subtree_data("posts", "{}")
+        .set_subtree_data("users", "{}")
+        .set_subtree_parents("users", vec!["c".into(), "d".into()])
+        .build();
+
+    let unsorted = tamper(&entry, |json| {
+        json["tree"]["parents"] = serde_json::json!(["b", "a"]);
+    });
+    assert!(matches!(
+        entry_error(unsorted.validate_structure()),
+        EntryError::NotCanonical { .. }
+    ));
+    // The reordered entry hashes to a different ID than the original
+    assert_ne!(unsorted.id(), entry.id());
+
+    let duplicate = tamper(&entry, |json| {
+        json["subtrees"][1]["parents"] = serde_json::json!(["c", "c"]);
+    });
+    let err = entry_error(duplicate.validate_structure());
+    assert!(err.is_malformed());
+    assert!(err.to_string().contains("subtree 'users'"));
+
+    let swapped = tamper(&entry, |json| {
+        json["subtrees"].as_array_mut().unwrap().swap(0, 1);
+    });
+    assert!(matches!(
+        entry_error(swapped.validate_structure()),
+        EntryError::NotCanonical { .. }
+    ));
+
+    let repeated = tamper(&entry, |json| {
+        let first = json["subtrees"][0].clone();
+        json["subtrees"].as_array_mut().unwrap().insert(0, first);
+    });
+    assert!(
+        entry_error(repeated.validate_structure())
+            .to_string()
+            .contains("listed twice")
+    );
+}
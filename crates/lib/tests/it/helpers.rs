@@ -1,5 +1,8 @@
+use eidetica::Tree;
+use eidetica::atomicop::AtomicOp;
 use eidetica::backend::database::InMemory;
 use eidetica::crdt::map::Value;
+use eidetica::entry::ID;
 use eidetica::subtree::Dict;
 
 const DEFAULT_TEST_KEY_NAME: &str = "test_key";
@@ -76,6 +79,25 @@ pub fn setup_tree_with_settings(settings: &[(&str, &str)]) -> eidetica::Tree {
     tree
 }
 
+/// Commits `value` under `key` in the `Dict` subtree `subtree` of `tree`
+pub fn commit_dict_value(tree: &Tree, subtree: &str, key: &str, value: &str) -> ID {
+    commit_dict_write(tree.new_operation(), subtree, key, value)
+}
+
+/// Like [`commit_dict_value`], but on top of `tips` instead of the tree's tips
+pub fn commit_dict_value_on(tree: &Tree, tips: &[ID], subtree: &str, key: &str, value: &str) -> ID {
+    commit_dict_write(tree.new_operation_with_tips(tips), subtree, key, value)
+}
+
+fn commit_dict_write(op: eidetica::Result<AtomicOp>, subtree: &str, key: &str, value: &str) -> ID {
+    let op = op.expect("Failed to create operation");
+    op.get_subtree::<Dict>(subtree)
+        .expect("Failed to get subtree")
+        .set(key, value)
+        .expect("Failed to set value");
+    op.commit().expect("Failed to commit value")
+}
+
 /// Helper for common assertions around Dict value retrieval
 pub fn assert_dict_value(store: &Dict, key: &str, expected: &str) {
     match store
@@ -12,6 +12,8 @@
  * - crdt: Tests for the CRDT implementations (Map, List, Value types)
  * - data: Tests for the CRDT trait and implementations (e.g., KVOverWrite)
  * - entry: Tests for the Entry struct and related functionality
+ * - query: Tests for preparing and executing queries over subtrees
+ * - sync: Tests for syncing trees between databases with SyncPeer
  * - tree: Tests for the Tree struct and related functionality
  */
 
@@ -23,5 +25,7 @@ mod crdt;
 mod data;
 mod entry;
 mod helpers;
+mod query;
 mod subtree;
+mod sync;
 mod tree;
@@ -0,0 +1,149 @@
+use crate::helpers::*;
+use eidetica::query::{Query, QueryError, eq, ge, gt, lt, ne, param};
+use eidetica::subtree::{Dict, Table, TableSchema};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct User {
+    name: String,
+    age: i64,
+    #[serde(default)]
+    city: Option<String>,
+}
+
+fn user(name: &str, age: i64, city: &str) -> User {
+    User {
+        name: name.to_string(),
+        age,
+        city: Some(city.to_string()),
+    }
+}
+
+#[test]
+fn test_dict_query_includes_staged_changes() {
+    let tree = setup_tree();
+    let op = tree.new_operation().unwrap();
+    let users = op.get_subtree::<Dict>("users").unwrap();
+    users.set_path("alice.age", 34).unwrap();
+    users.set_path("bob.age", 27).unwrap();
+    op.commit().unwrap();
+
+    let op = tree.new_operation().unwrap();
+    let users = op.get_subtree::<Dict>("users").unwrap();
+    users.set_path("carol.age", 41).unwrap();
+
+    let rows = users
+        .query(&Query::subtree("users").where_field("age", gt(30)))
+        .unwrap();
+    let keys: Vec<&str> = rows.iter().map(|row| row.key.as_str()).collect();
+    assert_eq!(keys, ["alice", "carol"]);
+
+    let rows = users
+        .query(&Query::subtree("users").where_key(eq("bob")))
+        .unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].key, "bob");
+}
+
+#[test]
+fn test_table_query_returns_records() {
+    let tree = setup_tree();
+    let op = tree.new_operation().unwrap();
+    let users = op.get_subtree::<Table<User>>("users").unwrap();
+    users.set("a", user("Ann", 52, "Oslo")).unwrap();
+    users.set("b", user("Ben", 23, "Lima")).unwrap();
+    users.set("c", user("Cal", 37, "Oslo")).unwrap();
+    users.set("d", user("Dee", 61, "Pune")).unwrap();
+
+    let query = Query::subtree("users")
+        .where_field("age", ge(30))
+        .where_field("city", ne("Pune"));
+    let rows = users.query(&query).unwrap();
+    assert_eq!(
+        rows,
+        vec![
+            ("a".to_string(), user("Ann", 52, "Oslo")),
+            ("c".to_string(), user("Cal", 37, "Oslo")),
+        ]
+    );
+
+    let rows = users.query(&query.limit(1)).unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].0, "a");
+
+    let rows = users
+        .query(
+            &Query::subtree("users")
+                .where_key(eq("b"))
+                .where_field("age", lt(30)),
+        )
+        .unwrap();
+    assert_eq!(rows, vec![("b".to_string(), user("Ben", 23, "Lima"))]);
+}
+
+#[test]
+fn test_table_query_matches_upgraded_rows() {
+    #[derive(Clone, Serialize, Deserialize)]
+    struct UserV0 {
+        full_name: String,
+        age: i64,
+    }
+
+    let tree = setup_tree();
+    let op = tree.new_operation().unwrap();
+    let old = op.get_subtree::<Table<UserV0>>("users").unwrap();
+    old.set(
+        "a",
+        UserV0 {
+            full_name: "Ann".to_string(),
+            age: 52,
+        },
+    )
+    .unwrap();
+    op.commit().unwrap();
+
+    // The stored row has no `name`, but the upgraded record does
+    let op = tree.new_operation().unwrap();
+    let users = op
+        .get_subtree::<Table<User>>("users")
+        .unwrap()
+        .with_schema(TableSchema::new(1).rename_field(0, "full_name", "name"));
+    let rows = users
+        .query(&Query::subtree("users").where_field("name", eq("Ann")))
+        .unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].1.city, None);
+}
+
+#[test]
+fn test_handle_query_errors() {
+    let tree = setup_tree();
+    let op = tree.new_operation().unwrap();
+    let users = op.get_subtree::<Table<User>>("users").unwrap();
+    users.insert(user("Ann", 52, "Oslo")).unwrap();
+
+    let err = users
+        .query(&Query::subtree("accounts").where_field("age", gt(30)))
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        eidetica::Error::Query(QueryError::SubtreeMismatch { .. })
+    ));
+
+    let err = users
+        .query(&Query::subtree("users").where_field("age", gt(param("age"))))
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        eidetica::Error::Query(QueryError::MissingParameter { .. })
+    ));
+
+    let dict = op.get_subtree::<Dict>("users").unwrap();
+    let err = dict
+        .query(&Query::subtree("users").where_field("a..b", eq(1)))
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        eidetica::Error::Query(QueryError::InvalidField { .. })
+    ));
+}
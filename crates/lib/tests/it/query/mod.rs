@@ -0,0 +1,8 @@
+//! Query integration tests
+//!
+//! This module tests preparing queries with `Tree::prepare` and executing them
+//! with parameters against Dict and Table subtrees, and running queries directly
+//! on Dict and Table handles.
+
+mod handles;
+mod prepared;
@@ -0,0 +1,173 @@
+use crate::helpers::*;
+use eidetica::Tree;
+use eidetica::query::{Params, Query, eq, ge, gt, lt, param};
+use eidetica::subtree::{Dict, Table, TableSchema};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct User {
+    name: String,
+    age: i64,
+}
+
+fn add_users(tree: &Tree) {
+    let op = tree.new_operation().unwrap();
+    let users = op.get_subtree::<Dict>("users").unwrap();
+    for (key, age, city) in [
+        ("alice", 34, "Oslo"),
+        ("bob", 27, "Lima"),
+        ("carol", 41, "Oslo"),
+        ("dave", 19, "Pune"),
+    ] {
+        users.set_path(format!("{key}.age"), age).unwrap();
+        users.set_path(format!("{key}.address.city"), city).unwrap();
+    }
+    op.commit().unwrap();
+}
+
+fn keys(rows: &[eidetica::query::Row]) -> Vec<&str> {
+    rows.iter().map(|row| row.key.as_str()).collect()
+}
+
+#[test]
+fn test_prepared_query_runs_with_different_parameters() {
+    let tree = setup_tree();
+    add_users(&tree);
+
+    let query = Query::subtree("users").where_field("age", ge(param("min_age")));
+    let prepared = tree.prepare(&query).unwrap();
+    assert_eq!(prepared.parameters(), ["min_age"]);
+    assert!(!prepared.is_key_lookup());
+
+    let rows = prepared.execute(&Params::new().set("min_age", 30)).unwrap();
+    assert_eq!(keys(&rows), ["alice", "carol"]);
+
+    let rows = prepared.execute(&Params::new().set("min_age", 20)).unwrap();
+    assert_eq!(keys(&rows), ["alice", "bob", "carol"]);
+}
+
+#[test]
+fn test_prepared_query_nested_fields_and_limit() {
+    let tree = setup_tree();
+    add_users(&tree);
+
+    let query = Query::subtree("users")
+        .where_field("address.city", eq(param("city")))
+        .where_field("age", lt(100))
+        .limit(1);
+    let prepared = tree.prepare(&query).unwrap();
+
+    let rows = prepared
+        .execute(&Params::new().set("city", "Oslo"))
+        .unwrap();
+    assert_eq!(keys(&rows), ["alice"]);
+    let rows = prepared
+        .execute(&Params::new().set("city", "Rome"))
+        .unwrap();
+    assert!(rows.is_empty());
+}
+
+#[test]
+fn test_prepared_query_key_lookup() {
+    let tree = setup_tree();
+    add_users(&tree);
+
+    let prepared = tree
+        .prepare(&Query::subtree("users").where_key(eq(param("user"))))
+        .unwrap();
+    assert!(prepared.is_key_lookup());
+
+    let rows = prepared.execute(&Params::new().set("user", "bob")).unwrap();
+    assert_eq!(keys(&rows), ["bob"]);
+    assert!(
+        prepared
+            .execute(&Params::new().set("user", "nobody"))
+            .unwrap()
+            .is_empty()
+    );
+
+    let err = prepared.execute(&Params::new().set("user", 7)).unwrap_err();
+    assert_eq!(err.module(), "query");
+}
+
+#[test]
+fn test_prepared_query_sees_later_commits() {
+    let tree = setup_tree();
+    let prepared = tree
+        .prepare(&Query::subtree("users").where_field("age", gt(30)))
+        .unwrap();
+    assert!(prepared.execute(&Params::new()).unwrap().is_empty());
+
+    add_users(&tree);
+    assert_eq!(
+        keys(&prepared.execute(&Params::new()).unwrap()),
+        ["alice", "carol"]
+    );
+
+    let op = tree.new_operation().unwrap();
+    op.get_subtree::<Dict>("users")
+        .unwrap()
+        .set_path("bob.age", 31)
+        .unwrap();
+    op.commit().unwrap();
+    assert_eq!(
+        keys(&prepared.execute(&Params::new()).unwrap()),
+        ["alice", "bob", "carol"]
+    );
+}
+
+#[test]
+fn test_prepared_query_over_table_rows() {
+    let tree = setup_tree();
+    let op = tree.new_operation().unwrap();
+    let plain = op.get_subtree::<Table<User>>("plain").unwrap();
+    let tagged = op
+        .get_subtree::<Table<User>>("tagged")
+        .unwrap()
+        .with_schema(TableSchema::new(1));
+    for (name, age) in [("Ann", 52), ("Ben", 23)] {
+        let user = User {
+            name: name.to_string(),
+            age,
+        };
+        plain.insert(user.clone()).unwrap();
+        tagged.insert(user).unwrap();
+    }
+    op.commit().unwrap();
+
+    for subtree in ["plain", "tagged"] {
+        let prepared = tree
+            .prepare(&Query::subtree(subtree).where_field("age", gt(param("age"))))
+            .unwrap();
+        let rows = prepared.execute(&Params::new().set("age", 40)).unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].decode::<User>().unwrap().name, "Ann");
+    }
+}
+
+#[test]
+fn test_prepare_and_execute_errors() {
+    let tree = setup_tree();
+    add_users(&tree);
+
+    let err = tree
+        .prepare(&Query::subtree("users").where_field("address..city", eq("Oslo")))
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        eidetica::Error::Query(ref e) if e.is_invalid_query() && e.field() == Some("address..city")
+    ));
+
+    let err = tree
+        .prepare(&Query::subtree("users").where_key(eq(3)))
+        .unwrap_err();
+    assert_eq!(err.module(), "query");
+
+    let prepared = tree
+        .prepare(&Query::subtree("users").where_field("age", gt(param("age"))))
+        .unwrap();
+    match prepared.execute(&Params::new()).unwrap_err() {
+        eidetica::Error::Query(e) => assert_eq!(e.parameter(), Some("age")),
+        other => panic!("Unexpected error: {other}"),
+    }
+}
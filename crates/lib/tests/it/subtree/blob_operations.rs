@@ -0,0 +1,109 @@
+//! BlobStore subtree operation tests
+//!
+//! This module contains tests for BlobStore functionality including storing and
+//! reading binary content, chunking, deduplication and merging concurrent additions.
+
+use crate::helpers::*;
+use eidetica::Tree;
+use eidetica::subtree::BlobStore;
+
+/// Creates content spanning several chunks, with a distinct value in every byte position.
+fn large_content() -> Vec<u8> {
+    (0..BlobStore::CHUNK_SIZE * 2 + 1000)
+        .map(|i| (i % 251) as u8)
+        .collect()
+}
+
+fn put(tree: &Tree, content: &[u8]) -> String {
+    let op = tree.new_operation().unwrap();
+    let blobs = op.get_subtree::<BlobStore>("blobs").unwrap();
+    let id = blobs.put(content).unwrap();
+    op.commit().unwrap();
+    id
+}
+
+#[test]
+fn test_blob_put_and_get() {
+    let tree = setup_tree();
+    let binary: Vec<u8> = (0..=255).collect();
+    let id = put(&tree, &binary);
+
+    let blobs = tree.get_subtree_viewer::<BlobStore>("blobs").unwrap();
+    assert_eq!(blobs.get(&id).unwrap(), binary);
+    assert_eq!(blobs.size(&id).unwrap(), 256);
+    assert!(blobs.contains(&id).unwrap());
+    assert!(blobs.get("missing").unwrap_err().is_not_found());
+
+    // Empty content is a valid blob
+    let empty = put(&tree, b"");
+    let blobs = tree.get_subtree_viewer::<BlobStore>("blobs").unwrap();
+    assert!(blobs.get(&empty).unwrap().is_empty());
+    assert_eq!(blobs.list().unwrap().len(), 2);
+}
+
+#[test]
+fn test_blob_content_addressing() {
+    let tree = setup_tree();
+    let op = tree.new_operation().unwrap();
+    let blobs = op.get_subtree::<BlobStore>("blobs").unwrap();
+    let first = blobs.put(b"same content").unwrap();
+    let second = blobs.put(b"same content").unwrap();
+    let other = blobs.put(b"other content").unwrap();
+    assert_eq!(first, second);
+    assert_ne!(first, other);
+    assert_eq!(first.len(), 64);
+    op.commit().unwrap();
+
+    // Identical content stored in another tree gets the same ID
+    let other_tree = setup_tree();
+    assert_eq!(put(&other_tree, b"same content"), first);
+}
+
+#[test]
+fn test_blob_large_content_is_chunked_and_deduplicated() {
+    let tree = setup_tree();
+    let content = large_content();
+    let id = put(&tree, &content);
+
+    let blobs = tree.get_subtree_viewer::<BlobStore>("blobs").unwrap();
+    assert_eq!(blobs.get(&id).unwrap(), content);
+
+    // Re-storing the blob returns the same ID
+    let op = tree.new_operation().unwrap();
+    let blobs = op.get_subtree::<BlobStore>("blobs").unwrap();
+    assert_eq!(blobs.put(&content).unwrap(), id);
+
+    // A blob sharing its leading chunks only stores the new chunk
+    let mut extended = content.clone();
+    extended.extend_from_slice(b"appended");
+    let extended_id = blobs.put(&extended).unwrap();
+    let entry_id = op.commit().unwrap();
+
+    // Only the manifest and the final chunk were written by this entry
+    let entry = tree.get_entry(&entry_id).unwrap();
+    let data = entry.data("blobs").unwrap();
+    assert!(data.len() < BlobStore::CHUNK_SIZE);
+
+    let blobs = tree.get_subtree_viewer::<BlobStore>("blobs").unwrap();
+    assert_eq!(blobs.get(&extended_id).unwrap(), extended);
+}
+
+#[test]
+fn test_blob_concurrent_additions_merge() {
+    let tree = setup_tree();
+    put(&tree, b"seed");
+    let base = tree.get_tips().unwrap();
+
+    let mut ids = Vec::new();
+    for content in [b"from a".as_slice(), b"from b".as_slice()] {
+        let op = tree.new_operation_with_tips(&base).unwrap();
+        let blobs = op.get_subtree::<BlobStore>("blobs").unwrap();
+        ids.push(blobs.put(content).unwrap());
+        op.commit().unwrap();
+    }
+
+    let blobs = tree.get_subtree_viewer::<BlobStore>("blobs").unwrap();
+    assert_eq!(blobs.get(&ids[0]).unwrap(), b"from a");
+    assert_eq!(blobs.get(&ids[1]).unwrap(), b"from b");
+    assert_eq!(blobs.list().unwrap().len(), 3);
+}
@@ -0,0 +1,189 @@
+//! DictModel derive tests
+//!
+//! This module tests storing structs in Dict paths via `#[derive(DictModel)]`,
+//! including nested models, optional fields, field attributes and merging.
+
+use crate::helpers::*;
+use eidetica::subtree::{Dict, DictModel};
+
+#[derive(DictModel, Debug, Clone, PartialEq)]
+struct Endpoint {
+    host: String,
+    port: u16,
+}
+
+#[derive(DictModel, Debug, Clone, PartialEq)]
+struct AppConfig {
+    name: String,
+    enabled: bool,
+    retries: u32,
+    motd: Option<String>,
+    endpoint: Endpoint,
+    #[model(rename = "tag_list", json)]
+    tags: Vec<String>,
+    #[model(default)]
+    verbosity: i64,
+}
+
+fn sample_config() -> AppConfig {
+    AppConfig {
+        name: "demo".to_string(),
+        enabled: true,
+        retries: 3,
+        motd: Some("hello".to_string()),
+        endpoint: Endpoint {
+            host: "localhost".to_string(),
+            port: 8080,
+        },
+        tags: vec!["a".to_string(), "b".to_string()],
+        verbosity: 2,
+    }
+}
+
+#[test]
+fn test_dict_model_roundtrip_and_layout() {
+    let tree = setup_tree();
+
+    let op = tree.new_operation().unwrap();
+    let dict = op.get_subtree::<Dict>("settings").unwrap();
+    dict.model::<AppConfig>("app.config")
+        .set(&sample_config())
+        .unwrap();
+    op.commit().unwrap();
+
+    let viewer = tree.get_subtree_viewer::<Dict>("settings").unwrap();
+    let model = viewer.model::<AppConfig>("app.config");
+    assert_eq!(model.get().unwrap(), sample_config());
+    assert_eq!(model.path(), "app.config");
+
+    // Fields are laid out as a plain nested map
+    let all = viewer.get_all().unwrap();
+    assert_eq!(all.get_text_at_path("app.config.name"), Some("demo"));
+    assert_eq!(all.get_int_at_path("app.config.endpoint.port"), Some(8080));
+    assert_eq!(
+        all.get_text_at_path("app.config.tag_list"),
+        Some(r#"["a","b"]"#)
+    );
+}
+
+#[test]
+fn test_dict_model_field_access() {
+    let tree = setup_tree();
+
+    let op = tree.new_operation().unwrap();
+    let dict = op.get_subtree::<Dict>("settings").unwrap();
+    let model = dict.model::<AppConfig>("config");
+    model.set(&sample_config()).unwrap();
+
+    model.set_field("retries", 5u32).unwrap();
+    model.set_field("motd", None::<String>).unwrap();
+    assert_eq!(model.get_field::<u32>("retries").unwrap(), 5);
+    assert_eq!(model.get_field::<Option<String>>("motd").unwrap(), None);
+    assert_eq!(
+        model.get_field::<Endpoint>("endpoint").unwrap().host,
+        "localhost"
+    );
+
+    // Unknown fields and type mismatches are rejected
+    assert!(model.get_field::<String>("missing").is_err());
+    assert!(model.set_field("missing", 1i64).is_err());
+    assert!(model.get_field::<bool>("name").is_err());
+    op.commit().unwrap();
+
+    let viewer = tree.get_subtree_viewer::<Dict>("settings").unwrap();
+    let config = viewer.model::<AppConfig>("config").get().unwrap();
+    assert_eq!(config.retries, 5);
+    assert_eq!(config.motd, None);
+}
+
+#[test]
+fn test_dict_model_clearing_optional_field_across_commits() {
+    let tree = setup_tree();
+
+    let op = tree.new_operation().unwrap();
+    op.get_subtree::<Dict>("settings")
+        .unwrap()
+        .model::<AppConfig>("config")
+        .set(&sample_config())
+        .unwrap();
+    op.commit().unwrap();
+
+    let op = tree.new_operation().unwrap();
+    let mut cleared = sample_config();
+    cleared.motd = None;
+    op.get_subtree::<Dict>("settings")
+        .unwrap()
+        .model::<AppConfig>("config")
+        .set(&cleared)
+        .unwrap();
+    op.commit().unwrap();
+
+    let viewer = tree.get_subtree_viewer::<Dict>("settings").unwrap();
+    assert_eq!(viewer.model::<AppConfig>("config").get().unwrap(), cleared);
+}
+
+#[test]
+fn test_dict_model_concurrent_field_updates_merge() {
+    let tree = setup_tree();
+
+    let op = tree.new_operation().unwrap();
+    op.get_subtree::<Dict>("settings")
+        .unwrap()
+        .model::<Endpoint>("endpoint")
+        .set(&Endpoint {
+            host: "localhost".to_string(),
+            port: 80,
+        })
+        .unwrap();
+    let base = op.commit().unwrap();
+
+    // Two branches from the same base each update a different field
+    let op_a = tree.new_operation_with_tips([base.clone()]).unwrap();
+    op_a.get_subtree::<Dict>("settings")
+        .unwrap()
+        .model::<Endpoint>("endpoint")
+        .set_field("host", "example.com".to_string())
+        .unwrap();
+    op_a.commit().unwrap();
+
+    let op_b = tree.new_operation_with_tips([base]).unwrap();
+    op_b.get_subtree::<Dict>("settings")
+        .unwrap()
+        .model::<Endpoint>("endpoint")
+        .set_field("port", 443u16)
+        .unwrap();
+    op_b.commit().unwrap();
+
+    let viewer = tree.get_subtree_viewer::<Dict>("settings").unwrap();
+    assert_eq!(
+        viewer.model::<Endpoint>("endpoint").get().unwrap(),
+        Endpoint {
+            host: "example.com".to_string(),
+            port: 443,
+        }
+    );
+}
+
+#[test]
+fn test_dict_model_missing_and_defaulted_fields() {
+    let tree = setup_tree();
+
+    let op = tree.new_operation().unwrap();
+    let dict = op.get_subtree::<Dict>("settings").unwrap();
+
+    // Nothing stored yet
+    assert!(dict.model::<Endpoint>("endpoint").get().is_err());
+
+    // A partial map is missing the required `port` field
+    dict.set_at_path(["endpoint", "host"], "localhost".into())
+        .unwrap();
+    let err = dict.model::<Endpoint>("endpoint").get().unwrap_err();
+    assert!(err.to_string().contains("port"));
+
+    // `#[model(default)]` fields may be absent
+    let mut config = sample_config();
+    config.verbosity = 0;
+    let mut map = config.to_map();
+    map.remove("verbosity");
+    assert_eq!(AppConfig::from_map(&map).unwrap(), config);
+}
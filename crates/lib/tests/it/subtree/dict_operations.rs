@@ -316,6 +316,9 @@ fn test_dict_update_nested_value() {
 fn test_dict_comprehensive_operations() {
     let tree = setup_tree();
     let op = tree.new_operation().expect("Failed to start operation");
+    let mut nested = Map::new();
+    nested.set_string("nested_key1", "nested_value1");
+    nested.set_string("nested_key2", "nested_value2");
 
     {
         let dict = op
@@ -327,9 +330,6 @@ fn test_dict_comprehensive_operations() {
         dict.set("key2", "value2").expect("Failed to set key2");
 
         // Set a nested map value
-        let mut nested = Map::new();
-        nested.set_string("nested_key1", "nested_value1");
-        nested.set_string("nested_key2", "nested_value2");
         dict.set_value("nested", Value::Map(nested.clone()))
             .expect("Failed to set nested map");
     }
@@ -356,6 +356,9 @@ fn test_dict_comprehensive_operations() {
         ],
     );
 
+    // The committed map is stamped, but still equals the map that was written
+    assert_eq!(viewer.get("nested").unwrap(), Value::Map(nested));
+
     // Check non-existent key
     assert_key_not_found(viewer.get("non_existent"));
 }
@@ -372,3 +375,252 @@ fn test_empty_dict_behavior() {
         .expect("Failed to get empty Dict viewer");
     assert_key_not_found(dict_viewer.get("any_key"));
 }
+
+#[test]
+fn test_dict_rename() {
+    let tree = setup_tree();
+    create_dict_operation(&tree, "my_kv", &[("colour", "red"), ("size", "large")]);
+
+    let op = tree.new_operation().unwrap();
+    let dict = op.get_subtree::<Dict>("my_kv").unwrap();
+    dict.rename("colour", "color").unwrap();
+    assert_dict_value(&dict, "color", "red");
+    assert!(dict.rename("missing", "other").unwrap_err().is_not_found());
+    op.commit().unwrap();
+
+    let viewer = tree.get_subtree_viewer::<Dict>("my_kv").unwrap();
+    assert_eq!(viewer.get_string("color").unwrap(), "red");
+    assert!(viewer.get("colour").unwrap_err().is_not_found());
+    assert_eq!(viewer.get_string("size").unwrap(), "large");
+}
+
+#[test]
+fn test_dict_rename_redirects_concurrent_writes() {
+    let tree = setup_tree();
+    create_dict_operation(&tree, "my_kv", &[("colour", "red")]);
+    let tips = tree.get_tips().unwrap();
+
+    // One branch renames the key while another updates it
+    let rename = tree.new_operation_with_tips(&tips).unwrap();
+    rename
+        .get_subtree::<Dict>("my_kv")
+        .unwrap()
+        .rename("colour", "color")
+        .unwrap();
+    rename.commit().unwrap();
+    let update = tree.new_operation_with_tips(&tips).unwrap();
+    update
+        .get_subtree::<Dict>("my_kv")
+        .unwrap()
+        .set("colour", "blue")
+        .unwrap();
+    update.commit().unwrap();
+
+    let viewer = tree.get_subtree_viewer::<Dict>("my_kv").unwrap();
+    assert_eq!(viewer.get_string("color").unwrap(), "blue");
+    assert!(viewer.get("colour").unwrap_err().is_not_found());
+
+    // Later writes to the old name keep following the rename
+    create_dict_operation(&tree, "my_kv", &[("colour", "green")]);
+    let viewer = tree.get_subtree_viewer::<Dict>("my_kv").unwrap();
+    assert_eq!(viewer.get_string("color").unwrap(), "green");
+}
+
+#[test]
+fn test_dict_path_strings() {
+    let tree = setup_tree();
+    let op = tree.new_operation().unwrap();
+    let dict = op.get_subtree::<Dict>("my_kv").unwrap();
+
+    dict.set_path("user.profile.name", "Alice").unwrap();
+    dict.set_path("user/profile/age", 30).unwrap();
+    dict.set_path("files.report\\.pdf", "final").unwrap();
+    dict.set_path("user.profile.old", "gone").unwrap();
+    dict.delete_path("user.profile.old").unwrap();
+    op.commit().unwrap();
+
+    let viewer = tree.get_subtree_viewer::<Dict>("my_kv").unwrap();
+    assert_eq!(
+        viewer.get_path("user/profile/name").unwrap(),
+        Value::Text("Alice".to_string())
+    );
+    assert_eq!(
+        viewer.get_at_path(["user", "profile", "age"]).unwrap(),
+        Value::Int(30)
+    );
+    assert_eq!(
+        viewer.get_at_path(["files", "report.pdf"]).unwrap(),
+        Value::Text("final".to_string())
+    );
+    assert!(
+        viewer
+            .get_path("user.profile.old")
+            .unwrap_err()
+            .is_not_found()
+    );
+}
+
+#[test]
+fn test_dict_malformed_path_strings() {
+    let tree = setup_tree();
+    let op = tree.new_operation().unwrap();
+    let dict = op.get_subtree::<Dict>("my_kv").unwrap();
+
+    for malformed in ["", "user.", "user..name", "trailing\\"] {
+        match dict.set_path(malformed, "value") {
+            Err(eidetica::Error::CRDT(e)) => assert!(e.is_path_error()),
+            other => panic!("expected a path error for {malformed:?}, got {other:?}"),
+        }
+        assert!(dict.get_path(malformed).is_err());
+    }
+    assert!(dict.get_all().unwrap().is_empty());
+}
+
+#[test]
+fn test_dict_set_many() {
+    let tree = setup_tree();
+    create_dict_operation(&tree, "bulk", &[("existing", "old")]);
+
+    let op = tree.new_operation().unwrap();
+    let dict = op.get_subtree::<Dict>("bulk").unwrap();
+    let entries = (0..100).map(|i| (format!("key_{i}"), format!("value_{i}")));
+    dict.set_many(entries).unwrap();
+
+    // Overwrites existing keys, and later pairs win within a batch
+    dict.set_many([("existing", "new"), ("dup", "first"), ("dup", "second")])
+        .unwrap();
+    assert_dict_value(&dict, "key_42", "value_42");
+    op.commit().unwrap();
+
+    let viewer = tree.get_subtree_viewer::<Dict>("bulk").unwrap();
+    assert_dict_value(&viewer, "key_99", "value_99");
+    assert_dict_value(&viewer, "existing", "new");
+    assert_dict_value(&viewer, "dup", "second");
+    assert_dict_viewer_count(&tree, "bulk", 102);
+}
+
+#[test]
+fn test_dict_update() {
+    let tree = setup_tree();
+    create_dict_operation(&tree, "counters", &[("name", "visits")]);
+
+    let op = tree.new_operation().unwrap();
+    let dict = op.get_subtree::<Dict>("counters").unwrap();
+    let increment = |old: Option<&Value>| Value::Int(old.map_or(0, |v| v.as_int_or_zero()) + 1);
+    assert_eq!(dict.update("count", increment).unwrap(), Value::Int(1));
+    // Staged values are seen by later updates in the same operation
+    assert_eq!(dict.update("count", increment).unwrap(), Value::Int(2));
+    op.commit().unwrap();
+
+    let op = tree.new_operation().unwrap();
+    let dict = op.get_subtree::<Dict>("counters").unwrap();
+    assert_eq!(dict.update("count", increment).unwrap(), Value::Int(3));
+    dict.update("name", |old| {
+        assert_eq!(old, Some(&Value::Text("visits".to_string())));
+        Value::Text("page visits".to_string())
+    })
+    .unwrap();
+    op.commit().unwrap();
+
+    let viewer = tree.get_subtree_viewer::<Dict>("counters").unwrap();
+    assert_eq!(viewer.get("count").unwrap(), Value::Int(3));
+    assert_dict_value(&viewer, "name", "page visits");
+}
+
+#[test]
+fn test_dict_compare_and_set() {
+    let tree = setup_tree();
+    create_dict_operation(&tree, "locks", &[("owner", "alice")]);
+
+    let op = tree.new_operation().unwrap();
+    let dict = op.get_subtree::<Dict>("locks").unwrap();
+    let alice = Value::Text("alice".to_string());
+    let bob = Value::Text("bob".to_string());
+
+    // A mismatch changes nothing
+    assert!(!dict.compare_and_set("owner", Some(&bob), "carol").unwrap());
+    assert!(!dict.compare_and_set("owner", None, "carol").unwrap());
+    assert_dict_value(&dict, "owner", "alice");
+
+    assert!(dict.compare_and_set("owner", Some(&alice), "bob").unwrap());
+    // The staged value is compared from then on
+    assert!(
+        !dict
+            .compare_and_set("owner", Some(&alice), "carol")
+            .unwrap()
+    );
+    assert!(dict.compare_and_set("owner", Some(&bob), "dave").unwrap());
+
+    // `None` expects the key to have no value, deleted keys included
+    assert!(dict.compare_and_set("lease", None, "1").unwrap());
+    dict.delete("owner").unwrap();
+    assert!(dict.compare_and_set("owner", None, "erin").unwrap());
+    op.commit().unwrap();
+
+    let viewer = tree.get_subtree_viewer::<Dict>("locks").unwrap();
+    assert_dict_value(&viewer, "owner", "erin");
+    assert_dict_value(&viewer, "lease", "1");
+}
+
+#[test]
+fn test_dict_compare_and_set_nested_map_from_history() {
+    let tree = setup_tree();
+    let mut profile = Map::new();
+    profile.set_string("name", "Alice");
+    let op = tree.new_operation().unwrap();
+    op.get_subtree::<Dict>("d")
+        .unwrap()
+        .set_value("profile", Value::Map(profile.clone()))
+        .unwrap();
+    op.commit().unwrap();
+
+    // The committed map is stamped, which must not get in the way
+    let op = tree.new_operation().unwrap();
+    let dict = op.get_subtree::<Dict>("d").unwrap();
+    let mut other = Map::new();
+    other.set_string("name", "Bob");
+    assert!(
+        !dict
+            .compare_and_set("profile", Some(&Value::Map(other)), "x")
+            .unwrap()
+    );
+    assert!(
+        dict.compare_and_set("profile", Some(&Value::Map(profile)), "x")
+            .unwrap()
+    );
+    op.commit().unwrap();
+
+    let viewer = tree.get_subtree_viewer::<Dict>("d").unwrap();
+    assert_dict_value(&viewer, "profile", "x");
+}
+
+#[test]
+fn test_dict_typed_accessors() {
+    let tree = setup_tree();
+    let op = tree.new_operation().unwrap();
+    let dict = op.get_subtree::<Dict>("typed").unwrap();
+    dict.set_int("count", 42).unwrap();
+    dict.set_float("ratio", 0.75).unwrap();
+    dict.set_bool("enabled", true).unwrap();
+    dict.set_bytes("blob", [0u8, 1, 255]).unwrap();
+    dict.set_string("name", "typed").unwrap();
+    op.commit().unwrap();
+
+    let viewer = tree.get_subtree_viewer::<Dict>("typed").unwrap();
+    assert_eq!(viewer.get_int("count").unwrap(), 42);
+    assert_eq!(viewer.get_float("ratio").unwrap(), 0.75);
+    assert_eq!(viewer.get_float("count").unwrap(), 42.0);
+    assert!(viewer.get_bool("enabled").unwrap());
+    assert_eq!(viewer.get_bytes("blob").unwrap(), vec![0, 1, 255]);
+
+    // Reading a value as the wrong type is a type mismatch
+    match viewer.get_int("name") {
+        Err(eidetica::Error::Subtree(eidetica::subtree::SubtreeError::TypeMismatch {
+            expected,
+            actual,
+            ..
+        })) => assert_eq!((expected.as_str(), actual.as_str()), ("int", "text")),
+        other => panic!("unexpected result: {other:?}"),
+    }
+    assert!(viewer.get_bool("missing").unwrap_err().is_not_found());
+}
@@ -0,0 +1,202 @@
+//! Merge resolver tests
+//!
+//! Tests for `Dict::with_resolver`: concurrent writes resolved by the resolver,
+//! values changed on one branch, nested values, deletions, and resolutions
+//! made at merges that later entries build on.
+
+use crate::helpers::*;
+use eidetica::Tree;
+use eidetica::crdt::map::Value;
+use eidetica::entry::ID;
+use eidetica::subtree::Dict;
+use std::sync::{Arc, Mutex};
+
+/// Commits `(key, value)` pairs to "data" on top of `tips`.
+fn write_at(tree: &Tree, tips: &[ID], values: &[(&str, Value)]) -> ID {
+    let op = tree.new_operation_with_tips(tips).unwrap();
+    let dict = op.get_subtree::<Dict>("data").unwrap();
+    for (path, value) in values {
+        dict.set_path(*path, value.clone()).unwrap();
+    }
+    op.commit().unwrap()
+}
+
+fn max_resolver(_: &str, ours: &Value, theirs: &Value) -> Value {
+    match (ours, theirs) {
+        (Value::Int(a), Value::Int(b)) => Value::Int(*a.max(b)),
+        _ => theirs.clone(),
+    }
+}
+
+/// A tree where two branches set "score" concurrently after a common base.
+fn setup_conflict(ours: i64, theirs: i64) -> (Tree, ID, ID) {
+    let tree = setup_tree();
+    let base = write_at(
+        &tree,
+        &tree.get_tips().unwrap(),
+        &[("score", 1.into()), ("name", "base".into())],
+    );
+    let a = write_at(
+        &tree,
+        std::slice::from_ref(&base),
+        &[("score", ours.into())],
+    );
+    let b = write_at(&tree, &[base], &[("score", theirs.into())]);
+    (tree, a, b)
+}
+
+#[test]
+fn test_resolver_decides_concurrent_writes() {
+    for (ours, theirs) in [(10, 5), (5, 10)] {
+        let (tree, _, _) = setup_conflict(ours, theirs);
+        let dict = tree
+            .get_subtree_viewer::<Dict>("data")
+            .unwrap()
+            .with_resolver(max_resolver);
+        assert_eq!(dict.get("score").unwrap(), Value::Int(10));
+        assert_eq!(dict.get_string("name").unwrap(), "base");
+        assert_eq!(dict.get_all().unwrap().get_int("score"), Some(10));
+    }
+}
+
+#[test]
+fn test_resolver_sees_branches_in_write_order() {
+    let (tree, a, b) = setup_conflict(2, 3);
+    // Branches are ordered by timestamp, then by entry ID
+    let order = |id: &ID| (tree.backend().get(id).unwrap().timestamp(), id.clone());
+    let (first, second) = if order(&a) < order(&b) {
+        (Value::Int(2), Value::Int(3))
+    } else {
+        (Value::Int(3), Value::Int(2))
+    };
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let recorded = Arc::clone(&calls);
+    let dict = tree
+        .get_subtree_viewer::<Dict>("data")
+        .unwrap()
+        .with_resolver(move |key, ours, theirs| {
+            recorded
+                .lock()
+                .unwrap()
+                .push((key.to_string(), ours.clone(), theirs.clone()));
+            Value::Int(-1)
+        });
+    assert_eq!(dict.get("score").unwrap(), Value::Int(-1));
+    assert_eq!(
+        *calls.lock().unwrap(),
+        vec![("score".to_string(), first, second)]
+    );
+
+    // Without a resolver the subtree reads its last-write-wins state
+    let plain = tree.get_subtree_viewer::<Dict>("data").unwrap();
+    assert_ne!(plain.get("score").unwrap(), Value::Int(-1));
+}
+
+#[test]
+fn test_resolver_skips_values_changed_on_one_branch() {
+    let tree = setup_tree();
+    let base = write_at(&tree, &tree.get_tips().unwrap(), &[("a", 1.into())]);
+    write_at(&tree, std::slice::from_ref(&base), &[("a", 2.into())]);
+    write_at(&tree, &[base], &[("b", 3.into())]);
+
+    let dict = tree
+        .get_subtree_viewer::<Dict>("data")
+        .unwrap()
+        .with_resolver(|key, _, _| panic!("unexpected conflict on {key}"));
+    assert_eq!(dict.get("a").unwrap(), Value::Int(2));
+    assert_eq!(dict.get("b").unwrap(), Value::Int(3));
+}
+
+#[test]
+fn test_resolver_nested_values_and_deletions() {
+    let tree = setup_tree();
+    let base = write_at(
+        &tree,
+        &tree.get_tips().unwrap(),
+        &[("user.name", "Ann".into()), ("user.city", "Oslo".into())],
+    );
+    write_at(
+        &tree,
+        std::slice::from_ref(&base),
+        &[("user.name", "Anne".into()), ("user.city", "Lima".into())],
+    );
+    let op = tree.new_operation_with_tips([base]).unwrap();
+    let dict = op.get_subtree::<Dict>("data").unwrap();
+    dict.set_path("user.name", "Annie").unwrap();
+    dict.delete_path("user.city").unwrap();
+    op.commit().unwrap();
+
+    let keys = Arc::new(Mutex::new(Vec::new()));
+    let recorded = Arc::clone(&keys);
+    // Keep the longest name, and never lose a city to a deletion
+    let dict = tree
+        .get_subtree_viewer::<Dict>("data")
+        .unwrap()
+        .with_resolver(move |key, ours, theirs| {
+            recorded.lock().unwrap().push(key.to_string());
+            match (ours, theirs) {
+                (Value::Deleted, value) | (value, Value::Deleted) => value.clone(),
+                (Value::Text(a), Value::Text(b)) if a.len() >= b.len() => ours.clone(),
+                _ => theirs.clone(),
+            }
+        });
+    assert_eq!(
+        dict.get_path("user.name").unwrap(),
+        Value::Text("Annie".into())
+    );
+    assert_eq!(
+        dict.get_path("user.city").unwrap(),
+        Value::Text("Lima".into())
+    );
+    // Each read resolves again
+    let mut keys = keys.lock().unwrap().clone();
+    keys.sort();
+    keys.dedup();
+    assert_eq!(keys, ["user.city", "user.name"]);
+}
+
+#[test]
+fn test_resolution_carries_through_later_merges() {
+    let (tree, a, b) = setup_conflict(10, 5);
+    // A merge entry built on both branches, then an unrelated change
+    let merge = write_at(&tree, &[a, b], &[("other", "x".into())]);
+    write_at(&tree, &[merge], &[("name", "later".into())]);
+
+    let dict = tree
+        .get_subtree_viewer::<Dict>("data")
+        .unwrap()
+        .with_resolver(max_resolver);
+    assert_eq!(dict.get("score").unwrap(), Value::Int(10));
+    assert_eq!(dict.get_string("name").unwrap(), "later");
+
+    // A later write to the resolved value is not a conflict
+    let op = tree.new_operation().unwrap();
+    op.get_subtree::<Dict>("data")
+        .unwrap()
+        .set("score", 3)
+        .unwrap();
+    op.commit().unwrap();
+    let dict = tree
+        .get_subtree_viewer::<Dict>("data")
+        .unwrap()
+        .with_resolver(max_resolver);
+    assert_eq!(dict.get("score").unwrap(), Value::Int(3));
+}
+
+#[test]
+fn test_resolver_applies_to_staged_reads() {
+    let (tree, _, _) = setup_conflict(4, 9);
+    let op = tree.new_operation().unwrap();
+    let dict = op
+        .get_subtree::<Dict>("data")
+        .unwrap()
+        .with_resolver(|_, ours, theirs| match (ours, theirs) {
+            (Value::Int(a), Value::Int(b)) => Value::Int(*a.min(b)),
+            _ => theirs.clone(),
+        });
+    assert_eq!(dict.get("score").unwrap(), Value::Int(4));
+
+    // Staged values still take precedence
+    dict.set("score", 7).unwrap();
+    assert_eq!(dict.get("score").unwrap(), Value::Int(7));
+}
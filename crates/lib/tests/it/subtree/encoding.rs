@@ -0,0 +1,108 @@
+//! Subtree payload encoding tests
+//!
+//! This module tests declaring the encoding of a subtree's data in settings,
+//! the data written under each encoding, and reading entries written with a
+//! different encoding than the current declaration.
+
+use crate::helpers::*;
+use eidetica::Tree;
+use eidetica::entry::ID;
+use eidetica::subtree::{BlobStore, Dict, SubtreeEncoding};
+
+fn raw_data(tree: &Tree, id: &ID, subtree: &str) -> String {
+    tree.get_entry(id).unwrap().data(subtree).unwrap().clone()
+}
+
+#[test]
+fn test_default_encoding_is_json() {
+    let tree = setup_tree();
+    assert_eq!(
+        tree.subtree_encoding("data").unwrap(),
+        SubtreeEncoding::Json
+    );
+    let id = commit_dict_value(&tree, "data", "key", "value");
+    assert!(raw_data(&tree, &id, "data").starts_with('{'));
+}
+
+#[test]
+fn test_declared_encoding_is_read_transparently() {
+    let tree = setup_tree();
+    let json = commit_dict_value(&tree, "data", "before", "json");
+
+    tree.set_subtree_encoding("data", SubtreeEncoding::Cbor)
+        .unwrap();
+    assert_eq!(
+        tree.subtree_encoding("data").unwrap(),
+        SubtreeEncoding::Cbor
+    );
+    let cbor = commit_dict_value(&tree, "data", "after", "cbor");
+
+    if cfg!(feature = "cbor") {
+        assert!(raw_data(&tree, &cbor, "data").starts_with("cbor:"));
+    } else {
+        // Without the feature the data stays readable by every replica
+        assert!(raw_data(&tree, &cbor, "data").starts_with('{'));
+    }
+    assert!(raw_data(&tree, &json, "data").starts_with('{'));
+
+    // Entries of both encodings merge into one state
+    let dict = tree.get_subtree_viewer::<Dict>("data").unwrap();
+    assert_eq!(dict.get_string("before").unwrap(), "json");
+    assert_eq!(dict.get_string("after").unwrap(), "cbor");
+
+    // Switching back only affects later entries
+    tree.set_subtree_encoding("data", SubtreeEncoding::Json)
+        .unwrap();
+    let again = commit_dict_value(&tree, "data", "again", "json");
+    assert!(raw_data(&tree, &again, "data").starts_with('{'));
+    let dict = tree.get_subtree_viewer::<Dict>("data").unwrap();
+    assert_eq!(dict.get_string("after").unwrap(), "cbor");
+    assert_eq!(dict.get_string("again").unwrap(), "json");
+}
+
+#[test]
+fn test_binary_store_round_trip() {
+    let tree = setup_tree();
+    tree.set_subtree_encoding("blobs", SubtreeEncoding::Cbor)
+        .unwrap();
+
+    let content: Vec<u8> = (0..=255).cycle().take(10_000).collect();
+    let op = tree.new_operation().unwrap();
+    let id = op
+        .get_subtree::<BlobStore>("blobs")
+        .unwrap()
+        .put(&content)
+        .unwrap();
+    op.commit().unwrap();
+
+    let blobs = tree.get_subtree_viewer::<BlobStore>("blobs").unwrap();
+    assert_eq!(blobs.get(&id).unwrap(), content);
+
+    // Checkpoints still tell the blob data apart from map data
+    commit_dict_value(&tree, "data", "key", "value");
+    let checkpoint = tree.create_checkpoint().unwrap();
+    let entry = tree.get_entry(&checkpoint).unwrap();
+    assert_eq!(entry.checkpoint_subtrees(), vec!["data"]);
+}
+
+#[test]
+fn test_internal_subtrees_stay_json() {
+    let tree = setup_tree();
+    assert!(
+        tree.set_subtree_encoding("_settings", SubtreeEncoding::Cbor)
+            .is_err()
+    );
+    assert_eq!(
+        tree.subtree_encoding("_settings").unwrap(),
+        SubtreeEncoding::Json
+    );
+}
+
+#[test]
+fn test_encoding_names() {
+    for encoding in [SubtreeEncoding::Json, SubtreeEncoding::Cbor] {
+        assert_eq!(SubtreeEncoding::from_name(encoding.name()), Some(encoding));
+        assert_eq!(encoding.to_string(), encoding.name());
+    }
+    assert_eq!(SubtreeEncoding::from_name("yaml"), None);
+}
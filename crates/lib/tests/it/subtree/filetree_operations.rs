@@ -0,0 +1,208 @@
+//! FileTree subtree operation tests
+//!
+//! This module contains tests for FileTree functionality including creating,
+//! renaming, moving and deleting nodes, and merging concurrent changes to the
+//! hierarchy.
+
+use super::helpers::*;
+use crate::helpers::*;
+use eidetica::subtree::{FileTree, NodeKind};
+
+fn names(files: &FileTree, parent: Option<&str>) -> Vec<String> {
+    files
+        .children(parent)
+        .unwrap()
+        .into_iter()
+        .map(|node| node.name)
+        .collect()
+}
+
+#[test]
+fn test_filetree_basic_operations() {
+    let tree = setup_tree();
+    let (docs, readme) = commit(&tree, "files", |files: &FileTree| {
+        let docs = files.create_dir(None, "docs").unwrap();
+        let readme = files
+            .create_file(Some(&docs), "readme.md", "# Hello")
+            .unwrap();
+        files.create_file(None, "notes.txt", "notes").unwrap();
+
+        // Staged changes are visible within the operation
+        assert_eq!(files.path(&readme).unwrap(), "docs/readme.md");
+        (docs, readme)
+    });
+
+    let files = viewer::<FileTree>(&tree, "files");
+    assert_eq!(names(&files, None), vec!["docs", "notes.txt"]);
+    assert_eq!(names(&files, Some(&docs)), vec!["readme.md"]);
+    assert_eq!(files.read_file(&readme).unwrap(), "# Hello");
+    assert_eq!(files.find("docs/readme.md").unwrap().id, readme);
+    assert_eq!(files.get(&docs).unwrap().kind, NodeKind::Directory);
+    assert!(files.find("docs/missing.md").unwrap_err().is_not_found());
+
+    commit(&tree, "files", |files: &FileTree| {
+        files.write_file(&readme, "# Updated").unwrap();
+        files.rename(&readme, "README.md").unwrap();
+    });
+    let files = viewer::<FileTree>(&tree, "files");
+    assert_eq!(files.path(&readme).unwrap(), "docs/README.md");
+    assert_eq!(files.read_file(&readme).unwrap(), "# Updated");
+}
+
+#[test]
+fn test_filetree_move_and_delete() {
+    let tree = setup_tree();
+    let (a, b, file) = commit(&tree, "files", |files: &FileTree| {
+        let a = files.create_dir(None, "a").unwrap();
+        let b = files.create_dir(Some(&a), "b").unwrap();
+        let file = files.create_file(Some(&b), "file", "data").unwrap();
+        (a, b, file)
+    });
+
+    commit(&tree, "files", |files: &FileTree| {
+        // Moving a directory into its own descendant is rejected
+        assert!(files.move_node(&a, Some(&b)).is_err());
+        // Files cannot contain other nodes
+        assert!(files.move_node(&b, Some(&file)).is_err());
+        assert!(files.read_file(&a).is_err());
+
+        files.move_node(&b, None).unwrap();
+    });
+    assert_eq!(
+        viewer::<FileTree>(&tree, "files").path(&file).unwrap(),
+        "b/file"
+    );
+
+    commit(&tree, "files", |files: &FileTree| files.delete(&b).unwrap());
+    let files = viewer::<FileTree>(&tree, "files");
+    assert!(files.get(&b).unwrap_err().is_not_found());
+    assert!(files.get(&file).unwrap_err().is_not_found());
+    assert_eq!(names(&files, None), vec!["a"]);
+}
+
+#[test]
+fn test_filetree_concurrent_rename_and_move() {
+    let tree = setup_tree();
+    let (src, dst, file) = commit(&tree, "files", |files: &FileTree| {
+        let src = files.create_dir(None, "src").unwrap();
+        let dst = files.create_dir(None, "dst").unwrap();
+        let file = files.create_file(Some(&src), "old.txt", "text").unwrap();
+        (src, dst, file)
+    });
+    let base = tree.get_tips().unwrap();
+
+    commit_on(&tree, &base, "files", |files: &FileTree| {
+        files.rename(&file, "new.txt").unwrap()
+    });
+    commit_on(&tree, &base, "files", |files: &FileTree| {
+        files.move_node(&file, Some(&dst)).unwrap();
+        files.write_file(&file, "edited").unwrap();
+    });
+
+    // Both changes apply to the single node
+    let files = viewer::<FileTree>(&tree, "files");
+    assert_eq!(files.path(&file).unwrap(), "dst/new.txt");
+    assert_eq!(files.read_file(&file).unwrap(), "edited");
+    assert!(names(&files, Some(&src)).is_empty());
+    assert_eq!(files.nodes().unwrap().len(), 3);
+}
+
+#[test]
+fn test_filetree_concurrent_moves_of_same_node() {
+    let tree = setup_tree();
+    let (x, y, file) = commit(&tree, "files", |files: &FileTree| {
+        let x = files.create_dir(None, "x").unwrap();
+        let y = files.create_dir(None, "y").unwrap();
+        let file = files.create_file(None, "file", "").unwrap();
+        (x, y, file)
+    });
+    let base = tree.get_tips().unwrap();
+
+    commit_on(&tree, &base, "files", |files: &FileTree| {
+        files.move_node(&file, Some(&x)).unwrap()
+    });
+    commit_on(&tree, &base, "files", |files: &FileTree| {
+        files.move_node(&file, Some(&y)).unwrap()
+    });
+
+    // The node ends up in exactly one place
+    let files = viewer::<FileTree>(&tree, "files");
+    let in_x = names(&files, Some(&x)).len();
+    let in_y = names(&files, Some(&y)).len();
+    assert_eq!(in_x + in_y, 1);
+    assert_eq!(names(&files, None), vec!["x", "y"]);
+}
+
+#[test]
+fn test_filetree_concurrent_cycle_is_broken() {
+    let tree = setup_tree();
+    let (a, b) = commit(&tree, "files", |files: &FileTree| {
+        let a = files.create_dir(None, "a").unwrap();
+        let b = files.create_dir(None, "b").unwrap();
+        files.create_file(Some(&a), "in_a", "").unwrap();
+        (a, b)
+    });
+    let base = tree.get_tips().unwrap();
+
+    commit_on(&tree, &base, "files", |files: &FileTree| {
+        files.move_node(&a, Some(&b)).unwrap()
+    });
+    commit_on(&tree, &base, "files", |files: &FileTree| {
+        files.move_node(&b, Some(&a)).unwrap()
+    });
+
+    // The node with the smaller ID is moved to the top level, the other stays inside it
+    let files = viewer::<FileTree>(&tree, "files");
+    let (outer, inner) = if a < b { (&a, &b) } else { (&b, &a) };
+    assert!(files.get(outer).unwrap().parent.is_none());
+    assert_eq!(files.get(inner).unwrap().parent.as_ref(), Some(outer));
+    assert_eq!(files.find("a/in_a").ok().is_some(), outer == &a);
+    assert_eq!(files.nodes().unwrap().len(), 3);
+}
+
+#[test]
+fn test_filetree_create_in_concurrently_deleted_directory() {
+    let tree = setup_tree();
+    let (parent, dir) = commit(&tree, "files", |files: &FileTree| {
+        let parent = files.create_dir(None, "parent").unwrap();
+        let dir = files.create_dir(Some(&parent), "dir").unwrap();
+        (parent, dir)
+    });
+    let base = tree.get_tips().unwrap();
+
+    commit_on(&tree, &base, "files", |files: &FileTree| {
+        files.delete(&dir).unwrap()
+    });
+    let (_, file) = commit_on(&tree, &base, "files", |files: &FileTree| {
+        files.create_file(Some(&dir), "new", "keep me").unwrap()
+    });
+
+    // The new file is kept under the nearest surviving ancestor
+    let files = viewer::<FileTree>(&tree, "files");
+    assert!(files.get(&dir).unwrap_err().is_not_found());
+    assert_eq!(files.path(&file).unwrap(), "parent/new");
+    assert_eq!(files.get(&file).unwrap().parent, Some(parent));
+    assert_eq!(files.read_file(&file).unwrap(), "keep me");
+}
+
+#[test]
+fn test_filetree_duplicate_names() {
+    let tree = setup_tree();
+    // Seed the subtree so the concurrent entries share a common ancestor in it
+    commit(&tree, "files", |files: &FileTree| {
+        files.create_dir(None, "seed").unwrap()
+    });
+    let base = tree.get_tips().unwrap();
+
+    let (_, first) = commit_on(&tree, &base, "files", |files: &FileTree| {
+        files.create_file(None, "same", "one").unwrap()
+    });
+    let (_, second) = commit_on(&tree, &base, "files", |files: &FileTree| {
+        files.create_file(None, "same", "two").unwrap()
+    });
+
+    // Concurrent creations of the same name are both kept
+    let files = viewer::<FileTree>(&tree, "files");
+    assert_eq!(names(&files, None), vec!["same", "same", "seed"]);
+    assert_eq!(files.find("same").unwrap().id, first.min(second));
+}
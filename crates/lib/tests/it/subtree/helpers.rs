@@ -4,9 +4,11 @@
 //! including basic operations, CRUD operations, search functionality, and integration scenarios.
 
 use crate::helpers::*;
+use eidetica::Tree;
 use eidetica::crdt::Map;
 use eidetica::crdt::map::Value;
-use eidetica::subtree::{Dict, Table};
+use eidetica::entry::ID;
+use eidetica::subtree::{Dict, SubTree, Table};
 use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "y-crdt")]
@@ -28,6 +30,31 @@ pub struct SimpleRecord {
     pub value: i32,
 }
 
+// ===== GENERIC SUBTREE HELPERS =====
+
+/// Runs `f` on subtree `name` in an operation on top of `tips` and commits it.
+pub fn commit_on<T: SubTree, R>(
+    tree: &Tree,
+    tips: &[ID],
+    name: &str,
+    f: impl FnOnce(&T) -> R,
+) -> (ID, R) {
+    let op = tree.new_operation_with_tips(tips).unwrap();
+    let subtree = op.get_subtree::<T>(name).unwrap();
+    let result = f(&subtree);
+    (op.commit().unwrap(), result)
+}
+
+/// Runs `f` on subtree `name` in an operation on the current tips and commits it.
+pub fn commit<T: SubTree, R>(tree: &Tree, name: &str, f: impl FnOnce(&T) -> R) -> R {
+    commit_on(tree, &tree.get_tips().unwrap(), name, f).1
+}
+
+/// Read-only view of subtree `name` at the current tips.
+pub fn viewer<T: SubTree>(tree: &Tree, name: &str) -> T {
+    tree.get_subtree_viewer::<T>(name).unwrap()
+}
+
 // ===== DICT OPERATION HELPERS =====
 
 /// Create and commit a basic Dict operation with key-value data
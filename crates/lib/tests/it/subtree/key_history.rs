@@ -0,0 +1,187 @@
+//! Key history tests
+//!
+//! Tests for `Dict::history` and `Table::history`: the values a key has had,
+//! who changed it, deletions, concurrent changes and historical views.
+
+use crate::auth::helpers::{setup_authenticated_tree, setup_test_db_with_keys};
+use crate::helpers::*;
+use eidetica::Tree;
+use eidetica::auth::types::{KeyStatus, Permission, SigKey};
+use eidetica::crdt::map::Value;
+use eidetica::subtree::{Dict, Table};
+use serde::{Deserialize, Serialize};
+
+const ALICE: &str = "alice";
+const BOB: &str = "bob";
+
+/// Creates a tree that both `ALICE` and `BOB` can write to.
+fn setup_two_writers() -> Tree {
+    let keys = [
+        (ALICE, Permission::Admin(0), KeyStatus::Active),
+        (BOB, Permission::Write(10), KeyStatus::Active),
+    ];
+    let (db, public_keys) = setup_test_db_with_keys(&keys);
+    setup_authenticated_tree(&db, &keys, &public_keys)
+}
+
+fn set_as(tree: &Tree, key_name: &str, key: &str, value: &str) {
+    let op = tree.new_authenticated_operation(key_name).unwrap();
+    op.get_subtree::<Dict>("config")
+        .unwrap()
+        .set(key, value)
+        .unwrap();
+    op.commit().unwrap();
+}
+
+#[test]
+fn test_dict_history() {
+    let tree = setup_two_writers();
+    set_as(&tree, ALICE, "theme", "light");
+    set_as(&tree, ALICE, "unrelated", "value");
+    set_as(&tree, BOB, "theme", "dark");
+    let op = tree.new_authenticated_operation(ALICE).unwrap();
+    op.get_subtree::<Dict>("config")
+        .unwrap()
+        .delete("theme")
+        .unwrap();
+    let deletion = op.commit().unwrap();
+
+    let config = tree.get_subtree_viewer::<Dict>("config").unwrap();
+    let history = config.history("theme").unwrap();
+    let values: Vec<_> = history.iter().map(|change| change.value.clone()).collect();
+    assert_eq!(
+        values,
+        vec![
+            Some(Value::Text("light".to_string())),
+            Some(Value::Text("dark".to_string())),
+            None,
+        ]
+    );
+    let signers: Vec<_> = history.iter().map(|change| change.signer.clone()).collect();
+    assert_eq!(
+        signers,
+        vec![
+            SigKey::Direct(ALICE.to_string()),
+            SigKey::Direct(BOB.to_string()),
+            SigKey::Direct(ALICE.to_string()),
+        ]
+    );
+    assert_eq!(history[2].entry, deletion);
+    assert!(history.iter().all(|change| change.timestamp.is_some()));
+
+    assert!(config.history("missing").unwrap().is_empty());
+}
+
+#[test]
+fn test_history_excludes_staged_and_later_changes() {
+    let tree = setup_tree();
+    let op = tree.new_operation().unwrap();
+    op.get_subtree::<Dict>("data")
+        .unwrap()
+        .set("key", "first")
+        .unwrap();
+    let first = op.commit().unwrap();
+    let op = tree.new_operation().unwrap();
+    op.get_subtree::<Dict>("data")
+        .unwrap()
+        .set("key", "second")
+        .unwrap();
+    op.commit().unwrap();
+
+    // A historical view only sees the changes before it
+    let past = tree
+        .get_subtree_viewer_at::<Dict>("data", std::slice::from_ref(&first))
+        .unwrap();
+    assert_eq!(past.history("key").unwrap().len(), 1);
+
+    // Staged changes are not part of the history
+    let op = tree.new_operation().unwrap();
+    let data = op.get_subtree::<Dict>("data").unwrap();
+    data.set("key", "staged").unwrap();
+    assert_eq!(data.history("key").unwrap().len(), 2);
+}
+
+#[test]
+fn test_history_with_concurrent_changes() {
+    let tree = setup_tree();
+    let op = tree.new_operation().unwrap();
+    op.get_subtree::<Dict>("data")
+        .unwrap()
+        .set("key", "base")
+        .unwrap();
+    let base = op.commit().unwrap();
+    for value in ["left", "right"] {
+        let op = tree
+            .new_operation_with_tips(std::slice::from_ref(&base))
+            .unwrap();
+        op.get_subtree::<Dict>("data")
+            .unwrap()
+            .set("key", value)
+            .unwrap();
+        op.commit().unwrap();
+    }
+
+    // Both branches are listed, each with its own value
+    let data = tree.get_subtree_viewer::<Dict>("data").unwrap();
+    let mut values: Vec<_> = data
+        .history("key")
+        .unwrap()
+        .into_iter()
+        .map(|change| change.value.unwrap())
+        .collect();
+    assert_eq!(values.remove(0), Value::Text("base".to_string()));
+    values.sort_by_key(|value| value.to_string());
+    assert_eq!(
+        values,
+        vec![
+            Value::Text("left".to_string()),
+            Value::Text("right".to_string()),
+        ]
+    );
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Task {
+    title: String,
+    done: bool,
+}
+
+#[test]
+fn test_table_history() {
+    let tree = setup_two_writers();
+    let op = tree.new_authenticated_operation(ALICE).unwrap();
+    let task = Task {
+        title: "Write docs".to_string(),
+        done: false,
+    };
+    let id = op
+        .get_subtree::<Table<Task>>("tasks")
+        .unwrap()
+        .insert(task.clone())
+        .unwrap();
+    op.commit().unwrap();
+
+    let done = Task {
+        done: true,
+        ..task.clone()
+    };
+    let op = tree.new_authenticated_operation(BOB).unwrap();
+    op.get_subtree::<Table<Task>>("tasks")
+        .unwrap()
+        .set(&id, done.clone())
+        .unwrap();
+    op.commit().unwrap();
+
+    let op = tree.new_authenticated_operation(ALICE).unwrap();
+    op.get_subtree::<Table<Task>>("tasks")
+        .unwrap()
+        .delete(&id)
+        .unwrap();
+    op.commit().unwrap();
+
+    let tasks = tree.get_subtree_viewer::<Table<Task>>("tasks").unwrap();
+    let history = tasks.history(&id).unwrap();
+    let values: Vec<_> = history.iter().map(|change| change.value.clone()).collect();
+    assert_eq!(values, vec![Some(task), Some(done), None]);
+    assert_eq!(history[1].signer, SigKey::Direct(BOB.to_string()));
+}
@@ -0,0 +1,100 @@
+//! Log subtree operation tests
+//!
+//! This module contains tests for Log functionality including appending,
+//! reading from a sequence number, and the deterministic order of items
+//! appended on concurrent branches.
+
+use crate::helpers::*;
+use eidetica::Tree;
+use eidetica::entry::ID;
+use eidetica::subtree::Log;
+
+/// Appends `items` in an operation on top of `tips` and commits it.
+fn append_on(tree: &Tree, tips: &[ID], items: &[&str]) -> ID {
+    let op = tree.new_operation_with_tips(tips).unwrap();
+    let log = op.get_subtree::<Log<String>>("events").unwrap();
+    for item in items {
+        log.append(item.to_string()).unwrap();
+    }
+    op.commit().unwrap()
+}
+
+fn items(tree: &Tree) -> Vec<String> {
+    tree.get_subtree_viewer::<Log<String>>("events")
+        .unwrap()
+        .iter_from(0)
+        .unwrap()
+        .collect()
+}
+
+#[test]
+fn test_log_append_and_iterate() {
+    let tree = setup_tree();
+    let op = tree.new_operation().unwrap();
+    let log = op.get_subtree::<Log<String>>("events").unwrap();
+    assert!(log.is_empty().unwrap());
+    for item in ["a", "b", "c"] {
+        log.append(item.to_string()).unwrap();
+    }
+    assert_eq!(log.len().unwrap(), 3);
+    op.commit().unwrap();
+
+    let tips = tree.get_tips().unwrap();
+    append_on(&tree, &tips, &["d"]);
+    assert_eq!(items(&tree), vec!["a", "b", "c", "d"]);
+
+    let log = tree.get_subtree_viewer::<Log<String>>("events").unwrap();
+    assert_eq!(log.len().unwrap(), 4);
+    let rest: Vec<String> = log.iter_from(2).unwrap().collect();
+    assert_eq!(rest, vec!["c", "d"]);
+    assert_eq!(log.iter_from(10).unwrap().count(), 0);
+}
+
+#[test]
+fn test_staged_items_come_last() {
+    let tree = setup_tree();
+    let tips = tree.get_tips().unwrap();
+    append_on(&tree, &tips, &["committed"]);
+
+    let op = tree.new_operation().unwrap();
+    let log = op.get_subtree::<Log<String>>("events").unwrap();
+    log.append("staged".to_string()).unwrap();
+    let all: Vec<String> = log.iter_from(0).unwrap().collect();
+    assert_eq!(all, vec!["committed", "staged"]);
+}
+
+#[test]
+fn test_concurrent_appends_have_a_deterministic_order() {
+    let tree = setup_tree();
+    let base = append_on(&tree, &tree.get_tips().unwrap(), &["base"]);
+    let left = append_on(&tree, std::slice::from_ref(&base), &["left 1", "left 2"]);
+    let right = append_on(&tree, std::slice::from_ref(&base), &["right"]);
+    append_on(&tree, &[right.clone(), left.clone()], &["after merge"]);
+
+    // Branches at the same height are ordered by entry ID, keeping the order
+    // items were appended in within each entry
+    let expected = if left < right {
+        vec!["base", "left 1", "left 2", "right", "after merge"]
+    } else {
+        vec!["base", "right", "left 1", "left 2", "after merge"]
+    };
+    assert_eq!(items(&tree), expected);
+
+    // The order does not depend on which branch an operation saw first
+    let reversed = tree.new_operation_with_tips([left, right]).unwrap();
+    let log = reversed.get_subtree::<Log<String>>("events").unwrap();
+    let seen: Vec<String> = log.iter_from(0).unwrap().collect();
+    assert_eq!(seen, expected[..4]);
+}
+
+#[test]
+fn test_checkpoints_do_not_repeat_items() {
+    let tree = setup_tree();
+    let tips = tree.get_tips().unwrap();
+    append_on(&tree, &tips, &["a", "b"]);
+    tree.create_checkpoint().unwrap();
+    let tips = tree.get_tips().unwrap();
+    append_on(&tree, &tips, &["c"]);
+
+    assert_eq!(items(&tree), vec!["a", "b", "c"]);
+}
@@ -1,10 +1,26 @@
 //! Subtree integration tests
 //!
-//! This module tests subtree functionality including Dict, YDoc, and Table operations.
+//! This module tests subtree functionality including Dict, YDoc, Table, FileTree,
+//! TaskList, Text, Log, Queue and BlobStore operations, Table schema evolution and typed
+//! migrations, derived Table records, custom merge resolvers, the change history
+//! of individual keys, and the encoding of subtree data in entries.
 //! Tests are organized by subtree type and integration scenarios for better maintainability.
 
+mod blob_operations;
+mod dict_model;
 mod dict_operations;
+mod dict_resolver;
+mod encoding;
+mod filetree_operations;
 pub mod helpers;
 mod integration;
+mod key_history;
+mod log_operations;
+mod queue_operations;
+mod record_derive;
+mod table_migration;
 mod table_operations;
+mod table_schema;
+mod tasklist_operations;
+mod text_operations;
 mod ydoc_operations;
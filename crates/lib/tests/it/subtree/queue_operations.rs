@@ -0,0 +1,113 @@
+//! Queue subtree operation tests
+//!
+//! This module contains tests for Queue functionality including priority
+//! ordering, popping with claim markers, and concurrent pops of the same item
+//! on different branches.
+
+use super::helpers::*;
+use crate::helpers::*;
+use eidetica::subtree::Queue;
+
+fn pop(queue: &Queue<String>) -> Option<String> {
+    queue.pop().unwrap().map(|next| next.item)
+}
+
+#[test]
+fn test_queue_pops_by_priority_then_push_order() {
+    let tree = setup_tree();
+    commit(&tree, "jobs", |queue: &Queue<String>| {
+        assert!(queue.is_empty().unwrap());
+        assert!(queue.pop().unwrap().is_none());
+        queue.push(1, "low".to_string()).unwrap();
+        queue.push(5, "first high".to_string()).unwrap();
+        queue.push(5, "second high".to_string()).unwrap();
+        queue.push(-2, "lowest".to_string()).unwrap();
+        assert_eq!(queue.len().unwrap(), 4);
+    });
+
+    let next = viewer::<Queue<String>>(&tree, "jobs")
+        .peek()
+        .unwrap()
+        .unwrap();
+    assert_eq!((next.priority, next.item.as_str()), (5, "first high"));
+
+    let popped = commit(&tree, "jobs", |queue: &Queue<String>| {
+        let popped = vec![pop(queue), pop(queue)];
+        assert_eq!(queue.len().unwrap(), 2);
+        popped
+    });
+    assert_eq!(
+        popped,
+        vec![
+            Some("first high".to_string()),
+            Some("second high".to_string())
+        ]
+    );
+
+    let queue = viewer::<Queue<String>>(&tree, "jobs");
+    assert_eq!(queue.len().unwrap(), 2);
+    assert_eq!(queue.peek().unwrap().unwrap().item, "low");
+    commit(&tree, "jobs", |queue: &Queue<String>| {
+        assert_eq!(pop(queue).as_deref(), Some("low"));
+        assert_eq!(pop(queue).as_deref(), Some("lowest"));
+        assert_eq!(pop(queue), None);
+    });
+    assert!(viewer::<Queue<String>>(&tree, "jobs").is_empty().unwrap());
+}
+
+#[test]
+fn test_uncommitted_pop_leaves_the_item() {
+    let tree = setup_tree();
+    commit(&tree, "jobs", |queue: &Queue<String>| {
+        queue.push(0, "job".to_string()).unwrap()
+    });
+
+    let op = tree.new_operation().unwrap();
+    let queue = op.get_subtree::<Queue<String>>("jobs").unwrap();
+    assert_eq!(pop(&queue).as_deref(), Some("job"));
+    assert!(queue.is_empty().unwrap());
+    drop(op);
+
+    assert_eq!(viewer::<Queue<String>>(&tree, "jobs").len().unwrap(), 1);
+}
+
+#[test]
+fn test_concurrent_pops_both_claim_the_item() {
+    let tree = setup_tree();
+    let (base, id) = commit_on(
+        &tree,
+        &tree.get_tips().unwrap(),
+        "jobs",
+        |queue: &Queue<String>| queue.push(0, "job".to_string()).unwrap(),
+    );
+    assert!(
+        viewer::<Queue<String>>(&tree, "jobs")
+            .claims(&id)
+            .unwrap()
+            .is_empty()
+    );
+
+    // Two workers pop the same item without seeing each other's pop
+    let (left, _) = commit_on(
+        &tree,
+        std::slice::from_ref(&base),
+        "jobs",
+        |queue: &Queue<String>| {
+            assert_eq!(queue.pop().unwrap().unwrap().id, id);
+        },
+    );
+    let (right, _) = commit_on(
+        &tree,
+        std::slice::from_ref(&base),
+        "jobs",
+        |queue: &Queue<String>| {
+            assert_eq!(queue.pop().unwrap().unwrap().id, id);
+        },
+    );
+
+    // After merging, the item is gone and carries one claim per pop
+    let merged = tree.new_operation_with_tips([left, right]).unwrap();
+    let queue = merged.get_subtree::<Queue<String>>("jobs").unwrap();
+    assert!(queue.is_empty().unwrap());
+    assert_eq!(queue.claims(&id).unwrap(), vec!["test_key".to_string(); 2]);
+}
@@ -0,0 +1,183 @@
+//! EideticaRecord derive tests
+//!
+//! This module contains tests for Table row types deriving `EideticaRecord`:
+//! the generated key, index and field metadata, storing rows under their key,
+//! lookups by indexed field, and reading rows written by older versions.
+
+use crate::helpers::*;
+use eidetica::subtree::{EideticaRecord, Record, SubtreeError, Table};
+use serde::{Deserialize, Serialize};
+
+/// The first layout of the user record.
+#[derive(EideticaRecord, Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct UserV1 {
+    #[record(key)]
+    username: String,
+    #[record(index)]
+    email: String,
+    name: String,
+}
+
+/// The current layout: `name` became `display_name` in version 2.
+#[derive(EideticaRecord, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[record(version = 2)]
+struct User {
+    #[record(key)]
+    username: String,
+    #[record(index)]
+    email: String,
+    #[record(renamed_from = "name", since = 2)]
+    display_name: String,
+    #[serde(rename = "active", default)]
+    #[record(index)]
+    is_active: bool,
+}
+
+/// A record without a key field.
+#[derive(EideticaRecord, Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Event {
+    kind: String,
+}
+
+fn user(username: &str, email: &str) -> User {
+    User {
+        username: username.to_string(),
+        email: email.to_string(),
+        display_name: username.to_uppercase(),
+        is_active: true,
+    }
+}
+
+#[test]
+fn test_record_derive_metadata() {
+    assert_eq!(User::KEY_FIELD, Some("username"));
+    assert_eq!(User::INDEXED_FIELDS, ["email", "active"]);
+    assert_eq!(
+        User::FIELDS,
+        ["username", "email", "display_name", "active"]
+    );
+    assert_eq!(User::SCHEMA_VERSION, 2);
+    assert!(User::is_indexed("active"));
+    assert!(!User::is_indexed("display_name"));
+    assert_eq!(
+        user("alice", "a@x").primary_key(),
+        Some("alice".to_string())
+    );
+
+    assert_eq!(UserV1::SCHEMA_VERSION, 1);
+    assert_eq!(Event::KEY_FIELD, None);
+    assert!(Event::INDEXED_FIELDS.is_empty());
+}
+
+#[test]
+fn test_record_put_uses_primary_key() {
+    let tree = setup_tree();
+    let op = tree.new_operation().unwrap();
+    let users = op
+        .get_subtree::<Table<User>>("users")
+        .unwrap()
+        .with_record_schema();
+
+    assert_eq!(users.put(user("alice", "a@x")).unwrap(), "alice");
+    let mut updated = user("alice", "alice@x");
+    updated.is_active = false;
+    assert_eq!(users.put(updated.clone()).unwrap(), "alice");
+    op.commit().unwrap();
+
+    let op = tree.new_operation().unwrap();
+    let users = op
+        .get_subtree::<Table<User>>("users")
+        .unwrap()
+        .with_record_schema();
+    assert_eq!(users.get("alice").unwrap(), updated);
+    assert_eq!(users.iter().unwrap().count(), 1);
+}
+
+#[test]
+fn test_record_put_without_key_generates_one() {
+    let tree = setup_tree();
+    let op = tree.new_operation().unwrap();
+    let events = op.get_subtree::<Table<Event>>("events").unwrap();
+
+    let event = Event {
+        kind: "login".to_string(),
+    };
+    let first = events.put(event.clone()).unwrap();
+    let second = events.put(event.clone()).unwrap();
+    assert_ne!(first, second);
+    assert_eq!(events.get(&first).unwrap(), event);
+}
+
+#[test]
+fn test_record_find_by_indexed_field() {
+    let tree = setup_tree();
+    let op = tree.new_operation().unwrap();
+    let users = op
+        .get_subtree::<Table<User>>("users")
+        .unwrap()
+        .with_record_schema();
+    users.put(user("alice", "shared@x")).unwrap();
+    users.put(user("bob", "shared@x")).unwrap();
+    let mut carol = user("carol", "carol@x");
+    carol.is_active = false;
+    users.put(carol).unwrap();
+
+    let mut found: Vec<String> = users
+        .find_by("email", "shared@x")
+        .unwrap()
+        .into_iter()
+        .map(|(key, _)| key)
+        .collect();
+    found.sort();
+    assert_eq!(found, ["alice", "bob"]);
+
+    let inactive = users.find_by("active", false).unwrap();
+    assert_eq!(inactive.len(), 1);
+    assert_eq!(inactive[0].1.username, "carol");
+
+    let err = users.find_by("display_name", "ALICE").unwrap_err();
+    assert!(matches!(
+        err,
+        eidetica::Error::Subtree(SubtreeError::InvalidOperation { .. })
+    ));
+}
+
+#[test]
+fn test_record_reads_rows_from_older_versions() {
+    let tree = setup_tree();
+    let op = tree.new_operation().unwrap();
+    let old = op
+        .get_subtree::<Table<UserV1>>("users")
+        .unwrap()
+        .with_record_schema();
+    old.put(UserV1 {
+        username: "alice".to_string(),
+        email: "a@x".to_string(),
+        name: "Alice".to_string(),
+    })
+    .unwrap();
+    op.commit().unwrap();
+
+    let op = tree.new_operation().unwrap();
+    let users = op
+        .get_subtree::<Table<User>>("users")
+        .unwrap()
+        .with_record_schema();
+    assert_eq!(
+        users.get("alice").unwrap(),
+        User {
+            username: "alice".to_string(),
+            email: "a@x".to_string(),
+            display_name: "Alice".to_string(),
+            is_active: false,
+        }
+    );
+
+    // The old layout can't read rows written at the newer version
+    users.put(user("bob", "b@x")).unwrap();
+    let old = op
+        .get_subtree::<Table<UserV1>>("users")
+        .unwrap()
+        .with_record_schema();
+    assert!(old.get("bob").is_err());
+}
@@ -0,0 +1,255 @@
+//! Table migration tests
+//!
+//! This module contains tests for typed Table migrations: converting rows
+//! written with an older record type, rewriting upgraded rows with the next
+//! write, and the schema version marker stored in the table.
+
+use crate::helpers::*;
+use eidetica::crdt::Map;
+use eidetica::query::{Params, Query};
+use eidetica::subtree::{Dict, SubtreeError, Table, TableSchema};
+use serde::{Deserialize, Serialize};
+
+/// The original layout, written without a schema.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct ContactV1 {
+    name: String,
+    email: String,
+}
+
+/// The current layout: the name is split and a contact can have several emails.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Contact {
+    first: String,
+    last: String,
+    emails: Vec<String>,
+}
+
+fn upgrade(v1: ContactV1) -> Contact {
+    let (first, last) = v1.name.split_once(' ').unwrap_or((&v1.name, ""));
+    Contact {
+        first: first.to_string(),
+        last: last.to_string(),
+        emails: vec![v1.email],
+    }
+}
+
+/// Commits contacts in the original layout and returns their keys.
+fn insert_v1_contacts(tree: &eidetica::Tree, names: &[&str]) -> Vec<String> {
+    let op = tree.new_operation().unwrap();
+    let table = op.get_subtree::<Table<ContactV1>>("contacts").unwrap();
+    let keys = names
+        .iter()
+        .map(|name| {
+            table
+                .insert(ContactV1 {
+                    name: name.to_string(),
+                    email: format!("{}@example.com", name.to_lowercase().replace(' ', ".")),
+                })
+                .unwrap()
+        })
+        .collect();
+    op.commit().unwrap();
+    keys
+}
+
+/// The schema version a row is stored at, 0 if it is untagged.
+fn stored_version(tree: &eidetica::Tree, key: &str) -> u64 {
+    let viewer = tree.get_subtree_viewer::<Dict>("contacts").unwrap();
+    let stored: serde_json::Value = serde_json::from_str(&viewer.get_string(key).unwrap()).unwrap();
+    stored["_v"].as_u64().unwrap_or(0)
+}
+
+/// The number of keys changed in `contacts` by the operation.
+fn staged_changes(op: &eidetica::atomicop::AtomicOp) -> usize {
+    op.get_local_data::<Map>("contacts")
+        .map_or(0, |data| data.iter().count())
+}
+
+#[test]
+fn test_table_with_migration_converts_old_rows() {
+    let tree = setup_tree();
+    let keys = insert_v1_contacts(&tree, &["Ada Lovelace", "Grace Hopper"]);
+
+    let op = tree.new_operation().unwrap();
+    let contacts = op
+        .get_subtree::<Table<Contact>>("contacts")
+        .unwrap()
+        .with_migration(upgrade);
+    assert_eq!(
+        contacts.get(&keys[0]).unwrap(),
+        Contact {
+            first: "Ada".to_string(),
+            last: "Lovelace".to_string(),
+            emails: vec!["ada.lovelace@example.com".to_string()],
+        }
+    );
+    let lasts: Vec<String> = contacts
+        .search(|_| true)
+        .unwrap()
+        .into_iter()
+        .map(|(_, contact)| contact.last)
+        .collect();
+    assert_eq!(lasts.len(), 2);
+    assert!(lasts.contains(&"Hopper".to_string()));
+}
+
+#[test]
+fn test_table_upgraded_rows_rewritten_on_next_write() {
+    let tree = setup_tree();
+    let keys = insert_v1_contacts(&tree, &["Ada Lovelace", "Grace Hopper", "Alan Turing"]);
+
+    // Reading alone stages nothing
+    let op = tree.new_operation().unwrap();
+    let contacts = op
+        .get_subtree::<Table<Contact>>("contacts")
+        .unwrap()
+        .with_migration(upgrade);
+    contacts.get(&keys[0]).unwrap();
+    contacts.get(&keys[1]).unwrap();
+    assert_eq!(staged_changes(&op), 0);
+
+    // The next write also stores the rows read so far at the new version
+    let mut grace = contacts.get(&keys[1]).unwrap();
+    grace.emails.push("grace@navy.mil".to_string());
+    contacts.set(&keys[1], grace.clone()).unwrap();
+    op.commit().unwrap();
+
+    assert_eq!(stored_version(&tree, &keys[0]), 1);
+    assert_eq!(stored_version(&tree, &keys[1]), 1);
+    assert_eq!(stored_version(&tree, &keys[2]), 0);
+
+    let contacts = tree
+        .get_subtree_viewer::<Table<Contact>>("contacts")
+        .unwrap()
+        .with_schema(TableSchema::new(1));
+    assert_eq!(contacts.get(&keys[0]).unwrap().first, "Ada");
+    assert_eq!(contacts.get(&keys[1]).unwrap(), grace);
+}
+
+#[test]
+fn test_table_pending_upgrade_does_not_undo_delete() {
+    let tree = setup_tree();
+    let keys = insert_v1_contacts(&tree, &["Ada Lovelace", "Grace Hopper"]);
+
+    let op = tree.new_operation().unwrap();
+    let contacts = op
+        .get_subtree::<Table<Contact>>("contacts")
+        .unwrap()
+        .with_migration(upgrade);
+    contacts.get(&keys[0]).unwrap();
+    contacts.delete(&keys[0]).unwrap();
+    contacts.get(&keys[1]).unwrap();
+    contacts.delete(&keys[1]).unwrap();
+    op.commit().unwrap();
+
+    let contacts = tree
+        .get_subtree_viewer::<Table<Contact>>("contacts")
+        .unwrap()
+        .with_migration(upgrade);
+    assert!(contacts.get(&keys[0]).is_err());
+    assert!(contacts.get(&keys[1]).is_err());
+}
+
+#[test]
+fn test_table_migrations_chain() {
+    #[derive(Clone, Serialize, Deserialize)]
+    struct ContactV0 {
+        full_name: String,
+        email: String,
+    }
+
+    let tree = setup_tree();
+    let op = tree.new_operation().unwrap();
+    let key = op
+        .get_subtree::<Table<ContactV0>>("contacts")
+        .unwrap()
+        .insert(ContactV0 {
+            full_name: "Ada Lovelace".to_string(),
+            email: "ada@example.com".to_string(),
+        })
+        .unwrap();
+    op.commit().unwrap();
+
+    let schema = TableSchema::new(0).upgrade(|v0: ContactV0| ContactV1 {
+        name: v0.full_name,
+        email: v0.email,
+    });
+    let op = tree.new_operation().unwrap();
+    let contacts = op
+        .get_subtree::<Table<Contact>>("contacts")
+        .unwrap()
+        .with_schema(schema)
+        .with_migration(upgrade);
+    assert_eq!(contacts.get(&key).unwrap().last, "Lovelace");
+    assert_eq!(contacts.rewrite_all().unwrap(), 1);
+    op.commit().unwrap();
+    assert_eq!(stored_version(&tree, &key), 2);
+
+    // Rows that don't match the layout being upgraded from fail to read
+    let op = tree.new_operation().unwrap();
+    let table = op.get_subtree::<Table<ContactV1>>("other").unwrap();
+    let bad = table
+        .insert(ContactV1 {
+            name: "Alan Turing".to_string(),
+            email: "alan@example.com".to_string(),
+        })
+        .unwrap();
+    let contacts = op
+        .get_subtree::<Table<Contact>>("other")
+        .unwrap()
+        .with_schema(TableSchema::new(0).upgrade(|v0: ContactV0| ContactV1 {
+            name: v0.full_name,
+            email: v0.email,
+        }))
+        .with_migration(upgrade);
+    assert!(matches!(
+        contacts.get(&bad).unwrap_err(),
+        eidetica::Error::Subtree(SubtreeError::DeserializationFailed { .. })
+    ));
+}
+
+#[test]
+fn test_table_schema_marker_blocks_older_writers() {
+    let tree = setup_tree();
+    let keys = insert_v1_contacts(&tree, &["Ada Lovelace"]);
+
+    let op = tree.new_operation().unwrap();
+    let contacts = op
+        .get_subtree::<Table<Contact>>("contacts")
+        .unwrap()
+        .with_migration(upgrade);
+    assert_eq!(contacts.stored_schema_version().unwrap(), None);
+    contacts.rewrite_all().unwrap();
+    assert_eq!(contacts.stored_schema_version().unwrap(), Some(1));
+    op.commit().unwrap();
+
+    // The marker is not a row
+    let viewer = tree
+        .get_subtree_viewer::<Table<Contact>>("contacts")
+        .unwrap();
+    let contacts = viewer.with_schema(TableSchema::new(1));
+    assert_eq!(contacts.search(|_| true).unwrap().len(), 1);
+    let rows = tree
+        .prepare(&Query::subtree("contacts"))
+        .unwrap()
+        .execute(&Params::new())
+        .unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].key, keys[0]);
+
+    // A handle that still writes the old layout can't write to the table anymore
+    let op = tree.new_operation().unwrap();
+    let old = op.get_subtree::<Table<ContactV1>>("contacts").unwrap();
+    let err = old
+        .insert(ContactV1 {
+            name: "Grace Hopper".to_string(),
+            email: "grace@example.com".to_string(),
+        })
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        eidetica::Error::Subtree(SubtreeError::InvalidOperation { .. })
+    ));
+    assert_eq!(staged_changes(&op), 0);
+}
@@ -1,7 +1,8 @@
 //! Table subtree operation tests
 //!
 //! This module contains tests for Table subtree functionality including
-//! CRUD operations, search functionality, UUID generation, and multiple operations.
+//! CRUD operations, deletion, search functionality, ordered and paginated iteration,
+//! UUID generation, and multiple operations.
 
 use super::helpers::*;
 use crate::helpers::*;
@@ -278,3 +279,186 @@ fn test_table_with_authenticated_tree() {
     };
     assert_table_record(&tree, "auth_records", &primary_key, &expected);
 }
+
+#[test]
+fn test_table_delete() {
+    let tree = setup_tree();
+    let records = create_test_records();
+    let keys = create_table_operation(&tree, "delete_records", &records);
+
+    let op = tree.new_operation().expect("Failed to start operation");
+    let table = op
+        .get_subtree::<Table<TestRecord>>("delete_records")
+        .expect("Failed to get Table");
+    table.delete(&keys[0]).expect("Failed to delete record");
+
+    // The deletion is visible within the same operation
+    let err = table.get(&keys[0]).unwrap_err();
+    assert!(err.is_not_found());
+    assert_eq!(table.search(|_| true).unwrap().len(), records.len() - 1);
+    op.commit().expect("Failed to commit deletion");
+
+    let viewer = tree
+        .get_subtree_viewer::<Table<TestRecord>>("delete_records")
+        .expect("Failed to get Table viewer");
+    assert!(viewer.get(&keys[0]).unwrap_err().is_not_found());
+    assert_eq!(viewer.get(&keys[1]).unwrap(), records[1]);
+    assert_table_search_count(&tree, "delete_records", |_| true, records.len() - 1);
+
+    // A deleted key can be written again
+    let op = tree.new_operation().expect("Failed to start operation");
+    let table = op
+        .get_subtree::<Table<TestRecord>>("delete_records")
+        .expect("Failed to get Table");
+    table.set(&keys[0], records[0].clone()).unwrap();
+    op.commit().expect("Failed to commit restore");
+    assert_table_record(&tree, "delete_records", &keys[0], &records[0]);
+}
+
+#[test]
+fn test_table_delete_concurrent_branches() {
+    let tree = setup_tree();
+    let records = create_test_records();
+    let keys = create_table_operation(&tree, "branch_records", &records);
+    let base = tree.get_tips().unwrap();
+
+    // Branch A deletes one record while branch B updates another
+    let op_a = tree.new_operation_with_tips(&base).unwrap();
+    op_a.get_subtree::<Table<TestRecord>>("branch_records")
+        .unwrap()
+        .delete(&keys[0])
+        .unwrap();
+    op_a.commit().unwrap();
+
+    let updated = TestRecord {
+        name: "Updated in Branch B".to_string(),
+        age: 99,
+        email: "b@test.com".to_string(),
+    };
+    let op_b = tree.new_operation_with_tips(&base).unwrap();
+    op_b.get_subtree::<Table<TestRecord>>("branch_records")
+        .unwrap()
+        .set(&keys[1], updated.clone())
+        .unwrap();
+    op_b.commit().unwrap();
+
+    // The merged state has both changes
+    let viewer = tree
+        .get_subtree_viewer::<Table<TestRecord>>("branch_records")
+        .unwrap();
+    assert!(viewer.get(&keys[0]).unwrap_err().is_not_found());
+    assert_eq!(viewer.get(&keys[1]).unwrap(), updated);
+    assert_table_search_count(&tree, "branch_records", |_| true, records.len() - 1);
+}
+
+/// Inserts `count` records under the keys "row-00", "row-01", ... and returns the keys.
+fn create_numbered_rows(tree: &eidetica::Tree, subtree_name: &str, count: u32) -> Vec<String> {
+    let op = tree.new_operation().expect("Failed to start operation");
+    let table = op
+        .get_subtree::<Table<TestRecord>>(subtree_name)
+        .expect("Failed to get Table");
+    let keys: Vec<String> = (0..count).map(|i| format!("row-{i:02}")).collect();
+    for (i, key) in keys.iter().enumerate() {
+        let record = TestRecord {
+            name: format!("User {i}"),
+            age: i as u32,
+            email: format!("user{i}@example.com"),
+        };
+        table.set(key, record).unwrap();
+    }
+    op.commit().expect("Failed to commit rows");
+    keys
+}
+
+#[test]
+fn test_table_iter_and_range() {
+    let tree = setup_tree();
+    let keys = create_numbered_rows(&tree, "ordered", 10);
+
+    let op = tree.new_operation().expect("Failed to start operation");
+    let table = op
+        .get_subtree::<Table<TestRecord>>("ordered")
+        .expect("Failed to get Table");
+    table.delete(&keys[3]).unwrap();
+
+    // Rows come back in key order, without deleted rows, including staged changes
+    let all: Vec<String> = table.iter().unwrap().map(|row| row.unwrap().0).collect();
+    let mut expected = keys.clone();
+    expected.remove(3);
+    assert_eq!(all, expected);
+
+    let range: Vec<String> = table
+        .iter_range("row-02".."row-06")
+        .unwrap()
+        .map(|row| row.unwrap().0)
+        .collect();
+    assert_eq!(range, vec!["row-02", "row-04", "row-05"]);
+
+    let from: Vec<u32> = table
+        .iter_range("row-08"..)
+        .unwrap()
+        .map(|row| row.unwrap().1.age)
+        .collect();
+    assert_eq!(from, vec![8, 9]);
+
+    // Iteration can stop early
+    let first = table.iter().unwrap().next().unwrap().unwrap();
+    assert_eq!(first.0, "row-00");
+}
+
+#[test]
+fn test_table_search_paged() {
+    let tree = setup_tree();
+    create_numbered_rows(&tree, "paged", 10);
+    let table = tree
+        .get_subtree_viewer::<Table<TestRecord>>("paged")
+        .expect("Failed to get Table viewer");
+
+    // Collect even ages three at a time
+    let mut pages = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let page = table
+            .search_paged(|r| r.age % 2 == 0, 3, cursor.as_deref())
+            .unwrap();
+        pages.push(page.rows.iter().map(|(_, r)| r.age).collect::<Vec<_>>());
+        cursor = page.next_cursor;
+        if cursor.is_none() {
+            break;
+        }
+    }
+    assert_eq!(pages, vec![vec![0, 2, 4], vec![6, 8]]);
+
+    // An exactly full final page has no cursor
+    let page = table.search_paged(|r| r.age >= 7, 3, None).unwrap();
+    assert_eq!(page.rows.len(), 3);
+    assert!(page.next_cursor.is_none());
+
+    let empty = table.search_paged(|_| false, 5, None).unwrap();
+    assert!(empty.rows.is_empty());
+    assert!(empty.next_cursor.is_none());
+}
+
+#[test]
+fn test_table_insert_batch() {
+    let tree = setup_tree();
+    let records = create_test_records();
+
+    let op = tree.new_operation().unwrap();
+    let table = op.get_subtree::<Table<TestRecord>>("batch").unwrap();
+    let keys = table.insert_batch(records.clone()).unwrap();
+    assert_eq!(keys.len(), records.len());
+    assert_valid_uuids(&keys);
+
+    // Staged rows are visible in the operation, in the order given
+    for (key, record) in keys.iter().zip(&records) {
+        assert_eq!(&table.get(key).unwrap(), record);
+    }
+    assert!(table.insert_batch(Vec::new()).unwrap().is_empty());
+    op.commit().unwrap();
+
+    for (key, record) in keys.iter().zip(&records) {
+        assert_table_record(&tree, "batch", key, record);
+    }
+    assert_table_search_count(&tree, "batch", |_| true, records.len());
+}
@@ -0,0 +1,188 @@
+//! Table schema evolution tests
+//!
+//! This module contains tests for versioned Table records: reading rows written
+//! by older versions of a record type, field migrations, serde defaults for new
+//! fields, rejecting rows from newer versions, and rewriting old rows in place.
+
+use crate::helpers::*;
+use eidetica::subtree::{Dict, Table, TableSchema};
+use serde::{Deserialize, Serialize};
+
+/// The original record layout, written without a schema.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct NoteV0 {
+    name: String,
+}
+
+/// The current record layout: `name` became `title`, and `pinned` was added.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Note {
+    title: String,
+    #[serde(default)]
+    pinned: bool,
+}
+
+fn note_schema() -> TableSchema {
+    TableSchema::new(1).rename_field(0, "name", "title")
+}
+
+/// Commits unversioned rows and returns their keys.
+fn insert_legacy_notes(tree: &eidetica::Tree, names: &[&str]) -> Vec<String> {
+    let op = tree.new_operation().unwrap();
+    let table = op.get_subtree::<Table<NoteV0>>("notes").unwrap();
+    let keys = names
+        .iter()
+        .map(|name| {
+            table
+                .insert(NoteV0 {
+                    name: name.to_string(),
+                })
+                .unwrap()
+        })
+        .collect();
+    op.commit().unwrap();
+    keys
+}
+
+#[test]
+fn test_table_schema_upgrades_legacy_rows() {
+    let tree = setup_tree();
+    let keys = insert_legacy_notes(&tree, &["groceries", "todo"]);
+
+    let op = tree.new_operation().unwrap();
+    let table = op
+        .get_subtree::<Table<Note>>("notes")
+        .unwrap()
+        .with_schema(note_schema());
+
+    assert_eq!(
+        table.get(&keys[0]).unwrap(),
+        Note {
+            title: "groceries".to_string(),
+            pinned: false,
+        }
+    );
+    let titles: Vec<String> = table
+        .iter()
+        .unwrap()
+        .map(|row| row.unwrap().1.title)
+        .collect();
+    assert_eq!(titles.len(), 2);
+    assert!(titles.contains(&"todo".to_string()));
+
+    // Without the schema the old layout doesn't match the new struct
+    let plain = op.get_subtree::<Table<Note>>("notes").unwrap();
+    assert!(plain.get(&keys[0]).is_err());
+}
+
+#[test]
+fn test_table_schema_chains_migrations() {
+    let tree = setup_tree();
+    let keys = insert_legacy_notes(&tree, &["Groceries"]);
+
+    // Version 1 renamed the field, version 2 lowercased titles
+    let schema = TableSchema::new(2)
+        .rename_field(0, "name", "title")
+        .map_field(1, "title", |title| {
+            serde_json::Value::String(title.as_str().unwrap_or_default().to_lowercase())
+        });
+    assert_eq!(schema.version(), 2);
+
+    let op = tree.new_operation().unwrap();
+    let table = op
+        .get_subtree::<Table<Note>>("notes")
+        .unwrap()
+        .with_schema(schema);
+    assert_eq!(table.get(&keys[0]).unwrap().title, "groceries");
+}
+
+#[test]
+fn test_table_schema_tags_new_rows() {
+    let tree = setup_tree();
+
+    let op = tree.new_operation().unwrap();
+    let table = op
+        .get_subtree::<Table<Note>>("notes")
+        .unwrap()
+        .with_schema(note_schema());
+    let key = table
+        .insert(Note {
+            title: "tagged".to_string(),
+            pinned: true,
+        })
+        .unwrap();
+    op.commit().unwrap();
+
+    // Rows are stored with their version, and read back through the schema
+    let viewer = tree.get_subtree_viewer::<Dict>("notes").unwrap();
+    let stored: serde_json::Value =
+        serde_json::from_str(&viewer.get_string(&key).unwrap()).unwrap();
+    assert_eq!(stored["_v"], 1);
+    assert_eq!(stored["row"]["title"], "tagged");
+
+    let table = tree
+        .get_subtree_viewer::<Table<Note>>("notes")
+        .unwrap()
+        .with_schema(note_schema());
+    assert!(table.get(&key).unwrap().pinned);
+}
+
+#[test]
+fn test_table_schema_rejects_newer_rows() {
+    let tree = setup_tree();
+
+    let op = tree.new_operation().unwrap();
+    let table = op
+        .get_subtree::<Table<Note>>("notes")
+        .unwrap()
+        .with_schema(TableSchema::new(3));
+    let key = table
+        .insert(Note {
+            title: "from the future".to_string(),
+            pinned: false,
+        })
+        .unwrap();
+    op.commit().unwrap();
+
+    let table = tree
+        .get_subtree_viewer::<Table<Note>>("notes")
+        .unwrap()
+        .with_schema(note_schema());
+    let err = table.get(&key).unwrap_err();
+    assert!(err.to_string().contains("newer"), "unexpected error: {err}");
+}
+
+#[test]
+fn test_table_rewrite_all_upgrades_rows() {
+    let tree = setup_tree();
+    let keys = insert_legacy_notes(&tree, &["one", "two", "three"]);
+
+    // Without a schema there is nothing to rewrite
+    let op = tree.new_operation().unwrap();
+    let plain = op.get_subtree::<Table<NoteV0>>("notes").unwrap();
+    assert_eq!(plain.rewrite_all().unwrap(), 0);
+
+    let table = op
+        .get_subtree::<Table<Note>>("notes")
+        .unwrap()
+        .with_schema(note_schema());
+    assert_eq!(table.rewrite_all().unwrap(), 3);
+    op.commit().unwrap();
+
+    // The stored rows are now at version 1, so a schema without migrations reads them
+    let table = tree
+        .get_subtree_viewer::<Table<Note>>("notes")
+        .unwrap()
+        .with_schema(TableSchema::new(1));
+    for (key, title) in keys.iter().zip(["one", "two", "three"]) {
+        assert_eq!(table.get(key).unwrap().title, title);
+    }
+
+    // Upgraded rows are left alone by the next rewrite
+    let op = tree.new_operation().unwrap();
+    let table = op
+        .get_subtree::<Table<Note>>("notes")
+        .unwrap()
+        .with_schema(note_schema());
+    assert_eq!(table.rewrite_all().unwrap(), 0);
+}
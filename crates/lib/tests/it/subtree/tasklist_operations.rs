@@ -0,0 +1,149 @@
+//! TaskList subtree operation tests
+//!
+//! This module contains tests for TaskList functionality including ordering,
+//! checking items off, and merging concurrent edits at the item level.
+
+use super::helpers::*;
+use crate::helpers::*;
+use eidetica::subtree::TaskList;
+
+fn texts(tasks: &TaskList) -> Vec<String> {
+    tasks
+        .items()
+        .unwrap()
+        .into_iter()
+        .map(|item| item.text)
+        .collect()
+}
+
+#[test]
+fn test_tasklist_basic_operations() {
+    let tree = setup_tree();
+    let (a, b, c) = commit(&tree, "tasks", |tasks: &TaskList| {
+        assert!(tasks.is_empty().unwrap());
+        let b = tasks.push("b").unwrap();
+        let c = tasks.push("c").unwrap();
+        let a = tasks.insert(0, "a").unwrap();
+        assert!(tasks.insert(5, "out of bounds").is_err());
+        (a, b, c)
+    });
+
+    let tasks = viewer::<TaskList>(&tree, "tasks");
+    assert_eq!(texts(&tasks), vec!["a", "b", "c"]);
+    assert!(!tasks.get(&a).unwrap().checked);
+
+    commit(&tree, "tasks", |tasks: &TaskList| {
+        tasks.check(&b).unwrap();
+        tasks.set_text(&c, "c!").unwrap();
+        tasks.move_to(&a, 2).unwrap();
+        tasks.insert(1, "between").unwrap();
+    });
+    let tasks = viewer::<TaskList>(&tree, "tasks");
+    assert_eq!(texts(&tasks), vec!["b", "between", "c!", "a"]);
+    assert!(tasks.get(&b).unwrap().checked);
+
+    commit(&tree, "tasks", |tasks: &TaskList| {
+        tasks.uncheck(&b).unwrap();
+        tasks.remove(&c).unwrap();
+        assert!(tasks.get(&c).unwrap_err().is_not_found());
+    });
+    let tasks = viewer::<TaskList>(&tree, "tasks");
+    assert!(!tasks.get(&b).unwrap().checked);
+    assert_eq!(tasks.len().unwrap(), 3);
+    assert!(tasks.set_text(&c, "gone").unwrap_err().is_not_found());
+}
+
+#[test]
+fn test_tasklist_concurrent_item_edits_merge() {
+    let tree = setup_tree();
+    let (first, second) = commit(&tree, "tasks", |tasks: &TaskList| {
+        (tasks.push("first").unwrap(), tasks.push("second").unwrap())
+    });
+    let base = tree.get_tips().unwrap();
+
+    commit_on(&tree, &base, "tasks", |tasks: &TaskList| {
+        tasks.set_text(&first, "first edited").unwrap();
+        tasks.push("from a").unwrap();
+    });
+    commit_on(&tree, &base, "tasks", |tasks: &TaskList| {
+        tasks.check(&second).unwrap();
+        tasks.push("from b").unwrap();
+    });
+
+    // Both devices' edits apply and both new items are kept
+    let tasks = viewer::<TaskList>(&tree, "tasks");
+    let items = tasks.items().unwrap();
+    assert_eq!(items.len(), 4);
+    assert_eq!(items[0].text, "first edited");
+    assert!(items[1].checked);
+    let mut appended: Vec<&str> = items[2..].iter().map(|i| i.text.as_str()).collect();
+    appended.sort();
+    assert_eq!(appended, vec!["from a", "from b"]);
+}
+
+#[test]
+fn test_tasklist_check_wins_over_concurrent_uncheck() {
+    let tree = setup_tree();
+    let item = commit(&tree, "tasks", |tasks: &TaskList| {
+        let item = tasks.push("task").unwrap();
+        tasks.check(&item).unwrap();
+        item
+    });
+    let base = tree.get_tips().unwrap();
+
+    // One device unchecks, another unchecks and checks again
+    commit_on(&tree, &base, "tasks", |tasks: &TaskList| {
+        tasks.uncheck(&item).unwrap()
+    });
+    commit_on(&tree, &base, "tasks", |tasks: &TaskList| {
+        tasks.uncheck(&item).unwrap();
+        tasks.check(&item).unwrap();
+    });
+    assert!(
+        viewer::<TaskList>(&tree, "tasks")
+            .get(&item)
+            .unwrap()
+            .checked
+    );
+
+    // An uncheck that has seen every check applies
+    commit(&tree, "tasks", |tasks: &TaskList| {
+        tasks.uncheck(&item).unwrap()
+    });
+    assert!(
+        !viewer::<TaskList>(&tree, "tasks")
+            .get(&item)
+            .unwrap()
+            .checked
+    );
+}
+
+#[test]
+fn test_tasklist_concurrent_moves_and_removal() {
+    let tree = setup_tree();
+    let ids = commit(&tree, "tasks", |tasks: &TaskList| {
+        ["a", "b", "c"].map(|text| tasks.push(text).unwrap())
+    });
+    let base = tree.get_tips().unwrap();
+
+    commit_on(&tree, &base, "tasks", |tasks: &TaskList| {
+        tasks.move_to(&ids[0], 2).unwrap()
+    });
+    commit_on(&tree, &base, "tasks", |tasks: &TaskList| {
+        tasks.move_to(&ids[0], 1).unwrap()
+    });
+    commit_on(&tree, &base, "tasks", |tasks: &TaskList| {
+        tasks.set_text(&ids[2], "c edited").unwrap()
+    });
+    commit_on(&tree, &base, "tasks", |tasks: &TaskList| {
+        tasks.remove(&ids[2]).unwrap()
+    });
+
+    // The moved item appears exactly once, and the removal wins over the edit
+    let tasks = viewer::<TaskList>(&tree, "tasks");
+    let texts = texts(&tasks);
+    assert_eq!(texts.len(), 2);
+    assert_eq!(texts.iter().filter(|t| *t == "a").count(), 1);
+    assert!(texts.contains(&"b".to_string()));
+    assert!(tasks.get(&ids[2]).unwrap_err().is_not_found());
+}
@@ -0,0 +1,117 @@
+//! Text subtree operation tests
+//!
+//! This module contains tests for Text functionality including editing within
+//! an operation, reading through viewers, and merging concurrent edits made on
+//! separate branches.
+
+use super::helpers::*;
+use crate::helpers::*;
+use eidetica::subtree::Text;
+
+#[test]
+fn test_text_basic_operations() {
+    let tree = setup_tree();
+    commit_on(&tree, &tree.get_tips().unwrap(), "title", |title: &Text| {
+        assert!(title.is_empty().unwrap());
+        title.push_str("Draft").unwrap();
+        title.insert(0, "First ").unwrap();
+        assert_eq!(title.get().unwrap(), "First Draft");
+        assert!(title.insert(20, "x").is_err());
+    });
+
+    let title = viewer::<Text>(&tree, "title");
+    assert_eq!(title.get().unwrap(), "First Draft");
+    assert_eq!(title.len().unwrap(), 11);
+
+    commit_on(&tree, &tree.get_tips().unwrap(), "title", |title: &Text| {
+        title.delete(0, 6).unwrap();
+        title.push_str(" v2").unwrap();
+    });
+    assert_eq!(viewer::<Text>(&tree, "title").get().unwrap(), "Draft v2");
+
+    commit_on(&tree, &tree.get_tips().unwrap(), "title", |title: &Text| {
+        title.set("Final").unwrap();
+    });
+    assert_eq!(viewer::<Text>(&tree, "title").get().unwrap(), "Final");
+}
+
+#[test]
+fn test_text_entries_store_only_changes() {
+    let tree = setup_tree();
+    commit_on(&tree, &tree.get_tips().unwrap(), "title", |title: &Text| {
+        title.push_str("a long title").unwrap();
+    });
+    let (entry, ()) = commit_on(&tree, &tree.get_tips().unwrap(), "title", |title: &Text| {
+        title.push_str("!").unwrap();
+    });
+
+    let data = tree
+        .get_entry(&entry)
+        .unwrap()
+        .data("title")
+        .unwrap()
+        .clone();
+    let changes: eidetica::crdt::Rga = serde_json::from_str(&data).unwrap();
+    assert_eq!(changes.to_string(), "!");
+}
+
+#[test]
+fn test_text_concurrent_edits_merge() {
+    let tree = setup_tree();
+    let (base, ()) = commit_on(&tree, &tree.get_tips().unwrap(), "title", |title: &Text| {
+        title.push_str("Project plan").unwrap();
+    });
+
+    commit_on(
+        &tree,
+        std::slice::from_ref(&base),
+        "title",
+        |title: &Text| {
+            title.insert(0, "New ").unwrap();
+        },
+    );
+    commit_on(
+        &tree,
+        std::slice::from_ref(&base),
+        "title",
+        |title: &Text| {
+            title.push_str(" 2025").unwrap();
+        },
+    );
+    commit_on(
+        &tree,
+        std::slice::from_ref(&base),
+        "title",
+        |title: &Text| {
+            title.delete(8, 4).unwrap();
+            title.insert(8, "roadmap").unwrap();
+        },
+    );
+
+    assert_eq!(tree.get_tips().unwrap().len(), 3);
+    assert_eq!(
+        viewer::<Text>(&tree, "title").get().unwrap(),
+        "New Project roadmap 2025"
+    );
+
+    // Editing on top of the merged state sees every branch
+    commit_on(&tree, &tree.get_tips().unwrap(), "title", |title: &Text| {
+        assert_eq!(title.get().unwrap(), "New Project roadmap 2025");
+        title.delete(0, 4).unwrap();
+    });
+    assert_eq!(
+        viewer::<Text>(&tree, "title").get().unwrap(),
+        "Project roadmap 2025"
+    );
+}
+
+#[test]
+fn test_text_is_not_checkpointed_as_map() {
+    let tree = setup_tree();
+    commit_on(&tree, &tree.get_tips().unwrap(), "title", |title: &Text| {
+        title.push_str("kept").unwrap();
+    });
+    let checkpoint = tree.create_checkpoint().unwrap();
+    assert!(!tree.get_entry(&checkpoint).unwrap().in_subtree("title"));
+    assert_eq!(viewer::<Text>(&tree, "title").get().unwrap(), "kept");
+}
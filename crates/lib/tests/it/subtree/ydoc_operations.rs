@@ -1,7 +1,8 @@
 //! YDoc subtree operation tests
 //!
 //! This module contains tests for Y-CRDT subtree functionality including
-//! text operations, map operations, incremental updates, and external updates.
+//! text operations, map operations, incremental updates, external updates,
+//! observing committed updates, the state vector sync handshake, and compaction.
 
 #[cfg(feature = "y-crdt")]
 use super::helpers::*;
@@ -10,7 +11,11 @@ use crate::helpers::*;
 #[cfg(feature = "y-crdt")]
 use eidetica::subtree::YDoc;
 #[cfg(feature = "y-crdt")]
-use yrs::{GetString, Map as YrsMapTrait, Text, Transact};
+use yrs::updates::decoder::Decode;
+#[cfg(feature = "y-crdt")]
+use yrs::updates::encoder::Encode;
+#[cfg(feature = "y-crdt")]
+use yrs::{GetString, Map as YrsMapTrait, ReadTxn, Text, Transact};
 
 #[cfg(feature = "y-crdt")]
 #[test]
@@ -222,3 +227,101 @@ fn test_ydoc_apply_external_update() {
         })
         .expect("Failed to verify external update");
 }
+
+#[cfg(feature = "y-crdt")]
+#[test]
+fn test_ydoc_observe_committed_updates() {
+    use std::sync::{Arc, Mutex};
+
+    let tree = setup_tree();
+    let observer = tree
+        .get_subtree_viewer::<YDoc>("yrs_observed")
+        .expect("Failed to get YDoc viewer");
+    let received = Arc::new(Mutex::new(Vec::<Vec<u8>>::new()));
+    let sink = Arc::clone(&received);
+    let id = observer.observe(move |update| sink.lock().unwrap().push(update.to_vec()));
+
+    // Commits to the observed subtree are reported, others are not
+    create_ydoc_text_operation(&tree, "yrs_observed", "Hello");
+    create_ydoc_text_operation(&tree, "yrs_other", "Ignored");
+    assert_eq!(received.lock().unwrap().len(), 1);
+
+    // The reported update brings a remote document up to date
+    let remote = yrs::Doc::new();
+    {
+        let update = yrs::Update::decode_v1(&received.lock().unwrap()[0])
+            .expect("Failed to decode observed update");
+        let mut txn = remote.transact_mut();
+        txn.apply_update(update)
+            .expect("Failed to apply observed update");
+    }
+    let text = remote.get_or_insert_text("document");
+    assert_eq!(text.get_string(&remote.transact()), "Hello");
+
+    assert!(observer.unobserve(id));
+    assert!(!observer.unobserve(id));
+    create_ydoc_text_operation(&tree, "yrs_observed", "Hello again");
+    assert_eq!(received.lock().unwrap().len(), 1);
+}
+
+#[cfg(feature = "y-crdt")]
+#[test]
+fn test_ydoc_state_vector_handshake() {
+    let tree = setup_tree();
+    create_ydoc_text_operation(&tree, "yrs_sync", "Shared text");
+
+    // A remote client with part of the document sends its state vector
+    let remote = yrs::Doc::new();
+    let viewer = tree
+        .get_subtree_viewer::<YDoc>("yrs_sync")
+        .expect("Failed to get YDoc viewer");
+    let remote_state = remote.transact().state_vector().encode_v1();
+    let missing = viewer
+        .get_update_since(&remote_state)
+        .expect("Failed to compute missing updates");
+    {
+        let update = yrs::Update::decode_v1(&missing).expect("Failed to decode update");
+        let mut txn = remote.transact_mut();
+        txn.apply_update(update).expect("Failed to apply update");
+    }
+    let text = remote.get_or_insert_text("document");
+    assert_eq!(text.get_string(&remote.transact()), "Shared text");
+
+    // Once up to date, the remote is missing nothing of substance, and both
+    // documents share a state vector
+    assert_eq!(
+        viewer.state_vector().expect("Failed to get state vector"),
+        remote.transact().state_vector().encode_v1()
+    );
+    let nothing = viewer
+        .get_update_since(&remote.transact().state_vector().encode_v1())
+        .unwrap();
+    assert!(nothing.len() <= 2);
+
+    assert!(viewer.get_update_since(&[0xff, 0xff]).is_err());
+}
+
+#[cfg(feature = "y-crdt")]
+#[test]
+fn test_ydoc_compact() {
+    let tree = setup_tree();
+    for word in ["world", " ", "Hello,"] {
+        create_ydoc_text_operation(&tree, "yrs_compact", word);
+    }
+    let viewer = tree
+        .get_subtree_viewer::<YDoc>("yrs_compact")
+        .expect("Failed to get YDoc viewer");
+    let checkpoint = viewer.compact().expect("Failed to compact YDoc");
+    let entry = tree.get_entry(&checkpoint).unwrap();
+    assert!(entry.is_checkpoint_of("yrs_compact"));
+    assert_eq!(tree.get_tips().unwrap(), vec![checkpoint.clone()]);
+
+    // The document is unchanged, and later edits build on the checkpoint
+    assert_ydoc_text_content(&tree, "yrs_compact", "Hello, world");
+    create_ydoc_text_operation(&tree, "yrs_compact", ">> ");
+    assert_ydoc_text_content(&tree, "yrs_compact", ">> Hello, world");
+
+    // Subtrees without entries have nothing to compact
+    let empty = tree.get_subtree_viewer::<YDoc>("yrs_empty").unwrap();
+    assert!(empty.compact().is_err());
+}
@@ -0,0 +1,199 @@
+//! Tests for compression negotiated between sync peers
+
+use super::helpers::*;
+use eidetica::Result;
+use eidetica::backend::{Compression, Database};
+use eidetica::basedb::BaseDB;
+use eidetica::entry::ID;
+use eidetica::subtree::Dict;
+use eidetica::sync::SyncPeer;
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread::JoinHandle;
+
+/// A transport that counts the bytes passing through it.
+struct Counting {
+    stream: TcpStream,
+    read: Arc<AtomicUsize>,
+    written: Arc<AtomicUsize>,
+}
+
+impl Read for Counting {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.stream.read(buf)?;
+        self.read.fetch_add(n, Ordering::SeqCst);
+        Ok(n)
+    }
+}
+
+impl Write for Counting {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.stream.write(buf)?;
+        self.written.fetch_add(n, Ordering::SeqCst);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+/// Byte counts of one connection, as seen by the initiating peer.
+struct Traffic {
+    read: Arc<AtomicUsize>,
+    written: Arc<AtomicUsize>,
+}
+
+impl Traffic {
+    fn read(&self) -> usize {
+        self.read.load(Ordering::SeqCst)
+    }
+
+    fn written(&self) -> usize {
+        self.written.load(Ordering::SeqCst)
+    }
+}
+
+/// Serves `remote` with `server_compression` and connects a peer for `local`
+/// using `client_compression`.
+fn connect_with(
+    local: &BaseDB,
+    client_compression: Compression,
+    remote: &BaseDB,
+    server_compression: Compression,
+) -> (SyncPeer<Counting>, Traffic, JoinHandle<Result<Compression>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let backend: Arc<dyn Database> = remote.backend().clone();
+    let server = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut peer = SyncPeer::new(backend, stream).with_compression(server_compression);
+        peer.serve_one()?;
+        Ok(peer.session_compression())
+    });
+
+    let traffic = Traffic {
+        read: Arc::new(AtomicUsize::new(0)),
+        written: Arc::new(AtomicUsize::new(0)),
+    };
+    let transport = Counting {
+        stream: TcpStream::connect(addr).unwrap(),
+        read: Arc::clone(&traffic.read),
+        written: Arc::clone(&traffic.written),
+    };
+    let peer =
+        SyncPeer::new(local.backend().clone(), transport).with_compression(client_compression);
+    (peer, traffic, server)
+}
+
+/// Creates a tree in `db` with `count` entries of similar data.
+fn populated_tree(db: &BaseDB, count: usize) -> ID {
+    let tree = db.new_tree_default(TEST_KEY).unwrap();
+    for i in 0..count {
+        let op = tree.new_authenticated_operation(TEST_KEY).unwrap();
+        let dict = op.get_subtree::<Dict>("todos").unwrap();
+        dict.set(
+            format!("todo_{i}"),
+            format!("Remember to do thing number {i}"),
+        )
+        .unwrap();
+        op.commit().unwrap();
+    }
+    tree.root_id().clone()
+}
+
+/// Syncs `tree` into a fresh replica of `remote` and returns the bytes read.
+fn fetch(remote: &BaseDB, tree: &ID, compression: Compression) -> usize {
+    let local = setup_replica(remote);
+    let (mut peer, traffic, server) = connect_with(&local, compression, remote, compression);
+    let stats = peer.sync_tree(tree).unwrap();
+    assert_eq!(stats.received, 31);
+    assert_eq!(peer.session_compression(), compression);
+    drop(peer);
+    assert_eq!(server.join().unwrap().unwrap(), compression);
+
+    let replica = local.load_tree(tree).unwrap();
+    let todos = replica.get_subtree_viewer::<Dict>("todos").unwrap();
+    assert_eq!(
+        todos.get_string("todo_29").unwrap(),
+        "Remember to do thing number 29"
+    );
+    traffic.read()
+}
+
+#[test]
+fn test_compressed_fetch_reads_fewer_bytes() {
+    let remote = setup_db();
+    let tree = populated_tree(&remote, 30);
+
+    let plain = fetch(&remote, &tree, Compression::None);
+    let lz4 = fetch(&remote, &tree, Compression::Lz4);
+    let zstd_dict = fetch(&remote, &tree, Compression::ZstdDict);
+    assert!(lz4 < plain / 2, "LZ4 read {lz4} of {plain} bytes");
+    assert!(
+        zstd_dict <= lz4,
+        "zstd-dict read {zstd_dict}, LZ4 {lz4} bytes"
+    );
+}
+
+#[test]
+fn test_compressed_push_writes_fewer_bytes() {
+    let local = setup_db();
+    let tree = populated_tree(&local, 30);
+
+    let mut written = Vec::new();
+    for compression in [Compression::None, Compression::ZstdDict] {
+        let remote = setup_replica(&local);
+        let (mut peer, traffic, server) = connect_with(&local, compression, &remote, compression);
+        assert_eq!(peer.sync_tree(&tree).unwrap().sent, 31);
+        drop(peer);
+        server.join().unwrap().unwrap();
+        assert_eq!(
+            remote
+                .load_tree(&tree)
+                .unwrap()
+                .get_all_entries()
+                .unwrap()
+                .len(),
+            31
+        );
+        written.push(traffic.written());
+    }
+    assert!(written[1] < written[0] / 2, "wrote {written:?} bytes");
+}
+
+#[test]
+fn test_compression_needs_both_peers() {
+    let remote = setup_db();
+    let tree = populated_tree(&remote, 3);
+
+    for (client, server_compression) in [
+        (Compression::ZstdDict, Compression::None),
+        (Compression::None, Compression::Lz4),
+    ] {
+        let local = setup_replica(&remote);
+        let (mut peer, _traffic, server) =
+            connect_with(&local, client, &remote, server_compression);
+        assert_eq!(peer.sync_tree(&tree).unwrap().received, 4);
+        assert_eq!(peer.session_compression(), Compression::None);
+        drop(peer);
+        assert_eq!(server.join().unwrap().unwrap(), Compression::None);
+    }
+}
+
+#[test]
+fn test_responder_uses_offered_algorithm() {
+    let remote = setup_db();
+    let tree = populated_tree(&remote, 3);
+    let local = setup_replica(&remote);
+
+    // The server prefers LZ4 but accepts the dictionary variant the client offers
+    let (mut peer, _traffic, server) =
+        connect_with(&local, Compression::ZstdDict, &remote, Compression::Lz4);
+    peer.sync_tree(&tree).unwrap();
+    assert_eq!(peer.session_compression(), Compression::ZstdDict);
+    drop(peer);
+    assert_eq!(server.join().unwrap().unwrap(), Compression::ZstdDict);
+}
@@ -0,0 +1,174 @@
+//! Ephemeral subtree sync tests
+//!
+//! Tests for exchanging the in-memory state of ephemeral subtrees during sync
+//! with `SyncPeer::with_ephemeral_state`, and for ignoring state sent for
+//! subtrees that are not marked ephemeral.
+
+use super::helpers::*;
+use eidetica::Result;
+use eidetica::basedb::BaseDB;
+use eidetica::crdt::Map;
+use eidetica::subtree::Dict;
+use eidetica::sync::SyncPeer;
+use serde_json::{Value as Json, json};
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::thread::JoinHandle;
+
+/// Serves sync sessions for `db`, sharing its ephemeral state, for a single
+/// connection made to the returned address.
+fn serve_ephemeral(db: &BaseDB) -> (SocketAddr, JoinHandle<Result<()>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let backend = db.backend().clone();
+    let state = db.ephemeral_state();
+    let server = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        SyncPeer::new(backend, stream)
+            .with_ephemeral_state(state)
+            .serve()
+    });
+    (addr, server)
+}
+
+/// Connects a peer for `local` to a server for `remote`, both sharing their
+/// ephemeral state.
+fn connect_ephemeral(
+    local: &BaseDB,
+    remote: &BaseDB,
+) -> (SyncPeer<TcpStream>, JoinHandle<Result<()>>) {
+    let (addr, server) = serve_ephemeral(remote);
+    let peer = SyncPeer::new(local.backend().clone(), TcpStream::connect(addr).unwrap())
+        .with_ephemeral_state(local.ephemeral_state());
+    (peer, server)
+}
+
+/// Sends a raw protocol message.
+fn send(stream: &mut TcpStream, message: Json) {
+    let body = serde_json::to_vec(&message).unwrap();
+    stream
+        .write_all(&(body.len() as u32).to_be_bytes())
+        .unwrap();
+    stream.write_all(&body).unwrap();
+}
+
+/// Reads a raw protocol message.
+fn receive(stream: &mut TcpStream) -> Json {
+    let mut header = [0u8; 4];
+    stream.read_exact(&mut header).unwrap();
+    let mut body = vec![0u8; u32::from_be_bytes(header) as usize];
+    stream.read_exact(&mut body).unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+fn set_presence(db: &BaseDB, tree: &eidetica::entry::ID, user: &str, status: &str) {
+    let mut tree = db.load_tree(tree).unwrap();
+    tree.set_default_auth_key(TEST_KEY);
+    let op = tree.new_operation().unwrap();
+    op.get_subtree::<Dict>("presence")
+        .unwrap()
+        .set(user, status)
+        .unwrap();
+    op.commit().unwrap();
+}
+
+fn presence(db: &BaseDB, tree: &eidetica::entry::ID, user: &str) -> Option<String> {
+    let tree = db.load_tree(tree).unwrap();
+    let presence = tree.get_subtree_viewer::<Dict>("presence").unwrap();
+    presence.get_string(user).ok()
+}
+
+#[test]
+fn test_sync_exchanges_ephemeral_state() {
+    let remote = setup_db();
+    let tree = remote.new_tree_default(TEST_KEY).unwrap();
+    tree.mark_ephemeral("presence").unwrap();
+    let root = tree.root_id().clone();
+    set_presence(&remote, &root, "alice", "online");
+
+    // Fetching the tree brings the remote's ephemeral state along
+    let local = setup_replica(&remote);
+    let (mut peer, server) = connect_ephemeral(&local, &remote);
+    let stats = peer.sync_tree(&root).unwrap();
+    assert_eq!(stats.received, 2);
+    assert_eq!(presence(&local, &root, "alice").as_deref(), Some("online"));
+
+    // Local ephemeral writes are pushed, without any entries
+    set_presence(&local, &root, "bob", "away");
+    set_presence(&local, &root, "alice", "offline");
+    let stats = peer.sync_tree(&root).unwrap();
+    assert_eq!(stats.sent, 0);
+    finish(peer, server);
+    assert_eq!(presence(&remote, &root, "bob").as_deref(), Some("away"));
+    assert_eq!(
+        presence(&remote, &root, "alice").as_deref(),
+        Some("offline")
+    );
+    assert_eq!(
+        remote
+            .load_tree(&root)
+            .unwrap()
+            .get_all_entries()
+            .unwrap()
+            .len(),
+        2
+    );
+}
+
+#[test]
+fn test_sync_without_ephemeral_state() {
+    let remote = setup_db();
+    let tree = remote.new_tree_default(TEST_KEY).unwrap();
+    tree.mark_ephemeral("presence").unwrap();
+    let root = tree.root_id().clone();
+    set_presence(&remote, &root, "alice", "online");
+
+    // A peer that does not share ephemeral state gets the entries only
+    let local = setup_replica(&remote);
+    let (mut peer, server) = connect(&local, &remote);
+    peer.sync_tree(&root).unwrap();
+    finish(peer, server);
+    assert!(
+        local
+            .load_tree(&root)
+            .unwrap()
+            .is_ephemeral("presence")
+            .unwrap()
+    );
+    assert_eq!(presence(&local, &root, "alice"), None);
+}
+
+#[test]
+fn test_sync_ignores_subtrees_not_marked_ephemeral() {
+    let remote = setup_db();
+    let tree = remote.new_tree_default(TEST_KEY).unwrap();
+    tree.mark_ephemeral("presence").unwrap();
+    let root = tree.root_id().clone();
+
+    // A peer pushing ephemeral state for a subtree that is not marked
+    let (addr, server) = serve_ephemeral(&remote);
+    let mut stream = TcpStream::connect(addr).unwrap();
+    let tips = tree.get_tips().unwrap();
+    send(
+        &mut stream,
+        json!({"SyncTree": {"version": 1, "tree": root, "tips": tips}}),
+    );
+    assert!(receive(&mut stream).get("TreeState").is_some());
+    let mut state = Map::new();
+    state.set_string("alice", "online");
+    send(
+        &mut stream,
+        json!({"Push": {
+            "tree": root,
+            "entries": [],
+            "ephemeral": {"presence": state, "notes": state},
+        }}),
+    );
+    assert!(receive(&mut stream).get("Stored").is_some());
+    drop(stream);
+    server.join().unwrap().unwrap();
+
+    assert_eq!(presence(&remote, &root, "alice").as_deref(), Some("online"));
+    let notes = tree.get_subtree_viewer::<Dict>("notes").unwrap();
+    assert!(notes.get("alice").is_err());
+}
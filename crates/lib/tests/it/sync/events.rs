@@ -0,0 +1,112 @@
+//! Sync event tests
+//!
+//! Tests for publishing the entries received during sync to a database's event
+//! bus with `SyncPeer::with_event_bus`, on both sides of a session.
+
+use super::helpers::*;
+use eidetica::Result;
+use eidetica::basedb::{BaseDB, DatabaseEvent};
+use eidetica::entry::ID;
+use eidetica::subtree::Dict;
+use eidetica::sync::SyncPeer;
+use std::net::{TcpListener, TcpStream};
+use std::thread::JoinHandle;
+
+fn commit_value(db: &BaseDB, tree: &ID, value: &str) -> ID {
+    let mut tree = db.load_tree(tree).unwrap();
+    tree.set_default_auth_key(TEST_KEY);
+    let op = tree.new_operation().unwrap();
+    op.get_subtree::<Dict>("data")
+        .unwrap()
+        .set("value", value)
+        .unwrap();
+    op.commit().unwrap()
+}
+
+/// Connects a peer for `local` to a server for `remote`, both publishing to
+/// their database's event bus.
+fn connect_with_events(
+    local: &BaseDB,
+    remote: &BaseDB,
+) -> (SyncPeer<TcpStream>, JoinHandle<Result<()>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let backend = remote.backend().clone();
+    let bus = remote.event_bus();
+    let server = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        SyncPeer::new(backend, stream).with_event_bus(bus).serve()
+    });
+    let peer = SyncPeer::new(local.backend().clone(), TcpStream::connect(addr).unwrap())
+        .with_event_bus(local.event_bus());
+    (peer, server)
+}
+
+/// The entries of the `EntryVerified` events in `events`, sorted, and the
+/// count of the final `SyncReceived` event.
+fn received(events: Vec<DatabaseEvent>, tree: &ID) -> (Vec<ID>, usize) {
+    let (last, verified) = events.split_last().expect("no events");
+    let mut entries: Vec<ID> = verified
+        .iter()
+        .map(|event| match event {
+            DatabaseEvent::EntryVerified { tree: t, entry } if t == tree => entry.clone(),
+            other => panic!("unexpected event {other:?}"),
+        })
+        .collect();
+    entries.sort();
+    match last {
+        DatabaseEvent::SyncReceived {
+            tree: t,
+            entries: count,
+        } if t == tree => (entries, *count),
+        other => panic!("unexpected event {other:?}"),
+    }
+}
+
+#[test]
+fn test_sync_publishes_received_entries() {
+    let remote = setup_db();
+    let root = remote.new_tree_default(TEST_KEY).unwrap().root_id().clone();
+    let entry = commit_value(&remote, &root, "one");
+
+    let local = setup_replica(&remote);
+    let local_events = local.events();
+    let remote_events = remote.events();
+    let (mut peer, server) = connect_with_events(&local, &remote);
+
+    // Fetching the tree publishes each verified entry, then a summary
+    peer.sync_tree(&root).unwrap();
+    let mut expected = vec![root.clone(), entry];
+    expected.sort();
+    assert_eq!(
+        received(local_events.try_iter().collect(), &root),
+        (expected, 2)
+    );
+
+    // Pushed entries are published on the serving side
+    let pushed = commit_value(&local, &root, "two");
+    assert_eq!(local_events.try_iter().count(), 1);
+    peer.sync_tree(&root).unwrap();
+    finish(peer, server);
+    assert_eq!(
+        received(remote_events.try_iter().collect(), &root),
+        (vec![pushed], 1)
+    );
+
+    // Sessions that receive nothing new publish nothing
+    assert_eq!(local_events.try_recv(), None);
+}
+
+#[test]
+fn test_sync_without_event_bus() {
+    let remote = setup_db();
+    let root = remote.new_tree_default(TEST_KEY).unwrap().root_id().clone();
+    commit_value(&remote, &root, "one");
+
+    let local = setup_replica(&remote);
+    let events = local.events();
+    let (mut peer, server) = connect(&local, &remote);
+    assert_eq!(peer.sync_tree(&root).unwrap().received, 2);
+    finish(peer, server);
+    assert_eq!(events.try_recv(), None);
+}
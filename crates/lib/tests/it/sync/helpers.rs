@@ -0,0 +1,63 @@
+use eidetica::Result;
+use eidetica::backend::Database;
+use eidetica::backend::database::InMemory;
+use eidetica::basedb::BaseDB;
+use eidetica::sync::SyncPeer;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+pub const TEST_KEY: &str = "test_key";
+
+/// Creates a database with `TEST_KEY` added.
+pub fn setup_db() -> BaseDB {
+    let db = BaseDB::new(Box::new(InMemory::new()));
+    db.add_private_key(TEST_KEY).expect("Failed to add key");
+    db
+}
+
+/// Creates a second database that can sign for trees created by `db`.
+pub fn setup_replica(db: &BaseDB) -> BaseDB {
+    let replica = BaseDB::new(Box::new(InMemory::new()));
+    let key = db.backend().get_private_key(TEST_KEY).unwrap().unwrap();
+    replica.import_private_key(TEST_KEY, key).unwrap();
+    replica
+}
+
+/// Serves sync sessions for `backend` on a background thread.
+///
+/// Returns a connected stream and the server thread, which finishes once the
+/// stream is closed.
+pub fn spawn_server(backend: Arc<dyn Database>) -> (TcpStream, JoinHandle<Result<()>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        SyncPeer::new(backend, stream).serve()
+    });
+    (TcpStream::connect(addr).unwrap(), server)
+}
+
+/// Connects a `SyncPeer` for `local` to a server for `remote`.
+pub fn connect(local: &BaseDB, remote: &BaseDB) -> (SyncPeer<TcpStream>, JoinHandle<Result<()>>) {
+    let (stream, server) = spawn_server(remote.backend().clone());
+    (SyncPeer::new(local.backend().clone(), stream), server)
+}
+
+/// Closes the peer's connection and waits for the server to finish.
+pub fn finish(peer: SyncPeer<TcpStream>, server: JoinHandle<Result<()>>) {
+    drop(peer);
+    server.join().unwrap().expect("Server failed");
+}
+
+/// Serves sync sessions for `backend` on a background thread, for a single
+/// connection made to the returned address.
+pub fn spawn_listener(backend: Arc<dyn Database>) -> (SocketAddr, JoinHandle<Result<()>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        SyncPeer::new(backend, stream).serve()
+    });
+    (addr, server)
+}
@@ -0,0 +1,278 @@
+use super::helpers::*;
+use eidetica::Tree;
+use eidetica::basedb::BaseDB;
+use eidetica::entry::ID;
+use eidetica::subtree::Dict;
+use eidetica::sync::transport::{
+    Libp2p, Libp2pConfig, Multiaddr, Ticket, key_for_peer, peer_id_for_key,
+};
+use std::time::{Duration, Instant};
+
+const NODE_KEY: &str = "node_key";
+
+/// Listens on localhost over TCP, without mDNS so tests only find each other.
+fn local_config() -> Libp2pConfig {
+    Libp2pConfig {
+        listen: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+        mdns: false,
+        gossip_interval: Duration::from_millis(100),
+        ..Default::default()
+    }
+}
+
+/// Starts a node for `db`.
+///
+/// Replicas share `TEST_KEY`, so each node gets a key of its own.
+fn start_node(db: &BaseDB, config: Libp2pConfig) -> Libp2p {
+    db.add_private_key(NODE_KEY).unwrap();
+    let key = db.backend().get_private_key(NODE_KEY).unwrap().unwrap();
+    Libp2p::start(db.backend().clone(), &key, config).unwrap()
+}
+
+fn connect(from: &Libp2p, to: &Libp2p) {
+    let addr = to.listen_addrs().unwrap()[0].clone();
+    from.dial(addr).unwrap();
+    wait_until(|| from.connected_peers().unwrap().contains(&to.peer_id()));
+}
+
+fn wait_until(condition: impl Fn() -> bool) {
+    let deadline = Instant::now() + Duration::from_secs(20);
+    while !condition() {
+        assert!(Instant::now() < deadline, "timed out");
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+fn set_value(tree: &Tree, key: &str) {
+    let op = tree.new_authenticated_operation(TEST_KEY).unwrap();
+    op.get_subtree::<Dict>("data")
+        .unwrap()
+        .set(key, "value")
+        .unwrap();
+    op.commit().unwrap();
+}
+
+fn tips(db: &BaseDB, tree: &ID) -> Vec<ID> {
+    let mut tips = db.backend().get_tips(tree).unwrap();
+    tips.sort();
+    tips
+}
+
+#[test]
+fn test_peer_id_is_bound_to_signing_key() {
+    let db = setup_db();
+    let node = start_node(&db, local_config());
+
+    let key = db.backend().get_private_key(NODE_KEY).unwrap().unwrap();
+    assert_eq!(node.peer_id(), peer_id_for_key(&key.verifying_key()));
+    assert_eq!(key_for_peer(&node.peer_id()), Some(key.verifying_key()));
+}
+
+#[test]
+fn test_gossip_syncs_subscribed_tree() {
+    let db = setup_db();
+    let tree = db.new_tree_default(TEST_KEY).unwrap();
+    for i in 0..3 {
+        set_value(&tree, &format!("key{i}"));
+    }
+    let replica = setup_replica(&db);
+
+    let node = start_node(&db, local_config());
+    let replica_node = start_node(&replica, local_config());
+    node.subscribe(tree.root_id()).unwrap();
+    replica_node.subscribe(tree.root_id()).unwrap();
+    connect(&replica_node, &node);
+
+    let root_id = tree.root_id().clone();
+    wait_until(|| tips(&replica, &root_id) == tips(&db, &root_id));
+    let synced = replica.load_tree(&root_id).unwrap();
+    let viewer = synced.get_subtree_viewer::<Dict>("data").unwrap();
+    assert_eq!(viewer.get_string("key2").unwrap(), "value");
+
+    // Commits on the replica flow back the same way
+    set_value(&synced, "from_replica");
+    replica_node.announce(&root_id).unwrap();
+    wait_until(|| tips(&db, &root_id) == tips(&replica, &root_id));
+}
+
+#[test]
+fn test_sync_tree_fetches_in_batches() {
+    let db = setup_db();
+    let tree = db.new_tree_default(TEST_KEY).unwrap();
+    for i in 0..5 {
+        set_value(&tree, &format!("key{i}"));
+    }
+    let replica = setup_replica(&db);
+
+    let node = start_node(
+        &db,
+        Libp2pConfig {
+            batch_size: 2,
+            gossip_interval: Duration::from_secs(3600),
+            ..local_config()
+        },
+    );
+    let replica_node = start_node(&replica, local_config());
+    node.subscribe(tree.root_id()).unwrap();
+    connect(&replica_node, &node);
+
+    let stats = replica_node
+        .sync_tree(&node.peer_id(), tree.root_id())
+        .unwrap();
+    assert_eq!(stats.received, 6);
+    assert_eq!(stats.sent, 0);
+    assert_eq!(tips(&replica, tree.root_id()), tips(&db, tree.root_id()));
+}
+
+#[test]
+fn test_unsubscribed_tree_is_not_served() {
+    let db = setup_db();
+    let tree = db.new_tree_default(TEST_KEY).unwrap();
+    let replica = setup_replica(&db);
+
+    let node = start_node(&db, local_config());
+    let replica_node = start_node(&replica, local_config());
+    connect(&replica_node, &node);
+
+    let err = replica_node
+        .sync_tree(&node.peer_id(), tree.root_id())
+        .unwrap_err();
+    assert_eq!(err.module(), "sync");
+    assert!(
+        replica
+            .backend()
+            .get_tips(tree.root_id())
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[test]
+fn test_peers_outside_allowed_keys_are_rejected() {
+    let db = setup_db();
+    let tree = db.new_tree_default(TEST_KEY).unwrap();
+    let stranger = setup_db();
+
+    let (_, other_key) = eidetica::auth::crypto::generate_keypair();
+    let node = start_node(
+        &db,
+        Libp2pConfig {
+            allowed_keys: Some(vec![other_key]),
+            ..local_config()
+        },
+    );
+    node.subscribe(tree.root_id()).unwrap();
+    let stranger_node = start_node(&stranger, local_config());
+    stranger_node
+        .dial(node.listen_addrs().unwrap()[0].clone())
+        .unwrap();
+
+    assert!(
+        stranger_node
+            .sync_tree(&node.peer_id(), tree.root_id())
+            .is_err()
+    );
+    wait_until(|| {
+        !node
+            .connected_peers()
+            .unwrap()
+            .contains(&stranger_node.peer_id())
+    });
+}
+
+#[test]
+fn test_ticket_round_trip() {
+    let db = setup_db();
+    let node = start_node(&db, local_config());
+
+    let ticket = node.ticket().unwrap();
+    assert_eq!(ticket.peer, node.peer_id());
+    let listen_addrs = node.listen_addrs().unwrap();
+    assert!(listen_addrs.iter().all(|addr| ticket.addrs.contains(addr)));
+
+    let text = ticket.to_string();
+    assert!(text.starts_with("eidetica-node:"));
+    assert_eq!(text.parse::<Ticket>().unwrap(), ticket);
+
+    assert!("eidetica-node:???".parse::<Ticket>().is_err());
+    assert!("not a ticket".parse::<Ticket>().is_err());
+}
+
+#[test]
+fn test_sync_over_quic_from_ticket() {
+    let db = setup_db();
+    let tree = db.new_tree_default(TEST_KEY).unwrap();
+    set_value(&tree, "key");
+    let replica = setup_replica(&db);
+
+    let quic_config = || Libp2pConfig {
+        listen: vec!["/ip4/127.0.0.1/udp/0/quic-v1".parse().unwrap()],
+        ..local_config()
+    };
+    let node = start_node(&db, quic_config());
+    let replica_node = start_node(&replica, quic_config());
+    node.subscribe(tree.root_id()).unwrap();
+
+    let ticket: Ticket = node.ticket().unwrap().to_string().parse().unwrap();
+    replica_node.dial_ticket(&ticket).unwrap();
+    wait_until(|| {
+        replica_node
+            .connected_peers()
+            .unwrap()
+            .contains(&node.peer_id())
+    });
+
+    let stats = replica_node
+        .sync_tree(&node.peer_id(), tree.root_id())
+        .unwrap();
+    assert_eq!(stats.received, 2);
+}
+
+#[test]
+fn test_sync_through_relay() {
+    let relay_db = setup_db();
+    let relay = start_node(
+        &relay_db,
+        Libp2pConfig {
+            relay_server: true,
+            ..local_config()
+        },
+    );
+    let relay_addr = relay.listen_addrs().unwrap()[0]
+        .clone()
+        .with_p2p(relay.peer_id())
+        .unwrap();
+
+    let db = setup_db();
+    let tree = db.new_tree_default(TEST_KEY).unwrap();
+    set_value(&tree, "key");
+    let node = start_node(
+        &db,
+        Libp2pConfig {
+            relays: vec![relay_addr],
+            ..local_config()
+        },
+    );
+    node.subscribe(tree.root_id()).unwrap();
+
+    // Only hand out the relayed address, as for a node behind a NAT
+    let relayed = |addr: &Multiaddr| addr.to_string().contains("/p2p-circuit");
+    wait_until(|| node.ticket().unwrap().addrs.iter().any(relayed));
+    let mut ticket = node.ticket().unwrap();
+    ticket.addrs.retain(relayed);
+
+    let replica = setup_replica(&db);
+    let replica_node = start_node(&replica, local_config());
+    replica_node.dial_ticket(&ticket).unwrap();
+    wait_until(|| {
+        replica_node
+            .connected_peers()
+            .unwrap()
+            .contains(&node.peer_id())
+    });
+
+    let stats = replica_node
+        .sync_tree(&node.peer_id(), tree.root_id())
+        .unwrap();
+    assert_eq!(stats.received, 2);
+}
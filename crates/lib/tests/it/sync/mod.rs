@@ -0,0 +1,21 @@
+//! Sync integration tests
+//!
+//! This module tests `SyncPeer`, exchanging tree entries between two backends
+//! over a local TCP connection, compressing sync sessions, sending missing
+//! entries in priority order, verifying received entries on a
+//! `VerificationPool`, mounting remote trees with `BaseDB::mount_remote`,
+//! sharing ephemeral subtrees, publishing received entries to a database's
+//! event bus, and syncing subscribed trees over the libp2p
+//! transport.
+
+#[cfg(feature = "compression")]
+mod compression;
+mod ephemeral;
+mod events;
+mod helpers;
+#[cfg(feature = "libp2p")]
+mod libp2p;
+mod priority;
+mod remote_mount;
+mod tree_sync;
+mod verification_pool;
@@ -0,0 +1,274 @@
+//! Tests for prioritized, batched transfers of missing entries
+
+use super::helpers::*;
+use eidetica::backend::{Database, VerificationStatus};
+use eidetica::basedb::BaseDB;
+use eidetica::entry::{Entry, ID};
+use eidetica::subtree::Dict;
+use eidetica::sync::{Priority, SyncPeer};
+use std::collections::HashSet;
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+
+/// A transport that records which entries of a tree are stored locally each
+/// time the peer reads from it.
+struct Observing {
+    stream: TcpStream,
+    backend: Arc<dyn Database>,
+    tree: ID,
+    stored: Arc<Mutex<Vec<HashSet<ID>>>>,
+}
+
+impl Read for Observing {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let stored: HashSet<ID> = self
+            .backend
+            .get_tree(&self.tree)
+            .unwrap_or_default()
+            .iter()
+            .map(Entry::id)
+            .collect();
+        let mut snapshots = self.stored.lock().unwrap();
+        if snapshots.last() != Some(&stored) {
+            snapshots.push(stored);
+        }
+        drop(snapshots);
+        self.stream.read(buf)
+    }
+}
+
+impl Write for Observing {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+/// IDs of the entries written by `populate`, grouped by what they changed.
+struct Populated {
+    tree: ID,
+    settings: Vec<ID>,
+    notes: Vec<ID>,
+    todos: Vec<ID>,
+}
+
+fn write(db: &BaseDB, tree: &ID, subtree: &str, key: &str) -> ID {
+    let tree = db.load_tree(tree).unwrap();
+    let op = tree.new_authenticated_operation(TEST_KEY).unwrap();
+    op.get_subtree::<Dict>(subtree)
+        .unwrap()
+        .set(key, "value")
+        .unwrap();
+    op.commit().unwrap()
+}
+
+/// Creates a tree with old notes, a settings change and newer todos.
+fn populate(db: &BaseDB) -> Populated {
+    let tree = db.new_tree_default(TEST_KEY).unwrap().root_id().clone();
+    let notes = (0..8)
+        .map(|i| write(db, &tree, "notes", &format!("note_{i}")))
+        .collect();
+    let settings = vec![tree.clone(), write(db, &tree, "_settings", "name")];
+    let todos = (0..8)
+        .map(|i| write(db, &tree, "todos", &format!("todo_{i}")))
+        .collect();
+    Populated {
+        tree,
+        settings,
+        notes,
+        todos,
+    }
+}
+
+/// Fetches `tree` from `remote`, served by `server_peer`, into a fresh replica
+/// and returns the replica and the entries it had stored after each read.
+fn fetch<F>(
+    remote: &BaseDB,
+    tree: &ID,
+    configure_server: F,
+    subscribe: &[&str],
+) -> (BaseDB, Vec<HashSet<ID>>)
+where
+    F: FnOnce(SyncPeer<TcpStream>) -> SyncPeer<TcpStream> + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let backend = remote.backend().clone();
+    let server = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        configure_server(SyncPeer::new(backend, stream)).serve()
+    });
+
+    let local = setup_replica(remote);
+    let stored = Arc::new(Mutex::new(Vec::new()));
+    let transport = Observing {
+        stream: TcpStream::connect(addr).unwrap(),
+        backend: local.backend().clone(),
+        tree: tree.clone(),
+        stored: Arc::clone(&stored),
+    };
+    let mut peer = SyncPeer::new(local.backend().clone(), transport);
+    for subtree in subscribe {
+        peer = peer.subscribe(*subtree);
+    }
+    assert_eq!(peer.sync_tree(tree).unwrap().received, 18);
+    drop(peer);
+    server.join().unwrap().unwrap();
+
+    let stored = stored.lock().unwrap().clone();
+    (local, stored)
+}
+
+/// The first snapshot holding any of `ids`.
+fn first_with<'a>(stored: &'a [HashSet<ID>], ids: &[ID]) -> &'a HashSet<ID> {
+    stored
+        .iter()
+        .find(|snapshot| ids.iter().any(|id| snapshot.contains(id)))
+        .unwrap()
+}
+
+fn contains_all(snapshot: &HashSet<ID>, ids: &[ID]) -> bool {
+    ids.iter().all(|id| snapshot.contains(id))
+}
+
+#[test]
+fn test_settings_and_subscribed_subtrees_arrive_first() {
+    let remote = setup_db();
+    let populated = populate(&remote);
+
+    let (local, stored) = fetch(
+        &remote,
+        &populated.tree,
+        |peer| peer.with_batch_size(3),
+        &["notes"],
+    );
+    // One read before anything arrives, then one per batch of three
+    assert_eq!(stored.len(), 7);
+    assert!(contains_all(
+        first_with(&stored, &populated.notes),
+        &populated.settings
+    ));
+    let first_todo = first_with(&stored, &populated.todos);
+    assert!(contains_all(first_todo, &populated.notes));
+
+    let tree = local.load_tree(&populated.tree).unwrap();
+    assert_eq!(
+        tree.get_tips().unwrap(),
+        remote
+            .load_tree(&populated.tree)
+            .unwrap()
+            .get_tips()
+            .unwrap()
+    );
+    for entry in tree.get_all_entries().unwrap() {
+        assert_eq!(
+            local
+                .backend()
+                .get_verification_status(&entry.id())
+                .unwrap(),
+            VerificationStatus::Verified
+        );
+    }
+    let todos = tree.get_subtree_viewer::<Dict>("todos").unwrap();
+    assert_eq!(todos.get_string("todo_7").unwrap(), "value");
+}
+
+#[test]
+fn test_recent_entries_arrive_first_without_subscriptions() {
+    let remote = setup_db();
+    let populated = populate(&remote);
+
+    let (_local, stored) = fetch(
+        &remote,
+        &populated.tree,
+        |peer| peer.with_batch_size(4),
+        &[],
+    );
+    let first_note = first_with(&stored, &populated.notes);
+    assert!(contains_all(first_note, &populated.settings));
+    // All entries are recent, so they arrive parents first after the settings
+    assert!(!populated.todos.iter().any(|id| first_note.contains(id)));
+}
+
+#[test]
+fn test_priority_hook_overrides_defaults() {
+    let remote = setup_db();
+    let populated = populate(&remote);
+    let seen = Arc::new(Mutex::new(Vec::new()));
+
+    let recorded = Arc::clone(&seen);
+    let (_local, stored) = fetch(
+        &remote,
+        &populated.tree,
+        move |peer| {
+            peer.with_batch_size(2)
+                .with_priority(move |entry, default| {
+                    recorded.lock().unwrap().push((entry.id(), default));
+                    if entry.subtrees().iter().any(|name| name == "todos") {
+                        Priority(Priority::SETTINGS.0 + 1)
+                    } else {
+                        default
+                    }
+                })
+        },
+        &["notes"],
+    );
+
+    // The hook is asked once per entry and sees the default priorities
+    let seen = seen.lock().unwrap();
+    assert_eq!(seen.len(), 18);
+    for (id, default) in seen.iter() {
+        let expected = if populated.settings.contains(id) {
+            Priority::SETTINGS
+        } else if populated.notes.contains(id) {
+            Priority::RECENT
+        } else {
+            Priority::HISTORY
+        };
+        assert_eq!(*default, expected);
+    }
+
+    // Todos now come straight after the settings, ahead of the notes
+    assert!(contains_all(
+        first_with(&stored, &populated.notes),
+        &populated.todos
+    ));
+}
+
+#[test]
+fn test_batched_fetch_merges_with_existing_entries() {
+    let remote = setup_db();
+    let populated = populate(&remote);
+    let local = setup_replica(&remote);
+
+    let (mut peer, server) = connect(&local, &remote);
+    peer.sync_tree(&populated.tree).unwrap();
+    finish(peer, server);
+
+    // New entries on both sides are exchanged in a later session
+    write(&remote, &populated.tree, "todos", "remote");
+    write(&local, &populated.tree, "notes", "local");
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let backend = remote.backend().clone();
+    let server = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        SyncPeer::new(backend, stream).with_batch_size(1).serve()
+    });
+    let mut peer = SyncPeer::new(local.backend().clone(), TcpStream::connect(addr).unwrap());
+    let stats = peer.sync_tree(&populated.tree).unwrap();
+    assert_eq!((stats.sent, stats.received), (1, 1));
+    drop(peer);
+    server.join().unwrap().unwrap();
+
+    let tips = |db: &BaseDB| -> HashSet<ID> {
+        let tree = db.load_tree(&populated.tree).unwrap();
+        tree.get_tips().unwrap().into_iter().collect()
+    };
+    assert_eq!(tips(&local), tips(&remote));
+    assert_eq!(tips(&local).len(), 2);
+}
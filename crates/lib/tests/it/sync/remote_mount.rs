@@ -0,0 +1,108 @@
+use super::helpers::*;
+use eidetica::Tree;
+use eidetica::backend::VerificationStatus;
+use eidetica::backend::database::InMemory;
+use eidetica::basedb::BaseDB;
+use eidetica::subtree::Dict;
+
+/// Creates a remote database with a tree holding `greeting = hello`, and a
+/// local database that holds the same signing key.
+fn setup() -> (BaseDB, Tree, BaseDB) {
+    let remote = setup_db();
+    let tree = remote.new_tree_default(TEST_KEY).unwrap();
+    let op = tree.new_authenticated_operation(TEST_KEY).unwrap();
+    op.get_subtree::<Dict>("data")
+        .unwrap()
+        .set("greeting", "hello")
+        .unwrap();
+    op.commit().unwrap();
+
+    let local = BaseDB::new(Box::new(InMemory::new()));
+    let key = remote.backend().get_private_key(TEST_KEY).unwrap().unwrap();
+    local.import_private_key(TEST_KEY, key).unwrap();
+    (remote, tree, local)
+}
+
+#[test]
+fn test_mount_reads_remote_tree() {
+    let (remote, tree, local) = setup();
+    let (addr, server) = spawn_listener(remote.backend().clone());
+
+    let mounted = local.mount_remote(addr, tree.root_id()).unwrap();
+    assert_eq!(mounted.get_tips().unwrap(), tree.get_tips().unwrap());
+    let data = mounted.get_subtree_viewer::<Dict>("data").unwrap();
+    assert_eq!(data.get_string("greeting").unwrap(), "hello");
+
+    // Nothing was replicated locally
+    assert!(local.backend().all_roots().unwrap().is_empty());
+
+    drop(data);
+    drop(mounted);
+    server.join().unwrap().expect("Server failed");
+}
+
+#[test]
+fn test_mount_forwards_commits() {
+    let (remote, tree, local) = setup();
+    let (addr, server) = spawn_listener(remote.backend().clone());
+
+    let mut mounted = local.mount_remote(addr, tree.root_id()).unwrap();
+    mounted.set_default_auth_key(TEST_KEY);
+    let op = mounted.new_operation().unwrap();
+    op.get_subtree::<Dict>("data")
+        .unwrap()
+        .set("greeting", "hi")
+        .unwrap();
+    let id = op.commit().unwrap();
+
+    // The commit is stored and verified on the remote only
+    assert_eq!(tree.get_tips().unwrap(), vec![id.clone()]);
+    assert_eq!(
+        remote.backend().get_verification_status(&id).unwrap(),
+        VerificationStatus::Verified
+    );
+    let data = tree.get_subtree_viewer::<Dict>("data").unwrap();
+    assert_eq!(data.get_string("greeting").unwrap(), "hi");
+    assert!(local.backend().get(&id).unwrap_err().is_not_found());
+
+    // Later commits on the remote are visible through the mount
+    let op = tree.new_authenticated_operation(TEST_KEY).unwrap();
+    op.get_subtree::<Dict>("data")
+        .unwrap()
+        .set("greeting", "hey")
+        .unwrap();
+    op.commit().unwrap();
+    let data = mounted.get_subtree_viewer::<Dict>("data").unwrap();
+    assert_eq!(data.get_string("greeting").unwrap(), "hey");
+
+    // Viewers hold the connection open too
+    drop(data);
+    drop(mounted);
+    server.join().unwrap().expect("Server failed");
+}
+
+#[test]
+fn test_mount_unknown_tree() {
+    let (remote, _tree, local) = setup();
+    let (addr, server) = spawn_listener(remote.backend().clone());
+
+    match local.mount_remote(addr, &"missing".into()) {
+        Err(err) => assert!(err.is_not_found()),
+        Ok(_) => panic!("Mounted a tree the remote does not have"),
+    }
+    server.join().unwrap().expect("Server failed");
+}
+
+#[test]
+fn test_mount_survives_missing_entries() {
+    let (remote, tree, local) = setup();
+    let (addr, server) = spawn_listener(remote.backend().clone());
+
+    let mounted = local.mount_remote(addr, tree.root_id()).unwrap();
+    assert!(mounted.get_entry("missing").unwrap_err().is_not_found());
+    // The connection is still usable after a failed lookup
+    assert_eq!(mounted.get_tips().unwrap(), tree.get_tips().unwrap());
+
+    drop(mounted);
+    server.join().unwrap().expect("Server failed");
+}
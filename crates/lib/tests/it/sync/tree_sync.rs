@@ -0,0 +1,314 @@
+use super::helpers::*;
+use eidetica::auth::crypto::sign_entry;
+use eidetica::auth::types::{SigInfo, SigKey};
+use eidetica::backend::Database;
+use eidetica::backend::VerificationStatus;
+use eidetica::backend::database::InMemory;
+use eidetica::basedb::TreeQuota;
+use eidetica::entry::Entry;
+use eidetica::subtree::Dict;
+use eidetica::sync::{RemoteDatabase, SyncPeer, SyncStats};
+use std::io::{Read, Write};
+
+fn set_value(tree: &eidetica::Tree, key: &str, value: &str) {
+    let op = tree.new_authenticated_operation(TEST_KEY).unwrap();
+    op.get_subtree::<Dict>("data")
+        .unwrap()
+        .set(key, value)
+        .unwrap();
+    op.commit().unwrap();
+}
+
+#[test]
+fn test_sync_fetches_unknown_tree() {
+    let remote = setup_db();
+    let tree = remote.new_tree_default(TEST_KEY).unwrap();
+    set_value(&tree, "greeting", "hello");
+
+    let local = setup_replica(&remote);
+    let (mut peer, server) = connect(&local, &remote);
+    let stats = peer.sync_tree(tree.root_id()).unwrap();
+    assert_eq!(
+        stats,
+        SyncStats {
+            sent: 0,
+            received: 2
+        }
+    );
+    finish(peer, server);
+
+    let replica = local.load_tree(tree.root_id()).unwrap();
+    assert_eq!(replica.get_tips().unwrap(), tree.get_tips().unwrap());
+    let data = replica.get_subtree_viewer::<Dict>("data").unwrap();
+    assert_eq!(data.get_string("greeting").unwrap(), "hello");
+    for entry in replica.get_all_entries().unwrap() {
+        assert_eq!(
+            local
+                .backend()
+                .get_verification_status(&entry.id())
+                .unwrap(),
+            VerificationStatus::Verified
+        );
+    }
+}
+
+#[test]
+fn test_sync_pushes_tree_to_peer() {
+    let local = setup_db();
+    let tree = local.new_tree_default(TEST_KEY).unwrap();
+    set_value(&tree, "key", "value");
+
+    let remote = setup_replica(&local);
+    let (mut peer, server) = connect(&local, &remote);
+    let stats = peer.sync_tree(tree.root_id()).unwrap();
+    assert_eq!(
+        stats,
+        SyncStats {
+            sent: 2,
+            received: 0
+        }
+    );
+    finish(peer, server);
+
+    let pushed = remote.load_tree(tree.root_id()).unwrap();
+    let data = pushed.get_subtree_viewer::<Dict>("data").unwrap();
+    assert_eq!(data.get_string("key").unwrap(), "value");
+}
+
+#[test]
+fn test_sync_merges_divergent_changes() {
+    let db_a = setup_db();
+    let tree_a = db_a.new_tree_default(TEST_KEY).unwrap();
+    // Seed the subtree so both sides' changes share a common ancestor in it
+    set_value(&tree_a, "shared", "base");
+
+    let db_b = setup_replica(&db_a);
+    let (mut peer, server) = connect(&db_b, &db_a);
+    peer.sync_tree(tree_a.root_id()).unwrap();
+    let tree_b = db_b.load_tree(tree_a.root_id()).unwrap();
+
+    set_value(&tree_a, "from_a", "1");
+    set_value(&tree_b, "from_b", "2");
+    set_value(&tree_b, "from_b_again", "3");
+
+    let stats = peer.sync_tree(tree_a.root_id()).unwrap();
+    assert_eq!(
+        stats,
+        SyncStats {
+            sent: 2,
+            received: 1
+        }
+    );
+
+    // Both sides now share the same tips and merged state
+    let mut tips_a = tree_a.get_tips().unwrap();
+    let mut tips_b = tree_b.get_tips().unwrap();
+    tips_a.sort();
+    tips_b.sort();
+    assert_eq!(tips_a, tips_b);
+    assert_eq!(tips_a.len(), 2);
+    for tree in [&tree_a, &tree_b] {
+        let data = tree.get_subtree_viewer::<Dict>("data").unwrap();
+        assert_eq!(data.get_string("shared").unwrap(), "base");
+        assert_eq!(data.get_string("from_a").unwrap(), "1");
+        assert_eq!(data.get_string("from_b").unwrap(), "2");
+        assert_eq!(data.get_string("from_b_again").unwrap(), "3");
+    }
+
+    // A further sync has nothing left to exchange
+    let stats = peer.sync_tree(tree_a.root_id()).unwrap();
+    assert_eq!(stats, SyncStats::default());
+    finish(peer, server);
+}
+
+#[test]
+fn test_sync_does_not_send_failed_entries() {
+    let remote = setup_db();
+    let tree = remote.new_tree_default(TEST_KEY).unwrap();
+    set_value(&tree, "key", "value");
+    let failed_id = tree.get_tips().unwrap()[0].clone();
+    remote
+        .backend()
+        .update_verification_status(&failed_id, VerificationStatus::Failed)
+        .unwrap();
+
+    let local = setup_replica(&remote);
+    let (mut peer, server) = connect(&local, &remote);
+    let stats = peer.sync_tree(tree.root_id()).unwrap();
+    assert_eq!(stats.received, 1);
+    finish(peer, server);
+
+    assert!(local.backend().get(&failed_id).is_err());
+}
+
+#[test]
+fn test_sync_marks_unverifiable_entries_failed() {
+    let remote = setup_db();
+    let tree = remote.new_tree_default(TEST_KEY).unwrap();
+
+    // An entry signed with a key the tree does not know
+    let forged = Entry::builder(tree.root_id().clone())
+        .add_parent(tree.root_id().clone())
+        .set_subtree_data("data", "{}")
+        .set_sig(SigInfo {
+            key: SigKey::Direct("unknown_key".to_string()),
+            sig: Some("bm90IGEgc2lnbmF0dXJl".to_string()),
+        })
+        .build();
+    let forged_id = tree.insert_raw(forged).unwrap();
+
+    let local = setup_replica(&remote);
+    let (mut peer, server) = connect(&local, &remote);
+    let stats = peer.sync_tree(tree.root_id()).unwrap();
+    assert_eq!(stats.received, 2);
+    finish(peer, server);
+
+    let backend = local.backend();
+    assert_eq!(
+        backend.get_verification_status(tree.root_id()).unwrap(),
+        VerificationStatus::Verified
+    );
+    assert_eq!(
+        backend.get_verification_status(&forged_id).unwrap(),
+        VerificationStatus::Failed
+    );
+}
+
+#[test]
+fn test_sync_reverifies_entries_stored_as_failed() {
+    let remote = setup_db();
+    let tree = remote.new_tree_default(TEST_KEY).unwrap();
+    set_value(&tree, "key", "value");
+    let tip = tree.get_tips().unwrap()[0].clone();
+    // As left behind by an interrupted verification
+    remote
+        .backend()
+        .update_verification_status(&tip, VerificationStatus::Failed)
+        .unwrap();
+
+    // A peer sends the entry again
+    let (addr, server) = spawn_listener(remote.backend().clone());
+    let peer = RemoteDatabase::connect(addr, std::sync::Arc::new(InMemory::new())).unwrap();
+    peer.put(VerificationStatus::Verified, tree.get_entry(&tip).unwrap())
+        .unwrap();
+    drop(peer);
+    server.join().unwrap().expect("Server failed");
+
+    assert_eq!(
+        remote.backend().get_verification_status(&tip).unwrap(),
+        VerificationStatus::Verified
+    );
+}
+
+#[test]
+fn test_sync_marks_over_quota_entries_failed() {
+    let remote = setup_db();
+    let tree = remote.new_tree_default(TEST_KEY).unwrap();
+    let quota_id = tree
+        .set_quota(TreeQuota {
+            max_subtree_size: Some(64),
+            ..TreeQuota::default()
+        })
+        .unwrap();
+
+    // A correctly signed entry that commits would refuse, as from a peer that
+    // does not enforce the quota
+    let signing_key = remote.backend().get_private_key(TEST_KEY).unwrap().unwrap();
+    let data = format!(r#"{{"children":{{"k":{{"Text":"{}"}}}}}}"#, "x".repeat(100));
+    let mut oversized = Entry::builder(tree.root_id().clone())
+        .add_parent(quota_id.clone())
+        .set_subtree_data("data", data)
+        .set_sig(SigInfo {
+            key: SigKey::Direct(TEST_KEY.to_string()),
+            sig: None,
+        })
+        .build();
+    oversized.sig.sig = Some(sign_entry(&oversized, &signing_key).unwrap());
+    let oversized_id = tree.insert_raw(oversized).unwrap();
+
+    let local = setup_replica(&remote);
+    let (mut peer, server) = connect(&local, &remote);
+    let stats = peer.sync_tree(tree.root_id()).unwrap();
+    assert_eq!(stats.received, 3);
+    finish(peer, server);
+
+    let backend = local.backend();
+    assert_eq!(
+        backend.get_verification_status(&quota_id).unwrap(),
+        VerificationStatus::Verified
+    );
+    assert_eq!(
+        backend.get_verification_status(&oversized_id).unwrap(),
+        VerificationStatus::Failed
+    );
+}
+
+#[test]
+fn test_sync_rejects_malformed_entries() {
+    let remote = setup_db();
+    let tree = remote.new_tree_default(TEST_KEY).unwrap();
+    // An entry with an empty subtree name, which no commit produces
+    let malformed = Entry::builder(tree.root_id().clone())
+        .add_parent(tree.root_id().clone())
+        .set_subtree_data("", "{}")
+        .build();
+    let malformed_id = tree.insert_raw(malformed).unwrap();
+
+    let local = setup_replica(&remote);
+    let (mut peer, server) = connect(&local, &remote);
+    let err = peer.sync_tree(tree.root_id()).unwrap_err();
+    assert!(matches!(
+        err,
+        eidetica::Error::Entry(eidetica::entry::EntryError::InvalidSubtreeName { .. })
+    ));
+    assert!(err.is_validation_error());
+    drop(peer);
+    let _ = server.join().unwrap();
+
+    // Nothing of the rejected batch was stored
+    assert!(local.backend().get(&malformed_id).is_err());
+    assert!(local.backend().get(tree.root_id()).is_err());
+}
+
+#[test]
+fn test_sync_rejects_out_of_order_message() {
+    let remote = setup_db();
+    let (mut stream, server) = spawn_server(remote.backend().clone());
+
+    // Push without first starting a session
+    let body = br#"{"Push":{"tree":"tree","entries":[]}}"#;
+    stream
+        .write_all(&(body.len() as u32).to_be_bytes())
+        .unwrap();
+    stream.write_all(body).unwrap();
+
+    // The server reports the error to the client before failing
+    let mut header = [0u8; 4];
+    stream.read_exact(&mut header).unwrap();
+    let mut reply = vec![0u8; u32::from_be_bytes(header) as usize];
+    stream.read_exact(&mut reply).unwrap();
+    assert!(String::from_utf8(reply).unwrap().contains("Error"));
+
+    let err = server.join().unwrap().unwrap_err();
+    assert!(matches!(
+        err,
+        eidetica::Error::Sync(eidetica::sync::SyncError::UnexpectedMessage { .. })
+    ));
+}
+
+#[test]
+fn test_sync_reports_closed_connection() {
+    let local = setup_db();
+    let tree = local.new_tree_default(TEST_KEY).unwrap();
+
+    // A server that hangs up as soon as a client connects
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = std::thread::spawn(move || drop(listener.accept().unwrap()));
+
+    let stream = std::net::TcpStream::connect(addr).unwrap();
+    server.join().unwrap();
+    let mut peer = SyncPeer::new(local.backend().clone(), stream);
+    let err = peer.sync_tree(tree.root_id()).unwrap_err();
+    assert!(err.is_io_error());
+}
@@ -0,0 +1,113 @@
+//! Verification pool tests
+//!
+//! Tests for `VerificationPool`: its configuration, and verifying the entries
+//! received during a sync on several workers with a bounded queue.
+
+use super::helpers::*;
+use eidetica::auth::types::{SigInfo, SigKey};
+use eidetica::backend::VerificationStatus;
+use eidetica::basedb::BaseDB;
+use eidetica::entry::{Entry, ID};
+use eidetica::subtree::Dict;
+use eidetica::sync::{SyncPeer, VerificationPool};
+
+#[test]
+fn test_pool_configuration() {
+    assert_eq!(VerificationPool::new(0).workers(), 1);
+    assert_eq!(VerificationPool::single_threaded().workers(), 1);
+    assert!(VerificationPool::default().workers() >= 1);
+    assert_eq!(
+        VerificationPool::new(4).queue_capacity(0),
+        VerificationPool::new(4).queue_capacity(1)
+    );
+}
+
+/// Creates a tree with many entries across branches, and entries signed with
+/// an unknown key. Returns the tree's root and the forged entries.
+fn setup_large_tree(db: &BaseDB) -> (ID, Vec<ID>) {
+    let tree = db.new_tree_default(TEST_KEY).unwrap();
+    for i in 0..40 {
+        let op = tree.new_operation().unwrap();
+        op.get_subtree::<Dict>("data")
+            .unwrap()
+            .set(format!("key{i}"), "value")
+            .unwrap();
+        op.commit().unwrap();
+    }
+    let base = tree.get_tips().unwrap();
+    for i in 0..10 {
+        let op = tree.new_operation_with_tips(&base).unwrap();
+        op.get_subtree::<Dict>("branches")
+            .unwrap()
+            .set(format!("branch{i}"), "value")
+            .unwrap();
+        op.commit().unwrap();
+    }
+
+    let forged = (0..3)
+        .map(|i| {
+            let entry = Entry::builder(tree.root_id().clone())
+                .add_parent(base[0].clone())
+                .set_subtree_data(
+                    "data",
+                    format!(r#"{{"children":{{"forged{i}":{{"Text":"x"}}}}}}"#),
+                )
+                .set_sig(SigInfo {
+                    key: SigKey::Direct("unknown_key".to_string()),
+                    sig: Some("bm90IGEgc2lnbmF0dXJl".to_string()),
+                })
+                .build();
+            tree.insert_raw(entry).unwrap()
+        })
+        .collect();
+    (tree.root_id().clone(), forged)
+}
+
+fn assert_replicated(local: &BaseDB, remote: &BaseDB, root: &ID, forged: &[ID]) {
+    let backend = local.backend();
+    let entries = remote.load_tree(root).unwrap().get_all_entries().unwrap();
+    assert_eq!(entries.len(), 54);
+    for entry in entries {
+        let expected = if forged.contains(&entry.id()) {
+            VerificationStatus::Failed
+        } else {
+            VerificationStatus::Verified
+        };
+        assert_eq!(
+            backend.get_verification_status(&entry.id()).unwrap(),
+            expected
+        );
+    }
+}
+
+#[test]
+fn test_sync_verifies_on_worker_pool() {
+    let remote = setup_db();
+    let (root, forged) = setup_large_tree(&remote);
+
+    // A small queue makes the ingest path wait for the workers
+    let local = setup_replica(&remote);
+    let (stream, server) = spawn_server(remote.backend().clone());
+    let mut peer = SyncPeer::new(local.backend().clone(), stream)
+        .with_verification_pool(VerificationPool::new(4).queue_capacity(2));
+    let stats = peer.sync_tree(&root).unwrap();
+    assert_eq!(stats.received, 54);
+    finish(peer, server);
+
+    assert_replicated(&local, &remote, &root, &forged);
+}
+
+#[test]
+fn test_sync_verifies_single_threaded() {
+    let remote = setup_db();
+    let (root, forged) = setup_large_tree(&remote);
+
+    let local = setup_replica(&remote);
+    let (stream, server) = spawn_server(remote.backend().clone());
+    let mut peer = SyncPeer::new(local.backend().clone(), stream)
+        .with_verification_pool(VerificationPool::single_threaded());
+    peer.sync_tree(&root).unwrap();
+    finish(peer, server);
+
+    assert_replicated(&local, &remote, &root, &forged);
+}
@@ -0,0 +1,97 @@
+//! Checkpoint tests
+//!
+//! Tests for `Tree::create_checkpoint`: the materialized state it stores, reads
+//! that start from the nearest checkpoint, concurrent branches around a
+//! checkpoint, and subtrees that are not checkpointed.
+
+use super::helpers::*;
+use crate::helpers::*;
+use eidetica::crdt::Map;
+use eidetica::entry::Entry;
+use eidetica::subtree::{BlobStore, Dict};
+
+#[test]
+fn test_checkpoint_preserves_state() {
+    let tree = setup_tree();
+    add_data_to_subtree(&tree, "data", &[("kept", "1"), ("removed", "2")]);
+    let op = tree.new_operation().unwrap();
+    op.get_subtree::<Dict>("data")
+        .unwrap()
+        .delete("removed")
+        .unwrap();
+    op.commit().unwrap();
+    add_data_to_subtree(&tree, "other", &[("key", "value")]);
+
+    let checkpoint = tree.create_checkpoint().unwrap();
+    assert_eq!(tree.get_tips().unwrap(), vec![checkpoint.clone()]);
+    let entry = tree.get_entry(&checkpoint).unwrap();
+    assert_eq!(entry.checkpoint_subtrees(), vec!["data", "other"]);
+    assert!(entry.is_checkpoint_of("data"));
+    assert!(!entry.is_checkpoint_of("_settings"));
+
+    // Writes after the checkpoint build on its state
+    add_data_to_subtree(&tree, "data", &[("added", "3")]);
+    let data = tree.get_subtree_viewer::<Dict>("data").unwrap();
+    assert_eq!(data.get_string("kept").unwrap(), "1");
+    assert_eq!(data.get_string("added").unwrap(), "3");
+    assert!(data.get("removed").is_err());
+    let other = tree.get_subtree_viewer::<Dict>("other").unwrap();
+    assert_eq!(other.get_string("key").unwrap(), "value");
+}
+
+#[test]
+fn test_reads_start_from_checkpoint() {
+    let tree = setup_tree();
+    add_data_to_subtree(&tree, "data", &[("key", "from history")]);
+
+    // A checkpoint whose state differs from the history shows that the history
+    // before it is not replayed
+    let mut state = Map::new();
+    state.set_string("key", "from checkpoint");
+    let checkpoint = Entry::builder(tree.root_id().clone())
+        .set_parents(tree.get_tips().unwrap())
+        .set_subtree_data("data", serde_json::to_string(&state).unwrap())
+        .set_subtree_parents("data", tree.subtree_tips("data").unwrap())
+        .set_metadata(r#"{"settings_tips":[],"entropy":null,"checkpoint":["data"]}"#)
+        .build();
+    tree.insert_raw(checkpoint).unwrap();
+
+    let data = tree.get_subtree_viewer::<Dict>("data").unwrap();
+    assert_eq!(data.get_string("key").unwrap(), "from checkpoint");
+}
+
+#[test]
+fn test_checkpoint_with_concurrent_branch() {
+    let tree = setup_tree();
+    let base = add_data_to_subtree(&tree, "data", &[("key", "base")]);
+    add_data_to_subtree(&tree, "data", &[("unrelated", "value")]);
+    tree.create_checkpoint().unwrap();
+
+    // A concurrent write is not undone by the older state in the checkpoint,
+    // even though the checkpoint is deeper in the history and merged later
+    create_branch_from_entry(&tree, &base, "data", &[("key", "concurrent")]);
+    assert_eq!(tree.get_tips().unwrap().len(), 2);
+    let data = tree.get_subtree_viewer::<Dict>("data").unwrap();
+    assert_eq!(data.get_string("key").unwrap(), "concurrent");
+}
+
+#[test]
+fn test_checkpoint_skips_non_map_subtrees() {
+    let tree = setup_tree();
+    add_data_to_subtree(&tree, "data", &[("key", "value")]);
+    let op = tree.new_operation().unwrap();
+    let blob_id = op
+        .get_subtree::<BlobStore>("files")
+        .unwrap()
+        .put(b"attachment")
+        .unwrap();
+    op.commit().unwrap();
+
+    let checkpoint = tree.create_checkpoint().unwrap();
+    let entry = tree.get_entry(&checkpoint).unwrap();
+    assert_eq!(entry.checkpoint_subtrees(), vec!["data"]);
+    assert!(!entry.in_subtree("files"));
+
+    let files = tree.get_subtree_viewer::<BlobStore>("files").unwrap();
+    assert_eq!(files.get(&blob_id).unwrap(), b"attachment");
+}
@@ -0,0 +1,247 @@
+//! Tree diff tests
+//!
+//! Tests for `Tree::diff`: comparing the state of a tree at two sets of tips,
+//! key by key for Dict and Table subtrees, item by item for lists, and only as
+//! changed or unchanged for subtrees that are not stored as a Map.
+
+use super::helpers::*;
+use crate::helpers::*;
+use eidetica::basedb::SubtreeDiff;
+use eidetica::crdt::map::{Change, List, Value};
+use eidetica::subtree::{BlobStore, Dict, Table};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Task {
+    title: String,
+}
+
+fn changes<'a>(diff: &'a eidetica::basedb::TreeDiff, subtree: &str) -> &'a [Change] {
+    match diff.subtree(subtree) {
+        Some(SubtreeDiff::Changes(changes)) => changes,
+        other => panic!("expected changes to '{subtree}', got {other:?}"),
+    }
+}
+
+#[test]
+fn test_diff_dict_keys() {
+    let tree = setup_tree();
+    let op = tree.new_operation().unwrap();
+    let data = op.get_subtree::<Dict>("data").unwrap();
+    data.set("title", "draft").unwrap();
+    data.set("status", "open").unwrap();
+    let mut nested = eidetica::crdt::Map::new();
+    nested.set("name", "alice");
+    data.set_node("owner", nested).unwrap();
+    let v1 = op.commit().unwrap();
+
+    let op = tree.new_operation().unwrap();
+    let data = op.get_subtree::<Dict>("data").unwrap();
+    data.set("title", "final").unwrap();
+    data.delete("status").unwrap();
+    data.set("priority", 3).unwrap();
+    let mut owner = data.get_node("owner").unwrap();
+    owner.set("name", "bob");
+    data.set_node("owner", owner).unwrap();
+    let v2 = op.commit().unwrap();
+
+    let diff = tree.diff([v1.clone()], [v2.clone()]).unwrap();
+    assert_eq!(
+        changes(&diff, "data"),
+        [
+            Change::Changed {
+                path: "owner.name".to_string(),
+                old: Value::from("alice"),
+                new: Value::from("bob"),
+            },
+            Change::Added {
+                path: "priority".to_string(),
+                value: Value::Int(3),
+            },
+            Change::Removed {
+                path: "status".to_string(),
+                value: Value::from("open"),
+            },
+            Change::Changed {
+                path: "title".to_string(),
+                old: Value::from("draft"),
+                new: Value::from("final"),
+            },
+        ]
+    );
+
+    // Diffing the other way round swaps additions and removals
+    let reverse = tree.diff([v2.clone()], [v1]).unwrap();
+    assert!(changes(&reverse, "data").contains(&Change::Added {
+        path: "status".to_string(),
+        value: Value::from("open"),
+    }));
+
+    // The same state has no differences
+    assert!(tree.diff([v2.clone()], [v2]).unwrap().is_empty());
+}
+
+#[test]
+fn test_diff_table_rows() {
+    let tree = setup_tree();
+    let op = tree.new_operation().unwrap();
+    let tasks = op.get_subtree::<Table<Task>>("tasks").unwrap();
+    let kept = tasks
+        .insert(Task {
+            title: "write".to_string(),
+        })
+        .unwrap();
+    let dropped = tasks
+        .insert(Task {
+            title: "review".to_string(),
+        })
+        .unwrap();
+    let v1 = op.commit().unwrap();
+
+    let op = tree.new_operation().unwrap();
+    let tasks = op.get_subtree::<Table<Task>>("tasks").unwrap();
+    tasks
+        .set(
+            &kept,
+            Task {
+                title: "rewrite".to_string(),
+            },
+        )
+        .unwrap();
+    tasks.delete(&dropped).unwrap();
+    let added = tasks
+        .insert(Task {
+            title: "ship".to_string(),
+        })
+        .unwrap();
+    let v2 = op.commit().unwrap();
+
+    let diff = tree.diff([v1], [v2]).unwrap();
+    let rows = changes(&diff, "tasks");
+    assert_eq!(rows.len(), 3);
+    for change in rows {
+        match change {
+            Change::Added { path, .. } => assert_eq!(path, &added),
+            Change::Removed { path, .. } => assert_eq!(path, &dropped),
+            Change::Changed { path, new, .. } => {
+                assert_eq!(path, &kept);
+                assert!(new.as_text().unwrap().contains("rewrite"));
+            }
+            other => panic!("unexpected change {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn test_diff_list_items() {
+    let tree = setup_tree();
+    let op = tree.new_operation().unwrap();
+    let data = op.get_subtree::<Dict>("data").unwrap();
+    let mut list = List::new();
+    for item in ["a", "b", "c", "d"] {
+        list.push(item);
+    }
+    data.set_list("items", list).unwrap();
+    let v1 = op.commit().unwrap();
+
+    let op = tree.new_operation().unwrap();
+    let data = op.get_subtree::<Dict>("data").unwrap();
+    let mut list = data.get_list("items").unwrap();
+    // [a, b, c, d] -> [x, C, d, e, a]
+    list.remove(1);
+    list.insert(0, "x").unwrap();
+    list.set(2, "C");
+    list.push("e");
+    list.move_item(1, 4).unwrap();
+    data.set_list("items", list).unwrap();
+    let v2 = op.commit().unwrap();
+
+    let diff = tree.diff([v1], [v2]).unwrap();
+    assert_eq!(
+        changes(&diff, "data"),
+        [
+            Change::Removed {
+                path: "items.1".to_string(),
+                value: Value::from("b"),
+            },
+            Change::Added {
+                path: "items.0".to_string(),
+                value: Value::from("x"),
+            },
+            Change::Changed {
+                path: "items.1".to_string(),
+                old: Value::from("c"),
+                new: Value::from("C"),
+            },
+            Change::Added {
+                path: "items.3".to_string(),
+                value: Value::from("e"),
+            },
+            Change::Moved {
+                path: "items".to_string(),
+                from: 0,
+                to: 4,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_diff_concurrent_branches() {
+    let tree = setup_tree();
+    let base = add_data_to_subtree(&tree, "data", &[("title", "draft")]);
+
+    let op = tree.new_operation_with_tips([base.clone()]).unwrap();
+    op.get_subtree::<Dict>("data")
+        .unwrap()
+        .set("title", "left")
+        .unwrap();
+    let left = op.commit().unwrap();
+
+    let op = tree.new_operation_with_tips([base]).unwrap();
+    op.get_subtree::<Dict>("notes")
+        .unwrap()
+        .set("text", "right")
+        .unwrap();
+    let right = op.commit().unwrap();
+
+    // Only the subtrees each branch touched differ
+    let diff = tree.diff([left.clone()], [right.clone()]).unwrap();
+    let names: Vec<&str> = diff.subtrees().map(|(name, _)| name).collect();
+    assert_eq!(names, vec!["data", "notes"]);
+
+    // Merging the right branch into the left only adds the right's changes
+    let diff = tree.diff([left.clone()], [left, right]).unwrap();
+    assert!(diff.subtree("data").is_none());
+    assert_eq!(
+        changes(&diff, "notes"),
+        [Change::Added {
+            path: "text".to_string(),
+            value: Value::from("right"),
+        }]
+    );
+}
+
+#[test]
+fn test_diff_opaque_subtree() {
+    let tree = setup_tree();
+    let op = tree.new_operation().unwrap();
+    op.get_subtree::<BlobStore>("blobs")
+        .unwrap()
+        .put(b"first")
+        .unwrap();
+    let v1 = op.commit().unwrap();
+
+    let op = tree.new_operation().unwrap();
+    op.get_subtree::<BlobStore>("blobs")
+        .unwrap()
+        .put(b"second")
+        .unwrap();
+    let v2 = op.commit().unwrap();
+
+    let diff = tree.diff([v1], [v2.clone()]).unwrap();
+    assert_eq!(diff.subtree("blobs"), Some(&SubtreeDiff::Opaque));
+
+    // Diffs need at least one tip on each side
+    assert!(tree.diff([], [v2]).is_err());
+}
@@ -0,0 +1,95 @@
+//! Ephemeral subtree tests
+//!
+//! Tests for `Tree::mark_ephemeral`: writes to ephemeral subtrees are read
+//! through the usual `Dict` API but never stored in entries, and are shared by
+//! the trees loaded from the same `BaseDB`.
+
+use crate::helpers::*;
+use eidetica::Tree;
+use eidetica::subtree::Dict;
+
+const KEY: &str = "key";
+
+fn get(tree: &Tree, subtree: &str, key: &str) -> Option<String> {
+    let dict = tree.get_subtree_viewer::<Dict>(subtree).unwrap();
+    dict.get_string(key).ok()
+}
+
+#[test]
+fn test_ephemeral_writes_create_no_entries() {
+    let (db, tree) = setup_db_and_tree_with_key(KEY);
+    tree.mark_ephemeral("cursors").unwrap();
+    assert!(tree.is_ephemeral("cursors").unwrap());
+    assert!(!tree.is_ephemeral("notes").unwrap());
+    assert_eq!(
+        tree.ephemeral_subtrees().unwrap(),
+        vec!["cursors".to_string()]
+    );
+    let tips = tree.get_tips().unwrap();
+    let entries = tree.get_all_entries().unwrap().len();
+
+    let id = commit_dict_value(&tree, "cursors", "alice", "10,20");
+    commit_dict_value(&tree, "cursors", "alice", "11,20");
+    commit_dict_value(&tree, "cursors", "bob", "3,4");
+    assert!(tips.contains(&id));
+    assert_eq!(tree.get_tips().unwrap(), tips);
+    assert_eq!(tree.get_all_entries().unwrap().len(), entries);
+
+    assert_eq!(get(&tree, "cursors", "alice").as_deref(), Some("11,20"));
+    let dict = tree.get_subtree_viewer::<Dict>("cursors").unwrap();
+    assert_eq!(dict.get_all().unwrap().as_hashmap().len(), 2);
+
+    // Deletes are ephemeral too
+    let op = tree.new_operation().unwrap();
+    op.get_subtree::<Dict>("cursors")
+        .unwrap()
+        .delete("bob")
+        .unwrap();
+    op.commit().unwrap();
+    assert_eq!(get(&tree, "cursors", "bob"), None);
+
+    // Trees loaded from the same database share the state, others do not
+    let loaded = db.load_tree(tree.root_id()).unwrap();
+    assert_eq!(get(&loaded, "cursors", "alice").as_deref(), Some("11,20"));
+    assert!(db.ephemeral_state().clear(tree.root_id()));
+    assert_eq!(get(&tree, "cursors", "alice"), None);
+}
+
+#[test]
+fn test_mixed_commit() {
+    let (_db, tree) = setup_db_and_tree_with_key(KEY);
+    tree.mark_ephemeral("cursors").unwrap();
+    let op = tree.new_operation().unwrap();
+    op.get_subtree::<Dict>("notes")
+        .unwrap()
+        .set("title", "hello")
+        .unwrap();
+    op.get_subtree::<Dict>("cursors")
+        .unwrap()
+        .set("alice", "1,1")
+        .unwrap();
+    let id = op.commit().unwrap();
+
+    // The entry only holds the persistent subtree
+    let entry = tree.get_entry(&id).unwrap();
+    assert!(entry.in_subtree("notes"));
+    assert!(!entry.in_subtree("cursors"));
+    assert_eq!(get(&tree, "notes", "title").as_deref(), Some("hello"));
+    assert_eq!(get(&tree, "cursors", "alice").as_deref(), Some("1,1"));
+}
+
+#[test]
+fn test_history_before_marking_is_kept() {
+    let (_db, tree) = setup_db_and_tree_with_key(KEY);
+    commit_dict_value(&tree, "presence", "alice", "online");
+    commit_dict_value(&tree, "presence", "bob", "online");
+
+    tree.mark_ephemeral("presence").unwrap();
+    let tips = tree.get_tips().unwrap();
+    commit_dict_value(&tree, "presence", "bob", "away");
+    assert_eq!(tree.get_tips().unwrap(), tips);
+    assert_eq!(get(&tree, "presence", "alice").as_deref(), Some("online"));
+    assert_eq!(get(&tree, "presence", "bob").as_deref(), Some("away"));
+
+    assert!(tree.mark_ephemeral("_settings").is_err());
+}
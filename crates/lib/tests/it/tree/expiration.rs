@@ -0,0 +1,216 @@
+//! Expiration tests
+//!
+//! Tests for ephemeral trees: `Tree::set_max_age`, `Tree::expire` removing old
+//! entries behind a checkpoint of the data that has not expired, removal of
+//! idle trees, and expiring all trees through `BaseDB` and `Maintenance`.
+
+use crate::helpers::commit_dict_value;
+use eidetica::Tree;
+use eidetica::backend::Database;
+use eidetica::backend::database::InMemory;
+use eidetica::basedb::{BaseDB, Maintenance};
+use eidetica::entry::ID;
+use eidetica::subtree::Dict;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+const KEY: &str = "key";
+const MAX_AGE: Duration = Duration::from_millis(200);
+
+fn setup(backend: Box<dyn Database>) -> (BaseDB, Tree) {
+    let db = BaseDB::new(backend);
+    db.add_private_key(KEY).unwrap();
+    let tree = db.new_tree_default(KEY).unwrap();
+    (db, tree)
+}
+
+/// Writes entries, lets them expire, then writes more on top.
+/// Returns the expired entries and the live ones.
+fn setup_aged(tree: &Tree) -> (Vec<ID>, Vec<ID>) {
+    tree.set_max_age(Some(MAX_AGE)).unwrap();
+    let old = vec![
+        commit_dict_value(tree, "data", "old", "1"),
+        commit_dict_value(tree, "data", "shared", "old"),
+    ];
+    sleep(MAX_AGE + Duration::from_millis(100));
+    let live = vec![
+        commit_dict_value(tree, "data", "shared", "new"),
+        commit_dict_value(tree, "data", "new", "1"),
+    ];
+    (old, live)
+}
+
+fn assert_expired(tree: &Tree, old: &[ID], live: &[ID]) {
+    let tip = live.last().unwrap().clone();
+    let stats = tree.expire().unwrap();
+    assert!(!stats.tree_removed);
+    assert_eq!(stats.entries_removed, old.len());
+    let checkpoint = stats.checkpoint.unwrap();
+    assert!(
+        tree.get_entry(&checkpoint)
+            .unwrap()
+            .is_checkpoint_of("data")
+    );
+
+    for id in old {
+        assert!(tree.get_entry(id).is_err());
+    }
+    for id in live {
+        assert!(tree.get_entry(id).is_ok());
+    }
+    assert!(tree.get_entry(tree.root_id()).is_ok());
+
+    // The checkpoint builds on the old tips, and kept entries do not become tips again
+    assert!(
+        tree.get_entry(&checkpoint)
+            .unwrap()
+            .parents()
+            .unwrap()
+            .contains(&tip)
+    );
+    assert_eq!(tree.get_tips().unwrap(), vec![checkpoint.clone()]);
+    assert_eq!(tree.subtree_tips("data").unwrap(), vec![checkpoint]);
+
+    let data = tree.get_subtree_viewer::<Dict>("data").unwrap();
+    assert!(data.get("old").is_err());
+    assert_eq!(data.get_string("shared").unwrap(), "new");
+    assert_eq!(data.get_string("new").unwrap(), "1");
+
+    // The tree keeps accepting writes, and stays ephemeral
+    commit_dict_value(tree, "data", "after", "1");
+    let data = tree.get_subtree_viewer::<Dict>("data").unwrap();
+    assert_eq!(data.get_string("new").unwrap(), "1");
+    assert_eq!(data.get_string("after").unwrap(), "1");
+    assert_eq!(tree.max_age().unwrap(), Some(MAX_AGE));
+}
+
+#[test]
+fn test_max_age_setting() {
+    let (_db, tree) = setup(Box::new(InMemory::new()));
+    assert_eq!(tree.max_age().unwrap(), None);
+
+    let entry = tree.set_max_age(Some(Duration::from_secs(3600))).unwrap();
+    assert!(tree.get_entry(&entry).unwrap().in_subtree("_settings"));
+    assert_eq!(tree.max_age().unwrap(), Some(Duration::from_secs(3600)));
+
+    tree.set_max_age(None).unwrap();
+    assert_eq!(tree.max_age().unwrap(), None);
+}
+
+#[test]
+fn test_expire_removes_old_entries() {
+    let (_db, tree) = setup(Box::new(InMemory::new()));
+    let (old, live) = setup_aged(&tree);
+    assert_expired(&tree, &old, &live);
+}
+
+#[test]
+fn test_expire_without_expired_entries() {
+    let (_db, tree) = setup(Box::new(InMemory::new()));
+
+    // Not ephemeral
+    commit_dict_value(&tree, "data", "key", "value");
+    assert_eq!(tree.expire().unwrap(), Default::default());
+
+    // Ephemeral, but nothing is old enough
+    tree.set_max_age(Some(Duration::from_secs(3600))).unwrap();
+    let tips = tree.get_tips().unwrap();
+    let entries = tree.get_all_entries().unwrap().len();
+    assert_eq!(tree.expire().unwrap(), Default::default());
+    assert_eq!(tree.get_tips().unwrap(), tips);
+    assert_eq!(tree.get_all_entries().unwrap().len(), entries);
+}
+
+#[test]
+fn test_expire_repeatedly() {
+    let (_db, tree) = setup(Box::new(InMemory::new()));
+    let (old, live) = setup_aged(&tree);
+    let first = tree.expire().unwrap().checkpoint.unwrap();
+    assert!(old.iter().all(|id| tree.get_entry(id).is_err()));
+
+    // The first checkpoint expires along with the entries written before it
+    sleep(MAX_AGE + Duration::from_millis(100));
+    commit_dict_value(&tree, "data", "latest", "1");
+    let stats = tree.expire().unwrap();
+    assert!(!stats.tree_removed);
+    assert_eq!(stats.entries_removed, live.len() + 1);
+    assert!(tree.get_entry(&first).is_err());
+
+    let data = tree.get_subtree_viewer::<Dict>("data").unwrap();
+    assert!(data.get("shared").is_err());
+    assert!(data.get("new").is_err());
+    assert_eq!(data.get_string("latest").unwrap(), "1");
+}
+
+#[test]
+fn test_expire_removes_idle_tree() {
+    let (db, tree) = setup(Box::new(InMemory::new()));
+    tree.set_max_age(Some(MAX_AGE)).unwrap();
+    commit_dict_value(&tree, "data", "key", "value");
+    let other = db.new_tree_default(KEY).unwrap();
+    sleep(MAX_AGE + Duration::from_millis(100));
+
+    let stats = tree.expire().unwrap();
+    assert!(stats.tree_removed);
+    assert_eq!(stats.checkpoint, None);
+    assert_eq!(stats.entries_removed, 3);
+    assert!(db.load_tree(tree.root_id()).is_err());
+    let roots: Vec<ID> = db
+        .all_trees()
+        .unwrap()
+        .iter()
+        .map(|tree| tree.root_id().clone())
+        .collect();
+    assert_eq!(roots, vec![other.root_id().clone()]);
+}
+
+#[test]
+fn test_expire_trees() {
+    let (db, idle) = setup(Box::new(InMemory::new()));
+    idle.set_max_age(Some(MAX_AGE)).unwrap();
+    let persistent = db.new_tree_default(KEY).unwrap();
+    let active = db.new_tree_default(KEY).unwrap();
+    let (old, live) = setup_aged(&active);
+
+    // Trees loaded from the database have no default key; the given key signs
+    let report = db.expire_trees(KEY).unwrap();
+    assert_eq!(report.len(), 2);
+    for (root, result) in report {
+        let stats = result.unwrap();
+        if root == *idle.root_id() {
+            assert!(stats.tree_removed);
+        } else {
+            assert_eq!(root, *active.root_id());
+            assert_eq!(stats.entries_removed, old.len());
+        }
+    }
+    assert!(db.load_tree(idle.root_id()).is_err());
+    assert!(db.load_tree(persistent.root_id()).is_ok());
+    assert!(live.iter().all(|id| active.get_entry(id).is_ok()));
+}
+
+#[test]
+fn test_maintenance_expires_in_background() {
+    let (db, tree) = setup(Box::new(InMemory::new()));
+    tree.set_max_age(Some(MAX_AGE)).unwrap();
+    let maintenance = Maintenance::new(&db, KEY, Duration::from_millis(50));
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while db.load_tree(tree.root_id()).is_ok() {
+        assert!(Instant::now() < deadline, "tree was not expired");
+        sleep(Duration::from_millis(20));
+    }
+    assert!(maintenance.take_error().is_none());
+    assert!(maintenance.run_now().unwrap().is_empty());
+    maintenance.shutdown();
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn test_expire_sqlite() {
+    use eidetica::backend::database::Sqlite;
+
+    let (_db, tree) = setup(Box::new(Sqlite::open_in_memory().unwrap()));
+    let (old, live) = setup_aged(&tree);
+    assert_expired(&tree, &old, &live);
+}
@@ -0,0 +1,104 @@
+//! Inclusion proof tests
+//!
+//! This module tests building proofs that an entry descends from a tree's
+//! root and checking them without a database.
+
+use crate::helpers::*;
+use eidetica::entry::{ID, InclusionProof, verify_inclusion};
+
+#[test]
+fn test_prove_and_verify_inclusion() {
+    let tree = setup_tree();
+    let first = commit_dict_value(&tree, "data", "first", "value");
+    let second = commit_dict_value(&tree, "data", "second", "value");
+    let third = commit_dict_value(&tree, "data", "third", "value");
+
+    let proof = tree.prove_inclusion(&third).unwrap();
+    assert_eq!(proof.entry, third);
+    assert_eq!(proof.depth(), 3);
+    let chain: Vec<ID> = proof.chain.iter().map(|entry| entry.id()).collect();
+    assert_eq!(chain, vec![third, second, first, tree.root_id().clone()]);
+    assert!(verify_inclusion(&proof, tree.root_id()));
+
+    // The root entry proves its own inclusion
+    let proof = tree.prove_inclusion(tree.root_id()).unwrap();
+    assert_eq!(proof.depth(), 0);
+    assert!(verify_inclusion(&proof, tree.root_id()));
+}
+
+#[test]
+fn test_proof_takes_shortest_path() {
+    let tree = setup_tree();
+    let base = commit_dict_value(&tree, "data", "base", "value");
+    let long = commit_dict_value_on(&tree, std::slice::from_ref(&base), "data", "long", "value");
+    let long = commit_dict_value_on(&tree, &[long], "data", "longer", "value");
+    let merge = commit_dict_value_on(&tree, &[long, base.clone()], "data", "merge", "value");
+
+    let proof = tree.prove_inclusion(&merge).unwrap();
+    assert_eq!(proof.depth(), 2);
+    assert_eq!(proof.chain[1].id(), base);
+    assert!(verify_inclusion(&proof, tree.root_id()));
+}
+
+#[test]
+fn test_tampered_proofs_fail() {
+    let tree = setup_tree();
+    let first = commit_dict_value(&tree, "data", "first", "value");
+    let other = commit_dict_value_on(
+        &tree,
+        std::slice::from_ref(tree.root_id()),
+        "data",
+        "other",
+        "value",
+    );
+    let second = commit_dict_value_on(&tree, &[first], "data", "second", "value");
+    let proof = tree.prove_inclusion(&second).unwrap();
+
+    // Against another root
+    let other_tree = setup_tree();
+    assert!(!verify_inclusion(&proof, other_tree.root_id()));
+
+    // Claiming another entry
+    let mut claimed = proof.clone();
+    claimed.entry = other.clone();
+    assert!(!verify_inclusion(&claimed, tree.root_id()));
+
+    // Replacing a link in the chain
+    let mut replaced = proof.clone();
+    replaced.chain[1] = tree.get_entry(&other).unwrap();
+    assert!(!verify_inclusion(&replaced, tree.root_id()));
+
+    // Stopping short of the root
+    let mut truncated = proof.clone();
+    truncated.chain.pop();
+    assert!(!verify_inclusion(&truncated, tree.root_id()));
+
+    let empty = InclusionProof {
+        entry: second,
+        chain: Vec::new(),
+    };
+    assert!(!verify_inclusion(&empty, tree.root_id()));
+}
+
+#[test]
+fn test_proof_survives_serialization() {
+    let tree = setup_tree();
+    let id = commit_dict_value(&tree, "data", "key", "value");
+    let proof = tree.prove_inclusion(&id).unwrap();
+
+    let json = serde_json::to_string(&proof).unwrap();
+    let decoded: InclusionProof = serde_json::from_str(&json).unwrap();
+    assert_eq!(decoded, proof);
+    assert!(verify_inclusion(&decoded, tree.root_id()));
+}
+
+#[test]
+fn test_prove_inclusion_rejects_foreign_entries() {
+    let db = setup_db_with_key("key");
+    let tree = db.new_tree_default("key").unwrap();
+    let other = db.new_tree_default("key").unwrap();
+    let id = commit_dict_value(&other, "data", "key", "value");
+
+    assert!(tree.prove_inclusion(&id).is_err());
+    assert!(tree.prove_inclusion("missing").is_err());
+}
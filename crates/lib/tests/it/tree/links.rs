@@ -0,0 +1,118 @@
+//! Entry link tests
+//!
+//! Tests for `Value::Link` references between entries: storing links in a Dict,
+//! resolving them within a tree and across trees, viewing the linked version of
+//! a subtree, and finding links that no longer resolve.
+
+use crate::helpers::*;
+use eidetica::crdt::map::Value;
+use eidetica::entry::ID;
+use eidetica::subtree::Dict;
+
+const KEY: &str = "link_key";
+
+/// Commits a value to the "docs" subtree of a tree and returns the entry ID.
+fn write_doc(tree: &eidetica::Tree, key: &str, value: &str) -> ID {
+    let op = tree.new_operation().unwrap();
+    op.get_subtree::<Dict>("docs")
+        .unwrap()
+        .set_string(key, value)
+        .unwrap();
+    op.commit().unwrap()
+}
+
+#[test]
+fn test_link_resolves_to_linked_version() {
+    let (_db, tree) = setup_db_and_tree_with_key(KEY);
+    let v1 = write_doc(&tree, "title", "First draft");
+    write_doc(&tree, "title", "Final");
+
+    let op = tree.new_operation().unwrap();
+    op.get_subtree::<Dict>("refs")
+        .unwrap()
+        .set_link("draft", &v1)
+        .unwrap();
+    op.commit().unwrap();
+
+    let refs = tree.get_subtree_viewer::<Dict>("refs").unwrap();
+    let link = refs.get_link("draft").unwrap();
+    assert_eq!(link, v1);
+    assert_eq!(tree.resolve_link(&link).unwrap().id(), v1);
+
+    // The linked view shows the subtree as of the linked entry
+    let draft = tree.linked_subtree::<Dict>(&link, "docs").unwrap();
+    assert_eq!(draft.get_string("title").unwrap(), "First draft");
+    let current = tree.get_subtree_viewer::<Dict>("docs").unwrap();
+    assert_eq!(current.get_string("title").unwrap(), "Final");
+}
+
+#[test]
+fn test_get_link_type_mismatch() {
+    let (_db, tree) = setup_db_and_tree_with_key(KEY);
+    write_doc(&tree, "title", "not a link");
+
+    let docs = tree.get_subtree_viewer::<Dict>("docs").unwrap();
+    let err = docs.get_link("title").unwrap_err();
+    assert!(err.is_type_error());
+}
+
+#[test]
+fn test_link_across_trees() {
+    let (db, people) = setup_db_and_tree_with_key(KEY);
+    let projects = db.new_tree_default(KEY).unwrap();
+
+    let alice = {
+        let op = people.new_operation().unwrap();
+        op.get_subtree::<Dict>("people")
+            .unwrap()
+            .set_string("alice", "Alice")
+            .unwrap();
+        op.commit().unwrap()
+    };
+    let op = projects.new_operation().unwrap();
+    op.get_subtree::<Dict>("projects")
+        .unwrap()
+        .set_link("owner", &alice)
+        .unwrap();
+    op.commit().unwrap();
+
+    let link = projects
+        .get_subtree_viewer::<Dict>("projects")
+        .unwrap()
+        .get_link("owner")
+        .unwrap();
+
+    // The link points outside the projects tree
+    assert!(projects.resolve_link(&link).is_err());
+
+    // ...but resolves through the database, to the tree that holds it
+    assert_eq!(db.resolve_link(&link).unwrap().id(), alice);
+    let linked_tree = db.load_linked_tree(&link).unwrap();
+    assert_eq!(linked_tree.root_id(), people.root_id());
+    let owners = linked_tree.linked_subtree::<Dict>(&link, "people").unwrap();
+    assert_eq!(owners.get_string("alice").unwrap(), "Alice");
+
+    // A link to a tree's root entry loads that tree
+    let root_tree = db.load_linked_tree(people.root_id()).unwrap();
+    assert_eq!(root_tree.root_id(), people.root_id());
+}
+
+#[test]
+fn test_broken_links() {
+    let (_db, tree) = setup_db_and_tree_with_key(KEY);
+    let valid = write_doc(&tree, "title", "Exists");
+    let missing =
+        ID::from("sha256:0000000000000000000000000000000000000000000000000000000000000000");
+
+    let op = tree.new_operation().unwrap();
+    let refs = op.get_subtree::<Dict>("refs").unwrap();
+    refs.set_link("valid", &valid).unwrap();
+    refs.set_link("missing", &missing).unwrap();
+    refs.set_at_path(["nested", "also_missing"], Value::Link(missing.clone()))
+        .unwrap();
+    op.commit().unwrap();
+
+    assert!(tree.resolve_link(&missing).unwrap_err().is_not_found());
+    assert_eq!(tree.broken_links("refs").unwrap(), vec![missing]);
+    assert!(tree.broken_links("docs").unwrap().is_empty());
+}
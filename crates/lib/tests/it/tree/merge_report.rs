@@ -0,0 +1,125 @@
+//! Merge report tests
+//!
+//! Tests for `Tree::merge_report`: listing the values two concurrent branches
+//! both changed, which merging keeps only one of, and leaving out changes that
+//! merge without losing a write.
+
+use super::helpers::*;
+use crate::helpers::*;
+use eidetica::Tree;
+use eidetica::crdt::Map;
+use eidetica::crdt::map::{List, Value};
+use eidetica::entry::ID;
+use eidetica::subtree::Dict;
+
+/// Commits `f` on top of `base` and returns the new entry.
+fn branch(tree: &Tree, base: &ID, f: impl FnOnce(&Dict)) -> ID {
+    let op = tree.new_operation_with_tips([base.clone()]).unwrap();
+    f(&op.get_subtree::<Dict>("data").unwrap());
+    op.commit().unwrap()
+}
+
+#[test]
+fn test_merge_report_lists_conflicting_writes() {
+    let tree = setup_tree();
+    let base = add_data_to_subtree(&tree, "data", &[("title", "draft"), ("status", "open")]);
+
+    let left = branch(&tree, &base, |data| {
+        data.set("title", "left").unwrap();
+        data.set("status", "done").unwrap();
+    });
+    let right = branch(&tree, &base, |data| {
+        data.set("title", "right").unwrap();
+        // The same change on both branches is not a conflict
+        data.set("status", "done").unwrap();
+    });
+
+    let report = tree.merge_report(&left, &right).unwrap();
+    assert!(!report.is_clean());
+    let [conflict] = report.conflicts() else {
+        panic!("expected one conflict, got {:?}", report.conflicts());
+    };
+    assert_eq!(conflict.subtree, "data");
+    assert_eq!(conflict.path, "title");
+    assert_eq!(conflict.base, Value::from("draft"));
+    assert_eq!(conflict.ours, Value::from("left"));
+    assert_eq!(conflict.theirs, Value::from("right"));
+
+    // The report names the value the merged tree actually holds
+    let op = tree.new_operation_with_tips([left, right]).unwrap();
+    let merged = op
+        .get_subtree::<Dict>("data")
+        .unwrap()
+        .get("title")
+        .unwrap();
+    assert_eq!(conflict.merged, merged);
+    assert!(conflict.ours_kept() != conflict.theirs_kept());
+}
+
+#[test]
+fn test_merge_report_delete_and_nested_keys() {
+    let tree = setup_tree();
+    let op = tree.new_operation().unwrap();
+    let data = op.get_subtree::<Dict>("data").unwrap();
+    data.set("note", "keep me").unwrap();
+    data.set_node("owner", Map::new().with_text("name", "alice"))
+        .unwrap();
+    let base = op.commit().unwrap();
+
+    let left = branch(&tree, &base, |data| {
+        data.delete("note").unwrap();
+        let mut owner = data.get_node("owner").unwrap();
+        owner.set("name", "bob");
+        data.set_node("owner", owner).unwrap();
+    });
+    let right = branch(&tree, &base, |data| {
+        data.set("note", "edited").unwrap();
+        let mut owner = data.get_node("owner").unwrap();
+        owner.set("name", "carol");
+        data.set_node("owner", owner).unwrap();
+    });
+
+    let report = tree.merge_report(&left, &right).unwrap();
+    let conflicts: Vec<_> = report
+        .conflicts()
+        .iter()
+        .map(|c| (c.path.as_str(), c.ours.clone(), c.theirs.clone()))
+        .collect();
+    assert_eq!(
+        conflicts,
+        vec![
+            ("note", Value::Deleted, Value::from("edited")),
+            ("owner.name", Value::from("bob"), Value::from("carol")),
+        ]
+    );
+}
+
+#[test]
+fn test_merge_report_clean_merges() {
+    let tree = setup_tree();
+    let op = tree.new_operation().unwrap();
+    let data = op.get_subtree::<Dict>("data").unwrap();
+    data.set("a", "1").unwrap();
+    data.set_list("items", List::new()).unwrap();
+    let base = op.commit().unwrap();
+
+    // Different keys, and items pushed to the same list, merge without loss
+    let left = branch(&tree, &base, |data| {
+        data.set("a", "2").unwrap();
+        let mut items = data.get_list("items").unwrap();
+        items.push("x");
+        data.set_list("items", items).unwrap();
+    });
+    let right = branch(&tree, &base, |data| {
+        data.set("b", "3").unwrap();
+        let mut items = data.get_list("items").unwrap();
+        items.push("y");
+        data.set_list("items", items).unwrap();
+    });
+    assert!(tree.merge_report(&left, &right).unwrap().is_clean());
+
+    // A branch that builds on the other has nothing to lose
+    let later = branch(&tree, &left, |data| data.set("a", "4").unwrap());
+    assert!(tree.merge_report(&left, &later).unwrap().is_clean());
+    assert!(tree.merge_report(&later, &later).unwrap().is_clean());
+}
@@ -0,0 +1,125 @@
+//! Merge window tests
+//!
+//! This module tests automatic batching of tips into intermediate merge entries
+//! when a tree accumulates more concurrent tips than its merge window allows.
+
+use crate::helpers::*;
+use eidetica::Tree;
+use eidetica::entry::ID;
+use eidetica::subtree::Dict;
+
+/// Creates `count` concurrent tips, each setting its own key in "data".
+fn create_concurrent_tips(tree: &Tree, count: usize) -> Vec<ID> {
+    // Seed the subtree so the concurrent entries share a common ancestor in it
+    let op = tree.new_operation().unwrap();
+    op.get_subtree::<Dict>("data")
+        .unwrap()
+        .set("seed", "value")
+        .unwrap();
+    let seed = op.commit().unwrap();
+
+    (0..count)
+        .map(|i| {
+            let op = tree.new_operation_with_tips([seed.clone()]).unwrap();
+            op.get_subtree::<Dict>("data")
+                .unwrap()
+                .set(format!("key{i}"), format!("value{i}"))
+                .unwrap();
+            op.commit().unwrap()
+        })
+        .collect()
+}
+
+#[test]
+fn test_merge_window_limits_parent_count() {
+    let mut tree = setup_tree();
+    create_concurrent_tips(&tree, 20);
+    assert_eq!(tree.get_tips().unwrap().len(), 20);
+
+    tree.set_merge_window(4);
+    let op = tree.new_operation().unwrap();
+    op.get_subtree::<Dict>("data")
+        .unwrap()
+        .set("final", "value")
+        .unwrap();
+    let id = op.commit().unwrap();
+
+    assert!(tree.get_entry(&id).unwrap().parents().unwrap().len() <= 4);
+    assert_eq!(tree.get_tips().unwrap(), vec![id]);
+    for entry in tree.get_all_entries().unwrap() {
+        assert!(entry.parents().unwrap().len() <= 4);
+    }
+
+    // All concurrent changes are still visible through the merge entries
+    let data = tree.get_subtree_viewer::<Dict>("data").unwrap();
+    assert_eq!(data.get_string("final").unwrap(), "value");
+    for i in 0..20 {
+        assert_eq!(
+            data.get_string(format!("key{i}")).unwrap(),
+            format!("value{i}")
+        );
+    }
+}
+
+#[test]
+fn test_merge_window_not_applied_below_limit() {
+    let mut tree = setup_tree();
+    let tips = create_concurrent_tips(&tree, 3);
+    let entry_count = tree.get_all_entries().unwrap().len();
+
+    tree.set_merge_window(8);
+    let id = tree.new_operation().unwrap().commit().unwrap();
+
+    let mut parents = tree.get_entry(&id).unwrap().parents().unwrap();
+    let mut expected = tips;
+    parents.sort();
+    expected.sort();
+    assert_eq!(parents, expected);
+    assert_eq!(tree.get_all_entries().unwrap().len(), entry_count + 1);
+}
+
+#[test]
+fn test_merge_window_with_authenticated_operation() {
+    let (db, tree) = setup_db_and_tree_with_key("merge_key");
+    create_concurrent_tips(&tree, 9);
+
+    // A loaded tree has no default key, so merges use the operation's key
+    let mut loaded = db.load_tree(tree.root_id()).unwrap();
+    loaded.set_merge_window(3);
+    let op = loaded.new_authenticated_operation("merge_key").unwrap();
+    let id = op.commit().unwrap();
+    assert_eq!(loaded.get_entry(&id).unwrap().parents().unwrap().len(), 3);
+}
+
+#[test]
+fn test_merge_tips_directly() {
+    let tree = setup_tree();
+    create_concurrent_tips(&tree, 10);
+
+    let tips = tree.merge_tips(3, "test_key").unwrap();
+    assert!(tips.len() <= 3);
+    let mut current = tree.get_tips().unwrap();
+    let mut expected = tips;
+    current.sort();
+    expected.sort();
+    assert_eq!(current, expected);
+
+    // Merging again with enough room is a no-op
+    assert_eq!(tree.merge_tips(3, "test_key").unwrap(), expected);
+}
+
+#[test]
+fn test_merge_window_settings() {
+    let mut tree = setup_tree();
+    assert_eq!(tree.merge_window(), None);
+
+    tree.set_merge_window(8);
+    assert_eq!(tree.merge_window(), Some(8));
+
+    // A window must allow at least two parents to make progress
+    tree.set_merge_window(1);
+    assert_eq!(tree.merge_window(), Some(2));
+
+    tree.clear_merge_window();
+    assert_eq!(tree.merge_window(), None);
+}
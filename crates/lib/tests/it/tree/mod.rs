@@ -7,13 +7,47 @@
 //! ## Test Organization
 //!
 //! - `core_operations`: Basic tree operations, entry management, tips handling
+//! - `ephemeral`: Ephemeral subtrees held in memory instead of entries
+//! - `diff`: Comparing the state of a tree at two sets of tips
+//! - `expiration`: Ephemeral trees whose old entries, and eventually the whole tree, expire
 //! - `api_methods`: Tree API methods for entry retrieval, authentication, validation
+//! - `checkpoints`: Checkpoint entries storing materialized subtree state
+//! - `inclusion_proofs`: Proving and verifying that entries descend from the root
+//! - `links`: Links between entries, within a tree and across trees
 //! - `merge_algorithms`: Parent-aware merging, LCA computation, complex DAG scenarios
+//! - `merge_report`: Listing the values two concurrent branches both changed
+//! - `merge_window`: Automatic merging of excess tips in batches
+//! - `quota`: Per-tree limits on entry size, subtree size and entry count
+//! - `read_only`: Read-only tree handles and viewing trees without private keys
+//! - `repair`: Comparing replicas and planning entries to copy or quarantine
 //! - `settings_metadata`: Settings tracking, metadata management, tips propagation
+//! - `sparse`: Loading only some subtrees of a tree and committing through them
+//! - `subtree_tips`: Querying and merging the tips of a single subtree
+//! - `time_travel`: Viewing subtrees as of historical entries and tips
+//! - `timestamps`: Hybrid logical clock timestamps and latest-edit ordering
+//! - `transactions`: Closure-based transactions retried on concurrent changes
+//! - `typed_settings`: Reading and updating settings through `TreeSettings`
 //! - `helpers`: Comprehensive helper functions for tree testing
 
 mod api_methods;
+mod checkpoints;
 mod core_operations;
+mod diff;
+mod ephemeral;
+mod expiration;
 mod helpers;
+mod inclusion_proofs;
+mod links;
 mod merge_algorithms;
+mod merge_report;
+mod merge_window;
+mod quota;
+mod read_only;
+mod repair;
 mod settings_metadata;
+mod sparse;
+mod subtree_tips;
+mod time_travel;
+mod timestamps;
+mod transactions;
+mod typed_settings;
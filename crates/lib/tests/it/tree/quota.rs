@@ -0,0 +1,97 @@
+//! Quota tests
+//!
+//! Tests for `Tree::set_quota`: commits that break the entry size, subtree
+//! size or entry count limits are rejected, and settings changes stay possible
+//! whatever the limits.
+
+use crate::helpers::*;
+use eidetica::Tree;
+use eidetica::basedb::TreeQuota;
+use eidetica::entry::ID;
+use eidetica::subtree::Dict;
+
+const KEY: &str = "key";
+
+fn set(tree: &Tree, key: &str, value: &str) -> eidetica::Result<ID> {
+    let op = tree.new_operation()?;
+    op.get_subtree::<Dict>("data")?.set(key, value)?;
+    op.commit()
+}
+
+/// Asserts that `result` failed on the quota named `quota`.
+fn assert_quota_exceeded(result: eidetica::Result<ID>, quota: &str) {
+    match result {
+        Err(eidetica::Error::AtomicOp(err)) => {
+            assert!(err.is_quota_exceeded(), "unexpected error: {err:?}");
+            assert!(err.to_string().contains(quota), "unexpected error: {err}");
+        }
+        other => panic!("expected a quota error, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_quota_setting() {
+    let (_db, tree) = setup_db_and_tree_with_key(KEY);
+    assert!(tree.quota().unwrap().is_unlimited());
+
+    let quota = TreeQuota {
+        max_entry_size: Some(4096),
+        max_subtree_size: None,
+        max_entries: Some(100),
+    };
+    tree.set_quota(quota).unwrap();
+    assert_eq!(tree.quota().unwrap(), quota);
+    assert_eq!(tree.read_only().quota().unwrap(), quota);
+
+    tree.set_quota(TreeQuota::default()).unwrap();
+    assert!(tree.quota().unwrap().is_unlimited());
+}
+
+#[test]
+fn test_size_quotas() {
+    let (_db, tree) = setup_db_and_tree_with_key(KEY);
+    tree.set_quota(TreeQuota {
+        max_subtree_size: Some(100),
+        ..TreeQuota::default()
+    })
+    .unwrap();
+    set(&tree, "small", "value").unwrap();
+    assert_quota_exceeded(set(&tree, "large", &"x".repeat(200)), "max_subtree_size");
+
+    tree.set_quota(TreeQuota {
+        max_entry_size: Some(1000),
+        ..TreeQuota::default()
+    })
+    .unwrap();
+    set(&tree, "large", &"x".repeat(200)).unwrap();
+    assert_quota_exceeded(set(&tree, "huge", &"x".repeat(1000)), "max_entry_size");
+
+    // Rejected commits leave no trace
+    let data = tree.get_subtree_viewer::<Dict>("data").unwrap();
+    assert!(data.get("huge").is_err());
+    assert_eq!(data.get_string("large").unwrap(), "x".repeat(200));
+}
+
+#[test]
+fn test_entry_count_quota() {
+    let (_db, tree) = setup_db_and_tree_with_key(KEY);
+    // The root and this settings entry count towards the limit
+    tree.set_quota(TreeQuota {
+        max_entries: Some(4),
+        ..TreeQuota::default()
+    })
+    .unwrap();
+    set(&tree, "a", "1").unwrap();
+    set(&tree, "b", "2").unwrap();
+    assert_quota_exceeded(set(&tree, "c", "3"), "max_entries");
+    assert_eq!(tree.get_all_entries().unwrap().len(), 4);
+
+    // Settings can still be changed to make room
+    tree.set_quota(TreeQuota {
+        max_entries: Some(6),
+        ..TreeQuota::default()
+    })
+    .unwrap();
+    set(&tree, "c", "3").unwrap();
+    assert_quota_exceeded(set(&tree, "d", "4"), "max_entries");
+}
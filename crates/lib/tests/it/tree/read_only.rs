@@ -0,0 +1,89 @@
+//! Read-only tree handle tests
+//!
+//! This module tests `Tree::read_only` handles and reading trees through
+//! subtree viewers on databases that hold no private keys.
+
+use crate::helpers::*;
+use eidetica::backend::database::InMemory;
+use eidetica::basedb::BaseDB;
+use eidetica::subtree::Dict;
+use eidetica::{ReadOnlyTree, Tree};
+
+const KEY: &str = "key";
+
+/// Copies `tree` into a database with no private keys, standing in for a sync.
+fn keyless_copy(tree: &Tree) -> Tree {
+    let db = BaseDB::new(Box::new(InMemory::new()));
+    let mut bundle = Vec::new();
+    tree.export_bundle(&mut bundle).unwrap();
+    db.import_bundle(bundle.as_slice()).unwrap()
+}
+
+#[test]
+fn test_read_only_handle_reads() {
+    let tree = setup_tree_with_key(KEY);
+    commit_dict_value(&tree, "data", "name", "alice");
+    let reader: ReadOnlyTree = tree.read_only();
+
+    assert_eq!(reader.root_id(), tree.root_id());
+    assert_eq!(reader.get_tips().unwrap(), tree.get_tips().unwrap());
+    let data = reader.get_subtree_viewer::<Dict>("data").unwrap();
+    assert_eq!(data.get_string("name").unwrap(), "alice");
+    for id in reader.get_tips().unwrap() {
+        assert!(reader.verify_entry_signature(&id).unwrap());
+        assert_eq!(reader.get_entry(&id).unwrap().id(), id);
+    }
+
+    // The handle sees writes made through the tree after it was created
+    commit_dict_value(&tree, "data", "name", "bob");
+    let data = reader.get_subtree_viewer::<Dict>("data").unwrap();
+    assert_eq!(data.get_string("name").unwrap(), "bob");
+    assert!(reader.get_settings().unwrap().get("auth").is_ok());
+}
+
+#[test]
+fn test_viewers_need_no_private_key() {
+    let tree = setup_tree_with_key(KEY);
+    commit_dict_value(&tree, "data", "name", "alice");
+
+    let mut copy = keyless_copy(&tree);
+    assert!(copy.backend().get_private_key(KEY).unwrap().is_none());
+    copy.set_default_auth_key(KEY);
+    let data = copy.read_only().get_subtree_viewer::<Dict>("data").unwrap();
+    assert_eq!(data.get_string("name").unwrap(), "alice");
+
+    // Writing fails for want of the key, as it should
+    let op = copy.new_operation().unwrap();
+    op.get_subtree::<Dict>("data")
+        .unwrap()
+        .set("name", "mallory")
+        .unwrap();
+    assert!(op.commit().is_err());
+}
+
+#[test]
+fn test_viewers_do_not_merge_tips() {
+    let tree = setup_tree_with_key(KEY);
+    commit_dict_value(&tree, "data", "seed", "value");
+    let seed = tree.get_tips().unwrap();
+    for i in 0..6 {
+        let op = tree.new_operation_with_tips(&seed).unwrap();
+        op.get_subtree::<Dict>("data")
+            .unwrap()
+            .set(format!("key{i}"), "value")
+            .unwrap();
+        op.commit().unwrap();
+    }
+
+    // A merge window applies to writes only, so viewing neither commits nor
+    // needs the key that merges would be signed with
+    let mut copy = keyless_copy(&tree);
+    copy.set_default_auth_key(KEY);
+    copy.set_merge_window(2);
+    let data = copy.get_subtree_viewer::<Dict>("data").unwrap();
+    for i in 0..6 {
+        assert_eq!(data.get_string(format!("key{i}")).unwrap(), "value");
+    }
+    assert_eq!(copy.read_only().get_tips().unwrap().len(), 6);
+    assert!(copy.broken_links("data").unwrap().is_empty());
+}
@@ -0,0 +1,176 @@
+//! Replica repair tests
+//!
+//! Tests for `Tree::reconcile_with`: consistent replicas, entries missing on
+//! either side and copying them in plan order, and quarantining entries that
+//! failed verification, have invalid signatures, or have missing or
+//! quarantined parents.
+
+use crate::helpers::{commit_dict_value, setup_db_with_key};
+use eidetica::Tree;
+use eidetica::auth::crypto::sign_entry;
+use eidetica::auth::types::{SigInfo, SigKey};
+use eidetica::backend::VerificationStatus;
+use eidetica::backend::database::InMemory;
+use eidetica::basedb::{BaseDB, QuarantineReason, QuarantinedEntry, Replica};
+use eidetica::entry::{Entry, ID};
+use eidetica::subtree::Dict;
+
+const KEY: &str = "key";
+
+/// Creates a tree with one write, and a second replica of it in another
+/// database holding the same signing key.
+fn setup() -> (BaseDB, Tree, BaseDB, Tree) {
+    let local_db = setup_db_with_key(KEY);
+    let local = local_db.new_tree_default(KEY).unwrap();
+    commit_dict_value(&local, "data", "shared", "value");
+
+    let mut bundle = Vec::new();
+    local.export_bundle(&mut bundle).unwrap();
+    let other_db = BaseDB::new(Box::new(InMemory::new()));
+    let signing_key = local_db.backend().get_private_key(KEY).unwrap().unwrap();
+    other_db
+        .backend()
+        .store_private_key(KEY, signing_key)
+        .unwrap();
+    let mut other = other_db.import_bundle(bundle.as_slice()).unwrap();
+    other.set_default_auth_key(KEY);
+    (local_db, local, other_db, other)
+}
+
+/// Builds an entry in `tree` on top of `parent`, signed with `key_name` from `db`.
+fn signed_entry(db: &BaseDB, tree: &Tree, parent: ID, key_name: &str) -> Entry {
+    let signing_key = db.backend().get_private_key(key_name).unwrap().unwrap();
+    let mut entry = Entry::builder(tree.root_id().clone())
+        .add_parent(parent)
+        .set_subtree_data("data", r#"{"children":{"forged":{"Text":"yes"}}}"#)
+        .set_sig(SigInfo {
+            key: SigKey::Direct(key_name.to_string()),
+            sig: None,
+        })
+        .build();
+    entry.sig.sig = Some(sign_entry(&entry, &signing_key).unwrap());
+    entry
+}
+
+fn quarantined(id: &ID, replica: Replica, reason: QuarantineReason) -> QuarantinedEntry {
+    QuarantinedEntry {
+        id: id.clone(),
+        replica,
+        reason,
+    }
+}
+
+#[test]
+fn test_identical_replicas_are_consistent() {
+    let (_, local, _, other) = setup();
+    let plan = local.reconcile_with(&other).unwrap();
+    assert!(plan.is_consistent());
+    assert_eq!(plan.shared, 2);
+}
+
+#[test]
+fn test_missing_entries_are_copied_in_order() {
+    let (_, local, _, other) = setup();
+    let local_only = vec![
+        commit_dict_value(&local, "data", "a", "1"),
+        commit_dict_value(&local, "data", "b", "2"),
+    ];
+    let other_only = vec![commit_dict_value(&other, "data", "c", "3")];
+
+    let plan = local.reconcile_with(&other).unwrap();
+    assert_eq!(plan.shared, 2);
+    assert_eq!(plan.copy_to_other, local_only);
+    assert_eq!(plan.copy_to_local, other_only);
+    assert!(plan.quarantine.is_empty());
+    assert!(!plan.is_consistent());
+
+    // Applying the plan brings the replicas in line
+    for id in &plan.copy_to_other {
+        other.insert_raw(local.get_entry(id).unwrap()).unwrap();
+    }
+    for id in &plan.copy_to_local {
+        local.insert_raw(other.get_entry(id).unwrap()).unwrap();
+    }
+    let repaired = local.reconcile_with(&other).unwrap();
+    assert!(repaired.is_consistent());
+    assert_eq!(repaired.shared, 5);
+    let data = local.get_subtree_viewer::<Dict>("data").unwrap();
+    assert_eq!(data.get_string("c").unwrap(), "3");
+}
+
+#[test]
+fn test_invalid_signatures_are_quarantined() {
+    let (_, local, other_db, other) = setup();
+    other_db.add_private_key("stranger").unwrap();
+
+    // An entry signed by a key the tree does not know, and one built on top of it
+    let tip = other.get_tips().unwrap().remove(0);
+    let forged = other
+        .insert_raw(signed_entry(&other_db, &other, tip, "stranger"))
+        .unwrap();
+    let child = other
+        .insert_raw(signed_entry(&other_db, &other, forged.clone(), KEY))
+        .unwrap();
+
+    let plan = local.reconcile_with(&other).unwrap();
+    assert!(plan.copy_to_local.is_empty());
+    assert_eq!(
+        plan.quarantine,
+        vec![
+            quarantined(&forged, Replica::Other, QuarantineReason::InvalidSignature),
+            quarantined(&child, Replica::Other, QuarantineReason::QuarantinedParent),
+        ]
+    );
+}
+
+#[test]
+fn test_missing_parents_are_quarantined() {
+    let (local_db, local, _, other) = setup();
+    let orphan = local
+        .insert_raw(signed_entry(&local_db, &local, ID::from("missing"), KEY))
+        .unwrap();
+
+    let plan = local.reconcile_with(&other).unwrap();
+    assert!(plan.copy_to_other.is_empty());
+    assert_eq!(
+        plan.quarantine,
+        vec![quarantined(
+            &orphan,
+            Replica::Local,
+            QuarantineReason::MissingParents
+        )]
+    );
+}
+
+#[test]
+fn test_failed_entries_are_quarantined() {
+    let (local_db, local, _, other) = setup();
+    let tip = local.get_tips().unwrap().remove(0);
+    local_db
+        .backend()
+        .update_verification_status(&tip, VerificationStatus::Failed)
+        .unwrap();
+
+    // Shared entries that failed on one side are reported too
+    let plan = local.reconcile_with(&other).unwrap();
+    assert_eq!(plan.shared, 2);
+    assert_eq!(
+        plan.quarantine,
+        vec![quarantined(
+            &tip,
+            Replica::Local,
+            QuarantineReason::FailedVerification
+        )]
+    );
+}
+
+#[test]
+fn test_reconcile_rejects_other_trees() {
+    let (local_db, local, _, _) = setup();
+    let unrelated = local_db.new_tree_default(KEY).unwrap();
+    let err = local.reconcile_with(&unrelated).unwrap_err();
+    assert!(matches!(
+        err,
+        eidetica::Error::Base(eidetica::basedb::BaseError::InvalidOperation { .. })
+    ));
+}
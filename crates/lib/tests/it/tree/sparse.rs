@@ -0,0 +1,88 @@
+//! Sparse tree loading tests
+//!
+//! This module tests loading only the entries of a tree that write to some
+//! of its subtrees, and committing through such a partial tree.
+
+use crate::helpers::*;
+use eidetica::entry::ID;
+use eidetica::subtree::Dict;
+use std::collections::HashSet;
+
+#[test]
+fn test_load_sparse_holds_only_requested_subtrees() {
+    let tree = setup_tree();
+    let mut notes = HashSet::new();
+    for i in 0..3 {
+        notes.insert(commit_dict_value(
+            &tree,
+            "notes",
+            &format!("note_{i}"),
+            "text",
+        ));
+        commit_dict_value(&tree, "todos", &format!("todo_{i}"), "open");
+    }
+
+    let sparse = tree.load_sparse(&["notes"]).unwrap();
+    let loaded: HashSet<ID> = sparse
+        .get_all_entries()
+        .unwrap()
+        .iter()
+        .map(|entry| entry.id())
+        .collect();
+    assert!(notes.is_subset(&loaded));
+    assert!(loaded.contains(tree.root_id()));
+    assert_eq!(loaded.len(), notes.len() + 1);
+
+    let viewer = sparse.get_subtree_viewer::<Dict>("notes").unwrap();
+    assert_eq!(viewer.get_string("note_2").unwrap(), "text");
+    assert!(sparse.get_settings().is_ok());
+
+    let todos = sparse.get_subtree_viewer::<Dict>("todos").unwrap();
+    match todos.get_all() {
+        Err(eidetica::Error::Backend(e)) => assert!(e.is_subtree_not_loaded()),
+        Err(e) => panic!("unexpected error: {e}"),
+        Ok(_) => panic!("todos should not be loaded"),
+    }
+}
+
+#[test]
+fn test_sparse_commits_are_stored_in_full_tree() {
+    let tree = setup_tree();
+    commit_dict_value(&tree, "notes", "first", "1");
+    commit_dict_value(&tree, "todos", "chore", "open");
+
+    let sparse = tree.load_sparse(&["notes"]).unwrap();
+    let id = commit_dict_value(&sparse, "notes", "second", "2");
+    assert!(sparse.get_tips().unwrap().contains(&id));
+    assert!(tree.get_entry(&id).is_ok());
+    assert!(tree.verify_entry_signature(&id).unwrap());
+
+    let notes = tree.get_subtree_viewer::<Dict>("notes").unwrap();
+    assert_eq!(notes.get_string("first").unwrap(), "1");
+    assert_eq!(notes.get_string("second").unwrap(), "2");
+    let todos = tree.get_subtree_viewer::<Dict>("todos").unwrap();
+    assert_eq!(todos.get_string("chore").unwrap(), "open");
+
+    // A commit through the full tree merges the sparse branch back in
+    commit_dict_value(&tree, "todos", "done", "yes");
+    assert_eq!(tree.get_tips().unwrap().len(), 1);
+}
+
+#[test]
+fn test_sparse_sees_settings_changes() {
+    let tree = setup_tree();
+    commit_dict_value(&tree, "notes", "first", "1");
+    commit_dict_value(&tree, "_settings", "name", "renamed");
+
+    let sparse = tree.load_sparse(&["notes"]).unwrap();
+    assert_eq!(sparse.get_name().unwrap(), "renamed");
+    commit_dict_value(&sparse, "notes", "second", "2");
+    assert_eq!(
+        sparse
+            .get_subtree_viewer::<Dict>("notes")
+            .unwrap()
+            .get_string("second")
+            .unwrap(),
+        "2"
+    );
+}
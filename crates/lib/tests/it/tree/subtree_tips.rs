@@ -0,0 +1,143 @@
+//! Subtree tip management tests
+//!
+//! This module tests querying a subtree's tips and merging them independently
+//! of the main tree and other subtrees.
+
+use crate::helpers::*;
+use eidetica::Tree;
+use eidetica::entry::ID;
+use eidetica::subtree::Dict;
+
+/// Creates two concurrent branches from a common seed, each changing both
+/// "data" and "other". Returns the branch entry IDs.
+fn create_concurrent_branches(tree: &Tree) -> (ID, ID) {
+    // Seed the subtrees so the branches share a common ancestor in them
+    let op = tree.new_operation().unwrap();
+    op.get_subtree::<Dict>("data")
+        .unwrap()
+        .set("seed", "0")
+        .unwrap();
+    op.get_subtree::<Dict>("other")
+        .unwrap()
+        .set("seed", "0")
+        .unwrap();
+    let seed = op.commit().unwrap();
+
+    let branch = |name: &str| {
+        let op = tree.new_operation_with_tips([seed.clone()]).unwrap();
+        op.get_subtree::<Dict>("data")
+            .unwrap()
+            .set(name, "1")
+            .unwrap();
+        op.get_subtree::<Dict>("other")
+            .unwrap()
+            .set(name, "1")
+            .unwrap();
+        op.commit().unwrap()
+    };
+    (branch("a"), branch("b"))
+}
+
+fn sorted(mut ids: Vec<ID>) -> Vec<ID> {
+    ids.sort();
+    ids
+}
+
+#[test]
+fn test_subtree_tips() {
+    let tree = setup_tree();
+    assert!(tree.subtree_tips("data").unwrap().is_empty());
+
+    let (a, b) = create_concurrent_branches(&tree);
+    assert_eq!(
+        sorted(tree.subtree_tips("data").unwrap()),
+        sorted(vec![a, b])
+    );
+}
+
+#[test]
+fn test_merge_subtree_converges_only_that_subtree() {
+    let tree = setup_tree();
+    let (a, b) = create_concurrent_branches(&tree);
+
+    let merge_id = tree
+        .merge_subtree("data")
+        .unwrap()
+        .expect("tips were merged");
+    assert_eq!(tree.subtree_tips("data").unwrap(), vec![merge_id.clone()]);
+
+    // The merge entry touches only the merged subtree
+    let entry = tree.get_entry(&merge_id).unwrap();
+    assert_eq!(entry.subtrees(), vec!["data".to_string()]);
+    assert_eq!(
+        sorted(entry.subtree_parents("data").unwrap()),
+        sorted(vec![a.clone(), b.clone()])
+    );
+    assert_eq!(
+        sorted(tree.subtree_tips("other").unwrap()),
+        sorted(vec![a, b])
+    );
+
+    // The subtree's state is unchanged by the merge
+    let data = tree.get_subtree_viewer::<Dict>("data").unwrap();
+    assert_eq!(data.get_string("seed").unwrap(), "0");
+    assert_eq!(data.get_string("a").unwrap(), "1");
+    assert_eq!(data.get_string("b").unwrap(), "1");
+
+    // Later writes build on the merge
+    let op = tree.new_operation().unwrap();
+    op.get_subtree::<Dict>("data")
+        .unwrap()
+        .set("c", "2")
+        .unwrap();
+    let next = op.commit().unwrap();
+    assert_eq!(
+        tree.get_entry(&next)
+            .unwrap()
+            .subtree_parents("data")
+            .unwrap(),
+        vec![merge_id]
+    );
+    let data = tree.get_subtree_viewer::<Dict>("data").unwrap();
+    assert_eq!(data.get_string("a").unwrap(), "1");
+    assert_eq!(data.get_string("c").unwrap(), "2");
+}
+
+#[test]
+fn test_merge_subtree_with_single_tip_is_noop() {
+    let tree = setup_tree();
+    let op = tree.new_operation().unwrap();
+    op.get_subtree::<Dict>("data")
+        .unwrap()
+        .set("key", "value")
+        .unwrap();
+    op.commit().unwrap();
+    let entry_count = tree.get_all_entries().unwrap().len();
+
+    assert_eq!(tree.merge_subtree("data").unwrap(), None);
+    assert_eq!(tree.merge_subtree("missing").unwrap(), None);
+    assert_eq!(tree.get_all_entries().unwrap().len(), entry_count);
+}
+
+#[test]
+fn test_operation_merge_subtree_with_data() {
+    let tree = setup_tree();
+    let (a, b) = create_concurrent_branches(&tree);
+
+    let op = tree.new_operation().unwrap();
+    op.merge_subtree("data").unwrap();
+    op.get_subtree::<Dict>("data")
+        .unwrap()
+        .set("merged", "yes")
+        .unwrap();
+    let id = op.commit().unwrap();
+
+    let entry = tree.get_entry(&id).unwrap();
+    assert_eq!(
+        sorted(entry.subtree_parents("data").unwrap()),
+        sorted(vec![a, b])
+    );
+    let data = tree.get_subtree_viewer::<Dict>("data").unwrap();
+    assert_eq!(data.get_string("merged").unwrap(), "yes");
+    assert_eq!(data.get_string("a").unwrap(), "1");
+}
@@ -0,0 +1,118 @@
+//! Time-travel read tests
+//!
+//! Tests for `Tree::get_subtree_viewer_at`: viewing a subtree as of a single
+//! historical entry, as of a past set of tips, and rejecting entries that do
+//! not belong to the tree.
+
+use super::helpers::*;
+use crate::helpers::*;
+use eidetica::entry::ID;
+use eidetica::subtree::{Dict, Table};
+use serde::{Deserialize, Serialize};
+
+#[test]
+fn test_viewer_at_historical_entry() {
+    let tree = setup_tree();
+    let v1 = add_data_to_subtree(&tree, "data", &[("title", "draft"), ("status", "open")]);
+    let op = tree.new_operation().unwrap();
+    let data = op.get_subtree::<Dict>("data").unwrap();
+    data.set("title", "final").unwrap();
+    data.delete("status").unwrap();
+    let v2 = op.commit().unwrap();
+
+    let at_v1 = tree.get_subtree_viewer_at::<Dict>("data", &[v1]).unwrap();
+    assert_eq!(at_v1.get_string("title").unwrap(), "draft");
+    assert_eq!(at_v1.get_string("status").unwrap(), "open");
+
+    let at_v2 = tree.get_subtree_viewer_at::<Dict>("data", &[v2]).unwrap();
+    assert_eq!(at_v2.get_string("title").unwrap(), "final");
+    assert!(at_v2.get("status").is_err());
+
+    // Before the subtree was written it is empty
+    let at_root = tree
+        .get_subtree_viewer_at::<Dict>("data", std::slice::from_ref(tree.root_id()))
+        .unwrap();
+    assert!(at_root.get("title").is_err());
+}
+
+#[test]
+fn test_viewer_at_past_tips() {
+    let tree = setup_tree();
+    let base = add_data_to_subtree(&tree, "data", &[("base", "1")]);
+    let left = create_branch_from_entry(&tree, &base, "data", &[("left", "1")]);
+    let right = create_branch_from_entry(&tree, &base, "data", &[("right", "1")]);
+    add_data_to_subtree(&tree, "data", &[("later", "1")]);
+
+    // One branch alone
+    let at_left = tree
+        .get_subtree_viewer_at::<Dict>("data", std::slice::from_ref(&left))
+        .unwrap();
+    assert!(at_left.get("right").is_err());
+
+    // The merged state of both branches, without later changes
+    let at_tips = tree
+        .get_subtree_viewer_at::<Dict>("data", &[left, right])
+        .unwrap();
+    assert_eq!(at_tips.get_string("base").unwrap(), "1");
+    assert_eq!(at_tips.get_string("left").unwrap(), "1");
+    assert_eq!(at_tips.get_string("right").unwrap(), "1");
+    assert!(at_tips.get("later").is_err());
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Record {
+    name: String,
+}
+
+#[test]
+fn test_table_viewer_at_entry() {
+    let tree = setup_tree();
+    let op = tree.new_operation().unwrap();
+    let row = op
+        .get_subtree::<Table<Record>>("records")
+        .unwrap()
+        .insert(Record {
+            name: "before".to_string(),
+        })
+        .unwrap();
+    let before = op.commit().unwrap();
+
+    let op = tree.new_operation().unwrap();
+    op.get_subtree::<Table<Record>>("records")
+        .unwrap()
+        .set(
+            &row,
+            Record {
+                name: "after".to_string(),
+            },
+        )
+        .unwrap();
+    op.commit().unwrap();
+
+    let records = tree
+        .get_subtree_viewer_at::<Table<Record>>("records", &[before])
+        .unwrap();
+    assert_eq!(records.get(&row).unwrap().name, "before");
+}
+
+#[test]
+fn test_viewer_at_invalid_entries() {
+    let (db, tree) = setup_db_and_tree_with_key("test_key");
+    let other = db.new_tree_default("test_key").unwrap();
+
+    // Entries of another tree
+    let foreign = add_data_to_subtree(&other, "data", &[("key", "value")]);
+    assert!(
+        tree.get_subtree_viewer_at::<Dict>("data", &[foreign])
+            .is_err()
+    );
+
+    // Unknown entries
+    let missing =
+        ID::from("sha256:0000000000000000000000000000000000000000000000000000000000000000");
+    let result = tree.get_subtree_viewer_at::<Dict>("data", &[missing]);
+    assert!(result.is_err_and(|err| err.is_not_found()));
+
+    // No entries
+    assert!(tree.get_subtree_viewer_at::<Dict>("data", &[]).is_err());
+}
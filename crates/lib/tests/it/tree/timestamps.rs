@@ -0,0 +1,118 @@
+//! Tests for hybrid logical clock timestamps on entries
+//!
+//! Covers timestamping on commit, ordering across skewed clocks, tie-breaking of
+//! concurrent edits when merging, write stamps of committed Map data, and
+//! `Tree::latest_edit`.
+
+use super::helpers::*;
+use crate::helpers::*;
+use eidetica::clock::Hlc;
+use eidetica::crdt::Map;
+use eidetica::entry::{Entry, ID};
+use eidetica::subtree::Dict;
+use std::thread::sleep;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+fn timestamp(tree: &eidetica::Tree, id: &ID) -> Hlc {
+    tree.get_entry(id)
+        .unwrap()
+        .timestamp()
+        .expect("committed entries are timestamped")
+}
+
+#[test]
+fn test_committed_entries_are_timestamped() {
+    let tree = setup_tree();
+    let chain = create_linear_chain(&tree, "data", 3);
+
+    let root = timestamp(&tree, tree.root_id());
+    let now_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64;
+    assert!(root.wall_ms <= now_ms);
+
+    let mut previous = root;
+    for id in &chain {
+        let current = timestamp(&tree, id);
+        assert!(current > previous, "children must be later than parents");
+        previous = current;
+    }
+
+    // Entries built by hand have no timestamp
+    assert!(Entry::builder("root").build().timestamp().is_none());
+}
+
+#[test]
+fn test_timestamp_after_skewed_parent() {
+    let tree = setup_tree();
+    let tip = tree.get_tips().unwrap();
+
+    // A parent written by a device whose clock is a day ahead
+    let future = Hlc::new(timestamp(&tree, tree.root_id()).wall_ms + 86_400_000, 7);
+    let skewed = Entry::builder(tree.root_id().clone())
+        .set_parents(tip)
+        .set_metadata(format!(
+            r#"{{"settings_tips":[],"entropy":null,"hlc":{}}}"#,
+            serde_json::to_string(&future).unwrap()
+        ))
+        .build();
+    assert_eq!(skewed.timestamp(), Some(future));
+    tree.insert_raw(skewed).unwrap();
+
+    let child = add_data_to_subtree(&tree, "data", &[("key", "value")]);
+    assert_eq!(timestamp(&tree, &child), Hlc::new(future.wall_ms, 8));
+}
+
+#[test]
+fn test_concurrent_edits_latest_wins() {
+    let tree = setup_tree();
+
+    // Over several rounds the later of two concurrent edits at the same height
+    // wins, independent of which entry has the smaller ID
+    for round in 0..5 {
+        let base = add_data_to_subtree(&tree, "data", &[("key", "base")]);
+        let first = create_branch_from_entry(&tree, &base, "data", &[("key", "first")]);
+        sleep(Duration::from_millis(2));
+        let second = create_branch_from_entry(&tree, &base, "data", &[("key", "second")]);
+        assert!(timestamp(&tree, &second) > timestamp(&tree, &first));
+
+        let dict = tree.get_subtree_viewer::<Dict>("data").unwrap();
+        assert_eq!(dict.get_string("key").unwrap(), "second", "round {round}");
+    }
+}
+
+#[test]
+fn test_committed_writes_are_stamped() {
+    let tree = setup_tree();
+    let id = add_data_to_subtree(&tree, "data", &[("key", "value")]);
+
+    let entry = tree.get_entry(&id).unwrap();
+    let data: Map = serde_json::from_str(entry.data("data").unwrap()).unwrap();
+    let stamp = data.stamp("key").expect("committed writes are stamped");
+    assert_eq!(Some(stamp.hlc), entry.timestamp());
+
+    // The merged state keeps the stamp of the write
+    let state = tree
+        .get_subtree_viewer::<Dict>("data")
+        .unwrap()
+        .get_all()
+        .unwrap();
+    assert_eq!(state.stamp("key"), Some(stamp));
+}
+
+#[test]
+fn test_latest_edit() {
+    let tree = setup_tree();
+    let (root_id, _) = tree.latest_edit().unwrap().unwrap();
+    assert_eq!(&root_id, tree.root_id());
+
+    let base = add_data_to_subtree(&tree, "data", &[("key", "base")]);
+    create_branch_from_entry(&tree, &base, "data", &[("key", "a")]);
+    sleep(Duration::from_millis(2));
+    let latest = create_branch_from_entry(&tree, &base, "data", &[("key", "b")]);
+
+    let (id, hlc) = tree.latest_edit().unwrap().unwrap();
+    assert_eq!(id, latest);
+    assert_eq!(hlc, timestamp(&tree, &latest));
+}
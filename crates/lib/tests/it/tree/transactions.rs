@@ -0,0 +1,141 @@
+//! Transaction tests
+//!
+//! Tests for `Tree::transact`: committing changes to several subtrees at once,
+//! aborting on errors from the closure, and retrying when the tree changes
+//! while the closure runs.
+
+use crate::helpers::*;
+use eidetica::Tree;
+use eidetica::atomicop::{AtomicOp, AtomicOpError};
+use eidetica::basedb::BaseError;
+use eidetica::subtree::Dict;
+use std::cell::Cell;
+
+const KEY: &str = "key";
+
+/// Opens two accounts holding 100 each.
+fn open_accounts(tree: &Tree) {
+    tree.transact(|tx| {
+        tx.get_subtree::<Dict>("checking")?.set("balance", 100)?;
+        tx.get_subtree::<Dict>("savings")?.set("balance", 100)?;
+        Ok(())
+    })
+    .unwrap();
+}
+
+fn balance(tx: &AtomicOp, account: &str) -> eidetica::Result<i64> {
+    Ok(tx
+        .get_subtree::<Dict>(account)?
+        .get("balance")?
+        .as_int_or_zero())
+}
+
+fn committed_balance(tree: &Tree, account: &str) -> i64 {
+    tree.get_subtree_viewer::<Dict>(account)
+        .unwrap()
+        .get("balance")
+        .unwrap()
+        .as_int_or_zero()
+}
+
+/// Moves `amount` from checking to savings, refusing to overdraw checking.
+fn transfer(tx: &AtomicOp, amount: i64) -> eidetica::Result<()> {
+    let checking = balance(tx, "checking")?;
+    if checking < amount {
+        return Err(BaseError::InvalidOperation {
+            reason: format!("cannot move {amount} out of {checking}"),
+        }
+        .into());
+    }
+    let savings = balance(tx, "savings")?;
+    tx.get_subtree::<Dict>("checking")?
+        .set("balance", checking - amount)?;
+    tx.get_subtree::<Dict>("savings")?
+        .set("balance", savings + amount)?;
+    Ok(())
+}
+
+#[test]
+fn test_transact_commits_all_subtrees_in_one_entry() {
+    let (_db, tree) = setup_db_and_tree_with_key(KEY);
+    open_accounts(&tree);
+    let id = tree.transact(|tx| transfer(tx, 30)).unwrap();
+
+    assert_eq!(tree.get_tips().unwrap(), vec![id.clone()]);
+    let entry = tree.get_entry(&id).unwrap();
+    assert!(entry.in_subtree("checking"));
+    assert!(entry.in_subtree("savings"));
+    assert_eq!(committed_balance(&tree, "checking"), 70);
+    assert_eq!(committed_balance(&tree, "savings"), 130);
+}
+
+#[test]
+fn test_transact_error_commits_nothing() {
+    let (_db, tree) = setup_db_and_tree_with_key(KEY);
+    open_accounts(&tree);
+    let tips = tree.get_tips().unwrap();
+
+    let err = tree.transact(|tx| transfer(tx, 500)).unwrap_err();
+    assert!(matches!(
+        err,
+        eidetica::Error::Base(BaseError::InvalidOperation { .. })
+    ));
+    assert_eq!(tree.get_tips().unwrap(), tips);
+    assert_eq!(committed_balance(&tree, "checking"), 100);
+    assert_eq!(committed_balance(&tree, "savings"), 100);
+}
+
+#[test]
+fn test_transact_retries_after_concurrent_commit() {
+    let (_db, tree) = setup_db_and_tree_with_key(KEY);
+    open_accounts(&tree);
+    let attempts = Cell::new(0);
+
+    tree.transact(|tx| {
+        attempts.set(attempts.get() + 1);
+        let checking = balance(tx, "checking")?;
+        if attempts.get() == 1 {
+            // Another writer spends from checking while this one is running
+            let other = tree.new_operation()?;
+            other.get_subtree::<Dict>("checking")?.set("balance", 20)?;
+            other.commit()?;
+        }
+        assert_eq!(checking, if attempts.get() == 1 { 100 } else { 20 });
+        transfer(tx, 20)
+    })
+    .unwrap();
+
+    assert_eq!(attempts.get(), 2);
+    assert_eq!(committed_balance(&tree, "checking"), 0);
+    assert_eq!(committed_balance(&tree, "savings"), 120);
+}
+
+#[test]
+fn test_transact_gives_up_on_constant_changes() {
+    let (_db, tree) = setup_db_and_tree_with_key(KEY);
+    open_accounts(&tree);
+    let tips = tree.get_tips().unwrap();
+    let attempts = Cell::new(0);
+
+    let err = tree
+        .transact(|tx| {
+            attempts.set(attempts.get() + 1);
+            let other = tree.new_operation()?;
+            other
+                .get_subtree::<Dict>("log")?
+                .set("attempt", attempts.get() as i64)?;
+            other.commit()?;
+            transfer(tx, 10)
+        })
+        .unwrap_err();
+
+    assert!(matches!(
+        err,
+        eidetica::Error::AtomicOp(AtomicOpError::ConcurrentModification)
+    ));
+    assert!(attempts.get() > 1);
+    // Only the other writer's commits landed
+    assert_eq!(tree.get_all_entries().unwrap().len(), 2 + attempts.get());
+    assert_ne!(tree.get_tips().unwrap(), tips);
+    assert_eq!(committed_balance(&tree, "checking"), 100);
+}
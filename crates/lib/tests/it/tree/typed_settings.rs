@@ -0,0 +1,163 @@
+//! Typed settings tests
+//!
+//! Tests for `Tree::settings` and `Tree::update_settings`: reading and changing
+//! the name, description and custom fields, the creation time, merging of
+//! concurrent changes to different fields, and admin permission checks.
+
+use crate::auth::helpers::{setup_authenticated_tree, setup_test_db_with_keys};
+use eidetica::atomicop::AtomicOpError;
+use eidetica::auth::types::{KeyStatus, Permission};
+use eidetica::backend::database::InMemory;
+use eidetica::basedb::BaseDB;
+use eidetica::crdt::Map;
+use eidetica::crdt::map::Value;
+use eidetica::subtree::Dict;
+
+fn setup() -> eidetica::Tree {
+    let db = BaseDB::new(Box::new(InMemory::new()));
+    db.add_private_key("key").unwrap();
+    let mut settings = Map::new();
+    settings.set_string("name", "Notes");
+    db.new_tree(settings, "key").unwrap()
+}
+
+#[test]
+fn test_settings_read_initial_values() {
+    let tree = setup();
+    let settings = tree.settings().unwrap();
+    assert_eq!(settings.name(), Some("Notes"));
+    assert_eq!(settings.description(), None);
+    assert_eq!(settings.custom("theme"), None);
+    assert!(settings.custom_keys().is_empty());
+
+    // The creation time is the timestamp of the root entry
+    let root = tree.get_root().unwrap();
+    assert!(settings.created_at().is_some());
+    assert_eq!(settings.created_at(), root.timestamp());
+}
+
+#[test]
+fn test_update_settings_commits_changes() {
+    let tree = setup();
+    tree.update_settings(|settings| {
+        settings.set_name("Journal");
+        settings.set_description("Daily entries");
+        settings.set_custom("theme", "dark");
+        settings.set_custom("columns", 3);
+    })
+    .unwrap();
+
+    let settings = tree.settings().unwrap();
+    assert_eq!(settings.name(), Some("Journal"));
+    assert_eq!(tree.get_name().unwrap(), "Journal");
+    assert_eq!(settings.description(), Some("Daily entries"));
+    assert_eq!(
+        settings.custom("theme"),
+        Some(&Value::Text("dark".to_string()))
+    );
+    assert_eq!(settings.custom("columns").and_then(Value::as_int), Some(3));
+    assert_eq!(settings.custom_keys(), vec!["columns", "theme"]);
+
+    // Auth settings are untouched
+    assert!(settings.as_map().get("auth").is_some());
+}
+
+#[test]
+fn test_update_settings_view_reflects_changes() {
+    let tree = setup();
+    tree.update_settings(|settings| {
+        settings.set_custom("theme", "dark");
+        assert_eq!(
+            settings.custom("theme"),
+            Some(&Value::Text("dark".to_string()))
+        );
+        settings.remove_custom("theme");
+        assert_eq!(settings.custom("theme"), None);
+    })
+    .unwrap();
+    assert_eq!(tree.settings().unwrap().custom("theme"), None);
+}
+
+#[test]
+fn test_update_settings_removes_fields() {
+    let tree = setup();
+    tree.update_settings(|settings| {
+        settings.set_description("Temporary");
+        settings.set_custom("theme", "dark");
+    })
+    .unwrap();
+    tree.update_settings(|settings| {
+        settings.remove_description();
+        settings.remove_custom("theme");
+    })
+    .unwrap();
+
+    let settings = tree.settings().unwrap();
+    assert_eq!(settings.description(), None);
+    assert_eq!(settings.custom("theme"), None);
+    assert!(settings.custom_keys().is_empty());
+    assert_eq!(settings.name(), Some("Notes"));
+}
+
+#[test]
+fn test_update_settings_writes_only_changed_fields() {
+    let tree = setup();
+    let id = tree
+        .update_settings(|settings| settings.set_description("Shared"))
+        .unwrap();
+
+    let entry = tree.get_entry(&id).unwrap();
+    let written: Map = serde_json::from_str(entry.data("_settings").unwrap()).unwrap();
+    assert_eq!(written.get_text("description"), Some("Shared"));
+    assert!(written.get("name").is_none());
+    assert!(written.get("auth").is_none());
+}
+
+#[test]
+fn test_concurrent_updates_to_different_fields_merge() {
+    let tree = setup();
+    let base = tree.get_tips().unwrap();
+
+    // Two branches from the same tips, each changing a different field
+    let op = tree.new_operation_with_tips(&base).unwrap();
+    op.get_subtree::<Dict>("_settings")
+        .unwrap()
+        .set("description", "From a branch")
+        .unwrap();
+    op.commit().unwrap();
+    tree.update_settings(|settings| settings.set_custom("theme", "light"))
+        .unwrap();
+
+    let settings = tree.settings().unwrap();
+    assert_eq!(settings.description(), Some("From a branch"));
+    assert_eq!(
+        settings.custom("theme"),
+        Some(&Value::Text("light".to_string()))
+    );
+    assert_eq!(settings.name(), Some("Notes"));
+}
+
+#[test]
+fn test_update_settings_requires_admin() {
+    let keys = [
+        ("admin", Permission::Admin(0), KeyStatus::Active),
+        ("writer", Permission::Write(10), KeyStatus::Active),
+    ];
+    let (db, public_keys) = setup_test_db_with_keys(&keys);
+    let mut tree = setup_authenticated_tree(&db, &keys, &public_keys);
+
+    tree.set_default_auth_key("writer");
+    let err = tree
+        .update_settings(|settings| settings.set_name("Taken over"))
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        eidetica::Error::AtomicOp(AtomicOpError::InsufficientPermissions)
+    ));
+    assert_ne!(tree.settings().unwrap().name(), Some("Taken over"));
+
+    tree.set_default_auth_key("admin");
+    tree.update_settings(|settings| settings.set_name("Renamed"))
+        .unwrap();
+    assert_eq!(tree.settings().unwrap().name(), Some("Renamed"));
+}
@@ -0,0 +1,354 @@
+//! Derive macros for Eidetica.
+//!
+//! These macros are re-exported by the `eidetica` crate and should be used
+//! through it rather than depending on this crate directly.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, LitStr, parse_macro_input};
+
+/// Derives `eidetica::subtree::DictModel` for a struct with named fields.
+///
+/// Each field is stored under its own key in a nested `Map`, so individual
+/// fields merge independently when concurrent writers touch the same struct.
+///
+/// Supported field attributes:
+/// - `#[model(rename = "key")]`: store the field under a different key
+/// - `#[model(json)]`: store the field as JSON text using serde, for types
+///   that don't implement `ModelValue` (e.g. `Vec<T>`)
+/// - `#[model(default)]`: use `Default::default()` when the key is missing
+#[proc_macro_derive(DictModel, attributes(model))]
+pub fn derive_dict_model(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_dict_model(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Parsed `#[model(...)]` options for one field.
+#[derive(Default)]
+struct FieldOptions {
+    rename: Option<String>,
+    json: bool,
+    default: bool,
+}
+
+fn parse_field_options(field: &syn::Field) -> syn::Result<FieldOptions> {
+    let mut options = FieldOptions::default();
+    for attr in field.attrs.iter().filter(|a| a.path().is_ident("model")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                let key: LitStr = meta.value()?.parse()?;
+                options.rename = Some(key.value());
+                Ok(())
+            } else if meta.path.is_ident("json") {
+                options.json = true;
+                Ok(())
+            } else if meta.path.is_ident("default") {
+                options.default = true;
+                Ok(())
+            } else {
+                Err(meta
+                    .error("unsupported model attribute, expected `rename`, `json` or `default`"))
+            }
+        })?;
+    }
+    Ok(options)
+}
+
+fn expand_dict_model(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    name,
+                    "DictModel can only be derived for structs with named fields",
+                ));
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                name,
+                "DictModel can only be derived for structs",
+            ));
+        }
+    };
+
+    let mut to_map = Vec::new();
+    let mut from_map = Vec::new();
+    let mut keys = Vec::new();
+
+    for field in fields {
+        let ident = field.ident.as_ref().expect("named field");
+        let ty = &field.ty;
+        let options = parse_field_options(field)?;
+        let key = options.rename.unwrap_or_else(|| ident.to_string());
+        keys.push(key.clone());
+
+        if options.json {
+            to_map.push(quote! {
+                map.set_raw(#key, ::eidetica::subtree::model::json_to_value(&self.#ident));
+            });
+        } else {
+            to_map.push(quote! {
+                map.set_raw(#key, ::eidetica::subtree::ModelValue::to_value(&self.#ident));
+            });
+        }
+
+        let convert = if options.json {
+            quote! { ::eidetica::subtree::model::json_from_value::<#ty>(map.get(#key)) }
+        } else {
+            quote! { <#ty as ::eidetica::subtree::ModelValue>::from_field(map.get(#key)) }
+        };
+        let fallback = if options.default {
+            quote! { match map.get(#key) {
+                ::std::option::Option::None => ::std::default::Default::default(),
+                ::std::option::Option::Some(_) => return ::std::result::Result::Err(#key.to_string()),
+            } }
+        } else {
+            quote! { return ::std::result::Result::Err(#key.to_string()) }
+        };
+        from_map.push(quote! {
+            #ident: match #convert {
+                ::std::option::Option::Some(value) => value,
+                ::std::option::Option::None => #fallback,
+            }
+        });
+    }
+
+    Ok(quote! {
+        impl #impl_generics ::eidetica::subtree::DictModel for #name #ty_generics #where_clause {
+            const FIELDS: &'static [&'static str] = &[#(#keys),*];
+
+            fn to_map(&self) -> ::eidetica::crdt::Map {
+                let mut map = ::eidetica::crdt::Map::new();
+                #(#to_map)*
+                map
+            }
+
+            fn from_map(
+                map: &::eidetica::crdt::Map,
+            ) -> ::std::result::Result<Self, ::std::string::String> {
+                ::std::result::Result::Ok(Self {
+                    #(#from_map,)*
+                })
+            }
+        }
+
+        impl #impl_generics ::eidetica::subtree::ModelValue for #name #ty_generics #where_clause {
+            fn to_value(&self) -> ::eidetica::crdt::map::Value {
+                ::eidetica::crdt::map::Value::Map(
+                    <Self as ::eidetica::subtree::DictModel>::to_map(self),
+                )
+            }
+
+            fn from_value(value: &::eidetica::crdt::map::Value) -> ::std::option::Option<Self> {
+                match value {
+                    ::eidetica::crdt::map::Value::Map(map) => {
+                        <Self as ::eidetica::subtree::DictModel>::from_map(map).ok()
+                    }
+                    _ => ::std::option::Option::None,
+                }
+            }
+        }
+    })
+}
+
+/// Derives `eidetica::subtree::Record` for a struct with named fields, to be
+/// stored in a `Table`.
+///
+/// Supported struct attributes:
+/// - `#[record(version = N)]`: the schema version rows are written at,
+///   1 if not given
+///
+/// Supported field attributes:
+/// - `#[record(key)]`: the field is the row's primary key; its type must
+///   implement `ToString`. At most one field can be the key
+/// - `#[record(index)]`: declare the field as indexed
+/// - `#[record(renamed_from = "old", since = N)]`: the field was serialized as
+///   `old` before version `N`, which defaults to the current version. Rows
+///   written at older versions are upgraded when read
+///
+/// Fields are named as they are serialized, honouring `#[serde(rename = "...")]`.
+#[proc_macro_derive(EideticaRecord, attributes(record))]
+pub fn derive_eidetica_record(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_record(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Parsed `#[record(...)]` options for one field.
+#[derive(Default)]
+struct RecordFieldOptions {
+    key: bool,
+    index: bool,
+    renamed_from: Option<LitStr>,
+    since: Option<u32>,
+}
+
+fn parse_record_version(input: &DeriveInput) -> syn::Result<u32> {
+    let mut version = 1;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("record")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("version") {
+                version = meta.value()?.parse::<syn::LitInt>()?.base10_parse()?;
+                Ok(())
+            } else {
+                Err(meta.error("unsupported record attribute, expected `version`"))
+            }
+        })?;
+    }
+    Ok(version)
+}
+
+fn parse_record_field_options(field: &syn::Field) -> syn::Result<RecordFieldOptions> {
+    let mut options = RecordFieldOptions::default();
+    for attr in field.attrs.iter().filter(|a| a.path().is_ident("record")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("key") {
+                options.key = true;
+                Ok(())
+            } else if meta.path.is_ident("index") {
+                options.index = true;
+                Ok(())
+            } else if meta.path.is_ident("renamed_from") {
+                options.renamed_from = Some(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("since") {
+                options.since = Some(meta.value()?.parse::<syn::LitInt>()?.base10_parse()?);
+                Ok(())
+            } else {
+                Err(meta.error(
+                    "unsupported record attribute, expected `key`, `index`, `renamed_from` or `since`",
+                ))
+            }
+        })?;
+    }
+    Ok(options)
+}
+
+/// The name a field is serialized under: its `#[serde(rename = "...")]` or its identifier.
+fn serialized_name(field: &syn::Field) -> syn::Result<String> {
+    let mut name = field.ident.as_ref().expect("named field").to_string();
+    for attr in field.attrs.iter().filter(|a| a.path().is_ident("serde")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") && meta.input.peek(syn::Token![=]) {
+                name = meta.value()?.parse::<LitStr>()?.value();
+            } else if meta.input.peek(syn::Token![=]) {
+                meta.value()?.parse::<syn::Expr>()?;
+            } else if meta.input.peek(syn::token::Paren) {
+                meta.parse_nested_meta(|nested| {
+                    if nested.input.peek(syn::Token![=]) {
+                        nested.value()?.parse::<syn::Expr>()?;
+                    }
+                    Ok(())
+                })?;
+            }
+            Ok(())
+        })?;
+    }
+    Ok(name)
+}
+
+fn expand_record(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let version = parse_record_version(&input)?;
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    name,
+                    "EideticaRecord can only be derived for structs with named fields",
+                ));
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                name,
+                "EideticaRecord can only be derived for structs",
+            ));
+        }
+    };
+
+    let mut names = Vec::new();
+    let mut indexed = Vec::new();
+    let mut key: Option<(&syn::Ident, String)> = None;
+    let mut migrations = Vec::new();
+
+    for field in fields {
+        let ident = field.ident.as_ref().expect("named field");
+        let options = parse_record_field_options(field)?;
+        let field_name = serialized_name(field)?;
+
+        if options.key {
+            if key.is_some() {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    "only one field can be the record's key",
+                ));
+            }
+            key = Some((ident, field_name.clone()));
+        }
+        if options.index {
+            indexed.push(field_name.clone());
+        }
+        match (&options.renamed_from, options.since) {
+            (Some(old), since) => {
+                let since = since.unwrap_or(version);
+                if since == 0 || since > version {
+                    return Err(syn::Error::new_spanned(
+                        old,
+                        format!("`since` must be between 1 and the record version {version}"),
+                    ));
+                }
+                let from = since - 1;
+                migrations.push(quote! { .rename_field(#from, #old, #field_name) });
+            }
+            (None, Some(_)) => {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    "`since` requires `renamed_from`",
+                ));
+            }
+            (None, None) => {}
+        }
+        names.push(field_name);
+    }
+
+    let key_field = match &key {
+        Some((_, field_name)) => quote! { ::std::option::Option::Some(#field_name) },
+        None => quote! { ::std::option::Option::None },
+    };
+    let primary_key = match &key {
+        Some((ident, _)) => quote! {
+            ::std::option::Option::Some(::std::string::ToString::to_string(&self.#ident))
+        },
+        None => quote! { ::std::option::Option::None },
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::eidetica::subtree::Record for #name #ty_generics #where_clause {
+            const KEY_FIELD: ::std::option::Option<&'static str> = #key_field;
+            const INDEXED_FIELDS: &'static [&'static str] = &[#(#indexed),*];
+            const FIELDS: &'static [&'static str] = &[#(#names),*];
+            const SCHEMA_VERSION: u32 = #version;
+
+            fn primary_key(&self) -> ::std::option::Option<::std::string::String> {
+                #primary_key
+            }
+
+            fn schema() -> ::eidetica::subtree::TableSchema {
+                ::eidetica::subtree::TableSchema::new(#version) #(#migrations)*
+            }
+        }
+    })
+}
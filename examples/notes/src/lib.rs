@@ -0,0 +1,249 @@
+//! A replicated notes app built on Eidetica
+//!
+//! This crate is a small but complete application meant as a reference for
+//! building on Eidetica. A [`Notebook`] is one tree:
+//!
+//! * The notebook's title is the tree's name, read and changed through its
+//!   typed settings.
+//! * Each note's metadata is a row of the `notes` [`Table`], keyed by a
+//!   generated ID. Concurrent edits to the same row keep the latest one.
+//! * Each note's body is a [`Text`] subtree of its own, `body:<note id>`, so
+//!   concurrent edits to a body merge character by character and syncing a
+//!   notebook never transfers more than the edits made to it.
+//! * Sharing adds a key to the tree's authentication settings, with read or
+//!   write access. The owner's key is the tree's admin key.
+//! * Devices exchange entries with [`SyncPeer`] over TCP, see [`serve_one`]
+//!   and [`sync_notebook`].
+//!
+//! Every change is a single atomic operation, so a note is never stored
+//! without its body, and every entry is signed by the key of the device that
+//! wrote it.
+
+use eidetica::auth::types::{AuthKey, KeyStatus, Permission};
+use eidetica::basedb::BaseDB;
+use eidetica::crdt::Map;
+use eidetica::crdt::map::Value;
+use eidetica::entry::ID;
+use eidetica::subtree::{Dict, Table, Text};
+use eidetica::sync::{SyncPeer, SyncStats};
+use eidetica::{Result, Tree};
+use serde::{Deserialize, Serialize};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Name of the Table subtree holding the notes' metadata.
+const NOTES: &str = "notes";
+
+/// The metadata of a note; its body is stored separately.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Note {
+    /// Title of the note
+    pub title: String,
+    /// Tags for finding the note, in the order they were given
+    pub tags: Vec<String>,
+    /// When the note was created, in milliseconds since the Unix epoch
+    pub created_ms: u64,
+    /// Archived notes are left out of [`Notebook::notes`]
+    pub archived: bool,
+}
+
+/// What a key a notebook is shared with may do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    /// Read the notebook only
+    Read,
+    /// Add and edit notes, but not change sharing or the title
+    Write,
+}
+
+/// A notebook, opened with the key of the device using it.
+#[derive(Clone)]
+pub struct Notebook {
+    tree: Tree,
+}
+
+impl Notebook {
+    /// Create a notebook owned by `key_name`, which must be a private key of `db`.
+    pub fn create(db: &BaseDB, key_name: &str, title: &str) -> Result<Self> {
+        let mut settings = Map::new();
+        settings.set_string("name", title);
+        let tree = db.new_tree(settings, key_name)?;
+        Ok(Self { tree })
+    }
+
+    /// Open the notebook with the given root ID, writing with `key_name`.
+    pub fn open(db: &BaseDB, root: &ID, key_name: &str) -> Result<Self> {
+        let mut tree = db.load_tree(root)?;
+        tree.set_default_auth_key(key_name);
+        Ok(Self { tree })
+    }
+
+    /// Open the only notebook titled `title`, writing with `key_name`.
+    pub fn find(db: &BaseDB, title: &str, key_name: &str) -> Result<Self> {
+        let mut tree = db.find_tree_by_name(title)?;
+        tree.set_default_auth_key(key_name);
+        Ok(Self { tree })
+    }
+
+    /// The root ID of the notebook, which identifies it on every device.
+    pub fn root_id(&self) -> &ID {
+        self.tree.root_id()
+    }
+
+    /// The underlying tree.
+    pub fn tree(&self) -> &Tree {
+        &self.tree
+    }
+
+    /// The title of the notebook.
+    pub fn title(&self) -> Result<String> {
+        Ok(self.tree.settings()?.name().unwrap_or_default().to_string())
+    }
+
+    /// Change the title of the notebook. Only the owner can.
+    pub fn set_title(&self, title: &str) -> Result<()> {
+        self.tree
+            .update_settings(|settings| settings.set_name(title))?;
+        Ok(())
+    }
+
+    /// Add a note, returning its ID.
+    pub fn add_note(&self, title: &str, body: &str, tags: &[&str]) -> Result<String> {
+        let op = self.tree.new_operation()?;
+        let id = op.get_subtree::<Table<Note>>(NOTES)?.insert(Note {
+            title: title.to_string(),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            created_ms: now_ms(),
+            archived: false,
+        })?;
+        op.get_subtree::<Text>(body_subtree(&id))?.set(body)?;
+        op.commit()?;
+        Ok(id)
+    }
+
+    /// The metadata of a note.
+    pub fn note(&self, id: &str) -> Result<Note> {
+        self.tree.get_subtree_viewer::<Table<Note>>(NOTES)?.get(id)
+    }
+
+    /// The body of a note.
+    pub fn body(&self, id: &str) -> Result<String> {
+        self.tree
+            .get_subtree_viewer::<Text>(body_subtree(id))?
+            .get()
+    }
+
+    /// The notes that are not archived, oldest first, with their IDs.
+    pub fn notes(&self) -> Result<Vec<(String, Note)>> {
+        self.search(|note| !note.archived)
+    }
+
+    /// The notes that are not archived and have `tag`, oldest first.
+    pub fn tagged(&self, tag: &str) -> Result<Vec<(String, Note)>> {
+        self.search(|note| !note.archived && note.tags.iter().any(|t| t == tag))
+    }
+
+    /// Change the title of a note.
+    pub fn rename_note(&self, id: &str, title: &str) -> Result<()> {
+        self.update_note(id, |note| note.title = title.to_string())
+    }
+
+    /// Archive a note, hiding it from [`notes`](Self::notes).
+    pub fn archive(&self, id: &str) -> Result<()> {
+        self.update_note(id, |note| note.archived = true)
+    }
+
+    /// Insert `text` into the body of a note, before the character at `index`.
+    pub fn insert_text(&self, id: &str, index: usize, text: &str) -> Result<()> {
+        self.edit_body(id, |body| body.insert(index, text))
+    }
+
+    /// Append `text` to the body of a note.
+    pub fn append_text(&self, id: &str, text: &str) -> Result<()> {
+        self.edit_body(id, |body| body.push_str(text))
+    }
+
+    /// Delete `len` characters from the body of a note, starting at `index`.
+    pub fn delete_text(&self, id: &str, index: usize, len: usize) -> Result<()> {
+        self.edit_body(id, |body| body.delete(index, len))
+    }
+
+    /// Share the notebook with the key whose public key is `public_key`.
+    ///
+    /// `key_name` is the name the key signs with on its own device. Only the
+    /// owner can share the notebook.
+    pub fn share(&self, key_name: &str, public_key: &str, access: Access) -> Result<()> {
+        let key = AuthKey {
+            pubkey: public_key.to_string(),
+            permissions: match access {
+                Access::Read => Permission::Read,
+                Access::Write => Permission::Write(10),
+            },
+            status: KeyStatus::Active,
+        };
+        let op = self.tree.new_operation()?;
+        op.get_subtree::<Dict>("_settings")?.set_at_path(
+            ["auth", key_name],
+            Value::Text(serde_json::to_string(&key)?),
+        )?;
+        op.commit()?;
+        Ok(())
+    }
+
+    fn search(&self, matches: impl Fn(&Note) -> bool) -> Result<Vec<(String, Note)>> {
+        let mut notes = self
+            .tree
+            .get_subtree_viewer::<Table<Note>>(NOTES)?
+            .search(matches)?;
+        notes.sort_by(|(a_id, a), (b_id, b)| (a.created_ms, a_id).cmp(&(b.created_ms, b_id)));
+        Ok(notes)
+    }
+
+    fn update_note(&self, id: &str, update: impl FnOnce(&mut Note)) -> Result<()> {
+        let op = self.tree.new_operation()?;
+        let notes = op.get_subtree::<Table<Note>>(NOTES)?;
+        let mut note = notes.get(id)?;
+        update(&mut note);
+        notes.set(id, note)?;
+        op.commit()?;
+        Ok(())
+    }
+
+    fn edit_body(&self, id: &str, edit: impl FnOnce(&Text) -> Result<()>) -> Result<()> {
+        // Fails if there is no such note
+        self.note(id)?;
+        let op = self.tree.new_operation()?;
+        edit(&op.get_subtree::<Text>(body_subtree(id))?)?;
+        op.commit()?;
+        Ok(())
+    }
+}
+
+/// Accept one connection on `listener` and serve sync sessions on it until
+/// the peer disconnects.
+///
+/// Entries the peer sends are verified against the notebook's authentication
+/// settings before they are used, so only keys a notebook is shared with can
+/// change it.
+pub fn serve_one(db: &BaseDB, listener: &TcpListener) -> Result<()> {
+    let (stream, _) = listener.accept()?;
+    SyncPeer::new(db.backend().clone(), stream).serve()
+}
+
+/// Sync the notebook `root` with the peer serving at `addr`.
+///
+/// The notebook does not need to exist locally yet; syncing fetches it.
+pub fn sync_notebook(db: &BaseDB, addr: impl ToSocketAddrs, root: &ID) -> Result<SyncStats> {
+    let mut peer = SyncPeer::new(db.backend().clone(), TcpStream::connect(addr)?);
+    peer.sync_tree(root)
+}
+
+fn body_subtree(id: &str) -> String {
+    format!("body:{id}")
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
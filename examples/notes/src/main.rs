@@ -0,0 +1,150 @@
+//! Command line interface of the notes app
+//!
+//! Each invocation opens the database file, runs one command and saves the
+//! file again. Notebooks are named by their title or by their root ID.
+
+use eidetica::Result;
+use eidetica::backend::database::InMemory;
+use eidetica::basedb::{BaseDB, BaseError};
+use eidetica::entry::ID;
+use eidetica_notes::{Access, Notebook, serve_one, sync_notebook};
+use std::net::TcpListener;
+use std::process::ExitCode;
+
+/// Database file used unless `--db <file>` is given.
+const DEFAULT_DB_FILE: &str = "notes.json";
+
+/// Name of this device's private key, created on first use.
+const KEY: &str = "notes_key";
+
+const USAGE: &str = "\
+Usage: eidetica-notes [--db <file>] <command>
+
+Commands:
+  key                                      Print this device's public key
+  create <title>                           Create a notebook
+  add <notebook> <title> <body> [tags...]  Add a note
+  list <notebook>                          List the notes of a notebook
+  show <notebook> <note>                   Print a note
+  append <notebook> <note> <text>          Append text to a note
+  share <notebook> <key> <public-key> <read|write>
+                                           Share a notebook with another device
+  serve <addr>                             Serve sync sessions, one peer at a time
+  sync <addr> <root-id>                    Sync a notebook with a serving peer";
+
+fn main() -> ExitCode {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let path = match args.iter().position(|arg| arg == "--db") {
+        Some(i) if i + 1 < args.len() => {
+            let path = args.remove(i + 1);
+            args.remove(i);
+            path
+        }
+        Some(_) => return usage(),
+        None => DEFAULT_DB_FILE.to_string(),
+    };
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    let result = BaseDB::open(&path).and_then(|db| {
+        if db.get_public_key(KEY)?.is_none() {
+            db.add_private_key(KEY)?;
+        }
+        let output = run(&db, &path, &args)?;
+        save(&db, &path)?;
+        Ok(output)
+    });
+    match result {
+        Ok(Some(output)) => {
+            println!("{output}");
+            ExitCode::SUCCESS
+        }
+        Ok(None) => usage(),
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn usage() -> ExitCode {
+    eprintln!("{USAGE}");
+    ExitCode::from(2)
+}
+
+/// Runs a command, returning what to print, or `None` for a usage error.
+fn run(db: &BaseDB, path: &str, args: &[&str]) -> Result<Option<String>> {
+    let output = match args {
+        ["key"] => db.get_formatted_public_key(KEY)?.unwrap_or_default(),
+        ["create", title] => {
+            let notebook = Notebook::create(db, KEY, title)?;
+            format!("Created notebook {}", notebook.root_id())
+        }
+        ["add", notebook, title, body, tags @ ..] => {
+            let id = open(db, notebook)?.add_note(title, body, tags)?;
+            format!("Added note {id}")
+        }
+        ["list", notebook] => open(db, notebook)?
+            .notes()?
+            .into_iter()
+            .map(|(id, note)| match note.tags.is_empty() {
+                true => format!("{id}  {}", note.title),
+                false => format!("{id}  {}  [{}]", note.title, note.tags.join(", ")),
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        ["show", notebook, note] => {
+            let notebook = open(db, notebook)?;
+            format!("{}\n\n{}", notebook.note(note)?.title, notebook.body(note)?)
+        }
+        ["append", notebook, note, text] => {
+            open(db, notebook)?.append_text(note, text)?;
+            "Appended".to_string()
+        }
+        ["share", notebook, key, public_key, access] => {
+            let access = match *access {
+                "read" => Access::Read,
+                "write" => Access::Write,
+                _ => return Ok(None),
+            };
+            open(db, notebook)?.share(key, public_key, access)?;
+            format!("Shared with {key}")
+        }
+        ["serve", addr] => {
+            let listener = TcpListener::bind(addr)?;
+            println!("Serving on {}", listener.local_addr()?);
+            loop {
+                if let Err(e) = serve_one(db, &listener) {
+                    eprintln!("Sync session failed: {e}");
+                }
+                // Keep what peers sent even if the server is killed
+                save(db, path)?;
+            }
+        }
+        ["sync", addr, root] => {
+            let stats = sync_notebook(db, addr, &ID::from(*root))?;
+            format!("Sent {} entries, received {}", stats.sent, stats.received)
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(output))
+}
+
+/// Opens a notebook by title, or by root ID if no notebook has that title.
+fn open(db: &BaseDB, notebook: &str) -> Result<Notebook> {
+    match Notebook::find(db, notebook, KEY) {
+        Err(eidetica::Error::Base(e)) if e.is_not_found() => {
+            Notebook::open(db, &ID::from(notebook), KEY)
+        }
+        result => result,
+    }
+}
+
+fn save(db: &BaseDB, path: &str) -> Result<()> {
+    match db.backend().as_any().downcast_ref::<InMemory>() {
+        Some(backend) => backend.save_to_file(path),
+        None => Err(BaseError::InvalidOperation {
+            reason: format!("{path} is not an InMemory database"),
+        }
+        .into()),
+    }
+}
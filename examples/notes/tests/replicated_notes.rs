@@ -0,0 +1,146 @@
+//! The notes app end to end
+//!
+//! Two devices, each with its own database and key, share a notebook and sync
+//! it over TCP. These tests double as a check of the public API the app is
+//! built on.
+
+use eidetica::atomicop::AtomicOpError;
+use eidetica::backend::database::InMemory;
+use eidetica::basedb::BaseDB;
+use eidetica::entry::ID;
+use eidetica::sync::SyncStats;
+use eidetica_notes::{Access, Notebook, serve_one, sync_notebook};
+use std::net::TcpListener;
+use std::thread;
+
+const ALICE: &str = "alice";
+const BOB: &str = "bob";
+
+/// A device: a database with its own private key.
+fn device(key_name: &str) -> BaseDB {
+    let db = BaseDB::new(Box::new(InMemory::new()));
+    db.add_private_key(key_name).unwrap();
+    db
+}
+
+/// Syncs `root` from `client` with `server`, served on a fresh port.
+fn sync(client: &BaseDB, server: &BaseDB, root: &ID) -> SyncStats {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = server.clone();
+    let serving = thread::spawn(move || serve_one(&server, &listener));
+    let stats = sync_notebook(client, addr, root).unwrap();
+    serving.join().unwrap().unwrap();
+    stats
+}
+
+/// Alice's notebook, shared with Bob with `access` and synced to his device.
+fn shared_notebook(access: Access) -> (BaseDB, Notebook, BaseDB, Notebook) {
+    let alice_db = device(ALICE);
+    let bob_db = device(BOB);
+    let alice = Notebook::create(&alice_db, ALICE, "Trip planning").unwrap();
+    let bob_key = bob_db.get_formatted_public_key(BOB).unwrap().unwrap();
+    alice.share(BOB, &bob_key, access).unwrap();
+
+    sync(&bob_db, &alice_db, alice.root_id());
+    let bob = Notebook::open(&bob_db, alice.root_id(), BOB).unwrap();
+    (alice_db, alice, bob_db, bob)
+}
+
+#[test]
+fn test_notes_on_one_device() {
+    let db = device(ALICE);
+    let notebook = Notebook::create(&db, ALICE, "Recipes").unwrap();
+    assert_eq!(notebook.title().unwrap(), "Recipes");
+
+    let soup = notebook
+        .add_note("Soup", "Boil water.", &["dinner", "easy"])
+        .unwrap();
+    let cake = notebook.add_note("Cake", "Preheat oven.", &[]).unwrap();
+    notebook.append_text(&soup, " Add salt.").unwrap();
+    notebook.insert_text(&cake, 0, "First, ").unwrap();
+    notebook.delete_text(&cake, 7, 1).unwrap();
+    notebook.rename_note(&cake, "Lemon cake").unwrap();
+
+    assert_eq!(notebook.body(&soup).unwrap(), "Boil water. Add salt.");
+    assert_eq!(notebook.body(&cake).unwrap(), "First, reheat oven.");
+    let notes = notebook.notes().unwrap();
+    let mut titles: Vec<&str> = notes.iter().map(|(_, note)| note.title.as_str()).collect();
+    titles.sort();
+    assert_eq!(titles, vec!["Lemon cake", "Soup"]);
+    assert_eq!(notebook.tagged("dinner").unwrap()[0].0, soup);
+
+    notebook.archive(&soup).unwrap();
+    assert_eq!(notebook.notes().unwrap().len(), 1);
+    assert!(notebook.note(&soup).unwrap().archived);
+    assert!(notebook.append_text("missing", "text").is_err());
+
+    // Notebooks are found by title
+    notebook.set_title("Family recipes").unwrap();
+    let found = Notebook::find(&db, "Family recipes", ALICE).unwrap();
+    assert_eq!(found.root_id(), notebook.root_id());
+}
+
+#[test]
+fn test_shared_notebook_converges() {
+    let (alice_db, alice, bob_db, bob) = shared_notebook(Access::Write);
+    let note = alice.add_note("Packing", "passport", &[]).unwrap();
+    sync(&bob_db, &alice_db, alice.root_id());
+    assert_eq!(bob.body(&note).unwrap(), "passport");
+
+    // Both edit the same body, and add notes, without syncing in between
+    alice.append_text(&note, ", tickets").unwrap();
+    bob.insert_text(&note, 0, "charger, ").unwrap();
+    let from_alice = alice.add_note("Hotels", "", &[]).unwrap();
+    let from_bob = bob.add_note("Trains", "", &["transport"]).unwrap();
+
+    let stats = sync(&bob_db, &alice_db, alice.root_id());
+    assert_eq!(stats.sent, 2);
+    assert_eq!(stats.received, 2);
+
+    for notebook in [&alice, &bob] {
+        assert_eq!(notebook.body(&note).unwrap(), "charger, passport, tickets");
+        let mut ids: Vec<String> = notebook
+            .notes()
+            .unwrap()
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        ids.sort();
+        let mut expected = vec![note.clone(), from_alice.clone(), from_bob.clone()];
+        expected.sort();
+        assert_eq!(ids, expected);
+    }
+    assert_eq!(alice.tagged("transport").unwrap()[0].0, from_bob);
+}
+
+#[test]
+fn test_sharing_controls_access() {
+    let (alice_db, alice, bob_db, bob) = shared_notebook(Access::Read);
+    let note = alice.add_note("Budget", "200", &[]).unwrap();
+    sync(&bob_db, &alice_db, alice.root_id());
+    assert_eq!(bob.body(&note).unwrap(), "200");
+
+    // A reader cannot write, and a writer cannot change the title
+    let err = bob.append_text(&note, "0").unwrap_err();
+    assert!(matches!(
+        err,
+        eidetica::Error::AtomicOp(AtomicOpError::InsufficientPermissions)
+    ));
+    let (_, _, _, writer) = shared_notebook(Access::Write);
+    assert!(writer.set_title("Mine now").is_err());
+}
+
+#[test]
+fn test_unshared_device_cannot_write() {
+    let alice_db = device(ALICE);
+    let mallory_db = device("mallory");
+    let alice = Notebook::create(&alice_db, ALICE, "Private").unwrap();
+    alice.add_note("Secret", "42", &[]).unwrap();
+
+    // Mallory can fetch the entries but not sign new ones the notebook accepts
+    sync(&mallory_db, &alice_db, alice.root_id());
+    let mallory = Notebook::open(&mallory_db, alice.root_id(), "mallory").unwrap();
+    assert!(mallory.add_note("Forged", "", &[]).is_err());
+    assert_eq!(mallory.notes().unwrap().len(), 1);
+}
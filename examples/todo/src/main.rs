@@ -143,13 +143,7 @@ fn main() -> Result<()> {
 }
 
 fn load_or_create_db(path: &PathBuf) -> Result<BaseDB> {
-    let db = if path.exists() {
-        let backend = InMemory::load_from_file(path)?;
-        BaseDB::new(Box::new(backend))
-    } else {
-        let backend = InMemory::new();
-        BaseDB::new(Box::new(backend))
-    };
+    let db = BaseDB::open(path)?;
 
     // Ensure the todo app authentication key exists
     // First check if the key already exists